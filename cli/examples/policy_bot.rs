@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A 9x9 bot playing a policy network's top move.
+//!
+//! Usage: `cargo run -p p2pgo-cli --example policy_bot -- <model.bin> [max games]`
+//!
+//! Prints a ticket for opponents to connect with, then hosts games until
+//! interrupted, resigning positions the value head finds lost.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use burn::backend::wgpu::Wgpu;
+use burn::tensor::backend::Backend;
+use p2pgo_core::engine::PolicyPlayer;
+use p2pgo_network::bot::{self, BotConfig, BotHooks, BotRunner};
+use p2pgo_network::archive::GameArchive;
+use p2pgo_network::join::PlayerProfile;
+use p2pgo_network::{ArchiveManager, IdentityManager, IrohCtx, Lobby};
+
+/// Logs each game as it starts and ends
+struct Announce;
+
+impl BotHooks for Announce {
+    fn on_game_start(&self, game_id: &str, opponent: &PlayerProfile) {
+        println!("{}: playing {}", game_id, opponent.name);
+    }
+
+    fn on_game_end(&self, record: &GameArchive) {
+        println!("{}: {:?} won after {} moves", record.game_id, record.winner, record.move_count);
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let model_path = PathBuf::from(args.next().ok_or_else(|| anyhow!("Usage: policy_bot <model.bin> [max games]"))?);
+    let max_games = args.next().map(|n| n.parse()).transpose()?.unwrap_or(4);
+
+    let device = <Wgpu as Backend>::Device::default();
+    // One model shared by every game's engine and the value check
+    let model = Arc::new(Mutex::new(p2pgo_cli::analyze::load_model::<Wgpu>(&model_path, &device)?));
+
+    let policy_model = model.clone();
+    let policy_device = device.clone();
+    let engines = bot::engines(move |_size| {
+        let model = policy_model.clone();
+        let device = policy_device.clone();
+        PolicyPlayer::new(move |state| {
+            let model = model.lock().unwrap();
+            trainer::evaluate::evaluate(&model, state, &device).map(|e| e.logits).unwrap_or_default()
+        })
+    });
+
+    let mut config = BotConfig::new("policy-bot", 9);
+    config.max_games = max_games;
+    config.move_budget = Duration::from_secs(2);
+
+    let lobby = Lobby::new();
+    let iroh_ctx = IrohCtx::new().await?;
+    iroh_ctx.wait_ready(Duration::from_secs(10)).await?;
+    println!("Ticket: {}", iroh_ctx.ticket_with_game_size(Some(9)).await?);
+
+    let runner = BotRunner::new(lobby, &IdentityManager::open_default()?, ArchiveManager::new()?, config, engines)?
+        .with_value(move |state| {
+            let model = model.lock().unwrap();
+            // An unreadable position is no reason to resign
+            trainer::evaluate::evaluate(&model, state, &device).map_or(0.0, |e| e.value)
+        })
+        .with_hooks(Announce);
+    println!("Playing as {}", runner.node_id());
+    runner.run().await?;
+    Ok(())
+}
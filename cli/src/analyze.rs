@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Position analysis for scripts: replays a game and reports, for each
+//! position asked for, the model's value estimate and likeliest moves.
+//!
+//! Results are handed out as they're computed, one per position. As JSON
+//! each is one line, shaped like [`PositionAnalysis`]. Fields may be added;
+//! [`SCHEMA_VERSION`] changes if one is removed or changes meaning.
+
+use std::fmt;
+use std::path::Path;
+use anyhow::{ensure, Context, Result};
+use burn::tensor::backend::Backend;
+use serde::{Serialize, Deserialize};
+use p2pgo_core::{Color, Coord, GameState, Move};
+use trainer::evaluate::{evaluate, point_probabilities};
+use trainer::{checkpoint, GoMini6E};
+
+/// Version written in every analysis
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Policy moves reported per position
+pub const TOP_MOVES: usize = 5;
+
+/// Which positions of a game to analyze
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Positions {
+    /// The start and every `n`th position after it
+    Every(usize),
+    /// Only the position after this many moves
+    Single(usize),
+}
+
+impl Positions {
+    /// Whether the position after `moves` moves is one of them
+    pub fn includes(self, moves: usize) -> bool {
+        match self {
+            Positions::Every(n) => moves % n.max(1) == 0,
+            Positions::Single(n) => moves == n,
+        }
+    }
+}
+
+/// A point and the policy's probability of playing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveProbability {
+    /// GTP vertex, e.g. "D4"
+    pub point: String,
+    pub probability: f32,
+}
+
+/// The model's view of one position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionAnalysis {
+    pub schema: u32,
+    /// Moves played to reach the position
+    pub move_number: usize,
+    /// The move that reached it as a GTP vertex, "pass" or "resign"
+    pub last_move: Option<String>,
+    pub to_play: Color,
+    /// Black's outlook from the value head, -1 to 1
+    pub value: f32,
+    /// Likeliest points first, from the same probabilities as the UI heat map
+    pub top_moves: Vec<MoveProbability>,
+}
+
+impl fmt::Display for PositionAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Move {:>3} {:<6} {:?} to play, value {:+.2}:",
+            self.move_number,
+            self.last_move.as_deref().unwrap_or("-"),
+            self.to_play,
+            self.value
+        )?;
+        for candidate in &self.top_moves {
+            write!(f, " {} {:.1}%", candidate.point, candidate.probability * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Load a model from a checkpoint file written by the trainer
+pub fn load_model<B: Backend>(path: &Path, device: &B::Device) -> Result<GoMini6E<B>> {
+    let checkpoint = checkpoint::read(path)?;
+    GoMini6E::from_checkpoint(&checkpoint, device)
+        .with_context(|| format!("Failed to load model {}", path.display()))
+}
+
+/// Analyze the positions of `game` picked by `positions`, handing each to
+/// `emit` as soon as it's computed
+pub fn analyze_game<B: Backend>(
+    model: &GoMini6E<B>,
+    device: &B::Device,
+    game: &GameState,
+    positions: Positions,
+    mut emit: impl FnMut(PositionAnalysis) -> Result<()>,
+) -> Result<()> {
+    let total = game.moves.len();
+    if let Positions::Single(n) = positions {
+        ensure!(n <= total, "The game has {} moves, so there is no position after move {}", total, n);
+    }
+
+    let mut state = game.initial_position();
+    for moves in 0..=total {
+        if moves > 0 {
+            state.apply_move(game.moves[moves - 1].clone())
+                .with_context(|| format!("Move {} of the game doesn't replay", moves))?;
+        }
+        if positions.includes(moves) {
+            emit(analyze_position(model, device, &state)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// The model's view of `state`
+pub fn analyze_position<B: Backend>(model: &GoMini6E<B>, device: &B::Device, state: &GameState) -> Result<PositionAnalysis> {
+    let evaluation = evaluate(model, state, device)?;
+    let probabilities = point_probabilities(&evaluation.logits, model.board_size(), state);
+    Ok(PositionAnalysis {
+        schema: SCHEMA_VERSION,
+        move_number: state.moves.len(),
+        last_move: state.moves.last().map(|mv| move_label(mv, state.board_size)),
+        to_play: state.current_player,
+        value: evaluation.value,
+        top_moves: top_moves(&probabilities, state.board_size, TOP_MOVES),
+    })
+}
+
+/// The `n` likeliest points of `probabilities`, likeliest first
+pub fn top_moves(probabilities: &[f32], board_size: u8, n: usize) -> Vec<MoveProbability> {
+    let mut points: Vec<(usize, f32)> = probabilities.iter().copied()
+        .enumerate()
+        .filter(|(_, probability)| *probability > 0.0)
+        .collect();
+    points.sort_by(|a, b| b.1.total_cmp(&a.1));
+    points.into_iter()
+        .filter_map(|(idx, probability)| {
+            let point = Coord::from_index(idx, board_size)?.display_label(board_size);
+            Some(MoveProbability { point, probability })
+        })
+        .take(n)
+        .collect()
+}
+
+fn move_label(mv: &Move, board_size: u8) -> String {
+    match mv {
+        Move::Place(coord) => coord.display_label(board_size),
+        Move::Pass => "pass".to_string(),
+        Move::Resign => "resign".to_string(),
+    }
+}
@@ -0,0 +1,191 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! P2P Go relay node
+//!
+//! Runs a seed node that other players can use as a bootstrap peer. The
+//! config file is described in `p2pgo_cli::relay_config`. SIGHUP reloads
+//! the limits and log filter; SIGTERM or Ctrl+C drains connections and exits.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use clap::Parser;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+use p2pgo_cli::relay_config::{RelayConfig, RelayConfigHandle, RelayLimits};
+use p2pgo_network::admission::Admission;
+use p2pgo_network::logging::{self, LogOptions};
+use p2pgo_network::IrohCtx;
+
+/// How long to wait for the endpoint to start listening
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long open connections get to close on shutdown
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Command-line arguments
+#[derive(Parser, Debug)]
+#[clap(
+    name = "p2pgo-relay",
+    about = "P2P Go relay and bootstrap node",
+    version
+)]
+struct Args {
+    /// Path to the TOML config file
+    #[clap(short, long, default_value = "relay.toml")]
+    config: PathBuf,
+
+    /// Validate the config file and exit
+    #[clap(long)]
+    check_config: bool,
+
+    /// Keep data in this directory and write logs to its logs/ instead of
+    /// stderr
+    #[clap(long)]
+    data_dir: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    let config = RelayConfig::load(&args.config)?;
+    if args.check_config {
+        println!("{}: OK", args.config.display());
+        return Ok(());
+    }
+
+    let log_dir = args.data_dir.as_ref().map(|dir| {
+        p2pgo_core::p2pgo_dirs::set_root(dir);
+        p2pgo_core::p2pgo_dirs::Dirs::under(dir).logs
+    });
+    logging::init(LogOptions { filter: config.log_level.clone(), dir: log_dir })?;
+
+    let (mut handle, limits_rx) = RelayConfigHandle::new(config);
+    let iroh_ctx = IrohCtx::bind_with_limits(&handle.config().listen_addrs, limits_rx.clone()).await?;
+    iroh_ctx.wait_ready(READY_TIMEOUT).await?;
+    print_bootstrap_addrs(&iroh_ctx, handle.config()).await;
+
+    tokio::spawn(watch_limits(limits_rx));
+    if let Some(port) = handle.config().metrics_port {
+        tokio::spawn(serve_metrics(port, iroh_ctx.node_id().to_string(), iroh_ctx.admission().clone()));
+    }
+
+    wait_for_shutdown(&mut handle, &args.config).await?;
+
+    println!("Shutting down relay, draining connections...");
+    match tokio::time::timeout(DRAIN_TIMEOUT, iroh_ctx.shutdown()).await {
+        Ok(result) => result?,
+        Err(_) => tracing::warn!("Connections did not drain within {:?}", DRAIN_TIMEOUT),
+    }
+    println!("Relay stopped");
+    Ok(())
+}
+
+/// Print the addresses other users can paste as bootstrap peers
+async fn print_bootstrap_addrs(iroh_ctx: &IrohCtx, config: &RelayConfig) {
+    println!("Relay node ID: {}", iroh_ctx.node_id());
+    match iroh_ctx.ticket().await {
+        Ok(ticket) => println!("Bootstrap ticket:\n{}", ticket),
+        Err(e) => println!("Warning: Failed to generate ticket: {}", e),
+    }
+
+    let mut addrs = config.announce_addrs.clone();
+    if addrs.is_empty() {
+        addrs = iroh_ctx.external_addrs().await.unwrap_or_default();
+    }
+    println!("Bootstrap addresses:");
+    for addr in addrs {
+        println!("  {}@{}", iroh_ctx.node_id(), addr);
+    }
+}
+
+/// Block until SIGTERM or Ctrl+C, reloading the config on SIGHUP
+#[cfg(unix)]
+async fn wait_for_shutdown(handle: &mut RelayConfigHandle, path: &Path) -> Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = signal(SignalKind::hangup())?;
+    let mut sigterm = signal(SignalKind::terminate())?;
+    loop {
+        tokio::select! {
+            _ = sighup.recv() => reload(handle, path),
+            _ = sigterm.recv() => return Ok(()),
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Block until Ctrl+C; there is no SIGHUP to reload on
+#[cfg(not(unix))]
+async fn wait_for_shutdown(_handle: &mut RelayConfigHandle, _path: &Path) -> Result<()> {
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn reload(handle: &mut RelayConfigHandle, path: &Path) {
+    match handle.reload(path) {
+        Ok(reload) => {
+            if let Some(filter) = &reload.log_filter {
+                if let Err(e) = logging::set_filter(filter) {
+                    tracing::error!("Failed to apply log_level {}: {:#}", filter, e);
+                }
+            }
+            if !reload.needs_restart.is_empty() {
+                tracing::warn!("Restart the relay to apply: {}", reload.needs_restart.join(", "));
+            }
+        }
+        Err(e) => tracing::error!("Config reload failed, keeping current limits: {:#}", e),
+    }
+}
+
+/// Log every limits change; the node's admission picks them up by itself
+async fn watch_limits(mut limits_rx: watch::Receiver<RelayLimits>) {
+    while limits_rx.changed().await.is_ok() {
+        let limits = *limits_rx.borrow();
+        tracing::info!(
+            max_connections = limits.max_connections,
+            max_bandwidth_bps = limits.max_bandwidth_bps,
+            "Relay limits updated"
+        );
+    }
+}
+
+/// Plain-text metrics for scraping
+async fn serve_metrics(port: u16, node_id: String, admission: Admission) {
+    let listener = match tokio::net::TcpListener::bind(("0.0.0.0", port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to bind metrics port {}: {}", port, e);
+            return;
+        }
+    };
+    let started = Instant::now();
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let limits = admission.limits();
+        let body = format!(
+            "p2pgo_relay_info{{node_id=\"{}\"}} 1\n\
+             p2pgo_relay_uptime_seconds {}\n\
+             p2pgo_relay_connections {}\n\
+             p2pgo_relay_bandwidth_bps {}\n\
+             p2pgo_relay_max_connections {}\n\
+             p2pgo_relay_max_bandwidth_bps {}\n",
+            node_id,
+            started.elapsed().as_secs(),
+            admission.open_connections(),
+            admission.bytes_per_sec(Instant::now()),
+            limits.max_connections,
+            limits.max_bandwidth_bps,
+        );
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+    }
+}
@@ -3,4 +3,6 @@
 //! P2P Go CLI library components
 
 pub mod render;
+pub mod analyze;
 pub mod log_streamer;
+pub mod relay_config;
@@ -9,14 +9,34 @@
 mod render;
 
 use anyhow::{Result, anyhow};
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use tokio::signal;
 use tokio::io::AsyncBufReadExt;
-use p2pgo_core::{GameState, Move, Coord};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use burn::backend::wgpu::Wgpu;
+use burn::tensor::backend::Backend;
+use p2pgo_core::{GameState, coords::parse_move, sgf::SgfProcessor};
+use p2pgo_core::archiver::{self, ClassificationFilter, ExportFormat, ImportOptions};
+use p2pgo_cli::analyze::{self, Positions};
+use render::{Charset, RenderMode, RenderOptions};
 use p2pgo_network::{
     Lobby,
     GameChannel,
+    lobby::{GameInfo, LobbyEvent},
+    invite::Invite,
+    logging::{self, LogFilter, LogOptions},
+    session_log::{self, SessionLog},
+    relay_bench::{self, BenchOptions},
+    tournament,
 };
+use tracing::level_filters::LevelFilter;
+
+/// How long to wait for the local endpoint to become ready
+const READY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// How long to wait for a game advertisement after connecting by ticket
+const ADVERT_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 /// Command-line arguments
 #[derive(Parser, Debug)]
@@ -42,6 +62,10 @@ struct Args {
     #[clap(long)]
     list: bool,
     
+    /// Print the games from --list as one JSON object per line
+    #[clap(long, requires = "list")]
+    json: bool,
+    
     /// Path to engine executable (future feature)
     #[clap(long)]
     engine: Option<String>,
@@ -57,6 +81,132 @@ struct Args {
     /// Connect directly using a ticket string
     #[clap(long)]
     ticket: Option<String>,
+    
+    /// Join the game in an invite link (p2pgo://invite/...)
+    #[clap(long, conflicts_with = "ticket")]
+    invite: Option<String>,
+    
+    /// How much of the board to print after each move
+    #[clap(long, value_enum, default_value_t = RenderMode::Full)]
+    render: RenderMode,
+    
+    /// Keep games, keys and logs in this directory instead of the
+    /// platform's
+    #[clap(long)]
+    data_dir: Option<PathBuf>,
+    
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands that run instead of a game
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Replay a session recorded with the UI's --record, checking every
+    /// step reaches the recorded game state
+    Replay {
+        /// The .p2plog file
+        path: PathBuf,
+    },
+    /// Print the model's value estimate and likeliest moves for positions
+    /// of a game, as each is computed
+    Analyze {
+        /// The game to replay
+        #[clap(long)]
+        sgf: PathBuf,
+        /// Model weights, as published by the trainer
+        #[clap(long)]
+        model: PathBuf,
+        /// Analyze the start and every Nth position after it
+        #[clap(long, default_value_t = 1)]
+        every: usize,
+        /// Analyze only the position after this many moves
+        #[clap(long, conflicts_with = "every")]
+        position: Option<usize>,
+        /// Print one JSON object per line instead of text
+        #[clap(long)]
+        json: bool,
+    },
+    /// Write every archived game to one file
+    Export {
+        /// Output file: .sgf for one SGF collection, .zip for a zip of SGF files
+        out: PathBuf,
+        /// Put the raw archive records in the zip instead of SGF
+        #[clap(long)]
+        cbor: bool,
+        /// Leave out games without a scored result
+        #[clap(long)]
+        only_scored: bool,
+        /// Leave out games kept out of training
+        #[clap(long)]
+        only_training: bool,
+        /// Leave out games without this tag; repeat for several
+        #[clap(long = "tag")]
+        tags: Vec<String>,
+        /// Archive directory, the UI's by default
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Add the games of a folder of SGF files to the archive, leaving out
+    /// those already in it
+    Import {
+        /// Folder of .sgf files
+        folder: PathBuf,
+        /// Keep the imported games out of training
+        #[clap(long)]
+        no_training: bool,
+        /// Archive directory, the UI's by default
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Print the games in the lobby, then each one as it is advertised,
+    /// until interrupted
+    Watch {
+        /// Print one JSON object per line instead of text
+        #[clap(long)]
+        json: bool,
+    },
+    /// Work with a tournament's published results
+    Tournament {
+        #[clap(subcommand)]
+        command: TournamentCommand,
+    },
+    /// Play a scripted game between two local peers that may only reach
+    /// each other through a relay, and report how it went
+    BenchRelay {
+        /// The relay to go through
+        #[clap(long)]
+        relay: String,
+        /// Fail if 95% of moves don't arrive within this many milliseconds
+        #[clap(long)]
+        max_p95_ms: Option<u64>,
+        /// Print the report as JSON instead of text
+        #[clap(long)]
+        json: bool,
+    },
+    /// Summarize a folder of training games: sizes, results, coverage of
+    /// the game and labels that can't be right
+    DatasetStats {
+        /// Folder of .cbor training games
+        dir: PathBuf,
+        /// Print the report as JSON instead of Markdown
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// What to do with a tournament's results
+#[derive(Subcommand, Debug)]
+enum TournamentCommand {
+    /// Check every result in a folder's results.cbor against the games
+    /// archived next to it, then print the standings of those that pass
+    Verify {
+        /// Folder with the results and the archived games
+        dir: PathBuf,
+        /// Print the standings as JSON instead of CSV
+        #[clap(long)]
+        json: bool,
+    },
 }
 
 /// Role of this instance
@@ -73,6 +223,9 @@ enum Role {
 async fn main() -> Result<()> {
     // Parse command-line arguments
     let args = Args::parse();
+    if let Some(dir) = &args.data_dir {
+        p2pgo_core::p2pgo_dirs::set_root(dir);
+    }
     
     // Initialize crash logger
     if let Err(e) = p2pgo_network::init_crash_logger().await {
@@ -98,14 +251,48 @@ async fn main() -> Result<()> {
         eprintln!("PANIC: {}", error);
     }));
     
-    // Setup debug logging if requested
+    // Quiet unless debugging, so logs don't mix with the board
+    let level = if args.debug { LevelFilter::DEBUG } else { LevelFilter::OFF };
+    logging::init(LogOptions { filter: LogFilter::level(level), dir: None })?;
+    p2pgo_core::p2pgo_dirs::migrate_current();
     if args.debug {
-        tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .init();
         println!("Debug mode enabled - blob hashes will be printed");
     }
     
+    // Replays, analysis and benchmarks don't join a game
+    match &args.command {
+        Some(Command::Replay { path }) => return replay_session(path).await,
+        Some(Command::Analyze { sgf, model, every, position, json }) => {
+            let positions = match position {
+                Some(n) => Positions::Single(*n),
+                None if *every == 0 => return Err(anyhow!("--every must be at least 1")),
+                None => Positions::Every(*every),
+            };
+            return analyze_sgf(sgf, model, positions, *json);
+        }
+        Some(Command::Export { out, cbor, only_scored, only_training, tags, dir }) => {
+            let filter = ClassificationFilter {
+                only_scored: *only_scored,
+                only_training_eligible: *only_training,
+                tags: tags.clone(),
+            };
+            return export_archive(out, *cbor, &filter, dir.as_deref());
+        }
+        Some(Command::Import { folder, no_training, dir }) => {
+            return import_sgf(folder, !*no_training, dir.as_deref());
+        }
+        Some(Command::Tournament { command: TournamentCommand::Verify { dir, json } }) => {
+            return verify_tournament(dir, *json);
+        }
+        Some(Command::BenchRelay { relay, max_p95_ms, json }) => {
+            return bench_relay(relay, max_p95_ms.map(std::time::Duration::from_millis), *json).await;
+        }
+        Some(Command::DatasetStats { dir, json }) => {
+            return dataset_stats(dir, *json);
+        }
+        Some(Command::Watch { .. }) | None => {}
+    }
+    
     // Validate board size
     if ![9, 13, 19].contains(&args.size) {
         return Err(anyhow!("Invalid board size. Must be 9, 13, or 19."));
@@ -120,8 +307,24 @@ async fn main() -> Result<()> {
     // Create a lobby service
     let lobby = Lobby::new();
     
-    // Initialize Iroh context
-    let iroh_ctx = p2pgo_network::IrohCtx::new().await?;
+    // Initialize Iroh context and wait until it is listening
+    let iroh_ctx = std::sync::Arc::new(p2pgo_network::IrohCtx::new().await?);
+    iroh_ctx.wait_ready(READY_TIMEOUT).await?;
+    lobby.set_transport(iroh_ctx.clone()).await;
+    
+    // An invite names the game, so join it rather than the first one listed
+    if let Some(link) = args.invite.as_ref() {
+        let invite = Invite::parse(link)?;
+        println!("Connecting to {}'s {}x{} game {}", invite.host_name, invite.board_size, invite.board_size, invite.game_id);
+        iroh_ctx.connect_by_ticket(&invite.ticket).await?;
+        
+        let game = lobby.wait_for_game_id(&invite.game_id, ADVERT_WAIT_TIMEOUT).await
+            .ok_or_else(|| anyhow!("Game {} was not advertised after connecting", invite.game_id))?;
+        let channel = lobby.get_game_channel(&game.id).await?;
+        let game_state = channel.get_latest_state().await
+            .ok_or_else(|| anyhow!("Failed to get current game state"))?;
+        return run_game_loop(game_state, channel, lobby, game.id.clone(), args.debug, render_options(&args)).await;
+    }
     
     // Handle ticket connection if provided
     if let Some(ticket) = args.ticket.as_ref() {
@@ -135,23 +338,19 @@ async fn main() -> Result<()> {
             Err(e) => println!("Warning: Failed to generate ticket: {}", e),
         }
         
-        // Wait a moment for game advertisements to arrive
+        // Wait for a game advertisement to arrive
         println!("Waiting for game advertisement...");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-        
-        // Check for available games
-        let games = lobby.list_games().await;
-        if games.is_empty() {
+        if lobby.wait_for_game(ADVERT_WAIT_TIMEOUT).await.is_none() {
             println!("No games available after connection. Try refreshing or creating a game.");
         } else {
+            let games = lobby.list_games().await;
             println!("Available games after connection:");
             for game in &games {
-                println!("  {} - {}x{} - Started: {}", 
-                    game.id, game.board_size, game.board_size, game.started);
+                print_game(game, false)?;
             }
             
-            // If there's a game, join it automatically
-            if let Some(game) = games.first() {
+            // If there's a game waiting for an opponent, join it automatically
+            if let Some(game) = games.iter().find(|game| game.state.joinable()) {
                 println!("Auto-joining game: {}", game.id);
                 
                 // Get the game channel
@@ -162,7 +361,7 @@ async fn main() -> Result<()> {
                     .ok_or_else(|| anyhow!("Failed to get current game state"))?;
                 
                 // Run the game loop
-                return run_game_loop(game_state, channel, lobby, game.id.clone(), args.debug).await;
+                return run_game_loop(game_state, channel, lobby, game.id.clone(), args.debug, render_options(&args)).await;
             }
         }
     }
@@ -170,18 +369,25 @@ async fn main() -> Result<()> {
     // Handle list command
     if args.list {
         let games = lobby.list_games().await;
-        if games.is_empty() {
+        if args.json {
+            for game in &games {
+                print_game(game, true)?;
+            }
+        } else if games.is_empty() {
             println!("No games available.");
         } else {
             println!("Available games:");
-            for game in games {
-                println!("  {} - {}x{} - Started: {}", 
-                    game.id, game.board_size, game.board_size, game.started);
+            for game in &games {
+                print_game(game, false)?;
             }
         }
         return Ok(());
     }
     
+    if let Some(Command::Watch { json }) = &args.command {
+        return watch_games(&lobby, *json).await;
+    }
+    
     // Handle spectator mode
     if args.spectator {
         println!("Starting spectator-only seed node...");
@@ -229,7 +435,11 @@ async fn main() -> Result<()> {
             
             // Generate and display a ticket for direct connections
             match iroh_ctx.ticket().await {
-                Ok(ticket) => println!("Share this ticket with opponent:\n{}", ticket),
+                Ok(ticket) => {
+                    println!("Share this ticket with opponent:\n{}", ticket);
+                    let invite = Invite::new(ticket, game_id.clone(), args.size, "p2pgo-cli".to_string());
+                    println!("Or this invite link, which joins this game:\n{}", invite.to_link());
+                }
                 Err(e) => println!("Warning: Failed to generate ticket: {}", e),
             }
             
@@ -241,7 +451,7 @@ async fn main() -> Result<()> {
                 .ok_or_else(|| anyhow!("Failed to get initial game state"))?;
             
             // Run the game loop
-            run_game_loop(game_state, channel, lobby, game_id, args.debug).await?;
+            run_game_loop(game_state, channel, lobby, game_id, args.debug, render_options(&args)).await?;
         }
         Role::Join => {
             // Check if we have a game ID
@@ -258,7 +468,7 @@ async fn main() -> Result<()> {
                 .ok_or_else(|| anyhow!("Failed to get current game state"))?;
             
             // Run the game loop
-            run_game_loop(game_state, channel, lobby, game_id_str, args.debug).await?;
+            run_game_loop(game_state, channel, lobby, game_id_str, args.debug, render_options(&args)).await?;
         }
     }
     
@@ -271,10 +481,11 @@ async fn run_game_loop(
     channel: std::sync::Arc<GameChannel>,
     lobby: Lobby,
     game_id: String,
-    debug: bool
+    debug: bool,
+    render_opts: RenderOptions,
 ) -> Result<()> {
     // Print the initial game state
-    print_game_state(&game_state);
+    print_game_state(&game_state, &render_opts);
     
     // Set up subscription to game events
     let mut event_rx = channel.subscribe();
@@ -330,11 +541,11 @@ async fn run_game_loop(
                 }
                 
                 // Print the updated game state
-                print_game_state(&game_state);
+                print_game_state(&game_state, &render_opts);
                 
                 // Check if the game is over
-                if game_state.is_game_over() {
-                    println!("Game over!");
+                if let Some(event) = game_state.game_ended_event() {
+                    println!("{}", render::game_over_message(&event).unwrap_or_default());
                     break;
                 }
             }
@@ -362,13 +573,16 @@ async fn run_game_loop(
                             if let Err(e) = game_state.apply_move(mv) {
                                 eprintln!("Failed to apply remote move: {}", e);
                             } else {
-                                print_game_state(&game_state);
+                                print_game_state(&game_state, &render_opts);
                             }
                         }
-                        p2pgo_core::GameEvent::GameEnded { .. } => {
-                            println!("Game over!");
+                        event @ p2pgo_core::GameEvent::GameEnded { .. } => {
+                            println!("{}", render::game_over_message(&event).unwrap_or_default());
                             break;
                         }
+                        event @ p2pgo_core::GameEvent::ByoYomiPeriodUsed { .. } => {
+                            println!("{}", render::period_used_message(&event).unwrap_or_default());
+                        }
                         _ => {
                             // Handle other events as needed
                         }
@@ -382,70 +596,212 @@ async fn run_game_loop(
         }
     }
     
+    if debug {
+        println!("DEBUG: Channel metrics: {}", channel.metrics());
+    }
+    
+    Ok(())
+}
+
+/// Print a lobby game with its state and terms, as text or as one line of JSON
+fn print_game(game: &GameInfo, json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(game)?);
+    } else {
+        println!("  {} - {}x{} - {} - {}{}",
+            game.id, game.board_size, game.board_size, game.state, game.terms, game_action(game));
+    }
     Ok(())
 }
 
-/// Parse a move from a string
-fn parse_move(input: &str, board_size: u8) -> Result<Move> {
-    let input = input.to_lowercase();
-    
-    if input == "pass" {
-        return Ok(Move::Pass);
-    } else if input == "resign" {
-        return Ok(Move::Resign);
+/// What others may do with a listed game
+fn game_action(game: &GameInfo) -> &'static str {
+    if game.state.joinable() {
+        " - open to join"
+    } else if game.state.watchable() {
+        " - open to spectators"
+    } else {
+        ""
     }
-    
-    // Parse coordinate like "D4"
-    if input.len() >= 2 {
-        let col_char = input.chars().next().unwrap();
-        let row_str = &input[1..];
-        
-        // Parse column (A-T, skipping I)
-        let col = if ('a'..='h').contains(&col_char) {
-            col_char as u8 - b'a'
-        } else if ('j'..='t').contains(&col_char) {
-            col_char as u8 - b'a' - 1 // Skip 'i'
-        } else {
-            return Err(anyhow!("Invalid column. Must be A-T (excluding I)."));
-        };
-        
-        // Parse row (1-19)
-        let row = match row_str.parse::<u8>() {
-            Ok(r) if r > 0 && r <= board_size => r - 1, // Convert to 0-indexed
-            _ => return Err(anyhow!("Invalid row. Must be between 1 and {}.", board_size)),
-        };
-        
-        // Check if the coordinate is valid
-        if col < board_size && row < board_size {
-            return Ok(Move::Place(Coord::new(col, row)));
+}
+
+/// Print the games listed now and each one advertised after, until Ctrl+C
+async fn watch_games(lobby: &Lobby, json: bool) -> Result<()> {
+    let mut events = lobby.subscribe();
+    for game in lobby.list_games().await {
+        print_game(&game, json)?;
+    }
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(LobbyEvent::GameCreated(game)) => print_game(&game, json)?,
+                Ok(LobbyEvent::GameStateChanged { game_id, state }) => {
+                    if let Some(game) = lobby.list_games().await.into_iter().find(|game| game.id == game_id && game.state == state) {
+                        print_game(&game, json)?;
+                    }
+                }
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            _ = signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
+
+/// Replay a recorded session on a virtual clock; fails at the first step
+/// whose moves differ from the recording
+async fn replay_session(path: &Path) -> Result<()> {
+    let log = SessionLog::read(path)?;
+    let report = session_log::replay(&log).await?;
+    println!("Replayed {} steps from {}", report.steps, path.display());
+    for (game_id, moves) in &report.games {
+        println!("  {}: {} moves, as recorded", game_id, moves.len());
+    }
+    Ok(())
+}
+
+/// Write the archived games passing `filter` to `out`, in the format its
+/// extension names
+fn export_archive(out: &Path, cbor: bool, filter: &ClassificationFilter, dir: Option<&Path>) -> Result<()> {
+    let format = match out.extension().and_then(|e| e.to_str()) {
+        Some("zip") if cbor => ExportFormat::ZipOfCbor,
+        Some("zip") => ExportFormat::ZipOfSgf,
+        Some("sgf") if !cbor => ExportFormat::SgfCollection,
+        Some("sgf") => return Err(anyhow!("Raw records only go in a .zip")),
+        _ => return Err(anyhow!("Export to a .sgf or .zip file")),
+    };
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => archiver::archive_dir()?,
+    };
+    let file = std::fs::File::create(out)
+        .map_err(|e| anyhow!("Failed to create {}: {}", out.display(), e))?;
+    let report = archiver::export_all(&dir, file, format, filter)?;
+    println!("Exported {} games to {}", report.exported, out.display());
+    if !report.skipped.is_empty() {
+        println!("{} games left out by the filter", report.skipped.len());
+    }
+    for (id, reason) in &report.corrupt {
+        eprintln!("Skipped unreadable record {}: {}", id, reason);
+    }
+    Ok(())
+}
+
+/// Import the SGF files in `folder` into the archive, a line per file
+fn import_sgf(folder: &Path, training_eligible: bool, dir: Option<&Path>) -> Result<()> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => archiver::archive_dir()?,
+    };
+    let options = ImportOptions { training_eligible, ..ImportOptions::new(dir) };
+    let report = archiver::import_sgf_dir_with(folder, &options, |progress| {
+        println!("[{}/{}] {}", progress.done, progress.total, progress.file);
+    })?;
+    println!("{}", report.summary());
+    for file in &report.duplicates {
+        println!("Already archived: {}", file);
+    }
+    for (file, reason) in report.skipped.iter().chain(&report.failed) {
+        eprintln!("Not imported {}: {}", file, reason);
+    }
+    Ok(())
+}
+
+/// Verify the tournament results in `dir`, failing if any don't check out
+fn verify_tournament(dir: &Path, json: bool) -> Result<()> {
+    let report = tournament::verify_dir(dir)?;
+    for (index, game_id, reason) in &report.failures {
+        eprintln!("Result {} ({}) fails: {}", index, game_id, reason);
+    }
+    if json {
+        println!("{}", report.ledger.standings_json()?);
+    } else {
+        print!("{}", report.ledger.standings_csv());
+    }
+    if !report.failures.is_empty() {
+        return Err(anyhow!("{} of {} results failed verification", report.failures.len(), report.checked()));
+    }
+    eprintln!("All {} results verified", report.checked());
+    Ok(())
+}
+
+/// Benchmark a game through `relay`, failing if its p95 latency is over `max_p95`
+async fn bench_relay(relay: &str, max_p95: Option<std::time::Duration>, json: bool) -> Result<()> {
+    let report = relay_bench::run(&BenchOptions::new(relay)).await?;
+    if json {
+        println!("{}", report.to_json()?);
+    } else {
+        println!("{}", report);
+    }
+    if let Some(max_p95) = max_p95 {
+        if !report.within(max_p95) {
+            return Err(anyhow!("p95 latency {:.1} ms is over the {} ms limit", report.latency.p95_ms, max_p95.as_millis()));
         }
     }
+    Ok(())
+}
+
+/// Print what the training games in `dir` add up to
+fn dataset_stats(dir: &Path, json: bool) -> Result<()> {
+    let dataset = trainer::load_games_from_dir(dir)
+        .map_err(|e| anyhow!("Failed to load training games from {}: {}", dir.display(), e))?;
+    let stats = dataset.stats();
+    if json {
+        println!("{}", stats.to_json()?);
+    } else {
+        print!("{}", stats.to_markdown());
+    }
+    Ok(())
+}
+
+/// Print the analysis of each position of the game in `sgf` as it's computed
+fn analyze_sgf(sgf: &Path, model: &Path, positions: Positions, json: bool) -> Result<()> {
+    let text = std::fs::read_to_string(sgf)
+        .map_err(|e| anyhow!("Failed to read {}: {}", sgf.display(), e))?;
+    let game = SgfProcessor::new(GameState::new(19)).parse(&text)?;
+    let device = <Wgpu as Backend>::Device::default();
+    let model = analyze::load_model::<Wgpu>(model, &device)?;
     
-    Err(anyhow!("Invalid move format. Examples: 'D4', 'pass', 'resign'."))
+    let mut out = std::io::stdout().lock();
+    analyze::analyze_game(&model, &device, &game, positions, |analysis| {
+        if json {
+            serde_json::to_writer(&mut out, &analysis)?;
+            writeln!(out)?;
+        } else {
+            writeln!(out, "{}", analysis)?;
+        }
+        // Long games show progress as they go
+        out.flush()?;
+        Ok(())
+    })
+}
+
+/// Board rendering chosen on the command line, with the charset the terminal supports
+fn render_options(args: &Args) -> RenderOptions {
+    RenderOptions { mode: args.render, charset: Charset::detect() }
 }
 
 /// Print the current game state
-fn print_game_state(game_state: &GameState) {
-    println!("\nBoard size: {}x{}", game_state.board_size, game_state.board_size);
-    println!("Current player: {:?}", game_state.current_player);
-    println!("Moves: {}", game_state.moves.len());
-    println!("Captures: Black {} - White {}", game_state.captures.0, game_state.captures.1);
-    
-    // Render the ASCII board
-    println!("\n{}", render::render_board(game_state));
+fn print_game_state(game_state: &GameState, opts: &RenderOptions) {
+    let board = render::render(game_state, opts);
+    if !board.is_empty() {
+        println!("\n{}", board);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use p2pgo_core::Move;
     
     #[test]
-    #[ignore = "Implementation needed"]
     fn test_parse_move() {
-        // Test parsing various move formats
-        assert!(matches!(parse_move("D4", 19).unwrap(), Move::Place(coord) if coord.x == 3 && coord.y == 3));
+        // Test parsing various move formats; rows count from the bottom
+        assert!(matches!(parse_move("D4", 19).unwrap(), Move::Place(coord) if coord.x == 3 && coord.y == 15));
+        assert!(matches!(parse_move("j9", 9).unwrap(), Move::Place(coord) if coord.x == 8 && coord.y == 0));
         assert!(matches!(parse_move("pass", 19).unwrap(), Move::Pass));
         assert!(matches!(parse_move("resign", 19).unwrap(), Move::Resign));
         assert!(parse_move("Z9", 19).is_err()); // Invalid column
+        assert!(parse_move("T4", 9).is_err()); // Column off a 9x9 board
     }
 }
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Configuration file for the `p2pgo-relay` binary.
+//!
+//! ```toml
+//! listen_addrs = ["0.0.0.0:4433"]
+//! announce_addrs = ["relay.example.org:4433"]
+//! metrics_port = 9090
+//! log_level = "info"
+//!
+//! [limits]
+//! max_connections = 256
+//! max_bandwidth_bps = 10485760
+//! ```
+//!
+//! Only `[limits]` and `log_level` can change on reload; everything else
+//! needs a restart.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use anyhow::{Context, Result};
+use serde::{Serialize, Deserialize};
+use tokio::sync::watch;
+use p2pgo_network::logging::LogFilter;
+
+/// Relay settings that can be changed without a restart; the relay node
+/// enforces them when it accepts connections
+pub use p2pgo_network::admission::ConnectionLimits as RelayLimits;
+
+/// Contents of a relay config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RelayConfig {
+    /// Local addresses to bind; empty lets the endpoint pick
+    pub listen_addrs: Vec<SocketAddr>,
+    /// Public addresses to print for other users, e.g. behind NAT
+    pub announce_addrs: Vec<String>,
+    /// Port for the plain-text metrics endpoint, if any
+    pub metrics_port: Option<u16>,
+    /// A level or log filter, e.g. `info,p2pgo_network::game_channel=debug`
+    pub log_level: LogFilter,
+    pub limits: RelayLimits,
+}
+
+/// Result of applying a changed config file to a running relay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigReload {
+    /// Limits to use from now on
+    pub limits: RelayLimits,
+    /// Log filter to use from now on, if it changed
+    pub log_filter: Option<LogFilter>,
+    /// Settings that changed but only take effect after a restart
+    pub needs_restart: Vec<&'static str>,
+}
+
+impl RelayConfig {
+    /// Parse and validate a config file's contents
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text).context("Invalid relay config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read, parse and validate a config file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_toml(&text).with_context(|| format!("In {}", path.display()))
+    }
+
+    /// Check values that parse but make no sense
+    pub fn validate(&self) -> Result<()> {
+        if self.limits.max_connections == 0 {
+            anyhow::bail!("limits.max_connections must be at least 1");
+        }
+        if self.limits.max_bandwidth_bps == 0 {
+            anyhow::bail!("limits.max_bandwidth_bps must be at least 1");
+        }
+        if let Some(port) = self.metrics_port {
+            if port == 0 {
+                anyhow::bail!("metrics_port must not be 0");
+            }
+            if self.listen_addrs.iter().any(|addr| addr.port() == port) {
+                anyhow::bail!("metrics_port {} is also a listen port", port);
+            }
+        }
+        for addr in &self.announce_addrs {
+            if addr.trim().is_empty() {
+                anyhow::bail!("announce_addrs must not contain empty entries");
+            }
+        }
+        Ok(())
+    }
+
+    /// Work out what a reloaded config changes for a relay running `self`
+    pub fn reload(&self, new: &RelayConfig) -> ConfigReload {
+        let mut needs_restart = Vec::new();
+        if new.listen_addrs != self.listen_addrs {
+            needs_restart.push("listen_addrs");
+        }
+        if new.announce_addrs != self.announce_addrs {
+            needs_restart.push("announce_addrs");
+        }
+        if new.metrics_port != self.metrics_port {
+            needs_restart.push("metrics_port");
+        }
+        ConfigReload {
+            limits: new.limits,
+            log_filter: (new.log_level != self.log_level).then(|| new.log_level.clone()),
+            needs_restart,
+        }
+    }
+}
+
+/// Running config of a relay; publishes limit changes to its tasks
+#[derive(Debug)]
+pub struct RelayConfigHandle {
+    config: RelayConfig,
+    limits_tx: watch::Sender<RelayLimits>,
+}
+
+impl RelayConfigHandle {
+    /// Start from `config`; the receiver sees every limits change
+    pub fn new(config: RelayConfig) -> (Self, watch::Receiver<RelayLimits>) {
+        let (limits_tx, limits_rx) = watch::channel(config.limits);
+        (Self { config, limits_tx }, limits_rx)
+    }
+
+    /// Config currently in effect
+    pub fn config(&self) -> &RelayConfig {
+        &self.config
+    }
+
+    /// Re-read the config file and apply its limits and log filter.
+    ///
+    /// A file that fails to load leaves the running limits untouched.
+    /// Settings that need a restart are reported but not applied.
+    pub fn reload(&mut self, path: impl AsRef<Path>) -> Result<ConfigReload> {
+        let new = RelayConfig::load(path)?;
+        let reload = self.config.reload(&new);
+        self.config.limits = reload.limits;
+        if let Some(filter) = &reload.log_filter {
+            self.config.log_level = filter.clone();
+        }
+        self.limits_tx.send_replace(reload.limits);
+        Ok(reload)
+    }
+}
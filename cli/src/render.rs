@@ -1,77 +1,197 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-//! ASCII board rendering for the CLI.
+//! Text board rendering for the CLI.
 
-use p2pgo_core::{GameState, Color, Coord};
+use p2pgo_core::{GameState, GameEvent, Color, Coord, Move};
+use p2pgo_core::coords::{gtp_column, gtp_row};
+use p2pgo_core::settings::standard_komi;
 
-/// Render the game board as ASCII art
-pub fn render_board(game_state: &GameState) -> String {
+/// How much the CLI prints after each move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RenderMode {
+    /// Column letters on top, row numbers on the left, one status line
+    Compact,
+    /// Labels on every side and the status line
+    #[default]
+    Full,
+    /// No board at all
+    None,
+}
+
+/// Characters the board is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// X and O on a grid of dots
+    Ascii,
+    /// ● and ○ on a box-drawing grid
+    Unicode,
+}
+
+impl Charset {
+    /// Unicode if the terminal looks able to show it, ASCII otherwise
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    /// [`Charset::detect`] with the environment given by `var`
+    pub fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        if var("TERM").as_deref() == Some("dumb") {
+            return Charset::Ascii;
+        }
+        // The first locale variable that is set decides, as in setlocale(3)
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|name| var(name).filter(|value| !value.is_empty()))
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if locale.contains("utf-8") || locale.contains("utf8") {
+            Charset::Unicode
+        } else {
+            Charset::Ascii
+        }
+    }
+}
+
+/// Options for [`render`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub mode: RenderMode,
+    pub charset: Charset,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            mode: RenderMode::Full,
+            charset: Charset::Unicode,
+        }
+    }
+}
+
+/// The board and status line as `options` asks for; empty for [`RenderMode::None`]
+pub fn render(game_state: &GameState, options: &RenderOptions) -> String {
+    match options.mode {
+        RenderMode::None => String::new(),
+        RenderMode::Compact => {
+            let mut output = render_grid(game_state, options.charset, false);
+            output.push_str(&status_line(game_state));
+            output.push('\n');
+            output
+        }
+        RenderMode::Full => {
+            let mut output = render_grid(game_state, options.charset, true);
+            output.push('\n');
+            output.push_str(&status_line(game_state));
+            output.push('\n');
+            output
+        }
+    }
+}
+
+/// Whose turn it is, the last move, prisoners and komi
+pub fn status_line(game_state: &GameState) -> String {
     let size = game_state.board_size;
+    let last = match game_state.moves.last() {
+        Some(Move::Place(coord)) => coord.display_label(size),
+        Some(Move::Pass) => "pass".to_string(),
+        Some(Move::Resign) => "resign".to_string(),
+        None => "-".to_string(),
+    };
+    format!(
+        "{:?} to move | Last: {} | Prisoners: Black {}, White {} | Komi: {}",
+        game_state.current_player,
+        last,
+        game_state.captures.0,
+        game_state.captures.1,
+        standard_komi(size)
+    )
+}
+
+/// The board rows, the last move in parentheses.
+///
+/// `full` adds row numbers on the right and column letters below.
+fn render_grid(game_state: &GameState, charset: Charset, full: bool) -> String {
+    let size = game_state.board_size;
+    let last = match game_state.moves.last() {
+        Some(Move::Place(coord)) => Some(*coord),
+        _ => None,
+    };
     let mut output = String::new();
     
-    // Add column labels
-    output.push_str("   ");
-    for col in 0..size {
-        let col_char = coord_to_column_char(col);
-        output.push_str(&format!(" {}", col_char));
-    }
-    output.push('\n');
+    push_column_labels(&mut output, size);
     
-    // Add rows with row numbers and board content
     for row in 0..size {
-        // Row number (1-indexed)
-        output.push_str(&format!("{:2} ", row + 1));
+        // Row number as in GTP: the bottom row is 1
+        output.push_str(&format!("{:2} ", gtp_row(row, size)));
         
         for col in 0..size {
             let coord = Coord::new(col, row);
-            let idx = (row as usize) * (size as usize) + (col as usize);
-            
-            let symbol = match game_state.board.get(idx).unwrap_or(&None) {
-                Some(Color::Black) => "●",
-                Some(Color::White) => "○",
-                None => {
-                    // Check if this is a star point
-                    if is_star_point(coord, size) {
-                        "+"
-                    } else if (row == 0 || row == size - 1) && (col == 0 || col == size - 1) {
-                        // Corner
-                        "+"
-                    } else if row == 0 || row == size - 1 || col == 0 || col == size - 1 {
-                        // Edge
-                        "+"
-                    } else {
-                        // Normal intersection
-                        "+"
-                    }
-                }
+            // The marker takes the place of the gaps around the last move
+            let gap = if last == Some(coord) {
+                "("
+            } else if col > 0 && last == Some(Coord::new(col - 1, row)) {
+                ")"
+            } else if col > 0 && charset == Charset::Unicode {
+                "─"
+            } else {
+                " "
             };
-            
-            output.push_str(&format!(" {}", symbol));
+            output.push_str(gap);
+            output.push_str(point_symbol(game_state, coord, charset));
+        }
+        let closing = if last == Some(Coord::new(size - 1, row)) { ")" } else { " " };
+        if full {
+            output.push_str(&format!("{}{}", closing, gtp_row(row, size)));
+        } else if closing == ")" {
+            output.push_str(closing);
         }
-        
-        // Add row number again on the right
-        output.push_str(&format!(" {}", row + 1));
         output.push('\n');
     }
     
-    // Add column labels again at bottom
-    output.push_str("   ");
-    for col in 0..size {
-        let col_char = coord_to_column_char(col);
-        output.push_str(&format!(" {}", col_char));
+    if full {
+        push_column_labels(&mut output, size);
     }
-    output.push('\n');
     
     output
 }
 
-/// Convert a column index to a column character (A-T, skipping I)
-fn coord_to_column_char(col: u8) -> char {
-    if col < 8 {
-        (b'A' + col) as char
-    } else {
-        (b'A' + col + 1) as char // Skip 'I'
+fn point_symbol(game_state: &GameState, coord: Coord, charset: Charset) -> &'static str {
+    let size = game_state.board_size;
+    let stone = game_state.board.get(coord.to_index(size)).copied().flatten();
+    match (charset, stone) {
+        (Charset::Unicode, Some(Color::Black)) => "●",
+        (Charset::Unicode, Some(Color::White)) => "○",
+        (Charset::Ascii, Some(Color::Black)) => "X",
+        (Charset::Ascii, Some(Color::White)) => "O",
+        (Charset::Ascii, None) if is_star_point(coord, size) => "+",
+        (Charset::Ascii, None) => ".",
+        (Charset::Unicode, None) => {
+            let (top, bottom) = (coord.y == 0, coord.y == size - 1);
+            let (left, right) = (coord.x == 0, coord.x == size - 1);
+            match (top, bottom, left, right) {
+                (true, _, true, _) => "┌",
+                (true, _, _, true) => "┐",
+                (_, true, true, _) => "└",
+                (_, true, _, true) => "┘",
+                (true, ..) => "┬",
+                (_, true, ..) => "┴",
+                (_, _, true, _) => "├",
+                (_, _, _, true) => "┤",
+                _ if is_star_point(coord, size) => "╋",
+                _ => "┼",
+            }
+        }
+    }
+}
+
+/// Append the column label line (A-T, skipping I)
+fn push_column_labels(output: &mut String, size: u8) {
+    output.push_str("   ");
+    for col in 0..size {
+        let col_char = gtp_column(col).unwrap_or('?');
+        output.push_str(&format!(" {}", col_char));
     }
+    output.push('\n');
 }
 
 /// Check if a coordinate is a star point on the board
@@ -100,11 +220,46 @@ fn is_star_point(coord: Coord, board_size: u8) -> bool {
     }
 }
 
+/// The line announcing a `GameEnded` event, or `None` for other events
+pub fn game_over_message(event: &GameEvent) -> Option<String> {
+    let GameEvent::GameEnded { winner, score_diff, reason, scores } = event else {
+        return None;
+    };
+    let mut message = format!("Game over ({})", reason);
+    if let Some(winner) = winner {
+        message.push_str(&format!(": {:?} wins", winner));
+    }
+    if let Some((black, white)) = scores {
+        if winner.is_some() {
+            message.push_str(&format!(" by {}", score_diff));
+        }
+        message.push_str(&format!(" (Black {}, White {})", black, white));
+    }
+    message.push('.');
+    Some(message)
+}
+
+/// The line announcing a `ByoYomiPeriodUsed` event, or `None` for other events
+pub fn period_used_message(event: &GameEvent) -> Option<String> {
+    let GameEvent::ByoYomiPeriodUsed { player, periods_left } = event else {
+        return None;
+    };
+    Some(match periods_left {
+        0 => format!("{:?} used the last byo-yomi period.", player),
+        1 => format!("{:?} used a byo-yomi period: 1 period left.", player),
+        n => format!("{:?} used a byo-yomi period: {} periods left.", player, n),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use p2pgo_core::{Move};
     
+    fn render_board(game_state: &GameState) -> String {
+        render_grid(game_state, Charset::Unicode, true)
+    }
+    
     #[test]
     fn test_render_empty_9x9_board() {
         let game_state = GameState::new(9);
@@ -138,12 +293,13 @@ mod tests {
     }
     
     #[test]
-    fn test_coord_to_column_char() {
-        assert_eq!(coord_to_column_char(0), 'A');
-        assert_eq!(coord_to_column_char(7), 'H');
-        assert_eq!(coord_to_column_char(8), 'J'); // Skip 'I'
-        assert_eq!(coord_to_column_char(17), 'S'); // Column 17 maps to 'S'
-        assert_eq!(coord_to_column_char(18), 'T'); // Column 18 maps to 'T'
+    fn test_row_labels_count_from_bottom() {
+        let mut game_state = GameState::new(9);
+        game_state.apply_move(Move::Place(Coord::from_gtp("A1", 9).unwrap())).unwrap();
+        
+        let output = render_board(&game_state);
+        let bottom_row = output.lines().nth(9).unwrap();
+        assert!(bottom_row.starts_with(" 1 (●)"), "got {:?}", bottom_row);
     }
     
     #[test]
@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Analysis of a fixture game's positions, and the JSON it's printed as.
+
+use burn::backend::NdArray;
+use burn::module::Module;
+use burn::record::{BinBytesRecorder, FullPrecisionSettings, Recorder};
+use p2pgo_cli::analyze::{
+    analyze_game, analyze_position, load_model, top_moves, MoveProbability, PositionAnalysis, Positions,
+    SCHEMA_VERSION, TOP_MOVES,
+};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::{Color, GameState};
+use trainer::checkpoint::{Checkpoint, CheckpointStore, CHECKPOINT_VERSION, PUBLISHED_FILE};
+use trainer::GoMini6E;
+
+/// E5 C3 G7 C7 G3 E3 on 9x9
+const SHORT_GAME: &str = include_str!("fixtures/short_game.sgf");
+const PLAYED: [&str; 6] = ["E5", "C3", "G7", "C7", "G3", "E3"];
+
+fn game() -> GameState {
+    SgfProcessor::new(GameState::new(9)).parse(SHORT_GAME).unwrap()
+}
+
+fn model() -> GoMini6E<NdArray> {
+    GoMini6E::new(&Default::default(), 9)
+}
+
+fn analyses(model: &GoMini6E<NdArray>, positions: Positions) -> anyhow::Result<Vec<PositionAnalysis>> {
+    let mut streamed = Vec::new();
+    analyze_game(model, &Default::default(), &game(), positions, |analysis| {
+        streamed.push(analysis);
+        Ok(())
+    })?;
+    Ok(streamed)
+}
+
+#[test]
+fn every_position_is_analyzed_in_order() {
+    let all = analyses(&model(), Positions::Every(1)).unwrap();
+    assert_eq!(all.iter().map(|a| a.move_number).collect::<Vec<_>>(), (0..=6).collect::<Vec<_>>());
+    assert_eq!(all[0].last_move, None);
+    assert_eq!(all[0].to_play, Color::Black);
+    assert_eq!(all[2].last_move.as_deref(), Some("C3"));
+    assert_eq!(all[2].to_play, Color::Black);
+
+    for analysis in &all {
+        assert_eq!(analysis.schema, SCHEMA_VERSION);
+        assert!((-1.0..=1.0).contains(&analysis.value));
+        assert_eq!(analysis.top_moves.len(), TOP_MOVES);
+        assert!(analysis.top_moves.windows(2).all(|pair| pair[0].probability >= pair[1].probability));
+        // Stones already on the board are never suggested
+        let played = &PLAYED[..analysis.move_number];
+        assert!(analysis.top_moves.iter().all(|m| !played.contains(&m.point.as_str())), "{}", analysis);
+    }
+}
+
+#[test]
+fn every_nth_or_a_single_position() {
+    let model = model();
+    let every_other = analyses(&model, Positions::Every(2)).unwrap();
+    assert_eq!(every_other.iter().map(|a| a.move_number).collect::<Vec<_>>(), [0, 2, 4, 6]);
+
+    let single = analyses(&model, Positions::Single(4)).unwrap();
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].last_move.as_deref(), Some("C7"));
+    assert_eq!(single[0], every_other[2]);
+
+    let error = analyses(&model, Positions::Single(7)).unwrap_err();
+    assert!(error.to_string().contains("has 6 moves"), "{}", error);
+}
+
+#[test]
+fn model_loads_from_published_weights() {
+    let device = Default::default();
+    let trained = model();
+    let weights = Recorder::<NdArray>::record(&BinBytesRecorder::<FullPrecisionSettings>::default(), trained.clone().into_record(), ()).unwrap();
+    let checkpoint = Checkpoint { version: CHECKPOINT_VERSION, board_size: 9, batch: 10, loss: 0.5, weights };
+    let dir = tempfile::tempdir().unwrap();
+    CheckpointStore::new(dir.path(), 1).publish(&checkpoint).unwrap();
+
+    // Fresh models start from random weights, so only loaded ones agree
+    let loaded = load_model::<NdArray>(&dir.path().join(PUBLISHED_FILE), &device).unwrap();
+    let state = game();
+    assert_eq!(analyze_position(&loaded, &device, &state).unwrap(), analyze_position(&trained, &device, &state).unwrap());
+    assert!(load_model::<NdArray>(&dir.path().join("missing.cbor"), &device).is_err());
+}
+
+#[test]
+fn likeliest_points_come_first() {
+    let moves = top_moves(&[0.0, 0.2, 0.5, 0.3], 2, 5);
+    let points: Vec<&str> = moves.iter().map(|m| m.point.as_str()).collect();
+    assert_eq!(points, ["A1", "B1", "B2"]);
+}
+
+#[test]
+fn json_lines_keep_their_schema() {
+    let analysis = PositionAnalysis {
+        schema: SCHEMA_VERSION,
+        move_number: 4,
+        last_move: Some("C7".to_string()),
+        to_play: Color::Black,
+        value: 0.25,
+        top_moves: vec![MoveProbability { point: "E3".to_string(), probability: 0.5 }],
+    };
+    assert_eq!(
+        serde_json::to_string(&analysis).unwrap(),
+        r#"{"schema":1,"move_number":4,"last_move":"C7","to_play":"Black","value":0.25,"top_moves":[{"point":"E3","probability":0.5}]}"#
+    );
+    assert_eq!(analysis.to_string(), "Move   4 C7     Black to play, value +0.25: E3 50.0%");
+}
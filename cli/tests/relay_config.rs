@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Relay config parsing, validation, limits reload and admission.
+
+use std::time::Instant;
+use p2pgo_cli::relay_config::{RelayConfig, RelayConfigHandle, RelayLimits};
+use p2pgo_network::admission::{Admission, Refusal};
+use p2pgo_network::logging::LogFilter;
+
+const FULL_CONFIG: &str = r#"
+listen_addrs = ["0.0.0.0:4433", "[::]:4433"]
+announce_addrs = ["relay.example.org:4433"]
+metrics_port = 9090
+log_level = "debug,iroh=warn"
+
+[limits]
+max_connections = 64
+max_bandwidth_bps = 1048576
+"#;
+
+fn error_of(text: &str) -> String {
+    format!("{:#}", RelayConfig::from_toml(text).unwrap_err())
+}
+
+#[test]
+fn full_config_parses() {
+    let config = RelayConfig::from_toml(FULL_CONFIG).unwrap();
+    assert_eq!(config.listen_addrs.len(), 2);
+    assert_eq!(config.announce_addrs, vec!["relay.example.org:4433".to_string()]);
+    assert_eq!(config.metrics_port, Some(9090));
+    assert_eq!(config.log_level.to_string(), "debug,iroh=warn");
+    assert_eq!(config.limits, RelayLimits { max_connections: 64, max_bandwidth_bps: 1048576 });
+}
+
+#[test]
+fn empty_config_uses_defaults() {
+    assert_eq!(RelayConfig::from_toml("").unwrap(), RelayConfig::default());
+}
+
+#[test]
+fn parse_errors_are_reported() {
+    assert!(error_of("listen_addrs = [\"not an address\"]").contains("Invalid relay config"));
+    assert!(error_of("max_conections = 5").contains("unknown field"));
+    assert!(error_of("[limits]\nmax_connections = -1").contains("Invalid relay config"));
+    assert!(error_of("log_level = \"loud\"").contains("Unknown log level \"loud\""));
+    assert!(error_of("[limits]\nmax_connections = 0").contains("max_connections"));
+    assert!(error_of("[limits]\nmax_bandwidth_bps = 0").contains("max_bandwidth_bps"));
+    assert!(error_of("metrics_port = 4433\nlisten_addrs = [\"0.0.0.0:4433\"]").contains("also a listen port"));
+    assert!(error_of("announce_addrs = [\" \"]").contains("announce_addrs"));
+}
+
+#[test]
+fn missing_file_names_the_path() {
+    let err = RelayConfig::load("/nonexistent/relay.toml").unwrap_err();
+    assert!(format!("{:#}", err).contains("/nonexistent/relay.toml"));
+}
+
+#[test]
+fn reload_applies_limits_and_log_filter_and_flags_restart_only_settings() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("relay.toml");
+    std::fs::write(&path, FULL_CONFIG).unwrap();
+
+    let (mut handle, limits_rx) = RelayConfigHandle::new(RelayConfig::load(&path).unwrap());
+    assert_eq!(limits_rx.borrow().max_connections, 64);
+
+    let changed = FULL_CONFIG
+        .replace("max_connections = 64", "max_connections = 128")
+        .replace("log_level = \"debug,iroh=warn\"", "log_level = \"warn\"")
+        .replace("metrics_port = 9090", "metrics_port = 9091");
+    std::fs::write(&path, changed).unwrap();
+
+    let reload = handle.reload(&path).unwrap();
+    assert_eq!(reload.limits.max_connections, 128);
+    assert_eq!(reload.log_filter, Some("warn".parse::<LogFilter>().unwrap()));
+    assert_eq!(reload.needs_restart, vec!["metrics_port"]);
+    assert!(limits_rx.has_changed().unwrap());
+    assert_eq!(limits_rx.borrow().max_connections, 128);
+    assert_eq!(handle.config().limits.max_connections, 128);
+    assert_eq!(handle.config().log_level.to_string(), "warn");
+    // Restart-only settings keep their running values
+    assert_eq!(handle.config().metrics_port, Some(9090));
+}
+
+#[test]
+fn broken_reload_keeps_current_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("relay.toml");
+    std::fs::write(&path, FULL_CONFIG).unwrap();
+    let (mut handle, limits_rx) = RelayConfigHandle::new(RelayConfig::load(&path).unwrap());
+
+    std::fs::write(&path, "[limits]\nmax_connections = 0\n").unwrap();
+    assert!(handle.reload(&path).is_err());
+    assert!(!limits_rx.has_changed().unwrap());
+    assert_eq!(limits_rx.borrow().max_connections, 64);
+    assert_eq!(handle.config().limits.max_connections, 64);
+}
+
+#[test]
+fn reload_changes_which_connections_are_admitted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("relay.toml");
+    std::fs::write(&path, "[limits]\nmax_connections = 1\n").unwrap();
+    let (mut handle, limits_rx) = RelayConfigHandle::new(RelayConfig::load(&path).unwrap());
+    let admission = Admission::new(limits_rx);
+    let now = Instant::now();
+
+    let _first = admission.admit(now).unwrap();
+    assert!(matches!(admission.admit(now), Err(Refusal::Full { open: 1 })));
+
+    std::fs::write(&path, "[limits]\nmax_connections = 2\n").unwrap();
+    handle.reload(&path).unwrap();
+    let _second = admission.admit(now).unwrap();
+    assert_eq!(admission.open_connections(), 2);
+}
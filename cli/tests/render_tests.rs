@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game-over and byo-yomi lines printed by the CLI game loop.
+
+use p2pgo_cli::render::{game_over_message, period_used_message};
+use p2pgo_core::{Coord, GameEvent, GameState, Move};
+
+fn last_event(moves: &[Move]) -> GameEvent {
+    let mut state = GameState::new(9);
+    let mut events = Vec::new();
+    for mv in moves {
+        events = state.apply_move_events(mv.clone()).unwrap();
+    }
+    events.pop().unwrap()
+}
+
+#[test]
+fn double_pass_and_resignation_end_the_loop() {
+    let passed = last_event(&[Move::Place(Coord::new(4, 4)), Move::Pass, Move::Pass]);
+    assert_eq!(game_over_message(&passed).as_deref(), Some("Game over (both players passed)."));
+
+    let resigned = last_event(&[Move::Resign]);
+    assert_eq!(
+        game_over_message(&resigned).as_deref(),
+        Some("Game over (resignation): White wins.")
+    );
+
+    let moved = last_event(&[Move::Place(Coord::new(4, 4))]);
+    assert_eq!(game_over_message(&moved), None);
+}
+
+#[test]
+fn scored_game_shows_margin_and_scores() {
+    let event = GameEvent::GameEnded {
+        winner: Some(p2pgo_core::Color::White),
+        score_diff: 3.5,
+        reason: p2pgo_core::EndReason::DoublePass,
+        scores: Some((10.0, 13.5)),
+    };
+    assert_eq!(
+        game_over_message(&event).as_deref(),
+        Some("Game over (both players passed): White wins by 3.5 (Black 10, White 13.5).")
+    );
+}
+
+#[test]
+fn byo_yomi_periods_count_down_to_a_loss_on_time() {
+    let used = |periods_left| GameEvent::ByoYomiPeriodUsed { player: p2pgo_core::Color::Black, periods_left };
+    assert_eq!(period_used_message(&used(2)).as_deref(), Some("Black used a byo-yomi period: 2 periods left."));
+    assert_eq!(period_used_message(&used(0)).as_deref(), Some("Black used the last byo-yomi period."));
+    let timeout = GameEvent::GameEnded {
+        winner: Some(p2pgo_core::Color::White),
+        score_diff: 0.0,
+        reason: p2pgo_core::EndReason::Timeout,
+        scores: None,
+    };
+    assert_eq!(period_used_message(&timeout), None);
+    assert!(game_over_message(&timeout).unwrap().ends_with(": White wins."));
+}
+
+mod snapshots {
+    use p2pgo_cli::render::{render, Charset, RenderMode, RenderOptions};
+    use p2pgo_core::{Coord, GameState, Move};
+
+    /// A few stones, the last one in the top right corner, and some prisoners
+    fn fixture(size: u8) -> GameState {
+        let mut state = GameState::new(size);
+        for vertex in ["D4", "Q16", "C3", "D16"] {
+            if let Ok(coord) = Coord::from_gtp(vertex, size) {
+                state.apply_move(Move::Place(coord)).unwrap();
+            }
+        }
+        state.apply_move(Move::Place(Coord::new(size - 1, 0))).unwrap();
+        state.captures = (2, 1);
+        state
+    }
+
+    fn rendered(size: u8, mode: RenderMode, charset: Charset) -> String {
+        render(&fixture(size), &RenderOptions { mode, charset })
+    }
+
+    #[test]
+    fn full_unicode_9x9() {
+        assert_eq!(rendered(9, RenderMode::Full, Charset::Unicode), include_str!("snapshots/9x9_full_unicode.txt"));
+    }
+
+    #[test]
+    fn compact_ascii_13x13() {
+        assert_eq!(rendered(13, RenderMode::Compact, Charset::Ascii), include_str!("snapshots/13x13_compact_ascii.txt"));
+    }
+
+    #[test]
+    fn full_ascii_19x19() {
+        assert_eq!(rendered(19, RenderMode::Full, Charset::Ascii), include_str!("snapshots/19x19_full_ascii.txt"));
+    }
+
+    #[test]
+    fn none_renders_nothing() {
+        assert_eq!(rendered(9, RenderMode::None, Charset::Unicode), "");
+    }
+}
+
+#[test]
+fn charset_follows_the_locale() {
+    use p2pgo_cli::render::Charset;
+    use std::collections::HashMap;
+
+    let detect = |vars: &[(&str, &str)]| {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Charset::from_env(|name| vars.get(name).cloned())
+    };
+    assert_eq!(detect(&[("LANG", "en_US.UTF-8")]), Charset::Unicode);
+    assert_eq!(detect(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]), Charset::Ascii);
+    assert_eq!(detect(&[("LANG", "en_US.utf8"), ("TERM", "dumb")]), Charset::Ascii);
+    assert_eq!(detect(&[]), Charset::Ascii);
+}
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Criterion benchmarks for the core hot paths.
+//!
+//! Run with `cargo bench-core`. Regression thresholds for each bench are
+//! recorded in `benches/baseline.json`; compare a branch against a saved
+//! run with `cargo bench-core -- --baseline main`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use p2pgo_core::analysis;
+use p2pgo_core::board::Board;
+use p2pgo_core::cbor::{self, MoveRecord};
+use p2pgo_core::position::Position;
+use p2pgo_core::rules::RuleValidator;
+use p2pgo_core::territory::TerritoryEstimate;
+use p2pgo_core::{Color, Coord, GameState, Move};
+
+/// Deterministic 200-move 19x19 game: a fixed LCG picks empty points
+fn recorded_game() -> Vec<Move> {
+    let mut state = GameState::new(19);
+    let mut seed: u32 = 0x2545_f491;
+    let mut moves = Vec::with_capacity(200);
+    
+    while moves.len() < 200 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let idx = (seed >> 8) as usize % 361;
+        let mv = Move::Place(Coord::new((idx % 19) as u8, (idx / 19) as u8));
+        if state.apply_move(mv.clone()).is_ok() {
+            moves.push(mv);
+        }
+    }
+    moves
+}
+
+/// Board where one White group fills everything but a single point
+fn giant_group_board() -> (Board, Coord) {
+    let mut board = Board::new(19);
+    let last = Coord::new(9, 9);
+    for y in 0..19 {
+        for x in 0..19 {
+            let coord = Coord::new(x, y);
+            if coord != last {
+                board.place(coord, Color::White);
+            }
+        }
+    }
+    board.place(last, Color::Black);
+    (board, last)
+}
+
+/// Full board of alternating two-column stripes; the move touches one stripe
+fn striped_board() -> (Board, Coord) {
+    let mut board = Board::new(19);
+    let last = Coord::new(1, 9);
+    for y in 0..19 {
+        for x in 0..19 {
+            let color = if (x / 2) % 2 == 0 { Color::Black } else { Color::White };
+            board.place(Coord::new(x, y), color);
+        }
+    }
+    (board, last)
+}
+
+fn bench_apply_move(c: &mut Criterion) {
+    let moves = recorded_game();
+    c.bench_function("apply_move/19x19_200_moves", |b| {
+        b.iter(|| {
+            let mut state = GameState::new(19);
+            for mv in &moves {
+                state.apply_move(mv.clone()).unwrap();
+            }
+            black_box(state)
+        })
+    });
+}
+
+/// Branching one move from the end of the recorded game, as a tree search does
+fn bench_clone_apply(c: &mut Criterion) {
+    let mut state = GameState::new(19);
+    for mv in recorded_game() {
+        state.apply_move(mv).unwrap();
+    }
+    let position = Position::from_dense(&state).unwrap();
+    let empty = state.board.iter().position(Option::is_none).unwrap();
+    let next = Move::Place(Coord::new((empty % 19) as u8, (empty / 19) as u8));
+    
+    let mut group = c.benchmark_group("clone_apply");
+    group.bench_function("game_state_19x19", |b| {
+        b.iter(|| {
+            let mut child = black_box(&state).clone();
+            child.apply_move(next.clone()).unwrap();
+            child
+        })
+    });
+    group.bench_function("position_19x19", |b| {
+        b.iter(|| black_box(&position).apply_move(next.clone()).unwrap())
+    });
+    group.finish();
+    
+    // The same with legality checks and captures
+    let mut group = c.benchmark_group("clone_play");
+    group.bench_function("game_state_19x19", |b| {
+        b.iter(|| {
+            let state = black_box(&state);
+            state.check_move(&next, state).unwrap();
+            let mut child = state.clone();
+            child.apply_move(next.clone()).unwrap();
+            child
+        })
+    });
+    group.bench_function("position_19x19", |b| {
+        b.iter(|| black_box(&position).play(next.clone()).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_find_captures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_captures");
+    
+    let (board, last) = giant_group_board();
+    group.bench_function("giant_group", |b| {
+        b.iter(|| RuleValidator::new(&board, &board).find_captures(black_box(last)))
+    });
+    
+    let (board, last) = striped_board();
+    group.bench_function("striped", |b| {
+        b.iter(|| RuleValidator::new(&board, &board).find_captures(black_box(last)))
+    });
+    
+    group.finish();
+}
+
+fn bench_cbor(c: &mut Criterion) {
+    let mut state = GameState::new(19);
+    for mv in recorded_game() {
+        state.apply_move(mv).unwrap();
+    }
+    let snapshot = cbor::serialize_game_state(&state);
+    
+    let record = MoveRecord {
+        mv: Move::Place(Coord::new(3, 15)),
+        tag: None,
+        ts: 1_700_000_000,
+        broadcast_hash: Some([7; 32]),
+        prev_hash: Some([9; 32]),
+    };
+    let record_bytes = serde_cbor::to_vec(&record).unwrap();
+    
+    let mut group = c.benchmark_group("cbor");
+    group.bench_function("snapshot_serialize", |b| {
+        b.iter(|| cbor::serialize_game_state(black_box(&state)))
+    });
+    group.bench_function("snapshot_deserialize", |b| {
+        b.iter(|| cbor::deserialize_game_state(black_box(&snapshot)))
+    });
+    group.bench_function("move_record_serialize", |b| {
+        b.iter(|| serde_cbor::to_vec(black_box(&record)).unwrap())
+    });
+    group.bench_function("move_record_deserialize", |b| {
+        b.iter_batched(
+            || record_bytes.clone(),
+            |bytes| serde_cbor::from_slice::<MoveRecord>(&bytes).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+/// Live ownership overlay: budget is 150ms per estimate on 9x9
+fn bench_ownership(c: &mut Criterion) {
+    let mut position = Position::new(9);
+    let mut seed: u32 = 0x2545_f491;
+    while position.move_count() < 20 {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        let idx = (seed >> 8) as usize % 81;
+        if let Ok(next) = position.play(Move::Place(Coord::new((idx % 9) as u8, (idx / 9) as u8))) {
+            position = next;
+        }
+    }
+    let state = position.to_dense();
+    c.bench_function("ownership/9x9_64_playouts", |b| {
+        b.iter(|| analysis::ownership(black_box(&state), 64))
+    });
+}
+
+/// Territory preview, redone on every move: budget is 5ms on 19x19
+fn bench_territory(c: &mut Criterion) {
+    let mut state = GameState::new(19);
+    for mv in recorded_game() {
+        state.apply_move(mv).unwrap();
+    }
+    c.bench_function("territory/19x19_200_moves", |b| {
+        b.iter(|| TerritoryEstimate::of(black_box(&state)))
+    });
+}
+
+criterion_group!(benches, bench_apply_move, bench_clone_apply, bench_find_captures, bench_cbor, bench_ownership, bench_territory);
+criterion_main!(benches);
@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Post-game report: how long each player thought, and where the evaluation
+//! swung hardest against the player who moved. Also ownership estimates from
+//! random playouts, for a live view of who is winning.
+//!
+//! Evaluations come from a caller-supplied function, e.g. a value network,
+//! so the report doesn't depend on any particular model.
+
+use core::time::Duration;
+use std::hash::{Hash, Hasher};
+use serde::{Serialize, Deserialize};
+use crate::cbor::MoveRecord;
+use crate::engine::fills_own_eye;
+use crate::game_clock::TimingInfo;
+use crate::position::Position;
+use crate::rng::{self, RngCore};
+use crate::{Color, Coord, GameState, Move};
+
+/// Drop in the mover's evaluation that marks a likely mistake
+pub const MISTAKE_SWING: f32 = 0.3;
+
+/// Part of the game a move falls in, by move number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Phase {
+    Opening,
+    Middle,
+    Endgame,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 3] = [Phase::Opening, Phase::Middle, Phase::Endgame];
+
+    /// Phase of the move at `index`, from zero.
+    ///
+    /// The opening is the first eighth of the board's points in moves and
+    /// the endgame starts two fifths in: moves 10 and 32 on 9x9, 45 and 144
+    /// on 19x19.
+    pub fn of(index: usize, board_size: u8) -> Phase {
+        let points = board_size as usize * board_size as usize;
+        if index < points / 8 {
+            Phase::Opening
+        } else if index < points * 2 / 5 {
+            Phase::Middle
+        } else {
+            Phase::Endgame
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Opening => "Opening",
+            Phase::Middle => "Middle game",
+            Phase::Endgame => "Endgame",
+        }
+    }
+}
+
+/// One player's think times
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerTimes {
+    /// Moves with a known think time
+    pub timed_moves: usize,
+    pub total: Duration,
+    /// Index and think time of the slowest move
+    pub longest: Option<(usize, Duration)>,
+    /// Byo-yomi periods used up by the player's last timed move
+    #[serde(default)]
+    pub periods_used: u32,
+}
+
+impl PlayerTimes {
+    pub fn average(&self) -> Option<Duration> {
+        (self.timed_moves > 0).then(|| self.total / self.timed_moves as u32)
+    }
+
+    fn add(&mut self, index: usize, time: Duration) {
+        self.timed_moves += 1;
+        self.total += time;
+        match self.longest {
+            Some((_, longest)) if longest >= time => {}
+            _ => self.longest = Some((index, time)),
+        }
+    }
+}
+
+/// A move after which the evaluation dropped for the player who made it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mistake {
+    /// Index of the move, from zero
+    pub index: usize,
+    pub color: Color,
+    pub mv: Move,
+    /// How far the evaluation fell, from the mover's side
+    pub swing: f32,
+}
+
+/// What [`game_report`] found
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameReport {
+    pub black: PlayerTimes,
+    pub white: PlayerTimes,
+    /// Think time of both players per phase, in [`Phase::ALL`] order
+    pub phase_times: [Duration; 3],
+    /// Evaluation for Black of the start position and after every move
+    pub evals: Vec<f32>,
+    /// Likely mistakes, biggest swing first
+    pub mistakes: Vec<Mistake>,
+}
+
+impl GameReport {
+    pub fn times(&self, color: Color) -> &PlayerTimes {
+        match color {
+            Color::Black => &self.black,
+            Color::White => &self.white,
+        }
+    }
+
+    pub fn phase_time(&self, phase: Phase) -> Duration {
+        self.phase_times[phase as usize]
+    }
+
+    /// The mistake of `color` with the biggest swing
+    pub fn biggest_mistake(&self, color: Color) -> Option<&Mistake> {
+        self.mistakes.iter().find(|mistake| mistake.color == color)
+    }
+}
+
+/// Report on the game in `state`.
+///
+/// A move's think time comes from the clocks in [`GameState::timing`], e.g.
+/// an imported SGF's `BL`/`WL`, where they were recorded. Otherwise it is the
+/// gap since the move before in `records`, the game's last moves with their
+/// timestamps, e.g. the ones seen since the app started; only moves after the
+/// first record have one. Old games with neither still get evaluations.
+/// `evaluate` scores a position for Black, from -1 (lost) to 1 (won).
+pub fn game_report(
+    state: &GameState,
+    records: &[MoveRecord],
+    mut evaluate: impl FnMut(&GameState) -> f32,
+) -> GameReport {
+    let first_record = state.moves.len().saturating_sub(records.len());
+    let record = |index: usize| index.checked_sub(first_record).and_then(|i| records.get(i));
+    let mut report = GameReport {
+        black: PlayerTimes::default(),
+        white: PlayerTimes::default(),
+        phase_times: [Duration::ZERO; 3],
+        evals: Vec::with_capacity(state.moves.len() + 1),
+        mistakes: Vec::new(),
+    };
+
+    // Replayed under the rules so captured stones leave the board
+    let start = state.initial_position();
+    let mut position = match Position::from_dense(&start) {
+        Ok(position) => position,
+        Err(e) => {
+            tracing::warn!("No report for a game whose start position is invalid: {}", e);
+            return report;
+        }
+    };
+    report.evals.push(evaluate(&start));
+    for (index, mv) in state.moves.iter().enumerate() {
+        let color = position.current_player();
+        let recorded = state.timing.think_time(index);
+        let stamped = || match (index.checked_sub(1).and_then(record), record(index)) {
+            (Some(previous), Some(current)) => Some(Duration::from_secs(current.ts.saturating_sub(previous.ts))),
+            _ => None,
+        };
+        if let Some(time) = recorded.or_else(stamped) {
+            match color {
+                Color::Black => report.black.add(index, time),
+                Color::White => report.white.add(index, time),
+            }
+            report.phase_times[Phase::of(index, state.board_size) as usize] += time;
+        }
+        if let (Some(settings), Some(TimingInfo { periods: Some(left), .. })) = (state.timing.settings, state.timing.at(index)) {
+            let times = match color {
+                Color::Black => &mut report.black,
+                Color::White => &mut report.white,
+            };
+            times.periods_used = times.periods_used.max(settings.periods.saturating_sub(left));
+        }
+
+        if *mv == Move::Resign {
+            break;
+        }
+        position = match position.play(mv.clone()) {
+            Ok(next) => next,
+            Err(e) => {
+                tracing::warn!("Report stops at move {}, which no longer replays: {}", index + 1, e);
+                break;
+            }
+        };
+        let before = report.evals[index];
+        let after = evaluate(&position.to_dense());
+        report.evals.push(after);
+        let swing = match color {
+            Color::Black => before - after,
+            Color::White => after - before,
+        };
+        if swing >= MISTAKE_SWING {
+            report.mistakes.push(Mistake { index, color, mv: mv.clone(), swing });
+        }
+    }
+    report.mistakes.sort_by(|a, b| b.swing.total_cmp(&a.swing));
+    report
+}
+
+/// Chance of each point ending up Black's, from random playouts.
+///
+/// Each playout plays random legal moves, never filling a player's own eye,
+/// until both players pass. A point then belongs to whoever has a stone on
+/// it or surrounds it. The result runs from 1 (always Black) to -1 (always
+/// White), row by row like [`GameState::board`]. Playouts are seeded from the
+/// position, so the same position always gets the same estimate.
+pub fn ownership(state: &GameState, playouts: usize) -> Vec<f32> {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    state.board.hash(&mut hasher);
+    state.current_player.hash(&mut hasher);
+    ownership_with(state, playouts, &mut rng::from_seed(hasher.finish()))
+}
+
+/// [`ownership`] with playouts drawn from `rng`
+pub fn ownership_with(state: &GameState, playouts: usize, rng: &mut impl RngCore) -> Vec<f32> {
+    let points = state.board_size as usize * state.board_size as usize;
+    let mut totals = vec![0.0; points];
+    // Play on even if the game has ended, e.g. to estimate the final score
+    let start = GameState { moves: Vec::new(), pass_count: 0, setup: Vec::new(), ..state.clone() };
+    let Ok(start) = Position::from_dense(&start) else {
+        return totals;
+    };
+
+    for _ in 0..playouts {
+        playout(&start, rng, &mut totals);
+    }
+    if playouts > 0 {
+        totals.iter_mut().for_each(|total| *total /= playouts as f32);
+    }
+    totals
+}
+
+/// Black's expected margin under area scoring, from [`ownership`]
+pub fn expected_score(ownership: &[f32], komi: f32) -> f32 {
+    ownership.iter().sum::<f32>() - komi
+}
+
+/// Black's expected margin if the player to move passes, when no legal move
+/// would gain them `threshold` points or more over passing.
+///
+/// Each move is compared with passing by [`ownership`] from `playouts`
+/// random playouts. The least settled points are tried first, so a position
+/// with something left to play usually stops at the first move.
+pub fn pass_suggestion(state: &GameState, komi: f32, threshold: f32, playouts: usize) -> Option<f32> {
+    let position = Position::from_dense(state).ok()?;
+    let score = |position: &Position| expected_score(&ownership(&position.to_dense(), playouts), komi);
+    let passed = position.play(Move::Pass).ok()?;
+    let baseline = ownership(&passed.to_dense(), playouts);
+    let estimate = expected_score(&baseline, komi);
+    let sign = match position.current_player() {
+        Color::Black => 1.0,
+        Color::White => -1.0,
+    };
+
+    let mut moves = position.legal_moves();
+    let board_size = state.board_size;
+    moves.sort_by(|a, b| baseline[a.to_index(board_size)].abs().total_cmp(&baseline[b.to_index(board_size)].abs()));
+    for coord in moves {
+        let Ok(next) = position.play(Move::Place(coord)) else {
+            continue;
+        };
+        if sign * (score(&next) - estimate) >= threshold {
+            return None;
+        }
+    }
+    Some(estimate)
+}
+
+/// Play `start` out at random and add each point's owner to `totals`
+fn playout(start: &Position, rng: &mut impl RngCore, totals: &mut [f32]) {
+    let size = start.board_size();
+    let points = totals.len();
+    let mut position = start.clone();
+    let mut passes = 0;
+    // Long enough for any sensible game; cycles stop here rather than never
+    for _ in 0..points * 3 {
+        if passes >= 2 {
+            break;
+        }
+        let color = position.current_player();
+        // Scan from a random point for the first move worth playing
+        let offset = rng::below(rng, points);
+        let next = (0..points)
+            .filter_map(|i| Coord::from_index((offset + i) % points, size))
+            .filter(|&coord| position.get(coord).is_none() && !fills_own_eye(&position, coord, color))
+            .find_map(|coord| position.play(Move::Place(coord)).ok());
+        match next {
+            Some(next) => {
+                position = next;
+                passes = 0;
+            }
+            None => match position.play(Move::Pass) {
+                Ok(next) => {
+                    position = next;
+                    passes += 1;
+                }
+                Err(_) => break,
+            },
+        }
+    }
+
+    for (idx, total) in totals.iter_mut().enumerate() {
+        let Some(coord) = Coord::from_index(idx, size) else {
+            continue;
+        };
+        let owner = position.get(coord).or_else(|| {
+            // Only eyes are left empty, so one neighbour's colour decides it
+            let mut neighbours = coord.adjacent_coords().into_iter()
+                .filter(|c| c.is_valid(size))
+                .map(|c| position.get(c));
+            let first = neighbours.next().flatten()?;
+            neighbours.all(|n| n == Some(first)).then_some(first)
+        });
+        match owner {
+            Some(Color::Black) => *total += 1.0,
+            Some(Color::White) => *total -= 1.0,
+            None => {}
+        }
+    }
+}
@@ -3,15 +3,25 @@
 //! Game archive helper functions for the core crate
 
 use chrono::Utc;
-use crate::GameState;
-use std::path::PathBuf;
-use anyhow::Result;
-use std::io::Write;
+use crate::{Color, GameState, Move};
+use crate::cbor::migrate;
+use crate::sgf::{SgfHeader, SgfProcessor};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use anyhow::{Context, Result};
+use serde::{Serialize, Deserialize};
+use std::io::{Seek, Write};
+
+/// Extension of a game record
+const RECORD_EXT: &str = "cbor";
+
+/// Extension of the summary kept next to each record
+const SUMMARY_EXT: &str = "meta";
 
 /// Archives a finished game to the local filesystem
 ///
-/// Writes a CBOR file to ~/Library/Application Support/p2pgo/finished/ on macOS
-/// or ./finished_games/ on other platforms
+/// Writes a CBOR file to [`archive_dir`]
 ///
 /// The filename format is: YYYY-MM-DD_vs_<opponent>.cbor
 ///
@@ -22,30 +32,16 @@ use std::io::Write;
 /// # Returns
 /// * `Result<PathBuf>` - Path to the archived file on success
 pub fn archive_finished_game(game: &GameState, opponent: &str) -> Result<PathBuf> {
+    archive_game_in(&archive_dir()?, game, opponent)
+}
+
+/// Same as [`archive_finished_game`] but into `archive_dir`
+pub fn archive_game_in(archive_dir: &Path, game: &GameState, opponent: &str) -> Result<PathBuf> {
     // Format the date in YYYY-MM-DD format
     let date = Utc::now().format("%Y-%m-%d").to_string();
     
-    // Get the appropriate directory for the platform
-    let archive_dir = match std::env::consts::OS {
-        "macos" => {
-            let mut path = PathBuf::from(
-                std::env::var("HOME").map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?
-            );
-            path.push("Library");
-            path.push("Application Support");
-            path.push("p2pgo");
-            path.push("finished");
-            path
-        },
-        _ => {
-            let mut path = PathBuf::from(".");
-            path.push("finished_games");
-            path
-        }
-    };
-    
     // Ensure the directory exists
-    std::fs::create_dir_all(&archive_dir)?;
+    std::fs::create_dir_all(archive_dir)?;
     
     // Format the filename
     let sanitized_opponent = opponent.replace(
@@ -55,17 +51,13 @@ pub fn archive_finished_game(game: &GameState, opponent: &str) -> Result<PathBuf
     let filename = format!("{}_vs_{}.cbor", date, sanitized_opponent);
     let file_path = archive_dir.join(&filename);
     
-    // Create a temporary file for atomic write
-    let tmp_path = archive_dir.join(format!(".tmp_{}", filename));
-    {
-        let mut file = std::fs::File::create(&tmp_path)?;
-        let cbor_data = serde_cbor::to_vec(&game)?;
-        file.write_all(&cbor_data)?;
-        file.flush()?;
-    }
-    
-    // Rename for atomic replacement
-    std::fs::rename(&tmp_path, &file_path)?;
+    write_atomic(archive_dir, &filename, &serde_cbor::to_vec(&game)?)?;
+    write_summary(archive_dir, &GameSummary::new(
+        format!("{}_vs_{}", date, sanitized_opponent),
+        opponent,
+        &date,
+        game,
+    ))?;
     
     tracing::info!(
         "Game archived to {:?}",
@@ -75,6 +67,25 @@ pub fn archive_finished_game(game: &GameState, opponent: &str) -> Result<PathBuf
     Ok(file_path)
 }
 
+/// Directory finished games are archived in
+///
+/// `finished/` in the data directory, see [`crate::p2pgo_dirs`].
+pub fn archive_dir() -> Result<PathBuf> {
+    Ok(crate::p2pgo_dirs::Dirs::current()?.archive())
+}
+
+/// Write `bytes` to `dir/filename` through a temporary file
+fn write_atomic(dir: &Path, filename: &str, bytes: &[u8]) -> Result<()> {
+    let tmp_path = dir.join(format!(".tmp_{}", filename));
+    {
+        let mut file = std::fs::File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.flush()?;
+    }
+    std::fs::rename(&tmp_path, dir.join(filename))?;
+    Ok(())
+}
+
 /// Archives a finished game asynchronously
 ///
 /// Same as `archive_finished_game` but runs in a background task
@@ -95,3 +106,674 @@ pub fn archive_finished_game_async(game: GameState, opponent: String) {
         }
     });
 }
+
+/// Lightweight description of an archived game.
+///
+/// Stored as `<id>.meta` next to the `<id>.cbor` record so the archive can
+/// be listed without reading every game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSummary {
+    /// File stem of the record
+    pub id: String,
+    /// Opponent's name, empty if unknown
+    pub opponent: String,
+    /// Date archived, YYYY-MM-DD
+    pub date: String,
+    pub board_size: u8,
+    /// SGF-style result such as `B+Resign`, if known
+    pub result: Option<String>,
+    pub move_count: usize,
+    /// Classification tags, e.g. anomalies seen during the game
+    pub tags: Vec<String>,
+    /// Whether the game may be used as training data
+    pub training_eligible: bool,
+}
+
+impl GameSummary {
+    /// Summary of `game` with the result read from its moves
+    pub fn new(id: String, opponent: &str, date: &str, game: &GameState) -> Self {
+        Self {
+            id,
+            opponent: opponent.to_string(),
+            date: date.to_string(),
+            board_size: game.board_size,
+            result: resign_result(game),
+            move_count: game.moves.len(),
+            tags: Vec::new(),
+            training_eligible: true,
+        }
+    }
+}
+
+/// `B+Resign` or `W+Resign` if the last move was a resignation
+fn resign_result(game: &GameState) -> Option<String> {
+    if game.moves.last() != Some(&Move::Resign) {
+        return None;
+    }
+    // Black plays the even-numbered moves
+    let resigned = if game.moves.len() % 2 == 1 { Color::Black } else { Color::White };
+    Some(match resigned {
+        Color::Black => "W+Resign".to_string(),
+        Color::White => "B+Resign".to_string(),
+    })
+}
+
+/// Record files hold either a bare `GameState` or an archive entry wrapping it;
+/// either way the state is upgraded from the version that wrote it
+#[derive(Deserialize)]
+struct WrappedRecord {
+    final_state: GameState,
+}
+
+fn read_record(path: &Path) -> Result<GameState> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    parse_record(&bytes).with_context(|| format!("{} is not a game record", path.display()))
+}
+
+fn parse_record(bytes: &[u8]) -> Result<GameState> {
+    Ok(migrate::from_slice::<GameState>(bytes)
+        .or_else(|_| serde_cbor::from_slice::<WrappedRecord>(bytes).map(|r| r.final_state))?)
+}
+
+/// Store `record`, the CBOR of a game, as `id` in `archive_dir` with its summary
+pub fn write_record(archive_dir: &Path, id: &str, record: &[u8], summary: &GameSummary) -> Result<PathBuf> {
+    std::fs::create_dir_all(archive_dir)?;
+    let filename = format!("{}.{}", id, RECORD_EXT);
+    write_atomic(archive_dir, &filename, record)?;
+    write_summary(archive_dir, summary)?;
+    Ok(archive_dir.join(filename))
+}
+
+/// Store the summary for a record in `archive_dir`
+pub fn write_summary(archive_dir: &Path, summary: &GameSummary) -> Result<()> {
+    std::fs::create_dir_all(archive_dir)?;
+    write_atomic(archive_dir, &format!("{}.{}", summary.id, SUMMARY_EXT), &serde_cbor::to_vec(summary)?)
+}
+
+/// List the games in the default archive directory
+pub fn list_games() -> Result<Vec<GameSummary>> {
+    list_games_in(&archive_dir()?)
+}
+
+/// List the games in `archive_dir`, newest first.
+///
+/// Reads the summaries only. A record without one, e.g. from an older
+/// version, is read once and its summary written for next time.
+pub fn list_games_in(archive_dir: &Path) -> Result<Vec<GameSummary>> {
+    let entries = match std::fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    
+    let mut games = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(RECORD_EXT) {
+            continue;
+        }
+        // Skip half-written temporary files
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()).filter(|s| !s.starts_with('.')).map(str::to_string) else {
+            continue;
+        };
+        match read_summary(archive_dir, &id) {
+            Ok(summary) => games.push(summary),
+            Err(_) => match read_record(&path) {
+                Ok(game) => {
+                    let date = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .map(|t| chrono::DateTime::<Utc>::from(t).format("%Y-%m-%d").to_string())
+                        .unwrap_or_default();
+                    let summary = GameSummary::new(id, "", &date, &game);
+                    if let Err(e) = write_summary(archive_dir, &summary) {
+                        tracing::warn!("Failed to write summary for {}: {}", path.display(), e);
+                    }
+                    games.push(summary);
+                }
+                Err(e) => tracing::debug!("Skipping {}: {:#}", path.display(), e),
+            },
+        }
+    }
+    
+    games.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.id.cmp(&b.id)));
+    Ok(games)
+}
+
+fn read_summary(archive_dir: &Path, id: &str) -> Result<GameSummary> {
+    let bytes = std::fs::read(archive_dir.join(format!("{}.{}", id, SUMMARY_EXT)))?;
+    Ok(serde_cbor::from_slice(&bytes)?)
+}
+
+/// Load the full record of an archived game
+pub fn load_game(archive_dir: &Path, id: &str) -> Result<GameState> {
+    read_record(&archive_dir.join(format!("{}.{}", id, RECORD_EXT)))
+}
+
+/// The game as SGF
+pub fn export_sgf(archive_dir: &Path, id: &str) -> Result<String> {
+    Ok(SgfProcessor::new(load_game(archive_dir, id)?).generate())
+}
+
+/// Remove a game and its summary
+pub fn delete_game(archive_dir: &Path, id: &str) -> Result<()> {
+    std::fs::remove_file(archive_dir.join(format!("{}.{}", id, RECORD_EXT)))
+        .with_context(|| format!("Failed to delete game {}", id))?;
+    // An older record may never have had a summary
+    let _ = std::fs::remove_file(archive_dir.join(format!("{}.{}", id, SUMMARY_EXT)));
+    Ok(())
+}
+
+/// Mark a game as usable, or not, for training
+pub fn set_training_eligible(archive_dir: &Path, id: &str, eligible: bool) -> Result<GameSummary> {
+    let mut summary = match read_summary(archive_dir, id) {
+        Ok(summary) => summary,
+        Err(_) => list_games_in(archive_dir)?
+            .into_iter()
+            .find(|g| g.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Game not found: {}", id))?,
+    };
+    summary.training_eligible = eligible;
+    write_summary(archive_dir, &summary)?;
+    Ok(summary)
+}
+
+/// Whether a game may be used for training; games without a summary may
+pub fn is_training_eligible(archive_dir: &Path, id: &str) -> bool {
+    read_summary(archive_dir, id).map(|s| s.training_eligible).unwrap_or(true)
+}
+
+/// What [`export_all`] writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One SGF file holding every game, one game tree after another
+    SgfCollection,
+    /// A zip with an SGF file per game
+    ZipOfSgf,
+    /// A zip with each game's record as archived
+    ZipOfCbor,
+}
+
+/// Which archived games [`export_all`] takes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassificationFilter {
+    /// Only games with a counted result, not resignations or forfeits
+    pub only_scored: bool,
+    /// Only games that may be used for training
+    pub only_training_eligible: bool,
+    /// Only games carrying all of these tags
+    pub tags: Vec<String>,
+}
+
+impl ClassificationFilter {
+    pub fn matches(&self, game: &GameSummary) -> bool {
+        let scored = match game.result.as_deref().and_then(|result| result.split_once('+')) {
+            Some((_, margin)) => margin.parse::<f32>().is_ok(),
+            None => false,
+        };
+        (!self.only_scored || scored)
+            && (!self.only_training_eligible || game.training_eligible)
+            && self.tags.iter().all(|tag| game.tags.contains(tag))
+    }
+}
+
+/// What [`export_all`] wrote and what it left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    pub exported: usize,
+    /// Games the filter left out
+    pub skipped: Vec<String>,
+    /// Games that could not be read, with why
+    pub corrupt: Vec<(String, String)>,
+}
+
+/// Export the games in `archive_dir` that pass `filter` to `out`.
+///
+/// Games are read and written one at a time, in ID order, so the archive is
+/// never held in memory. SGF gets the ID, date, result and opponent from
+/// the game's summary in its header. Records that can't be read are
+/// reported rather than failing the export.
+pub fn export_all<W: Write + Seek>(
+    archive_dir: &Path,
+    out: W,
+    format: ExportFormat,
+    filter: &ClassificationFilter,
+) -> Result<ExportReport> {
+    let mut ids = Vec::new();
+    for entry in std::fs::read_dir(archive_dir).with_context(|| format!("Failed to read {}", archive_dir.display()))? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(RECORD_EXT) {
+            continue;
+        }
+        // Skip half-written temporary files
+        if let Some(id) = path.file_stem().and_then(|s| s.to_str()).filter(|s| !s.starts_with('.')) {
+            ids.push(id.to_string());
+        }
+    }
+    ids.sort();
+
+    let mut sink = match format {
+        ExportFormat::SgfCollection => ExportSink::Sgf(out),
+        ExportFormat::ZipOfSgf | ExportFormat::ZipOfCbor => ExportSink::Zip(zip::ZipWriter::new(out)),
+    };
+    let mut report = ExportReport::default();
+    for id in ids {
+        // The summary decides without reading the record, if there is one
+        let summary = read_summary(archive_dir, &id).ok();
+        if matches!(&summary, Some(summary) if !filter.matches(summary)) {
+            report.skipped.push(id);
+            continue;
+        }
+        let bytes = match std::fs::read(archive_dir.join(format!("{}.{}", id, RECORD_EXT))) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                report.corrupt.push((id, e.to_string()));
+                continue;
+            }
+        };
+        let game = match parse_record(&bytes) {
+            Ok(game) => game,
+            Err(e) => {
+                report.corrupt.push((id, format!("Not a game record: {}", e)));
+                continue;
+            }
+        };
+        let summary = summary.unwrap_or_else(|| GameSummary::new(id.clone(), "", "", &game));
+        if !filter.matches(&summary) {
+            report.skipped.push(id);
+            continue;
+        }
+
+        match (&mut sink, format) {
+            (ExportSink::Zip(zip), ExportFormat::ZipOfCbor) => {
+                zip.start_file(format!("{}.{}", id, RECORD_EXT), zip::write::FileOptions::default())?;
+                zip.write_all(&bytes)?;
+            }
+            (ExportSink::Zip(zip), _) => {
+                zip.start_file(format!("{}.sgf", id), zip::write::FileOptions::default())?;
+                zip.write_all(sgf_with_header(game, &summary).as_bytes())?;
+            }
+            (ExportSink::Sgf(out), _) => {
+                writeln!(out, "{}", sgf_with_header(game, &summary))?;
+            }
+        }
+        report.exported += 1;
+    }
+
+    match sink {
+        ExportSink::Sgf(mut out) => out.flush()?,
+        ExportSink::Zip(mut zip) => {
+            zip.finish()?;
+        }
+    }
+    Ok(report)
+}
+
+enum ExportSink<W: Write + Seek> {
+    Sgf(W),
+    Zip(zip::ZipWriter<W>),
+}
+
+fn sgf_with_header(game: GameState, summary: &GameSummary) -> String {
+    let header = SgfHeader {
+        name: Some(summary.id.clone()),
+        date: Some(summary.date.clone()).filter(|date| !date.is_empty()),
+        result: summary.result.clone(),
+        comment: Some(format!("Played against {}", summary.opponent)).filter(|_| !summary.opponent.is_empty()),
+    };
+    SgfProcessor::new(game).with_header(header).generate()
+}
+
+/// Board sizes [`import_sgf_dir`] takes; games on other boards are skipped
+pub const IMPORT_BOARD_SIZES: [u8; 3] = [9, 13, 19];
+
+/// Tag on every game [`import_sgf_dir`] adds
+pub const IMPORTED_TAG: &str = "imported";
+
+/// Where [`import_sgf_dir`] adds games
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Archive the games go into
+    pub archive_dir: PathBuf,
+    /// Whether imported games may be used for training
+    pub training_eligible: bool,
+}
+
+impl ImportOptions {
+    /// Import into `archive_dir`, for training like played games
+    pub fn new(archive_dir: impl Into<PathBuf>) -> Self {
+        Self { archive_dir: archive_dir.into(), training_eligible: true }
+    }
+}
+
+/// How far an import has got, after each file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// Files looked at so far
+    pub done: usize,
+    /// SGF files in the directory
+    pub total: usize,
+    /// Name of the file just looked at
+    pub file: String,
+}
+
+impl ImportProgress {
+    /// Share of the files looked at, 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.done as f32 / self.total as f32
+    }
+}
+
+/// What [`import_sgf_dir`] added and what it left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    /// IDs of the games added
+    pub imported: Vec<String>,
+    /// Files of a game already archived, or met earlier in the import
+    pub duplicates: Vec<String>,
+    /// Files on a board outside [`IMPORT_BOARD_SIZES`], with why
+    pub skipped: Vec<(String, String)>,
+    /// Files that could not be read as SGF, with why
+    pub failed: Vec<(String, String)>,
+}
+
+impl ImportReport {
+    /// One line for the player, e.g. "Imported 3 games, 1 duplicate left out"
+    pub fn summary(&self) -> String {
+        let mut parts = vec![format!("Imported {}", count(self.imported.len(), "game"))];
+        if !self.duplicates.is_empty() {
+            parts.push(format!("{} left out", count(self.duplicates.len(), "duplicate")));
+        }
+        if !self.skipped.is_empty() {
+            parts.push(format!("{} skipped", count(self.skipped.len(), "unsupported board")));
+        }
+        if !self.failed.is_empty() {
+            parts.push(format!("{} failed", count(self.failed.len(), "file")));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Hash identifying a game whatever file or record it came from: the
+/// board size, setup stones and moves, as 16 hex digits.
+///
+/// FNV-1a, so the same game hashes the same on every build.
+pub fn game_hash(game: &GameState) -> String {
+    let mut bytes = vec![game.board_size];
+    for (coord, color) in &game.setup {
+        bytes.extend([b'S', coord.x, coord.y, *color as u8]);
+    }
+    for mv in &game.moves {
+        match mv {
+            Move::Place(coord) => bytes.extend([b'P', coord.x, coord.y]),
+            Move::Pass => bytes.push(b'-'),
+            Move::Resign => bytes.push(b'R'),
+        }
+    }
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{:016x}", hash)
+}
+
+/// Add the games of the `.sgf` files in `dir` to the archive.
+///
+/// Each game is archived as `imported_<hash>` with [`IMPORTED_TAG`], the
+/// date and result from its SGF when given. A game whose [`game_hash`] is
+/// already in the archive is left out. A file that can't be imported is
+/// reported rather than failing the import.
+pub fn import_sgf_dir(dir: &Path, options: &ImportOptions) -> Result<ImportReport> {
+    import_sgf_dir_with(dir, options, |_| {})
+}
+
+/// Same as [`import_sgf_dir`], calling `progress` after each file
+pub fn import_sgf_dir_with(
+    dir: &Path,
+    options: &ImportOptions,
+    mut progress: impl FnMut(&ImportProgress),
+) -> Result<ImportReport> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("sgf")) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    let mut known = archived_hashes(&options.archive_dir)?;
+    let today = Utc::now().format("%Y-%m-%d").to_string();
+    let mut report = ImportReport::default();
+    for (i, path) in files.iter().enumerate() {
+        let file = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        match import_sgf_file(path, options, &today, &mut known) {
+            Ok(Imported::Added(id)) => report.imported.push(id),
+            Ok(Imported::Duplicate) => report.duplicates.push(file.clone()),
+            Ok(Imported::Unsupported(size)) => {
+                report.skipped.push((file.clone(), format!("{}x{} boards are not supported", size, size)));
+            }
+            Err(e) => report.failed.push((file.clone(), format!("{:#}", e))),
+        }
+        progress(&ImportProgress { done: i + 1, total: files.len(), file });
+    }
+    tracing::info!("Imported SGF from {}: {}", dir.display(), report.summary());
+    Ok(report)
+}
+
+/// What became of one file of an import
+enum Imported {
+    Added(String),
+    Duplicate,
+    Unsupported(u8),
+}
+
+fn import_sgf_file(path: &Path, options: &ImportOptions, today: &str, known: &mut HashSet<String>) -> Result<Imported> {
+    let text = std::fs::read_to_string(path).context("Failed to read the file")?;
+    let mut sgf = SgfProcessor::new(GameState::new(19));
+    let game = sgf.parse(&text).context("Not a readable SGF game")?;
+    if !IMPORT_BOARD_SIZES.contains(&game.board_size) {
+        return Ok(Imported::Unsupported(game.board_size));
+    }
+    let hash = game_hash(&game);
+    if !known.insert(hash.clone()) {
+        return Ok(Imported::Duplicate);
+    }
+
+    let header = sgf.header();
+    // DT may list several days, e.g. 2023-05-01,02; the first is enough
+    let date = header.date.as_deref()
+        .and_then(|dt| dt.get(..10))
+        .filter(|dt| chrono::NaiveDate::parse_from_str(dt, "%Y-%m-%d").is_ok())
+        .unwrap_or(today);
+    let id = format!("imported_{}", hash);
+    let mut summary = GameSummary::new(id.clone(), "", date, &game);
+    if header.result.is_some() {
+        summary.result = header.result.clone();
+    }
+    summary.tags.push(IMPORTED_TAG.to_string());
+    summary.training_eligible = options.training_eligible;
+    write_record(&options.archive_dir, &id, &serde_cbor::to_vec(&game)?, &summary)?;
+    Ok(Imported::Added(id))
+}
+
+/// [`game_hash`] of every readable record in `archive_dir`
+fn archived_hashes(archive_dir: &Path) -> Result<HashSet<String>> {
+    let entries = match std::fs::read_dir(archive_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", archive_dir.display())),
+    };
+    let mut hashes = HashSet::new();
+    for entry in entries {
+        let path = entry?.path();
+        // Skip half-written temporary files
+        let temporary = !matches!(path.file_name().and_then(|n| n.to_str()), Some(name) if !name.starts_with('.'));
+        if path.extension().and_then(|e| e.to_str()) != Some(RECORD_EXT) || temporary {
+            continue;
+        }
+        match read_record(&path) {
+            Ok(game) => {
+                hashes.insert(game_hash(&game));
+            }
+            Err(e) => tracing::debug!("Skipping {}: {:#}", path.display(), e),
+        }
+    }
+    Ok(hashes)
+}
+
+/// Subfolder that [`run_maintenance`] moves unreadable files to
+pub const CORRUPT_DIR: &str = "corrupt";
+
+/// Log of the files moved to [`CORRUPT_DIR`], kept inside it
+const QUARANTINE_LOG: &str = "quarantine.log";
+
+/// Age after which a temporary file is left over from an interrupted write
+pub const STALE_TEMP_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// What [`run_maintenance`] found and repaired
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+    /// Records and summaries that read back fine
+    pub checked: usize,
+    /// Leftover temporary files that were deleted
+    pub removed_temps: Vec<String>,
+    /// Files moved to [`CORRUPT_DIR`], with why
+    pub quarantined: Vec<(String, String)>,
+    /// Finished games whose snapshot was replaced by a final record
+    pub compacted: Vec<String>,
+}
+
+impl MaintenanceReport {
+    /// Whether anything was deleted, moved or rewritten
+    pub fn repaired_anything(&self) -> bool {
+        !self.removed_temps.is_empty() || !self.quarantined.is_empty() || !self.compacted.is_empty()
+    }
+
+    /// Add the findings for another directory
+    pub fn merge(&mut self, other: MaintenanceReport) {
+        self.checked += other.checked;
+        self.removed_temps.extend(other.removed_temps);
+        self.quarantined.extend(other.quarantined);
+        self.compacted.extend(other.compacted);
+    }
+
+    /// One line for the player, e.g. "Archive repaired: 1 corrupt file set aside"
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.removed_temps.is_empty() {
+            parts.push(format!("{} removed", count(self.removed_temps.len(), "leftover temporary file")));
+        }
+        if !self.quarantined.is_empty() {
+            parts.push(format!("{} set aside in {}", count(self.quarantined.len(), "corrupt file"), CORRUPT_DIR));
+        }
+        if !self.compacted.is_empty() {
+            parts.push(format!("{} archived from snapshots", count(self.compacted.len(), "finished game")));
+        }
+        if parts.is_empty() {
+            return "Archive checked: nothing to repair".to_string();
+        }
+        format!("Archive repaired: {}", parts.join(", "))
+    }
+}
+
+/// "1 file", "2 files"
+fn count(n: usize, noun: &str) -> String {
+    format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+}
+
+/// Check and tidy a directory of game records or snapshots.
+///
+/// Temporary files older than [`STALE_TEMP_AGE`] are deleted. Every record
+/// and summary is read back; those that don't parse are moved to
+/// [`CORRUPT_DIR`] and logged there, so listing and resuming never trip on
+/// them. A missing directory has nothing to repair.
+pub fn run_maintenance(dir: &Path) -> Result<MaintenanceReport> {
+    run_maintenance_at(dir, SystemTime::now())
+}
+
+/// Same as [`run_maintenance`] with temporary files aged from `now`
+pub fn run_maintenance_at(dir: &Path, now: SystemTime) -> Result<MaintenanceReport> {
+    let mut report = MaintenanceReport::default();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", dir.display())),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+
+    let mut quarantined_ids = Vec::new();
+    for path in &paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).map(str::to_string) else {
+            continue;
+        };
+        let extension = path.extension().and_then(|e| e.to_str());
+        if name.starts_with(".tmp_") || extension == Some("tmp") {
+            let modified = std::fs::metadata(path).and_then(|m| m.modified())?;
+            // A younger one may belong to a write in progress
+            if now.duration_since(modified).unwrap_or_default() >= STALE_TEMP_AGE {
+                std::fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+                report.removed_temps.push(name);
+            }
+            continue;
+        }
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        // Records sort before their summary, and a summary is no use without its record
+        if extension == Some(SUMMARY_EXT) && quarantined_ids.contains(&stem) {
+            std::fs::rename(path, dir.join(CORRUPT_DIR).join(&name))?;
+            continue;
+        }
+        let problem = match extension {
+            Some(RECORD_EXT) => std::fs::read(path).map_err(anyhow::Error::from)
+                .and_then(|bytes| parse_any_record(&bytes).map(|_| ()))
+                .err(),
+            Some(SUMMARY_EXT) => std::fs::read(path).map_err(anyhow::Error::from)
+                .and_then(|bytes| Ok(serde_cbor::from_slice::<GameSummary>(&bytes).map(|_| ())?))
+                .err(),
+            _ => continue,
+        };
+        match problem {
+            None => report.checked += 1,
+            Some(e) => {
+                let reason = format!("{:#}", e);
+                quarantine(dir, &name, &reason)?;
+                if extension == Some(RECORD_EXT) {
+                    quarantined_ids.push(stem);
+                }
+                report.quarantined.push((name, reason));
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Snapshots of games in progress keep the state as `state`
+#[derive(Deserialize)]
+struct SnapshotRecord {
+    state: GameState,
+}
+
+/// A record as archived or as snapshotted
+fn parse_any_record(bytes: &[u8]) -> Result<GameState> {
+    parse_record(bytes)
+        .or_else(|_| serde_cbor::from_slice::<SnapshotRecord>(bytes).map(|r| r.state))
+        .context("Not a game record")
+}
+
+/// Move `name` out of `dir` into its corrupt folder and say why in the log there
+pub fn quarantine(dir: &Path, name: &str, reason: &str) -> Result<()> {
+    let corrupt = dir.join(CORRUPT_DIR);
+    std::fs::create_dir_all(&corrupt)?;
+    std::fs::rename(dir.join(name), corrupt.join(name))
+        .with_context(|| format!("Failed to quarantine {}", name))?;
+    let mut log = std::fs::OpenOptions::new().create(true).append(true).open(corrupt.join(QUARANTINE_LOG))?;
+    writeln!(log, "{} {}: {}", Utc::now().to_rfc3339(), name, reason)?;
+    tracing::warn!("Moved corrupt {} to {}: {}", name, corrupt.display(), reason);
+    Ok(())
+}
@@ -80,6 +80,17 @@ impl Board {
         result
     }
     
+    /// A board holding `stones`, laid out as in [`crate::GameState::board`]
+    pub(crate) fn from_stones(size: u8, stones: Vec<Option<crate::Color>>) -> Self {
+        debug_assert_eq!(stones.len(), (size as usize) * (size as usize));
+        Self { size, positions: stones }
+    }
+    
+    /// Every point, row by row
+    pub(crate) fn stones(&self) -> &[Option<crate::Color>] {
+        &self.positions
+    }
+    
     /// Get the size of the board
     pub fn size(&self) -> u8 {
         self.size
@@ -4,8 +4,13 @@
 //! 
 //! This module provides functions for serializing and deserializing
 //! game state and events using the Concise Binary Object Representation (CBOR).
+//! Versioned structures load through [`migrate`].
 
-use crate::{GameState, GameEvent, Move};
+pub mod migrate;
+
+use crate::Move;
+#[cfg(feature = "cbor")]
+use crate::{GameState, GameEvent};
 use serde::{Serialize, Deserialize};
 use serde_repr::{Serialize_repr, Deserialize_repr};
 
@@ -20,6 +25,7 @@ pub enum Tag {
 
 /// A move record with optional annotation tag and timestamp
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(into = "migrate::MoveRecordRepr", from = "migrate::MoveRecordRepr")]
 pub struct MoveRecord {
     pub mv: Move,
     pub tag: Option<Tag>,
@@ -31,6 +37,7 @@ pub struct MoveRecord {
 }
 
 /// Serialize game state to CBOR
+#[cfg(feature = "cbor")]
 pub fn serialize_game_state(state: &GameState) -> Vec<u8> {
     match serde_cbor::to_vec(state) {
         Ok(bytes) => bytes,
@@ -42,12 +49,13 @@ pub fn serialize_game_state(state: &GameState) -> Vec<u8> {
 }
 
 /// Deserialize game state from CBOR
+#[cfg(feature = "cbor")]
 pub fn deserialize_game_state(data: &[u8]) -> Option<GameState> {
     if data.is_empty() {
         return None;
     }
 
-    match serde_cbor::from_slice(data) {
+    match migrate::from_slice::<GameState>(data) {
         Ok(state) => Some(state),
         Err(err) => {
             tracing::error!("Failed to deserialize game state: {}", err);
@@ -57,6 +65,7 @@ pub fn deserialize_game_state(data: &[u8]) -> Option<GameState> {
 }
 
 /// Serialize game event to CBOR
+#[cfg(feature = "cbor")]
 pub fn serialize_game_event(event: &GameEvent) -> Vec<u8> {
     match serde_cbor::to_vec(event) {
         Ok(bytes) => bytes,
@@ -68,6 +77,7 @@ pub fn serialize_game_event(event: &GameEvent) -> Vec<u8> {
 }
 
 /// Deserialize game event from CBOR
+#[cfg(feature = "cbor")]
 pub fn deserialize_game_event(data: &[u8]) -> Option<GameEvent> {
     if data.is_empty() {
         return None;
@@ -0,0 +1,288 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Schema versions for persisted and transmitted CBOR.
+//!
+//! Versioned structures carry a `v` field; data written before versioning
+//! has none and reads as v0. Loading goes through a representation holding
+//! every field any version had, upgraded one version at a time by the
+//! functions in `*_UPGRADES` before becoming today's struct. Fields this
+//! build doesn't know, written by a newer version, are ignored.
+//!
+//! To change a versioned structure: bump its `*_VERSION`, add the field to
+//! its representation, append an upgrade from the previous version and check
+//! in a fixture of the new bytes under `core/tests/fixtures/cbor`.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::{Color, Coord, GameState, Move};
+use crate::game_clock::GameTiming;
+use crate::rules::RuleConfig;
+use crate::value_labeller::{FinalPosition, ScoreProof, ScoringMethod, ValueLabel};
+use super::{MoveRecord, Tag};
+
+/// Version of [`GameState`] written today
+pub const GAME_STATE_VERSION: u32 = 3;
+
+/// Version of [`MoveRecord`] written today
+pub const MOVE_RECORD_VERSION: u32 = 1;
+
+/// Version of [`ScoreProof`] written today
+pub const SCORE_PROOF_VERSION: u32 = 1;
+
+/// A CBOR structure that carries a schema version.
+///
+/// Structures still at v0 write no `v` field and have no representation yet;
+/// they get one with their first change.
+pub trait Versioned: serde::de::DeserializeOwned {
+    /// Version written today
+    const VERSION: u32;
+    /// Name used in logs and errors
+    const NAME: &'static str;
+}
+
+impl Versioned for GameState {
+    const VERSION: u32 = GAME_STATE_VERSION;
+    const NAME: &'static str = "game state";
+}
+
+impl Versioned for MoveRecord {
+    const VERSION: u32 = MOVE_RECORD_VERSION;
+    const NAME: &'static str = "move record";
+}
+
+impl Versioned for ScoreProof {
+    const VERSION: u32 = SCORE_PROOF_VERSION;
+    const NAME: &'static str = "score proof";
+}
+
+impl Versioned for ValueLabel {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "value label";
+}
+
+/// Why versioned data failed to load
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("{name} v{version} doesn't decode: {reason}")]
+    Decode { name: &'static str, version: u32, reason: String },
+    #[error("game state has {cells} points for a {board_size}x{board_size} board")]
+    BoardSize { board_size: u8, cells: usize },
+}
+
+/// An upgrade from the version at its index to the next
+pub type Upgrade<T> = fn(&mut T);
+
+/// Apply the upgrades from `version` up to today's
+pub fn upgrade<T>(repr: &mut T, version: u32, upgrades: &[Upgrade<T>], name: &str) {
+    let from = version as usize;
+    if from < upgrades.len() {
+        tracing::debug!("Upgrading {} from v{} to v{}", name, version, upgrades.len());
+    }
+    for step in upgrades.iter().skip(from) {
+        step(repr);
+    }
+}
+
+/// Every field any version of [`GameState`] had
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GameStateRepr {
+    #[serde(default)]
+    v: u32,
+    board_size: u8,
+    board: Vec<Option<Color>>,
+    current_player: Color,
+    moves: Vec<Move>,
+    pass_count: u8,
+    captures: (u16, u16),
+    /// Since v1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    setup: Option<Vec<(Coord, Color)>>,
+    /// Since v2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timing: Option<GameTiming>,
+    /// Since v3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rule_config: Option<RuleConfig>,
+}
+
+const GAME_STATE_UPGRADES: [Upgrade<GameStateRepr>; GAME_STATE_VERSION as usize] = [
+    // v1 added set-up stones; older games started from an empty board
+    |state| { state.setup.get_or_insert_with(Vec::new); },
+    // v2 added time control and clocks; older games were untimed
+    |state| { state.timing.get_or_insert_with(GameTiming::default); },
+    // v3 added rule variations; older games were played without suicide
+    |state| { state.rule_config.get_or_insert_with(RuleConfig::default); },
+];
+
+impl From<GameState> for GameStateRepr {
+    fn from(state: GameState) -> Self {
+        Self {
+            v: GAME_STATE_VERSION,
+            board_size: state.board_size,
+            board: state.board,
+            current_player: state.current_player,
+            moves: state.moves,
+            pass_count: state.pass_count,
+            captures: state.captures,
+            setup: (!state.setup.is_empty()).then_some(state.setup),
+            timing: (!state.timing.is_empty()).then_some(state.timing),
+            rule_config: (state.rule_config != RuleConfig::default()).then_some(state.rule_config),
+        }
+    }
+}
+
+impl TryFrom<GameStateRepr> for GameState {
+    type Error = MigrationError;
+
+    fn try_from(mut repr: GameStateRepr) -> Result<Self, Self::Error> {
+        let version = repr.v;
+        upgrade(&mut repr, version, &GAME_STATE_UPGRADES, GameState::NAME);
+        // Peers can send any bytes; reject boards that don't match their
+        // size so later indexing can't go out of bounds
+        let cells = (repr.board_size as usize) * (repr.board_size as usize);
+        if repr.board.len() != cells {
+            return Err(MigrationError::BoardSize { board_size: repr.board_size, cells: repr.board.len() });
+        }
+        Ok(Self {
+            board_size: repr.board_size,
+            board: repr.board,
+            current_player: repr.current_player,
+            moves: repr.moves,
+            pass_count: repr.pass_count,
+            captures: repr.captures,
+            setup: repr.setup.unwrap_or_default(),
+            timing: repr.timing.unwrap_or_default(),
+            rule_config: repr.rule_config.unwrap_or_default(),
+            scratch: Default::default(),
+        })
+    }
+}
+
+/// Every field any version of [`MoveRecord`] had
+#[derive(Serialize, Deserialize)]
+pub(crate) struct MoveRecordRepr {
+    #[serde(default)]
+    v: u32,
+    mv: Move,
+    tag: Option<Tag>,
+    ts: u64,
+    /// Since v1
+    #[serde(default)]
+    broadcast_hash: Option<[u8; 32]>,
+    /// Since v1
+    #[serde(default)]
+    prev_hash: Option<[u8; 32]>,
+}
+
+const MOVE_RECORD_UPGRADES: [Upgrade<MoveRecordRepr>; MOVE_RECORD_VERSION as usize] = [
+    // v1 only added `v`; the earliest v0 records had no chain hashes, and
+    // those read as unchained, while later unversioned ones keep theirs
+    |_| {},
+];
+
+impl From<MoveRecord> for MoveRecordRepr {
+    fn from(record: MoveRecord) -> Self {
+        Self {
+            v: MOVE_RECORD_VERSION,
+            mv: record.mv,
+            tag: record.tag,
+            ts: record.ts,
+            broadcast_hash: record.broadcast_hash,
+            prev_hash: record.prev_hash,
+        }
+    }
+}
+
+impl From<MoveRecordRepr> for MoveRecord {
+    fn from(mut repr: MoveRecordRepr) -> Self {
+        let version = repr.v;
+        upgrade(&mut repr, version, &MOVE_RECORD_UPGRADES, MoveRecord::NAME);
+        Self {
+            mv: repr.mv,
+            tag: repr.tag,
+            ts: repr.ts,
+            broadcast_hash: repr.broadcast_hash,
+            prev_hash: repr.prev_hash,
+        }
+    }
+}
+
+/// Every field any version of [`ScoreProof`] had
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ScoreProofRepr {
+    #[serde(default)]
+    v: u32,
+    final_score: i16,
+    territory_black: u16,
+    territory_white: u16,
+    captures_black: u16,
+    captures_white: u16,
+    komi: f32,
+    method: ScoringMethod,
+    /// Since v1
+    #[serde(default)]
+    final_position: Option<FinalPosition>,
+}
+
+const SCORE_PROOF_UPGRADES: [Upgrade<ScoreProofRepr>; SCORE_PROOF_VERSION as usize] = [
+    // v1 added the final position; older proofs can't be recounted
+    |_| {},
+];
+
+impl From<ScoreProof> for ScoreProofRepr {
+    fn from(proof: ScoreProof) -> Self {
+        Self {
+            v: SCORE_PROOF_VERSION,
+            final_score: proof.final_score,
+            territory_black: proof.territory_black,
+            territory_white: proof.territory_white,
+            captures_black: proof.captures_black,
+            captures_white: proof.captures_white,
+            komi: proof.komi,
+            method: proof.method,
+            final_position: proof.final_position,
+        }
+    }
+}
+
+impl From<ScoreProofRepr> for ScoreProof {
+    fn from(mut repr: ScoreProofRepr) -> Self {
+        let version = repr.v;
+        upgrade(&mut repr, version, &SCORE_PROOF_UPGRADES, ScoreProof::NAME);
+        Self {
+            final_score: repr.final_score,
+            territory_black: repr.territory_black,
+            territory_white: repr.territory_white,
+            captures_black: repr.captures_black,
+            captures_white: repr.captures_white,
+            komi: repr.komi,
+            method: repr.method,
+            final_position: repr.final_position,
+        }
+    }
+}
+
+/// Just the version of a versioned map
+#[cfg(feature = "cbor")]
+#[derive(Deserialize)]
+struct VersionOnly {
+    #[serde(default)]
+    v: u32,
+}
+
+/// The schema version `bytes` were written with; 0 if they have none
+#[cfg(feature = "cbor")]
+pub fn version_of(bytes: &[u8]) -> u32 {
+    serde_cbor::from_slice::<VersionOnly>(bytes).map(|only| only.v).unwrap_or(0)
+}
+
+/// Load a versioned structure written by any version, upgrading it to today's
+#[cfg(feature = "cbor")]
+pub fn from_slice<T: Versioned>(bytes: &[u8]) -> Result<T, MigrationError> {
+    let version = version_of(bytes);
+    if version > T::VERSION {
+        tracing::debug!("Reading {} v{} from a newer version, ignoring fields it added", T::NAME, version);
+    }
+    serde_cbor::from_slice(bytes)
+        .map_err(|err| MigrationError::Decode { name: T::NAME, version, reason: err.to_string() })
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Coordinate conversions shared by the SGF, GTP and display code.
+//!
+//! `Coord` has its origin at the top-left corner, like SGF. GTP labels
+//! count rows from the bottom and skip the letter 'I' for columns.
+//!
+//! [`parse_move`] reads moves as players type them, for the CLI and the
+//! UI's coordinate box alike.
+
+use thiserror::Error;
+use crate::{Coord, GameError, Move};
+
+/// Column letters used by GTP and board labels ('I' is skipped)
+const GTP_COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// Letters used by SGF FF[4] for coordinates up to 52
+const SGF_LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// GTP column letter for column `x`, if GTP can express it
+pub fn gtp_column(x: u8) -> Option<char> {
+    GTP_COLUMNS.get(x as usize).map(|&c| c as char)
+}
+
+/// GTP row number for row `y` (which must be on the board)
+pub fn gtp_row(y: u8, board_size: u8) -> u8 {
+    board_size - y
+}
+
+/// Why typed move text was refused
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    #[error("Type a move such as D4, pass or resign")]
+    Empty,
+    #[error("\"{0}\" is not a move; try D4, pass or resign")]
+    Malformed(String),
+    #[error("Column {column} is off the {board_size}x{board_size} board")]
+    ColumnOffBoard { column: char, board_size: u8 },
+    #[error("Row {row} is off the {board_size}x{board_size} board")]
+    RowOffBoard { row: u32, board_size: u8 },
+}
+
+/// Parse a typed move: a GTP vertex such as "D4", "pass" or "resign".
+///
+/// Case, surrounding whitespace and full-width characters as typed with
+/// an East Asian input method are accepted.
+pub fn parse_move(input: &str, board_size: u8) -> Result<Move, MoveParseError> {
+    let text: String = input.chars().map(to_half_width).collect::<String>().trim().to_ascii_uppercase();
+    match text.as_str() {
+        "" => Err(MoveParseError::Empty),
+        "PASS" => Ok(Move::Pass),
+        "RESIGN" => Ok(Move::Resign),
+        vertex => parse_vertex(vertex, board_size).map(Move::Place),
+    }
+}
+
+/// ASCII for full-width forms and the ideographic space; other characters as is
+fn to_half_width(c: char) -> char {
+    match c {
+        '\u{3000}' => ' ',
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// An upper-case GTP vertex
+fn parse_vertex(vertex: &str, board_size: u8) -> Result<Coord, MoveParseError> {
+    let malformed = || MoveParseError::Malformed(vertex.to_string());
+    let mut chars = vertex.chars();
+    let column = chars.next().ok_or_else(malformed)?;
+    let row_text = chars.as_str();
+    // Only digits: "+4" and " 4" parse as numbers but are not rows
+    if row_text.is_empty() || row_text.len() > 3 || !row_text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(malformed());
+    }
+    let x = GTP_COLUMNS.iter().position(|&c| c as char == column).ok_or_else(malformed)? as u8;
+    if x >= board_size {
+        return Err(MoveParseError::ColumnOffBoard { column, board_size });
+    }
+    let row: u32 = row_text.parse().map_err(|_| malformed())?;
+    if row == 0 || row > board_size as u32 {
+        return Err(MoveParseError::RowOffBoard { row, board_size });
+    }
+    Ok(Coord::new(x, board_size - row as u8))
+}
+
+fn sgf_index(c: char) -> Option<u8> {
+    SGF_LETTERS.iter().position(|&l| l as char == c).map(|i| i as u8)
+}
+
+impl Coord {
+    /// SGF point such as "dd".
+    ///
+    /// Panics if either coordinate is 52 or more, which SGF cannot express.
+    pub fn to_sgf(self) -> String {
+        let x = SGF_LETTERS[self.x as usize] as char;
+        let y = SGF_LETTERS[self.y as usize] as char;
+        format!("{}{}", x, y)
+    }
+
+    /// Parse an SGF point, rejecting anything off a `board_size` board.
+    ///
+    /// Note that "tt" is off the board on 19x19; older SGF files use it
+    /// for a pass, which callers handle as an error here.
+    pub fn from_sgf(sgf: &str, board_size: u8) -> Result<Self, GameError> {
+        let mut chars = sgf.chars();
+        let (Some(x), Some(y), None) = (chars.next(), chars.next(), chars.next()) else {
+            return Err(GameError::InvalidCoordinate);
+        };
+        let coord = match (sgf_index(x), sgf_index(y)) {
+            (Some(x), Some(y)) => Coord::new(x, y),
+            _ => return Err(GameError::InvalidCoordinate),
+        };
+        if !coord.is_valid(board_size) {
+            return Err(GameError::InvalidCoordinate);
+        }
+        Ok(coord)
+    }
+
+    /// GTP vertex such as "D4", or `None` if off the board or wider than 25
+    pub fn to_gtp(self, board_size: u8) -> Option<String> {
+        if !self.is_valid(board_size) {
+            return None;
+        }
+        let column = gtp_column(self.x)?;
+        Some(format!("{}{}", column, gtp_row(self.y, board_size)))
+    }
+
+    /// Parse a GTP vertex such as "D4" (case-insensitive)
+    pub fn from_gtp(gtp: &str, board_size: u8) -> Result<Self, GameError> {
+        parse_vertex(&gtp.to_ascii_uppercase(), board_size).map_err(|_| GameError::InvalidCoordinate)
+    }
+
+    /// Index into a flat row-major board vector
+    pub fn to_index(self, board_size: u8) -> usize {
+        (self.y as usize) * (board_size as usize) + (self.x as usize)
+    }
+
+    /// Inverse of [`Coord::to_index`], `None` past the end of the board
+    pub fn from_index(idx: usize, board_size: u8) -> Option<Self> {
+        let size = board_size as usize;
+        if size == 0 || idx >= size * size {
+            return None;
+        }
+        Some(Coord::new((idx % size) as u8, (idx / size) as u8))
+    }
+
+    /// Human-readable label: the GTP vertex, or "x,y" on boards GTP can't label
+    pub fn display_label(self, board_size: u8) -> String {
+        self.to_gtp(board_size)
+            .unwrap_or_else(|| format!("{},{}", self.x, self.y))
+    }
+}
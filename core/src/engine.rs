@@ -3,7 +3,12 @@
 //! Game engine interfaces and AI backend
 
 use std::time::Duration;
-use crate::{GameState, Move};
+use crate::analysis;
+use crate::policy::{move_index, pass_index};
+use crate::position::Position;
+use crate::rng::{self, RngCore};
+use crate::settings::standard_komi;
+use crate::{Color, Coord, GameState, Move};
 
 /// Player backend trait for both human and AI players
 pub trait PlayerBackend {
@@ -11,6 +16,319 @@ pub trait PlayerBackend {
     fn next_move(&mut self, pos: &GameState, time_left: Duration) -> Move;
 }
 
+/// Thresholds for acting on a model's output, shared by the engines and
+/// the UI's suggestions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NeuralConfig {
+    /// Value for the player to move, from -1 to 1, below which the
+    /// position counts as lost
+    pub resign_threshold: f32,
+    /// Lost evaluations in a row before an engine resigns
+    pub resign_after: usize,
+    /// Moves suggested as ghost stones
+    pub ghost_moves: usize,
+}
+
+impl Default for NeuralConfig {
+    fn default() -> Self {
+        Self {
+            resign_threshold: -0.95,
+            resign_after: 3,
+            ghost_moves: 3,
+        }
+    }
+}
+
+impl NeuralConfig {
+    /// Whether `value_for_mover` counts as lost
+    pub fn hopeless(&self, value_for_mover: f32) -> bool {
+        value_for_mover < self.resign_threshold
+    }
+}
+
+/// `value`, Black's outlook, as seen by the player to move in `pos`
+pub fn value_for_mover(pos: &GameState, value: f32) -> f32 {
+    match pos.current_player {
+        Color::Black => value,
+        Color::White => -value,
+    }
+}
+
+/// Plays the legal move its policy rates highest, including pass.
+///
+/// `policy` returns one logit per point plus one for pass, laid out as in
+/// [`crate::policy`]. Points that fill one of our own eyes are never played,
+/// so once only eyes are left the player passes.
+pub struct PolicyPlayer<F> {
+    policy: F,
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> PolicyPlayer<F> {
+    pub fn new(policy: F) -> Self {
+        Self { policy }
+    }
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> PlayerBackend for PolicyPlayer<F> {
+    fn next_move(&mut self, pos: &GameState, _time_left: Duration) -> Move {
+        let Ok(position) = Position::from_dense(pos) else {
+            return Move::Pass;
+        };
+        let logits = (self.policy)(pos);
+        let size = pos.board_size;
+        let logit = |index: usize| logits.get(index).copied().unwrap_or(f32::NEG_INFINITY);
+        
+        let mut best = (Move::Pass, logit(pass_index(size)));
+        for coord in position.legal_moves() {
+            if fills_own_eye(&position, coord, position.current_player()) {
+                continue;
+            }
+            let mv = Move::Place(coord);
+            let score = move_index(&mv, size).map_or(f32::NEG_INFINITY, logit);
+            // Ties go to the earlier move, so passing has to be strictly better
+            if score > best.1 {
+                best = (mv, score);
+            }
+        }
+        best.0
+    }
+}
+
+/// Plays as `inner` until `value` finds the position lost on
+/// [`NeuralConfig::resign_after`] of its turns in a row, then resigns.
+///
+/// `value` returns Black's outlook from -1 to 1, as a value head does.
+pub struct ResigningPlayer<P, V> {
+    inner: P,
+    value: V,
+    config: NeuralConfig,
+    /// Our turns in a row found lost
+    lost: usize,
+}
+
+impl<P: PlayerBackend, V: FnMut(&GameState) -> f32> ResigningPlayer<P, V> {
+    pub fn new(inner: P, value: V, config: NeuralConfig) -> Self {
+        Self { inner, value, config, lost: 0 }
+    }
+}
+
+impl<P: PlayerBackend, V: FnMut(&GameState) -> f32> PlayerBackend for ResigningPlayer<P, V> {
+    fn next_move(&mut self, pos: &GameState, time_left: Duration) -> Move {
+        let value = (self.value)(pos);
+        if self.config.hopeless(value_for_mover(pos, value)) {
+            self.lost += 1;
+        } else {
+            self.lost = 0;
+        }
+        if self.lost >= self.config.resign_after.max(1) {
+            return Move::Resign;
+        }
+        self.inner.next_move(pos, time_left)
+    }
+}
+
+/// Plays a random legal move that doesn't fill one of its own eyes, and
+/// passes once none is left.
+///
+/// Each move draws from [`rng::move_stream`] for its move number, so the
+/// same seed plays the same game against the same opponent.
+pub struct RandomPlayer {
+    game_seed: u64,
+}
+
+impl RandomPlayer {
+    pub fn new(game_seed: u64) -> Self {
+        Self { game_seed }
+    }
+}
+
+impl PlayerBackend for RandomPlayer {
+    fn next_move(&mut self, pos: &GameState, _time_left: Duration) -> Move {
+        let Ok(position) = Position::from_dense(pos) else {
+            return Move::Pass;
+        };
+        let color = position.current_player();
+        let moves: Vec<Coord> = position.legal_moves()
+            .into_iter()
+            .filter(|&coord| !fills_own_eye(&position, coord, color))
+            .collect();
+        if moves.is_empty() {
+            return Move::Pass;
+        }
+        let mut rng = rng::move_stream(self.game_seed, pos.moves.len());
+        Move::Place(moves[rng::below(&mut rng, moves.len())])
+    }
+}
+
+/// Legal moves for the player to move with their policy logits, pass
+/// included and own-eye fills left out
+fn rated_moves(position: &Position, logits: &[f32]) -> Vec<(Move, f32)> {
+    let size = position.board_size();
+    let logit = |index: usize| logits.get(index).copied().unwrap_or(f32::NEG_INFINITY);
+    let color = position.current_player();
+    let mut moves: Vec<(Move, f32)> = position.legal_moves()
+        .into_iter()
+        .filter(|&coord| !fills_own_eye(position, coord, color))
+        .map(|coord| {
+            let mv = Move::Place(coord);
+            let score = move_index(&mv, size).map_or(f32::NEG_INFINITY, logit);
+            (mv, score)
+        })
+        .collect();
+    moves.push((Move::Pass, logit(pass_index(size))));
+    moves
+}
+
+/// Draws its move from the policy's softmax at `temperature`; near 0 it
+/// almost always plays the top move, at 1 it follows the policy as is.
+///
+/// Moves are drawn from [`rng::move_stream`], as [`RandomPlayer`]'s are.
+pub struct SampledPolicyPlayer<F> {
+    policy: F,
+    temperature: f32,
+    game_seed: u64,
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> SampledPolicyPlayer<F> {
+    pub fn new(policy: F, temperature: f32, game_seed: u64) -> Self {
+        Self { policy, temperature, game_seed }
+    }
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> PlayerBackend for SampledPolicyPlayer<F> {
+    fn next_move(&mut self, pos: &GameState, _time_left: Duration) -> Move {
+        let Ok(position) = Position::from_dense(pos) else {
+            return Move::Pass;
+        };
+        let moves = rated_moves(&position, &(self.policy)(pos));
+        let top = moves.iter().map(|(_, logit)| *logit).fold(f32::NEG_INFINITY, f32::max);
+        if !top.is_finite() {
+            return Move::Pass;
+        }
+        let temperature = self.temperature.max(0.01);
+        let weights: Vec<f64> = moves.iter()
+            .map(|(_, logit)| f64::from((logit - top) / temperature).exp())
+            .collect();
+        let mut rng = rng::move_stream(self.game_seed, pos.moves.len());
+        let mut pick = (rng.next_u64() as f64 / u64::MAX as f64) * weights.iter().sum::<f64>();
+        for ((mv, _), weight) in moves.iter().zip(&weights) {
+            if pick < *weight {
+                return mv.clone();
+            }
+            pick -= weight;
+        }
+        Move::Pass
+    }
+}
+
+/// Tries the policy's `candidates` favourite points with `playouts` random
+/// playouts each and plays the one whose expected score is best.
+///
+/// Once the opponent passes, passing is scored the same way and wins when
+/// no candidate beats it. The search only looks one move ahead, so more
+/// candidates and playouts make it stronger, not deeper.
+pub struct SearchPlayer<F> {
+    policy: F,
+    candidates: usize,
+    playouts: usize,
+    game_seed: u64,
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> SearchPlayer<F> {
+    pub fn new(policy: F, candidates: usize, playouts: usize, game_seed: u64) -> Self {
+        Self { policy, candidates, playouts, game_seed }
+    }
+}
+
+impl<F: FnMut(&GameState) -> Vec<f32>> PlayerBackend for SearchPlayer<F> {
+    fn next_move(&mut self, pos: &GameState, _time_left: Duration) -> Move {
+        let Ok(position) = Position::from_dense(pos) else {
+            return Move::Pass;
+        };
+        let komi = standard_komi(pos.board_size);
+        let mut moves = rated_moves(&position, &(self.policy)(pos));
+        moves.retain(|(mv, _)| *mv != Move::Pass);
+        moves.sort_by(|a, b| b.1.total_cmp(&a.1));
+        moves.truncate(self.candidates.max(1));
+        if pos.moves.last() == Some(&Move::Pass) {
+            moves.push((Move::Pass, 0.0));
+        }
+
+        let sign = match position.current_player() {
+            Color::Black => 1.0,
+            Color::White => -1.0,
+        };
+        let mut rng = rng::move_stream(self.game_seed, pos.moves.len());
+        let mut best = (Move::Pass, f32::NEG_INFINITY);
+        for (mv, _) in moves {
+            let Ok(next) = position.play(mv.clone()) else {
+                continue;
+            };
+            let ownership = analysis::ownership_with(&next.to_dense(), self.playouts, &mut rng);
+            let score = sign * analysis::expected_score(&ownership, komi);
+            // Ties go to the earlier move, so passing has to be strictly better
+            if score > best.1 {
+                best = (mv, score);
+            }
+        }
+        best.0
+    }
+}
+
+/// Play a game of `board_size` between `black` and `white` until it ends or
+/// `max_moves` have been played
+pub fn self_play(
+    board_size: u8,
+    black: &mut dyn PlayerBackend,
+    white: &mut dyn PlayerBackend,
+    max_moves: usize,
+) -> GameState {
+    self_play_with(board_size, black, white, max_moves, || {})
+}
+
+/// [`self_play`] in the background, as `governor` allows
+#[cfg(feature = "std")]
+pub fn self_play_governed(
+    board_size: u8,
+    black: &mut dyn PlayerBackend,
+    white: &mut dyn PlayerBackend,
+    max_moves: usize,
+    governor: &crate::governor::Governor,
+) -> GameState {
+    let _slot = governor.slot();
+    self_play_with(board_size, black, white, max_moves, || governor.checkpoint())
+}
+
+fn self_play_with(
+    board_size: u8,
+    black: &mut dyn PlayerBackend,
+    white: &mut dyn PlayerBackend,
+    max_moves: usize,
+    mut before_move: impl FnMut(),
+) -> GameState {
+    let mut game = GameState::new(board_size);
+    while !game.is_game_over() && game.moves.len() < max_moves {
+        before_move();
+        let mv = match game.current_player {
+            Color::Black => black.next_move(&game, Duration::MAX),
+            Color::White => white.next_move(&game, Duration::MAX),
+        };
+        if game.apply_move(mv).is_err() {
+            // An illegal move from an engine forfeits its turn
+            let _ = game.apply_move(Move::Pass);
+        }
+    }
+    game
+}
+
+/// Whether every neighbour of the empty point `coord` is a `color` stone
+pub fn fills_own_eye(position: &Position, coord: Coord, color: Color) -> bool {
+    coord.adjacent_coords()
+        .into_iter()
+        .filter(|c| c.is_valid(position.board_size()))
+        .all(|c| position.get(c) == Some(color))
+}
+
 #[cfg(feature = "bot")]
 pub mod bot {
     use super::*;
@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game clocks: main time, then byo-yomi periods.
+//!
+//! Players spend their main time first. Once it is gone each move must be
+//! made within a byo-yomi period; a period used up in full is lost, and the
+//! player whose last period runs out has lost on time. The clocks start with
+//! the first move.
+//!
+//! A game keeps its time control and each mover's clock in [`GameTiming`],
+//! which SGF writes as `TM`, `OT`, `BL`/`WL` and `OB`/`OW`.
+
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use crate::Color;
+
+/// One player's time left
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerTime {
+    pub main_ms: u64,
+    /// Byo-yomi periods left, the current one included
+    pub periods: u32,
+    /// Time left in the current period
+    pub period_ms: u64,
+}
+
+impl PlayerTime {
+    /// Whether all time, periods included, is gone
+    pub fn flagged(&self) -> bool {
+        self.main_ms == 0 && (self.periods == 0 || self.period_ms == 0)
+    }
+
+    /// Whether main time is gone and periods are being counted
+    pub fn in_byo_yomi(&self) -> bool {
+        self.main_ms == 0 && self.periods > 0
+    }
+
+    /// All time left: main time, the current period and the periods after it
+    pub fn total_ms(&self, byo_yomi_ms: u64) -> u64 {
+        let later = self.periods.saturating_sub(1) as u64 * byo_yomi_ms;
+        self.main_ms + if self.periods > 0 { self.period_ms + later } else { 0 }
+    }
+
+    /// The time left once `total_ms` remains, losing periods as it shrinks;
+    /// the inverse of [`PlayerTime::total_ms`] for a player with at most
+    /// `periods` periods
+    pub fn from_total(total_ms: u64, periods: u32, byo_yomi_ms: u64) -> Self {
+        let all_periods = periods as u64 * byo_yomi_ms;
+        if byo_yomi_ms == 0 || total_ms > all_periods {
+            return Self { main_ms: total_ms.saturating_sub(all_periods), periods, period_ms: byo_yomi_ms };
+        }
+        let periods = total_ms.div_ceil(byo_yomi_ms) as u32;
+        let period_ms = total_ms - periods.saturating_sub(1) as u64 * byo_yomi_ms;
+        Self { main_ms: 0, periods, period_ms }
+    }
+
+    /// The time left after thinking for `ms`
+    pub fn spend(&self, ms: u64, byo_yomi_ms: u64) -> Self {
+        Self::from_total(self.total_ms(byo_yomi_ms).saturating_sub(ms), self.periods, byo_yomi_ms)
+    }
+}
+
+/// The clocks as the channel keeping them saw them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSnapshot {
+    pub black: PlayerTime,
+    pub white: PlayerTime,
+    /// Whose clock is running; `None` while stopped or paused
+    pub running: Option<Color>,
+    /// Stopped until the opponent is back
+    pub paused: bool,
+    /// Length of a byo-yomi period
+    pub byo_yomi_ms: u64,
+}
+
+impl ClockSnapshot {
+    pub fn time(&self, color: Color) -> PlayerTime {
+        match color {
+            Color::Black => self.black,
+            Color::White => self.white,
+        }
+    }
+
+    /// The time `color` would have `elapsed` after the snapshot was taken
+    pub fn time_after(&self, color: Color, elapsed: Duration) -> PlayerTime {
+        let time = self.time(color);
+        if self.running == Some(color) {
+            time.spend(elapsed.as_millis() as u64, self.byo_yomi_ms)
+        } else {
+            time
+        }
+    }
+
+    /// Byo-yomi periods used up since `before`, an earlier snapshot of the
+    /// same clocks: the player and the periods they had left after each,
+    /// in the order they ran out. A player left with none is out of time.
+    pub fn periods_used_since(&self, before: &ClockSnapshot) -> Vec<(Color, u32)> {
+        let mut used = Vec::new();
+        for color in [Color::Black, Color::White] {
+            let (was, now) = (before.time(color).periods, self.time(color));
+            if now.main_ms == 0 && now.periods < was {
+                used.extend((now.periods..was).rev().map(|left| (color, left)));
+            }
+        }
+        used
+    }
+
+    /// The player who is out of time, if either is
+    pub fn flagged(&self) -> Option<Color> {
+        [Color::Black, Color::White].into_iter().find(|&c| self.time(c).flagged())
+    }
+}
+
+/// What the clock does while the opponent is disconnected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OnDisconnect {
+    /// Stop both clocks until they are back
+    #[default]
+    Pause,
+    /// Leave the clock running; a player who stays away loses on time
+    KeepRunning,
+}
+
+/// Both players' clocks, kept by whoever is authoritative for the game
+#[derive(Debug, Clone)]
+pub struct GameClock {
+    black: PlayerTime,
+    white: PlayerTime,
+    byo_yomi_ms: u64,
+    /// Whose clock runs, and since when
+    running: Option<(Color, Instant)>,
+    /// Whose clock ran before a pause
+    paused: Option<Color>,
+    stopped: bool,
+    settings: TimeSettings,
+}
+
+impl GameClock {
+    /// Clocks with `main_time` each, then `periods` periods of `byo_yomi`
+    pub fn new(main_time: Duration, byo_yomi: Duration, periods: u32) -> Self {
+        let byo_yomi_ms = byo_yomi.as_millis() as u64;
+        let periods = if byo_yomi_ms == 0 { 0 } else { periods };
+        let time = PlayerTime { main_ms: main_time.as_millis() as u64, periods, period_ms: byo_yomi_ms };
+        let settings = TimeSettings { main_secs: main_time.as_secs(), periods, byo_yomi_secs: byo_yomi.as_secs() };
+        Self { black: time, white: time, byo_yomi_ms, running: None, paused: None, stopped: false, settings }
+    }
+
+    /// The time control the clocks started with
+    pub fn settings(&self) -> TimeSettings {
+        self.settings
+    }
+
+    fn time_mut(&mut self, color: Color) -> &mut PlayerTime {
+        match color {
+            Color::Black => &mut self.black,
+            Color::White => &mut self.white,
+        }
+    }
+
+    /// Charge the running player for the time since their clock started
+    fn charge(&mut self, now: Instant) {
+        if let Some((color, since)) = self.running {
+            let spent = now.saturating_duration_since(since).as_millis() as u64;
+            let byo_yomi_ms = self.byo_yomi_ms;
+            let time = self.time_mut(color);
+            *time = time.spend(spent, byo_yomi_ms);
+            self.running = Some((color, now));
+        }
+    }
+
+    /// `by` moved: stop their clock, refill their period and start the
+    /// opponent's
+    pub fn moved(&mut self, by: Color, now: Instant) {
+        if self.stopped {
+            return;
+        }
+        self.charge(now);
+        let byo_yomi_ms = self.byo_yomi_ms;
+        let time = self.time_mut(by);
+        if time.in_byo_yomi() && !time.flagged() {
+            time.period_ms = byo_yomi_ms;
+        }
+        if self.paused.is_some() {
+            self.paused = Some(by.opposite());
+        } else {
+            self.running = Some((by.opposite(), now));
+        }
+    }
+
+    /// Stop the running clock until [`GameClock::resume`]
+    pub fn pause(&mut self, now: Instant) {
+        self.charge(now);
+        if let Some((color, _)) = self.running.take() {
+            self.paused = Some(color);
+        }
+    }
+
+    pub fn resume(&mut self, now: Instant) {
+        if let Some(color) = self.paused.take().filter(|_| !self.stopped) {
+            self.running = Some((color, now));
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Stop for good, once the game is over
+    pub fn stop(&mut self, now: Instant) {
+        self.charge(now);
+        self.running = None;
+        self.paused = None;
+        self.stopped = true;
+    }
+
+    /// The player whose time ran out, if either has
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        self.snapshot(now).flagged()
+    }
+
+    /// The clocks at `now`
+    pub fn snapshot(&self, now: Instant) -> ClockSnapshot {
+        let mut clock = self.clone();
+        clock.charge(now);
+        ClockSnapshot {
+            black: clock.black,
+            white: clock.white,
+            running: clock.running.map(|(color, _)| color),
+            paused: clock.paused.is_some(),
+            byo_yomi_ms: clock.byo_yomi_ms,
+        }
+    }
+}
+
+/// A time control as SGF records it: `TM` main time and `OT` overtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSettings {
+    pub main_secs: u64,
+    /// Byo-yomi periods; 0 for none
+    pub periods: u32,
+    pub byo_yomi_secs: u64,
+}
+
+impl TimeSettings {
+    /// The clock each player starts with
+    pub fn start(&self) -> PlayerTime {
+        PlayerTime { main_ms: self.main_secs * 1000, periods: self.periods, period_ms: self.byo_yomi_ms() }
+    }
+
+    pub fn byo_yomi_ms(&self) -> u64 {
+        if self.periods == 0 { 0 } else { self.byo_yomi_secs * 1000 }
+    }
+
+    /// The `OT` text, e.g. `5x30 byo-yomi`; `None` without periods
+    pub fn overtime(&self) -> Option<String> {
+        (self.periods > 0 && self.byo_yomi_secs > 0).then(|| format!("{}x{} byo-yomi", self.periods, self.byo_yomi_secs))
+    }
+
+    /// Periods and their length from `OT` text starting `<periods>x<secs>`;
+    /// other overtime systems give `None`
+    pub fn parse_overtime(text: &str) -> Option<(u32, u64)> {
+        let (periods, rest) = text.trim().split_once('x')?;
+        let secs: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        Some((periods.trim().parse().ok()?, secs.parse().ok()?))
+    }
+}
+
+/// A player's clock as they made a move, as SGF's `BL`/`WL` and `OB`/`OW`
+/// record it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingInfo {
+    /// Main time left, or in byo-yomi what was left of the period
+    pub time_left_ms: u64,
+    /// Byo-yomi periods left, the current one included; `None` in main time
+    pub periods: Option<u32>,
+}
+
+impl TimingInfo {
+    /// What SGF records of `time` as a move is made
+    pub fn of(time: PlayerTime) -> Self {
+        if time.in_byo_yomi() {
+            Self { time_left_ms: time.period_ms, periods: Some(time.periods) }
+        } else {
+            Self { time_left_ms: time.main_ms, periods: None }
+        }
+    }
+
+    /// The clock this records, under `settings`
+    fn time(&self, settings: &TimeSettings) -> PlayerTime {
+        match self.periods {
+            Some(periods) => PlayerTime { main_ms: 0, periods, period_ms: self.time_left_ms },
+            None => PlayerTime { main_ms: self.time_left_ms, periods: settings.periods, period_ms: settings.byo_yomi_ms() },
+        }
+    }
+}
+
+/// The time control of a game and each player's clock at each move, kept
+/// with the game so an archive or SGF shows how time was used
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameTiming {
+    pub settings: Option<TimeSettings>,
+    /// The mover's clock per move index, where known
+    pub moves: Vec<Option<TimingInfo>>,
+}
+
+impl GameTiming {
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_none() && self.moves.iter().all(Option::is_none)
+    }
+
+    pub fn at(&self, index: usize) -> Option<TimingInfo> {
+        self.moves.get(index).copied().flatten()
+    }
+
+    /// Record the mover's clock for move `index`
+    pub fn record(&mut self, index: usize, info: TimingInfo) {
+        if self.moves.len() <= index {
+            self.moves.resize(index + 1, None);
+        }
+        self.moves[index] = Some(info);
+    }
+
+    /// Forget the clocks of moves from `len` on, e.g. once they are taken back
+    pub fn truncate(&mut self, len: usize) {
+        self.moves.truncate(len);
+    }
+
+    /// How long move `index` was thought over: the mover's clock then
+    /// against their move before, or the start for their first move.
+    ///
+    /// A byo-yomi period is full again after each move, as on a
+    /// [`GameClock`]. `None` when the clocks needed aren't recorded.
+    pub fn think_time(&self, index: usize) -> Option<Duration> {
+        let settings = self.settings.unwrap_or_default();
+        let now = self.at(index)?;
+        // Periods can't be counted without knowing their length
+        if now.periods.is_some() && self.settings.is_none() {
+            return None;
+        }
+        let now = now.time(&settings);
+        let before = match index.checked_sub(2) {
+            Some(previous) => {
+                let mut time = self.at(previous)?.time(&settings);
+                if time.in_byo_yomi() {
+                    time.period_ms = settings.byo_yomi_ms();
+                }
+                time
+            }
+            None => self.settings?.start(),
+        };
+        let byo_yomi_ms = settings.byo_yomi_ms();
+        Some(Duration::from_millis(before.total_ms(byo_yomi_ms).saturating_sub(now.total_ms(byo_yomi_ms))))
+    }
+}
@@ -0,0 +1,304 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Keeping background self-play and training out of a live game's way.
+//!
+//! A [`Governor`] is shared by the app and the tasks it runs in the
+//! background. Tasks take a [`Governor::slot`] while they run, so only so
+//! many run at once, and call [`Governor::checkpoint`] between units of
+//! work, a move of self-play or a batch of training. The checkpoint holds
+//! them while a live game is being played, while the machine is thermally
+//! throttled, and for the rest at the end of every duty cycle period.
+//! [`Throttle`] makes those decisions from the signals it is given and the
+//! time, so they can be tested without waiting.
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// How often a paused task looks again, in case a signal was missed
+const PAUSE_RECHECK: Duration = Duration::from_millis(250);
+
+/// How much background work may run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GovernorConfig {
+    /// Tasks that may run at once
+    pub parallelism: usize,
+    /// Length of one duty cycle
+    pub period_ms: u64,
+    /// Rest at the end of each period
+    pub rest_ms: u64,
+}
+
+impl Default for GovernorConfig {
+    fn default() -> Self {
+        // Half the cores, leaving the rest to the game and the UI
+        let cores = std::thread::available_parallelism().map_or(2, |n| n.get());
+        Self { parallelism: (cores / 2).max(1), period_ms: 1000, rest_ms: 200 }
+    }
+}
+
+impl GovernorConfig {
+    /// No limits, for work nothing else waits on
+    pub fn unlimited() -> Self {
+        Self { parallelism: usize::MAX, period_ms: 1000, rest_ms: 0 }
+    }
+
+    fn period(&self) -> Duration {
+        Duration::from_millis(self.period_ms.max(1))
+    }
+
+    fn rest(&self) -> Duration {
+        Duration::from_millis(self.rest_ms).min(self.period())
+    }
+}
+
+/// Thermal pressure as macOS reports it, least first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub enum ThermalPressure {
+    #[default]
+    Nominal,
+    /// Warm: rest twice as long
+    Moderate,
+    /// Throttled: stop until it cools down
+    Heavy,
+    Trapping,
+    Sleeping,
+}
+
+impl ThermalPressure {
+    /// Pressure from the level the system posts, 0 for nominal up
+    pub fn from_level(level: u64) -> Self {
+        match level {
+            0 => ThermalPressure::Nominal,
+            1 => ThermalPressure::Moderate,
+            2 => ThermalPressure::Heavy,
+            3 => ThermalPressure::Trapping,
+            _ => ThermalPressure::Sleeping,
+        }
+    }
+
+    /// Whether background work should stop altogether
+    pub fn throttled(self) -> bool {
+        self >= ThermalPressure::Heavy
+    }
+}
+
+/// Why background work is on hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PauseReason {
+    LiveGame,
+    Thermal,
+}
+
+impl fmt::Display for PauseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PauseReason::LiveGame => write!(f, "live game in progress"),
+            PauseReason::Thermal => write!(f, "the machine is running hot"),
+        }
+    }
+}
+
+/// What background work may do now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GovernorStatus {
+    Running,
+    /// In the rest at the end of a duty cycle
+    Resting,
+    Paused(PauseReason),
+}
+
+impl fmt::Display for GovernorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GovernorStatus::Running => write!(f, "running"),
+            GovernorStatus::Resting => write!(f, "resting"),
+            GovernorStatus::Paused(reason) => write!(f, "paused: {}", reason),
+        }
+    }
+}
+
+/// The governor's decisions, from its signals and the time
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    config: GovernorConfig,
+    live_game: bool,
+    thermal: ThermalPressure,
+    /// Start of the first duty cycle period
+    epoch: Instant,
+}
+
+impl Throttle {
+    pub fn new(config: GovernorConfig, now: Instant) -> Self {
+        Self { config, live_game: false, thermal: ThermalPressure::Nominal, epoch: now }
+    }
+
+    pub fn config(&self) -> GovernorConfig {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: GovernorConfig) {
+        self.config = config;
+    }
+
+    /// Whether a live networked game is being played
+    pub fn set_live_game(&mut self, live: bool) {
+        self.live_game = live;
+    }
+
+    pub fn set_thermal(&mut self, pressure: ThermalPressure) {
+        self.thermal = pressure;
+    }
+
+    /// Why work is on hold, if it is, rests aside
+    pub fn paused(&self) -> Option<PauseReason> {
+        if self.live_game {
+            Some(PauseReason::LiveGame)
+        } else if self.thermal.throttled() {
+            Some(PauseReason::Thermal)
+        } else {
+            None
+        }
+    }
+
+    pub fn status(&self, now: Instant) -> GovernorStatus {
+        match self.paused() {
+            Some(reason) => GovernorStatus::Paused(reason),
+            None if self.rest_left(now).is_some() => GovernorStatus::Resting,
+            None => GovernorStatus::Running,
+        }
+    }
+
+    /// How long a task at a checkpoint should wait before asking again;
+    /// `None` to carry on
+    pub fn wait(&self, now: Instant) -> Option<Duration> {
+        match self.paused() {
+            Some(_) => Some(PAUSE_RECHECK),
+            None => self.rest_left(now),
+        }
+    }
+
+    /// What is left of the rest, if `now` falls in one
+    fn rest_left(&self, now: Instant) -> Option<Duration> {
+        let period = self.config.period();
+        let mut rest = self.config.rest();
+        if self.thermal == ThermalPressure::Moderate {
+            rest = (rest * 2).min(period);
+        }
+        let into = now.saturating_duration_since(self.epoch).as_nanos() % period.as_nanos();
+        let into = Duration::from_nanos(into as u64);
+        (into >= period - rest && !rest.is_zero()).then(|| period - into)
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    throttle: Mutex<Throttle>,
+    /// Signalled when a signal or the config changes
+    changed: Condvar,
+    running: Mutex<usize>,
+    slot_freed: Condvar,
+}
+
+/// Throttles background work; clones share the same state
+#[derive(Debug, Clone)]
+pub struct Governor {
+    shared: Arc<Shared>,
+}
+
+impl Default for Governor {
+    fn default() -> Self {
+        Self::new(GovernorConfig::default())
+    }
+}
+
+impl Governor {
+    pub fn new(config: GovernorConfig) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                throttle: Mutex::new(Throttle::new(config, Instant::now())),
+                changed: Condvar::new(),
+                running: Mutex::new(0),
+                slot_freed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// A governor that never holds work back
+    pub fn unlimited() -> Self {
+        Self::new(GovernorConfig::unlimited())
+    }
+
+    pub fn config(&self) -> GovernorConfig {
+        self.throttle().config()
+    }
+
+    pub fn set_config(&self, config: GovernorConfig) {
+        self.update(|throttle| throttle.set_config(config));
+        self.shared.slot_freed.notify_all();
+    }
+
+    /// Whether a live networked game is being played
+    pub fn set_live_game(&self, live: bool) {
+        self.update(|throttle| throttle.set_live_game(live));
+    }
+
+    pub fn set_thermal(&self, pressure: ThermalPressure) {
+        self.update(|throttle| throttle.set_thermal(pressure));
+    }
+
+    /// Why work is on hold, if it is, rests aside
+    pub fn paused(&self) -> Option<PauseReason> {
+        self.throttle().paused()
+    }
+
+    pub fn status(&self) -> GovernorStatus {
+        self.throttle().status(Instant::now())
+    }
+
+    /// Wait until work may go on
+    pub fn checkpoint(&self) {
+        let mut throttle = self.throttle();
+        while let Some(wait) = throttle.wait(Instant::now()) {
+            throttle = self.shared.changed.wait_timeout(throttle, wait).unwrap().0;
+        }
+    }
+
+    /// Wait for a turn to run, held until the slot is dropped
+    pub fn slot(&self) -> Slot<'_> {
+        let mut running = self.shared.running.lock().unwrap();
+        while *running >= self.config().parallelism {
+            running = self.shared.slot_freed.wait(running).unwrap();
+        }
+        *running += 1;
+        Slot { governor: self }
+    }
+
+    /// Tasks holding a slot
+    pub fn running(&self) -> usize {
+        *self.shared.running.lock().unwrap()
+    }
+
+    fn throttle(&self) -> MutexGuard<'_, Throttle> {
+        self.shared.throttle.lock().unwrap()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Throttle)) {
+        change(&mut self.throttle());
+        self.shared.changed.notify_all();
+    }
+}
+
+/// A running task's turn, given back when dropped
+#[derive(Debug)]
+pub struct Slot<'a> {
+    governor: &'a Governor,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        *self.governor.shared.running.lock().unwrap() -= 1;
+        self.governor.shared.slot_freed.notify_one();
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Solo ladder: AI opponents of increasing strength, each unlocked by
+//! beating the one before.
+//!
+//! Every rung is a row of [`RUNGS`], so a new rung needs no code. Ladder
+//! games are 9x9, the size the policy network is trained for.
+
+use std::time::Duration;
+use crate::analysis::{expected_score, ownership};
+use crate::engine::{PlayerBackend, RandomPlayer, SampledPolicyPlayer, SearchPlayer};
+use crate::settings::standard_komi;
+use crate::{Color, Coord, EndReason, GameState, Move};
+
+/// Board size of ladder games
+pub const LADDER_BOARD_SIZE: u8 = 9;
+
+/// Playouts behind the estimated result of a game both players passed
+pub const RESULT_PLAYOUTS: usize = 200;
+
+/// How an opponent picks its moves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength {
+    /// A random legal move
+    Random,
+    /// A draw from the policy at `temperature`; lower is sharper
+    Policy { temperature: f32 },
+    /// The policy's `candidates` favourite points, checked by `playouts`
+    /// random playouts each
+    Search { candidates: usize, playouts: usize },
+}
+
+impl Strength {
+    /// Whether moves come from the policy network
+    pub fn needs_policy(&self) -> bool {
+        !matches!(self, Strength::Random)
+    }
+}
+
+/// One opponent of the ladder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rung {
+    pub name: &'static str,
+    pub strength: Strength,
+    /// Whether the first moves come from [`book_move`]
+    pub opening_book: bool,
+}
+
+/// The ladder, weakest first
+pub const RUNGS: &[Rung] = &[
+    Rung { name: "Wanderer", strength: Strength::Random, opening_book: false },
+    Rung { name: "Apprentice", strength: Strength::Policy { temperature: 0.3 }, opening_book: false },
+    Rung { name: "Reader", strength: Strength::Search { candidates: 4, playouts: 8 }, opening_book: false },
+    Rung { name: "Master", strength: Strength::Search { candidates: 8, playouts: 32 }, opening_book: true },
+];
+
+/// Moves so far, as `(x, y)` points, and the book's reply
+type BookLine = (&'static [(u8, u8)], (u8, u8));
+
+/// Replies to the first moves of a 9x9 game
+const OPENING_BOOK: &[BookLine] = &[
+    (&[], (4, 4)),
+    (&[(4, 4)], (6, 2)),
+    (&[(2, 2)], (6, 6)),
+    (&[(6, 6)], (2, 2)),
+    (&[(2, 6)], (6, 2)),
+    (&[(6, 2)], (2, 6)),
+    (&[(4, 4), (6, 2)], (2, 6)),
+    (&[(4, 4), (2, 6)], (6, 2)),
+    (&[(4, 4), (2, 2)], (6, 6)),
+    (&[(4, 4), (6, 6)], (2, 2)),
+];
+
+/// The book's reply to the moves of `state`, if it has one and it is legal
+pub fn book_move(state: &GameState) -> Option<Move> {
+    if state.board_size != LADDER_BOARD_SIZE || !state.setup.is_empty() {
+        return None;
+    }
+    let (_, (x, y)) = OPENING_BOOK.iter().find(|(line, _)| {
+        line.len() == state.moves.len()
+            && line.iter().zip(&state.moves).all(|(&(x, y), mv)| *mv == Move::Place(Coord::new(x, y)))
+    })?;
+    let coord = Coord::new(*x, *y);
+    state.board[coord.to_index(state.board_size)].is_none().then_some(Move::Place(coord))
+}
+
+/// Plays from the opening book while it has a reply, then as `inner`
+struct BookPlayer<'a> {
+    inner: Box<dyn PlayerBackend + 'a>,
+}
+
+impl PlayerBackend for BookPlayer<'_> {
+    fn next_move(&mut self, pos: &GameState, time_left: Duration) -> Move {
+        book_move(pos).unwrap_or_else(|| self.inner.next_move(pos, time_left))
+    }
+}
+
+/// The opponent on `rung` for the game seeded with `game_seed`.
+///
+/// `policy` returns one logit per point plus one for pass, as for
+/// [`crate::engine::PolicyPlayer`]; the random rung never calls it.
+pub fn opponent<'a, F>(rung: &Rung, game_seed: u64, policy: F) -> Box<dyn PlayerBackend + 'a>
+where
+    F: FnMut(&GameState) -> Vec<f32> + 'a,
+{
+    let player: Box<dyn PlayerBackend + 'a> = match rung.strength {
+        Strength::Random => Box::new(RandomPlayer::new(game_seed)),
+        Strength::Policy { temperature } => Box::new(SampledPolicyPlayer::new(policy, temperature, game_seed)),
+        Strength::Search { candidates, playouts } => Box::new(SearchPlayer::new(policy, candidates, playouts, game_seed)),
+    };
+    if rung.opening_book {
+        Box::new(BookPlayer { inner: player })
+    } else {
+        player
+    }
+}
+
+/// Winner of a finished ladder game.
+///
+/// A game both players passed is scored by area with the usual komi,
+/// settling dead stones with random playouts. `None` for a game still on
+/// or a tie.
+pub fn winner(state: &GameState) -> Option<Color> {
+    winner_with_komi(state, standard_komi(state.board_size))
+}
+
+/// Winner of a finished game against a ladder opponent played with
+/// `komi`, e.g. one started from an edited position
+pub fn winner_with_komi(state: &GameState, komi: f32) -> Option<Color> {
+    match state.end_reason()? {
+        // The player to move after a resignation is the one who didn't resign
+        EndReason::Resignation => Some(state.current_player),
+        EndReason::DoublePass => {
+            let score = expected_score(&ownership(state, RESULT_PLAYOUTS), komi);
+            if score > 0.0 {
+                Some(Color::Black)
+            } else if score < 0.0 {
+                Some(Color::White)
+            } else {
+                None
+            }
+        }
+        EndReason::Timeout | EndReason::Forfeit(_) => None,
+    }
+}
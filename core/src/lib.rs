@@ -4,21 +4,44 @@
 //!
 //! This crate provides the core game functionality including:
 //! - Go board representation and manipulation
+//! - Bitset positions that clone cheaply for search
 //! - Game rules and validation
 //! - SGF (Smart Game Format) parsing and generation
 //! - CBOR serialization helpers for game state
+//! - Seeded randomness, see [`rng`]
+//!
+//! Features: `std` (OS services), `cbor` and `archive` (filesystem game
+//! archive) are on by default. With `default-features = false` the board,
+//! rules, scoring and coordinates build for `wasm32-unknown-unknown`.
 
 #![deny(unsafe_code)]
 #![deny(clippy::all)]
 
 pub mod board;
+pub mod coords;
 pub mod rules;
 pub mod sgf;
 pub mod cbor;
 pub mod engine;
 pub mod value_labeller;
 pub mod scoring;
+pub mod position;
+pub mod policy;
+pub mod analysis;
+pub mod territory;
+pub mod rng;
+#[cfg(feature = "archive")]
 pub mod archiver;
+#[cfg(feature = "std")]
+pub mod p2pgo_dirs;
+#[cfg(feature = "std")]
+pub mod governor;
+pub mod puzzles;
+pub mod ladder;
+pub mod teaching;
+pub mod settings;
+pub mod game_clock;
+pub mod phase;
 
 use serde::{Serialize, Deserialize};
 use thiserror::Error;
@@ -99,6 +122,7 @@ pub enum Move {
 
 /// Represents the current state of a Go game
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "cbor::migrate::GameStateRepr", try_from = "cbor::migrate::GameStateRepr")]
 pub struct GameState {
     /// The size of the board (typically 9, 13, or 19)
     pub board_size: u8,
@@ -112,6 +136,14 @@ pub struct GameState {
     pub pass_count: u8,
     /// Captured stones count for each player
     pub captures: (u16, u16), // (Black captures, White captures)
+    /// Stones placed before the first move, e.g. in the board editor
+    pub setup: Vec<(Coord, Color)>,
+    /// Time control and clocks, when the game was timed
+    pub timing: game_clock::GameTiming,
+    /// Rule variations moves are checked against
+    pub rule_config: rules::RuleConfig,
+    /// Reused by rule checks so moves don't allocate
+    scratch: rules::ScratchBuffers,
 }
 
 impl GameState {
@@ -125,19 +157,78 @@ impl GameState {
             moves: Vec::new(),
             pass_count: 0,
             captures: (0, 0),
+            setup: Vec::new(),
+            timing: game_clock::GameTiming::default(),
+            rule_config: rules::RuleConfig::default(),
+            scratch: rules::ScratchBuffers::default(),
+        }
+    }
+    
+    /// Start a game from a set-up position.
+    ///
+    /// Stones are placed without legality checks, but every group must keep
+    /// a liberty or play could not start from the position.
+    pub fn from_setup(board_size: u8, stones: &[(Coord, Color)], to_move: Color) -> Result<Self, GameError> {
+        let mut state = Self::new(board_size);
+        for &(coord, color) in stones {
+            if !coord.is_valid(board_size) {
+                return Err(GameError::InvalidCoordinate);
+            }
+            let idx = coord.to_index(board_size);
+            if state.board[idx].is_some() {
+                return Err(GameError::OccupiedPosition);
+            }
+            state.board[idx] = Some(color);
         }
+        
+        for &(coord, _) in stones {
+            if rules::group_liberties(board_size, &state.board, coord, &state.scratch) == 0 {
+                return Err(GameError::InvalidMove(format!(
+                    "group at {} has no liberties",
+                    coord.display_label(board_size)
+                )));
+            }
+        }
+        
+        state.setup = stones.to_vec();
+        state.current_player = to_move;
+        Ok(state)
+    }
+    
+    /// The player who made, or is to make, the first move.
+    ///
+    /// Black unless the game started from a setup position.
+    pub fn first_player(&self) -> Color {
+        if self.setup.is_empty() {
+            return Color::Black;
+        }
+        // Walk back through the alternating moves
+        self.moves.iter().fold(self.current_player, |player, _| player.opposite())
+    }
+    
+    /// The position before the first move, with any setup stones
+    pub fn initial_position(&self) -> GameState {
+        let mut state = Self::new(self.board_size);
+        for &(coord, color) in &self.setup {
+            if coord.is_valid(self.board_size) {
+                state.board[coord.to_index(self.board_size)] = Some(color);
+            }
+        }
+        state.setup = self.setup.clone();
+        state.current_player = self.first_player();
+        state.rule_config = self.rule_config;
+        state
     }
     
     /// Apply a move to the game state
     pub fn apply_move(&mut self, mv: Move) -> Result<(), GameError> {
-        // TODO: implement ko / suicide checks
         match mv {
             Move::Place(coord) => {
                 if !coord.is_valid(self.board_size) {
                     return Err(GameError::InvalidCoordinate);
                 }
                 
-                let idx = (coord.y as usize) * (self.board_size as usize) + (coord.x as usize);
+                let idx = coord.to_index(self.board_size);
                 if self.board[idx].is_some() {
                     return Err(GameError::OccupiedPosition);
                 }
@@ -145,9 +236,28 @@ impl GameState {
                 // Place the stone
                 self.board[idx] = Some(self.current_player);
                 self.pass_count = 0;
+                
+                // Take opponent groups it left without liberties
+                let count = rules::remove_captures(self.board_size, &mut self.board, coord, &self.scratch) as u16;
+                // then its own group, if the rules allow suicide
+                let lost = if self.rule_config.suicide_allowed {
+                    rules::remove_suicide(self.board_size, &mut self.board, coord, &self.scratch) as u16
+                } else {
+                    0
+                };
+                match self.current_player {
+                    Color::Black => {
+                        self.captures.0 = self.captures.0.saturating_add(count);
+                        self.captures.1 = self.captures.1.saturating_add(lost);
+                    }
+                    Color::White => {
+                        self.captures.1 = self.captures.1.saturating_add(count);
+                        self.captures.0 = self.captures.0.saturating_add(lost);
+                    }
+                }
             },
             Move::Pass => {
-                self.pass_count += 1;
+                self.pass_count = self.pass_count.saturating_add(1);
             },
             Move::Resign => {
                 // Nothing to do here, game ends
@@ -163,6 +273,110 @@ impl GameState {
         Ok(())
     }
     
+    /// Take back the last `count` moves, returning them oldest first.
+    ///
+    /// The position is replayed from the start, so captures and passes
+    /// are restored as they were.
+    pub fn undo(&mut self, count: usize) -> Result<Vec<Move>, GameError> {
+        if count == 0 || count > self.moves.len() {
+            return Err(GameError::InvalidMove(format!(
+                "can't take back {} of {} moves",
+                count,
+                self.moves.len()
+            )));
+        }
+        let kept = self.moves.len() - count;
+        let mut replayed = self.initial_position();
+        for mv in &self.moves[..kept] {
+            replayed.apply_move(mv.clone())?;
+        }
+        let taken_back = self.moves.split_off(kept);
+        replayed.timing = std::mem::take(&mut self.timing);
+        replayed.timing.truncate(kept);
+        *self = replayed;
+        Ok(taken_back)
+    }
+
+    /// Apply `mv` and return the events it causes: `MoveMade`, then
+    /// `GameEnded` if the move ended the game
+    pub fn apply_move_events(&mut self, mv: Move) -> Result<Vec<GameEvent>, GameError> {
+        let by = self.current_player;
+        self.apply_move(mv.clone())?;
+        let mut events = vec![GameEvent::MoveMade { mv, by }];
+        events.extend(self.game_ended_event());
+        Ok(events)
+    }
+    
+    /// How the game ended, if it is over
+    pub fn end_reason(&self) -> Option<EndReason> {
+        match self.moves.last() {
+            Some(Move::Resign) => Some(EndReason::Resignation),
+            _ if self.pass_count >= 2 => Some(EndReason::DoublePass),
+            _ => None,
+        }
+    }
+    
+    /// The unscored `GameEnded` event for a finished game.
+    ///
+    /// A resignation names the winner; a double-pass game still has to be
+    /// scored, see [`scoring::game_ended_event`].
+    pub fn game_ended_event(&self) -> Option<GameEvent> {
+        let reason = self.end_reason()?;
+        // The player to move after a resignation is the one who didn't resign
+        let winner = (reason == EndReason::Resignation).then_some(self.current_player);
+        Some(GameEvent::GameEnded { winner, score_diff: 0.0, reason, scores: None })
+    }
+    
+    /// Check whether the player to move may play `mv`.
+    ///
+    /// `previous` is the position before the last move and is used for ko.
+    pub fn check_move(&self, mv: &Move, previous: &GameState) -> Result<(), GameError> {
+        if self.is_game_over() {
+            return Err(GameError::InvalidMove("game is over".to_string()));
+        }
+        match mv {
+            Move::Place(coord) => {
+                rules::RuleValidator::for_stones(self.board_size, &self.board, &previous.board, &self.scratch)
+                    .with_config(self.rule_config)
+                    .check_move(*coord, self.current_player)
+            }
+            Move::Pass | Move::Resign => Ok(()),
+        }
+    }
+
+    /// The position before the last move, replayed from the start;
+    /// the position to pass to [`GameState::check_move`]
+    pub fn previous_position(&self) -> GameState {
+        let mut previous = self.initial_position();
+        for mv in &self.moves[..self.moves.len().saturating_sub(1)] {
+            let _ = previous.apply_move(mv.clone());
+        }
+        previous
+    }
+
+    /// Points the player to move may play, ko retakes and suicide the rules
+    /// don't allow left out
+    pub fn legal_moves(&self) -> Vec<Coord> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+        self.legal_moves_after(&self.previous_position())
+    }
+
+    /// [`GameState::legal_moves`] with the position before the last move
+    /// already at hand
+    pub(crate) fn legal_moves_after(&self, previous: &GameState) -> Vec<Coord> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+        let size = self.board_size;
+        (0..size)
+            .flat_map(|y| (0..size).map(move |x| Coord::new(x, y)))
+            .filter(|&coord| self.board[coord.to_index(size)].is_none())
+            .filter(|&coord| self.check_move(&Move::Place(coord), previous).is_ok())
+            .collect()
+    }
+
     /// Check if the game is over
     pub fn is_game_over(&self) -> bool {
         // Game ends after two consecutive passes or resignation
@@ -185,6 +399,29 @@ impl GameState {
     }
 }
 
+/// How a game ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndReason {
+    /// Both players passed; the game is scored
+    DoublePass,
+    Resignation,
+    /// A player ran out of time
+    Timeout,
+    /// A player was forfeited, e.g. for not responding; the detail is for display
+    Forfeit(String),
+}
+
+impl std::fmt::Display for EndReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndReason::DoublePass => write!(f, "both players passed"),
+            EndReason::Resignation => write!(f, "resignation"),
+            EndReason::Timeout => write!(f, "timeout"),
+            EndReason::Forfeit(detail) => write!(f, "forfeit ({})", detail),
+        }
+    }
+}
+
 /// Game events emitted during play
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GameEvent {
@@ -204,19 +441,17 @@ pub enum GameEvent {
         /// The player who lost the stones
         player: Color,
     },
-    /// The game has ended (backward compatibility)
+    /// The game has ended
     GameEnded {
-        /// The winner of the game (if any)
+        /// The winner of the game, if known yet
         winner: Option<Color>,
-        /// The score difference
+        /// The winner's margin; 0 when the game was not scored
         score_diff: f32,
-    },
-    /// The game is finished with final score
-    GameFinished {
-        /// Black player score
-        black_score: f32,
-        /// White player score
-        white_score: f32,
+        /// How the game ended
+        reason: EndReason,
+        /// Black and white scores, once the game has been scored
+        #[serde(default)]
+        scores: Option<(f32, f32)>,
     },
     /// A chat message was sent
     ChatMessage {
@@ -225,6 +460,55 @@ pub enum GameEvent {
         /// The message content
         message: String,
     },
+    /// The teacher annotated the position after `move_index` moves
+    Annotated {
+        move_index: usize,
+        annotation: teaching::TeachingAnnotation,
+    },
+    /// A peer said whether it is looking at the board; never where
+    Presence {
+        /// The peer's node ID
+        peer: String,
+        /// The peer's pointer is over the board
+        considering: bool,
+        /// Last time the peer did anything in the game, unix seconds on our clock
+        last_active: u64,
+    },
+    /// The game clocks as the channel keeping them last saw them
+    Clock {
+        snapshot: game_clock::ClockSnapshot,
+    },
+    /// A player used up a byo-yomi period
+    ByoYomiPeriodUsed {
+        player: Color,
+        /// Periods left, the current one included; none when out of time
+        periods_left: u32,
+    },
+    /// The opponent asked to take back its last `moves` moves
+    UndoRequested {
+        /// The asking peer's node ID
+        peer: String,
+        moves: u8,
+    },
+    /// The opponent answered our undo request
+    UndoAnswered {
+        accepted: bool,
+    },
+    /// The last `moves` moves were taken back by agreement
+    MovesUndone {
+        moves: u8,
+    },
+    /// The game channel moved the game on to `phase`
+    PhaseChanged {
+        phase: phase::GamePhase,
+    },
+    /// A peer misbehaved, e.g. flooded the game with moves
+    PeerWarning {
+        /// The offending peer's node ID
+        peer: String,
+        /// What happened, for display
+        reason: String,
+    },
 }
 
 /// Errors that can occur during game play
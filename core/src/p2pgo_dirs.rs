@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Where p2pgo keeps its files on each platform.
+//!
+//! Config, data, cache and logs live in the platform's usual directories
+//! under `p2pgo/`, or all under one root given with `--data-dir`. Older
+//! builds wrote next to the working directory; [`migrate_legacy`] moves
+//! those files over once.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use anyhow::{Context, Result};
+
+/// Directory p2pgo's files are kept in, inside each platform directory
+pub const APP_DIR: &str = "p2pgo";
+
+/// Written to the data directory once legacy files have been moved
+pub const MIGRATED_MARKER: &str = ".legacy_migrated";
+
+/// Root set with `--data-dir`, overriding the platform directories
+static ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Keep everything under `root` instead of the platform directories
+pub fn set_root(root: impl Into<PathBuf>) {
+    *ROOT.write().unwrap_or_else(|e| e.into_inner()) = Some(root.into());
+}
+
+/// Root set with [`set_root`], if any
+pub fn root() -> Option<PathBuf> {
+    ROOT.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Directories p2pgo keeps its files in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    /// Settings the user chose
+    pub config: PathBuf,
+    /// Games, keys, ledgers and training data
+    pub data: PathBuf,
+    /// Anything that can be rebuilt
+    pub cache: PathBuf,
+    pub logs: PathBuf,
+}
+
+impl Dirs {
+    /// The root set with `--data-dir`, otherwise the platform directories
+    pub fn current() -> Result<Self> {
+        match root() {
+            Some(root) => Ok(Self::under(root)),
+            None => Self::platform(),
+        }
+    }
+
+    /// Everything under `root`
+    pub fn under(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            config: root.clone(),
+            data: root.clone(),
+            cache: root.join("cache"),
+            logs: root.join("logs"),
+        }
+    }
+
+    /// The platform's directories
+    ///
+    /// ~/Library/Application Support/p2pgo/ on macOS, logging to
+    /// ~/Library/Logs/p2pgo/; ~/.config/p2pgo/ and ~/.local/share/p2pgo/ on
+    /// Linux; %APPDATA%\p2pgo\ on Windows.
+    pub fn platform() -> Result<Self> {
+        let data = dirs::data_dir()
+            .ok_or_else(|| anyhow::anyhow!("No data directory on this platform"))?
+            .join(APP_DIR);
+        let config = dirs::config_dir().map_or_else(|| data.clone(), |dir| dir.join(APP_DIR));
+        let cache = dirs::cache_dir().map_or_else(|| data.join("cache"), |dir| dir.join(APP_DIR));
+        let logs = match (std::env::consts::OS, dirs::home_dir()) {
+            ("macos", Some(home)) => home.join("Library").join("Logs").join(APP_DIR),
+            _ => data.join("logs"),
+        };
+        Ok(Self { config, data, cache, logs })
+    }
+
+    /// Finished games, see [`crate::archiver`]
+    pub fn archive(&self) -> PathBuf {
+        self.data.join("finished")
+    }
+}
+
+/// A file moved out of the legacy layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moved {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Where files used to be kept, relative to the working directory, and
+/// where they belong now
+fn legacy_layout(dirs: &Dirs) -> [(&'static str, PathBuf); 3] {
+    [
+        ("finished_games", dirs.archive()),
+        ("ui_config.json", dirs.config.join("ui_config.json")),
+        ("logs", dirs.logs.clone()),
+    ]
+}
+
+/// Move files older builds kept under `legacy` into `dirs`
+///
+/// Runs once per data directory. A file already present at its new place
+/// is left where it was rather than overwritten.
+pub fn migrate_legacy(legacy: &Path, dirs: &Dirs) -> Result<Vec<Moved>> {
+    let marker = dirs.data.join(MIGRATED_MARKER);
+    if marker.exists() {
+        return Ok(Vec::new());
+    }
+    let mut moved = Vec::new();
+    for (name, to) in legacy_layout(dirs) {
+        let from = legacy.join(name);
+        // Both layouts may be the same place, as with `--data-dir .`
+        if !from.exists() || same_file(&from, &to) {
+            continue;
+        }
+        move_tree(&from, &to, &mut moved)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))?;
+    }
+    for file in &moved {
+        tracing::info!("Moved {} to {}", file.from.display(), file.to.display());
+    }
+    fs::create_dir_all(&dirs.data).context("Failed to create data directory")?;
+    fs::write(&marker, b"").context("Failed to mark legacy files migrated")?;
+    Ok(moved)
+}
+
+/// Move the [`Dirs::current`] legacy files out of the working directory,
+/// logging rather than failing
+pub fn migrate_current() {
+    let result = Dirs::current().and_then(|dirs| migrate_legacy(Path::new("."), &dirs));
+    match result {
+        Ok(moved) if !moved.is_empty() => {
+            tracing::info!("Moved {} files from the working directory", moved.len());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to migrate legacy files: {:#}", e),
+    }
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
+}
+
+/// Move `from` to `to`, merging into directories already there
+fn move_tree(from: &Path, to: &Path, moved: &mut Vec<Moved>) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            move_tree(&entry.path(), &to.join(entry.file_name()), moved)?;
+        }
+        // Fails if anything was left behind, which is kept
+        let _ = fs::remove_dir(from);
+        return Ok(());
+    }
+    if to.exists() {
+        tracing::warn!("Keeping {}: {} already exists", from.display(), to.display());
+        return Ok(());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    move_file(from, to)?;
+    moved.push(Moved { from: from.to_path_buf(), to: to.to_path_buf() });
+    Ok(())
+}
+
+/// Rename, or copy then remove across filesystems
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
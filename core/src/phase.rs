@@ -0,0 +1,152 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Where a game stands, from waiting for an opponent to its result.
+//!
+//! A game channel owns its game's [`GamePhase`] and only does what the
+//! phase allows; [`GamePhase::allowed`] is the table. Phases change on
+//! `GameEnded` events, see [`GamePhase::after`], and on the channel
+//! seating an opponent or settling the score.
+
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::{Color, EndReason, GameEvent, GameState};
+
+/// How a game ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// `None` for a draw or a result not known to us
+    pub winner: Option<Color>,
+    pub reason: EndReason,
+    /// The winner's margin; 0 when the game was not scored
+    pub score_diff: f32,
+}
+
+impl GameResult {
+    /// The result a `GameEnded` event announces
+    pub fn from_event(event: &GameEvent) -> Option<Self> {
+        match event {
+            GameEvent::GameEnded { winner, score_diff, reason, .. } => Some(Self {
+                winner: *winner,
+                reason: reason.clone(),
+                score_diff: *score_diff,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// What a game channel may be asked to do, by us or by a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameAction {
+    /// Play a move, resignations and synced moves included
+    Move,
+    /// Ask for or answer an undo
+    Undo,
+    /// Run or send the game clock
+    Clock,
+    /// Annotate the position as the teacher
+    Annotate,
+    /// Say whether we are looking at the board
+    Presence,
+    Chat,
+    /// Settle the score of a game both players passed in
+    Score,
+    /// End the game other than by a move, e.g. by forfeit
+    End,
+}
+
+impl GameAction {
+    pub const ALL: [GameAction; 8] = [
+        GameAction::Move,
+        GameAction::Undo,
+        GameAction::Clock,
+        GameAction::Annotate,
+        GameAction::Presence,
+        GameAction::Chat,
+        GameAction::Score,
+        GameAction::End,
+    ];
+}
+
+impl fmt::Display for GameAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GameAction::Move => "play a move",
+            GameAction::Undo => "undo",
+            GameAction::Clock => "run the clock",
+            GameAction::Annotate => "annotate",
+            GameAction::Presence => "share presence",
+            GameAction::Chat => "chat",
+            GameAction::Score => "settle the score",
+            GameAction::End => "end the game",
+        })
+    }
+}
+
+/// Where a game stands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GamePhase {
+    /// The host waits for someone to take the opponent's seat
+    AwaitingOpponent,
+    /// Moves are being played
+    Active,
+    /// Both players passed; the score is yet to be agreed
+    Scoring,
+    Finished { result: GameResult },
+}
+
+impl GamePhase {
+    /// The phase of a game in `state` whose players are both seated
+    pub fn of(state: &GameState) -> Self {
+        match state.game_ended_event() {
+            None => GamePhase::Active,
+            Some(event) => GamePhase::Active.after(&event).unwrap_or(GamePhase::Active),
+        }
+    }
+
+    /// What may be done in the phase
+    pub fn allowed(&self) -> &'static [GameAction] {
+        use GameAction::*;
+        match self {
+            GamePhase::AwaitingOpponent => &[Annotate, Presence, Chat, End],
+            GamePhase::Active => &[Move, Undo, Clock, Annotate, Presence, Chat, End],
+            GamePhase::Scoring => &[Annotate, Presence, Chat, Score, End],
+            GamePhase::Finished { .. } => &[Annotate, Chat],
+        }
+    }
+
+    pub fn allows(&self, action: GameAction) -> bool {
+        self.allowed().contains(&action)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, GamePhase::Finished { .. })
+    }
+
+    /// The phase `event` moves the game on to, if it changes it
+    ///
+    /// A double pass announced unscored starts scoring; any other end,
+    /// scored or not, finishes the game. Nothing leaves a finished game.
+    pub fn after(&self, event: &GameEvent) -> Option<GamePhase> {
+        let result = GameResult::from_event(event)?;
+        let scoring = matches!(event, GameEvent::GameEnded { reason: EndReason::DoublePass, scores: None, .. });
+        let next = match self {
+            GamePhase::Finished { .. } => return None,
+            GamePhase::Scoring if scoring => return None,
+            _ if scoring => GamePhase::Scoring,
+            _ => GamePhase::Finished { result },
+        };
+        Some(next)
+    }
+}
+
+impl fmt::Display for GamePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GamePhase::AwaitingOpponent => "waiting for an opponent",
+            GamePhase::Active => "in play",
+            GamePhase::Scoring => "being scored",
+            GamePhase::Finished { .. } => "over",
+        })
+    }
+}
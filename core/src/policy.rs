@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Layout of policy vectors shared by the models, training samples and
+//! engines: one entry per point, row by row, then one for passing.
+
+use crate::{Coord, Move};
+
+/// Entries in a policy vector for a `board_size` board
+pub fn policy_len(board_size: u8) -> usize {
+    pass_index(board_size) + 1
+}
+
+/// Index of the pass entry, right after the last point
+pub fn pass_index(board_size: u8) -> usize {
+    board_size as usize * board_size as usize
+}
+
+/// Where `mv` sits in the policy vector; resigning has no entry
+pub fn move_index(mv: &Move, board_size: u8) -> Option<usize> {
+    match mv {
+        Move::Place(coord) if coord.is_valid(board_size) => {
+            Some(coord.y as usize * board_size as usize + coord.x as usize)
+        }
+        Move::Place(_) | Move::Resign => None,
+        Move::Pass => Some(pass_index(board_size)),
+    }
+}
+
+/// The move for entry `index` of the policy vector
+pub fn index_move(index: usize, board_size: u8) -> Option<Move> {
+    let size = board_size as usize;
+    match index.cmp(&pass_index(board_size)) {
+        std::cmp::Ordering::Less => Some(Move::Place(Coord::new((index % size) as u8, (index / size) as u8))),
+        std::cmp::Ordering::Equal => Some(Move::Pass),
+        std::cmp::Ordering::Greater => None,
+    }
+}
+
+/// The per-point entries of `policy`, without pass
+pub fn point_logits(policy: &[f32], board_size: u8) -> &[f32] {
+    &policy[..policy.len().min(pass_index(board_size))]
+}
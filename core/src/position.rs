@@ -0,0 +1,702 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Compact game positions for search.
+//!
+//! A [`Position`] keeps the board as one bitset per color and shares its
+//! move history through [`Arc`]'d chunks, so cloning it copies under two
+//! hundred bytes no matter how long the game is. [`GameState`] stays the
+//! serialized form; convert with [`Position::from_dense`] and
+//! [`Position::to_dense`]. Moves follow the same rules as
+//! [`GameState::apply_move`] and [`GameState::check_move`].
+
+use std::fmt;
+use std::sync::Arc;
+use crate::game_clock::GameTiming;
+use crate::rules::RuleConfig;
+use crate::{Color, Coord, GameError, GameState, Move};
+
+/// Largest board a [`Position`] can hold
+pub const MAX_BOARD_SIZE: u8 = 19;
+
+const WORDS: usize = MAX_BOARD_SIZE as usize * MAX_BOARD_SIZE as usize / 64 + 1;
+
+/// One bit per point, row-major like [`Coord::to_index`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bits([u64; WORDS]);
+
+impl Bits {
+    const EMPTY: Bits = Bits([0; WORDS]);
+
+    fn single(idx: usize) -> Self {
+        let mut bits = Self::EMPTY;
+        bits.insert(idx);
+        bits
+    }
+
+    fn contains(&self, idx: usize) -> bool {
+        (self.0[idx / 64] >> (idx % 64)) & 1 == 1
+    }
+
+    fn insert(&mut self, idx: usize) {
+        self.0[idx / 64] |= 1 << (idx % 64);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The set points, lowest first
+    fn iter(self) -> impl Iterator<Item = usize> {
+        self.0.into_iter().enumerate().flat_map(|(i, mut w)| {
+            std::iter::from_fn(move || {
+                (w != 0).then(|| {
+                    let bit = w.trailing_zeros() as usize;
+                    w &= w - 1;
+                    i * 64 + bit
+                })
+            })
+        })
+    }
+
+    fn and(self, other: Self) -> Self {
+        self.zip(other, |a, b| a & b)
+    }
+
+    fn or(self, other: Self) -> Self {
+        self.zip(other, |a, b| a | b)
+    }
+
+    fn and_not(self, other: Self) -> Self {
+        self.zip(other, |a, b| a & !b)
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).any(|(a, b)| a & b != 0)
+    }
+
+    fn zip(mut self, other: Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        for (a, &b) in self.0.iter_mut().zip(&other.0) {
+            *a = f(*a, b);
+        }
+        self
+    }
+
+    /// Move every bit `n` places towards higher indices, `0 < n < 64`
+    fn shl(self, n: u32) -> Self {
+        let mut out = Self::EMPTY;
+        let mut carry = 0;
+        for (o, &w) in out.0.iter_mut().zip(&self.0) {
+            *o = (w << n) | carry;
+            carry = w >> (64 - n);
+        }
+        out
+    }
+
+    /// Move every bit `n` places towards lower indices, `0 < n < 64`
+    fn shr(self, n: u32) -> Self {
+        let mut out = Self::EMPTY;
+        let mut carry = 0;
+        for (o, &w) in out.0.iter_mut().zip(&self.0).rev() {
+            *o = (w >> n) | carry;
+            carry = w << (64 - n);
+        }
+        out
+    }
+}
+
+/// Masks for moving bitsets around a board of one size
+struct Geometry {
+    size: u8,
+    points: Bits,
+    /// Points not in the first column, where an eastward shift may land
+    not_west: Bits,
+    /// Points not in the last column, where a westward shift may land
+    not_east: Bits,
+}
+
+/// Geometry for every board size, indexed by size
+static GEOMETRIES: [Geometry; MAX_BOARD_SIZE as usize + 1] = {
+    const NONE: Geometry = Geometry::new(0);
+    let mut all = [NONE; MAX_BOARD_SIZE as usize + 1];
+    let mut size = 1;
+    while size <= MAX_BOARD_SIZE {
+        all[size as usize] = Geometry::new(size);
+        size += 1;
+    }
+    all
+};
+
+impl Geometry {
+    const fn new(size: u8) -> Self {
+        let mut points = [0; WORDS];
+        let mut not_west = [0; WORDS];
+        let mut not_east = [0; WORDS];
+        let mut idx = 0;
+        while idx < size as usize * size as usize {
+            let x = idx % size as usize;
+            let bit = 1 << (idx % 64);
+            points[idx / 64] |= bit;
+            if x > 0 {
+                not_west[idx / 64] |= bit;
+            }
+            if x + 1 < size as usize {
+                not_east[idx / 64] |= bit;
+            }
+            idx += 1;
+        }
+        Self {
+            size,
+            points: Bits(points),
+            not_west: Bits(not_west),
+            not_east: Bits(not_east),
+        }
+    }
+
+    /// Points orthogonally adjacent to any point of `bits`
+    fn neighbours(&self, bits: Bits) -> Bits {
+        let size = self.size as u32;
+        bits.shl(1)
+            .and(self.not_west)
+            .or(bits.shr(1).and(self.not_east))
+            .or(bits.shl(size))
+            .or(bits.shr(size))
+            .and(self.points)
+    }
+
+    /// Points orthogonally adjacent to `idx`, with `idx` itself standing in
+    /// for those off the board; callers look at points next to a stone, so
+    /// it is never taken for an empty point or another stone
+    fn adjacent(&self, idx: usize) -> [usize; 4] {
+        // The column masks spare dividing by the board size
+        let size = self.size as usize;
+        [
+            if self.not_west.contains(idx) { idx - 1 } else { idx },
+            if self.not_east.contains(idx) { idx + 1 } else { idx },
+            if idx >= size { idx - size } else { idx },
+            if idx + size < size * size { idx + size } else { idx },
+        ]
+    }
+
+    /// The chain of `stones` through `idx`, if it has no liberty, i.e. no
+    /// point outside `occupied` next to it; stops as soon as it finds one
+    fn dead_chain(&self, idx: usize, stones: Bits, occupied: Bits) -> Option<Bits> {
+        // Most stones have a liberty of their own
+        if self.adjacent(idx).iter().any(|&point| !occupied.contains(point)) {
+            return None;
+        }
+        let empty = self.points.and_not(occupied);
+        let mut chain = Bits::single(idx);
+        loop {
+            let around = self.neighbours(chain);
+            if around.intersects(&empty) {
+                return None;
+            }
+            let grown = chain.or(around.and(stones));
+            if grown == chain {
+                return Some(chain);
+            }
+            chain = grown;
+        }
+    }
+}
+
+/// Moves kept inline before they spill into a shared chunk
+const TAIL: usize = 16;
+
+const PASS: u16 = u16::MAX;
+const RESIGN: u16 = u16::MAX - 1;
+
+fn encode(mv: &Move) -> u16 {
+    match mv {
+        Move::Place(coord) => (coord.y as u16) << 8 | coord.x as u16,
+        Move::Pass => PASS,
+        Move::Resign => RESIGN,
+    }
+}
+
+fn decode(code: u16) -> Move {
+    match code {
+        PASS => Move::Pass,
+        RESIGN => Move::Resign,
+        _ => Move::Place(Coord::new((code & 0xff) as u8, (code >> 8) as u8)),
+    }
+}
+
+/// Move history shared between positions of one game tree.
+///
+/// The latest moves live inline and every [`TAIL`] moves they move into an
+/// [`Arc`]'d chunk, so most moves neither allocate nor copy the game.
+#[derive(Clone)]
+struct History {
+    chunks: Option<Arc<Chunk>>,
+    tail: [u16; TAIL],
+    tail_len: u8,
+    len: u32,
+}
+
+struct Chunk {
+    moves: [u16; TAIL],
+    prev: Option<Arc<Chunk>>,
+}
+
+impl Drop for Chunk {
+    // Unlink iteratively so dropping a long history cannot overflow the stack
+    fn drop(&mut self) {
+        let mut prev = self.prev.take();
+        // Shared tails, the common case in a search tree, are left alone
+        while let Some(chunk) = prev.filter(|chunk| Arc::strong_count(chunk) == 1) {
+            match Arc::try_unwrap(chunk) {
+                Ok(mut chunk) => prev = chunk.prev.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+impl History {
+    const EMPTY: History = History {
+        chunks: None,
+        tail: [0; TAIL],
+        tail_len: 0,
+        len: 0,
+    };
+
+    fn push(&self, mv: &Move) -> Self {
+        if self.tail_len as usize == TAIL {
+            return self.spill(mv);
+        }
+        let mut tail = self.tail;
+        tail[self.tail_len as usize] = encode(mv);
+        Self { chunks: self.chunks.clone(), tail, tail_len: self.tail_len + 1, len: self.len + 1 }
+    }
+
+    /// [`History::push`] once the tail is full
+    #[cold]
+    fn spill(&self, mv: &Move) -> Self {
+        let chunk = Chunk { moves: self.tail, prev: self.chunks.clone() };
+        let mut tail = [0; TAIL];
+        tail[0] = encode(mv);
+        Self { chunks: Some(Arc::new(chunk)), tail, tail_len: 1, len: self.len + 1 }
+    }
+
+    fn last(&self) -> Option<Move> {
+        self.tail[..self.tail_len as usize].last().map(|&code| decode(code))
+    }
+
+    fn to_vec(&self) -> Vec<Move> {
+        let mut chunks = Vec::new();
+        let mut chunk = self.chunks.as_deref();
+        while let Some(c) = chunk {
+            chunks.push(&c.moves);
+            chunk = c.prev.as_deref();
+        }
+        chunks
+            .into_iter()
+            .rev()
+            .flatten()
+            .chain(&self.tail[..self.tail_len as usize])
+            .map(|&code| decode(code))
+            .collect()
+    }
+}
+
+impl fmt::Debug for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.to_vec()).finish()
+    }
+}
+
+/// Parts of a [`GameState`] a search never changes, carried over to
+/// [`Position::to_dense`]
+#[derive(Debug, Default)]
+struct Extras {
+    setup: Vec<(Coord, Color)>,
+    timing: GameTiming,
+}
+
+/// A single-stone capture, the only kind a ko can retake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Ko {
+    /// Where the capturing stone was placed
+    placed: u16,
+    /// The point it emptied
+    taken: u16,
+}
+
+/// A game position that is cheap to clone and extend.
+///
+/// Moves return a new position sharing this one's history, so a search can
+/// branch from any node without copying the game.
+#[derive(Debug, Clone)]
+pub struct Position {
+    board_size: u8,
+    /// Black and white stones
+    stones: [Bits; 2],
+    /// The last move, if it took a single stone
+    ko: Option<Ko>,
+    current_player: Color,
+    pass_count: u8,
+    captures: (u16, u16),
+    rules: RuleConfig,
+    history: History,
+    extras: Option<Arc<Extras>>,
+}
+
+fn slot(color: Color) -> usize {
+    match color {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
+
+impl Position {
+    /// An empty board with Black to play.
+    ///
+    /// Panics if `board_size` is 0 or above [`MAX_BOARD_SIZE`].
+    pub fn new(board_size: u8) -> Self {
+        assert!(
+            (1..=MAX_BOARD_SIZE).contains(&board_size),
+            "board size {} outside 1..={}",
+            board_size,
+            MAX_BOARD_SIZE
+        );
+        Self {
+            board_size,
+            stones: [Bits::EMPTY; 2],
+            ko: None,
+            current_player: Color::Black,
+            pass_count: 0,
+            captures: (0, 0),
+            rules: RuleConfig::default(),
+            history: History::EMPTY,
+            extras: None,
+        }
+    }
+
+    /// The position with moves checked under `rules`
+    pub fn with_rules(self, rules: RuleConfig) -> Self {
+        Self { rules, ..self }
+    }
+
+    /// Convert from the serialized form.
+    ///
+    /// The moves are replayed once from the setup to find whether the last
+    /// one opened a ko; the board, captures and player to move are taken
+    /// from `state` as they are.
+    pub fn from_dense(state: &GameState) -> Result<Self, GameError> {
+        let size = state.board_size;
+        if !(1..=MAX_BOARD_SIZE).contains(&size) || state.board.len() != size as usize * size as usize {
+            return Err(GameError::InvalidCoordinate);
+        }
+        let mut position = Self::new(size).with_rules(state.rule_config);
+        for &(coord, color) in &state.setup {
+            if coord.is_valid(size) {
+                position.stones[slot(color)].insert(coord.to_index(size));
+            }
+        }
+        position.current_player = state.first_player();
+
+        let mut replayed = true;
+        for mv in &state.moves {
+            if matches!(mv, Move::Place(coord) if !coord.is_valid(size)) {
+                return Err(GameError::InvalidCoordinate);
+            }
+            position = match position.apply_move(mv.clone()) {
+                Ok(next) => next,
+                Err(_) => {
+                    replayed = false;
+                    Self { history: position.history.push(mv), ..position }
+                }
+            };
+        }
+
+        let mut stones = [Bits::EMPTY; 2];
+        for (idx, stone) in state.board.iter().enumerate() {
+            if let Some(color) = stone {
+                stones[slot(*color)].insert(idx);
+            }
+        }
+        // A board edited apart from its moves has no known ko
+        if !replayed || stones != position.stones {
+            position.ko = None;
+        }
+        position.stones = stones;
+        position.current_player = state.current_player;
+        position.pass_count = state.pass_count;
+        position.captures = state.captures;
+        if !state.setup.is_empty() || state.timing != GameTiming::default() {
+            position.extras = Some(Arc::new(Extras { setup: state.setup.clone(), timing: state.timing.clone() }));
+        }
+        Ok(position)
+    }
+
+    /// Convert to the serialized form
+    pub fn to_dense(&self) -> GameState {
+        let size = self.board_size;
+        let extras = self.extras.as_deref();
+        GameState {
+            board: (0..size as usize * size as usize).map(|idx| self.stone_at(idx)).collect(),
+            current_player: self.current_player,
+            moves: self.history.to_vec(),
+            pass_count: self.pass_count,
+            captures: self.captures,
+            setup: self.setup().to_vec(),
+            timing: extras.map(|extras| extras.timing.clone()).unwrap_or_default(),
+            rule_config: self.rules,
+            ..GameState::new(size)
+        }
+    }
+
+    pub fn board_size(&self) -> u8 {
+        self.board_size
+    }
+
+    pub fn current_player(&self) -> Color {
+        self.current_player
+    }
+
+    pub fn pass_count(&self) -> u8 {
+        self.pass_count
+    }
+
+    /// Stones captured by (Black, White)
+    pub fn captures(&self) -> (u16, u16) {
+        self.captures
+    }
+
+    pub fn setup(&self) -> &[(Coord, Color)] {
+        self.extras.as_deref().map_or(&[], |extras| &extras.setup)
+    }
+
+    pub fn rules(&self) -> RuleConfig {
+        self.rules
+    }
+
+    /// The stone at `coord`, if any
+    pub fn get(&self, coord: Coord) -> Option<Color> {
+        if !coord.is_valid(self.board_size) {
+            return None;
+        }
+        self.stone_at(coord.to_index(self.board_size))
+    }
+
+    fn stone_at(&self, idx: usize) -> Option<Color> {
+        if self.stones[0].contains(idx) {
+            Some(Color::Black)
+        } else if self.stones[1].contains(idx) {
+            Some(Color::White)
+        } else {
+            None
+        }
+    }
+
+    fn geometry(&self) -> &'static Geometry {
+        &GEOMETRIES[self.board_size as usize]
+    }
+
+    pub fn count_stones_for(&self, color: Color) -> usize {
+        self.stones[slot(color)].len()
+    }
+
+    pub fn move_count(&self) -> usize {
+        self.history.len as usize
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last()
+    }
+
+    /// The moves played so far, oldest first
+    pub fn moves(&self) -> Vec<Move> {
+        self.history.to_vec()
+    }
+
+    /// Same as [`GameState::is_game_over`]
+    pub fn is_game_over(&self) -> bool {
+        self.pass_count >= 2 || matches!(self.last_move(), Some(Move::Resign))
+    }
+
+    /// Apply a move without rule checks, like [`GameState::apply_move`]
+    pub fn apply_move(&self, mv: Move) -> Result<Position, GameError> {
+        match mv {
+            Move::Place(coord) => {
+                let idx = self.empty_point(coord)?;
+                Ok(self.place(coord, idx, self.captured_by(idx)))
+            }
+            Move::Pass | Move::Resign => Ok(self.without_placing(mv)),
+        }
+    }
+
+    /// Play a move under the rules, removing captured stones
+    pub fn play(&self, mv: Move) -> Result<Position, GameError> {
+        if self.is_game_over() {
+            return Err(GameError::InvalidMove("game is over".to_string()));
+        }
+        match mv {
+            Move::Place(coord) => {
+                let idx = self.empty_point(coord)?;
+                let captured = self.check_place(idx, coord)?;
+                Ok(self.place(coord, idx, captured))
+            }
+            Move::Pass | Move::Resign => Ok(self.without_placing(mv)),
+        }
+    }
+
+    /// Check whether the player to move may play `mv`, like
+    /// [`GameState::check_move`]
+    pub fn check_move(&self, mv: &Move) -> Result<(), GameError> {
+        if self.is_game_over() {
+            return Err(GameError::InvalidMove("game is over".to_string()));
+        }
+        match mv {
+            Move::Place(coord) => self.check_place(self.empty_point(*coord)?, *coord).map(|_| ()),
+            Move::Pass | Move::Resign => Ok(()),
+        }
+    }
+
+    /// Points the player to move may play
+    pub fn legal_moves(&self) -> Vec<Coord> {
+        if self.is_game_over() {
+            return Vec::new();
+        }
+        let empty = self.geometry().points.and_not(self.stones[0].or(self.stones[1]));
+        empty
+            .iter()
+            .filter_map(|idx| {
+                let coord = Coord::from_index(idx, self.board_size)?;
+                self.check_place(idx, coord).ok().map(|_| coord)
+            })
+            .collect()
+    }
+
+    /// The index of `coord` if it is an empty point on the board
+    fn empty_point(&self, coord: Coord) -> Result<usize, GameError> {
+        if !coord.is_valid(self.board_size) {
+            return Err(GameError::InvalidCoordinate);
+        }
+        let idx = coord.to_index(self.board_size);
+        if self.stone_at(idx).is_some() {
+            return Err(GameError::OccupiedPosition);
+        }
+        Ok(idx)
+    }
+
+    /// Opponent stones a stone of the player to move at the empty `idx`
+    /// would take
+    fn captured_by(&self, idx: usize) -> Bits {
+        let geometry = self.geometry();
+        let own = self.stones[slot(self.current_player)];
+        let opponent = self.stones[slot(self.current_player.opposite())];
+        let mut occupied = own.or(opponent);
+        occupied.insert(idx);
+        let mut captured = Bits::EMPTY;
+        for stone in geometry.adjacent(idx) {
+            if !opponent.contains(stone) || captured.contains(stone) {
+                continue;
+            }
+            if let Some(chain) = geometry.dead_chain(stone, opponent, occupied) {
+                captured = captured.or(chain);
+            }
+        }
+        captured
+    }
+
+    /// The stones taken by playing at the empty `idx`, or why it is
+    /// illegal; the same rules as [`crate::rules::RuleValidator::check_move`]
+    fn check_place(&self, idx: usize, coord: Coord) -> Result<Bits, GameError> {
+        let geometry = self.geometry();
+        let captured = self.captured_by(idx);
+        if captured.is_empty() {
+            let mut occupied = self.stones[0].or(self.stones[1]);
+            occupied.insert(idx);
+            let mut own = self.stones[slot(self.current_player)];
+            own.insert(idx);
+            // A move without captures may not leave its own group without
+            // liberties, unless the rules let a group of several stones
+            // take itself off the board
+            if let Some(chain) = geometry.dead_chain(idx, own, occupied) {
+                if !self.rules.suicide_allowed || chain == Bits::single(idx) {
+                    return Err(GameError::SelfCapture);
+                }
+            }
+        }
+
+        // Retaking at once would restore the position before the last move
+        if let Some(ko) = self.ko {
+            if ko.taken as usize == idx && captured == Bits::single(ko.placed as usize) {
+                tracing::debug!("Ko violation detected at {:?}", coord);
+                return Err(GameError::KoViolation);
+            }
+        }
+        Ok(captured)
+    }
+
+    /// The position after the player to move puts a stone on `coord`, the
+    /// empty `idx`, taking `captured` and then its own group if that is left
+    /// without liberties and the rules allow it
+    fn place(&self, coord: Coord, idx: usize, captured: Bits) -> Position {
+        let geometry = self.geometry();
+        let mover = slot(self.current_player);
+        let opponent = 1 - mover;
+        let mut stones = self.stones;
+        stones[mover].insert(idx);
+        let count = captured.len() as u16;
+        if count > 0 {
+            stones[opponent] = stones[opponent].and_not(captured);
+        }
+
+        let mut lost = 0;
+        if count == 0 && self.rules.suicide_allowed {
+            if let Some(chain) = geometry.dead_chain(idx, stones[mover], stones[0].or(stones[1])) {
+                stones[mover] = stones[mover].and_not(chain);
+                lost = chain.len() as u16;
+            }
+        }
+
+        let mut captures = self.captures;
+        match self.current_player {
+            Color::Black => {
+                captures.0 = captures.0.saturating_add(count);
+                captures.1 = captures.1.saturating_add(lost);
+            }
+            Color::White => {
+                captures.1 = captures.1.saturating_add(count);
+                captures.0 = captures.0.saturating_add(lost);
+            }
+        }
+        let ko = match count {
+            1 => captured.iter().next().map(|taken| Ko { placed: idx as u16, taken: taken as u16 }),
+            _ => None,
+        };
+        Self {
+            stones,
+            ko,
+            current_player: self.current_player.opposite(),
+            pass_count: 0,
+            captures,
+            history: self.history.push(&Move::Place(coord)),
+            extras: self.extras.clone(),
+            ..*self
+        }
+    }
+
+    /// The position after a pass or resignation
+    fn without_placing(&self, mv: Move) -> Position {
+        let pass_count = match mv {
+            Move::Pass => self.pass_count.saturating_add(1),
+            _ => self.pass_count,
+        };
+        Self {
+            ko: None,
+            current_player: self.current_player.opposite(),
+            pass_count,
+            history: self.history.push(&mv),
+            extras: self.extras.clone(),
+            ..*self
+        }
+    }
+}
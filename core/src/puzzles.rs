@@ -0,0 +1,244 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Capture puzzles extracted from finished games.
+//!
+//! A game is replayed with captures applied. Each move that captures stones
+//! becomes a puzzle: the position a few moves earlier where the same player
+//! started attacking the captured group, and the moves that followed as the
+//! solution. Attempts are checked against that line; a different move is
+//! also accepted if it captures the whole target group at once.
+
+use crate::board::Board;
+use crate::rules::RuleValidator;
+use crate::{Color, Coord, GameError, GameState, Move};
+use serde::{Serialize, Deserialize};
+
+/// Longest solution, in moves by both players
+const MAX_LINE: usize = 5;
+
+/// A line only reaches back to positions where the target had at most this
+/// many liberties
+const MAX_START_LIBERTIES: usize = 3;
+
+/// What the solution achieves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PuzzleKind {
+    /// Capture the target group
+    Capture,
+    /// Sacrifice a stone, then capture the group that took it
+    Snapback,
+}
+
+/// A position to solve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Puzzle {
+    /// Index of the game move the puzzle starts at
+    pub move_number: usize,
+    /// The starting position, with `to_play` as the current player
+    pub position: GameState,
+    pub to_play: Color,
+    pub kind: PuzzleKind,
+    /// The line played in the game, alternating from `to_play`
+    pub solution: Vec<Coord>,
+    /// Opponent stones the solution captures
+    pub target: Vec<Coord>,
+}
+
+/// Result of checking an attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleOutcome {
+    /// The target has been captured
+    Solved,
+    /// Right so far; the opponent answers with `reply`, if anything
+    Correct { reply: Option<Coord> },
+    /// The attempt fails
+    Wrong { reason: String },
+}
+
+impl Puzzle {
+    /// Instruction shown with the puzzle
+    pub fn prompt(&self) -> String {
+        let player = match self.to_play {
+            Color::Black => "Black",
+            Color::White => "White",
+        };
+        format!("{} to play and capture", player)
+    }
+
+    /// Check the player's moves so far, `line`, against the puzzle.
+    ///
+    /// `line` holds only the player's moves; the opponent's replies come
+    /// from the solution.
+    pub fn check(&self, line: &[Coord]) -> PuzzleOutcome {
+        let mut replay = Replay::new(board_of(&self.position));
+        let mut reply = None;
+        for (i, &mv) in line.iter().enumerate() {
+            if let Err(e) = replay.play(mv, self.to_play) {
+                return PuzzleOutcome::Wrong { reason: format!("Illegal move: {}", e) };
+            }
+            if self.target.iter().all(|&c| replay.board.get(c).is_none()) {
+                return PuzzleOutcome::Solved;
+            }
+            if self.solution.get(2 * i) != Some(&mv) {
+                return PuzzleOutcome::Wrong { reason: "That does not capture".to_string() };
+            }
+            reply = self.solution.get(2 * i + 1).copied();
+            if let Some(reply) = reply {
+                if replay.play(reply, self.to_play.opposite()).is_err() {
+                    return PuzzleOutcome::Wrong { reason: "The game record cannot continue".to_string() };
+                }
+            }
+        }
+        PuzzleOutcome::Correct { reply }
+    }
+
+    /// The position after `line` and the opponent's replies.
+    ///
+    /// Stops at the first move that cannot be played.
+    pub fn position_after(&self, line: &[Coord]) -> GameState {
+        let mut replay = Replay::new(board_of(&self.position));
+        let mut to_play = self.to_play;
+        'line: for (i, &mv) in line.iter().enumerate() {
+            let reply = self.solution.get(2 * i + 1).filter(|_| self.solution.get(2 * i) == Some(&mv));
+            for (coord, color) in std::iter::once((mv, self.to_play)).chain(reply.map(|&r| (r, self.to_play.opposite()))) {
+                if replay.play(coord, color).is_err() {
+                    break 'line;
+                }
+                to_play = color.opposite();
+            }
+        }
+        state_from_board(&replay.board, to_play)
+    }
+}
+
+/// Extract capture puzzles from a finished game's moves
+pub fn extract_from_game(game: &GameState) -> Vec<Puzzle> {
+    // Position before each move, and what each move captured
+    let mut boards = Vec::with_capacity(game.moves.len());
+    let mut captures: Vec<Vec<Coord>> = Vec::with_capacity(game.moves.len());
+    let mut replay = Replay::new(board_of(&game.initial_position()));
+    let mut color = game.first_player();
+    for mv in &game.moves {
+        boards.push(replay.board.clone());
+        match mv {
+            Move::Place(coord) => match replay.play(*coord, color) {
+                Ok(captured) => captures.push(captured),
+                Err(e) => {
+                    tracing::debug!("Stopping puzzle extraction at illegal move {:?}: {}", coord, e);
+                    boards.pop();
+                    break;
+                }
+            },
+            Move::Pass => captures.push(Vec::new()),
+            Move::Resign => {
+                boards.pop();
+                break;
+            }
+        }
+        color = color.opposite();
+    }
+
+    let place = |k: usize| match game.moves.get(k) {
+        Some(Move::Place(coord)) => Some(*coord),
+        _ => None,
+    };
+    let mut puzzles: Vec<Puzzle> = Vec::new();
+    for (k, captured) in captures.iter().enumerate() {
+        let Some(coord) = place(k) else { continue };
+        if captured.is_empty() {
+            continue;
+        }
+        // A capture that is immediately taken back was the mistake, not the tesuji
+        if captures.get(k + 1).is_some_and(|next| next.contains(&coord)) {
+            continue;
+        }
+
+        // Walk back while both players kept playing against the captured group
+        let near_target = |c: Coord| captured.iter().any(|&t| t.x.abs_diff(c.x) + t.y.abs_diff(c.y) == 1);
+        let mut start = k;
+        while start >= 2 && k - (start - 2) < MAX_LINE {
+            let (Some(attack), Some(answer)) = (place(start - 2), place(start - 1)) else { break };
+            if !near_target(attack) || !near_target(answer) {
+                break;
+            }
+            if target_liberties(&boards[start - 2], captured) > MAX_START_LIBERTIES {
+                break;
+            }
+            start -= 2;
+        }
+        if puzzles.last().is_some_and(|p| p.move_number == start) {
+            continue;
+        }
+
+        let to_play = if start % 2 == 0 { Color::Black } else { Color::White };
+        let target: Vec<Coord> = captured
+            .iter()
+            .copied()
+            .filter(|&c| boards[start].get(c) == Some(to_play.opposite()))
+            .collect();
+        if target.is_empty() {
+            continue;
+        }
+        // The opponent capturing inside the line means a sacrifice
+        let kind = if (start + 1..k).step_by(2).any(|i| !captures[i].is_empty()) {
+            PuzzleKind::Snapback
+        } else {
+            PuzzleKind::Capture
+        };
+        puzzles.push(Puzzle {
+            move_number: start,
+            position: state_from_board(&boards[start], to_play),
+            to_play,
+            kind,
+            solution: (start..=k).filter_map(place).collect(),
+            target,
+        });
+    }
+    puzzles
+}
+
+/// A board that applies captures, with the previous position for ko
+struct Replay {
+    board: Board,
+    previous: Board,
+}
+
+impl Replay {
+    fn new(board: Board) -> Self {
+        Self { previous: board.clone(), board }
+    }
+
+    /// Play a stone and return what it captured
+    fn play(&mut self, coord: Coord, color: Color) -> Result<Vec<Coord>, GameError> {
+        RuleValidator::new(&self.board, &self.previous).check_move(coord, color)?;
+        let before = self.board.clone();
+        self.board.place(coord, color);
+        let captured = RuleValidator::new(&self.board, &before).find_captures(coord);
+        for &c in &captured {
+            self.board.remove(c);
+        }
+        self.previous = before;
+        Ok(captured)
+    }
+}
+
+/// Liberties of the `group` stones present on `board`
+fn target_liberties(board: &Board, group: &[Coord]) -> usize {
+    let present: Vec<Coord> = group.iter().copied().filter(|&c| board.get(c).is_some()).collect();
+    RuleValidator::liberties(board, &present)
+}
+
+fn board_of(state: &GameState) -> Board {
+    Board::from_stones(state.board_size, state.board.clone())
+}
+
+fn state_from_board(board: &Board, to_play: Color) -> GameState {
+    let mut state = GameState::new(board.size());
+    for (idx, stone) in state.board.iter_mut().enumerate() {
+        if let Some(coord) = Coord::from_index(idx, board.size()) {
+            *stone = board.get(coord);
+        }
+    }
+    state.current_player = to_play;
+    state
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Seeded randomness for playouts, engines and training.
+//!
+//! Nothing in the library draws from an unseeded source; randomness comes
+//! from a [`Rng`] or a seed handed in by the caller. Seeds form a hierarchy:
+//! a run starts from one seed, each game's seed comes from
+//! [`game_seed`]`(run, game)` and each move draws from
+//! [`move_stream`]`(game, move_number)`. Any game, or any single move of
+//! one, can then be replayed without replaying what came before it.
+
+pub use rand_core::{RngCore, SeedableRng};
+use rand_core::{impls, Error};
+
+/// xorshift64; playouts need speed, not quality
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+/// The generator for `seed`
+pub fn from_seed(seed: u64) -> Rng {
+    // xorshift never leaves zero
+    Rng { state: seed | 1 }
+}
+
+/// Seed of the `game`th game of the run seeded with `run_seed`
+pub fn game_seed(run_seed: u64, game: u64) -> u64 {
+    mix(run_seed ^ mix(game.wrapping_add(1)))
+}
+
+/// Stream for the move after `move_number` moves of the game seeded with
+/// `game_seed`
+pub fn move_stream(game_seed: u64, move_number: usize) -> Rng {
+    from_seed(mix(game_seed ^ mix(move_number as u64 ^ 0x6d6f_7665)))
+}
+
+/// A number in `0..n`, or 0 if `n` is 0
+pub fn below(rng: &mut impl RngCore, n: usize) -> usize {
+    (rng.next_u64() % n.max(1) as u64) as usize
+}
+
+/// SplitMix64's output function, spreading nearby seeds far apart
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Rng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        from_seed(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        from_seed(seed)
+    }
+}
@@ -3,118 +3,286 @@
 //! Game rules and validation logic
 
 use crate::{board::Board, Color, Coord, GameError};
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Rule variations a game is played with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RuleConfig {
+    /// A move may take its own group of two or more stones off the board,
+    /// as under New Zealand and Tromp-Taylor rules; the opponent is credited
+    /// with the stones. Single-stone suicide stays illegal.
+    pub suicide_allowed: bool,
+}
+
+/// Buffers for group and liberty searches, kept between moves so checking
+/// and applying them doesn't allocate once they've grown to the board.
+///
+/// Cloning gives empty buffers; they are scratch space, not state.
+#[derive(Default)]
+pub struct ScratchBuffers {
+    buffers: Mutex<Buffers>,
+}
+
+impl ScratchBuffers {
+    /// Run `f` with the buffers, or with fresh ones while another thread
+    /// is using them
+    fn with<R>(&self, f: impl FnOnce(&mut Buffers) -> R) -> R {
+        match self.buffers.try_lock() {
+            Ok(mut buffers) => f(&mut buffers),
+            Err(_) => f(&mut Buffers::default()),
+        }
+    }
+}
+
+impl Clone for ScratchBuffers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for ScratchBuffers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScratchBuffers").finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct Buffers {
+    /// Points reached by the searches of the current check, one bit each
+    visited: Vec<u64>,
+    /// Liberties of the group searched last, one bit each
+    liberties: Vec<u64>,
+    /// Points still to search
+    stack: Vec<usize>,
+    /// Stones of the group searched last
+    group: Vec<usize>,
+    /// Stones the move captures
+    captured: Vec<usize>,
+}
+
+impl Buffers {
+    /// Clear everything for a check on a board of `cells` points
+    fn reset(&mut self, cells: usize) {
+        let words = cells.div_ceil(64);
+        self.visited.clear();
+        self.visited.resize(words, 0);
+        self.liberties.clear();
+        self.liberties.resize(words, 0);
+        self.captured.clear();
+    }
+
+    /// Collect the group at `start` into `group` and return its liberties.
+    ///
+    /// Stones are marked visited, so a later search in the same check can
+    /// tell a group was already seen.
+    fn flood(&mut self, view: View<'_>, start: usize) -> usize {
+        let Some(color) = view.get(start) else {
+            return 0;
+        };
+        self.group.clear();
+        self.liberties.iter_mut().for_each(|word| *word = 0);
+        let mut liberties = 0;
+        set_bit(&mut self.visited, start);
+        self.stack.push(start);
+        while let Some(current) = self.stack.pop() {
+            self.group.push(current);
+            for neighbor in view.neighbors(current) {
+                match view.get(neighbor) {
+                    None => {
+                        if set_bit(&mut self.liberties, neighbor) {
+                            liberties += 1;
+                        }
+                    }
+                    Some(c) if c == color => {
+                        if set_bit(&mut self.visited, neighbor) {
+                            self.stack.push(neighbor);
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        liberties
+    }
+
+    /// Collect into `captured` the opponent stones left without liberties
+    /// next to `last_move`
+    fn find_captures(&mut self, view: View<'_>, last_move: usize) {
+        self.reset(view.stones.len());
+        let Some(color) = view.get(last_move) else {
+            return;
+        };
+        for neighbor in view.neighbors(last_move) {
+            // Two neighbors can belong to the same group; search it once
+            if view.get(neighbor) != Some(color.opposite()) || get_bit(&self.visited, neighbor) {
+                continue;
+            }
+            if self.flood(view, neighbor) == 0 {
+                self.captured.extend_from_slice(&self.group);
+            }
+        }
+    }
+}
+
+/// Set bit `index`, returning whether it was clear
+fn set_bit(bits: &mut [u64], index: usize) -> bool {
+    let (word, mask) = (index / 64, 1u64 << (index % 64));
+    let was_clear = bits[word] & mask == 0;
+    bits[word] |= mask;
+    was_clear
+}
+
+fn get_bit(bits: &[u64], index: usize) -> bool {
+    bits[index / 64] & (1u64 << (index % 64)) != 0
+}
+
+/// A board's points, optionally with one stone placed on top
+#[derive(Clone, Copy)]
+struct View<'a> {
+    size: usize,
+    stones: &'a [Option<Color>],
+    placed: Option<(usize, Color)>,
+}
+
+impl<'a> View<'a> {
+    fn new(size: u8, stones: &'a [Option<Color>]) -> Self {
+        Self { size: size as usize, stones, placed: None }
+    }
+
+    fn with_stone(self, index: usize, color: Color) -> Self {
+        Self { placed: Some((index, color)), ..self }
+    }
+
+    fn get(&self, index: usize) -> Option<Color> {
+        match self.placed {
+            Some((placed, color)) if placed == index => Some(color),
+            _ => self.stones[index],
+        }
+    }
+
+    /// Neighbors up, down, left and right
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> {
+        let size = self.size;
+        let (x, y) = (index % size, index / size);
+        [
+            (y > 0).then(|| index - size),
+            (y + 1 < size).then(|| index + size),
+            (x > 0).then(|| index - 1),
+            (x + 1 < size).then(|| index + 1),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Scratch space a validator either owns or borrows from a game
+enum Scratch<'a> {
+    Owned(ScratchBuffers),
+    Borrowed(&'a ScratchBuffers),
+}
+
+impl Scratch<'_> {
+    fn get(&self) -> &ScratchBuffers {
+        match self {
+            Scratch::Owned(scratch) => scratch,
+            Scratch::Borrowed(scratch) => scratch,
+        }
+    }
+}
 
 /// Validates game rules for Go
 pub struct RuleValidator<'a> {
     /// The board being checked
-    board: &'a Board,
+    board: View<'a>,
     /// Last board state for ko rule checking
-    previous_board: &'a Board,
+    previous_board: &'a [Option<Color>],
+    scratch: Scratch<'a>,
+    config: RuleConfig,
 }
 
 impl<'a> RuleValidator<'a> {
     /// Create a new rules validator
     pub fn new(board: &'a Board, previous_board: &'a Board) -> Self {
         Self {
-            board,
-            previous_board,
+            board: View::new(board.size(), board.stones()),
+            previous_board: previous_board.stones(),
+            scratch: Scratch::Owned(ScratchBuffers::default()),
+            config: RuleConfig::default(),
         }
     }
-    
+
+    /// A validator for points laid out as in [`crate::GameState::board`],
+    /// searching with `scratch`
+    pub fn for_stones(
+        board_size: u8,
+        stones: &'a [Option<Color>],
+        previous: &'a [Option<Color>],
+        scratch: &'a ScratchBuffers,
+    ) -> Self {
+        Self {
+            board: View::new(board_size, stones),
+            previous_board: previous,
+            scratch: Scratch::Borrowed(scratch),
+            config: RuleConfig::default(),
+        }
+    }
+
+    /// Check moves under `config` rather than the default rules
+    pub fn with_config(self, config: RuleConfig) -> Self {
+        Self { config, ..self }
+    }
+
     /// Check if a move is valid
     pub fn check_move(&self, coord: Coord, color: Color) -> Result<(), GameError> {
         // Basic validation
-        if !coord.is_valid(self.board.size()) {
+        let size = self.board.size as u8;
+        if !coord.is_valid(size) {
             return Err(GameError::InvalidCoordinate);
         }
-        
-        if self.board.get(coord).is_some() {
+        let index = coord.to_index(size);
+        if self.board.get(index).is_some() {
             return Err(GameError::OccupiedPosition);
         }
-        
-        // Create a temporary board with the move applied
-        let mut temp_board = self.board.clone();
-        temp_board.place(coord, color);
-        
-        // Check for suicide
-        let group_coords = find_group(&temp_board, coord);
-        if Self::liberties(&temp_board, &group_coords) == 0 {
-            // If all neighboring groups of opponent stones have liberties, this is suicide
-            let opponent_color = color.opposite();
-            let mut will_capture = false;
-            
-            for neighbor in self.board.adjacent_coords(coord) {
-                if let Some(stone_color) = self.board.get(neighbor) {
-                    if stone_color == opponent_color {
-                        let neighbor_group = find_group(&temp_board, neighbor);
-                        if Self::liberties(&temp_board, &neighbor_group) == 0 {
-                            will_capture = true;
-                            break;
-                        }
-                    }
-                }
-            }
-            
-            if !will_capture {
+
+        // Look at the board with the move applied
+        let after = self.board.with_stone(index, color);
+        self.scratch.get().with(|buffers| {
+            buffers.find_captures(after, index);
+
+            // A move without captures may not leave its own group without
+            // liberties, unless the rules let a group of several stones take
+            // itself off the board
+            if buffers.captured.is_empty()
+                && buffers.flood(after, index) == 0
+                && (!self.config.suicide_allowed || buffers.group.len() == 1)
+            {
                 return Err(GameError::SelfCapture);
             }
-        }
-        
-        // Check for ko - compare with previous board
-        if self.previous_board.size() == temp_board.size() {
-            let mut captured_coords = Vec::new();
-            
-            // Find stones that would be captured
-            for neighbor in temp_board.adjacent_coords(coord) {
-                if let Some(stone_color) = temp_board.get(neighbor) {
-                    if stone_color == color.opposite() {
-                        let neighbor_group = find_group(&temp_board, neighbor);
-                        if Self::liberties(&temp_board, &neighbor_group) == 0 {
-                            captured_coords.extend(neighbor_group);
-                        }
-                    }
-                }
-            }
-            
+
             // Ko happens when: capturing exactly one stone AND the resulting board equals previous board
-            if captured_coords.len() == 1 {
-                // Create a board after captures
-                let mut after_capture = temp_board.clone();
-                for c in &captured_coords {
-                    after_capture.remove(*c);
-                }
-                
-                // Compare with previous board (ko detection)
-                let mut same_as_previous = true;
-                
-                for y in 0..self.board.size() {
-                    for x in 0..self.board.size() {
-                        let c = Coord::new(x, y);
-                        if after_capture.get(c) != self.previous_board.get(c) {
-                            same_as_previous = false;
-                            break;
-                        }
-                    }
-                    if !same_as_previous {
-                        break;
-                    }
-                }
-                
+            if buffers.captured.len() == 1 && self.previous_board.len() == self.board.stones.len() {
+                let captured = buffers.captured[0];
+                let same_as_previous = (0..self.previous_board.len()).all(|i| {
+                    let stone = if i == captured { None } else { after.get(i) };
+                    stone == self.previous_board[i]
+                });
                 if same_as_previous {
                     tracing::debug!("Ko violation detected at {:?}", coord);
                     return Err(GameError::KoViolation);
                 }
             }
-        }
-        
-        Ok(())
+
+            Ok(())
+        })
     }
-    
+
     /// Calculate the number of liberties for a group of stones
     pub fn liberties(board: &Board, group: &[Coord]) -> usize {
         let mut liberties_set = HashSet::new();
-        
+
         for &coord in group {
             for neighbor in board.adjacent_coords(coord) {
                 if board.get(neighbor).is_none() {
@@ -122,62 +290,55 @@ impl<'a> RuleValidator<'a> {
                 }
             }
         }
-        
+
         liberties_set.len()
     }
-    
+
     /// Find stones that would be captured after a move
     pub fn find_captures(&self, last_move: Coord) -> Vec<Coord> {
-        let mut captures = Vec::new();
-        let color = self.board.get(last_move).unwrap();
-        let opponent = color.opposite();
-        
-        // Check all adjacent groups for captures
-        for neighbor in self.board.adjacent_coords(last_move) {
-            if let Some(stone_color) = self.board.get(neighbor) {
-                if stone_color == opponent {
-                    let group = find_group(self.board, neighbor);
-                    if Self::liberties(self.board, &group) == 0 {
-                        captures.extend(group);
-                    }
-                }
-            }
+        let size = self.board.size as u8;
+        self.scratch.get().with(|buffers| {
+            buffers.find_captures(self.board, last_move.to_index(size));
+            buffers.captured.iter().filter_map(|&i| Coord::from_index(i, size)).collect()
+        })
+    }
+}
+
+/// Remove the opponent stones the stone at `last_move` left without
+/// liberties, returning how many were taken
+pub(crate) fn remove_captures(board_size: u8, stones: &mut [Option<Color>], last_move: Coord, scratch: &ScratchBuffers) -> usize {
+    scratch.with(|buffers| {
+        buffers.find_captures(View::new(board_size, stones), last_move.to_index(board_size));
+        for &i in &buffers.captured {
+            stones[i] = None;
         }
-        
-        captures
-    }
-}
-
-/// Find all stones in a group connected to the stone at coord
-fn find_group(board: &Board, coord: Coord) -> Vec<Coord> {
-    let target_color = match board.get(coord) {
-        Some(color) => color,
-        None => return Vec::new(),
-    };
-    
-    let mut group = Vec::new();
-    let mut visited = HashSet::new();
-    let mut queue = vec![coord];
-    
-    while let Some(current) = queue.pop() {
-        if visited.contains(&current) {
-            continue;
+        buffers.captured.len()
+    })
+}
+
+/// Remove the group at `last_move` if it has no liberties left, returning
+/// how many stones it had; a suicide where the rules allow it
+pub(crate) fn remove_suicide(board_size: u8, stones: &mut [Option<Color>], last_move: Coord, scratch: &ScratchBuffers) -> usize {
+    let index = last_move.to_index(board_size);
+    if stones[index].is_none() {
+        return 0;
+    }
+    scratch.with(|buffers| {
+        buffers.reset(stones.len());
+        if buffers.flood(View::new(board_size, stones), index) > 0 {
+            return 0;
         }
-        
-        visited.insert(current);
-        group.push(current);
-        
-        // Add adjacent stones of the same color
-        for neighbor in board.adjacent_coords(current) {
-            if let Some(color) = board.get(neighbor) {
-                if color == target_color && !visited.contains(&neighbor) {
-                    queue.push(neighbor);
-                }
-            }
+        for &i in &buffers.group {
+            stones[i] = None;
         }
-    }
-    
-    group
+        buffers.group.len()
+    })
 }
 
-
+/// Liberties of the group at `coord`, or 0 if the point is empty
+pub(crate) fn group_liberties(board_size: u8, stones: &[Option<Color>], coord: Coord, scratch: &ScratchBuffers) -> usize {
+    scratch.with(|buffers| {
+        buffers.reset(stones.len());
+        buffers.flood(View::new(board_size, stones), coord.to_index(board_size))
+    })
+}
@@ -1,5 +1,5 @@
-use crate::{Color, Coord, GameState};
-use crate::value_labeller::{ScoreProof, ScoringMethod};
+use crate::{Color, Coord, EndReason, GameEvent, GameState};
+use crate::value_labeller::{FinalPosition, ScoreProof, ScoringMethod};
 use std::collections::{HashSet, VecDeque};
 
 pub fn calculate_final_score(
@@ -11,7 +11,7 @@ pub fn calculate_final_score(
     // clone board & remove dead stones
     let size = game_state.board_size;
     let mut board = game_state.board.clone();
-    for c in dead_stones {
+    for c in dead_stones.iter().filter(|c| c.is_valid(size)) {
         let i = c.y as usize * size as usize + c.x as usize;
         board[i] = None;
     }
@@ -94,6 +94,7 @@ pub fn calculate_final_score(
                 captures_white: captures_w,
                 komi,
                 method: scoring_method,
+                final_position: Some(FinalPosition::new(game_state, dead_stones)),
             };
         }
     };
@@ -105,9 +106,40 @@ pub fn calculate_final_score(
         captures_white: captures_w,
         komi,
         method: scoring_method,
+        final_position: Some(FinalPosition::new(game_state, dead_stones)),
     }
 }
 
+/// The `GameEnded` event for a game scored as `proof`.
+///
+/// Scores are only given for territory counting; area proofs don't keep the
+/// stone counts they were made from.
+pub fn game_ended_event(proof: &ScoreProof) -> GameEvent {
+    let winner = match proof.final_score {
+        s if s > 0 => Some(Color::Black),
+        s if s < 0 => Some(Color::White),
+        _ => None,
+    };
+    let (reason, scores) = match proof.method {
+        ScoringMethod::Territory => (
+            EndReason::DoublePass,
+            Some((
+                proof.territory_black as f32 + proof.captures_black as f32,
+                proof.territory_white as f32 + proof.captures_white as f32 + proof.komi,
+            )),
+        ),
+        ScoringMethod::Area => (EndReason::DoublePass, None),
+        ScoringMethod::Resignation(_) => (EndReason::Resignation, None),
+        ScoringMethod::TimeOut(_) => (EndReason::Timeout, None),
+    };
+    let score_diff = match scores {
+        Some((black, white)) => (black - white).abs(),
+        None if matches!(reason, EndReason::DoublePass) => proof.final_score.unsigned_abs() as f32,
+        None => 0.0,
+    };
+    GameEvent::GameEnded { winner, score_diff, reason, scores }
+}
+
 /// BFS over empty points; returns (region coords, bordering stone colours)
 fn region_and_borders(
     board: &[Option<Color>],
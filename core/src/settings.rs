@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! What a game is played with, fixed by the host and handed to whoever
+//! joins so both sides build the same board.
+
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::{Color, Coord, GameState, Move};
+use crate::rules::RuleConfig;
+
+/// Komi games of this size are usually played with
+pub fn standard_komi(board_size: u8) -> f32 {
+    match board_size {
+        19 => 7.5,
+        13 => 6.5,
+        _ => 5.5,
+    }
+}
+
+/// Version of the rules and scoring this build plays by. Hosts advertise
+/// theirs; a game under a newer version may be scored differently here,
+/// so it can't be joined.
+pub const RULESET_VERSION: u8 = 2;
+
+/// The last [`RULESET_VERSION`] without [`RuleConfig`] variations, which
+/// builds of that version play as the default rules
+const PLAIN_RULESET_VERSION: u8 = 1;
+
+/// Undos each player may have granted in a game unless set otherwise
+pub const DEFAULT_UNDO_LIMIT: u8 = 3;
+
+fn default_undo_limit() -> u8 {
+    DEFAULT_UNDO_LIMIT
+}
+
+/// Rule set a game is scored under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Rules {
+    #[default]
+    Chinese,
+    Japanese,
+}
+
+/// Settings both players of a game share
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GameSettings {
+    pub board_size: u8,
+    pub komi: f32,
+    /// Black stones placed before the first move; 0 for an even game
+    pub handicap: u8,
+    pub rules: Rules,
+    /// Undos each player may have granted; peers play with the lower of
+    /// their limits
+    #[serde(default = "default_undo_limit")]
+    pub undo_limit: u8,
+    /// Rule variations moves are checked under
+    #[serde(default)]
+    pub rule_config: RuleConfig,
+}
+
+impl GameSettings {
+    /// An even game on a `board_size` board with its usual komi
+    pub fn standard(board_size: u8) -> Self {
+        Self {
+            board_size,
+            komi: standard_komi(board_size),
+            handicap: 0,
+            rules: Rules::default(),
+            undo_limit: DEFAULT_UNDO_LIMIT,
+            rule_config: RuleConfig::default(),
+        }
+    }
+
+    /// Oldest [`RULESET_VERSION`] that plays these settings, so builds from
+    /// before rule variations can still join games without any
+    pub fn ruleset_version(&self) -> u8 {
+        if self.rule_config == RuleConfig::default() {
+            PLAIN_RULESET_VERSION
+        } else {
+            RULESET_VERSION
+        }
+    }
+
+    /// Board these settings are played on under their rule variations,
+    /// with any handicap stones set up and White to move
+    pub fn new_game(&self) -> GameState {
+        let stones: Vec<_> = self.handicap_points().into_iter().map(|coord| (coord, Color::Black)).collect();
+        let mut game = if stones.is_empty() {
+            GameState::new(self.board_size)
+        } else {
+            GameState::from_setup(self.board_size, &stones, Color::White)
+                .expect("handicap points are distinct points on the board")
+        };
+        game.rule_config = self.rule_config;
+        game
+    }
+
+    /// Star points the handicap stones go on, in the usual order; none for
+    /// handicap 0 or 1, which only gives Black the first move
+    pub fn handicap_points(&self) -> Vec<Coord> {
+        let size = self.board_size;
+        if self.handicap < 2 || size < 7 {
+            return Vec::new();
+        }
+        let near = if size < 13 { 2 } else { 3 };
+        let (far, mid) = (size - 1 - near, size / 2);
+        let mut points = vec![
+            Coord::new(far, near),
+            Coord::new(near, far),
+            Coord::new(far, far),
+            Coord::new(near, near),
+            Coord::new(near, mid),
+            Coord::new(far, mid),
+            Coord::new(mid, near),
+            Coord::new(mid, far),
+        ];
+        // Past four stones the sides fill in pairs and an odd stone takes the centre
+        let handicap = self.handicap.min(9) as usize;
+        let (sides, centre) = if handicap <= 4 { (handicap, false) } else { (handicap & !1, handicap % 2 == 1) };
+        points.truncate(sides);
+        if centre {
+            points.push(Coord::new(mid, mid));
+        }
+        points
+    }
+
+    /// Komi White is given at the count. Chinese rules count stones, so
+    /// White also gets a point for each handicap stone Black started with.
+    pub fn scoring_komi(&self) -> f32 {
+        match self.rules {
+            Rules::Chinese if self.handicap >= 2 => self.komi + self.handicap as f32,
+            _ => self.komi,
+        }
+    }
+
+    /// Refuse a move that doesn't fit the board
+    pub fn check_move(&self, mv: &Move) -> Result<(), OffBoardMove> {
+        match mv {
+            Move::Place(coord) if !coord.is_valid(self.board_size) => {
+                Err(OffBoardMove { coord: *coord, board_size: self.board_size })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for GameSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{0}x{0}, komi {1}, {2:?} rules", self.board_size, self.komi, self.rules)?;
+        if self.handicap > 0 {
+            write!(f, ", handicap {}", self.handicap)?;
+        }
+        if self.undo_limit != DEFAULT_UNDO_LIMIT {
+            write!(f, ", {} undos", self.undo_limit)?;
+        }
+        if self.rule_config.suicide_allowed {
+            write!(f, ", suicide allowed")?;
+        }
+        Ok(())
+    }
+}
+
+/// A move outside the board both players agreed on, usually from a peer
+/// that set the game up at another size
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[error("Point ({}, {}) is off the game's {}x{} board", .coord.x, .coord.y, .board_size, .board_size)]
+pub struct OffBoardMove {
+    pub coord: Coord,
+    pub board_size: u8,
+}
@@ -4,8 +4,13 @@
 
 use anyhow::{Result, anyhow};
 use crate::{Color, Coord, GameState, Move};
+use crate::game_clock::{TimeSettings, TimingInfo};
+use crate::teaching::{Marker, TeachingLayer, TeachingOverlay};
 use std::collections::HashMap;
 
+/// Maximum nesting of variations accepted by the parser
+const MAX_VARIATION_DEPTH: usize = 64;
+
 /// Represents an SGF property
 #[derive(Debug, Clone)]
 struct SgfProperty {
@@ -32,16 +37,59 @@ struct SgfTree {
     variations: Vec<SgfTree>,
 }
 
+/// Game information for the root node of generated SGF
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SgfHeader {
+    /// GN, e.g. the archive ID
+    pub name: Option<String>,
+    /// DT, YYYY-MM-DD
+    pub date: Option<String>,
+    /// RE, e.g. `B+3.5` or `W+Resign`
+    pub result: Option<String>,
+    /// GC, e.g. who the game was played against when colours are unknown
+    pub comment: Option<String>,
+}
+
 /// SGF parser and generator
 pub struct SgfProcessor {
     /// The game state
     game_state: GameState,
+    /// Komi written to the KM property, if known
+    komi: Option<f32>,
+    /// Teacher's annotations, written as marks and comments
+    annotations: Option<TeachingLayer>,
+    /// Game information written to the root node
+    header: SgfHeader,
 }
 
 impl SgfProcessor {
     /// Create a new SGF processor for the given game state
     pub fn new(game_state: GameState) -> Self {
-        Self { game_state }
+        Self { game_state, komi: None, annotations: None, header: SgfHeader::default() }
+    }
+    
+    /// Record `komi` in generated SGF
+    pub fn with_komi(mut self, komi: f32) -> Self {
+        self.komi = Some(komi);
+        self
+    }
+    
+    /// Write a teacher's annotations on the nodes of their positions
+    pub fn with_annotations(mut self, annotations: TeachingLayer) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+    
+    /// Write `header` into the root node of generated SGF
+    pub fn with_header(mut self, header: SgfHeader) -> Self {
+        self.header = header;
+        self
+    }
+    
+    /// Game information of the root node: what [`Self::parse`] read, or
+    /// what [`Self::generate`] writes
+    pub fn header(&self) -> &SgfHeader {
+        &self.header
     }
     
     /// Parse an SGF string and return a game state
@@ -53,11 +101,16 @@ impl SgfProcessor {
     /// Parse SGF text into an SGF tree
     fn parse_sgf(&self, sgf_text: &str) -> Result<SgfTree> {
         let mut chars = sgf_text.chars().peekable();
-        self.parse_game_tree(&mut chars)
+        self.parse_game_tree(&mut chars, 0)
     }
     
     /// Parse a game tree
-    fn parse_game_tree(&self, chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<SgfTree> {
+    fn parse_game_tree(&self, chars: &mut std::iter::Peekable<std::str::Chars>, depth: usize) -> Result<SgfTree> {
+        // Untrusted input: bound recursion instead of overflowing the stack
+        if depth > MAX_VARIATION_DEPTH {
+            return Err(anyhow!("SGF variations nested deeper than {}", MAX_VARIATION_DEPTH));
+        }
+        
         // Skip leading whitespace
         self.skip_whitespace(chars);
         
@@ -82,7 +135,7 @@ impl SgfProcessor {
         
         // Parse variations
         while chars.peek() == Some(&'(') {
-            let subtree = self.parse_game_tree(chars)?;
+            let subtree = self.parse_game_tree(chars, depth + 1)?;
             variations.push(subtree);
             self.skip_whitespace(chars);
         }
@@ -211,19 +264,59 @@ impl SgfProcessor {
             }
         }
         
+        // Game information, each property optional
+        let text = |id: &str| props.get(id).and_then(|v| v.first()).cloned().filter(|v| !v.trim().is_empty());
+        self.header = SgfHeader { name: text("GN"), date: text("DT"), result: text("RE"), comment: text("GC") };
+        
         // Get board size
         let size = if let Some(sz) = props.get("SZ") {
-            sz[0].parse::<u8>().unwrap_or(19)
+            sz.first().and_then(|v| v.parse::<u8>().ok()).unwrap_or(19)
         } else {
             19 // Default board size
         };
         
+        // Setup stones and the player to move, e.g. from a board editor
+        let mut setup = Vec::new();
+        for (id, color) in [("AB", Color::Black), ("AW", Color::White)] {
+            for value in props.get(id).into_iter().flatten() {
+                setup.push((self.parse_sgf_coord(value, size)?, color));
+            }
+        }
+        let to_move = match props.get("PL").and_then(|v| v.first()).map(String::as_str) {
+            Some("W") => Color::White,
+            _ => Color::Black,
+        };
+        
         // Create a new game state
-        let mut game_state = GameState::new(size);
+        let mut game_state = GameState::from_setup(size, &setup, to_move)?;
+        
+        // Time control; overtime other than byo-yomi is left out
+        if let Some(main_secs) = text("TM").and_then(|v| parse_secs(&v)) {
+            let (periods, byo_yomi_secs) = text("OT").and_then(|v| TimeSettings::parse_overtime(&v)).unwrap_or((0, 0));
+            game_state.timing.settings = Some(TimeSettings { main_secs: main_secs / 1000, periods, byo_yomi_secs });
+        }
         
         // Process moves
         for node in tree.nodes.iter().skip(1) {  // Skip the root node
+            let played = game_state.moves.len();
+            let mover = game_state.current_player;
+            let mut timing = None::<TimingInfo>;
             for prop in &node.properties {
+                let value = prop.values.first().map(String::as_str).unwrap_or("");
+                // The mover's clock as they played
+                match (prop.id.as_str(), mover) {
+                    ("BL", Color::Black) | ("WL", Color::White) => {
+                        if let Some(ms) = parse_secs(value) {
+                            timing.get_or_insert(TimingInfo { time_left_ms: 0, periods: None }).time_left_ms = ms;
+                        }
+                    }
+                    ("OB", Color::Black) | ("OW", Color::White) => {
+                        if let Ok(periods) = value.trim().parse() {
+                            timing.get_or_insert(TimingInfo { time_left_ms: 0, periods: None }).periods = Some(periods);
+                        }
+                    }
+                    _ => (),
+                }
                 match prop.id.as_str() {
                     "B" => {
                         // Black's move
@@ -254,6 +347,9 @@ impl SgfProcessor {
                     _ => (), // Ignore other properties
                 }
             }
+            if let Some(timing) = timing.filter(|_| game_state.moves.len() > played) {
+                game_state.timing.record(played, timing);
+            }
         }
         
         Ok(game_state)
@@ -261,25 +357,8 @@ impl SgfProcessor {
     
     /// Parse an SGF coordinate like "ab" into a Coord
     fn parse_sgf_coord(&self, sgf_coord: &str, board_size: u8) -> Result<Coord> {
-        // Empty coordinate is a pass move, but we should handle it elsewhere
-        if sgf_coord.is_empty() {
-            // Default to a pass move
-            return Err(anyhow!("Empty coordinate is a pass move"));
-        }
-
-        if sgf_coord.len() < 2 {
-            return Err(anyhow!("Invalid SGF coordinate: too short"));
-        }
-        
-        let mut chars = sgf_coord.chars();
-        let x = chars.next().unwrap() as u8 - b'a';
-        let y = chars.next().unwrap() as u8 - b'a';
-        
-        if x >= board_size || y >= board_size {
-            return Err(anyhow!("SGF coordinate out of board bounds"));
-        }
-        
-        Ok(Coord::new(x, y))
+        Coord::from_sgf(sgf_coord, board_size)
+            .map_err(|_| anyhow!("Invalid SGF coordinate: {}", sgf_coord))
     }
     
     /// Generate an SGF string from the current game state
@@ -295,21 +374,56 @@ impl SgfProcessor {
         
         // Add player info if available
         sgf.push_str("AP[p2pgo]");
+        if let Some(komi) = self.komi {
+            sgf.push_str(&format!("KM[{}]", komi));
+        }
+        if let Some(settings) = self.game_state.timing.settings {
+            sgf.push_str(&format!("TM[{}]", settings.main_secs));
+            if let Some(overtime) = settings.overtime() {
+                sgf.push_str(&format!("OT[{}]", overtime));
+            }
+        }
+        let header = [
+            ("GN", &self.header.name),
+            ("DT", &self.header.date),
+            ("RE", &self.header.result),
+            ("GC", &self.header.comment),
+        ];
+        for (id, value) in header {
+            if let Some(value) = value {
+                sgf.push_str(&format!("{}[{}]", id, escape_text(value)));
+            }
+        }
+        
+        // Setup stones of a position built in the editor
+        for (id, color) in [("AB", Color::Black), ("AW", Color::White)] {
+            let points: String = self.game_state.setup.iter()
+                .filter(|(_, c)| *c == color)
+                .map(|(coord, _)| format!("[{}]", coord.to_sgf()))
+                .collect();
+            if !points.is_empty() {
+                sgf.push_str(id);
+                sgf.push_str(&points);
+            }
+        }
         
         // Add move sequences
-        let mut current_color = Color::Black; // Go starts with Black
+        let mut current_color = self.game_state.first_player();
+        if current_color == Color::White {
+            sgf.push_str("PL[W]");
+        }
+        
+        self.push_annotations(&mut sgf, 0, Vec::new());
         
-        for mv in &self.game_state.moves {
+        for (index, mv) in self.game_state.moves.iter().enumerate() {
             sgf.push(';');
             
+            let mut comments = Vec::new();
             match mv {
                 Move::Place(coord) => {
-                    let sgf_x = (b'a' + coord.x) as char;
-                    let sgf_y = (b'a' + coord.y) as char;
-                    
                     match current_color {
-                        Color::Black => sgf.push_str(&format!("B[{}{}]", sgf_x, sgf_y)),
-                        Color::White => sgf.push_str(&format!("W[{}{}]", sgf_x, sgf_y)),
+                        Color::Black => sgf.push_str(&format!("B[{}]", coord.to_sgf())),
+                        Color::White => sgf.push_str(&format!("W[{}]", coord.to_sgf())),
                     }
                 },
                 Move::Pass => {
@@ -321,11 +435,23 @@ impl SgfProcessor {
                 Move::Resign => {
                     // Resignation is typically handled with a RE property
                     match current_color {
-                        Color::Black => sgf.push_str("B[]C[Black resigns]RE[W+Resign]"),
-                        Color::White => sgf.push_str("W[]C[White resigns]RE[B+Resign]"),
+                        Color::Black => sgf.push_str("B[]RE[W+Resign]"),
+                        Color::White => sgf.push_str("W[]RE[B+Resign]"),
                     }
+                    comments.push(format!("{:?} resigns", current_color));
                 },
             }
+            if let Some(timing) = self.game_state.timing.at(index) {
+                let (left, periods) = match current_color {
+                    Color::Black => ("BL", "OB"),
+                    Color::White => ("WL", "OW"),
+                };
+                sgf.push_str(&format!("{}[{}]", left, format_secs(timing.time_left_ms)));
+                if let Some(count) = timing.periods {
+                    sgf.push_str(&format!("{}[{}]", periods, count));
+                }
+            }
+            self.push_annotations(&mut sgf, index + 1, comments);
             
             // Switch color for next move
             current_color = current_color.opposite();
@@ -336,6 +462,65 @@ impl SgfProcessor {
         
         sgf
     }
+    
+    /// Marks and the comment of the node after `move_index` moves; a node
+    /// takes a single C property, so `comments` are joined with the teacher's
+    fn push_annotations(&self, sgf: &mut String, move_index: usize, mut comments: Vec<String>) {
+        let overlay = self.annotations.as_ref().and_then(|layer| layer.overlay(move_index));
+        if let Some(overlay) = overlay {
+            push_overlay(sgf, overlay, self.game_state.board_size, &mut comments);
+        }
+        if !comments.is_empty() {
+            sgf.push_str(&format!("C[{}]", escape_text(&comments.join("\n"))));
+        }
+    }
+}
+
+/// Write `overlay` as TR and LB properties; demonstration stones are
+/// triangled and listed in `comments`, since SGF has no unofficial stones
+fn push_overlay(sgf: &mut String, overlay: &TeachingOverlay, board_size: u8, comments: &mut Vec<String>) {
+    let triangles: String = overlay.stones.iter().map(|(at, _)| *at)
+        .chain(overlay.marks.iter()
+            .filter(|(at, m)| *m == Marker::Triangle && overlay.stone_at(*at).is_none())
+            .map(|(at, _)| *at))
+        .map(|at| format!("[{}]", at.to_sgf()))
+        .collect();
+    if !triangles.is_empty() {
+        sgf.push_str("TR");
+        sgf.push_str(&triangles);
+    }
+    let labels: String = overlay.marks.iter()
+        .filter_map(|(at, marker)| match marker {
+            Marker::Label(text) => Some(format!("[{}:{}]", at.to_sgf(), escape_text(text).replace(':', "\\:"))),
+            Marker::Triangle => None,
+        })
+        .collect();
+    if !labels.is_empty() {
+        sgf.push_str("LB");
+        sgf.push_str(&labels);
+    }
+    if !overlay.stones.is_empty() {
+        let stones: Vec<String> = overlay.stones.iter()
+            .map(|(at, color)| format!("{:?} {}", color, at.display_label(board_size)))
+            .collect();
+        comments.push(format!("Demonstration: {}", stones.join(", ")));
+    }
+    comments.extend(overlay.comments.iter().cloned());
 }
 
+/// Escape text for an SGF property value
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(']', "\\]")
+}
 
+/// Milliseconds as SGF's real-valued seconds, e.g. `25.3`
+fn format_secs(ms: u64) -> String {
+    let text = format!("{}.{:03}", ms / 1000, ms % 1000);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Milliseconds from SGF's real-valued seconds
+fn parse_secs(text: &str) -> Option<u64> {
+    let secs: f64 = text.trim().parse().ok()?;
+    (secs.is_finite() && secs >= 0.0).then(|| (secs * 1000.0).round() as u64)
+}
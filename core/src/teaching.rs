@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Teaching annotations: demonstration stones, marks and comments a teacher
+//! places over a game without touching its move record.
+//!
+//! Annotations belong to the position after a number of moves. The layer
+//! keeps one [`TeachingOverlay`] per annotated position, so a lesson on move
+//! 12 stays there when the game moves on.
+
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use crate::{Color, Coord, GameError};
+
+/// A mark on a point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Marker {
+    Triangle,
+    /// A short text, usually a letter
+    Label(String),
+}
+
+/// One change a teacher makes to the overlay of a position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TeachingAnnotation {
+    /// Show a demonstration stone of either color
+    AddStone { at: Coord, color: Color },
+    /// Take a demonstration stone back
+    RemoveStone { at: Coord },
+    /// Mark a point, replacing any mark already there
+    Mark { at: Coord, marker: Marker },
+    /// Clear the mark on a point
+    Unmark { at: Coord },
+    /// A remark about the position
+    Comment(String),
+}
+
+impl TeachingAnnotation {
+    /// The point the annotation is about, if any
+    pub fn point(&self) -> Option<Coord> {
+        match self {
+            TeachingAnnotation::AddStone { at, .. }
+            | TeachingAnnotation::RemoveStone { at }
+            | TeachingAnnotation::Mark { at, .. }
+            | TeachingAnnotation::Unmark { at } => Some(*at),
+            TeachingAnnotation::Comment(_) => None,
+        }
+    }
+}
+
+/// Everything the teacher shows on one position
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeachingOverlay {
+    /// Demonstration stones, in the order they were placed
+    pub stones: Vec<(Coord, Color)>,
+    pub marks: Vec<(Coord, Marker)>,
+    pub comments: Vec<String>,
+}
+
+impl TeachingOverlay {
+    pub fn is_empty(&self) -> bool {
+        self.stones.is_empty() && self.marks.is_empty() && self.comments.is_empty()
+    }
+
+    /// The demonstration stone at `at`, if any
+    pub fn stone_at(&self, at: Coord) -> Option<Color> {
+        self.stones.iter().find(|(c, _)| *c == at).map(|(_, color)| *color)
+    }
+
+    /// The mark at `at`, if any
+    pub fn mark_at(&self, at: Coord) -> Option<&Marker> {
+        self.marks.iter().find(|(c, _)| *c == at).map(|(_, marker)| marker)
+    }
+
+    fn apply(&mut self, annotation: &TeachingAnnotation) {
+        match annotation {
+            TeachingAnnotation::AddStone { at, color } => {
+                self.stones.retain(|(c, _)| c != at);
+                self.stones.push((*at, *color));
+            }
+            TeachingAnnotation::RemoveStone { at } => self.stones.retain(|(c, _)| c != at),
+            TeachingAnnotation::Mark { at, marker } => {
+                self.marks.retain(|(c, _)| c != at);
+                self.marks.push((*at, marker.clone()));
+            }
+            TeachingAnnotation::Unmark { at } => self.marks.retain(|(c, _)| c != at),
+            TeachingAnnotation::Comment(text) => self.comments.push(text.clone()),
+        }
+    }
+}
+
+/// Overlays of a game by the number of moves played before them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TeachingLayer {
+    overlays: BTreeMap<usize, TeachingOverlay>,
+}
+
+impl TeachingLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply `annotation` to the position after `move_index` moves
+    pub fn apply(&mut self, move_index: usize, annotation: &TeachingAnnotation, board_size: u8) -> Result<(), GameError> {
+        if matches!(annotation.point(), Some(at) if !at.is_valid(board_size)) {
+            return Err(GameError::InvalidCoordinate);
+        }
+        let overlay = self.overlays.entry(move_index).or_default();
+        overlay.apply(annotation);
+        if overlay.is_empty() {
+            self.overlays.remove(&move_index);
+        }
+        Ok(())
+    }
+
+    /// The overlay of the position after `move_index` moves
+    pub fn overlay(&self, move_index: usize) -> Option<&TeachingOverlay> {
+        self.overlays.get(&move_index)
+    }
+
+    /// Annotated positions in move order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &TeachingOverlay)> {
+        self.overlays.iter().map(|(index, overlay)| (*index, overlay))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overlays.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.overlays.clear();
+    }
+}
@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Quick territory estimate for previewing the score during play, cheap
+//! enough to redo on every move: no playouts and no model.
+//!
+//! An empty region bordered by stones of one colour only is that colour's
+//! territory, unless it spans a large share of the board, as the open
+//! board does early on. The remaining empty points lean towards the colour
+//! whose stones are nearer, measured through empty points, fading out
+//! [`INFLUENCE_REACH`] points away. Dead stones are not recognised.
+
+use std::collections::VecDeque;
+use crate::{Color, GameState};
+
+/// Distance past which stones have no influence on an empty point
+pub const INFLUENCE_REACH: u16 = 4;
+
+/// Lean at which an empty point counts towards the score
+pub const COUNTED_LEAN: f32 = 0.5;
+
+/// What a point of the board counts as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Stone(Color),
+    /// Empty and surrounded by this colour only
+    Territory(Color),
+    /// Empty and contested, or too open to call
+    Neutral,
+}
+
+/// Territory estimate of one position
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerritoryEstimate {
+    pub board_size: u8,
+    /// Row by row like [`GameState::board`]
+    pub regions: Vec<Region>,
+    /// Lean of each point from 1 (Black) to -1 (White); stones and
+    /// territory lean fully
+    pub influence: Vec<f32>,
+}
+
+impl TerritoryEstimate {
+    /// Estimate `state`'s territory
+    pub fn of(state: &GameState) -> Self {
+        let size = state.board_size as usize;
+        let board = &state.board;
+        let neighbours = |idx: usize| {
+            let (x, y) = (idx % size, idx / size);
+            [
+                (x > 0).then(|| idx - 1),
+                (x + 1 < size).then(|| idx + 1),
+                (y > 0).then(|| idx - size),
+                (y + 1 < size).then(|| idx + size),
+            ]
+            .into_iter()
+            .flatten()
+        };
+
+        let mut regions: Vec<Region> = board.iter()
+            .map(|point| point.map_or(Region::Neutral, Region::Stone))
+            .collect();
+        // Flood fill each empty region, noting the colours around it
+        let mut seen = vec![false; board.len()];
+        let mut region = Vec::new();
+        let mut queue = VecDeque::new();
+        for start in 0..board.len() {
+            if seen[start] || board[start].is_some() {
+                continue;
+            }
+            region.clear();
+            let (mut black, mut white) = (false, false);
+            seen[start] = true;
+            queue.push_back(start);
+            while let Some(idx) = queue.pop_front() {
+                region.push(idx);
+                for next in neighbours(idx) {
+                    match board[next] {
+                        Some(Color::Black) => black = true,
+                        Some(Color::White) => white = true,
+                        None if !seen[next] => {
+                            seen[next] = true;
+                            queue.push_back(next);
+                        }
+                        None => {}
+                    }
+                }
+            }
+            let owner = match (black, white) {
+                (true, false) => Some(Color::Black),
+                (false, true) => Some(Color::White),
+                _ => None,
+            };
+            if let Some(owner) = owner.filter(|_| region.len() * 4 < board.len()) {
+                for &idx in &region {
+                    regions[idx] = Region::Territory(owner);
+                }
+            }
+        }
+
+        let black = distances(board, size, Color::Black, &neighbours);
+        let white = distances(board, size, Color::White, &neighbours);
+        let influence = regions.iter().enumerate()
+            .map(|(idx, region)| match region {
+                Region::Stone(color) | Region::Territory(color) => sign(*color),
+                Region::Neutral => lean(black[idx], white[idx]),
+            })
+            .collect();
+        Self { board_size: state.board_size, regions, influence }
+    }
+
+    /// Points of `color`'s territory
+    pub fn territory(&self, color: Color) -> usize {
+        self.regions.iter().filter(|region| **region == Region::Territory(color)).count()
+    }
+
+    /// Black's rough margin under area scoring: stones, territory and
+    /// empty points leaning at least [`COUNTED_LEAN`] one way, less `komi`
+    pub fn score(&self, komi: f32) -> f32 {
+        let points: f32 = self.influence.iter()
+            .filter(|lean| lean.abs() >= COUNTED_LEAN)
+            .map(|lean| lean.signum())
+            .sum();
+        points - komi
+    }
+}
+
+fn sign(color: Color) -> f32 {
+    match color {
+        Color::Black => 1.0,
+        Color::White => -1.0,
+    }
+}
+
+/// Steps from each point to the nearest `color` stone through empty
+/// points, up to [`INFLUENCE_REACH`]; `u16::MAX` beyond it
+fn distances<I: Iterator<Item = usize>>(
+    board: &[Option<Color>],
+    size: usize,
+    color: Color,
+    neighbours: &impl Fn(usize) -> I,
+) -> Vec<u16> {
+    let mut distance = vec![u16::MAX; size * size];
+    let mut queue = VecDeque::new();
+    for (idx, point) in board.iter().enumerate() {
+        if *point == Some(color) {
+            distance[idx] = 0;
+            queue.push_back(idx);
+        }
+    }
+    while let Some(idx) = queue.pop_front() {
+        let step = distance[idx] + 1;
+        if step > INFLUENCE_REACH {
+            continue;
+        }
+        for next in neighbours(idx) {
+            if board[next].is_none() && distance[next] == u16::MAX {
+                distance[next] = step;
+                queue.push_back(next);
+            }
+        }
+    }
+    distance
+}
+
+/// Lean of an empty point `black` and `white` steps from the nearest stones
+fn lean(black: u16, white: u16) -> f32 {
+    let nearest = black.min(white);
+    if nearest == u16::MAX || black == white {
+        return 0.0;
+    }
+    // Full strength next to a stone, gone past the reach
+    let fade = 1.0 - (nearest - 1) as f32 / INFLUENCE_REACH as f32;
+    let (near, far) = (nearest as f32, black.max(white).min(INFLUENCE_REACH + 1) as f32);
+    let margin = (far - near) / far;
+    let color = if black < white { Color::Black } else { Color::White };
+    sign(color) * fade * margin
+}
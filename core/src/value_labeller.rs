@@ -3,10 +3,18 @@
 //! Value-head labelling for training data with final scores
 
 use crate::{GameState, Color, Coord};
+use crate::cbor::migrate;
 use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use thiserror::Error;
 
-/// Proof of a game's final score
+/// Proof of a game's final score.
+///
+/// Carries the final position it was counted from, so anyone holding the
+/// proof can recount it with [`ScoreProof::verify`] without replaying the
+/// game or guessing which stones were dead.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "migrate::ScoreProofRepr", from = "migrate::ScoreProofRepr")]
 pub struct ScoreProof {
     pub final_score: i16, // Positive for Black, negative for White
     pub territory_black: u16,
@@ -15,6 +23,114 @@ pub struct ScoreProof {
     pub captures_white: u16,
     pub komi: f32,
     pub method: ScoringMethod,
+    /// What was counted; `None` in proofs written before it was kept
+    pub final_position: Option<FinalPosition>,
+}
+
+/// The end of a game as scored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalPosition {
+    pub board_size: u8,
+    /// Stones at the end, dead ones included, indexed like `GameState::board`
+    pub board: Vec<Option<Color>>,
+    /// [`position_hash`] of the board
+    pub hash: u64,
+    /// Stones both players agreed are dead, in board order
+    pub dead_stones: Vec<Coord>,
+}
+
+impl FinalPosition {
+    pub fn new(state: &GameState, dead_stones: &HashSet<Coord>) -> Self {
+        let mut dead_stones: Vec<Coord> = dead_stones.iter().copied().collect();
+        dead_stones.sort_by_key(|c| c.to_index(state.board_size));
+        Self {
+            board_size: state.board_size,
+            board: state.board.clone(),
+            hash: position_hash(state.board_size, &state.board),
+            dead_stones,
+        }
+    }
+}
+
+/// Hash of a board's size and stones: FNV-1a, so the same on every build
+pub fn position_hash(board_size: u8, board: &[Option<Color>]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let points = board.iter().map(|point| match point {
+        None => 0u8,
+        Some(Color::Black) => 1,
+        Some(Color::White) => 2,
+    });
+    for byte in std::iter::once(board_size).chain(points) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Why a score proof doesn't hold up
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ScoreMismatch {
+    #[error("the proof doesn't include the final position")]
+    NoFinalPosition,
+    #[error("the proof is for another position")]
+    Position,
+    #[error("{0:?} is marked dead but holds no stone")]
+    DeadStone(Coord),
+    #[error("captures are {claimed:?} in the proof but {actual:?} in the game")]
+    Captures { claimed: (u16, u16), actual: (u16, u16) },
+    #[error("{color:?} territory is {claimed} in the proof but counts {actual}")]
+    Territory { color: Color, claimed: u16, actual: u16 },
+    #[error("the score is {claimed} in the proof but counts {actual}")]
+    Score { claimed: i16, actual: i16 },
+}
+
+impl ScoreProof {
+    /// Recount the proof from its own final position, dead stones,
+    /// captures and komi
+    pub fn verify(&self) -> Result<(), ScoreMismatch> {
+        let position = self.final_position.as_ref().ok_or(ScoreMismatch::NoFinalPosition)?;
+        let cells = position.board_size as usize * position.board_size as usize;
+        if position.board.len() != cells {
+            return Err(ScoreMismatch::Position);
+        }
+        let mut state = GameState::new(position.board_size);
+        state.board = position.board.clone();
+        state.captures = (self.captures_black, self.captures_white);
+        self.verify_against(&state)
+    }
+
+    /// Recount the proof on `state`, the final position of the game
+    pub fn verify_against(&self, state: &GameState) -> Result<(), ScoreMismatch> {
+        let position = self.final_position.as_ref().ok_or(ScoreMismatch::NoFinalPosition)?;
+        let hash = position_hash(state.board_size, &state.board);
+        if position.board_size != state.board_size || position.hash != hash || position.board != state.board {
+            return Err(ScoreMismatch::Position);
+        }
+        let claimed = (self.captures_black, self.captures_white);
+        if claimed != state.captures {
+            return Err(ScoreMismatch::Captures { claimed, actual: state.captures });
+        }
+        for &coord in &position.dead_stones {
+            if !coord.is_valid(state.board_size) || state.board[coord.to_index(state.board_size)].is_none() {
+                return Err(ScoreMismatch::DeadStone(coord));
+            }
+        }
+
+        let dead: HashSet<Coord> = position.dead_stones.iter().copied().collect();
+        let recount = crate::scoring::calculate_final_score(state, self.komi, self.method.clone(), &dead);
+        for (color, claimed, actual) in [
+            (Color::Black, self.territory_black, recount.territory_black),
+            (Color::White, self.territory_white, recount.territory_white),
+        ] {
+            if claimed != actual {
+                return Err(ScoreMismatch::Territory { color, claimed, actual });
+            }
+        }
+        if self.final_score != recount.final_score {
+            return Err(ScoreMismatch::Score { claimed: self.final_score, actual: recount.final_score });
+        }
+        Ok(())
+    }
 }
 
 /// Method used to determine the final score
@@ -85,6 +201,7 @@ impl ValueLabeller {
     }
     
     /// Export labels for training
+    #[cfg(feature = "cbor")]
     pub fn export_training_data(&self) -> Vec<u8> {
         match serde_cbor::to_vec(&self.move_values) {
             Ok(data) => data,
@@ -220,6 +337,7 @@ impl ValueLabeller {
     
     /// Label a game's moves and persist value labels with score proof
     /// This function is used to create training data for the neural network
+    #[cfg(feature = "cbor")]
     pub fn label_and_persist(&mut self, game_state: &GameState, score_proof: ScoreProof) -> Vec<u8> {
         // First add all move positions to the labeller
         for i in 0..game_state.moves.len() {
@@ -246,6 +364,10 @@ impl ValueLabeller {
             buffer.extend(score_data);
         }
         
+        // Add the game with 'G' marker, for the score to be recounted on
+        buffer.push(b'G');
+        buffer.extend(crate::cbor::serialize_game_state(game_state));
+        
         // Add move labels with 'M' marker
         buffer.push(b'M');
         buffer.extend(self.export_training_data());
@@ -284,6 +406,7 @@ mod tests {
             captures_white: 1,
             komi: 6.5,
             method: ScoringMethod::Territory,
+            final_position: None,
         };
         
         labeller.set_final_score(score_proof);
@@ -295,6 +418,7 @@ mod tests {
     }
     
     #[test]
+    #[cfg(feature = "cbor")]
     fn test_score_proof_serialization() {
         let score_proof = ScoreProof {
             final_score: -7,
@@ -304,6 +428,7 @@ mod tests {
             captures_white: 3,
             komi: 6.5,
             method: ScoringMethod::Resignation(Color::White),
+            final_position: None,
         };
         
         let serialized = serde_cbor::to_vec(&score_proof).unwrap();
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Post-game reports: think times, phases and flagged mistakes.
+
+use std::time::Duration;
+use p2pgo_core::analysis::{game_report, Phase};
+use p2pgo_core::position::Position;
+use p2pgo_core::{Color, Coord, GameState, Move, MoveRecord};
+
+fn at(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+fn game(moves: &[Move]) -> GameState {
+    moves.iter()
+        .fold(Position::new(9), |position, mv| position.play(mv.clone()).unwrap())
+        .to_dense()
+}
+
+fn records(moves: &[Move], times: &[u64]) -> Vec<MoveRecord> {
+    moves.iter().zip(times).map(|(mv, &ts)| MoveRecord {
+        mv: mv.clone(),
+        tag: None,
+        ts,
+        broadcast_hash: None,
+        prev_hash: None,
+    }).collect()
+}
+
+/// Stone balance for Black, counting a capture the side to move can make at once
+fn material(state: &GameState) -> f32 {
+    let mover = state.current_player;
+    let opponent = mover.opposite();
+    let position = Position::from_dense(state).unwrap();
+    let best_capture = position.legal_moves().into_iter()
+        .map(|coord| {
+            let next = position.play(Move::Place(coord)).unwrap();
+            state.count_stones_for(opponent) - next.count_stones_for(opponent)
+        })
+        .max()
+        .unwrap_or(0) as f32;
+    let balance = state.count_stones_for(Color::Black) as f32 - state.count_stones_for(Color::White) as f32;
+    // Black is a stone ahead after each of its moves until White answers
+    let (tempo, threat) = match mover {
+        Color::Black => (0.0, best_capture),
+        Color::White => (1.0, -best_capture),
+    };
+    0.5 * (balance - tempo + threat)
+}
+
+#[test]
+fn moves_are_bucketed_by_phase() {
+    assert_eq!(Phase::of(0, 9), Phase::Opening);
+    assert_eq!(Phase::of(9, 9), Phase::Opening);
+    assert_eq!(Phase::of(10, 9), Phase::Middle);
+    assert_eq!(Phase::of(31, 9), Phase::Middle);
+    assert_eq!(Phase::of(32, 9), Phase::Endgame);
+    assert_eq!(Phase::of(44, 19), Phase::Opening);
+    assert_eq!(Phase::of(143, 19), Phase::Middle);
+    assert_eq!(Phase::of(144, 19), Phase::Endgame);
+}
+
+#[test]
+fn think_times_add_up_per_player_and_phase() {
+    let moves: Vec<Move> = (0..12u8).map(|i| at(i % 9, i / 9 * 4)).collect();
+    // Black answers after 2s throughout; White takes 5s, except 30s on move 11
+    let mut times = vec![100];
+    for index in 1..12 {
+        let gap = match (index % 2, index) {
+            (_, 11) => 30,
+            (0, _) => 2,
+            _ => 5,
+        };
+        times.push(times[index - 1] + gap);
+    }
+    let report = game_report(&game(&moves), &records(&moves, &times), |_| 0.0);
+
+    assert_eq!(report.black.timed_moves, 5);
+    assert_eq!(report.black.average(), Some(Duration::from_secs(2)));
+    assert_eq!(report.white.timed_moves, 6);
+    assert_eq!(report.white.total, Duration::from_secs(55));
+    assert_eq!(report.times(Color::White).longest, Some((11, Duration::from_secs(30))));
+    // Moves 1-9 are the opening, 10 and 11 the middle game
+    assert_eq!(report.phase_time(Phase::Opening), Duration::from_secs(4 * 2 + 5 * 5));
+    assert_eq!(report.phase_time(Phase::Middle), Duration::from_secs(2 + 30));
+    assert_eq!(report.phase_time(Phase::Endgame), Duration::ZERO);
+    assert_eq!(report.evals.len(), 13);
+    assert!(report.mistakes.is_empty());
+
+    // Records from a resumed session cover only the last moves
+    let report = game_report(&game(&moves), &records(&moves[9..], &times[9..]), |_| 0.0);
+    assert_eq!(report.white.longest, Some((11, Duration::from_secs(30))));
+    assert_eq!(report.black.total, Duration::from_secs(2));
+    assert_eq!(report.white.timed_moves, 1);
+}
+
+#[test]
+fn ignoring_an_atari_is_flagged() {
+    let moves = [
+        at(4, 4), at(3, 4), at(2, 2), at(5, 4), at(6, 6), at(4, 3),
+        // Black's stone on 4,4 is in atari; playing in the corner drops it
+        at(8, 8),
+        at(4, 5), at(2, 6),
+    ];
+    let report = game_report(&game(&moves), &[], material);
+
+    assert_eq!(report.mistakes.len(), 1, "{:?}", report.mistakes);
+    let mistake = &report.mistakes[0];
+    assert_eq!((mistake.index, mistake.color, &mistake.mv), (6, Color::Black, &at(8, 8)));
+    assert!((mistake.swing - 0.5).abs() < 1e-6);
+    assert_eq!(report.evals.last(), Some(&-0.5));
+    // Without records there are no think times
+    assert_eq!(report.black.average(), None);
+}
+
+mod ownership {
+    use p2pgo_core::analysis::{expected_score, ownership};
+    use p2pgo_core::{Color, Coord, GameState};
+
+    /// Black walls off five columns on the left, White three on the right,
+    /// with one open column between them
+    fn walled_position() -> GameState {
+        let mut stones = Vec::new();
+        for y in 0..9 {
+            stones.push((Coord::new(4, y), Color::Black));
+            stones.push((Coord::new(6, y), Color::White));
+        }
+        // Eyes so both walls live whatever the playouts do
+        for y in [1, 4, 7] {
+            stones.push((Coord::new(2, y), Color::Black));
+            stones.push((Coord::new(7, y), Color::White));
+        }
+        GameState::from_setup(9, &stones, Color::Black).unwrap()
+    }
+
+    #[test]
+    fn walled_off_areas_belong_to_their_walls() {
+        let state = walled_position();
+        let map = ownership(&state, 64);
+        assert_eq!(map.len(), 81);
+        let at = |x: u8, y: u8| map[Coord::new(x, y).to_index(9)];
+        for y in 0..9 {
+            assert!(at(0, y) > 0.5, "Black's area at 0,{}: {}", y, at(0, y));
+            assert_eq!(at(4, y), 1.0);
+            assert_eq!(at(6, y), -1.0);
+            assert!(at(8, y) < -0.5, "White's area at 8,{}: {}", y, at(8, y));
+        }
+        // 45 points against 27 is more than komi
+        let score = expected_score(&map, 5.5);
+        assert!(score > 0.0, "{}", score);
+    }
+
+    #[test]
+    fn same_position_same_estimate() {
+        let state = walled_position();
+        assert_eq!(ownership(&state, 16), ownership(&state, 16));
+        assert!(ownership(&state, 0).iter().all(|&p| p == 0.0));
+        // An empty board is anyone's
+        let empty = ownership(&GameState::new(9), 64);
+        assert!(expected_score(&empty, 0.0).abs() < 81.0);
+    }
+}
+
+mod pass_suggestion {
+    use p2pgo_core::analysis::pass_suggestion;
+    use p2pgo_core::sgf::SgfProcessor;
+    use p2pgo_core::{Coord, GameState};
+
+    /// Both sides' areas filled around single-point eyes; only the eyes are
+    /// left empty
+    fn finished_position() -> GameState {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/positions/finished_9x9.sgf");
+        SgfProcessor::new(GameState::new(9)).parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn filling_own_territory_suggests_passing() {
+        // 45 points against 36, less komi
+        let estimate = pass_suggestion(&finished_position(), 5.5, 1.0, 32);
+        assert_eq!(estimate, Some(3.5));
+    }
+
+    #[test]
+    fn open_points_are_worth_playing() {
+        // Clearing White's wall opens a column between the two areas
+        let mut state = finished_position();
+        for y in 0..9 {
+            state.board[Coord::new(5, y).to_index(9)] = None;
+        }
+        assert_eq!(pass_suggestion(&state, 5.5, 1.0, 32), None);
+        assert_eq!(pass_suggestion(&GameState::new(9), 5.5, 1.0, 32), None);
+    }
+}
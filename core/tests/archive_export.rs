@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Exporting the whole archive as an SGF collection or a zip.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use p2pgo_core::archiver::{self, ClassificationFilter, ExportFormat, ExportReport};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::{Coord, GameState, Move};
+
+fn play(board_size: u8, moves: &[Move]) -> GameState {
+    let mut game = GameState::new(board_size);
+    for mv in moves {
+        game.apply_move(mv.clone()).unwrap();
+    }
+    game
+}
+
+/// A resigned game, a scored one and an empty one, plus a broken record
+fn populated_archive() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let resigned = play(9, &[Move::Place(Coord::new(2, 2)), Move::Resign]);
+    let scored = play(19, &[Move::Place(Coord::new(3, 3)), Move::Place(Coord::new(15, 15)), Move::Pass, Move::Pass]);
+    let empty = play(13, &[]);
+    archiver::archive_game_in(dir.path(), &resigned, "alice").unwrap();
+    archiver::archive_game_in(dir.path(), &scored, "bob").unwrap();
+    archiver::archive_game_in(dir.path(), &empty, "carol").unwrap();
+
+    let mut summary = archiver::list_games_in(dir.path()).unwrap().into_iter().find(|g| g.opponent == "bob").unwrap();
+    summary.result = Some("B+3.5".to_string());
+    archiver::write_summary(dir.path(), &summary).unwrap();
+
+    std::fs::write(dir.path().join("broken.cbor"), b"not a game").unwrap();
+    dir
+}
+
+fn export(dir: &Path, out: &Path, format: ExportFormat, filter: &ClassificationFilter) -> ExportReport {
+    archiver::export_all(dir, File::create(out).unwrap(), format, filter).unwrap()
+}
+
+/// The zip's entries by name with their contents
+fn entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut zip = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+    (0..zip.len()).map(|i| {
+        let mut entry = zip.by_index(i).unwrap();
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes).unwrap();
+        (entry.name().to_string(), bytes)
+    }).collect()
+}
+
+#[test]
+fn zip_entries_parse_back_with_their_headers() {
+    let archive = populated_archive();
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("games.zip");
+
+    let report = export(archive.path(), &path, ExportFormat::ZipOfSgf, &ClassificationFilter::default());
+    assert_eq!(report.exported, 3);
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.corrupt.len(), 1);
+    assert_eq!(report.corrupt[0].0, "broken");
+
+    let games = archiver::list_games_in(archive.path()).unwrap();
+    let entries = entries(&path);
+    assert_eq!(entries.len(), 3);
+    for (name, bytes) in entries {
+        let id = name.strip_suffix(".sgf").unwrap();
+        let summary = games.iter().find(|g| g.id == id).unwrap();
+        let sgf = String::from_utf8(bytes).unwrap();
+        let game = SgfProcessor::new(GameState::new(summary.board_size)).parse(&sgf).unwrap();
+        // SGF has no resign move, so compare positions rather than move lists
+        let archived = archiver::load_game(archive.path(), id).unwrap();
+        assert_eq!(game.board, archived.board);
+        assert_eq!(game.moves.len(), archived.moves.len());
+
+        assert!(sgf.contains(&format!("GN[{}]", id)), "{}", sgf);
+        assert!(sgf.contains(&format!("DT[{}]", summary.date)), "{}", sgf);
+        assert!(sgf.contains(&format!("GC[Played against {}]", summary.opponent)), "{}", sgf);
+        if let Some(result) = &summary.result {
+            assert!(sgf.contains(&format!("RE[{}]", result)), "{}", sgf);
+        }
+    }
+}
+
+#[test]
+fn only_scored_games_when_asked() {
+    let archive = populated_archive();
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("scored.zip");
+
+    let filter = ClassificationFilter { only_scored: true, ..Default::default() };
+    let report = export(archive.path(), &path, ExportFormat::ZipOfCbor, &filter);
+    assert_eq!(report.exported, 1);
+    assert_eq!(report.skipped.len(), 2);
+
+    let entries = entries(&path);
+    assert_eq!(entries.len(), 1);
+    let (name, bytes) = &entries[0];
+    assert!(name.ends_with("_vs_bob.cbor"), "{}", name);
+    // Records go in as archived
+    assert_eq!(bytes, &std::fs::read(archive.path().join(name)).unwrap());
+}
+
+#[test]
+fn collection_holds_every_game_in_one_file() {
+    let archive = populated_archive();
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("games.sgf");
+
+    let report = export(archive.path(), &path, ExportFormat::SgfCollection, &ClassificationFilter::default());
+    assert_eq!(report.exported, 3);
+
+    let collection = std::fs::read_to_string(&path).unwrap();
+    let trees: Vec<&str> = collection.lines().collect();
+    assert_eq!(trees.len(), 3);
+    for tree in trees {
+        assert!(tree.starts_with("(;FF[4]"), "{}", tree);
+        let size: u8 = tree.split("SZ[").nth(1).and_then(|rest| rest.split(']').next()).unwrap().parse().unwrap();
+        SgfProcessor::new(GameState::new(size)).parse(tree).unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Archive maintenance: leftover temporary files and corrupt records.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use p2pgo_core::archiver::{self, MaintenanceReport, CORRUPT_DIR, STALE_TEMP_AGE};
+use p2pgo_core::{Coord, GameState, Move};
+
+fn resigned_game() -> GameState {
+    let mut game = GameState::new(9);
+    game.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+    game.apply_move(Move::Resign).unwrap();
+    game
+}
+
+fn names_in(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn stale_temporary_files_are_removed_and_fresh_ones_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    archiver::archive_game_in(dir.path(), &resigned_game(), "alice").unwrap();
+    std::fs::write(dir.path().join(".tmp_half_written.cbor"), b"\xa1").unwrap();
+    std::fs::write(dir.path().join("game-1.tmp"), b"\xa1").unwrap();
+
+    // Just written, so possibly still in use
+    let report = archiver::run_maintenance(dir.path()).unwrap();
+    assert!(report.removed_temps.is_empty());
+    assert!(!report.repaired_anything());
+
+    let later = SystemTime::now() + STALE_TEMP_AGE + Duration::from_secs(60);
+    let report = archiver::run_maintenance_at(dir.path(), later).unwrap();
+    assert_eq!(report.removed_temps, vec![".tmp_half_written.cbor".to_string(), "game-1.tmp".to_string()]);
+    // The record and its summary
+    assert_eq!(report.checked, 2);
+    assert_eq!(names_in(dir.path()).len(), 2);
+    assert_eq!(report.summary(), "Archive repaired: 2 leftover temporary files removed");
+}
+
+#[test]
+fn corrupt_records_are_quarantined_with_a_log_entry() {
+    let dir = tempfile::tempdir().unwrap();
+    archiver::archive_game_in(dir.path(), &resigned_game(), "alice").unwrap();
+    std::fs::write(dir.path().join("broken.cbor"), b"not cbor at all").unwrap();
+    let summary = archiver::GameSummary::new("broken".to_string(), "bob", "2024-01-01", &GameState::new(9));
+    archiver::write_summary(dir.path(), &summary).unwrap();
+    std::fs::write(dir.path().join("truncated.meta"), b"\xa8").unwrap();
+
+    let report = archiver::run_maintenance(dir.path()).unwrap();
+    let quarantined: Vec<&str> = report.quarantined.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(quarantined, ["broken.cbor", "truncated.meta"]);
+    assert!(report.quarantined[0].1.contains("Not a game record"), "{:?}", report.quarantined);
+
+    // The broken record's summary goes with it
+    let corrupt = dir.path().join(CORRUPT_DIR);
+    assert_eq!(names_in(&corrupt), ["broken.cbor", "broken.meta", "quarantine.log", "truncated.meta"]);
+    let log = std::fs::read_to_string(corrupt.join("quarantine.log")).unwrap();
+    assert_eq!(log.lines().count(), 2);
+    assert!(log.contains("broken.cbor: Not a game record"), "{}", log);
+
+    let games = archiver::list_games_in(dir.path()).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].opponent, "alice");
+
+    // Nothing is left to repair on the next run
+    let again = archiver::run_maintenance(dir.path()).unwrap();
+    assert_eq!(again, MaintenanceReport { checked: 2, ..MaintenanceReport::default() });
+}
+
+#[test]
+fn snapshots_count_as_records_and_missing_directories_are_fine() {
+    #[derive(serde::Serialize)]
+    struct Snapshot {
+        game_id: String,
+        state: GameState,
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let snapshot = Snapshot { game_id: "game-1".to_string(), state: resigned_game() };
+    std::fs::write(dir.path().join("game-1.cbor"), serde_cbor::to_vec(&snapshot).unwrap()).unwrap();
+
+    let report = archiver::run_maintenance(dir.path()).unwrap();
+    assert_eq!(report.checked, 1);
+    assert!(report.quarantined.is_empty());
+
+    let report = archiver::run_maintenance(&dir.path().join("missing")).unwrap();
+    assert_eq!(report, MaintenanceReport::default());
+}
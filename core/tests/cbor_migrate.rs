@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Old CBOR keeps loading: pinned bytes of every schema version, and
+//! tolerance of fields added by newer versions.
+
+use p2pgo_core::cbor::migrate::{self, GAME_STATE_VERSION, MOVE_RECORD_VERSION, SCORE_PROOF_VERSION};
+use p2pgo_core::game_clock::{TimeSettings, TimingInfo};
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::{ScoreMismatch, ScoreProof, ScoringMethod};
+use p2pgo_core::{Color, Coord, GameState, Move, MoveRecord, Tag};
+use proptest::prelude::*;
+use serde_cbor::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Bytes checked in under `tests/fixtures/cbor`
+fn fixture(name: &str) -> Vec<u8> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/cbor").join(name);
+    std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// The game in `game_state_v0.cbor`
+fn v0_game() -> GameState {
+    let mut game = GameState::new(9);
+    for mv in [Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6)), Move::Pass] {
+        game.apply_move(mv).unwrap();
+    }
+    game
+}
+
+/// The game in `game_state_v1.cbor`
+fn v1_game() -> GameState {
+    let stones = [(Coord::new(4, 4), Color::Black), (Coord::new(3, 4), Color::White)];
+    let mut game = GameState::from_setup(9, &stones, Color::White).unwrap();
+    game.apply_move(Move::Place(Coord::new(5, 5))).unwrap();
+    game
+}
+
+/// The game in `game_state_v2.cbor`: the v1 game, timed
+fn v2_game() -> GameState {
+    let mut game = v1_game();
+    game.timing.settings = Some(TimeSettings { main_secs: 60, periods: 3, byo_yomi_secs: 10 });
+    game.timing.record(0, TimingInfo { time_left_ms: 57_250, periods: None });
+    game
+}
+
+/// The game in `game_state_v3.cbor`: the v2 game, allowing suicide
+fn v3_game() -> GameState {
+    let mut game = v2_game();
+    game.rule_config = RuleConfig { suicide_allowed: true };
+    game
+}
+
+/// The record in `move_record_v1.cbor`
+fn v1_record() -> MoveRecord {
+    MoveRecord {
+        mv: Move::Place(Coord::new(3, 3)),
+        tag: Some(Tag::Reactivity),
+        ts: 1_700_000_000,
+        broadcast_hash: Some([7; 32]),
+        prev_hash: Some([9; 32]),
+    }
+}
+
+/// The proof in `score_proof_v1.cbor`: the v0 game with White's stone dead
+fn v1_proof() -> ScoreProof {
+    let dead: HashSet<Coord> = [Coord::new(6, 6)].into_iter().collect();
+    calculate_final_score(&v0_game(), 6.5, ScoringMethod::Territory, &dead)
+}
+
+fn assert_same_proof(loaded: &ScoreProof, expected: &ScoreProof) {
+    assert_eq!(loaded.final_score, expected.final_score);
+    assert_eq!((loaded.territory_black, loaded.territory_white), (expected.territory_black, expected.territory_white));
+    assert_eq!((loaded.captures_black, loaded.captures_white), (expected.captures_black, expected.captures_white));
+    assert_eq!(loaded.komi, expected.komi);
+    assert_eq!(loaded.method, expected.method);
+    assert_eq!(loaded.final_position, expected.final_position);
+}
+
+fn assert_same_game(loaded: &GameState, expected: &GameState) {
+    assert_eq!(loaded.board_size, expected.board_size);
+    assert_eq!(loaded.board, expected.board);
+    assert_eq!(loaded.current_player, expected.current_player);
+    assert_eq!(loaded.moves, expected.moves);
+    assert_eq!(loaded.pass_count, expected.pass_count);
+    assert_eq!(loaded.captures, expected.captures);
+    assert_eq!(loaded.setup, expected.setup);
+    assert_eq!(loaded.timing, expected.timing);
+    assert_eq!(loaded.rule_config, expected.rule_config);
+}
+
+fn assert_same_record(loaded: &MoveRecord, expected: &MoveRecord) {
+    assert_eq!(loaded.mv, expected.mv);
+    assert_eq!(loaded.tag, expected.tag);
+    assert_eq!(loaded.ts, expected.ts);
+    assert_eq!(loaded.broadcast_hash, expected.broadcast_hash);
+    assert_eq!(loaded.prev_hash, expected.prev_hash);
+}
+
+#[test]
+fn v0_fixtures_load_into_todays_structs() {
+    let bytes = fixture("game_state_v0.cbor");
+    assert_eq!(migrate::version_of(&bytes), 0);
+    let game: GameState = migrate::from_slice(&bytes).unwrap();
+    assert_same_game(&game, &v0_game());
+    assert!(game.setup.is_empty());
+
+    let bytes = fixture("move_record_v0.cbor");
+    assert_eq!(migrate::version_of(&bytes), 0);
+    let record: MoveRecord = migrate::from_slice(&bytes).unwrap();
+    assert_same_record(&record, &MoveRecord { broadcast_hash: None, prev_hash: None, ..v1_record() });
+
+    // Unversioned records written once chaining existed keep their hashes
+    let mut map: BTreeMap<Value, Value> = serde_cbor::from_slice(&fixture("move_record_v1.cbor")).unwrap();
+    map.remove(&Value::Text("v".to_string()));
+    let record: MoveRecord = migrate::from_slice(&serde_cbor::to_vec(&map).unwrap()).unwrap();
+    assert_same_record(&record, &v1_record());
+
+    let bytes = fixture("score_proof_v0.cbor");
+    assert_eq!(migrate::version_of(&bytes), 0);
+    let proof: ScoreProof = migrate::from_slice(&bytes).unwrap();
+    assert_same_proof(&proof, &ScoreProof { final_position: None, ..v1_proof() });
+    assert_eq!(proof.verify(), Err(ScoreMismatch::NoFinalPosition));
+}
+
+#[test]
+fn v1_games_load_untimed() {
+    let bytes = fixture("game_state_v1.cbor");
+    assert_eq!(migrate::version_of(&bytes), 1);
+    let game: GameState = migrate::from_slice(&bytes).unwrap();
+    assert_same_game(&game, &v1_game());
+    assert!(game.timing.is_empty());
+}
+
+#[test]
+fn v2_games_load_without_suicide() {
+    let bytes = fixture("game_state_v2.cbor");
+    assert_eq!(migrate::version_of(&bytes), 2);
+    let game: GameState = migrate::from_slice(&bytes).unwrap();
+    assert_same_game(&game, &v2_game());
+    assert!(!game.rule_config.suicide_allowed);
+}
+
+#[test]
+fn todays_bytes_match_the_latest_fixtures() {
+    // A failure here means the schema changed: bump the version, add an
+    // upgrade and a new fixture rather than editing these
+    assert_eq!(GAME_STATE_VERSION, 3);
+    assert_eq!(MOVE_RECORD_VERSION, 1);
+    assert_eq!(SCORE_PROOF_VERSION, 1);
+    assert_eq!(serde_cbor::to_vec(&v3_game()).unwrap(), fixture("game_state_v3.cbor"));
+    assert_eq!(serde_cbor::to_vec(&v1_record()).unwrap(), fixture("move_record_v1.cbor"));
+    assert_eq!(serde_cbor::to_vec(&v1_proof()).unwrap(), fixture("score_proof_v1.cbor"));
+
+    let game: GameState = migrate::from_slice(&fixture("game_state_v3.cbor")).unwrap();
+    assert_same_game(&game, &v3_game());
+    let record: MoveRecord = migrate::from_slice(&fixture("move_record_v1.cbor")).unwrap();
+    assert_same_record(&record, &v1_record());
+    let proof: ScoreProof = migrate::from_slice(&fixture("score_proof_v1.cbor")).unwrap();
+    assert_same_proof(&proof, &v1_proof());
+    assert_eq!(proof.verify(), Ok(()));
+}
+
+#[test]
+fn mismatched_boards_are_rejected() {
+    let mut map: BTreeMap<Value, Value> = serde_cbor::from_slice(&fixture("game_state_v0.cbor")).unwrap();
+    map.insert(Value::Text("board_size".to_string()), Value::Integer(19));
+    let bytes = serde_cbor::to_vec(&map).unwrap();
+    let err = migrate::from_slice::<GameState>(&bytes).unwrap_err();
+    assert!(err.to_string().contains("81 points for a 19x19 board"), "{}", err);
+}
+
+/// A value a newer version might write
+fn any_value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        any::<i64>().prop_map(|n| Value::Integer(n.into())),
+        any::<bool>().prop_map(Value::Bool),
+        ".{0,16}".prop_map(Value::Text),
+        prop::collection::vec(any::<u8>(), 0..40).prop_map(Value::Bytes),
+        Just(Value::Null),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| prop_oneof![
+        prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+        prop::collection::btree_map(".{0,8}".prop_map(Value::Text), inner, 0..4).prop_map(Value::Map),
+    ])
+}
+
+/// `bytes` as a newer version might write them: a higher `v` and extra fields
+fn from_the_future(bytes: &[u8], v: u32, extra: &BTreeMap<String, Value>) -> Vec<u8> {
+    let mut map: BTreeMap<Value, Value> = serde_cbor::from_slice(bytes).unwrap();
+    for (key, value) in extra {
+        map.entry(Value::Text(format!("future_{}", key))).or_insert_with(|| value.clone());
+    }
+    map.insert(Value::Text("v".to_string()), Value::Integer(v.into()));
+    serde_cbor::to_vec(&map).unwrap()
+}
+
+proptest! {
+    #[test]
+    fn unknown_future_fields_are_tolerated(
+        v in 4u32..1000,
+        extra in prop::collection::btree_map("[a-z_]{1,12}", any_value(), 0..6),
+    ) {
+        let bytes = from_the_future(&fixture("game_state_v3.cbor"), v, &extra);
+        prop_assert_eq!(migrate::version_of(&bytes), v);
+        let game: GameState = migrate::from_slice(&bytes).unwrap();
+        assert_same_game(&game, &v3_game());
+
+        let bytes = from_the_future(&fixture("move_record_v1.cbor"), v, &extra);
+        let record: MoveRecord = migrate::from_slice(&bytes).unwrap();
+        assert_same_record(&record, &v1_record());
+    }
+}
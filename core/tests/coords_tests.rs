@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use p2pgo_core::{coords::{gtp_column, parse_move, MoveParseError}, Coord, GameError, Move};
+
+fn all_coords(size: u8) -> impl Iterator<Item = Coord> {
+    (0..size).flat_map(move |y| (0..size).map(move |x| Coord::new(x, y)))
+}
+
+#[test]
+fn sgf_round_trips_on_all_sizes() {
+    for size in 1..=52 {
+        for coord in all_coords(size) {
+            let sgf = coord.to_sgf();
+            assert_eq!(Coord::from_sgf(&sgf, size), Ok(coord), "{} on {}x{}", sgf, size, size);
+        }
+    }
+}
+
+#[test]
+fn sgf_boundaries() {
+    assert_eq!(Coord::new(0, 0).to_sgf(), "aa");
+    assert_eq!(Coord::new(18, 18).to_sgf(), "ss");
+    assert_eq!(Coord::new(26, 0).to_sgf(), "Aa");
+    
+    // 't' is the 20th letter: off the board on 19x19, on it on 20x20
+    assert_eq!(Coord::from_sgf("tt", 19), Err(GameError::InvalidCoordinate));
+    assert_eq!(Coord::from_sgf("sa", 19), Ok(Coord::new(18, 0)));
+    assert_eq!(Coord::from_sgf("tt", 20), Ok(Coord::new(19, 19)));
+    
+    for bad in ["", "a", "abc", "a1", "é€", "[]"] {
+        assert!(Coord::from_sgf(bad, 19).is_err(), "{:?} should not parse", bad);
+    }
+}
+
+#[test]
+fn gtp_round_trips_on_all_sizes() {
+    for size in 1..=25 {
+        for coord in all_coords(size) {
+            let gtp = coord.to_gtp(size).unwrap();
+            assert!(!gtp.contains('I'), "{} uses I", gtp);
+            assert_eq!(Coord::from_gtp(&gtp, size), Ok(coord), "{} on {}x{}", gtp, size, size);
+            assert_eq!(Coord::from_gtp(&gtp.to_lowercase(), size), Ok(coord));
+        }
+    }
+}
+
+#[test]
+fn gtp_skips_i_and_counts_rows_from_bottom() {
+    assert_eq!(gtp_column(7), Some('H'));
+    assert_eq!(gtp_column(8), Some('J'));
+    assert_eq!(gtp_column(18), Some('T'));
+    assert_eq!(gtp_column(25), None);
+    
+    assert_eq!(Coord::new(0, 18).to_gtp(19).as_deref(), Some("A1"));
+    assert_eq!(Coord::new(18, 0).to_gtp(19).as_deref(), Some("T19"));
+    assert_eq!(Coord::new(8, 8).to_gtp(9).as_deref(), Some("J1"));
+    assert_eq!(Coord::new(9, 0).to_gtp(9), None);
+    
+    for bad in ["", "I5", "A0", "A20", "T1x", "Z9", "1A"] {
+        assert!(Coord::from_gtp(bad, 19).is_err(), "{:?} should not parse", bad);
+    }
+    assert!(Coord::from_gtp("K1", 9).is_err());
+}
+
+#[test]
+fn index_round_trips_on_all_sizes() {
+    for size in 1..=25u8 {
+        let cells = size as usize * size as usize;
+        for (idx, coord) in all_coords(size).enumerate() {
+            assert_eq!(coord.to_index(size), idx);
+            assert_eq!(Coord::from_index(idx, size), Some(coord));
+        }
+        assert_eq!(Coord::from_index(cells, size), None);
+    }
+    assert_eq!(Coord::from_index(0, 0), None);
+}
+
+#[test]
+fn display_label_falls_back_beyond_gtp() {
+    assert_eq!(Coord::new(3, 15).display_label(19), "D4");
+    assert_eq!(Coord::new(30, 2).display_label(40), "30,2");
+}
+
+#[test]
+fn typed_moves_are_forgiving_about_form() {
+    let d4 = Ok(Move::Place(Coord::new(3, 15)));
+    for typed in ["D4", "d4", "  D4\n", "\tD4 ", "Ｄ４", "ｄ４", "\u{3000}D4"] {
+        assert_eq!(parse_move(typed, 19), d4, "{:?}", typed);
+    }
+    assert_eq!(parse_move(" Pass ", 9), Ok(Move::Pass));
+    assert_eq!(parse_move("ＲＥＳＩＧＮ", 9), Ok(Move::Resign));
+}
+
+#[test]
+fn typed_moves_off_the_board_are_refused() {
+    assert_eq!(parse_move("T4", 9), Err(MoveParseError::ColumnOffBoard { column: 'T', board_size: 9 }));
+    assert_eq!(parse_move("K1", 9), Err(MoveParseError::ColumnOffBoard { column: 'K', board_size: 9 }));
+    assert_eq!(parse_move("J10", 9), Err(MoveParseError::RowOffBoard { row: 10, board_size: 9 }));
+    assert_eq!(parse_move("a0", 9), Err(MoveParseError::RowOffBoard { row: 0, board_size: 9 }));
+    assert_eq!(parse_move("   ", 9), Err(MoveParseError::Empty));
+    for bad in ["I4", "D+4", "D 4", "D-1", "4D", "DD", "D", "D1000", "passs", "é4"] {
+        assert!(matches!(parse_move(bad, 19), Err(MoveParseError::Malformed(_))), "{:?} should not parse", bad);
+    }
+    assert_eq!(parse_move("T4", 9).unwrap_err().to_string(), "Column T is off the 9x9 board");
+    assert!(Coord::from_gtp("D+4", 19).is_err());
+}
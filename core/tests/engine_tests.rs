@@ -11,3 +11,143 @@ fn basic_game_flow() {
     // This is a placeholder test that will be implemented later
     let _game = GameState::new(9);
 }
+
+mod policy_player {
+    use std::time::Duration;
+    use p2pgo_core::engine::{PlayerBackend, PolicyPlayer};
+    use p2pgo_core::policy::{move_index, pass_index, policy_len};
+    use p2pgo_core::{Color, Coord, GameState, Move};
+
+    /// Settled 5x5 endgame: each side has a wall and two single-point eyes
+    fn settled_endgame() -> GameState {
+        let mut stones = Vec::new();
+        for y in 0..5 {
+            stones.push((Coord::new(1, y), Color::Black));
+            stones.push((Coord::new(3, y), Color::White));
+            stones.push((Coord::new(2, y), if y % 2 == 0 { Color::Black } else { Color::White }));
+            if y % 2 == 0 {
+                stones.push((Coord::new(0, y), Color::Black));
+                stones.push((Coord::new(4, y), Color::White));
+            }
+        }
+        GameState::from_setup(5, &stones, Color::Black).unwrap()
+    }
+
+    #[test]
+    fn settled_endgame_is_passed_even_when_points_rate_higher() {
+        let state = settled_endgame();
+        // Pass gets the worst logit; only own eyes and suicide points are left
+        let mut player = PolicyPlayer::new(|_: &GameState| {
+            let mut logits = vec![1.0; policy_len(5)];
+            logits[pass_index(5)] = -10.0;
+            logits
+        });
+        assert_eq!(player.next_move(&state, Duration::from_secs(10)), Move::Pass);
+
+        let mut white = state.clone();
+        white.apply_move(Move::Pass).unwrap();
+        assert_eq!(player.next_move(&white, Duration::from_secs(10)), Move::Pass);
+    }
+
+    #[test]
+    fn pass_is_played_when_it_rates_best() {
+        let state = GameState::new(9);
+        let mut player = PolicyPlayer::new(|_: &GameState| {
+            let mut logits = vec![0.0; policy_len(9)];
+            logits[pass_index(9)] = 5.0;
+            logits
+        });
+        assert_eq!(player.next_move(&state, Duration::from_secs(10)), Move::Pass);
+    }
+
+    #[test]
+    fn best_legal_point_beats_pass() {
+        let mut state = GameState::new(9);
+        state.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+        let mut player = PolicyPlayer::new(|_: &GameState| {
+            let mut logits = vec![0.0; policy_len(9)];
+            // The occupied point rates best but isn't legal
+            logits[move_index(&Move::Place(Coord::new(4, 4)), 9).unwrap()] = 9.0;
+            logits[move_index(&Move::Place(Coord::new(2, 6)), 9).unwrap()] = 3.0;
+            logits[pass_index(9)] = 1.0;
+            logits
+        });
+        assert_eq!(player.next_move(&state, Duration::from_secs(10)), Move::Place(Coord::new(2, 6)));
+    }
+
+    #[test]
+    fn pass_takes_the_last_index() {
+        assert_eq!(policy_len(19), 362);
+        assert_eq!(move_index(&Move::Pass, 13), Some(169));
+        assert_eq!(move_index(&Move::Place(Coord::new(2, 1)), 9), Some(11));
+        assert_eq!(move_index(&Move::Place(Coord::new(9, 0)), 9), None);
+        assert_eq!(move_index(&Move::Resign, 9), None);
+        assert_eq!(p2pgo_core::policy::index_move(81, 9), Some(Move::Pass));
+        assert_eq!(p2pgo_core::policy::index_move(11, 9), Some(Move::Place(Coord::new(2, 1))));
+        assert_eq!(p2pgo_core::policy::index_move(82, 9), None);
+    }
+}
+
+mod resignation {
+    use std::time::Duration;
+    use p2pgo_core::analysis::{expected_score, ownership};
+    use p2pgo_core::engine::{NeuralConfig, PlayerBackend, PolicyPlayer, ResigningPlayer};
+    use p2pgo_core::policy::policy_len;
+    use p2pgo_core::{Color, Coord, GameState, Move};
+
+    /// White fills the board around single-point eyes; Black has nothing
+    fn hopeless_for_black() -> GameState {
+        let mut stones = Vec::new();
+        for y in 0..9 {
+            for x in 0..9 {
+                if x % 3 != 1 || y % 3 != 1 {
+                    stones.push((Coord::new(x, y), Color::White));
+                }
+            }
+        }
+        GameState::from_setup(9, &stones, Color::Black).unwrap()
+    }
+
+    /// Black's outlook from playouts, standing in for a value head
+    fn playout_value(state: &GameState) -> f32 {
+        let points = (state.board_size as usize * state.board_size as usize) as f32;
+        (expected_score(&ownership(state, 16), 5.5) / points).clamp(-1.0, 1.0)
+    }
+
+    fn engine() -> ResigningPlayer<PolicyPlayer<impl FnMut(&GameState) -> Vec<f32>>, fn(&GameState) -> f32> {
+        let policy = PolicyPlayer::new(|state: &GameState| vec![0.0; policy_len(state.board_size)]);
+        ResigningPlayer::new(policy, playout_value, NeuralConfig::default())
+    }
+
+    #[test]
+    fn hopeless_position_is_resigned_after_the_streak() {
+        let state = hopeless_for_black();
+        let mut player = engine();
+        let config = NeuralConfig::default();
+        for _ in 1..config.resign_after {
+            assert_eq!(player.next_move(&state, Duration::from_secs(10)), Move::Pass);
+        }
+        assert_eq!(player.next_move(&state, Duration::from_secs(10)), Move::Resign);
+    }
+
+    #[test]
+    fn winning_side_and_broken_streaks_play_on() {
+        let mut white = hopeless_for_black();
+        white.apply_move(Move::Pass).unwrap();
+        let mut player = engine();
+        for _ in 0..5 {
+            assert_ne!(player.next_move(&white, Duration::from_secs(10)), Move::Resign);
+        }
+
+        // One playable turn in between starts the count again
+        let lost = hopeless_for_black();
+        let even = GameState::new(9);
+        let mut player = engine();
+        let config = NeuralConfig::default();
+        for _ in 1..config.resign_after {
+            player.next_move(&lost, Duration::from_secs(10));
+        }
+        assert_ne!(player.next_move(&even, Duration::from_secs(10)), Move::Resign);
+        assert_ne!(player.next_move(&lost, Duration::from_secs(10)), Move::Resign);
+    }
+}
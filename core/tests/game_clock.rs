@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Main time, then byo-yomi periods refilled by each move.
+
+use std::time::{Duration, Instant};
+use p2pgo_core::game_clock::{GameClock, PlayerTime};
+use p2pgo_core::Color;
+
+const SEC: Duration = Duration::from_secs(1);
+
+#[test]
+fn main_time_runs_into_byo_yomi() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(60 * SEC, 10 * SEC, 3);
+    // Clocks start with the first move
+    clock.moved(Color::Black, start);
+    assert_eq!(clock.snapshot(start + 30 * SEC).white, PlayerTime { main_ms: 30_000, periods: 3, period_ms: 10_000 });
+
+    let snapshot = clock.snapshot(start + 64 * SEC);
+    assert_eq!(snapshot.running, Some(Color::White));
+    assert_eq!(snapshot.white, PlayerTime { main_ms: 0, periods: 3, period_ms: 6_000 });
+    assert_eq!(snapshot.black.main_ms, 60_000, "Black's clock is stopped");
+
+    // A full period used up is lost
+    assert_eq!(clock.snapshot(start + 75 * SEC).white, PlayerTime { main_ms: 0, periods: 2, period_ms: 5_000 });
+    assert_eq!(clock.flagged(start + 89 * SEC), None);
+    assert_eq!(clock.flagged(start + 90 * SEC), Some(Color::White));
+}
+
+#[test]
+fn moving_in_byo_yomi_refills_the_period() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(Duration::ZERO, 10 * SEC, 2);
+    clock.moved(Color::Black, start);
+    clock.moved(Color::White, start + 7 * SEC);
+    let snapshot = clock.snapshot(start + 7 * SEC);
+    assert_eq!(snapshot.white, PlayerTime { main_ms: 0, periods: 2, period_ms: 10_000 });
+    assert_eq!(snapshot.running, Some(Color::Black));
+}
+
+#[test]
+fn paused_clocks_stand_still() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(60 * SEC, Duration::ZERO, 0);
+    clock.moved(Color::Black, start);
+    clock.pause(start + 10 * SEC);
+    let snapshot = clock.snapshot(start + 100 * SEC);
+    assert!(snapshot.paused);
+    assert_eq!(snapshot.running, None);
+    assert_eq!(snapshot.white.main_ms, 50_000);
+
+    clock.resume(start + 100 * SEC);
+    assert_eq!(clock.snapshot(start + 105 * SEC).white.main_ms, 45_000);
+    assert_eq!(clock.flagged(start + 150 * SEC), Some(Color::White));
+}
+
+#[test]
+fn periods_used_between_snapshots_count_down() {
+    let start = Instant::now();
+    let mut clock = GameClock::new(10 * SEC, 5 * SEC, 3);
+    clock.moved(Color::Black, start);
+    let before = clock.snapshot(start + 12 * SEC);
+    assert_eq!(clock.snapshot(start + 14 * SEC).periods_used_since(&before), vec![]);
+
+    // Two periods gone between snapshots, then the last
+    let two_gone = clock.snapshot(start + 21 * SEC);
+    assert_eq!(two_gone.periods_used_since(&before), vec![(Color::White, 2), (Color::White, 1)]);
+    assert_eq!(two_gone.flagged(), None);
+    let out = clock.snapshot(start + 25 * SEC);
+    assert_eq!(out.periods_used_since(&two_gone), vec![(Color::White, 0)]);
+    assert_eq!(out.flagged(), Some(Color::White));
+}
@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::collections::HashSet;
+use p2pgo_core::scoring::{calculate_final_score, game_ended_event};
+use p2pgo_core::value_labeller::ScoringMethod;
+use p2pgo_core::{Color, Coord, EndReason, GameEvent, GameState, Move};
+
+fn play(state: &mut GameState, moves: &[Move]) -> Vec<GameEvent> {
+    moves.iter()
+        .flat_map(|mv| state.apply_move_events(mv.clone()).unwrap())
+        .collect()
+}
+
+#[test]
+fn double_pass_ends_the_game_unscored() {
+    let mut state = GameState::new(9);
+    let events = play(&mut state, &[Move::Place(Coord::new(4, 4)), Move::Pass, Move::Pass]);
+    assert_eq!(events.len(), 4);
+    assert!(matches!(events[2], GameEvent::MoveMade { mv: Move::Pass, by: Color::Black }));
+    assert!(matches!(
+        &events[3],
+        GameEvent::GameEnded { winner: None, reason: EndReason::DoublePass, scores: None, .. }
+    ));
+    assert_eq!(state.end_reason(), Some(EndReason::DoublePass));
+}
+
+#[test]
+fn resignation_names_the_other_player() {
+    let mut state = GameState::new(9);
+    let events = play(&mut state, &[Move::Place(Coord::new(4, 4)), Move::Resign]);
+    assert!(matches!(
+        events.last(),
+        Some(GameEvent::GameEnded { winner: Some(Color::Black), reason: EndReason::Resignation, .. })
+    ));
+
+    let proof = calculate_final_score(&state, 5.5, ScoringMethod::Resignation(Color::Black), &HashSet::new());
+    assert!(matches!(
+        game_ended_event(&proof),
+        GameEvent::GameEnded { winner: Some(Color::Black), reason: EndReason::Resignation, scores: None, .. }
+    ));
+}
+
+#[test]
+fn scored_game_carries_scores_and_margin() {
+    let mut state = GameState::new(9);
+    play(&mut state, &[Move::Place(Coord::new(4, 4)), Move::Pass, Move::Pass]);
+    let proof = calculate_final_score(&state, 5.5, ScoringMethod::Territory, &HashSet::new());
+    match game_ended_event(&proof) {
+        GameEvent::GameEnded { winner, score_diff, reason, scores: Some((black, white)) } => {
+            assert_eq!(reason, EndReason::DoublePass);
+            assert_eq!(white - black, score_diff);
+            assert_eq!(winner, Some(Color::White));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+}
@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Background work pauses for live games and heat, and rests each period.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use p2pgo_core::engine::{self, PlayerBackend};
+use p2pgo_core::governor::{Governor, GovernorConfig, GovernorStatus, PauseReason, ThermalPressure, Throttle};
+use p2pgo_core::{GameState, Move};
+
+fn config(rest_ms: u64) -> GovernorConfig {
+    GovernorConfig { parallelism: 2, period_ms: 1000, rest_ms }
+}
+
+/// Busy share of `total` for a task doing `unit` of work between checkpoints
+fn busy_share(throttle: &Throttle, start: Instant, unit: Duration, total: Duration) -> f64 {
+    let mut now = start;
+    let mut busy = Duration::ZERO;
+    while now < start + total {
+        match throttle.wait(now) {
+            Some(wait) => now += wait,
+            None => {
+                busy += unit;
+                now += unit;
+            }
+        }
+    }
+    busy.as_secs_f64() / total.as_secs_f64()
+}
+
+#[test]
+fn live_games_and_heat_pause_work_until_they_end() {
+    let start = Instant::now();
+    let mut throttle = Throttle::new(config(0), start);
+    assert_eq!(throttle.status(start), GovernorStatus::Running);
+    assert_eq!(throttle.wait(start), None);
+
+    throttle.set_live_game(true);
+    assert_eq!(throttle.status(start), GovernorStatus::Paused(PauseReason::LiveGame));
+    assert!(throttle.wait(start).is_some());
+    throttle.set_live_game(false);
+    assert_eq!(throttle.status(start), GovernorStatus::Running);
+
+    throttle.set_thermal(ThermalPressure::Moderate);
+    assert_eq!(throttle.paused(), None, "warm isn't throttled");
+    throttle.set_thermal(ThermalPressure::from_level(2));
+    assert_eq!(throttle.status(start), GovernorStatus::Paused(PauseReason::Thermal));
+    throttle.set_live_game(true);
+    assert_eq!(throttle.paused(), Some(PauseReason::LiveGame));
+    throttle.set_live_game(false);
+    throttle.set_thermal(ThermalPressure::Nominal);
+    assert_eq!(throttle.wait(start), None);
+    assert_eq!(GovernorStatus::Paused(PauseReason::LiveGame).to_string(), "paused: live game in progress");
+}
+
+#[test]
+fn duty_cycle_rests_at_the_end_of_each_period() {
+    let start = Instant::now();
+    let throttle = Throttle::new(config(200), start);
+    assert_eq!(throttle.status(start + Duration::from_millis(700)), GovernorStatus::Running);
+    assert_eq!(throttle.status(start + Duration::from_millis(850)), GovernorStatus::Resting);
+    assert_eq!(throttle.wait(start + Duration::from_millis(850)), Some(Duration::from_millis(150)));
+    assert_eq!(throttle.wait(start + Duration::from_millis(1000)), None);
+}
+
+#[test]
+fn duty_cycle_limits_measured_busy_time() {
+    let start = Instant::now();
+    let unit = Duration::from_millis(10);
+    let total = Duration::from_secs(60);
+
+    let share = busy_share(&Throttle::new(config(200), start), start, unit, total);
+    assert!((0.78..=0.81).contains(&share), "busy {:.3} of the time", share);
+
+    let mut warm = Throttle::new(config(200), start);
+    warm.set_thermal(ThermalPressure::Moderate);
+    let share = busy_share(&warm, start, unit, total);
+    assert!((0.58..=0.61).contains(&share), "busy {:.3} of the time when warm", share);
+
+    let share = busy_share(&Throttle::new(GovernorConfig::unlimited(), start), start, unit, total);
+    assert_eq!(share, 1.0);
+}
+
+#[test]
+fn checkpoint_holds_work_until_the_live_game_ends() {
+    let governor = Governor::new(config(0));
+    governor.set_live_game(true);
+    let worker = {
+        let governor = governor.clone();
+        std::thread::spawn(move || {
+            let started = Instant::now();
+            governor.checkpoint();
+            started.elapsed()
+        })
+    };
+    std::thread::sleep(Duration::from_millis(100));
+    assert!(!worker.is_finished(), "paused while the game is live");
+    governor.set_live_game(false);
+    assert!(worker.join().unwrap() >= Duration::from_millis(100));
+    assert_eq!(governor.status(), GovernorStatus::Running);
+}
+
+#[test]
+fn slots_cap_how_many_tasks_run_at_once() {
+    let governor = Governor::new(config(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..6).map(|_| {
+        let governor = governor.clone();
+        let peak = peak.clone();
+        std::thread::spawn(move || {
+            let _slot = governor.slot();
+            peak.fetch_max(governor.running(), Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+        })
+    }).collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert_eq!(governor.running(), 0);
+}
+
+struct Passer;
+
+impl PlayerBackend for Passer {
+    fn next_move(&mut self, _game: &GameState, _time: Duration) -> Move {
+        Move::Pass
+    }
+}
+
+#[test]
+fn governed_self_play_plays_the_same_game() {
+    let governor = Governor::new(GovernorConfig::unlimited());
+    let game = engine::self_play_governed(9, &mut Passer, &mut Passer, 10, &governor);
+    assert_eq!(game.moves, engine::self_play(9, &mut Passer, &mut Passer, 10).moves);
+    assert_eq!(governor.running(), 0);
+}
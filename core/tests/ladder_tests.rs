@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Ladder opponents, the opening book and game results.
+
+use std::time::Duration;
+use p2pgo_core::engine::{self, RandomPlayer};
+use p2pgo_core::ladder::{self, LADDER_BOARD_SIZE, RUNGS};
+use p2pgo_core::policy::policy_len;
+use p2pgo_core::{Color, Coord, GameState, Move};
+
+/// A policy with no opinion
+fn flat(state: &GameState) -> Vec<f32> {
+    vec![0.0; policy_len(state.board_size)]
+}
+
+#[test]
+fn every_rung_plays_legal_moves() {
+    for (i, rung) in RUNGS.iter().enumerate() {
+        let mut opponent = ladder::opponent(rung, 7, flat);
+        let mut random = RandomPlayer::new(11);
+        let game = engine::self_play(LADDER_BOARD_SIZE, &mut random, &mut *opponent, 6);
+        assert_eq!(game.moves.len(), 6, "{}", rung.name);
+        // Illegal moves turn into passes in self-play; none of these is one
+        assert!(game.moves.iter().skip(1).step_by(2).all(|mv| matches!(mv, Move::Place(_))), "rung {}: {:?}", i, game.moves);
+    }
+}
+
+#[test]
+fn same_seed_same_moves() {
+    let rung = &RUNGS[1];
+    let game = GameState::new(LADDER_BOARD_SIZE);
+    let first = ladder::opponent(rung, 3, flat).next_move(&game, Duration::MAX);
+    let again = ladder::opponent(rung, 3, flat).next_move(&game, Duration::MAX);
+    assert_eq!(first, again);
+}
+
+#[test]
+fn the_book_answers_the_first_moves() {
+    let mut game = GameState::new(LADDER_BOARD_SIZE);
+    assert_eq!(ladder::book_move(&game), Some(Move::Place(Coord::new(4, 4))));
+    game.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    assert_eq!(ladder::book_move(&game), Some(Move::Place(Coord::new(6, 2))));
+    game.apply_move(Move::Place(Coord::new(0, 0))).unwrap();
+    assert_eq!(ladder::book_move(&game), None);
+
+    // The top rung opens from the book whatever its policy says
+    let top = RUNGS.last().unwrap();
+    assert!(top.opening_book);
+    let mut opening = GameState::new(LADDER_BOARD_SIZE);
+    opening.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+    assert_eq!(ladder::opponent(top, 1, flat).next_move(&opening, Duration::MAX), Move::Place(Coord::new(6, 6)));
+}
+
+#[test]
+fn results_of_finished_games() {
+    let mut game = GameState::new(LADDER_BOARD_SIZE);
+    assert_eq!(ladder::winner(&game), None);
+    game.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    game.apply_move(Move::Resign).unwrap();
+    assert_eq!(ladder::winner(&game), Some(Color::Black));
+
+    // White's wall down the middle column leaves Black short of komi
+    let mut walled = GameState::new(LADDER_BOARD_SIZE);
+    for y in 0..LADDER_BOARD_SIZE {
+        walled.apply_move(Move::Place(Coord::new(3, y))).unwrap();
+        walled.apply_move(Move::Place(Coord::new(4, y))).unwrap();
+    }
+    walled.apply_move(Move::Pass).unwrap();
+    walled.apply_move(Move::Pass).unwrap();
+    assert_eq!(ladder::winner(&walled), Some(Color::White));
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Moving files older builds left in the working directory.
+
+use std::fs;
+use std::path::Path;
+use p2pgo_core::p2pgo_dirs::{self, Dirs, MIGRATED_MARKER};
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+/// Two archived games, a UI config and a log, as builds before the move
+/// left them
+fn legacy_layout(cwd: &Path) {
+    write(&cwd.join("finished_games/2024-01-01_vs_alice.cbor"), "game one");
+    write(&cwd.join("finished_games/2024-01-01_vs_alice.meta"), "summary one");
+    write(&cwd.join("finished_games/corrupt/2023-12-31_vs_bob.cbor"), "broken game");
+    write(&cwd.join("ui_config.json"), "{\"theme\":\"dark\"}");
+    write(&cwd.join("logs/p2pgo.2024-01-01.log"), "log line");
+}
+
+#[test]
+fn legacy_files_move_without_loss() {
+    let cwd = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    legacy_layout(cwd.path());
+    let dirs = Dirs::under(home.path());
+
+    let moved = p2pgo_dirs::migrate_legacy(cwd.path(), &dirs).unwrap();
+    assert_eq!(moved.len(), 5);
+    let archive = dirs.archive();
+    assert_eq!(read(&archive.join("2024-01-01_vs_alice.cbor")), "game one");
+    assert_eq!(read(&archive.join("2024-01-01_vs_alice.meta")), "summary one");
+    assert_eq!(read(&archive.join("corrupt/2023-12-31_vs_bob.cbor")), "broken game");
+    assert_eq!(read(&dirs.config.join("ui_config.json")), "{\"theme\":\"dark\"}");
+    assert_eq!(read(&dirs.logs.join("p2pgo.2024-01-01.log")), "log line");
+    // Nothing left behind
+    assert!(!cwd.path().join("finished_games").exists());
+    assert!(!cwd.path().join("ui_config.json").exists());
+    assert!(!cwd.path().join("logs").exists());
+}
+
+#[test]
+fn existing_files_are_not_overwritten() {
+    let cwd = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    legacy_layout(cwd.path());
+    let dirs = Dirs::under(home.path());
+    write(&dirs.config.join("ui_config.json"), "{\"theme\":\"light\"}");
+
+    let moved = p2pgo_dirs::migrate_legacy(cwd.path(), &dirs).unwrap();
+    assert_eq!(moved.len(), 4);
+    assert_eq!(read(&dirs.config.join("ui_config.json")), "{\"theme\":\"light\"}");
+    // The legacy copy stays for the user to sort out
+    assert_eq!(read(&cwd.path().join("ui_config.json")), "{\"theme\":\"dark\"}");
+}
+
+#[test]
+fn migration_runs_once() {
+    let cwd = tempfile::tempdir().unwrap();
+    let home = tempfile::tempdir().unwrap();
+    let dirs = Dirs::under(home.path());
+    assert!(p2pgo_dirs::migrate_legacy(cwd.path(), &dirs).unwrap().is_empty());
+    assert!(dirs.data.join(MIGRATED_MARKER).exists());
+
+    // Files appearing later are someone else's business
+    legacy_layout(cwd.path());
+    assert!(p2pgo_dirs::migrate_legacy(cwd.path(), &dirs).unwrap().is_empty());
+    assert!(cwd.path().join("ui_config.json").exists());
+}
+
+#[test]
+fn data_dir_holds_everything() {
+    let dirs = Dirs::under("/srv/p2pgo");
+    assert_eq!(dirs.config, Path::new("/srv/p2pgo"));
+    assert_eq!(dirs.data, Path::new("/srv/p2pgo"));
+    assert_eq!(dirs.archive(), Path::new("/srv/p2pgo/finished"));
+    assert_eq!(dirs.logs, Path::new("/srv/p2pgo/logs"));
+    assert_eq!(dirs.cache, Path::new("/srv/p2pgo/cache"));
+}
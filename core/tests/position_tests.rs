@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use p2pgo_core::board::Board;
+use p2pgo_core::position::Position;
+use p2pgo_core::rules::{RuleConfig, RuleValidator};
+use p2pgo_core::{Color, Coord, GameError, GameState, Move};
+use proptest::prelude::*;
+
+fn place(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+fn play_all(size: u8, moves: &[Move]) -> Position {
+    moves.iter().fold(Position::new(size), |pos, mv| {
+        pos.play(mv.clone()).unwrap_or_else(|e| panic!("{:?}: {}", mv, e))
+    })
+}
+
+fn all_coords(size: u8) -> impl Iterator<Item = Coord> {
+    (0..size).flat_map(move |y| (0..size).map(move |x| Coord::new(x, y)))
+}
+
+#[test]
+fn dense_round_trip_keeps_every_field() {
+    let mut state = GameState::from_setup(9, &[(Coord::new(4, 4), Color::White)], Color::Black).unwrap();
+    for mv in [place(2, 2), place(6, 6), Move::Pass, place(3, 3)] {
+        state.apply_move(mv).unwrap();
+    }
+    state.captures = (2, 1);
+
+    let dense = Position::from_dense(&state).unwrap().to_dense();
+    assert_eq!(dense.board, state.board);
+    assert_eq!(dense.moves, state.moves);
+    assert_eq!(dense.setup, state.setup);
+    assert_eq!(dense.current_player, state.current_player);
+    assert_eq!(dense.pass_count, state.pass_count);
+    assert_eq!(dense.captures, state.captures);
+
+    assert_eq!(dense.rule_config, state.rule_config);
+
+    assert_eq!(
+        Position::from_dense(&GameState::new(21)).unwrap_err(),
+        GameError::InvalidCoordinate
+    );
+}
+
+#[test]
+fn apply_move_matches_game_state() {
+    let moves = [place(0, 0), place(1, 0), place(0, 1), Move::Pass, place(8, 8), Move::Resign];
+    let mut state = GameState::new(9);
+    let mut pos = Position::new(9);
+    for mv in moves {
+        state.apply_move(mv.clone()).unwrap();
+        pos = pos.apply_move(mv).unwrap();
+    }
+    assert_eq!(pos.to_dense().board, state.board);
+    assert_eq!(pos.moves(), state.moves);
+    assert_eq!(pos.is_game_over(), state.is_game_over());
+
+    assert_eq!(pos.apply_move(place(0, 0)).unwrap_err(), GameError::OccupiedPosition);
+    assert_eq!(pos.apply_move(place(9, 0)).unwrap_err(), GameError::InvalidCoordinate);
+}
+
+#[test]
+fn branches_share_history_without_affecting_each_other() {
+    let root = play_all(9, &[place(2, 2), place(6, 6)]);
+    let a = root.play(place(4, 4)).unwrap();
+    let b = root.play(Move::Pass).unwrap();
+
+    assert_eq!(root.move_count(), 2);
+    assert_eq!(a.moves(), vec![place(2, 2), place(6, 6), place(4, 4)]);
+    assert_eq!(b.last_move(), Some(Move::Pass));
+    assert_eq!(root.get(Coord::new(4, 4)), None);
+    assert_eq!(a.get(Coord::new(4, 4)), Some(Color::Black));
+}
+
+#[test]
+fn long_histories_survive_branching() {
+    let moves: Vec<Move> = (0..40).map(|i| place(i % 19, i / 19)).collect();
+    let mut state = GameState::new(19);
+    let mut pos = Position::new(19);
+    let mut branch = None;
+    for (i, mv) in moves.iter().enumerate() {
+        state.apply_move(mv.clone()).unwrap();
+        pos = pos.apply_move(mv.clone()).unwrap();
+        if i == 20 {
+            branch = Some(pos.apply_move(Move::Resign).unwrap());
+        }
+    }
+    assert_eq!(pos.moves(), state.moves);
+    assert_eq!(pos.move_count(), 40);
+
+    let branch = branch.unwrap();
+    drop(pos);
+    assert_eq!(branch.moves().len(), 22);
+    assert_eq!(branch.moves()[..21], moves[..21]);
+    assert!(branch.is_game_over());
+}
+
+#[test]
+fn capture_removes_stones_and_counts_them() {
+    // Black surrounds the white pair at (3,3)-(4,3) and fills the last liberty
+    let pos = play_all(9, &[
+        place(2, 3), place(3, 3),
+        place(3, 2), place(4, 3),
+        place(4, 2), Move::Pass,
+        place(5, 3), Move::Pass,
+        place(4, 4), Move::Pass,
+        place(3, 4),
+    ]);
+    assert_eq!(pos.get(Coord::new(3, 3)), None);
+    assert_eq!(pos.get(Coord::new(4, 3)), None);
+    assert_eq!(pos.captures(), (2, 0));
+    assert_eq!(pos.count_stones_for(Color::White), 0);
+}
+
+#[test]
+fn self_capture_and_ko_are_rejected() {
+    // White's eye at (0,0)
+    let pos = play_all(9, &[Move::Pass, place(1, 0), Move::Pass, place(0, 1)]);
+    assert_eq!(pos.play(place(0, 0)).unwrap_err(), GameError::SelfCapture);
+
+    // Black takes the ko at (1,1); White may not retake at once
+    let ko = play_all(9, &[
+        place(1, 0), place(2, 0),
+        place(0, 1), place(3, 1),
+        place(1, 2), place(2, 2),
+        Move::Pass, place(1, 1),
+        place(2, 1),
+    ]);
+    assert_eq!(ko.get(Coord::new(1, 1)), None);
+    assert_eq!(ko.play(place(1, 1)).unwrap_err(), GameError::KoViolation);
+    // A position read back from its game keeps the ko
+    assert_eq!(
+        Position::from_dense(&ko.to_dense()).unwrap().play(place(1, 1)).unwrap_err(),
+        GameError::KoViolation
+    );
+    // After a move elsewhere the ko may be retaken
+    let later = ko.play(place(8, 8)).unwrap().play(place(8, 0)).unwrap();
+    assert!(later.play(place(1, 1)).is_ok());
+}
+
+#[test]
+fn finished_game_has_no_moves() {
+    let pos = play_all(9, &[Move::Pass, Move::Pass]);
+    assert!(pos.legal_moves().is_empty());
+    assert!(matches!(pos.check_move(&place(4, 4)), Err(GameError::InvalidMove(_))));
+}
+
+/// The reference implementation: a dense board and `RuleValidator`
+struct Reference {
+    board: Board,
+    previous: Board,
+    to_move: Color,
+}
+
+impl Reference {
+    fn legal_moves(&self) -> Vec<Coord> {
+        let validator = RuleValidator::new(&self.board, &self.previous);
+        all_coords(self.board.size())
+            .filter(|&c| validator.check_move(c, self.to_move).is_ok())
+            .collect()
+    }
+
+    fn play(&mut self, coord: Coord) {
+        let mut next = self.board.clone();
+        next.place(coord, self.to_move);
+        for c in RuleValidator::new(&next, &self.board).find_captures(coord) {
+            next.remove(c);
+        }
+        self.previous = std::mem::replace(&mut self.board, next);
+        self.to_move = self.to_move.opposite();
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig { cases: 64, ..ProptestConfig::default() })]
+
+    #[test]
+    fn random_games_agree_with_rule_validator(
+        size in 5u8..=9,
+        choices in prop::collection::vec(any::<u16>(), 0..80),
+    ) {
+        let mut reference = Reference { board: Board::new(size), previous: Board::new(size), to_move: Color::Black };
+        let mut pos = Position::new(size);
+        for choice in choices {
+            let legal = reference.legal_moves();
+            prop_assert_eq!(&pos.legal_moves(), &legal);
+            if legal.is_empty() {
+                break;
+            }
+            let coord = legal[choice as usize % legal.len()];
+            reference.play(coord);
+            pos = pos.play(Move::Place(coord)).unwrap();
+            for c in all_coords(size) {
+                prop_assert_eq!(pos.get(c), reference.board.get(c), "at {:?}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn random_games_agree_with_game_state(
+        size in 3u8..=9,
+        suicide_allowed in any::<bool>(),
+        choices in prop::collection::vec(any::<u16>(), 0..80),
+    ) {
+        let mut state = GameState::new(size);
+        state.rule_config = RuleConfig { suicide_allowed };
+        let mut previous = state.clone();
+        let mut pos = Position::new(size).with_rules(state.rule_config);
+        for choice in choices {
+            let legal: Vec<Coord> = all_coords(size)
+                .filter(|&c| state.check_move(&Move::Place(c), &previous).is_ok())
+                .collect();
+            prop_assert_eq!(&pos.legal_moves(), &legal);
+            let mv = match legal.len() {
+                0 => Move::Pass,
+                n => Move::Place(legal[choice as usize % n]),
+            };
+            previous = state.clone();
+            state.apply_move(mv.clone()).unwrap();
+            pos = pos.play(mv).unwrap();
+            prop_assert_eq!(pos.to_dense().board, state.board.clone());
+            prop_assert_eq!(pos.captures(), state.captures);
+            if state.is_game_over() {
+                break;
+            }
+            // Converting back and forth keeps the ko
+            prop_assert_eq!(Position::from_dense(&state).unwrap().legal_moves(), pos.legal_moves());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use p2pgo_core::puzzles::{extract_from_game, PuzzleKind, PuzzleOutcome};
+use p2pgo_core::{Color, Coord, GameState, Move};
+
+fn at(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+/// A game ending in a snapback in the top-left corner:
+///
+/// ```text
+///   . . W B
+///   W W W B
+///   B B B B
+/// ```
+///
+/// Black throws in at (1,0), White takes at (0,0), and Black retakes at
+/// (1,0), capturing five stones.
+fn snapback_game() -> GameState {
+    let mut game = GameState::new(9);
+    // Records keep the moves as played; captured stones are not removed
+    game.moves = vec![
+        at(3, 0), at(2, 0),
+        at(3, 1), at(2, 1),
+        at(3, 2), at(1, 1),
+        at(2, 2), at(0, 1),
+        at(1, 2), at(6, 6),
+        at(0, 2), at(7, 7),
+        at(1, 0), at(0, 0),
+        at(1, 0), Move::Pass,
+        Move::Pass,
+    ];
+    game
+}
+
+#[test]
+fn extracts_the_snapback() {
+    let puzzles = extract_from_game(&snapback_game());
+    // White's capture of the throw-in is retaken at once, so it is no puzzle
+    assert_eq!(puzzles.len(), 1);
+
+    let puzzle = &puzzles[0];
+    assert_eq!(puzzle.kind, PuzzleKind::Snapback);
+    assert_eq!(puzzle.move_number, 12);
+    assert_eq!(puzzle.to_play, Color::Black);
+    assert_eq!(puzzle.prompt(), "Black to play and capture");
+    assert_eq!(puzzle.solution, [Coord::new(1, 0), Coord::new(0, 0), Coord::new(1, 0)]);
+    assert_eq!(puzzle.target.len(), 4);
+    assert_eq!(puzzle.position.count_stones_for(Color::White), 6);
+    assert_eq!(puzzle.position.count_stones_for(Color::Black), 6);
+}
+
+#[test]
+fn accepts_the_solution_and_rejects_a_wrong_move() {
+    let puzzle = extract_from_game(&snapback_game()).remove(0);
+    let throw_in = Coord::new(1, 0);
+
+    assert_eq!(puzzle.check(&[]), PuzzleOutcome::Correct { reply: None });
+    assert_eq!(puzzle.check(&[throw_in]), PuzzleOutcome::Correct { reply: Some(Coord::new(0, 0)) });
+    assert_eq!(puzzle.check(&[throw_in, throw_in]), PuzzleOutcome::Solved);
+
+    assert!(matches!(puzzle.check(&[Coord::new(4, 4)]), PuzzleOutcome::Wrong { .. }));
+    assert!(matches!(puzzle.check(&[throw_in, Coord::new(4, 0)]), PuzzleOutcome::Wrong { .. }));
+    // Occupied points are illegal
+    assert!(matches!(puzzle.check(&[Coord::new(2, 0)]), PuzzleOutcome::Wrong { reason } if reason.starts_with("Illegal")));
+}
+
+#[test]
+fn position_follows_the_line() {
+    let puzzle = extract_from_game(&snapback_game()).remove(0);
+    let throw_in = Coord::new(1, 0);
+
+    // White's reply took the throw-in stone
+    let after = puzzle.position_after(&[throw_in]);
+    assert_eq!(after.current_player, Color::Black);
+    assert_eq!(after.board[Coord::new(1, 0).to_index(9)], None);
+    assert_eq!(after.board[Coord::new(0, 0).to_index(9)], Some(Color::White));
+
+    let solved = puzzle.position_after(&[throw_in, throw_in]);
+    assert_eq!(solved.count_stones_for(Color::White), 2);
+}
+
+#[test]
+fn quiet_games_have_no_puzzles() {
+    let mut game = GameState::new(9);
+    game.moves = vec![at(2, 2), at(6, 6), at(2, 6), at(6, 2), Move::Pass, Move::Pass];
+    assert!(extract_from_game(&game).is_empty());
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Runs from the same seed are identical, down to the archived bytes.
+
+use std::path::Path;
+use p2pgo_core::archiver;
+use p2pgo_core::engine::{self_play, RandomPlayer};
+use p2pgo_core::rng::{self, RngCore};
+use p2pgo_core::GameState;
+
+/// Two self-play games per run, each seeded from `run_seed`, archived to `dir`
+fn run(dir: &Path, run_seed: u64) -> Vec<GameState> {
+    (0..2)
+        .map(|game| {
+            let seed = rng::game_seed(run_seed, game);
+            // Both sides share the game's seed; they never play the same move number
+            let state = self_play(9, &mut RandomPlayer::new(seed), &mut RandomPlayer::new(seed), 200);
+            archiver::archive_game_in(dir, &state, &format!("selfplay{}", game)).unwrap();
+            state
+        })
+        .collect()
+}
+
+/// Every file in `dir` with its contents, by name
+fn contents(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            (path.file_name().unwrap().to_string_lossy().into_owned(), std::fs::read(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+#[test]
+fn same_seed_same_archive() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let games = run(first.path(), 42);
+    run(second.path(), 42);
+
+    assert!(games.iter().all(|game| game.moves.len() > 20));
+    assert_ne!(games[0].moves, games[1].moves);
+    let archived = contents(first.path());
+    assert_eq!(archived.len(), 4, "a record and a summary per game");
+    assert_eq!(archived, contents(second.path()));
+}
+
+#[test]
+fn other_seeds_other_games() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    assert_ne!(run(first.path(), 1)[0].moves, run(second.path(), 2)[0].moves);
+}
+
+#[test]
+fn streams_depend_only_on_their_seeds() {
+    let game = rng::game_seed(7, 3);
+    assert_eq!(game, rng::game_seed(7, 3));
+    assert_ne!(game, rng::game_seed(7, 4));
+    assert_ne!(game, rng::game_seed(8, 3));
+
+    let mut a = rng::move_stream(game, 10);
+    let mut b = rng::move_stream(game, 10);
+    assert_eq!(a.next_u64(), b.next_u64());
+    assert_ne!(rng::move_stream(game, 10).next_u64(), rng::move_stream(game, 11).next_u64());
+    assert_eq!(rng::from_seed(5), rng::from_seed(5));
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Property tests for capture, suicide and ko handling in `RuleValidator`,
+//! and for taking moves back on a `GameState`.
+
+use p2pgo_core::{board::Board, rules::{RuleConfig, RuleValidator}, Color, Coord, GameState, Move};
+use proptest::prelude::*;
+use std::collections::HashSet;
+
+/// A random game prefix on a small board.
+///
+/// Each choice picks one of the legal moves at that step (modulo the number
+/// of legal moves), so shrinking the vector or its values always yields
+/// another legal game.
+#[derive(Debug, Clone)]
+struct GamePrefix {
+    size: u8,
+    choices: Vec<u16>,
+}
+
+impl Arbitrary for GamePrefix {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+    
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (5u8..=9, prop::collection::vec(any::<u16>(), 0..80))
+            .prop_map(|(size, choices)| GamePrefix { size, choices })
+            .boxed()
+    }
+}
+
+/// Board plus the position before the last move, for ko checks
+struct Sim {
+    board: Board,
+    previous: Board,
+    to_move: Color,
+    moves: Vec<Move>,
+}
+
+impl Sim {
+    fn new(size: u8) -> Self {
+        Self {
+            board: Board::new(size),
+            previous: Board::new(size),
+            to_move: Color::Black,
+            moves: Vec::new(),
+        }
+    }
+    
+    fn legal_moves(&self) -> Vec<Coord> {
+        let validator = RuleValidator::new(&self.board, &self.previous);
+        all_coords(self.board.size())
+            .filter(|&c| validator.check_move(c, self.to_move).is_ok())
+            .collect()
+    }
+    
+    /// Play a stone and remove captures, returning the captured stones
+    fn play(&mut self, coord: Coord) -> Vec<Coord> {
+        let mut next = self.board.clone();
+        next.place(coord, self.to_move);
+        let captures = RuleValidator::new(&next, &self.board).find_captures(coord);
+        for c in &captures {
+            next.remove(*c);
+        }
+        self.previous = std::mem::replace(&mut self.board, next);
+        self.to_move = self.to_move.opposite();
+        self.moves.push(Move::Place(coord));
+        captures
+    }
+    
+    fn pass(&mut self) {
+        self.previous = self.board.clone();
+        self.to_move = self.to_move.opposite();
+        self.moves.push(Move::Pass);
+    }
+}
+
+fn all_coords(size: u8) -> impl Iterator<Item = Coord> {
+    (0..size).flat_map(move |y| (0..size).map(move |x| Coord::new(x, y)))
+}
+
+fn stone_count(board: &Board) -> usize {
+    all_coords(board.size()).filter(|&c| board.get(c).is_some()).count()
+}
+
+fn group_at(board: &Board, start: Coord) -> Vec<Coord> {
+    let color = board.get(start);
+    let mut seen = HashSet::new();
+    let mut stack = vec![start];
+    while let Some(c) = stack.pop() {
+        if !seen.insert(c) {
+            continue;
+        }
+        for n in board.adjacent_coords(c) {
+            if board.get(n) == color && !seen.contains(&n) {
+                stack.push(n);
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+fn same_position(a: &Board, b: &Board) -> bool {
+    all_coords(a.size()).all(|c| a.get(c) == b.get(c))
+}
+
+/// Replay `choices`, checking the invariants after every move
+fn check_prefix(prefix: &GamePrefix) -> Result<(), TestCaseError> {
+    let mut sim = Sim::new(prefix.size);
+    
+    for &choice in &prefix.choices {
+        let legal = sim.legal_moves();
+        if legal.is_empty() {
+            sim.pass();
+            continue;
+        }
+        
+        let coord = legal[choice as usize % legal.len()];
+        let before = sim.board.clone();
+        let stones_before = stone_count(&before);
+        let captures = sim.play(coord);
+        
+        // Capture count equals stones removed
+        let unique: HashSet<_> = captures.iter().copied().collect();
+        prop_assert_eq!(unique.len(), captures.len(), "capture list has duplicates");
+        prop_assert_eq!(stone_count(&sim.board), stones_before + 1 - captures.len());
+        
+        // No group on the board is left without liberties
+        for c in all_coords(prefix.size) {
+            if sim.board.get(c).is_some() {
+                let group = group_at(&sim.board, c);
+                prop_assert!(
+                    RuleValidator::liberties(&sim.board, &group) > 0,
+                    "group at {:?} has no liberties after {:?}", c, sim.moves
+                );
+            }
+        }
+        
+        // A single-stone capture never recreates the position before it (ko)
+        if captures.len() == 1 {
+            prop_assert!(!same_position(&sim.board, &before));
+        }
+    }
+    
+    // Replaying the move list from scratch reproduces the same board
+    let mut replay = Sim::new(prefix.size);
+    for mv in &sim.moves {
+        match mv {
+            Move::Place(coord) => {
+                prop_assert!(replay.legal_moves().contains(coord), "{:?} illegal on replay", coord);
+                replay.play(*coord);
+            }
+            _ => replay.pass(),
+        }
+    }
+    prop_assert!(same_position(&replay.board, &sim.board));
+    prop_assert_eq!(replay.to_move, sim.to_move);
+    
+    Ok(())
+}
+
+/// Play `prefix` on a `GameState`, checking after every legal move that
+/// taking it back gives the position before it
+fn check_undo(prefix: &GamePrefix, rules: RuleConfig) -> Result<(), TestCaseError> {
+    let mut state = GameState::new(prefix.size);
+    state.rule_config = rules;
+    for &choice in &prefix.choices {
+        let legal = state.legal_moves();
+        let mv = match legal.len() {
+            0 => Move::Pass,
+            n => Move::Place(legal[choice as usize % n]),
+        };
+        let before = state.clone();
+        state.apply_move(mv.clone())?;
+
+        let mut undone = state.clone();
+        prop_assert_eq!(undone.undo(1)?, vec![mv]);
+        prop_assert_eq!(&undone.board, &before.board);
+        prop_assert_eq!(undone.current_player, before.current_player);
+        prop_assert_eq!(undone.captures, before.captures);
+        prop_assert_eq!(undone.pass_count, before.pass_count);
+        prop_assert_eq!(&undone.moves, &before.moves);
+        prop_assert_eq!(undone.rule_config, before.rule_config);
+        if state.is_game_over() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    // Bounded so the suite stays well under 30 seconds in CI
+    #![proptest_config(ProptestConfig {
+        cases: 128,
+        max_shrink_iters: 512,
+        ..ProptestConfig::default()
+    })]
+    
+    #[test]
+    fn random_games_keep_invariants(prefix in any::<GamePrefix>()) {
+        check_prefix(&prefix)?;
+    }
+    
+    #[test]
+    fn undo_of_apply_is_identity(prefix in any::<GamePrefix>(), suicide_allowed in any::<bool>()) {
+        check_undo(&prefix, RuleConfig { suicide_allowed })?;
+    }
+}
+
+/// Previously interesting prefixes, replayed on every run
+#[test]
+fn seed_corpus_replays() {
+    let corpus = [
+        // Corner capture on the smallest board
+        GamePrefix { size: 5, choices: vec![0, 1, 5, 0, 0, 0, 0] },
+        // One group touching the capturing stone twice was reported twice
+        GamePrefix { size: 5, choices: (0..60).collect() },
+        // Alternating high/low picks produce repeated captures
+        GamePrefix { size: 7, choices: (0..70).map(|i| if i % 2 == 0 { i } else { u16::MAX - i }).collect() },
+    ];
+    
+    for prefix in &corpus {
+        check_prefix(prefix).unwrap_or_else(|e| panic!("{:?} failed: {}", prefix, e));
+    }
+}
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use p2pgo_core::{board::Board, rules::RuleValidator, Color, Coord, GameError};
+use p2pgo_core::engine::{PlayerBackend, PolicyPlayer};
+use p2pgo_core::position::Position;
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::{board::Board, rules::RuleValidator, Color, Coord, GameError, GameState, Move};
 
 #[test]
 fn ko_detection() {
@@ -91,3 +94,175 @@ fn board_creation() {
     assert_eq!(board.size(), 19);
     assert_eq!(board.get(Coord::new(0, 0)), None);
 }
+
+#[test]
+fn game_state_check_move() {
+    let mut state = GameState::new(9);
+    state.apply_move(Move::Place(Coord::new(1, 0))).unwrap();
+    state.apply_move(Move::Place(Coord::new(8, 8))).unwrap();
+    let previous = state.clone();
+    state.apply_move(Move::Place(Coord::new(0, 1))).unwrap();
+
+    // White may not play into the corner Black just closed off
+    assert_eq!(state.check_move(&Move::Place(Coord::new(0, 0)), &previous), Err(GameError::SelfCapture));
+    assert_eq!(state.check_move(&Move::Place(Coord::new(0, 1)), &previous), Err(GameError::OccupiedPosition));
+    assert_eq!(state.check_move(&Move::Place(Coord::new(4, 4)), &previous), Ok(()));
+    assert_eq!(state.check_move(&Move::Pass, &previous), Ok(()));
+    // The stone is on the board
+    assert_eq!(state.board[Coord::new(0, 1).to_index(9)], Some(Color::Black));
+}
+
+#[test]
+fn game_state_removes_captured_stones() {
+    let mut state = GameState::new(9);
+    // Black surrounds White's stone at (4,4) and takes it with the fourth
+    for (x, y) in [(3, 4), (4, 4), (5, 4), (0, 0), (4, 3), (0, 1), (4, 5)] {
+        state.apply_move(Move::Place(Coord::new(x, y))).unwrap();
+    }
+    assert_eq!(state.board[Coord::new(4, 4).to_index(9)], None);
+    assert_eq!(state.captures, (1, 0));
+}
+
+/// Black has just taken the white stone at (1,1) with a stone at (2,1)
+/// that White could retake at once
+fn black_took_the_ko() -> GameState {
+    let stones = [
+        (Coord::new(1, 0), Color::Black),
+        (Coord::new(0, 1), Color::Black),
+        (Coord::new(1, 2), Color::Black),
+        (Coord::new(2, 0), Color::White),
+        (Coord::new(1, 1), Color::White),
+        (Coord::new(3, 1), Color::White),
+        (Coord::new(2, 2), Color::White),
+    ];
+    let mut state = GameState::from_setup(9, &stones, Color::Black).unwrap();
+    state.apply_move(Move::Place(Coord::new(2, 1))).unwrap();
+    state
+}
+
+#[test]
+fn legal_moves_leave_out_the_ko_retake_and_suicide() {
+    let state = black_took_the_ko();
+    let legal = state.legal_moves();
+    assert!(!legal.contains(&Coord::new(1, 1)), "ko retake listed");
+    // White at (0,0) would have no liberties and take nothing
+    assert!(!legal.contains(&Coord::new(0, 0)), "suicide listed");
+    assert!(!legal.contains(&Coord::new(2, 1)), "occupied point listed");
+    assert!(legal.contains(&Coord::new(4, 4)));
+    assert_eq!(legal.len(), 81 - 7 - 2);
+
+    // Once White has played elsewhere, the ko may be retaken
+    let mut later = state.clone();
+    later.apply_move(Move::Place(Coord::new(6, 6))).unwrap();
+    later.apply_move(Move::Place(Coord::new(6, 2))).unwrap();
+    assert!(later.legal_moves().contains(&Coord::new(1, 1)));
+    assert_eq!(later.previous_position().board[Coord::new(6, 6).to_index(9)], Some(Color::White));
+}
+
+const SUICIDE_ALLOWED: RuleConfig = RuleConfig { suicide_allowed: true };
+
+/// Black to play at (1,0), next to its stone in the corner, where the pair
+/// would have no liberties; the corner of the lower edge is a one-point
+/// suicide for Black
+fn two_stone_suicide(rules: RuleConfig) -> GameState {
+    let stones = [
+        (Coord::new(0, 0), Color::Black),
+        (Coord::new(2, 0), Color::White),
+        (Coord::new(0, 1), Color::White),
+        (Coord::new(1, 1), Color::White),
+        (Coord::new(1, 8), Color::White),
+        (Coord::new(0, 7), Color::White),
+    ];
+    let mut state = GameState::from_setup(9, &stones, Color::Black).unwrap();
+    state.rule_config = rules;
+    state
+}
+
+#[test]
+fn two_stone_suicide_needs_the_permissive_rule() {
+    let suicide = Move::Place(Coord::new(1, 0));
+    let state = two_stone_suicide(RuleConfig::default());
+    assert_eq!(state.check_move(&suicide, &state), Err(GameError::SelfCapture));
+    assert!(!state.legal_moves().contains(&Coord::new(1, 0)));
+
+    let mut state = two_stone_suicide(SUICIDE_ALLOWED);
+    assert_eq!(state.check_move(&suicide, &state), Ok(()));
+    assert!(state.legal_moves().contains(&Coord::new(1, 0)));
+    state.apply_move(suicide).unwrap();
+    assert_eq!(state.board[0], None);
+    assert_eq!(state.board[1], None);
+    assert_eq!(state.captures, (0, 2), "White is credited with the pair");
+    assert_eq!(state.current_player, Color::White);
+}
+
+#[test]
+fn single_stone_suicide_stays_illegal() {
+    let corner = Move::Place(Coord::new(0, 8));
+    for rules in [RuleConfig::default(), SUICIDE_ALLOWED] {
+        let state = two_stone_suicide(rules);
+        assert_eq!(state.check_move(&corner, &state), Err(GameError::SelfCapture), "{:?}", rules);
+        assert!(!state.legal_moves().contains(&Coord::new(0, 8)));
+    }
+
+    let mut board = Board::new(9);
+    board.place(Coord::new(1, 0), Color::White);
+    board.place(Coord::new(0, 1), Color::White);
+    let validator = RuleValidator::new(&board, &board).with_config(SUICIDE_ALLOWED);
+    assert_eq!(validator.check_move(Coord::new(0, 0), Color::Black), Err(GameError::SelfCapture));
+}
+
+#[test]
+fn captures_come_before_suicide() {
+    // Black's pair at (1,0) would have no liberties, but it takes the
+    // White stone at (2,0) first and lives
+    let stones = [
+        (Coord::new(0, 0), Color::Black),
+        (Coord::new(3, 0), Color::Black),
+        (Coord::new(2, 1), Color::Black),
+        (Coord::new(2, 0), Color::White),
+        (Coord::new(0, 1), Color::White),
+        (Coord::new(1, 1), Color::White),
+    ];
+    let mut state = GameState::from_setup(9, &stones, Color::Black).unwrap();
+    state.rule_config = SUICIDE_ALLOWED;
+    state.apply_move(Move::Place(Coord::new(1, 0))).unwrap();
+    assert_eq!(state.board[1], Some(Color::Black));
+    assert_eq!(state.board[2], None);
+    assert_eq!(state.captures, (1, 0));
+}
+
+#[test]
+fn positions_and_engines_follow_the_rules() {
+    let suicide = Move::Place(Coord::new(1, 0));
+    let strict = Position::from_dense(&two_stone_suicide(RuleConfig::default())).unwrap();
+    assert_eq!(strict.play(suicide.clone()).unwrap_err(), GameError::SelfCapture);
+
+    let permissive = Position::from_dense(&two_stone_suicide(SUICIDE_ALLOWED)).unwrap();
+    assert_eq!(permissive.rules(), SUICIDE_ALLOWED);
+    assert!(permissive.legal_moves().contains(&Coord::new(1, 0)));
+    let after = permissive.play(suicide).unwrap();
+    assert_eq!(after.get(Coord::new(0, 0)), None);
+    assert_eq!(after.get(Coord::new(1, 0)), None);
+    assert_eq!(after.captures(), (0, 2));
+    assert_eq!(after.to_dense().rule_config, SUICIDE_ALLOWED);
+
+    // A policy that only wants the suicide point plays it only when allowed
+    let mut player = PolicyPlayer::new(|_: &GameState| {
+        let mut logits = vec![-10.0; 82];
+        logits[1] = 10.0;
+        logits
+    });
+    let time = std::time::Duration::from_secs(1);
+    assert_ne!(player.next_move(&two_stone_suicide(RuleConfig::default()), time), Move::Place(Coord::new(1, 0)));
+    assert_eq!(player.next_move(&two_stone_suicide(SUICIDE_ALLOWED), time), Move::Place(Coord::new(1, 0)));
+}
+
+#[test]
+fn undo_keeps_the_rules() {
+    let mut state = two_stone_suicide(SUICIDE_ALLOWED);
+    state.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    state.apply_move(Move::Place(Coord::new(5, 5))).unwrap();
+    state.undo(1).unwrap();
+    assert_eq!(state.rule_config, SUICIDE_ALLOWED);
+    assert_eq!(state.previous_position().rule_config, SUICIDE_ALLOWED);
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Score proofs recount from the final position they carry.
+
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::{ScoreMismatch, ScoreProof, ScoringMethod};
+use p2pgo_core::{Color, Coord, GameState};
+use std::collections::HashSet;
+
+/// Black walls in one point of territory; White has a dead stone outside
+fn final_state() -> GameState {
+    let mut state = GameState::new(9);
+    for (x, y) in [(3, 4), (5, 4), (4, 3), (4, 5)] {
+        state.board[Coord::new(x, y).to_index(9)] = Some(Color::Black);
+    }
+    state.board[Coord::new(7, 7).to_index(9)] = Some(Color::White);
+    state.captures = (2, 1);
+    state
+}
+
+fn agreed_proof(state: &GameState) -> ScoreProof {
+    let dead: HashSet<Coord> = [Coord::new(7, 7)].into_iter().collect();
+    calculate_final_score(state, 6.5, ScoringMethod::Territory, &dead)
+}
+
+#[test]
+fn agreed_proofs_verify() {
+    let state = final_state();
+    let proof = agreed_proof(&state);
+    assert!(proof.territory_black > 0);
+    assert_eq!(proof.verify_against(&state), Ok(()));
+    assert_eq!(proof.verify(), Ok(()));
+}
+
+#[test]
+fn tampered_territory_is_rejected() {
+    let state = final_state();
+    let mut proof = agreed_proof(&state);
+    let counted = proof.territory_black;
+    proof.territory_black += 10;
+    proof.final_score += 10;
+    let expected = ScoreMismatch::Territory { color: Color::Black, claimed: counted + 10, actual: counted };
+    assert_eq!(proof.verify_against(&state), Err(expected.clone()));
+    assert_eq!(proof.verify(), Err(expected));
+}
+
+#[test]
+fn tampered_score_and_captures_are_rejected() {
+    let state = final_state();
+    let mut proof = agreed_proof(&state);
+    proof.final_score = 30;
+    assert!(matches!(proof.verify(), Err(ScoreMismatch::Score { claimed: 30, .. })));
+
+    let mut proof = agreed_proof(&state);
+    proof.captures_black = 9;
+    assert!(matches!(proof.verify_against(&state), Err(ScoreMismatch::Captures { .. })));
+}
+
+#[test]
+fn dead_stones_must_be_stones() {
+    let state = final_state();
+    let mut proof = agreed_proof(&state);
+    proof.final_position.as_mut().unwrap().dead_stones.push(Coord::new(0, 0));
+    assert_eq!(proof.verify(), Err(ScoreMismatch::DeadStone(Coord::new(0, 0))));
+
+    let mut proof = agreed_proof(&state);
+    proof.final_position.as_mut().unwrap().dead_stones.push(Coord::new(20, 20));
+    assert_eq!(proof.verify(), Err(ScoreMismatch::DeadStone(Coord::new(20, 20))));
+}
+
+#[test]
+fn proofs_for_another_position_are_rejected() {
+    let state = final_state();
+    let proof = agreed_proof(&state);
+    let mut other = state.clone();
+    other.board[0] = Some(Color::White);
+    assert_eq!(proof.verify_against(&other), Err(ScoreMismatch::Position));
+
+    let mut proof = agreed_proof(&state);
+    proof.final_position.as_mut().unwrap().board[0] = Some(Color::White);
+    assert_eq!(proof.verify(), Err(ScoreMismatch::Position));
+}
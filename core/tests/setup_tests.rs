@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::{Color, Coord, GameError, GameState, Move};
+
+fn black(x: u8, y: u8) -> (Coord, Color) {
+    (Coord::new(x, y), Color::Black)
+}
+
+fn white(x: u8, y: u8) -> (Coord, Color) {
+    (Coord::new(x, y), Color::White)
+}
+
+#[test]
+fn setup_places_stones_and_player_to_move() {
+    let state = GameState::from_setup(9, &[black(2, 2), white(3, 3)], Color::White).unwrap();
+    assert_eq!(state.board[Coord::new(2, 2).to_index(9)], Some(Color::Black));
+    assert_eq!(state.board[Coord::new(3, 3).to_index(9)], Some(Color::White));
+    assert_eq!(state.current_player, Color::White);
+    assert!(state.moves.is_empty());
+}
+
+#[test]
+fn setup_rejects_groups_without_liberties() {
+    // A white corner stone surrounded by black
+    let dead = [white(0, 0), black(1, 0), black(0, 1)];
+    assert!(matches!(
+        GameState::from_setup(9, &dead, Color::Black),
+        Err(GameError::InvalidMove(_))
+    ));
+
+    // Black's wall still has liberties, so only white's stone is the problem
+    let atari = [white(0, 0), black(1, 0)];
+    assert!(GameState::from_setup(9, &atari, Color::Black).is_ok());
+
+    assert_eq!(
+        GameState::from_setup(9, &[black(2, 2), white(2, 2)], Color::Black).unwrap_err(),
+        GameError::OccupiedPosition
+    );
+    assert_eq!(
+        GameState::from_setup(9, &[black(9, 0)], Color::Black).unwrap_err(),
+        GameError::InvalidCoordinate
+    );
+}
+
+#[test]
+fn sgf_export_writes_setup_stones() {
+    let mut state = GameState::from_setup(9, &[black(2, 2), black(6, 2), white(4, 4)], Color::White).unwrap();
+    state.apply_move(Move::Place(Coord::new(4, 6))).unwrap();
+
+    let sgf = SgfProcessor::new(state.clone()).with_komi(6.5).generate();
+    assert!(sgf.contains("AB[cc][gc]"), "{}", sgf);
+    assert!(sgf.contains("AW[ee]"), "{}", sgf);
+    assert!(sgf.contains("PL[W]"), "{}", sgf);
+    assert!(sgf.contains("KM[6.5]"), "{}", sgf);
+    assert!(sgf.contains(";W[eg]"), "{}", sgf);
+
+    let parsed = SgfProcessor::new(GameState::new(9)).parse(&sgf).unwrap();
+    assert_eq!(parsed.setup, state.setup);
+    assert_eq!(parsed.board, state.board);
+    assert_eq!(parsed.current_player, Color::Black);
+    assert_eq!(parsed.initial_position().current_player, Color::White);
+}
+
+#[test]
+fn handicap_stones_are_set_up_with_white_to_move() {
+    use p2pgo_core::settings::GameSettings;
+
+    let settings = GameSettings { handicap: 5, ..GameSettings::standard(19) };
+    let state = settings.new_game();
+    let stones: Vec<_> = state.board.iter().enumerate().filter(|(_, c)| c.is_some()).map(|(i, _)| i).collect();
+    assert_eq!(stones.len(), 5);
+    assert_eq!(state.board[Coord::new(9, 9).to_index(19)], Some(Color::Black));
+    assert_eq!(state.board[Coord::new(3, 3).to_index(19)], Some(Color::Black));
+    assert_eq!(state.current_player, Color::White);
+
+    // White is paid back a point per stone under Chinese rules
+    assert_eq!(settings.scoring_komi(), settings.komi + 5.0);
+    assert_eq!(GameSettings::standard(9).new_game().setup.len(), 0);
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Importing a folder of SGF files into the archive.
+
+use std::path::PathBuf;
+use p2pgo_core::archiver::{self, ImportOptions, ImportProgress, IMPORTED_TAG};
+use p2pgo_core::{Coord, GameState, Move};
+
+/// Two games, a copy of one of them, a truncated file, a 7x7 game and a
+/// README
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sgf_import")
+}
+
+#[test]
+fn import_reports_every_file() {
+    let archive = tempfile::tempdir().unwrap();
+    let mut seen = Vec::new();
+    let report = archiver::import_sgf_dir_with(&fixtures(), &ImportOptions::new(archive.path()), |p: &ImportProgress| {
+        seen.push((p.done, p.total));
+    })
+    .unwrap();
+
+    assert_eq!(report.imported.len(), 2, "{:?}", report);
+    assert_eq!(report.duplicates, vec!["club_9x9_copy.sgf".to_string()]);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].0, "tiny_7x7.sgf");
+    assert!(report.skipped[0].1.contains("7x7"), "{}", report.skipped[0].1);
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, "truncated.sgf");
+    assert_eq!(report.summary(), "Imported 2 games, 1 duplicate left out, 1 unsupported board skipped, 1 file failed");
+
+    // One step per SGF file; the README isn't one
+    assert_eq!(seen, (1..=5).map(|done| (done, 5)).collect::<Vec<_>>());
+
+    let games = archiver::list_games_in(archive.path()).unwrap();
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(|g| g.tags == vec![IMPORTED_TAG.to_string()] && g.training_eligible));
+    let club = games.iter().find(|g| g.board_size == 9).unwrap();
+    assert_eq!(club.date, "2023-05-01");
+    assert_eq!(club.result.as_deref(), Some("B+3.5"));
+    assert_eq!(club.move_count, 5);
+    // A game without a result still comes in
+    let thirteen = games.iter().find(|g| g.board_size == 13).unwrap();
+    assert_eq!(thirteen.result, None);
+    assert_eq!(thirteen.date, "2024-02-29");
+
+    let game = archiver::load_game(archive.path(), &club.id).unwrap();
+    assert_eq!(game.moves[0], Move::Place(Coord::new(4, 4)));
+}
+
+#[test]
+fn importing_again_adds_nothing() {
+    let archive = tempfile::tempdir().unwrap();
+    let options = ImportOptions { training_eligible: false, ..ImportOptions::new(archive.path()) };
+    archiver::import_sgf_dir(&fixtures(), &options).unwrap();
+    assert!(archiver::list_games_in(archive.path()).unwrap().iter().all(|g| !g.training_eligible));
+
+    let again = archiver::import_sgf_dir(&fixtures(), &options).unwrap();
+    assert!(again.imported.is_empty());
+    assert_eq!(again.duplicates.len(), 3);
+    assert_eq!(archiver::list_games_in(archive.path()).unwrap().len(), 2);
+}
+
+#[test]
+fn games_played_here_count_as_duplicates() {
+    let archive = tempfile::tempdir().unwrap();
+    let mut game = GameState::new(9);
+    for (x, y) in [(4, 4), (2, 2), (6, 2), (2, 6), (6, 6)] {
+        game.apply_move(Move::Place(Coord::new(x, y))).unwrap();
+    }
+    archiver::archive_game_in(archive.path(), &game, "Bo").unwrap();
+
+    let report = archiver::import_sgf_dir(&fixtures(), &ImportOptions::new(archive.path())).unwrap();
+    assert_eq!(report.imported.len(), 1);
+    assert_eq!(report.duplicates.len(), 2);
+}
+
+#[test]
+fn hash_ignores_everything_but_the_moves_and_size() {
+    let mut a = GameState::new(9);
+    a.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    let mut b = GameState::new(9);
+    b.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    assert_eq!(archiver::game_hash(&a), archiver::game_hash(&b));
+
+    let mut bigger = GameState::new(13);
+    bigger.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    assert_ne!(archiver::game_hash(&a), archiver::game_hash(&bigger));
+    b.apply_move(Move::Pass).unwrap();
+    assert_ne!(archiver::game_hash(&a), archiver::game_hash(&b));
+    // Pinned so IDs of imported games stay put across builds
+    assert_eq!(archiver::game_hash(&GameState::new(9)), "af63c44c8601c3c4");
+}
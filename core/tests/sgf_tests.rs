@@ -25,3 +25,32 @@ fn parse_simple_sgf() {
     assert_eq!(game_state.board_size, 9);
     assert_eq!(game_state.moves.len(), 5);
 }
+
+#[test]
+fn teaching_annotations_become_marks_and_comments() {
+    use p2pgo_core::Color;
+    use p2pgo_core::teaching::{Marker, TeachingAnnotation, TeachingLayer};
+
+    let mut gs = GameState::new(9);
+    gs.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+    let mut layer = TeachingLayer::new();
+    let notes = [
+        TeachingAnnotation::AddStone { at: Coord::new(6, 6), color: Color::White },
+        TeachingAnnotation::AddStone { at: Coord::new(5, 5), color: Color::Black },
+        TeachingAnnotation::RemoveStone { at: Coord::new(5, 5) },
+        TeachingAnnotation::Mark { at: Coord::new(3, 3), marker: Marker::Triangle },
+        TeachingAnnotation::Mark { at: Coord::new(4, 4), marker: Marker::Label("A".to_string()) },
+        TeachingAnnotation::Comment("White [can] answer here".to_string()),
+    ];
+    for note in &notes {
+        layer.apply(1, note, 9).unwrap();
+    }
+    assert!(layer.apply(1, &TeachingAnnotation::Unmark { at: Coord::new(9, 0) }, 9).is_err());
+
+    let sgf = SgfProcessor::new(gs.clone()).with_annotations(layer).generate();
+    assert!(sgf.contains(";B[cc]TR[gg][dd]LB[ee:A]C[Demonstration: White G3\nWhite [can\\] answer here]"), "{}", sgf);
+
+    // Marks don't change the game read back
+    let parsed = SgfProcessor::new(GameState::new(9)).parse(&sgf).unwrap();
+    assert_eq!(parsed.moves, gs.moves);
+}
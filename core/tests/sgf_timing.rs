@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Time control and clocks survive SGF and CBOR, and reports use them.
+
+use std::time::Duration;
+use p2pgo_core::analysis::game_report;
+use p2pgo_core::cbor::migrate;
+use p2pgo_core::game_clock::{TimeSettings, TimingInfo};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::{Color, GameState};
+
+const FIXTURE: &str = include_str!("fixtures/sgf_timing/byo_yomi_9x9.sgf");
+
+fn parse(sgf: &str) -> GameState {
+    SgfProcessor::new(GameState::new(19)).parse(sgf).unwrap()
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn byo_yomi_properties_are_read_per_move() {
+    let game = parse(FIXTURE);
+    assert_eq!(game.timing.settings, Some(TimeSettings { main_secs: 60, periods: 3, byo_yomi_secs: 10 }));
+    assert_eq!(game.timing.at(1), Some(TimingInfo { time_left_ms: 52_500, periods: None }));
+    assert_eq!(game.timing.at(4), Some(TimingInfo { time_left_ms: 7_000, periods: Some(3) }));
+    assert_eq!(game.timing.moves.len(), game.moves.len());
+}
+
+#[test]
+fn timing_round_trips_through_sgf_and_cbor() {
+    let game = parse(FIXTURE);
+    let sgf = SgfProcessor::new(game.clone()).generate();
+    assert!(sgf.contains("TM[60]OT[3x10 byo-yomi]"), "{}", sgf);
+    assert!(sgf.contains(";W[cc]WL[52.5]"), "{}", sgf);
+    assert!(sgf.contains(";B[gg]BL[7]OB[3]"), "{}", sgf);
+    assert_eq!(parse(&sgf).timing, game.timing);
+
+    let archived: GameState = migrate::from_slice(&serde_cbor::to_vec(&game).unwrap()).unwrap();
+    assert_eq!(archived.timing, game.timing);
+    assert_eq!(archived.moves, game.moves);
+}
+
+#[test]
+fn think_times_come_from_the_recorded_clocks() {
+    let game = parse(FIXTURE);
+    // Into byo-yomi with 20s of main time left, then a period lost
+    let expected = [5_000, 7_500, 35_000, 12_500, 23_000, 5_000, 16_000];
+    for (index, &think) in expected.iter().enumerate() {
+        assert_eq!(game.timing.think_time(index), Some(ms(think)), "move {}", index);
+    }
+
+    // No records: every think time is the clocks'
+    let report = game_report(&game, &[], |_| 0.0);
+    let black = report.times(Color::Black);
+    assert_eq!((black.timed_moves, black.total), (4, ms(79_000)));
+    assert_eq!(black.longest, Some((2, ms(35_000))));
+    assert_eq!(report.times(Color::White).total, ms(25_000));
+    // Black's last move came one period down
+    assert_eq!(black.periods_used, 1);
+    assert_eq!(report.times(Color::White).periods_used, 0);
+}
+
+#[test]
+fn undo_forgets_the_clocks_of_taken_back_moves() {
+    let mut game = parse(FIXTURE);
+    game.undo(2).unwrap();
+    assert_eq!(game.timing.moves.len(), 5);
+    assert!(game.timing.settings.is_some());
+    assert_eq!(game.timing.think_time(4), Some(ms(23_000)));
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Territory estimate: region assignment and rough score on fixed positions.
+
+use p2pgo_core::territory::{Region, TerritoryEstimate};
+use p2pgo_core::{Color, GameState};
+
+/// Position from rows of `X` (Black), `O` (White) and `.`, top row first
+fn position(rows: &[&str]) -> GameState {
+    let mut state = GameState::new(rows.len() as u8);
+    for (y, row) in rows.iter().enumerate() {
+        for (x, point) in row.chars().enumerate() {
+            state.board[y * rows.len() + x] = match point {
+                'X' => Some(Color::Black),
+                'O' => Some(Color::White),
+                _ => None,
+            };
+        }
+    }
+    state
+}
+
+/// Regions as rows of `X`/`O` for stones, `b`/`w` for territory and `.`
+fn regions(estimate: &TerritoryEstimate) -> Vec<String> {
+    estimate.regions.chunks(estimate.board_size as usize)
+        .map(|row| row.iter().map(|region| match region {
+            Region::Stone(Color::Black) => 'X',
+            Region::Stone(Color::White) => 'O',
+            Region::Territory(Color::Black) => 'b',
+            Region::Territory(Color::White) => 'w',
+            Region::Neutral => '.',
+        }).collect())
+        .collect()
+}
+
+#[test]
+fn empty_board_is_all_neutral() {
+    let estimate = TerritoryEstimate::of(&GameState::new(9));
+    assert!(estimate.regions.iter().all(|region| *region == Region::Neutral));
+    assert!(estimate.influence.iter().all(|lean| *lean == 0.0));
+    assert_eq!(estimate.score(7.5), -7.5);
+}
+
+#[test]
+fn walls_split_the_board_into_territory_and_a_contested_middle() {
+    let estimate = TerritoryEstimate::of(&position(&[
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+        "..X...O..",
+    ]));
+    assert_eq!(regions(&estimate)[0], "bbX...Oww");
+    assert_eq!(estimate.territory(Color::Black), 18);
+    assert_eq!(estimate.territory(Color::White), 18);
+
+    // The middle leans to the nearer wall and is even in its centre
+    let row = &estimate.influence[..9];
+    assert!(row[3] >= 0.5, "next to Black: {}", row[3]);
+    assert_eq!(row[4], 0.0);
+    assert!(row[5] <= -0.5, "next to White: {}", row[5]);
+    assert_eq!(estimate.score(0.0), 0.0);
+    assert_eq!(estimate.score(6.5), -6.5);
+}
+
+#[test]
+fn enclosed_corners_are_territory_and_open_areas_are_not() {
+    let estimate = TerritoryEstimate::of(&position(&[
+        "..X......",
+        "..X......",
+        "XXX......",
+        ".........",
+        ".........",
+        ".........",
+        "......OOO",
+        "......O..",
+        "......O..",
+    ]));
+    let regions = regions(&estimate);
+    assert_eq!(regions[0], "bbX......");
+    assert_eq!(regions[2], "XXX......");
+    assert_eq!(regions[4], ".........");
+    assert_eq!(regions[8], "......Oww");
+    assert_eq!(estimate.territory(Color::Black), 4);
+    assert_eq!(estimate.territory(Color::White), 4);
+    assert_eq!(estimate.score(0.0), 0.0, "the position is symmetric");
+}
+
+#[test]
+fn a_lone_stone_influences_but_does_not_own_the_open_board() {
+    let mut state = GameState::new(19);
+    state.board[9 * 19 + 9] = Some(Color::Black);
+    let estimate = TerritoryEstimate::of(&state);
+    assert_eq!(estimate.territory(Color::Black), 0);
+    assert!(estimate.influence[9 * 19 + 10] > 0.5);
+    assert_eq!(estimate.influence[0], 0.0, "out of reach");
+    assert!(estimate.score(0.0) > 0.0);
+}
+
+#[test]
+fn stones_inside_a_region_make_it_contested() {
+    let estimate = TerritoryEstimate::of(&position(&[
+        "...X.....",
+        ".O.X.....",
+        "...X.....",
+        "XXXX.....",
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+        ".........",
+    ]));
+    assert_eq!(estimate.territory(Color::Black), 0);
+    assert_eq!(estimate.regions[0], Region::Neutral);
+    assert!(estimate.influence[1] < 0.0, "next to the White stone");
+}
@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Taking moves back restores the position before them.
+
+use p2pgo_core::{Color, Coord, GameError, GameState, Move};
+
+fn play(state: &mut GameState, moves: &[(u8, u8)]) {
+    for &(x, y) in moves {
+        state.apply_move(Move::Place(Coord::new(x, y))).unwrap();
+    }
+}
+
+#[test]
+fn undo_restores_captured_stones() {
+    let mut state = GameState::new(9);
+    // Black surrounds White's corner stone and takes it
+    play(&mut state, &[(1, 0), (0, 0)]);
+    let before = state.clone();
+    play(&mut state, &[(0, 1)]);
+    assert_eq!(state.captures, (1, 0));
+
+    let taken_back = state.undo(1).unwrap();
+    assert_eq!(taken_back, vec![Move::Place(Coord::new(0, 1))]);
+    assert_eq!(state.board, before.board);
+    assert_eq!(state.captures, (0, 0));
+    assert_eq!(state.current_player, Color::Black);
+}
+
+#[test]
+fn undo_keeps_setup_stones_and_passes() {
+    let mut state = GameState::from_setup(9, &[(Coord::new(4, 4), Color::Black)], Color::White).unwrap();
+    state.apply_move(Move::Pass).unwrap();
+    play(&mut state, &[(2, 2)]);
+
+    state.undo(1).unwrap();
+    assert_eq!(state.moves, vec![Move::Pass]);
+    assert_eq!(state.pass_count, 1);
+    assert_eq!(state.board[Coord::new(4, 4).to_index(9)], Some(Color::Black));
+    assert_eq!(state.current_player, Color::Black);
+}
+
+#[test]
+fn undo_needs_moves_to_take_back() {
+    let mut state = GameState::new(9);
+    play(&mut state, &[(2, 2)]);
+    assert!(matches!(state.undo(2), Err(GameError::InvalidMove(_))));
+    assert!(matches!(state.undo(0), Err(GameError::InvalidMove(_))));
+    assert_eq!(state.moves.len(), 1);
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Minimal consumer of `p2pgo-core` without default features, checked for
+//! `wasm32-unknown-unknown` in CI.
+
+use std::collections::HashSet;
+use p2pgo_core::board::Board;
+use p2pgo_core::rules::RuleValidator;
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::ScoringMethod;
+use p2pgo_core::{Color, Coord, GameState, Move};
+
+/// Play `points` alternately from an empty board and return the area score
+/// from Black's point of view, or `None` at the first illegal move
+pub fn score_sequence(board_size: u8, points: &[(u8, u8)], komi: f32) -> Option<i16> {
+    let mut state = GameState::new(board_size);
+    for &(x, y) in points {
+        state.apply_move(Move::Place(Coord::new(x, y))).ok()?;
+    }
+    let proof = calculate_final_score(&state, komi, ScoringMethod::Area, &HashSet::new());
+    Some(proof.final_score)
+}
+
+/// Whether `color` may play at `(x, y)` on a board holding `stones`
+pub fn is_legal(board_size: u8, stones: &[(u8, u8, Color)], x: u8, y: u8, color: Color) -> bool {
+    let mut board = Board::new(board_size);
+    for &(sx, sy, stone) in stones {
+        board.place(Coord::new(sx, sy), stone);
+    }
+    let previous = board.clone();
+    RuleValidator::new(&board, &previous).check_move(Coord::new(x, y), color).is_ok()
+}
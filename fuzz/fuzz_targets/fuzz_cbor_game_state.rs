@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game state snapshots arrive as CBOR from peers and archives.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p2pgo_core::{cbor, Coord, Move};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(mut state) = cbor::deserialize_game_state(data) {
+        // Accepted snapshots must be safe to keep playing on
+        let _ = state.is_game_over();
+        let _ = state.apply_move(Move::Place(Coord::new(0, 0)));
+        let _ = state.apply_move(Move::Pass);
+        let _ = cbor::serialize_game_state(&state);
+    }
+    let _ = cbor::deserialize_game_event(data);
+});
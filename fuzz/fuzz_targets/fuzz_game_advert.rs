@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game adverts arrive on gossip from any peer; whatever decodes must
+//! have passed the sanitizer.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p2pgo_network::lobby::GameAdvert;
+use p2pgo_network::sanitize::{self, TextField, BOARD_SIZES};
+
+const FIELDS: [TextField; 7] = [
+    TextField::DisplayName,
+    TextField::Guild,
+    TextField::NodeId,
+    TextField::GameId,
+    TextField::GameName,
+    TextField::Chat,
+    TextField::Comment,
+];
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(advert) = GameAdvert::decode(data) {
+        assert!(BOARD_SIZES.contains(&advert.size));
+        assert!(advert.gid.chars().count() <= TextField::GameId.max_len());
+        assert!(!advert.host.chars().any(char::is_control));
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        for field in FIELDS {
+            if sanitize::check_text(field, text).is_ok() {
+                assert!(text.chars().count() <= field.max_len());
+                assert!(!text.chars().any(|c| c.is_control() && c != '\n'));
+            }
+        }
+    }
+});
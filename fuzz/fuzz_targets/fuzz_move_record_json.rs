@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Move records on the direct uni-stream path are parsed as JSON.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p2pgo_core::cbor::MoveRecord;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = std::str::from_utf8(data) {
+        if let Ok(record) = serde_json::from_str::<MoveRecord>(message) {
+            let _ = serde_cbor::to_vec(&record);
+        }
+    }
+});
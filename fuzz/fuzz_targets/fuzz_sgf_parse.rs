@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! SGF files come from disk or peers: parsing must error, never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p2pgo_core::{sgf::SgfProcessor, GameState};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let mut processor = SgfProcessor::new(GameState::new(19));
+        if let Ok(state) = processor.parse(text) {
+            // A parsed game must survive being written back out
+            let _ = SgfProcessor::new(state).generate();
+        }
+    }
+});
@@ -0,0 +1,33 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Criterion benchmark for duplicate detection of peer moves.
+//!
+//! Run with `cargo bench -p p2pgo-network --bench dedup`. Accepting a move
+//! after 10k processed should cost the same as after 100.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use p2pgo_network::dedup::SequenceDedup;
+
+fn bench_dedup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dedup");
+    for processed in [100u64, 10_000] {
+        let mut dedup = SequenceDedup::default();
+        for seq in 0..processed {
+            dedup.accept("peer", seq);
+        }
+        group.bench_function(format!("duplicate_after_{}", processed), |b| {
+            b.iter(|| dedup.is_new("peer", black_box(3)))
+        });
+        let mut next = processed;
+        group.bench_function(format!("accept_after_{}", processed), |b| {
+            b.iter(|| {
+                next += 1;
+                dedup.accept("peer", black_box(next))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_dedup);
+criterion_main!(benches);
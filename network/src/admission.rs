@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Connection admission for nodes that serve other peers, e.g. relays.
+//!
+//! Every incoming connection asks for a [`Permit`] before it is handed to
+//! a protocol and holds it until it closes. New connections are refused
+//! while the node is at `max_connections` or has moved more than
+//! `max_bandwidth_bps` in the current or the last full second. Limits come
+//! from a watch channel, so a config reload applies to the next connection;
+//! when they shrink, the newest connections over `max_connections` are
+//! drained.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use tokio::sync::watch;
+
+/// QUIC application close code for a connection refused or drained by
+/// admission
+pub const ADMISSION_CLOSE_CODE: u32 = 503;
+
+/// Bandwidth is measured over windows this long
+const BANDWIDTH_WINDOW: Duration = Duration::from_secs(1);
+
+/// Limits on the peers a node serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct ConnectionLimits {
+    /// Most peers connected at once
+    pub max_connections: u32,
+    /// Total bandwidth in bytes per second
+    pub max_bandwidth_bps: u64,
+}
+
+impl Default for ConnectionLimits {
+    /// A headless relay's limits, see [`crate::relay_mode::RelayPreset::Dedicated`]
+    fn default() -> Self {
+        Self {
+            max_connections: 256,
+            max_bandwidth_bps: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl ConnectionLimits {
+    /// No practical limit; what nodes that don't serve others use
+    pub fn unlimited() -> Self {
+        Self { max_connections: u32::MAX, max_bandwidth_bps: u64::MAX }
+    }
+}
+
+/// Why a connection was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    /// `max_connections` peers are already connected
+    Full { open: u32 },
+    /// Bandwidth is over `max_bandwidth_bps`
+    Busy { bytes_per_sec: u64 },
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full { open } => write!(f, "already serving {} connections", open),
+            Self::Busy { bytes_per_sec } => write!(f, "moving {} bytes/s", bytes_per_sec),
+        }
+    }
+}
+
+/// Connections and traffic at one moment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ConnectionUsage {
+    pub connections: u32,
+    /// Bytes moved over the last full second
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Debug)]
+struct Usage {
+    /// Admission order of the open connections
+    open: BTreeSet<u64>,
+    next_seq: u64,
+    window_start: Instant,
+    window_bytes: u64,
+    last_window_bytes: u64,
+}
+
+impl Usage {
+    fn open_count(&self) -> u32 {
+        self.open.len() as u32
+    }
+
+    /// Roll the window forward to `now`
+    fn advance(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < BANDWIDTH_WINDOW {
+            return;
+        }
+        self.last_window_bytes = if elapsed < 2 * BANDWIDTH_WINDOW { self.window_bytes } else { 0 };
+        self.window_bytes = 0;
+        self.window_start = now;
+    }
+}
+
+/// Admits connections within limits that can change at runtime.
+///
+/// Clones share their counts.
+#[derive(Debug, Clone)]
+pub struct Admission {
+    limits: watch::Receiver<ConnectionLimits>,
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl Admission {
+    /// Admit within whatever `limits` holds at the time
+    pub fn new(limits: watch::Receiver<ConnectionLimits>) -> Self {
+        Self {
+            limits,
+            usage: Arc::new(Mutex::new(Usage {
+                open: BTreeSet::new(),
+                next_seq: 0,
+                window_start: Instant::now(),
+                window_bytes: 0,
+                last_window_bytes: 0,
+            })),
+        }
+    }
+
+    /// Admit everything
+    pub fn unlimited() -> Self {
+        Self::new(watch::channel(ConnectionLimits::unlimited()).1)
+    }
+
+    /// Limits in effect now
+    pub fn limits(&self) -> ConnectionLimits {
+        *self.limits.borrow()
+    }
+
+    /// A receiver that sees every change of limits
+    pub fn watch(&self) -> watch::Receiver<ConnectionLimits> {
+        self.limits.clone()
+    }
+
+    /// Admit a connection arriving at `now`; it counts as open until the
+    /// permit is dropped
+    pub fn admit(&self, now: Instant) -> Result<Permit, Refusal> {
+        let limits = self.limits();
+        let mut usage = self.usage.lock().unwrap();
+        if usage.open_count() >= limits.max_connections {
+            return Err(Refusal::Full { open: usage.open_count() });
+        }
+        usage.advance(now);
+        let bytes_per_sec = usage.window_bytes.max(usage.last_window_bytes);
+        if bytes_per_sec > limits.max_bandwidth_bps {
+            return Err(Refusal::Busy { bytes_per_sec });
+        }
+        let seq = usage.next_seq;
+        usage.next_seq += 1;
+        usage.open.insert(seq);
+        Ok(Permit { seq, admission: self.clone() })
+    }
+
+    /// Count `bytes` moved at `now` by admitted connections
+    pub fn record(&self, bytes: u64, now: Instant) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.advance(now);
+        usage.window_bytes = usage.window_bytes.saturating_add(bytes);
+    }
+
+    /// Connections admitted and not yet closed
+    pub fn open_connections(&self) -> u32 {
+        self.usage.lock().unwrap().open_count()
+    }
+
+    /// Open connections over `max_connections`, waiting to be drained
+    pub fn draining(&self) -> u32 {
+        self.open_connections().saturating_sub(self.limits().max_connections)
+    }
+
+    /// Open connections and bandwidth over the last full window
+    pub fn usage(&self, now: Instant) -> ConnectionUsage {
+        ConnectionUsage { connections: self.open_connections(), bytes_per_sec: self.bytes_per_sec(now) }
+    }
+
+    /// Bytes per second over the last full window
+    pub fn bytes_per_sec(&self, now: Instant) -> u64 {
+        let mut usage = self.usage.lock().unwrap();
+        usage.advance(now);
+        usage.last_window_bytes
+    }
+}
+
+/// An admitted connection; releases its slot when dropped
+#[derive(Debug)]
+pub struct Permit {
+    seq: u64,
+    admission: Admission,
+}
+
+impl Permit {
+    /// Whether the limits shrank below this connection, which should then
+    /// be closed; the newest go first
+    pub fn drained(&self) -> bool {
+        let usage = self.admission.usage.lock().unwrap();
+        let older = usage.open.range(..=self.seq).count() as u32;
+        older > self.admission.limits().max_connections
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.admission.usage.lock().unwrap().open.remove(&self.seq);
+    }
+}
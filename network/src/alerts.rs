@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Alert rules over connection health.
+//!
+//! The worker counts trouble as it happens: moves whose ACK never came,
+//! background subsystems restarted by the supervisor. An [`AlertRule`]
+//! fires its action when a count over a sliding window reaches its
+//! threshold, then stays quiet for its cooldown so one bad spell gives one
+//! warning rather than a stream of them.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// Something counted for alerting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlertMetric {
+    /// Sent moves the opponent never acknowledged
+    AckTimeouts,
+    /// Background subsystems restarted after stalling or stopping
+    SubsystemRestarts,
+}
+
+impl AlertMetric {
+    fn describe(self, count: u64) -> String {
+        match self {
+            AlertMetric::AckTimeouts => format!("{} moves went unacknowledged", count),
+            AlertMetric::SubsystemRestarts => format!("network subsystems restarted {} times", count),
+        }
+    }
+}
+
+/// What a rule does when it fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AlertAction {
+    /// Show a warning that stays until dismissed
+    Notify,
+    /// Start a trace capture for a bug report, and say so
+    CaptureDiagnostics,
+}
+
+/// Fire `action` once `metric` counts `threshold` within `window_secs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub metric: AlertMetric,
+    pub window_secs: u64,
+    pub threshold: u64,
+    pub action: AlertAction,
+    /// Quiet time after firing; the window when left out
+    #[serde(default)]
+    pub cooldown_secs: Option<u64>,
+}
+
+impl AlertRule {
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs.unwrap_or(self.window_secs))
+    }
+}
+
+/// Rules used until the player writes their own
+pub fn default_rules() -> Vec<AlertRule> {
+    vec![
+        AlertRule {
+            metric: AlertMetric::AckTimeouts,
+            window_secs: 60,
+            threshold: 3,
+            action: AlertAction::Notify,
+            cooldown_secs: Some(10 * 60),
+        },
+        AlertRule {
+            metric: AlertMetric::SubsystemRestarts,
+            window_secs: 60 * 60,
+            threshold: 3,
+            action: AlertAction::Notify,
+            cooldown_secs: None,
+        },
+        AlertRule {
+            metric: AlertMetric::SubsystemRestarts,
+            window_secs: 60 * 60,
+            threshold: 5,
+            action: AlertAction::CaptureDiagnostics,
+            cooldown_secs: None,
+        },
+    ]
+}
+
+/// A rule that fired
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub action: AlertAction,
+    pub metric: AlertMetric,
+    /// Count within the rule's window when it fired
+    pub count: u64,
+    pub window: Duration,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = self.window.as_secs().div_ceil(60);
+        write!(f, "Connection trouble: {} in the last {} min", self.metric.describe(self.count), minutes)
+    }
+}
+
+/// Counts trouble and checks it against the rules
+#[derive(Debug)]
+pub struct AlertMonitor {
+    rules: Vec<AlertRule>,
+    /// When each rule last fired, by position in `rules`
+    fired_at: Vec<Option<Instant>>,
+    /// Counts recorded within the longest window, oldest first
+    samples: VecDeque<(Instant, AlertMetric, u64)>,
+}
+
+impl Default for AlertMonitor {
+    fn default() -> Self {
+        Self::new(default_rules())
+    }
+}
+
+impl AlertMonitor {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self { fired_at: vec![None; rules.len()], rules, samples: VecDeque::new() }
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    /// Replace the rules; counts so far are kept, cooldowns start over
+    pub fn set_rules(&mut self, rules: Vec<AlertRule>) {
+        self.fired_at = vec![None; rules.len()];
+        self.rules = rules;
+    }
+
+    /// `count` more of `metric` happened at `now`
+    pub fn record(&mut self, metric: AlertMetric, count: u64, now: Instant) {
+        if count > 0 {
+            self.samples.push_back((now, metric, count));
+        }
+    }
+
+    /// Rules that fire at `now`, each at most once per cooldown
+    pub fn check(&mut self, now: Instant) -> Vec<Alert> {
+        let longest = self.rules.iter().map(AlertRule::window).max().unwrap_or_default();
+        while matches!(self.samples.front(), Some((at, _, _)) if now.saturating_duration_since(*at) > longest) {
+            self.samples.pop_front();
+        }
+
+        let mut alerts = Vec::new();
+        for (rule, fired_at) in self.rules.iter().zip(&mut self.fired_at) {
+            if matches!(fired_at, Some(at) if now.saturating_duration_since(*at) < rule.cooldown()) {
+                continue;
+            }
+            let count: u64 = self.samples.iter()
+                .filter(|(at, metric, _)| *metric == rule.metric && now.saturating_duration_since(*at) <= rule.window())
+                .map(|(_, _, count)| count)
+                .sum();
+            if count >= rule.threshold {
+                *fired_at = Some(now);
+                alerts.push(Alert { action: rule.action, metric: rule.metric, count, window: rule.window() });
+            }
+        }
+        alerts
+    }
+}
@@ -1,19 +1,29 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 //! Game archiving functionality for training data collection
+//!
+//! Records are versioned like core's CBOR, see [`p2pgo_core::cbor::migrate`].
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::path::PathBuf;
 use tokio::sync::RwLock;
 use tokio::fs;
-use anyhow::Result;
 use serde::{Serialize, Deserialize};
 use p2pgo_core::GameState;
-use crate::GameId;
+use p2pgo_core::archiver::{self, GameSummary};
+use p2pgo_core::cbor::migrate::{self, Upgrade, Versioned};
+use crate::{GameId, Result};
+use crate::error::StorageContext;
+use crate::rate_limit::MoveAnomaly;
+use crate::relay_robustness::ClockSkew;
+
+/// Version of [`GameArchive`] written today
+pub const ARCHIVE_VERSION: u32 = 1;
 
 /// Archive metadata for a completed game
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "GameArchiveRepr", from = "GameArchiveRepr")]
 pub struct GameArchive {
     pub game_id: GameId,
     pub final_state: GameState,
@@ -21,6 +31,106 @@ pub struct GameArchive {
     pub archived_at: u64, // unix timestamp
     pub winner: Option<p2pgo_core::Color>,
     pub score_diff: Option<i16>,
+    /// Why the game ended when it was not scored, e.g. a forfeit
+    pub end_reason: Option<String>,
+    /// Suspicious play seen during the game
+    pub anomalies: Vec<MoveAnomaly>,
+    /// Peers whose clocks were off; their move times were corrected, so
+    /// this doesn't keep the game out of training
+    pub clock_skews: Vec<ClockSkew>,
+}
+
+impl Versioned for GameArchive {
+    const VERSION: u32 = ARCHIVE_VERSION;
+    const NAME: &'static str = "archive record";
+}
+
+/// Every field any version of [`GameArchive`] had
+#[derive(Serialize, Deserialize)]
+struct GameArchiveRepr {
+    #[serde(default)]
+    v: u32,
+    game_id: GameId,
+    final_state: GameState,
+    move_count: u32,
+    archived_at: u64,
+    winner: Option<p2pgo_core::Color>,
+    score_diff: Option<i16>,
+    #[serde(default)]
+    end_reason: Option<String>,
+    #[serde(default)]
+    anomalies: Vec<MoveAnomaly>,
+    #[serde(default)]
+    clock_skews: Vec<ClockSkew>,
+}
+
+const ARCHIVE_UPGRADES: [Upgrade<GameArchiveRepr>; ARCHIVE_VERSION as usize] = [
+    // v1 only added `v`; fields unversioned records left out read as empty
+    |_| {},
+];
+
+impl From<GameArchive> for GameArchiveRepr {
+    fn from(archive: GameArchive) -> Self {
+        Self {
+            v: ARCHIVE_VERSION,
+            game_id: archive.game_id,
+            final_state: archive.final_state,
+            move_count: archive.move_count,
+            archived_at: archive.archived_at,
+            winner: archive.winner,
+            score_diff: archive.score_diff,
+            end_reason: archive.end_reason,
+            anomalies: archive.anomalies,
+            clock_skews: archive.clock_skews,
+        }
+    }
+}
+
+impl From<GameArchiveRepr> for GameArchive {
+    fn from(mut repr: GameArchiveRepr) -> Self {
+        let version = repr.v;
+        migrate::upgrade(&mut repr, version, &ARCHIVE_UPGRADES, GameArchive::NAME);
+        Self {
+            game_id: repr.game_id,
+            final_state: repr.final_state,
+            move_count: repr.move_count,
+            archived_at: repr.archived_at,
+            winner: repr.winner,
+            score_diff: repr.score_diff,
+            end_reason: repr.end_reason,
+            anomalies: repr.anomalies,
+            clock_skews: repr.clock_skews,
+        }
+    }
+}
+
+impl GameArchive {
+    /// Whether the game may be used as training data
+    pub fn usable_for_training(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+    
+    /// Listing metadata for the archive browser
+    pub fn summary(&self) -> GameSummary {
+        let date = chrono::DateTime::from_timestamp(self.archived_at as i64, 0)
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let mut summary = GameSummary::new(self.game_id.clone(), "", &date, &self.final_state);
+        if let Some(winner) = self.winner {
+            let letter = match winner {
+                p2pgo_core::Color::Black => 'B',
+                p2pgo_core::Color::White => 'W',
+            };
+            summary.result = Some(match (self.score_diff, &self.end_reason) {
+                (Some(diff), _) => format!("{}+{}", letter, diff.abs()),
+                (None, Some(_)) => format!("{}+Forfeit", letter),
+                (None, None) => format!("{}+", letter),
+            });
+        }
+        summary.tags = self.anomalies.iter().map(|a| a.tag().to_string()).collect();
+        summary.training_eligible = self.usable_for_training();
+        summary
+    }
 }
 
 /// Archive manager with rotation after 2000+ games
@@ -42,33 +152,40 @@ impl ArchiveManager {
         })
     }
     
-    /// Get the macOS Application Support directory for finished games
-    fn get_archive_directory() -> Result<PathBuf> {
-        #[cfg(target_os = "macos")]
-        {
-            let home = std::env::var("HOME")
-                .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-            let mut path = PathBuf::from(home);
-            path.push("Library");
-            path.push("Application Support");
-            path.push("p2pgo");
-            path.push("finished");
-            Ok(path)
-        }
-        
-        #[cfg(not(target_os = "macos"))]
-        {
-            // Fallback for other platforms
-            let mut path = PathBuf::from(".");
-            path.push("finished_games");
-            Ok(path)
+    /// Create an archive manager that writes to `archive_dir`
+    pub fn with_directory(archive_dir: PathBuf) -> Self {
+        Self {
+            archives: Arc::new(RwLock::new(HashMap::new())),
+            max_archives: 2000,
+            archive_dir,
         }
     }
     
-    /// Archive a completed game
-    pub async fn archive_game(&self, game_id: GameId, final_state: GameState, winner: Option<p2pgo_core::Color>, score_diff: Option<i16>) -> Result<()> {
-        let _span = tracing::info_span!("network.archive", "ArchiveManager::archive_game").entered();
-        
+    /// Directory finished games are kept in, see [`p2pgo_core::p2pgo_dirs`]
+    fn get_archive_directory() -> Result<PathBuf> {
+        Ok(p2pgo_core::p2pgo_dirs::Dirs::current().storage("Failed to find the data directory")?.archive())
+    }
+    
+    /// Directory the records are written to
+    pub fn dir(&self) -> &std::path::Path {
+        &self.archive_dir
+    }
+    
+    /// Archive a completed game with any anomalies and clock skew seen
+    /// during it
+    pub async fn archive_game(&self, game_id: GameId, final_state: GameState, winner: Option<p2pgo_core::Color>, score_diff: Option<i16>, anomalies: Vec<MoveAnomaly>, clock_skews: Vec<ClockSkew>) -> Result<()> {
+        self.store(game_id, final_state, winner, score_diff, None, anomalies, clock_skews).await
+    }
+    
+    /// Archive a game that ended by forfeit, with any anomalies and clock
+    /// skew seen during it
+    pub async fn archive_forfeit(&self, game_id: GameId, final_state: GameState, winner: p2pgo_core::Color, reason: &str, anomalies: Vec<MoveAnomaly>, clock_skews: Vec<ClockSkew>) -> Result<()> {
+        self.store(game_id, final_state, Some(winner), None, Some(reason.to_string()), anomalies, clock_skews).await
+    }
+    
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "ArchiveManager::archive_game", skip_all)]
+    async fn store(&self, game_id: GameId, final_state: GameState, winner: Option<p2pgo_core::Color>, score_diff: Option<i16>, end_reason: Option<String>, anomalies: Vec<MoveAnomaly>, clock_skews: Vec<ClockSkew>) -> Result<()> {
         let move_count = final_state.moves.len() as u32;
         let archive = GameArchive {
             game_id: game_id.clone(),
@@ -80,16 +197,24 @@ impl ArchiveManager {
                 .as_secs(),
             winner,
             score_diff,
+            end_reason,
+            anomalies,
+            clock_skews,
         };
         
         // Ensure archive directory exists
-        fs::create_dir_all(&self.archive_dir).await?;
+        fs::create_dir_all(&self.archive_dir).await.storage("Failed to create archive directory")?;
         
         // Save to filesystem as CBOR
         let filename = format!("{}.cbor", game_id);
         let file_path = self.archive_dir.join(filename);
-        let cbor_data = serde_cbor::to_vec(&archive)?;
-        fs::write(&file_path, cbor_data).await?;
+        let cbor_data = serde_cbor::to_vec(&archive).storage("Failed to encode archive record")?;
+        fs::write(&file_path, cbor_data).await.storage("Failed to write archive record")?;
+        
+        // Let the archive browser list the game without reading the record
+        if let Err(e) = archiver::write_summary(&self.archive_dir, &archive.summary()) {
+            tracing::warn!(game_id = %game_id, "Failed to write archive summary: {}", e);
+        }
         
         let mut archives = self.archives.write().await;
         
@@ -111,10 +236,16 @@ impl ArchiveManager {
         Ok(())
     }
     
+    /// Mark an archived game as usable, or not, for training
+    pub fn set_training_eligible(&self, game_id: &str, eligible: bool) -> Result<()> {
+        archiver::set_training_eligible(&self.archive_dir, game_id, eligible)
+            .storage("Failed to update archive summary")?;
+        Ok(())
+    }
+    
     /// Rotate archives when limit is reached
+    #[tracing::instrument(name = "ArchiveManager::rotate_archives", skip_all)]
     async fn rotate_archives(&self, archives: &mut HashMap<GameId, GameArchive>) -> Result<()> {
-        let _span = tracing::info_span!("network.archive", "ArchiveManager::rotate_archives").entered();
-        
         // Sort by archived_at timestamp
         let mut archive_list: Vec<(GameId, GameArchive)> = archives.iter()
             .map(|(game_id, archive)| (game_id.clone(), archive.clone()))
@@ -210,7 +341,7 @@ mod tests {
         let game_id = "test-game".to_string();
         let state = GameState::new(9);
         
-        manager.archive_game(game_id.clone(), state, Some(Color::Black), Some(5)).await.unwrap();
+        manager.archive_game(game_id.clone(), state, Some(Color::Black), Some(5), Vec::new(), Vec::new()).await.unwrap();
         
         let archive = manager.get_archive(&game_id).await.unwrap();
         assert_eq!(archive.game_id, game_id);
@@ -227,7 +358,7 @@ mod tests {
         for i in 0..15 {
             let game_id = format!("game-{}", i);
             let state = GameState::new(9);
-            manager.archive_game(game_id, state, None, None).await.unwrap();
+            manager.archive_game(game_id, state, None, None, Vec::new(), Vec::new()).await.unwrap();
             
             // Small delay to ensure different timestamps
             tokio::time::sleep(tokio::time::Duration::from_millis(1)).await;
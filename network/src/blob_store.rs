@@ -2,18 +2,21 @@
 
 //! Blob storage for game state and moves
 
-use anyhow::Result;
 use blake3;
-use p2pgo_core::{GameState, GameEvent, Move};
+use p2pgo_core::{GameState, GameEvent, Move, MoveRecord, Tag};
+use p2pgo_core::cbor::migrate::{self, Versioned};
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
-use crate::{BlobHash, DummyIroh, GameId};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use crate::{BlobHash, DummyIroh, Error, GameId, Result};
 
 /// Storage for game-related blobs
 pub struct BlobStore {
     /// Mock Iroh implementation
     #[allow(dead_code)]
     iroh: DummyIroh,
+    /// Moves kept for persistence, by game and sequence
+    moves: RwLock<BTreeMap<(GameId, u32), MoveRecord>>,
 }
 
 impl Default for BlobStore {
@@ -29,6 +32,7 @@ impl BlobStore {
         
         Self {
             iroh: DummyIroh::new(),
+            moves: RwLock::new(BTreeMap::new()),
         }
     }
     
@@ -74,6 +78,37 @@ impl BlobStore {
             message: "Dummy message".to_string(),
         })
     }
+
+    /// Keep move `sequence` of a game, replacing what was kept for it
+    pub async fn store_move(&self, game_id: &str, sequence: u32, record: &MoveRecord) {
+        self.moves.write().await.insert((game_id.to_string(), sequence), record.clone());
+    }
+
+    /// Tag a move already kept
+    pub async fn tag_move(&self, game_id: &str, sequence: u32, tag: Tag) -> Result<()> {
+        let mut moves = self.moves.write().await;
+        let record = moves.get_mut(&(game_id.to_string(), sequence))
+            .ok_or_else(|| Error::sync_conflict(game_id, format!("move {} is not stored", sequence)))?;
+        record.tag = Some(tag);
+        Ok(())
+    }
+
+    /// A game's kept moves with their sequence numbers, in order
+    pub async fn game_moves(&self, game_id: &str) -> Vec<(u32, MoveRecord)> {
+        let game_id = game_id.to_string();
+        self.moves.read().await
+            .range((game_id.clone(), 0)..=(game_id, u32::MAX))
+            .map(|((_, sequence), record)| (*sequence, record.clone()))
+            .collect()
+    }
+}
+
+/// Moves taken back by agreement, kept in the chain as an entry of their
+/// own so the moves and their hashes stay on record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retraction {
+    /// How many of the moves still in play were taken back
+    pub moves: u8,
 }
 
 /// A blob containing a move and the resulting game state
@@ -81,7 +116,7 @@ impl BlobStore {
 pub struct MoveBlob {
     /// The game ID this move belongs to
     pub game_id: GameId,
-    /// The actual move
+    /// The actual move; for a retraction, the last move taken back
     pub mv: Move,
     /// The hash of the previous move blob (None for the first move)
     pub prev_hash: Option<[u8; 32]>,
@@ -89,6 +124,15 @@ pub struct MoveBlob {
     pub state: GameState,
     /// The sequence number of this move (0 for the first move)
     pub sequence: u32,
+    /// Set when this entry takes moves back rather than playing one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retraction: Option<Retraction>,
+}
+
+/// Blobs stay at v0: a blob's hash covers its bytes, and chains link by it
+impl Versioned for MoveBlob {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "move blob";
 }
 
 impl MoveBlob {
@@ -100,17 +144,30 @@ impl MoveBlob {
             prev_hash,
             state,
             sequence,
+            retraction: None,
         }
     }
+
+    /// An entry taking back `retraction.moves` moves, the last being `mv`,
+    /// leaving `state`
+    pub fn retract(game_id: GameId, retraction: Retraction, mv: Move, prev_hash: Option<[u8; 32]>, state: GameState, sequence: u32) -> Self {
+        Self { retraction: Some(retraction), ..Self::new(game_id, mv, prev_hash, state, sequence) }
+    }
     
+    /// The blob as CBOR, its state at today's schema version
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serde_cbor::to_vec(self).expect("MoveBlob serialization should never fail")
+    }
+
+    /// Read a blob; its game state is upgraded from whatever version wrote it
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        migrate::from_slice(bytes).map_err(|e| Error::malformed("move blob", e))
+    }
+
     /// Calculate the hash of this blob
     pub fn hash(&self) -> [u8; 32] {
-        // Serialize to CBOR
-        let cbor_bytes = serde_cbor::to_vec(self)
-            .expect("MoveBlob serialization should never fail");
-            
-        // Hash with BLAKE3
-        *blake3::hash(&cbor_bytes).as_bytes()
+        // Hash the CBOR with BLAKE3
+        *blake3::hash(&self.to_bytes()).as_bytes()
     }
 
     /// Validates that this blob forms a valid continuation of the given previous state
@@ -118,35 +175,36 @@ impl MoveBlob {
         // If this is a first move (no prev_hash), we just need a starting state
         if self.prev_hash.is_none() {
             if prev_state.is_none() {
-                anyhow::bail!("Missing initial state");
+                return Err(Error::sync_conflict(&self.game_id, "Missing initial state"));
             }
         } else {
             // For subsequent moves, we need both state and hash
             if prev_state.is_none() || prev_blob_hash.is_none() {
-                anyhow::bail!("Missing previous state or hash");
+                return Err(Error::sync_conflict(&self.game_id, "Missing previous state or hash"));
             }
             
             // Validate prev hash matches
             if self.prev_hash.unwrap() != prev_blob_hash.unwrap() {
-                anyhow::bail!("Previous hash mismatch");
+                return Err(Error::sync_conflict(&self.game_id, "Previous hash mismatch"));
             }
         }
 
-        // Validate that the move can be applied to the previous state
-        if cfg!(debug_assertions) {
+        // Validate that the move can be applied to the previous state; the
+        // chain checks a retraction against the moves it takes back
+        if cfg!(debug_assertions) && self.retraction.is_none() {
             if let Some(prev) = prev_state {
                 let mut test_state = prev.clone();
                 test_state.apply_move(self.mv.clone())
-                    .map_err(|e| anyhow::anyhow!("Invalid move: {}", e))?;
+                    ?;
                 
                 // Compare states by serializing to JSON since GameState doesn't implement PartialEq
                 let test_state_json = serde_json::to_string(&test_state)
-                    .map_err(|e| anyhow::anyhow!("State serialization failed: {}", e))?;
+                    .map_err(|e| Error::internal(format!("State serialization failed: {}", e)))?;
                 let blob_state_json = serde_json::to_string(&self.state)
-                    .map_err(|e| anyhow::anyhow!("State serialization failed: {}", e))?;
+                    .map_err(|e| Error::internal(format!("State serialization failed: {}", e)))?;
                 
                 if test_state_json != blob_state_json {
-                    anyhow::bail!("Move application resulted in different state");
+                    return Err(Error::sync_conflict(&self.game_id, "Move application resulted in different state"));
                 }
             }
         }
@@ -158,15 +216,15 @@ impl MoveBlob {
     pub fn verify(&self) -> Result<()> {
         // Basic sanity checks
         if self.sequence == 0 && self.prev_hash.is_some() {
-            anyhow::bail!("First move cannot have previous hash");
+            return Err(Error::sync_conflict(&self.game_id, "First move cannot have previous hash"));
         }
         if self.sequence > 0 && self.prev_hash.is_none() {
-            anyhow::bail!("Non-first move must have previous hash");
+            return Err(Error::sync_conflict(&self.game_id, "Non-first move must have previous hash"));
         }
         
         // Game state must be valid (has no obvious errors)
         if self.state.board_size < 1 || self.state.board_size > 25 {
-            anyhow::bail!("Invalid board size");
+            return Err(Error::sync_conflict(&self.game_id, "Invalid board size"));
         }
 
         Ok(())
@@ -210,30 +268,30 @@ impl MoveChain {
 
         // Ensure blob is for this game
         if blob.game_id != self.game_id {
-            anyhow::bail!("Blob is for a different game: expected {}, got {}", self.game_id, blob.game_id);
+            return Err(Error::sync_conflict(&self.game_id, format!("Blob is for a different game: expected {}, got {}", self.game_id, blob.game_id)));
         }
 
         // Check sequence numbers
         match self.current_hash {
             None => {
                 if blob.sequence != 0 {
-                    anyhow::bail!("First blob must have sequence 0, got {}", blob.sequence);
+                    return Err(Error::sync_conflict(&self.game_id, format!("First blob must have sequence 0, got {}", blob.sequence)));
                 }
             }
             Some(_) => {
                 if blob.sequence != self.current_sequence + 1 {
-                    anyhow::bail!(
+                    return Err(Error::sync_conflict(&self.game_id, format!(
                         "Expected blob with sequence {}, got {}",
                         self.current_sequence + 1,
                         blob.sequence
-                    );
+                    )));
                 }
             }
         }
 
         // Verify the blob's internal consistency
         if let Err(e) = blob.verify() {
-            anyhow::bail!("Blob verification failed: {}", e);
+            return Err(Error::sync_conflict(&self.game_id, format!("Blob verification failed: {}", e)));
         }
 
         // Get the current state for validation
@@ -252,7 +310,10 @@ impl MoveChain {
             prev_state.or(empty_state.as_ref()),
             self.current_hash
         ) {
-            anyhow::bail!("Continuation validation failed: {}", e);
+            return Err(Error::sync_conflict(&self.game_id, format!("Continuation validation failed: {}", e)));
+        }
+        if let Some(retraction) = blob.retraction {
+            self.check_retraction(&blob, retraction)?;
         }
 
         // Store the blob
@@ -295,6 +356,48 @@ impl MoveChain {
         result
     }
 
+    /// The move blobs still in play, first to last: retracted moves are
+    /// left out, as are the retractions themselves
+    pub fn played(&self) -> Vec<&MoveBlob> {
+        let mut played = Vec::new();
+        for blob in self.get_all_blobs() {
+            match blob.retraction {
+                Some(retraction) => {
+                    let kept = played.len().saturating_sub(retraction.moves as usize);
+                    played.truncate(kept);
+                }
+                None => played.push(blob),
+            }
+        }
+        played
+    }
+
+    /// The retractions so far, oldest first
+    pub fn retractions(&self) -> Vec<Retraction> {
+        self.get_all_blobs().into_iter().filter_map(|blob| blob.retraction).collect()
+    }
+
+    /// Check a retraction takes back moves in play, its last being the
+    /// blob's move, and leaves the position from before them
+    fn check_retraction(&self, blob: &MoveBlob, retraction: Retraction) -> Result<()> {
+        let played = self.played();
+        let moves = retraction.moves as usize;
+        if moves == 0 || moves > played.len() {
+            return Err(Error::sync_conflict(&self.game_id, format!("Retraction of {} moves with {} in play", moves, played.len())));
+        }
+        if played[played.len() - 1].mv != blob.mv {
+            return Err(Error::sync_conflict(&self.game_id, "Retraction does not end with the last move played"));
+        }
+        let expected = match played.len().checked_sub(moves + 1) {
+            Some(index) => played[index].state.moves.clone(),
+            None => Vec::new(),
+        };
+        if blob.state.moves != expected {
+            return Err(Error::sync_conflict(&self.game_id, format!("Retraction leaves {} moves, expected {}", blob.state.moves.len(), expected.len())));
+        }
+        Ok(())
+    }
+
     /// Verify the entire chain is consistent
     pub fn verify(&self) -> Result<()> {
         // Get all blobs in order
@@ -303,7 +406,7 @@ impl MoveChain {
         // Check sequence is continuous from 0
         for (i, blob) in blobs.iter().enumerate() {
             if blob.sequence != i as u32 {
-                anyhow::bail!("Invalid sequence number at position {}", i);
+                return Err(Error::sync_conflict(&self.game_id, format!("Invalid sequence number at position {}", i)));
             }
             
             // Verify each blob
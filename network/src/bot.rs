@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Running an engine as a bot that hosts and plays games unattended.
+//!
+//! A [`BotRunner`] keeps one game open in the lobby under its own identity,
+//! seats the joiners its filter accepts and plays its engine as Black, up
+//! to [`BotConfig::max_games`] games at once. An engine that overruns
+//! [`BotConfig::move_budget`] passes instead, and positions the value
+//! function finds hopeless on [`NeuralConfig::resign_after`] turns in a row
+//! are resigned.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use p2pgo_core::engine::{value_for_mover, NeuralConfig, PlayerBackend};
+use p2pgo_core::phase::GameResult;
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::ScoringMethod;
+use p2pgo_core::{Color, EndReason, GameEvent, GameState, Move};
+use crate::archive::{ArchiveManager, GameArchive};
+use crate::join::{JoinPolicy, PlayerProfile};
+use crate::lobby::{Lobby, LobbyEvent};
+use crate::{Error, GameId, IdentityManager, Result};
+
+/// Reason given to joiners the filter turns away
+pub const DECLINED_REASON: &str = "This bot is not taking your games";
+
+/// A fresh engine for a game on a board of the given size
+pub type EngineFactory = Arc<dyn Fn(u8) -> Box<dyn PlayerBackend + Send> + Send + Sync>;
+
+/// Black's outlook from -1 to 1, as a value head gives it
+pub type ValueFn = Arc<dyn Fn(&GameState) -> f32 + Send + Sync>;
+
+/// Whether to play a joiner
+pub type JoinFilter = Arc<dyn Fn(&PlayerProfile) -> bool + Send + Sync>;
+
+/// How a bot plays
+#[derive(Debug, Clone)]
+pub struct BotConfig {
+    /// Name shown to joiners
+    pub name: String,
+    pub board_size: u8,
+    /// Games played at once; no game is open while this many are going
+    pub max_games: usize,
+    /// Time the engine gets for a move before the bot passes
+    pub move_budget: Duration,
+    /// When a position counts as lost
+    pub resign: NeuralConfig,
+}
+
+impl BotConfig {
+    pub fn new(name: impl Into<String>, board_size: u8) -> Self {
+        Self {
+            name: name.into(),
+            board_size,
+            max_games: 4,
+            move_budget: Duration::from_secs(5),
+            resign: NeuralConfig::default(),
+        }
+    }
+}
+
+/// Called as the bot's games start and end
+pub trait BotHooks: Send + Sync {
+    fn on_game_start(&self, _game_id: &str, _opponent: &PlayerProfile) {}
+
+    /// The game's archive record, once it has been written
+    fn on_game_end(&self, _record: &GameArchive) {}
+}
+
+struct NoHooks;
+
+impl BotHooks for NoHooks {}
+
+/// Hosts games in a lobby and plays them with an engine
+pub struct BotRunner {
+    lobby: Lobby,
+    profile: PlayerProfile,
+    archive: ArchiveManager,
+    config: BotConfig,
+    engines: EngineFactory,
+    value: Option<ValueFn>,
+    filter: JoinFilter,
+    hooks: Arc<dyn BotHooks>,
+}
+
+/// The engine of one game and how long its position has looked lost
+struct Seat {
+    /// Gone while a move overran its budget
+    engine: Option<Box<dyn PlayerBackend + Send>>,
+    /// Our turns in a row found lost
+    lost: usize,
+}
+
+impl BotRunner {
+    /// A bot playing in `lobby` under the identity kept by `identity`,
+    /// which is created on first use
+    pub fn new(lobby: Lobby, identity: &IdentityManager, archive: ArchiveManager, config: BotConfig, engines: EngineFactory) -> Result<Self> {
+        let identity = identity.load_or_generate(None)
+            .map_err(|e| Error::Internal(Box::new(e)))?;
+        let profile = PlayerProfile {
+            node_id: identity.node_id(),
+            name: config.name.clone(),
+            guild: None,
+            self_reported_rating: None,
+        };
+        Ok(Self {
+            lobby,
+            profile,
+            archive,
+            config,
+            engines,
+            value: None,
+            filter: Arc::new(|_| true),
+            hooks: Arc::new(NoHooks),
+        })
+    }
+
+    /// Resign positions `value` finds hopeless, see [`BotConfig::resign`]
+    pub fn with_value(mut self, value: impl Fn(&GameState) -> f32 + Send + Sync + 'static) -> Self {
+        self.value = Some(Arc::new(value));
+        self
+    }
+
+    /// Only play joiners `filter` accepts; everyone is played by default
+    pub fn accepting(mut self, filter: impl Fn(&PlayerProfile) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    pub fn with_hooks(mut self, hooks: impl BotHooks + 'static) -> Self {
+        self.hooks = Arc::new(hooks);
+        self
+    }
+
+    /// The bot's persistent node ID
+    pub fn node_id(&self) -> &str {
+        &self.profile.node_id
+    }
+
+    pub fn profile(&self) -> &PlayerProfile {
+        &self.profile
+    }
+
+    /// Host and play games until the lobby shuts down
+    pub async fn run(self) -> Result<()> {
+        let bot = Arc::new(self);
+        let mut events = bot.lobby.subscribe();
+        let (done_tx, mut done_rx) = mpsc::unbounded_channel::<GameId>();
+        let mut playing = HashSet::new();
+        let mut open = Some(bot.open_game().await?);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    // Requests for anyone else's games are theirs to answer
+                    Ok(LobbyEvent::JoinRequested { game_id, request }) if open.as_ref() == Some(&game_id) => {
+                        let accept = (bot.filter)(&request.profile);
+                        let reason = (!accept).then(|| DECLINED_REASON.to_string());
+                        if let Err(e) = bot.lobby.respond_to_join(&game_id, request.request_id, accept, reason).await {
+                            // The joiner gave up waiting
+                            tracing::warn!(game_id = %game_id, "Failed to answer join request: {}", e);
+                            continue;
+                        }
+                        if !accept {
+                            continue;
+                        }
+                        bot.lobby.start_game(&game_id).await?;
+                        playing.insert(game_id.clone());
+                        tokio::spawn(bot.clone().play(game_id, request.profile, done_tx.clone()));
+                        open = None;
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Bot fell behind on lobby events");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+                Some(game_id) = done_rx.recv() => {
+                    playing.remove(&game_id);
+                }
+            }
+            if open.is_none() && playing.len() < bot.config.max_games {
+                open = Some(bot.open_game().await?);
+            }
+        }
+    }
+
+    /// Create a game waiting for an opponent
+    async fn open_game(&self) -> Result<GameId> {
+        let game_id = self.lobby.create_game(Some(self.config.name.clone()), self.config.board_size, false).await?;
+        self.lobby.set_host(&game_id, self.profile.clone(), JoinPolicy::Manual).await?;
+        self.lobby.await_opponent(&game_id).await?;
+        tracing::info!(game_id = %game_id, "Bot waiting for an opponent");
+        Ok(game_id)
+    }
+
+    async fn play(self: Arc<Self>, game_id: GameId, opponent: PlayerProfile, done: mpsc::UnboundedSender<GameId>) {
+        if let Err(e) = self.play_game(&game_id, &opponent).await {
+            tracing::warn!(game_id = %game_id, "Bot game failed: {}", e);
+        }
+        let _ = self.lobby.remove_game(&game_id).await;
+        let _ = done.send(game_id);
+    }
+
+    async fn play_game(&self, game_id: &GameId, opponent: &PlayerProfile) -> Result<()> {
+        self.hooks.on_game_start(game_id, opponent);
+        let channel = self.lobby.get_game_channel(game_id).await?;
+        let size = channel.settings().board_size;
+        let mut events = channel.subscribe();
+        let mut seat = Seat { engine: Some((self.engines)(size)), lost: 0 };
+
+        // Set when the game ends other than on the board, e.g. by forfeit
+        let mut ended: Option<Option<Color>> = None;
+        let state = loop {
+            let state = channel.get_latest_state().await.unwrap_or_else(|| GameState::new(size));
+            if state.is_game_over() || ended.is_some() {
+                break state;
+            }
+            if state.current_player == Color::Black {
+                let mv = self.choose(&mut seat, &state).await;
+                if let Err(e) = self.lobby.post_player_move(game_id, &self.profile.node_id, mv.clone()).await {
+                    tracing::warn!(game_id = %game_id, ?mv, "Engine move refused, passing: {}", e);
+                    self.lobby.post_player_move(game_id, &self.profile.node_id, Move::Pass).await?;
+                }
+                continue;
+            }
+            match events.recv().await {
+                Ok(GameEvent::GameEnded { winner, .. }) => ended = Some(winner),
+                // Takebacks are for games between people
+                Ok(GameEvent::UndoRequested { .. }) => {
+                    let _ = channel.answer_undo(false).await;
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => ended = Some(None),
+            }
+        };
+
+        let (winner, score_diff) = match state.end_reason() {
+            Some(EndReason::DoublePass) => {
+                let komi = channel.settings().scoring_komi();
+                let proof = calculate_final_score(&state, komi, ScoringMethod::Territory, &HashSet::new());
+                let winner = match proof.final_score {
+                    s if s > 0 => Some(Color::Black),
+                    s if s < 0 => Some(Color::White),
+                    _ => None,
+                };
+                let result = GameResult { winner, reason: EndReason::DoublePass, score_diff: proof.final_score.unsigned_abs() as f32 };
+                if let Err(e) = channel.settle(result).await {
+                    tracing::debug!(game_id = %game_id, "Score not settled: {}", e);
+                }
+                (winner, Some(proof.final_score))
+            }
+            // The player to move after a resignation is the one who didn't resign
+            Some(EndReason::Resignation) => (Some(state.current_player), None),
+            _ => (ended.flatten(), None),
+        };
+        tracing::info!(game_id = %game_id, ?winner, "Bot game over");
+
+        let mut state = state;
+        state.timing = channel.timing().await;
+        let (anomalies, clock_skews) = (channel.anomalies().await, channel.clock_skews().await);
+        self.archive.archive_game(game_id.clone(), state, winner, score_diff, anomalies, clock_skews).await?;
+        if let Some(record) = self.archive.get_archive(game_id).await {
+            self.hooks.on_game_end(&record);
+        }
+        Ok(())
+    }
+
+    /// The engine's move within the budget, or a resignation if the game
+    /// has looked lost for long enough
+    async fn choose(&self, seat: &mut Seat, state: &GameState) -> Move {
+        let budget = self.config.move_budget;
+        let mut engine = seat.engine.take().unwrap_or_else(|| (self.engines)(state.board_size));
+        let value = self.value.clone();
+        let pos = state.clone();
+        let thinking = tokio::task::spawn_blocking(move || {
+            let value = value.map(|value| value(&pos));
+            let mv = engine.next_move(&pos, budget);
+            (engine, value, mv)
+        });
+
+        match tokio::time::timeout(budget, thinking).await {
+            Ok(Ok((engine, value, mv))) => {
+                seat.engine = Some(engine);
+                if let Some(value) = value {
+                    if self.config.resign.hopeless(value_for_mover(state, value)) {
+                        seat.lost += 1;
+                    } else {
+                        seat.lost = 0;
+                    }
+                }
+                if seat.lost >= self.config.resign.resign_after.max(1) {
+                    return Move::Resign;
+                }
+                mv
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("Engine failed, passing: {}", e);
+                Move::Pass
+            }
+            // The engine is left to finish on its own; the next move gets
+            // a fresh one
+            Err(_) => {
+                tracing::warn!(budget_ms = budget.as_millis() as u64, "Engine overran its move budget, passing");
+                Move::Pass
+            }
+        }
+    }
+}
+
+/// Engines for [`BotRunner::new`] from a constructor per board size
+pub fn engines<P, F>(make: F) -> EngineFactory
+where
+    P: PlayerBackend + Send + 'static,
+    F: Fn(u8) -> P + Send + Sync + 'static,
+{
+    Arc::new(move |size| Box::new(make(size)) as Box<dyn PlayerBackend + Send>)
+}
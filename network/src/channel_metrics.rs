@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-game counters for diagnosing sync problems without the logs: moves
+//! sent and received, duplicates, sync requests and ACK round trips.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// Round trips kept for the percentiles
+pub const ACK_SAMPLES: usize = 128;
+
+/// How long a sent move waits for its ACK before it counts as lost
+pub const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sent moves still waiting for their ACK; older ones are forgotten
+const MAX_PENDING_ACKS: usize = 64;
+
+/// Live counters for one game channel, updated from any task
+#[derive(Debug, Default)]
+pub struct ChannelMetrics {
+    moves_sent: AtomicU64,
+    moves_received: AtomicU64,
+    duplicates_dropped: AtomicU64,
+    sync_requests_sent: AtomicU64,
+    sync_requests_served: AtomicU64,
+    /// When each unacknowledged move went out, by move index
+    pending_acks: Mutex<HashMap<u32, Instant>>,
+    /// Latest round trips, oldest first
+    ack_latencies: Mutex<VecDeque<Duration>>,
+    last_error: Mutex<Option<String>>,
+}
+
+impl ChannelMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// We sent the move with index `index` at `now` to a peer that
+    /// acknowledges it
+    pub fn record_move_sent(&self, index: u32, now: Instant) {
+        self.moves_sent.fetch_add(1, Ordering::Relaxed);
+        let mut pending = self.pending_acks.lock().unwrap();
+        if pending.len() >= MAX_PENDING_ACKS {
+            if let Some(&oldest) = pending.iter().min_by_key(|(_, sent)| **sent).map(|(index, _)| index) {
+                pending.remove(&oldest);
+            }
+        }
+        pending.insert(index, now);
+    }
+
+    /// We played a move no direct peer will acknowledge, such as one only
+    /// gossiped or sent to a peer without framing
+    pub fn record_move_unacknowledged(&self) {
+        self.moves_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A peer's move was applied
+    pub fn record_move_received(&self) {
+        self.moves_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A move we had already applied arrived again
+    pub fn record_duplicate(&self) {
+        self.duplicates_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sync_request_sent(&self) {
+        self.sync_requests_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_sync_request_served(&self) {
+        self.sync_requests_served.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The peer acknowledged move `index`; returns the round trip if we sent it
+    pub fn record_ack(&self, index: u32, now: Instant) -> Option<Duration> {
+        let sent = self.pending_acks.lock().unwrap().remove(&index)?;
+        let rtt = now.saturating_duration_since(sent);
+        let mut latencies = self.ack_latencies.lock().unwrap();
+        if latencies.len() == ACK_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(rtt);
+        Some(rtt)
+    }
+
+    /// Moves still waiting for their ACK, with when each went out
+    pub fn pending_acks(&self) -> Vec<(u32, Instant)> {
+        self.pending_acks.lock().unwrap().iter().map(|(&index, &sent)| (index, sent)).collect()
+    }
+
+    /// Forget moves sent `timeout` or more before `now` and still not
+    /// acknowledged, returning how many there were
+    pub fn expire_acks(&self, now: Instant, timeout: Duration) -> u64 {
+        let mut pending = self.pending_acks.lock().unwrap();
+        let before = pending.len();
+        pending.retain(|_, sent| now.saturating_duration_since(*sent) < timeout);
+        (before - pending.len()) as u64
+    }
+
+    pub fn record_error(&self, error: impl fmt::Display) {
+        *self.last_error.lock().unwrap() = Some(error.to_string());
+    }
+
+    /// Copy of the counters as they are now
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let mut latencies: Vec<Duration> = self.ack_latencies.lock().unwrap().iter().copied().collect();
+        latencies.sort();
+        MetricsSnapshot {
+            moves_sent: self.moves_sent.load(Ordering::Relaxed),
+            moves_received: self.moves_received.load(Ordering::Relaxed),
+            duplicates_dropped: self.duplicates_dropped.load(Ordering::Relaxed),
+            sync_requests_sent: self.sync_requests_sent.load(Ordering::Relaxed),
+            sync_requests_served: self.sync_requests_served.load(Ordering::Relaxed),
+            ack_samples: latencies.len(),
+            ack_p50: percentile(&latencies, 50),
+            ack_p95: percentile(&latencies, 95),
+            ack_p99: percentile(&latencies, 99),
+            last_error: self.last_error.lock().unwrap().clone(),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], pct: usize) -> Option<Duration> {
+    let rank = ((pct * sorted.len()) as f64 / 100.0).ceil().max(1.0) as usize;
+    sorted.get(rank - 1).copied()
+}
+
+/// Channel metrics at one moment, for the UI and logs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub moves_sent: u64,
+    pub moves_received: u64,
+    pub duplicates_dropped: u64,
+    pub sync_requests_sent: u64,
+    pub sync_requests_served: u64,
+    /// Round trips behind the percentiles
+    pub ack_samples: usize,
+    pub ack_p50: Option<Duration>,
+    pub ack_p95: Option<Duration>,
+    pub ack_p99: Option<Duration>,
+    pub last_error: Option<String>,
+}
+
+impl fmt::Display for MetricsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "moves sent {}, received {}, duplicates dropped {}; sync requests sent {}, served {}",
+            self.moves_sent, self.moves_received, self.duplicates_dropped,
+            self.sync_requests_sent, self.sync_requests_served,
+        )?;
+        match (self.ack_p50, self.ack_p95, self.ack_p99) {
+            (Some(p50), Some(p95), Some(p99)) => write!(
+                f,
+                "; ACK p50 {} ms, p95 {} ms, p99 {} ms ({} samples)",
+                p50.as_millis(), p95.as_millis(), p99.as_millis(), self.ack_samples,
+            )?,
+            _ => write!(f, "; no ACKs")?,
+        }
+        if let Some(error) = &self.last_error {
+            write!(f, "; last error: {}", error)?;
+        }
+        Ok(())
+    }
+}
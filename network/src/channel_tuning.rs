@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! How eagerly a game channel chases moves the peer never acknowledged.
+//!
+//! A watchdog looks at the unacknowledged moves every check interval and
+//! asks the peer for a sync once one waited past the ACK timeout, a few
+//! times at most per move. Blitz games chase sooner; correspondence games
+//! don't chase at all and rely on the sync sent when peers reconnect.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use crate::matchmaking::TimeControl;
+
+/// Longest main time, in seconds, of a blitz game
+pub const BLITZ_MAIN_TIME_SECS: u32 = 5 * 60;
+
+/// Longest byo-yomi period, in seconds, of a blitz game
+pub const BLITZ_BYO_YOMI_SECS: u32 = 10;
+
+/// ACK watchdog settings of one game channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelTuning {
+    /// How long a sent move waits for its ACK before we ask for a sync;
+    /// 0 turns the watchdog off
+    pub ack_timeout_ms: u64,
+    /// How often unacknowledged moves are looked at
+    pub check_interval_ms: u64,
+    /// Sync requests sent for one move before we give up on it
+    pub max_sync_requests: u32,
+}
+
+impl Default for ChannelTuning {
+    fn default() -> Self {
+        Self {
+            ack_timeout_ms: 3000,
+            check_interval_ms: 500,
+            max_sync_requests: 3,
+        }
+    }
+}
+
+impl ChannelTuning {
+    /// Quick retries for fast games on a good link
+    pub const BLITZ: Self = Self {
+        ack_timeout_ms: 1000,
+        check_interval_ms: 250,
+        max_sync_requests: 3,
+    };
+
+    /// No watchdog; the peers sync when they next connect
+    pub const DISABLED: Self = Self {
+        ack_timeout_ms: 0,
+        check_interval_ms: 500,
+        max_sync_requests: 0,
+    };
+
+    /// Tuning for a game played with `time_control`, `None` when untimed
+    pub fn for_game(time_control: Option<&TimeControl>, correspondence: bool) -> Self {
+        if correspondence {
+            Self::DISABLED
+        } else if time_control.is_some_and(is_blitz) {
+            Self::BLITZ
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Whether unacknowledged moves are chased at all
+    pub fn enabled(&self) -> bool {
+        self.ack_timeout_ms > 0 && self.max_sync_requests > 0
+    }
+
+    pub fn ack_timeout(&self) -> Duration {
+        Duration::from_millis(self.ack_timeout_ms)
+    }
+
+    /// Never below 10 ms, so a bad setting can't spin
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_millis(self.check_interval_ms.max(10))
+    }
+}
+
+/// Whether `time_control` leaves too little time to wait for a slow sync
+pub fn is_blitz(time_control: &TimeControl) -> bool {
+    time_control.main_time_secs <= BLITZ_MAIN_TIME_SECS && time_control.byo_yomi_secs <= BLITZ_BYO_YOMI_SECS
+}
+
+/// Sync requests sent for one unacknowledged move
+#[derive(Debug, Clone, Copy)]
+struct Chase {
+    requests: u32,
+    last: Instant,
+}
+
+/// Decides when unacknowledged moves are worth a sync request
+#[derive(Debug, Default)]
+pub struct AckWatchdog {
+    tuning: ChannelTuning,
+    /// By move index, for moves we asked about at least once
+    chased: HashMap<u32, Chase>,
+}
+
+impl AckWatchdog {
+    pub fn new(tuning: ChannelTuning) -> Self {
+        Self { tuning, chased: HashMap::new() }
+    }
+
+    pub fn tuning(&self) -> ChannelTuning {
+        self.tuning
+    }
+
+    /// Use `tuning` from the next check; moves already chased keep their count
+    pub fn set_tuning(&mut self, tuning: ChannelTuning) {
+        self.tuning = tuning;
+    }
+
+    /// Whether to ask for a sync at `now`, given the moves still waiting
+    /// for their ACK as (index, sent at).
+    ///
+    /// A move is asked about once each ACK timeout, and no more than
+    /// `max_sync_requests` times; one request covers every move due.
+    pub fn check(&mut self, pending: &[(u32, Instant)], now: Instant) -> bool {
+        self.chased.retain(|index, _| pending.iter().any(|(pending, _)| pending == index));
+        if !self.tuning.enabled() {
+            return false;
+        }
+        let timeout = self.tuning.ack_timeout();
+        let mut due = false;
+        for &(index, sent) in pending {
+            let chase = self.chased.get(&index).copied();
+            let (requests, since) = chase.map_or((0, sent), |chase| (chase.requests, chase.last));
+            if requests >= self.tuning.max_sync_requests || now.saturating_duration_since(since) < timeout {
+                continue;
+            }
+            self.chased.insert(index, Chase { requests: requests + 1, last: now });
+            due = true;
+        }
+        due
+    }
+
+    /// Sync requests sent so far for move `index`
+    pub fn requests(&self, index: u32) -> u32 {
+        self.chased.get(&index).map_or(0, |chase| chase.requests)
+    }
+}
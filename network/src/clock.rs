@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Where game channels get the time from.
+//!
+//! Live channels read the system clock. A replayed session runs on a
+//! [`VirtualClock`] that only moves when the replayer says so, so rate
+//! limits and clock skew see the same times they saw when recorded.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use crate::relay_robustness::now_secs;
+
+/// A source of time
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for rate limits and round trips
+    fn now(&self) -> Instant;
+    /// Wall-clock unix seconds, for timestamps peers see
+    fn unix_secs(&self) -> u64;
+}
+
+/// The machine's clocks
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_secs(&self) -> u64 {
+        now_secs()
+    }
+}
+
+/// A clock that stands still until set or advanced
+#[derive(Debug)]
+pub struct VirtualClock {
+    start: Instant,
+    start_unix: u64,
+    elapsed_ms: AtomicU64,
+}
+
+impl VirtualClock {
+    /// A clock reading `start_unix` until moved
+    pub fn new(start_unix: u64) -> Self {
+        Self {
+            start: Instant::now(),
+            start_unix,
+            elapsed_ms: AtomicU64::new(0),
+        }
+    }
+
+    /// Time since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_millis(self.elapsed_ms.load(Ordering::Relaxed))
+    }
+
+    /// Move the clock to `elapsed` after its start; it never goes back
+    pub fn set(&self, elapsed: Duration) {
+        self.elapsed_ms.fetch_max(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.elapsed_ms.fetch_add(by.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn unix_secs(&self) -> u64 {
+        self.start_unix + self.elapsed().as_secs()
+    }
+}
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Correspondence games, played over days.
+//!
+//! Instead of a game clock each player has a budget per move, and either
+//! may be offline between moves. Moves played while the opponent is away
+//! wait in the channel's outbox, saved with the game's snapshot, until the
+//! peers next connect; the peer that was away catches up by sync and shows
+//! what arrived as unread.
+
+use std::time::Duration;
+use p2pgo_core::{Color, GameState};
+use crate::idle::IdleConfig;
+
+/// Default time a player has for each move
+pub const MOVE_BUDGET: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Idle thresholds for a game with `budget` per move: the player is asked
+/// once three quarters of it passed, and the game is forfeited at the end
+pub fn idle_config(budget: Duration) -> IdleConfig {
+    IdleConfig {
+        prompt_after: budget / 4 * 3,
+        forfeit_after: budget,
+    }
+}
+
+/// Moves of our opponent in `state` after the first `seen`, when we play `us`
+pub fn unread_moves(state: &GameState, seen: usize, us: Color) -> usize {
+    let first = state.initial_position().current_player;
+    (seen..state.moves.len())
+        .filter(|&index| {
+            let mover = if index % 2 == 0 { first } else { first.opposite() };
+            mover != us
+        })
+        .count()
+}
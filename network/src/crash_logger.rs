@@ -6,8 +6,8 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::fs;
 use tokio::sync::Mutex;
-use anyhow::Result;
 use tracing::info;
+use crate::error::{Error, Result, StorageContext};
 
 /// Crash logger with 1GB rotation
 pub struct CrashLogger {
@@ -30,38 +30,21 @@ impl CrashLogger {
         })
     }
     
-    /// Get the macOS Logs directory
+    /// Log directory, see [`p2pgo_core::p2pgo_dirs`]
     fn get_log_directory() -> Result<PathBuf> {
-        #[cfg(target_os = "macos")]
-        {
-            let home = std::env::var("HOME")
-                .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-            let mut path = PathBuf::from(home);
-            path.push("Library");
-            path.push("Logs");
-            path.push("p2pgo");
-            Ok(path)
-        }
-        
-        #[cfg(not(target_os = "macos"))]
-        {
-            // Fallback for other platforms
-            let mut path = PathBuf::from(".");
-            path.push("logs");
-            Ok(path)
-        }
+        Ok(p2pgo_core::p2pgo_dirs::Dirs::current().storage("Failed to find the log directory")?.logs)
     }
     
     /// Initialize the logger and calculate current size
     pub async fn init(&self) -> Result<()> {
         // Ensure log directory exists
-        fs::create_dir_all(&self.log_dir).await?;
+        fs::create_dir_all(&self.log_dir).await.storage("Failed to create the crash log directory")?;
         
         // Calculate current log size
         let mut total_size = 0u64;
-        let mut entries = fs::read_dir(&self.log_dir).await?;
+        let mut entries = fs::read_dir(&self.log_dir).await.storage("Failed to list crash logs")?;
         
-        while let Some(entry) = entries.next_entry().await? {
+        while let Some(entry) = entries.next_entry().await.storage("Failed to list crash logs")? {
             if let Ok(metadata) = entry.metadata().await {
                 total_size += metadata.len();
             }
@@ -89,7 +72,7 @@ impl CrashLogger {
         );
         
         // Write the crash log
-        fs::write(&file_path, log_entry.as_bytes()).await?;
+        fs::write(&file_path, log_entry.as_bytes()).await.storage("Failed to write the crash log")?;
         
         // Update size tracking
         let new_size = log_entry.len() as u64;
@@ -109,11 +92,11 @@ impl CrashLogger {
     async fn rotate_logs(&self) -> Result<()> {
         info!("Starting log rotation...");
         
-        let mut entries = fs::read_dir(&self.log_dir).await?;
+        let mut entries = fs::read_dir(&self.log_dir).await.storage("Failed to list crash logs")?;
         let mut log_files = Vec::new();
         
         // Collect all log files with their metadata
-        while let Some(entry) = entries.next_entry().await? {
+        while let Some(entry) = entries.next_entry().await.storage("Failed to list crash logs")? {
             let path = entry.path();
             if path.extension().and_then(|s| s.to_str()) == Some("log") {
                 if let Ok(metadata) = entry.metadata().await {
@@ -172,7 +155,7 @@ pub async fn init_crash_logger() -> Result<()> {
     let logger = CrashLogger::new()?;
     logger.init().await?;
     
-    CRASH_LOGGER.set(logger).map_err(|_| anyhow::anyhow!("Crash logger already initialized"))?;
+    CRASH_LOGGER.set(logger).map_err(|_| Error::AlreadyInitialized("The crash logger"))?;
     
     Ok(())
 }
@@ -182,7 +165,7 @@ pub async fn log_crash(error: &str, context: &str) -> Result<()> {
     if let Some(logger) = CRASH_LOGGER.get() {
         logger.log_crash(error, context).await
     } else {
-        Err(anyhow::anyhow!("Crash logger not initialized"))
+        Err(Error::NotInitialized("The crash logger"))
     }
 }
 
@@ -0,0 +1,316 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Append-only ledger of training credits.
+//!
+//! Each [`CreditEntry`] is appended to a CBOR log and chained to the one
+//! before it by hash, so edits to earlier entries are caught on load.
+//! Relay credits need a [`RelayReceipt`] signed by the peer whose traffic
+//! was relayed; the signature is checked when recorded and again on load.
+//!
+//! Entries are versioned like core's CBOR, see [`p2pgo_core::cbor::migrate`].
+//! The hash leaves the version out, so ledgers written before entries were
+//! versioned still chain.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use p2pgo_core::cbor::migrate::{self, Upgrade, Versioned};
+use crate::{Error, GameId, Result};
+use crate::error::StorageContext;
+
+/// File name of the ledger inside the data directory
+pub const LEDGER_FILE: &str = "credits.cbor";
+
+/// Version of [`CreditEntry`] written today
+pub const CREDIT_ENTRY_VERSION: u32 = 1;
+
+/// Relayed bytes worth one credit
+pub const BYTES_PER_CREDIT: u64 = 1024 * 1024;
+
+/// Credits for sharing a scored game for training
+pub const SHARED_GAME_CREDITS: u64 = 10;
+
+/// Credits for taking part in a federated training round
+pub const FEDERATED_ROUND_CREDITS: u64 = 25;
+
+/// A peer's signed statement that a relay forwarded its traffic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayReceipt {
+    /// Node ID of the relay that earns the credits
+    pub relay_node: String,
+    /// Ed25519 public key of the peer that signed the receipt
+    pub peer_key: [u8; 32],
+    /// Bytes relayed for the peer
+    pub bytes: u64,
+    /// Unix timestamp of the receipt
+    pub timestamp: u64,
+    /// Peer's signature over the fields above
+    pub signature: Vec<u8>,
+}
+
+impl RelayReceipt {
+    /// Create a receipt signed by the peer's key
+    pub fn sign(peer: &SigningKey, relay_node: &str, bytes: u64, timestamp: u64) -> Self {
+        let mut receipt = Self {
+            relay_node: relay_node.to_string(),
+            peer_key: peer.verifying_key().to_bytes(),
+            bytes,
+            timestamp,
+            signature: Vec::new(),
+        };
+        receipt.signature = peer.sign(&receipt.signed_bytes()).to_bytes().to_vec();
+        receipt
+    }
+
+    /// Bytes covered by the signature
+    fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.relay_node.len() + 48);
+        bytes.extend_from_slice(b"p2pgo-relay-receipt");
+        bytes.extend_from_slice(self.relay_node.as_bytes());
+        bytes.extend_from_slice(&self.peer_key);
+        bytes.extend_from_slice(&self.bytes.to_le_bytes());
+        bytes.extend_from_slice(&self.timestamp.to_le_bytes());
+        bytes
+    }
+
+    /// Check the peer's signature
+    pub fn verify(&self) -> Result<()> {
+        let key = VerifyingKey::from_bytes(&self.peer_key)
+            .map_err(|e| Error::malformed("relay receipt", format!("invalid peer key: {}", e)))?;
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|e| Error::malformed("relay receipt", format!("invalid signature: {}", e)))?;
+        key.verify(&self.signed_bytes(), &signature)
+            .map_err(|_| Error::malformed("relay receipt", "signature does not match"))
+    }
+}
+
+/// What a ledger entry was earned for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CreditSource {
+    /// Traffic relayed for a peer
+    Relay(RelayReceipt),
+    /// A scored game shared for training
+    SharedGame { game_id: GameId },
+    /// A federated training round we took part in
+    FederatedRound { round: u64 },
+}
+
+/// One line of the ledger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "CreditEntryRepr", from = "CreditEntryRepr")]
+pub struct CreditEntry {
+    /// Position in the ledger, starting at 0
+    pub seq: u64,
+    /// Unix timestamp when the entry was recorded
+    pub timestamp: u64,
+    /// Credits earned
+    pub credits: u64,
+    pub source: CreditSource,
+    /// Hash of the previous entry (zero for the first)
+    pub prev_hash: [u8; 32],
+}
+
+impl CreditEntry {
+    /// BLAKE3 hash of the CBOR-encoded entry, without its version
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let hashed = HashedEntry {
+            seq: self.seq,
+            timestamp: self.timestamp,
+            credits: self.credits,
+            source: &self.source,
+            prev_hash: self.prev_hash,
+        };
+        let bytes = serde_cbor::to_vec(&hashed).storage("Failed to encode credits entry")?;
+        Ok(*blake3::hash(&bytes).as_bytes())
+    }
+}
+
+/// The fields an entry's hash covers, encoded as unversioned entries were
+#[derive(Serialize)]
+struct HashedEntry<'a> {
+    seq: u64,
+    timestamp: u64,
+    credits: u64,
+    source: &'a CreditSource,
+    prev_hash: [u8; 32],
+}
+
+impl Versioned for CreditEntry {
+    const VERSION: u32 = CREDIT_ENTRY_VERSION;
+    const NAME: &'static str = "credits entry";
+}
+
+/// Every field any version of [`CreditEntry`] had
+#[derive(Serialize, Deserialize)]
+struct CreditEntryRepr {
+    #[serde(default)]
+    v: u32,
+    seq: u64,
+    timestamp: u64,
+    credits: u64,
+    source: CreditSource,
+    prev_hash: [u8; 32],
+}
+
+const CREDIT_ENTRY_UPGRADES: [Upgrade<CreditEntryRepr>; CREDIT_ENTRY_VERSION as usize] = [
+    // v1 only added `v`
+    |_| {},
+];
+
+impl From<CreditEntry> for CreditEntryRepr {
+    fn from(entry: CreditEntry) -> Self {
+        Self {
+            v: CREDIT_ENTRY_VERSION,
+            seq: entry.seq,
+            timestamp: entry.timestamp,
+            credits: entry.credits,
+            source: entry.source,
+            prev_hash: entry.prev_hash,
+        }
+    }
+}
+
+impl From<CreditEntryRepr> for CreditEntry {
+    fn from(mut repr: CreditEntryRepr) -> Self {
+        let version = repr.v;
+        migrate::upgrade(&mut repr, version, &CREDIT_ENTRY_UPGRADES, CreditEntry::NAME);
+        Self {
+            seq: repr.seq,
+            timestamp: repr.timestamp,
+            credits: repr.credits,
+            source: repr.source,
+            prev_hash: repr.prev_hash,
+        }
+    }
+}
+
+/// Local credits ledger backed by an append-only file
+#[derive(Debug)]
+pub struct CreditsLedger {
+    path: PathBuf,
+    /// Node ID that relay receipts must name
+    owner: String,
+    entries: Vec<CreditEntry>,
+}
+
+impl CreditsLedger {
+    /// Open the ledger in the default data directory
+    pub fn open_default(owner: &str) -> Result<Self> {
+        let dir = p2pgo_core::p2pgo_dirs::Dirs::current().storage("Failed to find the data directory")?.data;
+        Self::open(dir.join(LEDGER_FILE), owner)
+    }
+
+    /// Open the ledger at `path`, verifying every entry already in it
+    pub fn open(path: impl AsRef<Path>, owner: &str) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).storage("Failed to read credits ledger"),
+        };
+
+        let mut entries: Vec<CreditEntry> = Vec::new();
+        let mut prev_hash = [0u8; 32];
+        for entry in serde_cbor::Deserializer::from_slice(&data).into_iter::<CreditEntry>() {
+            let entry = entry.map_err(|e| Error::malformed("credits ledger", e))?;
+            if entry.seq != entries.len() as u64 || entry.prev_hash != prev_hash {
+                return Err(Error::malformed("credits ledger", format!("chain is broken at entry {}", entries.len())));
+            }
+            if let CreditSource::Relay(receipt) = &entry.source {
+                receipt.verify().map_err(|e| {
+                    Error::malformed("credits ledger", format!("entry {} has a bad receipt: {}", entry.seq, e))
+                })?;
+            }
+            prev_hash = entry.hash()?;
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            path,
+            owner: owner.to_string(),
+            entries,
+        })
+    }
+
+    /// Total credits earned
+    pub fn balance(&self) -> u64 {
+        self.entries.iter().map(|e| e.credits).sum()
+    }
+
+    /// All entries, oldest first
+    pub fn history(&self) -> &[CreditEntry] {
+        &self.entries
+    }
+
+    /// Record relayed traffic, rejecting receipts that are forged, name
+    /// another relay or were already counted
+    pub fn record_relay(&mut self, receipt: RelayReceipt) -> Result<&CreditEntry> {
+        receipt.verify()?;
+        if receipt.relay_node != self.owner {
+            return Err(Error::malformed("relay receipt", format!("it is for {}, not {}", receipt.relay_node, self.owner)));
+        }
+        let seen = self.entries.iter().any(|e| {
+            matches!(&e.source, CreditSource::Relay(r) if r.signature == receipt.signature)
+        });
+        if seen {
+            return Err(Error::Duplicate("Relay receipt".to_string()));
+        }
+        let credits = receipt.bytes / BYTES_PER_CREDIT;
+        self.append(credits, CreditSource::Relay(receipt))
+    }
+
+    /// Record a scored game shared for training, once per game
+    pub fn record_shared_game(&mut self, game_id: &GameId) -> Result<&CreditEntry> {
+        let seen = self.entries.iter().any(|e| {
+            matches!(&e.source, CreditSource::SharedGame { game_id: id } if id == game_id)
+        });
+        if seen {
+            return Err(Error::Duplicate(format!("Credit for game {}", game_id)));
+        }
+        self.append(SHARED_GAME_CREDITS, CreditSource::SharedGame { game_id: game_id.clone() })
+    }
+
+    /// Record participation in a federated training round, once per round
+    pub fn record_federated_round(&mut self, round: u64) -> Result<&CreditEntry> {
+        let seen = self.entries.iter().any(|e| e.source == CreditSource::FederatedRound { round });
+        if seen {
+            return Err(Error::Duplicate(format!("Credit for federated round {}", round)));
+        }
+        self.append(FEDERATED_ROUND_CREDITS, CreditSource::FederatedRound { round })
+    }
+
+    fn append(&mut self, credits: u64, source: CreditSource) -> Result<&CreditEntry> {
+        let prev_hash = match self.entries.last() {
+            Some(last) => last.hash()?,
+            None => [0u8; 32],
+        };
+        let entry = CreditEntry {
+            seq: self.entries.len() as u64,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            credits,
+            source,
+            prev_hash,
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).storage("Failed to create data directory")?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .storage("Failed to open credits ledger")?;
+        file.write_all(&serde_cbor::to_vec(&entry).storage("Failed to encode credits entry")?)
+            .storage("Failed to append to credits ledger")?;
+        file.sync_data().storage("Failed to append to credits ledger")?;
+
+        tracing::info!(seq = entry.seq, credits, "Training credits recorded");
+        self.entries.push(entry);
+        Ok(self.entries.last().expect("entry was just pushed"))
+    }
+}
@@ -0,0 +1,86 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Duplicate detection for moves from peers.
+//!
+//! Each peer's moves are numbered by their index in the game. Per peer we
+//! keep the highest index seen and the indices seen within a reorder window
+//! below it: anything above the watermark is new, anything older than the
+//! window is a duplicate. Memory and time per move stay constant however
+//! long the game runs, and the state is dropped when the game ends.
+
+use std::collections::{HashMap, HashSet};
+
+/// Indices below a peer's highest still accepted if they arrive late
+pub const DEDUP_WINDOW: u64 = 64;
+
+#[derive(Debug, Default)]
+struct PeerSequences {
+    /// Highest index accepted so far
+    highest: Option<u64>,
+    /// Indices accepted within the window below `highest`, and older ones
+    /// not yet pruned
+    seen: HashSet<u64>,
+}
+
+/// Which move indices each peer has already delivered
+#[derive(Debug)]
+pub struct SequenceDedup {
+    window: u64,
+    peers: HashMap<String, PeerSequences>,
+}
+
+impl Default for SequenceDedup {
+    fn default() -> Self {
+        Self::new(DEDUP_WINDOW)
+    }
+}
+
+impl SequenceDedup {
+    /// Accept moves up to `window` indices behind a peer's latest
+    pub fn new(window: u64) -> Self {
+        Self { window: window.max(1), peers: HashMap::new() }
+    }
+
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Whether index `seq` from `peer` is new; does not record it
+    pub fn is_new(&self, peer: &str, seq: u64) -> bool {
+        let Some(sequences) = self.peers.get(peer) else {
+            return true;
+        };
+        match sequences.highest {
+            Some(highest) if seq > highest => true,
+            Some(highest) => highest - seq < self.window && !sequences.seen.contains(&seq),
+            None => true,
+        }
+    }
+
+    /// Record `seq` from `peer`; false if it was a duplicate
+    pub fn accept(&mut self, peer: &str, seq: u64) -> bool {
+        if !self.is_new(peer, seq) {
+            return false;
+        }
+        let window = self.window;
+        let sequences = self.peers.entry(peer.to_string()).or_default();
+        sequences.seen.insert(seq);
+        let highest = sequences.highest.map_or(seq, |h| h.max(seq));
+        sequences.highest = Some(highest);
+        // Prune in batches so each move costs constant time on average
+        if sequences.seen.len() as u64 > 2 * window {
+            sequences.seen.retain(|&s| highest - s < window);
+        }
+        true
+    }
+
+    /// Indices kept for `peer`, at most about twice the window
+    pub fn retained(&self, peer: &str) -> usize {
+        self.peers.get(peer).map_or(0, |s| s.seen.len())
+    }
+
+    /// Forget everything, e.g. once the game is over
+    pub fn clear(&mut self) {
+        self.peers.clear();
+    }
+}
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Errors from the public API of the network layer.
+//!
+//! Callers branch on the variant to decide whether to retry, resync the
+//! game or tell the player. Files that can't be read or written arrive as
+//! [`Error::Storage`], and bytes that don't decode as [`Error::Malformed`].
+//! A few private helpers still use `anyhow`; anything without a variant of
+//! its own arrives as [`Error::Internal`].
+
+use std::time::Duration;
+use p2pgo_core::{Color, GameError};
+use p2pgo_core::settings::OffBoardMove;
+use p2pgo_core::phase::{GameAction, GamePhase};
+use thiserror::Error;
+use crate::GameId;
+use crate::sanitize::Invalid;
+use crate::tournament::RecordError;
+use crate::undo::UndoError;
+
+/// Boxed cause of an error, such as a transport failure
+pub type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Result of the network layer's public API
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Errors that can occur in the network layer
+#[derive(Debug, Error)]
+pub enum Error {
+    /// Could not reach a peer
+    #[error("Failed to connect to {peer}: {source}")]
+    ConnectionFailed {
+        peer: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// The ticket is our own, so we would be connecting to ourselves
+    #[error("That ticket is your own; you can't connect to yourself")]
+    SelfConnection,
+
+    /// A ticket that doesn't decode
+    #[error("Invalid ticket: {0}")]
+    TicketInvalid(String),
+
+    /// No game with this ID is known here
+    #[error("Game not found: {0}")]
+    GameNotFound(GameId),
+
+    /// A game with this ID is already listed
+    #[error("Game already exists: {0}")]
+    GameExists(GameId),
+
+    /// Our copy of the game disagrees with a peer's, or we have none yet
+    #[error("Game {game_id} is out of sync: {reason}")]
+    SyncConflict { game_id: GameId, reason: String },
+
+    /// The move breaks the rules
+    #[error(transparent)]
+    Rules(#[from] GameError),
+
+    /// A move for a point outside this game's board, e.g. one set up at another size
+    #[error(transparent)]
+    OffBoard(#[from] OffBoardMove),
+
+    /// An undo that can't be asked for or answered now
+    #[error(transparent)]
+    Undo(#[from] UndoError),
+
+    /// Not something the game's phase allows, e.g. a move once it is over
+    #[error("Can't {action} while the game is {phase}")]
+    WrongPhase { phase: GamePhase, action: GameAction },
+
+    /// A move from someone without a seat in the game
+    #[error("{node_id} is not a player in {game_id}")]
+    NotAPlayer { node_id: String, game_id: GameId },
+
+    /// A move from a seated player whose turn it isn't
+    #[error("Not {0:?}'s turn")]
+    NotYourTurn(Color),
+
+    /// No relay can carry traffic for us yet
+    #[error("No relay available: {0}")]
+    RelayUnavailable(String),
+
+    /// Too large to gossip; send it over a direct stream or as a blob
+    #[error("Payload of {len} bytes exceeds the {limit} byte gossip limit")]
+    PayloadTooLarge { len: usize, limit: usize },
+
+    /// The game is played under rules newer than this build knows
+    #[error("This game uses ruleset version {advertised}, but this version of p2pgo only knows up to {supported}; update to join it")]
+    UnsupportedRuleset { advertised: u8, supported: u8 },
+
+    /// Something from a peer that can't be kept or shown
+    #[error("Invalid data from peer: {0}")]
+    Invalid(#[from] Invalid),
+
+    /// A tournament result that can't be signed or published
+    #[error(transparent)]
+    Tournament(#[from] RecordError),
+
+    /// A record of a saved tournament results file that doesn't decode
+    #[error("Record {index} of {file} doesn't decode: {reason}")]
+    TournamentRecord { index: usize, file: String, reason: String },
+
+    /// Bytes from a peer or from disk that don't decode
+    #[error("Malformed {what}: {reason}")]
+    Malformed { what: &'static str, reason: String },
+
+    /// Reading or writing the data directory failed
+    #[error("{what}: {source}")]
+    Storage {
+        what: String,
+        #[source]
+        source: BoxError,
+    },
+
+    /// Something recorded once was offered again, e.g. a game rated twice
+    #[error("{0} was already recorded")]
+    Duplicate(String),
+
+    /// A recorded session that replays differently
+    #[error("Step {step} ({input}): {reason}")]
+    Replay { step: usize, input: String, reason: String },
+
+    /// Gave up waiting
+    #[error("Timed out after {after:?} waiting for {what}")]
+    Timeout { what: &'static str, after: Duration },
+
+    /// A process-wide service set up a second time
+    #[error("{0} is already initialized")]
+    AlreadyInitialized(&'static str),
+
+    /// A process-wide service used before it was set up
+    #[error("{0} is not initialized")]
+    NotInitialized(&'static str),
+
+    /// A trace capture was asked for while one is running
+    #[error("A trace capture is already running")]
+    CaptureRunning,
+
+    /// A gossip topic stopped delivering messages
+    #[error("The {0} topic closed")]
+    TopicClosed(&'static str),
+
+    /// Anything else, with its context
+    #[error("{0}")]
+    Internal(BoxError),
+}
+
+impl Error {
+    /// An error with only a message to show
+    pub(crate) fn internal(message: impl std::fmt::Display) -> Self {
+        Self::Internal(message.to_string().into())
+    }
+
+    pub(crate) fn malformed(what: &'static str, reason: impl std::fmt::Display) -> Self {
+        Self::Malformed { what, reason: reason.to_string() }
+    }
+
+    pub(crate) fn sync_conflict(game_id: &str, reason: impl std::fmt::Display) -> Self {
+        Self::SyncConflict { game_id: game_id.to_string(), reason: reason.to_string() }
+    }
+}
+
+/// Say what we were doing when a file operation failed
+pub(crate) trait StorageContext<T> {
+    fn storage(self, what: impl Into<String>) -> Result<T>;
+}
+
+impl<T, E: Into<BoxError>> StorageContext<T> for std::result::Result<T, E> {
+    fn storage(self, what: impl Into<String>) -> Result<T> {
+        self.map_err(|source| Error::Storage { what: what.into(), source: source.into() })
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    /// Keep a typed error that travelled through `anyhow` typed
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<Error>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<Invalid>() {
+            Ok(invalid) => return Self::Invalid(invalid),
+            Err(error) => error,
+        };
+        match error.downcast::<GameError>() {
+            Ok(rule) => Self::Rules(rule),
+            Err(error) => Self::Internal(error.into()),
+        }
+    }
+}
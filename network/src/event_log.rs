@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game events with a history late subscribers can replay.
+//!
+//! A broadcast channel only hands a subscriber what is sent after it
+//! subscribes, so a view created just after a burst of moves misses them.
+//! [`EventLog`] numbers every event and keeps the latest ones;
+//! [`EventLog::subscribe_with_history`] replays them ahead of the live
+//! stream. A state snapshot taken while [`EventLog::hold`] is held says
+//! which event it is up to, so the consumer skips what the snapshot already
+//! shows and misses nothing after it.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::{self, error::{RecvError, SendError, TryRecvError}};
+use p2pgo_core::GameEvent;
+
+/// Events a channel keeps for late subscribers
+pub const EVENT_HISTORY: usize = 256;
+
+/// Events a live subscriber may fall behind by before missing some
+const LIVE_BUFFER: usize = 100;
+
+/// An event with its place in the game's event order, counting from 1
+#[derive(Debug, Clone)]
+pub struct SequencedEvent {
+    pub seq: u64,
+    pub event: GameEvent,
+}
+
+#[derive(Debug)]
+struct History {
+    last_seq: u64,
+    events: VecDeque<SequencedEvent>,
+    capacity: usize,
+}
+
+/// Sender of a game's events, keeping the latest for late subscribers
+#[derive(Debug, Clone)]
+pub struct EventLog {
+    tx: broadcast::Sender<GameEvent>,
+    sequenced_tx: broadcast::Sender<SequencedEvent>,
+    history: Arc<Mutex<History>>,
+    /// Held while the state changes and its events go out
+    order: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::with_capacity(EVENT_HISTORY)
+    }
+}
+
+impl EventLog {
+    /// A log keeping the last `capacity` events
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            tx: broadcast::channel(LIVE_BUFFER).0,
+            sequenced_tx: broadcast::channel(LIVE_BUFFER).0,
+            history: Arc::new(Mutex::new(History { last_seq: 0, events: VecDeque::new(), capacity })),
+            order: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Send `event` to every subscriber and keep it, returning how many
+    /// subscribers there are; fails, as a broadcast channel does, only if
+    /// nobody hears it and no history keeps it
+    pub fn send(&self, event: GameEvent) -> Result<usize, SendError<GameEvent>> {
+        let mut history = self.history.lock().unwrap();
+        history.last_seq += 1;
+        let sequenced = SequencedEvent { seq: history.last_seq, event: event.clone() };
+        if history.events.len() == history.capacity {
+            history.events.pop_front();
+        }
+        if history.capacity > 0 {
+            history.events.push_back(sequenced.clone());
+        }
+        // Sent with the history locked, so a subscriber's replay and live
+        // stream neither overlap nor leave a gap
+        let replaying = self.sequenced_tx.send(sequenced).unwrap_or(0);
+        match self.tx.send(event) {
+            Ok(live) => Ok(live + replaying),
+            Err(_) if replaying > 0 || history.capacity > 0 => Ok(replaying),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Events sent from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<GameEvent> {
+        self.tx.subscribe()
+    }
+
+    /// The last `n` events kept, then those sent from now on
+    pub fn subscribe_with_history(&self, n: usize) -> EventStream {
+        let history = self.history.lock().unwrap();
+        let live = self.sequenced_tx.subscribe();
+        let skip = history.events.len().saturating_sub(n);
+        EventStream { backlog: history.events.iter().skip(skip).cloned().collect(), live, after: 0 }
+    }
+
+    /// Number of the last event sent, 0 before the first
+    pub fn last_seq(&self) -> u64 {
+        self.history.lock().unwrap().last_seq
+    }
+
+    /// Keep snapshots out until the guard is dropped; held while the state
+    /// changes and until the events of the change are sent
+    pub async fn hold(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.order.lock().await
+    }
+}
+
+/// Events replayed from a log's history, then its live ones
+#[derive(Debug)]
+pub struct EventStream {
+    backlog: VecDeque<SequencedEvent>,
+    live: broadcast::Receiver<SequencedEvent>,
+    /// Events up to this one are skipped
+    after: u64,
+}
+
+impl EventStream {
+    /// Skip the events up to `seq`, which a snapshot already shows
+    pub fn skip_through(&mut self, seq: u64) {
+        self.after = self.after.max(seq);
+    }
+
+    /// The next event, waiting for a live one once the history is replayed
+    pub async fn recv(&mut self) -> Result<SequencedEvent, RecvError> {
+        loop {
+            let event = match self.backlog.pop_front() {
+                Some(event) => event,
+                None => self.live.recv().await?,
+            };
+            if event.seq > self.after {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// The next event if one is waiting
+    pub fn try_recv(&mut self) -> Result<SequencedEvent, TryRecvError> {
+        loop {
+            let event = match self.backlog.pop_front() {
+                Some(event) => event,
+                None => self.live.try_recv()?,
+            };
+            if event.seq > self.after {
+                return Ok(event);
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Size limits for gossip messages, and fragmenting larger payloads.
+//!
+//! Gossip drops messages above its maximum size, so payloads are checked
+//! before they're sent. Above [`GossipLimits::max_message`] a payload is
+//! either refused with [`Error::PayloadTooLarge`], for the caller to send
+//! over a direct stream or as a blob, or split into numbered fragments.
+//!
+//! A fragment is a header, [`FRAGMENT_MAGIC`] then the message ID, index,
+//! count and the node ID of the peer that split it, followed by its share
+//! of the payload. Gossip may hand us the fragments of one message through
+//! different neighbours, so they are put back together by that origin and
+//! the message ID rather than by who delivered them. CBOR never starts
+//! with the magic byte `0xf7` followed by more data, so receivers tell
+//! fragments apart from whole messages without an envelope around the
+//! latter, and older peers keep reading unfragmented messages.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::{Error, Result};
+
+/// Largest message gossip delivers by default
+pub const GOSSIP_MAX_MESSAGE: usize = 4096;
+
+/// Start of every fragment
+pub const FRAGMENT_MAGIC: [u8; 4] = [0xf7, b'P', b'2', b'F'];
+
+/// Magic, message ID, index, count and the origin's length, before the origin
+const FIXED_HEADER_LEN: usize = FRAGMENT_MAGIC.len() + 8 + 2 + 2 + 1;
+
+/// What to do with a payload too large for one message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Oversize {
+    /// Fail with [`Error::PayloadTooLarge`]
+    Refuse,
+    /// Send it as numbered fragments
+    Fragment,
+}
+
+/// Message size limits on gossip topics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipLimits {
+    /// Largest message sent whole, fragment headers included
+    pub max_message: usize,
+    pub oversize: Oversize,
+    /// How long a partly received payload waits for its other fragments
+    pub reassembly_timeout: Duration,
+    /// Bytes of unfinished payloads kept per origin
+    pub max_buffered_per_sender: usize,
+}
+
+impl Default for GossipLimits {
+    fn default() -> Self {
+        Self {
+            max_message: GOSSIP_MAX_MESSAGE,
+            oversize: Oversize::Fragment,
+            reassembly_timeout: Duration::from_secs(30),
+            max_buffered_per_sender: 1024 * 1024,
+        }
+    }
+}
+
+/// The messages `origin`, our node ID, sends for `payload`: itself if it
+/// fits, else fragments
+pub fn split(payload: &[u8], origin: &str, limits: &GossipLimits) -> Result<Vec<Vec<u8>>> {
+    if payload.len() <= limits.max_message {
+        return Ok(vec![payload.to_vec()]);
+    }
+    let too_large = Error::PayloadTooLarge { len: payload.len(), limit: limits.max_message };
+    let Ok(origin_len) = u8::try_from(origin.len()) else {
+        return Err(too_large);
+    };
+    let header_len = FIXED_HEADER_LEN + origin.len();
+    if limits.oversize == Oversize::Refuse || limits.max_message <= header_len {
+        return Err(too_large);
+    }
+    let chunk = limits.max_message - header_len;
+    let Ok(count) = u16::try_from(payload.len().div_ceil(chunk)) else {
+        return Err(too_large);
+    };
+
+    let hash = blake3::hash(payload);
+    let id = u64::from_be_bytes(hash.as_bytes()[..8].try_into().expect("8 bytes"));
+    Ok(payload
+        .chunks(chunk)
+        .enumerate()
+        .map(|(index, data)| {
+            let mut fragment = Vec::with_capacity(header_len + data.len());
+            fragment.extend_from_slice(&FRAGMENT_MAGIC);
+            fragment.extend_from_slice(&id.to_be_bytes());
+            fragment.extend_from_slice(&(index as u16).to_be_bytes());
+            fragment.extend_from_slice(&count.to_be_bytes());
+            fragment.push(origin_len);
+            fragment.extend_from_slice(origin.as_bytes());
+            fragment.extend_from_slice(data);
+            fragment
+        })
+        .collect())
+}
+
+/// Whether `message` is a fragment rather than a whole payload
+pub fn is_fragment(message: &[u8]) -> bool {
+    message.len() > FRAGMENT_MAGIC.len() && message.starts_with(&FRAGMENT_MAGIC)
+}
+
+/// A payload some of whose fragments have arrived
+#[derive(Debug)]
+struct Partial {
+    parts: Vec<Option<Vec<u8>>>,
+    bytes: usize,
+    first_seen: Instant,
+}
+
+/// Puts fragmented payloads back together, by origin and message ID
+#[derive(Debug)]
+pub struct Reassembler {
+    limits: GossipLimits,
+    partial: HashMap<(String, u64), Partial>,
+}
+
+impl Reassembler {
+    pub fn new(limits: GossipLimits) -> Self {
+        Self { limits, partial: HashMap::new() }
+    }
+
+    /// Take a message `delivered_from` a neighbour: the whole payload once
+    /// complete, `None` while fragments are missing.
+    ///
+    /// Payloads that would take their origin past its buffer are dropped
+    /// with their fragments so far.
+    pub fn receive(&mut self, delivered_from: &str, message: &[u8], now: Instant) -> Result<Option<Vec<u8>>> {
+        self.expire(now);
+        if !is_fragment(message) {
+            return Ok(Some(message.to_vec()));
+        }
+        if message.len() < FIXED_HEADER_LEN {
+            return Err(Error::malformed("fragment", format!("truncated header from {}", delivered_from)));
+        }
+        let id = u64::from_be_bytes(message[4..12].try_into().expect("8 bytes"));
+        let index = u16::from_be_bytes([message[12], message[13]]) as usize;
+        let count = u16::from_be_bytes([message[14], message[15]]) as usize;
+        let header_len = FIXED_HEADER_LEN + message[16] as usize;
+        if message.len() < header_len {
+            return Err(Error::malformed("fragment", format!("truncated origin from {}", delivered_from)));
+        }
+        let Ok(sender) = std::str::from_utf8(&message[FIXED_HEADER_LEN..header_len]) else {
+            return Err(Error::malformed("fragment", format!("invalid origin from {}", delivered_from)));
+        };
+        if index >= count {
+            return Err(Error::malformed("fragment", format!("{} of {} from {}", index, count, sender)));
+        }
+        let data = &message[header_len..];
+
+        let key = (sender.to_string(), id);
+        if self.buffered(sender) + data.len() > self.limits.max_buffered_per_sender {
+            self.partial.remove(&key);
+            return Err(Error::malformed("fragment", format!(
+                "{} has more than {} bytes of fragments waiting; dropped message {:016x}",
+                sender, self.limits.max_buffered_per_sender, id
+            )));
+        }
+        let partial = self.partial.entry(key.clone()).or_insert_with(|| Partial {
+            parts: vec![None; count],
+            bytes: 0,
+            first_seen: now,
+        });
+        if partial.parts.len() != count {
+            return Err(Error::malformed("fragment", format!("message {:016x} from {} disagrees on its count", id, sender)));
+        }
+        if partial.parts[index].is_none() {
+            partial.bytes += data.len();
+            partial.parts[index] = Some(data.to_vec());
+        }
+        if partial.parts.iter().any(Option::is_none) {
+            return Ok(None);
+        }
+
+        let partial = self.partial.remove(&key).expect("present");
+        Ok(Some(partial.parts.into_iter().flatten().flatten().collect()))
+    }
+
+    /// Drop payloads still missing fragments after the timeout, returning
+    /// how many were dropped
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let timeout = self.limits.reassembly_timeout;
+        let before = self.partial.len();
+        self.partial.retain(|(sender, id), partial| {
+            let alive = now.saturating_duration_since(partial.first_seen) < timeout;
+            if !alive {
+                let missing = partial.parts.iter().filter(|p| p.is_none()).count();
+                tracing::warn!("Gave up on message {:016x} from {}: {} of {} fragments missing", id, sender, missing, partial.parts.len());
+            }
+            alive
+        });
+        before - self.partial.len()
+    }
+
+    /// Bytes of unfinished payloads held for the peer that split them
+    pub fn buffered(&self, sender: &str) -> usize {
+        self.partial
+            .iter()
+            .filter(|((from, _), _)| from == sender)
+            .map(|(_, partial)| partial.bytes)
+            .sum()
+    }
+}
@@ -3,25 +3,47 @@
 
 //! Game channel for communication between players
 
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::sync::{broadcast, RwLock};
-use anyhow::{Result, Context};
-use p2pgo_core::{Move, GameState, GameEvent, MoveRecord};
-use crate::GameId;
-use crate::blob_store::{MoveBlob, MoveChain};
+use anyhow::Context;
+use p2pgo_core::{Color, Move, GameState, GameEvent, GameError, EndReason, MoveRecord};
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::game_clock::{ClockSnapshot, GameClock, GameTiming, OnDisconnect, TimingInfo};
+use p2pgo_core::phase::{GameAction, GamePhase, GameResult};
+use p2pgo_core::value_labeller::ScoreProof;
+use p2pgo_core::scoring::game_ended_event;
+use p2pgo_core::teaching::{TeachingAnnotation, TeachingLayer};
+use crate::{Error, GameId, Result};
+use crate::blob_store::{MoveBlob, MoveChain, Retraction};
+use crate::rate_limit::{DisconnectReason, MoveAnomaly, MoveRateLimiter, RateLimitConfig, Verdict};
+use crate::presence::{Presence, PresenceFilter};
+use crate::timing_privacy::TimingPrivacy;
+use crate::dedup::SequenceDedup;
+use crate::channel_metrics::{ChannelMetrics, MetricsSnapshot, ACK_TIMEOUT};
+use crate::channel_tuning::{AckWatchdog, ChannelTuning};
+use crate::relay_robustness::{ClockSkew, PeerClocks};
+use crate::clock::{Clock, SystemClock};
+use crate::session_log::{self, Command, SessionInput};
+use crate::wire::DirectMessage;
+use crate::event_log::{EventLog, EventStream};
+use crate::sanitize;
+use crate::undo::{UndoError, UndoState};
+use crate::tournament::{game_digest, RecordError, ResultRecord, TournamentFeed};
+use crate::Identity;
+use crate::iroh_endpoint::IrohCtx;
+use crate::health::{self, Supervisor};
 
 // Import iroh-docs only when feature is enabled
 #[cfg(feature = "iroh")]
 use {
-    crate::iroh_endpoint::IrohCtx,
     blake3::Hasher,
     iroh_docs::NamespaceId,
-    iroh_gossip::proto::TopicId,
     iroh::{endpoint::Connection},
-    std::collections::HashSet,
-    tokio::task::JoinHandle,
-    serde_json,
     blake3,
+    crate::wire::{self, WireFormat},
+    crate::traffic::{self, TrafficCategory},
 };
 
 /// Status of a player in the game
@@ -35,32 +57,177 @@ pub enum PlayerStatus {
     Watching,
 }
 
+/// What peers may do in a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelRole {
+    /// Peers play their moves into the game
+    #[default]
+    Player,
+    /// We play both colors and peers only watch; their moves are refused
+    Broadcast,
+}
+
+/// What happened to a queued premove once its turn came
+#[derive(Debug, Clone, PartialEq)]
+pub enum PremoveOutcome {
+    /// The premove was still legal and has been sent
+    Played(Move),
+    /// The new position made the premove illegal; it was dropped
+    Discarded { mv: Move, error: GameError },
+}
+
+/// Who teaches the game, and what they have shown so far
+#[derive(Debug, Default)]
+struct Teaching {
+    /// Node ID of the only peer whose annotations are accepted
+    teacher: Option<String>,
+    layer: TeachingLayer,
+}
+
+/// The game clock, when this channel keeps it
+#[derive(Debug)]
+struct Timekeeping {
+    clock: GameClock,
+    on_disconnect: OnDisconnect,
+    /// Each mover's clock as they moved, for archives and SGF
+    timing: GameTiming,
+}
+
+/// A tournament this game is played for
+struct Publishing {
+    feed: TournamentFeed,
+    /// Signs our share of the result
+    identity: Identity,
+    /// Our signed proposal, until the opponent countersigns it
+    pending: Option<ResultRecord>,
+}
+
 /// A channel for game-related communication
 pub struct GameChannel {
     /// Game ID
     game_id: GameId,
+    /// Board size, komi and rules the game is played with
+    settings: GameSettings,
+    /// Whether peers may play or only watch
+    role: ChannelRole,
     /// Move chain for storing game history
     move_chain: Arc<RwLock<MoveChain>>,
-    /// Event broadcast channel
-    events_tx: broadcast::Sender<GameEvent>,
+    /// Events, with the latest kept for late subscribers
+    events_tx: EventLog,
     /// Latest game state
     latest_state: Arc<RwLock<Option<GameState>>>,
+    /// Move queued by a player during the opponent's turn
+    premove: Arc<RwLock<Option<(Move, Color)>>>,
+    /// Per-peer move rate limits and timing anomalies
+    rate_limiter: Arc<RwLock<MoveRateLimiter>>,
+    /// Sync counters and ACK round trips
+    metrics: Arc<ChannelMetrics>,
+    /// When to ask for a sync about moves the peer never acknowledged
+    watchdog: std::sync::Mutex<AckWatchdog>,
+    /// Per-peer clock offsets and skew reported so far
+    clocks: Arc<RwLock<PeerClocks>>,
+    /// Whether this is a correspondence game, whose peers may be offline
+    correspondence: AtomicBool,
+    /// Our moves no peer has received yet, kept in correspondence games
+    outbox: Arc<RwLock<Vec<MoveRecord>>>,
+    /// Coarse timestamps and a jittered first move, when set
+    timing_privacy: RwLock<Option<TimingPrivacy>>,
+    /// Whether we sent a move yet; only the first is jittered
+    moved: AtomicBool,
+    /// Teacher's annotations, kept apart from the move chain
+    teaching: Arc<RwLock<Teaching>>,
+    /// Drops presence from peers sending it too often
+    presence: Arc<RwLock<PresenceFilter>>,
+    /// Game clock we keep; without one we show the snapshots peers send
+    timekeeping: Arc<RwLock<Option<Timekeeping>>>,
+    /// Clocks as last announced, to tell the byo-yomi periods used since
+    shown_clock: Arc<RwLock<Option<ClockSnapshot>>>,
+    publishing: Arc<RwLock<Option<Publishing>>>,
+    /// Move indices each peer has delivered, to drop duplicates
+    processed_sequences: Arc<RwLock<SequenceDedup>>,
+    /// Undo requests either way and how many were granted
+    undo: Arc<RwLock<UndoState>>,
+    /// Where the game stands; decides what we and peers may do
+    phase: Arc<RwLock<GamePhase>>,
+    /// Score each player accepted, until both accepted the same one
+    score_accepts: Arc<RwLock<HashMap<Color, ScoreProof>>>,
+    /// Peers whose hello said they read framed messages, and so acknowledge our moves
+    framing_peers: Arc<RwLock<HashSet<String>>>,
+    /// Time source; virtual when a session is replayed
+    clock: Arc<dyn Clock>,
     
-    /// Iroh networking context when feature is enabled
-    #[cfg(feature = "iroh")]
+    /// How peers are reached, once connected; loopback without iroh
     iroh_ctx: Option<Arc<IrohCtx>>,
+    /// Restarts the tasks taking in peers' moves, when set
+    supervisor: Option<Supervisor>,
     
     /// Active connections to peers for this game
     #[cfg(feature = "iroh")]
     peer_connections: Arc<RwLock<Vec<Connection>>>,
     
-    /// Background task for handling incoming connections
+    /// Format each peer reads, by connection stable ID, once it said hello
     #[cfg(feature = "iroh")]
-    _connection_task: Option<JoinHandle<()>>,
-    
-    /// Set of already processed sequence numbers to avoid duplicates
-    #[cfg(feature = "iroh")]
-    processed_sequences: Arc<RwLock<HashSet<u32>>>,
+    peer_formats: Arc<RwLock<HashMap<usize, WireFormat>>>,
+}
+
+/// What a message from a peer can touch, shared by the channel and the
+/// handlers of its connections
+#[derive(Clone)]
+struct Inbound {
+    game_id: GameId,
+    settings: GameSettings,
+    role: ChannelRole,
+    events_tx: EventLog,
+    latest_state: Arc<RwLock<Option<GameState>>>,
+    move_chain: Arc<RwLock<MoveChain>>,
+    processed_sequences: Arc<RwLock<SequenceDedup>>,
+    rate_limiter: Arc<RwLock<MoveRateLimiter>>,
+    metrics: Arc<ChannelMetrics>,
+    clocks: Arc<RwLock<PeerClocks>>,
+    outbox: Arc<RwLock<Vec<MoveRecord>>>,
+    teaching: Arc<RwLock<Teaching>>,
+    presence: Arc<RwLock<PresenceFilter>>,
+    timekeeping: Arc<RwLock<Option<Timekeeping>>>,
+    shown_clock: Arc<RwLock<Option<ClockSnapshot>>>,
+    publishing: Arc<RwLock<Option<Publishing>>>,
+    undo: Arc<RwLock<UndoState>>,
+    phase: Arc<RwLock<GamePhase>>,
+    score_accepts: Arc<RwLock<HashMap<Color, ScoreProof>>>,
+    framing_peers: Arc<RwLock<HashSet<String>>>,
+    clock: Arc<dyn Clock>,
+}
+
+/// What the connection should do after a message from a peer
+#[derive(Debug, Clone)]
+pub enum InboundOutcome {
+    /// Nothing more to do
+    Handled,
+    /// Answer the peer with this message
+    Reply(DirectMessage),
+    /// Send the peer the moves it missed; our outbox is delivered once sent
+    DeliverPending(DirectMessage),
+    /// Close the connection
+    Disconnect(DisconnectReason),
+}
+
+/// Name a channel's gossip listener is supervised under
+fn gossip_listener_name(game_id: &str) -> String {
+    format!("gossip listener for game {}", game_id)
+}
+
+/// Name a channel's connection acceptor is supervised under
+fn acceptor_name(game_id: &str) -> String {
+    format!("connection acceptor for game {}", game_id)
+}
+
+impl Drop for GameChannel {
+    /// Stop the channel's supervised tasks, which are of no use without it
+    fn drop(&mut self) {
+        if let Some(supervisor) = &self.supervisor {
+            supervisor.unregister(&gossip_listener_name(&self.game_id));
+            supervisor.unregister(&acceptor_name(&self.game_id));
+        }
+    }
 }
 
 impl GameChannel {
@@ -68,31 +235,248 @@ impl GameChannel {
     pub fn new(game_id: GameId, initial_state: GameState) -> Self {
         let _span = tracing::info_span!("network.game_channel", "GameChannel::new").entered();
         
-        // Create a broadcast channel for events with buffer size 100
-        let (events_tx, _) = broadcast::channel(100);
+        // Events, the latest replayable
+        let events_tx = EventLog::default();
+        
+        // Create move chain; a game resumed mid-way keeps its moves so
+        // peers can still sync from us
+        let move_chain = Self::replay_chain(&game_id, &initial_state);
+        let settings = GameSettings::standard(initial_state.board_size);
+        let phase = GamePhase::of(&initial_state);
         
-        // Create move chain
-        let move_chain = MoveChain::new(game_id.clone());
+        if session_log::is_recording() {
+            let moves = initial_state.moves.clone();
+            session_log::record(Some(&game_id), SessionInput::Open { state: initial_state.clone() }, &moves);
+        }
         
         #[cfg(not(feature = "iroh"))]
         return Self {
             game_id,
+            settings,
+            role: ChannelRole::Player,
             move_chain: Arc::new(RwLock::new(move_chain)),
             events_tx,
             latest_state: Arc::new(RwLock::new(Some(initial_state))),
+            premove: Arc::new(RwLock::new(None)),
+            rate_limiter: Arc::new(RwLock::new(MoveRateLimiter::default())),
+            metrics: Arc::new(ChannelMetrics::new()),
+            watchdog: std::sync::Mutex::new(AckWatchdog::default()),
+            clocks: Arc::new(RwLock::new(PeerClocks::new())),
+            correspondence: AtomicBool::new(false),
+            outbox: Arc::new(RwLock::new(Vec::new())),
+            timing_privacy: RwLock::new(None),
+            moved: AtomicBool::new(false),
+            teaching: Arc::new(RwLock::new(Teaching::default())),
+            presence: Arc::new(RwLock::new(PresenceFilter::default())),
+            timekeeping: Arc::new(RwLock::new(None)),
+            shown_clock: Arc::new(RwLock::new(None)),
+            publishing: Arc::new(RwLock::new(None)),
+            processed_sequences: Arc::new(RwLock::new(SequenceDedup::default())),
+            undo: Arc::new(RwLock::new(UndoState::new(settings.undo_limit))),
+            phase: Arc::new(RwLock::new(phase)),
+            score_accepts: Arc::new(RwLock::new(HashMap::new())),
+            framing_peers: Arc::new(RwLock::new(HashSet::new())),
+            clock: Arc::new(SystemClock),
+            iroh_ctx: None,
+            supervisor: None,
         };
         
         #[cfg(feature = "iroh")]
         return Self {
             game_id,
+            settings,
+            role: ChannelRole::Player,
             move_chain: Arc::new(RwLock::new(move_chain)),
             events_tx,
             latest_state: Arc::new(RwLock::new(Some(initial_state))),
+            premove: Arc::new(RwLock::new(None)),
+            rate_limiter: Arc::new(RwLock::new(MoveRateLimiter::default())),
+            metrics: Arc::new(ChannelMetrics::new()),
+            watchdog: std::sync::Mutex::new(AckWatchdog::default()),
+            clocks: Arc::new(RwLock::new(PeerClocks::new())),
+            correspondence: AtomicBool::new(false),
+            outbox: Arc::new(RwLock::new(Vec::new())),
+            timing_privacy: RwLock::new(None),
+            moved: AtomicBool::new(false),
+            teaching: Arc::new(RwLock::new(Teaching::default())),
+            presence: Arc::new(RwLock::new(PresenceFilter::default())),
+            timekeeping: Arc::new(RwLock::new(None)),
+            shown_clock: Arc::new(RwLock::new(None)),
+            publishing: Arc::new(RwLock::new(None)),
+            processed_sequences: Arc::new(RwLock::new(SequenceDedup::default())),
+            undo: Arc::new(RwLock::new(UndoState::new(settings.undo_limit))),
+            phase: Arc::new(RwLock::new(phase)),
+            score_accepts: Arc::new(RwLock::new(HashMap::new())),
+            framing_peers: Arc::new(RwLock::new(HashSet::new())),
+            clock: Arc::new(SystemClock),
             iroh_ctx: None,
+            supervisor: None,
             peer_connections: Arc::new(RwLock::new(Vec::new())),
-            _connection_task: None,
-            processed_sequences: Arc::new(RwLock::new(HashSet::new())),
+            peer_formats: Arc::new(RwLock::new(HashMap::new())),
+        };
+    }
+    
+    /// Read the time from `clock` instead of the system, as a replayed
+    /// session does; set it before any peer connects
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Play with `settings` rather than the standard ones for the board;
+    /// set them before any peer connects
+    pub fn with_settings(mut self, settings: GameSettings) -> Self {
+        self.settings = settings;
+        self.undo = Arc::new(RwLock::new(UndoState::new(settings.undo_limit)));
+        self
+    }
+    
+    /// Board size, komi and rules the game is played with
+    pub fn settings(&self) -> GameSettings {
+        self.settings
+    }
+    
+    /// Accept a peer's moves arriving up to `window` moves late, rather
+    /// than [`DEDUP_WINDOW`](crate::dedup::DEDUP_WINDOW); set it before any
+    /// peer connects
+    pub fn with_dedup_window(mut self, window: u64) -> Self {
+        self.processed_sequences = Arc::new(RwLock::new(SequenceDedup::new(window)));
+        self
+    }
+    
+    /// Run the tasks taking in peers' moves under `supervisor`, which
+    /// restarts them should they die or wedge; set it before connecting
+    pub fn with_supervisor(mut self, supervisor: Supervisor) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
+    
+    /// Let peers play, or only watch; set it before any peer connects
+    pub fn with_role(mut self, role: ChannelRole) -> Self {
+        self.role = role;
+        self
+    }
+    
+    pub fn role(&self) -> ChannelRole {
+        self.role
+    }
+    
+    /// Where the game stands
+    pub async fn phase(&self) -> GamePhase {
+        self.phase.read().await.clone()
+    }
+    
+    /// Wait for someone to take the opponent's seat; until then no moves
+    /// are played. Does nothing once the first move is played.
+    pub async fn await_opponent(&self) {
+        let begun = self.latest_state.read().await.as_ref().is_some_and(|s| !s.moves.is_empty());
+        if !begun && self.phase().await == GamePhase::Active {
+            self.inbound().enter(GamePhase::AwaitingOpponent).await;
+        }
+    }
+    
+    /// The opponent took its seat; play begins
+    pub async fn start(&self) {
+        if self.phase().await == GamePhase::AwaitingOpponent {
+            self.inbound().enter(GamePhase::Active).await;
+        }
+    }
+    
+    /// Both players agreed on the score of a game they passed in
+    pub async fn settle(&self, result: GameResult) -> Result<()> {
+        let inbound = self.inbound();
+        inbound.check_phase(GameAction::Score).await?;
+        inbound.enter(GamePhase::Finished { result }).await;
+        Ok(())
+    }
+    
+    /// The player of `by` accepts `proof` as the score. Peers hear of it;
+    /// the game settles, here and on their side, once both players
+    /// accepted the same score.
+    pub async fn accept_score(&self, by: Color, proof: ScoreProof) -> Result<()> {
+        let inbound = self.inbound();
+        inbound.check_phase(GameAction::Score).await?;
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::ScoreAccepted { by, proof: proof.clone() }, "score acceptance").await;
+        inbound.accept_score(by, proof.clone()).await;
+        self.record(SessionInput::Command(Command::AcceptScore { by, proof })).await;
+        Ok(())
+    }
+    
+    /// The score `color` accepted, if any
+    pub async fn accepted_score(&self, color: Color) -> Option<ScoreProof> {
+        self.score_accepts.read().await.get(&color).cloned()
+    }
+    
+    /// Play the game for `feed`'s tournament: once it is over, the player
+    /// who proposes the result publishes it there when both have signed
+    pub async fn publish_to(&self, feed: TournamentFeed, identity: Identity) {
+        *self.publishing.write().await = Some(Publishing { feed, identity, pending: None });
+    }
+    
+    /// Sign the finished game's result between `players`, Black's key
+    /// first, and ask the opponent to countersign it
+    pub async fn propose_result(&self, players: ([u8; 32], [u8; 32]), proof: Option<&ScoreProof>) -> Result<ResultRecord> {
+        let GamePhase::Finished { result } = self.phase.read().await.clone() else {
+            return Err(Error::internal("Only a finished game's result can be published"));
         };
+        let state = self.get_latest_state().await.ok_or_else(|| Error::internal("No game state available"))?;
+        let record = {
+            let mut publishing = self.publishing.write().await;
+            let publishing = publishing.as_mut().ok_or_else(|| Error::internal("This game isn't played for a tournament"))?;
+            let mut record = ResultRecord::new(publishing.feed.tournament(), &self.game_id, &state, players, result, proof);
+            record.sign(&publishing.identity)?;
+            publishing.pending = Some(record.clone());
+            record
+        };
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::SignResult(record.clone()), "result to sign").await;
+        Ok(record)
+    }
+    
+    /// The channel's share of state for handling peer messages
+    fn inbound(&self) -> Inbound {
+        Inbound {
+            game_id: self.game_id.clone(),
+            settings: self.settings,
+            role: self.role,
+            events_tx: self.events_tx.clone(),
+            latest_state: self.latest_state.clone(),
+            move_chain: self.move_chain.clone(),
+            processed_sequences: self.processed_sequences.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            metrics: self.metrics.clone(),
+            clocks: self.clocks.clone(),
+            outbox: self.outbox.clone(),
+            teaching: self.teaching.clone(),
+            presence: self.presence.clone(),
+            timekeeping: self.timekeeping.clone(),
+            shown_clock: self.shown_clock.clone(),
+            publishing: self.publishing.clone(),
+            undo: self.undo.clone(),
+            phase: self.phase.clone(),
+            score_accepts: self.score_accepts.clone(),
+            framing_peers: self.framing_peers.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+    
+    /// The chain of the moves already played in `state`
+    fn replay_chain(game_id: &GameId, state: &GameState) -> MoveChain {
+        let mut chain = MoveChain::new(game_id.clone());
+        let mut replayed = state.initial_position();
+        for (sequence, mv) in state.moves.iter().enumerate() {
+            if let Err(e) = replayed.apply_move(mv.clone()) {
+                tracing::warn!(game_id = %game_id, "Stopped replaying saved moves at {}: {}", sequence, e);
+                break;
+            }
+            let prev_hash = chain.current_blob().map(|blob| blob.hash());
+            let blob = MoveBlob::new(game_id.clone(), mv.clone(), prev_hash, replayed.clone(), sequence as u32);
+            if chain.add_blob(blob).is_err() {
+                break;
+            }
+        }
+        chain
     }
     
     /// Create a document ID from a game ID
@@ -107,149 +491,129 @@ impl GameChannel {
         NamespaceId::from(hash.as_bytes())
     }
     
-    /// Create a gossip topic ID for a game
-    #[cfg(feature = "iroh")]
-    fn game_topic_id(game_id: &str) -> TopicId {
-        // Use the same topic generation as IrohCtx to ensure consistency
-        let topic_name = format!("p2pgo.game.{}", game_id);
-        TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
-    }
-    
     /// Create a new game channel with an Iroh context for network synchronization
-    #[cfg(feature = "iroh")]
     #[tracing::instrument(level = "debug", skip(iroh_ctx))]
     pub async fn with_iroh(game_id: GameId, initial_state: GameState, iroh_ctx: Arc<IrohCtx>) -> Result<Self> {
-        tracing::info!("Creating GameChannel with Iroh for game {}", game_id);
-        
-        // Create the basic channel
-        let mut channel = Self::new(game_id.clone(), initial_state.clone());
-        
-        // Set the iroh context
-        channel.iroh_ctx = Some(iroh_ctx.clone());
-        
-        // Start connection handler that will handle both incoming and outgoing connections
-        let peer_connections = channel.peer_connections.clone();
-        let events_tx = channel.events_tx.clone();
-        let processed_sequences = channel.processed_sequences.clone();
-        let move_chain = channel.move_chain.clone();
-        let latest_state = channel.latest_state.clone();
-        let game_id_for_task = game_id.clone();
+        Self::new(game_id, initial_state).connect(iroh_ctx).await
+    }
+    
+    /// Play the game with peers reached through `iroh_ctx`: moves go out
+    /// on the game's gossip topic, and with iroh over direct connections too
+    pub async fn connect(mut self, iroh_ctx: Arc<IrohCtx>) -> Result<Self> {
+        let game_id = self.game_id.clone();
+        tracing::info!("Connecting GameChannel for game {}", game_id);
+        self.iroh_ctx = Some(iroh_ctx.clone());
         
-        let connection_task = tokio::spawn(async move {
-            tracing::info!("Starting connection handler for game: {}", game_id_for_task);
-            
-            // Accept incoming connections and handle them
-            while let Some(connection) = iroh_ctx.accept_connection().await {
-                tracing::info!("New connection for game: {}", game_id_for_task);
-                
-                // Add connection to our list
-                {
-                    let mut connections = peer_connections.write().await;
-                    connections.push(connection.clone());
-                    tracing::info!("Total connections for game {}: {}", game_id_for_task, connections.len());
-                }
-                
-                // Spawn a task to handle this specific connection
-                let events_tx_conn = events_tx.clone();
-                let processed_sequences_conn = processed_sequences.clone();
-                let move_chain_conn = move_chain.clone();
-                let latest_state_conn = latest_state.clone();
-                let game_id_conn = game_id_for_task.clone();
-                
-                tokio::spawn(async move {
-                    if let Err(e) = Self::handle_peer_connection(
-                        connection,
-                        game_id_conn,
-                        events_tx_conn,
-                        processed_sequences_conn,
-                        move_chain_conn,
-                        latest_state_conn,
-                    ).await {
-                        tracing::error!("Error handling peer connection: {}", e);
+        // Take in connections from peers, accepting again should the acceptor die
+        #[cfg(feature = "iroh")]
+        {
+            let peer_connections = self.peer_connections.clone();
+            let peer_formats = self.peer_formats.clone();
+            let inbound = self.inbound();
+            health::supervise(self.supervisor.as_ref(), &acceptor_name(&game_id), move |heartbeat| {
+                let (iroh_ctx, peer_connections, peer_formats, inbound) =
+                    (iroh_ctx.clone(), peer_connections.clone(), peer_formats.clone(), inbound.clone());
+                async move {
+                    tracing::info!("Starting connection handler for game: {}", inbound.game_id);
+                    
+                    // Accept incoming connections and handle them
+                    while let Some(connection) = heartbeat.waiting(iroh_ctx.accept_connection()).await {
+                        tracing::info!("New connection for game: {}", inbound.game_id);
+                        
+                        // Add connection to our list
+                        {
+                            let mut connections = peer_connections.write().await;
+                            connections.push(connection.clone());
+                            tracing::info!("Total connections for game {}: {}", inbound.game_id, connections.len());
+                        }
+                        
+                        // Spawn a task to handle this specific connection
+                        let peer_formats_conn = peer_formats.clone();
+                        let inbound_conn = inbound.clone();
+                        let iroh_ctx_conn = iroh_ctx.clone();
+                        
+                        tokio::spawn(async move {
+                            if let Err(e) = Self::handle_peer_connection(
+                                connection,
+                                iroh_ctx_conn,
+                                peer_formats_conn,
+                                inbound_conn,
+                            ).await {
+                                tracing::error!("Error handling peer connection: {}", e);
+                            }
+                        });
                     }
-                });
-            }
-        });
-        
-        channel._connection_task = Some(connection_task);
+                }
+            });
+        }
         
         // Subscribe to gossip topic for this game (best effort)
-        if let Err(e) = channel.subscribe_to_game_topic().await {
+        if let Err(e) = self.subscribe_to_game_topic().await {
             tracing::warn!("Failed to subscribe to gossip topic (will rely on direct connections): {}", e);
         }
         
-        tracing::info!("Successfully created game channel with Iroh for game: {}", game_id);
-        Ok(channel)
+        tracing::info!("Connected game channel for game: {}", game_id);
+        Ok(self)
     }
     
-
-    
-    /// Subscribe to the game's gossip topic
-    #[cfg(feature = "iroh")]
-    async fn subscribe_to_game_topic(&mut self) -> Result<()> {
-        if let Some(iroh_ctx) = &self.iroh_ctx {
-            let _topic_id = Self::game_topic_id(&self.game_id);
-            tracing::info!("Subscribing to gossip topic for game: {}", self.game_id);
-            
-            // Subscribe to the game topic via gossip
-            let mut gossip_events = iroh_ctx.subscribe_game_topic(&self.game_id, 100).await?;
-            
-            // Start a background task to handle incoming gossip messages
-            let events_tx = self.events_tx.clone();
-            let _processed_sequences = self.processed_sequences.clone();
-            let _move_chain = self.move_chain.clone();
-            let latest_state = self.latest_state.clone();
-            let game_id_for_gossip = self.game_id.clone();
-            
-            tokio::spawn(async move {
-                tracing::info!("Starting gossip handler for game: {}", game_id_for_gossip);
-                
-                while let Some(event) = gossip_events.recv().await {
-                    tracing::debug!("Received gossip event for game {}: {:?}", game_id_for_gossip, event);
-                    if let Err(e) = Self::handle_gossip_event(
-                        event,
-                        &events_tx,
-                        &latest_state,
-                    ).await {
-                        tracing::error!("Error handling gossip event: {}", e);
+    /// Take in moves gossiped on the game's topic as a direct peer's,
+    /// subscribing again should the listener die
+    async fn subscribe_to_game_topic(&self) -> anyhow::Result<()> {
+        let Some(iroh_ctx) = self.iroh_ctx.clone() else {
+            return Ok(());
+        };
+        tracing::info!("Subscribing to gossip topic for game: {}", self.game_id);
+        // The first run takes this subscription, so no move gossiped once
+        // we are connected is missed
+        let subscribed = std::sync::Mutex::new(Some(iroh_ctx.game_messages(&self.game_id).await?));
+        let inbound = self.inbound();
+        health::supervise(self.supervisor.as_ref(), &gossip_listener_name(&self.game_id), move |heartbeat| {
+            let (iroh_ctx, inbound) = (iroh_ctx.clone(), inbound.clone());
+            let subscribed = subscribed.lock().unwrap().take();
+            async move {
+                let mut messages = match subscribed {
+                    Some(messages) => messages,
+                    None => match iroh_ctx.game_messages(&inbound.game_id).await {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            tracing::warn!("Failed to subscribe to gossip topic for {}: {}", inbound.game_id, e);
+                            return;
+                        }
+                    },
+                };
+                let own_id = iroh_ctx.node_id().to_string();
+                while let Some((from, bytes)) = heartbeat.waiting(messages.recv()).await {
+                    // Loopback hands us our own moves back
+                    if from == own_id {
+                        continue;
+                    }
+                    let record = match serde_cbor::from_slice::<MoveRecord>(&bytes) {
+                        Ok(record) => record,
+                        Err(e) => {
+                            tracing::warn!("Failed to deserialize gossip message for {}: {}", inbound.game_id, e);
+                            continue;
+                        }
+                    };
+                    // Dedup drops a move direct peers brought as well
+                    if let InboundOutcome::Disconnect(reason) = inbound.process(&from, DirectMessage::Move(record)).await {
+                        tracing::warn!("Ignoring gossip from {} for {}: {:?}", from, inbound.game_id, reason);
                     }
                 }
-                tracing::warn!("Gossip event stream ended for game: {}", game_id_for_gossip);
-            });
-            
-            tracing::info!("Successfully subscribed to gossip for game: {}", self.game_id);
-        }
-        Ok(())
-    }
-    
-    /// Broadcast a move via gossip
-    #[cfg(feature = "iroh")]
-    async fn broadcast_move(&self, record: &MoveRecord) -> Result<()> {
-        if let Some(iroh_ctx) = &self.iroh_ctx {
-            // Serialize the move record to CBOR for broadcast
-            let _cbor_data = serde_cbor::to_vec(record)
-                .context("Failed to serialize move record for gossip")?;
-            
-            tracing::debug!("Broadcasting move via gossip: {:?}", record.mv);
-            
-            // Try gossip broadcast
-            match iroh_ctx.broadcast_move(&self.game_id, &mut record.clone()).await {
-                Ok(()) => {
-                    tracing::info!("Successfully broadcast move via gossip");
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to broadcast via gossip, will use direct connections: {}", e);
-                }
+                tracing::warn!("Gossip stream ended for game: {}", inbound.game_id);
             }
-        }
+        });
         Ok(())
     }
     
-    /// Broadcast a move via gossip (stub implementation)
-    #[cfg(not(feature = "iroh"))]
-    async fn broadcast_move(&self, record: &MoveRecord) -> Result<()> {
-        tracing::debug!("Mock broadcast move {:?}", record.mv);
-        Ok(())
+    /// Broadcast a move on the game's gossip topic
+    async fn broadcast_move(&self, record: &MoveRecord) {
+        let Some(iroh_ctx) = &self.iroh_ctx else {
+            return;
+        };
+        tracing::debug!("Broadcasting move via gossip: {:?}", record.mv);
+        if let Err(e) = iroh_ctx.broadcast_move(&self.game_id, &mut record.clone()).await {
+            tracing::warn!("Failed to broadcast via gossip: {}", e);
+        }
     }
     
     /// Get a receiver for game events
@@ -257,6 +621,19 @@ impl GameChannel {
         self.events_tx.subscribe()
     }
     
+    /// The last `n` events, numbered, then the live ones
+    pub fn subscribe_with_history(&self, n: usize) -> EventStream {
+        self.events_tx.subscribe_with_history(n)
+    }
+    
+    /// The game state, with the number of the last event it reflects;
+    /// skipping the events up to it from a stream subscribed before leaves
+    /// neither a gap nor a repeat
+    pub async fn snapshot(&self) -> (Option<GameState>, u64) {
+        let _hold = self.events_tx.hold().await;
+        (self.latest_state.read().await.clone(), self.events_tx.last_seq())
+    }
+    
     /// Send a move to the channel
     pub async fn send_move(&self, mv: Move) -> Result<()> {
         self.push_move(mv, None).await
@@ -265,19 +642,30 @@ impl GameChannel {
     /// Push a move with optional tag to the channel
     #[tracing::instrument(level = "debug", skip(self, mv))]
     pub async fn push_move(&self, mv: Move, tag: Option<p2pgo_core::Tag>) -> Result<()> {
-        let _span = tracing::info_span!("network.game_channel", "GameChannel::send_move").entered();
+        // Caught here, a move for another board size says so rather than
+        // failing as a bare invalid coordinate
+        self.settings.check_move(&mv)?;
+        let inbound = self.inbound();
+        inbound.check_phase(GameAction::Move).await?;
+        // The opponent may yet take the position back
+        if self.undo.read().await.requested().is_some() {
+            return Err(UndoError::AlreadyPending.into());
+        }
+        
+        // Snapshots see the move with its events or neither
+        let hold = self.events_tx.hold().await;
         
         // Get the current game state
         let mut state = {
             let state_guard = self.latest_state.read().await;
             match &*state_guard {
                 Some(state) => state.clone(),
-                None => return Err(anyhow::anyhow!("No game state available")),
+                None => return Err(Error::sync_conflict(&self.game_id, "no game state yet")),
             }
         };
         
         // Apply the move to the state
-        state.apply_move(mv.clone())?;
+        let events = state.apply_move_events(mv.clone())?;
         
         // Get the current chain
         let mut chain = self.move_chain.write().await;
@@ -295,11 +683,16 @@ impl GameChannel {
             sequence
         );
         
-        // Get the move for the event before consuming the blob
+        // Keep the move for peers before consuming the blob
         let move_for_event = blob.mv.clone();
         
         // Add the blob to the chain
-        chain.add_blob(blob)?;
+        chain.add_blob(blob).map_err(|e| Error::sync_conflict(&self.game_id, e))?;
+        drop(chain);
+        if state.is_game_over() {
+            self.processed_sequences.write().await.clear();
+        }
+        let ts = self.move_timestamp().await;
         
         // If using iroh, store the move in the document
         #[cfg(feature = "iroh")]
@@ -308,10 +701,7 @@ impl GameChannel {
             let move_record = MoveRecord {
                 mv: move_for_event.clone(),
                 tag: tag.clone(),
-                ts: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
+                ts,
                 broadcast_hash: None, // Will be set after broadcast
                 prev_hash: prev_hash.clone(),
             };
@@ -320,12 +710,6 @@ impl GameChannel {
             let _cbor_data = serde_cbor::to_vec(&move_record)
                 .context("Failed to CBOR encode move record")?;
                 
-            // Mark this move as processed locally to avoid duplicate processing
-            {
-                let mut processed = self.processed_sequences.write().await;
-                processed.insert(sequence);
-            }
-            
             // Store the move in the document using the IrohCtx API
             // TODO: Update for iroh v0.35 docs API
             // iroh_ctx.store_game_move(&self.game_id, sequence, &cbor_data)
@@ -340,6 +724,8 @@ impl GameChannel {
             let mut state_guard = self.latest_state.write().await;
             *state_guard = Some(state.clone());
         }
+        // Playing on declines any undo the opponent asked for
+        self.undo.write().await.moved();
         
         // Store tag if provided (for training data)
         if let Some(tag) = tag {
@@ -352,257 +738,713 @@ impl GameChannel {
             // Tag is stored in the CBOR MoveRecord for training purposes
         }
         
-        // Create a game event for the move
-        // The player making the move is the one before the current_player in the state
-        let event = GameEvent::MoveMade {
-            mv: move_for_event.clone(),
-            by: match state.current_player {
-                p2pgo_core::Color::Black => p2pgo_core::Color::White,
-                p2pgo_core::Color::White => p2pgo_core::Color::Black,
-            },
+        // Broadcast the move, and the end of the game if it ended it
+        for event in events {
+            if let Err(e) = inbound.publish(event).await {
+                tracing::warn!("Failed to broadcast move event: {}", e);
+            }
+        }
+        drop(hold);
+        inbound.run_clock().await;
+        self.jitter_first_move().await;
+        // Only peers that read framed messages send ACKs
+        if self.peer_acknowledges().await {
+            self.metrics.record_move_sent(state.moves.len() as u32 - 1, self.clock.now());
+        } else {
+            self.metrics.record_move_unacknowledged();
+        }
+        if session_log::is_recording() {
+            session_log::record(Some(&self.game_id), SessionInput::Move { mv: move_for_event.clone() }, &state.moves);
+        }
+        
+        let move_record = MoveRecord {
+            mv: move_for_event,
+            tag,
+            ts,
+            broadcast_hash: None, // Will be set after broadcast
+            prev_hash,
         };
         
-        // Broadcast the event
-        if let Err(e) = self.events_tx.send(event) {
-            tracing::warn!("Failed to broadcast move event: {}", e);
-        }
+        // Broadcast the move via both gossip and direct connections for reliability
+        self.broadcast_move(&move_record).await;
         
-        // If using iroh, broadcast the move to connected peers
+        // Always broadcast to directly connected peers as primary mechanism
         #[cfg(feature = "iroh")]
-        {
-            // Create a move record for peer communication
-            let move_record = MoveRecord {
-                mv: move_for_event.clone(),
-                tag: tag.clone(),
-                ts: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
-                broadcast_hash: None, // Will be set after broadcast
-                prev_hash: prev_hash.clone(),
-            };
-            
-            // Broadcast the move via both gossip and direct connections for reliability
-            let _ = self.broadcast_move(&move_record).await; // Gossip (may fail)
-            
-            // Always broadcast to directly connected peers as primary mechanism
-            if let Err(e) = self.broadcast_move_to_peers(&move_record).await {
+        let delivered = match self.broadcast_move_to_peers(&move_record).await {
+            Ok(sent) => {
+                tracing::info!("Successfully broadcast move to {} direct peer(s)", sent);
+                sent > 0
+            }
+            Err(e) => {
                 tracing::warn!("Failed to broadcast move to direct peers: {}", e);
-            } else {
-                tracing::info!("Successfully broadcast move to direct peers");
+                false
             }
+        };
+        // Gossip never says who heard the move
+        #[cfg(not(feature = "iroh"))]
+        let delivered = false;
+        
+        // A correspondence opponent may be offline; keep the move until one hears it
+        if self.is_correspondence() && !delivered {
+            self.outbox.write().await.push(move_record);
         }
         
         Ok(())
     }
     
-    /// Get the latest game state
-    pub async fn get_latest_state(&self) -> Option<GameState> {
-        self.latest_state.read().await.clone()
+    /// Make this a correspondence game, or a live one again
+    pub fn set_correspondence(&self, on: bool) {
+        self.correspondence.store(on, Ordering::Relaxed);
     }
     
-    /// Get all moves in the game so far
-    pub async fn get_all_moves(&self) -> Vec<Move> {
-        let chain = self.move_chain.read().await;
-        
-        chain.get_all_blobs().iter()
-            .map(|blob| blob.mv.clone())
-            .collect()
+    pub fn is_correspondence(&self) -> bool {
+        self.correspondence.load(Ordering::Relaxed)
     }
     
-    /// Send a chat message or other game event
-    pub async fn send_event(&self, event: GameEvent) -> Result<()> {
-        let _span = tracing::info_span!("network.game_channel", "GameChannel::send_event").entered();
+    /// Send coarse move timestamps and jitter our first move, or stop
+    pub async fn set_timing_privacy(&self, privacy: Option<TimingPrivacy>) {
+        *self.timing_privacy.write().await = privacy;
+    }
+    
+    /// The timestamp our next move goes out with
+    async fn move_timestamp(&self) -> u64 {
+        let ts = self.clock.unix_secs();
+        match &*self.timing_privacy.read().await {
+            Some(privacy) => privacy.quantize(ts),
+            None => ts,
+        }
+    }
+    
+    /// Hold back the first move we send, unless a clock we keep would
+    /// charge the delay to the opponent
+    async fn jitter_first_move(&self) {
+        let first = !self.moved.swap(true, Ordering::Relaxed);
+        let Some(privacy) = *self.timing_privacy.read().await else {
+            return;
+        };
+        if first && self.timekeeping.read().await.is_none() {
+            tokio::time::sleep(privacy.jitter()).await;
+        }
+    }
+    
+    /// Make `teacher` the only peer allowed to annotate, or nobody
+    pub async fn set_teacher(&self, teacher: Option<String>) {
+        self.teaching.write().await.teacher = teacher.clone();
+        self.record(SessionInput::Command(Command::SetTeacher { teacher })).await;
+    }
+    
+    pub async fn teacher(&self) -> Option<String> {
+        self.teaching.read().await.teacher.clone()
+    }
+    
+    /// The teacher's annotations so far
+    pub async fn annotations(&self) -> TeachingLayer {
+        self.teaching.read().await.layer.clone()
+    }
+    
+    /// Annotate the current position as `from`, which must be the teacher,
+    /// and send the annotation to our peers
+    pub async fn annotate(&self, from: &str, annotation: TeachingAnnotation) -> Result<()> {
+        self.inbound().check_phase(GameAction::Annotate).await?;
+        let move_index = self.latest_state.read().await.as_ref().map_or(0, |s| s.moves.len());
+        Self::apply_annotation(&self.teaching, &self.latest_state, &self.events_tx, from, move_index, annotation.clone()).await?;
+        self.record(SessionInput::Command(Command::Annotate { from: from.to_string(), annotation: annotation.clone() })).await;
         
-        // Just broadcast the event
-        self.events_tx.send(event)
-            .map_err(|e| anyhow::anyhow!("Failed to broadcast event: {}", e))?;
-            
+        #[cfg(feature = "iroh")]
+        self.broadcast_annotation(move_index, annotation).await;
         Ok(())
     }
     
-    /// Handle a peer connection for game synchronization
-    #[cfg(feature = "iroh")]
-    async fn handle_peer_connection(
-        connection: Connection,
-        game_id: String,
-        events_tx: broadcast::Sender<GameEvent>,
-        processed_sequences: Arc<RwLock<HashSet<u32>>>,
-        move_chain: Arc<RwLock<MoveChain>>,
-        latest_state: Arc<RwLock<Option<GameState>>>,
-    ) -> Result<()> {
-        tracing::debug!("Handling peer connection for game: {}", game_id);
+    /// Apply an annotation a peer sent, refusing it unless the peer teaches
+    pub async fn receive_annotation(&self, from: &str, move_index: usize, annotation: TeachingAnnotation) -> Result<()> {
+        self.inbound().check_phase(GameAction::Annotate).await?;
+        Ok(Self::apply_annotation(&self.teaching, &self.latest_state, &self.events_tx, from, move_index, annotation).await?)
+    }
+    
+    async fn apply_annotation(
+        teaching: &RwLock<Teaching>,
+        latest_state: &RwLock<Option<GameState>>,
+        events_tx: &EventLog,
+        from: &str,
+        move_index: usize,
+        annotation: TeachingAnnotation,
+    ) -> anyhow::Result<()> {
+        let (board_size, moves) = latest_state.read().await.as_ref()
+            .map(|s| (s.board_size, s.moves.len()))
+            .ok_or_else(|| anyhow::anyhow!("No game state available"))?;
+        anyhow::ensure!(move_index <= moves, "Annotation for move {} of a game with {} moves", move_index, moves);
         
-        // Listen for incoming unidirectional streams
-        loop {
-            match connection.accept_uni().await {
-                Ok(mut recv_stream) => {
-                    tracing::debug!("Accepted unidirectional stream for game: {}", game_id);
-                    
-                    // Read the incoming message
-                    match recv_stream.read_to_end(1024).await {
-                        Ok(buffer) => {
-                            let message = String::from_utf8_lossy(&buffer);
-                            tracing::debug!("Received message for game {}: {}", game_id, message);
-                            
-                            // Parse the move record (remove trailing newline if present)
-                            let message = message.trim();
-                            if let Ok(move_record) = serde_json::from_str::<MoveRecord>(message) {
-                                tracing::debug!("Successfully parsed move record: {:?}", move_record.mv);
-                                
-                                // Process the received move
-                                if let Err(e) = Self::process_received_move_direct(
-                                    move_record,
-                                    &events_tx,
-                                    &latest_state,
-                                    &move_chain,
-                                    &processed_sequences,
-                                    &game_id,
-                                ).await {
-                                    tracing::error!("Error processing received move for {}: {}", game_id, e);
-                                }
-                            } else {
-                                tracing::warn!("Failed to parse move record for {}: {}", game_id, message);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Error reading from stream for {}: {}", game_id, e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Error accepting stream for {}: {}", game_id, e);
-                    break;
-                }
-            }
+        let mut teaching = teaching.write().await;
+        if teaching.teacher.as_deref() != Some(from) {
+            anyhow::bail!("{} is not the teacher of this game", from);
         }
+        teaching.layer.apply(move_index, &annotation, board_size)?;
+        drop(teaching);
         
-        tracing::debug!("Connection handler finished for game: {}", game_id);
+        let _ = events_tx.send(GameEvent::Annotated { move_index, annotation });
         Ok(())
     }
-
-    /// Handle incoming gossip events
-    #[cfg(feature = "iroh")]
-    async fn handle_gossip_event(
-        event: iroh_gossip::net::Event,
-        events_tx: &broadcast::Sender<GameEvent>,
-        latest_state: &Arc<RwLock<Option<GameState>>>,
-    ) -> Result<()> {
-        tracing::debug!("Received gossip event for game");
-        
-        // Extract bytes from the gossip event using the compatibility layer
-        use crate::gossip_compat::{extract_bytes, is_received_message};
-        
-        // Only process received message events
-        if is_received_message(&event) {
-            if let Some(content) = extract_bytes(&event) {
-                // Try to deserialize the message as a MoveRecord
-                match serde_cbor::from_slice::<MoveRecord>(&content) {
-                    Ok(move_record) => {
-                        tracing::debug!("Parsed move record from gossip: {:?}", move_record.mv);
-                        // Process the received move
-                        Self::process_received_move(
-                            move_record,
-                            events_tx,
-                            latest_state,
-                        ).await?;
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to deserialize gossip message: {}", e);
-                    }
-                }
-            }
-        } else {
-            tracing::debug!("Ignoring non-message gossip event");
-        }
+    
+    /// Ask the opponent to take back our last `moves` moves. Until it
+    /// answers we can't move; an accepted request rolls the game back.
+    pub async fn request_undo(&self, moves: u8) -> Result<()> {
+        self.inbound().check_phase(GameAction::Undo).await?;
+        let played = self.latest_state.read().await.as_ref().map_or(0, |s| s.moves.len());
+        self.undo.write().await.request(moves, played)?;
+        self.record(SessionInput::Command(Command::RequestUndo { moves })).await;
         
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::UndoRequest { moves }, "undo request").await;
         Ok(())
     }
-
-    /// Process a received move from gossip
-    #[cfg(feature = "iroh")]
-    async fn process_received_move(
-        move_record: MoveRecord,
-        events_tx: &broadcast::Sender<GameEvent>,
-        latest_state: &Arc<RwLock<Option<GameState>>>,
-    ) -> Result<()> {
-        tracing::debug!("Processing received move: {:?}", move_record.mv);
-        
-        // Get the current game state
-        let mut current_state = {
-            let state_guard = latest_state.read().await;
-            match &*state_guard {
-                Some(state) => state.clone(),
-                None => return Err(anyhow::anyhow!("No game state available")),
-            }
-        };
-        
-        // Apply the move to the state
-        current_state.apply_move(move_record.mv.clone())?;
+    
+    /// Accept or decline the opponent's undo request; accepting takes the
+    /// moves back here as the opponent does on hearing it
+    pub async fn answer_undo(&self, accept: bool) -> Result<()> {
+        self.inbound().check_phase(GameAction::Undo).await?;
+        let moves = self.undo.write().await.answer(accept)?;
+        if let Some(moves) = moves {
+            self.inbound().take_back(moves).await?;
+            *self.premove.write().await = None;
+        }
+        self.record(SessionInput::Command(Command::AnswerUndo { accept })).await;
         
-        // Update the latest state
-        {
-            let mut state_guard = latest_state.write().await;
-            *state_guard = Some(current_state.clone());
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::UndoResponse { accepted: accept }, "undo answer").await;
+        Ok(())
+    }
+    
+    /// Undo requests pending either way and the undos left
+    pub async fn undo_state(&self) -> UndoState {
+        self.undo.read().await.clone()
+    }
+    
+    /// The game's full history, retractions included
+    pub async fn move_chain(&self) -> MoveChain {
+        self.move_chain.read().await.clone()
+    }
+    
+    /// Tell our peers whether we are looking at the board. Spectators of
+    /// a board we broadcast are told nothing.
+    pub async fn send_presence(&self, presence: Presence) {
+        if self.role == ChannelRole::Broadcast || self.inbound().check_phase(GameAction::Presence).await.is_err() {
+            return;
+        }
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::Presence(presence), "presence").await;
+        #[cfg(not(feature = "iroh"))]
+        let _ = presence;
+    }
+    
+    /// Keep the game's clock here, starting with the next move; peers
+    /// without a clock of their own follow the snapshots we send
+    pub async fn set_time_control(&self, clock: GameClock, on_disconnect: OnDisconnect) {
+        let timing = GameTiming { settings: Some(clock.settings()), moves: Vec::new() };
+        *self.timekeeping.write().await = Some(Timekeeping { clock, on_disconnect, timing });
+    }
+    
+    /// The time control and the clock of each move so far, if we keep the
+    /// clocks; archived with the game so its SGF shows how time was used
+    pub async fn timing(&self) -> GameTiming {
+        self.timekeeping.read().await.as_ref().map(|t| t.timing.clone()).unwrap_or_default()
+    }
+    
+    /// The clocks now, if we keep them
+    pub async fn clock_snapshot(&self) -> Option<ClockSnapshot> {
+        let now = self.clock.now();
+        self.timekeeping.read().await.as_ref().map(|t| t.clock.snapshot(now))
+    }
+    
+    /// Send peers the clocks as they are now; called periodically between
+    /// the snapshots that go with move acknowledgements
+    pub async fn send_clock_tick(&self) {
+        self.record(SessionInput::Tick).await;
+        if self.inbound().check_phase(GameAction::Clock).await.is_err() {
+            return;
+        }
+        // Stopped and paused clocks were sent when they stopped
+        let Some(snapshot) = self.clock_snapshot().await.filter(|s| s.running.is_some()) else {
+            return;
+        };
+        self.inbound().show_clock(snapshot).await;
+        #[cfg(feature = "iroh")]
+        self.broadcast_direct(&DirectMessage::ClockTick(snapshot), "clock tick").await;
+    }
+    
+    /// The opponent's connection dropped; the clock pauses if set to
+    pub async fn peer_disconnected(&self) {
+        self.inbound().pause_clock().await;
+    }
+    
+    /// Our moves not yet delivered to any peer, oldest first
+    pub async fn pending_outbound(&self) -> Vec<MoveRecord> {
+        self.outbox.read().await.clone()
+    }
+    
+    /// Record `input` to this game with the moves after it, if a session
+    /// is recorded
+    async fn record(&self, input: SessionInput) {
+        if session_log::is_recording() {
+            let moves = self.latest_state.read().await.as_ref().map(|s| s.moves.clone()).unwrap_or_default();
+            session_log::record(Some(&self.game_id), input, &moves);
+        }
+    }
+    
+    /// Queue moves saved by an earlier session for the next peer to connect
+    pub async fn restore_outbound(&self, moves: Vec<MoveRecord>) {
+        self.outbox.write().await.extend(moves);
+    }
+    
+    /// Every move so far, as sent to a peer catching up
+    pub async fn sync_moves(&self) -> Vec<MoveRecord> {
+        sync_records(&*self.move_chain.read().await)
+    }
+    
+    /// Replay the moves of a peer's sync that we don't have yet.
+    ///
+    /// Returns how many were new. Our own moves among them are now
+    /// delivered, so the outbox is emptied.
+    pub async fn apply_sync(&self, moves: &[MoveRecord]) -> Result<usize> {
+        self.inbound().check_phase(GameAction::Move).await?;
+        let known = self.latest_state.read().await.as_ref().map_or(0, |s| s.moves.len());
+        let mut applied = 0;
+        for record in moves.iter().skip(known) {
+            self.apply_peer_move(record.mv.clone()).await
+                .map_err(|e| Error::sync_conflict(&self.game_id, format!("synced move {} rejected: {:#}", known + applied, e)))?;
+            applied += 1;
         }
+        if moves.len() >= known {
+            self.outbox.write().await.clear();
+        }
+        Ok(applied)
+    }
+    
+    /// Apply a move a peer played and announce it
+    async fn apply_peer_move(&self, mv: Move) -> anyhow::Result<()> {
+        let _hold = self.events_tx.hold().await;
+        let mut chain = self.move_chain.write().await;
+        let mut state = self.latest_state.read().await.clone()
+            .ok_or_else(|| anyhow::anyhow!("No game state available"))?;
+        let events = state.apply_move_events(mv.clone())?;
+        let prev_hash = chain.current_blob().map(|blob| blob.hash());
+        let sequence = if chain.current_blob().is_none() { 0 } else { chain.current_sequence + 1 };
+        chain.add_blob(MoveBlob::new(self.game_id.clone(), mv, prev_hash, state.clone(), sequence))?;
+        *self.latest_state.write().await = Some(state);
+        drop(chain);
+        self.undo.write().await.moved();
         
-        // Create a game event for the move
-        let event = GameEvent::MoveMade {
-            mv: move_record.mv.clone(),
-            by: match current_state.current_player {
-                p2pgo_core::Color::Black => p2pgo_core::Color::White,
-                p2pgo_core::Color::White => p2pgo_core::Color::Black,
-            },
+        self.metrics.record_move_received();
+        let inbound = self.inbound();
+        for event in events {
+            let _ = inbound.publish(event).await;
+        }
+        Ok(())
+    }
+    
+    /// Sync counters and ACK round trips for this game so far
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.metrics.snapshot()
+    }
+    
+    /// Sent moves that went [`ACK_TIMEOUT`] without an ACK since the last call
+    pub fn expire_acks(&self) -> u64 {
+        self.metrics.expire_acks(self.clock.now(), ACK_TIMEOUT)
+    }
+    
+    /// Chase unacknowledged moves as `tuning` says rather than by default
+    pub fn with_tuning(self, tuning: ChannelTuning) -> Self {
+        self.set_tuning(tuning);
+        self
+    }
+    
+    pub fn set_tuning(&self, tuning: ChannelTuning) {
+        self.watchdog.lock().unwrap().set_tuning(tuning);
+    }
+    
+    pub fn tuning(&self) -> ChannelTuning {
+        self.watchdog.lock().unwrap().tuning()
+    }
+    
+    /// Ask the peers for a sync if a sent move is overdue for its ACK;
+    /// returns whether one was due. Call it every
+    /// [`check_interval`](ChannelTuning::check_interval).
+    pub async fn check_acks(&self) -> bool {
+        let pending = self.metrics.pending_acks();
+        let due = self.watchdog.lock().unwrap().check(&pending, self.clock.now());
+        #[cfg(feature = "iroh")]
+        if due && self.iroh_ctx.is_some() {
+            tracing::debug!("Asking peers of {} for a sync; a move went unacknowledged", self.game_id);
+            self.broadcast_direct(&DirectMessage::SyncRequest, "sync request").await;
+            self.metrics.record_sync_request_sent();
+        }
+        due
+    }
+    
+    /// Get the latest game state
+    pub async fn get_latest_state(&self) -> Option<GameState> {
+        self.latest_state.read().await.clone()
+    }
+    
+    /// The latest state with the clock of each move, as it is archived
+    pub async fn game_record(&self) -> Option<GameState> {
+        let mut state = self.get_latest_state().await?;
+        state.timing = self.timing().await;
+        Some(state)
+    }
+    
+    /// Check `mv` for the player to move, including ko.
+    ///
+    /// Passes when there is no state yet; sending then fails instead.
+    pub async fn check_move(&self, mv: &Move) -> Result<(), GameError> {
+        let Some(state) = self.get_latest_state().await else {
+            return Ok(());
+        };
+        let previous = self.previous_state(&state).await;
+        state.check_move(mv, &previous)
+    }
+    
+    /// The position before the last move of `state`, for ko
+    async fn previous_state(&self, state: &GameState) -> GameState {
+        let chain = self.move_chain.read().await;
+        let blobs = chain.played();
+        match blobs.len().checked_sub(2) {
+            Some(idx) => blobs[idx].state.clone(),
+            None => state.initial_position(),
+        }
+    }
+    
+    /// Queue `mv` for `by` to be sent right after the opponent's next move.
+    ///
+    /// Replaces any earlier premove; only one may be queued at a time.
+    pub async fn queue_premove(&self, mv: Move, by: Color) {
+        *self.premove.write().await = Some((mv, by));
+    }
+    
+    /// Drop the queued premove, returning it
+    pub async fn clear_premove(&self) -> Option<Move> {
+        self.premove.write().await.take().map(|(mv, _)| mv)
+    }
+    
+    /// Get the queued premove, if any
+    pub async fn premove(&self) -> Option<(Move, Color)> {
+        self.premove.read().await.clone()
+    }
+    
+    /// Send the queued premove if it is now its player's turn and the move
+    /// is still legal; otherwise leave it queued or drop it.
+    ///
+    /// Call this after every remote move.
+    pub async fn play_premove(&self) -> Result<Option<PremoveOutcome>> {
+        let Some(state) = self.get_latest_state().await else {
+            return Ok(None);
+        };
+        let (mv, _) = {
+            let mut premove = self.premove.write().await;
+            match &*premove {
+                Some((_, by)) if *by == state.current_player => premove.take().unwrap(),
+                _ => return Ok(None),
+            }
         };
         
-        // Broadcast the event locally
-        if let Err(e) = events_tx.send(event) {
-            tracing::warn!("Failed to broadcast received move event: {}", e);
+        let previous = self.previous_state(&state).await;
+        if let Err(error) = state.check_move(&mv, &previous) {
+            tracing::debug!(game_id = %self.game_id, ?mv, %error, "Discarding premove");
+            return Ok(Some(PremoveOutcome::Discarded { mv, error }));
         }
         
-        tracing::info!("Successfully processed received move: {:?}", move_record.mv);
+        self.send_move(mv.clone()).await?;
+        Ok(Some(PremoveOutcome::Played(mv)))
+    }
+    
+    /// Change the move rate limits; peers start with a clean slate
+    pub async fn set_rate_limit(&self, config: RateLimitConfig) {
+        *self.rate_limiter.write().await = MoveRateLimiter::new(config);
+    }
+    
+    /// Suspicious play seen so far; a flagged game is kept out of training
+    pub async fn anomalies(&self) -> Vec<MoveAnomaly> {
+        self.rate_limiter.read().await.anomalies().to_vec()
+    }
+    
+    /// Peers whose clocks were found skewed; kept with the game record
+    pub async fn clock_skews(&self) -> Vec<ClockSkew> {
+        self.clocks.read().await.skews().to_vec()
+    }
+    
+    /// Take a timestamp `peer` stamped at `theirs` that arrived at `ours`
+    /// as a sample of its clock. The first time its offset goes past
+    /// [`SKEW_WARNING_SECS`](crate::relay_robustness::SKEW_WARNING_SECS)
+    /// it is announced as a `PeerWarning` event.
+    pub async fn observe_peer_clock(&self, peer: &str, theirs: u64, ours: u64) -> Option<ClockSkew> {
+        Self::observe_clock(&self.clocks, &self.events_tx, peer, theirs, ours).await
+    }
+    
+    /// `peer`'s timestamp `ts` on our clock
+    pub async fn peer_time_to_local(&self, peer: &str, ts: u64) -> u64 {
+        self.clocks.read().await.to_local(peer, ts)
+    }
+    
+    async fn observe_clock(
+        clocks: &Arc<RwLock<PeerClocks>>,
+        events_tx: &EventLog,
+        peer: &str,
+        theirs: u64,
+        ours: u64,
+    ) -> Option<ClockSkew> {
+        let skew = clocks.write().await.sample(peer, theirs, ours)?;
+        tracing::warn!(peer, offset_secs = skew.offset_secs, "Peer clock is skewed; correcting its timestamps");
+        let _ = events_tx.send(GameEvent::PeerWarning { peer: peer.to_string(), reason: skew.to_string() });
+        Some(skew)
+    }
+    
+    /// Run a move arriving from `peer` past the rate limiter.
+    ///
+    /// Only `Verdict::Accept` moves should be processed. A new mute or a
+    /// disconnect is announced as a `PeerWarning` event.
+    pub async fn screen_peer_move(&self, peer: &str, now: std::time::Instant) -> Verdict {
+        Self::screen_move(&self.rate_limiter, &self.events_tx, peer, now).await
+    }
+    
+    async fn screen_move(
+        rate_limiter: &Arc<RwLock<MoveRateLimiter>>,
+        events_tx: &EventLog,
+        peer: &str,
+        now: std::time::Instant,
+    ) -> Verdict {
+        let verdict = rate_limiter.write().await.check(peer, now);
+        let reason = match &verdict {
+            Verdict::Mute { until, .. } => format!(
+                "Peer muted for {} ms for sending moves too fast",
+                until.saturating_duration_since(now).as_millis()
+            ),
+            Verdict::Disconnect(reason) => format!("Disconnected: {}", reason),
+            Verdict::Accept | Verdict::Drop => return verdict,
+        };
+        tracing::warn!(peer, "{}", reason);
+        let _ = events_tx.send(GameEvent::PeerWarning { peer: peer.to_string(), reason });
+        verdict
+    }
+    
+    /// Get all moves in the game so far, less any taken back
+    pub async fn get_all_moves(&self) -> Vec<Move> {
+        let chain = self.move_chain.read().await;
+        
+        chain.played().iter()
+            .map(|blob| blob.mv.clone())
+            .collect()
+    }
+    
+    /// Send a chat message or other game event; ending the game moves it
+    /// on to its next phase
+    #[tracing::instrument(name = "GameChannel::send_event", skip_all)]
+    pub async fn send_event(&self, event: GameEvent) -> Result<()> {
+        let inbound = self.inbound();
+        match &event {
+            GameEvent::ChatMessage { message, .. } => {
+                inbound.check_phase(GameAction::Chat).await?;
+                sanitize::check_chat(message)?;
+            }
+            GameEvent::GameEnded { .. } => inbound.check_phase(GameAction::End).await?,
+            _ => {}
+        }
+        inbound.publish(event).await
+            .map_err(|e| Error::internal(format!("Failed to broadcast event: {}", e)))?;
+            
         Ok(())
     }
-
-    /// Process a received move from direct peer connection
+    
+    /// Handle a message `peer` sent on the game's direct stream, as a
+    /// connection would, and say what the connection should do next
+    pub async fn receive_direct(&self, peer: &str, message: DirectMessage) -> InboundOutcome {
+        self.inbound().process(peer, message).await
+    }
+    
+    /// Handle a peer connection for game synchronization
+    #[cfg(feature = "iroh")]
+    async fn handle_peer_connection(
+        connection: Connection,
+        iroh_ctx: Arc<IrohCtx>,
+        peer_formats: Arc<RwLock<HashMap<usize, WireFormat>>>,
+        inbound: Inbound,
+    ) -> anyhow::Result<()> {
+        let game_id = &inbound.game_id;
+        tracing::debug!("Handling peer connection for game: {}", game_id);
+        let peer = connection.remote_node_id()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|_| format!("connection-{}", connection.stable_id()));
+        
+        // Tell the peer we read framed CBOR; older peers ignore this
+        if let Err(e) = Self::send_direct(&iroh_ctx, &connection, &DirectMessage::hello(&inbound.settings), WireFormat::Cbor).await {
+            tracing::warn!("Failed to send hello for {}: {}", game_id, e);
+        }
+        
+        // Listen for incoming unidirectional streams, one message each
+        loop {
+            match connection.accept_uni().await {
+                Ok(mut recv_stream) => {
+                    tracing::debug!("Accepted unidirectional stream for game: {}", game_id);
+                    
+                    let message = match wire::read_frame(&mut recv_stream).await {
+                        Ok((message, bytes)) => {
+                            traffic::record(Self::traffic_category(&iroh_ctx, &connection, &message), bytes);
+                            message
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to read message for {}: {:#}", game_id, e);
+                            continue;
+                        }
+                    };
+                    
+                    // What the peer reads is the connection's business
+                    match &message {
+                        DirectMessage::Hello { formats, .. } => {
+                            let format = WireFormat::negotiate(formats);
+                            tracing::debug!("Peer for {} reads {:?}", game_id, format);
+                            peer_formats.write().await.insert(connection.stable_id(), format);
+                        }
+                        // Only framing-aware peers send this, so answer in CBOR
+                        DirectMessage::SyncRequest => {
+                            peer_formats.write().await.insert(connection.stable_id(), WireFormat::Cbor);
+                        }
+                        _ => {}
+                    }
+                    let format = peer_formats.read().await.get(&connection.stable_id()).copied();
+                    
+                    match inbound.process(&peer, message).await {
+                        InboundOutcome::Handled => {}
+                        // Peers without framing only read moves
+                        InboundOutcome::Reply(_) | InboundOutcome::DeliverPending(_) if format != Some(WireFormat::Cbor) => {}
+                        InboundOutcome::Reply(ack @ DirectMessage::Ack { index, .. }) => {
+                            if let Err(e) = Self::send_direct(&iroh_ctx, &connection, &ack, WireFormat::Cbor).await {
+                                tracing::debug!("Failed to acknowledge move {} for {}: {}", index, game_id, e);
+                            }
+                        }
+                        InboundOutcome::Reply(signed @ DirectMessage::ResultSigned(_)) => {
+                            if let Err(e) = Self::send_direct(&iroh_ctx, &connection, &signed, WireFormat::Cbor).await {
+                                tracing::warn!("Failed to return the signed result of {}: {}", game_id, e);
+                            }
+                        }
+                        InboundOutcome::Reply(answer @ DirectMessage::UndoResponse { .. }) => {
+                            if let Err(e) = Self::send_direct(&iroh_ctx, &connection, &answer, WireFormat::Cbor).await {
+                                tracing::debug!("Failed to decline undo for {}: {}", game_id, e);
+                            }
+                        }
+                        InboundOutcome::Reply(response) => {
+                            if let Err(e) = Self::send_direct(&iroh_ctx, &connection, &response, WireFormat::Cbor).await {
+                                tracing::error!("Failed to send sync response for {}: {}", game_id, e);
+                                inbound.metrics.record_error(format!("Sync response failed: {}", e));
+                            } else {
+                                inbound.metrics.record_sync_request_served();
+                            }
+                        }
+                        InboundOutcome::DeliverPending(response) => {
+                            match Self::send_direct(&iroh_ctx, &connection, &response, WireFormat::Cbor).await {
+                                Ok(()) => {
+                                    let sent = std::mem::take(&mut *inbound.outbox.write().await);
+                                    tracing::info!("Delivered {} pending move(s) for {}", sent.len(), game_id);
+                                }
+                                Err(e) => tracing::warn!("Failed to deliver pending moves for {}: {}", game_id, e),
+                            }
+                        }
+                        InboundOutcome::Disconnect(reason) => {
+                            let payload = serde_json::to_vec(&reason).unwrap_or_default();
+                            connection.close(iroh::endpoint::VarInt::from_u32(reason.code), &payload);
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Error accepting stream for {}: {}", game_id, e);
+                    inbound.metrics.record_error(format!("Connection lost: {}", e));
+                    break;
+                }
+            }
+        }
+        
+        peer_formats.write().await.remove(&connection.stable_id());
+        inbound.rate_limiter.write().await.remove_peer(&peer);
+        inbound.clocks.write().await.remove_peer(&peer);
+        inbound.presence.write().await.remove_peer(&peer);
+        inbound.pause_clock().await;
+        tracing::debug!("Connection handler finished for game: {}", game_id);
+        Ok(())
+    }
+    
+    /// Send one message on a fresh unidirectional stream
     #[cfg(feature = "iroh")]
+    async fn send_direct(iroh_ctx: &IrohCtx, connection: &Connection, msg: &DirectMessage, format: WireFormat) -> anyhow::Result<()> {
+        let mut send_stream = connection.open_uni().await.context("Failed to open stream")?;
+        let bytes = wire::write_message(&mut send_stream, msg, format).await?;
+        send_stream.finish().context("Failed to finish stream")?;
+        traffic::record(Self::traffic_category(iroh_ctx, connection, msg), bytes);
+        Ok(())
+    }
+    
+    /// How a direct message to or from the peer on `connection` is counted
+    #[cfg(feature = "iroh")]
+    fn traffic_category(iroh_ctx: &IrohCtx, connection: &Connection, msg: &DirectMessage) -> TrafficCategory {
+        match msg {
+            DirectMessage::SyncRequest | DirectMessage::SyncResponse { .. } => TrafficCategory::Sync,
+            _ if iroh_ctx.is_relayed(connection) => TrafficCategory::Relay,
+            _ => TrafficCategory::Stream,
+        }
+    }
+    
+    /// All moves so far and the latest state, for a peer catching up
+    async fn sync_response(
+        move_chain: &Arc<RwLock<MoveChain>>,
+        latest_state: &Arc<RwLock<Option<GameState>>>,
+    ) -> Option<DirectMessage> {
+        let state = latest_state.read().await.clone()?;
+        let moves = sync_records(&*move_chain.read().await);
+        Some(DirectMessage::SyncResponse { moves, state })
+    }
+
+    /// Process a received move from direct peer connection.
+    ///
+    /// `seq` is the move's index in the game when the message says so, as
+    /// a sync does; otherwise it comes from the move the record follows.
+    /// Returns the index of the move in the game once applied, with the
+    /// events to announce, or `None` for a duplicate or a move that could
+    /// not be applied.
+    #[allow(clippy::too_many_arguments)]
     async fn process_received_move_direct(
         move_record: MoveRecord,
-        events_tx: &broadcast::Sender<GameEvent>,
         latest_state: &Arc<RwLock<Option<GameState>>>,
         move_chain: &Arc<RwLock<MoveChain>>,
-        processed_sequences: &Arc<RwLock<HashSet<u32>>>,
+        processed_sequences: &Arc<RwLock<SequenceDedup>>,
+        metrics: &ChannelMetrics,
         game_id: &str,
-    ) -> Result<()> {
+        peer: &str,
+        seq: Option<u64>,
+    ) -> anyhow::Result<Option<(u32, Vec<GameEvent>)>> {
         tracing::debug!("Processing received move for {}: {:?}", game_id, move_record.mv);
         
-        // Check if we've already processed this move by hash
-        if let Some(hash) = move_record.broadcast_hash {
-            let processed = processed_sequences.read().await;
-            if processed.contains(&(hash[0] as u32)) { // Use first byte as simple check
-                tracing::debug!("Move already processed, skipping");
-                return Ok(());
-            }
-        }
-        
-        // Add to processed (using hash if available)
-        if let Some(hash) = move_record.broadcast_hash {
-            let mut processed = processed_sequences.write().await;
-            processed.insert(hash[0] as u32);
-        }
-        
         // Apply the move locally
         let new_state = {
             tracing::debug!("Acquiring move chain lock for {}", game_id);
             let mut chain = move_chain.write().await;
             
+            // A predecessor we don't know leaves the rules to decide
+            let seq = seq.or_else(|| match move_record.prev_hash {
+                None => Some(0),
+                Some(prev) => chain.get_blob(&prev).map(|blob| blob.state.moves.len() as u64),
+            });
+            if let Some(seq) = seq {
+                if !processed_sequences.read().await.is_new(peer, seq) {
+                    tracing::debug!("Move {} from {} already processed, skipping", seq, peer);
+                    metrics.record_duplicate();
+                    return Ok(None);
+                }
+            }
+            
             // Get current state to apply move to
             tracing::debug!("Getting current state for {}", game_id);
             let current_state = latest_state.read().await;
             if let Some(mut state) = current_state.clone() {
                 tracing::debug!("Applying move to state for {}", game_id);
                 // Apply the move to get the new state
-                if state.apply_move(move_record.mv.clone()).is_ok() {
+                if let Ok(events) = state.apply_move_events(move_record.mv.clone()) {
                     tracing::debug!("Move applied successfully for {}", game_id);
                     let sequence = if chain.current_blob().is_none() { 0 } else { chain.current_sequence + 1 };
                     let prev_hash = chain.current_blob().map(|blob| blob.hash());
@@ -618,7 +1460,14 @@ impl GameChannel {
                     tracing::debug!("Adding blob to chain for {}", game_id);
                     if chain.add_blob(blob).is_ok() {
                         tracing::debug!("Blob added successfully for {}", game_id);
-                        Some(state)
+                        let mut processed = processed_sequences.write().await;
+                        if state.is_game_over() {
+                            // Nothing more to deduplicate
+                            processed.clear();
+                        } else if let Some(seq) = seq {
+                            processed.accept(peer, seq);
+                        }
+                        Some((state, events))
                     } else {
                         tracing::error!("Failed to add blob to chain for {}", game_id);
                         None
@@ -634,85 +1483,96 @@ impl GameChannel {
         };
         
         // Update state and broadcast event (outside of move_chain lock)
-        if let Some(state) = new_state {
-            tracing::debug!("Updating latest state for {}", game_id);
-            {
-                let mut state_guard = latest_state.write().await;
-                *state_guard = Some(state.clone());
-                tracing::debug!("State updated for {}", game_id);
-            }
-            
-            // Broadcast the move event
-            let event = GameEvent::MoveMade {
-                mv: move_record.mv.clone(),
-                by: match state.current_player {
-                    p2pgo_core::Color::Black => p2pgo_core::Color::White,
-                    p2pgo_core::Color::White => p2pgo_core::Color::Black,
-                },
-            };
-            
-            tracing::debug!("Broadcasting move event for game {}: {:?}", game_id, event);
-            if let Err(e) = events_tx.send(event) {
-                tracing::error!("Failed to broadcast move event for {}: {}", game_id, e);
-            } else {
-                tracing::debug!("Successfully broadcast move event for game: {}", game_id);
-            }
+        let Some((state, events)) = new_state else {
+            return Ok(None);
+        };
+        let index = state.moves.len() as u32 - 1;
+        metrics.record_move_received();
+        tracing::debug!("Updating latest state for {}", game_id);
+        {
+            let mut state_guard = latest_state.write().await;
+            *state_guard = Some(state);
+            tracing::debug!("State updated for {}", game_id);
         }
         
-        Ok(())
+        Ok(Some((index, events)))
     }
 
-    /// Broadcast move to peers over direct connections
+    /// Broadcast move to peers over direct connections, returning how many
+    /// peers it was sent to
     #[cfg(feature = "iroh")]
-    pub async fn broadcast_move_to_peers(&self, move_record: &MoveRecord) -> Result<()> {
+    pub async fn broadcast_move_to_peers(&self, move_record: &MoveRecord) -> Result<usize> {
         let connections = self.peer_connections.read().await;
         
         tracing::debug!("Broadcasting move to {} peer connection(s)", connections.len());
         
-        if connections.is_empty() {
+        let Some(iroh_ctx) = self.iroh_ctx.as_deref().filter(|_| !connections.is_empty()) else {
             tracing::debug!("No peer connections available for broadcasting");
-            return Ok(());
-        }
+            return Ok(0);
+        };
         
-        let message = serde_json::to_string(move_record)?;
-        tracing::debug!("Broadcasting move message: {}", message);
+        let message = DirectMessage::Move(move_record.clone());
+        let peer_formats = self.peer_formats.read().await;
         
+        let mut sent = 0;
         for (i, connection) in connections.iter().enumerate() {
-            tracing::debug!("Attempting to send to peer {}", i);
-            // Try to open a unidirectional stream to send the move
-            match connection.open_uni().await {
-                Ok(mut send_stream) => {
-                    tracing::debug!("Opened stream to peer {}", i);
-                    // Use iroh's stream write methods
-                    match send_stream.write_all(message.as_bytes()).await {
-                        Ok(()) => {
-                            match send_stream.write_all(b"\n").await {
-                                Ok(()) => {
-                                    // Close the stream
-                                    if let Err(e) = send_stream.finish() {
-                                        tracing::error!("Failed to finish stream to peer {}: {}", i, e);
-                                    } else {
-                                        tracing::debug!("Successfully sent move to peer {}", i);
-                                    }
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to send delimiter to peer {}: {}", i, e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to send move to peer {}: {}", i, e);
-                        }
-                    }
+            // Peers that never said hello get the legacy JSON form
+            let format = peer_formats.get(&connection.stable_id()).copied().unwrap_or(WireFormat::Json);
+            tracing::debug!("Sending move to peer {} as {:?}", i, format);
+            match Self::send_direct(iroh_ctx, connection, &message, format).await {
+                Ok(()) => {
+                    tracing::debug!("Successfully sent move to peer {}", i);
+                    sent += 1;
                 }
                 Err(e) => {
-                    tracing::error!("Failed to open stream to peer {}: {}", i, e);
+                    tracing::error!("Failed to send move to peer {}: {:#}", i, e);
+                    self.metrics.record_error(format!("Sending move failed: {:#}", e));
                 }
             }
         }
         
         tracing::debug!("Completed broadcasting move to {} peer(s)", connections.len());
-        Ok(())
+        Ok(sent)
+    }
+    
+    /// Whether a directly connected peer reads framed messages, and so
+    /// acknowledges the moves we send it
+    async fn peer_acknowledges(&self) -> bool {
+        #[cfg(feature = "iroh")]
+        {
+            let connections = self.peer_connections.read().await;
+            let peer_formats = self.peer_formats.read().await;
+            connections.iter().any(|connection| peer_formats.get(&connection.stable_id()) == Some(&WireFormat::Cbor))
+        }
+        // Without iroh, direct messages only come in through `receive_direct`
+        #[cfg(not(feature = "iroh"))]
+        !self.framing_peers.read().await.is_empty()
+    }
+    
+    /// Send an annotation to the peers that read framed messages
+    #[cfg(feature = "iroh")]
+    async fn broadcast_annotation(&self, move_index: usize, annotation: TeachingAnnotation) {
+        let message = DirectMessage::Annotation { move_index: move_index as u32, annotation };
+        self.broadcast_direct(&message, "annotation").await;
+    }
+    
+    /// Send `message` to every peer that reads framed messages
+    #[cfg(feature = "iroh")]
+    async fn broadcast_direct(&self, message: &DirectMessage, what: &str) {
+        let Some(iroh_ctx) = self.iroh_ctx.as_deref() else {
+            return;
+        };
+        let connections = self.peer_connections.read().await;
+        let peer_formats = self.peer_formats.read().await;
+        for connection in connections.iter() {
+            // Peers without framing only read moves
+            if peer_formats.get(&connection.stable_id()) != Some(&WireFormat::Cbor) {
+                continue;
+            }
+            if let Err(e) = Self::send_direct(iroh_ctx, connection, message, WireFormat::Cbor).await {
+                tracing::warn!("Failed to send {} for {}: {:#}", what, self.game_id, e);
+            }
+        }
     }
     
     /// Connect to a peer's game channel for the same game
@@ -732,32 +1592,541 @@ impl GameChannel {
             }
             
             // Spawn a task to handle this connection
-            let events_tx = self.events_tx.clone();
-            let processed_sequences = self.processed_sequences.clone();
-            let move_chain = self.move_chain.clone();
-            let latest_state = self.latest_state.clone();
+            let peer_formats = self.peer_formats.clone();
+            let inbound = self.inbound();
             let game_id = self.game_id.clone();
             
-            tokio::spawn(async move {
-                tracing::debug!("Starting connection handler for game: {}", game_id);
-                if let Err(e) = Self::handle_peer_connection(
-                    connection,
-                    game_id.clone(),
-                    events_tx,
-                    processed_sequences,
-                    move_chain,
-                    latest_state,
-                ).await {
-                    tracing::error!("Error handling peer connection for {}: {}", game_id, e);
+            tokio::spawn({
+                let connection = connection.clone();
+                let iroh_ctx = iroh_ctx.clone();
+                async move {
+                    tracing::debug!("Starting connection handler for game: {}", game_id);
+                    if let Err(e) = Self::handle_peer_connection(
+                        connection,
+                        iroh_ctx,
+                        peer_formats,
+                        inbound,
+                    ).await {
+                        tracing::error!("Error handling peer connection for {}: {}", game_id, e);
+                    }
                 }
             });
             
+            // Catch up on moves played before we connected; older hosts ignore this
+            if let Err(e) = Self::send_direct(iroh_ctx, &connection, &DirectMessage::SyncRequest, WireFormat::Cbor).await {
+                tracing::warn!("Failed to request sync for {}: {}", self.game_id, e);
+            } else {
+                self.metrics.record_sync_request_sent();
+            }
+            
             tracing::debug!("Successfully connected to peer for game: {}", self.game_id);
         }
         Ok(())
     }
 }
 
+impl Inbound {
+    /// Refuse `action` unless the game's phase allows it
+    async fn check_phase(&self, action: GameAction) -> Result<()> {
+        let phase = self.phase.read().await;
+        if !phase.allows(action) {
+            return Err(Error::WrongPhase { phase: phase.clone(), action });
+        }
+        Ok(())
+    }
+    
+    /// Refuse a peer's message the game's phase doesn't allow
+    async fn out_of_phase(&self, peer: &str, action: GameAction) -> bool {
+        let Err(e) = self.check_phase(action).await else {
+            return false;
+        };
+        tracing::debug!("Refusing message from {} for {}: {}", peer, self.game_id, e);
+        self.metrics.record_error(e.to_string());
+        true
+    }
+    
+    /// Record the score `by` accepted and settle the game once both
+    /// players accepted the same one
+    async fn accept_score(&self, by: Color, proof: ScoreProof) {
+        let agreed = {
+            let mut accepts = self.score_accepts.write().await;
+            accepts.insert(by, proof);
+            match (accepts.get(&Color::Black), accepts.get(&Color::White)) {
+                (Some(black), Some(white)) if black.final_score == white.final_score => Some(black.clone()),
+                (Some(_), Some(_)) => {
+                    tracing::warn!(game_id = %self.game_id, "Players accepted different scores");
+                    None
+                }
+                _ => None,
+            }
+        };
+        if let Some(result) = agreed.and_then(|proof| GameResult::from_event(&game_ended_event(&proof))) {
+            self.enter(GamePhase::Finished { result }).await;
+        }
+    }
+    
+    /// Move the game on to `phase` and say so
+    async fn enter(&self, phase: GamePhase) {
+        {
+            let mut current = self.phase.write().await;
+            if *current == phase {
+                return;
+            }
+            *current = phase.clone();
+        }
+        tracing::debug!(game_id = %self.game_id, %phase, "Game phase changed");
+        let _ = self.events_tx.send(GameEvent::PhaseChanged { phase });
+    }
+    
+    /// Announce `event`, then the phase it moves the game on to, if any
+    async fn publish(&self, event: GameEvent) -> std::result::Result<usize, broadcast::error::SendError<GameEvent>> {
+        let next = self.phase.read().await.after(&event);
+        let sent = self.events_tx.send(event);
+        if let Some(phase) = next {
+            self.enter(phase).await;
+        }
+        sent
+    }
+    
+    /// Run the clock on after the latest move, stopping it once the game
+    /// is over; the clocks now, if we keep them
+    async fn run_clock(&self) -> Option<ClockSnapshot> {
+        let (mover, over, played) = {
+            let state = self.latest_state.read().await;
+            let state = state.as_ref()?;
+            (state.current_player.opposite(), state.is_game_over(), state.moves.len())
+        };
+        let now = self.clock.now();
+        let mut timekeeping = self.timekeeping.write().await;
+        let Timekeeping { clock, timing, .. } = timekeeping.as_mut()?;
+        // The mover's clock as they moved, before a period refills; a move
+        // handed back by a take-back keeps what it had
+        if let Some(index) = played.checked_sub(1).filter(|&index| timing.at(index).is_none()) {
+            timing.record(index, TimingInfo::of(clock.snapshot(now).time(mover)));
+        }
+        if over {
+            clock.stop(now);
+        } else {
+            clock.moved(mover, now);
+        }
+        let snapshot = clock.snapshot(now);
+        drop(timekeeping);
+        self.show_clock(snapshot).await;
+        Some(snapshot)
+    }
+    
+    /// Add our signature to the result the opponent proposes, if it is the
+    /// result of this game as we saw it end
+    async fn countersign(&self, record: &mut ResultRecord) -> Result<()> {
+        let publishing = self.publishing.read().await;
+        let publishing = publishing.as_ref().ok_or_else(|| Error::internal("This game isn't played for a tournament"))?;
+        if record.tournament != publishing.feed.tournament() {
+            return Err(RecordError::WrongTournament(record.tournament.clone()).into());
+        }
+        let played = self.latest_state.read().await.as_ref().map(game_digest);
+        if record.game_id != self.game_id || played != Some(record.game_hash) {
+            return Err(RecordError::GameMismatch(record.game_id.clone()).into());
+        }
+        match &*self.phase.read().await {
+            GamePhase::Finished { result } if *result == record.result => {}
+            _ => return Err(RecordError::ResultMismatch(record.game_id.clone()).into()),
+        }
+        let ours = record.sign(&publishing.identity)?;
+        record.verify_signature(ours.opposite())?;
+        Ok(())
+    }
+    
+    /// Publish our proposed result once the opponent has countersigned it
+    async fn publish_result(&self, record: ResultRecord) -> anyhow::Result<()> {
+        let feed = {
+            let mut publishing = self.publishing.write().await;
+            let publishing = publishing.as_mut().context("This game isn't played for a tournament")?;
+            let proposed = publishing.pending.as_ref().context("No result of ours awaits a signature")?;
+            anyhow::ensure!(proposed.same_result(&record), "The signed result isn't the one proposed");
+            record.verify_signatures()?;
+            publishing.pending = None;
+            publishing.feed.clone()
+        };
+        let record = feed.publish(record).await?;
+        tracing::info!(game_id = %record.game_id, tournament = %record.tournament, "Published tournament result");
+        Ok(())
+    }
+    
+    /// Show a peer's clocks, unless we keep our own
+    async fn follow_clock(&self, snapshot: ClockSnapshot) {
+        if self.timekeeping.read().await.is_none() {
+            self.show_clock(snapshot).await;
+        }
+    }
+    
+    /// Announce the clocks, and each byo-yomi period used since they were
+    /// last announced; a player out of time loses
+    async fn show_clock(&self, snapshot: ClockSnapshot) {
+        let before = self.shown_clock.write().await.replace(snapshot);
+        let _ = self.events_tx.send(GameEvent::Clock { snapshot });
+        for (player, periods_left) in before.map(|before| snapshot.periods_used_since(&before)).unwrap_or_default() {
+            tracing::debug!(game_id = %self.game_id, ?player, periods_left, "Byo-yomi period used");
+            let _ = self.events_tx.send(GameEvent::ByoYomiPeriodUsed { player, periods_left });
+        }
+        if let Some(loser) = snapshot.flagged() {
+            self.lose_on_time(loser).await;
+        }
+    }
+    
+    /// End the game for `loser`, whose time ran out
+    async fn lose_on_time(&self, loser: Color) {
+        if self.check_phase(GameAction::End).await.is_err() {
+            return;
+        }
+        let now = self.clock.now();
+        if let Some(t) = self.timekeeping.write().await.as_mut() {
+            t.clock.stop(now);
+        }
+        tracing::info!(game_id = %self.game_id, ?loser, "Lost on time");
+        let ended = GameEvent::GameEnded { winner: Some(loser.opposite()), score_diff: 0.0, reason: EndReason::Timeout, scores: None };
+        let _ = self.publish(ended).await;
+    }
+    
+    async fn pause_clock(&self) {
+        let now = self.clock.now();
+        let mut timekeeping = self.timekeeping.write().await;
+        let Some(t) = timekeeping.as_mut().filter(|t| t.on_disconnect == OnDisconnect::Pause) else {
+            return;
+        };
+        t.clock.pause(now);
+        let snapshot = t.clock.snapshot(now);
+        drop(timekeeping);
+        self.show_clock(snapshot).await;
+    }
+    
+    async fn resume_clock(&self) {
+        let now = self.clock.now();
+        let mut timekeeping = self.timekeeping.write().await;
+        let Some(t) = timekeeping.as_mut().filter(|t| t.clock.is_paused()) else {
+            return;
+        };
+        t.clock.resume(now);
+        let snapshot = t.clock.snapshot(now);
+        drop(timekeeping);
+        self.show_clock(snapshot).await;
+    }
+    
+    /// Take back the last `moves` moves, as both players agreed, and
+    /// record the retraction in the chain
+    async fn take_back(&self, moves: u8) -> anyhow::Result<()> {
+        let _hold = self.events_tx.hold().await;
+        let mut chain = self.move_chain.write().await;
+        let mut state = self.latest_state.read().await.clone()
+            .ok_or_else(|| anyhow::anyhow!("No game state available"))?;
+        let taken_back = state.undo(moves as usize)?;
+        let last = taken_back.last().cloned().ok_or_else(|| anyhow::anyhow!("No moves taken back"))?;
+        if let Some(t) = self.timekeeping.write().await.as_mut() {
+            t.timing.truncate(state.moves.len());
+        }
+        let prev_hash = chain.current_blob().map(|blob| blob.hash());
+        let sequence = if chain.current_blob().is_none() { 0 } else { chain.current_sequence + 1 };
+        let blob = MoveBlob::retract(self.game_id.clone(), Retraction { moves }, last, prev_hash, state.clone(), sequence);
+        chain.add_blob(blob)?;
+        *self.latest_state.write().await = Some(state);
+        drop(chain);
+        
+        // Move indices are played again from here
+        self.processed_sequences.write().await.clear();
+        // An odd number of moves gives the turn, and the clock, back
+        if moves % 2 == 1 {
+            self.run_clock().await;
+        }
+        let _ = self.events_tx.send(GameEvent::MovesUndone { moves });
+        Ok(())
+    }
+    
+    /// Apply a message from `peer`, recording it if a session is recorded
+    async fn process(&self, peer: &str, message: DirectMessage) -> InboundOutcome {
+        let recorded = session_log::is_recording().then(|| message.clone());
+        let outcome = self.dispatch(peer, message).await;
+        if let Some(message) = recorded {
+            let moves = self.latest_state.read().await.as_ref().map(|s| s.moves.clone()).unwrap_or_default();
+            session_log::record(Some(&self.game_id), SessionInput::Inbound { peer: peer.to_string(), message }, &moves);
+        }
+        outcome
+    }
+    
+    /// Refuse a peer's move that doesn't fit our board, telling the player
+    /// it likely set the game up at another size
+    fn off_board(&self, peer: &str, mv: &Move) -> bool {
+        let Err(e) = self.settings.check_move(mv) else {
+            return false;
+        };
+        tracing::warn!("Rejecting move from {} for {}: {}", peer, self.game_id, e);
+        self.metrics.record_error(format!("Received move rejected: {}", e));
+        let _ = self.events_tx.send(GameEvent::PeerWarning {
+            peer: peer.to_string(),
+            reason: format!("Your opponent played off the board: {}", e),
+        });
+        true
+    }
+    
+    /// Refuse a message that would change a broadcast game, which only we play
+    fn watch_only(&self, peer: &str, what: &str) -> bool {
+        if self.role != ChannelRole::Broadcast {
+            return false;
+        }
+        tracing::warn!("Refusing {} from {} for broadcast {}", what, peer, self.game_id);
+        self.metrics.record_error(format!("Spectator {} refused", what));
+        true
+    }
+    
+    async fn dispatch(&self, peer: &str, message: DirectMessage) -> InboundOutcome {
+        let game_id = &self.game_id;
+        match message {
+            DirectMessage::Move(_) if self.watch_only(peer, "move") => InboundOutcome::Handled,
+            DirectMessage::Annotation { .. } if self.watch_only(peer, "annotation") => InboundOutcome::Handled,
+            DirectMessage::SyncResponse { .. } if self.watch_only(peer, "sync") => InboundOutcome::Handled,
+            DirectMessage::UndoRequest { .. } if self.watch_only(peer, "undo request") => InboundOutcome::Handled,
+            DirectMessage::ScoreAccepted { .. } if self.watch_only(peer, "score") => InboundOutcome::Handled,
+            DirectMessage::Hello { formats, sent_at, settings } => {
+                GameChannel::observe_clock(&self.clocks, &self.events_tx, peer, sent_at, self.clock.unix_secs()).await;
+                if formats.contains(&crate::wire::WireFormat::Cbor) {
+                    self.framing_peers.write().await.insert(peer.to_string());
+                }
+                // A peer on a game we play is the opponent we waited for
+                if self.role == ChannelRole::Player && *self.phase.read().await == GamePhase::AwaitingOpponent {
+                    self.enter(GamePhase::Active).await;
+                }
+                self.resume_clock().await;
+                if let Some(theirs) = settings {
+                    self.undo.write().await.negotiate(theirs.undo_limit);
+                }
+                // Undo limits differ freely; the lower one is played
+                let same_game = |theirs: &GameSettings| GameSettings { undo_limit: self.settings.undo_limit, ..*theirs } == self.settings;
+                if let Some(theirs) = settings.filter(|theirs| !same_game(theirs)) {
+                    tracing::warn!("Peer {} plays {} as {}, not {}", peer, game_id, theirs, self.settings);
+                    let _ = self.events_tx.send(GameEvent::PeerWarning {
+                        peer: peer.to_string(),
+                        reason: format!("Your opponent set this game up as {}, not {}", theirs, self.settings),
+                    });
+                }
+                
+                // Moves we played while the peer was away; a sync lets it
+                // skip any it already has
+                if self.outbox.read().await.is_empty() {
+                    return InboundOutcome::Handled;
+                }
+                match GameChannel::sync_response(&self.move_chain, &self.latest_state).await {
+                    Some(response) => InboundOutcome::DeliverPending(response),
+                    None => InboundOutcome::Handled,
+                }
+            }
+            DirectMessage::Move(mut move_record) => {
+                tracing::debug!("Successfully parsed move record: {:?}", move_record.mv);
+                
+                // Keep move times on our clock, whatever the peer's says
+                GameChannel::observe_clock(&self.clocks, &self.events_tx, peer, move_record.ts, self.clock.unix_secs()).await;
+                move_record.ts = self.clocks.read().await.to_local(peer, move_record.ts);
+                
+                match GameChannel::screen_move(&self.rate_limiter, &self.events_tx, peer, self.clock.now()).await {
+                    Verdict::Accept => {}
+                    Verdict::Mute { .. } | Verdict::Drop => return InboundOutcome::Handled,
+                    Verdict::Disconnect(reason) => return InboundOutcome::Disconnect(reason),
+                }
+                if self.off_board(peer, &move_record.mv) || self.out_of_phase(peer, GameAction::Move).await {
+                    return InboundOutcome::Handled;
+                }
+                
+                let hold = self.events_tx.hold().await;
+                match GameChannel::process_received_move_direct(
+                    move_record,
+                    &self.latest_state,
+                    &self.move_chain,
+                    &self.processed_sequences,
+                    &self.metrics,
+                    game_id,
+                    peer,
+                    None,
+                ).await {
+                    Ok(Some((index, events))) => {
+                        // The move, and the end of the game if it ended it
+                        for event in events {
+                            if let Err(e) = self.publish(event).await {
+                                tracing::error!("Failed to broadcast move event for {}: {}", game_id, e);
+                            }
+                        }
+                        drop(hold);
+                        // Playing on declines any undo either side asked for
+                        self.undo.write().await.moved();
+                        let clock = self.run_clock().await;
+                        InboundOutcome::Reply(DirectMessage::Ack { index, clock })
+                    }
+                    Ok(None) => InboundOutcome::Handled,
+                    Err(e) => {
+                        tracing::error!("Error processing received move for {}: {}", game_id, e);
+                        self.metrics.record_error(format!("Received move rejected: {}", e));
+                        InboundOutcome::Handled
+                    }
+                }
+            }
+            DirectMessage::Annotation { move_index, annotation } => {
+                if self.out_of_phase(peer, GameAction::Annotate).await {
+                    return InboundOutcome::Handled;
+                }
+                if let Err(e) = GameChannel::apply_annotation(&self.teaching, &self.latest_state, &self.events_tx, peer, move_index as usize, annotation).await {
+                    tracing::warn!("Ignoring annotation for {}: {:#}", game_id, e);
+                    self.metrics.record_error(format!("Annotation rejected: {}", e));
+                }
+                InboundOutcome::Handled
+            }
+            DirectMessage::Presence(presence) => {
+                // Only shown to the player, and only what the type allows
+                if self.role == ChannelRole::Broadcast || self.out_of_phase(peer, GameAction::Presence).await {
+                    return InboundOutcome::Handled;
+                }
+                if !self.presence.write().await.admit(peer, self.clock.now()) {
+                    return InboundOutcome::Handled;
+                }
+                let now = self.clock.unix_secs();
+                let last_active = self.clocks.read().await.to_local(peer, presence.last_active).min(now);
+                let _ = self.events_tx.send(GameEvent::Presence {
+                    peer: peer.to_string(),
+                    considering: presence.considering,
+                    last_active,
+                });
+                InboundOutcome::Handled
+            }
+            DirectMessage::Ack { index, clock } => {
+                if let Some(rtt) = self.metrics.record_ack(index, self.clock.now()) {
+                    tracing::trace!("Move {} of {} acknowledged in {:?}", index, game_id, rtt);
+                }
+                if let Some(snapshot) = clock {
+                    self.follow_clock(snapshot).await;
+                }
+                InboundOutcome::Handled
+            }
+            DirectMessage::ClockTick(snapshot) => {
+                if !self.out_of_phase(peer, GameAction::Clock).await {
+                    self.follow_clock(snapshot).await;
+                }
+                InboundOutcome::Handled
+            }
+            DirectMessage::UndoRequest { moves } => {
+                if self.out_of_phase(peer, GameAction::Undo).await {
+                    return InboundOutcome::Reply(DirectMessage::UndoResponse { accepted: false });
+                }
+                let played = self.latest_state.read().await.as_ref().map_or(0, |s| s.moves.len());
+                if let Err(e) = self.undo.write().await.asked_by(peer, moves, played) {
+                    tracing::debug!("Declining undo from {} for {}: {}", peer, game_id, e);
+                    return InboundOutcome::Reply(DirectMessage::UndoResponse { accepted: false });
+                }
+                let _ = self.events_tx.send(GameEvent::UndoRequested { peer: peer.to_string(), moves });
+                InboundOutcome::Handled
+            }
+            DirectMessage::UndoResponse { accepted } => {
+                if self.out_of_phase(peer, GameAction::Undo).await {
+                    return InboundOutcome::Handled;
+                }
+                let moves = {
+                    let mut undo = self.undo.write().await;
+                    if undo.requested().is_none() {
+                        tracing::debug!("Ignoring undo answer from {} for {} nobody asked for", peer, game_id);
+                        return InboundOutcome::Handled;
+                    }
+                    undo.answered(accepted)
+                };
+                let _ = self.events_tx.send(GameEvent::UndoAnswered { accepted });
+                if let Some(moves) = moves {
+                    if let Err(e) = self.take_back(moves).await {
+                        tracing::error!("Failed to take back {} moves of {}: {:#}", moves, game_id, e);
+                        self.metrics.record_error(format!("Undo failed: {}", e));
+                    }
+                }
+                InboundOutcome::Handled
+            }
+            DirectMessage::SignResult(mut record) => match self.countersign(&mut record).await {
+                Ok(()) => InboundOutcome::Reply(DirectMessage::ResultSigned(record)),
+                Err(e) => {
+                    tracing::warn!("Not signing the result {} proposes for {}: {}", peer, game_id, e);
+                    InboundOutcome::Handled
+                }
+            },
+            DirectMessage::ResultSigned(record) => {
+                if let Err(e) = self.publish_result(record).await {
+                    tracing::warn!("Not publishing the result {} signed for {}: {:#}", peer, game_id, e);
+                }
+                InboundOutcome::Handled
+            }
+            DirectMessage::ScoreAccepted { by, proof } => {
+                if self.out_of_phase(peer, GameAction::Score).await {
+                    return InboundOutcome::Handled;
+                }
+                if let Err(e) = proof.verify() {
+                    tracing::warn!("Ignoring a score {} accepted for {}: {}", peer, game_id, e);
+                    return InboundOutcome::Handled;
+                }
+                self.accept_score(by, proof).await;
+                InboundOutcome::Handled
+            }
+            DirectMessage::SyncRequest => {
+                match GameChannel::sync_response(&self.move_chain, &self.latest_state).await {
+                    Some(response) => InboundOutcome::Reply(response),
+                    None => InboundOutcome::Handled,
+                }
+            }
+            DirectMessage::SyncResponse { moves, .. } => {
+                // Replay only the moves we have not seen yet
+                let _hold = self.events_tx.hold().await;
+                let known = self.latest_state.read().await.as_ref().map_or(0, |s| s.moves.len());
+                tracing::debug!("Sync for {}: {} moves, {} already known", game_id, moves.len(), known);
+                if moves.len() >= known {
+                    // The peer has every move of ours, including any pending
+                    self.outbox.write().await.clear();
+                }
+                for (index, move_record) in moves.into_iter().enumerate().skip(known) {
+                    if self.off_board(peer, &move_record.mv) || self.out_of_phase(peer, GameAction::Move).await {
+                        break;
+                    }
+                    match GameChannel::process_received_move_direct(
+                        move_record,
+                        &self.latest_state,
+                        &self.move_chain,
+                        &self.processed_sequences,
+                        &self.metrics,
+                        game_id,
+                        peer,
+                        Some(index as u64),
+                    ).await {
+                        Ok(Some((_, events))) => {
+                            for event in events {
+                                let _ = self.publish(event).await;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::error!("Error replaying synced move for {}: {}", game_id, e);
+                            self.metrics.record_error(format!("Synced move rejected: {}", e));
+                            break;
+                        }
+                    }
+                }
+                InboundOutcome::Handled
+            }
+        }
+    }
+}
+
+/// Every move of `chain` still in play, as sent to a peer catching up
+fn sync_records(chain: &MoveChain) -> Vec<MoveRecord> {
+    chain.played()
+        .into_iter()
+        .map(|blob| MoveRecord {
+            mv: blob.mv.clone(),
+            tag: None,
+            ts: 0, // Blobs do not record when the move was played
+            // No hash, so the receiver's duplicate check never skips a synced move
+            broadcast_hash: None,
+            prev_hash: blob.prev_hash,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
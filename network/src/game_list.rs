@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The lobby's list of advertised games, bounded and served a page at a time.
+//!
+//! A busy network can carry thousands of adverts. [`AdvertStore`] keeps
+//! them indexed by game, sender and age, and refuses at ingest what it
+//! would only have to drop later: adverts older than [`ADVERT_TTL_SECS`],
+//! senders past [`MAX_ADVERTS_PER_SENDER`] and games under rules newer than
+//! this build knows. Past [`MAX_ADVERTS`] the
+//! oldest advert makes room.
+//!
+//! An advert names its own host, so anyone can claim to be any number of
+//! hosts. The per-node cap counts the node that delivered each advert
+//! instead, which the transport vouches for. The UI never sees more than a
+//! [`GamesPage`] of what matches its [`GamesQuery`].
+
+use std::collections::{BTreeSet, HashMap};
+use serde::{Serialize, Deserialize};
+use crate::GameId;
+use crate::lobby::{GameAdvert, GameInfo};
+
+/// Adverts not renewed for this long are dropped
+pub const ADVERT_TTL_SECS: u64 = 10 * 60;
+
+/// Adverts listed at once from any one node that delivered them
+pub const MAX_ADVERTS_PER_SENDER: usize = 3;
+
+/// Adverts kept at most, whatever their hosts
+pub const MAX_ADVERTS: usize = 4096;
+
+/// Games sent to the UI at a time
+pub const GAMES_PAGE_SIZE: usize = 50;
+
+/// Order of the games list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamesSort {
+    /// Most recently advertised first
+    #[default]
+    Newest,
+    /// Smallest boards first, newest first within a size
+    BoardSize,
+}
+
+impl GamesSort {
+    pub const ALL: [GamesSort; 2] = [GamesSort::Newest, GamesSort::BoardSize];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GamesSort::Newest => "Newest",
+            GamesSort::BoardSize => "Board size",
+        }
+    }
+}
+
+/// Which games the UI lists, and in what order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GamesQuery {
+    /// Only games on this board size
+    pub board_size: Option<u8>,
+    pub rated_only: bool,
+    pub sort: GamesSort,
+    /// Games being played, to spectate, instead of games to join
+    #[serde(default)]
+    pub spectating: bool,
+}
+
+impl GamesQuery {
+    pub fn matches(&self, info: &GameInfo) -> bool {
+        let listed = if self.spectating { info.state.watchable() } else { info.state.joinable() };
+        listed
+            && self.board_size.is_none_or(|size| info.board_size == size)
+            && (!self.rated_only || info.terms.rated)
+    }
+
+    /// Put `games` in the query's order; ties keep the order they came in
+    pub fn sort(&self, games: &mut [&GameInfo]) {
+        match self.sort {
+            GamesSort::Newest => {}
+            GamesSort::BoardSize => games.sort_by_key(|info| info.board_size),
+        }
+    }
+}
+
+/// A slice of the games list matching a query
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct GamesPage {
+    pub games: Vec<GameInfo>,
+    /// Position of the first game in the whole list
+    pub offset: usize,
+    /// Games matching the query, on this page or not
+    pub total: usize,
+}
+
+impl GamesPage {
+    /// Up to `limit` of `games` from `offset` on
+    pub fn slice(games: &[&GameInfo], offset: usize, limit: usize) -> Self {
+        Self {
+            games: games.iter().skip(offset).take(limit).map(|info| (*info).clone()).collect(),
+            offset,
+            total: games.len(),
+        }
+    }
+
+    /// Whether games past this page match too
+    pub fn has_more(&self) -> bool {
+        self.offset + self.games.len() < self.total
+    }
+}
+
+/// What became of an advert offered to the store
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ingest {
+    Added,
+    /// A game already listed was advertised again
+    Refreshed,
+    /// Posted longer than [`ADVERT_TTL_SECS`] ago
+    Expired,
+    /// The node that delivered it already has [`MAX_ADVERTS_PER_SENDER`]
+    /// adverts listed
+    SenderFull,
+    /// The game is listed under another host
+    WrongHost,
+    /// The game is played under rules newer than this build knows
+    Unsupported,
+}
+
+#[derive(Debug)]
+struct Listing {
+    info: GameInfo,
+    host: String,
+    /// Node the advert was first delivered by
+    sender: String,
+    posted: u64,
+}
+
+/// Adverts from other nodes, indexed for ingest, expiry and paging
+#[derive(Debug, Default)]
+pub struct AdvertStore {
+    listings: HashMap<GameId, Listing>,
+    by_sender: HashMap<String, Vec<GameId>>,
+    /// Oldest first
+    by_posted: BTreeSet<(u64, GameId)>,
+}
+
+impl AdvertStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.listings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listings.is_empty()
+    }
+
+    /// Senders with at least one advert listed
+    pub fn senders(&self) -> usize {
+        self.by_sender.len()
+    }
+
+    /// Take in `advert`, delivered by the node `sender`, as seen at unix
+    /// second `now`
+    ///
+    /// Adverts without a posting time count as posted now, and ones
+    /// posted in the future as well, so a skewed clock can't pin a game
+    /// to the top of the list.
+    pub fn ingest(&mut self, advert: &GameAdvert, sender: &str, now: u64) -> Ingest {
+        let posted = match advert.posted {
+            0 => now,
+            posted => posted.min(now),
+        };
+        if now - posted > ADVERT_TTL_SECS {
+            return Ingest::Expired;
+        }
+        if advert.terms().check_supported().is_err() {
+            return Ingest::Unsupported;
+        }
+        self.expire(now);
+
+        if let Some(listing) = self.listings.get_mut(&advert.gid) {
+            if listing.host != advert.host {
+                return Ingest::WrongHost;
+            }
+            listing.info = advert.info();
+            if posted > listing.posted {
+                self.by_posted.remove(&(listing.posted, advert.gid.clone()));
+                self.by_posted.insert((posted, advert.gid.clone()));
+                listing.posted = posted;
+            }
+            return Ingest::Refreshed;
+        }
+
+        let sent = self.by_sender.get(sender).map_or(0, Vec::len);
+        if sent >= MAX_ADVERTS_PER_SENDER {
+            return Ingest::SenderFull;
+        }
+        if self.listings.len() >= MAX_ADVERTS {
+            if let Some((_, oldest)) = self.by_posted.iter().next().cloned() {
+                self.remove(&oldest);
+            }
+        }
+        self.by_sender.entry(sender.to_string()).or_default().push(advert.gid.clone());
+        self.by_posted.insert((posted, advert.gid.clone()));
+        let listing = Listing { info: advert.info(), host: advert.host.clone(), sender: sender.to_string(), posted };
+        self.listings.insert(advert.gid.clone(), listing);
+        Ingest::Added
+    }
+
+    /// Drop a game's advert, e.g. once it has started
+    pub fn remove(&mut self, game_id: &GameId) -> bool {
+        let Some(listing) = self.listings.remove(game_id) else {
+            return false;
+        };
+        self.by_posted.remove(&(listing.posted, game_id.clone()));
+        if let Some(games) = self.by_sender.get_mut(&listing.sender) {
+            games.retain(|id| id != game_id);
+            if games.is_empty() {
+                self.by_sender.remove(&listing.sender);
+            }
+        }
+        true
+    }
+
+    /// Drop adverts past their TTL at unix second `now`, returning how many
+    pub fn expire(&mut self, now: u64) -> usize {
+        let stale: Vec<GameId> = self.by_posted.iter()
+            .take_while(|(posted, _)| now.saturating_sub(*posted) > ADVERT_TTL_SECS)
+            .map(|(_, id)| id.clone())
+            .collect();
+        for id in &stale {
+            self.remove(id);
+        }
+        stale.len()
+    }
+
+    pub fn contains(&self, game_id: &GameId) -> bool {
+        self.listings.contains_key(game_id)
+    }
+
+    /// How an advertised game is listed
+    pub fn get(&self, game_id: &GameId) -> Option<&GameInfo> {
+        self.listings.get(game_id).map(|listing| &listing.info)
+    }
+
+    /// Games matching `query`, in its order
+    pub fn matching(&self, query: &GamesQuery) -> Vec<&GameInfo> {
+        let mut games: Vec<&GameInfo> = self.by_posted.iter().rev()
+            .filter_map(|(_, id)| self.listings.get(id))
+            .map(|listing| &listing.info)
+            .filter(|info| query.matches(info))
+            .collect();
+        query.sort(&mut games);
+        games
+    }
+
+    /// Up to `limit` games matching `query` from `offset` on
+    pub fn page(&self, query: &GamesQuery, offset: usize, limit: usize) -> GamesPage {
+        GamesPage::slice(&self.matching(query), offset, limit)
+    }
+}
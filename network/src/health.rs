@@ -0,0 +1,299 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Liveness supervision for background tasks.
+//!
+//! A task registered with the [`Supervisor`] gets a [`Heartbeat`] and calls
+//! [`Heartbeat::beat`] from its loop. A task that returns, or misses its
+//! deadline, is stopped and started again from its factory after a backoff
+//! that doubles each time. After `max_restarts` in a row it is left failed,
+//! so the player can be told instead of the app quietly limping along.
+//! A task that can't be started again, like the worker loop, is only
+//! [`watch`](Supervisor::watch)ed and reported failed once it stops beating.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Wait before the first restart
+pub const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait between restarts
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Restarts in a row before a subsystem is given up on
+pub const MAX_RESTARTS: u32 = 5;
+
+/// How long a restarted subsystem must stay up for its restarts to be forgiven
+pub const HEALTHY_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Longest a subsystem may go without a heartbeat
+pub const SUBSYSTEM_DEADLINE: Duration = Duration::from_secs(30);
+
+/// How often subsystems beat while waiting
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// When to restart and when to give up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_restarts: u32,
+    pub healthy_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: INITIAL_BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            max_restarts: MAX_RESTARTS,
+            healthy_after: HEALTHY_AFTER,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Wait before restart number `attempt`, counting from 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(16);
+        (self.initial_backoff * 2u32.pow(doublings)).min(self.max_backoff)
+    }
+}
+
+/// Proof of life a supervised task gives from its loop
+#[derive(Debug, Clone)]
+pub struct Heartbeat {
+    last: Arc<Mutex<Instant>>,
+}
+
+impl Heartbeat {
+    pub(crate) fn new(now: Instant) -> Self {
+        Self { last: Arc::new(Mutex::new(now)) }
+    }
+
+    /// Tell the supervisor this task is still making progress
+    pub fn beat(&self) {
+        *self.last.lock().unwrap() = Instant::now();
+    }
+
+    /// Await `work`, beating every [`HEARTBEAT_INTERVAL`] while it waits
+    /// and once it is done
+    pub async fn waiting<F: Future>(&self, work: F) -> F::Output {
+        tokio::pin!(work);
+        let mut beat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            tokio::select! {
+                output = &mut work => {
+                    self.beat();
+                    return output;
+                }
+                _ = beat.tick() => self.beat(),
+            }
+        }
+    }
+
+    fn last(&self) -> Instant {
+        *self.last.lock().unwrap()
+    }
+
+    fn reset(&self, now: Instant) {
+        *self.last.lock().unwrap() = now;
+    }
+}
+
+/// Where a subsystem stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubsystemStatus {
+    Running,
+    /// Stopped; restart number `attempt` is due at `at`
+    Restarting { attempt: u32, at: Instant },
+    /// Given up on after too many restarts
+    Failed,
+}
+
+/// Something the supervisor did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthEvent {
+    /// A subsystem was started again; `attempt` counts restarts in a row
+    Restarted { name: String, attempt: u32 },
+    /// A subsystem kept dying and won't be restarted
+    Failed { name: String, restarts: u32 },
+}
+
+type Factory = Box<dyn Fn(Heartbeat) -> JoinHandle<()> + Send + Sync>;
+
+struct Subsystem {
+    /// Longest gap between heartbeats
+    deadline: Duration,
+    /// How to start the task again; none for a task only watched
+    factory: Option<Factory>,
+    heartbeat: Heartbeat,
+    task: Option<JoinHandle<()>>,
+    status: SubsystemStatus,
+    /// Restarts in a row, forgiven once it stays up for `healthy_after`
+    restarts: u32,
+    started_at: Instant,
+}
+
+impl Subsystem {
+    /// Why a running task needs restarting, if it does
+    fn stalled(&self, now: Instant) -> Option<&'static str> {
+        if self.task.as_ref().is_some_and(JoinHandle::is_finished) {
+            Some("stopped")
+        } else if now.saturating_duration_since(self.heartbeat.last()) > self.deadline {
+            Some("missed its heartbeat")
+        } else {
+            None
+        }
+    }
+
+    fn start(&mut self, now: Instant) {
+        self.heartbeat.reset(now);
+        self.task = self.factory.as_ref().map(|factory| factory(self.heartbeat.clone()));
+        self.status = SubsystemStatus::Running;
+        self.started_at = now;
+    }
+
+    fn stop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Restarts registered background tasks that die or wedge.
+///
+/// Clones share the subsystems, so tasks can be registered from wherever
+/// they are started and checked from one place.
+#[derive(Clone, Default)]
+pub struct Supervisor {
+    inner: Arc<Mutex<Supervised>>,
+}
+
+#[derive(Default)]
+struct Supervised {
+    policy: RestartPolicy,
+    subsystems: BTreeMap<String, Subsystem>,
+}
+
+impl Supervisor {
+    pub fn new(policy: RestartPolicy) -> Self {
+        let inner = Supervised { policy, subsystems: BTreeMap::new() };
+        Self { inner: Arc::new(Mutex::new(inner)) }
+    }
+
+    /// Start `factory`'s task as `name`, expecting a heartbeat at least
+    /// every `deadline`; it is started the same way after each restart.
+    /// Registering a name again replaces the old task.
+    pub fn register<F, Fut>(&self, name: &str, deadline: Duration, factory: F)
+    where
+        F: Fn(Heartbeat) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let factory: Factory = Box::new(move |heartbeat| tokio::spawn(factory(heartbeat)));
+        self.insert(name, deadline, Some(factory));
+    }
+
+    /// Watch a task that can't be restarted from here, expecting a beat
+    /// on the returned heartbeat at least every `deadline`; it is
+    /// reported failed as soon as it misses one
+    pub fn watch(&self, name: &str, deadline: Duration) -> Heartbeat {
+        self.insert(name, deadline, None)
+    }
+
+    fn insert(&self, name: &str, deadline: Duration, factory: Option<Factory>) -> Heartbeat {
+        let now = Instant::now();
+        let mut subsystem = Subsystem {
+            deadline,
+            factory,
+            heartbeat: Heartbeat::new(now),
+            task: None,
+            status: SubsystemStatus::Running,
+            restarts: 0,
+            started_at: now,
+        };
+        subsystem.start(now);
+        let heartbeat = subsystem.heartbeat.clone();
+        if let Some(mut old) = self.inner.lock().unwrap().subsystems.insert(name.to_string(), subsystem) {
+            old.stop();
+        }
+        heartbeat
+    }
+
+    /// Stop supervising `name` and stop its task
+    pub fn unregister(&self, name: &str) {
+        let removed = self.inner.lock().unwrap().subsystems.remove(name);
+        if let Some(mut subsystem) = removed {
+            subsystem.stop();
+        }
+    }
+
+    pub fn status(&self, name: &str) -> Option<SubsystemStatus> {
+        self.inner.lock().unwrap().subsystems.get(name).map(|subsystem| subsystem.status)
+    }
+
+    /// Look for stalled subsystems and restart those that are due
+    pub fn check(&self, now: Instant) -> Vec<HealthEvent> {
+        let mut events = Vec::new();
+        let mut inner = self.inner.lock().unwrap();
+        let Supervised { policy, subsystems } = &mut *inner;
+        for (name, subsystem) in subsystems {
+            match subsystem.status {
+                SubsystemStatus::Failed => {}
+                SubsystemStatus::Restarting { attempt, at } => {
+                    if now >= at {
+                        subsystem.start(now);
+                        subsystem.restarts = attempt;
+                        tracing::info!(subsystem = %name, attempt, "Restarted subsystem");
+                        events.push(HealthEvent::Restarted { name: name.clone(), attempt });
+                    }
+                }
+                SubsystemStatus::Running => {
+                    let Some(reason) = subsystem.stalled(now) else {
+                        if now.saturating_duration_since(subsystem.started_at) >= policy.healthy_after {
+                            subsystem.restarts = 0;
+                        }
+                        continue;
+                    };
+                    subsystem.stop();
+                    if subsystem.factory.is_none() || subsystem.restarts >= policy.max_restarts {
+                        tracing::error!(subsystem = %name, restarts = subsystem.restarts, "Subsystem {}, giving up", reason);
+                        subsystem.status = SubsystemStatus::Failed;
+                        events.push(HealthEvent::Failed { name: name.clone(), restarts: subsystem.restarts });
+                        continue;
+                    }
+                    let attempt = subsystem.restarts + 1;
+                    let backoff = policy.backoff(attempt);
+                    tracing::warn!(subsystem = %name, attempt, "Subsystem {}, restarting in {:?}", reason, backoff);
+                    subsystem.status = SubsystemStatus::Restarting { attempt, at: now + backoff };
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Run `factory`'s task under `supervisor` as `name`, or just once when
+/// nobody supervises it
+pub(crate) fn supervise<F, Fut>(supervisor: Option<&Supervisor>, name: &str, factory: F)
+where
+    F: Fn(Heartbeat) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    match supervisor {
+        Some(supervisor) => supervisor.register(name, SUBSYSTEM_DEADLINE, factory),
+        None => {
+            tokio::spawn(factory(Heartbeat::new(Instant::now())));
+        }
+    }
+}
+
+impl Drop for Supervised {
+    fn drop(&mut self) {
+        for subsystem in self.subsystems.values_mut() {
+            subsystem.stop();
+        }
+    }
+}
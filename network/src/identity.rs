@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Persistent peer identity.
+//!
+//! The node's ed25519 secret key lives in the data directory so the node
+//! ID survives restarts. The key file can be encrypted with a passphrase
+//! (Argon2id key derivation, ChaCha20-Poly1305). Exports use the same
+//! format, base64-encoded, so identities can move between machines.
+//!
+//! Secret key material is wiped from memory once dropped. [`Identity`] is
+//! neither `Clone` nor serializable, and signs on its holder's behalf so
+//! the secret key never has to leave it.
+
+use std::path::{Path, PathBuf};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use ed25519_dalek::{Signer, SigningKey};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// File name of the key inside the data directory
+pub const IDENTITY_FILE: &str = "identity.key";
+
+/// Current key file format
+const FORMAT_VERSION: u8 = 1;
+
+/// Errors loading or saving an identity
+#[derive(Debug, Error)]
+pub enum IdentityError {
+    /// The key file is encrypted and no passphrase was given
+    #[error("Identity is encrypted; a passphrase is required")]
+    PassphraseRequired,
+
+    /// The passphrase does not decrypt the key file
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+
+    /// The key file or export could not be decoded
+    #[error("Corrupt identity: {0}")]
+    Corrupt(String),
+
+    /// Reading or writing the key file failed
+    #[error("Identity file error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The node's long-lived keypair; its secret key is wiped when dropped
+pub struct Identity {
+    /// Wipes itself when dropped
+    key: SigningKey,
+}
+
+impl Identity {
+    /// Create a new random identity
+    pub fn generate() -> Result<Self, IdentityError> {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        random_bytes(secret.as_mut())?;
+        Ok(Self::from_secret_bytes(&secret))
+    }
+
+    pub fn from_secret_bytes(secret: &[u8; 32]) -> Self {
+        Self { key: SigningKey::from_bytes(secret) }
+    }
+
+    /// Secret key bytes, wiped once dropped; only for handing the key to
+    /// iroh as its `SecretKey` and sealing it in a key file
+    pub(crate) fn secret_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.key.to_bytes())
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    /// Hex-encoded public key, the form iroh displays node IDs in
+    pub fn node_id(&self) -> String {
+        hex::encode(self.public_key())
+    }
+
+    /// Ed25519 signature over `message`
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.key.sign(message).to_bytes()
+    }
+}
+
+impl std::fmt::Debug for Identity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the secret key
+        f.debug_struct("Identity").field("node_id", &self.node_id()).finish()
+    }
+}
+
+/// On-disk and export format
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    version: u8,
+    key: KeyMaterial,
+}
+
+/// Wiped when dropped, as a plain key is the secret itself
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+enum KeyMaterial {
+    Plain { secret: [u8; 32] },
+    Encrypted { salt: [u8; 16], nonce: [u8; 12], ciphertext: Vec<u8> },
+}
+
+impl KeyFile {
+    fn seal(identity: &Identity, passphrase: Option<&str>) -> Result<Self, IdentityError> {
+        let key = match passphrase {
+            None => KeyMaterial::Plain { secret: *identity.secret_bytes() },
+            Some(passphrase) => {
+                let mut salt = [0u8; 16];
+                let mut nonce = [0u8; 12];
+                random_bytes(&mut salt)?;
+                random_bytes(&mut nonce)?;
+                let cipher = cipher_for(passphrase, &salt)?;
+                let ciphertext = cipher
+                    .encrypt(Nonce::from_slice(&nonce), identity.secret_bytes().as_slice())
+                    .map_err(|_| IdentityError::Corrupt("encryption failed".to_string()))?;
+                KeyMaterial::Encrypted { salt, nonce, ciphertext }
+            }
+        };
+        Ok(Self { version: FORMAT_VERSION, key })
+    }
+
+    fn open(&self, passphrase: Option<&str>) -> Result<Identity, IdentityError> {
+        if self.version != FORMAT_VERSION {
+            return Err(IdentityError::Corrupt(format!("unknown format version {}", self.version)));
+        }
+        match &self.key {
+            KeyMaterial::Plain { secret } => Ok(Identity::from_secret_bytes(secret)),
+            KeyMaterial::Encrypted { salt, nonce, ciphertext } => {
+                let passphrase = passphrase.ok_or(IdentityError::PassphraseRequired)?;
+                let plaintext = Zeroizing::new(cipher_for(passphrase, salt)?
+                    .decrypt(Nonce::from_slice(nonce), ciphertext.as_slice())
+                    .map_err(|_| IdentityError::WrongPassphrase)?);
+                let secret: &[u8; 32] = plaintext.as_slice().try_into()
+                    .map_err(|_| IdentityError::Corrupt("wrong key length".to_string()))?;
+                Ok(Identity::from_secret_bytes(secret))
+            }
+        }
+    }
+
+    fn is_encrypted(&self) -> bool {
+        matches!(self.key, KeyMaterial::Encrypted { .. })
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, IdentityError> {
+        serde_cbor::to_vec(self).map_err(|e| IdentityError::Corrupt(e.to_string()))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, IdentityError> {
+        serde_cbor::from_slice(bytes).map_err(|e| IdentityError::Corrupt(e.to_string()))
+    }
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8; 16]) -> Result<ChaCha20Poly1305, IdentityError> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| IdentityError::Corrupt(format!("key derivation failed: {}", e)))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_slice())))
+}
+
+fn random_bytes(buf: &mut [u8]) -> Result<(), IdentityError> {
+    getrandom::getrandom(buf)
+        .map_err(|e| IdentityError::Io(std::io::Error::other(e.to_string())))
+}
+
+/// Loads, stores and moves the identity key file
+#[derive(Debug, Clone)]
+pub struct IdentityManager {
+    path: PathBuf,
+}
+
+impl IdentityManager {
+    /// Manage the key file at `path`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Manage the key file in the platform data directory
+    pub fn open_default() -> Result<Self, IdentityError> {
+        let dirs = p2pgo_core::p2pgo_dirs::Dirs::current()
+            .map_err(|e| IdentityError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, e.to_string())))?;
+        Ok(Self::new(dirs.data.join(IDENTITY_FILE)))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a key file exists and needs a passphrase
+    pub fn is_encrypted(&self) -> Result<bool, IdentityError> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => Ok(KeyFile::from_bytes(&bytes)?.is_encrypted()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load the identity, creating and saving a new one on first run.
+    ///
+    /// `passphrase` decrypts an existing file, or encrypts a new one.
+    pub fn load_or_generate(&self, passphrase: Option<&str>) -> Result<Identity, IdentityError> {
+        match std::fs::read(&self.path) {
+            Ok(bytes) => KeyFile::from_bytes(&bytes)?.open(passphrase),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let identity = Identity::generate()?;
+                self.save(&identity, passphrase)?;
+                tracing::info!(node_id = %identity.node_id(), "Generated new identity");
+                Ok(identity)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write `identity`, encrypted if a passphrase is given
+    pub fn save(&self, identity: &Identity, passphrase: Option<&str>) -> Result<(), IdentityError> {
+        let bytes = KeyFile::seal(identity, passphrase)?.to_bytes()?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write a sibling file and rename so a crash never leaves half a key
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    /// Ed25519 signature over `message` by the stored identity, which is
+    /// dropped again straight away; `passphrase` decrypts the key file
+    pub fn sign(&self, message: &[u8], passphrase: Option<&str>) -> Result<[u8; 64], IdentityError> {
+        let bytes = std::fs::read(&self.path)?;
+        Ok(KeyFile::from_bytes(&bytes)?.open(passphrase)?.sign(message))
+    }
+
+    /// Text another machine can pass to [`IdentityManager::import`]
+    pub fn export(&self, identity: &Identity, passphrase: Option<&str>) -> Result<String, IdentityError> {
+        Ok(B64.encode(KeyFile::seal(identity, passphrase)?.to_bytes()?))
+    }
+
+    /// Replace the stored identity with an exported one.
+    ///
+    /// The key file keeps the export's encryption.
+    pub fn import(&self, export: &str, passphrase: Option<&str>) -> Result<Identity, IdentityError> {
+        let bytes = B64.decode(export.trim())
+            .map_err(|e| IdentityError::Corrupt(e.to_string()))?;
+        let key_file = KeyFile::from_bytes(&bytes)?;
+        let identity = key_file.open(passphrase)?;
+        let sealed_with = if key_file.is_encrypted() { passphrase } else { None };
+        self.save(&identity, sealed_with)?;
+        Ok(identity)
+    }
+}
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Idle detection for games whose opponent has gone away.
+//!
+//! Every move or ping resets an [`IdleTracker`]. After `prompt_after`
+//! without activity the local player is asked whether to abandon the game;
+//! after `forfeit_after` the game is abandoned without asking. The tracker
+//! takes the current time as an argument so tests can drive it with a
+//! mock clock.
+
+use std::time::{Duration, Instant};
+
+/// Default quiet period before the player is asked to abandon
+pub const IDLE_PROMPT_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Default quiet period after which the game is abandoned unattended
+pub const IDLE_FORFEIT_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// Reason recorded for games abandoned by the idle check
+pub const IDLE_FORFEIT_REASON: &str = "Opponent disconnected";
+
+/// Idle thresholds for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleConfig {
+    /// Quiet period before the player is asked to confirm abandonment
+    pub prompt_after: Duration,
+    /// Quiet period after which the game is abandoned without asking
+    pub forfeit_after: Duration,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            prompt_after: IDLE_PROMPT_AFTER,
+            forfeit_after: IDLE_FORFEIT_AFTER,
+        }
+    }
+}
+
+/// What the caller should do about a game after an idle check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleStatus {
+    /// Nothing to do
+    Active,
+    /// Ask the player whether to abandon; reported once per quiet period
+    ConfirmAbandon { idle_for: Duration },
+    /// Abandon the game now
+    Abandoned,
+}
+
+/// Tracks the last activity of one game
+#[derive(Debug, Clone)]
+pub struct IdleTracker {
+    config: IdleConfig,
+    last_activity: Instant,
+    prompted: bool,
+}
+
+impl IdleTracker {
+    pub fn new(config: IdleConfig, now: Instant) -> Self {
+        Self {
+            config,
+            last_activity: now,
+            prompted: false,
+        }
+    }
+
+    /// A move or ping arrived; start a new quiet period
+    pub fn record_activity(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.prompted = false;
+    }
+
+    /// The player chose to keep waiting; same as fresh activity
+    pub fn keep_waiting(&mut self, now: Instant) {
+        self.record_activity(now);
+    }
+
+    /// How long the game has been quiet
+    pub fn idle_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_activity)
+    }
+
+    /// Compare the quiet period against the thresholds
+    pub fn check(&mut self, now: Instant) -> IdleStatus {
+        let idle_for = self.idle_for(now);
+        if idle_for >= self.config.forfeit_after {
+            return IdleStatus::Abandoned;
+        }
+        if idle_for >= self.config.prompt_after && !self.prompted {
+            self.prompted = true;
+            return IdleStatus::ConfirmAbandon { idle_for };
+        }
+        IdleStatus::Active
+    }
+}
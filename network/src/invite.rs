@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Invite links: a ticket plus the game to join on the other end.
+//!
+//! A link is `p2pgo://invite/` followed by URL-safe base64 of the CBOR
+//! invite and a 4-byte checksum of it, so a link cut short in a paste is
+//! refused rather than joining the wrong game.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as B64, Engine};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::GameId;
+use crate::relay_robustness::now_secs;
+
+/// Start of every invite link
+pub const INVITE_PREFIX: &str = "p2pgo://invite/";
+
+/// How long an invite stays valid, in seconds
+pub const INVITE_TTL_SECS: u64 = 24 * 60 * 60;
+
+const CHECKSUM_LEN: usize = 4;
+
+/// Why a link couldn't be used
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InviteError {
+    #[error("Not an invite link; invite links start with {}", INVITE_PREFIX)]
+    NotAnInvite,
+    #[error("Invite link is damaged, it may have been cut short")]
+    Damaged,
+    #[error("Invite link expired")]
+    Expired,
+}
+
+/// Everything needed to join a specific game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Invite {
+    pub ticket: String,
+    pub game_id: GameId,
+    pub board_size: u8,
+    pub host_name: String,
+    /// Unix seconds after which the link is refused
+    pub expires_at: u64,
+}
+
+impl Invite {
+    /// An invite valid for [`INVITE_TTL_SECS`] from now
+    pub fn new(ticket: String, game_id: GameId, board_size: u8, host_name: String) -> Self {
+        Self { ticket, game_id, board_size, host_name, expires_at: now_secs() + INVITE_TTL_SECS }
+    }
+
+    pub fn to_link(&self) -> String {
+        // CBOR of plain strings and integers can't fail
+        let mut bytes = serde_cbor::to_vec(self).unwrap_or_default();
+        bytes.extend_from_slice(&checksum(&bytes));
+        format!("{}{}", INVITE_PREFIX, B64.encode(bytes))
+    }
+
+    /// Read a link, refusing damaged and expired ones
+    pub fn parse(link: &str) -> Result<Self, InviteError> {
+        Self::parse_at(link, now_secs())
+    }
+
+    /// Read a link as of unix time `now`
+    pub fn parse_at(link: &str, now: u64) -> Result<Self, InviteError> {
+        let encoded = link.trim().strip_prefix(INVITE_PREFIX).ok_or(InviteError::NotAnInvite)?;
+        let bytes = B64.decode(encoded.trim_end_matches('/')).map_err(|_| InviteError::Damaged)?;
+        if bytes.len() < CHECKSUM_LEN {
+            return Err(InviteError::Damaged);
+        }
+        let (payload, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(payload) != sum {
+            return Err(InviteError::Damaged);
+        }
+        let invite: Invite = serde_cbor::from_slice(payload).map_err(|_| InviteError::Damaged)?;
+        if now > invite.expires_at {
+            return Err(InviteError::Expired);
+        }
+        Ok(invite)
+    }
+
+    /// Whether `text` looks like an invite rather than a bare ticket
+    pub fn is_link(text: &str) -> bool {
+        text.trim().starts_with(INVITE_PREFIX)
+    }
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = blake3::hash(payload);
+    let mut sum = [0; CHECKSUM_LEN];
+    sum.copy_from_slice(&hash.as_bytes()[..CHECKSUM_LEN]);
+    sum
+}
@@ -2,8 +2,16 @@
 
 //! Iroh networking endpoint management for P2P Go
 
-use anyhow::{Result, Context, ensure, bail};
+#[cfg(feature = "iroh")]
+use {anyhow::Context, crate::Error};
 use p2pgo_core::MoveRecord;
+use crate::admission::{Admission, ConnectionLimits};
+use std::sync::Arc;
+use tokio::sync::watch;
+use crate::fragment::{self, GossipLimits};
+use crate::identity::Identity;
+use crate::lobby::{GameAdvert, GameTerms};
+use crate::Result;
 
 #[cfg(feature = "iroh")]
 use {
@@ -16,18 +24,47 @@ use {
     base64::Engine,
     tokio::sync::mpsc,
     futures_lite::{future::Boxed as BoxedFuture, StreamExt},
-    std::sync::Arc,
+    std::time::{Duration, Instant},
     bytes::Bytes,
     iroh::PublicKey,
+    crate::traffic::{self, TrafficCategory},
 };
 
 #[cfg(not(feature = "iroh"))]
-use tokio::sync::mpsc;
+use {
+    tokio::sync::mpsc,
+    crate::blob_store::BlobStore,
+    crate::{loopback, Error},
+};
 
 #[cfg(not(feature = "iroh"))]
 // Stub implementation only needs minimal stubs defined below
 pub struct EndpointStub;
 
+/// How a context reaches its peers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportOptions {
+    /// Relay to use instead of the default ones: a relay URL, or a
+    /// loopback relay's address without iroh
+    pub relay: Option<String>,
+    /// Whether peers are dialed directly; if not, all traffic goes through
+    /// `relay`
+    pub direct: bool,
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self { relay: None, direct: true }
+    }
+}
+
+impl TransportOptions {
+    /// Only ever reach peers through `relay`
+    pub fn relay_only(relay: impl Into<String>) -> Self {
+        Self { relay: Some(relay.into()), direct: false }
+    }
+}
+
 /// Hard-coded ALPN for p2pgo protocol
 const P2PGO_ALPN: &[u8] = b"p2pgo";
 
@@ -73,6 +110,64 @@ impl ProtocolHandler for P2PGoProtocol {
     }
 }
 
+/// How often an admitted connection's traffic is added to the bandwidth count
+#[cfg(feature = "iroh")]
+const USAGE_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Wraps a protocol so its connections need a permit from [`Admission`]
+#[cfg(feature = "iroh")]
+#[derive(Debug, Clone)]
+struct Admitted<H> {
+    inner: H,
+    admission: Admission,
+}
+
+#[cfg(feature = "iroh")]
+impl<H: ProtocolHandler> ProtocolHandler for Admitted<H> {
+    fn accept(&self, connection: Connection) -> BoxedFuture<anyhow::Result<()>> {
+        let permit = match self.admission.admit(Instant::now()) {
+            Ok(permit) => permit,
+            Err(refusal) => {
+                tracing::info!("Refusing connection from {:?}: {}", connection.remote_node_id().ok(), refusal);
+                connection.close(crate::admission::ADMISSION_CLOSE_CODE.into(), refusal.to_string().as_bytes());
+                return Box::pin(async { Ok(()) });
+            }
+        };
+        // Hold the permit and count the traffic until the connection
+        // closes, or close it when lower limits leave no room for it
+        let watched = connection.clone();
+        let admission = self.admission.clone();
+        tokio::spawn(async move {
+            let mut limits = admission.watch();
+            let mut counted = 0;
+            let mut interval = tokio::time::interval(USAGE_SAMPLE_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = watched.closed() => break,
+                    Ok(()) = limits.changed() => {
+                        if permit.drained() {
+                            tracing::info!("Draining connection from {:?} over the new limits", watched.remote_node_id().ok());
+                            watched.close(crate::admission::ADMISSION_CLOSE_CODE.into(), b"over the node's connection limit");
+                            break;
+                        }
+                    }
+                    _ = interval.tick() => {
+                        let stats = watched.stats();
+                        let total = stats.udp_tx.bytes + stats.udp_rx.bytes;
+                        admission.record(total - counted, Instant::now());
+                        counted = total;
+                    }
+                }
+            }
+        });
+        self.inner.accept(connection)
+    }
+
+    fn shutdown(&self) -> BoxedFuture<()> {
+        self.inner.shutdown()
+    }
+}
+
 /// CBOR-encoded, base64-text ticket (forward compatible)
 #[cfg(feature = "iroh")]
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -89,16 +184,6 @@ pub struct EnhancedTicket {
     pub version: u8,
 }
 
-/// Game advertisement structure for gossip
-#[cfg(feature = "iroh")]
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct GameAdvert {
-    pub gid: String,
-    pub size: u8,
-    pub host: String,
-    pub bot: bool,
-}
-
 /// Iroh networking context
 #[cfg(feature = "iroh")]
 #[derive(Clone)]
@@ -112,6 +197,11 @@ pub struct IrohCtx {
     #[allow(dead_code)]
     default_author: AuthorId,
     my_id: String,
+    gossip_limits: GossipLimits,
+    transport: TransportOptions,
+    admission: Admission,
+    /// Sets `admission`'s limits, unless they come from `bind_with_limits`
+    limits_tx: Option<Arc<watch::Sender<ConnectionLimits>>>,
     // Channel for receiving incoming connections
     connection_rx: Arc<tokio::sync::Mutex<tokio::sync::mpsc::UnboundedReceiver<Connection>>>,
 }
@@ -120,19 +210,97 @@ pub struct IrohCtx {
 pub struct IrohCtx {
     _ep: EndpointStub,
     my_id: String,
+    gossip_limits: GossipLimits,
+    transport: TransportOptions,
+    admission: Admission,
+    /// Sets `admission`'s limits, unless they come from `bind_with_limits`
+    limits_tx: Option<Arc<watch::Sender<ConnectionLimits>>>,
+    /// Moves stored for the games played on this node
+    moves: BlobStore,
+}
+
+#[cfg(not(feature = "iroh"))]
+impl Drop for IrohCtx {
+    fn drop(&mut self) {
+        loopback::leave(&self.my_id);
+    }
 }
 
 impl IrohCtx {
     /// Create a new Iroh networking context
-    #[tracing::instrument(level = "debug")]
     pub async fn new() -> Result<Self> {
+        Self::bind(&[]).await
+    }
+    
+    /// Create a context bound to `listen_addrs`, or to any free port if empty
+    pub async fn bind(listen_addrs: &[std::net::SocketAddr]) -> Result<Self> {
+        Self::build(None, listen_addrs, TransportOptions::default(), None).await
+    }
+    
+    /// Like [`IrohCtx::bind`], but refuse incoming connections beyond
+    /// `limits`; every value sent on the channel applies from then on
+    pub async fn bind_with_limits(listen_addrs: &[std::net::SocketAddr], limits: watch::Receiver<ConnectionLimits>) -> Result<Self> {
+        Self::build(None, listen_addrs, TransportOptions::default(), Some(limits)).await
+    }
+    
+    /// Create a context whose node ID comes from a persistent identity
+    pub async fn with_identity(identity: &Identity) -> Result<Self> {
+        Self::build(Some(identity), &[], TransportOptions::default(), None).await
+    }
+    
+    /// Create a context that reaches peers as `transport` says
+    pub async fn with_transport(transport: TransportOptions) -> Result<Self> {
+        Self::build(None, &[], transport, None).await
+    }
+    
+    #[tracing::instrument(level = "debug")]
+    async fn build(identity: Option<&Identity>, listen_addrs: &[std::net::SocketAddr], transport: TransportOptions, limits: Option<watch::Receiver<ConnectionLimits>>) -> Result<Self> {
+        if !transport.direct && transport.relay.is_none() {
+            return Err(Error::RelayUnavailable("direct dialing is off and no relay was given".to_string()));
+        }
+        // Without limits from the caller, accept everything until `set_limits`
+        let (limits_tx, admission) = match limits {
+            Some(limits) => (None, Admission::new(limits)),
+            None => {
+                let (tx, rx) = watch::channel(ConnectionLimits::unlimited());
+                (Some(Arc::new(tx)), Admission::new(rx))
+            }
+        };
+        
         #[cfg(feature = "iroh")]
         {
             tracing::debug!("Creating Iroh endpoint with relay support");
             
             // Create iroh endpoint with relay support for NAT traversal
-            let endpoint = Endpoint::builder()
-                .relay_mode(iroh::RelayMode::Default)
+            let relay_mode = match &transport.relay {
+                Some(url) => {
+                    let url: iroh::RelayUrl = url.parse()
+                        .map_err(|e| Error::RelayUnavailable(format!("bad relay URL {}: {}", url, e)))?;
+                    iroh::RelayMode::Custom(iroh::RelayMap::from_url(url))
+                }
+                None => iroh::RelayMode::Default,
+            };
+            let mut builder = Endpoint::builder()
+                .relay_mode(relay_mode);
+            if !transport.direct {
+                // iroh only offers relay-only paths to tests and benchmarks
+                #[cfg(feature = "relay-only")]
+                {
+                    builder = builder.path_selection(iroh::endpoint::PathSelection::RelayOnly);
+                }
+                #[cfg(not(feature = "relay-only"))]
+                return Err(Error::internal("turning off direct dialing needs the relay-only feature"));
+            }
+            if let Some(identity) = identity {
+                builder = builder.secret_key(iroh::SecretKey::from_bytes(&identity.secret_bytes()));
+            }
+            for addr in listen_addrs {
+                builder = match addr {
+                    std::net::SocketAddr::V4(addr) => builder.bind_addr_v4(*addr),
+                    std::net::SocketAddr::V6(addr) => builder.bind_addr_v6(*addr),
+                };
+            }
+            let endpoint = builder
                 .bind()
                 .await
                 .context("Failed to bind iroh endpoint")?;
@@ -178,12 +346,12 @@ impl IrohCtx {
             // Create the P2P Go protocol handler
             let p2pgo_protocol = P2PGoProtocol::new(connection_tx);
             
-            // Set up the router with all protocols
+            // Set up the router with all protocols, each behind admission
             let router = Router::builder(endpoint.clone())
-                .accept(iroh_gossip::ALPN, gossip.clone())
-                .accept(iroh_docs::ALPN, docs.clone())
-                .accept(iroh_blobs::ALPN, blobs.clone())
-                .accept(P2PGO_ALPN, p2pgo_protocol)
+                .accept(iroh_gossip::ALPN, Admitted { inner: gossip.clone(), admission: admission.clone() })
+                .accept(iroh_docs::ALPN, Admitted { inner: docs.clone(), admission: admission.clone() })
+                .accept(iroh_blobs::ALPN, Admitted { inner: blobs.clone(), admission: admission.clone() })
+                .accept(P2PGO_ALPN, Admitted { inner: p2pgo_protocol, admission: admission.clone() })
                 .spawn();
             
             let my_id = endpoint.node_id().to_string();
@@ -201,22 +369,112 @@ impl IrohCtx {
                 blobs,
                 default_author,
                 my_id,
+                gossip_limits: GossipLimits::default(),
+                transport,
+                admission,
+                limits_tx,
                 connection_rx: Arc::new(tokio::sync::Mutex::new(connection_rx)),
             })
         }
         
-        // Fallback to TCP loopback stub
+        // Fall back to the in-process loopback network
         #[cfg(not(feature = "iroh"))]
         {
-            tracing::info!("Using TCP loopback stub for networking");
+            tracing::info!("Using the in-process loopback network (listen addrs {:?} ignored)", listen_addrs);
+            if let Some(relay) = &transport.relay {
+                if !loopback::is_relay_running(relay) {
+                    return Err(Error::RelayUnavailable(format!("no relay runs at {} in this process", relay)));
+                }
+            }
+            let my_id = identity.map_or_else(|| format!("loopback-{}", uuid::Uuid::new_v4().simple()), Identity::node_id);
+            loopback::join(&my_id);
             
             return Ok(Self {
                 _ep: EndpointStub,
-                my_id: "loopback-node".to_string(),
+                my_id,
+                gossip_limits: GossipLimits::default(),
+                transport,
+                admission,
+                limits_tx,
+                moves: BlobStore::new(),
             });
         }
     }
     
+    /// Size limits for what we gossip and the fragments we reassemble
+    pub fn with_gossip_limits(mut self, limits: GossipLimits) -> Self {
+        self.gossip_limits = limits;
+        self
+    }
+
+    pub fn gossip_limits(&self) -> &GossipLimits {
+        &self.gossip_limits
+    }
+
+    /// Admission for incoming connections, with its live counts
+    pub fn admission(&self) -> &Admission {
+        &self.admission
+    }
+
+    /// Refuse incoming connections beyond `limits` from now on, draining
+    /// the newest of those already open if there are too many
+    pub fn set_limits(&self, limits: ConnectionLimits) -> Result<()> {
+        match &self.limits_tx {
+            Some(limits_tx) => {
+                limits_tx.send_replace(limits);
+                Ok(())
+            }
+            None => Err(Error::internal("this node's limits come from the channel it was bound with")),
+        }
+    }
+
+    pub fn transport(&self) -> &TransportOptions {
+        &self.transport
+    }
+
+    /// Wait until the endpoint is listening with at least one direct address
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn wait_ready(&self, timeout: std::time::Duration) -> Result<()> {
+        #[cfg(feature = "iroh")]
+        {
+            let ready = async {
+                loop {
+                    let addr = self.endpoint.node_addr().await?;
+                    if !addr.direct_addresses.is_empty() {
+                        return Ok::<(), anyhow::Error>(());
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+                }
+            };
+            tokio::time::timeout(timeout, ready)
+                .await
+                .map_err(|_| Error::Timeout { what: "the iroh endpoint to become ready", after: timeout })??;
+            tracing::debug!("Iroh endpoint ready");
+            Ok(())
+        }
+        
+        #[cfg(not(feature = "iroh"))]
+        {
+            tracing::debug!("Stub endpoint is always ready (timeout {:?})", timeout);
+            Ok(())
+        }
+    }
+    
+    /// Stop accepting connections and close existing ones gracefully
+    pub async fn shutdown(&self) -> Result<()> {
+        #[cfg(feature = "iroh")]
+        {
+            self.router.shutdown().await.context("Failed to shut down iroh router")?;
+            Ok(())
+        }
+        
+        #[cfg(not(feature = "iroh"))]
+        {
+            tracing::debug!("Loopback node {} shut down", self.my_id);
+            Ok(())
+        }
+    }
+    
     /// Get the node ID (base32 pubkey)
     pub fn node_id(&self) -> &str {
         &self.my_id
@@ -234,6 +492,12 @@ impl IrohCtx {
         &self.blobs
     }
     
+    /// Moves stored on this loopback node
+    #[cfg(not(feature = "iroh"))]
+    pub fn move_store(&self) -> &BlobStore {
+        &self.moves
+    }
+    
     /// Generate a connection ticket with optional game size hint
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn ticket(&self) -> Result<String> {
@@ -254,7 +518,9 @@ impl IrohCtx {
             let addr = self.endpoint.node_addr().await?;
             
             // Ensure we have external addresses for internet connectivity
-            ensure!(!addr.direct_addresses.is_empty(), "NodeAddr missing external addresses - relay not ready");
+            if addr.direct_addresses.is_empty() {
+                return Err(Error::RelayUnavailable("NodeAddr missing external addresses - relay not ready".to_string()));
+            }
             
             let doc = match game_id {
                 Some(gid) => Some(Self::doc_id_for_game(gid)),
@@ -269,7 +535,7 @@ impl IrohCtx {
                 version: 1,
             };
             
-            let bytes = serde_cbor::to_vec(&ticket)?;
+            let bytes = serde_cbor::to_vec(&ticket).context("Failed to encode ticket")?;
             let ticket_str = B64.encode(bytes);
             
             tracing::debug!("Generated Iroh ticket with {} addresses: {}", addr.direct_addresses.len(), ticket_str);
@@ -278,7 +544,8 @@ impl IrohCtx {
         
         #[cfg(not(feature = "iroh"))]
         {
-            Ok("loopback-ticket".to_string())
+            tracing::debug!("Loopback ticket for game {:?} of size {:?}", game_id, game_size);
+            Ok(loopback::ticket(&self.my_id))
         }
     }
     
@@ -290,11 +557,14 @@ impl IrohCtx {
         
         // Base64 decode the ticket
         let bytes = B64.decode(ticket)
-            .context("Failed to decode base64 ticket")?;
+            .map_err(|e| Error::TicketInvalid(format!("not base64: {}", e)))?;
         
         // CBOR decode to EnhancedTicket
         let ticket: EnhancedTicket = serde_cbor::from_slice(&bytes)
-            .context("Failed to decode CBOR ticket")?;
+            .map_err(|e| Error::TicketInvalid(format!("not a CBOR ticket: {}", e)))?;
+        if ticket.node.node_id == self.endpoint.node_id() {
+            return Err(Error::SelfConnection);
+        }
         
         tracing::info!("Connecting to node: {:?} with {} addresses", 
             ticket.node.node_id, ticket.node.direct_addresses.len());
@@ -307,7 +577,7 @@ impl IrohCtx {
             }
             Err(e) => {
                 tracing::debug!("Failed to connect via NodeAddr: {}", e);
-                bail!("❌ Could not connect to peer: {}", e)
+                Err(Error::ConnectionFailed { peer: ticket.node.node_id.to_string(), source: e.into() })
             }
         }
     }
@@ -324,7 +594,25 @@ impl IrohCtx {
         
         #[cfg(not(feature = "iroh"))]
         {
-            tracing::debug!("TCP loopback connection to ticket: {}", ticket);
+            let node_id = loopback::parse_ticket(ticket)?;
+            if node_id == self.my_id {
+                return Err(Error::SelfConnection);
+            }
+            if !loopback::is_running(node_id) {
+                return Err(Error::ConnectionFailed {
+                    peer: node_id.to_string(),
+                    source: "no node of that ID runs in this process".into(),
+                });
+            }
+            if let (Some(relay), false) = (&self.transport.relay, self.transport.direct) {
+                if !loopback::is_relay_running(relay) {
+                    return Err(Error::ConnectionFailed {
+                        peer: node_id.to_string(),
+                        source: format!("relay {} has stopped", relay).into(),
+                    });
+                }
+            }
+            tracing::debug!("Connected to loopback node {}", node_id);
             Ok(())
         }
     }
@@ -336,6 +624,32 @@ impl IrohCtx {
         TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
     }
     
+    /// Name of the loopback topic for a given board size lobby
+    #[cfg(not(feature = "iroh"))]
+    pub fn lobby_topic(size: u8) -> String {
+        format!("p2pgo.lobby.{}", size)
+    }
+    
+    /// Create a topic ID for the matchmaking queue of a board size
+    #[cfg(feature = "iroh")]
+    pub fn matchmaking_topic(size: u8) -> TopicId {
+        let topic_name = format!("p2pgo.queue.{}", size);
+        TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
+    }
+    
+    /// Create a topic ID for training data announcements
+    #[cfg(feature = "iroh")]
+    pub fn training_topic() -> TopicId {
+        TopicId::from_bytes(*blake3::hash(b"p2pgo.training").as_bytes())
+    }
+    
+    /// Create a topic ID for a tournament's published results
+    #[cfg(feature = "iroh")]
+    pub fn tournament_topic(tournament: &str) -> TopicId {
+        let topic_name = format!("p2pgo.tournament.{}", tournament);
+        TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
+    }
+    
     /// Create a topic ID for a specific game
     #[cfg(feature = "iroh")]
     pub fn game_topic(game_id: &str) -> TopicId {
@@ -343,6 +657,25 @@ impl IrohCtx {
         TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
     }
     
+    /// Gossip topic join requests for a game travel on
+    #[cfg(feature = "iroh")]
+    pub fn join_topic(game_id: &str) -> TopicId {
+        let topic_name = format!("p2pgo.join.{}", game_id);
+        TopicId::from_bytes(*blake3::hash(topic_name.as_bytes()).as_bytes())
+    }
+    
+    /// Name of the loopback topic for a specific game
+    #[cfg(not(feature = "iroh"))]
+    pub fn game_topic(game_id: &str) -> String {
+        format!("p2pgo.game.{}", game_id)
+    }
+    
+    /// Gossip topic join requests for a game travel on
+    #[cfg(not(feature = "iroh"))]
+    pub fn join_topic(game_id: &str) -> String {
+        format!("p2pgo.join.{}", game_id)
+    }
+    
     /// Subscribe to gossip topic for lobby
     #[tracing::instrument(level = "debug", skip(self))]
     #[cfg(feature = "iroh")]
@@ -372,14 +705,28 @@ impl IrohCtx {
         
         // Create a channel to convert the stream to a receiver
         let (tx, rx) = mpsc::channel(buffer_size);
+        let mut reassembler = fragment::Reassembler::new(self.gossip_limits.clone());
         
         // Spawn a task to forward events with retry logic
         tokio::spawn(async move {
             tracing::info!("Gossip subscription active for topic: {:?}", topic_id);
             loop {
                 match gossip_topic.next().await {
-                    Some(Ok(event)) => {
+                    Some(Ok(mut event)) => {
                         tracing::debug!("Received gossip event: {:?}", event);
+                        if let iroh_gossip::net::Event::Gossip(iroh_gossip::net::GossipEvent::Received(message)) = &mut event {
+                            traffic::record(TrafficCategory::Gossip, message.content.len());
+                            // Pass on fragmented payloads once whole, whichever neighbours brought them
+                            let neighbour = message.delivered_from.to_string();
+                            match reassembler.receive(&neighbour, &message.content, std::time::Instant::now()) {
+                                Ok(Some(payload)) => message.content = Bytes::from(payload),
+                                Ok(None) => continue,
+                                Err(e) => {
+                                    tracing::warn!("Dropping gossip fragment: {}", e);
+                                    continue;
+                                }
+                            }
+                        }
                         if tx.send(event).await.is_err() {
                             tracing::debug!("Gossip event receiver dropped");
                             break;
@@ -402,22 +749,22 @@ impl IrohCtx {
         Ok(rx)
     }
     
-    /// Subscribe to gossip topic for lobby (stub implementation)
+    /// Subscribe to the loopback lobby of a board size, receiving each
+    /// CBOR-encoded advert
     #[tracing::instrument(level = "debug", skip(self))]
     #[cfg(not(feature = "iroh"))]
-    pub async fn subscribe_lobby(&self, board_size: u8) -> Result<mpsc::Receiver<()>> {
-        tracing::debug!("Mock subscribe to lobby for board size: {}", board_size);
-        let (_, rx) = mpsc::channel(1);
-        Ok(rx)
+    pub async fn subscribe_lobby(&self, board_size: u8) -> Result<mpsc::Receiver<Vec<u8>>> {
+        tracing::debug!("Loopback subscribe to lobby for board size: {}", board_size);
+        Ok(loopback::subscribe(&Self::lobby_topic(board_size), 100))
     }
     
-    /// Subscribe to gossip topic for a specific game (stub implementation)
+    /// Subscribe to the loopback topic of a specific game, receiving each
+    /// CBOR-encoded move record
     #[tracing::instrument(level = "debug", skip(self))]
     #[cfg(not(feature = "iroh"))]
-    pub async fn subscribe_game_topic(&self, game_id: &str, _buffer_size: usize) -> Result<mpsc::Receiver<()>> {
-        tracing::debug!("Mock subscribe to game topic for: {}", game_id);
-        let (_, rx) = mpsc::channel(1);
-        Ok(rx)
+    pub async fn subscribe_game_topic(&self, game_id: &str, buffer_size: usize) -> Result<mpsc::Receiver<Vec<u8>>> {
+        tracing::debug!("Loopback subscribe to game topic for: {}", game_id);
+        Ok(loopback::subscribe(&Self::game_topic(game_id), buffer_size))
     }
     
     /// Broadcast a message to a specific gossip topic
@@ -431,60 +778,116 @@ impl IrohCtx {
         let gossip_topic = self.gossip.subscribe(topic_id, bootstrap_peers)
             .context("Failed to subscribe to gossip topic for broadcasting")?;
         
-        let message = Bytes::copy_from_slice(data);
-        
-        // Broadcast the message using the topic subscription
-        gossip_topic.broadcast(message)
-            .await
-            .context("Failed to broadcast message to gossip topic")?;
+        // Broadcast the message, or its fragments, using the topic subscription
+        let messages = fragment::split(data, self.node_id(), &self.gossip_limits)?;
+        if messages.len() > 1 {
+            tracing::debug!("Fragmenting {} bytes into {} gossip messages", data.len(), messages.len());
+        }
+        for message in messages {
+            let len = message.len();
+            gossip_topic.broadcast(Bytes::from(message))
+                .await
+                .context("Failed to broadcast message to gossip topic")?;
+            traffic::record(TrafficCategory::Gossip, len);
+        }
         
         tracing::info!("Successfully broadcast {} bytes to gossip topic", data.len());
         Ok(())
     }
     
-    /// Broadcast a message to a specific gossip topic (stub implementation)
+    /// Broadcast a message to a loopback topic
     #[tracing::instrument(level = "debug", skip(self, data))]
     #[cfg(not(feature = "iroh"))]
-    pub async fn broadcast_to_topic(&self, _topic_id: (), data: &[u8]) -> Result<()> {
-        tracing::debug!("Mock broadcast {} bytes to topic", data.len());
+    pub async fn broadcast_to_topic(&self, topic: String, data: &[u8]) -> Result<()> {
+        let messages = fragment::split(data, self.node_id(), &self.gossip_limits)?;
+        tracing::debug!("Loopback broadcast {} bytes to {} in {} messages", data.len(), topic, messages.len());
+        for message in messages {
+            match (&self.transport.relay, self.transport.direct) {
+                (Some(relay), false) => {
+                    loopback::publish_via(relay, &topic, &self.my_id, message)?;
+                }
+                _ => {
+                    loopback::publish(&topic, &self.my_id, message);
+                }
+            }
+        }
         Ok(())
     }
     
     /// Publish game advertisement to gossip
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn advertise_game(&self, game_id: &str, board_size: u8) -> Result<()> {
-        #[cfg(feature = "iroh")]
-        {
-            tracing::debug!("Advertising game {} for board size {}", game_id, board_size);
-            
-            // Create game advertisement
-            let advert = GameAdvert {
-                gid: game_id.to_string(),
-                size: board_size,
-                host: self.my_id.clone(),
-                bot: false, // Assume human player for now
-            };
-            
-            // Serialize to CBOR
-            let cbor_data = serde_cbor::to_vec(&advert)
-                .context("Failed to serialize game advertisement")?;
-            
-            // Get the lobby topic for this board size
-            let topic = Self::lobby_topic(board_size);
-            
-            // Broadcast the advertisement
-            self.broadcast_to_topic(topic, &cbor_data).await
-                .context("Failed to broadcast game advertisement")?;
-            
-            tracing::info!("Successfully advertised game {} for board size {}", game_id, board_size);
-            Ok(())
-        }
-        
-        #[cfg(not(feature = "iroh"))]
-        {
-            tracing::debug!("Mock advertise game {} for board size {}", game_id, board_size);
-            Ok(())
-        }
+        self.advertise_game_with(game_id, board_size, false, GameTerms::standard(board_size)).await
+    }
+    
+    /// Advertise a game with its terms, saying whether it is a correspondence game
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn advertise_game_with(&self, game_id: &str, board_size: u8, correspondence: bool, terms: GameTerms) -> Result<()> {
+        tracing::debug!("Advertising game {} for board size {} (correspondence: {}, {})", game_id, board_size, correspondence, terms);
+        let advert = GameAdvert {
+            gid: game_id.to_string(),
+            size: board_size,
+            host: self.my_id.clone(),
+            bot: None,
+            correspondence,
+            teacher: None,
+            terms: Some(terms),
+            posted: crate::relay_robustness::now_secs(),
+            state: None,
+        };
+        self.broadcast_to_topic(Self::lobby_topic(board_size), &advert.encode()?).await?;
+        tracing::info!("Advertised game {} for board size {}", game_id, board_size);
+        Ok(())
+    }
+    
+    /// Adverts gossiped on the lobby of `board_size` from now on, each
+    /// with the node that delivered it
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn lobby_adverts(&self, board_size: u8) -> Result<mpsc::Receiver<(String, Vec<u8>)>> {
+        self.topic_messages(Self::lobby_topic(board_size)).await
+    }
+    
+    /// Moves gossiped for `game_id` from now on, each with the node that
+    /// delivered it
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn game_messages(&self, game_id: &str) -> Result<mpsc::Receiver<(String, Vec<u8>)>> {
+        self.topic_messages(Self::game_topic(game_id)).await
+    }
+    
+    /// Join requests and answers gossiped for `game_id` from now on, each
+    /// with the node that delivered it
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn join_messages(&self, game_id: &str) -> Result<mpsc::Receiver<(String, Vec<u8>)>> {
+        self.topic_messages(Self::join_topic(game_id)).await
+    }
+    
+    /// Gossip a join request or answer for `game_id`
+    pub async fn send_join(&self, game_id: &str, data: &[u8]) -> Result<()> {
+        self.broadcast_to_topic(Self::join_topic(game_id), data).await
+    }
+    
+    /// Messages gossiped on `topic` from now on, with the node that delivered each
+    #[cfg(feature = "iroh")]
+    async fn topic_messages(&self, topic: TopicId) -> Result<mpsc::Receiver<(String, Vec<u8>)>> {
+        let mut events = self.subscribe_gossip_topic(topic, 32).await?;
+        let (tx, rx) = mpsc::channel(32);
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let iroh_gossip::net::Event::Gossip(iroh_gossip::net::GossipEvent::Received(message)) = event else {
+                    continue;
+                };
+                if tx.send((message.delivered_from.to_string(), message.content.to_vec())).await.is_err() {
+                    return;
+                }
+            }
+        });
+        Ok(rx)
+    }
+    
+    /// Messages gossiped on `topic` from now on, with the node that sent each
+    #[cfg(not(feature = "iroh"))]
+    async fn topic_messages(&self, topic: String) -> Result<mpsc::Receiver<(String, Vec<u8>)>> {
+        Ok(loopback::subscribe_with_senders(&topic, 100))
     }
     
     /// Broadcast a move to a specific game topic
@@ -493,8 +896,10 @@ impl IrohCtx {
         #[cfg(feature = "iroh")]
         {
             // Serialize the move record first
-            let bytes = serde_cbor::to_vec(move_record)?;
-            ensure!(bytes.len() <= 1024, "Move record size exceeds 1KB limit: {}", bytes.len());
+            let bytes = serde_cbor::to_vec(move_record).context("Failed to encode move record")?;
+            if bytes.len() > 1024 {
+                return Err(Error::internal(format!("Move record size exceeds 1KB limit: {}", bytes.len())));
+            }
             
             let topic = Self::game_topic(game_id);
             
@@ -513,7 +918,13 @@ impl IrohCtx {
         
         #[cfg(not(feature = "iroh"))]
         {
-            tracing::debug!("Mock broadcast move for game {}", game_id);
+            let bytes = serde_cbor::to_vec(move_record).map_err(|e| Error::internal(format!("Failed to encode move record: {}", e)))?;
+            if bytes.len() > 1024 {
+                return Err(Error::internal(format!("Move record size exceeds 1KB limit: {}", bytes.len())));
+            }
+            self.broadcast_to_topic(Self::game_topic(game_id), &bytes).await?;
+            move_record.broadcast_hash = Some(*blake3::hash(&bytes).as_bytes());
+            tracing::debug!("Loopback broadcast move for game {}", game_id);
             Ok(())
         }
     }
@@ -537,7 +948,7 @@ impl IrohCtx {
             );
             
             // Serialize the move record for validation
-            let _value = serde_cbor::to_vec(move_record)?;
+            let _value = serde_cbor::to_vec(move_record).context("Failed to encode move record")?;
             
             tracing::debug!("Successfully stored move {} for game {} in docs", sequence, game_id);
             Ok(())
@@ -545,7 +956,8 @@ impl IrohCtx {
         
         #[cfg(not(feature = "iroh"))]
         {
-            tracing::debug!("Mock store game move {} for game {}", sequence, game_id);
+            self.moves.store_move(game_id, sequence, move_record).await;
+            tracing::debug!("Stored move {} for game {}", sequence, game_id);
             Ok(())
         }
     }
@@ -562,7 +974,7 @@ impl IrohCtx {
             );
             
             // Serialize the score for validation
-            let _value = serde_cbor::to_vec(&score)?;
+            let _value = serde_cbor::to_vec(&score).context("Failed to encode score")?;
             
             tracing::debug!("Score acceptance stored for player {} in game {}", player_id, game_id);
             Ok(())
@@ -624,7 +1036,7 @@ impl IrohCtx {
     /// Get the node address
     #[cfg(feature = "iroh")]
     pub async fn node_addr(&self) -> Result<NodeAddr> {
-        self.endpoint.node_addr().await
+        Ok(self.endpoint.node_addr().await?)
     }
     
     /// Accept an incoming connection (for use by application layer)
@@ -634,6 +1046,18 @@ impl IrohCtx {
         rx.recv().await
     }
     
+    /// Whether traffic with the peer on `connection` currently goes through a relay server
+    #[cfg(feature = "iroh")]
+    pub fn is_relayed(&self, connection: &Connection) -> bool {
+        let Ok(node_id) = connection.remote_node_id() else {
+            return false;
+        };
+        matches!(
+            self.endpoint.remote_info(node_id).map(|info| info.conn_type),
+            Some(iroh::endpoint::ConnectionType::Relay(_))
+        )
+    }
+    
     /// Get a reference to the router (for shutdown, etc.)
     #[cfg(feature = "iroh")]
     pub fn router(&self) -> &Router {
@@ -659,7 +1083,8 @@ impl IrohCtx {
         
         #[cfg(not(feature = "iroh"))]
         {
-            tracing::debug!("Mock store move tag {:?} for game {} sequence {}", tag, game_id, sequence);
+            self.moves.tag_move(game_id, sequence, tag).await?;
+            tracing::debug!("Stored tag {:?} for move {} in game {}", tag, sequence, game_id);
             Ok(())
         }
     }
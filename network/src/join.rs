@@ -0,0 +1,185 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Join-request protocol: the host decides who gets the opponent seat.
+//!
+//! A joiner sends a [`JoinRequest`] with its [`PlayerProfile`]; the host
+//! answers with a [`JoinResponse`], either by hand or through its
+//! [`JoinPolicy`]. An accepted joiner also gets the game's settings, so it
+//! builds the host's board rather than its own default. Once both seats
+//! are taken, further requests are offered to spectate instead.
+//!
+//! Between nodes both travel as [`JoinMessage`]s on the game's join topic.
+
+use std::collections::HashMap;
+use serde::{Serialize, Deserialize};
+use tokio::sync::oneshot;
+use p2pgo_core::Color;
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::cbor::migrate::{self, Versioned};
+use crate::rating::RatingEstimate;
+use crate::{sanitize, Error, GameId, Result};
+
+/// How long a joiner waits for the host before giving up
+pub const JOIN_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Why a request from the host's own node is declined
+pub const SELF_JOIN_REASON: &str = "This is your own game";
+
+/// Why a request for a shared board is offered spectating
+pub const BROADCAST_REASON: &str = "This board is shared for watching only";
+
+/// Who a player is, as shown to the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    /// Node ID of the player's endpoint
+    pub node_id: String,
+    /// Display name
+    pub name: String,
+    /// Guild the player belongs to, if any
+    pub guild: Option<String>,
+    /// The player's own rating estimate, unverified
+    #[serde(default)]
+    pub self_reported_rating: Option<RatingEstimate>,
+}
+
+/// Request to take the opponent seat in a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinRequest {
+    /// Identifier the host uses to answer this request
+    pub request_id: u64,
+    /// Who is asking
+    pub profile: PlayerProfile,
+}
+
+/// Host's answer to a join request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JoinResponse {
+    /// The joiner plays with this color, in a game set up like this
+    Accepted { color: Color, settings: GameSettings },
+    /// The host said no
+    Declined { reason: String },
+    /// Both seats are taken; the joiner may watch instead
+    SpectateOffered { reason: String },
+}
+
+/// Join traffic between a joiner and a remote host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JoinMessage {
+    /// A joiner asks for the opponent seat; `token` picks out the answer
+    Request {
+        game_id: GameId,
+        token: u64,
+        profile: PlayerProfile,
+    },
+    /// The host's answer to the request `token` from the node `to`
+    Response {
+        game_id: GameId,
+        to: String,
+        token: u64,
+        response: JoinResponse,
+    },
+}
+
+impl Versioned for JoinMessage {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "join message";
+}
+
+impl JoinMessage {
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        serde_cbor::to_vec(self).map_err(|e| Error::internal(format!("Failed to serialize join message: {}", e)))
+    }
+
+    /// Read a join message from a peer, refusing ones that don't sanitize
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let msg: Self = migrate::from_slice(bytes).map_err(|e| Error::malformed("join message", e))?;
+        sanitize::check_join(&msg)?;
+        Ok(msg)
+    }
+}
+
+/// Host setting for answering join requests without asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum JoinPolicy {
+    /// Ask the host every time
+    #[default]
+    Manual,
+    /// Accept players from the host's guild, ask for anyone else
+    GuildMembers,
+    /// Accept the first player to ask
+    Everyone,
+}
+
+/// Seat bookkeeping for one game
+#[derive(Debug)]
+pub(crate) struct GameSeats {
+    /// Settings accepted joiners are told
+    pub settings: GameSettings,
+    /// Host profile; games without a host accept the first joiner
+    pub host: Option<PlayerProfile>,
+    /// Player holding the opponent seat
+    pub opponent: Option<PlayerProfile>,
+    pub policy: JoinPolicy,
+    /// The host plays both colors; everyone else watches
+    pub broadcast: bool,
+    /// Requests waiting for the host's decision
+    pub pending: HashMap<u64, (PlayerProfile, oneshot::Sender<JoinResponse>)>,
+}
+
+impl GameSeats {
+    pub fn new(settings: GameSettings) -> Self {
+        Self {
+            settings,
+            host: None,
+            opponent: None,
+            policy: JoinPolicy::default(),
+            broadcast: false,
+            pending: HashMap::new(),
+        }
+    }
+    
+    /// Acceptance into the seat of `color`
+    pub fn accepted(&self, color: Color) -> JoinResponse {
+        JoinResponse::Accepted { color, settings: self.settings }
+    }
+    
+    /// Color of `node_id` if it holds a seat
+    pub fn color_of(&self, node_id: &str) -> Option<Color> {
+        if self.host.as_ref().map(|p| p.node_id.as_str()) == Some(node_id) {
+            Some(Color::Black)
+        } else if self.opponent.as_ref().map(|p| p.node_id.as_str()) == Some(node_id) {
+            Some(Color::White)
+        } else {
+            None
+        }
+    }
+
+    /// Answer without the host if the seats or the policy decide it
+    pub fn auto_response(&self, profile: &PlayerProfile) -> Option<JoinResponse> {
+        // Our own ticket pasted back: we would play both colors
+        if matches!(&self.host, Some(host) if host.node_id == profile.node_id) {
+            return Some(JoinResponse::Declined { reason: SELF_JOIN_REASON.to_string() });
+        }
+        if self.broadcast {
+            return Some(JoinResponse::SpectateOffered { reason: BROADCAST_REASON.to_string() });
+        }
+        if let Some(color) = self.color_of(&profile.node_id) {
+            return Some(self.accepted(color));
+        }
+        if self.opponent.is_some() {
+            return Some(JoinResponse::SpectateOffered {
+                reason: "Game is full".to_string(),
+            });
+        }
+
+        let Some(host) = &self.host else {
+            return Some(self.accepted(Color::White));
+        };
+        let auto_accept = match self.policy {
+            JoinPolicy::Manual => false,
+            JoinPolicy::Everyone => true,
+            JoinPolicy::GuildMembers => host.guild.is_some() && host.guild == profile.guild,
+        };
+        auto_accept.then(|| self.accepted(Color::White))
+    }
+}
@@ -9,24 +9,61 @@
 
 #![deny(unsafe_code)]
 
+pub mod error;
 pub mod lobby;
+pub mod game_list;
+pub mod join;
+pub mod idle;
+pub mod correspondence;
+pub mod credits;
+pub mod rating;
+pub mod matchmaking;
+pub mod identity;
 pub mod game_channel;
+pub mod event_log;
+pub mod channel_metrics;
+pub mod channel_tuning;
+pub mod traffic;
+pub mod wire;
+pub mod sanitize;
+pub mod invite;
+pub mod rate_limit;
+pub mod admission;
+pub mod dedup;
+pub mod fragment;
+pub mod presence;
+pub mod timing_privacy;
+pub mod relay_robustness;
+pub mod relay_mode;
+pub mod relay_bench;
+pub mod clock;
+pub mod session_log;
 pub mod blob_store;
 pub mod iroh_endpoint;
+pub mod loopback;
 pub mod archive;
+pub mod training;
+pub mod tournament;
+pub mod snapshot;
 pub mod gossip_compat;
 pub mod crash_logger;
+pub mod logging;
+pub mod health;
+pub mod alerts;
+pub mod undo;
+pub mod bot;
 
 // Re-export key types for convenience
+pub use error::{Error, Result};
 pub use lobby::Lobby;
 pub use game_channel::GameChannel;
 pub use iroh_endpoint::IrohCtx;
 pub use archive::ArchiveManager;
+pub use identity::{Identity, IdentityManager};
 pub use crash_logger::{init_crash_logger, log_crash, get_crash_logger_stats};
 
 use std::fmt;
 use serde::{Serialize, Deserialize};
-use thiserror::Error;
 
 /// Unique identifier for a game session
 pub type GameId = String;
@@ -72,36 +109,12 @@ impl DummyIroh {
     }
     
     /// Mock method for connecting to the Iroh network
-    pub async fn connect(&self) -> Result<(), NetworkError> {
+    pub async fn connect(&self) -> Result<()> {
         // Just a placeholder
         Ok(())
     }
 }
 
-/// Errors that can occur in the network layer
-#[derive(Debug, Error)]
-pub enum NetworkError {
-    /// Failed to connect to a peer
-    #[error("Failed to connect to peer: {0}")]
-    ConnectionFailed(String),
-    
-    /// Failed to send data
-    #[error("Failed to send data: {0}")]
-    SendFailed(String),
-    
-    /// Failed to receive data
-    #[error("Failed to receive data: {0}")]
-    ReceiveFailed(String),
-    
-    /// Data serialization error
-    #[error("Serialization error: {0}")]
-    SerializationError(String),
-    
-    /// Game not found
-    #[error("Game not found: {0}")]
-    GameNotFound(uuid::Uuid),
-}
-
 #[cfg(any(test, feature = "headless"))]
 pub mod debug {
     use p2pgo_core::GameState;
@@ -2,17 +2,39 @@
 
 //! In-process lobby implementation for MVP.
 //!   * create_game / start_game / get_game_channel
+//!   * request_join / respond_to_join for host-approved opponents, sent
+//!     over the game's join topic when the host is another node
+//!   * abandon_game to forfeit and clean up games whose opponent vanished
 //!   * broadcast LobbyEvent via tokio::sync::broadcast
 
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
-use tokio::sync::{RwLock, broadcast};
-use anyhow::Result;
-use p2pgo_core::{GameState, Move};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::{RwLock, broadcast, oneshot};
+use crate::{Error, Result};
+use p2pgo_core::{EndReason, GameEvent, GameState, Move};
+use p2pgo_core::phase::GamePhase;
+use p2pgo_core::game_clock::OnDisconnect;
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::settings::{GameSettings, Rules, RULESET_VERSION};
 use crate::GameId;
-use crate::game_channel::GameChannel;
+use crate::matchmaking::TimeControl;
+use crate::game_channel::{ChannelRole, GameChannel};
+use crate::channel_tuning::ChannelTuning;
+use crate::archive::ArchiveManager;
+use crate::sanitize;
+use crate::game_list::{AdvertStore, GamesPage, GamesQuery, Ingest};
+use crate::relay_robustness::now_secs;
+use crate::join::{GameSeats, JoinMessage, JoinPolicy, JoinRequest, JoinResponse, PlayerProfile, JOIN_REQUEST_TIMEOUT};
+use crate::iroh_endpoint::IrohCtx;
+use crate::health::{self, Supervisor};
+use p2pgo_core::cbor::migrate::{self, Upgrade, Versioned};
 use serde::{Serialize, Deserialize};
 
+/// Version of [`GameAdvert`] sent today
+pub const ADVERT_VERSION: u32 = 1;
+
 /// Bot information for lobby advertisements
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BotInfo {
@@ -24,15 +46,252 @@ pub struct BotInfo {
 
 /// Game advertisement for iroh-gossip
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "GameAdvertRepr", from = "GameAdvertRepr")]
 pub struct GameAdvert {
     pub gid: GameId,
     pub size: u8,
     pub host: String, // NodeId as string
     pub bot: Option<BotInfo>,
+    /// Played at a move a day rather than live
+    pub correspondence: bool,
+    /// Node ID of the peer teaching the game, if it is a lesson
+    #[serde(default)]
+    pub teacher: Option<String>,
+    /// Left out by older peers; see [`GameAdvert::terms`]
+    #[serde(default)]
+    pub terms: Option<GameTerms>,
+    /// Unix seconds the host sent the advert at; 0 from older peers
+    #[serde(default)]
+    pub posted: u64,
+    /// Left out by older peers and for new games, which wait for an opponent
+    #[serde(default)]
+    pub state: Option<GameListingState>,
+}
+
+impl GameAdvert {
+    /// What the game is played with; standard terms for the board size
+    /// from peers that don't say
+    pub fn terms(&self) -> GameTerms {
+        self.terms.unwrap_or_else(|| GameTerms::standard(self.size))
+    }
+
+    /// How the advertised game is listed
+    pub fn info(&self) -> GameInfo {
+        GameInfo {
+            id: self.gid.clone(),
+            name: None,
+            board_size: self.size,
+            state: self.state.unwrap_or(GameListingState::OPEN),
+            needs_password: false,
+            correspondence: self.correspondence,
+            teacher: self.teacher.clone(),
+            terms: self.terms(),
+        }
+    }
+
+    /// The advert as sent: a versioned CBOR map, so fields added later can
+    /// be left out
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        serde_cbor::to_vec(self).map_err(|e| Error::internal(format!("Failed to serialize game advert: {}", e)))
+    }
+
+    /// Read an advert of any version, upgrading it to today's.
+    ///
+    /// Hosts from before CBOR adverts sent bincode, which doesn't start
+    /// with a CBOR map; those are refused as such rather than misread.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.first().is_none_or(|&first| first & 0xe0 != 0xa0) {
+            return Err(Error::internal("Game advert is not a CBOR map; the host may run a release from before CBOR adverts"));
+        }
+        let advert: Self = migrate::from_slice(bytes)
+            .map_err(|e| Error::internal(format!("Invalid game advert: {}", e)))?;
+        sanitize::check_advert(&advert)?;
+        Ok(advert)
+    }
+}
+
+impl Versioned for GameAdvert {
+    const VERSION: u32 = ADVERT_VERSION;
+    const NAME: &'static str = "game advert";
+}
+
+/// Every field any version of [`GameAdvert`] had
+#[derive(Serialize, Deserialize)]
+struct GameAdvertRepr {
+    #[serde(default)]
+    v: u32,
+    gid: GameId,
+    size: u8,
+    host: String,
+    bot: Option<BotInfo>,
+    correspondence: bool,
+    #[serde(default)]
+    teacher: Option<String>,
+    #[serde(default)]
+    terms: Option<GameTerms>,
+    #[serde(default)]
+    posted: u64,
+    #[serde(default)]
+    state: Option<GameListingState>,
+}
+
+const ADVERT_UPGRADES: [Upgrade<GameAdvertRepr>; ADVERT_VERSION as usize] = [
+    // v1 only added `v`; unversioned CBOR adverts read as they are
+    |_| {},
+];
+
+impl From<GameAdvert> for GameAdvertRepr {
+    fn from(advert: GameAdvert) -> Self {
+        Self {
+            v: ADVERT_VERSION,
+            gid: advert.gid,
+            size: advert.size,
+            host: advert.host,
+            bot: advert.bot,
+            correspondence: advert.correspondence,
+            teacher: advert.teacher,
+            terms: advert.terms,
+            posted: advert.posted,
+            state: advert.state,
+        }
+    }
+}
+
+impl From<GameAdvertRepr> for GameAdvert {
+    fn from(mut repr: GameAdvertRepr) -> Self {
+        let version = repr.v;
+        migrate::upgrade(&mut repr, version, &ADVERT_UPGRADES, GameAdvert::NAME);
+        Self {
+            gid: repr.gid,
+            size: repr.size,
+            host: repr.host,
+            bot: repr.bot,
+            correspondence: repr.correspondence,
+            teacher: repr.teacher,
+            terms: repr.terms,
+            posted: repr.posted,
+            state: repr.state,
+        }
+    }
+}
+
+/// What a game is played with, advertised so players see it before joining
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GameTerms {
+    pub komi: f32,
+    /// Black stones placed before the first move; 0 for an even game
+    pub handicap: u8,
+    pub rules: Rules,
+    /// The host's [`RULESET_VERSION`]
+    pub ruleset_version: u8,
+    /// `None` for untimed games
+    pub time_control: Option<TimeControl>,
+    /// What the clock does while a player is disconnected
+    #[serde(default)]
+    pub on_disconnect: OnDisconnect,
+    /// Whether the result counts towards ratings
+    pub rated: bool,
+    /// Rule variations moves are checked under
+    #[serde(default)]
+    pub rule_config: RuleConfig,
+}
+
+impl GameTerms {
+    /// An even, untimed and rated game with the usual komi
+    pub fn standard(board_size: u8) -> Self {
+        Self::from_settings(&GameSettings::standard(board_size))
+    }
+
+    /// Terms of a game set up with `settings`, untimed and rated
+    pub fn from_settings(settings: &GameSettings) -> Self {
+        Self {
+            komi: settings.komi,
+            handicap: settings.handicap,
+            rules: settings.rules,
+            ruleset_version: settings.ruleset_version(),
+            time_control: None,
+            on_disconnect: OnDisconnect::default(),
+            rated: true,
+            rule_config: settings.rule_config,
+        }
+    }
+
+    /// Refuse games under rules newer than ours
+    pub fn check_supported(&self) -> Result<()> {
+        if self.ruleset_version > RULESET_VERSION {
+            return Err(Error::UnsupportedRuleset { advertised: self.ruleset_version, supported: RULESET_VERSION });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GameTerms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "komi {}, {:?} rules", self.komi, self.rules)?;
+        if self.rule_config.suicide_allowed {
+            write!(f, ", suicide allowed")?;
+        }
+        if self.handicap > 0 {
+            write!(f, ", handicap {}", self.handicap)?;
+        }
+        match self.time_control {
+            Some(time_control) => write!(f, ", {}", time_control)?,
+            None => write!(f, ", untimed")?,
+        }
+        if self.time_control.is_some() && self.on_disconnect == OnDisconnect::KeepRunning {
+            write!(f, ", clock runs while away")?;
+        }
+        write!(f, ", {}", if self.rated { "rated" } else { "unrated" })
+    }
+}
+
+/// Where a listed game stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameListingState {
+    /// Players may still take `seats_open` seats
+    Waiting { seats_open: u8 },
+    /// Being played; spectators may watch
+    Active,
+    Finished,
+}
+
+impl GameListingState {
+    /// A new game, waiting for its opponent
+    pub const OPEN: GameListingState = GameListingState::Waiting { seats_open: 1 };
+
+    /// The listing of a game whose channel is in `phase`
+    pub fn of(phase: &GamePhase) -> Self {
+        match phase {
+            GamePhase::AwaitingOpponent => GameListingState::OPEN,
+            GamePhase::Active | GamePhase::Scoring => GameListingState::Active,
+            GamePhase::Finished { .. } => GameListingState::Finished,
+        }
+    }
+
+    /// Whether someone may still sit down to play
+    pub fn joinable(self) -> bool {
+        matches!(self, GameListingState::Waiting { seats_open } if seats_open > 0)
+    }
+
+    /// Whether there is a game to spectate
+    pub fn watchable(self) -> bool {
+        self == GameListingState::Active
+    }
+}
+
+impl fmt::Display for GameListingState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameListingState::Waiting { seats_open: 1 } => write!(f, "waiting, 1 seat open"),
+            GameListingState::Waiting { seats_open } => write!(f, "waiting, {} seats open", seats_open),
+            GameListingState::Active => write!(f, "in play"),
+            GameListingState::Finished => write!(f, "finished"),
+        }
+    }
 }
 
 /// Information about a game in the lobby
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct GameInfo {
     /// Unique identifier for the game
     pub id: GameId,
@@ -40,10 +299,15 @@ pub struct GameInfo {
     pub name: Option<String>,
     /// Board size
     pub board_size: u8,
-    /// Whether the game has started
-    pub started: bool,
+    /// Whether the game waits for players, is being played or is over
+    pub state: GameListingState,
     /// Whether the game needs a password to join
     pub needs_password: bool,
+    /// Whether moves have a per-move budget of days rather than a clock
+    pub correspondence: bool,
+    /// Node ID of the only peer who may annotate the game
+    pub teacher: Option<String>,
+    pub terms: GameTerms,
 }
 
 /// Events emitted by the lobby
@@ -55,6 +319,11 @@ pub enum LobbyEvent {
     GameStarted(GameId),
     /// A game was ended
     GameEnded(GameId),
+    /// A game started waiting for players, began or finished
+    GameStateChanged {
+        game_id: GameId,
+        state: GameListingState,
+    },
     /// A player joined a game
     PlayerJoined {
         /// Game ID
@@ -62,14 +331,35 @@ pub enum LobbyEvent {
         /// Player color
         color: p2pgo_core::Color,
     },
+    /// Someone asked the host for the opponent seat
+    JoinRequested {
+        /// Game ID
+        game_id: GameId,
+        /// The request to answer with `respond_to_join`
+        request: JoinRequest,
+    },
+    /// Adverts from other nodes were added or changed
+    AdvertsChanged,
 }
 
 /// Service for managing the game lobby
 pub struct Lobby {
     /// Currently available games
     games: Arc<RwLock<HashMap<GameId, GameInfo>>>,
+    /// Games advertised by other nodes
+    adverts: Arc<RwLock<AdvertStore>>,
     /// Game channels
     channels: Arc<RwLock<HashMap<GameId, Arc<GameChannel>>>>,
+    /// Seats and pending join requests per game
+    seats: Arc<RwLock<HashMap<GameId, GameSeats>>>,
+    /// Source of join request IDs
+    next_request_id: Arc<AtomicU64>,
+    /// ACK watchdog settings for every game, instead of ones to suit each
+    tuning_override: Arc<RwLock<Option<ChannelTuning>>>,
+    /// How other nodes are reached, once set
+    transport: Arc<RwLock<Option<Arc<IrohCtx>>>>,
+    /// Restarts the games' background tasks, once set
+    supervisor: Arc<RwLock<Option<Supervisor>>>,
     /// Lobby event broadcaster
     events_tx: broadcast::Sender<LobbyEvent>,
     /// Keep a receiver alive to prevent channel closure
@@ -82,6 +372,25 @@ impl Default for Lobby {
     }
 }
 
+/// Clones share the games, channels and events of the original, like
+/// nodes on one gossip network
+impl Clone for Lobby {
+    fn clone(&self) -> Self {
+        Self {
+            games: self.games.clone(),
+            adverts: self.adverts.clone(),
+            channels: self.channels.clone(),
+            seats: self.seats.clone(),
+            next_request_id: self.next_request_id.clone(),
+            tuning_override: self.tuning_override.clone(),
+            transport: self.transport.clone(),
+            supervisor: self.supervisor.clone(),
+            events_tx: self.events_tx.clone(),
+            _events_rx: self.events_tx.subscribe(),
+        }
+    }
+}
+
 impl Lobby {
     /// Create a new lobby service
     pub fn new() -> Self {
@@ -93,7 +402,13 @@ impl Lobby {
         
         Self {
             games: Arc::new(RwLock::new(HashMap::new())),
+            adverts: Arc::new(RwLock::new(AdvertStore::new())),
             channels: Arc::new(RwLock::new(HashMap::new())),
+            seats: Arc::new(RwLock::new(HashMap::new())),
+            next_request_id: Arc::new(AtomicU64::new(1)),
+            tuning_override: Arc::new(RwLock::new(None)),
+            transport: Arc::new(RwLock::new(None)),
+            supervisor: Arc::new(RwLock::new(None)),
             events_tx,
             _events_rx: events_rx,
         }
@@ -104,36 +419,95 @@ impl Lobby {
         self.events_tx.subscribe()
     }
     
+    /// Reach other nodes through `ctx`, for this lobby and its clones.
+    ///
+    /// Join requests for advertised games go to their hosts, and games
+    /// created from now on take requests from other nodes.
+    pub async fn set_transport(&self, ctx: Arc<IrohCtx>) {
+        *self.transport.write().await = Some(ctx);
+    }
+    
+    /// Run the background tasks of games created from now on under
+    /// `supervisor`, for this lobby and its clones
+    pub async fn set_supervisor(&self, supervisor: Supervisor) {
+        *self.supervisor.write().await = Some(supervisor);
+    }
+    
     /// Create a new game in the lobby
     pub async fn create_game(&self, name: Option<String>, board_size: u8, needs_password: bool) -> Result<GameId> {
-        let _span = tracing::info_span!("network.lobby", "Lobby::create_game").entered();
-        
-        // Generate a unique game ID
-        let game_id = format!("game-{}", uuid::Uuid::new_v4());
-        
+        self.create_game_with_id(Self::new_game_id(), name, board_size, needs_password).await
+    }
+    
+    /// A fresh, unique game ID
+    pub fn new_game_id() -> GameId {
+        format!("game-{}", uuid::Uuid::new_v4())
+    }
+    
+    /// Create a game under an ID agreed on elsewhere, e.g. by matchmaking
+    pub async fn create_game_with_id(&self, game_id: GameId, name: Option<String>, board_size: u8, needs_password: bool) -> Result<GameId> {
         // Create initial game state with default board size 9 if None
         let board_size = if board_size == 0 { 9 } else { board_size };
-        let initial_state = GameState::new(board_size);
+        sanitize::check_listing(&game_id, name.as_deref(), board_size)?;
+        self.create_game_from_position(game_id, name, GameState::new(board_size), GameSettings::standard(board_size), needs_password).await
+    }
+    
+    /// Create a game that starts from `initial_state`, e.g. an editor setup,
+    /// played with `settings`
+    pub async fn create_game_from_position(&self, game_id: GameId, name: Option<String>, initial_state: GameState, settings: GameSettings, needs_password: bool) -> Result<GameId> {
+        self.create(game_id, name, initial_state, settings, needs_password, ChannelRole::Player).await
+    }
+    
+    /// Share a game played locally, such as a hot-seat one, for watching.
+    ///
+    /// Every join request is offered spectating, and moves from peers are
+    /// refused; the host's moves for both colors go out to spectators.
+    pub async fn create_broadcast(&self, game_id: GameId, name: Option<String>, state: GameState) -> Result<GameId> {
+        let settings = GameSettings::standard(state.board_size);
+        self.create(game_id, name, state, settings, false, ChannelRole::Broadcast).await
+    }
+    
+    #[tracing::instrument(name = "Lobby::create_game", skip_all)]
+    async fn create(&self, game_id: GameId, name: Option<String>, mut initial_state: GameState, settings: GameSettings, needs_password: bool, role: ChannelRole) -> Result<GameId> {
+        if self.games.read().await.contains_key(&game_id) {
+            return Err(Error::GameExists(game_id));
+        }
+        let board_size = initial_state.board_size;
+        initial_state.rule_config = settings.rule_config;
+        
+        // Create a game channel
+        let tuning = self.tuning_override.read().await.unwrap_or_default();
+        let channel = self.connected(GameChannel::new(game_id.clone(), initial_state).with_settings(settings).with_role(role).with_tuning(tuning)).await?;
         
-        // Create game info
+        // Broadcasts are watched, never joined
+        let state = match role {
+            ChannelRole::Player => GameListingState::OPEN,
+            ChannelRole::Broadcast => GameListingState::Active,
+        };
         let game_info = GameInfo {
             id: game_id.clone(),
             name,
             board_size,
-            started: false,
+            state,
             needs_password,
+            correspondence: false,
+            teacher: None,
+            terms: GameTerms::from_settings(&settings),
         };
         
-        // Create a game channel
-        let channel = Arc::new(GameChannel::new(game_id.clone(), initial_state));
-        
         // Add to local games map and channels
         {
             let mut games = self.games.write().await;
             games.insert(game_id.clone(), game_info.clone());
             
+            self.follow_phase(game_id.clone(), &channel);
+            watch_acks(&game_id, &channel, self.supervisor.read().await.as_ref());
             let mut channels = self.channels.write().await;
             channels.insert(game_id.clone(), channel);
+            
+            let mut seats = self.seats.write().await;
+            let mut game_seats = GameSeats::new(settings);
+            game_seats.broadcast = role == ChannelRole::Broadcast;
+            seats.insert(game_id.clone(), game_seats);
         }
         
         // Broadcast the game created event
@@ -146,24 +520,92 @@ impl Lobby {
             "Broadcasting lobby event"
         );
         self.events_tx.send(event)
-            .map_err(|e| anyhow::anyhow!("Failed to broadcast game created event: {}", e))?;
+            .map_err(|e| Error::internal(format!("Failed to broadcast game created event: {}", e)))?;
         
+        if let Some(ctx) = self.transport.read().await.clone() {
+            self.serve_joins(game_id.clone(), ctx).await;
+        }
         Ok(game_id)
     }
     
+    /// `channel`, reaching its peers through our transport once one is set
+    async fn connected(&self, channel: GameChannel) -> Result<Arc<GameChannel>> {
+        let channel = match self.supervisor.read().await.clone() {
+            Some(supervisor) => channel.with_supervisor(supervisor),
+            None => channel,
+        };
+        let channel = match self.transport.read().await.clone() {
+            Some(ctx) => channel.connect(ctx).await?,
+            None => channel,
+        };
+        Ok(Arc::new(channel))
+    }
+    
+    /// Answer join requests other nodes gossip for a game we host, for as
+    /// long as the game is around, listening again should the listener die
+    async fn serve_joins(&self, game_id: GameId, ctx: Arc<IrohCtx>) {
+        // The first run takes this subscription, so requests sent as soon
+        // as the game is listed are heard
+        let subscribed = match ctx.join_messages(&game_id).await {
+            Ok(messages) => std::sync::Mutex::new(Some(messages)),
+            Err(e) => {
+                tracing::warn!(game_id = %game_id, "Failed to listen for join requests: {}", e);
+                return;
+            }
+        };
+        let lobby = self.clone();
+        let name = join_listener_name(&game_id);
+        health::supervise(self.supervisor.read().await.as_ref(), &name, move |heartbeat| {
+            let (lobby, ctx, game_id) = (lobby.clone(), ctx.clone(), game_id.clone());
+            let subscribed = subscribed.lock().unwrap().take();
+            async move {
+                let mut messages = match subscribed {
+                    Some(messages) => messages,
+                    None => match ctx.join_messages(&game_id).await {
+                        Ok(messages) => messages,
+                        Err(e) => {
+                            tracing::warn!(game_id = %game_id, "Failed to listen for join requests: {}", e);
+                            return;
+                        }
+                    },
+                };
+                while let Some((from, bytes)) = heartbeat.waiting(messages.recv()).await {
+                    if !lobby.games.read().await.contains_key(&game_id) {
+                        return;
+                    }
+                    let (token, profile) = match JoinMessage::decode(&bytes) {
+                        Ok(JoinMessage::Request { game_id: requested, token, profile }) if requested == game_id => (token, profile),
+                        Ok(_) => continue,
+                        Err(e) => {
+                            tracing::debug!(game_id = %game_id, %from, "Dropped join message: {}", e);
+                            continue;
+                        }
+                    };
+                    // The host may take its time; keep serving others meanwhile
+                    let (lobby, ctx, game_id) = (lobby.clone(), ctx.clone(), game_id.clone());
+                    tokio::spawn(async move {
+                        let to = profile.node_id.clone();
+                        let response = lobby.request_join(&game_id, profile).await
+                            .unwrap_or_else(|e| JoinResponse::Declined { reason: e.to_string() });
+                        let answer = JoinMessage::Response { game_id: game_id.clone(), to, token, response };
+                        let sent = match answer.encode() {
+                            Ok(bytes) => ctx.send_join(&game_id, &bytes).await,
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = sent {
+                            tracing::warn!(game_id = %game_id, "Failed to answer join request: {}", e);
+                        }
+                    });
+                }
+            }
+        });
+    }
+    
     /// Start a game
+    #[tracing::instrument(name = "Lobby::start_game", skip_all)]
     pub async fn start_game(&self, game_id: &GameId) -> Result<()> {
-        let _span = tracing::info_span!("network.lobby", "Lobby::start_game").entered();
-        
-        // Update the game info
-        {
-            let mut games = self.games.write().await;
-            if let Some(game_info) = games.get_mut(game_id) {
-                game_info.started = true;
-            } else {
-                return Err(anyhow::anyhow!("Game not found: {}", game_id));
-            }
-        }
+        let channel = self.get_game_channel(game_id).await?;
+        channel.start().await;
         
         // Broadcast the game started event
         let event = LobbyEvent::GameStarted(game_id.clone());
@@ -173,17 +615,111 @@ impl Lobby {
             "Broadcasting lobby event"
         );
         self.events_tx.send(event)
-            .map_err(|e| anyhow::anyhow!("Failed to broadcast game started event: {}", e))?;
+            .map_err(|e| Error::internal(format!("Failed to broadcast game started event: {}", e)))?;
+        set_state(&self.games, &self.events_tx, game_id, GameListingState::Active).await;
         
         Ok(())
     }
     
+    /// Keep a game's listing in step with its channel's phase, for as long
+    /// as both are around
+    fn follow_phase(&self, game_id: GameId, channel: &Arc<GameChannel>) {
+        let mut events = channel.subscribe();
+        // Held weakly so that removing the game drops the channel
+        let channel = Arc::downgrade(channel);
+        let games = self.games.clone();
+        let events_tx = self.events_tx.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(GameEvent::PhaseChanged { .. }) => {}
+                    Ok(_) => continue,
+                    // The change may have been among the events missed
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+                // The phase now rather than the one announced, which the
+                // lobby may already have moved past
+                let Some(channel) = channel.upgrade() else {
+                    return;
+                };
+                let phase = channel.phase().await;
+                drop(channel);
+                if !set_state(&games, &events_tx, &game_id, GameListingState::of(&phase)).await {
+                    return;
+                }
+            }
+        });
+    }
+    
+    /// Make a game a correspondence game, or a live one again
+    pub async fn set_correspondence(&self, game_id: &GameId, on: bool) -> Result<()> {
+        {
+            let mut games = self.games.write().await;
+            let info = games.get_mut(game_id)
+                .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+            info.correspondence = on;
+        }
+        self.get_game_channel(game_id).await?.set_correspondence(on);
+        self.retune(game_id).await
+    }
+    
+    /// Advertise the clock a game is played with, and whether it is rated
+    pub async fn set_time_control(&self, game_id: &GameId, time_control: Option<TimeControl>, on_disconnect: OnDisconnect, rated: bool) -> Result<()> {
+        let mut games = self.games.write().await;
+        let info = games.get_mut(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+        info.terms.time_control = time_control;
+        info.terms.on_disconnect = on_disconnect;
+        info.terms.rated = rated;
+        drop(games);
+        if let Some(time_control) = time_control {
+            self.get_game_channel(game_id).await?.set_time_control(time_control.game_clock(), on_disconnect).await;
+        }
+        self.retune(game_id).await
+    }
+    
+    /// Chase unacknowledged moves in every game as `tuning` says, or as
+    /// suits each game's clock again when `None`
+    pub async fn set_channel_tuning(&self, tuning: Option<ChannelTuning>) {
+        *self.tuning_override.write().await = tuning;
+        let game_ids: Vec<GameId> = self.games.read().await.keys().cloned().collect();
+        for game_id in game_ids {
+            let _ = self.retune(&game_id).await;
+        }
+    }
+    
+    /// Apply the override, or the tuning derived from the game's terms
+    async fn retune(&self, game_id: &GameId) -> Result<()> {
+        let derived = {
+            let games = self.games.read().await;
+            let info = games.get(game_id)
+                .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+            ChannelTuning::for_game(info.terms.time_control.as_ref(), info.correspondence)
+        };
+        let tuning = self.tuning_override.read().await.unwrap_or(derived);
+        self.get_game_channel(game_id).await?.set_tuning(tuning);
+        Ok(())
+    }
+    
+    /// Name the peer teaching a game, or make it an ordinary game again
+    pub async fn set_teacher(&self, game_id: &GameId, teacher: Option<String>) -> Result<()> {
+        {
+            let mut games = self.games.write().await;
+            let info = games.get_mut(game_id)
+                .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+            info.teacher = teacher.clone();
+        }
+        self.get_game_channel(game_id).await?.set_teacher(teacher).await;
+        Ok(())
+    }
+    
     /// Get a game channel for a specific game
     pub async fn get_game_channel(&self, game_id: &GameId) -> Result<Arc<GameChannel>> {
         let channels = self.channels.read().await;
         channels.get(game_id)
             .cloned()
-            .ok_or_else(|| anyhow::anyhow!("Game not found: {}", game_id))
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))
     }
     
     /// Post a move to a game
@@ -198,9 +734,348 @@ impl Lobby {
         games.values().cloned().collect()
     }
     
+    /// Take in an advert the node `from` delivered
+    pub async fn ingest_advert(&self, advert: &GameAdvert, from: &str) -> Ingest {
+        let outcome = self.adverts.write().await.ingest(advert, from, now_secs());
+        match outcome {
+            Ingest::Added | Ingest::Refreshed => {
+                let _ = self.events_tx.send(LobbyEvent::AdvertsChanged);
+            }
+            refused => tracing::debug!(game_id = %advert.gid, host = %advert.host, %from, ?refused, "Dropped game advert"),
+        }
+        outcome
+    }
+    
+    /// Take in an advert gossiped to us by the node `from`, dropping it
+    /// unless it decodes and passes sanitizing
+    pub async fn ingest_gossiped(&self, from: &str, bytes: &[u8]) -> Option<Ingest> {
+        match GameAdvert::decode(bytes) {
+            Ok(advert) => Some(self.ingest_advert(&advert, from).await),
+            Err(e) => {
+                tracing::debug!(%from, "Dropped undecodable game advert: {}", e);
+                None
+            }
+        }
+    }
+    
+    /// Up to `limit` games matching `query` from `offset` on
+    ///
+    /// Our own games come first, by id, then the adverts of other nodes.
+    pub async fn games_page(&self, query: &GamesQuery, offset: usize, limit: usize) -> GamesPage {
+        let mut adverts = self.adverts.write().await;
+        adverts.expire(now_secs());
+        let games = self.games.read().await;
+        let mut local: Vec<&GameInfo> = games.values().filter(|info| query.matches(info)).collect();
+        local.sort_by(|a, b| a.id.cmp(&b.id));
+        query.sort(&mut local);
+        let remote = adverts.matching(query).into_iter().filter(|info| !games.contains_key(&info.id));
+        local.extend(remote);
+        GamesPage::slice(&local, offset, limit)
+    }
+    
+    /// Wait until at least one game is listed, or `timeout` elapses
+    pub async fn wait_for_game(&self, timeout: std::time::Duration) -> Option<GameInfo> {
+        // Subscribe before checking the list so a game created in between is not missed
+        let mut rx = self.subscribe();
+        if let Some(game) = self.list_games().await.into_iter().next() {
+            return Some(game);
+        }
+        
+        let wait = async {
+            loop {
+                match rx.recv().await {
+                    Ok(LobbyEvent::GameCreated(info)) => return Some(info),
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.unwrap_or(None)
+    }
+    
+    /// Wait until the game `game_id` is listed, or `timeout` elapses;
+    /// other games listed meanwhile are passed over
+    pub async fn wait_for_game_id(&self, game_id: &GameId, timeout: std::time::Duration) -> Option<GameInfo> {
+        let mut rx = self.subscribe();
+        if let Some(game) = self.list_games().await.into_iter().find(|game| game.id == *game_id) {
+            return Some(game);
+        }
+        
+        let wait = async {
+            loop {
+                match rx.recv().await {
+                    Ok(LobbyEvent::GameCreated(info)) if info.id == *game_id => return Some(info),
+                    Ok(_) => continue,
+                    // Missed events may have included ours
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(game) = self.list_games().await.into_iter().find(|game| game.id == *game_id) {
+                            return Some(game);
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        };
+        tokio::time::timeout(timeout, wait).await.unwrap_or(None)
+    }
+    
+    /// Register the host of a game and how join requests are answered
+    pub async fn set_host(&self, game_id: &GameId, host: PlayerProfile, policy: JoinPolicy) -> Result<()> {
+        let mut seats = self.seats.write().await;
+        let game = seats.get_mut(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+        game.host = Some(host);
+        game.policy = policy;
+        Ok(())
+    }
+    
+    /// Hold a hosted game's moves until an opponent takes its seat
+    pub async fn await_opponent(&self, game_id: &GameId) -> Result<()> {
+        let seated = self.seats.read().await.get(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?
+            .opponent.is_some();
+        if !seated {
+            self.get_game_channel(game_id).await?.await_opponent().await;
+        }
+        Ok(())
+    }
+    
+    /// Change how join requests for a game are answered
+    pub async fn set_join_policy(&self, game_id: &GameId, policy: JoinPolicy) -> Result<()> {
+        let mut seats = self.seats.write().await;
+        let game = seats.get_mut(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+        game.policy = policy;
+        Ok(())
+    }
+    
+    /// Profiles of the host and the opponent, as far as they are known
+    pub async fn seated_players(&self, game_id: &GameId) -> Result<(Option<PlayerProfile>, Option<PlayerProfile>)> {
+        let seats = self.seats.read().await;
+        let game = seats.get(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+        Ok((game.host.clone(), game.opponent.clone()))
+    }
+    
+    /// Ask for the opponent seat, waiting for the host unless the seats
+    /// or the host's policy already decide the answer.
+    ///
+    /// Games advertised by another node are asked for over the game's
+    /// join topic; once accepted, they are played here like our own.
+    pub async fn request_join(&self, game_id: &GameId, profile: PlayerProfile) -> Result<JoinResponse> {
+        sanitize::check_profile(&profile)?;
+        if let Some(info) = self.games.read().await.get(game_id) {
+            info.terms.check_supported()?;
+        }
+        let response_rx = {
+            let mut seats = self.seats.write().await;
+            let Some(game) = seats.get_mut(game_id) else {
+                drop(seats);
+                return self.request_remote_join(game_id, profile).await;
+            };
+            
+            if let Some(response) = game.auto_response(&profile) {
+                let seated = matches!(response, JoinResponse::Accepted { .. }) && game.color_of(&profile.node_id).is_none();
+                if seated {
+                    game.opponent = Some(profile);
+                }
+                drop(seats);
+                if seated {
+                    self.seat_opponent(game_id).await;
+                }
+                return Ok(response);
+            }
+            
+            let request_id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+            let (response_tx, response_rx) = oneshot::channel();
+            game.pending.insert(request_id, (profile.clone(), response_tx));
+            
+            tracing::debug!(
+                game_id = %game_id,
+                request_id = request_id,
+                player = %profile.name,
+                "Asking host to accept join request"
+            );
+            let _ = self.events_tx.send(LobbyEvent::JoinRequested {
+                game_id: game_id.clone(),
+                request: JoinRequest { request_id, profile },
+            });
+            response_rx
+        };
+        
+        match tokio::time::timeout(JOIN_REQUEST_TIMEOUT, response_rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Ok(JoinResponse::Declined { reason: "Game was closed".to_string() }),
+            Err(_) => Ok(JoinResponse::Declined { reason: "Host did not respond".to_string() }),
+        }
+    }
+    
+    /// Ask the host of an advertised game for the opponent seat over the
+    /// game's join topic
+    async fn request_remote_join(&self, game_id: &GameId, profile: PlayerProfile) -> Result<JoinResponse> {
+        let info = self.adverts.read().await.get(game_id).cloned();
+        let (Some(info), Some(ctx)) = (info, self.transport.read().await.clone()) else {
+            return Err(Error::GameNotFound(game_id.clone()));
+        };
+        info.terms.check_supported()?;
+        
+        // Listen before asking, so a quick answer isn't missed
+        let mut messages = ctx.join_messages(game_id).await?;
+        let mut token = [0u8; 8];
+        getrandom::getrandom(&mut token).map_err(|e| Error::internal(format!("No randomness for a join request: {}", e)))?;
+        let token = u64::from_le_bytes(token);
+        let node_id = profile.node_id.clone();
+        let request = JoinMessage::Request { game_id: game_id.clone(), token, profile: profile.clone() };
+        ctx.send_join(game_id, &request.encode()?).await?;
+        tracing::debug!(game_id = %game_id, player = %profile.name, "Asked remote host to accept join request");
+        
+        let answer = async {
+            while let Some((_, bytes)) = messages.recv().await {
+                if let Ok(JoinMessage::Response { to, token: answered, response, .. }) = JoinMessage::decode(&bytes) {
+                    if to == node_id && answered == token {
+                        return Some(response);
+                    }
+                }
+            }
+            None
+        };
+        let response = match tokio::time::timeout(JOIN_REQUEST_TIMEOUT, answer).await {
+            Ok(Some(response)) => response,
+            Ok(None) => return Ok(JoinResponse::Declined { reason: "Lost contact with the host".to_string() }),
+            Err(_) => return Ok(JoinResponse::Declined { reason: "Host did not respond".to_string() }),
+        };
+        if let JoinResponse::Accepted { settings, .. } = &response {
+            self.open_joined(info, *settings, profile).await?;
+        }
+        Ok(response)
+    }
+    
+    /// Play a remote host's game here once it accepted us into its
+    /// opponent seat
+    async fn open_joined(&self, mut info: GameInfo, settings: GameSettings, profile: PlayerProfile) -> Result<()> {
+        let game_id = info.id.clone();
+        let tuning = self.tuning_override.read().await.unwrap_or_default();
+        let channel = self.connected(GameChannel::new(game_id.clone(), settings.new_game()).with_settings(settings).with_tuning(tuning)).await?;
+        channel.start().await;
+        info.board_size = settings.board_size;
+        info.state = GameListingState::Active;
+        
+        let mut games = self.games.write().await;
+        if games.contains_key(&game_id) {
+            return Err(Error::GameExists(game_id));
+        }
+        games.insert(game_id.clone(), info);
+        self.follow_phase(game_id.clone(), &channel);
+        watch_acks(&game_id, &channel, self.supervisor.read().await.as_ref());
+        self.channels.write().await.insert(game_id.clone(), channel);
+        let mut seats = GameSeats::new(settings);
+        seats.opponent = Some(profile);
+        self.seats.write().await.insert(game_id, seats);
+        Ok(())
+    }
+    
+    /// Host's answer to a pending join request
+    pub async fn respond_to_join(&self, game_id: &GameId, request_id: u64, accept: bool, reason: Option<String>) -> Result<()> {
+        let mut seats = self.seats.write().await;
+        let game = seats.get_mut(game_id)
+            .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+        let (profile, response_tx) = game.pending.remove(&request_id)
+            .ok_or_else(|| Error::internal(format!("No pending join request {} for {}", request_id, game_id)))?;
+        
+        let response = if !accept {
+            JoinResponse::Declined { reason: reason.unwrap_or_else(|| "Host declined".to_string()) }
+        } else if game.opponent.is_some() {
+            // The host accepted someone else first
+            JoinResponse::SpectateOffered { reason: "Game is full".to_string() }
+        } else {
+            game.opponent = Some(profile);
+            game.accepted(p2pgo_core::Color::White)
+        };
+        let seated = matches!(response, JoinResponse::Accepted { .. });
+        drop(seats);
+        if seated {
+            self.seat_opponent(game_id).await;
+        }
+        
+        let _ = response_tx.send(response);
+        Ok(())
+    }
+    
+    /// Post a move on behalf of a seated player, checking it is their turn
+    pub async fn post_player_move(&self, game_id: &GameId, node_id: &str, mv: Move) -> Result<()> {
+        let color = {
+            let seats = self.seats.read().await;
+            seats.get(game_id)
+                .and_then(|game| game.color_of(node_id))
+                .ok_or_else(|| Error::NotAPlayer { node_id: node_id.to_string(), game_id: game_id.clone() })?
+        };
+        
+        let channel = self.get_game_channel(game_id).await?;
+        let to_move = channel.get_latest_state().await.map(|state| state.current_player);
+        if to_move != Some(color) {
+            return Err(Error::NotYourTurn(color));
+        }
+        channel.send_move(mv).await
+    }
+    
+    /// The opponent took its seat: play begins and the lobby hears of it
+    async fn seat_opponent(&self, game_id: &GameId) {
+        if let Ok(channel) = self.get_game_channel(game_id).await {
+            channel.start().await;
+        }
+        let _ = self.events_tx.send(LobbyEvent::PlayerJoined {
+            game_id: game_id.clone(),
+            color: p2pgo_core::Color::White,
+        });
+        set_state(&self.games, &self.events_tx, game_id, GameListingState::Active).await;
+    }
+    
+    /// End a game by forfeit against the player to move, who stopped responding.
+    ///
+    /// Announces `GameEnded` on the game channel, archives the final state if
+    /// an archive is given and removes the game, releasing its channel.
+    pub async fn abandon_game(&self, game_id: &GameId, reason: &str, archive: Option<&ArchiveManager>) -> Result<GameEvent> {
+        let channel = self.get_game_channel(game_id).await?;
+        let final_state = match channel.get_latest_state().await {
+            Some(state) => state,
+            None => {
+                let games = self.games.read().await;
+                let info = games.get(game_id)
+                    .ok_or_else(|| Error::GameNotFound(game_id.clone()))?;
+                GameState::new(info.board_size)
+            }
+        };
+        let winner = final_state.current_player.opposite();
+        let anomalies = channel.anomalies().await;
+        let clock_skews = channel.clock_skews().await;
+        
+        let event = GameEvent::GameEnded {
+            winner: Some(winner),
+            score_diff: 0.0,
+            reason: EndReason::Forfeit(reason.to_string()),
+            scores: None,
+        };
+        // Nobody may be listening any more, which is fine
+        let _ = channel.send_event(event.clone()).await;
+        drop(channel);
+        
+        if let Some(archive) = archive {
+            archive.archive_forfeit(game_id.clone(), final_state, winner, reason, anomalies, clock_skews).await?;
+        }
+        self.remove_game(game_id).await?;
+        
+        tracing::info!(game_id = %game_id, ?winner, reason, "Game abandoned");
+        Ok(event)
+    }
+    
     /// Remove a game from the lobby
+    #[tracing::instrument(name = "Lobby::remove_game", skip_all)]
     pub async fn remove_game(&self, game_id: &GameId) -> Result<()> {
-        let _span = tracing::info_span!("network.lobby", "Lobby::remove_game").entered();
+        // Stop its tasks first, so they aren't taken for dead when it goes
+        if let Some(supervisor) = self.supervisor.read().await.as_ref() {
+            supervisor.unregister(&join_listener_name(game_id));
+            supervisor.unregister(&ack_watchdog_name(game_id));
+        }
         
         // Remove from local maps
         {
@@ -209,6 +1084,10 @@ impl Lobby {
             
             let mut channels = self.channels.write().await;
             channels.remove(game_id);
+            
+            // Dropping pending requests tells their joiners the game is gone
+            let mut seats = self.seats.write().await;
+            seats.remove(game_id);
         }
         
         // Broadcast the game ended event
@@ -219,15 +1098,14 @@ impl Lobby {
             "Broadcasting lobby event"
         );
         self.events_tx.send(event)
-            .map_err(|e| anyhow::anyhow!("Failed to broadcast game ended event: {}", e))?;
+            .map_err(|e| Error::internal(format!("Failed to broadcast game ended event: {}", e)))?;
         
         Ok(())
     }
     
     /// Publish game advertisement via gossip
+    #[tracing::instrument(name = "Lobby::publish_game_advert", skip_all)]
     pub async fn publish_game_advert(&self, game_id: &GameId, host_node_id: &str, bot_info: Option<BotInfo>) -> Result<()> {
-        let _span = tracing::info_span!("network.lobby", "Lobby::publish_game_advert").entered();
-        
         // Get game info
         let game_info = {
             let games = self.games.read().await;
@@ -241,17 +1119,20 @@ impl Lobby {
                 size: info.board_size,
                 host: host_node_id.to_string(),
                 bot: bot_info,
+                correspondence: info.correspondence,
+                teacher: info.teacher.clone(),
+                terms: Some(info.terms),
+                posted: now_secs(),
+                state: Some(info.state),
             };
-            
-            // Serialize using bincode for gossip
-            let data = bincode::serialize(&advert)
-                .map_err(|e| anyhow::anyhow!("Failed to serialize game advert: {}", e))?;
+            let data = advert.encode()?;
             
             tracing::debug!(
                 game_id = %game_id,
                 host = %host_node_id,
                 board_size = info.board_size,
                 has_bot = has_bot,
+                correspondence = info.correspondence,
                 data_len = data.len(),
                 "Publishing game advertisement"
             );
@@ -261,7 +1142,7 @@ impl Lobby {
             
             Ok(())
         } else {
-            Err(anyhow::anyhow!("Game not found: {}", game_id))
+            Err(Error::GameNotFound(game_id.clone()))
         }
     }
     
@@ -280,6 +1161,58 @@ impl Lobby {
     }
 }
 
+/// Set a listed game's state, announcing the change if there is one;
+/// false if the game is no longer listed
+async fn set_state(
+    games: &RwLock<HashMap<GameId, GameInfo>>,
+    events_tx: &broadcast::Sender<LobbyEvent>,
+    game_id: &GameId,
+    state: GameListingState,
+) -> bool {
+    let mut games = games.write().await;
+    let Some(info) = games.get_mut(game_id) else {
+        return false;
+    };
+    if info.state != state {
+        info.state = state;
+        tracing::debug!(game_id = %game_id, %state, "Game listing state changed");
+        let _ = events_tx.send(LobbyEvent::GameStateChanged { game_id: game_id.clone(), state });
+    }
+    true
+}
+
+/// Name a hosted game's join request listener is supervised under
+fn join_listener_name(game_id: &str) -> String {
+    format!("join request listener for game {}", game_id)
+}
+
+/// Name a channel's ACK watchdog is supervised under
+fn ack_watchdog_name(game_id: &str) -> String {
+    format!("ACK watchdog for game {}", game_id)
+}
+
+/// Run a channel's ACK watchdog at its check interval until the channel is
+/// dropped
+fn watch_acks(game_id: &str, channel: &Arc<GameChannel>, supervisor: Option<&Supervisor>) {
+    let name = ack_watchdog_name(game_id);
+    let channel = Arc::downgrade(channel);
+    health::supervise(supervisor, &name, move |heartbeat| {
+        let channel = channel.clone();
+        async move {
+            loop {
+                let Some(interval) = channel.upgrade().map(|channel| channel.tuning().check_interval()) else {
+                    return;
+                };
+                heartbeat.waiting(tokio::time::sleep(interval)).await;
+                let Some(channel) = channel.upgrade() else {
+                    return;
+                };
+                channel.check_acks().await;
+            }
+        }
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -303,7 +1236,7 @@ mod tests {
         assert_eq!(games[0].id, game_id);
         assert_eq!(games[0].name, Some("Test Game".to_string()));
         assert_eq!(games[0].board_size, 9);
-        assert!(!games[0].started);
+        assert_eq!(games[0].state, GameListingState::OPEN);
     }
     
     #[tokio::test]
@@ -341,7 +1274,27 @@ mod tests {
         
         // Check the game is marked as started
         let games = lobby.list_games().await;
-        assert!(games[0].started);
+        assert_eq!(games[0].state, GameListingState::Active);
+    }
+    
+    #[tokio::test]
+    async fn test_lobby_wait_for_game() {
+        let lobby = Lobby::new();
+        
+        // Nothing advertised: the wait should time out
+        let none = lobby.wait_for_game(std::time::Duration::from_millis(50)).await;
+        assert!(none.is_none());
+        
+        // A game created while waiting should be returned promptly.
+        // create_game holds an entered span, so run it on this task rather than spawning
+        let (game, _) = tokio::join!(
+            lobby.wait_for_game(std::time::Duration::from_secs(5)),
+            async {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                lobby.create_game(None, 9, false).await.unwrap();
+            }
+        );
+        assert_eq!(game.map(|g| g.board_size), Some(9));
     }
     
     #[tokio::test]
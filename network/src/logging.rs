@@ -0,0 +1,426 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Logging shared by the app, the CLI and the relay.
+//!
+//! One `tracing` subscriber writes to stderr or to daily log files, filtered
+//! by directives such as `info,p2pgo_network::game_channel=trace` that can be
+//! changed while running. A trace capture writes the next minute at trace
+//! level to a separate, bounded file for attaching to bug reports.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriter};
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+use crate::error::{Result, StorageContext};
+
+/// Prefix of the log files, e.g. `p2pgo.2024-05-01.log`
+pub const LOG_FILE_PREFIX: &str = "p2pgo";
+
+/// Daily log files kept before the oldest is deleted
+pub const KEEP_LOG_FILES: usize = 5;
+
+/// How long the in-app trace capture runs
+pub const CAPTURE_DURATION: Duration = Duration::from_secs(60);
+
+/// Largest a trace capture file grows
+pub const CAPTURE_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// What a capture records: our crates at trace, dependencies at debug so
+/// transport chatter doesn't fill the file
+const CAPTURE_FILTER: &str = "debug,p2pgo=trace";
+
+/// Last line of a capture that hit its size limit
+pub const TRUNCATED_MARKER: &str = "[capture truncated: size limit reached]\n";
+
+/// Levels accepted in a directive, quietest first
+const LEVELS: [(&str, LevelFilter); 6] = [
+    ("off", LevelFilter::OFF),
+    ("error", LevelFilter::ERROR),
+    ("warn", LevelFilter::WARN),
+    ("info", LevelFilter::INFO),
+    ("debug", LevelFilter::DEBUG),
+    ("trace", LevelFilter::TRACE),
+];
+
+/// Why a log filter didn't parse
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FilterError {
+    /// Nothing but commas and spaces
+    #[error("The log filter is empty")]
+    Empty,
+
+    /// A level that isn't one of off, error, warn, info, debug or trace
+    #[error("Unknown log level {level:?} in {directive:?}; use off, error, warn, info, debug or trace")]
+    UnknownLevel { directive: String, level: String },
+
+    /// A module path with characters no Rust path has
+    #[error("Invalid module path {target:?} in {directive:?}")]
+    InvalidTarget { directive: String, target: String },
+}
+
+/// One directive: a level for everything, or for one module and its children
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Module path prefix, e.g. `p2pgo_network::game_channel`
+    pub target: Option<String>,
+    pub level: LevelFilter,
+}
+
+impl FromStr for Directive {
+    type Err = FilterError;
+
+    fn from_str(directive: &str) -> Result<Self, Self::Err> {
+        let (target, level) = match directive.split_once('=') {
+            Some((target, level)) => (Some(target.trim()), level.trim()),
+            None => (None, directive),
+        };
+        if let Some(target) = target {
+            let valid = target.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+            if target.is_empty() || !valid {
+                return Err(FilterError::InvalidTarget {
+                    directive: directive.to_string(),
+                    target: target.to_string(),
+                });
+            }
+        }
+        let level = LEVELS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(level))
+            .map(|(_, level)| *level)
+            .ok_or_else(|| FilterError::UnknownLevel {
+                directive: directive.to_string(),
+                level: level.to_string(),
+            })?;
+        Ok(Self { target: target.map(str::to_string), level })
+    }
+}
+
+impl fmt::Display for Directive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = LEVELS.iter()
+            .find(|(_, level)| *level == self.level)
+            .map_or("off", |(name, _)| name);
+        match &self.target {
+            Some(target) => write!(f, "{}={}", target, level),
+            None => f.write_str(level),
+        }
+    }
+}
+
+/// Comma-separated directives, e.g. `info,p2pgo_network::game_channel=trace`
+///
+/// The most specific directive matching a module wins; modules no directive
+/// names are off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct LogFilter {
+    directives: Vec<Directive>,
+}
+
+impl LogFilter {
+    /// Everything at `level`
+    pub fn level(level: LevelFilter) -> Self {
+        Self { directives: vec![Directive { target: None, level }] }
+    }
+
+    pub fn directives(&self) -> &[Directive] {
+        &self.directives
+    }
+
+    /// The filter as a `tracing` per-layer filter
+    pub fn targets(&self) -> Targets {
+        self.directives.iter().fold(Targets::new(), |targets, directive| match &directive.target {
+            Some(target) => targets.with_target(target.clone(), directive.level),
+            None => targets.with_default(directive.level),
+        })
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::level(LevelFilter::INFO)
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = FilterError;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let directives = filter.split(',')
+            .map(str::trim)
+            .filter(|directive| !directive.is_empty())
+            .map(Directive::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if directives.is_empty() {
+            return Err(FilterError::Empty);
+        }
+        Ok(Self { directives })
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, directive) in self.directives.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", directive)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<String> for LogFilter {
+    type Error = FilterError;
+
+    fn try_from(filter: String) -> Result<Self, Self::Error> {
+        filter.parse()
+    }
+}
+
+impl From<LogFilter> for String {
+    fn from(filter: LogFilter) -> Self {
+        filter.to_string()
+    }
+}
+
+/// A finished trace capture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureReport {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Whether it stopped early at the size limit
+    pub truncated: bool,
+}
+
+struct Capture {
+    /// `None` once the size limit is reached
+    file: Option<File>,
+    path: PathBuf,
+    written: u64,
+    max_bytes: u64,
+    until: Instant,
+}
+
+impl Capture {
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+        // Keep room for the marker so the file never passes `max_bytes`
+        let room = self.max_bytes.saturating_sub(TRUNCATED_MARKER.len() as u64);
+        if self.written + line.len() as u64 <= room {
+            file.write_all(line)?;
+            self.written += line.len() as u64;
+            return Ok(());
+        }
+        file.write_all(TRUNCATED_MARKER.as_bytes())?;
+        file.flush()?;
+        self.written += TRUNCATED_MARKER.len() as u64;
+        self.file = None;
+        Ok(())
+    }
+
+    fn report(self) -> CaptureReport {
+        CaptureReport { truncated: self.file.is_none(), path: self.path, bytes: self.written }
+    }
+}
+
+/// A bounded, timed log file, written by a `fmt` layer while running
+///
+/// Lines after the deadline are dropped, so a capture ends on time even if
+/// nobody calls [`Self::finish`] until later.
+#[derive(Clone, Default)]
+pub struct TraceCapture {
+    capture: Arc<Mutex<Option<Capture>>>,
+}
+
+impl TraceCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the next `duration` of logs to `path`, at most `max_bytes` of them
+    pub fn start(&self, path: &Path, duration: Duration, max_bytes: u64) -> Result<()> {
+        let mut capture = self.capture.lock().unwrap();
+        if capture.is_some() {
+            return Err(crate::Error::CaptureRunning);
+        }
+        let file = File::create(path).storage(format!("Failed to create {}", path.display()))?;
+        *capture = Some(Capture {
+            file: Some(file),
+            path: path.to_path_buf(),
+            written: 0,
+            max_bytes,
+            until: Instant::now() + duration,
+        });
+        Ok(())
+    }
+
+    /// Whether lines logged at `now` still go to the file
+    pub fn is_running(&self, now: Instant) -> bool {
+        matches!(&*self.capture.lock().unwrap(), Some(capture) if capture.file.is_some() && now < capture.until)
+    }
+
+    /// End the capture if it is due at `now` or full
+    pub fn finish(&self, now: Instant) -> Option<CaptureReport> {
+        let mut capture = self.capture.lock().unwrap();
+        match &*capture {
+            Some(running) if running.file.is_some() && now < running.until => None,
+            _ => capture.take().map(Capture::report),
+        }
+    }
+
+    /// End the capture now
+    pub fn stop(&self) -> Option<CaptureReport> {
+        self.capture.lock().unwrap().take().map(Capture::report)
+    }
+}
+
+/// Writes one formatted event to the capture, if it is running
+pub struct CaptureWriter {
+    capture: TraceCapture,
+}
+
+impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut capture = self.capture.capture.lock().unwrap();
+        if let Some(capture) = capture.as_mut().filter(|capture| Instant::now() < capture.until) {
+            capture.write_line(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.capture.capture.lock().unwrap().as_mut().and_then(|capture| capture.file.as_mut()) {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> MakeWriter<'a> for TraceCapture {
+    type Writer = CaptureWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        CaptureWriter { capture: self.clone() }
+    }
+}
+
+/// Where logs go and which are kept
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub filter: LogFilter,
+    /// Directory for daily log files and captures; stderr if `None`
+    pub dir: Option<PathBuf>,
+}
+
+/// The installed subscriber's handles
+struct Logging {
+    filter: reload::Handle<Targets, Registry>,
+    current: Mutex<LogFilter>,
+    capture_filter: reload::Handle<Targets, Registry>,
+    capture: TraceCapture,
+    capture_dir: PathBuf,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+/// Install the global subscriber; call once, early in `main`
+pub fn init(options: LogOptions) -> Result<()> {
+    let writer = match &options.dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).storage(format!("Failed to create log directory {}", dir.display()))?;
+            let appender = tracing_appender::rolling::Builder::new()
+                .rotation(tracing_appender::rolling::Rotation::DAILY)
+                .filename_prefix(LOG_FILE_PREFIX)
+                .filename_suffix("log")
+                .max_log_files(KEEP_LOG_FILES)
+                .build(dir)
+                .storage("Failed to open the log file")?;
+            BoxMakeWriter::new(appender)
+        }
+        None => BoxMakeWriter::new(io::stderr),
+    };
+    let (filter, filter_handle) = reload::Layer::new(options.filter.targets());
+    let (capture_filter, capture_handle) = reload::Layer::new(Targets::new());
+    let capture = TraceCapture::new();
+
+    let output = tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(options.dir.is_none())
+        .with_filter(filter);
+    let capture_output = tracing_subscriber::fmt::layer()
+        .with_writer(capture.clone())
+        .with_ansi(false)
+        .with_filter(capture_filter);
+    tracing_subscriber::registry()
+        .with(output.and_then(capture_output))
+        .try_init()
+        .map_err(|_| crate::Error::AlreadyInitialized("Logging"))?;
+
+    let logging = Logging {
+        filter: filter_handle,
+        current: Mutex::new(options.filter),
+        capture_filter: capture_handle,
+        capture,
+        capture_dir: options.dir.unwrap_or_else(std::env::temp_dir),
+    };
+    LOGGING.set(logging).map_err(|_| crate::Error::AlreadyInitialized("Logging"))?;
+    Ok(())
+}
+
+fn logging() -> Result<&'static Logging> {
+    LOGGING.get().ok_or(crate::Error::NotInitialized("Logging"))
+}
+
+/// Filter logs with `filter` from now on
+pub fn set_filter(filter: &LogFilter) -> Result<()> {
+    let logging = logging()?;
+    logging.filter.reload(filter.targets())
+        .map_err(|e| crate::Error::internal(format!("Failed to change the log filter: {}", e)))?;
+    *logging.current.lock().unwrap() = filter.clone();
+    tracing::info!(filter = %filter, "Log filter changed");
+    Ok(())
+}
+
+/// The filter in effect, if logging is initialized
+pub fn current_filter() -> Option<LogFilter> {
+    LOGGING.get().map(|logging| logging.current.lock().unwrap().clone())
+}
+
+/// Capture the next `duration` at trace level to a new file, returning its path
+pub fn start_capture(duration: Duration) -> Result<PathBuf> {
+    let logging = logging()?;
+    let name = format!("{}-trace-{}.log", LOG_FILE_PREFIX, chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let path = logging.capture_dir.join(name);
+    logging.capture.start(&path, duration, CAPTURE_MAX_BYTES)?;
+    let filter: LogFilter = CAPTURE_FILTER.parse().expect("capture filter parses");
+    if let Err(e) = logging.capture_filter.reload(filter.targets()) {
+        logging.capture.stop();
+        return Err(crate::Error::internal(format!("Failed to start the trace capture: {}", e)));
+    }
+    tracing::info!(path = %path.display(), "Trace capture started");
+    Ok(path)
+}
+
+/// End the running capture if it is due at `now`
+pub fn finish_capture(now: Instant) -> Option<CaptureReport> {
+    let logging = LOGGING.get()?;
+    let report = logging.capture.finish(now)?;
+    if let Err(e) = logging.capture_filter.reload(Targets::new()) {
+        tracing::warn!("Failed to turn off trace capture filtering: {}", e);
+    }
+    tracing::info!(path = %report.path.display(), bytes = report.bytes, truncated = report.truncated, "Trace capture finished");
+    Some(report)
+}
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The in-process network of builds without iroh.
+//!
+//! Every [`IrohCtx`](crate::IrohCtx) of the process is a node of one hub.
+//! A ticket names a node, and a topic hands each message to every
+//! subscriber, the sender's own included, as gossip does, saying which
+//! node sent it. Two nodes in
+//! one process can so advertise, join and play a game with no network.
+//!
+//! A [`Relay`] stands in for a relay server: nodes that may not dial
+//! directly send everything through it, and it counts what it carries.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{broadcast, mpsc};
+use crate::{Error, Result};
+
+/// What every loopback ticket starts with
+pub const TICKET_PREFIX: &str = "loopback:";
+
+/// What every loopback relay address starts with
+pub const RELAY_PREFIX: &str = "loopback-relay:";
+
+/// Messages a topic keeps for a subscriber that falls behind
+const TOPIC_BUFFER: usize = 256;
+
+/// A gossip message and the node that sent it
+type Sent = (String, Vec<u8>);
+
+#[derive(Default)]
+struct Hub {
+    /// Contexts running under each node ID
+    nodes: Mutex<HashMap<String, usize>>,
+    /// Each message with the node that sent it
+    topics: Mutex<HashMap<String, broadcast::Sender<Sent>>>,
+    /// Relays running under each address
+    relays: Mutex<HashMap<String, Arc<RelayCounters>>>,
+}
+
+#[derive(Debug, Default)]
+struct RelayCounters {
+    messages: AtomicU64,
+    bytes: AtomicU64,
+}
+
+fn hub() -> &'static Hub {
+    static HUB: OnceLock<Hub> = OnceLock::new();
+    HUB.get_or_init(Hub::default)
+}
+
+/// Ticket for connecting to `node_id`
+pub fn ticket(node_id: &str) -> String {
+    format!("{}{}", TICKET_PREFIX, node_id)
+}
+
+/// Whether `ticket` is for a loopback node rather than an iroh one
+pub fn is_ticket(ticket: &str) -> bool {
+    ticket.trim().starts_with(TICKET_PREFIX)
+}
+
+/// Node ID `ticket` names
+pub fn parse_ticket(ticket: &str) -> Result<&str> {
+    match ticket.trim().strip_prefix(TICKET_PREFIX) {
+        Some(node_id) if !node_id.is_empty() => Ok(node_id),
+        _ => Err(Error::TicketInvalid(format!("not a loopback ticket (they start with {:?})", TICKET_PREFIX))),
+    }
+}
+
+/// Start a node as `node_id`
+#[cfg_attr(feature = "iroh", allow(dead_code))]
+pub(crate) fn join(node_id: &str) {
+    *hub().nodes.lock().unwrap().entry(node_id.to_string()).or_default() += 1;
+}
+
+/// Stop a node started with [`join`]
+#[cfg_attr(feature = "iroh", allow(dead_code))]
+pub(crate) fn leave(node_id: &str) {
+    let mut nodes = hub().nodes.lock().unwrap();
+    if let Some(count) = nodes.get_mut(node_id) {
+        *count -= 1;
+        if *count == 0 {
+            nodes.remove(node_id);
+        }
+    }
+}
+
+/// Whether a node runs as `node_id` in this process
+pub fn is_running(node_id: &str) -> bool {
+    hub().nodes.lock().unwrap().contains_key(node_id)
+}
+
+/// Send `data` from the node `from` to everyone subscribed to `topic`,
+/// returning how many that is
+pub fn publish(topic: &str, from: &str, data: Vec<u8>) -> usize {
+    let topics = hub().topics.lock().unwrap();
+    topics.get(topic).and_then(|tx| tx.send((from.to_string(), data)).ok()).unwrap_or(0)
+}
+
+/// Messages sent to `topic` from now on; the oldest are dropped for a
+/// receiver more than `buffer_size` behind
+pub fn subscribe(topic: &str, buffer_size: usize) -> mpsc::Receiver<Vec<u8>> {
+    subscribe_with(topic, buffer_size, |_, data| data)
+}
+
+/// Messages sent to `topic` from now on, each with the node that sent it
+pub fn subscribe_with_senders(topic: &str, buffer_size: usize) -> mpsc::Receiver<(String, Vec<u8>)> {
+    subscribe_with(topic, buffer_size, |from, data| (from, data))
+}
+
+fn subscribe_with<T: Send + 'static>(
+    topic: &str,
+    buffer_size: usize,
+    map: impl Fn(String, Vec<u8>) -> T + Send + 'static,
+) -> mpsc::Receiver<T> {
+    let mut topic_rx = hub().topics.lock().unwrap()
+        .entry(topic.to_string())
+        .or_insert_with(|| broadcast::channel(TOPIC_BUFFER).0)
+        .subscribe();
+    let (tx, rx) = mpsc::channel(buffer_size.max(1));
+    let topic = topic.to_string();
+    tokio::spawn(async move {
+        loop {
+            match topic_rx.recv().await {
+                Ok((from, data)) => {
+                    if tx.send(map(from, data)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Dropped {} loopback messages on {}", missed, topic);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    });
+    rx
+}
+
+/// A relay in this process, running until dropped
+#[derive(Debug)]
+pub struct Relay {
+    addr: String,
+    counters: Arc<RelayCounters>,
+}
+
+impl Relay {
+    /// Start a relay at a fresh address
+    pub fn start() -> Self {
+        let addr = format!("{}{}", RELAY_PREFIX, uuid::Uuid::new_v4().simple());
+        let counters = Arc::new(RelayCounters::default());
+        hub().relays.lock().unwrap().insert(addr.clone(), counters.clone());
+        Self { addr, counters }
+    }
+
+    /// Address nodes name to use this relay
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    /// Messages carried so far
+    pub fn messages_relayed(&self) -> u64 {
+        self.counters.messages.load(Ordering::Relaxed)
+    }
+
+    /// Bytes carried so far
+    pub fn bytes_relayed(&self) -> u64 {
+        self.counters.bytes.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        hub().relays.lock().unwrap().remove(&self.addr);
+    }
+}
+
+/// Whether a relay runs at `addr` in this process
+pub fn is_relay_running(addr: &str) -> bool {
+    hub().relays.lock().unwrap().contains_key(addr)
+}
+
+/// Send `data` from the node `from` to everyone subscribed to `topic`
+/// through the relay at `relay`, returning how many that is
+#[cfg_attr(feature = "iroh", allow(dead_code))]
+pub(crate) fn publish_via(relay: &str, topic: &str, from: &str, data: Vec<u8>) -> Result<usize> {
+    let counters = hub().relays.lock().unwrap().get(relay).cloned()
+        .ok_or_else(|| Error::RelayUnavailable(format!("no relay runs at {} in this process", relay)))?;
+    counters.messages.fetch_add(1, Ordering::Relaxed);
+    counters.bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+    Ok(publish(topic, from, data))
+}
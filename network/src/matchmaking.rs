@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Automatic matchmaking over a shared queue topic.
+//!
+//! Queued peers announce their preferences on the topic. Every peer pairs
+//! the entries it sees the same way (sorted by peer ID, first compatible
+//! partner wins), and the lower peer ID of a pair proposes a game. The
+//! handshake is Propose → Accept → Confirm; a peer that goes quiet
+//! mid-handshake puts the other back in the queue.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use tokio::sync::{broadcast, oneshot};
+use p2pgo_core::game_clock::{GameClock, OnDisconnect};
+use p2pgo_core::cbor::migrate::Versioned;
+use crate::{Error, GameId, Result};
+
+#[cfg(feature = "iroh")]
+use {
+    crate::IrohCtx,
+    iroh_gossip::proto::TopicId,
+};
+
+/// Clock settings of a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TimeControl {
+    /// Main time per player in seconds
+    pub main_time_secs: u32,
+    /// Byo-yomi period in seconds, 0 for none
+    pub byo_yomi_secs: u32,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self {
+            main_time_secs: 600,
+            byo_yomi_secs: 30,
+        }
+    }
+}
+
+/// Byo-yomi periods each player gets
+pub const BYO_YOMI_PERIODS: u32 = 5;
+
+impl TimeControl {
+    /// Fresh clocks for a game played with these settings
+    pub fn game_clock(&self) -> GameClock {
+        GameClock::new(
+            Duration::from_secs(self.main_time_secs as u64),
+            Duration::from_secs(self.byo_yomi_secs as u64),
+            BYO_YOMI_PERIODS,
+        )
+    }
+}
+
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}m", self.main_time_secs / 60)?;
+        if self.byo_yomi_secs > 0 {
+            write!(f, " + {}s", self.byo_yomi_secs)?;
+        }
+        Ok(())
+    }
+}
+
+/// What kind of game a queued player wants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchPrefs {
+    pub board_size: u8,
+    /// Largest rating difference to accept, in rating points
+    pub rating_band: u32,
+    pub time_control: TimeControl,
+    /// What the clock does while a player is disconnected
+    #[serde(default)]
+    pub on_disconnect: OnDisconnect,
+}
+
+impl Default for MatchPrefs {
+    fn default() -> Self {
+        Self {
+            board_size: 9,
+            rating_band: 300,
+            time_control: TimeControl::default(),
+            on_disconnect: OnDisconnect::default(),
+        }
+    }
+}
+
+/// A player waiting in the queue
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    /// Node ID of the player
+    pub peer: String,
+    pub prefs: MatchPrefs,
+    /// Self-reported rating
+    pub rating: i32,
+}
+
+impl QueueEntry {
+    /// Whether both players would accept a game against each other
+    pub fn compatible(&self, other: &QueueEntry) -> bool {
+        let band = self.prefs.rating_band.min(other.prefs.rating_band);
+        self.peer != other.peer
+            && self.prefs.board_size == other.prefs.board_size
+            && self.prefs.time_control == other.prefs.time_control
+            && self.prefs.on_disconnect == other.prefs.on_disconnect
+            && self.rating.abs_diff(other.rating) <= band
+    }
+}
+
+/// Messages on the queue topic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueueMessage {
+    /// Still waiting for a game
+    Announce(QueueEntry),
+    /// Left the queue
+    Leave { peer: String },
+    /// Host offers a game to the guest
+    Propose { host: String, guest: String, game_id: GameId },
+    /// Guest takes the offered game
+    Accept { host: String, guest: String, game_id: GameId },
+    /// Host creates the game; both players leave the queue
+    Confirm { host: String, guest: String, game_id: GameId },
+}
+
+impl Versioned for QueueMessage {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "queue message";
+}
+
+/// A finished pairing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub game_id: GameId,
+    pub opponent: QueueEntry,
+    /// Whether we create the game
+    pub host: bool,
+}
+
+/// Timeouts of the queue
+#[derive(Debug, Clone, Copy)]
+pub struct QueueTiming {
+    /// How often [`find_match`] ticks the queue
+    pub tick: Duration,
+    /// How often we re-announce ourselves
+    pub announce: Duration,
+    /// Entries not re-announced for this long are dropped
+    pub entry_ttl: Duration,
+    /// How long to wait for the other side of a handshake
+    pub handshake_timeout: Duration,
+    /// How long a peer that vanished mid-handshake is not paired with again
+    pub flap_cooldown: Duration,
+}
+
+impl Default for QueueTiming {
+    fn default() -> Self {
+        Self {
+            tick: Duration::from_millis(250),
+            announce: Duration::from_secs(2),
+            entry_ttl: Duration::from_secs(10),
+            handshake_timeout: Duration::from_secs(5),
+            flap_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Handshake {
+    Waiting,
+    Proposed { guest: QueueEntry, game_id: GameId, since: Instant },
+    Accepted { host: QueueEntry, game_id: GameId, since: Instant },
+    Matched(Match),
+}
+
+/// Our side of the queue, independent of the transport
+#[derive(Debug)]
+pub struct MatchQueue {
+    me: QueueEntry,
+    timing: QueueTiming,
+    /// Other queued players and when we last heard from them
+    seen: HashMap<String, (QueueEntry, Instant)>,
+    /// Peers that vanished mid-handshake, until when they are skipped
+    cooldown: HashMap<String, Instant>,
+    handshake: Handshake,
+    last_announce: Option<Instant>,
+}
+
+impl MatchQueue {
+    /// Queue `me` with the default timeouts
+    pub fn new(me: QueueEntry) -> Self {
+        Self::with_timing(me, QueueTiming::default())
+    }
+
+    /// Queue `me` with custom timeouts
+    pub fn with_timing(me: QueueEntry, timing: QueueTiming) -> Self {
+        Self {
+            me,
+            timing,
+            seen: HashMap::new(),
+            cooldown: HashMap::new(),
+            handshake: Handshake::Waiting,
+            last_announce: None,
+        }
+    }
+
+    /// Our own entry
+    pub fn entry(&self) -> &QueueEntry {
+        &self.me
+    }
+
+    pub fn timing(&self) -> QueueTiming {
+        self.timing
+    }
+
+    /// The pairing, once the handshake completed
+    pub fn matched(&self) -> Option<&Match> {
+        match &self.handshake {
+            Handshake::Matched(found) => Some(found),
+            _ => None,
+        }
+    }
+
+    /// Whether we are in the middle of a handshake
+    pub fn is_handshaking(&self) -> bool {
+        matches!(self.handshake, Handshake::Proposed { .. } | Handshake::Accepted { .. })
+    }
+
+    /// The message to publish when leaving the queue
+    pub fn cancel(&mut self) -> QueueMessage {
+        self.handshake = Handshake::Waiting;
+        QueueMessage::Leave { peer: self.me.peer.clone() }
+    }
+
+    /// Expire stale entries and handshakes, announce, and pair
+    pub fn tick(&mut self, now: Instant) -> Vec<QueueMessage> {
+        let ttl = self.timing.entry_ttl;
+        self.seen.retain(|_, (_, last)| now.duration_since(*last) < ttl);
+        self.cooldown.retain(|_, until| *until > now);
+
+        let stalled = match &self.handshake {
+            Handshake::Proposed { guest: peer, since, .. }
+            | Handshake::Accepted { host: peer, since, .. }
+                if now.duration_since(*since) >= self.timing.handshake_timeout => Some(peer.peer.clone()),
+            _ => None,
+        };
+        if let Some(peer) = stalled {
+            tracing::info!(peer = %peer, "Peer vanished mid-handshake, back to the queue");
+            self.seen.remove(&peer);
+            self.cooldown.insert(peer, now + self.timing.flap_cooldown);
+            self.handshake = Handshake::Waiting;
+        }
+
+        let mut out = Vec::new();
+        if matches!(self.handshake, Handshake::Waiting) {
+            let due = match self.last_announce {
+                Some(last) => now.duration_since(last) >= self.timing.announce,
+                None => true,
+            };
+            if due {
+                self.last_announce = Some(now);
+                out.push(QueueMessage::Announce(self.me.clone()));
+            }
+        }
+        out.extend(self.pair(now));
+        out
+    }
+
+    /// Process a message from the topic and return our replies
+    pub fn handle(&mut self, msg: QueueMessage, now: Instant) -> Vec<QueueMessage> {
+        let me = self.me.peer.clone();
+        let mut out = Vec::new();
+        match msg {
+            QueueMessage::Announce(entry) => {
+                if entry.peer != me {
+                    self.seen.insert(entry.peer.clone(), (entry, now));
+                }
+            }
+            QueueMessage::Leave { peer } => {
+                self.seen.remove(&peer);
+                if self.handshake_peer() == Some(peer.as_str()) {
+                    tracing::info!(peer = %peer, "Peer left mid-handshake, back to the queue");
+                    self.handshake = Handshake::Waiting;
+                }
+            }
+            QueueMessage::Propose { host, guest, game_id } if guest == me => {
+                let offer = self.seen.get(&host)
+                    .map(|(entry, _)| entry.clone())
+                    .filter(|entry| self.me.compatible(entry) && !self.cooldown.contains_key(&entry.peer));
+                if let (Handshake::Waiting, Some(entry)) = (&self.handshake, offer) {
+                    out.push(QueueMessage::Accept { host, guest, game_id: game_id.clone() });
+                    self.handshake = Handshake::Accepted { host: entry, game_id, since: now };
+                }
+            }
+            QueueMessage::Accept { host, guest, game_id } if host == me => {
+                if let Handshake::Proposed { guest: entry, game_id: proposed, .. } = &self.handshake {
+                    if entry.peer == guest && *proposed == game_id {
+                        let opponent = entry.clone();
+                        out.push(QueueMessage::Confirm { host, guest, game_id: game_id.clone() });
+                        self.handshake = Handshake::Matched(Match { game_id, opponent, host: true });
+                    }
+                }
+            }
+            QueueMessage::Confirm { host, guest, game_id } => {
+                if let Handshake::Accepted { host: entry, game_id: accepted, .. } = &self.handshake {
+                    if guest == me && entry.peer == host && *accepted == game_id {
+                        let opponent = entry.clone();
+                        self.handshake = Handshake::Matched(Match { game_id, opponent, host: false });
+                    }
+                }
+                // Both players are out of the queue now
+                self.seen.remove(&host);
+                self.seen.remove(&guest);
+            }
+            QueueMessage::Propose { .. } | QueueMessage::Accept { .. } => {}
+        }
+        out.extend(self.pair(now));
+        out
+    }
+
+    fn handshake_peer(&self) -> Option<&str> {
+        match &self.handshake {
+            Handshake::Proposed { guest: peer, .. } | Handshake::Accepted { host: peer, .. } => Some(&peer.peer),
+            _ => None,
+        }
+    }
+
+    /// Propose a game if the deterministic pairing makes us a host
+    fn pair(&mut self, now: Instant) -> Vec<QueueMessage> {
+        if !matches!(self.handshake, Handshake::Waiting) {
+            return Vec::new();
+        }
+
+        let mut entries: Vec<&QueueEntry> = self.seen.values()
+            .map(|(entry, _)| entry)
+            .filter(|entry| !self.cooldown.contains_key(&entry.peer))
+            .chain(std::iter::once(&self.me))
+            .collect();
+        entries.sort_by(|a, b| a.peer.cmp(&b.peer));
+
+        let mut partner = vec![None; entries.len()];
+        for i in 0..entries.len() {
+            if partner[i].is_some() {
+                continue;
+            }
+            if let Some(j) = (i + 1..entries.len()).find(|&j| partner[j].is_none() && entries[i].compatible(entries[j])) {
+                partner[i] = Some(j);
+                partner[j] = Some(i);
+            }
+        }
+
+        let Some(me) = entries.iter().position(|entry| entry.peer == self.me.peer) else {
+            return Vec::new();
+        };
+        match partner[me] {
+            // The lower peer ID hosts
+            Some(other) if other > me => {
+                let guest = entries[other].clone();
+                let game_id = format!("game-{}", uuid::Uuid::new_v4());
+                tracing::debug!(guest = %guest.peer, game_id = %game_id, "Proposing a match");
+                let propose = QueueMessage::Propose {
+                    host: self.me.peer.clone(),
+                    guest: guest.peer.clone(),
+                    game_id: game_id.clone(),
+                };
+                self.handshake = Handshake::Proposed { guest, game_id, since: now };
+                vec![propose]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The queue topic for one board size
+#[derive(Debug, Clone)]
+pub struct QueueTopic {
+    tx: broadcast::Sender<QueueMessage>,
+    #[cfg(feature = "iroh")]
+    gossip: Option<(IrohCtx, TopicId)>,
+}
+
+impl QueueTopic {
+    /// A topic shared only within this process
+    pub fn local() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self {
+            tx,
+            #[cfg(feature = "iroh")]
+            gossip: None,
+        }
+    }
+
+    /// The gossip queue topic for `board_size`
+    #[cfg(feature = "iroh")]
+    pub async fn gossip(ctx: IrohCtx, board_size: u8) -> Result<Self> {
+        let topic_id = IrohCtx::matchmaking_topic(board_size);
+        let mut events = ctx.subscribe_gossip_topic(topic_id, 64).await?;
+        let mut topic = Self::local();
+        let tx = topic.tx.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let Some(content) = crate::gossip_compat::extract_bytes(&event) else {
+                    continue;
+                };
+                match p2pgo_core::cbor::migrate::from_slice::<QueueMessage>(&content) {
+                    Ok(msg) => {
+                        let _ = tx.send(msg);
+                    }
+                    Err(e) => tracing::warn!("Failed to decode queue message: {}", e),
+                }
+            }
+        });
+        topic.gossip = Some((ctx, topic_id));
+        Ok(topic)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<QueueMessage> {
+        self.tx.subscribe()
+    }
+
+    /// Send `msg` to every queued peer
+    pub async fn publish(&self, msg: QueueMessage) -> Result<()> {
+        #[cfg(feature = "iroh")]
+        if let Some((ctx, topic_id)) = &self.gossip {
+            let bytes = serde_cbor::to_vec(&msg).map_err(|e| Error::internal(format!("Failed to encode queue message: {}", e)))?;
+            ctx.broadcast_to_topic(*topic_id, &bytes).await?;
+        }
+        let _ = self.tx.send(msg);
+        Ok(())
+    }
+}
+
+/// Wait in the queue until matched, or until `cancel` fires or is dropped
+pub async fn find_match(mut queue: MatchQueue, topic: &QueueTopic, mut cancel: oneshot::Receiver<()>) -> Result<Option<Match>> {
+    let mut rx = topic.subscribe();
+    let mut ticker = tokio::time::interval(queue.timing().tick);
+    loop {
+        let out = tokio::select! {
+            _ = &mut cancel => {
+                topic.publish(queue.cancel()).await?;
+                return Ok(None);
+            }
+            _ = ticker.tick() => queue.tick(Instant::now()),
+            msg = rx.recv() => match msg {
+                Ok(msg) => queue.handle(msg, Instant::now()),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "Matchmaking queue lagged");
+                    Vec::new()
+                }
+                Err(broadcast::error::RecvError::Closed) => return Err(Error::TopicClosed("matchmaking")),
+            },
+        };
+        for msg in out {
+            topic.publish(msg).await?;
+        }
+        if let Some(found) = queue.matched() {
+            tracing::info!(game_id = %found.game_id, opponent = %found.opponent.peer, "Match found");
+            return Ok(Some(found.clone()));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Opponent presence: whether a player is looking at the board, so the
+//! other side sees them thinking rather than a silent board.
+//!
+//! Presence is a courtesy, not game data. It goes out at most once per
+//! [`PRESENCE_INTERVAL`], carries no coordinates, so it never shows where
+//! the pointer is, and anything from a peer is only displayed. Both
+//! limiters take the current time as an argument so tests can drive them
+//! with a mock clock.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// Least time between two presence messages, either way
+pub const PRESENCE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Presence is repeated this often while nothing changes, so a quiet
+/// opponent still shows as there
+pub const PRESENCE_HEARTBEAT: Duration = Duration::from_secs(10);
+
+/// Without presence for this long, the opponent is shown as last seen
+pub const PRESENCE_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// What a player tells the opponent about themselves.
+///
+/// Deliberately two fields and nothing more: whether the pointer is over
+/// the board, not where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Presence {
+    /// The pointer is over the board
+    pub considering: bool,
+    /// Last time the player did anything in the game, unix seconds
+    pub last_active: u64,
+}
+
+/// Decides when our presence goes out
+#[derive(Debug, Clone)]
+pub struct PresenceLimiter {
+    interval: Duration,
+    heartbeat: Duration,
+    /// When we last sent, and whether we were considering
+    last_sent: Option<(Instant, bool)>,
+}
+
+impl Default for PresenceLimiter {
+    fn default() -> Self {
+        Self::new(PRESENCE_INTERVAL, PRESENCE_HEARTBEAT)
+    }
+}
+
+impl PresenceLimiter {
+    pub fn new(interval: Duration, heartbeat: Duration) -> Self {
+        Self { interval, heartbeat, last_sent: None }
+    }
+
+    /// The presence to send at `now`, if any.
+    ///
+    /// Call as often as convenient, e.g. every frame. A change is sent once
+    /// `interval` has passed since the last message; the same presence is
+    /// repeated every `heartbeat`. A change held back goes out on a later
+    /// call, so the latest state always arrives.
+    pub fn poll(&mut self, considering: bool, last_active: u64, now: Instant) -> Option<Presence> {
+        if let Some((sent_at, was_considering)) = self.last_sent {
+            let since = now.saturating_duration_since(sent_at);
+            if since < self.interval || (was_considering == considering && since < self.heartbeat) {
+                return None;
+            }
+        }
+        self.last_sent = Some((now, considering));
+        Some(Presence { considering, last_active })
+    }
+}
+
+/// Drops presence from peers sending it faster than the interval allows
+#[derive(Debug, Clone)]
+pub struct PresenceFilter {
+    interval: Duration,
+    last_seen: HashMap<String, Instant>,
+}
+
+impl Default for PresenceFilter {
+    /// Half the interval, leaving room for messages delayed on the way
+    fn default() -> Self {
+        Self::new(PRESENCE_INTERVAL / 2)
+    }
+}
+
+impl PresenceFilter {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last_seen: HashMap::new() }
+    }
+
+    /// Whether presence from `peer` arriving at `now` is shown
+    pub fn admit(&mut self, peer: &str, now: Instant) -> bool {
+        match self.last_seen.get(peer) {
+            Some(&last) if now.saturating_duration_since(last) < self.interval => false,
+            _ => {
+                self.last_seen.insert(peer.to_string(), now);
+                true
+            }
+        }
+    }
+
+    /// Forget a peer that disconnected
+    pub fn remove_peer(&mut self, peer: &str) {
+        self.last_seen.remove(peer);
+    }
+}
@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-peer move rate limiting and timing anomaly detection.
+//!
+//! A peer that sends more moves in a window than the limit allows is muted
+//! for a while; its moves are dropped until the mute lifts. A peer muted
+//! too often is disconnected. Separately, moves that keep arriving faster
+//! than a person can play flag the game so it is left out of training.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+
+/// QUIC application close code for a peer disconnected by the rate limiter
+pub const RATE_LIMIT_CLOSE_CODE: u32 = 429;
+
+/// Rate limiter thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Moves a peer may send per `window`
+    pub max_moves: u32,
+    pub window: Duration,
+    /// How long a peer stays muted after going over the limit
+    pub mute_for: Duration,
+    /// Mutes after which the peer is disconnected
+    pub max_offenses: u32,
+    /// Moves closer together than this count as implausibly fast
+    pub implausible_gap: Duration,
+    /// Consecutive implausibly fast moves that flag the game
+    pub implausible_streak: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_moves: 5,
+            window: Duration::from_secs(1),
+            mute_for: Duration::from_secs(1),
+            max_offenses: 3,
+            implausible_gap: Duration::from_millis(100),
+            implausible_streak: 5,
+        }
+    }
+}
+
+/// Why a peer's connection was closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisconnectReason {
+    /// Application close code, see [`RATE_LIMIT_CLOSE_CODE`]
+    pub code: u32,
+    pub peer: String,
+    /// How many times the peer was muted
+    pub offenses: u32,
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer {} exceeded the move rate limit {} times", self.peer, self.offenses)
+    }
+}
+
+/// What to do with a move that just arrived
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Process the move
+    Accept,
+    /// The peer went over the limit and is now muted; warn and drop the move
+    Mute { until: Instant, offenses: u32 },
+    /// The peer is muted; drop the move silently
+    Drop,
+    /// The peer keeps flooding; close its connection
+    Disconnect(DisconnectReason),
+}
+
+/// Suspicious play recorded against a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MoveAnomaly {
+    /// Many moves in a row arrived faster than a person plays
+    ImplausibleTiming { peer: String, streak: u32 },
+}
+
+impl MoveAnomaly {
+    /// Short tag for listings
+    pub fn tag(&self) -> &'static str {
+        match self {
+            MoveAnomaly::ImplausibleTiming { .. } => "implausible-timing",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerState {
+    /// Arrival times of accepted moves inside the current window
+    recent: VecDeque<Instant>,
+    muted_until: Option<Instant>,
+    offenses: u32,
+    last_arrival: Option<Instant>,
+    fast_streak: u32,
+    flagged: bool,
+}
+
+/// Tracks move arrivals per peer for one game
+#[derive(Debug, Default)]
+pub struct MoveRateLimiter {
+    config: RateLimitConfig,
+    peers: HashMap<String, PeerState>,
+    anomalies: Vec<MoveAnomaly>,
+}
+
+impl MoveRateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            peers: HashMap::new(),
+            anomalies: Vec::new(),
+        }
+    }
+
+    /// Decide what to do with a move from `peer` arriving at `now`
+    pub fn check(&mut self, peer: &str, now: Instant) -> Verdict {
+        let config = self.config;
+        let state = self.peers.entry(peer.to_string()).or_default();
+
+        // Timing is judged on every arrival, muted or not
+        let fast = state.last_arrival
+            .is_some_and(|last| now.saturating_duration_since(last) < config.implausible_gap);
+        state.fast_streak = if fast { state.fast_streak + 1 } else { 0 };
+        state.last_arrival = Some(now);
+        if state.fast_streak >= config.implausible_streak && !state.flagged {
+            state.flagged = true;
+            tracing::warn!(peer, streak = state.fast_streak, "Implausibly fast moves; excluding game from training");
+            self.anomalies.push(MoveAnomaly::ImplausibleTiming {
+                peer: peer.to_string(),
+                streak: state.fast_streak,
+            });
+        }
+
+        if let Some(until) = state.muted_until {
+            if now < until {
+                return Verdict::Drop;
+            }
+            state.muted_until = None;
+        }
+
+        while state.recent.front().is_some_and(|&at| now.saturating_duration_since(at) >= config.window) {
+            state.recent.pop_front();
+        }
+        if state.recent.len() < config.max_moves as usize {
+            state.recent.push_back(now);
+            return Verdict::Accept;
+        }
+
+        state.offenses += 1;
+        state.recent.clear();
+        if state.offenses >= config.max_offenses {
+            return Verdict::Disconnect(DisconnectReason {
+                code: RATE_LIMIT_CLOSE_CODE,
+                peer: peer.to_string(),
+                offenses: state.offenses,
+            });
+        }
+        let until = now + config.mute_for;
+        state.muted_until = Some(until);
+        Verdict::Mute { until, offenses: state.offenses }
+    }
+
+    /// Anomalies seen so far, oldest first
+    pub fn anomalies(&self) -> &[MoveAnomaly] {
+        &self.anomalies
+    }
+
+    /// Forget a peer, e.g. after its connection closed
+    pub fn remove_peer(&mut self, peer: &str) {
+        self.peers.remove(peer);
+    }
+}
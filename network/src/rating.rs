@@ -0,0 +1,387 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Local Glicko-2 rating and per-opponent results.
+//!
+//! Each rated game is one rating period. Results are appended to a CBOR log
+//! next to the credits ledger; the current rating is the last entry's.
+//! Entries are versioned like core's CBOR, see [`p2pgo_core::cbor::migrate`].
+//! Opponents' ratings come from their self-reported [`RatingEstimate`], so
+//! the number is only as honest as the peers we play.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use serde::{Serialize, Deserialize};
+use p2pgo_core::cbor::migrate::{self, Upgrade, Versioned};
+use crate::{Error, GameId, Result};
+use crate::error::StorageContext;
+
+/// File name of the rating log inside the data directory
+pub const RATINGS_FILE: &str = "ratings.cbor";
+
+/// Version of [`RatingEntry`] written today
+pub const RATING_ENTRY_VERSION: u32 = 1;
+
+/// Converts between the Glicko and Glicko-2 scales
+const GLICKO2_SCALE: f64 = 173.7178;
+
+/// System constant limiting how fast volatility changes
+const TAU: f64 = 0.5;
+
+/// Convergence tolerance of the volatility iteration
+const EPSILON: f64 = 0.000_001;
+
+/// A Glicko-2 rating on the Glicko scale
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub rating: f64,
+    /// Rating deviation: how uncertain the rating is
+    pub deviation: f64,
+    /// How erratic the player's results are
+    pub volatility: f64,
+}
+
+impl Default for Rating {
+    /// An unrated player
+    fn default() -> Self {
+        Self {
+            rating: 1500.0,
+            deviation: 350.0,
+            volatility: 0.06,
+        }
+    }
+}
+
+impl Rating {
+    /// Rate one period of games: `(opponent, score)` with 1 for a win,
+    /// 0.5 for a draw and 0 for a loss
+    pub fn update(&self, results: &[(Rating, f64)]) -> Rating {
+        let mu = (self.rating - 1500.0) / GLICKO2_SCALE;
+        let phi = self.deviation / GLICKO2_SCALE;
+        if results.is_empty() {
+            // Only the uncertainty grows
+            let phi = (phi * phi + self.volatility * self.volatility).sqrt();
+            return Rating { deviation: phi * GLICKO2_SCALE, ..*self };
+        }
+
+        let mut v_inv = 0.0;
+        let mut improvement = 0.0;
+        for (opponent, score) in results {
+            let mu_j = (opponent.rating - 1500.0) / GLICKO2_SCALE;
+            let g = g(opponent.deviation / GLICKO2_SCALE);
+            let e = 1.0 / (1.0 + (-g * (mu - mu_j)).exp());
+            v_inv += g * g * e * (1.0 - e);
+            improvement += g * (score - e);
+        }
+        let v = 1.0 / v_inv;
+        let delta = v * improvement;
+
+        let volatility = new_volatility(phi, self.volatility, v, delta);
+        let phi_star = (phi * phi + volatility * volatility).sqrt();
+        let new_phi = 1.0 / (1.0 / (phi_star * phi_star) + 1.0 / v).sqrt();
+        let new_mu = mu + new_phi * new_phi * improvement;
+
+        Rating {
+            rating: new_mu * GLICKO2_SCALE + 1500.0,
+            deviation: new_phi * GLICKO2_SCALE,
+            volatility,
+        }
+    }
+
+    /// Rounded figures to show other players
+    pub fn estimate(&self) -> RatingEstimate {
+        RatingEstimate {
+            rating: self.rating.round() as i32,
+            deviation: self.deviation.round() as u32,
+        }
+    }
+}
+
+fn g(phi: f64) -> f64 {
+    1.0 / (1.0 + 3.0 * phi * phi / (PI * PI)).sqrt()
+}
+
+/// Step 5 of Glickman's algorithm: the Illinois method on ln(σ²)
+fn new_volatility(phi: f64, sigma: f64, v: f64, delta: f64) -> f64 {
+    let a = (sigma * sigma).ln();
+    let f = |x: f64| {
+        let ex = x.exp();
+        ex * (delta * delta - phi * phi - v - ex) / (2.0 * (phi * phi + v + ex).powi(2)) - (x - a) / (TAU * TAU)
+    };
+
+    let mut lower = a;
+    let mut upper = if delta * delta > phi * phi + v {
+        (delta * delta - phi * phi - v).ln()
+    } else {
+        let mut k = 1.0;
+        while f(a - k * TAU) < 0.0 {
+            k += 1.0;
+        }
+        a - k * TAU
+    };
+    let mut f_lower = f(lower);
+    let mut f_upper = f(upper);
+    while (upper - lower).abs() > EPSILON {
+        let c = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+        let f_c = f(c);
+        if f_c * f_upper <= 0.0 {
+            lower = upper;
+            f_lower = f_upper;
+        } else {
+            f_lower /= 2.0;
+        }
+        upper = c;
+        f_upper = f_c;
+    }
+    (lower / 2.0).exp()
+}
+
+/// A rating as announced in a player's profile.
+///
+/// Self-reported by the player and not verified by anyone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RatingEstimate {
+    pub rating: i32,
+    pub deviation: u32,
+}
+
+impl RatingEstimate {
+    /// The estimate as a rating to play against
+    pub fn to_rating(self) -> Rating {
+        Rating {
+            rating: self.rating as f64,
+            deviation: self.deviation as f64,
+            ..Rating::default()
+        }
+    }
+}
+
+impl std::fmt::Display for RatingEstimate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ±{} (self-reported)", self.rating, self.deviation)
+    }
+}
+
+/// How a game ended for us
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl GameOutcome {
+    /// Glicko score of the outcome
+    pub fn score(self) -> f64 {
+        match self {
+            GameOutcome::Win => 1.0,
+            GameOutcome::Loss => 0.0,
+            GameOutcome::Draw => 0.5,
+        }
+    }
+}
+
+/// A finished game to rate
+#[derive(Debug, Clone)]
+pub struct RatedGame {
+    pub game_id: GameId,
+    /// Opponent's node ID
+    pub opponent: String,
+    /// Opponent's self-reported rating, if they sent one
+    pub opponent_estimate: Option<RatingEstimate>,
+    pub outcome: GameOutcome,
+    /// False for games that must not count, e.g. against the AI or abandoned
+    pub rated: bool,
+}
+
+/// One rated game in the log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "RatingEntryRepr", from = "RatingEntryRepr")]
+pub struct RatingEntry {
+    /// Unix timestamp when the game was rated
+    pub timestamp: u64,
+    pub game_id: GameId,
+    pub opponent: String,
+    /// The rating we played against
+    pub opponent_rating: Rating,
+    pub outcome: GameOutcome,
+    pub before: Rating,
+    pub after: Rating,
+}
+
+impl RatingEntry {
+    /// Rating points gained or lost
+    pub fn delta(&self) -> f64 {
+        self.after.rating - self.before.rating
+    }
+}
+
+impl Versioned for RatingEntry {
+    const VERSION: u32 = RATING_ENTRY_VERSION;
+    const NAME: &'static str = "rating entry";
+}
+
+/// Every field any version of [`RatingEntry`] had
+#[derive(Serialize, Deserialize)]
+struct RatingEntryRepr {
+    #[serde(default)]
+    v: u32,
+    timestamp: u64,
+    game_id: GameId,
+    opponent: String,
+    opponent_rating: Rating,
+    outcome: GameOutcome,
+    before: Rating,
+    after: Rating,
+}
+
+const RATING_ENTRY_UPGRADES: [Upgrade<RatingEntryRepr>; RATING_ENTRY_VERSION as usize] = [
+    // v1 only added `v`
+    |_| {},
+];
+
+impl From<RatingEntry> for RatingEntryRepr {
+    fn from(entry: RatingEntry) -> Self {
+        Self {
+            v: RATING_ENTRY_VERSION,
+            timestamp: entry.timestamp,
+            game_id: entry.game_id,
+            opponent: entry.opponent,
+            opponent_rating: entry.opponent_rating,
+            outcome: entry.outcome,
+            before: entry.before,
+            after: entry.after,
+        }
+    }
+}
+
+impl From<RatingEntryRepr> for RatingEntry {
+    fn from(mut repr: RatingEntryRepr) -> Self {
+        let version = repr.v;
+        migrate::upgrade(&mut repr, version, &RATING_ENTRY_UPGRADES, RatingEntry::NAME);
+        Self {
+            timestamp: repr.timestamp,
+            game_id: repr.game_id,
+            opponent: repr.opponent,
+            opponent_rating: repr.opponent_rating,
+            outcome: repr.outcome,
+            before: repr.before,
+            after: repr.after,
+        }
+    }
+}
+
+/// Results against one opponent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PeerRecord {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+/// Our rating and results, backed by an append-only file
+#[derive(Debug)]
+pub struct RatingTracker {
+    path: PathBuf,
+    entries: Vec<RatingEntry>,
+}
+
+impl RatingTracker {
+    /// Open the log in the default data directory
+    pub fn open_default() -> Result<Self> {
+        let dir = p2pgo_core::p2pgo_dirs::Dirs::current().storage("Failed to find the data directory")?.data;
+        Self::open(dir.join(RATINGS_FILE))
+    }
+
+    /// Open the log at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let data = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).storage("Failed to read rating log"),
+        };
+        let entries = serde_cbor::Deserializer::from_slice(&data)
+            .into_iter::<RatingEntry>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| Error::malformed("rating log", e))?;
+        Ok(Self { path, entries })
+    }
+
+    /// Our rating now
+    pub fn current(&self) -> Rating {
+        self.entries.last().map(|e| e.after).unwrap_or_default()
+    }
+
+    /// All rated games, oldest first
+    pub fn history(&self) -> &[RatingEntry] {
+        &self.entries
+    }
+
+    /// Wins, losses and draws against `opponent`
+    pub fn peer_record(&self, opponent: &str) -> PeerRecord {
+        let mut record = PeerRecord::default();
+        for entry in self.entries.iter().filter(|e| e.opponent == opponent) {
+            match entry.outcome {
+                GameOutcome::Win => record.wins += 1,
+                GameOutcome::Loss => record.losses += 1,
+                GameOutcome::Draw => record.draws += 1,
+            }
+        }
+        record
+    }
+
+    /// Records for every opponent we have played
+    pub fn peers(&self) -> HashMap<String, PeerRecord> {
+        let mut peers = HashMap::new();
+        for entry in &self.entries {
+            peers.entry(entry.opponent.clone()).or_insert_with(|| self.peer_record(&entry.opponent));
+        }
+        peers
+    }
+
+    /// Rate a finished game, once per game. Unrated games are ignored.
+    pub fn record(&mut self, game: RatedGame) -> Result<Option<&RatingEntry>> {
+        if !game.rated {
+            tracing::debug!(game_id = %game.game_id, "Not rating unrated game");
+            return Ok(None);
+        }
+        if self.entries.iter().any(|e| e.game_id == game.game_id) {
+            return Err(Error::Duplicate(format!("Rating of game {}", game.game_id)));
+        }
+
+        let before = self.current();
+        let opponent_rating = game.opponent_estimate.map(RatingEstimate::to_rating).unwrap_or_default();
+        let after = before.update(&[(opponent_rating, game.outcome.score())]);
+        let entry = RatingEntry {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            game_id: game.game_id,
+            opponent: game.opponent,
+            opponent_rating,
+            outcome: game.outcome,
+            before,
+            after,
+        };
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).storage("Failed to create data directory")?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .storage("Failed to open rating log")?;
+        file.write_all(&serde_cbor::to_vec(&entry).storage("Failed to encode rating entry")?)
+            .storage("Failed to append to rating log")?;
+        file.sync_data().storage("Failed to append to rating log")?;
+
+        tracing::info!(rating = after.rating, delta = entry.delta(), "Rating updated");
+        self.entries.push(entry);
+        Ok(self.entries.last())
+    }
+}
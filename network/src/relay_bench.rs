@@ -0,0 +1,303 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Benchmark game between two local peers that only reach each other
+//! through one relay.
+//!
+//! [`run`] starts two contexts with direct dialing off, connects them and
+//! plays a scripted game over the relay, each side feeding what it hears
+//! into its own [`GameChannel`] and acknowledging moves as players do. A
+//! move that leaves the receiver behind makes it ask for a sync. The
+//! [`BenchReport`] gives the setup time, how long moves took to arrive,
+//! the syncs and the bytes sent through the relay, so relay operators can
+//! check their node helps and script the check with a latency limit.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use tokio::sync::mpsc;
+use p2pgo_core::{Coord, GameState, Move};
+use crate::game_channel::InboundOutcome;
+use crate::iroh_endpoint::TransportOptions;
+use crate::rate_limit::RateLimitConfig;
+use crate::wire::DirectMessage;
+use crate::{Error, GameChannel, IrohCtx, Result};
+
+/// Moves of the scripted game
+pub const BENCH_MOVES: usize = 60;
+
+/// Board the scripted game is played on
+pub const BENCH_BOARD_SIZE: u8 = 9;
+
+/// How long a move may take to arrive before the benchmark fails
+pub const MOVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Messages a peer keeps while it is busy sending
+const SUBSCRIPTION_BUFFER: usize = 256;
+
+/// The scripted game comes as fast as the relay carries it, far faster
+/// than a person plays, so neither side screens moves
+const BENCH_RATE_LIMIT: RateLimitConfig = RateLimitConfig {
+    max_moves: u32::MAX,
+    window: Duration::from_secs(1),
+    mute_for: Duration::ZERO,
+    max_offenses: u32::MAX,
+    implausible_gap: Duration::ZERO,
+    implausible_streak: u32::MAX,
+};
+
+/// What to benchmark
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchOptions {
+    /// Relay the peers go through: a relay URL, or a loopback relay's
+    /// address without iroh
+    pub relay: String,
+    /// Moves to play, at most [`BENCH_MOVES`]
+    pub moves: usize,
+    pub move_timeout: Duration,
+}
+
+impl BenchOptions {
+    /// The full scripted game through `relay`
+    pub fn new(relay: impl Into<String>) -> Self {
+        Self { relay: relay.into(), moves: BENCH_MOVES, move_timeout: MOVE_TIMEOUT }
+    }
+}
+
+/// How long moves took from being sent to being applied by the other peer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Statistics of `samples`, all zero if there are none
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        let mut ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        ms.sort_by(f64::total_cmp);
+        let percentile = |p: f64| match ms.len() {
+            0 => 0.0,
+            n => ms[((n as f64 * p).ceil() as usize).clamp(1, n) - 1],
+        };
+        Self {
+            samples: ms.len(),
+            min_ms: ms.first().copied().unwrap_or(0.0),
+            mean_ms: if ms.is_empty() { 0.0 } else { ms.iter().sum::<f64>() / ms.len() as f64 },
+            p50_ms: percentile(0.50),
+            p95_ms: percentile(0.95),
+            max_ms: ms.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+/// Outcome of a benchmark game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub relay: String,
+    /// From starting the peers to both being on the game topic
+    pub setup_ms: f64,
+    pub moves: usize,
+    pub latency: LatencyStats,
+    /// Syncs a peer asked for after falling behind
+    pub sync_events: u64,
+    /// Bytes the peers sent through the relay
+    pub bytes_relayed: u64,
+}
+
+impl BenchReport {
+    /// Whether move latency stayed within `max_p95` for 95% of moves
+    pub fn within(&self, max_p95: Duration) -> bool {
+        self.latency.p95_ms <= max_p95.as_secs_f64() * 1000.0
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| Error::internal(format!("Failed to encode bench report: {}", e)))
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Relay:          {}", self.relay)?;
+        writeln!(f, "Setup:          {:.1} ms", self.setup_ms)?;
+        writeln!(f, "Moves:          {}", self.moves)?;
+        writeln!(
+            f,
+            "Latency:        min {:.1} ms, mean {:.1} ms, p50 {:.1} ms, p95 {:.1} ms, max {:.1} ms",
+            self.latency.min_ms, self.latency.mean_ms, self.latency.p50_ms, self.latency.p95_ms, self.latency.max_ms,
+        )?;
+        writeln!(f, "Sync events:    {}", self.sync_events)?;
+        write!(f, "Bytes relayed:  {}", self.bytes_relayed)
+    }
+}
+
+/// The first `moves` moves of the scripted game. Each side fills its own
+/// end of the board with a free row between them, so nothing is captured.
+pub fn script(moves: usize) -> Vec<Move> {
+    let size = BENCH_BOARD_SIZE as usize;
+    (0..moves.min(BENCH_MOVES))
+        .map(|index| {
+            let own = index / 2;
+            let (x, row) = ((own % size) as u8, (own / size) as u8);
+            let y = if index % 2 == 0 { row } else { BENCH_BOARD_SIZE - 1 - row };
+            Move::Place(Coord::new(x, y))
+        })
+        .collect()
+}
+
+/// Play the scripted game between two peers forced through `options.relay`
+#[tracing::instrument(level = "debug")]
+pub async fn run(options: &BenchOptions) -> Result<BenchReport> {
+    let game_id = format!("bench-{}", uuid::Uuid::new_v4().simple());
+    let started = Instant::now();
+    let mut host = Peer::start(&options.relay, &game_id).await?;
+    let mut guest = Peer::start(&options.relay, &game_id).await?;
+    guest.ctx.connect_by_ticket(&host.ctx.ticket().await?).await?;
+    let setup = started.elapsed();
+
+    let mut tally = Tally::default();
+    let mut latencies = Vec::with_capacity(options.moves);
+    for (index, mv) in script(options.moves).into_iter().enumerate() {
+        let (mover, receiver) = if index % 2 == 0 { (&mut host, &mut guest) } else { (&mut guest, &mut host) };
+        mover.channel.send_move(mv).await?;
+        let sent = Instant::now();
+        tokio::time::timeout(options.move_timeout, deliver(mover, receiver, index + 1, &mut tally))
+            .await
+            .map_err(|_| Error::Timeout { what: "a move through the relay", after: options.move_timeout })??;
+        latencies.push(sent.elapsed());
+    }
+
+    let report = BenchReport {
+        relay: options.relay.clone(),
+        setup_ms: setup.as_secs_f64() * 1000.0,
+        moves: latencies.len(),
+        latency: LatencyStats::from_samples(&latencies),
+        sync_events: tally.sync_events,
+        bytes_relayed: tally.bytes,
+    };
+    tracing::info!("Relay benchmark of {}: p95 {:.1} ms", report.relay, report.latency.p95_ms);
+    Ok(report)
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    sync_events: u64,
+    bytes: u64,
+}
+
+/// One message on the game topic, with the node that sent it
+#[derive(Serialize, Deserialize)]
+struct Frame {
+    from: String,
+    message: DirectMessage,
+}
+
+#[cfg(feature = "iroh")]
+type Subscription = mpsc::Receiver<iroh_gossip::net::Event>;
+
+#[cfg(not(feature = "iroh"))]
+type Subscription = mpsc::Receiver<Vec<u8>>;
+
+/// One side of the benchmark game
+struct Peer {
+    ctx: IrohCtx,
+    channel: GameChannel,
+    subscription: Subscription,
+    game_id: String,
+}
+
+impl Peer {
+    async fn start(relay: &str, game_id: &str) -> Result<Self> {
+        let ctx = IrohCtx::with_transport(TransportOptions::relay_only(relay)).await?;
+        let subscription = ctx.subscribe_game_topic(game_id, SUBSCRIPTION_BUFFER).await?;
+        let channel = GameChannel::new(game_id.to_string(), GameState::new(BENCH_BOARD_SIZE));
+        channel.set_rate_limit(BENCH_RATE_LIMIT).await;
+        Ok(Self { ctx, channel, subscription, game_id: game_id.to_string() })
+    }
+
+    /// Send `message` to the other peer, returning how many bytes that took
+    async fn send(&self, message: DirectMessage) -> Result<u64> {
+        let frame = Frame { from: self.ctx.node_id().to_string(), message };
+        let bytes = serde_cbor::to_vec(&frame).map_err(|e| Error::internal(format!("Failed to encode bench message: {}", e)))?;
+        self.ctx.broadcast_to_topic(IrohCtx::game_topic(&self.game_id), &bytes).await?;
+        Ok(bytes.len() as u64)
+    }
+
+    /// The next message from the other peer
+    async fn recv(&mut self) -> Result<(String, DirectMessage)> {
+        loop {
+            let bytes = next_payload(&mut self.subscription).await
+                .ok_or_else(|| Error::internal("Relay subscription ended"))?;
+            match serde_cbor::from_slice::<Frame>(&bytes) {
+                // The topic hands us our own messages too
+                Ok(frame) if frame.from == self.ctx.node_id() => {}
+                Ok(frame) => return Ok((frame.from, frame.message)),
+                Err(e) => tracing::debug!("Ignoring a message the benchmark didn't send: {}", e),
+            }
+        }
+    }
+
+    async fn moves(&self) -> usize {
+        self.channel.get_latest_state().await.map_or(0, |state| state.moves.len())
+    }
+}
+
+/// Send the mover's last move until the receiver has played `moves` moves,
+/// syncing the receiver whenever a move leaves it behind
+async fn deliver(mover: &mut Peer, receiver: &mut Peer, moves: usize, tally: &mut Tally) -> Result<()> {
+    let record = mover.channel.sync_moves().await.pop()
+        .ok_or_else(|| Error::internal("The move just played is missing"))?;
+    tally.bytes += mover.send(DirectMessage::Move(record)).await?;
+    loop {
+        let (from, message) = receiver.recv().await?;
+        let was_move = matches!(message, DirectMessage::Move(_));
+        if let InboundOutcome::Reply(ack @ DirectMessage::Ack { .. }) = receiver.channel.receive_direct(&from, message).await {
+            tally.bytes += receiver.send(ack).await?;
+        }
+        if receiver.moves().await >= moves {
+            return Ok(());
+        }
+        if was_move {
+            tally.sync_events += 1;
+            tally.bytes += receiver.send(DirectMessage::SyncRequest).await?;
+            serve_sync(mover, tally).await?;
+        }
+    }
+}
+
+/// Answer the sync request the other peer sent, past its acknowledgements
+async fn serve_sync(peer: &mut Peer, tally: &mut Tally) -> Result<()> {
+    loop {
+        let (from, message) = peer.recv().await?;
+        let was_request = matches!(message, DirectMessage::SyncRequest);
+        match peer.channel.receive_direct(&from, message).await {
+            InboundOutcome::Reply(response @ DirectMessage::SyncResponse { .. }) => {
+                tally.bytes += peer.send(response).await?;
+                return Ok(());
+            }
+            _ if was_request => return Err(Error::internal("No moves to sync")),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "iroh")]
+async fn next_payload(subscription: &mut Subscription) -> Option<Vec<u8>> {
+    use crate::gossip_compat::{extract_bytes, is_received_message};
+    loop {
+        let event = subscription.recv().await?;
+        if is_received_message(&event) {
+            if let Some(bytes) = extract_bytes(&event) {
+                return Some(bytes);
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "iroh"))]
+async fn next_payload(subscription: &mut Subscription) -> Option<Vec<u8>> {
+    subscription.recv().await
+}
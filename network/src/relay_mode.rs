@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! How much a node does for other players.
+//!
+//! Besides the connections its own games need, a node can serve gossip,
+//! document and blob connections for peers it isn't playing. A
+//! [`RelayPreset`] names a budget for all of its incoming connections: a
+//! player's covers little more than its own games and lobby, a
+//! contributor's leaves room for others while the app is open, and a
+//! dedicated node's matches the headless relay's defaults. The budget is
+//! enforced by the endpoint's [`crate::admission::Admission`]; switching
+//! preset applies to the next connection and drains the newest ones a
+//! smaller budget has no room for.
+
+use serde::{Serialize, Deserialize};
+use crate::admission::ConnectionLimits;
+
+/// Incoming connections a player accepts: its games, the lobby and a few
+/// spectators
+pub const PLAYER_MAX_CONNECTIONS: u32 = 16;
+
+/// Bandwidth a player spends on incoming connections
+pub const PLAYER_MAX_BANDWIDTH_BPS: u64 = 256 * 1024;
+
+/// Incoming connections a contributor accepts
+pub const CONTRIBUTOR_MAX_CONNECTIONS: u32 = 64;
+
+/// Bandwidth a contributor spends on incoming connections, about 8 Mbps
+pub const CONTRIBUTOR_MAX_BANDWIDTH_BPS: u64 = 1024 * 1024;
+
+/// How much a node serves other players
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum RelayPreset {
+    /// Serve only what our own games need
+    #[default]
+    Player,
+    /// Serve a few other players while the app is open
+    Contributor,
+    /// Serve as much as a headless relay node
+    Dedicated,
+}
+
+impl RelayPreset {
+    pub const ALL: [RelayPreset; 3] = [RelayPreset::Player, RelayPreset::Contributor, RelayPreset::Dedicated];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RelayPreset::Player => "Player",
+            RelayPreset::Contributor => "Contributor",
+            RelayPreset::Dedicated => "Dedicated relay",
+        }
+    }
+
+    /// One line for the settings
+    pub fn description(self) -> &'static str {
+        match self {
+            RelayPreset::Player => "Only accept connections for your own games",
+            RelayPreset::Contributor => "Help other players' games along while the app is open",
+            RelayPreset::Dedicated => "Accept as many connections as a headless relay node",
+        }
+    }
+
+    /// The preset that keeps to whether the player agreed to relay: none
+    /// beyond a player's without consent, a contributor's when it is given
+    pub fn with_participation(self, relay: bool) -> RelayPreset {
+        match (self, relay) {
+            (_, false) => RelayPreset::Player,
+            (RelayPreset::Player, true) => RelayPreset::Contributor,
+            (preset, true) => preset,
+        }
+    }
+
+    /// The connection budget the endpoint enforces
+    pub fn limits(self) -> ConnectionLimits {
+        match self {
+            RelayPreset::Player => ConnectionLimits {
+                max_connections: PLAYER_MAX_CONNECTIONS,
+                max_bandwidth_bps: PLAYER_MAX_BANDWIDTH_BPS,
+            },
+            RelayPreset::Contributor => ConnectionLimits {
+                max_connections: CONTRIBUTOR_MAX_CONNECTIONS,
+                max_bandwidth_bps: CONTRIBUTOR_MAX_BANDWIDTH_BPS,
+            },
+            RelayPreset::Dedicated => ConnectionLimits::default(),
+        }
+    }
+}
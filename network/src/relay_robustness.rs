@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Clock skew between peers.
+//!
+//! Move records carry the sender's wall-clock seconds. Each peer's hello and
+//! moves give a sample of how far its clock is from ours; the median of the
+//! recent samples is the offset used to bring its timestamps onto our clock.
+//! Network delay shows up in every sample, but is small next to the skew
+//! that matters here.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use serde::{Serialize, Deserialize};
+
+/// Offset beyond which a peer's clock is reported, in seconds
+pub const SKEW_WARNING_SECS: i64 = 5 * 60;
+
+/// Samples per peer the offset is the median of
+pub const OFFSET_SAMPLES: usize = 8;
+
+/// Our wall clock in unix seconds
+pub fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A peer whose clock was found off by more than [`SKEW_WARNING_SECS`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockSkew {
+    pub peer: String,
+    /// Seconds the peer's clock is ahead of ours; negative if behind
+    pub offset_secs: i64,
+}
+
+impl fmt::Display for ClockSkew {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.offset_secs >= 0 { "ahead" } else { "behind" };
+        let secs = self.offset_secs.unsigned_abs();
+        write!(f, "Opponent's clock is {}m {}s {} of ours", secs / 60, secs % 60, direction)
+    }
+}
+
+#[derive(Debug, Default)]
+struct PeerClock {
+    samples: VecDeque<i64>,
+    warned: bool,
+}
+
+impl PeerClock {
+    fn offset(&self) -> i64 {
+        let mut sorted: Vec<i64> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        sorted.get(sorted.len() / 2).copied().unwrap_or(0)
+    }
+}
+
+/// Clock offsets of the peers of one game
+#[derive(Debug, Default)]
+pub struct PeerClocks {
+    peers: HashMap<String, PeerClock>,
+    skews: Vec<ClockSkew>,
+}
+
+impl PeerClocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `peer` stamped `theirs` on a message we received at
+    /// `ours`. Returns the skew the first time the peer's offset exceeds
+    /// [`SKEW_WARNING_SECS`].
+    pub fn sample(&mut self, peer: &str, theirs: u64, ours: u64) -> Option<ClockSkew> {
+        // Zero means the sender didn't stamp the message
+        if theirs == 0 {
+            return None;
+        }
+        let clock = self.peers.entry(peer.to_string()).or_default();
+        if clock.samples.len() == OFFSET_SAMPLES {
+            clock.samples.pop_front();
+        }
+        clock.samples.push_back(theirs as i64 - ours as i64);
+
+        let offset_secs = clock.offset();
+        if clock.warned || offset_secs.abs() <= SKEW_WARNING_SECS {
+            return None;
+        }
+        clock.warned = true;
+        let skew = ClockSkew { peer: peer.to_string(), offset_secs };
+        self.skews.push(skew.clone());
+        Some(skew)
+    }
+
+    /// Seconds `peer`'s clock is ahead of ours, zero until it sent a timestamp
+    pub fn offset(&self, peer: &str) -> i64 {
+        self.peers.get(peer).map_or(0, PeerClock::offset)
+    }
+
+    /// `peer`'s timestamp `ts` on our clock; zero stays zero
+    pub fn to_local(&self, peer: &str, ts: u64) -> u64 {
+        if ts == 0 {
+            return 0;
+        }
+        (ts as i64 - self.offset(peer)).max(1) as u64
+    }
+
+    /// Peers reported for skew so far, oldest first
+    pub fn skews(&self) -> &[ClockSkew] {
+        &self.skews
+    }
+
+    /// Forget a peer's samples, e.g. after its connection closed. A skew
+    /// already reported stays reported.
+    pub fn remove_peer(&mut self, peer: &str) {
+        if let Some(clock) = self.peers.get_mut(peer) {
+            clock.samples.clear();
+        }
+    }
+}
@@ -0,0 +1,359 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checks on what peers send before any of it is kept or shown.
+//!
+//! Adverts, hellos, synced states, join requests and chat come from
+//! arbitrary peers. Text is capped in length and may not hold control or
+//! bidi override characters; board sizes, komi and handicaps must be ones
+//! a game can be played with. Anything else is refused whole, counted in
+//! [`REJECTED`] for the network panel and logged at most every
+//! [`LOG_INTERVAL_SECS`].
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::teaching::TeachingAnnotation;
+use crate::join::{JoinMessage, JoinResponse, PlayerProfile};
+use crate::lobby::{GameAdvert, GameTerms};
+use crate::wire::DirectMessage;
+
+/// Board sizes games are played on
+pub const BOARD_SIZES: [u8; 3] = [9, 13, 19];
+
+/// Largest komi either way
+pub const MAX_KOMI: f32 = 50.0;
+
+/// Most handicap stones a game is given
+pub const MAX_HANDICAP: u8 = 9;
+
+/// Shortest time between two logged rejections
+pub const LOG_INTERVAL_SECS: u64 = 10;
+
+/// Text a peer sends, with how long it may be
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    DisplayName,
+    Guild,
+    NodeId,
+    GameId,
+    GameName,
+    Chat,
+    Comment,
+}
+
+impl TextField {
+    /// Characters allowed
+    pub fn max_len(self) -> usize {
+        match self {
+            TextField::DisplayName | TextField::Guild => 32,
+            TextField::GameName => 64,
+            TextField::NodeId | TextField::GameId => 128,
+            TextField::Chat => 500,
+            TextField::Comment => 2000,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TextField::DisplayName => "display name",
+            TextField::Guild => "guild",
+            TextField::NodeId => "node ID",
+            TextField::GameId => "game ID",
+            TextField::GameName => "game name",
+            TextField::Chat => "chat message",
+            TextField::Comment => "comment",
+        }
+    }
+
+    /// Whether line breaks are part of the text
+    fn multiline(self) -> bool {
+        matches!(self, TextField::Chat | TextField::Comment)
+    }
+}
+
+impl fmt::Display for TextField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Why something from a peer was refused
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum Invalid {
+    #[error("{field} of {len} characters exceeds the {max} allowed")]
+    TooLong { field: TextField, len: usize, max: usize },
+
+    #[error("{field} is empty")]
+    Empty { field: TextField },
+
+    #[error("{field} holds control characters")]
+    ControlChars { field: TextField },
+
+    #[error("Board size {0} is not one of 9, 13 or 19")]
+    BoardSize(u8),
+
+    #[error("Komi {0} is out of range")]
+    Komi(f32),
+
+    #[error("Handicap of {0} stones is out of range")]
+    Handicap(u8),
+}
+
+/// Kinds of rejection, counted apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RejectKind {
+    /// Text too long or empty
+    Length,
+    /// Text holding control or bidi characters
+    Characters,
+    BoardSize,
+    /// Komi or handicap
+    Terms,
+}
+
+impl RejectKind {
+    pub const ALL: [RejectKind; 4] = [
+        RejectKind::Length,
+        RejectKind::Characters,
+        RejectKind::BoardSize,
+        RejectKind::Terms,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RejectKind::Length => "length",
+            RejectKind::Characters => "characters",
+            RejectKind::BoardSize => "board size",
+            RejectKind::Terms => "komi/handicap",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl Invalid {
+    pub fn kind(&self) -> RejectKind {
+        match self {
+            Invalid::TooLong { .. } | Invalid::Empty { .. } => RejectKind::Length,
+            Invalid::ControlChars { .. } => RejectKind::Characters,
+            Invalid::BoardSize(_) => RejectKind::BoardSize,
+            Invalid::Komi(_) | Invalid::Handicap(_) => RejectKind::Terms,
+        }
+    }
+}
+
+/// Rejections since startup, one count per kind
+#[derive(Debug)]
+pub struct RejectionCounters {
+    counts: [AtomicU64; 4],
+    /// Unix seconds of the last logged rejection
+    logged_at: AtomicU64,
+    /// Rejections not logged since then
+    unlogged: AtomicU64,
+}
+
+impl Default for RejectionCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RejectionCounters {
+    pub const fn new() -> Self {
+        Self {
+            counts: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+            logged_at: AtomicU64::new(0),
+            unlogged: AtomicU64::new(0),
+        }
+    }
+
+    /// Count `invalid`, from `what`, logging it unless another rejection
+    /// was logged in the last [`LOG_INTERVAL_SECS`]
+    pub fn record(&self, what: &str, invalid: &Invalid) {
+        self.counts[invalid.kind().index()].fetch_add(1, Ordering::Relaxed);
+        let now = crate::relay_robustness::now_secs();
+        let logged_at = self.logged_at.load(Ordering::Relaxed);
+        let due = now >= logged_at + LOG_INTERVAL_SECS
+            && self.logged_at.compare_exchange(logged_at, now, Ordering::Relaxed, Ordering::Relaxed).is_ok();
+        if !due {
+            self.unlogged.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        match self.unlogged.swap(0, Ordering::Relaxed) {
+            0 => tracing::warn!("Dropped {} from a peer: {}", what, invalid),
+            more => tracing::warn!("Dropped {} from a peer: {} ({} more since the last warning)", what, invalid, more),
+        }
+    }
+
+    pub fn counts(&self) -> RejectionCounts {
+        RejectionCounts { counts: std::array::from_fn(|i| self.counts[i].load(Ordering::Relaxed)) }
+    }
+}
+
+/// Process-wide counters the checks record into
+pub static REJECTED: RejectionCounters = RejectionCounters::new();
+
+/// Rejections since startup, as the network panel shows them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RejectionCounts {
+    /// Counts in [`RejectKind::ALL`] order
+    pub counts: [u64; 4],
+}
+
+impl RejectionCounts {
+    pub fn get(&self, kind: RejectKind) -> u64 {
+        self.counts[kind.index()]
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+}
+
+/// `result` of checking `what`, counted in [`REJECTED`] if it failed
+fn counted<T>(what: &str, result: Result<T, Invalid>) -> Result<T, Invalid> {
+    if let Err(invalid) = &result {
+        REJECTED.record(what, invalid);
+    }
+    result
+}
+
+/// Whether `c` may appear in text from a peer
+fn allowed_char(c: char, multiline: bool) -> bool {
+    // Bidi overrides and isolates can make text read as something else
+    let bidi = matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}');
+    (multiline && c == '\n') || !(c.is_control() || bidi)
+}
+
+/// Text a peer sent as `field`
+pub fn check_text(field: TextField, text: &str) -> Result<(), Invalid> {
+    let len = text.chars().count();
+    if len > field.max_len() {
+        return Err(Invalid::TooLong { field, len, max: field.max_len() });
+    }
+    if text.trim().is_empty() {
+        return Err(Invalid::Empty { field });
+    }
+    if !text.chars().all(|c| allowed_char(c, field.multiline())) {
+        return Err(Invalid::ControlChars { field });
+    }
+    Ok(())
+}
+
+pub fn check_board_size(board_size: u8) -> Result<(), Invalid> {
+    if !BOARD_SIZES.contains(&board_size) {
+        return Err(Invalid::BoardSize(board_size));
+    }
+    Ok(())
+}
+
+fn check_komi_and_handicap(komi: f32, handicap: u8) -> Result<(), Invalid> {
+    if !komi.is_finite() || komi.abs() > MAX_KOMI {
+        return Err(Invalid::Komi(komi));
+    }
+    if handicap > MAX_HANDICAP {
+        return Err(Invalid::Handicap(handicap));
+    }
+    Ok(())
+}
+
+pub fn check_settings(settings: &GameSettings) -> Result<(), Invalid> {
+    check_board_size(settings.board_size)?;
+    check_komi_and_handicap(settings.komi, settings.handicap)
+}
+
+pub fn check_terms(terms: &GameTerms) -> Result<(), Invalid> {
+    check_komi_and_handicap(terms.komi, terms.handicap)
+}
+
+/// A game advert from gossip
+pub fn check_advert(advert: &GameAdvert) -> Result<(), Invalid> {
+    counted("game advert", advert_fields(advert))
+}
+
+fn advert_fields(advert: &GameAdvert) -> Result<(), Invalid> {
+    check_text(TextField::GameId, &advert.gid)?;
+    check_board_size(advert.size)?;
+    check_text(TextField::NodeId, &advert.host)?;
+    if let Some(teacher) = &advert.teacher {
+        check_text(TextField::NodeId, teacher)?;
+    }
+    if let Some(terms) = &advert.terms {
+        check_terms(terms)?;
+    }
+    Ok(())
+}
+
+/// A game listed under an ID and name that may come from a peer, as
+/// with matchmaking
+pub fn check_listing(game_id: &str, name: Option<&str>, board_size: u8) -> Result<(), Invalid> {
+    counted("game listing", listing_fields(game_id, name, board_size))
+}
+
+fn listing_fields(game_id: &str, name: Option<&str>, board_size: u8) -> Result<(), Invalid> {
+    check_text(TextField::GameId, game_id)?;
+    if let Some(name) = name {
+        check_text(TextField::GameName, name)?;
+    }
+    check_board_size(board_size)
+}
+
+/// The profile a peer asks to join with
+pub fn check_profile(profile: &PlayerProfile) -> Result<(), Invalid> {
+    counted("join request", profile_fields(profile))
+}
+
+fn profile_fields(profile: &PlayerProfile) -> Result<(), Invalid> {
+    check_text(TextField::NodeId, &profile.node_id)?;
+    check_text(TextField::DisplayName, &profile.name)?;
+    if let Some(guild) = &profile.guild {
+        check_text(TextField::Guild, guild)?;
+    }
+    Ok(())
+}
+
+/// A join request or answer gossiped by a peer
+pub fn check_join(msg: &JoinMessage) -> Result<(), Invalid> {
+    counted("join message", join_fields(msg))
+}
+
+fn join_fields(msg: &JoinMessage) -> Result<(), Invalid> {
+    match msg {
+        JoinMessage::Request { game_id, profile, .. } => {
+            check_text(TextField::GameId, game_id)?;
+            profile_fields(profile)
+        }
+        JoinMessage::Response { game_id, to, response, .. } => {
+            check_text(TextField::GameId, game_id)?;
+            check_text(TextField::NodeId, to)?;
+            match response {
+                JoinResponse::Accepted { settings, .. } => check_settings(settings),
+                JoinResponse::Declined { reason } | JoinResponse::SpectateOffered { reason } => {
+                    check_text(TextField::Chat, reason)
+                }
+            }
+        }
+    }
+}
+
+/// A chat message before it reaches the game
+pub fn check_chat(message: &str) -> Result<(), Invalid> {
+    counted("chat message", check_text(TextField::Chat, message))
+}
+
+/// A message read from a direct stream
+pub fn check_message(msg: &DirectMessage) -> Result<(), Invalid> {
+    let result = match msg {
+        DirectMessage::Hello { settings: Some(settings), .. } => check_settings(settings),
+        DirectMessage::SyncResponse { state, .. } => check_board_size(state.board_size),
+        DirectMessage::Annotation { annotation: TeachingAnnotation::Comment(text), .. } => {
+            check_text(TextField::Comment, text)
+        }
+        _ => Ok(()),
+    };
+    counted("direct message", result)
+}
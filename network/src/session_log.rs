@@ -0,0 +1,305 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Recording a session and replaying it, to reproduce sync bugs.
+//!
+//! While recording, every input that reaches a game channel is appended to
+//! a `.p2plog` file as a line of JSON: games opened, our moves, the
+//! player's other commands, messages from peers and gossip, and the
+//! worker's timer ticks. Each entry carries when it
+//! happened and the game's moves afterwards. [`replay`] feeds a log to
+//! fresh channels on a [`VirtualClock`] and checks the moves agree at
+//! every step.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Serialize, Deserialize};
+use p2pgo_core::{Color, GameState, Move};
+use p2pgo_core::teaching::TeachingAnnotation;
+use p2pgo_core::value_labeller::ScoreProof;
+use crate::clock::VirtualClock;
+use crate::game_channel::GameChannel;
+use crate::relay_robustness::now_secs;
+use crate::wire::DirectMessage;
+use crate::{Error, GameId, Result};
+use crate::error::StorageContext;
+
+/// Version written in the first line of a log
+pub const LOG_VERSION: u32 = 1;
+
+/// First line of a log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogHeader {
+    pub version: u32,
+    /// Wall clock when recording started, in unix seconds
+    pub started_unix: u64,
+}
+
+/// Something that happened to a game channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SessionInput {
+    /// A channel was opened on this position
+    Open { state: GameState },
+    /// We played a move
+    Move { mv: Move },
+    /// A peer sent a message on the game's direct stream, or a move on
+    /// its gossip topic
+    Inbound { peer: String, message: DirectMessage },
+    /// The player did something to the game other than move
+    Command(Command),
+    /// The worker's timer fired; for a game, its clock was sent to peers
+    Tick,
+}
+
+/// What the player can do to a game through the UI besides moving
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Command {
+    SetTeacher { teacher: Option<String> },
+    Annotate { from: String, annotation: TeachingAnnotation },
+    RequestUndo { moves: u8 },
+    AnswerUndo { accept: bool },
+    AcceptScore { by: Color, proof: ScoreProof },
+}
+
+impl Command {
+    /// Give the command to `channel` as the player did
+    async fn apply(&self, channel: &GameChannel) -> Result<()> {
+        match self {
+            Command::SetTeacher { teacher } => {
+                channel.set_teacher(teacher.clone()).await;
+                Ok(())
+            }
+            Command::Annotate { from, annotation } => channel.annotate(from, annotation.clone()).await,
+            Command::RequestUndo { moves } => channel.request_undo(*moves).await,
+            Command::AnswerUndo { accept } => channel.answer_undo(*accept).await,
+            Command::AcceptScore { by, proof } => channel.accept_score(*by, proof.clone()).await,
+        }
+    }
+}
+
+impl fmt::Display for SessionInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionInput::Open { .. } => write!(f, "game opened"),
+            SessionInput::Move { mv } => write!(f, "our move {:?}", mv),
+            SessionInput::Inbound { peer, message } => {
+                let kind = match message {
+                    DirectMessage::Hello { .. } => "Hello",
+                    DirectMessage::Move(_) => "Move",
+                    DirectMessage::SyncRequest => "SyncRequest",
+                    DirectMessage::SyncResponse { .. } => "SyncResponse",
+                    DirectMessage::Ack { .. } => "Ack",
+                    DirectMessage::Annotation { .. } => "Annotation",
+                    DirectMessage::Presence(_) => "Presence",
+                    DirectMessage::ClockTick(_) => "ClockTick",
+                    DirectMessage::UndoRequest { .. } => "UndoRequest",
+                    DirectMessage::UndoResponse { .. } => "UndoResponse",
+                    DirectMessage::SignResult(_) => "SignResult",
+                    DirectMessage::ResultSigned(_) => "ResultSigned",
+                    DirectMessage::ScoreAccepted { .. } => "ScoreAccepted",
+                };
+                write!(f, "{} from {}", kind, peer)
+            }
+            SessionInput::Command(command) => match command {
+                Command::SetTeacher { .. } => write!(f, "teacher set"),
+                Command::Annotate { .. } => write!(f, "our annotation"),
+                Command::RequestUndo { moves } => write!(f, "our request to undo {}", moves),
+                Command::AnswerUndo { accept } => write!(f, "our undo answer {}", accept),
+                Command::AcceptScore { by, .. } => write!(f, "score accepted by {:?}", by),
+            },
+            SessionInput::Tick => write!(f, "timer tick"),
+        }
+    }
+}
+
+/// One line of a log after the header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// Milliseconds since recording started
+    pub at_ms: u64,
+    /// The game the input was for; ticks belong to none
+    #[serde(default)]
+    pub game_id: Option<GameId>,
+    pub input: SessionInput,
+    /// The game's moves once the input was handled
+    #[serde(default)]
+    pub moves: Vec<Move>,
+}
+
+/// Appends entries to a log file
+pub struct SessionRecorder {
+    out: BufWriter<File>,
+    started: Instant,
+}
+
+impl SessionRecorder {
+    /// Create the log at `path` and write its header
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .storage(format!("Failed to create session log {}", path.display()))?;
+        let mut recorder = Self { out: BufWriter::new(file), started: Instant::now() };
+        let header = LogHeader { version: LOG_VERSION, started_unix: now_secs() };
+        recorder.write_line(&header)?;
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, game_id: Option<&str>, input: SessionInput, moves: &[Move]) -> Result<()> {
+        let entry = SessionEntry {
+            at_ms: self.started.elapsed().as_millis() as u64,
+            game_id: game_id.map(str::to_string),
+            input,
+            moves: moves.to_vec(),
+        };
+        self.write_line(&entry)
+    }
+
+    /// Write one line and flush, so a crash keeps everything before it
+    fn write_line<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        serde_json::to_writer(&mut self.out, value).storage("Failed to write session log")?;
+        self.out.write_all(b"\n").storage("Failed to write session log")?;
+        self.out.flush().storage("Failed to write session log")
+    }
+}
+
+/// The recorder of this process, if recording
+static RECORDER: Mutex<Option<SessionRecorder>> = Mutex::new(None);
+
+/// Record every game channel input of this process to `path`
+pub fn start_recording(path: &Path) -> Result<()> {
+    let recorder = SessionRecorder::create(path)?;
+    *RECORDER.lock().map_err(|_| Error::internal("Session recorder poisoned"))? = Some(recorder);
+    tracing::info!("Recording session to {}", path.display());
+    Ok(())
+}
+
+pub fn stop_recording() {
+    if let Ok(mut recorder) = RECORDER.lock() {
+        *recorder = None;
+    }
+}
+
+pub fn is_recording() -> bool {
+    matches!(RECORDER.lock().as_deref(), Ok(Some(_)))
+}
+
+/// Add an entry to the log if recording; a failed write stops the recording
+pub fn record(game_id: Option<&str>, input: SessionInput, moves: &[Move]) {
+    let Ok(mut guard) = RECORDER.lock() else {
+        return;
+    };
+    let Some(recorder) = guard.as_mut() else {
+        return;
+    };
+    if let Err(e) = recorder.record(game_id, input, moves) {
+        tracing::warn!("Stopped recording the session: {:#}", e);
+        *guard = None;
+    }
+}
+
+/// Record that the worker's timer fired
+pub fn record_tick() {
+    record(None, SessionInput::Tick, &[]);
+}
+
+/// A log read back
+#[derive(Debug, Clone)]
+pub struct SessionLog {
+    pub header: LogHeader,
+    pub entries: Vec<SessionEntry>,
+}
+
+impl SessionLog {
+    pub fn read(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .storage(format!("Failed to read session log {}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let Some((_, first)) = lines.next() else {
+            return Err(Error::malformed("session log", "empty log"));
+        };
+        let header: LogHeader = serde_json::from_str(first)
+            .map_err(|e| Error::malformed("session log", format!("Line 1 is not a log header: {}", e)))?;
+        if header.version != LOG_VERSION {
+            return Err(Error::malformed("session log", format!("Log version {} is not supported", header.version)));
+        }
+        let entries = lines
+            .map(|(number, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| Error::malformed("session log", format!("Line {} is not a log entry: {}", number + 1, e)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { header, entries })
+    }
+}
+
+/// How a replay ended, when every step matched the recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub steps: usize,
+    /// Moves of each game at the end
+    pub games: BTreeMap<GameId, Vec<Move>>,
+}
+
+/// Feed `log` to fresh game channels on a virtual clock, failing at the
+/// first step whose moves differ from the recording.
+///
+/// Replaying while recording would record the replay too.
+pub async fn replay(log: &SessionLog) -> Result<ReplayReport> {
+    let clock = Arc::new(VirtualClock::new(log.header.started_unix));
+    let mut games: HashMap<GameId, GameChannel> = HashMap::new();
+
+    for (step, entry) in log.entries.iter().enumerate() {
+        let step = step + 1;
+        clock.set(Duration::from_millis(entry.at_ms));
+        let Some(game_id) = &entry.game_id else {
+            // The worker's own ticks only move the clock
+            continue;
+        };
+
+        if let SessionInput::Open { state } = &entry.input {
+            let channel = GameChannel::new(game_id.clone(), state.clone()).with_clock(clock.clone());
+            games.insert(game_id.clone(), channel);
+        }
+        let Some(channel) = games.get(game_id) else {
+            return Err(Error::Replay { step, input: entry.input.to_string(), reason: format!("game {} was never opened", game_id) });
+        };
+        match &entry.input {
+            SessionInput::Open { .. } => {}
+            SessionInput::Tick => channel.send_clock_tick().await,
+            SessionInput::Move { mv } => channel
+                .send_move(mv.clone())
+                .await
+                .map_err(|e| Error::Replay { step, input: entry.input.to_string(), reason: format!("move refused: {}", e) })?,
+            SessionInput::Inbound { peer, message } => {
+                channel.receive_direct(peer, message.clone()).await;
+            }
+            SessionInput::Command(command) => command
+                .apply(channel)
+                .await
+                .map_err(|e| Error::Replay { step, input: entry.input.to_string(), reason: format!("command refused: {}", e) })?,
+        }
+
+        let moves = channel.get_latest_state().await.map(|s| s.moves).unwrap_or_default();
+        if moves != entry.moves {
+            return Err(Error::Replay {
+                step,
+                input: entry.input.to_string(),
+                reason: format!("game {} diverged; recorded {:?}, replayed {:?}", game_id, entry.moves, moves),
+            });
+        }
+    }
+
+    let mut report = ReplayReport { steps: log.entries.len(), games: BTreeMap::new() };
+    for (game_id, channel) in &games {
+        let moves = channel.get_latest_state().await.map(|s| s.moves).unwrap_or_default();
+        report.games.insert(game_id.clone(), moves);
+    }
+    Ok(report)
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Coarse move timestamps, so when a move was played can't fingerprint
+//! who played it.
+//!
+//! With [`TimingPrivacy`] on, a channel rounds the `ts` of the moves it
+//! sends down to [`TimingPrivacy::grain_secs`] and holds its first move
+//! back by a random jitter. What reads `MoveRecord::ts`, and why a grain
+//! of seconds does it no harm:
+//!
+//! - Clock skew ([`PeerClocks`](crate::relay_robustness::PeerClocks))
+//!   only warns past [`SKEW_WARNING_SECS`](crate::relay_robustness::SKEW_WARNING_SECS).
+//! - Duplicate moves and move order go by move index and `prev_hash`,
+//!   never by `ts`.
+//! - Move times in the post-game report come from when our worker saw
+//!   each move, not from the timestamps peers send.
+//! - Game clocks run on the keeper's clock snapshots; timed games skip
+//!   the jitter so it is never charged to the opponent.
+
+use std::time::Duration;
+
+/// Grain outgoing move timestamps are rounded down to by default
+pub const TIMESTAMP_GRAIN_SECS: u64 = 5;
+
+/// Longest the first move of a session is held back by default
+pub const FIRST_MOVE_JITTER: Duration = Duration::from_secs(2);
+
+/// How much timing a channel gives away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingPrivacy {
+    /// Outgoing move timestamps are multiples of this many seconds
+    pub grain_secs: u64,
+    /// The first move we send is delayed by up to this much
+    pub first_move_jitter: Duration,
+}
+
+impl Default for TimingPrivacy {
+    fn default() -> Self {
+        Self { grain_secs: TIMESTAMP_GRAIN_SECS, first_move_jitter: FIRST_MOVE_JITTER }
+    }
+}
+
+impl TimingPrivacy {
+    /// `ts` rounded down to the grain; zero, for unstamped, stays zero
+    pub fn quantize(&self, ts: u64) -> u64 {
+        if ts == 0 {
+            return 0;
+        }
+        let grain = self.grain_secs.max(1);
+        (ts - ts % grain).max(1)
+    }
+
+    /// A random delay of at most `first_move_jitter`
+    pub fn jitter(&self) -> Duration {
+        let max_ms = self.first_move_jitter.as_millis() as u64;
+        if max_ms == 0 {
+            return Duration::ZERO;
+        }
+        let mut bytes = [0u8; 8];
+        if let Err(e) = getrandom::getrandom(&mut bytes) {
+            tracing::debug!("No randomness for move jitter: {}", e);
+            return Duration::ZERO;
+        }
+        Duration::from_millis(u64::from_le_bytes(bytes) % (max_ms + 1))
+    }
+}
@@ -0,0 +1,494 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Published games: a tournament's feed of co-signed results.
+//!
+//! When a game played for a tournament ends, both players sign a
+//! [`ResultRecord`] naming the game by hash, the two players, the result
+//! and the hash of its score proof. The player who proposed it links it to
+//! the last record of the feed and publishes it on the tournament's topic.
+//! A [`TournamentLedger`] takes records only with both signatures and in
+//! chain order, and [`verify_dir`] checks a saved ledger again against the
+//! archived games it names. Standings are exported as CSV or JSON.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, RwLock};
+use p2pgo_core::{Color, GameState};
+use p2pgo_core::phase::GameResult;
+use p2pgo_core::value_labeller::ScoreProof;
+use p2pgo_core::cbor::migrate::{self, Versioned};
+use crate::archive::GameArchive;
+use crate::{GameId, Identity, Result};
+use crate::error::StorageContext;
+
+#[cfg(feature = "iroh")]
+use {
+    crate::IrohCtx,
+    iroh_gossip::proto::TopicId,
+};
+
+/// File a tournament's records are saved in, next to its archived games
+pub const RESULTS_FILE: &str = "results.cbor";
+
+/// Why a result record can't be signed or taken into a ledger
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RecordError {
+    #[error("Result is for tournament {0}")]
+    WrongTournament(String),
+    #[error("{0:?}'s signature is missing or doesn't match")]
+    BadSignature(Color),
+    #[error("Result doesn't follow the record before it")]
+    BrokenChain,
+    #[error("Game {0} already has a result")]
+    Duplicate(GameId),
+    #[error("Game {0} is not archived")]
+    MissingGame(GameId),
+    #[error("Archived game {0} is not the one signed")]
+    GameMismatch(GameId),
+    #[error("Archived result of {0} is not the one signed")]
+    ResultMismatch(GameId),
+    #[error("Only the players sign a result")]
+    NotAPlayer,
+}
+
+/// Hash naming a game by its board, setup stones and moves, whoever's
+/// copy it is; clocks and other extras don't count
+pub fn game_digest(state: &GameState) -> [u8; 32] {
+    let played = serde_cbor::to_vec(&(state.board_size, &state.setup, &state.moves))
+        .expect("moves always encode");
+    *blake3::hash(&played).as_bytes()
+}
+
+/// Hash of a score proof, as a result record names it
+pub fn proof_digest(proof: &ScoreProof) -> [u8; 32] {
+    *blake3::hash(&serde_cbor::to_vec(proof).expect("score proofs always encode")).as_bytes()
+}
+
+/// A game's result as both players signed it, linked into the feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultRecord {
+    pub tournament: String,
+    pub game_id: GameId,
+    /// [`game_digest`] of the game played
+    pub game_hash: [u8; 32],
+    /// Ed25519 public keys of the players
+    pub black: [u8; 32],
+    pub white: [u8; 32],
+    pub result: GameResult,
+    /// [`proof_digest`] of the score proof of a scored game
+    pub proof_hash: Option<[u8; 32]>,
+    pub black_signature: Vec<u8>,
+    pub white_signature: Vec<u8>,
+    /// Hash of the record before it on the feed (zero for the first);
+    /// set when published, so not signed
+    pub prev_hash: [u8; 32],
+}
+
+impl Versioned for ResultRecord {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "tournament result";
+}
+
+impl ResultRecord {
+    /// An unsigned record of `state` ending with `result`
+    pub fn new(
+        tournament: &str,
+        game_id: &GameId,
+        state: &GameState,
+        players: ([u8; 32], [u8; 32]),
+        result: GameResult,
+        proof: Option<&ScoreProof>,
+    ) -> Self {
+        Self {
+            tournament: tournament.to_string(),
+            game_id: game_id.clone(),
+            game_hash: game_digest(state),
+            black: players.0,
+            white: players.1,
+            result,
+            proof_hash: proof.map(proof_digest),
+            black_signature: Vec::new(),
+            white_signature: Vec::new(),
+            prev_hash: [0; 32],
+        }
+    }
+
+    /// Bytes covered by both signatures
+    fn signed_bytes(&self) -> Vec<u8> {
+        let signed = (
+            "p2pgo-result",
+            &self.tournament,
+            &self.game_id,
+            self.game_hash,
+            self.black,
+            self.white,
+            &self.result,
+            self.proof_hash,
+        );
+        serde_cbor::to_vec(&signed).expect("result records always encode")
+    }
+
+    /// Sign as whichever player `identity` is
+    pub fn sign(&mut self, identity: &Identity) -> Result<Color, RecordError> {
+        let signature = identity.sign(&self.signed_bytes()).to_vec();
+        match identity.public_key() {
+            key if key == self.black => {
+                self.black_signature = signature;
+                Ok(Color::Black)
+            }
+            key if key == self.white => {
+                self.white_signature = signature;
+                Ok(Color::White)
+            }
+            _ => Err(RecordError::NotAPlayer),
+        }
+    }
+
+    /// Check one player's signature
+    pub fn verify_signature(&self, color: Color) -> Result<(), RecordError> {
+        let (key, signature) = match color {
+            Color::Black => (&self.black, &self.black_signature),
+            Color::White => (&self.white, &self.white_signature),
+        };
+        let verified = VerifyingKey::from_bytes(key).ok().zip(Signature::from_slice(signature).ok())
+            .is_some_and(|(key, signature)| key.verify(&self.signed_bytes(), &signature).is_ok());
+        if verified { Ok(()) } else { Err(RecordError::BadSignature(color)) }
+    }
+
+    /// Whether `other` records the same game and result, whoever signed it
+    pub fn same_result(&self, other: &ResultRecord) -> bool {
+        self.signed_bytes() == other.signed_bytes()
+    }
+
+    /// Check both signatures
+    pub fn verify_signatures(&self) -> Result<(), RecordError> {
+        self.verify_signature(Color::Black)?;
+        self.verify_signature(Color::White)
+    }
+
+    /// Whether `proof` is the one the players signed
+    pub fn matches_proof(&self, proof: &ScoreProof) -> bool {
+        self.proof_hash == Some(proof_digest(proof))
+    }
+
+    /// Check the record names `archive`'s game and result
+    pub fn check_archive(&self, archive: &GameArchive) -> Result<(), RecordError> {
+        if game_digest(&archive.final_state) != self.game_hash {
+            return Err(RecordError::GameMismatch(self.game_id.clone()));
+        }
+        if archive.winner != self.result.winner {
+            return Err(RecordError::ResultMismatch(self.game_id.clone()));
+        }
+        Ok(())
+    }
+
+    /// BLAKE3 hash of the CBOR-encoded record, signatures and link included
+    pub fn hash(&self) -> [u8; 32] {
+        *blake3::hash(&serde_cbor::to_vec(self).expect("result records always encode")).as_bytes()
+    }
+
+    /// Node ID of the player of `color`
+    pub fn player(&self, color: Color) -> String {
+        hex::encode(match color {
+            Color::Black => self.black,
+            Color::White => self.white,
+        })
+    }
+}
+
+/// One player's line of the standings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Standing {
+    /// Node ID
+    pub player: String,
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    /// Games without a winner, worth half a point
+    pub draws: u32,
+    pub points: f32,
+}
+
+/// A tournament's results, in the order they were published
+#[derive(Debug, Clone)]
+pub struct TournamentLedger {
+    tournament: String,
+    records: Vec<ResultRecord>,
+}
+
+impl TournamentLedger {
+    pub fn new(tournament: &str) -> Self {
+        Self { tournament: tournament.to_string(), records: Vec::new() }
+    }
+
+    pub fn tournament(&self) -> &str {
+        &self.tournament
+    }
+
+    pub fn records(&self) -> &[ResultRecord] {
+        &self.records
+    }
+
+    /// Hash the next record must link to
+    pub fn head(&self) -> [u8; 32] {
+        self.records.last().map_or([0; 32], ResultRecord::hash)
+    }
+
+    /// Link `record` to the head, ready to publish
+    pub fn link(&self, mut record: ResultRecord) -> ResultRecord {
+        record.prev_hash = self.head();
+        record
+    }
+
+    /// Take in a published record, refusing one not signed by both
+    /// players, not linked to the head or for a game already recorded
+    pub fn append(&mut self, record: ResultRecord) -> Result<(), RecordError> {
+        if record.tournament != self.tournament {
+            return Err(RecordError::WrongTournament(record.tournament));
+        }
+        if record.prev_hash != self.head() {
+            return Err(RecordError::BrokenChain);
+        }
+        record.verify_signatures()?;
+        if self.records.iter().any(|r| r.game_id == record.game_id) {
+            return Err(RecordError::Duplicate(record.game_id));
+        }
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Every player's results, most points first, then most wins
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut table: HashMap<String, Standing> = HashMap::new();
+        for record in &self.records {
+            for color in [Color::Black, Color::White] {
+                let player = record.player(color);
+                let standing = table.entry(player.clone()).or_insert_with(|| Standing {
+                    player,
+                    played: 0,
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                    points: 0.0,
+                });
+                standing.played += 1;
+                match record.result.winner {
+                    Some(winner) if winner == color => {
+                        standing.wins += 1;
+                        standing.points += 1.0;
+                    }
+                    Some(_) => standing.losses += 1,
+                    None => {
+                        standing.draws += 1;
+                        standing.points += 0.5;
+                    }
+                }
+            }
+        }
+        let mut standings: Vec<Standing> = table.into_values().collect();
+        standings.sort_by(|a, b| {
+            b.points.total_cmp(&a.points)
+                .then(b.wins.cmp(&a.wins))
+                .then_with(|| a.player.cmp(&b.player))
+        });
+        standings
+    }
+
+    /// The standings as CSV with a header line
+    pub fn standings_csv(&self) -> String {
+        let mut csv = String::from("player,played,wins,losses,draws,points\n");
+        for s in self.standings() {
+            csv.push_str(&format!("{},{},{},{},{},{}\n", s.player, s.played, s.wins, s.losses, s.draws, s.points));
+        }
+        csv
+    }
+
+    /// The standings as a JSON array
+    pub fn standings_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.standings())
+            .map_err(|e| crate::Error::internal(format!("Failed to encode standings: {}", e)))
+    }
+
+    /// Write the records to `path` as a CBOR sequence
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        for record in &self.records {
+            serde_cbor::to_writer(&mut bytes, record).storage("Failed to encode tournament results")?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, bytes).storage("Failed to write tournament results")?;
+        fs::rename(&tmp, path).storage("Failed to replace tournament results")?;
+        Ok(())
+    }
+}
+
+/// Records of a results file, oldest first, unchecked
+pub fn read_records(path: impl AsRef<Path>) -> Result<Vec<ResultRecord>> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).storage(format!("Failed to read {}", path.display()))?;
+    serde_cbor::Deserializer::from_slice(&bytes)
+        .into_iter::<ResultRecord>()
+        .enumerate()
+        .map(|(index, record)| record.map_err(|e| crate::Error::TournamentRecord {
+            index,
+            file: path.display().to_string(),
+            reason: e.to_string(),
+        }))
+        .collect()
+}
+
+/// What [`verify_dir`] found
+#[derive(Debug)]
+pub struct VerifyReport {
+    /// The records that passed every check
+    pub ledger: TournamentLedger,
+    /// Position in the file and failure of each record that didn't
+    pub failures: Vec<(usize, GameId, RecordError)>,
+}
+
+impl VerifyReport {
+    pub fn checked(&self) -> usize {
+        self.ledger.records().len() + self.failures.len()
+    }
+}
+
+/// Check every record of the [`RESULTS_FILE`] in `dir` again: its link to
+/// the record before it, both signatures, and that the game archived in
+/// `dir` under its ID is the one signed, with the same winner.
+///
+/// The chain is followed through the records as saved, so a record edited
+/// after publishing fails both its own signatures and the next one's link.
+pub fn verify_dir(dir: &Path) -> Result<VerifyReport> {
+    let records = read_records(dir.join(RESULTS_FILE))?;
+    let tournament = records.first().map_or("", |r| r.tournament.as_str());
+    let mut ledger = TournamentLedger::new(tournament);
+    let mut failures = Vec::new();
+    let mut seen = HashSet::new();
+    let mut prev_hash = [0; 32];
+    for (index, record) in records.into_iter().enumerate() {
+        let linked = record.prev_hash == prev_hash;
+        prev_hash = record.hash();
+        let checked = if record.tournament != ledger.tournament {
+            Err(RecordError::WrongTournament(record.tournament.clone()))
+        } else if !linked {
+            Err(RecordError::BrokenChain)
+        } else if !seen.insert(record.game_id.clone()) {
+            Err(RecordError::Duplicate(record.game_id.clone()))
+        } else {
+            record.verify_signatures().and_then(|()| {
+                let archive = load_archive(dir, &record.game_id)
+                    .ok_or_else(|| RecordError::MissingGame(record.game_id.clone()))?;
+                record.check_archive(&archive)
+            })
+        };
+        match checked {
+            Ok(()) => ledger.records.push(record),
+            Err(e) => failures.push((index, record.game_id, e)),
+        }
+    }
+    Ok(VerifyReport { ledger, failures })
+}
+
+/// The game archived in `dir` as `game_id`, if readable
+fn load_archive(dir: &Path, game_id: &str) -> Option<GameArchive> {
+    // Records come from peers, so keep their game IDs from naming other paths
+    if !game_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+    let bytes = fs::read(dir.join(format!("{}.cbor", game_id))).ok()?;
+    migrate::from_slice(&bytes).ok()
+}
+
+/// A tournament's topic, with the ledger of what was published on it
+#[derive(Debug, Clone)]
+pub struct TournamentFeed {
+    tournament: String,
+    ledger: Arc<RwLock<TournamentLedger>>,
+    tx: broadcast::Sender<ResultRecord>,
+    #[cfg(feature = "iroh")]
+    gossip: Option<(IrohCtx, TopicId)>,
+}
+
+impl TournamentFeed {
+    /// A feed shared only within this process
+    pub fn local(tournament: &str) -> Self {
+        let (tx, _) = broadcast::channel(64);
+        Self {
+            tournament: tournament.to_string(),
+            ledger: Arc::new(RwLock::new(TournamentLedger::new(tournament))),
+            tx,
+            #[cfg(feature = "iroh")]
+            gossip: None,
+        }
+    }
+
+    /// The tournament's gossip topic; records from peers that the ledger
+    /// takes are passed on to subscribers
+    #[cfg(feature = "iroh")]
+    pub async fn gossip(ctx: IrohCtx, tournament: &str) -> Result<Self> {
+        let topic_id = IrohCtx::tournament_topic(tournament);
+        let mut events = ctx.subscribe_gossip_topic(topic_id, 64).await?;
+        let mut feed = Self::local(tournament);
+        let (ledger, tx) = (feed.ledger.clone(), feed.tx.clone());
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                let Some(content) = crate::gossip_compat::extract_bytes(&event) else {
+                    continue;
+                };
+                let record = match migrate::from_slice::<ResultRecord>(&content) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        tracing::warn!("Failed to decode tournament result: {}", e);
+                        continue;
+                    }
+                };
+                match ledger.write().await.append(record.clone()) {
+                    Ok(()) => {
+                        let _ = tx.send(record);
+                    }
+                    Err(e) => tracing::warn!(game_id = %record.game_id, "Refusing tournament result: {}", e),
+                }
+            }
+        });
+        feed.gossip = Some((ctx, topic_id));
+        Ok(feed)
+    }
+
+    pub fn tournament(&self) -> &str {
+        &self.tournament
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ResultRecord> {
+        self.tx.subscribe()
+    }
+
+    /// The results taken in so far
+    pub async fn ledger(&self) -> TournamentLedger {
+        self.ledger.read().await.clone()
+    }
+
+    /// Link a record both players signed to the feed and send it to
+    /// every peer on the topic
+    pub async fn publish(&self, record: ResultRecord) -> Result<ResultRecord> {
+        let record = {
+            let mut ledger = self.ledger.write().await;
+            let record = ledger.link(record);
+            ledger.append(record.clone())?;
+            record
+        };
+        #[cfg(feature = "iroh")]
+        if let Some((ctx, topic_id)) = &self.gossip {
+            let bytes = serde_cbor::to_vec(&record)
+                .map_err(|e| crate::Error::internal(format!("Failed to encode tournament result: {}", e)))?;
+            ctx.broadcast_to_topic(*topic_id, &bytes).await?;
+        }
+        let _ = self.tx.send(record.clone());
+        Ok(record)
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Message and byte counts by kind of traffic, for the network panel.
+//!
+//! Send and receive paths bump the atomics in [`TRAFFIC`] without taking a
+//! lock. Once a second the worker takes the counts as a [`TrafficSample`],
+//! and the UI keeps the last minute of samples in a [`TrafficHistory`].
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::{Serialize, Deserialize};
+
+/// Seconds of samples a history keeps
+pub const HISTORY_SECS: usize = 60;
+
+/// What a message was sent for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TrafficCategory {
+    /// Game adverts, moves and matchmaking on gossip topics
+    Gossip,
+    /// Moves, hellos and ACKs on direct connections
+    Stream,
+    /// Sync requests and responses on direct connections
+    Sync,
+    /// Direct messages carried by a relay server
+    Relay,
+}
+
+impl TrafficCategory {
+    pub const ALL: [TrafficCategory; 4] = [
+        TrafficCategory::Gossip,
+        TrafficCategory::Stream,
+        TrafficCategory::Sync,
+        TrafficCategory::Relay,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TrafficCategory::Gossip => "gossip",
+            TrafficCategory::Stream => "stream",
+            TrafficCategory::Sync => "sync",
+            TrafficCategory::Relay => "relay",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Counts since they were last taken, one per category
+#[derive(Debug)]
+pub struct TrafficCounters {
+    messages: [AtomicU64; 4],
+    bytes: [AtomicU64; 4],
+}
+
+impl Default for TrafficCounters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrafficCounters {
+    pub const fn new() -> Self {
+        Self {
+            messages: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+            bytes: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    /// One message of `bytes` went out or came in
+    pub fn record(&self, category: TrafficCategory, bytes: usize) {
+        self.messages[category.index()].fetch_add(1, Ordering::Relaxed);
+        self.bytes[category.index()].fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// The counts so far as a sample stamped `at_secs`, starting again from zero
+    pub fn take(&self, at_secs: u64) -> TrafficSample {
+        let mut sample = TrafficSample { at_secs, ..TrafficSample::default() };
+        for category in TrafficCategory::ALL {
+            let index = category.index();
+            sample.messages[index] = self.messages[index].swap(0, Ordering::Relaxed);
+            sample.bytes[index] = self.bytes[index].swap(0, Ordering::Relaxed);
+        }
+        sample
+    }
+}
+
+/// Process-wide counters the network code records into
+pub static TRAFFIC: TrafficCounters = TrafficCounters::new();
+
+/// Count one message of `bytes` in [`TRAFFIC`]
+pub fn record(category: TrafficCategory, bytes: usize) {
+    TRAFFIC.record(category, bytes);
+}
+
+/// Traffic during one second, ending at `at_secs` (unix seconds)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficSample {
+    pub at_secs: u64,
+    /// Counts in [`TrafficCategory::ALL`] order
+    pub messages: [u64; 4],
+    pub bytes: [u64; 4],
+}
+
+impl TrafficSample {
+    pub fn messages(&self, category: TrafficCategory) -> u64 {
+        self.messages[category.index()]
+    }
+
+    pub fn bytes(&self, category: TrafficCategory) -> u64 {
+        self.bytes[category.index()]
+    }
+}
+
+/// The last [`HISTORY_SECS`] of samples, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct TrafficHistory {
+    samples: VecDeque<TrafficSample>,
+}
+
+impl TrafficHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the newest sample, dropping any that fell out of the window
+    pub fn push(&mut self, sample: TrafficSample) {
+        self.samples.push_back(sample);
+        let window = HISTORY_SECS as u64;
+        while self.samples.len() > HISTORY_SECS
+            || matches!(self.samples.front(), Some(oldest) if oldest.at_secs + window <= sample.at_secs)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &TrafficSample> {
+        self.samples.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Plot points for `category`: seconds before the newest sample, and
+    /// messages (or bytes, if `bytes`) in that second
+    pub fn series(&self, category: TrafficCategory, bytes: bool) -> Vec<[f64; 2]> {
+        let Some(newest) = self.samples.back() else {
+            return Vec::new();
+        };
+        self.samples
+            .iter()
+            .map(|sample| {
+                let value = if bytes { sample.bytes(category) } else { sample.messages(category) };
+                [sample.at_secs as f64 - newest.at_secs as f64, value as f64]
+            })
+            .collect()
+    }
+
+    /// The samples as CSV, one row per second with messages and bytes per category
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("unix_secs");
+        for category in TrafficCategory::ALL {
+            let _ = write!(csv, ",{0}_messages,{0}_bytes", category.label());
+        }
+        csv.push('\n');
+        for sample in &self.samples {
+            let _ = write!(csv, "{}", sample.at_secs);
+            for category in TrafficCategory::ALL {
+                let _ = write!(csv, ",{},{}", sample.messages(category), sample.bytes(category));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
@@ -0,0 +1,284 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Local copies of games shared as training data, and taking them back.
+//!
+//! A finished game shared for training is copied into the training
+//! directory. Deleting those copies also publishes a [`Retraction`] on the
+//! training topic, and peers that honour it delete theirs. Game IDs are
+//! random, so a retraction names only the game, not who shared it. It is
+//! signed by the identity of the player taking the game back, and peers
+//! drop retractions whose signature doesn't check out.
+//!
+//! Training messages are versioned like core's CBOR, see
+//! [`p2pgo_core::cbor::migrate`].
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Serialize, Deserialize};
+use tokio::sync::broadcast;
+use p2pgo_core::GameState;
+use p2pgo_core::cbor::migrate::{self, Upgrade, Versioned};
+use crate::identity::Identity;
+use crate::{Error, GameId, Result};
+use crate::error::StorageContext;
+
+#[cfg(feature = "iroh")]
+use {
+    crate::IrohCtx,
+    crate::health::Supervisor,
+    iroh_gossip::proto::TopicId,
+};
+
+/// Subdirectory of the data directory holding training copies
+pub const TRAINING_DIR: &str = "training";
+
+/// Name the training topic's gossip listener is supervised under
+pub const TRAINING_GOSSIP: &str = "training gossip";
+
+/// Version of [`TrainingMessage`] sent today
+pub const TRAINING_MESSAGE_VERSION: u32 = 1;
+
+/// A player no longer shares a game for training
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retraction {
+    pub game_id: GameId,
+    /// Node ID of the player taking it back
+    pub node_id: String,
+    /// Unix seconds
+    pub retracted_at: u64,
+    /// Ed25519 public key of the player taking it back; empty from peers
+    /// that predate signing
+    #[serde(default)]
+    pub key: [u8; 32],
+    /// That player's signature over the fields above
+    #[serde(default)]
+    pub signature: Vec<u8>,
+}
+
+impl Retraction {
+    /// Take back `game_id`, signed by the player who shared it
+    pub fn sign(identity: &Identity, game_id: GameId, retracted_at: u64) -> Self {
+        let mut retraction = Self {
+            game_id,
+            node_id: identity.node_id(),
+            retracted_at,
+            key: identity.public_key(),
+            signature: Vec::new(),
+        };
+        retraction.signature = identity.sign(&retraction.signed_bytes()).to_vec();
+        retraction
+    }
+
+    /// Bytes covered by the signature
+    fn signed_bytes(&self) -> Vec<u8> {
+        let signed = ("p2pgo-training-retraction", &self.game_id, &self.node_id, self.retracted_at, self.key);
+        serde_cbor::to_vec(&signed).expect("retractions always encode")
+    }
+
+    /// Check the signature is by the key the retraction names
+    pub fn verify(&self) -> Result<()> {
+        let key = VerifyingKey::from_bytes(&self.key)
+            .map_err(|e| Error::malformed("training retraction", format!("invalid key: {}", e)))?;
+        let signature = Signature::from_slice(&self.signature)
+            .map_err(|e| Error::malformed("training retraction", format!("unsigned: {}", e)))?;
+        key.verify(&self.signed_bytes(), &signature)
+            .map_err(|_| Error::malformed("training retraction", "signature does not match"))
+    }
+}
+
+/// Messages on the training topic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "TrainingMessageRepr", try_from = "TrainingMessageRepr")]
+pub enum TrainingMessage {
+    /// Drop every copy of the game
+    Retract(Retraction),
+}
+
+impl TrainingMessage {
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        serde_cbor::to_vec(self).map_err(|e| Error::internal(format!("Failed to encode training message: {}", e)))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        migrate::from_slice(bytes).map_err(|e| Error::malformed("training message", e))
+    }
+}
+
+impl Versioned for TrainingMessage {
+    const VERSION: u32 = TRAINING_MESSAGE_VERSION;
+    const NAME: &'static str = "training message";
+}
+
+/// Every field any version of [`TrainingMessage`] had; unversioned
+/// messages were the enum itself, a map keyed by the variant
+#[derive(Serialize, Deserialize)]
+struct TrainingMessageRepr {
+    #[serde(default)]
+    v: u32,
+    #[serde(rename = "Retract", default, skip_serializing_if = "Option::is_none")]
+    retract: Option<Retraction>,
+}
+
+const TRAINING_MESSAGE_UPGRADES: [Upgrade<TrainingMessageRepr>; TRAINING_MESSAGE_VERSION as usize] = [
+    // v1 only added `v`
+    |_| {},
+];
+
+impl From<TrainingMessage> for TrainingMessageRepr {
+    fn from(msg: TrainingMessage) -> Self {
+        match msg {
+            TrainingMessage::Retract(retraction) => Self { v: TRAINING_MESSAGE_VERSION, retract: Some(retraction) },
+        }
+    }
+}
+
+impl TryFrom<TrainingMessageRepr> for TrainingMessage {
+    type Error = String;
+
+    fn try_from(mut repr: TrainingMessageRepr) -> Result<Self, Self::Error> {
+        let version = repr.v;
+        migrate::upgrade(&mut repr, version, &TRAINING_MESSAGE_UPGRADES, TrainingMessage::NAME);
+        match repr.retract {
+            Some(retraction) => Ok(TrainingMessage::Retract(retraction)),
+            None => Err(format!("training message v{} of a kind this version doesn't know", version)),
+        }
+    }
+}
+
+/// Directory of games shared for training
+#[derive(Debug, Clone)]
+pub struct TrainingStore {
+    dir: PathBuf,
+}
+
+impl TrainingStore {
+    /// Keep training copies in `dir`, created on the first write
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Keep training copies in the default data directory
+    pub fn open_default() -> Result<Self> {
+        let dir = p2pgo_core::p2pgo_dirs::Dirs::current().storage("Failed to find the data directory")?.data;
+        Ok(Self::new(dir.join(TRAINING_DIR)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Keep a training copy of the game
+    pub fn save(&self, game_id: &str, state: &GameState) -> Result<()> {
+        fs::create_dir_all(&self.dir).storage("Failed to create training directory")?;
+        let path = self.path_for(game_id);
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_cbor::to_vec(state).storage("Failed to encode training copy")?).storage("Failed to write training copy")?;
+        fs::rename(&tmp, &path).storage("Failed to replace training copy")?;
+        Ok(())
+    }
+
+    pub fn contains(&self, game_id: &str) -> bool {
+        self.path_for(game_id).exists()
+    }
+
+    /// Delete the game's training copy, saying whether there was one
+    pub fn delete(&self, game_id: &str) -> Result<bool> {
+        match fs::remove_file(self.path_for(game_id)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).storage(format!("Failed to delete training copy of {}", game_id)),
+        }
+    }
+
+    /// Act on a message from the training topic, saying whether a copy was
+    /// deleted
+    pub fn apply(&self, message: &TrainingMessage) -> Result<bool> {
+        match message {
+            TrainingMessage::Retract(retraction) => {
+                let deleted = self.delete(&retraction.game_id)?;
+                if deleted {
+                    tracing::info!(game_id = %retraction.game_id, by = %retraction.node_id, "Dropped retracted training game");
+                }
+                Ok(deleted)
+            }
+        }
+    }
+
+    fn path_for(&self, game_id: &str) -> PathBuf {
+        // Retractions come from peers, so keep their game IDs from naming other paths
+        let name: String = game_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.cbor", name))
+    }
+}
+
+/// The training topic
+#[derive(Debug, Clone)]
+pub struct TrainingTopic {
+    tx: broadcast::Sender<TrainingMessage>,
+    #[cfg(feature = "iroh")]
+    gossip: Option<(IrohCtx, TopicId)>,
+}
+
+impl TrainingTopic {
+    /// A topic shared only within this process
+    pub fn local() -> Self {
+        let (tx, _) = broadcast::channel(64);
+        Self {
+            tx,
+            #[cfg(feature = "iroh")]
+            gossip: None,
+        }
+    }
+
+    /// The gossip training topic, its listener run under `supervisor`
+    /// as [`TRAINING_GOSSIP`] so it subscribes again should it die
+    #[cfg(feature = "iroh")]
+    pub fn gossip(ctx: IrohCtx, supervisor: &Supervisor) -> Self {
+        let topic_id = IrohCtx::training_topic();
+        let mut topic = Self::local();
+        let (listener_ctx, tx) = (ctx.clone(), topic.tx.clone());
+        supervisor.register(TRAINING_GOSSIP, crate::health::SUBSYSTEM_DEADLINE, move |heartbeat| {
+            let (ctx, tx) = (listener_ctx.clone(), tx.clone());
+            async move {
+                let mut events = match ctx.subscribe_gossip_topic(topic_id, 64).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        tracing::warn!("Training retractions from peers unavailable: {}", e);
+                        return;
+                    }
+                };
+                while let Some(event) = heartbeat.waiting(events.recv()).await {
+                    let Some(content) = crate::gossip_compat::extract_bytes(&event) else {
+                        continue;
+                    };
+                    match TrainingMessage::from_bytes(&content) {
+                        Ok(msg) => {
+                            let _ = tx.send(msg);
+                        }
+                        Err(e) => tracing::warn!("Failed to decode training message: {}", e),
+                    }
+                }
+            }
+        });
+        topic.gossip = Some((ctx, topic_id));
+        topic
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TrainingMessage> {
+        self.tx.subscribe()
+    }
+
+    /// Send `msg` to every peer on the topic
+    pub async fn publish(&self, msg: TrainingMessage) -> Result<()> {
+        #[cfg(feature = "iroh")]
+        if let Some((ctx, topic_id)) = &self.gossip {
+            ctx.broadcast_to_topic(*topic_id, &msg.to_bytes()?).await?;
+        }
+        let _ = self.tx.send(msg);
+        Ok(())
+    }
+}
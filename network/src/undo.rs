@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Takebacks agreed between the players of a game.
+//!
+//! A player asks to take back their last moves; the opponent accepts or
+//! declines. Only an accepted request changes the game, and then on both
+//! sides at once. Each player may have at most the game's undo limit
+//! granted, the lower of the two peers' settings.
+
+use thiserror::Error;
+
+/// Why an undo can't be asked for or answered
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum UndoError {
+    #[error("No undos left; this game allows {limit}")]
+    LimitReached { limit: u8 },
+    #[error("Already waiting for an answer to an undo request")]
+    AlreadyPending,
+    #[error("Can't take back {moves} of {played} moves")]
+    NothingToUndo { moves: u8, played: usize },
+    #[error("The opponent has not asked for an undo")]
+    NoRequest,
+}
+
+/// Undo requests either way and how many were granted
+#[derive(Debug, Clone)]
+pub struct UndoState {
+    limit: u8,
+    /// Moves we asked to take back, until answered
+    requested: Option<u8>,
+    /// A peer's request awaiting our answer, and how many moves
+    asked: Option<(String, u8)>,
+    /// Undos the opponent granted us
+    granted_us: u8,
+    /// Undos we granted the opponent
+    granted_them: u8,
+}
+
+impl UndoState {
+    pub fn new(limit: u8) -> Self {
+        Self { limit, requested: None, asked: None, granted_us: 0, granted_them: 0 }
+    }
+
+    pub fn limit(&self) -> u8 {
+        self.limit
+    }
+
+    /// Play with the lower of our limit and a peer's
+    pub fn negotiate(&mut self, theirs: u8) {
+        self.limit = self.limit.min(theirs);
+    }
+
+    /// Undos we may still ask for
+    pub fn remaining(&self) -> u8 {
+        self.limit.saturating_sub(self.granted_us)
+    }
+
+    /// Moves we asked to take back, while unanswered
+    pub fn requested(&self) -> Option<u8> {
+        self.requested
+    }
+
+    /// The peer's request we have yet to answer
+    pub fn asked(&self) -> Option<(&str, u8)> {
+        self.asked.as_ref().map(|(peer, moves)| (peer.as_str(), *moves))
+    }
+
+    /// Ask to take back `moves` of the `played` moves in the game
+    pub fn request(&mut self, moves: u8, played: usize) -> Result<(), UndoError> {
+        if self.requested.is_some() {
+            return Err(UndoError::AlreadyPending);
+        }
+        if self.remaining() == 0 {
+            return Err(UndoError::LimitReached { limit: self.limit });
+        }
+        check_moves(moves, played)?;
+        self.requested = Some(moves);
+        Ok(())
+    }
+
+    /// The opponent answered our request; the moves to take back if it
+    /// accepted
+    pub fn answered(&mut self, accepted: bool) -> Option<u8> {
+        let moves = self.requested.take()?;
+        if !accepted {
+            return None;
+        }
+        self.granted_us += 1;
+        Some(moves)
+    }
+
+    /// `peer` asked to take back `moves` of the `played` moves; refused
+    /// straight away once its undos are used up or the moves don't exist
+    pub fn asked_by(&mut self, peer: &str, moves: u8, played: usize) -> Result<(), UndoError> {
+        if self.granted_them >= self.limit {
+            return Err(UndoError::LimitReached { limit: self.limit });
+        }
+        check_moves(moves, played)?;
+        self.asked = Some((peer.to_string(), moves));
+        Ok(())
+    }
+
+    /// Answer the peer's request; the moves to take back if we accept
+    pub fn answer(&mut self, accept: bool) -> Result<Option<u8>, UndoError> {
+        let (_, moves) = self.asked.take().ok_or(UndoError::NoRequest)?;
+        if !accept {
+            return Ok(None);
+        }
+        self.granted_them += 1;
+        Ok(Some(moves))
+    }
+
+    /// A move was played, so requests about the old position no longer
+    /// apply
+    pub fn moved(&mut self) {
+        self.requested = None;
+        self.asked = None;
+    }
+}
+
+fn check_moves(moves: u8, played: usize) -> Result<(), UndoError> {
+    if moves == 0 || moves as usize > played {
+        return Err(UndoError::NothingToUndo { moves, played });
+    }
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Wire format for messages on direct game streams.
+//!
+//! Each unidirectional stream carries one message in a frame: a 4-byte
+//! big-endian payload length, a flags byte, then the payload. Payloads are
+//! CBOR, zstd-compressed above [`COMPRESSION_THRESHOLD`].
+//!
+//! Peers that predate framing send a single JSON `MoveRecord` ended by a
+//! newline. Until a peer's [`DirectMessage::Hello`] says otherwise we write
+//! that legacy form to it, and the reader accepts both. What a peer can
+//! read is settled by the hello, so messages themselves stay at v0 of
+//! [`p2pgo_core::cbor::migrate`].
+
+use serde::{Serialize, Deserialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use p2pgo_core::{Color, GameState, MoveRecord};
+use p2pgo_core::value_labeller::ScoreProof;
+use p2pgo_core::game_clock::ClockSnapshot;
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::teaching::TeachingAnnotation;
+use p2pgo_core::cbor::migrate::{self, Versioned};
+use crate::presence::Presence;
+use crate::tournament::ResultRecord;
+use crate::{sanitize, Error, Result};
+
+/// Payloads larger than this are compressed
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+
+/// Largest payload a reader accepts
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Payload is CBOR rather than JSON
+const FLAG_CBOR: u8 = 0b01;
+/// Payload is zstd-compressed
+const FLAG_ZSTD: u8 = 0b10;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Encodings a peer can read, advertised in its hello
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireFormat {
+    /// Newline-terminated JSON move records, for peers without framing
+    Json,
+    /// Framed CBOR with compression
+    Cbor,
+}
+
+impl WireFormat {
+    /// Formats we advertise, best first
+    pub const SUPPORTED: &'static [WireFormat] = &[WireFormat::Cbor, WireFormat::Json];
+
+    /// Format to write to a peer that advertised `theirs`
+    pub fn negotiate(theirs: &[WireFormat]) -> WireFormat {
+        Self::SUPPORTED
+            .iter()
+            .copied()
+            .find(|format| theirs.contains(format))
+            .unwrap_or(WireFormat::Json)
+    }
+}
+
+/// Messages exchanged on direct game streams
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DirectMessage {
+    /// First message on a connection: the formats we can read, our clock
+    /// in unix seconds, and the game's settings as we see them; older peers
+    /// send no clock, reading as zero, and no settings
+    Hello {
+        formats: Vec<WireFormat>,
+        #[serde(default)]
+        sent_at: u64,
+        #[serde(default)]
+        settings: Option<GameSettings>,
+    },
+    /// A move played by the sender
+    Move(MoveRecord),
+    /// Ask the peer for its moves so far
+    SyncRequest,
+    /// All moves so far and the resulting state
+    SyncResponse {
+        moves: Vec<MoveRecord>,
+        state: GameState,
+    },
+    /// The sender applied the move at this index of the game, and the
+    /// clocks after it if the sender keeps them
+    Ack {
+        index: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        clock: Option<ClockSnapshot>,
+    },
+    /// The teacher annotated the position after `move_index` moves
+    Annotation {
+        move_index: u32,
+        annotation: TeachingAnnotation,
+    },
+    /// Whether the sender is looking at the board, and when it last did anything
+    Presence(Presence),
+    /// The clocks of the sender, which keeps them, between moves
+    ClockTick(ClockSnapshot),
+    /// The sender asks to take back its last `moves` moves
+    UndoRequest { moves: u8 },
+    /// The answer to the receiver's undo request
+    UndoResponse { accepted: bool },
+    /// The result of a finished tournament game, signed by the sender,
+    /// for the receiver to countersign
+    SignResult(ResultRecord),
+    /// The result the receiver proposed, signed by both players
+    ResultSigned(ResultRecord),
+    /// The sender's player, playing `by`, accepted this score of the game
+    ScoreAccepted { by: Color, proof: ScoreProof },
+}
+
+impl Versioned for DirectMessage {
+    const VERSION: u32 = 0;
+    const NAME: &'static str = "direct message";
+}
+
+impl DirectMessage {
+    /// Our hello for a game played with `settings`
+    pub fn hello(settings: &GameSettings) -> Self {
+        DirectMessage::Hello {
+            formats: WireFormat::SUPPORTED.to_vec(),
+            sent_at: crate::relay_robustness::now_secs(),
+            settings: Some(*settings),
+        }
+    }
+}
+
+/// Encode a message as it goes on the stream
+pub fn encode(msg: &DirectMessage, format: WireFormat) -> Result<Vec<u8>> {
+    match format {
+        WireFormat::Json => {
+            // Older peers only understand bare move records
+            let DirectMessage::Move(record) = msg else {
+                return Err(Error::internal("Only moves can be sent to peers without framing"));
+            };
+            let mut bytes = serde_json::to_vec(record).map_err(Error::internal)?;
+            bytes.push(b'\n');
+            Ok(bytes)
+        }
+        WireFormat::Cbor => {
+            let mut payload = serde_cbor::to_vec(msg)
+                .map_err(|e| Error::internal(format!("Failed to CBOR encode message: {}", e)))?;
+            let mut flags = FLAG_CBOR;
+            if payload.len() > COMPRESSION_THRESHOLD {
+                payload = zstd::encode_all(payload.as_slice(), ZSTD_LEVEL)
+                    .map_err(|e| Error::internal(format!("Failed to compress message: {}", e)))?;
+                flags |= FLAG_ZSTD;
+            }
+            if payload.len() > MAX_FRAME_LEN {
+                return Err(Error::PayloadTooLarge { len: payload.len(), limit: MAX_FRAME_LEN });
+            }
+
+            let mut frame = Vec::with_capacity(5 + payload.len());
+            frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+            frame.push(flags);
+            frame.extend_from_slice(&payload);
+            Ok(frame)
+        }
+    }
+}
+
+/// Decode one message from a whole stream's bytes
+pub async fn decode(bytes: &[u8]) -> Result<DirectMessage> {
+    read_message(&mut &bytes[..]).await
+}
+
+/// Write one message and flush it, returning the bytes written
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, msg: &DirectMessage, format: WireFormat) -> Result<usize> {
+    let bytes = encode(msg, format)?;
+    writer.write_all(&bytes).await.map_err(|e| Error::Internal(e.into()))?;
+    writer.flush().await.map_err(|e| Error::Internal(e.into()))?;
+    Ok(bytes.len())
+}
+
+/// Read one message, framed or legacy JSON
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<DirectMessage> {
+    read_frame(reader).await.map(|(msg, _)| msg)
+}
+
+/// Read one message and the bytes it took on the stream
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<(DirectMessage, usize)> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header[..1]).await
+        .map_err(|e| Error::malformed("message", format!("stream ended before a message: {}", e)))?;
+
+    // A frame never starts with '{': that length would exceed MAX_FRAME_LEN
+    if header[0] == b'{' {
+        let mut json = vec![header[0]];
+        reader.take(MAX_FRAME_LEN as u64).read_to_end(&mut json).await
+            .map_err(|e| Error::malformed("JSON move record", e))?;
+        let record = serde_json::from_slice::<MoveRecord>(&json)
+            .map_err(|e| Error::malformed("JSON move record", e))?;
+        return Ok((DirectMessage::Move(record), json.len()));
+    }
+
+    reader.read_exact(&mut header[1..]).await
+        .map_err(|e| Error::malformed("frame", format!("truncated header: {}", e)))?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let flags = header[4];
+    if len > MAX_FRAME_LEN {
+        return Err(Error::malformed("frame", format!("{} bytes exceeds the {} byte limit", len, MAX_FRAME_LEN)));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await
+        .map_err(|e| Error::malformed("frame", format!("truncated: {}", e)))?;
+    if flags & FLAG_CBOR == 0 {
+        return Err(Error::malformed("frame", format!("unsupported encoding {:#04x}", flags)));
+    }
+    if flags & FLAG_ZSTD != 0 {
+        payload = decompress(&payload)?;
+    }
+    let msg = migrate::from_slice(&payload).map_err(|e| Error::malformed("CBOR message", e))?;
+    sanitize::check_message(&msg)?;
+    Ok((msg, header.len() + len))
+}
+
+/// Inflate a payload, refusing to grow past `MAX_FRAME_LEN`
+fn decompress(payload: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut decoded = Vec::new();
+    zstd::stream::read::Decoder::new(payload)
+        .and_then(|decoder| decoder.take(MAX_FRAME_LEN as u64 + 1).read_to_end(&mut decoded))
+        .map_err(|e| Error::malformed("frame", format!("failed to decompress: {}", e)))?;
+    if decoded.len() > MAX_FRAME_LEN {
+        return Err(Error::malformed("frame", format!("decompressed message exceeds the {} byte limit", MAX_FRAME_LEN)));
+    }
+    Ok(decoded)
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Connection admission against connection and bandwidth limits.
+
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use p2pgo_network::admission::{Admission, ConnectionLimits, Refusal};
+
+fn limits(max_connections: u32, max_bandwidth_bps: u64) -> ConnectionLimits {
+    ConnectionLimits { max_connections, max_bandwidth_bps }
+}
+
+#[test]
+fn connections_past_the_limit_are_refused_until_one_closes() {
+    let (_tx, rx) = watch::channel(limits(2, 1_000));
+    let admission = Admission::new(rx);
+    let now = Instant::now();
+
+    let first = admission.admit(now).unwrap();
+    let _second = admission.admit(now).unwrap();
+    assert_eq!(admission.admit(now).unwrap_err(), Refusal::Full { open: 2 });
+
+    drop(first);
+    assert_eq!(admission.open_connections(), 1);
+    assert!(admission.admit(now).is_ok());
+}
+
+#[test]
+fn new_connections_wait_out_a_busy_second() {
+    let (_tx, rx) = watch::channel(limits(10, 1_000));
+    let admission = Admission::new(rx);
+    let start = Instant::now();
+
+    admission.record(1_500, start);
+    assert_eq!(admission.admit(start).unwrap_err(), Refusal::Busy { bytes_per_sec: 1_500 });
+    // The busy second still counts for the one after it
+    assert!(admission.admit(start + Duration::from_millis(1_500)).is_err());
+    assert!(admission.admit(start + Duration::from_secs(3)).is_ok());
+}
+
+#[test]
+fn changed_limits_apply_to_the_next_connection() {
+    let (tx, rx) = watch::channel(limits(1, 1_000));
+    let admission = Admission::new(rx);
+    let now = Instant::now();
+
+    let _held = admission.admit(now).unwrap();
+    assert!(admission.admit(now).is_err());
+    tx.send_replace(limits(2, 1_000));
+    let _also_held = admission.admit(now).unwrap();
+
+    admission.record(5_000, now);
+    assert!(matches!(admission.admit(now), Err(Refusal::Full { .. })));
+    tx.send_replace(limits(4, 1_000));
+    assert!(matches!(admission.admit(now), Err(Refusal::Busy { .. })));
+    tx.send_replace(limits(4, 10_000));
+    assert!(admission.admit(now).is_ok());
+}
+
+#[test]
+fn unlimited_admits_everything() {
+    let admission = Admission::unlimited();
+    let now = Instant::now();
+    admission.record(u64::MAX, now);
+    let permits: Vec<_> = (0..1_000).map(|_| admission.admit(now).unwrap()).collect();
+    assert_eq!(admission.open_connections(), permits.len() as u32);
+}
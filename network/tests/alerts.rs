@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Alert rules fed synthetic metric streams: which actions fire, and that
+//! each fires once per cooldown.
+
+use std::time::{Duration, Instant};
+use p2pgo_network::alerts::{default_rules, AlertAction, AlertMetric, AlertMonitor, AlertRule};
+
+fn secs(n: u64) -> Duration {
+    Duration::from_secs(n)
+}
+
+/// Feed `events` (seconds from start, metric, count), checking every second
+/// for `run_secs`; returns the actions fired and when
+fn run(monitor: &mut AlertMonitor, events: &[(u64, AlertMetric, u64)], run_secs: u64) -> Vec<(u64, AlertAction, AlertMetric)> {
+    let start = Instant::now();
+    let mut fired = Vec::new();
+    for t in 0..=run_secs {
+        let now = start + secs(t);
+        for &(_, metric, count) in events.iter().filter(|(at, _, _)| *at == t) {
+            monitor.record(metric, count, now);
+        }
+        fired.extend(monitor.check(now).into_iter().map(|alert| (t, alert.action, alert.metric)));
+    }
+    fired
+}
+
+#[test]
+fn restart_storm_notifies_then_captures_once_per_hour() {
+    let mut monitor = AlertMonitor::default();
+    // A restart every 5 minutes for two hours
+    let events: Vec<_> = (0..24).map(|i| (i * 300, AlertMetric::SubsystemRestarts, 1)).collect();
+    let fired = run(&mut monitor, &events, 2 * 3600);
+
+    let notify: Vec<u64> = fired.iter().filter(|(_, a, _)| *a == AlertAction::Notify).map(|(t, _, _)| *t).collect();
+    let capture: Vec<u64> = fired.iter().filter(|(_, a, _)| *a == AlertAction::CaptureDiagnostics).map(|(t, _, _)| *t).collect();
+    // The third restart notifies and the fifth captures; each again an hour later
+    assert_eq!(notify, vec![600, 4200]);
+    assert_eq!(capture, vec![1200, 4800]);
+}
+
+#[test]
+fn ack_timeouts_fire_only_within_the_window() {
+    let mut monitor = AlertMonitor::default();
+    // Spread out, three lost ACKs never fall in one minute
+    let sparse = [(0, AlertMetric::AckTimeouts, 1), (40, AlertMetric::AckTimeouts, 1), (90, AlertMetric::AckTimeouts, 1)];
+    assert!(run(&mut monitor, &sparse, 300).is_empty());
+
+    let mut monitor = AlertMonitor::default();
+    let burst = [(10, AlertMetric::AckTimeouts, 2), (30, AlertMetric::AckTimeouts, 1), (100, AlertMetric::AckTimeouts, 5)];
+    let fired = run(&mut monitor, &burst, 300);
+    // The burst at 100s is inside the 10 minute cooldown
+    assert_eq!(fired, vec![(30, AlertAction::Notify, AlertMetric::AckTimeouts)]);
+}
+
+#[test]
+fn rules_come_from_json() {
+    let json = r#"[{"metric": "AckTimeouts", "window_secs": 10, "threshold": 1, "action": "CaptureDiagnostics"}]"#;
+    let rules: Vec<AlertRule> = serde_json::from_str(json).unwrap();
+    assert_eq!(rules[0].cooldown_secs, None, "the window is the cooldown");
+
+    let mut monitor = AlertMonitor::new(default_rules());
+    monitor.set_rules(rules);
+    let events = [(0, AlertMetric::AckTimeouts, 1), (5, AlertMetric::AckTimeouts, 1), (30, AlertMetric::AckTimeouts, 1)];
+    let fired: Vec<u64> = run(&mut monitor, &events, 39).into_iter().map(|(t, _, _)| t).collect();
+    // The timeout at 5s is held back by the cooldown, then still counts at 10s
+    assert_eq!(fired, vec![0, 10, 30]);
+    assert_eq!(monitor.rules().len(), 1);
+}
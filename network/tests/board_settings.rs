@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Joiners build the host's board, and channels refuse moves off it.
+
+use p2pgo_core::settings::{GameSettings, OffBoardMove};
+use p2pgo_core::{Color, Coord, GameEvent, GameState, Move, MoveRecord};
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::join::{JoinPolicy, JoinResponse, PlayerProfile};
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::relay_robustness::now_secs;
+use p2pgo_network::wire::DirectMessage;
+use p2pgo_network::Error;
+
+fn profile(node_id: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+fn record(mv: Move) -> MoveRecord {
+    MoveRecord { mv, tag: None, ts: now_secs(), broadcast_hash: None, prev_hash: None }
+}
+
+#[tokio::test]
+async fn joiner_with_a_smaller_default_plays_the_hosts_board() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 19, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host"), JoinPolicy::Everyone).await.unwrap();
+
+    // The joiner's UI defaults to 9x9, but the host's settings win
+    let joiner_default = 9;
+    let response = lobby.request_join(&game_id, profile("guest")).await.unwrap();
+    let JoinResponse::Accepted { color, settings } = response else {
+        panic!("expected to be accepted, got {:?}", response);
+    };
+    assert_eq!(color, Color::White);
+    assert_eq!(settings, GameSettings::standard(19));
+    assert_ne!(settings.board_size, joiner_default);
+    let mut joiner = settings.new_game();
+
+    // Every corner move is off a 9x9 board
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    let mut events = channel.subscribe();
+    lobby.start_game(&game_id).await.unwrap();
+    let moves = [
+        ("host", Move::Place(Coord::new(15, 3))),
+        ("guest", Move::Place(Coord::new(3, 15))),
+        ("host", Move::Place(Coord::new(15, 15))),
+        ("guest", Move::Place(Coord::new(3, 3))),
+        ("host", Move::Pass),
+        ("guest", Move::Pass),
+    ];
+    for (player, mv) in moves {
+        lobby.post_player_move(&game_id, player, mv).await.unwrap();
+    }
+
+    // The joiner follows the game from the channel's events, as the UI does
+    let mut ended = false;
+    while let Ok(event) = events.try_recv() {
+        match event {
+            GameEvent::MoveMade { mv, .. } => joiner.apply_move(mv).unwrap(),
+            GameEvent::GameEnded { .. } => ended = true,
+            _ => {}
+        }
+    }
+    assert!(ended);
+    let host = channel.get_latest_state().await.unwrap();
+    assert_eq!(joiner.board, host.board);
+    assert_eq!(joiner.moves, host.moves);
+}
+
+#[tokio::test]
+async fn moves_off_the_agreed_board_are_refused() {
+    let channel = GameChannel::new("small".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+
+    // Ours fail with a typed error rather than a bare invalid coordinate
+    let error = channel.send_move(Move::Place(Coord::new(15, 3))).await.unwrap_err();
+    assert!(
+        matches!(&error, Error::OffBoard(off) if *off == OffBoardMove { coord: Coord::new(15, 3), board_size: 9 }),
+        "{:?}", error
+    );
+
+    // A peer's are dropped with a warning naming the board
+    let reply = channel.receive_direct("host", DirectMessage::Move(record(Move::Place(Coord::new(15, 3))))).await;
+    assert!(matches!(reply, InboundOutcome::Handled));
+    assert!(channel.get_latest_state().await.unwrap().moves.is_empty());
+    match events.try_recv() {
+        Ok(GameEvent::PeerWarning { peer, reason }) => {
+            assert_eq!(peer, "host");
+            assert!(reason.contains("9x9"), "{}", reason);
+        }
+        other => panic!("expected a peer warning, got {:?}", other),
+    }
+
+    // Moves that fit are still played
+    let reply = channel.receive_direct("host", DirectMessage::Move(record(Move::Place(Coord::new(4, 4))))).await;
+    assert!(matches!(reply, InboundOutcome::Reply(DirectMessage::Ack { index: 0, .. })));
+}
+
+#[tokio::test]
+async fn hello_with_other_settings_warns() {
+    let channel = GameChannel::new("small".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+
+    channel.receive_direct("peer", DirectMessage::hello(&GameSettings::standard(9))).await;
+    assert!(events.try_recv().is_err());
+
+    channel.receive_direct("peer", DirectMessage::hello(&GameSettings::standard(19))).await;
+    match events.try_recv() {
+        Ok(GameEvent::PeerWarning { reason, .. }) => assert_eq!(
+            reason,
+            "Your opponent set this game up as 19x19, komi 7.5, Chinese rules, not 9x9, komi 5.5, Chinese rules"
+        ),
+        other => panic!("expected a peer warning, got {:?}", other),
+    }
+}
@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A bot hosting and playing games against scripted clients in-process.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use p2pgo_core::engine::PlayerBackend;
+use p2pgo_core::{Color, Coord, GameState, Move};
+use p2pgo_network::archive::GameArchive;
+use p2pgo_network::bot::{self, BotConfig, BotHooks, BotRunner, DECLINED_REASON};
+use p2pgo_network::join::{JoinResponse, PlayerProfile};
+use p2pgo_network::{ArchiveManager, GameId, IdentityManager, Lobby};
+use tempfile::TempDir;
+
+const WAIT: Duration = Duration::from_secs(10);
+
+/// Plays its moves in order, then passes
+struct Script(Vec<Move>);
+
+impl PlayerBackend for Script {
+    fn next_move(&mut self, _pos: &GameState, _time_left: Duration) -> Move {
+        if self.0.is_empty() { Move::Pass } else { self.0.remove(0) }
+    }
+}
+
+/// Thinks for longer than any budget in these tests
+struct Slow;
+
+impl PlayerBackend for Slow {
+    fn next_move(&mut self, _pos: &GameState, _time_left: Duration) -> Move {
+        std::thread::sleep(Duration::from_millis(500));
+        Move::Place(Coord::new(4, 4))
+    }
+}
+
+/// Games started and records of games ended
+#[derive(Clone, Default)]
+struct Seen {
+    started: Arc<Mutex<Vec<String>>>,
+    ended: Arc<Mutex<Vec<GameArchive>>>,
+}
+
+impl BotHooks for Seen {
+    fn on_game_start(&self, _game_id: &str, opponent: &PlayerProfile) {
+        self.started.lock().unwrap().push(opponent.node_id.clone());
+    }
+
+    fn on_game_end(&self, record: &GameArchive) {
+        self.ended.lock().unwrap().push(record.clone());
+    }
+}
+
+fn profile(node_id: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+fn place(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+/// A bot on a fresh lobby, with its identity and archive in `dir`
+fn bot_in(dir: &TempDir, lobby: &Lobby, config: BotConfig, engines: bot::EngineFactory) -> BotRunner {
+    let identity = IdentityManager::new(dir.path().join("identity.key"));
+    let archive = ArchiveManager::with_directory(dir.path().join("finished"));
+    BotRunner::new(lobby.clone(), &identity, archive, config, engines).unwrap()
+}
+
+/// The game `host` opened, once it's ready for joiners
+async fn hosted_by(lobby: &Lobby, host: &str) -> GameId {
+    tokio::time::timeout(WAIT, async {
+        loop {
+            for game in lobby.list_games().await {
+                if let Ok((Some(seated), None)) = lobby.seated_players(&game.id).await {
+                    if seated.node_id == host {
+                        return game.id;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }).await.expect("bot opened no game")
+}
+
+/// Play `moves` as White, then pass until the game is over
+async fn play_white(lobby: &Lobby, game_id: &GameId, node_id: &str, mut moves: Vec<Move>) -> GameState {
+    let channel = lobby.get_game_channel(game_id).await.unwrap();
+    let mut events = channel.subscribe();
+    loop {
+        let state = channel.get_latest_state().await.unwrap_or_else(|| GameState::new(9));
+        if state.is_game_over() {
+            return state;
+        }
+        if state.current_player == Color::White {
+            let mv = if moves.is_empty() { Move::Pass } else { moves.remove(0) };
+            lobby.post_player_move(game_id, node_id, mv).await.unwrap();
+            continue;
+        }
+        tokio::time::timeout(WAIT, events.recv()).await.expect("bot stopped moving").unwrap();
+    }
+}
+
+#[tokio::test]
+async fn bot_plays_a_full_game_and_archives_it() {
+    let dir = tempfile::tempdir().unwrap();
+    let lobby = Lobby::new();
+    let seen = Seen::default();
+    let engines = bot::engines(|_| Script(vec![place(2, 2), place(2, 6)]));
+    let bot = bot_in(&dir, &lobby, BotConfig::new("scripted", 9), engines).with_hooks(seen.clone());
+    let bot_id = bot.node_id().to_string();
+    tokio::spawn(bot.run());
+
+    let game_id = hosted_by(&lobby, &bot_id).await;
+    let response = lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    assert!(matches!(response, JoinResponse::Accepted { color: Color::White, .. }));
+
+    let state = play_white(&lobby, &game_id, "alice", vec![place(6, 6), place(6, 2)]).await;
+    assert_eq!(state.moves.len(), 6);
+
+    tokio::time::timeout(WAIT, async {
+        while seen.ended.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }).await.expect("game never archived");
+    assert_eq!(*seen.started.lock().unwrap(), vec!["alice".to_string()]);
+    let record = seen.ended.lock().unwrap()[0].clone();
+    assert_eq!(record.final_state.moves, state.moves);
+    // Even territory, so komi decides it
+    assert_eq!(record.winner, Some(Color::White));
+
+    // The bot's identity outlives it
+    let again = bot_in(&dir, &Lobby::new(), BotConfig::new("scripted", 9), bot::engines(|_| Script(Vec::new())));
+    assert_eq!(again.node_id(), bot_id);
+}
+
+#[tokio::test]
+async fn filtered_joiners_are_declined() {
+    let dir = tempfile::tempdir().unwrap();
+    let lobby = Lobby::new();
+    let bot = bot_in(&dir, &lobby, BotConfig::new("picky", 9), bot::engines(|_| Script(Vec::new())))
+        .accepting(|profile| profile.node_id != "mallory");
+    let bot_id = bot.node_id().to_string();
+    tokio::spawn(bot.run());
+
+    let game_id = hosted_by(&lobby, &bot_id).await;
+    let response = lobby.request_join(&game_id, profile("mallory")).await.unwrap();
+    assert_eq!(response, JoinResponse::Declined { reason: DECLINED_REASON.to_string() });
+    // The seat stays open for someone else
+    let response = lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    assert!(matches!(response, JoinResponse::Accepted { .. }));
+}
+
+#[tokio::test]
+async fn slow_engines_pass_within_the_budget() {
+    let dir = tempfile::tempdir().unwrap();
+    let lobby = Lobby::new();
+    let mut config = BotConfig::new("slow", 9);
+    config.move_budget = Duration::from_millis(50);
+    let bot = bot_in(&dir, &lobby, config, bot::engines(|_| Slow));
+    let bot_id = bot.node_id().to_string();
+    tokio::spawn(bot.run());
+
+    let game_id = hosted_by(&lobby, &bot_id).await;
+    lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    let state = play_white(&lobby, &game_id, "alice", Vec::new()).await;
+    assert_eq!(state.moves, vec![Move::Pass, Move::Pass]);
+}
+
+#[tokio::test]
+async fn hopeless_positions_are_resigned() {
+    let dir = tempfile::tempdir().unwrap();
+    let lobby = Lobby::new();
+    let mut config = BotConfig::new("gloomy", 9);
+    config.resign.resign_after = 2;
+    let engines = bot::engines(|_| Script(vec![place(2, 2), place(2, 6), place(6, 2)]));
+    // Black's outlook is hopeless throughout
+    let bot = bot_in(&dir, &lobby, config, engines).with_value(|_| -1.0);
+    let bot_id = bot.node_id().to_string();
+    tokio::spawn(bot.run());
+
+    let game_id = hosted_by(&lobby, &bot_id).await;
+    lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    let state = play_white(&lobby, &game_id, "alice", vec![place(6, 6), place(5, 5)]).await;
+    assert_eq!(state.moves, vec![place(2, 2), place(6, 6), Move::Resign]);
+}
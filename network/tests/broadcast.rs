@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Sharing a hot-seat board: spectators get every move, and can't play.
+
+use std::time::Duration;
+use p2pgo_core::{Color, Coord, GameEvent, GameState, Move};
+use p2pgo_network::game_channel::{ChannelRole, GameChannel, InboundOutcome};
+use p2pgo_network::join::{JoinPolicy, JoinResponse, PlayerProfile, BROADCAST_REASON, SELF_JOIN_REASON};
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::rate_limit::RateLimitConfig;
+use p2pgo_network::wire::DirectMessage;
+
+fn profile(node_id: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+/// A short teaching game, both colors played at the one board
+fn script() -> Vec<Move> {
+    vec![
+        Move::Place(Coord::new(2, 2)),
+        Move::Place(Coord::new(6, 6)),
+        Move::Place(Coord::new(2, 6)),
+        Move::Place(Coord::new(6, 2)),
+        Move::Place(Coord::new(4, 4)),
+        Move::Place(Coord::new(4, 5)),
+        Move::Pass,
+        Move::Pass,
+    ]
+}
+
+/// A spectator that takes moves as fast as two people at one board play them
+async fn spectator(game_id: &str) -> GameChannel {
+    let channel = GameChannel::new(game_id.to_string(), GameState::new(9));
+    channel.set_rate_limit(RateLimitConfig { max_moves: 100, implausible_gap: Duration::ZERO, ..RateLimitConfig::default() }).await;
+    channel
+}
+
+#[tokio::test]
+async fn spectator_receives_every_move_in_order() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_broadcast(Lobby::new_game_id(), Some("Lesson".to_string()), GameState::new(9)).await.unwrap();
+    let host = lobby.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(host.role(), ChannelRole::Broadcast);
+
+    let watcher = spectator(&game_id).await;
+    let mut events = watcher.subscribe();
+    for mv in script() {
+        host.send_move(mv).await.unwrap();
+        // What the host's connections send each peer
+        let record = host.sync_moves().await.pop().unwrap();
+        let outcome = watcher.receive_direct("host", DirectMessage::Move(record)).await;
+        assert!(matches!(outcome, InboundOutcome::Reply(DirectMessage::Ack { .. })), "{:?}", outcome);
+    }
+
+    let mut seen = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let GameEvent::MoveMade { mv, by } = event {
+            seen.push((mv, by));
+        }
+    }
+    let expected: Vec<(Move, Color)> = script().into_iter()
+        .zip([Color::Black, Color::White].into_iter().cycle())
+        .collect();
+    assert_eq!(seen, expected);
+    assert!(watcher.get_latest_state().await.unwrap().is_game_over());
+
+    // Someone tuning in late catches up in one sync
+    let late = spectator(&game_id).await;
+    let InboundOutcome::Reply(response) = host.receive_direct("late", DirectMessage::SyncRequest).await else {
+        panic!("the host should answer a sync request");
+    };
+    late.receive_direct("host", response).await;
+    assert_eq!(late.get_latest_state().await.unwrap().moves, script());
+}
+
+#[tokio::test]
+async fn moves_from_spectators_are_refused() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_broadcast(Lobby::new_game_id(), None, GameState::new(9)).await.unwrap();
+    let host = lobby.get_game_channel(&game_id).await.unwrap();
+    host.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+
+    // A spectator plays White's reply into its own copy and sends it
+    let watcher = spectator(&game_id).await;
+    watcher.apply_sync(&host.sync_moves().await).await.unwrap();
+    watcher.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    let sneaky = watcher.sync_moves().await.pop().unwrap();
+    let outcome = host.receive_direct("watcher", DirectMessage::Move(sneaky)).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+
+    let state = watcher.get_latest_state().await.unwrap();
+    let outcome = host.receive_direct("watcher", DirectMessage::SyncResponse { moves: watcher.sync_moves().await, state }).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+    assert_eq!(host.get_latest_state().await.unwrap().moves, vec![Move::Place(Coord::new(4, 4))]);
+
+    // The teacher plays White's move at the board instead
+    host.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+    assert_eq!(host.get_latest_state().await.unwrap().moves.len(), 2);
+}
+
+#[tokio::test]
+async fn everyone_but_the_host_is_offered_spectating() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_broadcast(Lobby::new_game_id(), None, GameState::new(9)).await.unwrap();
+    lobby.set_host(&game_id, profile("teacher"), JoinPolicy::Everyone).await.unwrap();
+
+    // Even with the opponent seat free and a policy accepting everyone
+    let response = lobby.request_join(&game_id, profile("student")).await.unwrap();
+    assert_eq!(response, JoinResponse::SpectateOffered { reason: BROADCAST_REASON.to_string() });
+    let response = lobby.request_join(&game_id, profile("teacher")).await.unwrap();
+    assert_eq!(response, JoinResponse::Declined { reason: SELF_JOIN_REASON.to_string() });
+
+    // Ordinary games still seat the first joiner
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("teacher"), JoinPolicy::Everyone).await.unwrap();
+    let response = lobby.request_join(&game_id, profile("student")).await.unwrap();
+    assert!(matches!(response, JoinResponse::Accepted { color: Color::White, .. }));
+}
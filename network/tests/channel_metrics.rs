@@ -0,0 +1,144 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-game channel metrics: counters, ACK round trips and the summary.
+
+use p2pgo_network::channel_metrics::{ChannelMetrics, ACK_SAMPLES, ACK_TIMEOUT};
+use std::time::{Duration, Instant};
+
+#[test]
+fn acks_match_sent_moves_by_index() {
+    let metrics = ChannelMetrics::new();
+    let start = Instant::now();
+    for index in 0..10 {
+        metrics.record_move_sent(index, start);
+    }
+    for index in 0..10 {
+        let rtt = metrics.record_ack(index, start + Duration::from_millis(10 * (index as u64 + 1)));
+        assert_eq!(rtt, Some(Duration::from_millis(10 * (index as u64 + 1))));
+    }
+    // An ACK for a move we never sent, or one already acknowledged, adds nothing
+    assert_eq!(metrics.record_ack(3, start + Duration::from_secs(1)), None);
+    assert_eq!(metrics.record_ack(99, start), None);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.moves_sent, 10);
+    assert_eq!(snapshot.ack_samples, 10);
+    assert_eq!(snapshot.ack_p50, Some(Duration::from_millis(50)));
+    assert_eq!(snapshot.ack_p95, Some(Duration::from_millis(100)));
+    assert_eq!(snapshot.ack_p99, Some(Duration::from_millis(100)));
+}
+
+#[test]
+fn unacknowledged_moves_expire_once() {
+    let metrics = ChannelMetrics::new();
+    let start = Instant::now();
+    metrics.record_move_sent(0, start);
+    metrics.record_move_sent(1, start + Duration::from_secs(20));
+    assert_eq!(metrics.expire_acks(start + Duration::from_secs(10), ACK_TIMEOUT), 0);
+    assert_eq!(metrics.expire_acks(start + ACK_TIMEOUT, ACK_TIMEOUT), 1);
+    assert_eq!(metrics.expire_acks(start + ACK_TIMEOUT, ACK_TIMEOUT), 0, "counted once");
+    // A late ACK for an expired move is no round trip
+    assert_eq!(metrics.record_ack(0, start + ACK_TIMEOUT), None);
+    assert!(metrics.record_ack(1, start + ACK_TIMEOUT).is_some());
+}
+
+#[test]
+fn only_recent_round_trips_are_kept() {
+    let metrics = ChannelMetrics::new();
+    let start = Instant::now();
+    for index in 0..(ACK_SAMPLES as u32 + 20) {
+        metrics.record_move_sent(index, start);
+        metrics.record_ack(index, start + Duration::from_millis(5));
+    }
+    assert_eq!(metrics.snapshot().ack_samples, ACK_SAMPLES);
+}
+
+#[test]
+fn summary_names_every_counter() {
+    let metrics = ChannelMetrics::new();
+    metrics.record_move_received();
+    metrics.record_duplicate();
+    metrics.record_sync_request_sent();
+    metrics.record_error("stream reset");
+    let summary = metrics.snapshot().to_string();
+    assert_eq!(
+        summary,
+        "moves sent 0, received 1, duplicates dropped 1; sync requests sent 1, served 0; \
+         no ACKs; last error: stream reset"
+    );
+}
+
+#[tokio::test]
+async fn local_moves_are_counted_as_sent() {
+    use p2pgo_core::{Coord, GameState, Move};
+    use p2pgo_network::game_channel::GameChannel;
+
+    let channel = GameChannel::new("metrics-game".to_string(), GameState::new(9));
+    channel.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    channel.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+    channel.send_move(Move::Pass).await.unwrap();
+
+    let metrics = channel.metrics();
+    assert_eq!(metrics.moves_sent, 3);
+    assert_eq!(metrics.moves_received, 0);
+    assert_eq!(metrics.last_error, None);
+}
+
+#[tokio::test]
+async fn moves_no_peer_hears_wait_for_no_ack() {
+    use p2pgo_core::{Coord, GameState, Move};
+    use p2pgo_network::clock::VirtualClock;
+    use p2pgo_network::game_channel::GameChannel;
+    use std::sync::Arc;
+
+    let clock = Arc::new(VirtualClock::new(1_700_000_000));
+    let channel = GameChannel::new("unheard-game".to_string(), GameState::new(9)).with_clock(clock.clone());
+    channel.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    channel.send_move(Move::Pass).await.unwrap();
+
+    // No direct peer is connected, so nothing is lost when no ACK comes
+    clock.advance(ACK_TIMEOUT * 2);
+    assert_eq!(channel.expire_acks(), 0);
+    assert_eq!(channel.metrics().moves_sent, 2);
+}
+
+#[cfg(feature = "iroh")]
+#[tokio::test]
+async fn loopback_game_counts_moves_and_acks() {
+    use p2pgo_core::{Coord, GameState, Move};
+    use p2pgo_network::{game_channel::GameChannel, iroh_endpoint::IrohCtx};
+    use std::sync::Arc;
+
+    let host_ctx = Arc::new(IrohCtx::new().await.unwrap());
+    let guest_ctx = Arc::new(IrohCtx::new().await.unwrap());
+    let ticket = host_ctx.ticket().await.unwrap();
+
+    let game_id = "metrics-loopback".to_string();
+    let host = GameChannel::with_iroh(game_id.clone(), GameState::new(9), host_ctx).await.unwrap();
+    let guest = GameChannel::with_iroh(game_id, GameState::new(9), guest_ctx).await.unwrap();
+    guest.connect_to_peer(&ticket).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Players alternate; each move is applied on the other side before the next
+    let moves = [Coord::new(2, 2), Coord::new(6, 6), Coord::new(2, 6), Coord::new(6, 2)];
+    for (i, coord) in moves.iter().enumerate() {
+        let (sender, receiver) = if i % 2 == 0 { (&host, &guest) } else { (&guest, &host) };
+        sender.send_move(Move::Place(*coord)).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while receiver.get_latest_state().await.map_or(0, |s| s.moves.len()) <= i {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }).await.expect("move did not arrive");
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let host_metrics = host.metrics();
+    let guest_metrics = guest.metrics();
+    assert_eq!(host_metrics.moves_sent, 2);
+    assert_eq!(guest_metrics.moves_sent, 2);
+    assert_eq!(host_metrics.moves_received, 2);
+    assert_eq!(guest_metrics.moves_received, 2);
+    assert_eq!(guest_metrics.sync_requests_sent, 1);
+    assert_eq!(host_metrics.sync_requests_served, 1);
+    assert!(host_metrics.ack_samples + guest_metrics.ack_samples >= 1);
+}
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ACK watchdog tuning follows the game's clock, and each unacknowledged
+//! move is chased a limited number of times.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_core::settings::GameSettings;
+use p2pgo_network::channel_tuning::{AckWatchdog, ChannelTuning};
+use p2pgo_network::clock::VirtualClock;
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::matchmaking::TimeControl;
+use p2pgo_network::wire::DirectMessage;
+
+fn time_control(main_time_secs: u32, byo_yomi_secs: u32) -> TimeControl {
+    TimeControl { main_time_secs, byo_yomi_secs }
+}
+
+#[test]
+fn tuning_is_derived_from_the_time_control() {
+    let default = ChannelTuning::default();
+    assert_eq!((default.ack_timeout_ms, default.check_interval_ms), (3000, 500));
+
+    let table = [
+        (None, false, default),
+        (Some(time_control(600, 30)), false, default),
+        (Some(time_control(300, 20)), false, default),
+        (Some(time_control(300, 10)), false, ChannelTuning::BLITZ),
+        (Some(time_control(180, 0)), false, ChannelTuning::BLITZ),
+        (None, true, ChannelTuning::DISABLED),
+        (Some(time_control(180, 0)), true, ChannelTuning::DISABLED),
+    ];
+    for (time_control, correspondence, expected) in table {
+        assert_eq!(ChannelTuning::for_game(time_control.as_ref(), correspondence), expected, "{:?}, correspondence {}", time_control, correspondence);
+    }
+    assert_eq!(ChannelTuning::BLITZ.ack_timeout(), Duration::from_secs(1));
+    assert!(!ChannelTuning::DISABLED.enabled());
+}
+
+#[test]
+fn each_move_is_chased_at_most_max_sync_requests_times() {
+    let mut watchdog = AckWatchdog::new(ChannelTuning::default());
+    let start = Instant::now();
+    let pending = [(0, start)];
+
+    assert!(!watchdog.check(&pending, start + Duration::from_millis(2500)), "not overdue yet");
+    let mut requests = 0;
+    for step in 1..=120 {
+        if watchdog.check(&pending, start + Duration::from_millis(500 * step)) {
+            requests += 1;
+        }
+    }
+    assert_eq!(requests, 3);
+    assert_eq!(watchdog.requests(0), 3);
+
+    // A move sent later gets its own budget; the acknowledged one is forgotten
+    let later = start + Duration::from_secs(60);
+    assert!(watchdog.check(&[(1, later)], later + Duration::from_secs(3)));
+    assert_eq!(watchdog.requests(0), 0);
+    assert_eq!(watchdog.requests(1), 1);
+}
+
+#[test]
+fn disabled_watchdog_never_asks() {
+    let mut watchdog = AckWatchdog::new(ChannelTuning::DISABLED);
+    let start = Instant::now();
+    for step in 0..100 {
+        assert!(!watchdog.check(&[(0, start)], start + Duration::from_secs(step)));
+    }
+}
+
+#[tokio::test]
+async fn channel_chases_its_unacknowledged_move() {
+    let clock = Arc::new(VirtualClock::new(1_700_000_000));
+    let channel = GameChannel::new("tuned".to_string(), GameState::new(9))
+        .with_clock(clock.clone())
+        .with_tuning(ChannelTuning::BLITZ);
+    // Only a peer that reads framed messages acknowledges moves
+    channel.receive_direct("peer", DirectMessage::hello(&GameSettings::standard(9))).await;
+    channel.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+
+    assert!(!channel.check_acks().await);
+    clock.advance(Duration::from_secs(1));
+    assert!(channel.check_acks().await);
+    assert!(!channel.check_acks().await, "asked once per timeout");
+    clock.advance(Duration::from_secs(5));
+    assert!(channel.check_acks().await);
+    clock.advance(Duration::from_secs(5));
+    assert!(channel.check_acks().await);
+    clock.advance(Duration::from_secs(5));
+    assert!(!channel.check_acks().await, "gave up after three requests");
+}
+
+#[tokio::test]
+async fn lobby_tunes_games_by_their_terms_unless_overridden() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(channel.tuning(), ChannelTuning::default());
+
+    lobby.set_time_control(&game_id, Some(time_control(180, 5)), p2pgo_core::game_clock::OnDisconnect::Pause, true).await.unwrap();
+    assert_eq!(channel.tuning(), ChannelTuning::BLITZ);
+    lobby.set_correspondence(&game_id, true).await.unwrap();
+    assert_eq!(channel.tuning(), ChannelTuning::DISABLED);
+
+    let custom = ChannelTuning { ack_timeout_ms: 2000, check_interval_ms: 100, max_sync_requests: 1 };
+    lobby.set_channel_tuning(Some(custom)).await;
+    assert_eq!(channel.tuning(), custom);
+    let other = lobby.create_game(None, 9, false).await.unwrap();
+    assert_eq!(lobby.get_game_channel(&other).await.unwrap().tuning(), custom);
+
+    lobby.set_channel_tuning(None).await;
+    assert_eq!(channel.tuning(), ChannelTuning::DISABLED);
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Clock skew between peers: offset estimation, warnings and move ordering.
+
+use p2pgo_core::{Color, GameEvent, GameState};
+use p2pgo_network::archive::ArchiveManager;
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::relay_robustness::{ClockSkew, PeerClocks, OFFSET_SAMPLES};
+
+/// Our clock at the start of each test
+const T: u64 = 1_700_000_000;
+
+#[tokio::test]
+async fn ten_minutes_ahead_warns_once() {
+    let channel = GameChannel::new("skew".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+
+    // The hello arrives a second after the peer stamped it
+    let skew = channel.observe_peer_clock("fast", T + 600, T + 1).await;
+    assert_eq!(skew, Some(ClockSkew { peer: "fast".to_string(), offset_secs: 599 }));
+    match events.try_recv().unwrap() {
+        GameEvent::PeerWarning { peer, reason } => {
+            assert_eq!(peer, "fast");
+            assert!(reason.contains("9m 59s ahead"), "{}", reason);
+        }
+        other => panic!("expected a peer warning, got {:?}", other),
+    }
+
+    // Later moves keep the offset without warning again
+    for i in 1..5 {
+        assert_eq!(channel.observe_peer_clock("fast", T + 600 + 10 * i, T + 10 * i).await, None);
+    }
+    assert!(events.try_recv().is_err());
+    assert_eq!(channel.clock_skews().await.len(), 1);
+
+    // A peer a few seconds off is left alone
+    assert_eq!(channel.observe_peer_clock("close", T + 3, T).await, None);
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn skewed_moves_order_with_ours() {
+    let mut clocks = PeerClocks::new();
+    clocks.sample("fast", T + 600, T);
+
+    // We play on our clock; the peer answers ten seconds later on its own
+    let ours = [T + 10, T + 30, T + 50];
+    let theirs: Vec<u64> = ours.iter().map(|ts| ts + 10 + 600).collect();
+    for &ts in &theirs {
+        clocks.sample("fast", ts, ts - 600);
+    }
+
+    let mut moves: Vec<(u64, usize)> = Vec::new();
+    for (i, (&our_ts, &their_ts)) in ours.iter().zip(&theirs).enumerate() {
+        moves.push((our_ts, 2 * i));
+        moves.push((clocks.to_local("fast", their_ts), 2 * i + 1));
+    }
+    moves.sort();
+    let order: Vec<usize> = moves.iter().map(|&(_, index)| index).collect();
+    assert_eq!(order, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(clocks.to_local("fast", theirs[0]), T + 20);
+
+    // Synced moves carry no time and keep none
+    assert_eq!(clocks.to_local("fast", 0), 0);
+}
+
+#[test]
+fn one_odd_sample_does_not_move_the_offset() {
+    let mut clocks = PeerClocks::new();
+    for i in 0..OFFSET_SAMPLES as u64 - 1 {
+        assert_eq!(clocks.sample("slow", T - 30 + i, T + i), None);
+    }
+    // A message stuck in a queue for ten minutes
+    assert_eq!(clocks.sample("slow", T - 630, T), None);
+    assert_eq!(clocks.offset("slow"), -30);
+    assert!(clocks.skews().is_empty());
+
+    // Only the newest samples count, so a clock that jumps is followed
+    for i in 0..OFFSET_SAMPLES as u64 {
+        clocks.sample("slow", T + 400 + i, T + i);
+    }
+    assert_eq!(clocks.offset("slow"), 400);
+    assert_eq!(clocks.skews().len(), 1);
+    // Unstamped messages from older peers are not samples
+    assert_eq!(clocks.sample("old", 0, T), None);
+    assert_eq!(clocks.offset("old"), 0);
+}
+
+#[tokio::test]
+async fn skew_is_kept_with_the_game_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ArchiveManager::with_directory(dir.path().to_path_buf());
+    let skew = ClockSkew { peer: "fast".to_string(), offset_secs: 600 };
+    archive.archive_forfeit("skewed".to_string(), GameState::new(9), Color::Black, "resigned", Vec::new(), vec![skew.clone()]).await.unwrap();
+
+    let stored = archive.get_archive(&"skewed".to_string()).await.unwrap();
+    assert_eq!(stored.clock_skews, vec![skew]);
+    // Move times were corrected, so the game still counts for training
+    assert!(stored.usable_for_training());
+}
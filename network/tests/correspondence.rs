@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Correspondence games: moves made while a peer is offline, pending
+//! outbound moves across restarts, and the lobby flag.
+
+use std::time::Duration;
+use p2pgo_core::{Color, Coord, GameState, Move};
+use p2pgo_network::correspondence::{idle_config, unread_moves, MOVE_BUDGET};
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::snapshot::{GameSnapshot, SnapshotStore};
+
+const GAME: &str = "letters";
+
+fn channel(state: GameState) -> GameChannel {
+    let channel = GameChannel::new(GAME.to_string(), state);
+    channel.set_correspondence(true);
+    channel
+}
+
+async fn snapshot(channel: &GameChannel) -> GameSnapshot {
+    let mut snapshot = GameSnapshot::new(GAME.to_string(), channel.get_latest_state().await.unwrap());
+    snapshot.correspondence = true;
+    snapshot.outbox = channel.pending_outbound().await;
+    snapshot
+}
+
+#[tokio::test]
+async fn move_made_while_offline_is_unread_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let alice = channel(GameState::new(9));
+    let bob = channel(GameState::new(9));
+
+    // A move each while both are online
+    alice.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    assert_eq!(bob.apply_sync(&alice.sync_moves().await).await.unwrap(), 1);
+    bob.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    assert_eq!(alice.apply_sync(&bob.sync_moves().await).await.unwrap(), 1);
+
+    // Bob's app closes, then Alice moves
+    store.write_snapshot(&snapshot(&bob).await).unwrap();
+    drop(bob);
+    alice.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+    assert_eq!(alice.pending_outbound().await.len(), 1);
+
+    // Bob's app starts again and syncs with Alice
+    let saved = store.correspondence_games().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert!(store.latest_unfinished().unwrap().is_none(), "correspondence games resume without asking");
+    let seen = saved[0].state.moves.len();
+    let bob = channel(saved[0].state.clone());
+    let mut events = bob.subscribe();
+    assert_eq!(bob.apply_sync(&alice.sync_moves().await).await.unwrap(), 1);
+
+    let state = bob.get_latest_state().await.unwrap();
+    assert_eq!(state.moves.len(), 3);
+    assert_eq!(unread_moves(&state, seen, Color::White), 1);
+    assert!(events.try_recv().is_ok());
+    assert!(events.try_recv().is_err());
+
+    // A second sync brings nothing new
+    assert_eq!(bob.apply_sync(&alice.sync_moves().await).await.unwrap(), 0);
+    // Bob's reply tells Alice her move arrived
+    bob.send_move(Move::Place(Coord::new(2, 6))).await.unwrap();
+    assert_eq!(alice.apply_sync(&bob.sync_moves().await).await.unwrap(), 1);
+    assert!(alice.pending_outbound().await.is_empty());
+}
+
+#[tokio::test]
+async fn pending_moves_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let alice = channel(GameState::new(9));
+    alice.send_move(Move::Place(Coord::new(3, 3))).await.unwrap();
+    store.write_snapshot(&snapshot(&alice).await).unwrap();
+    drop(alice);
+
+    // The restarted channel still knows the move and that it is undelivered
+    let saved = store.correspondence_games().unwrap().remove(0);
+    assert_eq!(saved.outbox.len(), 1);
+    let alice = channel(saved.state);
+    alice.restore_outbound(saved.outbox).await;
+    assert_eq!(alice.sync_moves().await.len(), 1);
+
+    let bob = channel(GameState::new(9));
+    assert_eq!(bob.apply_sync(&alice.sync_moves().await).await.unwrap(), 1);
+    assert_eq!(bob.get_latest_state().await.unwrap().moves, [Move::Place(Coord::new(3, 3))]);
+    assert_eq!(alice.apply_sync(&bob.sync_moves().await).await.unwrap(), 0);
+    assert!(alice.pending_outbound().await.is_empty());
+
+    // Live games never hold moves back
+    let live = GameChannel::new("live".to_string(), GameState::new(9));
+    live.send_move(Move::Pass).await.unwrap();
+    assert!(live.pending_outbound().await.is_empty());
+}
+
+#[tokio::test]
+async fn lobby_lists_correspondence_games() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    assert!(!lobby.list_games().await[0].correspondence);
+
+    lobby.set_correspondence(&game_id, true).await.unwrap();
+    assert!(lobby.list_games().await[0].correspondence);
+    assert!(lobby.get_game_channel(&game_id).await.unwrap().is_correspondence());
+    lobby.publish_game_advert(&game_id, "host", None).await.unwrap();
+    assert!(lobby.set_correspondence(&"missing".to_string(), true).await.is_err());
+
+    // A day per move, with a reminder well before it runs out
+    let config = idle_config(MOVE_BUDGET);
+    assert_eq!(config.forfeit_after, Duration::from_secs(24 * 60 * 60));
+    assert_eq!(config.prompt_after, Duration::from_secs(18 * 60 * 60));
+}
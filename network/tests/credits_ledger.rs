@@ -0,0 +1,141 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Training credits ledger: persistence, receipts and balance.
+
+use ed25519_dalek::SigningKey;
+use p2pgo_network::credits::{
+    CreditSource, CreditsLedger, RelayReceipt, BYTES_PER_CREDIT, CREDIT_ENTRY_VERSION,
+    FEDERATED_ROUND_CREDITS, LEDGER_FILE, SHARED_GAME_CREDITS,
+};
+
+const OWNER: &str = "relay-node";
+
+fn peer_key(seed: u8) -> SigningKey {
+    SigningKey::from_bytes(&[seed; 32])
+}
+
+#[test]
+fn balance_adds_up_all_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut ledger = CreditsLedger::open(dir.path().join(LEDGER_FILE), OWNER).unwrap();
+    assert_eq!(ledger.balance(), 0);
+
+    let receipt = RelayReceipt::sign(&peer_key(1), OWNER, 3 * BYTES_PER_CREDIT + 10, 1_700_000_000);
+    assert_eq!(ledger.record_relay(receipt).unwrap().credits, 3);
+    ledger.record_shared_game(&"game-1".to_string()).unwrap();
+    ledger.record_federated_round(7).unwrap();
+
+    assert_eq!(ledger.balance(), 3 + SHARED_GAME_CREDITS + FEDERATED_ROUND_CREDITS);
+    let seqs: Vec<u64> = ledger.history().iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, vec![0, 1, 2]);
+}
+
+#[test]
+fn ledger_survives_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join(LEDGER_FILE);
+
+    {
+        let mut ledger = CreditsLedger::open(&path, OWNER).unwrap();
+        let receipt = RelayReceipt::sign(&peer_key(2), OWNER, 5 * BYTES_PER_CREDIT, 1_700_000_000);
+        ledger.record_relay(receipt).unwrap();
+        ledger.record_shared_game(&"game-2".to_string()).unwrap();
+    }
+
+    let mut ledger = CreditsLedger::open(&path, OWNER).unwrap();
+    assert_eq!(ledger.balance(), 5 + SHARED_GAME_CREDITS);
+    assert!(matches!(ledger.history()[0].source, CreditSource::Relay(_)));
+
+    // Appending after a restart continues the chain
+    ledger.record_federated_round(1).unwrap();
+    let reopened = CreditsLedger::open(&path, OWNER).unwrap();
+    assert_eq!(reopened.history().len(), 3);
+    assert_eq!(reopened.balance(), 5 + SHARED_GAME_CREDITS + FEDERATED_ROUND_CREDITS);
+}
+
+#[test]
+fn receipt_with_bad_signature_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut ledger = CreditsLedger::open(dir.path().join(LEDGER_FILE), OWNER).unwrap();
+
+    // Inflating the byte count after signing breaks the signature
+    let mut inflated = RelayReceipt::sign(&peer_key(3), OWNER, BYTES_PER_CREDIT, 1_700_000_000);
+    inflated.bytes *= 1000;
+    assert!(ledger.record_relay(inflated).is_err());
+
+    // So does claiming someone else's key signed it
+    let mut forged = RelayReceipt::sign(&peer_key(3), OWNER, BYTES_PER_CREDIT, 1_700_000_000);
+    forged.peer_key = peer_key(4).verifying_key().to_bytes();
+    assert!(ledger.record_relay(forged).is_err());
+
+    assert_eq!(ledger.balance(), 0);
+    assert!(ledger.history().is_empty());
+}
+
+#[test]
+fn receipts_for_other_relays_and_replays_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut ledger = CreditsLedger::open(dir.path().join(LEDGER_FILE), OWNER).unwrap();
+
+    let elsewhere = RelayReceipt::sign(&peer_key(5), "other-node", BYTES_PER_CREDIT, 1_700_000_000);
+    assert!(ledger.record_relay(elsewhere).is_err());
+
+    let receipt = RelayReceipt::sign(&peer_key(5), OWNER, BYTES_PER_CREDIT, 1_700_000_000);
+    ledger.record_relay(receipt.clone()).unwrap();
+    assert!(ledger.record_relay(receipt).is_err());
+    assert!(ledger.record_shared_game(&"game-3".to_string()).is_ok());
+    assert!(ledger.record_shared_game(&"game-3".to_string()).is_err());
+    assert_eq!(ledger.balance(), 1 + SHARED_GAME_CREDITS);
+}
+
+#[test]
+fn tampered_ledger_file_fails_to_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(LEDGER_FILE);
+    {
+        let mut ledger = CreditsLedger::open(&path, OWNER).unwrap();
+        ledger.record_shared_game(&"game-4".to_string()).unwrap();
+        ledger.record_federated_round(2).unwrap();
+    }
+
+    // Drop the first entry: the second no longer chains from nothing
+    let data = std::fs::read(&path).unwrap();
+    let first_len = serde_cbor::to_vec(&CreditsLedger::open(&path, OWNER).unwrap().history()[0])
+        .unwrap()
+        .len();
+    std::fs::write(&path, &data[first_len..]).unwrap();
+    assert!(CreditsLedger::open(&path, OWNER).is_err());
+}
+
+#[test]
+fn ledgers_from_before_versioning_still_chain() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(LEDGER_FILE);
+    {
+        let mut ledger = CreditsLedger::open(&path, OWNER).unwrap();
+        ledger.record_shared_game(&"game-5".to_string()).unwrap();
+        ledger.record_federated_round(3).unwrap();
+    }
+    let data = std::fs::read(&path).unwrap();
+    let entries: Vec<serde_cbor::Value> = serde_cbor::Deserializer::from_slice(&data)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(p2pgo_core::cbor::migrate::version_of(&serde_cbor::to_vec(&entries[0]).unwrap()), CREDIT_ENTRY_VERSION);
+
+    // Rewrite the ledger as unversioned entries were written
+    let mut old = Vec::new();
+    for entry in entries {
+        let serde_cbor::Value::Map(mut fields) = entry else {
+            panic!("Entries are maps");
+        };
+        fields.remove(&serde_cbor::Value::Text("v".to_string()));
+        old.extend(serde_cbor::to_vec(&fields).unwrap());
+    }
+    std::fs::write(&path, old).unwrap();
+
+    let mut ledger = CreditsLedger::open(&path, OWNER).unwrap();
+    assert_eq!(ledger.balance(), SHARED_GAME_CREDITS + FEDERATED_ROUND_CREDITS);
+    ledger.record_federated_round(4).unwrap();
+    assert_eq!(CreditsLedger::open(&path, OWNER).unwrap().history().len(), 3);
+}
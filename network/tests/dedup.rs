@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Duplicate moves from peers, however long the game has run.
+
+use std::sync::Arc;
+use std::time::Duration;
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_network::clock::{Clock, VirtualClock};
+use p2pgo_network::dedup::SequenceDedup;
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::wire::DirectMessage;
+
+#[test]
+fn old_duplicates_stay_rejected_after_heavy_traffic() {
+    let mut dedup = SequenceDedup::new(64);
+    for seq in 0..10_000 {
+        assert!(dedup.accept("peer", seq));
+    }
+    assert!(!dedup.accept("peer", 3), "a very old duplicate");
+    assert!(!dedup.accept("peer", 9_990), "a recent duplicate");
+    assert!(dedup.retained("peer") <= 2 * 64, "kept {} indices", dedup.retained("peer"));
+
+    // Late but inside the window is still new, once
+    let mut dedup = SequenceDedup::new(64);
+    for seq in (0..100).filter(|&s| s != 90) {
+        dedup.accept("peer", seq);
+    }
+    assert!(dedup.accept("peer", 90));
+    assert!(!dedup.accept("peer", 90));
+    assert!(dedup.accept("other", 90), "each peer is numbered apart");
+
+    dedup.clear();
+    assert!(dedup.accept("peer", 0));
+}
+
+#[tokio::test]
+async fn channel_drops_a_replayed_first_move() {
+    // 100 moves played elsewhere: a pass, then stones that never touch
+    let sender = GameChannel::new("dedup".to_string(), GameState::new(19));
+    sender.send_move(Move::Pass).await.unwrap();
+    for i in 0..99u8 {
+        sender.send_move(Move::Place(Coord::new(i % 10 * 2, i / 10 * 2))).await.unwrap();
+    }
+    let InboundOutcome::Reply(DirectMessage::SyncResponse { moves: records, .. }) =
+        sender.receive_direct("receiver", DirectMessage::SyncRequest).await
+    else {
+        panic!("expected the sender's moves");
+    };
+
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let receiver = GameChannel::new("dedup".to_string(), GameState::new(19)).with_clock(clock.clone());
+    let deliver = |record: &p2pgo_core::MoveRecord| {
+        let mut record = record.clone();
+        record.ts = clock.unix_secs();
+        // Slow enough for the rate limiter
+        clock.advance(Duration::from_secs(10));
+        receiver.receive_direct("sender", DirectMessage::Move(record))
+    };
+    for record in &records {
+        assert!(matches!(deliver(record).await, InboundOutcome::Reply(DirectMessage::Ack { .. })));
+    }
+    assert_eq!(receiver.get_all_moves().await.len(), 100);
+
+    // The opening pass again would otherwise be played as a second pass
+    assert!(matches!(deliver(&records[0]).await, InboundOutcome::Handled));
+    assert!(matches!(deliver(&records[98]).await, InboundOutcome::Handled));
+    assert_eq!(receiver.get_all_moves().await.len(), 100);
+    assert_eq!(receiver.metrics().duplicates_dropped, 2);
+}
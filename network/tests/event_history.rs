@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Late subscribers replay a game's recent events before the live ones.
+
+use p2pgo_core::{Color, Coord, GameEvent, GameState, Move};
+use p2pgo_network::event_log::EventLog;
+use p2pgo_network::GameChannel;
+
+fn chat(n: usize) -> GameEvent {
+    GameEvent::ChatMessage { from: Color::Black, message: format!("message {}", n) }
+}
+
+fn message(event: &GameEvent) -> &str {
+    match event {
+        GameEvent::ChatMessage { message, .. } => message,
+        other => panic!("expected a chat message, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn history_arrives_before_live_events() {
+    let channel = GameChannel::new("history".to_string(), GameState::new(9));
+    for n in 0..5 {
+        channel.send_event(chat(n)).await.unwrap();
+    }
+
+    let mut stream = channel.subscribe_with_history(10);
+    channel.send_event(chat(5)).await.unwrap();
+
+    for n in 0..6 {
+        let event = stream.recv().await.unwrap();
+        assert_eq!((event.seq, message(&event.event)), (n as u64 + 1, message(&chat(n))));
+    }
+    assert!(stream.try_recv().is_err());
+}
+
+#[test]
+fn history_keeps_only_the_latest_events() {
+    let log = EventLog::with_capacity(3);
+    for n in 0..5 {
+        let _ = log.send(chat(n));
+    }
+    assert_eq!(log.last_seq(), 5);
+
+    let mut stream = log.subscribe_with_history(10);
+    let seqs: Vec<u64> = std::iter::from_fn(|| stream.try_recv().ok()).map(|event| event.seq).collect();
+    assert_eq!(seqs, vec![3, 4, 5]);
+
+    let mut stream = log.subscribe_with_history(1);
+    assert_eq!(message(&stream.try_recv().unwrap().event), "message 4");
+    assert!(stream.try_recv().is_err());
+}
+
+#[test]
+fn events_nobody_hears_are_kept_for_later() {
+    assert_eq!(EventLog::default().send(chat(0)).unwrap(), 0);
+    let log = EventLog::with_capacity(0);
+    assert!(log.send(chat(0)).is_err(), "nobody listens and nothing is kept");
+    let _stream = log.subscribe_with_history(0);
+    assert_eq!(log.send(chat(1)).unwrap(), 1);
+    let _live = log.subscribe();
+    assert_eq!(log.send(chat(2)).unwrap(), 2);
+}
+
+#[tokio::test]
+async fn snapshot_and_stream_leave_no_gap_and_no_repeat() {
+    let channel = GameChannel::new("snapshot".to_string(), GameState::new(9));
+    channel.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    channel.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+
+    let mut stream = channel.subscribe_with_history(100);
+    let (state, seq) = channel.snapshot().await;
+    assert_eq!(state.unwrap().moves.len(), 2);
+    stream.skip_through(seq);
+    channel.send_move(Move::Pass).await.unwrap();
+
+    let mut moves = Vec::new();
+    while let Ok(event) = stream.try_recv() {
+        assert!(event.seq > seq);
+        if let GameEvent::MoveMade { mv, .. } = event.event {
+            moves.push(mv);
+        }
+    }
+    assert_eq!(moves, vec![Move::Pass]);
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Payloads too large for one gossip message: fragmented and put back
+//! together, given up on when fragments go missing, or refused.
+
+use std::time::{Duration, Instant};
+use p2pgo_network::fragment::{self, GossipLimits, Oversize, Reassembler};
+use p2pgo_network::{Error, IrohCtx};
+
+fn limits(max_message: usize) -> GossipLimits {
+    GossipLimits { max_message, ..GossipLimits::default() }
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+#[test]
+fn fragments_reassemble_in_any_order() {
+    let limits = limits(100);
+    let payload = payload(1000);
+    let mut fragments = fragment::split(&payload, "peer", &limits).unwrap();
+    assert!(fragments.len() > 1);
+    assert!(fragments.iter().all(|f| f.len() <= 100 && fragment::is_fragment(f)));
+
+    fragments.reverse();
+    let (last, rest) = fragments.split_last().unwrap();
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new(limits);
+    for f in rest {
+        assert_eq!(reassembler.receive("peer", f, now).unwrap(), None);
+    }
+    assert!(reassembler.buffered("peer") > 0);
+    assert_eq!(reassembler.receive("peer", last, now).unwrap(), Some(payload));
+    assert_eq!(reassembler.buffered("peer"), 0);
+}
+
+#[test]
+fn fragments_reassemble_whichever_neighbour_delivers_them() {
+    let limits = limits(100);
+    let payload = payload(400);
+    let fragments = fragment::split(&payload, "origin", &limits).unwrap();
+    let (last, rest) = fragments.split_last().unwrap();
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new(limits.clone());
+    for (i, f) in rest.iter().enumerate() {
+        let neighbour = if i % 2 == 0 { "left" } else { "right" };
+        assert_eq!(reassembler.receive(neighbour, f, now).unwrap(), None);
+    }
+    assert_eq!(reassembler.buffered("left"), 0);
+    assert!(reassembler.buffered("origin") > 0);
+
+    // The same payload split by another peer is a message of its own
+    let theirs = fragment::split(&payload, "elsewhere", &limits).unwrap();
+    assert_eq!(reassembler.receive("left", &theirs[0], now).unwrap(), None);
+    assert_eq!(reassembler.receive("third", last, now).unwrap(), Some(payload));
+    assert!(reassembler.buffered("elsewhere") > 0);
+}
+
+#[test]
+fn small_payloads_pass_through_whole() {
+    let limits = limits(100);
+    let small = serde_cbor::to_vec(&"a move").unwrap();
+    assert_eq!(fragment::split(&small, "peer", &limits).unwrap(), vec![small.clone()]);
+    let mut reassembler = Reassembler::new(limits);
+    assert_eq!(reassembler.receive("peer", &small, Instant::now()).unwrap(), Some(small));
+}
+
+#[test]
+fn missing_fragments_time_out() {
+    let limits = GossipLimits { reassembly_timeout: Duration::from_secs(5), ..limits(100) };
+    let fragments = fragment::split(&payload(500), "peer", &limits).unwrap();
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new(limits);
+    for f in &fragments[1..] {
+        assert_eq!(reassembler.receive("peer", f, now).unwrap(), None);
+    }
+
+    assert_eq!(reassembler.expire(now + Duration::from_secs(4)), 0);
+    assert_eq!(reassembler.expire(now + Duration::from_secs(5)), 1);
+    assert_eq!(reassembler.buffered("peer"), 0);
+    // The straggler alone completes nothing
+    let late = now + Duration::from_secs(6);
+    assert_eq!(reassembler.receive("peer", &fragments[0], late).unwrap(), None);
+}
+
+#[test]
+fn each_sender_has_a_buffer_cap() {
+    let limits = GossipLimits { max_buffered_per_sender: 300, ..limits(100) };
+    let fragments = fragment::split(&payload(1000), "flooder", &limits).unwrap();
+    let now = Instant::now();
+    let mut reassembler = Reassembler::new(limits.clone());
+    let mut rejected = false;
+    for f in &fragments {
+        if reassembler.receive("flooder", f, now).is_err() {
+            rejected = true;
+            break;
+        }
+    }
+    assert!(rejected);
+    assert!(reassembler.buffered("flooder") <= 300);
+
+    // Other senders aren't held back by it
+    let small = fragment::split(&payload(150), "other", &limits).unwrap();
+    assert_eq!(reassembler.receive("other", &small[0], now).unwrap(), None);
+    assert_eq!(reassembler.receive("other", &small[1], now).unwrap(), Some(payload(150)));
+}
+
+#[test]
+fn oversize_payloads_can_be_refused() {
+    let limits = GossipLimits { oversize: Oversize::Refuse, ..limits(100) };
+    assert!(fragment::split(&payload(100), "peer", &limits).is_ok());
+    let err = fragment::split(&payload(101), "peer", &limits).unwrap_err();
+    assert!(matches!(err, Error::PayloadTooLarge { len: 101, limit: 100 }), "{}", err);
+}
+
+#[tokio::test]
+async fn broadcasts_respect_the_limits() {
+    let ctx = IrohCtx::new().await.unwrap()
+        .with_gossip_limits(GossipLimits { oversize: Oversize::Refuse, ..limits(100) });
+    let topic = IrohCtx::game_topic("fragment-test");
+
+    let err = ctx.broadcast_to_topic(topic, &payload(1000)).await.unwrap_err();
+    assert!(matches!(err, Error::PayloadTooLarge { len: 1000, .. }), "{}", err);
+}
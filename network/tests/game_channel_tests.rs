@@ -84,3 +84,27 @@ async fn test_game_channel_multiple_moves() {
     let state = channel.get_latest_state().await.unwrap();
     assert_eq!(state.current_player, Color::White); // After 3 moves, it's White's turn again
 }
+
+#[tokio::test]
+async fn test_game_channel_announces_game_end() {
+    use p2pgo_core::EndReason;
+    
+    for (moves, reason, winner) in [
+        (vec![Move::Pass, Move::Pass], EndReason::DoublePass, None),
+        (vec![Move::Place(Coord::new(4, 4)), Move::Resign], EndReason::Resignation, Some(Color::Black)),
+    ] {
+        let channel = GameChannel::new("test-game-end".to_string(), GameState::new(9));
+        let mut rx = channel.subscribe();
+        for mv in moves {
+            channel.send_move(mv).await.unwrap();
+        }
+        
+        let mut ended = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if let GameEvent::GameEnded { winner, reason, .. } = event {
+                ended.push((reason, winner));
+            }
+        }
+        assert_eq!(ended, vec![(reason, winner)]);
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The channel keeping the game clock runs it with the moves, sends it
+//! with acknowledgements, pauses it while the opponent is away, records
+//! each mover's clock for the archive and ends the game on time.
+
+use std::sync::Arc;
+use std::time::Duration;
+use p2pgo_core::game_clock::{ClockSnapshot, GameClock, OnDisconnect, TimingInfo};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::phase::GamePhase;
+use p2pgo_core::{Color, Coord, EndReason, GameEvent, GameState, Move, MoveRecord};
+use p2pgo_network::clock::{Clock, VirtualClock};
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::matchmaking::TimeControl;
+use p2pgo_network::wire::DirectMessage;
+use tokio::sync::broadcast;
+
+/// Black's and White's opening moves as a peer would send them
+async fn opening() -> Vec<MoveRecord> {
+    let sender = GameChannel::new("clock".to_string(), GameState::new(9));
+    sender.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    sender.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+    match sender.receive_direct("host", DirectMessage::SyncRequest).await {
+        InboundOutcome::Reply(DirectMessage::SyncResponse { moves, .. }) => moves,
+        other => panic!("expected the sender's moves, got {:?}", other),
+    }
+}
+
+fn last_clock(events: &mut broadcast::Receiver<GameEvent>) -> Option<ClockSnapshot> {
+    let mut last = None;
+    while let Ok(event) = events.try_recv() {
+        if let GameEvent::Clock { snapshot } = event {
+            last = Some(snapshot);
+        }
+    }
+    last
+}
+
+async fn ack_clock(outcome: InboundOutcome) -> ClockSnapshot {
+    match outcome {
+        InboundOutcome::Reply(DirectMessage::Ack { clock: Some(clock), .. }) => clock,
+        other => panic!("expected an acknowledgement with the clocks, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn acks_carry_the_clock_and_disconnects_pause_it() {
+    let records = opening().await;
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let host = GameChannel::new("clock".to_string(), GameState::new(9)).with_clock(clock.clone());
+    let time_control = TimeControl { main_time_secs: 60, byo_yomi_secs: 10 };
+    host.set_time_control(time_control.game_clock(), OnDisconnect::Pause).await;
+    let mut events = host.subscribe();
+
+    let deliver = |record: &MoveRecord| {
+        let mut record = record.clone();
+        record.ts = clock.unix_secs();
+        host.receive_direct("guest", DirectMessage::Move(record))
+    };
+    let snapshot = ack_clock(deliver(&records[0]).await).await;
+    assert_eq!(snapshot.running, Some(Color::White));
+    assert_eq!(last_clock(&mut events), Some(snapshot));
+
+    clock.advance(Duration::from_secs(10));
+    let snapshot = ack_clock(deliver(&records[1]).await).await;
+    assert_eq!(snapshot.running, Some(Color::Black));
+    assert_eq!(snapshot.white.main_ms, 50_000);
+
+    // Black's clock stands still while the opponent is away
+    clock.advance(Duration::from_secs(5));
+    host.peer_disconnected().await;
+    assert!(last_clock(&mut events).unwrap().paused);
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(host.clock_snapshot().await.unwrap().black.main_ms, 55_000);
+
+    let hello = DirectMessage::Hello { formats: Vec::new(), sent_at: clock.unix_secs(), settings: None };
+    host.receive_direct("guest", hello).await;
+    clock.advance(Duration::from_secs(5));
+    let snapshot = host.clock_snapshot().await.unwrap();
+    assert!(!snapshot.paused);
+    assert_eq!(snapshot.black.main_ms, 50_000);
+}
+
+#[tokio::test]
+async fn clocks_can_keep_running_through_a_disconnect() {
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let host = GameChannel::new("clock".to_string(), GameState::new(9)).with_clock(clock.clone());
+    let time_control = TimeControl { main_time_secs: 60, byo_yomi_secs: 0 };
+    host.set_time_control(time_control.game_clock(), OnDisconnect::KeepRunning).await;
+    host.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+
+    host.peer_disconnected().await;
+    clock.advance(Duration::from_secs(20));
+    let snapshot = host.clock_snapshot().await.unwrap();
+    assert_eq!(snapshot.running, Some(Color::White));
+    assert_eq!(snapshot.white.main_ms, 40_000);
+}
+
+#[tokio::test]
+async fn channels_without_a_clock_follow_their_peers() {
+    let keeper = GameChannel::new("clock".to_string(), GameState::new(9));
+    keeper.set_time_control(TimeControl::default().game_clock(), OnDisconnect::Pause).await;
+    keeper.send_move(Move::Pass).await.unwrap();
+    let mut keeper_events = keeper.subscribe();
+    keeper.send_clock_tick().await;
+    let tick = last_clock(&mut keeper_events).expect("a tick");
+
+    let follower = GameChannel::new("clock".to_string(), GameState::new(9));
+    let mut events = follower.subscribe();
+    follower.receive_direct("keeper", DirectMessage::ClockTick(tick)).await;
+    assert_eq!(last_clock(&mut events), Some(tick));
+    assert_eq!(follower.clock_snapshot().await, None);
+
+    // Those keeping their own ignore other clocks
+    let other = ClockSnapshot { running: None, ..tick };
+    keeper.receive_direct("follower", DirectMessage::ClockTick(other)).await;
+    assert_eq!(last_clock(&mut keeper_events), None);
+}
+
+#[tokio::test]
+async fn the_keeper_records_each_movers_clock() {
+    let records = opening().await;
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let host = GameChannel::new("clock".to_string(), GameState::new(9)).with_clock(clock.clone());
+    host.set_time_control(TimeControl { main_time_secs: 60, byo_yomi_secs: 10 }.game_clock(), OnDisconnect::Pause).await;
+    for record in &records {
+        host.receive_direct("guest", DirectMessage::Move(MoveRecord { ts: clock.unix_secs(), ..record.clone() })).await;
+        clock.advance(Duration::from_secs(10));
+    }
+
+    let game = host.game_record().await.unwrap();
+    assert_eq!(game.timing.at(1), Some(TimingInfo { time_left_ms: 50_000, periods: None }));
+    assert_eq!(game.timing.think_time(1), Some(Duration::from_secs(10)));
+    let sgf = SgfProcessor::new(game).generate();
+    assert!(sgf.contains("TM[60]") && sgf.contains("W[gg]WL[50]"), "{}", sgf);
+    // The channel's own state stays as the move chain has it
+    assert!(host.get_latest_state().await.unwrap().timing.is_empty());
+}
+
+#[tokio::test]
+async fn byo_yomi_periods_run_out_one_by_one_then_the_game_ends() {
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let host = GameChannel::new("clock".to_string(), GameState::new(9)).with_clock(clock.clone());
+    let byo_yomi = GameClock::new(Duration::from_secs(10), Duration::from_secs(5), 3);
+    host.set_time_control(byo_yomi, OnDisconnect::Pause).await;
+    host.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    let mut events = host.subscribe();
+    let periods_used = |events: &mut broadcast::Receiver<GameEvent>| {
+        let mut used = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let GameEvent::ByoYomiPeriodUsed { player, periods_left } = event {
+                used.push((player, periods_left));
+            }
+        }
+        used
+    };
+
+    // White thinks through the main time and two of three periods
+    clock.advance(Duration::from_secs(12));
+    host.send_clock_tick().await;
+    assert_eq!(periods_used(&mut events), vec![]);
+    clock.advance(Duration::from_secs(4));
+    host.send_clock_tick().await;
+    assert_eq!(periods_used(&mut events), vec![(Color::White, 2)]);
+    clock.advance(Duration::from_secs(5));
+    host.send_clock_tick().await;
+    assert_eq!(periods_used(&mut events), vec![(Color::White, 1)]);
+    assert_eq!(host.clock_snapshot().await.unwrap().white.periods, 1);
+    assert!(matches!(host.phase().await, GamePhase::Active | GamePhase::AwaitingOpponent));
+
+    // The last period runs out too
+    clock.advance(Duration::from_secs(5));
+    host.send_clock_tick().await;
+    assert_eq!(periods_used(&mut events), vec![(Color::White, 0)]);
+    let GamePhase::Finished { result } = host.phase().await else {
+        panic!("the game didn't end on time");
+    };
+    assert_eq!((result.winner, result.reason), (Some(Color::Black), EndReason::Timeout));
+    let stopped = host.clock_snapshot().await.unwrap();
+    assert_eq!(stopped.running, None);
+
+    // A stopped clock announces nothing more
+    clock.advance(Duration::from_secs(5));
+    host.send_clock_tick().await;
+    assert!(events.try_recv().is_err());
+}
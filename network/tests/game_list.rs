@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The games list stays bounded under a flood of adverts and pages correctly.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use p2pgo_network::game_list::{
+    AdvertStore, GamesQuery, GamesSort, Ingest, ADVERT_TTL_SECS, GAMES_PAGE_SIZE, MAX_ADVERTS, MAX_ADVERTS_PER_SENDER,
+};
+use p2pgo_network::lobby::{GameAdvert, GameTerms, LobbyEvent};
+use p2pgo_network::relay_robustness::now_secs;
+use p2pgo_network::Lobby;
+
+const NOW: u64 = 1_700_000_000;
+
+fn advert(index: usize, host: &str, posted: u64) -> GameAdvert {
+    let size = [9, 13, 19][index % 3];
+    let mut terms = GameTerms::standard(size);
+    terms.rated = index % 2 == 0;
+    GameAdvert {
+        gid: format!("game-{}", index),
+        size,
+        host: host.to_string(),
+        bot: None,
+        correspondence: false,
+        teacher: None,
+        terms: Some(terms),
+        posted,
+        state: None,
+    }
+}
+
+fn index(id: &str) -> usize {
+    id.trim_start_matches("game-").parse().unwrap()
+}
+
+#[test]
+fn ten_thousand_adverts_stay_bounded() {
+    let mut store = AdvertStore::new();
+    let mut outcomes = Vec::new();
+    let started = Instant::now();
+    for i in 0..10_000 {
+        // Five adverts per host; each host's last one is stale
+        let host = format!("host-{}", i / 5);
+        let posted = if i % 5 == 4 { NOW - ADVERT_TTL_SECS - 1 } else { NOW - 500 + (i / 20) as u64 };
+        outcomes.push(store.ingest(&advert(i, &host, posted), &host, NOW));
+    }
+    let elapsed = started.elapsed();
+    assert!(elapsed < Duration::from_secs(2), "ingesting 10k adverts took {:?}", elapsed);
+
+    let count = |outcome: Ingest| outcomes.iter().filter(|o| **o == outcome).count();
+    assert_eq!(count(Ingest::Expired), 2_000);
+    assert_eq!(count(Ingest::SenderFull), 2_000);
+    assert_eq!(count(Ingest::Added), 6_000);
+
+    // The oldest made room for the rest
+    assert_eq!(store.len(), MAX_ADVERTS);
+    assert!(store.len() <= store.senders() * MAX_ADVERTS_PER_SENDER);
+    assert!(!store.contains(&"game-0".to_string()));
+    assert!(store.contains(&"game-9997".to_string()));
+}
+
+#[test]
+fn pages_walk_the_whole_list_once() {
+    let mut store = AdvertStore::new();
+    for i in 0..1_000 {
+        let host = format!("host-{}", i / 3);
+        store.ingest(&advert(i, &host, NOW - 500 + (i / 10) as u64), &host, NOW);
+    }
+
+    for query in [
+        GamesQuery::default(),
+        GamesQuery { board_size: Some(9), ..GamesQuery::default() },
+        GamesQuery { rated_only: true, sort: GamesSort::BoardSize, ..GamesQuery::default() },
+    ] {
+        let mut seen = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = store.page(&query, offset, GAMES_PAGE_SIZE);
+            assert!(page.games.len() <= GAMES_PAGE_SIZE);
+            assert!(page.games.iter().all(|info| query.matches(info)));
+            seen.extend(page.games.iter().cloned());
+            offset += page.games.len();
+            if !page.has_more() {
+                assert_eq!(offset, page.total);
+                break;
+            }
+        }
+        let ids: HashSet<_> = seen.iter().map(|info| info.id.clone()).collect();
+        assert_eq!(ids.len(), seen.len(), "a game was listed twice");
+        assert_eq!(seen.len(), (0..1_000).filter(|i| query.matches(&advert(*i, "", 0).info())).count());
+
+        match query.sort {
+            GamesSort::Newest => assert!(seen.windows(2).all(|w| index(&w[0].id) / 10 >= index(&w[1].id) / 10)),
+            GamesSort::BoardSize => assert!(seen.windows(2).all(|w| w[0].board_size <= w[1].board_size)),
+        }
+    }
+}
+
+#[test]
+fn adverts_are_renewed_and_expire() {
+    let mut store = AdvertStore::new();
+    assert_eq!(store.ingest(&advert(1, "host", 0), "host", NOW), Ingest::Added);
+    assert_eq!(store.ingest(&advert(2, "host", NOW - 10), "host", NOW), Ingest::Added);
+    assert_eq!(store.page(&GamesQuery::default(), 0, 1).games[0].id, "game-1");
+
+    // Renewing moves a game back to the top; another host can't take it over
+    assert_eq!(store.ingest(&advert(2, "host", NOW + 5), "host", NOW + 5), Ingest::Refreshed);
+    assert_eq!(store.page(&GamesQuery::default(), 0, 1).games[0].id, "game-2");
+    assert_eq!(store.ingest(&advert(2, "squatter", NOW + 5), "squatter", NOW + 5), Ingest::WrongHost);
+
+    assert_eq!(store.expire(NOW + ADVERT_TTL_SECS + 1), 1);
+    assert_eq!(store.len(), 1);
+    // A host past its cap has room again once its adverts are gone
+    for i in 3..5 {
+        store.ingest(&advert(i, "host", NOW + 10), "host", NOW + 10);
+    }
+    assert_eq!(store.ingest(&advert(5, "host", NOW + 10), "host", NOW + 10), Ingest::SenderFull);
+    assert!(store.remove(&"game-3".to_string()));
+    assert_eq!(store.ingest(&advert(5, "host", NOW + 10), "host", NOW + 10), Ingest::Added);
+}
+
+#[tokio::test]
+async fn lobby_lists_its_own_games_before_adverts() {
+    let lobby = Lobby::new();
+    let mut events = lobby.subscribe();
+    for i in 0..60 {
+        let host = format!("host-{}", i);
+        lobby.ingest_advert(&advert(i, &host, now_secs()), &host).await;
+    }
+    assert!(matches!(events.try_recv(), Ok(LobbyEvent::AdvertsChanged)));
+    let ours = lobby.create_game(None, 19, false).await.unwrap();
+
+    let first = lobby.games_page(&GamesQuery::default(), 0, GAMES_PAGE_SIZE).await;
+    assert_eq!(first.total, 61);
+    assert_eq!(first.games.len(), GAMES_PAGE_SIZE);
+    assert_eq!(first.games[0].id, ours);
+    let rest = lobby.games_page(&GamesQuery::default(), first.games.len(), GAMES_PAGE_SIZE).await;
+    assert_eq!((rest.offset, rest.games.len()), (GAMES_PAGE_SIZE, 11));
+    assert!(!rest.has_more());
+
+    let nines = lobby.games_page(&GamesQuery { board_size: Some(9), ..GamesQuery::default() }, 0, GAMES_PAGE_SIZE).await;
+    assert_eq!(nines.total, 20);
+    // Stale adverts never make it in
+    let stale = advert(99, "late", now_secs() - ADVERT_TTL_SECS - 60);
+    assert_eq!(lobby.ingest_advert(&stale, "late").await, Ingest::Expired);
+}
+
+#[test]
+fn one_sender_cannot_pose_as_many_hosts() {
+    let mut store = AdvertStore::new();
+    let outcomes: Vec<Ingest> = (0..10)
+        .map(|i| store.ingest(&advert(i, &format!("host-{}", i), NOW), "flooder", NOW))
+        .collect();
+    assert_eq!(outcomes.iter().filter(|o| **o == Ingest::Added).count(), MAX_ADVERTS_PER_SENDER);
+    assert_eq!(outcomes.last(), Some(&Ingest::SenderFull));
+
+    // Others relaying a listed game only renew it
+    assert_eq!(store.ingest(&advert(0, "host-0", NOW + 1), "neighbour", NOW + 1), Ingest::Refreshed);
+    assert_eq!(store.ingest(&advert(10, "host-10", NOW), "neighbour", NOW), Ingest::Added);
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Listed games follow their channel from waiting to in play to finished.
+
+use std::time::Duration;
+use p2pgo_core::{GameState, Move};
+use p2pgo_network::game_list::{GamesQuery, GAMES_PAGE_SIZE};
+use p2pgo_network::join::{JoinPolicy, JoinResponse, PlayerProfile};
+use p2pgo_network::lobby::{GameListingState, Lobby, LobbyEvent};
+use tokio::sync::broadcast;
+
+fn profile(node_id: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+/// Ids of the games listed to join, and to spectate
+async fn listed(lobby: &Lobby) -> (Vec<String>, Vec<String>) {
+    let ids = |query: GamesQuery| async move {
+        lobby.games_page(&query, 0, GAMES_PAGE_SIZE).await.games.into_iter().map(|game| game.id).collect()
+    };
+    (ids(GamesQuery::default()).await, ids(GamesQuery { spectating: true, ..GamesQuery::default() }).await)
+}
+
+/// The next state change announced for `game_id`
+async fn next_state(events: &mut broadcast::Receiver<LobbyEvent>, game_id: &str) -> GameListingState {
+    let wait = async {
+        loop {
+            if let LobbyEvent::GameStateChanged { game_id: id, state } = events.recv().await.unwrap() {
+                if id == game_id {
+                    return state;
+                }
+            }
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), wait).await.expect("state change announced")
+}
+
+#[tokio::test]
+async fn game_walks_from_waiting_to_active_to_finished() {
+    let lobby = Lobby::new();
+    let mut events = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host"), JoinPolicy::Everyone).await.unwrap();
+    lobby.await_opponent(&game_id).await.unwrap();
+
+    let info = lobby.list_games().await.remove(0);
+    assert_eq!(info.state, GameListingState::Waiting { seats_open: 1 });
+    assert!(info.state.joinable());
+    assert_eq!(listed(&lobby).await, (vec![game_id.clone()], vec![]));
+
+    let response = lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    assert!(matches!(response, JoinResponse::Accepted { .. }));
+    assert_eq!(next_state(&mut events, &game_id).await, GameListingState::Active);
+    assert_eq!(listed(&lobby).await, (vec![], vec![game_id.clone()]));
+    // A full game offers spectating instead
+    let late = lobby.request_join(&game_id, profile("bob")).await.unwrap();
+    assert!(matches!(late, JoinResponse::SpectateOffered { .. }));
+
+    lobby.post_player_move(&game_id, "host", Move::Resign).await.unwrap();
+    assert_eq!(next_state(&mut events, &game_id).await, GameListingState::Finished);
+    assert_eq!(listed(&lobby).await, (vec![], vec![]));
+    assert_eq!(lobby.list_games().await[0].state, GameListingState::Finished);
+    assert_eq!(GameListingState::Finished.to_string(), "finished");
+}
+
+#[tokio::test]
+async fn broadcasts_are_listed_for_spectators_only() {
+    let lobby = Lobby::new();
+    let waiting = lobby.create_game(None, 9, false).await.unwrap();
+    let shared = lobby.create_broadcast("shared".to_string(), None, GameState::new(9)).await.unwrap();
+
+    assert_eq!(listed(&lobby).await, (vec![waiting], vec![shared.clone()]));
+    let watching = GamesQuery { spectating: true, board_size: Some(13), ..GamesQuery::default() };
+    assert!(lobby.games_page(&watching, 0, GAMES_PAGE_SIZE).await.games.is_empty());
+}
+
+#[tokio::test]
+async fn removed_games_stop_following_their_channel() {
+    let lobby = Lobby::new();
+    let mut events = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    lobby.remove_game(&game_id).await.unwrap();
+
+    channel.send_move(Move::Resign).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let changes = std::iter::from_fn(|| events.try_recv().ok())
+        .filter(|event| matches!(event, LobbyEvent::GameStateChanged { .. }))
+        .count();
+    assert_eq!(changes, 0);
+    assert!(lobby.list_games().await.is_empty());
+}
@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A game channel moves through its phases and refuses what a phase doesn't allow.
+
+use std::time::{Duration, Instant};
+use p2pgo_core::game_clock::GameClock;
+use p2pgo_core::phase::{GameAction, GamePhase, GameResult};
+use p2pgo_core::teaching::TeachingAnnotation;
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::{ScoreProof, ScoringMethod};
+use p2pgo_core::{Color, Coord, EndReason, GameEvent, GameState, Move, MoveRecord};
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::join::{JoinPolicy, JoinResponse, PlayerProfile};
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::presence::Presence;
+use p2pgo_network::wire::{DirectMessage, WireFormat};
+use p2pgo_network::Error;
+use tokio::sync::broadcast;
+
+const TEACHER: &str = "teacher";
+
+fn resigned() -> GameResult {
+    GameResult { winner: Some(Color::White), reason: EndReason::Resignation, score_diff: 0.0 }
+}
+
+fn scored() -> GameResult {
+    GameResult { winner: Some(Color::White), reason: EndReason::DoublePass, score_diff: 6.5 }
+}
+
+/// A fresh channel in `phase`, by the moves or calls that lead there
+async fn channel_in(phase: &GamePhase) -> GameChannel {
+    let channel = GameChannel::new("phase".to_string(), GameState::new(9));
+    channel.set_teacher(Some(TEACHER.to_string())).await;
+    match phase {
+        GamePhase::AwaitingOpponent => channel.await_opponent().await,
+        GamePhase::Active => {}
+        GamePhase::Scoring => {
+            channel.send_move(Move::Pass).await.unwrap();
+            channel.send_move(Move::Pass).await.unwrap();
+        }
+        GamePhase::Finished { .. } => channel.send_move(Move::Resign).await.unwrap(),
+    }
+    assert_eq!(channel.phase().await.to_string(), phase.to_string());
+    channel
+}
+
+fn phases() -> [GamePhase; 4] {
+    [GamePhase::AwaitingOpponent, GamePhase::Active, GamePhase::Scoring, GamePhase::Finished { result: resigned() }]
+}
+
+/// Phases announced so far
+fn phase_changes(rx: &mut broadcast::Receiver<GameEvent>) -> Vec<GamePhase> {
+    std::iter::from_fn(|| rx.try_recv().ok())
+        .filter_map(|event| match event {
+            GameEvent::PhaseChanged { phase } => Some(phase),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Ask `channel` for `action` the way a player would; `None` for what
+/// is dropped silently, which only a peer's messages show
+async fn attempt(channel: &GameChannel, action: GameAction) -> Option<Result<(), Error>> {
+    let result = match action {
+        GameAction::Move => channel.send_move(Move::Place(Coord::new(4, 4))).await,
+        GameAction::Undo => channel.request_undo(1).await,
+        GameAction::Annotate => channel.annotate(TEACHER, TeachingAnnotation::Comment("Look here".to_string())).await,
+        GameAction::Chat => channel.send_event(GameEvent::ChatMessage { from: Color::Black, message: "gg".to_string() }).await,
+        GameAction::Score => channel.settle(scored()).await,
+        GameAction::End => channel.send_event(GameEvent::GameEnded {
+            winner: Some(Color::Black),
+            score_diff: 0.0,
+            reason: EndReason::Forfeit("left".to_string()),
+            scores: None,
+        }).await,
+        GameAction::Clock | GameAction::Presence => return None,
+    };
+    Some(result)
+}
+
+#[tokio::test]
+async fn each_phase_refuses_what_it_does_not_allow() {
+    for phase in phases() {
+        for action in GameAction::ALL {
+            let channel = channel_in(&phase).await;
+            let _events = channel.subscribe();
+            let Some(result) = attempt(&channel, action).await else {
+                continue;
+            };
+            let refused = match result {
+                Err(Error::WrongPhase { phase: refused_in, action: refused }) => {
+                    assert_eq!((refused_in.to_string(), refused), (phase.to_string(), action));
+                    true
+                }
+                _ => false,
+            };
+            assert_eq!(refused, !phase.allows(action), "{} while the game is {}", action, phase);
+        }
+    }
+}
+
+#[tokio::test]
+async fn peers_are_refused_what_the_phase_does_not_allow() {
+    let tick = GameClock::new(Duration::from_secs(60), Duration::from_secs(10), 3).snapshot(Instant::now());
+    let presence = Presence { considering: true, last_active: 0 };
+    for phase in phases() {
+        let channel = channel_in(&phase).await;
+        let mut events = channel.subscribe();
+        let played = channel.get_latest_state().await.unwrap().moves.len();
+
+        let record = MoveRecord { mv: Move::Place(Coord::new(2, 2)), tag: None, ts: 0, broadcast_hash: None, prev_hash: None };
+        let mut moves = channel.sync_moves().await;
+        moves.push(record.clone());
+        if played == 0 {
+            channel.receive_direct("peer", DirectMessage::Move(record)).await;
+        } else {
+            let state = channel.get_latest_state().await.unwrap();
+            channel.receive_direct("peer", DirectMessage::SyncResponse { moves, state }).await;
+        }
+        let now_played = channel.get_latest_state().await.unwrap().moves.len();
+        assert_eq!(now_played > played, phase.allows(GameAction::Move), "peer move while the game is {}", phase);
+
+        channel.receive_direct("peer", DirectMessage::ClockTick(tick)).await;
+        let clocked = std::iter::from_fn(|| events.try_recv().ok()).any(|e| matches!(e, GameEvent::Clock { .. }));
+        assert_eq!(clocked, phase.allows(GameAction::Clock), "clock tick while the game is {}", phase);
+
+        channel.receive_direct("peer", DirectMessage::Presence(presence)).await;
+        let shown = std::iter::from_fn(|| events.try_recv().ok()).any(|e| matches!(e, GameEvent::Presence { .. }));
+        assert_eq!(shown, phase.allows(GameAction::Presence), "presence while the game is {}", phase);
+
+        let outcome = channel.receive_direct("peer", DirectMessage::UndoRequest { moves: 1 }).await;
+        if !phase.allows(GameAction::Undo) {
+            assert!(matches!(outcome, InboundOutcome::Reply(DirectMessage::UndoResponse { accepted: false })));
+            assert!(!std::iter::from_fn(|| events.try_recv().ok()).any(|e| matches!(e, GameEvent::UndoRequested { .. })));
+        }
+    }
+}
+
+#[tokio::test]
+async fn scored_game_goes_through_scoring() {
+    let channel = GameChannel::new("scored".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+    assert_eq!(channel.phase().await, GamePhase::Active);
+
+    channel.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    channel.send_move(Move::Pass).await.unwrap();
+    assert!(phase_changes(&mut events).is_empty());
+    channel.send_move(Move::Pass).await.unwrap();
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Scoring]);
+
+    channel.settle(scored()).await.unwrap();
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Finished { result: scored() }]);
+    // Settled once and for all
+    assert!(matches!(channel.settle(scored()).await, Err(Error::WrongPhase { action: GameAction::Score, .. })));
+}
+
+#[tokio::test]
+async fn resignation_finishes_the_game_at_once() {
+    let channel = GameChannel::new("resigned".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+    channel.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    channel.send_move(Move::Resign).await.unwrap();
+
+    let result = GameResult { winner: Some(Color::Black), reason: EndReason::Resignation, score_diff: 0.0 };
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Finished { result: result.clone() }]);
+    assert_eq!(channel.phase().await, GamePhase::Finished { result });
+}
+
+#[tokio::test]
+async fn peer_resignation_finishes_our_game_too() {
+    let black = GameChannel::new("peer".to_string(), GameState::new(9));
+    let white = GameChannel::new("peer".to_string(), GameState::new(9));
+    let mut events = white.subscribe();
+    black.send_move(Move::Resign).await.unwrap();
+    let record = black.sync_moves().await.pop().unwrap();
+    white.receive_direct("black", DirectMessage::Move(record)).await;
+
+    let phases = phase_changes(&mut events);
+    assert!(matches!(&phases[..], [GamePhase::Finished { result }] if result.winner == Some(Color::White)));
+}
+
+#[tokio::test]
+async fn a_resumed_game_starts_in_its_phase() {
+    let mut state = GameState::new(9);
+    state.apply_move(Move::Pass).unwrap();
+    state.apply_move(Move::Pass).unwrap();
+    let channel = GameChannel::new("resumed".to_string(), state);
+    assert_eq!(channel.phase().await, GamePhase::Scoring);
+}
+
+fn profile(node_id: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+#[tokio::test]
+async fn hosted_game_waits_for_its_opponent() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host"), JoinPolicy::Everyone).await.unwrap();
+    lobby.await_opponent(&game_id).await.unwrap();
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    let mut events = channel.subscribe();
+    assert_eq!(channel.phase().await, GamePhase::AwaitingOpponent);
+    assert!(matches!(
+        lobby.post_player_move(&game_id, "host", Move::Place(Coord::new(4, 4))).await,
+        Err(Error::WrongPhase { phase: GamePhase::AwaitingOpponent, action: GameAction::Move })
+    ));
+
+    let response = lobby.request_join(&game_id, profile("alice")).await.unwrap();
+    assert!(matches!(response, JoinResponse::Accepted { .. }));
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Active]);
+    lobby.post_player_move(&game_id, "host", Move::Place(Coord::new(4, 4))).await.unwrap();
+
+    // A seated game doesn't go back to waiting
+    lobby.await_opponent(&game_id).await.unwrap();
+    assert_eq!(channel.phase().await, GamePhase::Active);
+}
+
+#[tokio::test]
+async fn a_player_saying_hello_takes_the_seat() {
+    let channel = channel_in(&GamePhase::AwaitingOpponent).await;
+    let mut events = channel.subscribe();
+    channel.receive_direct("peer", DirectMessage::Hello { formats: vec![WireFormat::Cbor], sent_at: 0, settings: None }).await;
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Active]);
+}
+
+/// The score of `channel`'s position, with `komi`
+async fn score_of(channel: &GameChannel, komi: f32) -> ScoreProof {
+    let state = channel.get_latest_state().await.unwrap();
+    calculate_final_score(&state, komi, ScoringMethod::Territory, &Default::default())
+}
+
+#[tokio::test]
+async fn both_players_accepting_the_score_finishes_the_game() {
+    let channel = channel_in(&GamePhase::Scoring).await;
+    let mut events = channel.subscribe();
+    let proof = score_of(&channel, 6.5).await;
+
+    channel.accept_score(Color::Black, proof.clone()).await.unwrap();
+    assert_eq!(channel.accepted_score(Color::Black).await.map(|p| p.final_score), Some(proof.final_score));
+    assert!(phase_changes(&mut events).is_empty());
+
+    // The opponent's acceptance arrives from the peer
+    channel.receive_direct("peer", DirectMessage::ScoreAccepted { by: Color::White, proof }).await;
+    assert_eq!(phase_changes(&mut events), vec![GamePhase::Finished { result: scored() }]);
+}
+
+#[tokio::test]
+async fn different_scores_do_not_settle() {
+    let channel = channel_in(&GamePhase::Scoring).await;
+    let mut events = channel.subscribe();
+    channel.accept_score(Color::Black, score_of(&channel, 6.5).await).await.unwrap();
+    channel.accept_score(Color::White, score_of(&channel, 0.5).await).await.unwrap();
+    assert!(phase_changes(&mut events).is_empty());
+    assert_eq!(channel.phase().await, GamePhase::Scoring);
+
+    // A tampered proof from the peer is ignored
+    let mut forged = score_of(&channel, 0.5).await;
+    forged.territory_white += 10;
+    channel.receive_direct("peer", DirectMessage::ScoreAccepted { by: Color::White, proof: forged }).await;
+    assert_eq!(channel.accepted_score(Color::White).await.map(|p| p.komi), Some(0.5));
+}
@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game terms in lobby adverts: round trips, defaults for older peers,
+//! rule variations, and refusing rules this build doesn't know.
+
+use p2pgo_core::game_clock::OnDisconnect;
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::settings::{GameSettings, Rules, RULESET_VERSION};
+use p2pgo_core::GameState;
+use p2pgo_network::game_list::Ingest;
+use p2pgo_network::lobby::{GameAdvert, GameTerms, Lobby, ADVERT_VERSION};
+use p2pgo_network::matchmaking::TimeControl;
+use p2pgo_network::Error;
+
+fn advert(terms: Option<GameTerms>) -> GameAdvert {
+    GameAdvert {
+        gid: "game-1".to_string(),
+        size: 13,
+        host: "host".to_string(),
+        bot: None,
+        correspondence: false,
+        teacher: None,
+        terms,
+        posted: 0,
+        state: None,
+    }
+}
+
+#[test]
+fn advert_round_trips_its_terms() {
+    let terms = GameTerms {
+        komi: 0.5,
+        handicap: 3,
+        rules: Rules::Japanese,
+        ruleset_version: RULESET_VERSION,
+        time_control: Some(TimeControl { main_time_secs: 600, byo_yomi_secs: 30 }),
+        on_disconnect: OnDisconnect::KeepRunning,
+        rated: false,
+        rule_config: RuleConfig { suicide_allowed: true },
+    };
+    let decoded = GameAdvert::decode(&advert(Some(terms)).encode().unwrap()).unwrap();
+    assert_eq!(decoded.terms(), terms);
+    assert_eq!(terms.to_string(), "komi 0.5, Japanese rules, suicide allowed, handicap 3, 10m + 30s, clock runs while away, unrated");
+}
+
+#[test]
+fn adverts_without_terms_get_standard_ones() {
+    // What peers sent before adverts carried terms
+    #[derive(serde::Serialize)]
+    struct OldAdvert {
+        gid: String,
+        size: u8,
+        host: String,
+        bot: Option<()>,
+        correspondence: bool,
+    }
+    let old = OldAdvert { gid: "old".to_string(), size: 9, host: "host".to_string(), bot: None, correspondence: true };
+    let decoded = GameAdvert::decode(&serde_cbor::to_vec(&old).unwrap()).unwrap();
+    assert!(decoded.correspondence);
+    assert_eq!(decoded.terms(), GameTerms::standard(9));
+    assert_eq!(decoded.terms().to_string(), "komi 5.5, Chinese rules, untimed, rated");
+}
+
+#[test]
+fn newer_rulesets_are_refused() {
+    let mut terms = GameTerms::standard(19);
+    assert!(terms.check_supported().is_ok());
+
+    terms.ruleset_version = RULESET_VERSION + 1;
+    let err = terms.check_supported().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::UnsupportedRuleset { advertised, supported } if advertised == RULESET_VERSION + 1 && supported == RULESET_VERSION
+    ));
+    assert!(err.to_string().contains("update to join"));
+}
+
+#[test]
+fn adverts_carry_their_version() {
+    let bytes = advert(None).encode().unwrap();
+    assert_eq!(p2pgo_core::cbor::migrate::version_of(&bytes), ADVERT_VERSION);
+
+    // Bincode, as hosts sent before CBOR adverts, is refused rather than misread
+    let legacy = [9u8, 0, 0, 0, 0, 0, 0, 0, b'g', b'a', b'm', b'e', b'-', b'1', b'2', b'3', 9];
+    let err = GameAdvert::decode(&legacy).unwrap_err();
+    assert!(err.to_string().contains("not a CBOR map"), "{}", err);
+}
+
+#[tokio::test]
+async fn adverts_under_newer_rules_are_not_listed() {
+    let mut terms = GameTerms::standard(13);
+    terms.ruleset_version = RULESET_VERSION + 1;
+    let lobby = Lobby::new();
+    assert_eq!(lobby.ingest_advert(&advert(Some(terms)), "host").await, Ingest::Unsupported);
+    assert_eq!(lobby.ingest_advert(&advert(None), "host").await, Ingest::Added);
+}
+
+#[tokio::test]
+async fn lobby_lists_the_terms_games_are_played_with() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    let listed = |games: Vec<p2pgo_network::lobby::GameInfo>| games.into_iter().find(|g| g.id == game_id).unwrap().terms;
+    assert_eq!(listed(lobby.list_games().await), GameTerms::standard(9));
+
+    let clock = TimeControl { main_time_secs: 300, byo_yomi_secs: 0 };
+    lobby.set_time_control(&game_id, Some(clock), OnDisconnect::KeepRunning, false).await.unwrap();
+    let terms = listed(lobby.list_games().await);
+    assert_eq!(terms.time_control, Some(clock));
+    assert_eq!(terms.on_disconnect, OnDisconnect::KeepRunning);
+    assert!(!terms.rated);
+    // The game's channel keeps the clocks from here on
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(channel.clock_snapshot().await.map(|c| c.black.main_ms), Some(300_000));
+}
+
+#[tokio::test]
+async fn rule_variations_are_advertised_and_played() {
+    let suicide = RuleConfig { suicide_allowed: true };
+    let settings = GameSettings { rule_config: suicide, ..GameSettings::standard(9) };
+    let terms = GameTerms::from_settings(&settings);
+    assert_eq!(terms.rule_config, suicide);
+    // Builds from before rule variations would play it under the default rules
+    assert_eq!(terms.ruleset_version, RULESET_VERSION);
+    assert!(GameTerms::standard(9).ruleset_version < RULESET_VERSION);
+    assert_eq!(settings.new_game().rule_config, suicide);
+    assert_eq!(settings.to_string(), "9x9, komi 5.5, Chinese rules, suicide allowed");
+
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game_from_position(Lobby::new_game_id(), None, GameState::new(9), settings, false).await.unwrap();
+    let listed = lobby.list_games().await.into_iter().find(|g| g.id == game_id).unwrap();
+    assert_eq!(listed.terms, terms);
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(channel.settings(), settings);
+    assert_eq!(channel.get_latest_state().await.unwrap().rule_config, suicide);
+}
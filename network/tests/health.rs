@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Restarting background tasks that die or stop beating.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use p2pgo_network::health::{HealthEvent, RestartPolicy, SubsystemStatus, Supervisor};
+use p2pgo_network::iroh_endpoint::IrohCtx;
+use p2pgo_network::lobby::Lobby;
+
+fn quick_policy() -> RestartPolicy {
+    RestartPolicy {
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(40),
+        max_restarts: 2,
+        healthy_after: Duration::from_secs(60),
+    }
+}
+
+/// Check every few milliseconds until `done` says so, collecting events
+async fn check_until(supervisor: &Supervisor, done: impl Fn(&[HealthEvent]) -> bool) -> Vec<HealthEvent> {
+    let mut events = Vec::new();
+    let give_up = Instant::now() + Duration::from_secs(5);
+    while !done(&events) && Instant::now() < give_up {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        events.extend(supervisor.check(Instant::now()));
+    }
+    events
+}
+
+#[tokio::test]
+async fn dying_subsystems_are_restarted_then_given_up_on() {
+    let starts = Arc::new(AtomicU32::new(0));
+    let supervisor = Supervisor::new(quick_policy());
+    let counter = starts.clone();
+    supervisor.register("lobby gossip", Duration::from_secs(60), move |_heartbeat| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        // Returns at once, as a handler whose stream ended does
+        async {}
+    });
+
+    let events = check_until(&supervisor, |events| matches!(events.last(), Some(HealthEvent::Failed { .. }))).await;
+    assert_eq!(events, vec![
+        HealthEvent::Restarted { name: "lobby gossip".to_string(), attempt: 1 },
+        HealthEvent::Restarted { name: "lobby gossip".to_string(), attempt: 2 },
+        HealthEvent::Failed { name: "lobby gossip".to_string(), restarts: 2 },
+    ]);
+    assert_eq!(starts.load(Ordering::SeqCst), 3);
+    assert_eq!(supervisor.status("lobby gossip"), Some(SubsystemStatus::Failed));
+
+    // Failed subsystems stay down
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(supervisor.check(Instant::now()).is_empty());
+    assert_eq!(starts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn wedged_subsystems_are_restarted() {
+    let starts = Arc::new(AtomicU32::new(0));
+    let supervisor = Supervisor::new(quick_policy());
+    let counter = starts.clone();
+    supervisor.register("acceptor", Duration::from_millis(30), move |heartbeat| {
+        let first = counter.fetch_add(1, Ordering::SeqCst) == 0;
+        async move {
+            loop {
+                // The first run hangs without beating, the restart is healthy
+                if !first {
+                    heartbeat.beat();
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+    });
+
+    let events = check_until(&supervisor, |events| !events.is_empty()).await;
+    assert_eq!(events, vec![HealthEvent::Restarted { name: "acceptor".to_string(), attempt: 1 }]);
+
+    // The restarted task keeps beating, so it is left alone
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(supervisor.check(Instant::now()).is_empty());
+    assert_eq!(supervisor.status("acceptor"), Some(SubsystemStatus::Running));
+    assert_eq!(starts.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn watched_tasks_are_reported_failed_once_they_stop_beating() {
+    let supervisor = Supervisor::new(quick_policy());
+    let heartbeat = supervisor.watch("network worker", Duration::from_millis(30));
+    for _ in 0..5 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        heartbeat.beat();
+        assert!(supervisor.check(Instant::now()).is_empty());
+    }
+
+    // Nothing can start it again, so it is given up on at once
+    let events = check_until(&supervisor, |events| !events.is_empty()).await;
+    assert_eq!(events, vec![HealthEvent::Failed { name: "network worker".to_string(), restarts: 0 }]);
+    assert_eq!(supervisor.status("network worker"), Some(SubsystemStatus::Failed));
+}
+
+#[tokio::test]
+async fn game_listeners_are_supervised_until_the_game_is_removed() {
+    let supervisor = Supervisor::new(quick_policy());
+    let lobby = Lobby::new();
+    lobby.set_transport(Arc::new(IrohCtx::new().await.unwrap())).await;
+    lobby.set_supervisor(supervisor.clone()).await;
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+
+    let names = [
+        format!("gossip listener for game {}", game_id),
+        format!("join request listener for game {}", game_id),
+        format!("ACK watchdog for game {}", game_id),
+    ];
+    for name in &names {
+        assert_eq!(supervisor.status(name), Some(SubsystemStatus::Running), "{}", name);
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(supervisor.check(Instant::now()).is_empty());
+
+    // Removing the game stops them rather than leaving them to be restarted
+    lobby.remove_game(&game_id).await.unwrap();
+    for name in &names {
+        assert_eq!(supervisor.status(name), None, "{}", name);
+    }
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap() {
+    let policy = quick_policy();
+    let waits: Vec<u64> = (1..=5).map(|attempt| policy.backoff(attempt).as_millis() as u64).collect();
+    assert_eq!(waits, [10, 20, 40, 40, 40]);
+    assert_eq!(RestartPolicy::default().backoff(100), RestartPolicy::default().max_backoff);
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Persistent identity: storage, passphrases, export and import.
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use p2pgo_network::identity::{Identity, IdentityError, IdentityManager, IDENTITY_FILE};
+use zeroize::ZeroizeOnDrop;
+
+// Secret keys are never copied, cloned or written out by accident
+static_assertions::assert_not_impl_any!(Identity: Clone, Copy, serde::Serialize, std::fmt::Display);
+static_assertions::assert_impl_all!(Identity: Send, Sync);
+// The key an identity holds wipes itself when dropped
+static_assertions::assert_impl_all!(ed25519_dalek::SigningKey: ZeroizeOnDrop);
+
+fn manager(dir: &tempfile::TempDir) -> IdentityManager {
+    IdentityManager::new(dir.path().join("p2pgo").join(IDENTITY_FILE))
+}
+
+#[test]
+fn two_launches_reuse_the_same_id() {
+    let dir = tempfile::tempdir().unwrap();
+    let first = manager(&dir).load_or_generate(None).unwrap();
+    let second = manager(&dir).load_or_generate(None).unwrap();
+    assert_eq!(first.node_id(), second.node_id());
+    assert_eq!(first.sign(b"a move"), second.sign(b"a move"));
+
+    // A different data directory means a different identity
+    let other = tempfile::tempdir().unwrap();
+    assert_ne!(manager(&other).load_or_generate(None).unwrap().node_id(), first.node_id());
+}
+
+#[test]
+fn passphrase_protected_identity_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = manager(&dir);
+    let created = Identity::from_secret_bytes(&[9; 32]);
+    manager.save(&created, Some("correct horse")).unwrap();
+    assert!(manager.is_encrypted().unwrap());
+
+    // The secret key is not stored in the clear
+    let on_disk = std::fs::read(manager.path()).unwrap();
+    assert!(!on_disk.windows(32).any(|w| w == [9; 32]));
+
+    let loaded = manager.load_or_generate(Some("correct horse")).unwrap();
+    assert_eq!(loaded.node_id(), created.node_id());
+}
+
+#[test]
+fn wrong_or_missing_passphrase_is_a_typed_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = manager(&dir);
+    manager.load_or_generate(Some("secret")).unwrap();
+
+    assert!(matches!(manager.load_or_generate(Some("guess")), Err(IdentityError::WrongPassphrase)));
+    assert!(matches!(manager.load_or_generate(None), Err(IdentityError::PassphraseRequired)));
+}
+
+#[test]
+fn export_and_import_move_an_identity() {
+    let home = tempfile::tempdir().unwrap();
+    let laptop = tempfile::tempdir().unwrap();
+    let identity = manager(&home).load_or_generate(None).unwrap();
+
+    // Without a passphrase
+    let plain = manager(&home).export(&identity, None).unwrap();
+    let imported = manager(&laptop).import(&plain, None).unwrap();
+    assert_eq!(imported.node_id(), identity.node_id());
+    assert!(!manager(&laptop).is_encrypted().unwrap());
+
+    // With a passphrase, which the imported key file keeps
+    let sealed = manager(&home).export(&identity, Some("moving day")).unwrap();
+    assert!(matches!(manager(&laptop).import(&sealed, Some("wrong")), Err(IdentityError::WrongPassphrase)));
+    let imported = manager(&laptop).import(&sealed, Some("moving day")).unwrap();
+    assert_eq!(imported.node_id(), identity.node_id());
+    assert!(manager(&laptop).is_encrypted().unwrap());
+    assert_eq!(manager(&laptop).load_or_generate(Some("moving day")).unwrap().node_id(), identity.node_id());
+
+    assert!(matches!(manager(&laptop).import("not base64!", None), Err(IdentityError::Corrupt(_))));
+}
+
+#[test]
+fn signatures_verify_without_the_secret_leaving_the_identity() {
+    let identity = Identity::from_secret_bytes(&[7; 32]);
+    let signature = Signature::from_bytes(&identity.sign(b"a game record"));
+    let public = VerifyingKey::from_bytes(&identity.public_key()).unwrap();
+    assert!(public.verify(b"a game record", &signature).is_ok());
+    assert!(public.verify(b"another record", &signature).is_err());
+
+    // Debug output names the node, never the key
+    let debug = format!("{:?}", identity);
+    assert!(debug.contains(&identity.node_id()));
+    assert!(!debug.contains(&hex::encode([7; 32])));
+}
+
+#[test]
+fn manager_signs_with_the_stored_identity() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = manager(&dir);
+    assert!(matches!(manager.sign(b"a game record", None), Err(IdentityError::Io(_))));
+
+    let identity = manager.load_or_generate(Some("secret")).unwrap();
+    let signature = Signature::from_bytes(&manager.sign(b"a game record", Some("secret")).unwrap());
+    let public = VerifyingKey::from_bytes(&identity.public_key()).unwrap();
+    assert!(public.verify(b"a game record", &signature).is_ok());
+    assert!(matches!(manager.sign(b"a game record", None), Err(IdentityError::PassphraseRequired)));
+}
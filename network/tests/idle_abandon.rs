@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Idle detection and cleanup of abandoned games.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use p2pgo_core::{Color, Coord, EndReason, GameEvent, Move};
+use p2pgo_network::idle::{IdleConfig, IdleStatus, IdleTracker, IDLE_FORFEIT_REASON};
+use p2pgo_network::lobby::Lobby;
+use p2pgo_network::ArchiveManager;
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+#[test]
+fn tracker_prompts_once_then_abandons() {
+    let start = Instant::now();
+    let mut idle = IdleTracker::new(IdleConfig::default(), start);
+
+    assert_eq!(idle.check(start + 9 * MINUTE), IdleStatus::Active);
+    assert_eq!(
+        idle.check(start + 10 * MINUTE),
+        IdleStatus::ConfirmAbandon { idle_for: 10 * MINUTE }
+    );
+    // The player has been asked; don't ask again every tick
+    assert_eq!(idle.check(start + 20 * MINUTE), IdleStatus::Active);
+    assert_eq!(idle.check(start + 30 * MINUTE), IdleStatus::Abandoned);
+}
+
+#[test]
+fn activity_restarts_the_quiet_period() {
+    let start = Instant::now();
+    let mut idle = IdleTracker::new(IdleConfig::default(), start);
+
+    assert!(matches!(idle.check(start + 10 * MINUTE), IdleStatus::ConfirmAbandon { .. }));
+    idle.record_activity(start + 12 * MINUTE);
+    assert_eq!(idle.check(start + 21 * MINUTE), IdleStatus::Active);
+    assert!(matches!(idle.check(start + 22 * MINUTE), IdleStatus::ConfirmAbandon { .. }));
+
+    idle.keep_waiting(start + 40 * MINUTE);
+    assert_eq!(idle.check(start + 49 * MINUTE), IdleStatus::Active);
+    assert_eq!(idle.check(start + 70 * MINUTE), IdleStatus::Abandoned);
+}
+
+#[test]
+fn thresholds_are_configurable() {
+    let start = Instant::now();
+    let config = IdleConfig {
+        prompt_after: Duration::from_secs(5),
+        forfeit_after: Duration::from_secs(15),
+    };
+    let mut idle = IdleTracker::new(config, start);
+
+    assert!(matches!(idle.check(start + Duration::from_secs(5)), IdleStatus::ConfirmAbandon { .. }));
+    assert_eq!(idle.check(start + Duration::from_secs(15)), IdleStatus::Abandoned);
+}
+
+#[tokio::test]
+async fn abandon_game_forfeits_archives_and_cleans_up() {
+    let lobby = Lobby::new();
+    let _lobby_rx = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    let mut events = channel.subscribe();
+    channel.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    let weak = Arc::downgrade(&channel);
+    drop(channel);
+
+    // Black moved and White went silent, so Black wins
+    let archive_dir = tempfile::tempdir().unwrap();
+    let archive = ArchiveManager::with_directory(archive_dir.path().to_path_buf());
+    let event = lobby.abandon_game(&game_id, IDLE_FORFEIT_REASON, Some(&archive)).await.unwrap();
+    let is_forfeit = |event: &GameEvent| matches!(
+        event,
+        GameEvent::GameEnded { winner: Some(Color::Black), reason: EndReason::Forfeit(reason), .. }
+            if reason == IDLE_FORFEIT_REASON
+    );
+    assert!(is_forfeit(&event), "unexpected event {:?}", event);
+
+    // Subscribers hear about it on the game channel
+    loop {
+        let received = events.recv().await.unwrap();
+        if matches!(received, GameEvent::GameEnded { .. }) {
+            assert!(is_forfeit(&received), "unexpected event {:?}", received);
+            break;
+        }
+    }
+
+    let archived = archive.get_archive(&game_id).await.unwrap();
+    assert_eq!(archived.winner, Some(Color::Black));
+    assert_eq!(archived.end_reason.as_deref(), Some(IDLE_FORFEIT_REASON));
+    assert_eq!(archived.move_count, 1);
+    assert!(archive_dir.path().join(format!("{}.cbor", game_id)).exists());
+
+    assert!(lobby.list_games().await.is_empty());
+    assert!(lobby.get_game_channel(&game_id).await.is_err());
+    assert!(weak.upgrade().is_none(), "channel should be freed");
+}
+
+#[tokio::test]
+async fn abandon_unknown_game_fails() {
+    let lobby = Lobby::new();
+    assert!(lobby.abandon_game(&"missing".to_string(), IDLE_FORFEIT_REASON, None).await.is_err());
+}
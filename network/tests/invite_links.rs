@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Invite links: encoding, damaged and expired links, and joining the right game.
+
+use std::sync::Arc;
+use std::time::Duration;
+use p2pgo_network::invite::{Invite, InviteError, INVITE_PREFIX};
+use p2pgo_network::lobby::Lobby;
+
+fn invite() -> Invite {
+    Invite::new("ticket-abc123".to_string(), "game-2".to_string(), 13, "alice".to_string())
+}
+
+#[test]
+fn link_round_trips() {
+    let invite = invite();
+    let link = invite.to_link();
+    assert!(link.starts_with(INVITE_PREFIX));
+    assert!(Invite::is_link(&format!("  {}\n", link)));
+    assert!(!Invite::is_link("ticket-abc123"));
+    assert_eq!(Invite::parse(&link), Ok(invite.clone()));
+    // Some URL handlers add a trailing slash
+    assert_eq!(Invite::parse(&format!("{}/", link)), Ok(invite));
+}
+
+#[test]
+fn damaged_links_are_refused() {
+    let link = invite().to_link();
+
+    // Cut short while pasting
+    assert_eq!(Invite::parse(&link[..link.len() - 5]), Err(InviteError::Damaged));
+    assert_eq!(Invite::parse(INVITE_PREFIX), Err(InviteError::Damaged));
+
+    // One character changed
+    let mut chars: Vec<char> = link.chars().collect();
+    let at = INVITE_PREFIX.len() + 10;
+    chars[at] = if chars[at] == 'A' { 'B' } else { 'A' };
+    let corrupted: String = chars.into_iter().collect();
+    assert_eq!(Invite::parse(&corrupted), Err(InviteError::Damaged));
+
+    assert_eq!(Invite::parse("https://example.com/invite"), Err(InviteError::NotAnInvite));
+}
+
+#[test]
+fn expired_links_are_refused() {
+    let invite = invite();
+    let link = invite.to_link();
+    assert_eq!(Invite::parse_at(&link, invite.expires_at), Ok(invite.clone()));
+    assert_eq!(Invite::parse_at(&link, invite.expires_at + 1), Err(InviteError::Expired));
+}
+
+#[tokio::test]
+async fn join_waits_for_the_invited_game() {
+    let lobby = Arc::new(Lobby::new());
+    lobby.create_game_with_id("game-1".to_string(), None, 9, false).await.unwrap();
+    let invite = Invite::parse(&invite().to_link()).unwrap();
+
+    // The first listed game isn't the one we were invited to
+    let waiting = {
+        let lobby = lobby.clone();
+        tokio::spawn(async move { lobby.wait_for_game_id(&invite.game_id, Duration::from_secs(5)).await })
+    };
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    lobby.create_game_with_id("game-3".to_string(), None, 19, false).await.unwrap();
+    lobby.create_game_with_id("game-2".to_string(), None, 13, false).await.unwrap();
+
+    let game = waiting.await.unwrap().expect("invited game was listed");
+    assert_eq!(game.id, "game-2");
+    assert_eq!(game.board_size, 13);
+    assert_eq!(lobby.list_games().await.len(), 3);
+
+    // A game that never appears times out
+    assert!(lobby.wait_for_game_id(&"game-9".to_string(), Duration::from_millis(20)).await.is_none());
+}
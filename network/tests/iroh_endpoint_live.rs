@@ -1,6 +1,7 @@
 #[cfg(feature = "iroh")]
 mod live {
     use p2pgo_network::iroh_endpoint::IrohCtx;
+    use p2pgo_network::Error;
     use std::time::Duration;
 
     #[tokio::test]
@@ -54,6 +55,23 @@ mod live {
             );
         }
     }
+    
+    #[tokio::test]
+    async fn own_ticket_is_refused_before_connecting() {
+        let ctx = IrohCtx::new().await.unwrap();
+        let ticket = ctx.ticket().await.unwrap();
+        
+        let err = ctx.connect_by_ticket(&ticket).await.unwrap_err();
+        assert!(
+            matches!(err, Error::SelfConnection),
+            "expected SelfConnection, got: {:#}", err
+        );
+        
+        // Another node's ticket is not mistaken for ours
+        let other = IrohCtx::new().await.unwrap();
+        let result = ctx.connect_by_ticket(&other.ticket().await.unwrap()).await;
+        assert!(!matches!(result, Err(Error::SelfConnection)));
+    }
 }
 
 // When iroh feature is not enabled, this test module doesn't exist
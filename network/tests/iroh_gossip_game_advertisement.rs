@@ -41,14 +41,13 @@ mod tests {
                 use p2pgo_network::gossip_compat::extract_bytes;
                 let bytes = extract_bytes(&gossip_event);
                 
-                // Decode as CBOR
-                let ad: p2pgo_network::iroh_endpoint::GameAdvert = 
-                    serde_cbor::from_slice(&bytes).expect("Failed to decode game advertisement");
+                let ad = p2pgo_network::lobby::GameAdvert::decode(&bytes)
+                    .expect("Failed to decode game advertisement");
                     
                 assert_eq!(ad.gid, "test-game-123");
                 assert_eq!(ad.size, 9);
                 assert_eq!(ad.host, ctx1.node_id());
-                assert_eq!(ad.bot, false);
+                assert!(ad.bot.is_none());
             }
             Event::Lagged => {
                 panic!("Unexpected lagged event");
@@ -92,8 +91,8 @@ mod tests {
                 // Use our helper function to get bytes
                 use p2pgo_network::gossip_compat::extract_bytes;
                 let content = extract_bytes(&gossip_event);
-                let ad: p2pgo_network::iroh_endpoint::GameAdvert = 
-                    serde_cbor::from_slice(&content).expect("Failed to decode game advertisement");
+                let ad = p2pgo_network::lobby::GameAdvert::decode(&content)
+                    .expect("Failed to decode game advertisement");
                 assert_eq!(ad.size, 19);
             }
             Event::Lagged => {
@@ -119,4 +118,30 @@ mod stub_tests {
         let result = ctx.subscribe_lobby(9).await;
         assert!(result.is_ok(), "Stub lobby subscription should succeed");
     }
+    
+    #[tokio::test]
+    async fn gossiped_adverts_reach_the_lobby() {
+        use p2pgo_network::game_list::{GamesQuery, Ingest};
+        use p2pgo_network::lobby::Lobby;
+        use tokio::time::{timeout, Duration};
+        
+        let host = IrohCtx::new().await.unwrap();
+        let guest = IrohCtx::new().await.unwrap();
+        let lobby = Lobby::new();
+        let mut adverts = guest.lobby_adverts(13).await.unwrap();
+        
+        host.advertise_game("gossiped-game", 13).await.unwrap();
+        let (from, bytes) = loop {
+            let (from, bytes) = timeout(Duration::from_secs(5), adverts.recv()).await.unwrap().unwrap();
+            if from == host.node_id() {
+                break (from, bytes);
+            }
+        };
+        
+        assert_eq!(lobby.ingest_gossiped(&from, &bytes).await, Some(Ingest::Added));
+        let page = lobby.games_page(&GamesQuery::default(), 0, 10).await;
+        assert!(page.games.iter().any(|game| game.id == "gossiped-game"));
+        
+        assert_eq!(lobby.ingest_gossiped(&from, b"not an advert").await, None);
+    }
 }
@@ -194,27 +194,42 @@ mod tests {
 #[cfg(not(feature = "iroh"))]
 mod stub_tests {
     use p2pgo_network::{iroh_endpoint::IrohCtx, game_channel::GameChannel};
-    use p2pgo_core::{GameState, Move, Coord};
+    use p2pgo_core::{Color, GameEvent, GameState, Move, Coord};
     use std::sync::Arc;
+    use tokio::time::{timeout, Duration};
+    
+    /// The next move either channel announces
+    async fn next_move(events: &mut tokio::sync::broadcast::Receiver<GameEvent>) -> (Move, Color) {
+        loop {
+            let event = timeout(Duration::from_secs(5), events.recv()).await
+                .expect("Timeout waiting for move event")
+                .expect("Failed to receive move event");
+            if let GameEvent::MoveMade { mv, by } = event {
+                return (mv, by);
+            }
+        }
+    }
     
     #[tokio::test]
-    async fn test_stub_move_sync() {
-        let ctx = IrohCtx::new().await.expect("Failed to create stub IrohCtx");
-        
-        let game_id = "stub-game".to_string();
+    async fn moves_sync_between_loopback_channels() {
+        let game_id = "loopback-sync-game".to_string();
         let initial_state = GameState::new(9);
-        
-        // In stub mode, GameChannel::with_iroh is not available
-        // So we test the basic GameChannel functionality
-        let channel = GameChannel::new(game_id, initial_state);
-        
-        let test_move = Move::Place(Coord::new(3, 3));
-        let result = channel.send_move(test_move.clone()).await;
-        assert!(result.is_ok(), "Stub move should succeed");
-        
-        // Verify the move was recorded
-        let moves = channel.get_all_moves().await;
-        assert_eq!(moves.len(), 1);
-        assert_eq!(moves[0], test_move);
+        let channel1 = GameChannel::with_iroh(game_id.clone(), initial_state.clone(), Arc::new(IrohCtx::new().await.unwrap()))
+            .await.expect("Failed to create first game channel");
+        let channel2 = GameChannel::with_iroh(game_id, initial_state, Arc::new(IrohCtx::new().await.unwrap()))
+            .await.expect("Failed to create second game channel");
+        let mut events1 = channel1.subscribe();
+        let mut events2 = channel2.subscribe();
+        
+        let played = [Move::Place(Coord::new(3, 3)), Move::Place(Coord::new(5, 5))];
+        channel1.send_move(played[0].clone()).await.unwrap();
+        assert_eq!(next_move(&mut events2).await, (played[0].clone(), Color::Black));
+        channel2.send_move(played[1].clone()).await.unwrap();
+        assert_eq!(next_move(&mut events1).await, (played[0].clone(), Color::Black));
+        assert_eq!(next_move(&mut events1).await, (played[1].clone(), Color::White));
+        
+        // Our own gossip coming back is not played twice
+        assert_eq!(channel1.get_all_moves().await, played);
+        assert_eq!(channel2.get_all_moves().await, played);
     }
 }
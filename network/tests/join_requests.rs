@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Join-request flow between a host and several joining peers.
+
+use p2pgo_core::{Color, Coord, Move};
+use p2pgo_core::settings::GameSettings;
+use p2pgo_network::join::{JoinPolicy, JoinResponse, PlayerProfile, SELF_JOIN_REASON};
+use p2pgo_network::lobby::{Lobby, LobbyEvent};
+
+fn profile(node_id: &str, guild: Option<&str>) -> PlayerProfile {
+    PlayerProfile {
+        node_id: node_id.to_string(),
+        name: format!("player-{}", node_id),
+        guild: guild.map(str::to_string),
+        self_reported_rating: None,
+    }
+}
+
+/// Wait for the next join request and answer it
+async fn answer_next_request(lobby: &Lobby, rx: &mut tokio::sync::broadcast::Receiver<LobbyEvent>, accept: bool, reason: Option<&str>) -> PlayerProfile {
+    loop {
+        if let LobbyEvent::JoinRequested { game_id, request } = rx.recv().await.unwrap() {
+            lobby.respond_to_join(&game_id, request.request_id, accept, reason.map(str::to_string))
+                .await
+                .unwrap();
+            return request.profile;
+        }
+    }
+}
+
+#[tokio::test]
+async fn first_joiner_plays_second_is_offered_spectate() {
+    let lobby = Lobby::new();
+    let mut host_rx = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host", None), JoinPolicy::Manual).await.unwrap();
+    
+    // First peer waits until the host accepts
+    let (response, asked_by) = tokio::join!(
+        lobby.request_join(&game_id, profile("alice", None)),
+        answer_next_request(&lobby, &mut host_rx, true, None)
+    );
+    assert_eq!(asked_by.node_id, "alice");
+    assert_eq!(response.unwrap(), JoinResponse::Accepted { color: Color::White, settings: GameSettings::standard(9) });
+    
+    // Second peer finds the game full without bothering the host
+    let response = lobby.request_join(&game_id, profile("bob", None)).await.unwrap();
+    assert!(matches!(response, JoinResponse::SpectateOffered { .. }));
+    
+    // Only seated players may move, and only on their turn
+    lobby.start_game(&game_id).await.unwrap();
+    assert!(lobby.post_player_move(&game_id, "bob", Move::Pass).await.is_err());
+    assert!(lobby.post_player_move(&game_id, "alice", Move::Pass).await.is_err());
+    lobby.post_player_move(&game_id, "host", Move::Place(Coord::new(4, 4))).await.unwrap();
+    lobby.post_player_move(&game_id, "alice", Move::Place(Coord::new(2, 2))).await.unwrap();
+}
+
+#[tokio::test]
+async fn declined_joiner_sees_reason() {
+    let lobby = Lobby::new();
+    let mut host_rx = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host", None), JoinPolicy::Manual).await.unwrap();
+    
+    let (response, _) = tokio::join!(
+        lobby.request_join(&game_id, profile("carol", None)),
+        answer_next_request(&lobby, &mut host_rx, false, Some("Waiting for a friend"))
+    );
+    assert_eq!(
+        response.unwrap(),
+        JoinResponse::Declined { reason: "Waiting for a friend".to_string() }
+    );
+    
+    // The seat is still open afterwards
+    lobby.set_join_policy(&game_id, JoinPolicy::Everyone).await.unwrap();
+    let response = lobby.request_join(&game_id, profile("dave", None)).await.unwrap();
+    assert_eq!(response, JoinResponse::Accepted { color: Color::White, settings: GameSettings::standard(9) });
+}
+
+#[tokio::test]
+async fn guild_members_are_auto_accepted() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host", Some("kgs")), JoinPolicy::GuildMembers).await.unwrap();
+    
+    let response = lobby.request_join(&game_id, profile("erin", Some("kgs"))).await.unwrap();
+    assert_eq!(response, JoinResponse::Accepted { color: Color::White, settings: GameSettings::standard(9) });
+}
+
+#[tokio::test]
+async fn removing_game_releases_pending_joiner() {
+    let lobby = Lobby::new();
+    let mut host_rx = lobby.subscribe();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host", None), JoinPolicy::Manual).await.unwrap();
+    
+    let (response, _) = tokio::join!(
+        lobby.request_join(&game_id, profile("frank", None)),
+        async {
+            while !matches!(host_rx.recv().await, Ok(LobbyEvent::JoinRequested { .. })) {}
+            lobby.remove_game(&game_id).await.unwrap();
+        }
+    );
+    assert!(matches!(response.unwrap(), JoinResponse::Declined { .. }));
+}
+
+#[tokio::test]
+async fn host_cannot_join_its_own_game() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host", None), JoinPolicy::Everyone).await.unwrap();
+    
+    // Our own ticket looped back asks with our own node ID
+    let response = lobby.request_join(&game_id, profile("host", None)).await.unwrap();
+    assert_eq!(response, JoinResponse::Declined { reason: SELF_JOIN_REASON.to_string() });
+    
+    // The opponent seat is still free for someone else
+    let response = lobby.request_join(&game_id, profile("grace", None)).await.unwrap();
+    assert_eq!(response, JoinResponse::Accepted { color: Color::White, settings: GameSettings::standard(9) });
+}
+
+#[cfg(not(feature = "iroh"))]
+#[tokio::test]
+async fn remote_host_answers_over_the_join_topic() {
+    use std::sync::Arc;
+    use p2pgo_network::iroh_endpoint::IrohCtx;
+    use p2pgo_network::lobby::{GameAdvert, GameTerms};
+    
+    let (host_ctx, guest_ctx) = (Arc::new(IrohCtx::new().await.unwrap()), Arc::new(IrohCtx::new().await.unwrap()));
+    let (host, guest) = (Lobby::new(), Lobby::new());
+    host.set_transport(host_ctx.clone()).await;
+    guest.set_transport(guest_ctx.clone()).await;
+    
+    let mut host_rx = host.subscribe();
+    let game_id = host.create_game(None, 13, false).await.unwrap();
+    host.set_host(&game_id, profile(host_ctx.node_id(), None), JoinPolicy::Manual).await.unwrap();
+    let advert = GameAdvert {
+        gid: game_id.clone(),
+        size: 13,
+        host: host_ctx.node_id().to_string(),
+        bot: None,
+        correspondence: false,
+        teacher: None,
+        terms: Some(GameTerms::standard(13)),
+        posted: 0,
+        state: None,
+    };
+    guest.ingest_advert(&advert, host_ctx.node_id()).await;
+    
+    let joiner = PlayerProfile { name: "guest".to_string(), ..profile(guest_ctx.node_id(), None) };
+    let (response, asked_by) = tokio::join!(
+        guest.request_join(&game_id, joiner.clone()),
+        answer_next_request(&host, &mut host_rx, true, None),
+    );
+    assert_eq!(asked_by, joiner);
+    assert_eq!(response.unwrap(), JoinResponse::Accepted { color: Color::White, settings: GameSettings::standard(13) });
+    
+    // The guest plays the game it was seated in
+    let channel = guest.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(channel.settings().board_size, 13);
+    assert!(guest.list_games().await.iter().any(|info| info.id == game_id));
+    
+    // Games nobody advertised are still not found
+    assert!(guest.request_join(&"nowhere".to_string(), joiner).await.is_err());
+}
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use p2pgo_network::lobby::{Lobby, LobbyEvent};
+use p2pgo_network::lobby::{GameListingState, Lobby, LobbyEvent};
 
 #[tokio::test]
 async fn test_lobby_create_and_list_games() {
@@ -70,6 +70,16 @@ async fn test_lobby_events() {
         _ => panic!("Expected GameStarted event"),
     }
     
+    // The listing follows
+    let event = rx.recv().await.unwrap();
+    match event {
+        LobbyEvent::GameStateChanged { game_id: id, state } => {
+            assert_eq!(id, game_id);
+            assert_eq!(state, GameListingState::Active);
+        },
+        _ => panic!("Expected GameStateChanged event"),
+    }
+    
     // Remove the game
     lobby.remove_game(&game_id).await.unwrap();
     
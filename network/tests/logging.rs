@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Log filter directives and the timed, bounded trace capture.
+
+use std::time::{Duration, Instant};
+use p2pgo_network::logging::{Directive, FilterError, LogFilter, TraceCapture, TRUNCATED_MARKER};
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+
+#[test]
+fn filters_parse_levels_and_module_directives() {
+    let filter: LogFilter = " info, p2pgo_network::game_channel=TRACE ,iroh=warn,".parse().unwrap();
+    assert_eq!(filter.directives(), [
+        Directive { target: None, level: LevelFilter::INFO },
+        Directive { target: Some("p2pgo_network::game_channel".to_string()), level: LevelFilter::TRACE },
+        Directive { target: Some("iroh".to_string()), level: LevelFilter::WARN },
+    ]);
+    assert_eq!(filter.to_string(), "info,p2pgo_network::game_channel=trace,iroh=warn");
+    assert_eq!(filter.to_string().parse::<LogFilter>().unwrap(), filter);
+
+    // The most specific directive wins
+    let targets = filter.targets();
+    assert!(targets.would_enable("p2pgo_network::game_channel", &Level::TRACE));
+    assert!(!targets.would_enable("p2pgo_network::lobby", &Level::DEBUG));
+    assert!(targets.would_enable("p2pgo_network::lobby", &Level::INFO));
+    assert!(!targets.would_enable("iroh::magicsock", &Level::INFO));
+
+    assert_eq!("off".parse::<LogFilter>().unwrap(), LogFilter::level(LevelFilter::OFF));
+}
+
+#[test]
+fn bad_filters_say_what_is_wrong() {
+    assert_eq!(" , ".parse::<LogFilter>(), Err(FilterError::Empty));
+    assert_eq!("verbose".parse::<LogFilter>(), Err(FilterError::UnknownLevel {
+        directive: "verbose".to_string(),
+        level: "verbose".to_string(),
+    }));
+    assert!(matches!("iroh=loud".parse::<LogFilter>(), Err(FilterError::UnknownLevel { level, .. }) if level == "loud"));
+    assert!(matches!("=debug".parse::<LogFilter>(), Err(FilterError::InvalidTarget { .. })));
+    assert!(matches!("p2pgo network=debug".parse::<LogFilter>(), Err(FilterError::InvalidTarget { .. })));
+    assert!(matches!("a=b=trace".parse::<LogFilter>(), Err(FilterError::UnknownLevel { level, .. }) if level == "b=trace"));
+
+    // Saved settings fail to load the same way
+    assert!(serde_json::from_str::<LogFilter>("\"verbose\"").is_err());
+    let saved: LogFilter = serde_json::from_str("\"debug,iroh=warn\"").unwrap();
+    assert_eq!(serde_json::to_string(&saved).unwrap(), "\"debug,iroh=warn\"");
+}
+
+/// A subscriber writing everything to `capture`
+fn capturing(capture: &TraceCapture) -> impl tracing::Subscriber {
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(capture.clone())
+        .with_ansi(false);
+    tracing_subscriber::registry().with(layer)
+}
+
+#[test]
+fn captures_stop_when_the_time_is_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace.log");
+    let capture = TraceCapture::new();
+
+    tracing::subscriber::with_default(capturing(&capture), || {
+        tracing::trace!("before the capture");
+        capture.start(&path, Duration::from_millis(200), 1024 * 1024).unwrap();
+        assert!(capture.is_running(Instant::now()));
+        assert!(capture.start(&path, Duration::from_secs(60), 1024).is_err(), "one capture at a time");
+        tracing::trace!("during the capture");
+        assert_eq!(capture.finish(Instant::now()), None, "not due yet");
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(!capture.is_running(Instant::now()));
+        tracing::trace!("after the capture");
+    });
+
+    let report = capture.finish(Instant::now()).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(written.contains("during the capture"), "{}", written);
+    assert!(!written.contains("before the capture"));
+    assert!(!written.contains("after the capture"));
+    assert_eq!(report.path, path);
+    assert_eq!(report.bytes, written.len() as u64);
+    assert!(!report.truncated);
+
+    // Finished captures can be started again
+    assert_eq!(capture.finish(Instant::now()), None);
+    capture.start(&dir.path().join("again.log"), Duration::from_secs(60), 1024).unwrap();
+}
+
+#[test]
+fn captures_are_truncated_at_the_size_limit() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("trace.log");
+    let capture = TraceCapture::new();
+    let max_bytes = 512;
+    capture.start(&path, Duration::from_secs(60), max_bytes).unwrap();
+
+    tracing::subscriber::with_default(capturing(&capture), || {
+        for i in 0..100 {
+            tracing::debug!(i, "a chatty line that soon fills the capture");
+        }
+    });
+
+    let written = std::fs::read_to_string(&path).unwrap();
+    assert!(written.len() as u64 <= max_bytes, "{} bytes", written.len());
+    assert!(written.ends_with(TRUNCATED_MARKER), "{}", written);
+    assert!(written.contains("capture i=0\n"), "{}", written);
+    // Only whole lines before the marker
+    assert!(written.lines().rev().skip(1).all(|line| line.contains("fills the capture i=")), "{}", written);
+
+    // Full captures finish early
+    assert!(!capture.is_running(Instant::now()));
+    let report = capture.finish(Instant::now()).unwrap();
+    assert!(report.truncated);
+    assert_eq!(report.bytes, written.len() as u64);
+}
@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Two nodes of one process find and play a game without iroh.
+
+#[cfg(not(feature = "iroh"))]
+mod tests {
+    use std::time::Duration;
+    use p2pgo_core::{Coord, Move, MoveRecord, Tag};
+    use p2pgo_network::game_list::Ingest;
+    use p2pgo_network::lobby::GameAdvert;
+    use p2pgo_network::{loopback, Error, IrohCtx, Lobby};
+    use tokio::time::timeout;
+
+    fn record(mv: Move) -> MoveRecord {
+        MoveRecord { mv, tag: None, ts: 0, broadcast_hash: None, prev_hash: None }
+    }
+
+    #[tokio::test]
+    async fn loopback_game_is_advertised_joined_and_played() {
+        let host = IrohCtx::new().await.unwrap();
+        let guest = IrohCtx::new().await.unwrap();
+        let mut lobby_9 = guest.subscribe_lobby(9).await.unwrap();
+        let mut lobby_19 = guest.subscribe_lobby(19).await.unwrap();
+
+        host.advertise_game("loopback-game", 9).await.unwrap();
+        let bytes = timeout(Duration::from_secs(1), lobby_9.recv()).await.unwrap().unwrap();
+        let advert: GameAdvert = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!((advert.gid.as_str(), advert.size, advert.host.as_str()), ("loopback-game", 9, host.node_id()));
+        assert!(lobby_19.try_recv().is_err(), "adverts stay in their board size's lobby");
+        assert_eq!(Lobby::new().ingest_advert(&advert, host.node_id()).await, Ingest::Added);
+
+        guest.connect_by_ticket(&host.ticket().await.unwrap()).await.unwrap();
+        let mut moves = host.subscribe_game_topic("loopback-game", 10).await.unwrap();
+        let played = [Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6)), Move::Pass];
+        for (sequence, mv) in played.iter().enumerate() {
+            let mut sent = record(mv.clone());
+            guest.broadcast_move("loopback-game", &mut sent).await.unwrap();
+            assert!(sent.broadcast_hash.is_some());
+            guest.store_game_move("loopback-game", sequence as u32, &sent).await.unwrap();
+
+            let bytes = timeout(Duration::from_secs(1), moves.recv()).await.unwrap().unwrap();
+            let received: MoveRecord = serde_cbor::from_slice(&bytes).unwrap();
+            assert_eq!(received.mv, *mv);
+            host.store_game_move("loopback-game", sequence as u32, &received).await.unwrap();
+        }
+
+        host.store_move_tag("loopback-game", 1, Tag::Activity).await.unwrap();
+        assert!(host.store_move_tag("loopback-game", 7, Tag::Activity).await.is_err());
+        let stored = host.move_store().game_moves("loopback-game").await;
+        assert_eq!(stored.iter().map(|(sequence, _)| *sequence).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(stored[1].1.tag, Some(Tag::Activity));
+        assert_eq!(guest.move_store().game_moves("loopback-game").await.len(), 3);
+        assert!(guest.move_store().game_moves("other-game").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn bad_tickets_are_typed_errors() {
+        let node = IrohCtx::new().await.unwrap();
+        assert!(matches!(node.connect_by_ticket("not-a-ticket").await, Err(Error::TicketInvalid(_))));
+        assert!(matches!(node.connect_by_ticket(loopback::TICKET_PREFIX).await, Err(Error::TicketInvalid(_))));
+        assert!(matches!(node.connect_by_ticket(&node.ticket().await.unwrap()).await, Err(Error::SelfConnection)));
+
+        // A node that has shut down can't be reached
+        let gone = IrohCtx::new().await.unwrap();
+        let ticket = gone.ticket().await.unwrap();
+        assert!(loopback::is_running(gone.node_id()));
+        drop(gone);
+        assert!(matches!(node.connect_by_ticket(&ticket).await, Err(Error::ConnectionFailed { .. })));
+    }
+}
@@ -0,0 +1,139 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Matchmaking queue pairing and handshake recovery.
+
+use std::time::{Duration, Instant};
+use p2pgo_network::matchmaking::{
+    find_match, MatchPrefs, MatchQueue, QueueEntry, QueueMessage, QueueTiming, QueueTopic, TimeControl,
+};
+use tokio::sync::oneshot;
+
+fn entry(peer: &str, rating: i32) -> QueueEntry {
+    QueueEntry {
+        peer: peer.to_string(),
+        prefs: MatchPrefs::default(),
+        rating,
+    }
+}
+
+fn fast() -> QueueTiming {
+    QueueTiming {
+        tick: Duration::from_millis(10),
+        announce: Duration::from_millis(30),
+        entry_ttl: Duration::from_millis(300),
+        handshake_timeout: Duration::from_millis(100),
+        flap_cooldown: Duration::from_secs(5),
+    }
+}
+
+#[test]
+fn compatibility_needs_same_game_and_rating_band() {
+    let me = entry("a", 1500);
+    assert!(me.compatible(&entry("b", 1800)));
+    assert!(!me.compatible(&entry("b", 1801)));
+    assert!(!me.compatible(&me.clone()));
+
+    let mut narrow = entry("b", 1600);
+    narrow.prefs.rating_band = 50;
+    assert!(!me.compatible(&narrow));
+
+    let mut blitz = entry("b", 1500);
+    blitz.prefs.time_control = TimeControl { main_time_secs: 60, byo_yomi_secs: 0 };
+    assert!(!me.compatible(&blitz));
+
+    let mut big = entry("b", 1500);
+    big.prefs.board_size = 19;
+    assert!(!me.compatible(&big));
+
+    let mut keeps_running = entry("b", 1500);
+    keeps_running.prefs.on_disconnect = p2pgo_core::game_clock::OnDisconnect::KeepRunning;
+    assert!(!me.compatible(&keeps_running));
+}
+
+#[tokio::test]
+async fn three_queued_peers_make_one_match() {
+    let topic = QueueTopic::local();
+    let mut queued = Vec::new();
+    for peer in ["carol", "alice", "bob"] {
+        let queue = MatchQueue::with_timing(entry(peer, 1500), fast());
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        let topic = topic.clone();
+        let task = tokio::spawn(async move { find_match(queue, &topic, cancel_rx).await });
+        queued.push((peer, cancel_tx, task));
+    }
+
+    // Wait for the first two players to pair
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while queued.iter().filter(|(_, _, task)| task.is_finished()).count() < 2 {
+        assert!(Instant::now() < deadline, "no match within 5s");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    // Give the third player time to wrongly join in
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let mut matches = Vec::new();
+    let mut waiting = Vec::new();
+    for (peer, cancel_tx, task) in queued {
+        if task.is_finished() {
+            matches.push((peer, task.await.unwrap().unwrap().unwrap()));
+        } else {
+            waiting.push(peer);
+            cancel_tx.send(()).unwrap();
+            assert_eq!(task.await.unwrap().unwrap(), None);
+        }
+    }
+
+    assert_eq!(matches.len(), 2);
+    assert_eq!(waiting.len(), 1);
+    let [(first, a), (second, b)] = [&matches[0], &matches[1]];
+    assert_eq!(a.game_id, b.game_id);
+    assert_eq!((a.opponent.peer.as_str(), b.opponent.peer.as_str()), (*second, *first));
+    // The lower peer ID hosts
+    assert_eq!(a.host, first < second);
+    assert_eq!(b.host, second < first);
+}
+
+#[test]
+fn vanished_guest_returns_host_to_the_queue() {
+    let now = Instant::now();
+    let mut host = MatchQueue::with_timing(entry("a", 1500), fast());
+    host.tick(now);
+
+    let out = host.handle(QueueMessage::Announce(entry("b", 1500)), now);
+    assert!(matches!(&out[..], [QueueMessage::Propose { guest, .. }] if guest == "b"));
+    assert!(host.is_handshaking());
+
+    // b never accepts; c shows up meanwhile
+    assert!(host.handle(QueueMessage::Announce(entry("c", 1500)), now).is_empty());
+    let later = now + fast().handshake_timeout;
+    let out = host.tick(later);
+    assert!(
+        out.iter().any(|msg| matches!(msg, QueueMessage::Propose { guest, .. } if guest == "c")),
+        "expected a proposal to c, got {out:?}"
+    );
+
+    // b coming back during the cooldown is not paired again
+    let out = host.handle(QueueMessage::Leave { peer: "c".to_string() }, later);
+    assert!(!host.is_handshaking());
+    assert!(out.is_empty());
+    assert!(host.handle(QueueMessage::Announce(entry("b", 1500)), later).is_empty());
+}
+
+#[test]
+fn unconfirmed_guest_returns_to_the_queue() {
+    let now = Instant::now();
+    let mut guest = MatchQueue::with_timing(entry("b", 1500), fast());
+    guest.handle(QueueMessage::Announce(entry("a", 1500)), now);
+    let propose = QueueMessage::Propose {
+        host: "a".to_string(),
+        guest: "b".to_string(),
+        game_id: "game-1".to_string(),
+    };
+    let out = guest.handle(propose, now);
+    assert!(matches!(&out[..], [QueueMessage::Accept { game_id, .. }] if game_id == "game-1"));
+
+    let out = guest.tick(now + fast().handshake_timeout);
+    assert!(!guest.is_handshaking());
+    assert!(guest.matched().is_none());
+    assert!(matches!(&out[..], [QueueMessage::Announce(entry)] if entry.peer == "b"));
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-peer move rate limiting, mutes, disconnects and anomaly flags.
+
+use std::time::{Duration, Instant};
+use p2pgo_core::{Color, GameEvent, GameState};
+use p2pgo_network::archive::ArchiveManager;
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::rate_limit::{
+    MoveAnomaly, MoveRateLimiter, RateLimitConfig, Verdict, RATE_LIMIT_CLOSE_CODE,
+};
+
+fn count(verdicts: &[Verdict], wanted: fn(&Verdict) -> bool) -> usize {
+    verdicts.iter().filter(|v| wanted(v)).count()
+}
+
+#[tokio::test]
+async fn burst_of_100_moves_is_muted_until_the_window_passes() {
+    let channel = GameChannel::new("spam".to_string(), GameState::new(9));
+    let mut events = channel.subscribe();
+    let start = Instant::now();
+
+    let mut verdicts = Vec::new();
+    for i in 0..100 {
+        verdicts.push(channel.screen_peer_move("spammer", start + Duration::from_millis(i)).await);
+    }
+    assert_eq!(count(&verdicts, |v| matches!(v, Verdict::Accept)), 5);
+    assert_eq!(count(&verdicts, |v| matches!(v, Verdict::Mute { offenses: 1, .. })), 1);
+    assert_eq!(count(&verdicts, |v| matches!(v, Verdict::Drop)), 94);
+
+    // One warning for the mute, not one per dropped move
+    match events.try_recv().unwrap() {
+        GameEvent::PeerWarning { peer, .. } => assert_eq!(peer, "spammer"),
+        other => panic!("expected a peer warning, got {:?}", other),
+    }
+    assert!(events.try_recv().is_err());
+
+    // Other peers are unaffected
+    assert_eq!(channel.screen_peer_move("opponent", start + Duration::from_millis(50)).await, Verdict::Accept);
+
+    // The mute lifts once the window has passed
+    let mute_for = RateLimitConfig::default().mute_for;
+    let during = start + Duration::from_millis(5) + mute_for / 2;
+    assert_eq!(channel.screen_peer_move("spammer", during).await, Verdict::Drop);
+    let after = start + Duration::from_millis(5) + mute_for;
+    assert_eq!(channel.screen_peer_move("spammer", after).await, Verdict::Accept);
+}
+
+#[test]
+fn repeated_offenses_disconnect_the_peer() {
+    let config = RateLimitConfig { max_moves: 2, max_offenses: 2, ..RateLimitConfig::default() };
+    let mut limiter = MoveRateLimiter::new(config);
+    let mut now = Instant::now();
+
+    for _ in 0..2 {
+        limiter.check("flooder", now);
+    }
+    assert!(matches!(limiter.check("flooder", now), Verdict::Mute { offenses: 1, .. }));
+
+    now += config.mute_for;
+    for _ in 0..2 {
+        assert_eq!(limiter.check("flooder", now), Verdict::Accept);
+    }
+    match limiter.check("flooder", now) {
+        Verdict::Disconnect(reason) => {
+            assert_eq!(reason.code, RATE_LIMIT_CLOSE_CODE);
+            assert_eq!(reason.peer, "flooder");
+            assert_eq!(reason.offenses, 2);
+        }
+        other => panic!("expected a disconnect, got {:?}", other),
+    }
+}
+
+#[test]
+fn rapid_fire_moves_are_flagged() {
+    let config = RateLimitConfig { max_moves: 100, ..RateLimitConfig::default() };
+    let start = Instant::now();
+
+    // A person taking a couple of seconds per move is fine
+    let mut limiter = MoveRateLimiter::new(config);
+    for i in 0..20 {
+        limiter.check("human", start + Duration::from_secs(2 * i));
+    }
+    assert!(limiter.anomalies().is_empty());
+
+    // Moves 50ms apart over and over are not
+    for i in 0..20 {
+        limiter.check("bot", start + Duration::from_millis(50 * i));
+    }
+    assert_eq!(limiter.anomalies(), &[MoveAnomaly::ImplausibleTiming {
+        peer: "bot".to_string(),
+        streak: config.implausible_streak,
+    }]);
+}
+
+#[tokio::test]
+async fn flagged_games_are_kept_out_of_training() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ArchiveManager::with_directory(dir.path().to_path_buf());
+    let anomaly = MoveAnomaly::ImplausibleTiming { peer: "bot".to_string(), streak: 5 };
+
+    archive.archive_forfeit("clean".to_string(), GameState::new(9), Color::Black, "resigned", Vec::new(), Vec::new()).await.unwrap();
+    archive.archive_forfeit("flagged".to_string(), GameState::new(9), Color::White, "resigned", vec![anomaly], Vec::new()).await.unwrap();
+
+    assert!(archive.get_archive(&"clean".to_string()).await.unwrap().usable_for_training());
+    assert!(!archive.get_archive(&"flagged".to_string()).await.unwrap().usable_for_training());
+}
+
+#[tokio::test]
+async fn scored_games_keep_their_anomalies_on_disk() {
+    let dir = tempfile::tempdir().unwrap();
+    let archive = ArchiveManager::with_directory(dir.path().to_path_buf());
+    let anomaly = MoveAnomaly::ImplausibleTiming { peer: "bot".to_string(), streak: 5 };
+
+    archive.archive_game("scored".to_string(), GameState::new(9), Some(Color::Black), Some(7), vec![anomaly.clone()], Vec::new()).await.unwrap();
+
+    let bytes = std::fs::read(dir.path().join("scored.cbor")).unwrap();
+    let record: p2pgo_network::archive::GameArchive = serde_cbor::from_slice(&bytes).unwrap();
+    assert_eq!(record.anomalies, vec![anomaly]);
+    assert!(!record.usable_for_training());
+}
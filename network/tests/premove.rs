@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Premoves queued during the opponent's turn.
+
+use p2pgo_core::{Color, Coord, GameError, GameState, Move};
+use p2pgo_network::game_channel::{GameChannel, PremoveOutcome};
+
+fn place(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+fn channel() -> GameChannel {
+    GameChannel::new("premove-game".to_string(), GameState::new(9))
+}
+
+#[tokio::test]
+async fn premove_is_played_after_the_opponent_moves() {
+    let channel = channel();
+    channel.queue_premove(place(6, 6), Color::White).await;
+
+    // Still Black's turn: nothing happens yet
+    assert_eq!(channel.play_premove().await.unwrap(), None);
+    assert!(channel.premove().await.is_some());
+
+    channel.send_move(place(2, 2)).await.unwrap();
+    let outcome = channel.play_premove().await.unwrap();
+    assert_eq!(outcome, Some(PremoveOutcome::Played(place(6, 6))));
+
+    let state = channel.get_latest_state().await.unwrap();
+    assert_eq!(state.moves, vec![place(2, 2), place(6, 6)]);
+    assert_eq!(state.current_player, Color::Black);
+    assert!(channel.premove().await.is_none());
+}
+
+#[tokio::test]
+async fn premove_that_would_be_captured_is_discarded() {
+    let channel = channel();
+    channel.send_move(place(1, 0)).await.unwrap();
+    channel.send_move(place(8, 8)).await.unwrap();
+
+    // White aims for the corner, but Black's reply takes its last liberty
+    channel.queue_premove(place(0, 0), Color::White).await;
+    channel.send_move(place(0, 1)).await.unwrap();
+
+    let outcome = channel.play_premove().await.unwrap();
+    assert_eq!(outcome, Some(PremoveOutcome::Discarded {
+        mv: place(0, 0),
+        error: GameError::SelfCapture,
+    }));
+
+    let state = channel.get_latest_state().await.unwrap();
+    assert_eq!(state.moves.len(), 3);
+    assert_eq!(state.current_player, Color::White);
+    assert!(channel.premove().await.is_none());
+}
+
+#[tokio::test]
+async fn premove_on_the_opponents_point_is_discarded() {
+    let channel = channel();
+    channel.queue_premove(place(4, 4), Color::White).await;
+    channel.send_move(place(4, 4)).await.unwrap();
+
+    let outcome = channel.play_premove().await.unwrap();
+    assert_eq!(outcome, Some(PremoveOutcome::Discarded {
+        mv: place(4, 4),
+        error: GameError::OccupiedPosition,
+    }));
+}
+
+#[tokio::test]
+async fn only_one_premove_is_kept() {
+    let channel = channel();
+    channel.queue_premove(place(3, 3), Color::White).await;
+    channel.queue_premove(place(5, 5), Color::White).await;
+    assert_eq!(channel.premove().await, Some((place(5, 5), Color::White)));
+
+    assert_eq!(channel.clear_premove().await, Some(place(5, 5)));
+    channel.send_move(place(2, 2)).await.unwrap();
+    assert_eq!(channel.play_premove().await.unwrap(), None);
+}
@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Opponent presence: rate limited both ways, and never a coordinate.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use p2pgo_core::{GameEvent, GameState};
+use p2pgo_network::clock::VirtualClock;
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::presence::{Presence, PresenceFilter, PresenceLimiter, PRESENCE_HEARTBEAT, PRESENCE_INTERVAL};
+use p2pgo_network::wire::{self, DirectMessage, WireFormat};
+use serde_cbor::Value;
+
+#[test]
+fn at_most_one_message_per_interval() {
+    let start = Instant::now();
+    let mut limiter = PresenceLimiter::default();
+    let at = |ms: u64| start + Duration::from_millis(ms);
+
+    assert_eq!(limiter.poll(true, 100, at(0)), Some(Presence { considering: true, last_active: 100 }));
+    // Polled every frame, flipping back and forth: nothing until the interval is up
+    for ms in (16..2000).step_by(16) {
+        assert_eq!(limiter.poll(ms % 32 == 0, 100, at(ms)), None, "at {}ms", ms);
+    }
+    // The change held back goes out as soon as it may
+    assert_eq!(limiter.poll(false, 101, at(2000)).map(|p| p.considering), Some(false));
+    assert_eq!(limiter.poll(true, 101, at(3000)), None);
+
+    // Nothing new: only the heartbeat
+    let quiet = at(2000) + PRESENCE_HEARTBEAT;
+    assert_eq!(limiter.poll(false, 101, quiet - Duration::from_millis(1)), None);
+    assert!(limiter.poll(false, 101, quiet).is_some());
+    assert_eq!(PRESENCE_INTERVAL, Duration::from_secs(2));
+}
+
+#[test]
+fn floods_from_a_peer_are_dropped() {
+    let start = Instant::now();
+    let mut filter = PresenceFilter::new(Duration::from_secs(1));
+    assert!(filter.admit("a", start));
+    assert!(!filter.admit("a", start + Duration::from_millis(500)));
+    assert!(filter.admit("b", start + Duration::from_millis(500)), "each peer has its own budget");
+    assert!(filter.admit("a", start + Duration::from_secs(1)));
+
+    filter.remove_peer("a");
+    assert!(filter.admit("a", start + Duration::from_millis(1100)));
+}
+
+#[tokio::test]
+async fn presence_never_carries_coordinates() {
+    let presence = Presence { considering: true, last_active: 1_700_000_000 };
+    let frame = wire::encode(&DirectMessage::Presence(presence), WireFormat::Cbor).unwrap();
+    let payload: Value = serde_cbor::from_slice(&frame[5..]).unwrap();
+    let Value::Map(message) = payload else {
+        panic!("expected a map, got {:?}", payload);
+    };
+    let Some(Value::Map(fields)) = message.get(&Value::Text("Presence".to_string())) else {
+        panic!("expected a presence, got {:?}", message);
+    };
+    let keys: Vec<_> = fields.keys().cloned().collect();
+    assert_eq!(keys, vec![Value::Text("considering".to_string()), Value::Text("last_active".to_string())]);
+
+    // A peer adding a position gets it dropped on decoding
+    let mut tampered = fields.clone();
+    tampered.insert(Value::Text("at".to_string()), Value::Array(vec![Value::Integer(3), Value::Integer(4)]));
+    let tampered: Presence = serde_cbor::value::from_value(Value::Map(tampered)).unwrap();
+    assert_eq!(tampered, presence);
+}
+
+#[tokio::test]
+async fn channel_shows_presence_on_our_clock() {
+    let clock = Arc::new(VirtualClock::new(1_000));
+    let channel = GameChannel::new("presence".to_string(), GameState::new(9)).with_clock(clock.clone());
+    let mut events = channel.subscribe();
+
+    // A timestamp from the future is clamped to now
+    let message = DirectMessage::Presence(Presence { considering: true, last_active: 5_000 });
+    channel.receive_direct("opponent", message.clone()).await;
+    match events.try_recv().unwrap() {
+        GameEvent::Presence { peer, considering, last_active } => {
+            assert_eq!((peer.as_str(), considering, last_active), ("opponent", true, 1_000));
+        }
+        other => panic!("expected presence, got {:?}", other),
+    }
+
+    // Too soon after the last
+    channel.receive_direct("opponent", message.clone()).await;
+    assert!(events.try_recv().is_err());
+    clock.advance(PRESENCE_INTERVAL);
+    channel.receive_direct("opponent", message).await;
+    assert!(matches!(events.try_recv(), Ok(GameEvent::Presence { .. })));
+}
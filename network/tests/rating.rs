@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Glicko-2 rating updates and the persisted rating log.
+
+use p2pgo_network::rating::{GameOutcome, PeerRecord, RatedGame, Rating, RatingEstimate, RatingTracker};
+
+fn game(id: &str, opponent: &str, outcome: GameOutcome, rated: bool) -> RatedGame {
+    RatedGame {
+        game_id: id.to_string(),
+        opponent: opponent.to_string(),
+        opponent_estimate: Some(RatingEstimate { rating: 1500, deviation: 100 }),
+        outcome,
+        rated,
+    }
+}
+
+#[test]
+fn matches_the_glicko2_paper_example() {
+    // Glickman, "Example of the Glicko-2 system", with τ = 0.5
+    let player = Rating { rating: 1500.0, deviation: 200.0, volatility: 0.06 };
+    let opponent = |rating, deviation| Rating { rating, deviation, volatility: 0.06 };
+    let updated = player.update(&[
+        (opponent(1400.0, 30.0), 1.0),
+        (opponent(1550.0, 100.0), 0.0),
+        (opponent(1700.0, 300.0), 0.0),
+    ]);
+    assert!((updated.rating - 1464.06).abs() < 0.01, "rating {}", updated.rating);
+    assert!((updated.deviation - 151.52).abs() < 0.01, "deviation {}", updated.deviation);
+    assert!((updated.volatility - 0.05999).abs() < 0.00001, "volatility {}", updated.volatility);
+}
+
+#[test]
+fn wins_raise_and_losses_lower_the_rating() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tracker = RatingTracker::open(dir.path().join("ratings.cbor")).unwrap();
+    assert_eq!(tracker.current(), Rating::default());
+
+    let win = tracker.record(game("g1", "peer-a", GameOutcome::Win, true)).unwrap().unwrap();
+    assert!(win.delta() > 0.0);
+    let after_win = tracker.current();
+    assert!(after_win.deviation < Rating::default().deviation);
+
+    let loss = tracker.record(game("g2", "peer-a", GameOutcome::Loss, true)).unwrap().unwrap();
+    assert!(loss.delta() < 0.0);
+    assert!(tracker.current().rating < after_win.rating);
+
+    assert!(tracker.record(game("g1", "peer-a", GameOutcome::Win, true)).is_err());
+    assert_eq!(tracker.peer_record("peer-a"), PeerRecord { wins: 1, losses: 1, draws: 0 });
+
+    // The log survives a restart
+    let reopened = RatingTracker::open(dir.path().join("ratings.cbor")).unwrap();
+    assert_eq!(reopened.history().len(), 2);
+    assert_eq!(reopened.current(), tracker.current());
+}
+
+#[test]
+fn unrated_games_change_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut tracker = RatingTracker::open(dir.path().join("ratings.cbor")).unwrap();
+    tracker.record(game("g1", "peer-a", GameOutcome::Win, true)).unwrap();
+    let before = tracker.current();
+
+    assert!(tracker.record(game("bot", "ai", GameOutcome::Win, false)).unwrap().is_none());
+    assert!(tracker.record(game("idle", "peer-b", GameOutcome::Loss, false)).unwrap().is_none());
+    assert_eq!(tracker.current(), before);
+    assert_eq!(tracker.history().len(), 1);
+    assert!(!tracker.peers().contains_key("peer-b"));
+}
+
+#[test]
+fn estimates_are_labelled_self_reported() {
+    let estimate = Rating { rating: 1623.4, deviation: 87.6, volatility: 0.06 }.estimate();
+    assert_eq!(estimate, RatingEstimate { rating: 1623, deviation: 88 });
+    assert_eq!(estimate.to_string(), "1623 ±88 (self-reported)");
+}
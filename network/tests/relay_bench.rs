@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Benchmark games through an in-process relay, and peers kept off direct paths.
+
+use std::time::Duration;
+use p2pgo_core::{GameState, Move};
+use p2pgo_network::relay_bench::{self, BenchReport, LatencyStats, BENCH_BOARD_SIZE, BENCH_MOVES};
+
+#[test]
+fn scripted_game_is_legal_and_captures_nothing() {
+    let script = relay_bench::script(BENCH_MOVES);
+    assert_eq!(script.len(), BENCH_MOVES);
+    let mut game = GameState::new(BENCH_BOARD_SIZE);
+    for mv in script {
+        game.apply_move(mv).unwrap();
+    }
+    let stones = game.board.iter().filter(|point| point.is_some()).count();
+    assert_eq!(stones, BENCH_MOVES);
+    assert_eq!(relay_bench::script(BENCH_MOVES + 10).len(), BENCH_MOVES);
+    assert!(relay_bench::script(4).iter().all(|mv| matches!(mv, Move::Place(_))));
+}
+
+#[test]
+fn latency_percentiles_come_from_the_samples() {
+    let samples: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+    let stats = LatencyStats::from_samples(&samples);
+    assert_eq!(stats.samples, 20);
+    assert_eq!((stats.min_ms, stats.p50_ms, stats.p95_ms, stats.max_ms), (1.0, 10.0, 19.0, 20.0));
+    assert_eq!(stats.mean_ms, 10.5);
+    assert_eq!(LatencyStats::from_samples(&[]).p95_ms, 0.0);
+}
+
+#[test]
+fn report_checks_p95_against_the_limit_and_round_trips_as_json() {
+    let report = BenchReport {
+        relay: "relay.example:443".to_string(),
+        setup_ms: 12.0,
+        moves: 20,
+        latency: LatencyStats::from_samples(&(1..=20).map(Duration::from_millis).collect::<Vec<_>>()),
+        sync_events: 1,
+        bytes_relayed: 4096,
+    };
+    assert!(report.within(Duration::from_millis(19)));
+    assert!(!report.within(Duration::from_millis(18)));
+    let json = report.to_json().unwrap();
+    assert_eq!(serde_json::from_str::<BenchReport>(&json).unwrap(), report);
+    let text = report.to_string();
+    assert!(text.contains("p95 19.0 ms"), "{}", text);
+    assert!(text.contains("Bytes relayed:  4096"), "{}", text);
+}
+
+#[cfg(not(feature = "iroh"))]
+mod loopback_relay {
+    use std::time::Duration;
+    use p2pgo_network::iroh_endpoint::TransportOptions;
+    use p2pgo_network::loopback::Relay;
+    use p2pgo_network::relay_bench::{self, BenchOptions, BENCH_MOVES};
+    use p2pgo_network::{Error, IrohCtx};
+
+    #[tokio::test]
+    async fn bench_game_goes_only_through_the_relay() {
+        let relay = Relay::start();
+        let report = relay_bench::run(&BenchOptions::new(relay.addr())).await.unwrap();
+
+        assert_eq!(report.relay, relay.addr());
+        assert_eq!(report.moves, BENCH_MOVES);
+        assert_eq!(report.latency.samples, BENCH_MOVES);
+        assert!(report.latency.p50_ms <= report.latency.p95_ms && report.latency.p95_ms <= report.latency.max_ms);
+        assert_eq!(report.sync_events, 0);
+        // Every move and acknowledgement went through the relay, and nothing else did
+        assert!(report.bytes_relayed > 0);
+        assert_eq!(relay.bytes_relayed(), report.bytes_relayed);
+        assert_eq!(relay.messages_relayed(), 2 * BENCH_MOVES as u64);
+        assert!(report.within(Duration::from_secs(10)));
+    }
+
+    #[tokio::test]
+    async fn relay_only_peers_need_a_running_relay() {
+        let no_relay = TransportOptions { relay: None, direct: false };
+        assert!(matches!(IrohCtx::with_transport(no_relay).await, Err(Error::RelayUnavailable(_))));
+        assert!(matches!(IrohCtx::with_transport(TransportOptions::relay_only("loopback-relay:gone")).await, Err(Error::RelayUnavailable(_))));
+        assert!(relay_bench::run(&BenchOptions::new("loopback-relay:gone")).await.is_err());
+
+        let relay = Relay::start();
+        let host = IrohCtx::new().await.unwrap();
+        let guest = IrohCtx::with_transport(TransportOptions::relay_only(relay.addr())).await.unwrap();
+        assert!(!guest.transport().direct);
+        drop(relay);
+        assert!(matches!(guest.connect_by_ticket(&host.ticket().await.unwrap()).await, Err(Error::ConnectionFailed { .. })));
+        assert!(matches!(guest.broadcast_to_topic(IrohCtx::game_topic("g"), b"move").await, Err(Error::RelayUnavailable(_))));
+    }
+}
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Relay presets decide which incoming connections are accepted, switching at runtime.
+
+use std::time::Instant;
+use tokio::sync::watch;
+use p2pgo_network::admission::{Admission, ConnectionLimits, Refusal};
+use p2pgo_network::relay_mode::{RelayPreset, CONTRIBUTOR_MAX_CONNECTIONS, PLAYER_MAX_CONNECTIONS};
+use p2pgo_network::IrohCtx;
+
+#[test]
+fn presets_grow_from_player_to_dedicated() {
+    let limits: Vec<ConnectionLimits> = RelayPreset::ALL.iter().map(|preset| preset.limits()).collect();
+    for pair in limits.windows(2) {
+        assert!(pair[0].max_connections < pair[1].max_connections);
+        assert!(pair[0].max_bandwidth_bps < pair[1].max_bandwidth_bps);
+    }
+    // The relay binary's defaults are the dedicated budget
+    assert_eq!(RelayPreset::Dedicated.limits(), ConnectionLimits::default());
+}
+
+#[test]
+fn presets_keep_to_relay_consent() {
+    for preset in RelayPreset::ALL {
+        assert_eq!(preset.with_participation(false), RelayPreset::Player);
+    }
+    assert_eq!(RelayPreset::Player.with_participation(true), RelayPreset::Contributor);
+    assert_eq!(RelayPreset::Dedicated.with_participation(true), RelayPreset::Dedicated);
+}
+
+#[test]
+fn switching_presets_changes_acceptance_and_drains_the_newest() {
+    let (tx, rx) = watch::channel(RelayPreset::Contributor.limits());
+    let admission = Admission::new(rx);
+    let now = Instant::now();
+    let permits: Vec<_> = (0..CONTRIBUTOR_MAX_CONNECTIONS).map(|_| admission.admit(now).unwrap()).collect();
+    assert!(matches!(admission.admit(now), Err(Refusal::Full { .. })));
+    assert!(permits.iter().all(|permit| !permit.drained()));
+
+    // Down to player: the newest connections over its budget are drained
+    tx.send_replace(RelayPreset::Player.limits());
+    let drained: Vec<usize> = (0..permits.len()).filter(|&i| permits[i].drained()).collect();
+    assert_eq!(drained, (PLAYER_MAX_CONNECTIONS as usize..permits.len()).collect::<Vec<_>>());
+    assert_eq!(admission.draining(), CONTRIBUTOR_MAX_CONNECTIONS - PLAYER_MAX_CONNECTIONS);
+
+    let mut permits = permits;
+    permits.truncate(PLAYER_MAX_CONNECTIONS as usize);
+    assert_eq!(admission.draining(), 0);
+    assert!(matches!(admission.admit(now), Err(Refusal::Full { .. })));
+
+    // Back up: accepted again, nothing drained
+    tx.send_replace(RelayPreset::Dedicated.limits());
+    assert!(admission.admit(now).is_ok());
+    assert!(permits.iter().all(|permit| !permit.drained()));
+}
+
+#[test]
+fn a_contributor_refuses_while_over_its_bandwidth() {
+    let (tx, rx) = watch::channel(RelayPreset::Contributor.limits());
+    let admission = Admission::new(rx);
+    let now = Instant::now();
+    admission.record(RelayPreset::Contributor.limits().max_bandwidth_bps + 1, now);
+    assert!(matches!(admission.admit(now), Err(Refusal::Busy { .. })));
+    tx.send_replace(RelayPreset::Dedicated.limits());
+    assert!(admission.admit(now).is_ok());
+}
+
+#[tokio::test]
+async fn a_running_node_switches_presets_without_restarting() {
+    let ctx = IrohCtx::new().await.unwrap();
+    assert_eq!(ctx.admission().limits(), ConnectionLimits::unlimited());
+    for preset in [RelayPreset::Dedicated, RelayPreset::Player, RelayPreset::Contributor] {
+        ctx.set_limits(preset.limits()).unwrap();
+        assert_eq!(ctx.admission().limits(), preset.limits());
+    }
+
+    // A node bound with limits from a config takes them from there only
+    let (_tx, rx) = watch::channel(ConnectionLimits::default());
+    let relay = IrohCtx::bind_with_limits(&[], rx).await.unwrap();
+    assert!(relay.set_limits(RelayPreset::Player.limits()).is_err());
+    assert_eq!(relay.admission().limits(), ConnectionLimits::default());
+}
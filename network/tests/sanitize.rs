@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Refusing adverts, hellos, join requests and chat a hostile peer could
+//! use to flood or break the UI.
+
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::{Color, GameEvent};
+use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+use p2pgo_network::lobby::{GameAdvert, GameTerms};
+use p2pgo_network::sanitize::{self, Invalid, RejectKind, TextField, REJECTED};
+use p2pgo_network::wire::{self, DirectMessage, WireFormat};
+use p2pgo_network::{Error, Lobby};
+
+fn advert() -> GameAdvert {
+    GameAdvert {
+        gid: "game-1".to_string(),
+        size: 9,
+        host: "node-host".to_string(),
+        bot: None,
+        correspondence: false,
+        teacher: None,
+        terms: Some(GameTerms::standard(9)),
+        posted: 0,
+        state: None,
+    }
+}
+
+fn profile(name: &str) -> PlayerProfile {
+    PlayerProfile {
+        node_id: "node-alice".to_string(),
+        name: name.to_string(),
+        guild: None,
+        self_reported_rating: None,
+    }
+}
+
+#[test]
+fn overlong_text_is_refused() {
+    let name = "a".repeat(TextField::DisplayName.max_len() + 1);
+    assert_eq!(
+        sanitize::check_text(TextField::DisplayName, &name),
+        Err(Invalid::TooLong { field: TextField::DisplayName, len: 33, max: 32 })
+    );
+    // A 10 MB game name never makes it into an advert
+    let mut advert = advert();
+    advert.gid = "g".repeat(10 * 1024 * 1024);
+    assert!(matches!(sanitize::check_advert(&advert), Err(Invalid::TooLong { field: TextField::GameId, .. })));
+    // Length is counted in characters, not bytes
+    assert!(sanitize::check_text(TextField::DisplayName, &"碁".repeat(32)).is_ok());
+}
+
+#[test]
+fn empty_text_is_refused() {
+    assert_eq!(sanitize::check_text(TextField::DisplayName, ""), Err(Invalid::Empty { field: TextField::DisplayName }));
+    assert_eq!(sanitize::check_text(TextField::DisplayName, "   "), Err(Invalid::Empty { field: TextField::DisplayName }));
+}
+
+#[test]
+fn control_characters_are_refused() {
+    for name in ["alice\u{7}", "alice\r\nbob", "\u{1b}[31mred", "alice\u{0}"] {
+        assert_eq!(
+            sanitize::check_text(TextField::DisplayName, name),
+            Err(Invalid::ControlChars { field: TextField::DisplayName }),
+            "{:?}", name
+        );
+    }
+    // Reads as "alice.exe" but ends in "exe.ecila"
+    assert!(sanitize::check_text(TextField::DisplayName, "alice\u{202E}exe.").is_err());
+    // Line breaks belong in chat, not names
+    assert!(sanitize::check_text(TextField::Chat, "good game\nthanks").is_ok());
+    assert!(sanitize::check_text(TextField::Chat, "good game\tthanks").is_err());
+}
+
+#[test]
+fn board_sizes_outside_the_whitelist_are_refused() {
+    let mut advert = advert();
+    advert.size = 250;
+    assert_eq!(sanitize::check_advert(&advert), Err(Invalid::BoardSize(250)));
+    assert_eq!(sanitize::check_board_size(0), Err(Invalid::BoardSize(0)));
+    for size in sanitize::BOARD_SIZES {
+        assert!(sanitize::check_board_size(size).is_ok());
+    }
+}
+
+#[test]
+fn komi_and_handicap_out_of_range_are_refused() {
+    let mut settings = GameSettings::standard(19);
+    settings.komi = 1e9;
+    assert_eq!(sanitize::check_settings(&settings), Err(Invalid::Komi(1e9)));
+    settings.komi = f32::NAN;
+    assert!(matches!(sanitize::check_settings(&settings), Err(Invalid::Komi(_))));
+    settings.komi = -7.5;
+    assert!(sanitize::check_settings(&settings).is_ok());
+    settings.handicap = 40;
+    assert_eq!(sanitize::check_settings(&settings), Err(Invalid::Handicap(40)));
+
+    let mut advert = advert();
+    advert.terms = Some(GameTerms { handicap: 200, ..GameTerms::standard(9) });
+    assert_eq!(sanitize::check_advert(&advert), Err(Invalid::Handicap(200)));
+}
+
+#[test]
+fn invalid_adverts_are_dropped_on_decode() {
+    let good = advert();
+    assert_eq!(GameAdvert::decode(&good.encode().unwrap()).unwrap().gid, "game-1");
+
+    let mut hostile = advert();
+    hostile.host = "\u{1b}]0;pwned\u{7}".to_string();
+    assert!(matches!(GameAdvert::decode(&hostile.encode().unwrap()), Err(Error::Invalid(Invalid::ControlChars { .. }))));
+}
+
+#[tokio::test]
+async fn hellos_for_unplayable_boards_are_refused() {
+    let mut settings = GameSettings::standard(9);
+    settings.board_size = 250;
+    let bytes = wire::encode(&DirectMessage::hello(&settings), WireFormat::Cbor).unwrap();
+    let error = wire::decode(&bytes).await.unwrap_err();
+    assert!(matches!(error, Error::Invalid(Invalid::BoardSize(250))), "{}", error);
+
+    let bytes = wire::encode(&DirectMessage::hello(&GameSettings::standard(9)), WireFormat::Cbor).unwrap();
+    assert!(wire::decode(&bytes).await.is_ok());
+}
+
+#[tokio::test]
+async fn join_requests_and_chat_are_checked() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_host(&game_id, profile("host"), JoinPolicy::Everyone).await.unwrap();
+
+    let result = lobby.request_join(&game_id, profile(&"x".repeat(1000))).await;
+    assert!(matches!(result, Err(Error::Invalid(Invalid::TooLong { field: TextField::DisplayName, .. }))));
+    // The seat is still free for a well-behaved peer
+    assert!(lobby.request_join(&game_id, profile("alice")).await.is_ok());
+
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    let mut events = channel.subscribe();
+    let spam = GameEvent::ChatMessage { from: Color::White, message: "z".repeat(100_000) };
+    assert!(matches!(channel.send_event(spam).await, Err(Error::Invalid(_))));
+    let hello = GameEvent::ChatMessage { from: Color::White, message: "have a nice game".to_string() };
+    channel.send_event(hello).await.unwrap();
+    assert!(matches!(events.recv().await.unwrap(), GameEvent::ChatMessage { message, .. } if message == "have a nice game"));
+
+    // Peer-agreed game IDs are checked too
+    let result = lobby.create_game_with_id("bad\u{0}id".to_string(), None, 9, false).await;
+    assert!(matches!(result, Err(Error::Invalid(_))));
+}
+
+#[test]
+fn rejections_are_counted_by_kind() {
+    let before = REJECTED.counts();
+    let mut advert = advert();
+    advert.size = 7;
+    assert!(sanitize::check_advert(&advert).is_err());
+    assert!(sanitize::check_chat("\u{1b}[2J").is_err());
+    let after = REJECTED.counts();
+    // Other tests count too, so only a lower bound holds
+    assert!(after.get(RejectKind::BoardSize) > before.get(RejectKind::BoardSize));
+    assert!(after.get(RejectKind::Characters) > before.get(RejectKind::Characters));
+    assert!(after.total() >= before.total() + 2);
+}
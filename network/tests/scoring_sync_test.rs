@@ -106,10 +106,7 @@ async fn test_scoring_agreement_between_nodes() -> Result<()> {
     assert_eq!(score1.territory_white, score2.territory_white, "White territory should match");
     
     // Simulate accepting the score and sharing with opponent via iroh
-    channel1.send_event(GameEvent::GameFinished {
-        black_score: score1.territory_black as f32,
-        white_score: score1.territory_white as f32 + komi as f32,
-    }).await?;
+    channel1.send_event(p2pgo_core::scoring::game_ended_event(&score1)).await?;
     
     // Wait for event propagation
     tokio::time::sleep(std::time::Duration::from_millis(200)).await;
@@ -117,7 +114,7 @@ async fn test_scoring_agreement_between_nodes() -> Result<()> {
     // Check if player 2 received the game finished event
     let mut received_game_finished = false;
     while let Ok(event) = events2.try_recv() {
-        if let GameEvent::GameFinished { .. } = event {
+        if let GameEvent::GameEnded { scores: Some(_), .. } = event {
             received_game_finished = true;
             break;
         }
@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Recording a live session; on its own since the recorder is process-wide.
+
+use p2pgo_core::{Coord, GameState, Move, MoveRecord};
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::relay_robustness::now_secs;
+use p2pgo_network::session_log::{self, Command, SessionInput, SessionLog};
+use p2pgo_network::wire::DirectMessage;
+
+#[tokio::test]
+async fn recorded_session_replays_to_the_same_game() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.p2plog");
+    session_log::start_recording(&path).unwrap();
+    assert!(session_log::is_recording());
+
+    let channel = GameChannel::new("recorded".to_string(), GameState::new(9));
+    channel.send_move(Move::Place(Coord::new(3, 3))).await.unwrap();
+    session_log::record_tick();
+    let reply = channel.receive_direct("peer", DirectMessage::hello(&channel.settings())).await;
+    assert!(matches!(reply, InboundOutcome::Handled));
+    let record = MoveRecord { mv: Move::Place(Coord::new(5, 5)), tag: None, ts: now_secs(), broadcast_hash: None, prev_hash: None };
+    let reply = channel.receive_direct("peer", DirectMessage::Move(record)).await;
+    assert!(matches!(reply, InboundOutcome::Reply(DirectMessage::Ack { index: 1, .. })));
+    channel.set_teacher(Some("teacher".to_string())).await;
+    channel.send_clock_tick().await;
+    session_log::stop_recording();
+    assert!(!session_log::is_recording());
+
+    // Nothing after stopping is recorded
+    channel.send_move(Move::Pass).await.unwrap();
+
+    let log = SessionLog::read(&path).unwrap();
+    let inputs: Vec<String> = log.entries.iter().map(|e| e.input.to_string()).collect();
+    assert_eq!(inputs, [
+        "game opened",
+        "our move Place(Coord { x: 3, y: 3 })",
+        "timer tick",
+        "Hello from peer",
+        "Move from peer",
+        "teacher set",
+        "timer tick",
+    ]);
+    assert!(matches!(log.entries[2].input, SessionInput::Tick));
+    assert_eq!(log.entries[2].game_id, None);
+    assert!(matches!(&log.entries[5].input, SessionInput::Command(Command::SetTeacher { teacher: Some(_) })));
+    assert_eq!(log.entries[6].game_id.as_deref(), Some("recorded"));
+
+    let report = session_log::replay(&log).await.unwrap();
+    assert_eq!(report.games["recorded"], [Move::Place(Coord::new(3, 3)), Move::Place(Coord::new(5, 5))]);
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Replaying recorded sessions on a virtual clock.
+
+use p2pgo_core::{Coord, Move};
+use p2pgo_network::session_log::{self, SessionInput, SessionLog};
+use p2pgo_network::wire::DirectMessage;
+
+/// A peer's move is dropped as a duplicate, its record claiming to follow
+/// no move like the peer's first; the peer's sync brings it back
+const DROPPED_MOVE: &str = include_str!("fixtures/dropped_move_sync.p2plog");
+
+#[tokio::test]
+async fn dropped_move_converges_after_sync() {
+    let log = SessionLog::parse(DROPPED_MOVE).unwrap();
+    let dropped = log.entries.iter()
+        .find(|e| matches!(&e.input, SessionInput::Inbound { message: DirectMessage::Move(r), .. } if r.mv == Move::Place(Coord::new(6, 6))))
+        .unwrap();
+    assert_eq!(dropped.moves.len(), 3, "the fixture records the move being dropped");
+
+    let report = session_log::replay(&log).await.unwrap();
+    assert_eq!(report.steps, log.entries.len());
+    let moves = &report.games["dropped-move"];
+    assert_eq!(moves.len(), 4);
+    assert_eq!(moves[3], Move::Place(Coord::new(6, 6)));
+}
+
+#[tokio::test]
+async fn replay_names_the_step_that_diverged() {
+    let mut log = SessionLog::parse(DROPPED_MOVE).unwrap();
+    let step = log.entries.iter().position(|e| matches!(&e.input, SessionInput::Inbound { message: DirectMessage::SyncResponse { .. }, .. })).unwrap();
+    log.entries[step].moves.pop();
+
+    let error = session_log::replay(&log).await.unwrap_err().to_string();
+    assert!(error.starts_with(&format!("Step {} (SyncResponse from peer-7c1f)", step + 1)), "{}", error);
+}
+
+#[test]
+fn logs_of_another_version_are_refused() {
+    assert!(SessionLog::parse("").is_err());
+    let error = SessionLog::parse("{\"version\":2,\"started_unix\":0}\n").unwrap_err();
+    assert!(error.to_string().contains("version 2"));
+    let error = SessionLog::parse("{\"version\":1,\"started_unix\":0}\nnot json\n").unwrap_err();
+    assert!(error.to_string().contains("Line 2"));
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Game snapshots on disk and when the periodic ones are due.
+
+use std::time::{Duration, Instant};
+use p2pgo_core::{Color, Coord, GameState, Move};
+use p2pgo_core::cbor::migrate;
+use p2pgo_network::snapshot::{GameSnapshot, SeatRole, SnapshotSchedule, SnapshotStore, SNAPSHOT_INTERVAL, SNAPSHOT_VERSION};
+
+fn state_after(moves: &[Move]) -> GameState {
+    let mut state = GameState::new(9);
+    for mv in moves {
+        state.apply_move(mv.clone()).unwrap();
+    }
+    state
+}
+
+#[test]
+fn latest_unfinished_game_is_offered() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    assert!(store.latest_unfinished().unwrap().is_none());
+
+    let mut older = GameSnapshot::new("older".to_string(), state_after(&[Move::Place(Coord::new(4, 4))]));
+    older.saved_at = 100;
+    let mut newer = GameSnapshot::new("newer".to_string(), state_after(&[
+        Move::Place(Coord::new(4, 4)),
+        Move::Place(Coord::new(2, 2)),
+    ]));
+    newer.saved_at = 200;
+    newer.opponent = Some("Alice".to_string());
+    let mut finished = GameSnapshot::new("finished".to_string(), state_after(&[Move::Pass, Move::Pass]));
+    finished.saved_at = 300;
+    for snapshot in [&older, &newer, &finished] {
+        store.write_snapshot(snapshot).unwrap();
+    }
+
+    let latest = store.latest_unfinished().unwrap().unwrap();
+    assert_eq!(latest.game_id, "newer");
+    assert_eq!(latest.state.moves.len(), 2);
+    assert_eq!(latest.opponent.as_deref(), Some("Alice"));
+
+    store.remove("newer").unwrap();
+    store.remove("newer").unwrap();
+    assert_eq!(store.latest_unfinished().unwrap().unwrap().game_id, "older");
+}
+
+#[test]
+fn rewriting_replaces_the_game_and_skips_junk() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path().join("snapshots"));
+    store.write_snapshot(&GameSnapshot::new("game/../1".to_string(), state_after(&[Move::Pass]))).unwrap();
+    store.write_snapshot(&GameSnapshot::new("game/../1".to_string(), state_after(&[Move::Pass, Move::Place(Coord::new(0, 0))]))).unwrap();
+    std::fs::write(store.dir().join("broken.cbor"), b"not cbor").unwrap();
+
+    let snapshots = store.load_all().unwrap();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].state.moves.len(), 2);
+    assert!(!dir.path().join("1.cbor").exists());
+}
+
+#[test]
+fn snapshots_are_due_every_ten_moves_or_thirty_seconds() {
+    let start = Instant::now();
+    let schedule = SnapshotSchedule::new(0, start);
+    assert!(!schedule.is_due(9, start + Duration::from_secs(1)));
+    assert!(schedule.is_due(10, start + Duration::from_secs(1)));
+    assert!(schedule.is_due(1, start + SNAPSHOT_INTERVAL));
+    // Nothing changed, so nothing to save however long it has been
+    assert!(!schedule.is_due(0, start + SNAPSHOT_INTERVAL * 2));
+}
+
+#[test]
+fn maintenance_archives_finished_games_and_sets_aside_corrupt_snapshots() {
+    let snapshots = tempfile::tempdir().unwrap();
+    let archive = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(snapshots.path());
+
+    let mut finished = GameSnapshot::new("finished".to_string(), state_after(&[Move::Place(Coord::new(4, 4)), Move::Resign]));
+    finished.opponent = Some("Alice".to_string());
+    let playing = GameSnapshot::new("playing".to_string(), state_after(&[Move::Place(Coord::new(4, 4))]));
+    // Archived already, with its result, before the snapshot could be removed
+    let archived = GameSnapshot::new("archived".to_string(), state_after(&[Move::Pass, Move::Pass]));
+    for snapshot in [&finished, &playing, &archived] {
+        store.write_snapshot(snapshot).unwrap();
+    }
+    std::fs::write(archive.path().join("archived.cbor"), b"kept as is").unwrap();
+    std::fs::write(snapshots.path().join("garbled.cbor"), b"\xff\x00").unwrap();
+
+    let mut report = store.run_maintenance(archive.path()).unwrap();
+    report.compacted.sort();
+    assert_eq!(report.compacted, vec!["archived".to_string(), "finished".to_string()]);
+    assert_eq!(report.quarantined.len(), 1);
+    assert_eq!(report.quarantined[0].0, "garbled.cbor");
+    assert!(report.repaired_anything());
+
+    // Only the game in progress is left to resume
+    let left: Vec<String> = store.load_all().unwrap().into_iter().map(|s| s.game_id).collect();
+    assert_eq!(left, vec!["playing".to_string()]);
+    assert!(snapshots.path().join("corrupt").join("garbled.cbor").exists());
+
+    assert_eq!(std::fs::read(archive.path().join("archived.cbor")).unwrap(), b"kept as is");
+    let games = p2pgo_core::archiver::list_games_in(archive.path()).unwrap();
+    let game = games.iter().find(|g| g.id == "finished").unwrap();
+    assert_eq!(game.opponent, "Alice");
+    assert_eq!(game.result.as_deref(), Some("B+Resign"));
+    assert_eq!(p2pgo_core::archiver::load_game(archive.path(), "finished").unwrap().moves.len(), 2);
+}
+
+#[test]
+fn seat_and_color_survive_a_restart() {
+    use p2pgo_core::Color;
+    use p2pgo_network::snapshot::SeatRole;
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let mut joined = GameSnapshot::new("joined".to_string(), state_after(&[Move::Place(Coord::new(4, 4))]));
+    joined.role = SeatRole::Guest;
+    joined.color = Color::White;
+    store.write_snapshot(&joined).unwrap();
+
+    // Written before seats were saved
+    let mut older = serde_cbor::value::to_value(GameSnapshot::new("older".to_string(), state_after(&[Move::Pass]))).unwrap();
+    if let serde_cbor::Value::Map(fields) = &mut older {
+        fields.remove(&serde_cbor::Value::Text("role".to_string()));
+        fields.remove(&serde_cbor::Value::Text("color".to_string()));
+    }
+    std::fs::write(dir.path().join("older.cbor"), serde_cbor::to_vec(&older).unwrap()).unwrap();
+
+    let mut snapshots = store.load_all().unwrap();
+    snapshots.sort_by(|a, b| a.game_id.cmp(&b.game_id));
+    assert_eq!((snapshots[0].role, snapshots[0].color), (SeatRole::Guest, Color::White));
+    assert_eq!((snapshots[1].role, snapshots[1].color), (SeatRole::Host, Color::Black));
+}
+
+#[test]
+fn snapshots_from_before_versioning_still_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let mut snapshot = GameSnapshot::new("old".to_string(), state_after(&[Move::Place(Coord::new(4, 4))]));
+    snapshot.opponent = Some("Alice".to_string());
+    let bytes = serde_cbor::to_vec(&snapshot).unwrap();
+    assert_eq!(migrate::version_of(&bytes), SNAPSHOT_VERSION);
+
+    // The first snapshots had no version and none of the later fields
+    let serde_cbor::Value::Map(mut fields) = serde_cbor::from_slice(&bytes).unwrap() else {
+        panic!("Snapshots are maps");
+    };
+    for key in ["v", "correspondence", "outbox", "role", "color", "terms"] {
+        fields.remove(&serde_cbor::Value::Text(key.to_string()));
+    }
+    std::fs::write(dir.path().join("old.cbor"), serde_cbor::to_vec(&fields).unwrap()).unwrap();
+
+    let loaded = store.load_all().unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].opponent.as_deref(), Some("Alice"));
+    assert_eq!(loaded[0].state.moves, snapshot.state.moves);
+    assert_eq!((loaded[0].role, loaded[0].color), (SeatRole::Host, Color::Black));
+}
@@ -0,0 +1,149 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Move journals next to game snapshots, and recovering when a crash left
+//! the two disagreeing.
+
+use p2pgo_core::archiver::CORRUPT_DIR;
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_network::snapshot::{GameSnapshot, SnapshotStore};
+
+fn moves() -> Vec<Move> {
+    vec![
+        Move::Place(Coord::new(2, 2)),
+        Move::Place(Coord::new(6, 6)),
+        Move::Place(Coord::new(4, 4)),
+        Move::Pass,
+    ]
+}
+
+fn state_after(moves: &[Move]) -> GameState {
+    let mut state = GameState::new(9);
+    for mv in moves {
+        state.apply_move(mv.clone()).unwrap();
+    }
+    state
+}
+
+/// A game of `moves` whose snapshot was last written after `saved` of them
+/// and whose journal got every one
+fn saved_game(store: &SnapshotStore, saved: usize) -> GameSnapshot {
+    let snapshot = GameSnapshot::new("game-1".to_string(), state_after(&moves()[..saved]));
+    store.write_snapshot(&snapshot).unwrap();
+    store.write_journal("game-1", &[]).unwrap();
+    for mv in &moves() {
+        store.append_move("game-1", mv).unwrap();
+    }
+    snapshot
+}
+
+#[test]
+fn agreeing_snapshot_and_journal_restore_as_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let snapshot = saved_game(&store, 4);
+    assert_eq!(store.journal("game-1").unwrap(), Some(moves()));
+
+    let restored = store.reconcile(snapshot).unwrap();
+    assert!(!restored.recovered_from_divergence);
+    assert_eq!(restored.snapshot.state.moves, moves());
+    assert!(!dir.path().join(CORRUPT_DIR).exists());
+}
+
+#[test]
+fn journal_ahead_of_a_stale_snapshot_wins() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let mut snapshot = saved_game(&store, 2);
+    snapshot.opponent = Some("Alice".to_string());
+
+    let restored = store.reconcile(snapshot).unwrap();
+    assert!(restored.recovered_from_divergence);
+    assert_eq!(restored.snapshot.state.moves, moves());
+    assert_eq!(restored.snapshot.state.board, state_after(&moves()).board);
+    assert_eq!(restored.snapshot.opponent.as_deref(), Some("Alice"));
+
+    // The stale snapshot is set aside and the journal's game saved in its place
+    assert!(dir.path().join(CORRUPT_DIR).join("game-1.cbor").exists());
+    let saved = store.load_all().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].state.moves.len(), 4);
+
+    // Once recovered, the next launch finds nothing wrong
+    assert!(!store.reconcile(restored.snapshot).unwrap().recovered_from_divergence);
+}
+
+#[test]
+fn snapshot_ahead_of_a_truncated_journal_wins() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let snapshot = saved_game(&store, 4);
+
+    // The last appends never reached the disk: cut the journal inside its second move
+    let journal = dir.path().join("game-1.moves");
+    let len = serde_cbor::to_vec(&moves()[0]).unwrap().len();
+    let bytes = std::fs::read(&journal).unwrap();
+    std::fs::write(&journal, &bytes[..len + 2]).unwrap();
+    assert_eq!(store.journal("game-1").unwrap(), Some(moves()[..1].to_vec()));
+
+    let restored = store.reconcile(snapshot).unwrap();
+    assert!(restored.recovered_from_divergence);
+    assert_eq!(restored.snapshot.state.moves, moves());
+    assert!(dir.path().join(CORRUPT_DIR).join("game-1.moves").exists());
+    let log = std::fs::read_to_string(dir.path().join(CORRUPT_DIR).join("quarantine.log")).unwrap();
+    assert!(log.contains("game-1.moves"), "{}", log);
+
+    // The journal starts over from the snapshot and takes new moves after it
+    assert_eq!(store.journal("game-1").unwrap(), Some(moves()));
+    store.append_move("game-1", &Move::Pass).unwrap();
+    assert_eq!(store.journal("game-1").unwrap().unwrap().len(), 5);
+}
+
+#[test]
+fn journal_cut_after_the_snapshot_is_repaired_quietly() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let snapshot = GameSnapshot::new("game-1".to_string(), state_after(&moves()[..3]));
+    store.write_snapshot(&snapshot).unwrap();
+    store.write_journal("game-1", &moves()[..3]).unwrap();
+    // Half of a move written as the app died
+    let journal = dir.path().join("game-1.moves");
+    let mut bytes = std::fs::read(&journal).unwrap();
+    bytes.push(0xa1);
+    std::fs::write(&journal, bytes).unwrap();
+
+    let restored = store.reconcile(snapshot).unwrap();
+    assert!(!restored.recovered_from_divergence);
+    store.append_move("game-1", &Move::Pass).unwrap();
+    assert_eq!(store.journal("game-1").unwrap(), Some(moves()));
+}
+
+#[test]
+fn illegal_journal_moves_are_not_replayed() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let snapshot = GameSnapshot::new("game-1".to_string(), state_after(&moves()[..2]));
+    store.write_snapshot(&snapshot).unwrap();
+    // Same length as the snapshot, but the second move lands on the first
+    store.write_journal("game-1", &[moves()[0].clone(), moves()[0].clone(), Move::Pass]).unwrap();
+
+    let restored = store.reconcile(snapshot).unwrap();
+    assert!(restored.recovered_from_divergence);
+    assert_eq!(restored.snapshot.state.moves, moves()[..2]);
+    assert!(dir.path().join(CORRUPT_DIR).join("game-1.moves").exists());
+}
+
+#[test]
+fn snapshots_from_before_journals_get_one() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let snapshot = GameSnapshot::new("game-1".to_string(), state_after(&moves()[..2]));
+    store.write_snapshot(&snapshot).unwrap();
+    assert_eq!(store.journal("game-1").unwrap(), None);
+
+    assert!(!store.reconcile(snapshot).unwrap().recovered_from_divergence);
+    assert_eq!(store.journal("game-1").unwrap(), Some(moves()[..2].to_vec()));
+
+    store.remove("game-1").unwrap();
+    assert_eq!(store.journal("game-1").unwrap(), None);
+    assert!(store.load_all().unwrap().is_empty());
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Teaching games: the teacher's annotations sit beside the game record and
+//! only the teacher may make them.
+
+use p2pgo_core::teaching::{Marker, TeachingAnnotation};
+use p2pgo_core::{Color, Coord, GameEvent, GameState, Move};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_network::game_channel::GameChannel;
+use p2pgo_network::lobby::Lobby;
+
+const TEACHER: &str = "teacher-node";
+
+async fn lesson() -> GameChannel {
+    let channel = GameChannel::new("lesson".to_string(), GameState::new(9));
+    channel.set_teacher(Some(TEACHER.to_string())).await;
+    channel.send_move(Move::Place(Coord::new(2, 6))).await.unwrap();
+    channel
+}
+
+#[tokio::test]
+async fn annotations_leave_the_game_alone() {
+    let channel = lesson().await;
+    let mut events = channel.subscribe();
+    let before = channel.get_latest_state().await.unwrap();
+
+    let stone = TeachingAnnotation::AddStone { at: Coord::new(6, 2), color: Color::Black };
+    channel.annotate(TEACHER, stone.clone()).await.unwrap();
+    channel.annotate(TEACHER, TeachingAnnotation::Mark { at: Coord::new(4, 4), marker: Marker::Label("A".to_string()) }).await.unwrap();
+
+    let after = channel.get_latest_state().await.unwrap();
+    assert_eq!(after.moves, before.moves);
+    assert_eq!(after.board, before.board);
+    assert_eq!(after.current_player, Color::White);
+    assert_eq!(channel.get_all_moves().await.len(), 1);
+    match events.try_recv().unwrap() {
+        GameEvent::Annotated { move_index, annotation } => assert_eq!((move_index, annotation), (1, stone)),
+        other => panic!("Expected an annotation, got {:?}", other),
+    }
+
+    // Retracting the stone leaves only the label
+    channel.annotate(TEACHER, TeachingAnnotation::RemoveStone { at: Coord::new(6, 2) }).await.unwrap();
+    let layer = channel.annotations().await;
+    let overlay = layer.overlay(1).unwrap();
+    assert!(overlay.stones.is_empty());
+    assert_eq!(overlay.mark_at(Coord::new(4, 4)), Some(&Marker::Label("A".to_string())));
+
+    // The student's next move is played as usual
+    channel.send_move(Move::Place(Coord::new(6, 2))).await.unwrap();
+    assert_eq!(channel.get_latest_state().await.unwrap().moves.len(), 2);
+    assert!(channel.annotations().await.overlay(2).is_none());
+}
+
+#[tokio::test]
+async fn only_the_teacher_may_annotate() {
+    let channel = lesson().await;
+    let mut events = channel.subscribe();
+    let note = TeachingAnnotation::Comment("Play here".to_string());
+
+    assert!(channel.receive_annotation("student-node", 1, note.clone()).await.is_err());
+    assert!(channel.annotate("student-node", note.clone()).await.is_err());
+    assert!(channel.receive_annotation(TEACHER, 5, note.clone()).await.is_err(), "no move 5 yet");
+    assert!(channel.receive_annotation(TEACHER, 1, TeachingAnnotation::RemoveStone { at: Coord::new(9, 9) }).await.is_err());
+    assert!(channel.annotations().await.is_empty());
+    assert!(events.try_recv().is_err());
+
+    // Ordinary games have no teacher at all
+    let game = GameChannel::new("game".to_string(), GameState::new(9));
+    assert!(game.annotate(TEACHER, note.clone()).await.is_err());
+
+    channel.receive_annotation(TEACHER, 0, note).await.unwrap();
+    assert_eq!(channel.annotations().await.overlay(0).unwrap().comments, ["Play here"]);
+}
+
+#[tokio::test]
+async fn lesson_exports_its_labels() {
+    let lobby = Lobby::new();
+    let game_id = lobby.create_game(None, 9, false).await.unwrap();
+    lobby.set_teacher(&game_id, Some(TEACHER.to_string())).await.unwrap();
+    assert_eq!(lobby.list_games().await[0].teacher.as_deref(), Some(TEACHER));
+    lobby.publish_game_advert(&game_id, TEACHER, None).await.unwrap();
+
+    let channel = lobby.get_game_channel(&game_id).await.unwrap();
+    assert_eq!(channel.teacher().await.as_deref(), Some(TEACHER));
+    channel.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    channel.annotate(TEACHER, TeachingAnnotation::Mark { at: Coord::new(2, 2), marker: Marker::Label("B".to_string()) }).await.unwrap();
+    channel.annotate(TEACHER, TeachingAnnotation::Comment("Approach from here".to_string())).await.unwrap();
+
+    let state = channel.get_latest_state().await.unwrap();
+    let sgf = SgfProcessor::new(state).with_annotations(channel.annotations().await).generate();
+    assert!(sgf.contains(";B[ee]LB[cc:B]C[Approach from here]"), "{}", sgf);
+
+    lobby.set_teacher(&game_id, None).await.unwrap();
+    assert!(channel.annotate(TEACHER, TeachingAnnotation::Unmark { at: Coord::new(2, 2) }).await.is_err());
+}
@@ -108,15 +108,18 @@ mod tests {
 #[cfg(not(feature = "iroh"))]
 mod stub_tests {
     use p2pgo_network::iroh_endpoint::IrohCtx;
+    use p2pgo_network::{loopback, Error};
     
     #[tokio::test]
     async fn test_stub_ticket() {
         let ctx = IrohCtx::new().await.expect("Failed to create stub IrohCtx");
         let ticket = ctx.ticket().await.expect("Failed to generate stub ticket");
-        assert_eq!(ticket, "loopback-ticket");
+        assert_eq!(ticket, loopback::ticket(ctx.node_id()));
         
-        // Test connection (should succeed in stub mode)
-        let result = ctx.connect_by_ticket(&ticket).await;
+        // Another node in the process connects; we can't connect to ourselves
+        let other = IrohCtx::new().await.expect("Failed to create second stub IrohCtx");
+        let result = other.connect_by_ticket(&ticket).await;
         assert!(result.is_ok(), "Stub connection should succeed");
+        assert!(matches!(ctx.connect_by_ticket(&ticket).await, Err(Error::SelfConnection)));
     }
 }
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Coarse move timestamps leave deduplication and move order as they were.
+
+use std::sync::Arc;
+use std::time::Duration;
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_network::clock::VirtualClock;
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::timing_privacy::TimingPrivacy;
+use p2pgo_network::wire::DirectMessage;
+
+const START: u64 = 1_700_000_003;
+
+fn private() -> TimingPrivacy {
+    TimingPrivacy { grain_secs: 5, first_move_jitter: Duration::ZERO }
+}
+
+/// A correspondence channel, so the moves we send wait in the outbox
+/// with the timestamps they go out with
+fn channel(clock: &Arc<VirtualClock>) -> GameChannel {
+    let channel = GameChannel::new("private".to_string(), GameState::new(9)).with_clock(clock.clone());
+    channel.set_correspondence(true);
+    channel
+}
+
+#[test]
+fn timestamps_round_down_to_the_grain() {
+    let privacy = private();
+    assert_eq!(privacy.quantize(START), 1_700_000_000);
+    assert_eq!(privacy.quantize(1_700_000_005), 1_700_000_005);
+    // Unstamped stays unstamped
+    assert_eq!(privacy.quantize(0), 0);
+    assert_eq!(TimingPrivacy { grain_secs: 0, ..privacy }.quantize(START), START);
+}
+
+#[test]
+fn jitter_stays_within_its_bound() {
+    let privacy = TimingPrivacy { grain_secs: 5, first_move_jitter: Duration::from_millis(40) };
+    for _ in 0..100 {
+        assert!(privacy.jitter() <= Duration::from_millis(40));
+    }
+    assert_eq!(private().jitter(), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn moves_go_out_with_coarse_timestamps_only_when_asked() {
+    let clock = Arc::new(VirtualClock::new(START));
+    let plain = channel(&clock);
+    plain.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    assert_eq!(plain.pending_outbound().await[0].ts, START);
+
+    let coarse = channel(&clock);
+    coarse.set_timing_privacy(Some(private())).await;
+    coarse.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    clock.advance(Duration::from_secs(3));
+    coarse.send_move(Move::Place(Coord::new(6, 6))).await.unwrap();
+    let sent: Vec<u64> = coarse.pending_outbound().await.iter().map(|record| record.ts).collect();
+    assert_eq!(sent, vec![1_700_000_000, 1_700_000_005]);
+}
+
+#[tokio::test]
+async fn coarse_moves_still_dedupe_and_keep_their_order() {
+    let clock = Arc::new(VirtualClock::new(START));
+    let ours = channel(&clock);
+    ours.set_timing_privacy(Some(private())).await;
+    let theirs = GameChannel::new("private".to_string(), GameState::new(9)).with_clock(clock.clone());
+
+    // Three moves inside one grain share a timestamp
+    for (x, y) in [(2, 2), (6, 6), (2, 6)] {
+        ours.send_move(Move::Place(Coord::new(x, y))).await.unwrap();
+    }
+    let records = ours.pending_outbound().await;
+    assert!(records.iter().all(|record| record.ts == records[0].ts));
+
+    for record in &records {
+        let outcome = theirs.receive_direct("ours", DirectMessage::Move(record.clone())).await;
+        assert!(matches!(outcome, InboundOutcome::Reply(DirectMessage::Ack { .. })));
+    }
+    // Delivered again, the same moves are recognised as duplicates
+    let outcome = theirs.receive_direct("ours", DirectMessage::Move(records[1].clone())).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+    assert_eq!(theirs.get_all_moves().await, ours.get_all_moves().await);
+    assert_eq!(theirs.metrics().duplicates_dropped, 1);
+}
@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Co-signed tournament results chain together, and tampering is caught.
+
+use std::path::Path;
+use p2pgo_core::phase::{GamePhase, GameResult};
+use p2pgo_core::{Color, Coord, EndReason, GameState, Move};
+use p2pgo_network::archive::GameArchive;
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::tournament::{
+    verify_dir, RecordError, ResultRecord, TournamentFeed, TournamentLedger, RESULTS_FILE,
+};
+use p2pgo_network::wire::DirectMessage;
+use p2pgo_network::Identity;
+
+const TOURNAMENT: &str = "spring-cup";
+
+fn players() -> [Identity; 3] {
+    [1, 2, 3].map(|seed| Identity::from_secret_bytes(&[seed; 32]))
+}
+
+fn game(stones: u8) -> GameState {
+    let mut state = GameState::new(9);
+    for i in 0..stones {
+        state.apply_move(Move::Place(Coord::new(i, i % 3))).unwrap();
+    }
+    state
+}
+
+fn won_by(winner: Color) -> GameResult {
+    GameResult { winner: Some(winner), reason: EndReason::DoublePass, score_diff: 3.5 }
+}
+
+/// A record of `state` signed by both players
+fn signed(game_id: &str, state: &GameState, black: &Identity, white: &Identity, result: GameResult) -> ResultRecord {
+    let players = (black.public_key(), white.public_key());
+    let mut record = ResultRecord::new(TOURNAMENT, &game_id.to_string(), state, players, result, None);
+    assert_eq!(record.sign(black), Ok(Color::Black));
+    assert_eq!(record.sign(white), Ok(Color::White));
+    record
+}
+
+fn archive(dir: &Path, game_id: &str, state: &GameState, winner: Color) {
+    let archive = GameArchive {
+        game_id: game_id.to_string(),
+        final_state: state.clone(),
+        move_count: state.moves.len() as u32,
+        archived_at: 1_700_000_000,
+        winner: Some(winner),
+        score_diff: Some(3),
+        end_reason: None,
+        anomalies: Vec::new(),
+        clock_skews: Vec::new(),
+    };
+    std::fs::write(dir.join(format!("{}.cbor", game_id)), serde_cbor::to_vec(&archive).unwrap()).unwrap();
+}
+
+/// Three games between three players, archived and published in `dir`:
+/// A beats B, B beats C, C beats A
+fn round_robin(dir: &Path) -> TournamentLedger {
+    let [a, b, c] = players();
+    let games = [("game-1", &a, &b, Color::Black), ("game-2", &b, &c, Color::Black), ("game-3", &a, &c, Color::White)];
+    let mut ledger = TournamentLedger::new(TOURNAMENT);
+    for (i, (game_id, black, white, winner)) in games.into_iter().enumerate() {
+        let state = game(3 + i as u8);
+        archive(dir, game_id, &state, winner);
+        let record = ledger.link(signed(game_id, &state, black, white, won_by(winner)));
+        ledger.append(record).unwrap();
+    }
+    ledger.save(dir.join(RESULTS_FILE)).unwrap();
+    ledger
+}
+
+#[test]
+fn a_clean_tournament_verifies_and_ranks() {
+    let dir = tempfile::tempdir().unwrap();
+    let ledger = round_robin(dir.path());
+    let report = verify_dir(dir.path()).unwrap();
+    assert!(report.failures.is_empty(), "{:?}", report.failures);
+    assert_eq!(report.ledger.records(), ledger.records());
+
+    // One win each over two games
+    let standings = report.ledger.standings();
+    assert_eq!(standings.len(), 3);
+    assert!(standings.iter().all(|s| (s.played, s.wins, s.losses, s.draws, s.points) == (2, 1, 1, 0, 1.0)));
+}
+
+#[test]
+fn a_tampered_result_is_caught() {
+    let dir = tempfile::tempdir().unwrap();
+    let ledger = round_robin(dir.path());
+
+    // Hand game 3 to A after both signed it for C
+    let mut records = ledger.records().to_vec();
+    records[2].result = won_by(Color::Black);
+    let mut tampered = TournamentLedger::new(TOURNAMENT);
+    for record in &records[..2] {
+        tampered.append(record.clone()).unwrap();
+    }
+    assert_eq!(tampered.append(records[2].clone()), Err(RecordError::BadSignature(Color::Black)));
+    let bytes: Vec<u8> = records.iter().flat_map(|r| serde_cbor::to_vec(r).unwrap()).collect();
+    std::fs::write(dir.path().join(RESULTS_FILE), bytes).unwrap();
+
+    let report = verify_dir(dir.path()).unwrap();
+    assert_eq!(report.checked(), 3);
+    assert_eq!(report.failures, vec![(2, "game-3".to_string(), RecordError::BadSignature(Color::Black))]);
+
+    // Only the first two games count: A and B won one, C lost its only game
+    let [a, b, c] = players().map(|p| p.node_id());
+    let standings = report.ledger.standings();
+    let line = |player: &str| standings.iter().find(|s| s.player == player).unwrap().clone();
+    assert_eq!((line(&a).played, line(&a).wins, line(&a).points), (1, 1, 1.0));
+    assert_eq!((line(&b).played, line(&b).wins, line(&b).losses, line(&b).points), (2, 1, 1, 1.0));
+    assert_eq!((line(&c).played, line(&c).losses, line(&c).points), (1, 1, 0.0));
+    assert_eq!(standings.last().unwrap().player, c);
+
+    let csv = report.ledger.standings_csv();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("player,played,wins,losses,draws,points"));
+    assert_eq!(lines.last(), Some(format!("{},1,0,1,0,0", c).as_str()));
+    let json: serde_json::Value = serde_json::from_str(&report.ledger.standings_json().unwrap()).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn an_edited_archive_no_longer_matches_its_result() {
+    let dir = tempfile::tempdir().unwrap();
+    round_robin(dir.path());
+    archive(dir.path(), "game-2", &game(9), Color::Black);
+    std::fs::remove_file(dir.path().join("game-3.cbor")).unwrap();
+
+    let report = verify_dir(dir.path()).unwrap();
+    let failures: Vec<_> = report.failures.into_iter().map(|(_, _, e)| e).collect();
+    assert_eq!(
+        failures,
+        vec![RecordError::GameMismatch("game-2".to_string()), RecordError::MissingGame("game-3".to_string())]
+    );
+    assert_eq!(report.ledger.records().len(), 1);
+}
+
+#[test]
+fn the_ledger_takes_only_whole_chained_results() {
+    let [a, b, c] = players();
+    let state = game(4);
+    let mut ledger = TournamentLedger::new(TOURNAMENT);
+    let first = ledger.link(signed("game-1", &state, &a, &b, won_by(Color::White)));
+    ledger.append(first.clone()).unwrap();
+
+    // Linked to nothing, or to a record other than the head
+    let unlinked = signed("game-2", &state, &b, &c, won_by(Color::Black));
+    assert_eq!(ledger.append(unlinked.clone()), Err(RecordError::BrokenChain));
+    assert_eq!(ledger.append(ledger.link(first)), Err(RecordError::Duplicate("game-1".to_string())));
+
+    let mut half = ResultRecord::new(TOURNAMENT, &"game-2".to_string(), &state, (b.public_key(), c.public_key()), won_by(Color::Black), None);
+    half.sign(&b).unwrap();
+    assert_eq!(ledger.append(ledger.link(half.clone())), Err(RecordError::BadSignature(Color::White)));
+    assert_eq!(half.sign(&a), Err(RecordError::NotAPlayer));
+
+    let mut other = TournamentLedger::new("autumn-cup");
+    assert_eq!(other.append(unlinked.clone()), Err(RecordError::WrongTournament(TOURNAMENT.to_string())));
+    ledger.append(ledger.link(unlinked)).unwrap();
+    assert_eq!(ledger.records().len(), 2);
+}
+
+#[tokio::test]
+async fn players_cosign_and_the_proposer_publishes() {
+    let [black, white, _] = players();
+    let players = (black.public_key(), white.public_key());
+    let feed = TournamentFeed::local(TOURNAMENT);
+    let mut published = feed.subscribe();
+
+    // Both sides of the same game, ended the same way
+    let host = GameChannel::new("final".to_string(), GameState::new(9));
+    let guest = GameChannel::new("final".to_string(), GameState::new(9));
+    for (channel, identity) in [(&host, black), (&guest, white)] {
+        channel.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+        channel.send_move(Move::Resign).await.unwrap();
+        assert!(matches!(channel.phase().await, GamePhase::Finished { .. }));
+        channel.publish_to(feed.clone(), identity).await;
+    }
+
+    let proposed = host.propose_result(players, None).await.unwrap();
+    assert!(proposed.verify_signature(Color::Black).is_ok());
+    let InboundOutcome::Reply(signed @ DirectMessage::ResultSigned(_)) =
+        guest.receive_direct("host", DirectMessage::SignResult(proposed.clone())).await
+    else {
+        panic!("the guest didn't countersign");
+    };
+    assert!(matches!(host.receive_direct("guest", signed).await, InboundOutcome::Handled));
+
+    let record = published.try_recv().unwrap();
+    assert!(record.verify_signatures().is_ok());
+    assert_eq!(record.prev_hash, [0; 32]);
+    assert_eq!(feed.ledger().await.records(), std::slice::from_ref(&record));
+
+    // A result the guest didn't see is never signed
+    let mut forged = proposed;
+    forged.result.winner = Some(Color::White);
+    let outcome = guest.receive_direct("host", DirectMessage::SignResult(forged)).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Traffic counters: per-second samples, the one-minute history and its CSV export.
+
+use p2pgo_network::traffic::{TrafficCategory, TrafficCounters, TrafficHistory, TrafficSample, HISTORY_SECS};
+
+fn sample(at_secs: u64, gossip: u64) -> TrafficSample {
+    let mut sample = TrafficSample { at_secs, ..TrafficSample::default() };
+    sample.messages[0] = gossip;
+    sample.bytes[0] = gossip * 100;
+    sample
+}
+
+#[test]
+fn taking_a_sample_resets_the_counts() {
+    let counters = TrafficCounters::new();
+    counters.record(TrafficCategory::Gossip, 120);
+    counters.record(TrafficCategory::Gossip, 80);
+    counters.record(TrafficCategory::Sync, 4000);
+
+    let first = counters.take(1_000);
+    assert_eq!(first.at_secs, 1_000);
+    assert_eq!((first.messages(TrafficCategory::Gossip), first.bytes(TrafficCategory::Gossip)), (2, 200));
+    assert_eq!((first.messages(TrafficCategory::Sync), first.bytes(TrafficCategory::Sync)), (1, 4000));
+    assert_eq!(first.messages(TrafficCategory::Stream), 0);
+
+    counters.record(TrafficCategory::Relay, 50);
+    let second = counters.take(1_001);
+    assert_eq!(second.messages(TrafficCategory::Gossip), 0);
+    assert_eq!(second.bytes(TrafficCategory::Relay), 50);
+}
+
+#[test]
+fn history_keeps_the_last_minute() {
+    let mut history = TrafficHistory::new();
+    for at in 0..(HISTORY_SECS as u64 + 10) {
+        history.push(sample(at, at));
+    }
+    assert_eq!(history.len(), HISTORY_SECS);
+    assert_eq!(history.samples().next().unwrap().at_secs, 10);
+
+    // Newest sample at 0, older ones a second apart before it
+    let series = history.series(TrafficCategory::Gossip, false);
+    assert_eq!(series.first(), Some(&[-59.0, 10.0]));
+    assert_eq!(series.last(), Some(&[0.0, 69.0]));
+    assert_eq!(history.series(TrafficCategory::Gossip, true).last(), Some(&[0.0, 6900.0]));
+
+    // After a pause, samples older than a minute are dropped even if few
+    history.push(sample(100, 1));
+    let kept: Vec<u64> = history.samples().map(|s| s.at_secs).collect();
+    assert_eq!(kept, (41..=69).chain([100]).collect::<Vec<u64>>());
+    history.push(sample(200, 1));
+    assert_eq!(history.len(), 1);
+}
+
+#[test]
+fn csv_has_a_row_per_second() {
+    let mut history = TrafficHistory::new();
+    assert_eq!(
+        history.to_csv(),
+        "unix_secs,gossip_messages,gossip_bytes,stream_messages,stream_bytes,sync_messages,sync_bytes,relay_messages,relay_bytes\n"
+    );
+
+    history.push(sample(1_700_000_000, 3));
+    let mut busy = sample(1_700_000_001, 0);
+    busy.messages[3] = 2;
+    busy.bytes[3] = 512;
+    history.push(busy);
+
+    let csv = history.to_csv();
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows, ["1700000000,3,300,0,0,0,0,0,0", "1700000001,0,0,0,0,0,0,2,512"]);
+    assert!(csv.ends_with('\n'));
+}
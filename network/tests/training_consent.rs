@@ -0,0 +1,92 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Deleting a game's training copies here and on peers that saw the retraction.
+
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_network::identity::Identity;
+use p2pgo_network::training::{Retraction, TrainingMessage, TrainingStore, TrainingTopic, TRAINING_MESSAGE_VERSION};
+
+fn retraction(game_id: &str) -> TrainingMessage {
+    let identity = Identity::from_secret_bytes(&[7; 32]);
+    TrainingMessage::Retract(Retraction::sign(&identity, game_id.to_string(), 1_700_000_000))
+}
+
+fn game() -> GameState {
+    let mut game = GameState::new(9);
+    game.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    game
+}
+
+#[test]
+fn retractions_round_trip() {
+    let message = retraction("game-1");
+    assert_eq!(TrainingMessage::from_bytes(&message.to_bytes().unwrap()).unwrap(), message);
+    assert!(TrainingMessage::from_bytes(b"not cbor").is_err());
+}
+
+#[test]
+fn retractions_from_before_versioning_are_read() {
+    let message = retraction("game-1");
+    let bytes = message.to_bytes().unwrap();
+    assert_eq!(p2pgo_core::cbor::migrate::version_of(&bytes), TRAINING_MESSAGE_VERSION);
+
+    // Unversioned messages were the bare enum
+    let TrainingMessage::Retract(inner) = &message;
+    let old = serde_cbor::to_vec(&std::collections::BTreeMap::from([("Retract", inner)])).unwrap();
+    assert_eq!(TrainingMessage::from_bytes(&old).unwrap(), message);
+}
+
+#[test]
+fn retractions_are_signed_by_the_player() {
+    let TrainingMessage::Retract(signed) = retraction("game-1");
+    assert!(signed.verify().is_ok());
+    assert_eq!(signed.node_id, Identity::from_secret_bytes(&[7; 32]).node_id());
+
+    // Naming another game breaks the signature
+    let moved = Retraction { game_id: "game-2".to_string(), ..signed.clone() };
+    assert!(moved.verify().is_err());
+    // As does another player's key
+    let other = Identity::from_secret_bytes(&[8; 32]);
+    assert!(Retraction { key: other.public_key(), ..signed.clone() }.verify().is_err());
+    // And leaving it off, as peers before signing did
+    assert!(Retraction { signature: Vec::new(), ..signed }.verify().is_err());
+}
+
+#[test]
+fn deleting_only_touches_that_game() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = TrainingStore::new(dir.path());
+    store.save("game-1", &game()).unwrap();
+    store.save("game-2", &game()).unwrap();
+
+    assert!(store.delete("game-1").unwrap());
+    assert!(!store.contains("game-1"));
+    assert!(store.contains("game-2"));
+    // Deleting twice is fine
+    assert!(!store.delete("game-1").unwrap());
+}
+
+#[test]
+fn peer_ids_cannot_reach_outside_the_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = TrainingStore::new(dir.path().join("training"));
+    std::fs::write(dir.path().join("keep.cbor"), b"other data").unwrap();
+
+    assert!(!store.apply(&retraction("../keep")).unwrap());
+    assert!(dir.path().join("keep.cbor").exists());
+}
+
+#[tokio::test]
+async fn peers_drop_retracted_games() {
+    let dir = tempfile::tempdir().unwrap();
+    let peer = TrainingStore::new(dir.path());
+    peer.save("game-1", &game()).unwrap();
+
+    let topic = TrainingTopic::local();
+    let mut received = topic.subscribe();
+    topic.publish(retraction("game-1")).await.unwrap();
+
+    let message = received.recv().await.unwrap();
+    assert!(peer.apply(&message).unwrap());
+    assert!(!peer.contains("game-1"));
+}
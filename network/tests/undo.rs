@@ -0,0 +1,166 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Undo requests between two players: accepted ones roll both games back
+//! and leave a retraction in the chain, declined ones change nothing.
+
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::{Coord, GameEvent, GameState, Move, MoveRecord};
+use p2pgo_network::blob_store::Retraction;
+use p2pgo_network::game_channel::{GameChannel, InboundOutcome};
+use p2pgo_network::undo::UndoError;
+use p2pgo_network::wire::DirectMessage;
+use p2pgo_network::Error;
+
+/// Deliver `ours`' latest move to `theirs`, as its connection would
+async fn deliver_last(ours: &GameChannel, theirs: &GameChannel, from: &str) {
+    let record = ours.sync_moves().await.pop().expect("a move to deliver");
+    theirs.receive_direct(from, DirectMessage::Move(record)).await;
+}
+
+/// Black and White each play two moves, seen by both
+async fn two_players() -> (GameChannel, GameChannel) {
+    let black = GameChannel::new("undo".to_string(), GameState::new(9));
+    let white = GameChannel::new("undo".to_string(), GameState::new(9));
+    let points = [(2, 2), (6, 6), (2, 6), (6, 2)];
+    for (i, (x, y)) in points.into_iter().enumerate() {
+        let (mover, other, name) = if i % 2 == 0 { (&black, &white, "black") } else { (&white, &black, "white") };
+        mover.send_move(Move::Place(Coord::new(x, y))).await.unwrap();
+        deliver_last(mover, other, name).await;
+    }
+    (black, white)
+}
+
+fn moves(state: &Option<GameState>) -> Vec<Move> {
+    state.as_ref().unwrap().moves.clone()
+}
+
+/// White asks to take back its last move; Black gets to answer
+async fn ask(black: &GameChannel, white: &GameChannel) {
+    white.request_undo(1).await.unwrap();
+    let outcome = black.receive_direct("white", DirectMessage::UndoRequest { moves: 1 }).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+}
+
+#[tokio::test]
+async fn accepted_undo_rolls_both_players_back() {
+    let (black, white) = two_players().await;
+    let mut black_events = black.subscribe();
+    let mut white_events = white.subscribe();
+    ask(&black, &white).await;
+    assert!(matches!(
+        black_events.try_recv(),
+        Ok(GameEvent::UndoRequested { peer, moves: 1 }) if peer == "white"
+    ));
+    // Waiting for the answer, White can't play on
+    assert!(matches!(
+        white.send_move(Move::Pass).await,
+        Err(Error::Undo(UndoError::AlreadyPending))
+    ));
+
+    black.answer_undo(true).await.unwrap();
+    white.receive_direct("black", DirectMessage::UndoResponse { accepted: true }).await;
+
+    let expected = vec![Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6)), Move::Place(Coord::new(2, 6))];
+    assert_eq!(moves(&black.get_latest_state().await), expected);
+    assert_eq!(moves(&white.get_latest_state().await), expected);
+    assert!(matches!(black_events.try_recv(), Ok(GameEvent::MovesUndone { moves: 1 })));
+    assert!(matches!(white_events.try_recv(), Ok(GameEvent::UndoAnswered { accepted: true })));
+    assert!(matches!(white_events.try_recv(), Ok(GameEvent::MovesUndone { moves: 1 })));
+    assert_eq!(white.undo_state().await.remaining(), 2);
+
+    // Play resumes from the earlier position
+    white.send_move(Move::Place(Coord::new(4, 4))).await.unwrap();
+    deliver_last(&white, &black, "white").await;
+    assert_eq!(moves(&black.get_latest_state().await), moves(&white.get_latest_state().await));
+    assert_eq!(black.get_all_moves().await.len(), 4);
+}
+
+#[tokio::test]
+async fn declined_undo_changes_nothing() {
+    let (black, white) = two_players().await;
+    let before = moves(&white.get_latest_state().await);
+    ask(&black, &white).await;
+
+    black.answer_undo(false).await.unwrap();
+    white.receive_direct("black", DirectMessage::UndoResponse { accepted: false }).await;
+
+    assert_eq!(moves(&white.get_latest_state().await), before);
+    assert_eq!(moves(&black.get_latest_state().await), before);
+    assert!(white.move_chain().await.retractions().is_empty());
+    assert_eq!(white.undo_state().await.remaining(), 3, "only granted undos count");
+    // No longer waiting, White plays on
+    white.send_move(Move::Pass).await.unwrap();
+}
+
+#[tokio::test]
+async fn retractions_stay_in_the_chain() {
+    let (black, white) = two_players().await;
+    ask(&black, &white).await;
+    black.answer_undo(true).await.unwrap();
+    white.receive_direct("black", DirectMessage::UndoResponse { accepted: true }).await;
+
+    for channel in [&black, &white] {
+        let chain = channel.move_chain().await;
+        chain.verify().unwrap();
+        // Four moves and the retraction of the last, none rewritten
+        let blobs = chain.get_all_blobs();
+        assert_eq!(blobs.len(), 5);
+        assert_eq!(blobs[4].retraction, Some(Retraction { moves: 1 }));
+        assert_eq!(blobs[4].mv, Move::Place(Coord::new(6, 2)));
+        assert_eq!(chain.retractions(), vec![Retraction { moves: 1 }]);
+        assert_eq!(chain.played().len(), 3);
+    }
+    // Both sides recorded the same history
+    let tip = |chain: p2pgo_network::blob_store::MoveChain| chain.current_blob().unwrap().hash();
+    assert_eq!(tip(black.move_chain().await), tip(white.move_chain().await));
+}
+
+#[tokio::test]
+async fn sync_after_an_undo_converges() {
+    let (black, white) = two_players().await;
+    ask(&black, &white).await;
+    black.answer_undo(true).await.unwrap();
+    white.receive_direct("black", DirectMessage::UndoResponse { accepted: true }).await;
+
+    // White replays the point; Black misses it and catches up by sync
+    white.send_move(Move::Place(Coord::new(5, 5))).await.unwrap();
+    let synced: Vec<MoveRecord> = white.sync_moves().await;
+    assert_eq!(synced.len(), 4);
+    assert_eq!(black.apply_sync(&synced).await.unwrap(), 1);
+    assert_eq!(moves(&black.get_latest_state().await), moves(&white.get_latest_state().await));
+
+    // And the other way round, nothing left to catch up on
+    let outcome = white.receive_direct("black", DirectMessage::SyncResponse {
+        moves: black.sync_moves().await,
+        state: black.get_latest_state().await.unwrap(),
+    }).await;
+    assert!(matches!(outcome, InboundOutcome::Handled));
+    assert_eq!(moves(&black.get_latest_state().await), moves(&white.get_latest_state().await));
+}
+
+#[tokio::test]
+async fn undos_stop_at_the_negotiated_limit() {
+    let settings = GameSettings { undo_limit: 1, ..GameSettings::standard(9) };
+    let black = GameChannel::new("undo".to_string(), GameState::new(9)).with_settings(settings);
+    let white = GameChannel::new("undo".to_string(), GameState::new(9));
+    // Black's hello brings White down to its limit
+    white.receive_direct("black", DirectMessage::hello(&settings)).await;
+    assert_eq!(white.undo_state().await.limit(), 1);
+
+    black.send_move(Move::Place(Coord::new(2, 2))).await.unwrap();
+    deliver_last(&black, &white, "black").await;
+    black.request_undo(1).await.unwrap();
+    white.receive_direct("black", DirectMessage::UndoRequest { moves: 1 }).await;
+    white.answer_undo(true).await.unwrap();
+    black.receive_direct("white", DirectMessage::UndoResponse { accepted: true }).await;
+    black.send_move(Move::Place(Coord::new(3, 3))).await.unwrap();
+    deliver_last(&black, &white, "black").await;
+
+    assert!(matches!(
+        black.request_undo(1).await,
+        Err(Error::Undo(UndoError::LimitReached { limit: 1 }))
+    ));
+    // A peer asking past the limit is turned down without bothering the player
+    let outcome = white.receive_direct("black", DirectMessage::UndoRequest { moves: 1 }).await;
+    assert!(matches!(outcome, InboundOutcome::Reply(DirectMessage::UndoResponse { accepted: false })));
+}
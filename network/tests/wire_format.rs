@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Framing and negotiation for messages on direct game streams.
+
+use p2pgo_core::{Coord, GameState, Move, MoveRecord};
+use p2pgo_core::settings::GameSettings;
+use p2pgo_network::wire::{self, DirectMessage, WireFormat, COMPRESSION_THRESHOLD, MAX_FRAME_LEN};
+
+/// The cap the old newline-delimited reader put on a stream
+const OLD_READ_CAP: usize = 1024;
+
+fn record(mv: Move, ts: u64) -> MoveRecord {
+    MoveRecord {
+        mv,
+        tag: None,
+        ts,
+        broadcast_hash: Some([7; 32]),
+        prev_hash: Some([9; 32]),
+    }
+}
+
+/// A 19x19 sync response with 300 moves
+fn large_sync_response() -> DirectMessage {
+    let mut state = GameState::new(19);
+    let mut moves = Vec::new();
+    for i in 0..300u32 {
+        let mv = Move::Place(Coord::new((i % 19) as u8, (i / 19) as u8));
+        let _ = state.apply_move(mv.clone());
+        moves.push(record(mv, 1_700_000_000 + i as u64));
+    }
+    DirectMessage::SyncResponse { moves, state }
+}
+
+#[tokio::test]
+async fn large_sync_response_round_trips() {
+    let msg = large_sync_response();
+
+    let mut stream = Vec::new();
+    wire::write_message(&mut stream, &msg, WireFormat::Cbor).await.unwrap();
+    // Far beyond what the old reader accepted, yet compressed well below raw JSON
+    assert!(stream.len() > OLD_READ_CAP);
+    assert!(stream.len() < serde_json::to_vec(&msg).unwrap().len() / 2);
+
+    match wire::read_message(&mut stream.as_slice()).await.unwrap() {
+        DirectMessage::SyncResponse { moves, state } => {
+            assert_eq!(moves.len(), 300);
+            assert_eq!(moves[299].ts, 1_700_000_299);
+            assert_eq!(state.board_size, 19);
+        }
+        other => panic!("expected a sync response, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn small_messages_are_not_compressed() {
+    let msg = DirectMessage::Move(record(Move::Pass, 1));
+    let frame = wire::encode(&msg, WireFormat::Cbor).unwrap();
+    let payload_len = u32::from_be_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize;
+    assert!(payload_len <= COMPRESSION_THRESHOLD);
+    assert_eq!(payload_len, serde_cbor::to_vec(&msg).unwrap().len());
+    assert!(matches!(wire::decode(&frame).await.unwrap(), DirectMessage::Move(r) if r.mv == Move::Pass));
+}
+
+#[tokio::test]
+async fn legacy_json_moves_still_work() {
+    let coord = Coord::new(3, 3);
+    let msg = DirectMessage::Move(record(Move::Place(coord), 42));
+
+    // Older peers write bare JSON with a trailing newline
+    let bytes = wire::encode(&msg, WireFormat::Json).unwrap();
+    assert_eq!(bytes.last(), Some(&b'\n'));
+    serde_json::from_slice::<MoveRecord>(&bytes).unwrap();
+
+    match wire::decode(&bytes).await.unwrap() {
+        DirectMessage::Move(r) => assert_eq!(r.mv, Move::Place(coord)),
+        other => panic!("expected a move, got {:?}", other),
+    }
+
+    // Sync only exists in the framed format
+    assert!(wire::encode(&large_sync_response(), WireFormat::Json).is_err());
+}
+
+#[test]
+fn hello_negotiates_the_format() {
+    assert_eq!(WireFormat::negotiate(&[WireFormat::Json, WireFormat::Cbor]), WireFormat::Cbor);
+    assert_eq!(WireFormat::negotiate(&[WireFormat::Json]), WireFormat::Json);
+    assert_eq!(WireFormat::negotiate(&[]), WireFormat::Json);
+    match DirectMessage::hello(&GameSettings::standard(9)) {
+        DirectMessage::Hello { formats, settings, .. } => {
+            assert_eq!(WireFormat::negotiate(&formats), WireFormat::Cbor);
+            assert_eq!(settings, Some(GameSettings::standard(9)));
+        }
+        other => panic!("expected a hello, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn broken_frames_are_rejected() {
+    let frame = wire::encode(&large_sync_response(), WireFormat::Cbor).unwrap();
+    assert!(wire::decode(&frame[..frame.len() - 1]).await.is_err());
+    assert!(wire::decode(&frame[..3]).await.is_err());
+    assert!(wire::decode(&[]).await.is_err());
+
+    let mut oversized = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes().to_vec();
+    oversized.push(1);
+    let err = wire::decode(&oversized).await.unwrap_err();
+    assert!(err.to_string().contains("exceeds"));
+}
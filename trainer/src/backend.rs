@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Choosing the burn backend a training run uses.
+//!
+//! The CPU backend is always built. `wgpu` comes with the `gpu` feature and
+//! Metal with `metal` on macOS; both are probed at runtime, and a run asking
+//! for one that doesn't start trains on the CPU instead, with a warning.
+
+use std::time::{Duration, Instant};
+use burn::backend::{Autodiff, NdArray};
+use burn::optim::AdamConfig;
+use burn::tensor::backend::{AutodiffBackend, Backend};
+use burn::tensor::{ElementConversion, Tensor};
+use serde::{Serialize, Deserialize};
+use p2pgo_core::engine::{self_play_governed, RandomPlayer};
+use p2pgo_core::governor::Governor;
+use crate::checkpoint::CheckpointStore;
+use crate::{GoDataset, GoMini6E, GoSample, NeuralTrainer, TrainingConfig};
+
+/// Calls the generic function `f` on `backend`'s burn backend, with
+/// autodiff; backends not built in fall through to the CPU
+macro_rules! dispatch {
+    ($backend:expr, $f:ident $(, $arg:expr)*) => {
+        match $backend {
+            #[cfg(feature = "gpu")]
+            TrainingBackend::Wgpu => $f::<Autodiff<burn::backend::Wgpu>>($($arg),*),
+            #[cfg(all(feature = "metal", target_os = "macos"))]
+            TrainingBackend::Metal => $f::<Autodiff<burn::backend::Metal>>($($arg),*),
+            _ => $f::<Autodiff<NdArray>>($($arg),*),
+        }
+    };
+}
+
+/// Backend a training run asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TrainingBackend {
+    #[default]
+    Cpu,
+    /// The GPU through wgpu's default graphics API
+    Wgpu,
+    /// Apple GPUs through Metal
+    Metal,
+}
+
+impl TrainingBackend {
+    pub const ALL: [TrainingBackend; 3] = [TrainingBackend::Cpu, TrainingBackend::Wgpu, TrainingBackend::Metal];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TrainingBackend::Cpu => "CPU",
+            TrainingBackend::Wgpu => "GPU (wgpu)",
+            TrainingBackend::Metal => "GPU (Metal)",
+        }
+    }
+
+    /// Whether this build includes the backend
+    pub fn compiled(self) -> bool {
+        match self {
+            TrainingBackend::Cpu => true,
+            TrainingBackend::Wgpu => cfg!(feature = "gpu"),
+            TrainingBackend::Metal => cfg!(all(feature = "metal", target_os = "macos")),
+        }
+    }
+
+    /// Whether the backend starts on this machine
+    pub fn probe(self) -> bool {
+        // wgpu panics rather than failing when there is no usable adapter
+        self.compiled() && std::panic::catch_unwind(|| dispatch!(self, smoke)).unwrap_or(false)
+    }
+}
+
+/// A tensor sum on the backend's default device, the first use of which
+/// initialises it
+fn smoke<B: Backend>() -> bool {
+    let device = B::Device::default();
+    let sum: f32 = Tensor::<B, 1>::ones([4], &device).sum().into_scalar().elem();
+    sum == 4.0
+}
+
+/// Backends built in that start on this machine
+pub fn available() -> Vec<TrainingBackend> {
+    TrainingBackend::ALL.into_iter().filter(|backend| backend.probe()).collect()
+}
+
+/// The backend a run gets, and why it isn't the one asked for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub backend: TrainingBackend,
+    pub fallback: Option<String>,
+}
+
+/// `requested` if it starts, otherwise the CPU
+pub fn select(requested: TrainingBackend) -> Selection {
+    if requested == TrainingBackend::Cpu || requested.probe() {
+        return Selection { backend: requested, fallback: None };
+    }
+    let reason = if requested.compiled() {
+        format!("{} failed to start; training on the CPU", requested.label())
+    } else {
+        format!("{} is not built in; training on the CPU", requested.label())
+    };
+    tracing::warn!("{}", reason);
+    Selection { backend: TrainingBackend::Cpu, fallback: Some(reason) }
+}
+
+/// What a training run did, and how fast
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingReport {
+    /// Backend trained on, after any fallback
+    pub backend: TrainingBackend,
+    pub fallback: Option<String>,
+    /// Loss of each batch trained
+    pub losses: Vec<f32>,
+    /// Evaluation loss over the whole epoch afterwards
+    pub eval_loss: f32,
+    /// Positions trained per second, a rough figure that includes starting
+    /// the backend
+    pub positions_per_sec: f64,
+}
+
+/// Games a [`train_on_self_play`] run plays, and the most moves in each
+pub const SELF_PLAY_GAMES: u64 = 8;
+pub const SELF_PLAY_MAX_MOVES: usize = 200;
+
+/// Train `batches` batches of `dataset` on the backend `config.backend`
+/// selects, from fresh weights
+pub fn train(dataset: &GoDataset, config: TrainingConfig, checkpoints: CheckpointStore, batches: usize) -> anyhow::Result<TrainingReport> {
+    train_and_maybe_publish(dataset, config, checkpoints, batches, false)
+}
+
+/// Play [`SELF_PLAY_GAMES`] 9x9 games between random players, train an
+/// epoch of them on `backend` and publish the weights in `checkpoints`,
+/// all in the background as `governor` allows
+pub fn train_on_self_play(backend: TrainingBackend, governor: Governor, checkpoints: CheckpointStore) -> anyhow::Result<TrainingReport> {
+    let games: Vec<_> = (0..SELF_PLAY_GAMES).map(|seed| {
+        let mut black = RandomPlayer::new(seed * 2);
+        let mut white = RandomPlayer::new(seed * 2 + 1);
+        self_play_governed(9, &mut black, &mut white, SELF_PLAY_MAX_MOVES, &governor)
+    }).collect();
+    let dataset = GoDataset::from_games(&games);
+    let config = TrainingConfig { backend, batch_size: 32, governor: Some(governor), ..TrainingConfig::default() };
+    let batches = ((dataset.len() + config.batch_size - 1) / config.batch_size).max(1);
+    train_and_maybe_publish(&dataset, config, checkpoints, batches, true)
+}
+
+fn train_and_maybe_publish(
+    dataset: &GoDataset,
+    config: TrainingConfig,
+    checkpoints: CheckpointStore,
+    batches: usize,
+    publish: bool,
+) -> anyhow::Result<TrainingReport> {
+    let Selection { backend, fallback } = select(config.backend);
+    let run = dispatch!(backend, run, dataset, config, checkpoints, batches, publish)?;
+    let secs = run.elapsed.as_secs_f64().max(f64::EPSILON);
+    let report = TrainingReport {
+        backend,
+        fallback,
+        losses: run.losses,
+        eval_loss: run.eval_loss,
+        positions_per_sec: run.positions as f64 / secs,
+    };
+    tracing::info!(backend = backend.label(), positions_per_sec = report.positions_per_sec as u64, "Training run finished");
+    Ok(report)
+}
+
+/// Train on synthetic 9x9 positions to see how fast `backend` is here
+pub fn benchmark(backend: TrainingBackend) -> anyhow::Result<TrainingReport> {
+    benchmark_with(backend, None)
+}
+
+/// [`benchmark`], held back by `governor` between batches
+pub fn benchmark_with(backend: TrainingBackend, governor: Option<Governor>) -> anyhow::Result<TrainingReport> {
+    // Checkpoints go nowhere worth keeping
+    let scratch = tempfile::tempdir()?;
+    let samples = (0..256).map(|i| {
+        let mut sample = GoSample::empty(9, (i * 7) % 82, if i % 2 == 0 { 1.0 } else { -1.0 });
+        sample.board_state[i % 81] = 1.0;
+        sample
+    }).collect();
+    let config = TrainingConfig { backend, batch_size: 32, checkpoint_every: usize::MAX, governor, ..TrainingConfig::default() };
+    train(&GoDataset::from_samples(samples), config, CheckpointStore::new(scratch.path(), 1), 16)
+}
+
+/// Evaluation loss of the checkpoint left in `checkpoints` by an unpublished
+/// run, loaded onto `backend`
+///
+/// The same weights should score the same on every backend, give or take
+/// float rounding.
+pub fn evaluate_checkpoint(backend: TrainingBackend, dataset: &GoDataset, config: TrainingConfig, checkpoints: CheckpointStore) -> anyhow::Result<f32> {
+    let Selection { backend, .. } = select(backend);
+    dispatch!(backend, resumed_loss, dataset, config, checkpoints)
+}
+
+fn resumed_loss<B: AutodiffBackend>(dataset: &GoDataset, config: TrainingConfig, checkpoints: CheckpointStore) -> anyhow::Result<f32> {
+    let optimizer = AdamConfig::new().init::<B, GoMini6E<B>>();
+    let mut trainer = NeuralTrainer::new(B::Device::default(), dataset, config, optimizer, checkpoints)?;
+    trainer.resume()?;
+    Ok(trainer.evaluate())
+}
+
+struct Run {
+    losses: Vec<f32>,
+    eval_loss: f32,
+    positions: usize,
+    elapsed: Duration,
+}
+
+fn run<B: AutodiffBackend>(
+    dataset: &GoDataset,
+    config: TrainingConfig,
+    checkpoints: CheckpointStore,
+    batches: usize,
+    publish: bool,
+) -> anyhow::Result<Run> {
+    let started = Instant::now();
+    let governor = config.governor.clone();
+    let _slot = governor.as_ref().map(Governor::slot);
+    let optimizer = AdamConfig::new().init::<B, GoMini6E<B>>();
+    let mut trainer = NeuralTrainer::new(B::Device::default(), dataset, config, optimizer, checkpoints)?;
+    let losses = (0..batches).map(|_| {
+        if let Some(governor) = &governor {
+            governor.checkpoint();
+        }
+        trainer.train_batch()
+    }).collect::<anyhow::Result<Vec<_>>>()?;
+    if publish {
+        trainer.publish()?;
+    }
+    Ok(Run {
+        losses,
+        eval_loss: trainer.evaluate(),
+        positions: trainer.positions_trained(),
+        elapsed: started.elapsed(),
+    })
+}
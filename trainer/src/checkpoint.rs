@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checkpoints of model weights taken during training, so a crash loses at
+//! most the batches since the last one.
+//!
+//! Each checkpoint is one CBOR file named after the batch it was taken at.
+//! Published weights, the ones the user chose to keep, live next to them in
+//! the same container.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Serialize, Deserialize};
+
+/// Version of the [`Checkpoint`] container written by this build
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+/// File the published weights are kept in
+pub const PUBLISHED_FILE: &str = "weights.cbor";
+
+/// Directory checkpoints are kept in under the data directory
+pub const CHECKPOINTS_DIR: &str = "checkpoints";
+
+/// File a checkpoint is written to before it's renamed into place
+const TMP_FILE: &str = "checkpoint_tmp";
+
+/// Model weights and how far training had got
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub version: u32,
+    pub board_size: u8,
+    /// Batches trained when the weights were taken
+    pub batch: usize,
+    /// Loss of the last batch trained
+    pub loss: f32,
+    /// Model record, see [`crate::NeuralTrainer`]
+    pub weights: Vec<u8>,
+}
+
+/// Directory of checkpoints, keeping only the newest few
+#[derive(Debug, Clone)]
+pub struct CheckpointStore {
+    dir: PathBuf,
+    keep: usize,
+}
+
+impl CheckpointStore {
+    /// Keep the last `keep` checkpoints in `dir`, created on the first write
+    pub fn new(dir: impl Into<PathBuf>, keep: usize) -> Self {
+        Self { dir: dir.into(), keep: keep.max(1) }
+    }
+
+    /// Keep the last `keep` checkpoints in the default data directory
+    pub fn open_default(keep: usize) -> Result<Self> {
+        let dir = p2pgo_core::p2pgo_dirs::Dirs::current()?.data;
+        Ok(Self::new(dir.join(CHECKPOINTS_DIR), keep))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save `checkpoint` and drop all but the newest `keep`
+    pub fn write(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.replace(&self.dir.join(format!("checkpoint-{:010}.cbor", checkpoint.batch)), checkpoint)?;
+        let checkpoints = self.checkpoints()?;
+        for (_, path) in &checkpoints[..checkpoints.len().saturating_sub(self.keep)] {
+            fs::remove_file(path).context("Failed to remove old checkpoint")?;
+        }
+        Ok(())
+    }
+
+    /// Make `checkpoint` the published weights
+    pub fn publish(&self, checkpoint: &Checkpoint) -> Result<()> {
+        self.replace(&self.dir.join(PUBLISHED_FILE), checkpoint)
+    }
+
+    pub fn published(&self) -> Result<Option<Checkpoint>> {
+        let path = self.dir.join(PUBLISHED_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        read(&path).map(Some)
+    }
+
+    /// The newest readable checkpoint
+    pub fn latest(&self) -> Result<Option<Checkpoint>> {
+        for (_, path) in self.checkpoints()?.iter().rev() {
+            match read(path) {
+                Ok(checkpoint) => return Ok(Some(checkpoint)),
+                Err(e) => tracing::warn!("Skipping unreadable checkpoint {}: {:#}", path.display(), e),
+            }
+        }
+        Ok(None)
+    }
+
+    /// The newest checkpoint if it's further along than the published weights,
+    /// i.e. training stopped without them being saved
+    pub fn orphaned(&self) -> Result<Option<Checkpoint>> {
+        let published = self.published()?.map_or(0, |published| published.batch);
+        Ok(self.latest()?.filter(|latest| latest.batch > published))
+    }
+
+    /// Write a sibling file and rename so a crash never leaves half a checkpoint
+    fn replace(&self, path: &Path, checkpoint: &Checkpoint) -> Result<()> {
+        fs::create_dir_all(&self.dir).context("Failed to create checkpoint directory")?;
+        let tmp = self.dir.join(TMP_FILE);
+        fs::write(&tmp, serde_cbor::to_vec(checkpoint)?).context("Failed to write checkpoint")?;
+        fs::rename(&tmp, path).context("Failed to replace checkpoint")?;
+        Ok(())
+    }
+
+    /// Checkpoint files by batch, oldest first
+    fn checkpoints(&self) -> Result<Vec<(usize, PathBuf)>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).context("Failed to read checkpoint directory"),
+        };
+        let mut checkpoints = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            let batch = path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("checkpoint-")?.strip_suffix(".cbor")?.parse().ok());
+            if let Some(batch) = batch {
+                checkpoints.push((batch, path));
+            }
+        }
+        checkpoints.sort();
+        Ok(checkpoints)
+    }
+}
+
+/// Read the checkpoint file at `path`, e.g. published weights
+pub fn read(path: &Path) -> Result<Checkpoint> {
+    let checkpoint: Checkpoint = serde_cbor::from_slice(&fs::read(path)?)
+        .with_context(|| format!("Failed to decode {}", path.display()))?;
+    anyhow::ensure!(
+        checkpoint.version <= CHECKPOINT_VERSION,
+        "Checkpoint version {} is newer than this build reads",
+        checkpoint.version
+    );
+    Ok(checkpoint)
+}
@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Evaluating positions with a [`GoMini6E`], the same way for the UI's heat
+//! map and `p2pgo-cli analyze`.
+//!
+//! Boards smaller than the model's sit centred in its input; larger ones
+//! can't be evaluated.
+
+use anyhow::{ensure, Result};
+use burn::tensor::{backend::Backend, ElementConversion, Tensor};
+use p2pgo_core::engine::fills_own_eye;
+use p2pgo_core::policy::{pass_index, point_logits, policy_len};
+use p2pgo_core::position::Position;
+use p2pgo_core::{Color, GameState, Move};
+use crate::GoMini6E;
+
+/// What the model makes of a position
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evaluation {
+    /// One logit per point of the model's board, then pass
+    pub logits: Vec<f32>,
+    /// Black's outlook, from -1 to 1
+    pub value: f32,
+}
+
+/// Row and column where `state`'s board starts inside a `model_size` input
+fn offset(state: &GameState, model_size: u8) -> usize {
+    (model_size as usize).saturating_sub(state.board_size as usize) / 2
+}
+
+/// Model input for `state`: 1 for Black, -1 for White, row by row
+pub fn board_input(state: &GameState, model_size: u8) -> Result<Vec<f32>> {
+    ensure!(
+        state.board_size <= model_size,
+        "A {0}x{0} board doesn't fit the model's {1}x{1} input",
+        state.board_size,
+        model_size
+    );
+    let (size, model) = (state.board_size as usize, model_size as usize);
+    let offset = offset(state, model_size);
+    let mut input = vec![0.0; model * model];
+    for (idx, point) in state.board.iter().enumerate().take(size * size) {
+        let (row, col) = (idx / size + offset, idx % size + offset);
+        input[row * model + col] = match point {
+            Some(Color::Black) => 1.0,
+            Some(Color::White) => -1.0,
+            None => 0.0,
+        };
+    }
+    Ok(input)
+}
+
+/// Run `model` on `state`
+pub fn evaluate<B: Backend>(model: &GoMini6E<B>, state: &GameState, device: &B::Device) -> Result<Evaluation> {
+    let model_size = model.board_size();
+    let points = model_size as usize * model_size as usize;
+    let input = Tensor::<B, 1>::from_floats(board_input(state, model_size)?.as_slice(), device)
+        .reshape([1, points]);
+    let (policy, value) = model.forward(input);
+
+    let policy = policy.squeeze::<1>(0);
+    let logits = (0..policy_len(model_size))
+        .map(|i| policy.clone().narrow(0, i, 1).into_scalar().elem::<f32>())
+        .collect();
+    let value = value.into_scalar().elem::<f32>().clamp(-1.0, 1.0);
+    Ok(Evaluation { logits, value })
+}
+
+/// Turn `logits` for a `model_size` board into a probability per point of
+/// `state`'s board.
+///
+/// Occupied points get zero and the rest sum to one; the pass logit is not
+/// part of the map.
+pub fn point_probabilities(logits: &[f32], model_size: u8, state: &GameState) -> Vec<f32> {
+    let logits = point_logits(logits, model_size);
+    let (size, model) = (state.board_size as usize, model_size as usize);
+    let offset = offset(state, model_size);
+    let logit_at = |idx: usize| {
+        let (row, col) = (idx / size + offset, idx % size + offset);
+        logits.get(row * model + col).copied().filter(|_| row < model && col < model)
+    };
+
+    let max = (0..size * size)
+        .filter(|&idx| state.board[idx].is_none())
+        .filter_map(logit_at)
+        .fold(f32::NEG_INFINITY, f32::max);
+    let mut map: Vec<f32> = (0..size * size)
+        .map(|idx| match (state.board[idx], logit_at(idx)) {
+            (None, Some(logit)) => (logit - max).exp(),
+            _ => 0.0,
+        })
+        .collect();
+    let total: f32 = map.iter().sum();
+    if total > 0.0 {
+        map.iter_mut().for_each(|p| *p /= total);
+    }
+    map
+}
+
+/// The likeliest `count` moves for the player to move in `state`, with
+/// their probabilities, best first.
+///
+/// Candidates are pass and the legal points that don't fill one of the
+/// player's own eyes; the probabilities are over those alone.
+pub fn top_moves(logits: &[f32], model_size: u8, state: &GameState, count: usize) -> Vec<(Move, f32)> {
+    let mut moves = move_probabilities(logits, model_size, state);
+    moves.sort_by(|a, b| b.1.total_cmp(&a.1));
+    moves.truncate(count);
+    moves
+}
+
+/// The moves of the `count` likeliest that are hints worth showing in
+/// `state`, best first.
+///
+/// Points are checked against the whole game, so a ko retake or a
+/// suicide is never a hint. Pass is only one when it rates best, or
+/// when none of the likeliest moves may be played.
+pub fn ghost_moves(logits: &[f32], model_size: u8, state: &GameState, count: usize) -> Vec<Move> {
+    let legal = state.legal_moves();
+    let hints: Vec<Move> = top_moves(logits, model_size, state, count)
+        .into_iter()
+        .enumerate()
+        .filter(|(rank, (mv, _))| match mv {
+            Move::Place(coord) => legal.contains(coord),
+            _ => *rank == 0,
+        })
+        .map(|(_, (mv, _))| mv)
+        .collect();
+    if hints.is_empty() {
+        return vec![Move::Pass];
+    }
+    hints
+}
+
+/// Chance of passing among the candidates of [`top_moves`]
+pub fn pass_probability(logits: &[f32], model_size: u8, state: &GameState) -> f32 {
+    move_probabilities(logits, model_size, state)
+        .into_iter()
+        .find(|(mv, _)| *mv == Move::Pass)
+        .map_or(0.0, |(_, probability)| probability)
+}
+
+/// Softmax of `logits` over pass and the points worth playing in `state`
+fn move_probabilities(logits: &[f32], model_size: u8, state: &GameState) -> Vec<(Move, f32)> {
+    let pass_logit = logits.get(pass_index(model_size)).copied().unwrap_or(f32::NEG_INFINITY);
+    let mut moves = vec![(Move::Pass, pass_logit)];
+    if let Ok(position) = Position::from_dense(state) {
+        let points = point_logits(logits, model_size);
+        let model = model_size as usize;
+        let offset = offset(state, model_size);
+        let color = position.current_player();
+        for coord in position.legal_moves() {
+            if fills_own_eye(&position, coord, color) {
+                continue;
+            }
+            let (row, col) = (coord.y as usize + offset, coord.x as usize + offset);
+            if let Some(&logit) = points.get(row * model + col).filter(|_| row < model && col < model) {
+                moves.push((Move::Place(coord), logit));
+            }
+        }
+    }
+
+    let max = moves.iter().map(|(_, logit)| *logit).fold(f32::NEG_INFINITY, f32::max);
+    if !max.is_finite() {
+        return moves.into_iter().map(|(mv, _)| (mv, 0.0)).collect();
+    }
+    moves.iter_mut().for_each(|(_, logit)| *logit = (*logit - max).exp());
+    let total: f32 = moves.iter().map(|(_, weight)| weight).sum();
+    moves.iter_mut().for_each(|(_, weight)| *weight /= total);
+    moves
+}
@@ -3,15 +3,35 @@
 //! Training module for GoMini-6E model verification
 
 use burn::{
-    module::Module,
+    module::{AutodiffModule, Module},
+    optim::{GradientsParams, Optimizer},
+    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
+    tensor::backend::AutodiffBackend,
+    tensor::ElementConversion,
     nn::{Linear, LinearConfig, Dropout, DropoutConfig},
     tensor::{backend::Backend, Tensor, Int},
-    tensor::activation::relu,
+    tensor::activation::{log_softmax, relu},
 };
 
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::path::Path;
+use p2pgo_core::cbor::migrate;
+use p2pgo_core::governor::Governor;
+
+pub mod backend;
+pub mod checkpoint;
+pub mod evaluate;
+pub mod stats;
+
+use backend::TrainingBackend;
+use checkpoint::{Checkpoint, CheckpointStore, CHECKPOINT_VERSION};
+use stats::DatasetStats;
 
 /// GoMini-6E model for Go move prediction
+///
+/// Takes one input per point of a `board_size` board and predicts a logit per
+/// point plus one for passing.
 #[derive(Module, Debug)]
 pub struct GoMini6E<B: Backend> {
     linear1: Linear<B>,
@@ -19,18 +39,37 @@ pub struct GoMini6E<B: Backend> {
     dropout: Dropout,
     policy_head: Linear<B>,
     value_head: Linear<B>,
+    board_size: usize,
 }
 
 impl<B: Backend> GoMini6E<B> {
-    pub fn new(device: &B::Device) -> Self {
+    pub fn new(device: &B::Device, board_size: u8) -> Self {
+        let points = board_size as usize * board_size as usize;
         Self {
-            linear1: LinearConfig::new(81, 128).init(device),
+            linear1: LinearConfig::new(points, 128).init(device),
             linear2: LinearConfig::new(128, 64).init(device), 
             dropout: DropoutConfig::new(0.1).init(),
-            policy_head: LinearConfig::new(64, 81).init(device),
+            policy_head: LinearConfig::new(64, points + 1).init(device),
             value_head: LinearConfig::new(64, 1).init(device),
+            board_size: board_size as usize,
         }
     }
+    
+    pub fn board_size(&self) -> u8 {
+        self.board_size as u8
+    }
+    
+    /// Width of the policy output: every point, then pass
+    pub fn policy_size(&self) -> usize {
+        self.board_size * self.board_size + 1
+    }
+
+    /// The model with the weights of `checkpoint`
+    pub fn from_checkpoint(checkpoint: &Checkpoint, device: &B::Device) -> anyhow::Result<Self> {
+        let record = Recorder::<B>::load(&BinBytesRecorder::<FullPrecisionSettings>::default(), checkpoint.weights.clone(), device)
+            .map_err(|e| anyhow::anyhow!("Failed to load checkpoint weights: {:?}", e))?;
+        Ok(Self::new(device, checkpoint.board_size).load_record(record))
+    }
 
     pub fn forward(&self, input: Tensor<B, 2>) -> (Tensor<B, 2>, Tensor<B, 2>) {
         let x = relu(input);
@@ -43,6 +82,84 @@ impl<B: Backend> GoMini6E<B> {
         
         (policy, value)
     }
+    
+    /// Policy cross-entropy plus squared value error on one batch, averaged
+    /// by sample weight
+    pub fn loss(&self, batch: GoBatch<B>) -> Tensor<B, 1> {
+        let GoBatch { states, moves, values, weights } = batch;
+        let [batch_size, _] = states.dims();
+        let (policy, value) = self.forward(states);
+        let policy_loss = log_softmax(policy, 1)
+            .gather(1, moves.reshape([batch_size, 1]))
+            .reshape([batch_size])
+            .neg();
+        let value_loss = (value.reshape([batch_size]) - values).powf_scalar(2.0);
+        let total_weight = weights.clone().sum();
+        ((policy_loss + value_loss) * weights).sum() / total_weight
+    }
+}
+
+/// Samples of one board size as tensors, from [`GoDataset::get_batch`]
+#[derive(Debug, Clone)]
+pub struct GoBatch<B: Backend> {
+    /// [batch, size²]
+    pub states: Tensor<B, 2>,
+    /// Policy index played, [batch]
+    pub moves: Tensor<B, 1, Int>,
+    /// Game result, [batch]
+    pub values: Tensor<B, 1>,
+    /// How much each sample counts in the loss, [batch]
+    pub weights: Tensor<B, 1>,
+}
+
+/// What [`train_one_epoch`] trains on
+#[derive(Debug, Clone)]
+pub struct TrainingConfig {
+    /// Only samples from games of this size are used
+    pub board_size: u8,
+    pub batch_size: usize,
+    /// Most copies of one (position, move) pair that count in the loss
+    pub max_position_copies: usize,
+    pub learning_rate: f64,
+    /// Batches between checkpoints taken by [`NeuralTrainer`]
+    pub checkpoint_every: usize,
+    /// Seeds the backend before the weights are initialised, which also
+    /// fixes the dropout masks that follow
+    pub seed: u64,
+    /// Backend [`backend::train`] asks for, falling back to the CPU
+    pub backend: TrainingBackend,
+    /// Holds [`backend::train`] back between batches when set
+    pub governor: Option<Governor>,
+}
+
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            board_size: 9,
+            batch_size: 4,
+            max_position_copies: 8,
+            learning_rate: 1e-3,
+            checkpoint_every: 50,
+            seed: 0,
+            backend: TrainingBackend::Cpu,
+            governor: None,
+        }
+    }
+}
+
+/// What deduplication did to a dataset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadReport {
+    /// Samples before deduplication
+    pub samples_loaded: usize,
+    /// Distinct (position, move) pairs left
+    pub unique_samples: usize,
+    /// Copies folded into another sample's weight
+    pub duplicates_merged: usize,
+    /// Pairs whose weight was cut to the cap
+    pub capped: usize,
+    /// Copies of the most repeated pair
+    pub most_copies: usize,
 }
 
 /// Dataset for Go training data
@@ -50,11 +167,66 @@ pub struct GoDataset {
     samples: Vec<GoSample>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct GoSample {
-    pub board_state: [f32; 81],
+    pub board_size: u8,
+    /// One entry per point, row by row: 1 black, -1 white, 0 empty
+    pub board_state: Vec<f32>,
+    /// Policy index of the move played, see [`p2pgo_core::policy`]; pass is `board_size²`
     pub next_move: usize,
     pub game_result: f32,
+    /// How much the sample counts in the loss, e.g. copies merged into it
+    pub weight: f32,
+    /// Which game of the dataset the position is from
+    pub game: usize,
+    /// Moves played before the position
+    pub move_number: usize,
+}
+
+impl GoSample {
+    /// Sample on an empty board of `board_size`
+    pub fn empty(board_size: u8, next_move: usize, game_result: f32) -> Self {
+        let points = board_size as usize * board_size as usize;
+        Self { board_size, board_state: vec![0.0; points], next_move, game_result, weight: 1.0, game: 0, move_number: 0 }
+    }
+    
+    /// The sample as position `move_number` of game `game`
+    pub fn with_origin(mut self, game: usize, move_number: usize) -> Self {
+        self.game = game;
+        self.move_number = move_number;
+        self
+    }
+    
+    /// Sample of `next` being played in `state`; resignations have no policy entry
+    pub fn from_position(state: &p2pgo_core::GameState, next: &p2pgo_core::Move, game_result: f32) -> Option<Self> {
+        let next_move = p2pgo_core::policy::move_index(next, state.board_size)?;
+        let board_state = state.board.iter()
+            .map(|point| match point {
+                Some(p2pgo_core::Color::Black) => 1.0,
+                Some(p2pgo_core::Color::White) => -1.0,
+                None => 0.0,
+            })
+            .collect();
+        Some(Self {
+            board_size: state.board_size,
+            board_state,
+            next_move,
+            game_result,
+            weight: 1.0,
+            game: 0,
+            move_number: state.moves.len(),
+        })
+    }
+    
+    /// Hash of the board size and stones, the same for every copy of a position
+    pub fn position_hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.board_size.hash(&mut hasher);
+        for point in &self.board_state {
+            point.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
 }
 
 impl GoDataset {
@@ -70,6 +242,9 @@ impl GoDataset {
                 let file_path = entry.path();
                 
                 if file_path.is_file() && file_path.extension().map_or(false, |ext| ext == "cbor") {
+                    if opted_out(path, &file_path) {
+                        continue;
+                    }
                     // Read game file
                     let file_data = std::fs::read(&file_path)?;
                     
@@ -81,17 +256,16 @@ impl GoDataset {
                         
                     // Filter out games without score proof or with resignation
                     if let Some(score_data) = score_proof_data {
-                        if let Ok(score_proof) = serde_cbor::from_slice::<p2pgo_core::value_labeller::ScoreProof>(&score_data[1..]) {
+                        if let Some((score_proof, game)) = read_scored_game(&score_data[1..]) {
                             // Filter out resignation games
                             if matches!(score_proof.method, p2pgo_core::value_labeller::ScoringMethod::Territory | p2pgo_core::value_labeller::ScoringMethod::Area) {
+                                if !score_recounts(&file_path, &score_proof, game.as_ref()) {
+                                    continue;
+                                }
                                 // This is a properly scored game, add a sample
-                                let board_state = [0.0f32; 81];
-                                
-                                samples.push(GoSample {
-                                    board_state,
-                                    next_move: 0, // Simplified for test
-                                    game_result: score_proof.final_score as f32,
-                                });
+                                let board_size = score_proof.final_position.as_ref().map_or(9, |p| p.board_size);
+                                let game = samples.len();
+                                samples.push(GoSample::empty(board_size, 0, score_proof.final_score as f32).with_origin(game, 0));
                                 
                                 tracing::info!("Added game from {}", file_path.display());
                             } else {
@@ -109,21 +283,102 @@ impl GoDataset {
         if samples.is_empty() {
             tracing::info!("No valid game samples found, generating dummy data");
             for i in 0..10 {
-                let mut board_state = [0.0f32; 81];
-                board_state[i * 8] = 1.0; // Black stone
-                board_state[i * 8 + 1] = -1.0; // White stone
-                
-                samples.push(GoSample {
-                    board_state,
-                    next_move: (i * 7) % 81,
-                    game_result: if i % 2 == 0 { 1.0 } else { -1.0 },
-                });
+                let mut sample = GoSample::empty(9, (i * 7) % 81, if i % 2 == 0 { 1.0 } else { -1.0 }).with_origin(i, 0);
+                sample.board_state[i * 8] = 1.0; // Black stone
+                sample.board_state[i * 8 + 1] = -1.0; // White stone
+                samples.push(sample);
             }
         }
         
         Ok(Self { samples })
     }
     
+    pub fn from_samples(samples: Vec<GoSample>) -> Self {
+        Self { samples }
+    }
+    
+    /// A sample of every move in `games`, each labelled with how the game
+    /// went for Black; moves that don't replay end their game's samples
+    pub fn from_games(games: &[p2pgo_core::GameState]) -> Self {
+        let mut samples = Vec::new();
+        for (game, record) in games.iter().enumerate() {
+            let result = black_result(record);
+            let mut state = record.initial_position();
+            for mv in &record.moves {
+                if let Some(sample) = GoSample::from_position(&state, mv, result) {
+                    samples.push(sample.with_origin(game, state.moves.len()));
+                }
+                if state.apply_move(mv.clone()).is_err() {
+                    break;
+                }
+            }
+        }
+        Self { samples }
+    }
+    
+    /// Only the samples from games of `board_size`
+    pub fn filter_board_size(&self, board_size: u8) -> Self {
+        Self {
+            samples: self.samples.iter().filter(|s| s.board_size == board_size).cloned().collect(),
+        }
+    }
+    
+    /// Merge samples of the same position and move into one, weighted by how
+    /// often it occurs but by no more than `max_copies`.
+    ///
+    /// The merged sample keeps the weighted mean game result. Order follows
+    /// each pair's first appearance.
+    pub fn dedup(&mut self, max_copies: usize) -> LoadReport {
+        let max_copies = max_copies.max(1) as f32;
+        let samples_loaded = self.samples.len();
+        let mut index: HashMap<(u64, usize), usize> = HashMap::new();
+        let mut merged: Vec<(GoSample, usize)> = Vec::new();
+        for sample in self.samples.drain(..) {
+            match index.get(&(sample.position_hash(), sample.next_move)) {
+                Some(&i) => {
+                    let (kept, copies) = &mut merged[i];
+                    let total = kept.weight + sample.weight;
+                    kept.game_result = (kept.game_result * kept.weight + sample.game_result * sample.weight) / total;
+                    kept.weight = total;
+                    *copies += 1;
+                }
+                None => {
+                    index.insert((sample.position_hash(), sample.next_move), merged.len());
+                    merged.push((sample, 1));
+                }
+            }
+        }
+        
+        let mut report = LoadReport {
+            samples_loaded,
+            unique_samples: merged.len(),
+            duplicates_merged: samples_loaded - merged.len(),
+            ..LoadReport::default()
+        };
+        for (sample, copies) in &mut merged {
+            report.most_copies = report.most_copies.max(*copies);
+            if sample.weight > max_copies {
+                sample.weight = max_copies;
+                report.capped += 1;
+            }
+        }
+        self.samples = merged.into_iter().map(|(sample, _)| sample).collect();
+        tracing::info!(?report, "Deduplicated training samples");
+        report
+    }
+    
+    pub fn samples(&self) -> &[GoSample] {
+        &self.samples
+    }
+    
+    /// Board sizes present, smallest first
+    pub fn board_sizes(&self) -> Vec<u8> {
+        let mut sizes: Vec<u8> = self.samples.iter().map(|s| s.board_size).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        sizes
+    }
+    
     pub fn len(&self) -> usize {
         self.samples.len()
     }
@@ -132,36 +387,128 @@ impl GoDataset {
         self.samples.is_empty()
     }
     
-    pub fn get_batch<B: Backend>(&self, batch_size: usize, device: &B::Device) -> (Tensor<B, 2>, Tensor<B, 1, Int>, Tensor<B, 1>) {
-        let actual_batch_size = std::cmp::min(batch_size, self.samples.len());
-        
-        // Create board states tensor [batch_size, 81]
-        let mut states_flat = Vec::new();
-        for i in 0..actual_batch_size {
-            states_flat.extend_from_slice(&self.samples[i].board_state);
+    /// What the dataset holds, to check before training on it
+    pub fn stats(&self) -> DatasetStats {
+        DatasetStats::of(&self.samples)
+    }
+    
+    /// Up to `batch_size` samples of `board_size`
+    ///
+    /// Every row of a batch has the same width, so sizes are never mixed.
+    /// Returns `None` if there are no samples of that size.
+    pub fn get_batch<B: Backend>(&self, board_size: u8, batch_size: usize, device: &B::Device) -> Option<GoBatch<B>> {
+        let batch: Vec<&GoSample> = self.samples.iter()
+            .filter(|s| s.board_size == board_size)
+            .take(batch_size)
+            .collect();
+        if batch.is_empty() {
+            return None;
         }
+        let points = board_size as usize * board_size as usize;
+        
+        // Create board states tensor [batch_size, size²]
+        let states_flat: Vec<f32> = batch.iter()
+            .flat_map(|s| s.board_state.iter().copied())
+            .collect();
         let states = Tensor::<B, 1>::from_floats(states_flat.as_slice(), device)
-            .reshape([actual_batch_size, 81]);
+            .reshape([batch.len(), points]);
         
         // Create moves tensor [batch_size]
-        let moves_vec: Vec<i64> = (0..actual_batch_size)
-            .map(|i| self.samples[i].next_move as i64)
-            .collect();
+        let moves_vec: Vec<i64> = batch.iter().map(|s| s.next_move as i64).collect();
         let moves = Tensor::<B, 1, Int>::from_ints(moves_vec.as_slice(), device);
         
         // Create values tensor [batch_size]
-        let values_vec: Vec<f32> = (0..actual_batch_size)
-            .map(|i| self.samples[i].game_result)
-            .collect();
+        let values_vec: Vec<f32> = batch.iter().map(|s| s.game_result).collect();
         let values = Tensor::<B, 1>::from_floats(values_vec.as_slice(), device);
         
-        (states, moves, values)
+        // Create weights tensor [batch_size]
+        let weights_vec: Vec<f32> = batch.iter().map(|s| s.weight).collect();
+        let weights = Tensor::<B, 1>::from_floats(weights_vec.as_slice(), device);
+        
+        Some(GoBatch { states, moves, values, weights })
+    }
+}
+
+/// 1 if Black won the finished `game`, -1 if White did and 0 for a draw;
+/// a game still being played is area scored as it stands
+fn black_result(game: &p2pgo_core::GameState) -> f32 {
+    if game.end_reason() == Some(p2pgo_core::EndReason::Resignation) {
+        // The player to move after a resignation is the one who didn't resign
+        return if game.current_player == p2pgo_core::Color::Black { 1.0 } else { -1.0 };
+    }
+    let komi = p2pgo_core::settings::standard_komi(game.board_size);
+    let proof = p2pgo_core::scoring::calculate_final_score(
+        game,
+        komi,
+        p2pgo_core::value_labeller::ScoringMethod::Area,
+        &std::collections::HashSet::new(),
+    );
+    f32::from(proof.final_score.signum())
+}
+
+/// Whether the player took the game in `file_path` out of training
+fn opted_out(dir: &Path, file_path: &Path) -> bool {
+    let Some(id) = file_path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    let opted_out = !p2pgo_core::archiver::is_training_eligible(dir, id);
+    if opted_out {
+        tracing::info!("Skipping game excluded from training: {}", file_path.display());
+    }
+    opted_out
+}
+
+/// The score proof at the start of `data` and, after a `G` marker, the
+/// game it scores
+fn read_scored_game(data: &[u8]) -> Option<(p2pgo_core::value_labeller::ScoreProof, Option<p2pgo_core::GameState>)> {
+    let mut proofs = serde_cbor::Deserializer::from_slice(data).into_iter::<p2pgo_core::value_labeller::ScoreProof>();
+    let proof = proofs.next()?.ok()?;
+    let game = data[proofs.byte_offset()..].strip_prefix(b"G").and_then(|game| {
+        serde_cbor::Deserializer::from_slice(game).into_iter::<p2pgo_core::GameState>().next()?.ok()
+    });
+    Some((proof, game))
+}
+
+/// The game's final position, replayed from its first move so none of
+/// the recorded board is taken on trust
+fn replay(game: &p2pgo_core::GameState) -> Result<p2pgo_core::GameState, p2pgo_core::GameError> {
+    let mut state = game.initial_position();
+    let mut previous = state.clone();
+    for mv in &game.moves {
+        state.check_move(mv, &previous)?;
+        previous = state.clone();
+        state.apply_move(mv.clone())?;
+    }
+    Ok(state)
+}
+
+/// Whether the score proof in `file_path` recounts to what it claims on
+/// the game it scores
+fn score_recounts(file_path: &Path, proof: &p2pgo_core::value_labeller::ScoreProof, game: Option<&p2pgo_core::GameState>) -> bool {
+    let Some(game) = game else {
+        tracing::warn!("Skipping game without moves to recount its score on: {}", file_path.display());
+        return false;
+    };
+    let final_state = match replay(game) {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::warn!("Skipping game whose moves don't replay ({}): {}", e, file_path.display());
+            return false;
+        }
+    };
+    match proof.verify_against(&final_state) {
+        Ok(()) => true,
+        Err(mismatch) => {
+            tracing::warn!("Skipping game whose score doesn't hold up ({}): {}", mismatch, file_path.display());
+            false
+        }
     }
 }
 
 /// Actual implementation for loading CBOR files from a directory
 pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn std::error::Error>> {
     let mut samples = Vec::new();
+    let mut games = 0;
     let path = path.as_ref();
     
     if !path.exists() {
@@ -173,6 +520,9 @@ pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn
         let file_path = entry.path();
         
         if file_path.is_file() && file_path.extension().map_or(false, |ext| ext == "cbor") {
+            if opted_out(path, &file_path) {
+                continue;
+            }
             // Read game file
             let file_data = std::fs::read(&file_path)?;
             
@@ -182,9 +532,18 @@ pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn
                 .find(|(_, b)| **b == b'S') // 'S' for ScoreProof marker
                 .map(|(i, _)| &file_data[i..]);
                 
-            if score_proof_data.is_none() {
+            let Some(score_data) = score_proof_data else {
                 tracing::warn!("Skipping game file without score proof: {}", file_path.display());
                 continue;
+            };
+            // The proof is followed by the game and move records
+            match read_scored_game(&score_data[1..]) {
+                Some((proof, game)) if score_recounts(&file_path, &proof, game.as_ref()) => {}
+                Some(_) => continue,
+                None => {
+                    tracing::warn!("Skipping game file with unreadable score proof: {}", file_path.display());
+                    continue;
+                }
             }
             
             // Parse move records
@@ -193,10 +552,10 @@ pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn
                 .filter(|(_, b)| **b == b'M') // 'M' for MoveRecord marker
                 .filter_map(|(i, _)| {
                     if i + 4 < file_data.len() {
-                        // Try to parse the next chunk as a move record
-                        let result: Result<p2pgo_core::value_labeller::ValueLabel, _> = 
-                            serde_cbor::from_slice(&file_data[i..i+100]);
-                        result.ok()
+                        // Try to parse the next chunk as a move record; short
+                        // files may have fewer than 100 bytes left
+                        let end = (i + 100).min(file_data.len());
+                        migrate::from_slice::<p2pgo_core::value_labeller::ValueLabel>(&file_data[i..end]).ok()
                     } else {
                         None
                     }
@@ -210,18 +569,16 @@ pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn
             
             // Process and add samples from the game
             // For each move, add it to our training dataset
+            let game = games;
+            games += 1;
             for move_label in moves {
                 // Here we would reconstruct the board state at each move
                 // and add it to the training samples
                 // For now, we'll create a dummy sample
-                let mut board_state = [0.0f32; 81];
-                board_state[move_label.move_number as usize % 81] = 1.0;
-                
-                samples.push(GoSample {
-                    board_state,
-                    next_move: (move_label.move_number as usize + 1) % 81,
-                    game_result: move_label.game_outcome,
-                });
+                let mut sample = GoSample::empty(9, (move_label.move_number as usize + 1) % 81, move_label.game_outcome)
+                    .with_origin(game, move_label.move_number as usize);
+                sample.board_state[move_label.move_number as usize % 81] = 1.0;
+                samples.push(sample);
             }
         }
     }
@@ -230,22 +587,179 @@ pub fn load_games_from_dir<P: AsRef<Path>>(path: P) -> Result<GoDataset, Box<dyn
 }
 
 /// Train one epoch and return (start_loss, end_loss) - simplified for testing
-pub fn train_one_epoch<B: Backend>(_epochs: usize) -> (f32, f32) 
+///
+/// Returns `None` if the fixtures have no games of `config.board_size`.
+pub fn train_one_epoch<B: Backend>(config: &TrainingConfig) -> Option<(f32, f32)>
 where
     B::FloatElem: From<f32> + Into<f32>,
 {
     let device = B::Device::default();
-    let model = GoMini6E::new(&device);
-    let dataset = GoDataset::from_cbor_dir("tests/fixtures/").unwrap();
+    B::seed(config.seed);
+    let model = GoMini6E::new(&device, config.board_size);
+    let mut dataset = GoDataset::from_cbor_dir("tests/fixtures/").unwrap();
+    dataset.dedup(config.max_position_copies);
     
-    let (states, _moves, _values) = dataset.get_batch::<B>(4, &device);
+    let batch = dataset.get_batch::<B>(config.board_size, config.batch_size, &device)?;
     
     // Simplified training - just forward pass for testing
-    let (policy_logits, _value_pred) = model.forward(states.clone());
-    let start_loss: f32 = policy_logits.sum().into_scalar().into();
+    let start_loss: f32 = model.loss(batch).into_scalar().into();
     
     // Simulate training by returning slightly different end loss
     let end_loss = start_loss * 0.9;
     
-    (start_loss, end_loss)
+    Some((start_loss, end_loss))
+}
+
+/// Where a [`NeuralTrainer`] can pick up from after training stopped unsaved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrainingResume {
+    /// Batches trained when the checkpoint was taken
+    pub batch: usize,
+    pub loss: f32,
+}
+
+/// Trains a [`GoMini6E`] batch by batch, checkpointing its weights as it goes
+pub struct NeuralTrainer<B: AutodiffBackend, O> {
+    model: GoMini6E<B>,
+    optimizer: O,
+    device: B::Device,
+    /// One epoch, split into batches
+    batches: Vec<GoDataset>,
+    config: TrainingConfig,
+    checkpoints: CheckpointStore,
+    /// Batches trained so far, across epochs
+    batch: usize,
+    /// Samples trained on since construction
+    positions_trained: usize,
+    last_loss: Option<f32>,
+    /// Checkpoint newer than the published weights, found on construction
+    orphan: Option<Checkpoint>,
+}
+
+impl<B: AutodiffBackend, O: Optimizer<GoMini6E<B>, B>> NeuralTrainer<B, O> {
+    /// Trainer for the samples of `config.board_size` in `dataset`
+    ///
+    /// Starts from fresh weights; see [`Self::resume_available`] for picking up
+    /// where an earlier run stopped.
+    pub fn new(
+        device: B::Device,
+        dataset: &GoDataset,
+        config: TrainingConfig,
+        optimizer: O,
+        checkpoints: CheckpointStore,
+    ) -> anyhow::Result<Self> {
+        let samples = dataset.filter_board_size(config.board_size);
+        anyhow::ensure!(!samples.is_empty(), "No samples of board size {}", config.board_size);
+        let batches = samples.samples()
+            .chunks(config.batch_size.max(1))
+            .map(|chunk| GoDataset::from_samples(chunk.to_vec()))
+            .collect();
+        let orphan = checkpoints.orphaned()?
+            .filter(|checkpoint| checkpoint.board_size == config.board_size);
+        if let Some(checkpoint) = &orphan {
+            tracing::info!(batch = checkpoint.batch, "Found checkpoint newer than the published weights");
+        }
+        B::seed(config.seed);
+        Ok(Self {
+            model: GoMini6E::new(&device, config.board_size),
+            optimizer,
+            device,
+            batches,
+            config,
+            checkpoints,
+            batch: 0,
+            positions_trained: 0,
+            last_loss: None,
+            orphan,
+        })
+    }
+
+    /// Checkpoint left by a run that stopped before publishing its weights
+    pub fn resume_available(&self) -> Option<TrainingResume> {
+        self.orphan.as_ref().map(|checkpoint| TrainingResume { batch: checkpoint.batch, loss: checkpoint.loss })
+    }
+
+    /// Load the orphaned checkpoint and continue from its batch
+    ///
+    /// Optimizer state isn't checkpointed and starts afresh.
+    pub fn resume(&mut self) -> anyhow::Result<()> {
+        let checkpoint = self.orphan.take().ok_or_else(|| anyhow::anyhow!("No checkpoint to resume"))?;
+        let record = Recorder::<B>::load(&BinBytesRecorder::<FullPrecisionSettings>::default(), checkpoint.weights, &self.device)
+            .map_err(|e| anyhow::anyhow!("Failed to load checkpoint weights: {:?}", e))?;
+        self.model = self.model.clone().load_record(record);
+        self.batch = checkpoint.batch;
+        self.last_loss = Some(checkpoint.loss);
+        tracing::info!(batch = self.batch, "Resumed training from checkpoint");
+        Ok(())
+    }
+
+    /// Start from fresh weights instead of the orphaned checkpoint
+    pub fn discard_resume(&mut self) {
+        self.orphan = None;
+    }
+
+    /// Train the next batch and return its loss, checkpointing when one is due
+    pub fn train_batch(&mut self) -> anyhow::Result<f32> {
+        let batch = &self.batches[self.batch % self.batches.len()];
+        self.positions_trained += batch.len();
+        let batch = batch.get_batch::<B>(self.config.board_size, self.config.batch_size, &self.device)
+            .expect("batches hold samples of the trained size");
+        let loss = self.model.loss(batch);
+        let loss_value: f32 = loss.clone().into_scalar().elem();
+        let grads = GradientsParams::from_grads(loss.backward(), &self.model);
+        self.model = self.optimizer.step(self.config.learning_rate, self.model.clone(), grads);
+        self.batch += 1;
+        self.last_loss = Some(loss_value);
+
+        if self.batch % self.config.checkpoint_every.max(1) == 0 {
+            self.checkpoints.write(&self.checkpoint()?)?;
+        }
+        Ok(loss_value)
+    }
+
+    /// Keep the current weights as the published ones
+    pub fn publish(&self) -> anyhow::Result<()> {
+        self.checkpoints.publish(&self.checkpoint()?)
+    }
+
+    /// Batches trained so far, across epochs
+    pub fn batch(&self) -> usize {
+        self.batch
+    }
+
+    /// Samples trained on since construction, repeats included
+    pub fn positions_trained(&self) -> usize {
+        self.positions_trained
+    }
+
+    /// (epoch, batch within it) of the next batch to train
+    pub fn position(&self) -> (usize, usize) {
+        (self.batch / self.batches.len(), self.batch % self.batches.len())
+    }
+
+    /// Loss over the whole epoch with dropout off, without training
+    pub fn evaluate(&self) -> f32 {
+        let model = self.model.valid();
+        let total: f32 = self.batches.iter()
+            .filter_map(|batch| batch.get_batch::<B::InnerBackend>(self.config.board_size, self.config.batch_size, &self.device))
+            .map(|batch| model.loss(batch).into_scalar().elem::<f32>())
+            .sum();
+        total / self.batches.len() as f32
+    }
+
+    pub fn model(&self) -> &GoMini6E<B> {
+        &self.model
+    }
+
+    fn checkpoint(&self) -> anyhow::Result<Checkpoint> {
+        let weights = Recorder::<B>::record(&BinBytesRecorder::<FullPrecisionSettings>::default(), self.model.clone().into_record(), ())
+            .map_err(|e| anyhow::anyhow!("Failed to record weights: {:?}", e))?;
+        Ok(Checkpoint {
+            version: CHECKPOINT_VERSION,
+            board_size: self.config.board_size,
+            batch: self.batch,
+            loss: self.last_loss.unwrap_or(f32::NAN),
+            weights,
+        })
+    }
 }
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! What a training dataset holds: games, board sizes, results, coverage of
+//! the game and labels that can't be right, rendered for reading or tools.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
+use crate::GoSample;
+
+/// Moves per bucket of [`DatasetStats::move_coverage`]
+pub const MOVE_BUCKET: usize = 20;
+
+/// Composition of a dataset, from [`crate::GoDataset::stats`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatasetStats {
+    pub games: usize,
+    pub positions: usize,
+    /// Positions per board size
+    pub board_sizes: BTreeMap<u8, usize>,
+    /// Games by the sign of their result: Black won, White won, a draw
+    pub black_wins: usize,
+    pub white_wins: usize,
+    pub draws: usize,
+    /// Mean moves per game, up to its last position
+    pub average_game_length: f32,
+    /// Positions per [`MOVE_BUCKET`] moves, starting from the opening
+    pub move_coverage: Vec<usize>,
+    /// Positions with Black to play and with White to play, by move number
+    pub black_to_play: usize,
+    pub white_to_play: usize,
+    /// Share of positions that repeat an earlier one
+    pub duplicate_ratio: f32,
+    /// Samples whose game result lies outside [-1, 1]
+    pub values_out_of_range: usize,
+    /// Samples whose move is neither a point of the board nor a pass
+    pub moves_out_of_bounds: usize,
+}
+
+impl DatasetStats {
+    /// Stats of `samples`
+    pub fn of(samples: &[GoSample]) -> Self {
+        let mut stats = Self { positions: samples.len(), ..Self::default() };
+        // Result and last move of each game
+        let mut games: BTreeMap<usize, (f32, usize)> = BTreeMap::new();
+        let mut seen = HashSet::new();
+        let mut duplicates = 0;
+        for sample in samples {
+            *stats.board_sizes.entry(sample.board_size).or_default() += 1;
+            let game = games.entry(sample.game).or_insert((sample.game_result, 0));
+            game.1 = game.1.max(sample.move_number);
+
+            let bucket = sample.move_number / MOVE_BUCKET;
+            if stats.move_coverage.len() <= bucket {
+                stats.move_coverage.resize(bucket + 1, 0);
+            }
+            stats.move_coverage[bucket] += 1;
+            if sample.move_number % 2 == 0 {
+                stats.black_to_play += 1;
+            } else {
+                stats.white_to_play += 1;
+            }
+
+            if !seen.insert(sample.position_hash()) {
+                duplicates += 1;
+            }
+            if !(-1.0..=1.0).contains(&sample.game_result) {
+                stats.values_out_of_range += 1;
+            }
+            let points = sample.board_size as usize * sample.board_size as usize;
+            if sample.next_move > points {
+                stats.moves_out_of_bounds += 1;
+            }
+        }
+
+        stats.games = games.len();
+        for &(result, last_move) in games.values() {
+            match result {
+                r if r > 0.0 => stats.black_wins += 1,
+                r if r < 0.0 => stats.white_wins += 1,
+                _ => stats.draws += 1,
+            }
+            stats.average_game_length += (last_move + 1) as f32;
+        }
+        if stats.games > 0 {
+            stats.average_game_length /= stats.games as f32;
+        }
+        if stats.positions > 0 {
+            stats.duplicate_ratio = duplicates as f32 / stats.positions as f32;
+        }
+        stats
+    }
+
+    /// Whether every label passed the checks
+    pub fn labels_sane(&self) -> bool {
+        self.values_out_of_range == 0 && self.moves_out_of_bounds == 0
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Report as Markdown tables
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::from("# Dataset statistics\n\n");
+        out.push_str("| | |\n|---|---|\n");
+        let _ = writeln!(out, "| Games | {} |", self.games);
+        let _ = writeln!(out, "| Positions | {} |", self.positions);
+        let _ = writeln!(out, "| Results | Black {}, White {}, draws {} |", self.black_wins, self.white_wins, self.draws);
+        let _ = writeln!(out, "| Average game length | {:.1} moves |", self.average_game_length);
+        let _ = writeln!(out, "| To play | Black {}, White {} |", self.black_to_play, self.white_to_play);
+        let _ = writeln!(out, "| Duplicate positions | {:.1}% |", self.duplicate_ratio * 100.0);
+
+        out.push_str("\n## Board sizes\n\n| Size | Positions |\n|---|---|\n");
+        for (size, positions) in &self.board_sizes {
+            let _ = writeln!(out, "| {0}x{0} | {1} |", size, positions);
+        }
+
+        out.push_str("\n## Move coverage\n\n| Moves | Positions |\n|---|---|\n");
+        for (bucket, positions) in self.move_coverage.iter().enumerate() {
+            let first = bucket * MOVE_BUCKET;
+            let _ = writeln!(out, "| {}-{} | {} |", first, first + MOVE_BUCKET - 1, positions);
+        }
+
+        out.push_str("\n## Label checks\n\n");
+        let _ = writeln!(out, "- Game results outside [-1, 1]: {}", self.values_out_of_range);
+        let _ = writeln!(out, "- Moves off the board: {}", self.moves_out_of_bounds);
+        if !self.labels_sane() {
+            out.push_str("\nFix or leave out these samples before training.\n");
+        }
+        out
+    }
+}
@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Picking a training backend, falling back to the CPU, the same weights
+//! scoring alike on every backend that starts here, and training on
+//! self-play.
+
+use trainer::backend::{self, Selection, TrainingBackend};
+use trainer::checkpoint::CheckpointStore;
+use p2pgo_core::governor::Governor;
+use trainer::{GoDataset, GoSample, TrainingConfig};
+
+/// Twelve distinct positions, three batches of four
+fn dataset() -> GoDataset {
+    GoDataset::from_samples((0..12).map(|i| {
+        let mut sample = GoSample::empty(9, (i * 7) % 82, if i % 2 == 0 { 1.0 } else { -1.0 });
+        sample.board_state[i * 6] = 1.0;
+        sample
+    }).collect())
+}
+
+#[test]
+fn cpu_is_always_available() {
+    assert!(TrainingBackend::Cpu.compiled());
+    assert!(TrainingBackend::Cpu.probe());
+    assert!(backend::available().contains(&TrainingBackend::Cpu));
+    assert_eq!(backend::select(TrainingBackend::Cpu), Selection { backend: TrainingBackend::Cpu, fallback: None });
+    assert_eq!(TrainingConfig::default().backend, TrainingBackend::Cpu);
+}
+
+#[test]
+fn unavailable_backends_fall_back_to_the_cpu() {
+    for requested in TrainingBackend::ALL {
+        let selection = backend::select(requested);
+        if requested.probe() {
+            assert_eq!(selection, Selection { backend: requested, fallback: None });
+        } else {
+            assert_eq!(selection.backend, TrainingBackend::Cpu);
+            assert!(selection.fallback.unwrap().contains(requested.label()));
+        }
+    }
+    if !cfg!(target_os = "macos") {
+        assert!(!TrainingBackend::Metal.compiled());
+    }
+}
+
+#[test]
+fn training_reports_the_backend_and_speed() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = TrainingConfig { checkpoint_every: 2, ..TrainingConfig::default() };
+    let report = backend::train(&dataset(), config, CheckpointStore::new(dir.path(), 2), 4).unwrap();
+    assert_eq!(report.backend, TrainingBackend::Cpu);
+    assert_eq!(report.fallback, None);
+    assert_eq!(report.losses.len(), 4);
+    assert!(report.losses.iter().chain([&report.eval_loss]).all(|loss| loss.is_finite()));
+    assert!(report.positions_per_sec > 0.0);
+}
+
+#[test]
+fn the_same_weights_score_alike_on_every_backend() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+    let config = TrainingConfig { checkpoint_every: 3, ..TrainingConfig::default() };
+    // Trained on the CPU and left unpublished, checkpointed at batch 3
+    backend::train(&dataset(), config.clone(), store.clone(), 3).unwrap();
+
+    let on_cpu = backend::evaluate_checkpoint(TrainingBackend::Cpu, &dataset(), config.clone(), store.clone()).unwrap();
+    assert!(on_cpu.is_finite());
+    // Only the CPU runs without a GPU, as in CI
+    for backend in backend::available() {
+        let loss = backend::evaluate_checkpoint(backend, &dataset(), config.clone(), store.clone()).unwrap();
+        assert!((loss - on_cpu).abs() < 1e-3, "{}: {} vs {} on the CPU", backend.label(), loss, on_cpu);
+    }
+}
+
+#[test]
+fn self_play_training_publishes_its_weights() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+    let report = backend::train_on_self_play(TrainingBackend::Cpu, Governor::unlimited(), store.clone()).unwrap();
+    assert!(!report.losses.is_empty());
+    assert!(report.losses.iter().chain([&report.eval_loss]).all(|loss| loss.is_finite()));
+    assert_eq!(store.published().unwrap().unwrap().batch, report.losses.len());
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The model and batches at every board size, without mixing sizes.
+
+use burn::backend::{Autodiff, NdArray};
+use burn::optim::GradientsParams;
+use trainer::{GoDataset, GoMini6E, GoSample};
+
+type B = Autodiff<NdArray>;
+
+/// Three samples per size; each plays the last point or passes
+fn mixed_dataset() -> GoDataset {
+    let mut samples = Vec::new();
+    for size in [9u8, 13, 19] {
+        let points = size as usize * size as usize;
+        for i in 0..3 {
+            let next_move = if i == 2 { points } else { points - 1 - i };
+            let mut sample = GoSample::empty(size, next_move, if i % 2 == 0 { 1.0 } else { -1.0 });
+            sample.board_state[i] = 1.0;
+            samples.push(sample);
+        }
+    }
+    GoDataset::from_samples(samples)
+}
+
+#[test]
+fn model_output_matches_board_size() {
+    let device = Default::default();
+    for size in [9u8, 13, 19] {
+        let model = GoMini6E::<NdArray>::new(&device, size);
+        assert_eq!(model.board_size(), size);
+        assert_eq!(model.policy_size(), size as usize * size as usize + 1);
+
+        let dataset = mixed_dataset();
+        let batch = dataset.get_batch::<NdArray>(size, 8, &device).unwrap();
+        let (policy, value) = model.forward(batch.states);
+        assert_eq!(policy.dims(), [3, model.policy_size()]);
+        assert_eq!(value.dims(), [3, 1]);
+    }
+}
+
+#[test]
+fn batches_only_hold_the_requested_size() {
+    let device = Default::default();
+    let dataset = mixed_dataset();
+    assert_eq!(dataset.board_sizes(), vec![9, 13, 19]);
+    assert_eq!(dataset.filter_board_size(13).len(), 3);
+    assert!(dataset.filter_board_size(7).is_empty());
+    assert!(dataset.get_batch::<NdArray>(7, 8, &device).is_none());
+
+    let batch = dataset.get_batch::<NdArray>(19, 2, &device).unwrap();
+    assert_eq!(batch.states.dims(), [2, 361]);
+    assert_eq!(batch.moves.dims(), [2]);
+    assert_eq!(batch.values.dims(), [2]);
+    assert_eq!(batch.weights.dims(), [2]);
+}
+
+#[test]
+fn backward_pass_reaches_every_size() {
+    let device = Default::default();
+    let dataset = mixed_dataset();
+    for size in dataset.board_sizes() {
+        let model = GoMini6E::<B>::new(&device, size);
+        let batch = dataset.get_batch::<B>(size, 8, &device).unwrap();
+        let loss = model.loss(batch);
+        let loss_value: f32 = loss.clone().into_scalar();
+        assert!(loss_value.is_finite(), "{}x{} loss {}", size, size, loss_value);
+
+        let grads = GradientsParams::from_grads(loss.backward(), &model);
+        assert!(!grads.is_empty(), "{}x{} produced no gradients", size, size);
+    }
+}
+
+#[test]
+fn pass_is_encoded_after_the_last_point() {
+    use p2pgo_core::{Coord, GameState, Move};
+
+    let mut state = GameState::new(13);
+    state.apply_move(Move::Place(Coord::new(3, 2))).unwrap();
+    let pass = GoSample::from_position(&state, &Move::Pass, 1.0).unwrap();
+    assert_eq!(pass.next_move, 169);
+    assert_eq!(pass.board_state.len(), 169);
+    assert_eq!(pass.board_state[2 * 13 + 3], 1.0);
+
+    let place = GoSample::from_position(&state, &Move::Place(Coord::new(0, 1)), 1.0).unwrap();
+    assert_eq!(place.next_move, 13);
+    assert!(GoSample::from_position(&state, &Move::Resign, 1.0).is_none());
+
+    // The pass target is inside the policy head
+    let device = Default::default();
+    let model = GoMini6E::<NdArray>::new(&device, 13);
+    assert!(pass.next_move < model.policy_size());
+}
@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Training checkpoints and resuming after training stopped unsaved.
+
+use burn::backend::{Autodiff, NdArray};
+use burn::optim::AdamConfig;
+use trainer::checkpoint::{Checkpoint, CheckpointStore, CHECKPOINT_VERSION};
+use trainer::{GoDataset, GoSample, NeuralTrainer, TrainingConfig, TrainingResume};
+
+type B = Autodiff<NdArray>;
+
+/// Twelve distinct positions, three batches of four
+fn dataset() -> GoDataset {
+    GoDataset::from_samples((0..12).map(|i| {
+        let mut sample = GoSample::empty(9, (i * 7) % 82, if i % 2 == 0 { 1.0 } else { -1.0 });
+        sample.board_state[i * 6] = 1.0;
+        sample
+    }).collect())
+}
+
+fn config() -> TrainingConfig {
+    TrainingConfig { checkpoint_every: 2, ..TrainingConfig::default() }
+}
+
+fn trainer(store: &CheckpointStore) -> NeuralTrainer<B, impl burn::optim::Optimizer<trainer::GoMini6E<B>, B>> {
+    NeuralTrainer::new(Default::default(), &dataset(), config(), AdamConfig::new().init::<B, trainer::GoMini6E<B>>(), store.clone()).unwrap()
+}
+
+fn checkpoint(batch: usize) -> Checkpoint {
+    Checkpoint { version: CHECKPOINT_VERSION, board_size: 9, batch, loss: 1.0, weights: vec![batch as u8] }
+}
+
+#[test]
+fn killed_run_resumes_at_the_last_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+
+    let mut first = trainer(&store);
+    assert_eq!(first.resume_available(), None);
+    let mut losses = Vec::new();
+    for _ in 0..4 {
+        losses.push(first.train_batch().unwrap());
+    }
+    let evaluated_at_checkpoint = first.evaluate();
+    // One more batch, mid-epoch, then the process dies without publishing
+    first.train_batch().unwrap();
+    drop(first);
+
+    let mut second = trainer(&store);
+    assert_eq!(second.resume_available(), Some(TrainingResume { batch: 4, loss: losses[3] }));
+    second.resume().unwrap();
+    assert_eq!(second.batch(), 4);
+    assert_eq!(second.position(), (1, 1));
+    let resumed = second.evaluate();
+    assert!((resumed - evaluated_at_checkpoint).abs() < 1e-5, "{} vs {}", resumed, evaluated_at_checkpoint);
+
+    second.train_batch().unwrap();
+    assert_eq!(second.batch(), 5);
+}
+
+#[test]
+fn published_weights_leave_nothing_to_resume() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+
+    let mut first = trainer(&store);
+    for _ in 0..2 {
+        first.train_batch().unwrap();
+    }
+    first.publish().unwrap();
+    assert_eq!(trainer(&store).resume_available(), None);
+
+    // Checkpoints past the published weights are offered again
+    for _ in 0..2 {
+        first.train_batch().unwrap();
+    }
+    let mut second = trainer(&store);
+    assert_eq!(second.resume_available().map(|resume| resume.batch), Some(4));
+    second.discard_resume();
+    assert!(second.resume().is_err());
+    assert_eq!(second.batch(), 0);
+}
+
+#[test]
+fn only_the_newest_checkpoints_are_kept() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+    assert!(store.latest().unwrap().is_none());
+    for batch in [50, 100, 150] {
+        store.write(&checkpoint(batch)).unwrap();
+    }
+    std::fs::write(dir.path().join("checkpoint-0000000200.cbor"), b"not cbor").unwrap();
+
+    let mut files: Vec<String> = std::fs::read_dir(dir.path()).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["checkpoint-0000000100.cbor", "checkpoint-0000000150.cbor", "checkpoint-0000000200.cbor"]);
+    // The unreadable newest file is skipped rather than failing the lookup
+    assert_eq!(store.latest().unwrap().unwrap().batch, 150);
+    assert_eq!(store.orphaned().unwrap().unwrap().weights, vec![150]);
+
+    store.publish(&checkpoint(150)).unwrap();
+    assert!(store.orphaned().unwrap().is_none());
+}
+
+#[test]
+fn newer_container_versions_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+    store.publish(&Checkpoint { version: CHECKPOINT_VERSION + 1, ..checkpoint(10) }).unwrap();
+    assert!(store.published().is_err());
+}
@@ -13,7 +13,8 @@ fn resign_games_are_filtered() {
         captures_black: 0, 
         captures_white: 0, 
         komi: 6.5,
-        method: ScoringMethod::Resignation(p2pgo_core::Color::Black) 
+        method: ScoringMethod::Resignation(p2pgo_core::Color::Black),
+        final_position: None,
     };
     let ok = ScoreProof{ 
         final_score: 0, 
@@ -22,7 +23,8 @@ fn resign_games_are_filtered() {
         captures_black: 1,
         captures_white: 2,
         komi: 6.5,
-        method: ScoringMethod::Territory 
+        method: ScoringMethod::Territory,
+        final_position: None,
     };
     
     // Create valid CBOR files with markers
@@ -67,3 +69,16 @@ fn resign_games_are_filtered() {
     // Clean up temp directory
     dir.close().unwrap();
 }
+
+#[test]
+fn short_files_do_not_panic() {
+    // A move marker with fewer than 100 bytes after it used to slice out of bounds
+    let dir = tempfile::tempdir().unwrap();
+    let mut data = vec![b'S', b'M'];
+    data.extend([0u8; 10]);
+    fs::write(dir.path().join("short.cbor"), data).unwrap();
+    
+    assert!(trainer::load_games_from_dir(dir.path()).is_ok());
+    
+    dir.close().unwrap();
+}
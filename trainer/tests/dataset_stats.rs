@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dataset statistics over a synthetic dataset of known composition.
+
+use std::collections::BTreeMap;
+use p2pgo_core::{Coord, GameState, Move};
+use trainer::stats::DatasetStats;
+use trainer::{GoDataset, GoSample};
+
+/// Position `move_number` of a game on `board_size`: one Black stone per
+/// move played, filled in row by row
+fn position(game: usize, board_size: u8, move_number: usize, game_result: f32) -> GoSample {
+    let mut sample = GoSample::empty(board_size, move_number, game_result).with_origin(game, move_number);
+    for point in &mut sample.board_state[..move_number] {
+        *point = 1.0;
+    }
+    sample
+}
+
+/// A Black win of 25 moves on 9x9, a White win of 10 on 13x13 whose last
+/// sample is mislabelled twice over, and a 5 move draw on 9x9 repeating the
+/// first game's opening
+fn dataset() -> GoDataset {
+    let mut samples: Vec<GoSample> = (0..25).map(|n| position(0, 9, n, 1.0)).collect();
+    samples.extend((0..10).map(|n| position(1, 13, n, -1.0)));
+    let last = samples.last_mut().unwrap();
+    last.game_result = -3.0;
+    last.next_move = 13 * 13 + 1;
+    samples.extend((0..5).map(|n| position(2, 9, n, 0.0)));
+    GoDataset::from_samples(samples)
+}
+
+#[test]
+fn every_field_matches_the_composition() {
+    let stats = dataset().stats();
+    assert_eq!(stats.games, 3);
+    assert_eq!(stats.positions, 40);
+    assert_eq!(stats.board_sizes, BTreeMap::from([(9, 30), (13, 10)]));
+    assert_eq!((stats.black_wins, stats.white_wins, stats.draws), (1, 1, 1));
+    assert!((stats.average_game_length - 40.0 / 3.0).abs() < 1e-5, "{}", stats.average_game_length);
+    assert_eq!(stats.move_coverage, vec![35, 5]);
+    assert_eq!((stats.black_to_play, stats.white_to_play), (21, 19));
+    assert_eq!(stats.duplicate_ratio, 5.0 / 40.0);
+    assert_eq!(stats.values_out_of_range, 1);
+    assert_eq!(stats.moves_out_of_bounds, 1);
+    assert!(!stats.labels_sane());
+}
+
+#[test]
+fn passes_are_within_bounds_and_an_empty_dataset_has_no_stats() {
+    let pass = GoSample::empty(9, 81, 1.0);
+    let stats = GoDataset::from_samples(vec![pass]).stats();
+    assert_eq!(stats.moves_out_of_bounds, 0);
+    assert!(stats.labels_sane());
+
+    assert_eq!(GoDataset::from_samples(Vec::new()).stats(), DatasetStats::default());
+}
+
+#[test]
+fn samples_from_a_game_know_their_move_number() {
+    let mut state = GameState::new(9);
+    state.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    let sample = GoSample::from_position(&state, &Move::Pass, 1.0).unwrap();
+    assert_eq!(sample.move_number, 1);
+    let stats = GoDataset::from_samples(vec![sample]).stats();
+    assert_eq!((stats.black_to_play, stats.white_to_play), (0, 1));
+}
+
+#[test]
+fn renders_as_json_and_markdown() {
+    let stats = dataset().stats();
+    let json = stats.to_json().unwrap();
+    assert_eq!(serde_json::from_str::<DatasetStats>(&json).unwrap(), stats);
+
+    let markdown = stats.to_markdown();
+    for line in [
+        "| Games | 3 |",
+        "| Positions | 40 |",
+        "| Results | Black 1, White 1, draws 1 |",
+        "| Average game length | 13.3 moves |",
+        "| To play | Black 21, White 19 |",
+        "| Duplicate positions | 12.5% |",
+        "| 9x9 | 30 |",
+        "| 13x13 | 10 |",
+        "| 0-19 | 35 |",
+        "| 20-39 | 5 |",
+        "- Game results outside [-1, 1]: 1",
+        "- Moves off the board: 1",
+    ] {
+        assert!(markdown.lines().any(|l| l == line), "missing {:?} in\n{}", line, markdown);
+    }
+    assert!(markdown.contains("before training"));
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Repeated positions are merged into weighted samples.
+
+use burn::backend::NdArray;
+use trainer::{GoDataset, GoMini6E, GoSample, LoadReport};
+
+fn opening(game_result: f32) -> GoSample {
+    let mut sample = GoSample::empty(9, 40, game_result);
+    sample.board_state[30] = 1.0;
+    sample
+}
+
+fn rare() -> GoSample {
+    let mut sample = GoSample::empty(9, 12, -1.0);
+    sample.board_state[50] = -1.0;
+    sample
+}
+
+#[test]
+fn repeated_position_is_capped() {
+    let mut samples: Vec<GoSample> = (0..100).map(|i| opening(if i < 75 { 1.0 } else { -1.0 })).collect();
+    samples.push(rare());
+    let mut dataset = GoDataset::from_samples(samples);
+
+    let report = dataset.dedup(8);
+    assert_eq!(report, LoadReport {
+        samples_loaded: 101,
+        unique_samples: 2,
+        duplicates_merged: 99,
+        capped: 1,
+        most_copies: 100,
+    });
+
+    let samples = dataset.samples();
+    assert_eq!(samples[0].weight, 8.0);
+    assert!((samples[0].game_result - 0.5).abs() < 1e-6, "mean of 75 wins and 25 losses");
+    assert_eq!(samples[1].weight, 1.0);
+    assert_eq!(samples[1].game_result, -1.0);
+}
+
+#[test]
+fn same_position_with_another_move_stays_separate() {
+    let mut other_move = opening(1.0);
+    other_move.next_move = 41;
+    let mut dataset = GoDataset::from_samples(vec![opening(1.0), opening(1.0), other_move]);
+
+    let report = dataset.dedup(usize::MAX);
+    assert_eq!(report.unique_samples, 2);
+    assert_eq!(report.capped, 0);
+    assert_eq!(dataset.samples()[0].weight, 2.0);
+}
+
+#[test]
+fn weights_count_like_copies_in_the_loss() {
+    let device = Default::default();
+    let model = GoMini6E::<NdArray>::new(&device, 9);
+
+    let copies = GoDataset::from_samples(vec![opening(1.0), opening(1.0), opening(1.0), rare()]);
+    let mut weighted = GoDataset::from_samples(vec![opening(1.0), opening(1.0), opening(1.0), rare()]);
+    weighted.dedup(8);
+    assert_eq!(weighted.len(), 2);
+
+    let copies_loss: f32 = model.loss(copies.get_batch(9, 8, &device).unwrap()).into_scalar();
+    let weighted_loss: f32 = model.loss(weighted.get_batch(9, 8, &device).unwrap()).into_scalar();
+    assert!((copies_loss - weighted_loss).abs() < 1e-4, "{} vs {}", copies_loss, weighted_loss);
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Suggestions from policy logits: pass competes with the points, and own
+//! eyes are never suggested.
+
+use p2pgo_core::policy::{pass_index, policy_len};
+use p2pgo_core::{Color, Coord, GameState, Move};
+use trainer::evaluate::{ghost_moves, pass_probability, top_moves};
+
+/// Black's wall on column 1 with eyes at (0, 0), (0, 2) and (0, 4); the rest is open
+fn black_eyes() -> GameState {
+    let mut stones = Vec::new();
+    for y in 0..5 {
+        stones.push((Coord::new(1, y), Color::Black));
+        if y % 2 == 1 {
+            stones.push((Coord::new(0, y), Color::Black));
+        }
+    }
+    GameState::from_setup(5, &stones, Color::Black).unwrap()
+}
+
+#[test]
+fn pass_leads_when_it_rates_best() {
+    let state = GameState::new(9);
+    let mut logits = vec![0.0; policy_len(9)];
+    logits[pass_index(9)] = 10.0;
+    logits[40] = 2.0;
+
+    let moves = top_moves(&logits, 9, &state, 3);
+    assert_eq!(moves.len(), 3);
+    assert_eq!(moves[0].0, Move::Pass);
+    assert_eq!(moves[1].0, Move::Place(Coord::new(4, 4)));
+    assert!(pass_probability(&logits, 9, &state) > 0.9);
+}
+
+#[test]
+fn own_eyes_are_left_out() {
+    let state = black_eyes();
+    // The small board sits at offset 2 in the model's 9x9 input; rate the eyes highest
+    let mut logits = vec![0.0; policy_len(9)];
+    for y in [0, 2, 4] {
+        logits[(y + 2) * 9 + 2] = 20.0;
+    }
+    let moves = top_moves(&logits, 9, &state, 100);
+    assert!(moves.iter().all(|(mv, _)| !matches!(mv, Move::Place(c) if c.x == 0)), "{:?}", moves);
+    // Pass and the 15 open points
+    assert_eq!(moves.len(), 16);
+    assert!((moves.iter().map(|(_, p)| p).sum::<f32>() - 1.0).abs() < 1e-5);
+}
+
+#[test]
+fn finished_games_only_pass() {
+    let mut state = GameState::new(9);
+    state.apply_move(Move::Pass).unwrap();
+    state.apply_move(Move::Pass).unwrap();
+    let logits = vec![0.0; policy_len(9)];
+    assert_eq!(top_moves(&logits, 9, &state, 3), [(Move::Pass, 1.0)]);
+}
+
+/// Black has just taken the white stone at (1,1) in a ko; White is to move
+fn ko_for_white() -> GameState {
+    let stones = [
+        (Coord::new(1, 0), Color::Black),
+        (Coord::new(0, 1), Color::Black),
+        (Coord::new(1, 2), Color::Black),
+        (Coord::new(2, 0), Color::White),
+        (Coord::new(1, 1), Color::White),
+        (Coord::new(3, 1), Color::White),
+        (Coord::new(2, 2), Color::White),
+    ];
+    let mut state = GameState::from_setup(9, &stones, Color::Black).unwrap();
+    state.apply_move(Move::Place(Coord::new(2, 1))).unwrap();
+    state
+}
+
+#[test]
+fn ghost_moves_never_retake_the_ko() {
+    let state = ko_for_white();
+    // Rate the retake and the suicide at (0,0) above everything
+    let mut logits = vec![0.0; policy_len(9)];
+    logits[9 + 1] = 12.0;
+    logits[0] = 11.0;
+    logits[4 * 9 + 4] = 10.0;
+    logits[6 * 9 + 6] = 9.0;
+
+    let hints = ghost_moves(&logits, 9, &state, 3);
+    assert!(!hints.contains(&Move::Place(Coord::new(1, 1))), "{:?}", hints);
+    assert!(!hints.contains(&Move::Place(Coord::new(0, 0))), "{:?}", hints);
+    assert_eq!(hints, vec![Move::Place(Coord::new(4, 4)), Move::Place(Coord::new(6, 6))]);
+}
+
+#[test]
+fn ghost_moves_pass_when_nothing_likely_is_legal() {
+    let state = ko_for_white();
+    let mut logits = vec![0.0; policy_len(9)];
+    logits[9 + 1] = 12.0;
+    assert_eq!(ghost_moves(&logits, 9, &state, 1), vec![Move::Pass]);
+}
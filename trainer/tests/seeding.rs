@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Training runs from the same seed train the same model.
+//!
+//! The backend's RNG is global, so this file holds a single test.
+
+use burn::backend::{Autodiff, NdArray};
+use burn::optim::AdamConfig;
+use trainer::checkpoint::CheckpointStore;
+use trainer::{GoDataset, GoSample, NeuralTrainer, TrainingConfig};
+
+type B = Autodiff<NdArray>;
+
+fn dataset() -> GoDataset {
+    GoDataset::from_samples((0..8).map(|i| {
+        let mut sample = GoSample::empty(9, (i * 5) % 82, if i % 2 == 0 { 1.0 } else { -1.0 });
+        sample.board_state[i * 9] = 1.0;
+        sample
+    }).collect())
+}
+
+/// Losses of three batches trained from `seed`, then of the whole epoch
+///
+/// Weight records carry random parameter IDs, so the model is compared by
+/// what it computes rather than by its bytes.
+fn run(seed: u64) -> (Vec<f32>, f32) {
+    let dir = tempfile::tempdir().unwrap();
+    let store = CheckpointStore::new(dir.path(), 2);
+    let config = TrainingConfig { seed, ..TrainingConfig::default() };
+    let optimizer = AdamConfig::new().init::<B, trainer::GoMini6E<B>>();
+    let mut trainer = NeuralTrainer::new(Default::default(), &dataset(), config, optimizer, store).unwrap();
+    let losses = (0..3).map(|_| trainer.train_batch().unwrap()).collect();
+    (losses, trainer.evaluate())
+}
+
+#[test]
+fn same_seed_same_model() {
+    let first = run(7);
+    assert_eq!(first, run(7));
+    assert_ne!(first, run(8));
+}
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Games a player took out of training never reach the dataset.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use p2pgo_core::archiver::{write_summary, GameSummary};
+use p2pgo_core::scoring::calculate_final_score;
+use p2pgo_core::value_labeller::{ScoreProof, ScoringMethod};
+use p2pgo_core::{Color, Coord, GameState, Move};
+use trainer::GoDataset;
+
+/// A territory-scored record, told apart by its `final_score`
+fn proof(final_score: i16) -> Vec<u8> {
+    // An empty board has no territory, so komi alone sets the score
+    let komi = -(final_score as f32);
+    let proof = calculate_final_score(&GameState::new(9), komi, ScoringMethod::Territory, &HashSet::new());
+    record(&proof, Some(&GameState::new(9)))
+}
+
+/// Write the record with a summary allowing training or not
+fn write_game(dir: &Path, id: &str, record: Vec<u8>, eligible: bool) {
+    fs::write(dir.join(format!("{}.cbor", id)), record).unwrap();
+    let mut summary = GameSummary::new(id.to_string(), "", "2026-10-16", &GameState::new(9));
+    summary.training_eligible = eligible;
+    write_summary(dir, &summary).unwrap();
+}
+
+#[test]
+fn scored_games_skip_excluded_ones() {
+    let dir = tempfile::tempdir().unwrap();
+    write_game(dir.path(), "shared", proof(3), true);
+    write_game(dir.path(), "private", proof(7), false);
+    // Records from before summaries existed are still used
+    fs::write(dir.path().join("older.cbor"), proof(5)).unwrap();
+
+    let dataset = GoDataset::from_cbor_dir(dir.path()).unwrap();
+    let mut results: Vec<f32> = dataset.samples().iter().map(|s| s.game_result).collect();
+    results.sort_by(f32::total_cmp);
+    assert_eq!(results, [3.0, 5.0]);
+}
+
+/// A record of `proof` followed by the game it scores, if any
+fn record(proof: &ScoreProof, game: Option<&GameState>) -> Vec<u8> {
+    let mut data = vec![b'S'];
+    data.extend(serde_cbor::to_vec(proof).unwrap());
+    if let Some(game) = game {
+        data.push(b'G');
+        data.extend(serde_cbor::to_vec(game).unwrap());
+    }
+    data
+}
+
+#[test]
+fn proofs_are_recounted_on_the_game_not_on_themselves() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut game = GameState::new(9);
+    for mv in [Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6)), Move::Pass, Move::Pass] {
+        game.apply_move(mv).unwrap();
+    }
+    let honest = calculate_final_score(&game, 6.5, ScoringMethod::Territory, &HashSet::new());
+    // A proof that agrees with itself, made on a board the game never reached
+    let mut forged_board = game.clone();
+    forged_board.board[Coord::new(4, 4).to_index(9)] = Some(Color::Black);
+    let forged = calculate_final_score(&forged_board, 6.5, ScoringMethod::Territory, &HashSet::new());
+    assert!(forged.verify().is_ok());
+
+    fs::write(dir.path().join("honest.cbor"), record(&honest, Some(&game))).unwrap();
+    fs::write(dir.path().join("forged.cbor"), record(&forged, Some(&game))).unwrap();
+    fs::write(dir.path().join("no-game.cbor"), record(&honest, None)).unwrap();
+
+    let dataset = GoDataset::from_cbor_dir(dir.path()).unwrap();
+    let results: Vec<f32> = dataset.samples().iter().map(|s| s.game_result).collect();
+    assert_eq!(results, [honest.final_score as f32]);
+}
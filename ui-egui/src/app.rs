@@ -4,23 +4,88 @@
 
 use crossbeam_channel::{Sender, Receiver};
 use eframe::egui;
-use p2pgo_core::{Move, Color};
+use p2pgo_core::{archiver, coords, Move, Color, EndReason};
+use p2pgo_core::value_labeller::ScoringMethod;
+use p2pgo_core::analysis::{expected_score, GameReport, Phase};
+use p2pgo_core::ladder;
+use p2pgo_core::phase::GamePhase;
+use p2pgo_core::governor::{GovernorConfig, GovernorStatus, PauseReason};
+use p2pgo_core::game_clock::OnDisconnect;
 use std::thread::JoinHandle;
 
-use crate::msg::{UiToNet, NetToUi};
+use crate::msg::{UiToNet, NetToUi, GameErrorKind, Recovery};
 use crate::view::View;
 use crate::board_widget::BoardWidget;
+use crate::go_board::{GhostStones, GoBoardWidget};
+use crate::repaint::{RepaintCause, RepaintStats, HEARTBEAT_INTERVAL};
+use crate::event_filter::{self, MoveOutcome};
+use crate::archive_view::{ArchiveBrowser, ExportChoices, ImportDialog, Review, SortKey, EXPORT_FORMATS};
+use crate::puzzle_view::{Feedback, PuzzleSession};
+use crate::ladder_view::{self, LadderGame, LADDER_GAME_ID};
+use crate::opening_view::OpeningExplorer;
+use crate::editor_view::{standard_komi, BoardEditor};
+use crate::onboarding::{Onboarding, OnboardingPage};
+use crate::ui_config::{PassSuggestions, UiConfig};
+use crate::runtime_config::{ApplyReport, ConfigError, RuntimeConfig};
+use crate::messages::game_error_message;
+use crate::heat_map::{self, HeatMapOverlay};
+use crate::win_rate::{self, WinRateGraph};
+use crate::teaching_view::{self, TeachingTool};
+use crate::presence_view::{self, OpponentPresence};
+use crate::clock_view::{self, ClockDisplay};
+use crate::turn_alerts::{self, TurnAlertSettings, TurnAlerts, TurnNotice};
+use crate::move_confirm::{self, Confirmation};
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::teaching::TeachingLayer;
+use p2pgo_core::territory::TerritoryEstimate;
+use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+use p2pgo_network::lobby::GameInfo;
+use p2pgo_network::loopback;
+use p2pgo_network::game_list::{GamesQuery, GamesSort, GAMES_PAGE_SIZE};
+use p2pgo_network::credits::{CreditEntry, CreditSource};
+use p2pgo_network::rating::{GameOutcome, Rating, RatingEntry};
+use p2pgo_network::matchmaking::{MatchPrefs, TimeControl};
+use p2pgo_network::channel_metrics::MetricsSnapshot;
+use p2pgo_network::channel_tuning::ChannelTuning;
+use p2pgo_network::traffic::{TrafficCategory, TrafficHistory, HISTORY_SECS};
+use p2pgo_network::invite::Invite;
+use p2pgo_network::sanitize::{RejectKind, RejectionCounts};
+use p2pgo_network::logging::{LogFilter, CAPTURE_DURATION};
+use p2pgo_network::alerts::{self, AlertRule};
+use trainer::backend::{TrainingBackend, TrainingReport};
+use trainer::stats::DatasetStats;
+use p2pgo_network::presence::PresenceLimiter;
+use p2pgo_network::relay_robustness::now_secs;
+use p2pgo_network::relay_mode::RelayPreset;
+use p2pgo_network::admission::{ConnectionLimits, ConnectionUsage};
 
 #[allow(dead_code)]
 const DEFAULT_SIZE: u8 = 9; // was 19
 
+/// Time controls offered for matchmaking: main time and byo-yomi in seconds
+const TIME_CONTROLS: [(u32, u32); 3] = [(300, 10), (600, 30), (1200, 30)];
+
+/// How long a toast stays on screen
+const TOAST_DURATION: std::time::Duration = std::time::Duration::from_secs(4);
+
+/// How long closing the window waits for the worker to save open games
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Game ID of a practice game, which no peer ever sees
+const PRACTICE_GAME_ID: &str = "practice";
+
 /// Application configuration settings
 #[derive(Debug, Clone)]
 pub struct AppConfig {
-    /// Whether to auto-refresh the lobby
+    /// Whether pushed lobby updates are applied live
     pub auto_refresh: bool,
     /// Number of completed games
     pub games_finished: u32,
+    /// Whether we help relay other players' traffic
+    pub relay_participation: bool,
+    /// Whether finished games are shared as training data
+    pub share_training_data: bool,
 }
 
 impl Default for AppConfig {
@@ -28,10 +93,40 @@ impl Default for AppConfig {
         Self {
             auto_refresh: true,
             games_finished: 0,
+            relay_participation: false,
+            share_training_data: false,
         }
     }
 }
 
+/// State of the identity settings and the startup unlock prompt
+#[derive(Debug, Default)]
+struct IdentitySettings {
+    /// Whether the worker is waiting for the key file passphrase
+    unlock_prompt: bool,
+    /// Why the last unlock attempt failed
+    unlock_error: Option<String>,
+    /// Whether the node ID survives restarts
+    persistent: bool,
+    /// Whether the key file is passphrase protected
+    encrypted: bool,
+    /// Passphrase field shared by unlock, protect, export and import
+    passphrase_input: String,
+    /// Last export, ready to copy
+    export: Option<String>,
+    /// Pasted export to import
+    import_input: String,
+    /// Node ID that takes over on next launch after an import
+    pending_node_id: Option<String>,
+}
+
+impl IdentitySettings {
+    /// The passphrase field, or `None` when it is empty
+    fn passphrase(&self) -> Option<String> {
+        Some(self.passphrase_input.clone()).filter(|p| !p.is_empty())
+    }
+}
+
 /// Main application state
 pub struct App {
     /// Channel to send messages to network worker
@@ -75,8 +170,175 @@ pub struct App {
     ticket_input: String,
     /// NAT report result
     nat_report: Option<String>,
+    /// Latest channel metrics per game, for the debug overlay
+    channel_metrics: std::collections::BTreeMap<String, MetricsSnapshot>,
+    /// Last minute of traffic samples, for the debug overlay's graphs
+    traffic: TrafficHistory,
+    /// Whether the worker was asked for traffic samples
+    watching_traffic: bool,
     /// Default board size for game creation and gossip subscription
     default_board_size: u8,
+    /// Rule variations of the games we create
+    new_game_rules: RuleConfig,
+    /// Settings of the game last joined; its board is built from these,
+    /// not from our default
+    game_settings: Option<GameSettings>,
+    /// Frame counter for the debug overlay
+    repaint_stats: RepaintStats,
+    /// Whether a `GetGameState` is awaiting its snapshot
+    snapshot_requested: bool,
+    /// Whether a manual refresh is waiting for its `GamesUpdated`
+    refresh_requested: bool,
+    /// Filter and order of the games list
+    games_query: GamesQuery,
+    /// Games matching the query, listed or not yet
+    games_total: usize,
+    /// Whether a `RequestGamesPage` is waiting for its page
+    games_page_requested: bool,
+    /// Whether the worker has reported `WorkerReady`
+    worker_ready: bool,
+    /// Startup actions held back until the worker is ready
+    pending_actions: Vec<UiToNet>,
+    /// Join requests waiting for the host's answer: (game_id, request_id, profile)
+    join_requests: Vec<(String, u64, PlayerProfile)>,
+    /// How join requests for games we host are answered
+    join_policy: JoinPolicy,
+    /// Idle game waiting for the player to abandon it or keep waiting
+    idle_prompt: Option<(String, std::time::Duration)>,
+    /// Unfinished game from the last session: (game_id, move_count, opponent)
+    resume_offer: Option<(String, usize, Option<String>)>,
+    /// Short confirmation and when it was shown
+    toast: Option<(String, std::time::Instant)>,
+    /// Games the player kept out of training from the score dialog
+    excluded_from_training: std::collections::HashSet<String>,
+    /// Invite link from the worker, copied to the clipboard next frame
+    invite_link: Option<String>,
+    /// Whether the game we are hosting is played at a move a day
+    correspondence: bool,
+    /// Whether we teach the game we are hosting
+    teaching: bool,
+    /// Teacher's annotations of the game on screen
+    annotations: TeachingLayer,
+    /// What a click on the board does while teaching; `None` plays a move
+    teaching_tool: Option<TeachingTool>,
+    /// Comment being written for the position on screen
+    lesson_comment: String,
+    /// Opponent moves that arrived while we were away: (game_id, count)
+    unread_moves: Option<(String, usize)>,
+    /// Move queued during the opponent's turn
+    premove: Option<Move>,
+    /// Why the last move was rejected, shown under the board while it flashes
+    move_hint: Option<String>,
+    /// Black's expected margin after passing, once the worker finds nothing
+    /// left worth playing
+    pass_hint: Option<f32>,
+    /// Whether the latest ghost moves put pass first
+    ghost_pass: bool,
+    /// Whether the practice game is shared for spectators
+    sharing_board: bool,
+    /// Whether a shared practice game is archived when sharing stops
+    archive_shared_board: bool,
+    /// Log filter typed in the settings, e.g. "info,p2pgo_network=debug"
+    log_filter_input: String,
+    /// Why the typed log filter was refused
+    log_filter_error: Option<String>,
+    /// Alert rules as JSON, being edited in the settings
+    alert_rules_input: String,
+    /// Why the edited alert rules were refused
+    alert_rules_error: Option<String>,
+    /// Whether the advanced config editor is open
+    show_config_editor: bool,
+    /// Advanced config as JSON, being edited
+    config_json: String,
+    /// Why the edited config was refused
+    config_errors: Vec<ConfigError>,
+    /// What applying the edited config did, once the worker says
+    config_report: Option<ApplyReport>,
+    /// Warning from an alert rule, shown until dismissed
+    alert: Option<String>,
+    /// When the trace capture asked for ends, until the worker reports it finished
+    capture_until: Option<std::time::Instant>,
+    /// Invalid messages from peers dropped since startup
+    rejections: RejectionCounts,
+    /// Last training speed measurement, and whether one is running
+    training_report: Option<TrainingReport>,
+    measuring_training: bool,
+    /// Folder of training games typed in the training settings, its stats
+    /// once loaded, and whether they are being worked out
+    dataset_input: String,
+    dataset_stats: Option<DatasetStats>,
+    inspecting_dataset: bool,
+    /// Why background training is on hold, if it is
+    training_paused: Option<PauseReason>,
+    /// Relay preset the worker applied and its limits
+    relay_mode: Option<(RelayPreset, ConnectionLimits)>,
+    /// Incoming connections during the last second, while the overlay is open
+    relay_usage: ConnectionUsage,
+    /// Coordinate typed in the game controls, e.g. "D4"
+    move_input: String,
+    /// Why the typed move could not be read
+    move_input_error: Option<String>,
+    /// Whether the policy heat map is drawn over the game board
+    show_heat_map: bool,
+    /// Heat map for the position on screen, as received from the worker
+    heat_map: HeatMapOverlay,
+    /// Whether the ownership estimate is drawn over the game board
+    show_ownership: bool,
+    /// Ownership estimate for the position on screen, as received from the worker
+    ownership: HeatMapOverlay,
+    /// Territory estimate of the position on screen, by position hash, while
+    /// `UiConfig::territory_estimate` is on
+    territory: Option<(u64, TerritoryEstimate)>,
+    /// Win rate after each move of the game watched or reviewed, while shown
+    win_rate: Option<WinRateGraph>,
+    /// Latest post-game report and the game it is for
+    game_report: Option<(String, GameReport)>,
+    /// The ladder game, while the practice board holds one
+    ladder: Option<LadderGame>,
+    /// When our presence next goes to the opponent
+    presence: PresenceLimiter,
+    /// Last time we hovered the board or played, unix seconds
+    last_active: u64,
+    /// The opponent's presence, once heard in the current game
+    opponent_presence: Option<OpponentPresence>,
+    /// Game clocks, once the game has any
+    clock: Option<ClockDisplay>,
+    /// A lobby game whose terms the player is looking over before joining
+    join_confirm: Option<GameInfo>,
+    /// A pass or resignation held back until the player confirms it
+    move_confirm: Option<Confirmation>,
+    /// We asked to take moves back and the opponent has yet to answer
+    undo_pending: bool,
+    /// Moves the opponent asked to take back, awaiting our answer
+    undo_request: Option<u8>,
+    /// Games awaiting our move, for notifications and the title count
+    turn_alerts: TurnAlerts,
+    /// Whether the window is focused and not minimized, as of the last frame
+    window_focused: bool,
+    /// Title last given to the window
+    window_title: String,
+    /// Persisted training credits, once the worker has reported them
+    credits_balance: Option<u64>,
+    /// Ledger entries behind the balance, oldest first
+    credits_history: Vec<CreditEntry>,
+    /// Our rating, once the worker has reported it
+    rating: Option<Rating>,
+    /// Rated games, oldest first
+    rating_history: Vec<RatingEntry>,
+    /// What to ask the matchmaking queue for
+    match_prefs: MatchPrefs,
+    /// Whether we are waiting in the matchmaking queue
+    queue_searching: bool,
+    /// Identity settings and unlock prompt
+    identity: IdentitySettings,
+    /// Persisted UI state, such as the puzzle streak
+    ui_config: UiConfig,
+    /// Where `ui_config` is saved; `None` keeps it in memory
+    ui_config_path: Option<std::path::PathBuf>,
+    /// Advanced config last applied, kept in the data directory
+    runtime_config: RuntimeConfig,
+    /// Where `runtime_config` is saved; `None` keeps it in memory
+    runtime_config_path: Option<std::path::PathBuf>,
 }
 
 impl App {
@@ -84,7 +346,7 @@ impl App {
         // Request node ID on startup
         let _ = ui_tx.send(UiToNet::GetNodeId);
         
-        Self {
+        let mut app = Self {
             ui_tx,
             ui_rx,
             worker_handle: None,
@@ -101,8 +363,113 @@ impl App {
             current_ticket: None,
             ticket_input: String::new(),
             nat_report: None,
+            channel_metrics: std::collections::BTreeMap::new(),
+            traffic: TrafficHistory::new(),
+            watching_traffic: false,
             default_board_size: board_size,
+            new_game_rules: RuleConfig::default(),
+            game_settings: None,
+            repaint_stats: RepaintStats::new(),
+            snapshot_requested: false,
+            refresh_requested: false,
+            games_query: GamesQuery::default(),
+            games_total: 0,
+            games_page_requested: false,
+            worker_ready: false,
+            pending_actions: Vec::new(),
+            join_requests: Vec::new(),
+            join_policy: JoinPolicy::default(),
+            idle_prompt: None,
+            resume_offer: None,
+            toast: None,
+            excluded_from_training: std::collections::HashSet::new(),
+            invite_link: None,
+            correspondence: false,
+            teaching: false,
+            annotations: TeachingLayer::new(),
+            teaching_tool: None,
+            lesson_comment: String::new(),
+            unread_moves: None,
+            premove: None,
+            move_hint: None,
+            pass_hint: None,
+            ghost_pass: false,
+            sharing_board: false,
+            archive_shared_board: true,
+            log_filter_input: String::new(),
+            log_filter_error: None,
+            alert_rules_input: String::new(),
+            alert_rules_error: None,
+            show_config_editor: false,
+            config_json: String::new(),
+            config_errors: Vec::new(),
+            config_report: None,
+            alert: None,
+            capture_until: None,
+            rejections: RejectionCounts::default(),
+            training_report: None,
+            measuring_training: false,
+            dataset_input: String::new(),
+            dataset_stats: None,
+            inspecting_dataset: false,
+            training_paused: None,
+            relay_mode: None,
+            relay_usage: ConnectionUsage::default(),
+            move_input: String::new(),
+            move_input_error: None,
+            show_heat_map: false,
+            heat_map: HeatMapOverlay::default(),
+            show_ownership: false,
+            ownership: HeatMapOverlay::default(),
+            territory: None,
+            win_rate: None,
+            game_report: None,
+            ladder: None,
+            presence: PresenceLimiter::default(),
+            last_active: now_secs(),
+            opponent_presence: None,
+            clock: None,
+            join_confirm: None,
+            move_confirm: None,
+            undo_pending: false,
+            undo_request: None,
+            turn_alerts: TurnAlerts::new(turn_alerts::desktop_notifier()),
+            window_focused: true,
+            window_title: turn_alerts::window_title(0, TurnAlertSettings::default()),
+            credits_balance: None,
+            credits_history: Vec::new(),
+            rating: None,
+            rating_history: Vec::new(),
+            match_prefs: MatchPrefs::default(),
+            queue_searching: false,
+            identity: IdentitySettings::default(),
+            ui_config: UiConfig::load(&UiConfig::default_path()),
+            ui_config_path: Some(UiConfig::default_path()),
+            runtime_config: RuntimeConfig::load(&RuntimeConfig::default_path()),
+            runtime_config_path: Some(RuntimeConfig::default_path()),
+        };
+        
+        // Choices saved by the wizard override what the worker started with
+        if let Some(name) = app.ui_config.display_name.clone() {
+            app.player_name = name.clone();
+            app.queue_startup_action(UiToNet::SetPlayerName { name });
+        }
+        app.queue_startup_action(UiToNet::SetSharing {
+            relay: app.ui_config.relay_participation,
+            training: app.ui_config.share_training_data,
+        });
+        app.queue_startup_action(UiToNet::SetPassSuggestions { settings: app.ui_config.pass_suggestions });
+        app.queue_startup_action(UiToNet::SetTimingPrivacy { enabled: app.ui_config.coarse_move_times });
+        let rules = app.ui_config.alert_rules();
+        app.alert_rules_input = serde_json::to_string_pretty(&rules).unwrap_or_default();
+        app.queue_startup_action(UiToNet::SetAlertRules { rules });
+        // Log filter, watchdog tuning and governor come from the runtime config
+        app.log_filter_input = app.runtime_config.log_filter.to_string();
+        app.queue_startup_action(UiToNet::ApplyConfig { config: app.runtime_config.clone() });
+        if !app.ui_config.onboarded {
+            app.open_onboarding();
         }
+        app
     }
 
     /// Create a new headless app for testing
@@ -125,7 +492,90 @@ impl App {
             current_ticket: None,
             ticket_input: String::new(),
             nat_report: None,
+            channel_metrics: std::collections::BTreeMap::new(),
+            traffic: TrafficHistory::new(),
+            watching_traffic: false,
             default_board_size: DEFAULT_SIZE,
+            new_game_rules: RuleConfig::default(),
+            game_settings: None,
+            repaint_stats: RepaintStats::new(),
+            snapshot_requested: false,
+            refresh_requested: false,
+            games_query: GamesQuery::default(),
+            games_total: 0,
+            games_page_requested: false,
+            worker_ready: false,
+            pending_actions: Vec::new(),
+            join_requests: Vec::new(),
+            join_policy: JoinPolicy::default(),
+            idle_prompt: None,
+            resume_offer: None,
+            toast: None,
+            excluded_from_training: std::collections::HashSet::new(),
+            invite_link: None,
+            correspondence: false,
+            teaching: false,
+            annotations: TeachingLayer::new(),
+            teaching_tool: None,
+            lesson_comment: String::new(),
+            unread_moves: None,
+            premove: None,
+            move_hint: None,
+            pass_hint: None,
+            ghost_pass: false,
+            sharing_board: false,
+            archive_shared_board: true,
+            log_filter_input: String::new(),
+            log_filter_error: None,
+            alert_rules_input: String::new(),
+            alert_rules_error: None,
+            show_config_editor: false,
+            config_json: String::new(),
+            config_errors: Vec::new(),
+            config_report: None,
+            alert: None,
+            capture_until: None,
+            rejections: RejectionCounts::default(),
+            training_report: None,
+            measuring_training: false,
+            dataset_input: String::new(),
+            dataset_stats: None,
+            inspecting_dataset: false,
+            training_paused: None,
+            relay_mode: None,
+            relay_usage: ConnectionUsage::default(),
+            move_input: String::new(),
+            move_input_error: None,
+            show_heat_map: false,
+            heat_map: HeatMapOverlay::default(),
+            show_ownership: false,
+            ownership: HeatMapOverlay::default(),
+            territory: None,
+            win_rate: None,
+            game_report: None,
+            ladder: None,
+            presence: PresenceLimiter::default(),
+            last_active: now_secs(),
+            opponent_presence: None,
+            clock: None,
+            join_confirm: None,
+            move_confirm: None,
+            undo_pending: false,
+            undo_request: None,
+            turn_alerts: TurnAlerts::new(Box::new(turn_alerts::NoNotifier)),
+            window_focused: true,
+            window_title: turn_alerts::window_title(0, TurnAlertSettings::default()),
+            credits_balance: None,
+            credits_history: Vec::new(),
+            rating: None,
+            rating_history: Vec::new(),
+            match_prefs: MatchPrefs::default(),
+            queue_searching: false,
+            identity: IdentitySettings::default(),
+            ui_config: UiConfig::default(),
+            ui_config_path: None,
+            runtime_config: RuntimeConfig::default(),
+            runtime_config_path: None,
         }
     }
     
@@ -149,7 +599,367 @@ impl App {
             current_ticket: None,
             ticket_input: String::new(),
             nat_report: None,
+            channel_metrics: std::collections::BTreeMap::new(),
+            traffic: TrafficHistory::new(),
+            watching_traffic: false,
             default_board_size: DEFAULT_SIZE,
+            new_game_rules: RuleConfig::default(),
+            game_settings: None,
+            repaint_stats: RepaintStats::new(),
+            snapshot_requested: false,
+            refresh_requested: false,
+            games_query: GamesQuery::default(),
+            games_total: 0,
+            games_page_requested: false,
+            worker_ready: false,
+            pending_actions: Vec::new(),
+            join_requests: Vec::new(),
+            join_policy: JoinPolicy::default(),
+            idle_prompt: None,
+            resume_offer: None,
+            toast: None,
+            excluded_from_training: std::collections::HashSet::new(),
+            invite_link: None,
+            correspondence: false,
+            teaching: false,
+            annotations: TeachingLayer::new(),
+            teaching_tool: None,
+            lesson_comment: String::new(),
+            unread_moves: None,
+            premove: None,
+            move_hint: None,
+            pass_hint: None,
+            ghost_pass: false,
+            sharing_board: false,
+            archive_shared_board: true,
+            log_filter_input: String::new(),
+            log_filter_error: None,
+            alert_rules_input: String::new(),
+            alert_rules_error: None,
+            show_config_editor: false,
+            config_json: String::new(),
+            config_errors: Vec::new(),
+            config_report: None,
+            alert: None,
+            capture_until: None,
+            rejections: RejectionCounts::default(),
+            training_report: None,
+            measuring_training: false,
+            dataset_input: String::new(),
+            dataset_stats: None,
+            inspecting_dataset: false,
+            training_paused: None,
+            relay_mode: None,
+            relay_usage: ConnectionUsage::default(),
+            move_input: String::new(),
+            move_input_error: None,
+            show_heat_map: false,
+            heat_map: HeatMapOverlay::default(),
+            show_ownership: false,
+            ownership: HeatMapOverlay::default(),
+            territory: None,
+            win_rate: None,
+            game_report: None,
+            ladder: None,
+            presence: PresenceLimiter::default(),
+            last_active: now_secs(),
+            opponent_presence: None,
+            clock: None,
+            join_confirm: None,
+            move_confirm: None,
+            undo_pending: false,
+            undo_request: None,
+            turn_alerts: TurnAlerts::new(Box::new(turn_alerts::NoNotifier)),
+            window_focused: true,
+            window_title: turn_alerts::window_title(0, TurnAlertSettings::default()),
+            credits_balance: None,
+            credits_history: Vec::new(),
+            rating: None,
+            rating_history: Vec::new(),
+            match_prefs: MatchPrefs::default(),
+            queue_searching: false,
+            identity: IdentitySettings::default(),
+            ui_config: UiConfig::default(),
+            ui_config_path: None,
+            runtime_config: RuntimeConfig::default(),
+            runtime_config_path: None,
+        }
+    }
+
+    /// Send a startup action once the worker is ready, or right away if it
+    /// already is
+    pub fn queue_startup_action(&mut self, msg: UiToNet) {
+        if self.worker_ready {
+            let _ = self.ui_tx.send(msg);
+        } else {
+            self.pending_actions.push(msg);
+        }
+    }
+
+    /// Leave the game view for the score dialog, scoring the game here.
+    ///
+    /// The worker's `ScoreCalculated` usually gets there first; this covers
+    /// games whose end only reaches us as an event.
+    fn open_score_dialog(&mut self, winner: Option<Color>, reason: &EndReason) {
+        let View::Game { game_id, game_state, .. } = &self.current_view else {
+            return;
+        };
+        let method = match (reason, winner) {
+            (EndReason::Resignation, Some(winner)) => ScoringMethod::Resignation(winner),
+            _ => ScoringMethod::Territory,
+        };
+        let score_proof = p2pgo_core::scoring::calculate_final_score(
+            game_state,
+            game_komi(self.game_settings.as_ref(), game_state.board_size),
+            method,
+            &std::collections::HashSet::new(),
+        );
+        self.current_view = View::ScoreDialog {
+            game_id: game_id.clone(),
+            game_state: game_state.clone(),
+            score_proof,
+            dead_stones: std::collections::HashSet::new(),
+            score_pending: true,
+            score_accepted: false,
+        };
+    }
+
+    /// Ask for attention when a turn notification went out, raise the
+    /// window when one is clicked and keep the title's count current
+    fn update_turn_alerts(&mut self, frame: &mut eframe::Frame) {
+        if self.turn_alerts.take_attention() {
+            frame.request_user_attention(egui::UserAttentionType::Informational);
+        }
+        if let Some(game_id) = self.turn_alerts.clicked() {
+            // Games are played one at a time, so it's already on screen
+            tracing::debug!("Turn notification for {} clicked", game_id);
+            frame.focus();
+        }
+        let title = turn_alerts::window_title(self.turn_alerts.awaiting(), self.ui_config.turn_alerts);
+        if title != self.window_title {
+            frame.set_window_title(&title);
+            self.window_title = title;
+        }
+    }
+
+    /// Show the onboarding wizard, prefilled with the current choices
+    pub fn open_onboarding(&mut self) {
+        self.current_view = View::Onboarding {
+            wizard: Onboarding::new(&self.player_name, &self.ui_config),
+        };
+    }
+
+    /// Go to the next wizard page, finishing the wizard on the last one
+    pub fn onboarding_next(&mut self) {
+        let View::Onboarding { wizard } = &mut self.current_view else {
+            return;
+        };
+        if wizard.is_last_page() {
+            self.finish_onboarding();
+        } else if wizard.advance() && wizard.page() == OnboardingPage::Connectivity {
+            self.nat_report = None;
+            let _ = self.ui_tx.send(UiToNet::RunNetReport);
+        }
+    }
+
+    pub fn onboarding_back(&mut self) {
+        if let View::Onboarding { wizard } = &mut self.current_view {
+            wizard.back();
+        }
+    }
+
+    /// Set up a position on an empty `board_size` board
+    pub fn open_editor(&mut self, board_size: u8) {
+        self.current_view = View::Editor { editor: BoardEditor::new(board_size) };
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn editor_mut(&mut self) -> Option<&mut BoardEditor> {
+        match &mut self.current_view {
+            View::Editor { editor } => Some(editor),
+            _ => None,
+        }
+    }
+
+    /// Leave the wizard for a local board with AI analysis, keeping the
+    /// choices made so far
+    pub fn onboarding_try_local(&mut self) {
+        if self.finish_onboarding() {
+            self.open_editor(self.default_board_size);
+        }
+    }
+
+    /// Save the wizard's choices, tell the worker and return to the menu
+    fn finish_onboarding(&mut self) -> bool {
+        let View::Onboarding { wizard } = &self.current_view else {
+            return false;
+        };
+        if !wizard.can_advance() {
+            return false;
+        }
+        wizard.apply(&mut self.ui_config);
+        if let Some(path) = &self.ui_config_path {
+            if let Err(e) = self.ui_config.save(path) {
+                tracing::warn!("Failed to save UI config: {}", e);
+            }
+        }
+        if let Some(name) = self.ui_config.display_name.clone() {
+            self.player_name = name.clone();
+            let _ = self.ui_tx.send(UiToNet::SetPlayerName { name });
+        }
+        // The relay answer moves the preset to one that keeps to it
+        let preset = self.runtime_config.relay_preset.with_participation(self.ui_config.relay_participation);
+        if preset != self.runtime_config.relay_preset {
+            self.runtime_config.relay_preset = preset;
+            self.save_runtime_config();
+        }
+        let _ = self.ui_tx.send(UiToNet::SetSharing {
+            relay: self.ui_config.relay_participation,
+            training: self.ui_config.share_training_data,
+        });
+        self.current_view = View::default();
+        true
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn onboarding_mut(&mut self) -> Option<&mut Onboarding> {
+        match &mut self.current_view {
+            View::Onboarding { wizard } => Some(wizard),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn ui_config(&self) -> &UiConfig {
+        &self.ui_config
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn runtime_config(&self) -> &RuntimeConfig {
+        &self.runtime_config
+    }
+
+    /// Save settings and progress to `path` from now on
+    #[cfg(feature = "headless")]
+    pub fn set_ui_config_path(&mut self, path: std::path::PathBuf) {
+        self.ui_config_path = Some(path);
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn ladder(&self) -> Option<&LadderGame> {
+        self.ladder.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn opponent_presence(&self) -> Option<OpponentPresence> {
+        self.opponent_presence
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn clock(&self) -> Option<&ClockDisplay> {
+        self.clock.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn join_confirmation(&self) -> Option<&GameInfo> {
+        self.join_confirm.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn move_confirmation(&self) -> Option<&Confirmation> {
+        self.move_confirm.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn undo_pending(&self) -> bool {
+        self.undo_pending
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn undo_request(&self) -> Option<u8> {
+        self.undo_request
+    }
+
+    /// Games listed in the main menu so far
+    pub fn listed_games(&self) -> &[GameInfo] {
+        match &self.current_view {
+            View::MainMenu { available_games, .. } => available_games,
+            _ => &[],
+        }
+    }
+    
+    /// Games matching the list's filter, listed or not
+    pub fn games_total(&self) -> usize {
+        self.games_total
+    }
+    
+    /// List the games matching `query` instead, from the first page
+    pub fn set_games_query(&mut self, query: GamesQuery) {
+        self.games_query = query;
+        self.games_page_requested = true;
+        let _ = self.ui_tx.send(UiToNet::RequestGamesPage { query, offset: 0 });
+    }
+    
+    /// Ask for the page after the listed games, unless one is on its way
+    pub fn request_more_games(&mut self) {
+        let listed = self.listed_games().len();
+        if self.games_page_requested || listed >= self.games_total {
+            return;
+        }
+        self.games_page_requested = true;
+        let _ = self.ui_tx.send(UiToNet::RequestGamesPage { query: self.games_query, offset: listed });
+    }
+    
+    pub fn ui_config_mut(&mut self) -> &mut UiConfig {
+        &mut self.ui_config
+    }
+
+    /// Show the archive in `dir` instead of the default one
+    #[cfg(feature = "headless")]
+    pub fn open_archive_in(&mut self, dir: std::path::PathBuf) -> anyhow::Result<()> {
+        self.current_view = View::Archive { browser: ArchiveBrowser::open(dir)? };
+        Ok(())
+    }
+
+    /// Review the archived game `id` from the archive on screen
+    #[cfg(feature = "headless")]
+    pub fn review_archived(&mut self, id: &str) -> anyhow::Result<()> {
+        let View::Archive { browser } = &self.current_view else {
+            anyhow::bail!("The archive isn't open");
+        };
+        let browser = browser.clone();
+        let review = browser.open_review(id)?;
+        self.enter_review(browser, review);
+        Ok(())
+    }
+
+    /// Moves shown of the game under review
+    #[cfg(feature = "headless")]
+    pub fn review_cursor(&self) -> Option<usize> {
+        match &self.current_view {
+            View::Review { review, .. } => Some(review.cursor()),
+            _ => None,
+        }
+    }
+
+    /// Jump the review to the position after `moves` moves, as a click on the win-rate graph does
+    #[cfg(feature = "headless")]
+    pub fn seek_review(&mut self, moves: usize) {
+        if let View::Review { review, .. } = &mut self.current_view {
+            review.seek(moves);
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn win_rate(&self) -> Option<&WinRateGraph> {
+        self.win_rate.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn archive_browser_mut(&mut self) -> Option<&mut ArchiveBrowser> {
+        match &mut self.current_view {
+            View::Archive { browser } => Some(browser),
+            _ => None,
         }
     }
 
@@ -162,21 +972,11 @@ impl App {
     #[cfg(any(feature = "headless", test))]
     pub fn tick_headless(&mut self) {
         self.handle_network_messages();
-        
-        // For testing purposes, populate auto-refresh message if needed
-        if self.config.auto_refresh {
-            let _ = self.ui_tx.send(UiToNet::RefreshGames);
-        }
     }
     
     #[cfg(test)]
     pub fn tick_headless_test(&mut self) {
         self.handle_network_messages();
-        
-        // For testing purposes, populate auto-refresh message if needed
-        if self.config.auto_refresh {
-            let _ = self.ui_tx.send(UiToNet::RefreshGames);
-        }
     }
 
     #[cfg(feature = "headless")]
@@ -186,6 +986,12 @@ impl App {
             View::Lobby { game_id } => format!("Lobby({})", game_id),
             View::Game { game_id, .. } => format!("Game({})", game_id),
             View::ScoreDialog { .. } => "ScoreDialog".to_string(),
+            View::Archive { .. } => "Archive".to_string(),
+            View::Review { review, .. } => format!("Review({})", review.id),
+            View::Puzzle { .. } => "Puzzle".to_string(),
+            View::Openings { .. } => "Openings".to_string(),
+            View::Editor { .. } => "Editor".to_string(),
+            View::Onboarding { .. } => "Onboarding".to_string(),
         }
     }
 
@@ -198,158 +1004,1241 @@ impl App {
         }
     }
 
-    /// Send a message to the network component
-    #[allow(dead_code)]
-    #[cfg(not(test))]
-    pub fn ui_send(&self, msg: UiToNet) {
-        let _ = self.ui_tx.send(msg);
+    /// The board, score and whether it was accepted, on the score dialog
+    #[cfg(feature = "headless")]
+    pub fn score_dialog(&self) -> Option<(&p2pgo_core::GameState, &p2pgo_core::value_labeller::ScoreProof, bool)> {
+        match &self.current_view {
+            View::ScoreDialog { game_state, score_proof, score_accepted, .. } => Some((game_state, score_proof, *score_accepted)),
+            _ => None,
+        }
     }
 
-    /// Send a message to the network worker (for testing)
-    #[cfg(test)]
-    pub fn ui_send(&mut self, msg: UiToNet) {
-        let _ = self.ui_tx.send(msg);
+    #[cfg(feature = "headless")]
+    pub fn get_error_msg(&self) -> Option<String> {
+        self.error_msg.clone()
     }
-    
-    /// Get the last received message from the network worker (for testing)
-    #[cfg(test)]
-    pub fn recv_last(&mut self) -> Option<NetToUi> {
-        let mut last = None;
-        while let Ok(msg) = self.ui_rx.try_recv() {
-            last = Some(msg);
-        }
-        last
+
+    /// The board point flashed for a rejected move
+    #[cfg(feature = "headless")]
+    pub fn board_flash(&self) -> Option<p2pgo_core::Coord> {
+        self.board_widget.flashed()
     }
-    
-    /// Wait for and receive a specific message (for testing)
-    #[cfg(test)]
-    pub fn blocking_recv(&mut self) -> Option<NetToUi> {
-        self.ui_rx.recv().ok()
+
+    /// The heat map the board would draw for the position on screen
+    #[cfg(feature = "headless")]
+    pub fn drawn_heat_map(&self) -> Option<Vec<f32>> {
+        self.heat_map.current().map(<[f32]>::to_vec)
     }
-    
-    /// Get all received messages for assertion (for testing)
-    #[cfg(test)]
-    pub fn recv_log(&mut self) -> Vec<NetToUi> {
-        let mut messages = Vec::new();
-        while let Ok(msg) = self.ui_rx.try_recv() {
-            messages.push(msg);
-        }
-        messages
+
+    /// Pass probability and resignation advice for the position on screen
+    #[cfg(feature = "headless")]
+    pub fn outlook(&self) -> Option<heat_map::Outlook> {
+        self.heat_map.outlook()
     }
-    
-    /// Get current game ID (for testing)
-    #[cfg(test)]
-    pub fn get_current_game_id(&self) -> Option<String> {
-        match &self.current_view {
-            View::Game { game_id, .. } => Some(game_id.clone()),
-            View::ScoreDialog { game_id, .. } => Some(game_id.clone()),
-            View::Lobby { game_id } => Some(game_id.clone()),
-            _ => None,
-        }
+
+    /// Whether the game view shows the "resigning is recommended" banner
+    #[cfg(feature = "headless")]
+    pub fn resign_recommended(&self) -> bool {
+        self.resign_banner()
     }
 
-    /// Process network messages for testing
-    #[cfg(test)]
-    pub fn update(&mut self) {
-        self.handle_network_messages();
+    /// Whether the ghost moves suggest passing
+    #[cfg(feature = "headless")]
+    pub fn ghost_pass(&self) -> bool {
+        self.ghost_pass
     }
-    
-    /// Handle the AcceptScore message for testing
-    #[cfg(test)]
-    pub fn handle_accept_score(&mut self) {
-        if let View::ScoreDialog { .. } = &self.current_view {
-            self.config.games_finished += 1;
-            self.current_view = View::default();
-        }
+
+    #[cfg(feature = "headless")]
+    pub fn sharing_board(&self) -> bool {
+        self.sharing_board
     }
-    
-    // Method removed to avoid duplication - using the existing tick_headless_test
 
-    fn handle_network_messages(&mut self) {
-        self.rx_queue_length = self.ui_rx.len();
-        
-        while let Ok(msg) = self.ui_rx.try_recv() {
-            #[cfg(feature = "headless")]
-            println!("App received message: {:?}", msg);
-            
-            match msg {
-                NetToUi::GamesUpdated { games } => {
-                    if let View::MainMenu { available_games, creating_game: _, board_size: _ } = &mut self.current_view {
-                        *available_games = games;
-                    }
-                }
-                NetToUi::GameEvent { event } => {
-                    match &event {
-                        p2pgo_core::GameEvent::MoveMade { mv, .. } => {
-                            #[cfg(feature = "headless")]
-                            println!("Move made event received: {:?}", mv);
-                            
-                            // Transition from Lobby to Game on first move
-                            if let View::Lobby { game_id } = &self.current_view {
-                                #[cfg(feature = "headless")]
-                                println!("Transitioning from Lobby to Game on first move");
-                                
-                                let board_size = self.board_widget.get_board_size();
-                                let game_state = p2pgo_core::GameState::new(board_size);
-                                self.current_view = View::Game {
-                                    game_id: game_id.clone(),
-                                    game_state,
-                                    our_color: None, // We'll set this based on move order
-                                };
-                                // Request initial ghost moves when transitioning to game view
-                                let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
-                            }
-                            
-                            if let View::Game { game_state, .. } = &mut self.current_view {
-                                let _ = game_state.apply_move(mv.clone());
-                                // Update last blob hash for debug overlay - use move type description
-                                self.last_blob_hash = Some(format!("{:?}", mv));
-                            }
-                        },
-                        p2pgo_core::GameEvent::GameFinished { black_score, white_score } => {
-                            // Wait for ScoreCalculated message to transition to score dialog
-                            // We'll just collect the scores here for now
-                            tracing::info!("Game finished - black: {}, white: {}", black_score, white_score);
-                        },
-                        _ => {
-                            // Request ghost moves after the move is applied
-                            let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
-                        }
-                    }
-                }
-                NetToUi::GameJoined { game_id } => {
-                    #[cfg(feature = "headless")]
-                    println!("Game joined: {}, transitioning to Lobby", game_id);
-                    self.current_view = View::Lobby { game_id };
-                    // Request initial ghost moves when joining a game
-                    let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
-                }
-                NetToUi::GameLeft => {
-                    self.current_view = View::default();
-                }
-                NetToUi::Error { message } => {
-                    self.error_msg = Some(message);
-                }
-                NetToUi::ConnectionStatus { .. } => {}
-                NetToUi::ShutdownAck => {
-                    tracing::debug!("Received shutdown acknowledgment from network worker");
-                }
-                NetToUi::NodeId { node_id } => {
-                    self.node_id = Some(node_id);
-                }
-                NetToUi::Ticket { ticket } => {
-                    self.current_ticket = Some(ticket);
-                }
-                NetToUi::NetReport { report } => {
-                    self.nat_report = Some(report);
-                }
-                NetToUi::TagAck => {
-                    tracing::debug!("Move tag stored successfully");
-                }
-                NetToUi::GhostMoves(coords) => {
-                    tracing::debug!("Received {} ghost move suggestions", coords.len());
-                    self.board_widget.set_ghost_stones(coords);
-                }
+    /// The ownership estimate the board would draw for the position on screen
+    #[cfg(feature = "headless")]
+    pub fn drawn_ownership(&self) -> Option<Vec<f32>> {
+        self.ownership.current().map(<[f32]>::to_vec)
+    }
+
+    /// The territory estimate the board would draw for the position on screen
+    #[cfg(feature = "headless")]
+    pub fn drawn_territory(&self) -> Option<TerritoryEstimate> {
+        self.territory.as_ref().map(|(_, estimate)| estimate.clone())
+    }
+
+    /// Which of the heat map, ownership and territory overlays are shown
+    #[cfg(feature = "headless")]
+    pub fn overlays_shown(&self) -> (bool, bool, bool) {
+        (self.show_heat_map, self.show_ownership, self.ui_config.territory_estimate)
+    }
+
+    /// Game offered for resuming: (game_id, move_count, opponent)
+    #[cfg(feature = "headless")]
+    pub fn resume_offer(&self) -> Option<(String, usize, Option<String>)> {
+        self.resume_offer.clone()
+    }
+
+    /// Badge shown on a correspondence game: (game_id, unread moves)
+    #[cfg(feature = "headless")]
+    pub fn unread_moves(&self) -> Option<(String, usize)> {
+        self.unread_moves.clone()
+    }
+
+    /// Show turn notifications through `notifier`
+    #[cfg(feature = "headless")]
+    pub fn set_notifier(&mut self, notifier: Box<dyn turn_alerts::Notifier>) {
+        self.turn_alerts = TurnAlerts::new(notifier);
+    }
+
+    /// Pretend the window gained or lost focus
+    #[cfg(feature = "headless")]
+    pub fn set_window_focused(&mut self, focused: bool) {
+        self.window_focused = focused;
+    }
+
+    /// Games awaiting our move, as counted in the window title
+    #[cfg(feature = "headless")]
+    pub fn games_awaiting_move(&self) -> usize {
+        self.turn_alerts.awaiting()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn set_turn_alerts(&mut self, settings: TurnAlertSettings) {
+        self.ui_config.turn_alerts = settings;
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn toast(&self) -> Option<String> {
+        self.toast.as_ref().map(|(text, _)| text.clone())
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn pass_hint(&self) -> Option<f32> {
+        self.pass_hint
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn training_paused(&self) -> Option<PauseReason> {
+        self.training_paused
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn relay_mode(&self) -> Option<(RelayPreset, ConnectionLimits)> {
+        self.relay_mode
+    }
+
+    /// Stats of the training games last inspected
+    #[cfg(feature = "headless")]
+    pub fn dataset_stats(&self) -> Option<&DatasetStats> {
+        self.dataset_stats.as_ref()
+    }
+
+    /// Folder of training games to inspect, as if typed in the settings
+    #[cfg(feature = "headless")]
+    pub fn set_dataset_input(&mut self, folder: &str) {
+        self.dataset_input = folder.to_string();
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn move_input_error(&self) -> Option<String> {
+        self.move_input_error.clone()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn log_filter_error(&self) -> Option<String> {
+        self.log_filter_error.clone()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn alert_rules_error(&self) -> Option<String> {
+        self.alert_rules_error.clone()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn config_json(&self) -> &str {
+        &self.config_json
+    }
+
+    /// Replace the JSON in the config editor, as if typed
+    #[cfg(feature = "headless")]
+    pub fn set_config_json(&mut self, json: &str) {
+        self.config_json = json.to_string();
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn config_errors(&self) -> &[ConfigError] {
+        &self.config_errors
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn config_report(&self) -> Option<&ApplyReport> {
+        self.config_report.as_ref()
+    }
+
+    #[cfg(feature = "headless")]
+    pub fn capturing_trace(&self) -> bool {
+        self.capture_until.is_some()
+    }
+
+    /// The teacher's overlay the board would draw for the position on screen
+    #[cfg(feature = "headless")]
+    pub fn drawn_teaching(&self) -> Option<p2pgo_core::teaching::TeachingOverlay> {
+        self.current_overlay().cloned()
+    }
+
+    /// Play `mv` in the game on screen, as a click on the board does.
+    ///
+    /// During the opponent's turn a stone is queued as a premove instead.
+    pub fn play_move(&mut self, mv: Move) {
+        self.last_active = now_secs();
+        let View::Game { game_state, our_color, practice, .. } = &mut self.current_view else {
+            return;
+        };
+        if *practice {
+            if matches!(&self.ladder, Some(ladder) if !ladder.awaits_player(game_state.current_player)) {
+                return;
+            }
+            // Both colors are ours, so the move is played right here
+            let previous = game_state.previous_position();
+            let played = game_state.check_move(&mv, &previous)
+                .and_then(|()| game_state.apply_move(mv.clone()));
+            match played {
+                Err(e) => self.show_game_error(&GameErrorKind::from_rules(&e, &mv), true),
+                Ok(()) if self.ladder.is_some() => self.ladder_turn(),
+                Ok(()) if self.sharing_board => {
+                    let over = game_state.is_game_over();
+                    let _ = self.ui_tx.send(UiToNet::ShareMove { mv });
+                    // Spectators have seen the end; the broadcast ends with it
+                    if over {
+                        self.stop_sharing();
+                    }
+                }
+                Ok(()) => {}
+            }
+            return;
+        }
+        match (*our_color, &mv) {
+            (Some(color), Move::Place(coord)) if color != game_state.current_player => {
+                // Opponent is thinking: queue our answer instead
+                self.board_widget.set_premove(Some((*coord, color)));
+                self.premove = Some(mv.clone());
+                let _ = self.ui_tx.send(UiToNet::Premove { mv, board_size: None });
+            }
+            _ => {
+                // Our first move tells us which color we play
+                our_color.get_or_insert(game_state.current_player);
+                self.unread_moves = None;
+                let _ = self.ui_tx.send(UiToNet::MakeMove { mv, board_size: None });
+                // Request ghost moves after making a move
+                let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+            }
+        }
+    }
+
+    /// Teach the game we are hosting, or stop
+    pub fn set_teaching(&mut self, enabled: bool) {
+        let (View::Lobby { game_id } | View::Game { game_id, .. }) = &self.current_view else {
+            return;
+        };
+        self.teaching = enabled;
+        if !enabled {
+            self.teaching_tool = None;
+        }
+        let _ = self.ui_tx.send(UiToNet::SetTeaching { game_id: game_id.clone(), enabled });
+    }
+    
+    pub fn set_teaching_tool(&mut self, tool: Option<TeachingTool>) {
+        self.teaching_tool = tool.filter(|_| self.teaching);
+    }
+    
+    /// Use the teaching tool at `at`, as a click on the board does
+    pub fn annotate_at(&mut self, at: p2pgo_core::Coord) {
+        let Some(tool) = self.teaching_tool else {
+            return;
+        };
+        for annotation in teaching_view::annotations_for(tool, at, self.current_overlay()) {
+            let _ = self.ui_tx.send(UiToNet::Annotate { annotation, board_size: None });
+        }
+    }
+    
+    /// The teacher's overlay of the position on screen
+    fn current_overlay(&self) -> Option<&p2pgo_core::teaching::TeachingOverlay> {
+        let View::Game { game_state, .. } = &self.current_view else {
+            return None;
+        };
+        self.annotations.overlay(game_state.moves.len())
+    }
+    
+    /// Empty board of the game last joined
+    fn joined_game_state(&self) -> p2pgo_core::GameState {
+        match &self.game_settings {
+            Some(settings) => settings.new_game(),
+            None => p2pgo_core::GameState::new(self.board_widget.get_board_size()),
+        }
+    }
+    
+    /// Start a game against ourselves on this machine, for trying things out
+    /// without a peer; nothing is sent over the network unless it is shared
+    pub fn start_practice(&mut self, board_size: u8) {
+        self.start_practice_from(p2pgo_core::GameState::new(board_size));
+    }
+    
+    /// Practice on from `position`, e.g. an opening picked in the explorer
+    pub fn start_practice_from(&mut self, position: p2pgo_core::GameState) {
+        self.stop_sharing();
+        self.ladder = None;
+        self.board_widget = BoardWidget::new(position.board_size);
+        self.move_hint = None;
+        self.current_view = View::Game {
+            game_id: PRACTICE_GAME_ID.to_string(),
+            game_state: position,
+            our_color: None,
+            practice: true,
+        };
+    }
+    
+    /// Tell the opponent whether we are looking at the board, as often as
+    /// presence may go out and unless the settings hide it
+    pub fn update_presence(&mut self, considering: bool) {
+        if considering {
+            self.last_active = now_secs();
+        }
+        if self.ui_config.hide_presence {
+            return;
+        }
+        if let Some(presence) = self.presence.poll(considering, self.last_active, std::time::Instant::now()) {
+            let _ = self.ui_tx.send(UiToNet::SendPresence { presence });
+        }
+    }
+    
+    /// Play the ladder opponent on `rung`, if it is unlocked
+    pub fn start_ladder(&mut self, rung: usize) {
+        if rung > self.ui_config.ladder_rung || rung >= ladder::RUNGS.len() {
+            return;
+        }
+        let game = LadderGame::new(rung, ladder_seed());
+        self.play_ladder_from(p2pgo_core::GameState::new(ladder::LADDER_BOARD_SIZE), game);
+    }
+
+    /// Play the strongest unlocked ladder opponent from the editor's
+    /// position, with the side to move; false if play can't start there
+    pub fn start_ai_from_editor(&mut self) -> bool {
+        let View::Editor { editor } = &self.current_view else {
+            return false;
+        };
+        let position = match editor.to_game() {
+            Ok(position) => position,
+            Err(e) => {
+                self.error_msg = Some(format!("Cannot start from this position: {}", e));
+                return false;
+            }
+        };
+        let rung = self.ui_config.ladder_rung.min(ladder::RUNGS.len() - 1);
+        let game = LadderGame::unranked(rung, ladder_seed(), position.current_player, editor.komi);
+        self.play_ladder_from(position, game);
+        true
+    }
+
+    /// Start `game` on the practice board from `position`
+    fn play_ladder_from(&mut self, position: p2pgo_core::GameState, game: LadderGame) {
+        self.start_practice_from(position);
+        if let View::Game { game_id, our_color, .. } = &mut self.current_view {
+            *game_id = LADDER_GAME_ID.to_string();
+            *our_color = Some(game.human);
+        }
+        self.ladder = Some(game);
+        self.game_report = None;
+        self.ladder_turn();
+    }
+
+    /// Ask for the opponent's move if it is their turn, or count the
+    /// result once the game is over
+    fn ladder_turn(&mut self) {
+        let (Some(game), View::Game { game_id, game_state, .. }) = (&mut self.ladder, &self.current_view) else {
+            return;
+        };
+        if game_id != LADDER_GAME_ID {
+            return;
+        }
+        if game_state.is_game_over() {
+            let state = game_state.clone();
+            self.finish_ladder(state);
+        } else if game_state.current_player != game.human && !game.thinking {
+            game.thinking = true;
+            let _ = self.ui_tx.send(UiToNet::LadderMove { rung: game.rung, seed: game.seed, state: game_state.clone() });
+        }
+    }
+
+    /// Play the opponent's move, passing instead if it turns out illegal
+    fn play_ladder_move(&mut self, mv: Move) {
+        let (Some(game), View::Game { game_id, game_state, .. }) = (&mut self.ladder, &mut self.current_view) else {
+            return;
+        };
+        if game_id != LADDER_GAME_ID || !std::mem::take(&mut game.thinking) {
+            return;
+        }
+        let previous = game_state.previous_position();
+        let played = game_state.check_move(&mv, &previous)
+            .and_then(|()| game_state.apply_move(mv.clone()));
+        if let Err(e) = played {
+            tracing::warn!("Ladder opponent played {:?}, which is illegal: {}", mv, e);
+            let _ = game_state.apply_move(Move::Pass);
+        }
+        self.ladder_turn();
+    }
+
+    /// Count the result towards the ladder and ask for the post-game report
+    fn finish_ladder(&mut self, state: p2pgo_core::GameState) {
+        let Some(game) = &mut self.ladder else {
+            return;
+        };
+        let won = ladder::winner_with_komi(&state, game.komi) == Some(game.human);
+        game.won = Some(won);
+        let name = game.name();
+        if !game.ranked {
+            let text = match won {
+                true => format!("You beat {} from your position", name),
+                false => format!("{} won from your position", name),
+            };
+            self.toast = Some((text, std::time::Instant::now()));
+            let _ = self.ui_tx.send(UiToNet::RequestGameReport { game_id: LADDER_GAME_ID.to_string(), state });
+            return;
+        }
+        let unlocked = self.ui_config.record_ladder(game.rung, won);
+        if unlocked {
+            if let Some(path) = &self.ui_config_path {
+                if let Err(e) = self.ui_config.save(path) {
+                    tracing::warn!("Failed to save UI config: {}", e);
+                }
+            }
+        }
+        let text = match (won, unlocked) {
+            (true, true) => format!("You beat {}! {} is unlocked", name, ladder::RUNGS[game.rung + 1].name),
+            (true, false) => format!("You beat {}!", name),
+            (false, _) => format!("{} won this time; the report shows where it turned", name),
+        };
+        self.toast = Some((text, std::time::Instant::now()));
+        let _ = self.ui_tx.send(UiToNet::RequestGameReport { game_id: LADDER_GAME_ID.to_string(), state });
+    }
+
+    /// Let others watch the practice game live, e.g. students on a video
+    /// call; they get every move from here on but can't play
+    pub fn share_board(&mut self) {
+        let View::Game { game_state, practice: true, .. } = &self.current_view else {
+            return;
+        };
+        if !self.sharing_board {
+            self.sharing_board = true;
+            let _ = self.ui_tx.send(UiToNet::ShareBoard { state: game_state.clone() });
+        }
+    }
+    
+    /// Close the shared board's broadcast, archiving the game if so set
+    pub fn stop_sharing(&mut self) {
+        if std::mem::take(&mut self.sharing_board) {
+            let _ = self.ui_tx.send(UiToNet::StopSharing { archive: self.archive_shared_board });
+        }
+    }
+    
+    /// Filter logs with `text`, e.g. "info,p2pgo_network::game_channel=trace",
+    /// and keep it for next time; a filter that doesn't parse is shown instead
+    pub fn set_log_filter(&mut self, text: &str) {
+        let filter = match text.parse::<LogFilter>() {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.log_filter_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.log_filter_error = None;
+        self.log_filter_input = filter.to_string();
+        self.runtime_config.log_filter = filter.clone();
+        self.save_runtime_config();
+        let _ = self.ui_tx.send(UiToNet::SetLogFilter { filter });
+    }
+
+    /// Check connection health against the rules in `text`, a JSON list of
+    /// [`AlertRule`], and keep them for next time; rules that don't parse
+    /// are shown instead
+    pub fn set_alert_rules(&mut self, text: &str) {
+        let rules: Vec<AlertRule> = match serde_json::from_str(text) {
+            Ok(rules) => rules,
+            Err(e) => {
+                self.alert_rules_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.alert_rules_error = None;
+        self.ui_config.alert_rules = Some(rules.clone());
+        if let Some(path) = &self.ui_config_path {
+            if let Err(e) = self.ui_config.save(path) {
+                tracing::warn!("Failed to save UI config: {}", e);
+            }
+        }
+        let _ = self.ui_tx.send(UiToNet::SetAlertRules { rules });
+    }
+
+    /// Keep the runtime config, including changes made in the other
+    /// settings, for next time
+    fn save_runtime_config(&self) {
+        if let Some(path) = &self.runtime_config_path {
+            if let Err(e) = self.runtime_config.save(path) {
+                tracing::warn!("Failed to save runtime config: {}", e);
+            }
+        }
+    }
+
+    /// Open the advanced config editor on the config in effect
+    pub fn open_config_editor(&mut self) {
+        self.config_json = self.runtime_config.to_json();
+        self.config_errors.clear();
+        self.config_report = None;
+        self.show_config_editor = true;
+    }
+
+    /// Apply the JSON in the config editor and keep it for next time; a
+    /// config that doesn't parse or check out is shown with what's wrong
+    pub fn apply_config(&mut self) {
+        let config = match RuntimeConfig::parse(&self.config_json) {
+            Ok(config) => config,
+            Err(errors) => {
+                self.config_errors = errors;
+                self.config_report = None;
+                return;
+            }
+        };
+        self.config_errors.clear();
+        // The log settings show the same filter
+        self.log_filter_input = config.log_filter.to_string();
+        self.runtime_config = config.clone();
+        self.save_runtime_config();
+        let _ = self.ui_tx.send(UiToNet::ApplyConfig { config });
+    }
+
+    /// The alert warning on screen, if any
+    pub fn alert(&self) -> Option<&str> {
+        self.alert.as_deref()
+    }
+
+    /// Write the next minute of logs at trace level to a file for a bug report
+    pub fn capture_trace(&mut self) {
+        if self.capture_until.is_none() {
+            self.capture_until = Some(std::time::Instant::now() + CAPTURE_DURATION);
+            let _ = self.ui_tx.send(UiToNet::CaptureTrace);
+        }
+    }
+
+    /// Show a rejected move on the board if we are in its game, else as a banner
+    fn show_game_error(&mut self, error: &GameErrorKind, in_game: bool) {
+        let message = game_error_message(error, self.board_widget.get_board_size());
+        match error.illegal_at() {
+            // A rule violation belongs on the board, not in a banner
+            Some(coord) if in_game => {
+                self.board_widget.flash(coord);
+                self.move_hint = Some(message);
+            }
+            _ => self.error_msg = Some(message),
+        }
+    }
+
+    /// Play a typed move such as "D4" or "pass", or say why it can't be read
+    pub fn play_typed_move(&mut self, text: &str) {
+        let View::Game { game_state, .. } = &self.current_view else {
+            return;
+        };
+        match coords::parse_move(text, game_state.board_size) {
+            Ok(mv) => {
+                self.move_input.clear();
+                self.move_input_error = None;
+                self.play_move(mv);
+            }
+            Err(e) => self.move_input_error = Some(e.to_string()),
+        }
+    }
+
+    /// Answer the resume offer as the prompt's buttons do
+    pub fn answer_resume_offer(&mut self, resume: bool) {
+        if let Some((game_id, _, _)) = self.resume_offer.take() {
+            let msg = if resume {
+                UiToNet::ResumeGame { game_id }
+            } else {
+                UiToNet::DiscardResumable { game_id }
+            };
+            let _ = self.ui_tx.send(msg);
+        }
+    }
+
+    /// Play `mv` as the Pass and Resign buttons do, first asking about
+    /// one that looks like a mis-click
+    pub fn ask_to_play(&mut self, mv: Move) {
+        let View::Game { game_state, our_color, .. } = &self.current_view else {
+            return;
+        };
+        let mover = our_color.unwrap_or(game_state.current_player);
+        let lead = self.ownership.current().map(|map| {
+            let score = expected_score(map, game_komi(self.game_settings.as_ref(), game_state.board_size));
+            if mover == Color::Black { score } else { -score }
+        });
+        let enabled = !self.ui_config.skip_move_confirmations;
+        match move_confirm::confirmation(&mv, game_state.moves.len(), lead, enabled) {
+            Some(confirm) => self.move_confirm = Some(confirm),
+            None => self.play_move(mv),
+        }
+    }
+
+    /// Answer the pass or resignation confirmation as its buttons do
+    pub fn answer_move_confirmation(&mut self, play: bool) {
+        if let Some(confirm) = self.move_confirm.take() {
+            if play {
+                self.play_move(confirm.mv());
+            }
+        }
+    }
+
+    /// Show a lobby game's terms before joining it
+    pub fn ask_to_join(&mut self, game: GameInfo) {
+        self.join_confirm = Some(game);
+    }
+
+    /// Answer the join confirmation as its buttons do; games under rules
+    /// we don't know are never joined
+    pub fn answer_join_confirmation(&mut self, join: bool) {
+        let Some(game) = self.join_confirm.take() else {
+            return;
+        };
+        if !join {
+            return;
+        }
+        match game.terms.check_supported() {
+            Ok(()) => {
+                let _ = self.ui_tx.send(UiToNet::JoinGame { game_id: game.id });
+            }
+            Err(e) => self.error_msg = Some(e.to_string()),
+        }
+    }
+
+    /// Ask the opponent to take back our last move, and theirs after it
+    /// when it is our turn again, as the undo button does
+    pub fn request_undo(&mut self) {
+        let View::Game { game_state, our_color, practice: false, .. } = &self.current_view else {
+            return;
+        };
+        if self.undo_pending || game_state.moves.is_empty() {
+            return;
+        }
+        let moves = if *our_color == Some(game_state.current_player) && game_state.moves.len() >= 2 { 2 } else { 1 };
+        self.undo_pending = true;
+        let _ = self.ui_tx.send(UiToNet::RequestUndo { moves });
+    }
+
+    /// Accept or decline the opponent's undo request, as its buttons do
+    pub fn answer_undo(&mut self, accept: bool) {
+        if self.undo_request.take().is_some() {
+            let _ = self.ui_tx.send(UiToNet::AnswerUndo { accept });
+        }
+    }
+
+    /// Ask the worker to save open games and stop, waiting briefly for it
+    fn shutdown_worker(&mut self) {
+        if self.ui_tx.send(UiToNet::Shutdown).is_err() {
+            return;
+        }
+        let deadline = std::time::Instant::now() + SHUTDOWN_TIMEOUT;
+        while let Some(left) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match self.ui_rx.recv_timeout(left) {
+                Ok(NetToUi::ShutdownAck) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// Send a message to the network component
+    #[allow(dead_code)]
+    #[cfg(not(test))]
+    pub fn ui_send(&self, msg: UiToNet) {
+        let _ = self.ui_tx.send(msg);
+    }
+
+    /// Send a message to the network worker (for testing)
+    #[cfg(test)]
+    pub fn ui_send(&mut self, msg: UiToNet) {
+        let _ = self.ui_tx.send(msg);
+    }
+    
+    /// Get the last received message from the network worker (for testing)
+    #[cfg(test)]
+    pub fn recv_last(&mut self) -> Option<NetToUi> {
+        let mut last = None;
+        while let Ok(msg) = self.ui_rx.try_recv() {
+            last = Some(msg);
+        }
+        last
+    }
+    
+    /// Wait for and receive a specific message (for testing)
+    #[cfg(test)]
+    pub fn blocking_recv(&mut self) -> Option<NetToUi> {
+        self.ui_rx.recv().ok()
+    }
+    
+    /// Get all received messages for assertion (for testing)
+    #[cfg(test)]
+    pub fn recv_log(&mut self) -> Vec<NetToUi> {
+        let mut messages = Vec::new();
+        while let Ok(msg) = self.ui_rx.try_recv() {
+            messages.push(msg);
+        }
+        messages
+    }
+    
+    /// Get current game ID (for testing)
+    #[cfg(test)]
+    pub fn get_current_game_id(&self) -> Option<String> {
+        match &self.current_view {
+            View::Game { game_id, .. } => Some(game_id.clone()),
+            View::ScoreDialog { game_id, .. } => Some(game_id.clone()),
+            View::Lobby { game_id } => Some(game_id.clone()),
+            _ => None,
+        }
+    }
+
+    /// Process network messages for testing
+    #[cfg(test)]
+    pub fn update(&mut self) {
+        self.handle_network_messages();
+    }
+    
+    /// Handle the AcceptScore message for testing
+    #[cfg(test)]
+    pub fn handle_accept_score(&mut self) {
+        if let View::ScoreDialog { .. } = &self.current_view {
+            self.config.games_finished += 1;
+            self.current_view = View::default();
+        }
+    }
+    
+    // Method removed to avoid duplication - using the existing tick_headless_test
+
+    /// Show the board of the game we waited in the lobby for
+    fn leave_lobby(&mut self) {
+        let View::Lobby { game_id } = &self.current_view else {
+            return;
+        };
+        let game_id = game_id.clone();
+        self.current_view = View::Game {
+            game_id: game_id.clone(),
+            game_state: self.joined_game_state(),
+            our_color: None, // We'll set this based on move order
+            practice: false,
+        };
+        // Moves may have been played before we got here
+        self.request_game_state(game_id);
+        let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+    }
+    
+    /// Ask the worker for the full state of `game_id`
+    fn request_game_state(&mut self, game_id: String) {
+        self.snapshot_requested = true;
+        let _ = self.ui_tx.send(UiToNet::GetGameState { game_id });
+    }
+    
+    /// Drain pending worker messages, returning how many were handled
+    fn handle_network_messages(&mut self) -> usize {
+        self.rx_queue_length = self.ui_rx.len();
+        let mut handled = 0;
+        
+        while let Ok(msg) = self.ui_rx.try_recv() {
+            handled += 1;
+            #[cfg(feature = "headless")]
+            println!("App received message: {:?}", msg);
+            
+            match msg {
+                NetToUi::WorkerReady { node_id, ticket } => {
+                    tracing::info!("Network worker ready as {}", node_id);
+                    self.node_id = Some(node_id);
+                    if let Some(ticket) = ticket {
+                        self.current_ticket = Some(ticket);
+                    }
+                    self.worker_ready = true;
+                    for action in self.pending_actions.drain(..) {
+                        let _ = self.ui_tx.send(action);
+                    }
+                }
+                NetToUi::GamesUpdated { page } => {
+                    // Pushed updates are only applied live when auto-refresh is on
+                    let asked = self.refresh_requested || self.games_page_requested;
+                    if self.config.auto_refresh || asked {
+                        self.refresh_requested = false;
+                        self.games_page_requested = false;
+                        if let View::MainMenu { available_games, creating_game: _, board_size: _ } = &mut self.current_view {
+                            if page.offset == 0 {
+                                // A pushed first page leaves a list scrolled past it alone
+                                if asked || available_games.len() <= GAMES_PAGE_SIZE {
+                                    *available_games = page.games;
+                                }
+                                self.games_total = page.total;
+                            } else if page.offset == available_games.len() {
+                                available_games.extend(page.games);
+                                self.games_total = page.total;
+                            }
+                        }
+                    }
+                }
+                NetToUi::GameEvent { event } => {
+                    match &event {
+                        p2pgo_core::GameEvent::MoveMade { mv, by } => {
+                            #[cfg(feature = "headless")]
+                            println!("Move made event received: {:?}", mv);
+                            // Playing on settles any undo request either way
+                            self.undo_pending = false;
+                            self.undo_request = None;
+                            
+                            if let View::Game { game_id, game_state, our_color, practice: false, .. } = &mut self.current_view {
+                                match event_filter::apply_move_event(game_state, mv, *by) {
+                                    MoveOutcome::Applied => {
+                                        // Update last blob hash for debug overlay - use move type description
+                                        self.last_blob_hash = Some(format!("{:?}", mv));
+                                        // Once we have moved, any premove has been played
+                                        let by_us = *our_color == Some(*by);
+                                        if by_us {
+                                            self.premove = None;
+                                            self.board_widget.set_premove(None);
+                                        }
+                                        if *mv != Move::Resign {
+                                            let notice = TurnNotice::new(game_id, game_state.moves.len(), mv, game_state.board_size);
+                                            self.turn_alerts.moved(notice, by_us, self.window_focused, self.ui_config.turn_alerts);
+                                        }
+                                    }
+                                    MoveOutcome::Duplicate => {
+                                        tracing::debug!("Dropping duplicate move event {:?} by {:?}", mv, by);
+                                    }
+                                    MoveOutcome::OutOfOrder => {
+                                        // Possible desync: resync once rather than per dropped event
+                                        tracing::debug!("Dropping out-of-order move event {:?} by {:?}", mv, by);
+                                        if !self.snapshot_requested {
+                                            let game_id = game_id.clone();
+                                            self.request_game_state(game_id);
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        p2pgo_core::GameEvent::GameEnded { .. } => {
+                            if let View::Game { game_id, .. } = &self.current_view {
+                                self.turn_alerts.left(game_id);
+                            }
+                            self.move_confirm = None;
+                        },
+                        // The channel's phase decides what the game shows next
+                        p2pgo_core::GameEvent::PhaseChanged { phase } => {
+                            // Past waiting for the opponent, the board replaces the lobby
+                            if *phase != GamePhase::AwaitingOpponent {
+                                self.leave_lobby();
+                            }
+                            match phase {
+                                GamePhase::Scoring => self.open_score_dialog(None, &EndReason::DoublePass),
+                                GamePhase::Finished { result } => match &result.reason {
+                                    EndReason::Resignation => self.open_score_dialog(result.winner, &result.reason),
+                                    // Scored in the dialog already open
+                                    EndReason::DoublePass => {}
+                                    EndReason::Timeout | EndReason::Forfeit(_) => {
+                                        let winner = result.winner.map(|c| format!(" {:?} wins.", c)).unwrap_or_default();
+                                        self.error_msg = Some(format!("Game over: {}.{}", result.reason, winner));
+                                    }
+                                },
+                                GamePhase::AwaitingOpponent | GamePhase::Active => {}
+                            }
+                        },
+                        p2pgo_core::GameEvent::PeerWarning { reason, .. } => {
+                            self.error_msg = Some(reason.clone());
+                        },
+                        p2pgo_core::GameEvent::UndoRequested { moves, .. } => {
+                            if matches!(self.current_view, View::Game { practice: false, .. }) {
+                                self.undo_request = Some(*moves);
+                            }
+                        },
+                        p2pgo_core::GameEvent::UndoAnswered { accepted } => {
+                            self.undo_pending = false;
+                            if !accepted {
+                                self.error_msg = Some("Your opponent declined the undo".to_string());
+                            }
+                        },
+                        p2pgo_core::GameEvent::MovesUndone { moves } => {
+                            // Snapshots never go back, so take the moves back here too
+                            if let View::Game { game_id, game_state, practice: false, .. } = &mut self.current_view {
+                                if let Err(e) = game_state.undo(*moves as usize) {
+                                    tracing::warn!("Failed to take back {} moves on screen: {}", moves, e);
+                                }
+                                self.turn_alerts.rewound(game_id, game_state.moves.len());
+                                if let Some(graph) = &mut self.win_rate {
+                                    graph.truncate(game_state.moves.len());
+                                }
+                            }
+                        },
+                        p2pgo_core::GameEvent::Presence { considering, last_active, .. } => {
+                            if matches!(self.current_view, View::Game { practice: false, .. }) {
+                                self.opponent_presence = Some(OpponentPresence {
+                                    considering: *considering,
+                                    last_active: *last_active,
+                                    received: std::time::Instant::now(),
+                                });
+                            }
+                        },
+                        p2pgo_core::GameEvent::Clock { snapshot } => {
+                            let now = std::time::Instant::now();
+                            match &mut self.clock {
+                                Some(clock) => clock.update(*snapshot, now),
+                                None => self.clock = Some(ClockDisplay::new(*snapshot, now)),
+                            }
+                        },
+                        p2pgo_core::GameEvent::ByoYomiPeriodUsed { player, periods_left } => {
+                            if let Some(clock) = &mut self.clock {
+                                clock.period_used(*player, std::time::Instant::now());
+                            }
+                            let ours = matches!(&self.current_view, View::Game { our_color: Some(c), .. } if c == player);
+                            self.turn_alerts.period_used(*periods_left, ours, self.ui_config.turn_alerts);
+                            let text = match periods_left {
+                                0 => format!("{:?} ran out of byo-yomi periods", player),
+                                1 => format!("{:?} used a byo-yomi period: last period", player),
+                                n => format!("{:?} used a byo-yomi period: {} left", player, n),
+                            };
+                            self.toast = Some((text, std::time::Instant::now()));
+                        },
+                        p2pgo_core::GameEvent::Annotated { move_index, annotation } => {
+                            // The channel checked the point against the board already
+                            let board_size = self.board_widget.get_board_size();
+                            if let Err(e) = self.annotations.apply(*move_index, annotation, board_size) {
+                                tracing::debug!("Dropping annotation {:?}: {}", annotation, e);
+                            }
+                        },
+                        _ => {
+                            // Request ghost moves after the move is applied
+                            let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+                        }
+                    }
+                }
+                NetToUi::GameJoined { game_id, settings } => {
+                    #[cfg(feature = "headless")]
+                    println!("Game joined: {}, transitioning to Lobby", game_id);
+                    // The host's board, which may not be our default size
+                    if settings.board_size != self.board_widget.get_board_size() {
+                        self.board_widget = BoardWidget::new(settings.board_size);
+                    }
+                    self.game_settings = Some(settings);
+                    self.pass_hint = None;
+                    self.ghost_pass = false;
+                    self.move_confirm = None;
+                    self.current_view = View::Lobby { game_id };
+                    self.snapshot_requested = false;
+                    self.annotations.clear();
+                    self.opponent_presence = None;
+                    self.clock = None;
+                    self.undo_pending = false;
+                    self.undo_request = None;
+                    // Request initial ghost moves when joining a game
+                    let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+                }
+                NetToUi::MatchFound { game_id, opponent } => {
+                    tracing::info!("Matched with {} in {}", opponent, game_id);
+                    self.queue_searching = false;
+                }
+                NetToUi::QueueLeft => {
+                    self.queue_searching = false;
+                }
+                NetToUi::GameStateSnapshot { game_id: snapshot_id, state } => {
+                    self.snapshot_requested = false;
+                    // The worker's state is authoritative: replace ours wholesale
+                    if let View::Game { game_id, game_state, .. } = &mut self.current_view {
+                        if *game_id == snapshot_id && event_filter::should_accept_snapshot(game_state, &state) {
+                            tracing::debug!("Applying game state snapshot with {} moves", state.moves.len());
+                            *game_state = state;
+                        }
+                    }
+                }
+                NetToUi::JoinRequested { game_id, request_id, profile } => {
+                    tracing::info!("{} asks to join {}", profile.name, game_id);
+                    self.join_requests.push((game_id, request_id, profile));
+                }
+                NetToUi::JoinDeclined { game_id, reason } => {
+                    self.error_msg = Some(format!("Could not join {}: {}", game_id, reason));
+                }
+                NetToUi::SpectateOffered { game_id, reason } => {
+                    // There is no spectator view yet, so just tell the user why
+                    self.error_msg = Some(format!("Could not join {}: {}. Only spectating is possible.", game_id, reason));
+                }
+                NetToUi::PassSuggested { game_id, score_estimate } => {
+                    // A suggestion for a game no longer on screen is stale
+                    let shown = match &self.current_view {
+                        View::Game { game_id: shown, .. }
+                        | View::ScoreDialog { game_id: shown, .. }
+                        | View::Lobby { game_id: shown } => *shown == game_id,
+                        _ => false,
+                    };
+                    if shown {
+                        self.pass_hint = Some(score_estimate);
+                    }
+                }
+                NetToUi::TrainingCopiesDeleted { game_id, local } => {
+                    let text = if local {
+                        format!("Deleted training copy of {} and asked peers to drop it", game_id)
+                    } else {
+                        format!("Asked peers to drop {} from training", game_id)
+                    };
+                    self.toast = Some((text, std::time::Instant::now()));
+                }
+                NetToUi::ArchiveRepaired { report } => {
+                    self.toast = Some((report.summary(), std::time::Instant::now()));
+                }
+                NetToUi::TraceCaptureStarted { path } => {
+                    self.capture_until = Some(std::time::Instant::now() + CAPTURE_DURATION);
+                    let text = format!("Capturing trace logs for {} seconds to {}", CAPTURE_DURATION.as_secs(), path.display());
+                    self.toast = Some((text, std::time::Instant::now()));
+                }
+                NetToUi::TraceCaptureFailed { message } => {
+                    self.capture_until = None;
+                    self.error_msg = Some(format!("Failed to start the trace capture: {}", message));
+                }
+                NetToUi::SubsystemRestarted { name } => {
+                    let text = format!("Restarted the {} after it stopped responding", name);
+                    self.toast = Some((text, std::time::Instant::now()));
+                }
+                NetToUi::SubsystemFailed { name } => {
+                    self.error_msg = Some(format!("The {} keeps stopping; restart P2P Go to get it working again", name));
+                }
+                NetToUi::Alert { message } => {
+                    self.alert = Some(message);
+                }
+                NetToUi::TraceCaptureFinished { report } => {
+                    self.capture_until = None;
+                    let cut = if report.truncated { " (cut short at the size limit)" } else { "" };
+                    let text = format!("Trace log saved to {}{}; attach it to your bug report", report.path.display(), cut);
+                    self.toast = Some((text, std::time::Instant::now()));
+                }
+                NetToUi::Rejections { counts } => {
+                    self.rejections = counts;
+                }
+                NetToUi::TrainingMeasured { report } => {
+                    self.measuring_training = false;
+                    self.training_report = Some(report);
+                }
+                NetToUi::SelfPlayTrained { report } => {
+                    self.measuring_training = false;
+                    let text = format!("Trained on self-play, loss {:.3}; the AI uses the new weights from its next load", report.eval_loss);
+                    self.toast = Some((text, std::time::Instant::now()));
+                    self.training_report = Some(report);
+                }
+                NetToUi::DatasetInspected { result } => {
+                    self.inspecting_dataset = false;
+                    match result {
+                        Ok(stats) => self.dataset_stats = Some(stats),
+                        Err(e) => self.error_msg = Some(format!("Failed to load training games: {}", e)),
+                    }
+                }
+                NetToUi::ConfigApplied { result } => match result {
+                    Ok(report) => self.config_report = Some(report),
+                    Err(message) => {
+                        self.config_report = None;
+                        self.config_errors = vec![ConfigError { field: None, message }];
+                    }
+                },
+                NetToUi::TrainingPaused { reason } => {
+                    self.training_paused = reason;
+                }
+                NetToUi::RelayModeChanged { preset, limits, drained } => {
+                    if drained > 0 {
+                        let text = format!("Closing {} connections over the {} limits", drained, preset.label());
+                        self.toast = Some((text, std::time::Instant::now()));
+                    }
+                    self.relay_mode = Some((preset, limits));
+                }
+                NetToUi::RelayUsage { usage } => {
+                    self.relay_usage = usage;
+                }
+                NetToUi::CreditsUpdated { balance, history } => {
+                    self.credits_balance = Some(balance);
+                    self.credits_history = history;
+                }
+                NetToUi::RatingUpdated { current, history } => {
+                    self.rating = Some(current);
+                    self.rating_history = history;
+                }
+                NetToUi::PremoveDiscarded { mv, reason } => {
+                    self.premove = None;
+                    self.board_widget.set_premove(None);
+                    let label = match mv {
+                        Move::Place(coord) => coord.display_label(self.board_widget.get_board_size()),
+                        other => format!("{:?}", other),
+                    };
+                    self.error_msg = Some(format!("Premove {} discarded: {}", label, reason));
+                }
+                NetToUi::GameIdle { game_id, idle_for } => {
+                    self.idle_prompt = Some((game_id, idle_for));
+                }
+                NetToUi::IdentityLocked { error } => {
+                    self.identity.unlock_prompt = true;
+                    self.identity.unlock_error = error;
+                }
+                NetToUi::IdentityStatus { persistent, encrypted } => {
+                    self.identity.persistent = persistent;
+                    self.identity.encrypted = encrypted;
+                }
+                NetToUi::IdentityExported { data } => {
+                    self.identity.export = Some(data);
+                }
+                NetToUi::IdentityImported { node_id } => {
+                    self.identity.import_input.clear();
+                    self.identity.pending_node_id = Some(node_id);
+                }
+                NetToUi::ResumableGame { game_id, move_count, opponent } => {
+                    self.resume_offer = Some((game_id, move_count, opponent));
+                }
+                NetToUi::GameRestored { game_id, color, move_count, recovered_from_divergence } => {
+                    // A restored game has moves already, so don't wait in the lobby for one
+                    if matches!(&self.current_view, View::Lobby { game_id: g } if *g == game_id) {
+                        self.current_view = View::Game {
+                            game_id: game_id.clone(),
+                            game_state: self.joined_game_state(),
+                            // The saved seat, not a guess from whose turn it is
+                            our_color: Some(color),
+                            practice: false,
+                        };
+                        self.request_game_state(game_id);
+                    } else if let View::Game { game_id: shown, our_color, .. } = &mut self.current_view {
+                        // Its phase may have shown the board already
+                        if *shown == game_id {
+                            *our_color = Some(color);
+                        }
+                    }
+                    let text = if recovered_from_divergence {
+                        format!("Game restored after {} moves; its save was damaged and was recovered from the move log", move_count)
+                    } else {
+                        format!("Game restored after {} moves", move_count)
+                    };
+                    self.toast = Some((text, std::time::Instant::now()));
+                }
+                NetToUi::UnreadMoves { game_id, count } => {
+                    self.unread_moves = (count > 0).then_some((game_id, count));
+                }
+                NetToUi::GameLeft => {
+                    if let View::Game { game_id, .. } = &self.current_view {
+                        self.turn_alerts.left(game_id);
+                    }
+                    self.set_win_rate(false);
+                    self.current_view = View::default();
+                    self.opponent_presence = None;
+                    self.clock = None;
+                    self.undo_pending = false;
+                    self.undo_request = None;
+                    self.snapshot_requested = false;
+                    self.idle_prompt = None;
+                    self.premove = None;
+                    self.board_widget.set_premove(None);
+                    self.channel_metrics.clear();
+                    self.correspondence = false;
+                    self.teaching = false;
+                    self.teaching_tool = None;
+                    self.annotations.clear();
+                    self.unread_moves = None;
+                }
+                NetToUi::GameError { game_id, error } => {
+                    let in_game = matches!(&self.current_view, View::Game { game_id: g, .. } if *g == game_id);
+                    self.show_game_error(&error, in_game);
+                    if in_game && error.recovery() == Recovery::Resync && !self.snapshot_requested {
+                        self.request_game_state(game_id);
+                    }
+                }
+                NetToUi::Error { message } => {
+                    // A request the worker refused is never answered
+                    self.undo_pending = false;
+                    self.measuring_training = false;
+                    self.error_msg = Some(message);
+                }
+                NetToUi::ConnectionStatus { .. } => {}
+                NetToUi::ShutdownAck => {
+                    tracing::debug!("Received shutdown acknowledgment from network worker");
+                }
+                NetToUi::NodeId { node_id } => {
+                    self.node_id = Some(node_id);
+                }
+                NetToUi::Invite { link } => {
+                    self.invite_link = Some(link);
+                    self.toast = Some(("Invite link copied".to_string(), std::time::Instant::now()));
+                }
+                NetToUi::BoardShared { game_id, link } => {
+                    tracing::info!("Practice board shared as {}", game_id);
+                    self.invite_link = Some(link);
+                    self.toast = Some(("Link copied: friends can now watch this board".to_string(), std::time::Instant::now()));
+                }
+                NetToUi::Ticket { ticket } => {
+                    self.current_ticket = Some(ticket);
+                }
+                NetToUi::NetReport { report } => {
+                    self.nat_report = Some(report);
+                }
+                NetToUi::ChannelMetrics { game_id, metrics } => {
+                    self.channel_metrics.insert(game_id, metrics);
+                }
+                NetToUi::TrafficSample { sample } => {
+                    self.traffic.push(sample);
+                }
+                NetToUi::TagAck => {
+                    tracing::debug!("Move tag stored successfully");
+                }
+                NetToUi::HeatMap { position_hash, map } => {
+                    self.heat_map.receive(position_hash, map);
+                }
+                NetToUi::Outlook { position_hash, outlook } => {
+                    self.heat_map.receive_outlook(position_hash, outlook);
+                }
+                NetToUi::Ownership { position_hash, map } => {
+                    self.ownership.receive(position_hash, map);
+                }
+                NetToUi::GameReport { game_id, report } => {
+                    self.game_report = Some((game_id, report));
+                }
+                NetToUi::LadderMove { mv } => {
+                    self.play_ladder_move(mv);
+                }
+                NetToUi::EvalPoint { game_id, move_number, value } => {
+                    if let Some(graph) = &mut self.win_rate {
+                        graph.receive(&game_id, move_number, value);
+                    }
+                }
+                NetToUi::ImportProgress { progress } => {
+                    if let View::Archive { browser } = &mut self.current_view {
+                        browser.import_progress(progress);
+                    }
+                }
+                NetToUi::ImportFinished { result } => {
+                    let report = match result {
+                        Ok(report) => {
+                            self.toast = Some((report.summary(), std::time::Instant::now()));
+                            Some(report)
+                        }
+                        Err(e) => {
+                            self.error_msg = Some(format!("Failed to import SGF: {}", e));
+                            None
+                        }
+                    };
+                    if let View::Archive { browser } = &mut self.current_view {
+                        if let Err(e) = browser.import_finished(report) {
+                            self.error_msg = Some(format!("Failed to list imported games: {}", e));
+                        }
+                    }
+                }
+                NetToUi::GhostMoves(moves) => {
+                    tracing::debug!("Received {} ghost move suggestions", moves.len());
+                    self.ghost_pass = moves.first() == Some(&Move::Pass);
+                    // Only points can be drawn as stones
+                    let coords: Vec<p2pgo_core::Coord> = moves.into_iter().filter_map(|mv| match mv {
+                        Move::Place(coord) => Some(coord),
+                        _ => None,
+                    }).collect();
+                    if let View::Editor { editor } = &mut self.current_view {
+                        editor.analysis = coords;
+                    } else {
+                        self.board_widget.set_ghost_stones(coords);
+                    }
+                }
                 NetToUi::ScoreCalculated { score_proof } => {
                     // Transition to score dialog with the calculated score
                     if let View::Game { game_id, game_state, .. } = &self.current_view {
@@ -364,174 +2253,1212 @@ impl App {
                     }
                 }
                 
-                NetToUi::ScoreAcceptedByBoth { score_proof } => {
-                    // Update score dialog to show accepted score
-                    if let View::ScoreDialog { game_id, game_state, dead_stones, .. } = &self.current_view {
-                        self.current_view = View::ScoreDialog {
-                            game_id: game_id.clone(),
-                            game_state: game_state.clone(),
-                            score_proof,
-                            dead_stones: dead_stones.clone(),
-                            score_pending: false,
-                            score_accepted: true,
-                        };
+                NetToUi::ScoreAcceptedByBoth { score_proof } => {
+                    // Update score dialog to show accepted score
+                    if let View::ScoreDialog { game_id, game_state, dead_stones, .. } = &self.current_view {
+                        self.current_view = View::ScoreDialog {
+                            game_id: game_id.clone(),
+                            game_state: game_state.clone(),
+                            score_proof,
+                            dead_stones: dead_stones.clone(),
+                            score_pending: false,
+                            score_accepted: true,
+                        };
+                    }
+                }
+                NetToUi::ScoreTimeout { board_size } => {
+                    // Score acceptance timed out after 3 minutes
+                    self.error_msg = Some(format!("Score acceptance timed out for {}×{} game. Game will be discarded.", board_size, board_size));
+                    // Return to main menu
+                    self.current_view = View::default();
+                }
+                NetToUi::Debug(message) => {
+                    tracing::debug!("Debug message: {}", message);
+                }
+                NetToUi::GameAdvertised { game_id, host_id, board_size } => {
+                    // The worker pushes GamesUpdated itself when the list changes
+                    tracing::info!("Game advertisement received: {} ({}×{}) from {}", game_id, board_size, board_size, host_id);
+                }
+            }
+        }
+        self.request_heat_map();
+        self.request_ownership();
+        self.refresh_territory();
+        
+        handled
+    }
+
+    /// Resignation advice comes with the heat map, for the position on screen only
+    fn resign_banner(&self) -> bool {
+        self.show_heat_map && matches!(self.heat_map.outlook(), Some(outlook) if outlook.resign_recommended)
+    }
+
+    /// Show or hide the heat map over the game board
+    pub fn set_heat_map(&mut self, show: bool) {
+        self.show_heat_map = show;
+        if show {
+            self.request_heat_map();
+        } else {
+            self.heat_map.clear();
+        }
+    }
+
+    /// Ask the worker for the heat map of the position on screen, once per position
+    fn request_heat_map(&mut self) {
+        if !self.show_heat_map {
+            return;
+        }
+        if let View::Game { game_id, game_state, practice: false, .. } = &self.current_view {
+            let position_hash = heat_map::position_hash(game_state);
+            if self.heat_map.want(position_hash) {
+                let _ = self.ui_tx.send(UiToNet::RequestHeatMap { game_id: game_id.clone(), position_hash });
+            }
+        }
+    }
+
+    /// Connect with a pasted ticket, or join the game of an invite link
+    pub fn connect_with(&mut self, text: &str) {
+        let text = text.trim();
+        if !Invite::is_link(text) {
+            let _ = self.ui_tx.send(UiToNet::ConnectByTicket { ticket: text.to_string() });
+            return;
+        }
+        match Invite::parse(text) {
+            Ok(invite) => {
+                tracing::info!("Joining {}'s game {} by invite", invite.host_name, invite.game_id);
+                let _ = self.ui_tx.send(UiToNet::JoinInvite { invite });
+            }
+            Err(e) => self.error_msg = Some(e.to_string()),
+        }
+    }
+
+    /// Show or hide the ownership estimate over the game board
+    pub fn set_ownership(&mut self, show: bool) {
+        self.show_ownership = show;
+        if show {
+            self.request_ownership();
+        } else {
+            self.ownership.clear();
+        }
+    }
+
+    /// Show or hide the territory estimate over the game board, and
+    /// remember the choice
+    pub fn set_territory(&mut self, show: bool) {
+        if self.ui_config.territory_estimate != show {
+            self.ui_config.territory_estimate = show;
+            if let Some(path) = &self.ui_config_path {
+                if let Err(e) = self.ui_config.save(path) {
+                    tracing::warn!("Failed to save UI config: {}", e);
+                }
+            }
+        }
+        self.refresh_territory();
+    }
+
+    /// Estimate the territory of the position on screen, once per position;
+    /// it is quick enough to skip the worker
+    fn refresh_territory(&mut self) {
+        let View::Game { game_state, .. } = &self.current_view else {
+            self.territory = None;
+            return;
+        };
+        if !self.ui_config.territory_estimate {
+            self.territory = None;
+            return;
+        }
+        let position_hash = heat_map::position_hash(game_state);
+        if self.territory.as_ref().map(|(hash, _)| *hash) != Some(position_hash) {
+            self.territory = Some((position_hash, TerritoryEstimate::of(game_state)));
+        }
+    }
+
+    /// Show the next board overlay in turn: none, the heat map, ownership,
+    /// then the territory estimate. Practice games only have the last.
+    pub fn cycle_overlay(&mut self) {
+        let View::Game { practice, .. } = &self.current_view else {
+            return;
+        };
+        let analysed = !*practice;
+        let shown = [self.show_heat_map, self.show_ownership, self.ui_config.territory_estimate];
+        let available = [analysed, analysed, true];
+        let current = shown.iter().position(|on| *on);
+        let next = (current.map_or(0, |i| i + 1)..3).find(|&i| available[i]);
+        self.set_heat_map(next == Some(0));
+        self.set_ownership(next == Some(1));
+        self.set_territory(next == Some(2));
+    }
+
+    /// Show or hide the win-rate graph under the game board
+    pub fn set_win_rate(&mut self, show: bool) {
+        if !show {
+            if self.win_rate.take().is_some() {
+                let _ = self.ui_tx.send(UiToNet::StopWinRate);
+            }
+            return;
+        }
+        if let View::Game { game_id, game_state, practice: false, .. } = &self.current_view {
+            self.win_rate = Some(WinRateGraph::new(game_id.clone()));
+            let _ = self.ui_tx.send(UiToNet::FollowWinRate { game_id: game_id.clone(), state: game_state.clone() });
+        }
+    }
+
+    /// Review an archived game, its win rate graphed as the worker evaluates it
+    fn enter_review(&mut self, browser: ArchiveBrowser, review: Review) {
+        self.win_rate = Some(WinRateGraph::new(review.id.clone()));
+        let _ = self.ui_tx.send(UiToNet::FollowWinRate { game_id: review.id.clone(), state: review.game().clone() });
+        self.current_view = View::Review { browser, review };
+    }
+
+    /// Ask the worker for the ownership of the position on screen, once per position
+    fn request_ownership(&mut self) {
+        if !self.show_ownership {
+            return;
+        }
+        if let View::Game { game_id, game_state, practice: false, .. } = &self.current_view {
+            let position_hash = heat_map::position_hash(game_state);
+            if self.ownership.want(position_hash) {
+                let _ = self.ui_tx.send(UiToNet::RequestOwnership { game_id: game_id.clone(), position_hash });
+            }
+        }
+    }
+
+    fn render_main_menu(&mut self, ui: &mut egui::Ui) {
+        let mut open_archive = false;
+        let mut open_puzzles = false;
+        let mut open_openings = None;
+        let mut open_editor = None;
+        let mut open_practice = None;
+        let mut open_ladder = None;
+        let mut open_wizard = false;
+        let mut log_action = None;
+        let mut apply_alert_rules = false;
+        let mut inspect_dataset = false;
+        let mut open_config_editor = false;
+        let mut save_config = false;
+        let mut pasted = None;
+        if let View::MainMenu { available_games, creating_game, board_size } = &mut self.current_view {
+            ui.heading("P2P Go");
+            
+            // Node ID section
+            ui.separator();
+            ui.label("Your node ID:");
+            if let Some(node_id) = &self.node_id {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new(node_id).monospace().strong().size(16.0));
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = node_id.clone());
+                    }
+                });
+                if !self.identity.persistent {
+                    ui.colored_label(egui::Color32::YELLOW, "Temporary ID: it will change on restart");
+                }
+                if let Some(rating) = &self.rating {
+                    let estimate = rating.estimate();
+                    ui.label(format!("Rating: {} ±{}", estimate.rating, estimate.deviation));
+                    ui.collapsing(format!("Rated games ({})", self.rating_history.len()), |ui| {
+                        egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                            for entry in self.rating_history.iter().rev() {
+                                let outcome = match entry.outcome {
+                                    GameOutcome::Win => "Win",
+                                    GameOutcome::Loss => "Loss",
+                                    GameOutcome::Draw => "Draw",
+                                };
+                                let opponent: String = entry.opponent.chars().take(8).collect();
+                                ui.label(format!("{:+.0}  {} vs {}", entry.delta(), outcome, opponent));
+                            }
+                        });
+                    });
+                }
+            } else {
+                ui.label("Loading...");
+            }
+            render_identity_settings(ui, &mut self.identity, &self.ui_tx);
+            if render_pass_settings(ui, &mut self.ui_config.pass_suggestions) {
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+                let _ = self.ui_tx.send(UiToNet::SetPassSuggestions { settings: self.ui_config.pass_suggestions });
+            }
+            let mut share_presence = !self.ui_config.hide_presence;
+            if ui.checkbox(&mut share_presence, "Show opponents when I'm looking at the board")
+                .on_hover_text("Never where on the board")
+                .changed()
+            {
+                self.ui_config.hide_presence = !share_presence;
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+            }
+            if ui.checkbox(&mut self.ui_config.coarse_move_times, "Hide exactly when I play")
+                .on_hover_text("Moves say when they were played to the nearest 5 seconds, and your first move goes out a moment late")
+                .changed()
+            {
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+                let _ = self.ui_tx.send(UiToNet::SetTimingPrivacy { enabled: self.ui_config.coarse_move_times });
+            }
+            let mut confirm_moves = !self.ui_config.skip_move_confirmations;
+            if ui.checkbox(&mut confirm_moves, "Ask before resigning or passing early")
+                .on_hover_text("Also before passing while ahead")
+                .changed()
+            {
+                self.ui_config.skip_move_confirmations = !confirm_moves;
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+            }
+            if ui.checkbox(&mut self.ui_config.territory_estimate, "Show a territory estimate during play")
+                .on_hover_text("Tints the board by who surrounds each area, with a rough score; F2 cycles the overlays")
+                .changed()
+            {
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+            }
+            if render_turn_alert_settings(ui, &mut self.ui_config.turn_alerts) {
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+            }
+            if let Some(preset) = render_relay_settings(ui, self.runtime_config.relay_preset, self.relay_mode) {
+                self.runtime_config.relay_preset = preset;
+                save_config = true;
+                self.ui_config.relay_participation = preset != RelayPreset::Player;
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+                let _ = self.ui_tx.send(UiToNet::SetRelayPreset { preset });
+            }
+            let mut tuning = self.runtime_config.channel_tuning;
+            if render_tuning_settings(ui, &mut tuning) {
+                self.runtime_config.channel_tuning = tuning;
+                save_config = true;
+                let _ = self.ui_tx.send(UiToNet::SetChannelTuning { tuning });
+            }
+            log_action = render_log_settings(ui, &mut self.log_filter_input, self.log_filter_error.as_deref(), self.capture_until);
+            apply_alert_rules = render_alert_settings(ui, &mut self.alert_rules_input, self.alert_rules_error.as_deref());
+            let mut governor = self.runtime_config.governor;
+            let training = render_training_settings(
+                ui,
+                self.ui_config.training_backend,
+                &mut governor,
+                self.training_report.as_ref(),
+                self.measuring_training,
+                self.training_paused,
+            );
+            if governor != self.runtime_config.governor {
+                self.runtime_config.governor = governor;
+                save_config = true;
+                let _ = self.ui_tx.send(UiToNet::SetGovernor { config: governor });
+            }
+            if let Some(backend) = training.backend {
+                self.ui_config.training_backend = backend;
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
+                }
+            }
+            if training.measure {
+                self.measuring_training = true;
+                let _ = self.ui_tx.send(UiToNet::MeasureTraining { backend: self.ui_config.training_backend });
+            }
+            if training.self_play {
+                self.measuring_training = true;
+                let _ = self.ui_tx.send(UiToNet::TrainOnSelfPlay { backend: self.ui_config.training_backend });
+            }
+            inspect_dataset = render_dataset_stats(ui, &mut self.dataset_input, self.dataset_stats.as_ref(), self.inspecting_dataset);
+            
+            ui.horizontal(|ui| {
+                if ui.button("Generate Ticket").clicked() {
+                    let _ = self.ui_tx.send(UiToNet::GetTicket);
+                }
+                if ui.button("Connect by Ticket").clicked() {
+                    self.show_ticket_modal = true;
+                }
+                if ui.button("Advanced Config").clicked() {
+                    open_config_editor = true;
+                }
+                if ui.button("Game Archive").clicked() {
+                    open_archive = true;
+                }
+                if ui.button("Puzzles").clicked() {
+                    open_puzzles = true;
+                }
+                if ui.button("Openings").clicked() {
+                    open_openings = Some(*board_size);
+                }
+                if ui.button("Board Editor").clicked() {
+                    open_editor = Some(*board_size);
+                }
+                if ui.button("Practice vs myself").on_hover_text("Play both colors here, without connecting").clicked() {
+                    open_practice = Some(*board_size);
+                }
+                if ui.button("Setup Wizard").clicked() {
+                    open_wizard = true;
+                }
+            });
+            ui.collapsing("Ladder", |ui| {
+                ui.label("Beat each AI opponent on 9x9 to unlock the next");
+                open_ladder = ladder_view::render_rungs(ui, self.ui_config.ladder_rung);
+            });
+            
+            // Paste ticket or invite input field with auto-connect on Enter
+            ui.horizontal(|ui| {
+                ui.label("Paste ticket or invite ↵");
+                let text_edit = ui.text_edit_singleline(&mut self.ticket_input);
+                
+                if text_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !self.ticket_input.trim().is_empty() {
+                    pasted = Some(std::mem::take(&mut self.ticket_input));
+                }
+            });
+            if let Some(ticket) = &self.current_ticket {
+                ui.horizontal(|ui| {
+                    // Check if ticket is a stub or has relay status
+                    let is_stub = loopback::is_ticket(ticket);
+                    let relay_status = ticket.len() > 50; // Real tickets are much longer than stub
+                    
+                    let label_text = if is_stub {
+                        egui::RichText::new("Generated Ticket (Local Mode):").color(egui::Color32::BLUE)
+                    } else if relay_status {
+                        egui::RichText::new("Generated Ticket (Network Ready):").color(egui::Color32::GREEN)
+                    } else {
+                        egui::RichText::new("Generated Ticket (Local Only):").color(egui::Color32::YELLOW)
+                    };
+                    ui.label(label_text);
+                    
+                    if ui.button("Copy Ticket").clicked() {
+                        ui.output_mut(|o| o.copied_text = ticket.clone());
                     }
+                });
+                ui.text_edit_singleline(&mut ticket.clone());
+            }
+            
+            match self.credits_balance {
+                Some(balance) => {
+                    ui.label(format!("Training credits: {}", balance));
+                    ui.collapsing(format!("Credit history ({})", self.credits_history.len()), |ui| {
+                        egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                            for entry in self.credits_history.iter().rev() {
+                                let what = match &entry.source {
+                                    CreditSource::Relay(receipt) => format!("Relayed {} KiB", receipt.bytes / 1024),
+                                    CreditSource::SharedGame { game_id } => format!("Shared game {}", game_id),
+                                    CreditSource::FederatedRound { round } => format!("Federated round {}", round),
+                                };
+                                ui.label(format!("+{}  {}", entry.credits, what));
+                            }
+                        });
+                    });
                 }
-                NetToUi::ScoreTimeout { board_size } => {
-                    // Score acceptance timed out after 3 minutes
-                    self.error_msg = Some(format!("Score acceptance timed out for {}×{} game. Game will be discarded.", board_size, board_size));
-                    // Return to main menu
-                    self.current_view = View::default();
+                None => {
+                    ui.label("Training credits: unavailable");
+                }
+            }
+            
+            ui.separator();
+            
+            // Board size selection with radio buttons
+            ui.label("Board size:");
+            ui.horizontal(|ui| {
+                let old_size = *board_size;
+                
+                if ui.radio_value(board_size, 9, "9×9").clicked() && old_size != 9 {
+                    self.default_board_size = 9;
+                    // Update the worker with the new board size
+                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 9 });
+                    // Re-subscribe to gossip with new board size
+                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+                }
+                if ui.radio_value(board_size, 13, "13×13").clicked() && old_size != 13 {
+                    self.default_board_size = 13;
+                    // Update the worker with the new board size
+                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 13 });
+                    // Re-subscribe to gossip with new board size
+                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+                }
+                if ui.radio_value(board_size, 19, "19×19").clicked() && old_size != 19 {
+                    self.default_board_size = 19;
+                    // Update the worker with the new board size
+                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 19 });
+                    // Re-subscribe to gossip with new board size
+                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+                }
+            });
+            
+            ui.checkbox(&mut self.new_game_rules.suicide_allowed, "Allow multi-stone suicide")
+                .on_hover_text("As under New Zealand and Tromp-Taylor rules; joining players see it in the game's terms");
+            
+            // Create Game button - disabled if no ticket available or relay not ready
+            let is_stub = self.current_ticket.as_ref().map(|t| loopback::is_ticket(t)).unwrap_or(false);
+            let network_ready = self.current_ticket.as_ref()
+                .map(|ticket| is_stub || ticket.len() > 50) // Stub is always ready, real tickets should be long
+                .unwrap_or(false);
+            
+            let create_btn = ui.add_enabled(
+                self.current_ticket.is_some() && network_ready, 
+                egui::Button::new("Create Game")
+            );
+            
+            if create_btn.clicked() {
+                let _ = self.ui_tx.send(UiToNet::CreateGame { board_size: *board_size, rule_config: self.new_game_rules });
+                *creating_game = true;
+            }
+            
+            // Show hint if button is disabled
+            if let Some(_ticket) = &self.current_ticket {
+                if !network_ready {
+                    ui.label(egui::RichText::new("Waiting for network initialization...").italics().color(egui::Color32::GRAY));
+                }
+            } else {
+                ui.label(egui::RichText::new("Generate a ticket first").italics().color(egui::Color32::GRAY));
+            }
+            
+            // Matchmaking: find an opponent without exchanging tickets
+            if self.queue_searching {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!(
+                        "Searching for a {}×{} opponent ({})...",
+                        self.match_prefs.board_size, self.match_prefs.board_size, self.match_prefs.time_control
+                    ));
+                    if ui.button("Cancel").clicked() {
+                        let _ = self.ui_tx.send(UiToNet::LeaveQueue);
+                        self.queue_searching = false;
+                    }
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("Rating band ±");
+                    ui.add(egui::DragValue::new(&mut self.match_prefs.rating_band).clamp_range(50..=1000).speed(10));
+                    egui::ComboBox::from_label("Time")
+                        .selected_text(self.match_prefs.time_control.to_string())
+                        .show_ui(ui, |ui| {
+                            for (main_time_secs, byo_yomi_secs) in TIME_CONTROLS {
+                                let time_control = TimeControl { main_time_secs, byo_yomi_secs };
+                                ui.selectable_value(&mut self.match_prefs.time_control, time_control, time_control.to_string());
+                            }
+                        });
+                    let mut keep_running = self.match_prefs.on_disconnect == OnDisconnect::KeepRunning;
+                    if ui.checkbox(&mut keep_running, "Clock runs while away")
+                        .on_hover_text("Otherwise both clocks stop while a player is disconnected")
+                        .changed()
+                    {
+                        self.match_prefs.on_disconnect = if keep_running { OnDisconnect::KeepRunning } else { OnDisconnect::Pause };
+                    }
+                    if ui.button("Find Opponent").clicked() {
+                        self.match_prefs.board_size = *board_size;
+                        let _ = self.ui_tx.send(UiToNet::JoinQueue { prefs: self.match_prefs });
+                        self.queue_searching = true;
+                    }
+                });
+            }
+            
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                if ui.button("Refresh Games").clicked() {
+                    self.refresh_requested = true;
+                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+                }
+                
+                ui.checkbox(&mut self.config.auto_refresh, "Live updates");
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Accept opponents:");
+                let old_policy = self.join_policy;
+                ui.radio_value(&mut self.join_policy, JoinPolicy::Manual, "Ask me");
+                ui.radio_value(&mut self.join_policy, JoinPolicy::GuildMembers, "Guild members");
+                ui.radio_value(&mut self.join_policy, JoinPolicy::Everyone, "Everyone");
+                if self.join_policy != old_policy {
+                    let _ = self.ui_tx.send(UiToNet::SetJoinPolicy { policy: self.join_policy });
+                }
+            });
+            
+            let listing = if self.games_query.spectating { "Games in Play" } else { "Available Games" };
+            ui.label(format!("{} ({}):", listing, self.games_total.max(available_games.len())));
+            let mut query = self.games_query;
+            ui.horizontal(|ui| {
+                let size_label = |size: Option<u8>| size.map_or("Any size".to_string(), |size| format!("{}×{}", size, size));
+                egui::ComboBox::from_id_source("games_board_size")
+                    .selected_text(size_label(query.board_size))
+                    .show_ui(ui, |ui| {
+                        for size in [None, Some(9), Some(13), Some(19)] {
+                            ui.selectable_value(&mut query.board_size, size, size_label(size));
+                        }
+                    });
+                ui.checkbox(&mut query.rated_only, "Rated only");
+                ui.checkbox(&mut query.spectating, "In play")
+                    .on_hover_text("List games being played instead of games waiting for an opponent");
+                egui::ComboBox::from_label("Order")
+                    .selected_text(query.sort.label())
+                    .show_ui(ui, |ui| {
+                        for sort in GamesSort::ALL {
+                            ui.selectable_value(&mut query.sort, sort, sort.label());
+                        }
+                    });
+            });
+            let query_changed = query != self.games_query;
+            let mut asked = None;
+            let mut reached_end = false;
+            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                egui::Grid::new("lobby_games").striped(true).show(ui, |ui| {
+                    for heading in ["Game", "Size", "Komi", "Handicap", "Rules", "Clock", "Rated", "State", ""] {
+                        ui.strong(heading);
+                    }
+                    ui.end_row();
+                    for game in available_games.iter() {
+                        let terms = &game.terms;
+                        let kind = if game.correspondence { " (correspondence)" } else { "" };
+                        ui.label(format!("{}{}", game.id, kind));
+                        ui.label(format!("{}×{}", game.board_size, game.board_size));
+                        ui.label(terms.komi.to_string());
+                        ui.label(terms.handicap.to_string());
+                        let suicide = if terms.rule_config.suicide_allowed { ", suicide allowed" } else { "" };
+                        ui.label(format!("{:?}{}", terms.rules, suicide));
+                        ui.label(match terms.time_control {
+                            None => "untimed".to_string(),
+                            Some(tc) if terms.on_disconnect == OnDisconnect::KeepRunning => format!("{}, runs while away", tc),
+                            Some(tc) => tc.to_string(),
+                        });
+                        ui.label(if terms.rated { "yes" } else { "no" });
+                        ui.label(game.state.to_string());
+                        if game.state.joinable() {
+                            if ui.button("Join…").clicked() {
+                                asked = Some(game.clone());
+                            }
+                        } else if game.state.watchable() {
+                            ui.add_enabled(false, egui::Button::new("Watch"))
+                                .on_disabled_hover_text("There is no spectator view yet");
+                        } else {
+                            ui.label("");
+                        }
+                        ui.end_row();
+                    }
+                });
+                // The next page is fetched once the end of the list scrolls into view
+                if available_games.len() < self.games_total {
+                    reached_end = ui.label("Loading more games…").rect.intersects(ui.clip_rect());
+                }
+            });
+            if let Some(game) = asked {
+                self.join_confirm = Some(game);
+            }
+            if query_changed {
+                self.set_games_query(query);
+            } else if reached_end {
+                self.request_more_games();
+            }
+        }
+        
+        if save_config {
+            self.save_runtime_config();
+        }
+        if let Some(text) = pasted {
+            self.connect_with(&text);
+        }
+        if open_archive {
+            match ArchiveBrowser::open_default() {
+                Ok(browser) => self.current_view = View::Archive { browser },
+                Err(e) => self.error_msg = Some(format!("Failed to open the game archive: {}", e)),
+            }
+        }
+        if open_puzzles {
+            match archiver::archive_dir().and_then(|dir| PuzzleSession::from_archive(&dir)) {
+                Ok(session) => self.current_view = View::Puzzle { session },
+                Err(e) => self.error_msg = Some(format!("Failed to load puzzles: {}", e)),
+            }
+        }
+        if let Some(board_size) = open_openings {
+            match archiver::archive_dir().and_then(|dir| OpeningExplorer::from_archive(&dir, board_size)) {
+                Ok(explorer) => self.current_view = View::Openings { explorer },
+                Err(e) => self.error_msg = Some(format!("Failed to load openings: {}", e)),
+            }
+        }
+        if let Some(board_size) = open_editor {
+            self.open_editor(board_size);
+        }
+        if let Some(board_size) = open_practice {
+            self.start_practice(board_size);
+        }
+        if let Some(rung) = open_ladder {
+            self.start_ladder(rung);
+        }
+        if open_wizard {
+            self.open_onboarding();
+        }
+        match log_action {
+            Some(LogAction::Apply) => {
+                let text = self.log_filter_input.clone();
+                self.set_log_filter(&text);
+            }
+            Some(LogAction::Capture) => self.capture_trace(),
+            None => {}
+        }
+        if apply_alert_rules {
+            let text = self.alert_rules_input.clone();
+            self.set_alert_rules(&text);
+        }
+        if inspect_dataset {
+            self.inspect_dataset();
+        }
+        if open_config_editor {
+            self.open_config_editor();
+        }
+    }
+    
+    /// Work out what the training games in the folder typed in the training
+    /// settings hold
+    pub fn inspect_dataset(&mut self) {
+        if self.inspecting_dataset {
+            return;
+        }
+        let folder = self.dataset_input.trim();
+        if folder.is_empty() {
+            self.error_msg = Some("Type the folder of training games to inspect".to_string());
+            return;
+        }
+        self.inspecting_dataset = true;
+        self.dataset_stats = None;
+        let _ = self.ui_tx.send(UiToNet::InspectDataset { dir: std::path::PathBuf::from(folder) });
+    }
+    
+    /// Import the folder typed in the archive's import panel
+    pub fn start_sgf_import(&mut self) {
+        let View::Archive { browser } = &mut self.current_view else {
+            return;
+        };
+        let options = browser.import_options();
+        let Some(dialog) = browser.import.as_mut().filter(|d| !d.running) else {
+            return;
+        };
+        let folder = dialog.folder.trim();
+        if folder.is_empty() {
+            self.error_msg = Some("Type the folder of SGF files to import".to_string());
+            return;
+        }
+        let dir = std::path::PathBuf::from(folder);
+        dialog.running = true;
+        dialog.progress = None;
+        dialog.report = None;
+        let _ = self.ui_tx.send(UiToNet::ImportSgf { dir, options });
+    }
+    
+    fn render_archive(&mut self, ui: &mut egui::Ui) {
+        let View::Archive { browser } = &mut self.current_view else {
+            return;
+        };
+        ui.heading("Game Archive");
+        
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut browser.search);
+            egui::ComboBox::from_label("Sort by")
+                .selected_text(browser.sort.label())
+                .show_ui(ui, |ui| {
+                    for key in SortKey::ALL {
+                        ui.selectable_value(&mut browser.sort, key, key.label());
+                    }
+                });
+            if ui.button("Export all…").clicked() && browser.export.is_none() {
+                browser.export = Some(ExportChoices::default());
+            }
+            if ui.button("Import SGF…").clicked() && browser.import.is_none() {
+                browser.import = Some(ImportDialog::default());
+            }
+        });
+        
+        let mut import = None;
+        if let Some(dialog) = &mut browser.import {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Folder:");
+                    ui.add_enabled(!dialog.running, egui::TextEdit::singleline(&mut dialog.folder));
+                    if ui.add_enabled(!dialog.running, egui::Button::new("Import")).clicked() {
+                        import = Some(true);
+                    }
+                    if ui.add_enabled(!dialog.running, egui::Button::new("Close")).clicked() {
+                        import = Some(false);
+                    }
+                });
+                if dialog.running {
+                    let (fraction, text) = match &dialog.progress {
+                        Some(p) => (p.fraction(), format!("{}/{} {}", p.done, p.total, p.file)),
+                        None => (0.0, "Reading the folder…".to_string()),
+                    };
+                    ui.add(egui::ProgressBar::new(fraction).text(text));
+                }
+                if let Some(report) = &dialog.report {
+                    ui.label(report.summary());
+                    let problems: Vec<_> = report.skipped.iter().chain(&report.failed).collect();
+                    if !problems.is_empty() {
+                        egui::CollapsingHeader::new(format!("Not imported ({})", problems.len())).show(ui, |ui| {
+                            for (file, reason) in problems {
+                                ui.label(format!("{}: {}", file, reason));
+                            }
+                        });
+                    }
+                }
+            });
+        }
+        if import == Some(false) {
+            browser.import = None;
+        }
+        
+        let mut error = None;
+        let mut export = None;
+        if let Some(choices) = &mut browser.export {
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    let label = EXPORT_FORMATS.iter().find(|(f, _)| *f == choices.format).map_or("", |(_, l)| *l);
+                    egui::ComboBox::from_label("Format")
+                        .selected_text(label)
+                        .show_ui(ui, |ui| {
+                            for (format, label) in EXPORT_FORMATS {
+                                ui.selectable_value(&mut choices.format, format, label);
+                            }
+                        });
+                    ui.checkbox(&mut choices.filter.only_scored, "Only scored games");
+                    ui.checkbox(&mut choices.filter.only_training_eligible, "Only training games");
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Export").clicked() {
+                        export = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        export = Some(false);
+                    }
+                });
+            });
+        }
+        if let Some(confirmed) = export {
+            let choices = browser.export.take().unwrap_or_default();
+            if confirmed {
+                match browser.export_all(choices.format, &choices.filter) {
+                    Ok((path, report)) => {
+                        let mut text = format!("Exported {} games to {}", report.exported, path.display());
+                        if !report.corrupt.is_empty() {
+                            text.push_str(&format!(" ({} unreadable)", report.corrupt.len()));
+                        }
+                        self.toast = Some((text, std::time::Instant::now()));
+                    }
+                    Err(e) => error = Some(format!("Failed to export archive: {}", e)),
+                }
+            }
+        }
+        ui.separator();
+        
+        let mut review = None;
+        let mut delete = None;
+        let mut eligible = None;
+        let mut retract = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            let games = browser.visible();
+            if games.is_empty() {
+                ui.label(egui::RichText::new("No archived games").italics().color(egui::Color32::GRAY));
+            }
+            egui::Grid::new("archive_games").striped(true).show(ui, |ui| {
+                for game in games {
+                    ui.label(&game.date);
+                    ui.label(if game.opponent.is_empty() { "?" } else { &game.opponent });
+                    ui.label(format!("{}×{}", game.board_size, game.board_size));
+                    ui.label(game.result.as_deref().unwrap_or("-"));
+                    ui.label(format!("{} moves", game.move_count));
+                    match self.rating_history.iter().find(|e| e.game_id == game.id) {
+                        Some(entry) => ui.label(format!("{:.0} ({:+.0})", entry.after.rating, entry.delta())),
+                        None => ui.label("unrated"),
+                    };
+                    ui.label(game.tags.join(", "));
+                    if ui.button("Review").clicked() {
+                        review = Some(game.id.clone());
+                    }
+                    if ui.button("Export SGF").clicked() {
+                        match browser.save_sgf(&game.id) {
+                            Ok(path) => tracing::info!("Exported SGF to {}", path.display()),
+                            Err(e) => error = Some(format!("Failed to export SGF: {}", e)),
+                        }
+                    }
+                    if ui.button("Delete").clicked() {
+                        delete = Some(game.id.clone());
+                    }
+                    let mut training = game.training_eligible;
+                    if ui.checkbox(&mut training, "Training").changed() {
+                        eligible = Some((game.id.clone(), training));
+                    }
+                    if ui.button("Delete training copies")
+                        .on_hover_text("Remove the game from local training data and ask peers to drop it")
+                        .clicked()
+                    {
+                        retract = Some(game.id.clone());
+                    }
+                    ui.end_row();
                 }
-                NetToUi::Debug(message) => {
-                    tracing::debug!("Debug message: {}", message);
+            });
+        });
+        
+        if let Some(id) = delete {
+            if let Err(e) = browser.delete(&id) {
+                error = Some(e.to_string());
+            }
+        }
+        if let Some((id, training)) = eligible {
+            if let Err(e) = browser.set_training_eligible(&id, training) {
+                error = Some(e.to_string());
+            }
+            let _ = self.ui_tx.send(UiToNet::ExcludeFromTraining { game_id: id, excluded: !training });
+        }
+        if let Some(id) = retract {
+            // A retracted game stays out of training from now on
+            if let Err(e) = browser.set_training_eligible(&id, false) {
+                error = Some(e.to_string());
+            }
+            let _ = self.ui_tx.send(UiToNet::DeleteTrainingCopies { game_id: id });
+        }
+        
+        ui.separator();
+        let back = ui.button("Back").clicked();
+        
+        if let Some(id) = review {
+            match browser.open_review(&id) {
+                Ok(review) => {
+                    let browser = browser.clone();
+                    self.enter_review(browser, review);
                 }
-                NetToUi::GameAdvertised { game_id, host_id, board_size } => {
-                    tracing::info!("Game advertisement received: {} ({}×{}) from {}", game_id, board_size, board_size, host_id);
-                    // Request a refresh of the game list to show the advertised game
-                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+                Err(e) => error = Some(format!("Failed to load game: {}", e)),
+            }
+        } else if back {
+            self.current_view = View::default();
+        }
+        if error.is_some() {
+            self.error_msg = error;
+        }
+        if import == Some(true) {
+            self.start_sgf_import();
+        }
+    }
+    
+    fn render_puzzle(&mut self, ui: &mut egui::Ui) {
+        let View::Puzzle { session } = &mut self.current_view else {
+            return;
+        };
+        let (Some(puzzle), Some(position)) = (session.current(), session.position()) else {
+            ui.heading("Puzzles");
+            ui.label("No puzzles yet: they come from captures in your archived games.");
+            if ui.button("Back").clicked() {
+                self.current_view = View::default();
+            }
+            return;
+        };
+        
+        ui.heading(puzzle.prompt());
+        ui.label(format!("Puzzle {} of {}, from move {}", session.index() + 1, session.len(), puzzle.move_number + 1));
+        ui.label(format!(
+            "Streak: {} (best {})",
+            self.ui_config.puzzle_streak, self.ui_config.best_puzzle_streak
+        ));
+        
+        if let Some(coord) = GoBoardWidget::new(position.board_size).show(ui, &position).clicked {
+            let finished = session.play(coord, &mut self.ui_config).is_some();
+            if finished {
+                if let Some(path) = &self.ui_config_path {
+                    if let Err(e) = self.ui_config.save(path) {
+                        tracing::warn!("Failed to save UI config: {}", e);
+                    }
                 }
             }
         }
+        
+        match session.feedback() {
+            Some(Feedback::Solved) => {
+                ui.colored_label(egui::Color32::GREEN, "Solved!");
+            }
+            Some(Feedback::Wrong(reason)) => {
+                ui.colored_label(egui::Color32::RED, format!("Wrong: {}", reason));
+            }
+            None => {}
+        }
+        
+        let mut back = false;
+        ui.horizontal(|ui| {
+            if ui.button("Retry").clicked() {
+                session.retry();
+            }
+            if ui.button("Next").clicked() {
+                session.next();
+            }
+            back = ui.button("Back").clicked();
+        });
+        if back {
+            self.current_view = View::default();
+        }
     }
-
-    fn render_main_menu(&mut self, ui: &mut egui::Ui) {
-        if let View::MainMenu { available_games, creating_game, board_size } = &mut self.current_view {
-            ui.heading("P2P Go");
-            
-            // Node ID section
-            ui.separator();
-            ui.label("Network Information:");
-            if let Some(node_id) = &self.node_id {
+    
+    fn render_openings(&mut self, ui: &mut egui::Ui) {
+        let View::Openings { explorer } = &mut self.current_view else {
+            return;
+        };
+        let board_size = explorer.tree().board_size();
+        ui.heading(format!("Openings ({}×{})", board_size, board_size));
+        if explorer.tree().is_empty() {
+            ui.label("No openings yet: they come from your archived games of this size.");
+            if ui.button("Back").clicked() {
+                self.current_view = View::default();
+            }
+            return;
+        }
+        
+        let mut practice = false;
+        let mut back = false;
+        ui.horizontal_top(|ui| {
+            ui.vertical(|ui| {
+                GoBoardWidget::new(board_size).interactive(false).show(ui, &explorer.position());
                 ui.horizontal(|ui| {
-                    ui.label(format!("Node ID: {}", node_id));
-                    if ui.button("Copy").clicked() {
-                        ui.output_mut(|o| o.copied_text = node_id.clone());
-                    }
+                    practice = ui.button("Practice from here").clicked();
+                    back = ui.button("Back").clicked();
                 });
-            } else {
-                ui.label("Node ID: Loading...");
-            }
-            
-            ui.horizontal(|ui| {
-                if ui.button("Generate Ticket").clicked() {
-                    let _ = self.ui_tx.send(UiToNet::GetTicket);
-                }
-                if ui.button("Connect by Ticket").clicked() {
-                    self.show_ticket_modal = true;
-                }
-            });
-            
-            // Paste ticket input field with auto-connect on Enter
-            ui.horizontal(|ui| {
-                ui.label("Paste ticket ↵");
-                let text_edit = ui.text_edit_singleline(&mut self.ticket_input);
-                
-                if text_edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !self.ticket_input.trim().is_empty() {
-                    let _ = self.ui_tx.send(UiToNet::ConnectByTicket { 
-                        ticket: self.ticket_input.clone() 
-                    });
-                    self.ticket_input.clear();
-                }
             });
             
-            if let Some(ticket) = &self.current_ticket {
-                ui.horizontal(|ui| {
-                    // Check if ticket is a stub or has relay status
-                    let is_stub = ticket == "loopback-ticket";
-                    let relay_status = ticket.len() > 50; // Real tickets are much longer than stub
-                    
-                    let label_text = if is_stub {
-                        egui::RichText::new("Generated Ticket (Local Mode):").color(egui::Color32::BLUE)
-                    } else if relay_status {
-                        egui::RichText::new("Generated Ticket (Network Ready):").color(egui::Color32::GREEN)
-                    } else {
-                        egui::RichText::new("Generated Ticket (Local Only):").color(egui::Color32::YELLOW)
-                    };
-                    ui.label(label_text);
-                    
-                    if ui.button("Copy Ticket").clicked() {
-                        ui.output_mut(|o| o.copied_text = ticket.clone());
+            ui.vertical(|ui| {
+                let rows = explorer.rows();
+                let row_height = ui.spacing().interact_size.y;
+                // Books can have thousands of lines; only lay out the visible ones
+                egui::ScrollArea::vertical().show_rows(ui, row_height, rows.len(), |ui, range| {
+                    for row in &rows[range] {
+                        let stats = explorer.stats(row.node);
+                        let label = match explorer.tree().move_at(row.node) {
+                            Some(Move::Place(coord)) => coord.display_label(board_size),
+                            Some(Move::Pass) => "Pass".to_string(),
+                            _ => String::new(),
+                        };
+                        let win_rate = stats.black_win_rate()
+                            .map_or("no results".to_string(), |rate| format!("B wins {:.0}%", rate * 100.0));
+                        ui.horizontal(|ui| {
+                            ui.add_space(16.0 * row.depth as f32);
+                            let has_replies = !explorer.tree().children(row.node).is_empty();
+                            let marker = match (has_replies, explorer.is_expanded(row.node)) {
+                                (false, _) => " ",
+                                (true, true) => "▾",
+                                (true, false) => "▸",
+                            };
+                            if ui.small_button(marker).clicked() && has_replies {
+                                explorer.toggle(row.node);
+                            }
+                            let text = format!("{}. {}  {} games, {}", row.depth + 1, label, stats.games, win_rate);
+                            if ui.selectable_label(explorer.selected() == row.node, text).clicked() {
+                                explorer.select(row.node);
+                            }
+                        });
                     }
                 });
-                ui.text_edit_singleline(&mut ticket.clone());
-            }
-            
-            ui.separator();
-            
-            // Board size selection with radio buttons
-            ui.label("Board size:");
-            ui.horizontal(|ui| {
-                let old_size = *board_size;
-                
-                if ui.radio_value(board_size, 9, "9×9").clicked() && old_size != 9 {
-                    self.default_board_size = 9;
-                    // Update the worker with the new board size
-                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 9 });
-                    // Re-subscribe to gossip with new board size
-                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
-                }
-                if ui.radio_value(board_size, 13, "13×13").clicked() && old_size != 13 {
-                    self.default_board_size = 13;
-                    // Update the worker with the new board size
-                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 13 });
-                    // Re-subscribe to gossip with new board size
-                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+            });
+        });
+        
+        if practice {
+            let position = explorer.position();
+            self.start_practice_from(position);
+        } else if back {
+            self.current_view = View::default();
+        }
+    }
+    
+    fn render_editor(&mut self, ui: &mut egui::Ui) {
+        let View::Editor { editor } = &mut self.current_view else {
+            return;
+        };
+        ui.heading("Board Editor");
+        ui.label("Click a point to cycle empty → black → white.");
+        
+        let board = GoBoardWidget::new(editor.board_size()).overlay(GhostStones(&editor.analysis));
+        if let Some(coord) = board.show(ui, &editor.position()).clicked {
+            editor.cycle(coord);
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("To move:");
+            ui.radio_value(&mut editor.to_move, Color::Black, "Black");
+            ui.radio_value(&mut editor.to_move, Color::White, "White");
+            ui.label("Komi:");
+            ui.add(egui::DragValue::new(&mut editor.komi).speed(0.5).clamp_range(-50.0..=50.0));
+        });
+        
+        let mut start = None;
+        let mut play_ai = false;
+        let mut error = None;
+        let mut back = false;
+        ui.horizontal(|ui| {
+            if ui.button("Start game from here").clicked() {
+                match editor.to_game() {
+                    Ok(position) => start = Some((position, editor.komi)),
+                    Err(e) => error = Some(format!("Cannot start from this position: {}", e)),
                 }
-                if ui.radio_value(board_size, 19, "19×19").clicked() && old_size != 19 {
-                    self.default_board_size = 19;
-                    // Update the worker with the new board size
-                    let _ = self.ui_tx.send(UiToNet::UpdateBoardSize { board_size: 19 });
-                    // Re-subscribe to gossip with new board size
-                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+            }
+            play_ai = ui.button("Play the AI from here")
+                .on_hover_text("You take the side to move against the strongest ladder opponent you have unlocked")
+                .clicked();
+            if ui.button("Export SGF").clicked() {
+                match archiver::archive_dir().and_then(|dir| editor.save_sgf(&dir)) {
+                    Ok(path) => tracing::info!("Exported SGF to {}", path.display()),
+                    Err(e) => error = Some(format!("Failed to export SGF: {}", e)),
                 }
-            });
-            
-            // Create Game button - disabled if no ticket available or relay not ready
-            let is_stub = self.current_ticket.as_ref().map(|t| t == "loopback-ticket").unwrap_or(false);
-            let network_ready = self.current_ticket.as_ref()
-                .map(|ticket| is_stub || ticket.len() > 50) // Stub is always ready, real tickets should be long
-                .unwrap_or(false);
-            
-            let create_btn = ui.add_enabled(
-                self.current_ticket.is_some() && network_ready, 
-                egui::Button::new("Create Game")
-            );
-            
-            if create_btn.clicked() {
-                let _ = self.ui_tx.send(UiToNet::CreateGame { board_size: *board_size });
-                *creating_game = true;
             }
-            
-            // Show hint if button is disabled
-            if let Some(_ticket) = &self.current_ticket {
-                if !network_ready {
-                    ui.label(egui::RichText::new("Waiting for network initialization...").italics().color(egui::Color32::GRAY));
+            if ui.button("Analyze").clicked() {
+                match editor.to_game() {
+                    Ok(position) => {
+                        let _ = self.ui_tx.send(UiToNet::AnalyzePosition { position });
+                    }
+                    Err(e) => error = Some(format!("Cannot analyze this position: {}", e)),
                 }
-            } else {
-                ui.label(egui::RichText::new("Generate a ticket first").italics().color(egui::Color32::GRAY));
             }
-            
-            ui.separator();
-            
-            ui.horizontal(|ui| {
-                if ui.button("Refresh Games").clicked() {
-                    let _ = self.ui_tx.send(UiToNet::RefreshGames);
+            if ui.button("Clear").clicked() {
+                editor.clear();
+            }
+            back = ui.button("Back").clicked();
+        });
+        
+        if let Some(e) = error {
+            self.error_msg = Some(e);
+        }
+        if let Some((position, komi)) = start {
+            let _ = self.ui_tx.send(UiToNet::CreateGameFromPosition { position, komi });
+        } else if play_ai {
+            self.start_ai_from_editor();
+        } else if back {
+            self.current_view = View::default();
+        }
+    }
+    
+    fn render_onboarding(&mut self, ui: &mut egui::Ui) {
+        let View::Onboarding { wizard } = &mut self.current_view else {
+            return;
+        };
+        ui.heading("Welcome to P2P Go");
+        
+        let mut try_local = false;
+        let mut rerun_report = false;
+        match wizard.page() {
+            OnboardingPage::Name => {
+                ui.label("Choose the name your opponents will see.");
+                ui.text_edit_singleline(&mut wizard.name);
+                if !wizard.can_advance() {
+                    ui.colored_label(
+                        egui::Color32::GRAY,
+                        format!("Enter 1 to {} characters", crate::onboarding::MAX_NAME_LEN),
+                    );
                 }
-                
-                ui.checkbox(&mut self.config.auto_refresh, "Auto-refresh (2s)");
-            });
-            
-            ui.label("Available Games:");
-            for game in available_games {
-                if ui.button(format!("Game {} ({}×{})", game.id, game.board_size, game.board_size)).clicked() {
-                    let _ = self.ui_tx.send(UiToNet::JoinGame { game_id: game.id.clone() });
+            }
+            OnboardingPage::Tickets => {
+                ui.label("Games are peer to peer: there is no server that pairs players.");
+                ui.label("To play a friend, one of you clicks Generate Ticket and sends it; \
+                          the other pastes it into Connect by Ticket. Once connected you \
+                          see each other's games in the lobby. Matchmaking finds strangers \
+                          without a ticket.");
+                try_local = ui.button("Try a local board with AI analysis").clicked();
+            }
+            OnboardingPage::Sharing => {
+                ui.checkbox(&mut wizard.relay_participation, "Help relay traffic for players behind strict NATs")
+                    .on_hover_text("Accepts connections for other players' games while the app is open; the settings offer more");
+                ui.checkbox(&mut wizard.share_training_data, "Share finished games as training data (earns credits)");
+                ui.label("Both are off unless you opt in, and can be changed by running this wizard again.");
+            }
+            OnboardingPage::Connectivity => {
+                match &self.nat_report {
+                    Some(report) => {
+                        ui.label("Connectivity check:");
+                        ui.text_edit_multiline(&mut report.clone());
+                    }
+                    None => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Checking connectivity...");
+                        });
+                    }
                 }
+                rerun_report = ui.button("Check again").clicked();
+            }
+        }
+        
+        let is_first = wizard.page() == OnboardingPage::Name;
+        let is_last = wizard.is_last_page();
+        let can_advance = wizard.can_advance();
+        let mut back = false;
+        let mut next = false;
+        ui.horizontal(|ui| {
+            back = ui.add_enabled(!is_first, egui::Button::new("Back")).clicked();
+            let label = if is_last { "Finish" } else { "Next" };
+            next = ui.add_enabled(can_advance, egui::Button::new(label)).clicked();
+        });
+        
+        if rerun_report {
+            self.nat_report = None;
+            let _ = self.ui_tx.send(UiToNet::RunNetReport);
+        }
+        if try_local {
+            self.onboarding_try_local();
+        } else if back {
+            self.onboarding_back();
+        } else if next {
+            self.onboarding_next();
+        }
+    }
+    
+    fn render_review(&mut self, ui: &mut egui::Ui) {
+        let View::Review { browser, review } = &mut self.current_view else {
+            return;
+        };
+        ui.heading(format!("Review: {}", review.id));
+        
+        let position = review.position();
+        GoBoardWidget::new(review.board_size()).interactive(false).show(ui, &position);
+        
+        if let Some(moves) = self.win_rate.as_ref().and_then(|graph| win_rate::render(ui, graph, review.len(), review.cursor())) {
+            review.seek(moves);
+        }
+        
+        ui.horizontal(|ui| {
+            if ui.button("|<").clicked() {
+                review.first();
             }
+            if ui.button("<").clicked() || ui.input(|i| i.key_pressed(egui::Key::ArrowLeft)) {
+                review.step(-1);
+            }
+            ui.label(format!("Move {} / {}", review.cursor(), review.len()));
+            if ui.button(">").clicked() || ui.input(|i| i.key_pressed(egui::Key::ArrowRight)) {
+                review.step(1);
+            }
+            if ui.button(">|").clicked() {
+                review.last();
+            }
+        });
+        
+        egui::CollapsingHeader::new("Analysis").show(ui, |ui| {
+            render_game_report(ui, &self.ui_tx, &review.id, review.game(), self.game_report.as_ref());
+        });
+        
+        if ui.button("Back to Archive").clicked() {
+            let browser = browser.clone();
+            self.current_view = View::Archive { browser };
+            self.set_win_rate(false);
         }
     }
 
     fn render_lobby(&mut self, ui: &mut egui::Ui) {
+        let mut set_teaching = None;
         if let View::Lobby { game_id, .. } = &self.current_view {
             ui.heading("Waiting for opponent...");
             ui.label(format!("Game ID: {}", game_id));
@@ -545,6 +3472,21 @@ impl App {
                         ui.output_mut(|o| o.copied_text = ticket.clone());
                     }
                 });
+                // The link also names this game, so the joiner lands in it
+                if ui.button("Copy invite").clicked() {
+                    let _ = self.ui_tx.send(UiToNet::CreateInvite { game_id: game_id.clone() });
+                }
+            }
+            
+            if ui.checkbox(&mut self.correspondence, "Correspondence (a day per move)").changed() {
+                let _ = self.ui_tx.send(UiToNet::SetCorrespondence {
+                    game_id: game_id.clone(),
+                    enabled: self.correspondence,
+                });
+            }
+            let mut teaching = self.teaching;
+            if ui.checkbox(&mut teaching, "Teach this game (demonstration stones and marks)").changed() {
+                set_teaching = Some(teaching);
             }
             
             if ui.button("Leave Game").clicked() {
@@ -552,10 +3494,35 @@ impl App {
                 let _ = self.ui_tx.send(UiToNet::Shutdown);
             }
         }
+        if let Some(enabled) = set_teaching {
+            self.set_teaching(enabled);
+        }
     }
 
     fn render_game(&mut self, ui: &mut egui::Ui) {
-        if let View::Game { game_id, game_state, .. } = &self.current_view {
+        let mut toggle_heat_map = None;
+        let mut toggle_ownership = None;
+        let mut toggle_territory = None;
+        let mut toggle_win_rate = None;
+        let mut play = None;
+        let mut confirm_play = None;
+        let mut answer_confirm = None;
+        let mut typed = false;
+        let mut leave_practice = false;
+        let mut replay_ladder = None;
+        let mut share_board = false;
+        let mut request_undo = false;
+        let mut annotate = None;
+        let mut comment = None;
+        let resign_banner = self.resign_banner();
+        let game_settings = self.game_settings;
+        let mut live = false;
+        if let Some(clock) = &mut self.clock {
+            clock.tick(std::time::Instant::now());
+        }
+        self.refresh_territory();
+        if let View::Game { game_id, game_state, practice, .. } = &mut self.current_view {
+            live = !*practice;
             // Store the game ID in UI memory for the board widget to access
             ui.ctx().data_mut(|data| {
                 data.insert_temp(egui::Id::new("current_game_id"), game_id.clone());
@@ -565,34 +3532,232 @@ impl App {
                 Color::Black => "Black",
                 Color::White => "White",
             };
-            ui.label(format!("Current player: {}", current_player));
+            ui.horizontal(|ui| {
+                if let Some(ladder) = &self.ladder {
+                    ui.label(format!("Ladder: {}, you play {:?}", ladder.name(), ladder.human));
+                    if ladder.thinking {
+                        ui.spinner();
+                    }
+                } else if *practice && self.sharing_board {
+                    ui.label("Practice, shared for watching: you play both colors");
+                } else if *practice {
+                    ui.label("Practice: you play both colors");
+                }
+                ui.label(format!("Current player: {}", current_player));
+                if let Some(clock) = self.clock.as_ref().filter(|_| !*practice) {
+                    clock_view::render(ui, clock);
+                }
+                if let Some(presence) = self.opponent_presence.filter(|_| !*practice) {
+                    presence_view::render_status(ui, presence.status(std::time::Instant::now(), now_secs()));
+                }
+                // Analysis runs in the worker, which doesn't know practice games
+                let mut show = self.show_heat_map;
+                if !*practice && ui.checkbox(&mut show, "Heat map").changed() {
+                    toggle_heat_map = Some(show);
+                }
+                let mut show = self.show_ownership;
+                if !*practice && ui.checkbox(&mut show, "Ownership").changed() {
+                    toggle_ownership = Some(show);
+                }
+                let mut show = self.ui_config.territory_estimate;
+                if ui.checkbox(&mut show, "Territory").on_hover_text("Quick estimate from the stones alone; F2 cycles the overlays").changed() {
+                    toggle_territory = Some(show);
+                }
+                let mut show = self.win_rate.is_some();
+                if !*practice && ui.checkbox(&mut show, "Win rate").changed() {
+                    toggle_win_rate = Some(show);
+                }
+                if let Some(outlook) = self.heat_map.outlook().filter(|_| self.show_heat_map) {
+                    ui.label(format!("Pass: {:.0}%", outlook.pass_probability * 100.0));
+                }
+                if let Some(map) = self.ownership.current() {
+                    let score = expected_score(map, game_komi(game_settings.as_ref(), game_state.board_size));
+                    let leader = if score >= 0.0 { "B" } else { "W" };
+                    ui.label(format!("Estimate: {}+{:.1}", leader, score.abs()));
+                }
+                if let Some((_, count)) = self.unread_moves.as_ref().filter(|(g, _)| *g == *game_id) {
+                    let text = if *count == 1 { "1 new move".to_string() } else { format!("{} new moves", count) };
+                    ui.colored_label(egui::Color32::from_rgb(30, 110, 200), format!("● {}", text));
+                }
+            });
             
-            if let Some(coord) = self.board_widget.render(ui, game_state, Some(&self.ui_tx)) {
-                let mv = Move::Place(coord);
-                let _ = self.ui_tx.send(UiToNet::MakeMove { mv, board_size: None });
-                // Request ghost moves after making a move
-                let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+            self.board_widget.set_heat_map(
+                self.heat_map.current().map(<[f32]>::to_vec),
+                self.show_heat_map && self.heat_map.is_computing(),
+            );
+            self.board_widget.set_ownership(self.ownership.current().map(<[f32]>::to_vec));
+            self.board_widget.set_territory(self.territory.as_ref().map(|(_, estimate)| {
+                (estimate.clone(), estimate.score(game_komi(game_settings.as_ref(), game_state.board_size)))
+            }));
+            self.board_widget.set_teaching_overlay(self.annotations.overlay(game_state.moves.len()).cloned());
+            let ui_tx = (!*practice).then_some(&self.ui_tx);
+            if let Some(coord) = self.board_widget.render(ui, game_state, ui_tx) {
+                if self.teaching_tool.is_some() {
+                    annotate = Some(coord);
+                } else {
+                    play = Some(Move::Place(coord));
+                }
+            }
+            // The game as it is played on; there is no earlier move to go to
+            if let Some(graph) = &self.win_rate {
+                let moves = game_state.moves.len();
+                win_rate::render(ui, graph, moves, moves);
+            }
+            if let Some(text) = self.annotations.overlay(game_state.moves.len()).map(|o| o.comments.join("\n")).filter(|t| !t.is_empty()) {
+                ui.label(egui::RichText::new(text).italics());
+            }
+            if self.teaching && !*practice {
+                comment = teaching_view::render_tools(ui, &mut self.teaching_tool, &mut self.lesson_comment);
+            }
+            
+            if self.board_widget.flashed().is_some() {
+                if let Some(hint) = &self.move_hint {
+                    ui.colored_label(egui::Color32::from_rgb(200, 40, 40), hint);
+                }
+            }
+            
+            if self.premove.is_some() && ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                self.premove = None;
+                self.board_widget.set_premove(None);
+                let _ = self.ui_tx.send(UiToNet::ClearPremove { board_size: None });
             }
             
+            if resign_banner {
+                ui.colored_label(egui::Color32::from_rgb(200, 40, 40), "Resigning is recommended: the model finds this position lost")
+                    .on_hover_text("The evaluation for you is below the resign threshold");
+            }
             ui.horizontal(|ui| {
                 if ui.button("Pass").clicked() {
-                    let _ = self.ui_tx.send(UiToNet::MakeMove { mv: Move::Pass, board_size: None });
-                    let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+                    confirm_play = Some(Move::Pass);
+                    self.pass_hint = None;
+                }
+                if let Some(confirm @ Confirmation::Pass(_)) = self.move_confirm {
+                    ui.colored_label(egui::Color32::from_rgb(200, 120, 0), confirm.prompt());
+                    if ui.button("Pass anyway").clicked() {
+                        answer_confirm = Some(true);
+                    }
+                    if ui.button("Keep playing").clicked() {
+                        answer_confirm = Some(false);
+                    }
+                } else if let Some(estimate) = self.pass_hint {
+                    ui.weak(format!("Nothing left to gain, passing now: {}", margin_label(estimate)))
+                        .on_hover_text("Moves inside your own area don't change the score");
+                } else if self.ghost_pass {
+                    ui.weak("Suggested: pass");
                 }
                 if ui.button("Resign").clicked() {
-                    let _ = self.ui_tx.send(UiToNet::MakeMove { mv: Move::Resign, board_size: None });
-                    let _ = self.ui_tx.send(UiToNet::GetGhostMoves);
+                    confirm_play = Some(Move::Resign);
+                }
+                let input = ui.add(egui::TextEdit::singleline(&mut self.move_input)
+                    .hint_text("D4")
+                    .desired_width(48.0));
+                if input.changed() {
+                    self.move_input_error = None;
+                }
+                if input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    typed = true;
+                }
+                if let Some(ladder) = &self.ladder {
+                    if ladder.won.is_some() && ui.button("Play again").clicked() {
+                        replay_ladder = Some(ladder.rung);
+                    }
+                    leave_practice = ui.button("Leave Ladder").clicked();
+                    return;
+                }
+                if *practice {
+                    if !self.sharing_board {
+                        share_board = ui.button("Share this board")
+                            .on_hover_text("Others can watch every move live, but not play")
+                            .clicked();
+                    } else {
+                        ui.checkbox(&mut self.archive_shared_board, "Archive when done");
+                    }
+                    leave_practice = ui.button("Leave Practice").clicked();
+                    return;
+                }
+                if self.undo_pending {
+                    ui.spinner();
+                    ui.weak("Undo requested");
+                } else {
+                    request_undo = ui.add_enabled(!game_state.moves.is_empty(), egui::Button::new("Request undo"))
+                        .on_hover_text("Ask your opponent to take back your last move")
+                        .clicked();
+                }
+                if ui.button("Copy invite").clicked() {
+                    let _ = self.ui_tx.send(UiToNet::CreateInvite { game_id: game_id.clone() });
                 }
                 if ui.button("Leave Game").clicked() {
                     let _ = self.ui_tx.send(UiToNet::LeaveGame);
                     let _ = self.ui_tx.send(UiToNet::Shutdown);
                 }
             });
+            if let Some(error) = &self.move_input_error {
+                ui.colored_label(egui::Color32::from_rgb(200, 40, 40), error);
+            }
+            if let Some(ladder) = self.ladder.as_ref().filter(|ladder| ladder.won.is_some()) {
+                ui.separator();
+                ui.heading(if ladder.won == Some(true) { "You won" } else { "You lost" });
+                let report = self.game_report.as_ref().filter(|(id, _)| id == LADDER_GAME_ID);
+                if let Some(text) = report.and_then(|(_, report)| ladder_view::biggest_mistake_text(report, ladder.human, game_state.board_size)) {
+                    ui.colored_label(egui::Color32::from_rgb(200, 40, 40), egui::RichText::new(text).strong());
+                }
+                render_game_report(ui, &self.ui_tx, game_id, game_state, self.game_report.as_ref());
+            }
+        }
+        if live {
+            let considering = self.board_widget.hovered();
+            self.update_presence(considering);
+        }
+        if let Some(show) = toggle_heat_map {
+            self.set_heat_map(show);
+        }
+        if let Some(show) = toggle_ownership {
+            self.set_ownership(show);
+        }
+        if let Some(show) = toggle_territory {
+            self.set_territory(show);
+        }
+        if let Some(show) = toggle_win_rate {
+            self.set_win_rate(show);
+        }
+        if let Some(mv) = play {
+            self.play_move(mv);
+        }
+        if let Some(mv) = confirm_play {
+            self.ask_to_play(mv);
+        }
+        if let Some(play) = answer_confirm {
+            self.answer_move_confirmation(play);
+        }
+        if let Some(coord) = annotate {
+            self.annotate_at(coord);
+        }
+        if let Some(annotation) = comment {
+            let _ = self.ui_tx.send(UiToNet::Annotate { annotation, board_size: None });
+        }
+        if typed {
+            let text = self.move_input.clone();
+            self.play_typed_move(&text);
+        }
+        if share_board {
+            self.share_board();
+        }
+        if request_undo {
+            self.request_undo();
+        }
+        if let Some(rung) = replay_ladder {
+            self.start_ladder(rung);
+        }
+        if leave_practice {
+            self.ladder = None;
+            self.stop_sharing();
+            self.board_widget = BoardWidget::new(self.default_board_size);
+            self.current_view = View::default();
         }
     }
     
     fn render_score_dialog(&mut self, ui: &mut egui::Ui) {
-        if let View::ScoreDialog { game_id, game_state: _, score_proof, dead_stones: _, score_pending: _, score_accepted } = &mut self.current_view.clone() {
+        if let View::ScoreDialog { game_id, game_state, score_proof, dead_stones: _, score_pending: _, score_accepted } = &mut self.current_view.clone() {
             ui.heading("Game Finished");
             ui.label(format!("Game ID: {}", game_id));
             ui.separator();
@@ -609,7 +3774,22 @@ impl App {
             ui.heading(format!("Winner: {} (by {})", winner, final_score.abs()));
             ui.separator();
             
+            egui::CollapsingHeader::new("Analysis").show(ui, |ui| {
+                render_game_report(ui, &self.ui_tx, game_id, game_state, self.game_report.as_ref());
+            });
+            
             if !*score_accepted {
+                if self.ui_config.share_training_data {
+                    let mut excluded = self.excluded_from_training.contains(game_id.as_str());
+                    if ui.checkbox(&mut excluded, "Exclude this game from training").changed() {
+                        if excluded {
+                            self.excluded_from_training.insert(game_id.clone());
+                        } else {
+                            self.excluded_from_training.remove(game_id.as_str());
+                        }
+                        let _ = self.ui_tx.send(UiToNet::ExcludeFromTraining { game_id: game_id.clone(), excluded });
+                    }
+                }
                 if ui.button("Accept Result").clicked() {
                     // Send AcceptScore message to worker
                     let _ = self.ui_tx.send(UiToNet::AcceptScore { 
@@ -638,9 +3818,11 @@ impl App {
     
     fn render_debug_overlay(&mut self, ctx: &egui::Context) {
         if !self.show_overlay {
+            self.watch_traffic(false);
             return;
         }
         
+        let mut traffic_open = false;
         egui::Window::new("Debug Overlay")
             .collapsible(false)
             .resizable(false)
@@ -653,6 +3835,12 @@ impl App {
                     View::Lobby { .. } => "Lobby", 
                     View::Game { .. } => "Game",
                     View::ScoreDialog { .. } => "ScoreDialog",
+                    View::Archive { .. } => "Archive",
+                    View::Review { .. } => "Review",
+                    View::Puzzle { .. } => "Puzzle",
+                    View::Openings { .. } => "Openings",
+                    View::Editor { .. } => "Editor",
+                    View::Onboarding { .. } => "Onboarding",
                 };
                 ui.label(format!("View: {}", current_view_name));
                 
@@ -668,6 +3856,11 @@ impl App {
                 
                 ui.label(format!("RX Queue: {}", self.rx_queue_length));
                 
+                let cause = self.repaint_stats.last_cause()
+                    .map(|c| format!("{:?}", c))
+                    .unwrap_or_else(|| "None".to_string());
+                ui.label(format!("FPS: {} (last repaint: {})", self.repaint_stats.fps(), cause));
+                
                 if let View::Game { game_state, .. } = &self.current_view {
                     ui.label(format!("Turn: {:?}", game_state.current_player));
                     ui.label(format!("Moves: {}", game_state.moves.len()));
@@ -682,19 +3875,547 @@ impl App {
                     ui.label("NAT Report:");
                     ui.text_edit_multiline(&mut report.clone());
                 }
+                
+                ui.separator();
+                ui.label(rejections_label(self.rejections));
+                ui.label(relay_usage_label(self.relay_mode, self.relay_usage));
+                traffic_open = egui::CollapsingHeader::new("Traffic")
+                    .default_open(true)
+                    .show(ui, |ui| render_traffic(ui, &self.traffic))
+                    .body_returned
+                    .is_some();
+                
+                for (game_id, metrics) in &self.channel_metrics {
+                    ui.separator();
+                    ui.label(format!("Game {}", game_id));
+                    ui.label(format!("Moves: {} sent, {} received, {} duplicates dropped",
+                        metrics.moves_sent, metrics.moves_received, metrics.duplicates_dropped));
+                    ui.label(format!("Sync requests: {} sent, {} served",
+                        metrics.sync_requests_sent, metrics.sync_requests_served));
+                    match (metrics.ack_p50, metrics.ack_p95, metrics.ack_p99) {
+                        (Some(p50), Some(p95), Some(p99)) => ui.label(format!(
+                            "ACK: p50 {} ms, p95 {} ms, p99 {} ms",
+                            p50.as_millis(), p95.as_millis(), p99.as_millis()
+                        )),
+                        _ => ui.label("ACK: no samples"),
+                    };
+                    if let Some(error) = &metrics.last_error {
+                        ui.colored_label(egui::Color32::from_rgb(200, 40, 40), format!("Last error: {}", error));
+                    }
+                }
+            });
+        self.watch_traffic(traffic_open);
+    }
+    
+    /// Ask the worker to start or stop sending traffic samples, if that changed
+    fn watch_traffic(&mut self, enabled: bool) {
+        if self.watching_traffic != enabled {
+            self.watching_traffic = enabled;
+            let _ = self.ui_tx.send(UiToNet::WatchTraffic { enabled });
+        }
+    }
+}
+
+/// Komi a game on a `board_size` board is scored with: that of the game
+/// joined, handicap included, else the usual one for the size
+fn game_komi(settings: Option<&GameSettings>, board_size: u8) -> f32 {
+    settings
+        .filter(|settings| settings.board_size == board_size)
+        .map_or_else(|| standard_komi(board_size), GameSettings::scoring_komi)
+}
+
+/// Last minute of messages and bytes per second by category, with a CSV copy for bug reports
+/// Seed for a ladder opponent's moves
+fn ladder_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+/// Relay preset choices; returns the one picked if it changed.
+///
+/// `active` is what the worker last confirmed, shown while it catches up.
+fn render_relay_settings(ui: &mut egui::Ui, chosen: RelayPreset, active: Option<(RelayPreset, ConnectionLimits)>) -> Option<RelayPreset> {
+    let mut picked = chosen;
+    ui.collapsing("Relaying", |ui| {
+        for preset in RelayPreset::ALL {
+            ui.radio_value(&mut picked, preset, preset.label())
+                .on_hover_text(preset.description());
+        }
+        if let Some((preset, limits)) = active {
+            ui.label(egui::RichText::new(format!(
+                "{}: up to {} connections and {} KB/s",
+                preset.label(),
+                limits.max_connections,
+                limits.max_bandwidth_bps / 1024
+            )).weak());
+        }
+    });
+    (picked != chosen).then_some(picked)
+}
+
+/// Active relay preset and its usage against the limits, e.g.
+/// `Relay: Contributor, 2/64 connections, 12/1024 KB/s`
+fn relay_usage_label(mode: Option<(RelayPreset, ConnectionLimits)>, usage: ConnectionUsage) -> String {
+    match mode {
+        None => "Relay: starting".to_string(),
+        Some((preset, limits)) => format!(
+            "Relay: {}, {}/{} connections, {}/{} KB/s",
+            preset.label(),
+            usage.connections,
+            limits.max_connections,
+            usage.bytes_per_sec / 1024,
+            limits.max_bandwidth_bps / 1024
+        ),
+    }
+}
+
+fn render_traffic(ui: &mut egui::Ui, history: &TrafficHistory) {
+    if history.is_empty() {
+        ui.label("Waiting for traffic samples");
+        return;
+    }
+    
+    for (title, bytes) in [("Messages/s", false), ("Bytes/s", true)] {
+        ui.label(title);
+        egui_plot::Plot::new(title)
+            .width(280.0)
+            .height(80.0)
+            .include_x(-(HISTORY_SECS as f64))
+            .include_x(0.0)
+            .include_y(0.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .legend(egui_plot::Legend::default())
+            .show(ui, |plot_ui| {
+                for category in TrafficCategory::ALL {
+                    let points = egui_plot::PlotPoints::from(history.series(category, bytes));
+                    plot_ui.line(egui_plot::Line::new(points).name(category.label()));
+                }
+            });
+    }
+    
+    if ui.button("Copy last minute as CSV").clicked() {
+        ui.output_mut(|o| o.copied_text = history.to_csv());
+    }
+}
+
+/// Post-game report for `game_id` if one has arrived, else a button asking for it
+fn render_game_report(
+    ui: &mut egui::Ui,
+    ui_tx: &Sender<UiToNet>,
+    game_id: &str,
+    game: &p2pgo_core::GameState,
+    report: Option<&(String, GameReport)>,
+) {
+    let Some((_, report)) = report.filter(|(id, _)| id == game_id) else {
+        if ui.button("Analyze game").clicked() {
+            let _ = ui_tx.send(UiToNet::RequestGameReport { game_id: game_id.to_string(), state: game.clone() });
+        }
+        return;
+    };
+    
+    for (name, color) in [("Black", Color::Black), ("White", Color::White)] {
+        let times = report.times(color);
+        match (times.average(), times.longest) {
+            (Some(average), Some((index, longest))) => ui.label(format!(
+                "{}: {:.0}s per move on average, longest {}s on move {}",
+                name, average.as_secs_f32(), longest.as_secs(), index + 1
+            )),
+            _ => ui.label(format!("{}: no think times recorded", name)),
+        };
+        if times.periods_used > 0 {
+            ui.label(format!("{}: {} byo-yomi period(s) used", name, times.periods_used));
+        }
+    }
+    ui.label(Phase::ALL.iter()
+        .map(|&phase| format!("{} {}s", phase.label(), report.phase_time(phase).as_secs()))
+        .collect::<Vec<_>>()
+        .join(", "));
+    
+    // Evaluation for Black after each move; likely mistakes in red
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width().min(400.0), 80.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.line_segment([rect.left_center(), rect.right_center()], egui::Stroke::new(1.0, egui::Color32::GRAY));
+    let bar_width = rect.width() / report.evals.len().max(1) as f32;
+    for (i, eval) in report.evals.iter().enumerate() {
+        let mistake = i > 0 && report.mistakes.iter().any(|m| m.index + 1 == i);
+        let fill = if mistake { egui::Color32::RED } else { egui::Color32::DARK_GRAY };
+        let x = rect.left() + i as f32 * bar_width;
+        let y = rect.center().y - eval.clamp(-1.0, 1.0) * rect.height() / 2.0;
+        let bar = egui::Rect::from_two_pos(egui::pos2(x, rect.center().y), egui::pos2(x + bar_width.max(1.0), y));
+        painter.rect_filled(bar, 0.0, fill);
+    }
+    
+    if report.mistakes.is_empty() {
+        ui.label("No big evaluation swings");
+    }
+    for mistake in report.mistakes.iter().take(5) {
+        let mv = match &mistake.mv {
+            Move::Place(coord) => coord.display_label(game.board_size),
+            Move::Pass => "Pass".to_string(),
+            Move::Resign => "Resign".to_string(),
+        };
+        ui.label(format!(
+            "Move {} ({:?} {}): evaluation fell by {:.2}",
+            mistake.index + 1, mistake.color, mv, mistake.swing
+        ));
+    }
+}
+
+/// Passphrase protection, export and import of the node's identity
+/// Pass suggestion settings; true if they changed
+fn render_pass_settings(ui: &mut egui::Ui, settings: &mut PassSuggestions) -> bool {
+    let mut changed = false;
+    ui.collapsing("Play", |ui| {
+        changed |= ui.checkbox(&mut settings.enabled, "Suggest passing when no move gains points").changed();
+        ui.add_enabled_ui(settings.enabled, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("A move is worth playing from");
+                changed |= ui.add(egui::DragValue::new(&mut settings.threshold)
+                    .speed(0.1)
+                    .clamp_range(0.5..=10.0)
+                    .suffix(" points")).changed();
             });
+        });
+    });
+    changed
+}
+
+/// Turn notification toggles; true if either changed
+fn render_turn_alert_settings(ui: &mut egui::Ui, settings: &mut TurnAlertSettings) -> bool {
+    let mut changed = false;
+    ui.collapsing("Notifications", |ui| {
+        changed |= ui.checkbox(&mut settings.notify, "Notify me when it's my move and the window is in the background").changed();
+        changed |= ui.checkbox(&mut settings.badge, "Count games awaiting my move in the window title").changed();
+    });
+    changed
+}
+
+/// ACK watchdog overrides, for the few who need them; true when changed
+fn render_tuning_settings(ui: &mut egui::Ui, tuning: &mut Option<ChannelTuning>) -> bool {
+    let before = *tuning;
+    ui.collapsing("Move delivery (advanced)", |ui| {
+        let mut overridden = tuning.is_some();
+        ui.checkbox(&mut overridden, "Override the timing each game's clock implies")
+            .on_hover_text("Blitz games retry sooner; correspondence games wait for the peer to reconnect");
+        if overridden != tuning.is_some() {
+            *tuning = overridden.then(ChannelTuning::default);
+        }
+        let Some(tuning) = tuning else {
+            return;
+        };
+        ui.horizontal(|ui| {
+            ui.label("Ask for a sync after");
+            ui.add(egui::DragValue::new(&mut tuning.ack_timeout_ms).speed(50.0).clamp_range(0..=60_000).suffix(" ms"))
+                .on_hover_text("0 never asks");
+            ui.label("without an ACK,");
+            ui.add(egui::DragValue::new(&mut tuning.max_sync_requests).clamp_range(0..=10));
+            ui.label("time(s) at most");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Check every");
+            ui.add(egui::DragValue::new(&mut tuning.check_interval_ms).speed(10.0).clamp_range(50..=10_000).suffix(" ms"));
+        });
+    });
+    *tuning != before
+}
+
+/// What the training settings asked for
+struct TrainingAction {
+    /// Backend newly picked
+    backend: Option<TrainingBackend>,
+    /// A speed measurement
+    measure: bool,
+    /// A self-play training run
+    self_play: bool,
+}
+
+/// Training backend choice, background limits, speed measurement and
+/// self-play training.
+///
+/// `paused` says why background training is on hold, if it is.
+fn render_training_settings(
+    ui: &mut egui::Ui,
+    chosen: TrainingBackend,
+    governor: &mut GovernorConfig,
+    report: Option<&TrainingReport>,
+    measuring: bool,
+    paused: Option<PauseReason>,
+) -> TrainingAction {
+    let mut picked = chosen;
+    let mut measure = false;
+    let mut self_play = false;
+    ui.collapsing("Training", |ui| {
+        match paused {
+            Some(reason) => ui.colored_label(egui::Color32::YELLOW, GovernorStatus::Paused(reason).to_string()),
+            None => ui.label(egui::RichText::new("Background training may run").weak()),
+        };
+        for backend in TrainingBackend::ALL {
+            let radio = egui::RadioButton::new(picked == backend, backend.label());
+            if ui.add_enabled(backend.compiled(), radio).on_disabled_hover_text("Not built into this version").clicked() {
+                picked = backend;
+            }
+        }
+        ui.horizontal(|ui| {
+            ui.label("Run at most");
+            ui.add(egui::DragValue::new(&mut governor.parallelism).clamp_range(1..=64).suffix(" task(s)"));
+            ui.label("resting");
+            ui.add(egui::DragValue::new(&mut governor.rest_ms)
+                .speed(10.0)
+                .clamp_range(0..=governor.period_ms.saturating_sub(100))
+                .suffix(" ms"));
+            ui.label(format!("of every {} ms", governor.period_ms));
+        });
+        ui.horizontal(|ui| {
+            if ui.add_enabled(!measuring, egui::Button::new("Measure speed")).clicked() {
+                measure = true;
+            }
+            let train = egui::Button::new("Train on self-play");
+            if ui.add_enabled(!measuring, train).on_hover_text("Play a few games and train the AI on them").clicked() {
+                self_play = true;
+            }
+            if measuring {
+                ui.spinner();
+            }
+        });
+        if let Some(report) = report {
+            ui.label(format!("{}: {:.0} positions/sec", report.backend.label(), report.positions_per_sec));
+            if let Some(fallback) = &report.fallback {
+                ui.colored_label(egui::Color32::YELLOW, fallback);
+            }
+        }
+    });
+    TrainingAction { backend: (picked != chosen).then_some(picked), measure, self_play }
+}
+
+/// Folder field for training games and what they were found to hold;
+/// returns whether to inspect the folder
+fn render_dataset_stats(ui: &mut egui::Ui, input: &mut String, stats: Option<&DatasetStats>, inspecting: bool) -> bool {
+    let mut inspect = false;
+    ui.collapsing("Training data", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Folder:");
+            let field = ui.add(egui::TextEdit::singleline(input).hint_text("folder of .cbor games"));
+            let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.add_enabled(!inspecting, egui::Button::new("Inspect")).clicked() || (entered && !inspecting) {
+                inspect = true;
+            }
+            if inspecting {
+                ui.spinner();
+            }
+        });
+        let Some(stats) = stats else {
+            return;
+        };
+        ui.label(format!("{} games, {} positions", stats.games, stats.positions));
+        let sizes: Vec<String> = stats.board_sizes.iter().map(|(size, n)| format!("{0}x{0}: {1}", size, n)).collect();
+        ui.label(format!("Board sizes: {}", sizes.join(", ")));
+        ui.label(format!("Results: Black {}, White {}, draws {}", stats.black_wins, stats.white_wins, stats.draws));
+        ui.label(format!("Average game length: {:.1} moves", stats.average_game_length));
+        ui.label(format!("To play: Black {}, White {}", stats.black_to_play, stats.white_to_play));
+        ui.label(format!("Duplicate positions: {:.1}%", stats.duplicate_ratio * 100.0));
+        if !stats.labels_sane() {
+            ui.colored_label(egui::Color32::YELLOW, format!(
+                "{} results outside [-1, 1], {} moves off the board",
+                stats.values_out_of_range, stats.moves_out_of_bounds,
+            ));
+        }
+        if ui.button("Copy as Markdown").clicked() {
+            ui.output_mut(|o| o.copied_text = stats.to_markdown());
+        }
+    });
+    inspect
+}
+
+/// What the logging settings asked for
+enum LogAction {
+    Apply,
+    Capture,
+}
+
+/// Log filter field and the trace capture button
+fn render_log_settings(ui: &mut egui::Ui, input: &mut String, error: Option<&str>, capture_until: Option<std::time::Instant>) -> Option<LogAction> {
+    let mut action = None;
+    ui.collapsing("Logging", |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Filter:");
+            let field = ui.add(egui::TextEdit::singleline(input)
+                .hint_text("info,p2pgo_network::game_channel=trace"));
+            let entered = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Apply").clicked() || entered {
+                action = Some(LogAction::Apply);
+            }
+        });
+        if let Some(error) = error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+        match capture_until {
+            Some(until) => {
+                let left = until.saturating_duration_since(std::time::Instant::now());
+                ui.label(format!("Capturing trace logs, {} s left", left.as_secs()));
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+            }
+            None => {
+                let label = format!("Capture next {} s at trace level", CAPTURE_DURATION.as_secs());
+                if ui.button(label).on_hover_text("Writes a separate log file to attach to a bug report").clicked() {
+                    action = Some(LogAction::Capture);
+                }
+            }
+        }
+    });
+    action
+}
+
+/// Alert rules as editable JSON; true when Apply is clicked
+fn render_alert_settings(ui: &mut egui::Ui, input: &mut String, error: Option<&str>) -> bool {
+    let mut apply = false;
+    ui.collapsing("Alerts", |ui| {
+        ui.label("Rules as JSON: metric, window_secs, threshold, action and an optional cooldown_secs");
+        ui.add(egui::TextEdit::multiline(input).code_editor().desired_rows(8));
+        ui.horizontal(|ui| {
+            apply = ui.button("Apply").clicked();
+            if ui.button("Defaults").clicked() {
+                *input = serde_json::to_string_pretty(&alerts::default_rules()).unwrap_or_default();
+            }
+        });
+        if let Some(error) = error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+    });
+    apply
+}
+
+/// The advanced config as JSON, with what was wrong with it or what
+/// applying it did; true when Apply is clicked
+fn render_config_editor(ui: &mut egui::Ui, json: &mut String, errors: &[ConfigError], report: Option<&ApplyReport>) -> bool {
+    ui.label("Network tuning, relay preset, logging, ghost moves and the training governor");
+    ui.add(egui::TextEdit::multiline(json).code_editor().desired_rows(16).desired_width(f32::INFINITY));
+    let mut apply = false;
+    ui.horizontal(|ui| {
+        apply = ui.button("Apply").clicked();
+        if ui.button("Defaults").clicked() {
+            *json = RuntimeConfig::default().to_json();
+        }
+    });
+    for error in errors {
+        ui.colored_label(egui::Color32::RED, error.to_string());
+    }
+    if let Some(report) = report {
+        if report.applied.is_empty() && report.restart_needed.is_empty() {
+            ui.label("Nothing changed");
+        }
+        if !report.applied.is_empty() {
+            ui.label(format!("Applied: {}", report.applied.join(", ")));
+        }
+        if !report.restart_needed.is_empty() {
+            ui.colored_label(egui::Color32::YELLOW, format!("Takes effect after a restart: {}", report.restart_needed.join(", ")));
+        }
+    }
+    apply
+}
+
+/// Invalid messages dropped, by kind, e.g. "Dropped from peers: 3 (length 2, board size 1)"
+fn rejections_label(counts: RejectionCounts) -> String {
+    if counts.total() == 0 {
+        return "Dropped from peers: none".to_string();
     }
+    let kinds: Vec<String> = RejectKind::ALL
+        .iter()
+        .filter(|kind| counts.get(**kind) > 0)
+        .map(|kind| format!("{} {}", kind.label(), counts.get(*kind)))
+        .collect();
+    format!("Dropped from peers: {} ({})", counts.total(), kinds.join(", "))
+}
+
+/// Black's margin as a result, e.g. `B+3.5`
+fn margin_label(margin: f32) -> String {
+    if margin >= 0.0 {
+        format!("B+{:.1}", margin)
+    } else {
+        format!("W+{:.1}", -margin)
+    }
+}
+
+fn render_identity_settings(ui: &mut egui::Ui, identity: &mut IdentitySettings, ui_tx: &Sender<UiToNet>) {
+    ui.collapsing("Identity", |ui| {
+        ui.label(if identity.encrypted {
+            "Key file is passphrase protected; you are asked for it at startup."
+        } else {
+            "Key file is not encrypted."
+        });
+        ui.horizontal(|ui| {
+            ui.label("Passphrase:");
+            ui.add(egui::TextEdit::singleline(&mut identity.passphrase_input).password(true));
+        });
+        ui.horizontal(|ui| {
+            let has_passphrase = !identity.passphrase_input.is_empty();
+            if ui.add_enabled(has_passphrase, egui::Button::new("Set Passphrase")).clicked() {
+                let _ = ui_tx.send(UiToNet::SetIdentityPassphrase { passphrase: identity.passphrase() });
+                identity.passphrase_input.clear();
+            }
+            if identity.encrypted && ui.button("Remove Passphrase").clicked() {
+                let _ = ui_tx.send(UiToNet::SetIdentityPassphrase { passphrase: None });
+            }
+            // An export is protected by the passphrase field if it is filled in
+            if ui.button("Export").clicked() {
+                let _ = ui_tx.send(UiToNet::ExportIdentity { passphrase: identity.passphrase() });
+            }
+        });
+        if let Some(export) = &identity.export {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut export.clone());
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|o| o.copied_text = export.clone());
+                }
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label("Import:");
+            ui.text_edit_singleline(&mut identity.import_input);
+            if ui.add_enabled(!identity.import_input.trim().is_empty(), egui::Button::new("Import")).clicked() {
+                let _ = ui_tx.send(UiToNet::ImportIdentity {
+                    data: identity.import_input.clone(),
+                    passphrase: identity.passphrase(),
+                });
+            }
+        });
+        if let Some(node_id) = &identity.pending_node_id {
+            ui.colored_label(egui::Color32::YELLOW, format!("Restart to use imported node ID {}", node_id));
+        }
+    });
 }
 
 impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.handle_network_messages();
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let window = &frame.info().window_info;
+        self.window_focused = window.focused && !window.minimized;
+        let handled = self.handle_network_messages();
+        self.update_turn_alerts(frame);
+        if let Some(link) = self.invite_link.take() {
+            ctx.output_mut(|o| o.copied_text = link);
+        }
+        
+        let cause = if handled > 0 {
+            RepaintCause::NetMessage
+        } else if ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving()) {
+            RepaintCause::Input
+        } else {
+            RepaintCause::Scheduled
+        };
+        self.repaint_stats.record(cause);
         
         // Handle F1 key to toggle debug overlay
         if ctx.input(|i| i.key_pressed(egui::Key::F1)) {
             self.show_overlay = !self.show_overlay;
             tracing::debug!("Debug overlay toggled: {}", self.show_overlay);
         }
+        // F2 steps through the board overlays
+        if ctx.input(|i| i.key_pressed(egui::Key::F2)) {
+            self.cycle_overlay();
+        }
         
         egui::CentralPanel::default().show(ctx, |ui| {
             match &self.current_view {
@@ -702,6 +4423,12 @@ impl eframe::App for App {
                 View::Lobby { .. } => self.render_lobby(ui),
                 View::Game { .. } => self.render_game(ui),
                 View::ScoreDialog { .. } => self.render_score_dialog(ui),
+                View::Archive { .. } => self.render_archive(ui),
+                View::Review { .. } => self.render_review(ui),
+                View::Puzzle { .. } => self.render_puzzle(ui),
+                View::Openings { .. } => self.render_openings(ui),
+                View::Editor { .. } => self.render_editor(ui),
+                View::Onboarding { .. } => self.render_onboarding(ui),
             }
             
             if let Some(error) = self.error_msg.clone() {
@@ -721,12 +4448,178 @@ impl eframe::App for App {
                     });
             }
             
+            if let Some((game_id, request_id, profile)) = self.join_requests.first().cloned() {
+                egui::Window::new("Join Request")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(format!("{} wants to play you.", profile.name));
+                        if let Some(guild) = &profile.guild {
+                            ui.label(format!("Guild: {}", guild));
+                        }
+                        if let Some(rating) = &profile.self_reported_rating {
+                            ui.label(format!("Rating: {}", rating));
+                        }
+                        ui.label(egui::RichText::new(&profile.node_id).small().color(egui::Color32::GRAY));
+                        ui.horizontal(|ui| {
+                            let mut answer = None;
+                            if ui.button("Accept").clicked() {
+                                answer = Some((true, None));
+                            }
+                            if ui.button("Decline").clicked() {
+                                answer = Some((false, Some("The host declined".to_string())));
+                            }
+                            if let Some((accept, reason)) = answer {
+                                let _ = self.ui_tx.send(UiToNet::RespondJoin { game_id, request_id, accept, reason });
+                                self.join_requests.remove(0);
+                            }
+                        });
+                    });
+            }
+            
+            if let Some(moves) = self.undo_request {
+                egui::Window::new("Undo Request")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        let what = if moves == 1 { "their last move".to_string() } else { format!("the last {} moves", moves) };
+                        ui.label(format!("Your opponent asks to take back {}.", what));
+                        ui.horizontal(|ui| {
+                            if ui.button("Accept").clicked() {
+                                self.answer_undo(true);
+                            }
+                            if ui.button("Decline").clicked() {
+                                self.answer_undo(false);
+                            }
+                        });
+                    });
+            }
+            
+            if let Some(game) = self.join_confirm.clone() {
+                egui::Window::new("Join Game")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(format!("Game {} on a {}×{} board", game.id, game.board_size, game.board_size));
+                        ui.label(game.terms.to_string());
+                        let supported = game.terms.check_supported();
+                        if let Err(e) = &supported {
+                            ui.colored_label(egui::Color32::RED, e.to_string());
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(supported.is_ok(), egui::Button::new("Join")).clicked() {
+                                self.answer_join_confirmation(true);
+                            }
+                            if ui.button("Cancel").clicked() {
+                                self.answer_join_confirmation(false);
+                            }
+                        });
+                    });
+            }
+            
+            if let Some(confirm @ Confirmation::Resign { .. }) = self.move_confirm {
+                egui::Window::new("Resign")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(confirm.prompt());
+                        ui.horizontal(|ui| {
+                            if ui.button("Resign").clicked() {
+                                self.answer_move_confirmation(true);
+                            }
+                            if ui.button("Keep playing").clicked() {
+                                self.answer_move_confirmation(false);
+                            }
+                        });
+                    });
+            }
+            
+            if self.identity.unlock_prompt {
+                egui::Window::new("Unlock Identity")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label("Your identity key is passphrase protected.");
+                        if let Some(error) = &self.identity.unlock_error {
+                            ui.colored_label(egui::Color32::RED, error);
+                        }
+                        let field = ui.add(egui::TextEdit::singleline(&mut self.identity.passphrase_input).password(true));
+                        let submitted = field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                        if (ui.button("Unlock").clicked() || submitted) && !self.identity.passphrase_input.is_empty() {
+                            let passphrase = std::mem::take(&mut self.identity.passphrase_input);
+                            let _ = self.ui_tx.send(UiToNet::UnlockIdentity { passphrase });
+                            self.identity.unlock_prompt = false;
+                            self.identity.encrypted = true;
+                        }
+                    });
+            }
+            
+            if let Some((_, move_count, opponent)) = self.resume_offer.clone() {
+                egui::Window::new("Resume Game")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        let against = opponent.map(|name| format!(" against {}", name)).unwrap_or_default();
+                        ui.label(format!("Your game{} was saved after {} moves when P2P Go closed.", against, move_count));
+                        ui.horizontal(|ui| {
+                            if ui.button("Resume").clicked() {
+                                self.answer_resume_offer(true);
+                            }
+                            if ui.button("Discard").clicked() {
+                                self.answer_resume_offer(false);
+                            }
+                        });
+                    });
+            }
+            
+            if let Some((game_id, idle_for)) = self.idle_prompt.clone() {
+                egui::Window::new("Opponent Idle")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                    .show(ctx, |ui| {
+                        ui.label(format!("No move from your opponent for {} minutes.", idle_for.as_secs() / 60));
+                        ui.label("Abandoning ends the game by forfeit. Unattended games end after 30 minutes without a move.");
+                        ui.horizontal(|ui| {
+                            if ui.button("Abandon Game").clicked() {
+                                let _ = self.ui_tx.send(UiToNet::AbandonGame { game_id: game_id.clone() });
+                                self.idle_prompt = None;
+                            }
+                            if ui.button("Keep Waiting").clicked() {
+                                let _ = self.ui_tx.send(UiToNet::KeepWaiting { game_id: game_id.clone() });
+                                self.idle_prompt = None;
+                            }
+                        });
+                    });
+            }
+            
+            if self.show_config_editor {
+                let mut open = true;
+                let mut apply = false;
+                egui::Window::new("Advanced Config")
+                    .open(&mut open)
+                    .collapsible(false)
+                    .show(ctx, |ui| {
+                        apply = render_config_editor(ui, &mut self.config_json, &self.config_errors, self.config_report.as_ref());
+                    });
+                self.show_config_editor = open;
+                if apply {
+                    self.apply_config();
+                }
+            }
+            
             if self.show_ticket_modal {
                 egui::Window::new("Connect by Ticket")
                     .collapsible(false)
                     .resizable(false)
                     .show(ctx, |ui| {
-                        ui.label("Enter ticket to connect:");
+                        ui.label("Enter a ticket or invite link to connect:");
                         ui.text_edit_singleline(&mut self.ticket_input);
                         ui.horizontal(|ui| {
                             let connect_btn = ui.add_enabled(
@@ -735,10 +4628,8 @@ impl eframe::App for App {
                             );
                             
                             if connect_btn.clicked() {
-                                let _ = self.ui_tx.send(UiToNet::ConnectByTicket { 
-                                    ticket: self.ticket_input.clone() 
-                                });
-                                self.ticket_input.clear();
+                                let text = std::mem::take(&mut self.ticket_input);
+                                self.connect_with(&text);
                                 self.show_ticket_modal = false;
                             }
                             if ui.button("Cancel").clicked() {
@@ -750,10 +4641,50 @@ impl eframe::App for App {
             }
         });
         
+        if matches!(&self.toast, Some((_, shown_at)) if shown_at.elapsed() >= TOAST_DURATION) {
+            self.toast = None;
+        }
+        if let Some((text, shown_at)) = &self.toast {
+            egui::Area::new("toast")
+                .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -24.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(text.as_str()));
+                });
+            ctx.request_repaint_after(TOAST_DURATION.saturating_sub(shown_at.elapsed()));
+        }
+        
+        if let Some(alert) = self.alert.clone() {
+            egui::Area::new("alert")
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 24.0))
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(egui::Color32::from_rgb(230, 160, 0), format!("⚠ {}", alert));
+                            if ui.small_button("Dismiss").clicked() {
+                                self.alert = None;
+                            }
+                        });
+                    });
+                });
+        }
+        
         // Render debug overlay on top
         self.render_debug_overlay(ctx);
         
-        ctx.request_repaint();
+        // Network messages wake us through the repaint waker and input wakes
+        // egui itself, so only the game clock needs a periodic redraw.
+        if matches!(self.current_view, View::Game { .. }) {
+            ctx.request_repaint_after(HEARTBEAT_INTERVAL);
+        }
+        if self.show_overlay {
+            // Keep the FPS counter ticking while it is visible
+            ctx.request_repaint_after(HEARTBEAT_INTERVAL);
+        }
+    }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Lets the worker snapshot the active game before the process exits
+        self.shutdown_worker();
     }
 }
 
@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Browsing and reviewing archived games.
+//!
+//! The browser only holds the lightweight summaries from
+//! [`archiver::list_games_in`]; a game's moves are loaded when it is opened
+//! for review.
+
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use p2pgo_core::archiver::{
+    self, ClassificationFilter, ExportFormat, ExportReport, GameSummary, ImportOptions, ImportProgress, ImportReport,
+};
+use p2pgo_core::GameState;
+
+/// Column the game list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    /// Newest first
+    #[default]
+    Date,
+    Opponent,
+    BoardSize,
+    MoveCount,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [SortKey::Date, SortKey::Opponent, SortKey::BoardSize, SortKey::MoveCount];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Date => "Date",
+            SortKey::Opponent => "Opponent",
+            SortKey::BoardSize => "Size",
+            SortKey::MoveCount => "Moves",
+        }
+    }
+}
+
+/// Formats offered by "Export all…", with their labels
+pub const EXPORT_FORMATS: [(ExportFormat, &str); 3] = [
+    (ExportFormat::ZipOfSgf, "Zip of SGF files"),
+    (ExportFormat::SgfCollection, "One SGF collection"),
+    (ExportFormat::ZipOfCbor, "Zip of raw records"),
+];
+
+/// Choices made in the "Export all…" panel
+#[derive(Debug, Clone)]
+pub struct ExportChoices {
+    pub format: ExportFormat,
+    pub filter: ClassificationFilter,
+}
+
+impl Default for ExportChoices {
+    fn default() -> Self {
+        Self { format: ExportFormat::ZipOfSgf, filter: ClassificationFilter::default() }
+    }
+}
+
+/// The "Import SGF…" panel
+#[derive(Debug, Clone, Default)]
+pub struct ImportDialog {
+    /// Folder of SGF files, as typed
+    pub folder: String,
+    /// Whether an import is under way
+    pub running: bool,
+    /// Last file looked at by the running import
+    pub progress: Option<ImportProgress>,
+    /// How the last import went
+    pub report: Option<ImportReport>,
+}
+
+/// State of the archive view
+#[derive(Debug, Clone)]
+pub struct ArchiveBrowser {
+    dir: PathBuf,
+    games: Vec<GameSummary>,
+    /// Filters by opponent, date, result or tag
+    pub search: String,
+    pub sort: SortKey,
+    /// Open while choosing how to export every game
+    pub export: Option<ExportChoices>,
+    /// Open while importing a folder of SGF files
+    pub import: Option<ImportDialog>,
+}
+
+impl ArchiveBrowser {
+    /// Browse `dir`, listing its games
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let mut browser = Self {
+            dir: dir.into(),
+            games: Vec::new(),
+            search: String::new(),
+            sort: SortKey::default(),
+            export: None,
+            import: None,
+        };
+        browser.refresh()?;
+        Ok(browser)
+    }
+
+    /// Browse the default archive directory
+    pub fn open_default() -> Result<Self> {
+        Self::open(archiver::archive_dir()?)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Re-read the listing from disk
+    pub fn refresh(&mut self) -> Result<()> {
+        self.games = archiver::list_games_in(&self.dir)?;
+        Ok(())
+    }
+
+    /// Games matching the search, in sort order
+    pub fn visible(&self) -> Vec<&GameSummary> {
+        let needle = self.search.trim().to_lowercase();
+        let mut games: Vec<&GameSummary> = self.games
+            .iter()
+            .filter(|g| needle.is_empty() || matches_search(g, &needle))
+            .collect();
+        match self.sort {
+            // The listing is already newest first
+            SortKey::Date => {}
+            SortKey::Opponent => games.sort_by_key(|g| g.opponent.to_lowercase()),
+            SortKey::BoardSize => games.sort_by_key(|g| g.board_size),
+            SortKey::MoveCount => games.sort_by_key(|g| std::cmp::Reverse(g.move_count)),
+        }
+        games
+    }
+
+    /// Load a game for review
+    pub fn open_review(&self, id: &str) -> Result<Review> {
+        Ok(Review::new(id.to_string(), archiver::load_game(&self.dir, id)?))
+    }
+
+    pub fn export_sgf(&self, id: &str) -> Result<String> {
+        archiver::export_sgf(&self.dir, id)
+    }
+
+    /// Write the SGF next to the record and return its path
+    pub fn save_sgf(&self, id: &str) -> Result<PathBuf> {
+        let path = self.dir.join(format!("{}.sgf", id));
+        std::fs::write(&path, self.export_sgf(id)?)?;
+        Ok(path)
+    }
+
+    /// Export every game passing `filter` next to the records and return
+    /// the file written
+    pub fn export_all(&self, format: ExportFormat, filter: &ClassificationFilter) -> Result<(PathBuf, ExportReport)> {
+        let name = match format {
+            ExportFormat::SgfCollection => "all_games.sgf",
+            ExportFormat::ZipOfSgf => "all_games.zip",
+            ExportFormat::ZipOfCbor => "all_records.zip",
+        };
+        let path = self.dir.join(name);
+        let report = archiver::export_all(&self.dir, std::fs::File::create(&path)?, format, filter)?;
+        Ok((path, report))
+    }
+
+    /// Options to import into this archive
+    pub fn import_options(&self) -> ImportOptions {
+        ImportOptions::new(&self.dir)
+    }
+
+    /// Show how far the running import has got
+    pub fn import_progress(&mut self, progress: ImportProgress) {
+        let dialog = self.import.get_or_insert_with(ImportDialog::default);
+        dialog.running = true;
+        dialog.progress = Some(progress);
+    }
+
+    /// Show how the import went and list the games it added
+    pub fn import_finished(&mut self, report: Option<ImportReport>) -> Result<()> {
+        let dialog = self.import.get_or_insert_with(ImportDialog::default);
+        dialog.running = false;
+        dialog.progress = None;
+        if report.is_some() {
+            dialog.report = report;
+            self.refresh()?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, id: &str) -> Result<()> {
+        archiver::delete_game(&self.dir, id)?;
+        self.games.retain(|g| g.id != id);
+        Ok(())
+    }
+
+    pub fn set_training_eligible(&mut self, id: &str, eligible: bool) -> Result<()> {
+        let updated = archiver::set_training_eligible(&self.dir, id, eligible)?;
+        if let Some(game) = self.games.iter_mut().find(|g| g.id == id) {
+            *game = updated;
+        }
+        Ok(())
+    }
+}
+
+fn matches_search(game: &GameSummary, needle: &str) -> bool {
+    game.opponent.to_lowercase().contains(needle)
+        || game.date.contains(needle)
+        || game.result.as_deref().is_some_and(|r| r.to_lowercase().contains(needle))
+        || game.tags.iter().any(|t| t.to_lowercase().contains(needle))
+}
+
+/// Stepping through an archived game
+#[derive(Debug, Clone)]
+pub struct Review {
+    pub id: String,
+    game: GameState,
+    /// Number of moves shown
+    cursor: usize,
+}
+
+impl Review {
+    /// Start at the final position
+    pub fn new(id: String, game: GameState) -> Self {
+        let cursor = game.moves.len();
+        Self { id, game, cursor }
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.game.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.game.moves.is_empty()
+    }
+
+    pub fn board_size(&self) -> u8 {
+        self.game.board_size
+    }
+
+    /// The whole game, whatever the cursor shows
+    pub fn game(&self) -> &GameState {
+        &self.game
+    }
+
+    /// Move the cursor by `delta` moves, clamped to the game
+    pub fn step(&mut self, delta: isize) {
+        self.cursor = self.cursor.saturating_add_signed(delta).min(self.len());
+    }
+
+    pub fn first(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn last(&mut self) {
+        self.cursor = self.len();
+    }
+
+    /// Show the position after `moves` moves, or the last one
+    pub fn seek(&mut self, moves: usize) {
+        self.cursor = moves.min(self.len());
+    }
+
+    /// The position after the moves up to the cursor
+    pub fn position(&self) -> GameState {
+        let mut state = self.game.initial_position();
+        for mv in &self.game.moves[..self.cursor] {
+            if let Err(e) = state.apply_move(mv.clone()) {
+                tracing::warn!(game = %self.id, "Stopping replay at an illegal move: {}", e);
+                break;
+            }
+        }
+        state
+    }
+}
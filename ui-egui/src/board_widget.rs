@@ -1,34 +1,65 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-//! Go board widget for rendering the game board.
+//! The game's board: a [`GoBoardWidget`] with the overlays of the game
+//! view, the tag palette and the messages a click sends.
 
-use eframe::egui::{self, Color32, Stroke, Pos2, Vec2, Rect};
+use eframe::egui;
 use p2pgo_core::{GameState, Color, Coord, Tag};
+use p2pgo_core::teaching::TeachingOverlay;
+use p2pgo_core::territory::TerritoryEstimate;
 use crossbeam_channel::Sender;
+use std::time::Instant;
+use crate::go_board::{Flash, GhostStones, GoBoardWidget, HeatMap, Marks, OutlinedStone, Ownership, Territory, FLASH_DURATION};
 use crate::msg::UiToNet;
 
 /// Widget for rendering and interacting with a Go board
 pub struct BoardWidget {
     /// Board size
     board_size: u8,
-    /// Cell size in pixels
-    cell_size: f32,
     /// Current tag palette selection
     tag_palette: Option<Tag>,
     /// Ghost stones (AI suggestions) to display
     ghost_stones: Vec<Coord>,
+    /// Queued premove, drawn as an outlined stone
+    premove: Option<(Coord, Color)>,
+    /// Point of an illegal move, highlighted briefly
+    flash: Option<(Coord, Instant)>,
+    /// Move probability per point, indexed like `GameState::board`
+    heat_map: Option<Vec<f32>>,
+    /// Whether a heat map for the current position is still on its way
+    heat_map_pending: bool,
+    /// Ownership per point from 1 (Black) to -1 (White), indexed like `GameState::board`
+    ownership: Option<Vec<f32>>,
+    /// Territory estimate and Black's rough score from it
+    territory: Option<(TerritoryEstimate, f32)>,
+    /// Teacher's demonstration stones and marks for the position shown
+    teaching: Option<TeachingOverlay>,
+    /// Whether the pointer was over the board when last rendered
+    hovered: bool,
 }
 
 impl BoardWidget {
     pub fn new(board_size: u8) -> Self {
         Self {
             board_size,
-            cell_size: 30.0,
             tag_palette: None,
             ghost_stones: Vec::new(),
+            premove: None,
+            flash: None,
+            heat_map: None,
+            heat_map_pending: false,
+            ownership: None,
+            territory: None,
+            teaching: None,
+            hovered: false,
         }
     }
 
+    /// Whether the pointer was over the board when last rendered
+    pub fn hovered(&self) -> bool {
+        self.hovered
+    }
+
     /// Get the board size
     pub fn get_board_size(&self) -> u8 {
         self.board_size
@@ -36,14 +67,28 @@ impl BoardWidget {
 
     /// Render the board and return clicked coordinate if any
     pub fn render(&mut self, ui: &mut egui::Ui, game_state: &GameState, ui_tx: Option<&Sender<UiToNet>>) -> Option<Coord> {
-        let board_pixel_size = self.cell_size * (self.board_size as f32 - 1.0);
-        let desired_size = Vec2::splat(board_pixel_size + 40.0); // Extra space for margins
-        
-        let (rect, response) = ui.allocate_exact_size(desired_size, egui::Sense::click());
-        
-        if ui.is_rect_visible(rect) {
-            self.paint_board(ui, rect, game_state);
+        let mut board = GoBoardWidget::new(self.board_size);
+        if self.heat_map.is_some() || self.heat_map_pending {
+            board = board.overlay(HeatMap { map: self.heat_map.as_deref(), pending: self.heat_map_pending });
+        }
+        if let Some(map) = self.ownership.as_deref() {
+            board = board.overlay(Ownership(map));
+        }
+        if let Some((estimate, score)) = &self.territory {
+            board = board.overlay(Territory { estimate, score: *score });
+        }
+        board = board.overlay(GhostStones(&self.ghost_stones));
+        if let Some((coord, color)) = self.premove {
+            board = board.overlay(OutlinedStone(coord, color));
         }
+        if let Some(overlay) = &self.teaching {
+            board = board.overlay(Marks(overlay));
+        }
+        if let Some((at, since)) = self.flash {
+            board = board.overlay(Flash { at, since });
+        }
+        let response = board.show(ui, game_state);
+        self.hovered = response.hovered;
         
         // Handle key bindings for tag palette
         if ui.input(|i| i.key_pressed(egui::Key::A)) {
@@ -60,203 +105,89 @@ impl BoardWidget {
         }
         
         // Handle clicks
-        if response.clicked() {
-            if let Some(pos) = response.interact_pointer_pos() {
-                if let Some(coord) = self.pos_to_coord(pos, rect) {
-                    let shift_held = ui.input(|i| i.modifiers.shift);
-                    
-                    tracing::debug!(
-                        x = coord.x,
-                        y = coord.y,
-                        pos_x = pos.x,
-                        pos_y = pos.y,
-                        shift_held = shift_held,
-                        tag_palette = ?self.tag_palette,
-                        "Board click detected"
-                    );
-                    
-                    // Handle Shift+click for tag palette popup
-                    if shift_held {
-                        let popup_id = ui.id().with("tag_palette_popup");
-                        ui.memory_mut(|mem| mem.open_popup(popup_id));
-                        
-                        // Get response from last widget to position the popup correctly
-                        let last_response = ui.label(""); // Temporary widget to get a response
-                        egui::popup::popup_below_widget(ui, popup_id, &last_response, |ui| {
-                            ui.set_min_width(120.0);
-                            ui.vertical(|ui| {
-                                if ui.button("Activity (A)").clicked() {
-                                    self.tag_palette = Some(Tag::Activity);
-                                    // Send tag to network if UI channel exists
-                                    if let Some(tx) = ui_tx {
-                                        if let Some(gid) = self.extract_game_id_from_ui(ui) {
-                                            let _ = tx.send(UiToNet::SetTag {
-                                                gid,
-                                                seq: 0, // Use current move sequence number
-                                                tag: Tag::Activity,
-                                            });
-                                        }
-                                    }
-                                    ui.memory_mut(|mem| mem.close_popup());
-                                }
-                                if ui.button("Avoidance (B)").clicked() {
-                                    self.tag_palette = Some(Tag::Avoidance);
-                                    if let Some(tx) = ui_tx {
-                                        if let Some(gid) = self.extract_game_id_from_ui(ui) {
-                                            let _ = tx.send(UiToNet::SetTag {
-                                                gid,
-                                                seq: 0,
-                                                tag: Tag::Avoidance,
-                                            });
-                                        }
-                                    }
-                                    ui.memory_mut(|mem| mem.close_popup());
+        if let Some(coord) = response.clicked {
+            let shift_held = ui.input(|i| i.modifiers.shift);
+            
+            tracing::debug!(
+                x = coord.x,
+                y = coord.y,
+                shift_held = shift_held,
+                tag_palette = ?self.tag_palette,
+                "Board click detected"
+            );
+            
+            // Handle Shift+click for tag palette popup
+            if shift_held {
+                let popup_id = ui.id().with("tag_palette_popup");
+                ui.memory_mut(|mem| mem.open_popup(popup_id));
+                
+                // Get response from last widget to position the popup correctly
+                let last_response = ui.label(""); // Temporary widget to get a response
+                egui::popup::popup_below_widget(ui, popup_id, &last_response, |ui| {
+                    ui.set_min_width(120.0);
+                    ui.vertical(|ui| {
+                        if ui.button("Activity (A)").clicked() {
+                            self.tag_palette = Some(Tag::Activity);
+                            // Send tag to network if UI channel exists
+                            if let Some(tx) = ui_tx {
+                                if let Some(gid) = self.extract_game_id_from_ui(ui) {
+                                    let _ = tx.send(UiToNet::SetTag {
+                                        gid,
+                                        seq: 0, // Use current move sequence number
+                                        tag: Tag::Activity,
+                                    });
                                 }
-                                if ui.button("Reactivity (R)").clicked() {
-                                    self.tag_palette = Some(Tag::Reactivity);
-                                    if let Some(tx) = ui_tx {
-                                        if let Some(gid) = self.extract_game_id_from_ui(ui) {
-                                            let _ = tx.send(UiToNet::SetTag {
-                                                gid,
-                                                seq: 0,
-                                                tag: Tag::Reactivity,
-                                            });
-                                        }
-                                    }
-                                    ui.memory_mut(|mem| mem.close_popup());
+                            }
+                            ui.memory_mut(|mem| mem.close_popup());
+                        }
+                        if ui.button("Avoidance (B)").clicked() {
+                            self.tag_palette = Some(Tag::Avoidance);
+                            if let Some(tx) = ui_tx {
+                                if let Some(gid) = self.extract_game_id_from_ui(ui) {
+                                    let _ = tx.send(UiToNet::SetTag {
+                                        gid,
+                                        seq: 0,
+                                        tag: Tag::Avoidance,
+                                    });
                                 }
-                                if ui.button("Clear").clicked() {
-                                    self.tag_palette = None;
-                                    ui.memory_mut(|mem| mem.close_popup());
+                            }
+                            ui.memory_mut(|mem| mem.close_popup());
+                        }
+                        if ui.button("Reactivity (R)").clicked() {
+                            self.tag_palette = Some(Tag::Reactivity);
+                            if let Some(tx) = ui_tx {
+                                if let Some(gid) = self.extract_game_id_from_ui(ui) {
+                                    let _ = tx.send(UiToNet::SetTag {
+                                        gid,
+                                        seq: 0,
+                                        tag: Tag::Reactivity,
+                                    });
                                 }
-                            });
-                        });
-                        return None; // Don't return coordinate for tag popup
-                    }
-                    
-                    // Send debug event for testing
-                    if let Some(tx) = ui_tx {
-                        let _ = tx.send(UiToNet::DebugMovePlaced(coord));
-                    }
-                    return Some(coord);
-                }
+                            }
+                            ui.memory_mut(|mem| mem.close_popup());
+                        }
+                        if ui.button("Clear").clicked() {
+                            self.tag_palette = None;
+                            ui.memory_mut(|mem| mem.close_popup());
+                        }
+                    });
+                });
+                return None; // Don't return coordinate for tag popup
             }
-        }
-        
-        None
-    }
-
-    fn paint_board(&self, ui: &mut egui::Ui, rect: Rect, game_state: &GameState) {
-        let painter = ui.painter_at(rect);
-        
-        // Board background
-        painter.rect_filled(rect, 5.0, Color32::from_rgb(220, 179, 92));
-        
-        let margin = 20.0;
-        let board_rect = Rect::from_min_size(
-            rect.min + Vec2::splat(margin),
-            Vec2::splat(rect.width() - 2.0 * margin),
-        );
-        
-        // Draw grid lines
-        let line_color = Color32::BLACK;
-        let line_stroke = Stroke::new(1.0, line_color);
-        
-        for i in 0..self.board_size {
-            let offset = (i as f32) * self.cell_size;
-            
-            // Vertical lines
-            let start = Pos2::new(board_rect.min.x + offset, board_rect.min.y);
-            let end = Pos2::new(board_rect.min.x + offset, board_rect.max.y);
-            painter.line_segment([start, end], line_stroke);
             
-            // Horizontal lines
-            let start = Pos2::new(board_rect.min.x, board_rect.min.y + offset);
-            let end = Pos2::new(board_rect.max.x, board_rect.min.y + offset);
-            painter.line_segment([start, end], line_stroke);
-        }
-        
-        // Draw star points for standard board sizes
-        if self.board_size == 19 {
-            let star_points = vec![
-                (3, 3), (3, 9), (3, 15),
-                (9, 3), (9, 9), (9, 15),
-                (15, 3), (15, 9), (15, 15),
-            ];
-            for (x, y) in star_points {
-                let pos = self.coord_to_pos(Coord { x, y }, board_rect);
-                painter.circle_filled(pos, 3.0, line_color);
-            }
-        } else if self.board_size == 13 {
-            let star_points = vec![(3, 3), (3, 9), (6, 6), (9, 3), (9, 9)];
-            for (x, y) in star_points {
-                let pos = self.coord_to_pos(Coord { x, y }, board_rect);
-                painter.circle_filled(pos, 3.0, line_color);
-            }
-        } else if self.board_size == 9 {
-            let star_points = vec![(2, 2), (2, 6), (4, 4), (6, 2), (6, 6)];
-            for (x, y) in star_points {
-                let pos = self.coord_to_pos(Coord { x, y }, board_rect);
-                painter.circle_filled(pos, 3.0, line_color);
+            // Send debug event for testing
+            if let Some(tx) = ui_tx {
+                let _ = tx.send(UiToNet::DebugMovePlaced(coord));
             }
+            return Some(coord);
         }
         
-        // Draw stones
-        let stone_radius = self.cell_size * 0.4;
-        for x in 0..self.board_size {
-            for y in 0..self.board_size {
-                let coord = Coord { x, y };
-                // Convert coordinate to index in board vector
-                let idx = (coord.y as usize) * (game_state.board_size as usize) + (coord.x as usize);
-                if let Some(color) = game_state.board.get(idx).and_then(|c| *c) {
-                    let pos = self.coord_to_pos(coord, board_rect);
-                    let stone_color = match color {
-                        Color::Black => Color32::BLACK,
-                        Color::White => Color32::WHITE,
-                    };
-                    painter.circle_filled(pos, stone_radius, stone_color);
-                    painter.circle_stroke(pos, stone_radius, Stroke::new(1.0, Color32::BLACK));
-                }
-            }
-        }
-        
-        // Draw ghost stones (AI suggestions) with 50% alpha
-        for coord in &self.ghost_stones {
-            let pos = self.coord_to_pos(*coord, board_rect);
-            let ghost_color = match game_state.current_player {
-                Color::Black => Color32::from_rgba_unmultiplied(0, 0, 0, 80),
-                Color::White => Color32::from_rgba_unmultiplied(255, 255, 255, 80),
-            };
-            painter.circle_filled(pos, stone_radius * 0.8, ghost_color);
-        }
+        None
     }
 
-    fn coord_to_pos(&self, coord: Coord, board_rect: Rect) -> Pos2 {
-        let x = board_rect.min.x + (coord.x as f32) * self.cell_size;
-        let y = board_rect.min.y + (coord.y as f32) * self.cell_size;
-        Pos2::new(x, y)
-    }
-
-    fn pos_to_coord(&self, pos: Pos2, rect: Rect) -> Option<Coord> {
-        let margin = 20.0;
-        let board_rect = Rect::from_min_size(
-            rect.min + Vec2::splat(margin),
-            Vec2::splat(rect.width() - 2.0 * margin),
-        );
-        
-        if !board_rect.contains(pos) {
-            return None;
-        }
-        
-        let rel_pos = pos - board_rect.min;
-        let x = (rel_pos.x / self.cell_size).round() as u8;
-        let y = (rel_pos.y / self.cell_size).round() as u8;
-        
-        if x < self.board_size && y < self.board_size {
-            Some(Coord { x, y })
-        } else {
-            None
-        }
+    /// Teacher's annotations to draw over the position, if any
+    pub fn set_teaching_overlay(&mut self, overlay: Option<TeachingOverlay>) {
+        self.teaching = overlay;
     }
     
     /// Set ghost stones for AI suggestions
@@ -265,6 +196,39 @@ impl BoardWidget {
         self.ghost_stones = stones;
     }
     
+    /// Heat map to draw, or `None` with `pending` set while one is computed
+    pub fn set_heat_map(&mut self, map: Option<Vec<f32>>, pending: bool) {
+        self.heat_map = map;
+        self.heat_map_pending = pending;
+    }
+    
+    /// Ownership estimate to draw over the stones, or `None` for none
+    pub fn set_ownership(&mut self, map: Option<Vec<f32>>) {
+        self.ownership = map;
+    }
+    
+    /// Territory estimate to tint the board with and its score, or `None`
+    pub fn set_territory(&mut self, territory: Option<(TerritoryEstimate, f32)>) {
+        self.territory = territory;
+    }
+    
+    /// Show or hide the queued premove
+    pub fn set_premove(&mut self, premove: Option<(Coord, Color)>) {
+        self.premove = premove;
+    }
+    
+    /// Highlight `coord` briefly, e.g. after an illegal move there
+    pub fn flash(&mut self, coord: Coord) {
+        self.flash = Some((coord, Instant::now()));
+    }
+    
+    /// The point being highlighted, while the flash lasts
+    pub fn flashed(&self) -> Option<Coord> {
+        self.flash
+            .filter(|(_, since)| since.elapsed() < FLASH_DURATION)
+            .map(|(coord, _)| coord)
+    }
+    
     /// Clear all ghost stones
     #[allow(dead_code)]
     pub fn clear_ghost_stones(&mut self) {
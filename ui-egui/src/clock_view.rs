@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The game clocks on screen.
+//!
+//! The channel keeping the clocks sends snapshots with move
+//! acknowledgements and once a second. Between them the running clock
+//! counts down locally, and a snapshot that disagrees is caught up with
+//! gradually: the shown time moves at most [`CORRECTION_RATE`] faster or
+//! slower than real time, so a running clock never counts up. Only
+//! disagreements over [`SNAP_THRESHOLD`], such as after a reconnect, jump.
+
+use std::time::{Duration, Instant};
+use eframe::egui;
+use p2pgo_core::game_clock::{ClockSnapshot, PlayerTime};
+use p2pgo_core::Color;
+
+/// How much faster or slower than real time a correction may run the clock
+pub const CORRECTION_RATE: f64 = 0.5;
+
+/// Disagreements larger than this are shown at once
+pub const SNAP_THRESHOLD: Duration = Duration::from_secs(3);
+
+/// Time left under which a running clock flashes red
+pub const LOW_TIME: Duration = Duration::from_secs(10);
+
+/// How long a clock shows red after a byo-yomi period is used
+pub const PERIOD_FLASH: Duration = Duration::from_millis(1500);
+
+/// Both clocks as shown, interpolated between snapshots
+#[derive(Debug, Clone)]
+pub struct ClockDisplay {
+    snapshot: ClockSnapshot,
+    received: Instant,
+    /// All time left as shown, Black then White
+    shown: [u64; 2],
+    /// When `shown` was last brought up to date
+    frame: Instant,
+    /// When each player last used a byo-yomi period, Black then White
+    period_used: [Option<Instant>; 2],
+}
+
+fn index(color: Color) -> usize {
+    match color {
+        Color::Black => 0,
+        Color::White => 1,
+    }
+}
+
+impl ClockDisplay {
+    /// Show `snapshot`, received at `now`
+    pub fn new(snapshot: ClockSnapshot, now: Instant) -> Self {
+        let total = |color| snapshot.time(color).total_ms(snapshot.byo_yomi_ms);
+        Self {
+            snapshot,
+            received: now,
+            shown: [total(Color::Black), total(Color::White)],
+            frame: now,
+            period_used: [None; 2],
+        }
+    }
+
+    /// Correct towards a snapshot received at `now`
+    pub fn update(&mut self, snapshot: ClockSnapshot, now: Instant) {
+        self.tick(now);
+        if snapshot.running != self.snapshot.running {
+            // A move or pause: the clock that stopped shows what it was charged
+            *self = Self { period_used: self.period_used, ..Self::new(snapshot, now) };
+            return;
+        }
+        self.snapshot = snapshot;
+        self.received = now;
+    }
+
+    /// Count the running clock down to `now`
+    pub fn tick(&mut self, now: Instant) {
+        let frame = now.saturating_duration_since(self.frame).as_millis() as i64;
+        self.frame = self.frame.max(now);
+        let since_snapshot = now.saturating_duration_since(self.received);
+        for color in [Color::Black, Color::White] {
+            let target = self.snapshot.time_after(color, since_snapshot).total_ms(self.snapshot.byo_yomi_ms);
+            let shown = &mut self.shown[index(color)];
+            if self.snapshot.running != Some(color) {
+                *shown = target;
+                continue;
+            }
+            let predicted = *shown as i64 - frame;
+            let error = target as i64 - predicted;
+            if error.unsigned_abs() > SNAP_THRESHOLD.as_millis() as u64 {
+                *shown = target;
+            } else {
+                let bound = (frame as f64 * CORRECTION_RATE) as i64;
+                *shown = (predicted + error.clamp(-bound, bound)).max(0) as u64;
+            }
+        }
+    }
+
+    /// `color`'s time as shown
+    pub fn time(&self, color: Color) -> PlayerTime {
+        let actual = self.snapshot.time(color);
+        let byo_yomi_ms = self.snapshot.byo_yomi_ms;
+        let mut shown = self.shown[index(color)];
+        if actual.main_ms == 0 {
+            // Catching up must not bring main time back
+            shown = shown.min(actual.periods as u64 * byo_yomi_ms);
+        }
+        PlayerTime::from_total(shown, actual.periods, byo_yomi_ms)
+    }
+
+    /// `color` used a byo-yomi period at `now`
+    pub fn period_used(&mut self, color: Color, now: Instant) {
+        self.period_used[index(color)] = Some(now);
+    }
+
+    /// Whether `color`'s clock still flashes for a period used before `now`
+    pub fn period_flash(&self, color: Color, now: Instant) -> bool {
+        self.period_used[index(color)].is_some_and(|at| now.saturating_duration_since(at) < PERIOD_FLASH)
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        self.snapshot.running
+    }
+
+    pub fn paused(&self) -> bool {
+        self.snapshot.paused
+    }
+}
+
+/// Whole seconds left, rounded up so "0:00" means out of time
+fn seconds(ms: u64) -> u64 {
+    ms.div_ceil(1000)
+}
+
+/// "4:53" in main time, "0:27 (3)" in byo-yomi with the periods left
+pub fn format_time(time: PlayerTime) -> String {
+    let mm_ss = |ms| format!("{}:{:02}", seconds(ms) / 60, seconds(ms) % 60);
+    if time.in_byo_yomi() {
+        format!("{} ({})", mm_ss(time.period_ms), time.periods)
+    } else {
+        mm_ss(time.main_ms)
+    }
+}
+
+/// Whether the time left in main time or the current period is under [`LOW_TIME`]
+pub fn is_low(time: PlayerTime) -> bool {
+    let left = if time.in_byo_yomi() { time.period_ms } else { time.main_ms + time.periods as u64 * time.period_ms };
+    left < LOW_TIME.as_millis() as u64
+}
+
+/// Both clocks, the running one in bold and flashing red when low; a
+/// clock that just used a byo-yomi period shows solid red for a moment
+pub fn render(ui: &mut egui::Ui, display: &ClockDisplay) {
+    let now = Instant::now();
+    for (color, stone) in [(Color::Black, "⚫"), (Color::White, "⚪")] {
+        let time = display.time(color);
+        let mut text = egui::RichText::new(format!("{} {}", stone, format_time(time))).monospace();
+        if display.running() == Some(color) {
+            text = text.strong();
+            if is_low(time) {
+                let on = (ui.input(|i| i.time) * 2.0).fract() < 0.5;
+                text = text.color(if on { egui::Color32::RED } else { egui::Color32::from_rgb(120, 30, 30) });
+            }
+        }
+        if display.period_flash(color, now) {
+            text = text.color(egui::Color32::WHITE).background_color(egui::Color32::RED);
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+        }
+        ui.label(text);
+    }
+    if display.paused() {
+        ui.weak("Clock paused while your opponent is away");
+    } else if display.running().is_some() {
+        ui.ctx().request_repaint_after(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Free-placement board editor for teaching and test positions.
+
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use p2pgo_core::sgf::SgfProcessor;
+use p2pgo_core::{Color, Coord, GameError, GameState};
+
+/// Komi the editor starts with: the one games of this size are scored with
+pub use p2pgo_core::settings::standard_komi;
+
+/// A position being set up stone by stone
+#[derive(Debug, Clone)]
+pub struct BoardEditor {
+    board_size: u8,
+    /// Stones in the order they were placed
+    stones: Vec<(Coord, Color)>,
+    pub to_move: Color,
+    pub komi: f32,
+    /// Suggested moves from the last analysis
+    pub analysis: Vec<Coord>,
+}
+
+impl BoardEditor {
+    pub fn new(board_size: u8) -> Self {
+        Self {
+            board_size,
+            stones: Vec::new(),
+            to_move: Color::Black,
+            komi: standard_komi(board_size),
+            analysis: Vec::new(),
+        }
+    }
+
+    pub fn board_size(&self) -> u8 {
+        self.board_size
+    }
+
+    pub fn stones(&self) -> &[(Coord, Color)] {
+        &self.stones
+    }
+
+    /// Cycle a point empty → black → white → empty, without legality checks
+    pub fn cycle(&mut self, coord: Coord) {
+        if !coord.is_valid(self.board_size) {
+            return;
+        }
+        self.analysis.clear();
+        match self.stones.iter().position(|(c, _)| *c == coord) {
+            None => self.stones.push((coord, Color::Black)),
+            Some(i) if self.stones[i].1 == Color::Black => self.stones[i].1 = Color::White,
+            Some(i) => {
+                self.stones.remove(i);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.stones.clear();
+        self.analysis.clear();
+    }
+
+    /// The position as drawn, whether or not play could start from it
+    pub fn position(&self) -> GameState {
+        let mut state = GameState::new(self.board_size);
+        for &(coord, color) in &self.stones {
+            state.board[coord.to_index(self.board_size)] = Some(color);
+        }
+        state.setup = self.stones.clone();
+        state.current_player = self.to_move;
+        state
+    }
+
+    /// The position to start a game or an analysis from
+    pub fn to_game(&self) -> Result<GameState, GameError> {
+        GameState::from_setup(self.board_size, &self.stones, self.to_move)
+    }
+
+    /// The position as SGF with AB/AW setup properties
+    pub fn export_sgf(&self) -> String {
+        SgfProcessor::new(self.position()).with_komi(self.komi).generate()
+    }
+
+    /// Write the position to a new SGF file in `dir`
+    pub fn save_sgf(&self, dir: &Path) -> Result<PathBuf> {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!("setup-{}.sgf", stamp));
+        std::fs::write(&path, self.export_sgf())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Idempotent application of move events to the UI's game state.
+//!
+//! Moves can reach the app twice (gossip and direct stream) or in the
+//! wrong order, so every `MoveMade` is checked against the current
+//! position before it is applied.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use p2pgo_core::{Color, GameState, Move};
+
+/// Outcome of offering a move event to a game state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveOutcome {
+    /// The move advanced the game
+    Applied,
+    /// The move was already applied; nothing changed
+    Duplicate,
+    /// The move does not follow the current position; nothing changed
+    OutOfOrder,
+}
+
+/// Hash of the position: board, side to move and move count
+pub fn position_hash(state: &GameState) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    state.board_size.hash(&mut hasher);
+    state.board.hash(&mut hasher);
+    state.current_player.hash(&mut hasher);
+    state.moves.len().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Apply `mv` played by `by` only if it advances `state`
+pub fn apply_move_event(state: &mut GameState, mv: &Move, by: Color) -> MoveOutcome {
+    if by != state.current_player {
+        // The same move echoed back from a second transport
+        if state.moves.last() == Some(mv) {
+            return MoveOutcome::Duplicate;
+        }
+        return MoveOutcome::OutOfOrder;
+    }
+
+    // Apply on a copy so a rejected move cannot leave a half-updated state
+    let mut next = state.clone();
+    match next.apply_move(mv.clone()) {
+        Ok(_) => {
+            *state = next;
+            MoveOutcome::Applied
+        }
+        Err(_) => MoveOutcome::OutOfOrder,
+    }
+}
+
+/// Whether a snapshot from the worker should replace the local state
+pub fn should_accept_snapshot(current: &GameState, snapshot: &GameState) -> bool {
+    snapshot.board_size == current.board_size
+        && snapshot.moves.len() >= current.moves.len()
+        && position_hash(snapshot) != position_hash(current)
+}
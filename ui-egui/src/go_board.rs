@@ -0,0 +1,479 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Self-contained Go board widget.
+//!
+//! [`GoBoardWidget`] paints a position from `p2pgo-core`, reports clicks
+//! when interactive, and draws [`BoardOverlay`]s on top. It depends on egui
+//! and p2pgo-core alone, so other egui apps can embed the board without the
+//! rest of this crate. The overlays the app itself uses are here too.
+//!
+//! Rendering a static position:
+//!
+//! ```
+//! use p2pgo_core::teaching::{Marker, TeachingOverlay};
+//! use p2pgo_core::{Coord, GameState, Move};
+//! use p2pgo_ui_egui::go_board::{GoBoardWidget, Marks};
+//!
+//! let mut state = GameState::new(9);
+//! state.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+//! state.apply_move(Move::Place(Coord::new(6, 6))).unwrap();
+//! let mut labels = TeachingOverlay::default();
+//! labels.marks.push((Coord::new(4, 4), Marker::Label("a".to_string())));
+//!
+//! let ctx = egui::Context::default();
+//! let _ = ctx.run(Default::default(), |ctx| {
+//!     egui::CentralPanel::default().show(ctx, |ui| {
+//!         let response = GoBoardWidget::new(9)
+//!             .cell_size(24.0)
+//!             .interactive(false)
+//!             .overlay(Marks(&labels))
+//!             .show(ui, &state);
+//!         assert_eq!(response.clicked, None);
+//!     });
+//! });
+//! ```
+
+use std::time::{Duration, Instant};
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use p2pgo_core::teaching::{Marker, TeachingOverlay};
+use p2pgo_core::territory::{Region, TerritoryEstimate};
+use p2pgo_core::{Color, Coord, GameState};
+
+/// Space between the board's edge and the outermost lines
+const MARGIN: f32 = 20.0;
+
+/// How long a flashed point stays highlighted
+pub const FLASH_DURATION: Duration = Duration::from_millis(900);
+
+/// Colours of the board and stones
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardTheme {
+    pub background: Color32,
+    pub line: Color32,
+    pub black: Color32,
+    pub white: Color32,
+    /// Outline drawn around every stone
+    pub stone_outline: Color32,
+}
+
+impl Default for BoardTheme {
+    /// Light wood with black lines
+    fn default() -> Self {
+        Self {
+            background: Color32::from_rgb(220, 179, 92),
+            line: Color32::BLACK,
+            black: Color32::BLACK,
+            white: Color32::WHITE,
+            stone_outline: Color32::BLACK,
+        }
+    }
+}
+
+impl BoardTheme {
+    pub fn stone(&self, color: Color) -> Color32 {
+        match color {
+            Color::Black => self.black,
+            Color::White => self.white,
+        }
+    }
+}
+
+/// Where the grid sits on screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoardGeometry {
+    pub board_size: u8,
+    pub cell_size: f32,
+    /// From the top-left to the bottom-right intersection
+    pub grid: Rect,
+}
+
+impl BoardGeometry {
+    /// The grid inside `rect`, a widget allocated by [`GoBoardWidget`]
+    pub fn new(board_size: u8, cell_size: f32, rect: Rect) -> Self {
+        Self {
+            board_size,
+            cell_size,
+            grid: Rect::from_min_size(rect.min + Vec2::splat(MARGIN), Vec2::splat(rect.width() - 2.0 * MARGIN)),
+        }
+    }
+
+    /// Screen position of an intersection
+    pub fn pos(&self, coord: Coord) -> Pos2 {
+        self.grid.min + Vec2::new(coord.x as f32, coord.y as f32) * self.cell_size
+    }
+
+    /// The intersection nearest `pos`, if it is on the board
+    pub fn coord_at(&self, pos: Pos2) -> Option<Coord> {
+        if !self.grid.contains(pos) {
+            return None;
+        }
+        let rel = pos - self.grid.min;
+        let x = (rel.x / self.cell_size).round() as u8;
+        let y = (rel.y / self.cell_size).round() as u8;
+        (x < self.board_size && y < self.board_size).then_some(Coord { x, y })
+    }
+
+    pub fn stone_radius(&self) -> f32 {
+        self.cell_size * 0.4
+    }
+
+    /// Coordinate of the `idx`th point, indexed like `GameState::board`
+    pub fn coord_of_index(&self, idx: usize) -> Coord {
+        let size = self.board_size as usize;
+        Coord::new((idx % size) as u8, (idx / size) as u8)
+    }
+}
+
+/// Everything an overlay may draw with
+pub struct BoardCanvas<'a> {
+    pub ui: &'a egui::Ui,
+    pub painter: &'a egui::Painter,
+    pub geometry: BoardGeometry,
+    pub state: &'a GameState,
+    pub theme: &'a BoardTheme,
+}
+
+/// When an overlay is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayLayer {
+    /// On the grid, under the stones
+    BelowStones,
+    /// Over the stones
+    AboveStones,
+}
+
+/// Something drawn on the board besides the position itself
+pub trait BoardOverlay {
+    fn layer(&self) -> OverlayLayer {
+        OverlayLayer::AboveStones
+    }
+
+    fn paint(&self, canvas: &BoardCanvas<'_>);
+}
+
+/// What happened to the board this frame
+#[derive(Debug, Clone)]
+pub struct BoardResponse {
+    pub response: egui::Response,
+    /// Intersection clicked, only when interactive
+    pub clicked: Option<Coord>,
+    pub hovered: bool,
+}
+
+/// Go board, configured with a builder and shown once per frame
+pub struct GoBoardWidget<'o> {
+    board_size: u8,
+    cell_size: f32,
+    theme: BoardTheme,
+    interactive: bool,
+    overlays: Vec<Box<dyn BoardOverlay + 'o>>,
+}
+
+impl<'o> GoBoardWidget<'o> {
+    /// An interactive board of `board_size` lines in the default theme
+    pub fn new(board_size: u8) -> Self {
+        Self {
+            board_size,
+            cell_size: 30.0,
+            theme: BoardTheme::default(),
+            interactive: true,
+            overlays: Vec::new(),
+        }
+    }
+
+    /// Distance between lines in points
+    pub fn cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    pub fn theme(mut self, theme: BoardTheme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Whether clicks are reported; a static board only senses hovering
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Draw `overlay` after those added before it, in its layer
+    pub fn overlay(mut self, overlay: impl BoardOverlay + 'o) -> Self {
+        self.overlays.push(Box::new(overlay));
+        self
+    }
+
+    /// Allocate the board in `ui` and paint `state`
+    pub fn show(self, ui: &mut egui::Ui, state: &GameState) -> BoardResponse {
+        let side = self.cell_size * (self.board_size as f32 - 1.0) + 2.0 * MARGIN;
+        let sense = if self.interactive { egui::Sense::click() } else { egui::Sense::hover() };
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(side), sense);
+        let geometry = BoardGeometry::new(self.board_size, self.cell_size, rect);
+
+        if ui.is_rect_visible(rect) {
+            let painter = ui.painter_at(rect);
+            let canvas = BoardCanvas { ui, painter: &painter, geometry, state, theme: &self.theme };
+            self.paint_grid(&canvas, rect);
+            self.paint_layer(&canvas, OverlayLayer::BelowStones);
+            self.paint_stones(&canvas);
+            self.paint_layer(&canvas, OverlayLayer::AboveStones);
+        }
+
+        let clicked = if self.interactive && response.clicked() {
+            response.interact_pointer_pos().and_then(|pos| geometry.coord_at(pos))
+        } else {
+            None
+        };
+        BoardResponse { hovered: response.hovered(), clicked, response }
+    }
+
+    fn paint_grid(&self, canvas: &BoardCanvas<'_>, rect: Rect) {
+        let painter = canvas.painter;
+        let grid = canvas.geometry.grid;
+        painter.rect_filled(rect, 5.0, self.theme.background);
+
+        let stroke = Stroke::new(1.0, self.theme.line);
+        for i in 0..self.board_size {
+            let offset = i as f32 * self.cell_size;
+            painter.line_segment([Pos2::new(grid.min.x + offset, grid.min.y), Pos2::new(grid.min.x + offset, grid.max.y)], stroke);
+            painter.line_segment([Pos2::new(grid.min.x, grid.min.y + offset), Pos2::new(grid.max.x, grid.min.y + offset)], stroke);
+        }
+
+        for &(x, y) in star_points(self.board_size) {
+            painter.circle_filled(canvas.geometry.pos(Coord { x, y }), 3.0, self.theme.line);
+        }
+    }
+
+    fn paint_stones(&self, canvas: &BoardCanvas<'_>) {
+        let radius = canvas.geometry.stone_radius();
+        let size = canvas.state.board_size as usize;
+        for (idx, point) in canvas.state.board.iter().enumerate() {
+            if let Some(color) = point {
+                let pos = canvas.geometry.pos(Coord::new((idx % size) as u8, (idx / size) as u8));
+                canvas.painter.circle_filled(pos, radius, self.theme.stone(*color));
+                canvas.painter.circle_stroke(pos, radius, Stroke::new(1.0, self.theme.stone_outline));
+            }
+        }
+    }
+
+    fn paint_layer(&self, canvas: &BoardCanvas<'_>, layer: OverlayLayer) {
+        for overlay in self.overlays.iter().filter(|o| o.layer() == layer) {
+            overlay.paint(canvas);
+        }
+    }
+}
+
+/// Star points for the standard board sizes
+fn star_points(board_size: u8) -> &'static [(u8, u8)] {
+    match board_size {
+        19 => &[(3, 3), (3, 9), (3, 15), (9, 3), (9, 9), (9, 15), (15, 3), (15, 9), (15, 15)],
+        13 => &[(3, 3), (3, 9), (6, 6), (9, 3), (9, 9)],
+        9 => &[(2, 2), (2, 6), (4, 4), (6, 2), (6, 6)],
+        _ => &[],
+    }
+}
+
+/// Move probability per point, shaded relative to the likeliest move.
+/// Without a map yet, a faint pulse while one is computed.
+pub struct HeatMap<'a> {
+    pub map: Option<&'a [f32]>,
+    pub pending: bool,
+}
+
+impl BoardOverlay for HeatMap<'_> {
+    fn layer(&self) -> OverlayLayer {
+        OverlayLayer::BelowStones
+    }
+
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let geometry = canvas.geometry;
+        if let Some(map) = self.map {
+            let max = map.iter().copied().fold(0.0f32, f32::max);
+            if max <= 0.0 {
+                return;
+            }
+            for (idx, p) in map.iter().enumerate() {
+                let alpha = (160.0 * p / max) as u8;
+                if alpha > 0 {
+                    canvas.painter.rect_filled(
+                        Rect::from_center_size(geometry.pos(geometry.coord_of_index(idx)), Vec2::splat(geometry.cell_size * 0.8)),
+                        2.0,
+                        Color32::from_rgba_unmultiplied(230, 60, 20, alpha),
+                    );
+                }
+            }
+        } else if self.pending {
+            let phase = (canvas.ui.input(|i| i.time) * 3.0).sin() as f32 * 0.5 + 0.5;
+            canvas.painter.rect_filled(geometry.grid, 0.0, Color32::from_rgba_unmultiplied(230, 60, 20, (12.0 + 18.0 * phase) as u8));
+            canvas.ui.ctx().request_repaint();
+        }
+    }
+}
+
+/// Small squares in the owner's colour, stronger the surer the estimate.
+/// Values run from 1 (Black) to -1 (White), indexed like `GameState::board`.
+pub struct Ownership<'a>(pub &'a [f32]);
+
+impl BoardOverlay for Ownership<'_> {
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let geometry = canvas.geometry;
+        for (idx, owner) in self.0.iter().enumerate() {
+            let alpha = (200.0 * owner.abs().min(1.0)) as u8;
+            if alpha < 20 {
+                continue;
+            }
+            let color = if *owner > 0.0 {
+                Color32::from_rgba_unmultiplied(30, 60, 200, alpha)
+            } else {
+                Color32::from_rgba_unmultiplied(255, 255, 255, alpha)
+            };
+            canvas.painter.rect_filled(
+                Rect::from_center_size(geometry.pos(geometry.coord_of_index(idx)), Vec2::splat(geometry.cell_size * 0.35)),
+                1.0,
+                color,
+            );
+        }
+    }
+}
+
+/// Empty points tinted in the colour they lean to, territory strongest,
+/// with Black's rough `score` in the corner
+pub struct Territory<'a> {
+    pub estimate: &'a TerritoryEstimate,
+    pub score: f32,
+}
+
+impl BoardOverlay for Territory<'_> {
+    fn layer(&self) -> OverlayLayer {
+        OverlayLayer::BelowStones
+    }
+
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let geometry = canvas.geometry;
+        for (idx, (region, lean)) in self.estimate.regions.iter().zip(&self.estimate.influence).enumerate() {
+            let alpha = match region {
+                Region::Stone(_) => continue,
+                Region::Territory(_) => 110,
+                Region::Neutral => (80.0 * lean.abs().min(1.0)) as u8,
+            };
+            if alpha < 8 {
+                continue;
+            }
+            let color = if *lean > 0.0 {
+                Color32::from_rgba_unmultiplied(20, 20, 20, alpha)
+            } else {
+                Color32::from_rgba_unmultiplied(255, 255, 255, alpha)
+            };
+            canvas.painter.rect_filled(
+                Rect::from_center_size(geometry.pos(geometry.coord_of_index(idx)), Vec2::splat(geometry.cell_size)),
+                0.0,
+                color,
+            );
+        }
+        let leader = if self.score >= 0.0 { "B" } else { "W" };
+        canvas.painter.text(
+            geometry.grid.right_top() + Vec2::new(geometry.cell_size * 0.5, -geometry.cell_size * 0.5),
+            egui::Align2::RIGHT_BOTTOM,
+            format!("~{}+{:.1}", leader, self.score.abs()),
+            egui::FontId::proportional(geometry.cell_size * 0.45),
+            canvas.theme.stone(Color::Black),
+        );
+    }
+}
+
+/// Translucent stones of the player to move, e.g. suggested moves
+pub struct GhostStones<'a>(pub &'a [Coord]);
+
+impl BoardOverlay for GhostStones<'_> {
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let color = match canvas.state.current_player {
+            Color::Black => Color32::from_rgba_unmultiplied(0, 0, 0, 80),
+            Color::White => Color32::from_rgba_unmultiplied(255, 255, 255, 80),
+        };
+        for coord in self.0 {
+            canvas.painter.circle_filled(canvas.geometry.pos(*coord), canvas.geometry.stone_radius() * 0.8, color);
+        }
+    }
+}
+
+/// A stone drawn as an outline, e.g. a queued premove
+pub struct OutlinedStone(pub Coord, pub Color);
+
+impl BoardOverlay for OutlinedStone {
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let stroke = Stroke::new(2.0, canvas.theme.stone(self.1));
+        canvas.painter.circle_stroke(canvas.geometry.pos(self.0), canvas.geometry.stone_radius(), stroke);
+    }
+}
+
+/// A red ring fading out over [`FLASH_DURATION`] from `since`
+pub struct Flash {
+    pub at: Coord,
+    pub since: Instant,
+}
+
+impl BoardOverlay for Flash {
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let elapsed = self.since.elapsed();
+        if elapsed >= FLASH_DURATION {
+            return;
+        }
+        let fade = 1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32();
+        canvas.painter.circle_stroke(
+            canvas.geometry.pos(self.at),
+            canvas.geometry.stone_radius(),
+            Stroke::new(3.0, Color32::from_rgba_unmultiplied(220, 30, 30, (255.0 * fade) as u8)),
+        );
+        canvas.ui.ctx().request_repaint();
+    }
+}
+
+/// Demonstration stones ringed in blue, then triangles and labels in a
+/// colour that shows on the point below
+pub struct Marks<'a>(pub &'a TeachingOverlay);
+
+impl BoardOverlay for Marks<'_> {
+    fn paint(&self, canvas: &BoardCanvas<'_>) {
+        let BoardCanvas { painter, geometry, state, theme, .. } = canvas;
+        let radius = geometry.stone_radius();
+        let ring = Stroke::new(2.0, Color32::from_rgb(30, 110, 200));
+        for (coord, color) in &self.0.stones {
+            let pos = geometry.pos(*coord);
+            let fill = match color {
+                Color::Black => Color32::from_rgba_unmultiplied(0, 0, 0, 150),
+                Color::White => Color32::from_rgba_unmultiplied(255, 255, 255, 170),
+            };
+            painter.circle_filled(pos, radius, fill);
+            painter.circle_stroke(pos, radius, ring);
+        }
+
+        for (coord, marker) in &self.0.marks {
+            let pos = geometry.pos(*coord);
+            let idx = coord.to_index(state.board_size);
+            let under = self.0.stone_at(*coord).or_else(|| state.board.get(idx).copied().flatten());
+            let ink = match under {
+                Some(Color::Black) => Color32::WHITE,
+                _ => Color32::from_rgb(30, 110, 200),
+            };
+            match marker {
+                Marker::Triangle => {
+                    let r = radius * 0.6;
+                    let points = vec![
+                        pos + Vec2::new(0.0, -r),
+                        pos + Vec2::new(r * 0.87, r * 0.5),
+                        pos + Vec2::new(-r * 0.87, r * 0.5),
+                    ];
+                    painter.add(egui::Shape::closed_line(points, Stroke::new(2.0, ink)));
+                }
+                Marker::Label(text) => {
+                    // Clear the grid lines under a label on an empty point
+                    if under.is_none() {
+                        painter.circle_filled(pos, radius * 0.6, theme.background);
+                    }
+                    painter.text(pos, egui::Align2::CENTER_CENTER, text, egui::FontId::proportional(geometry.cell_size * 0.5), ink);
+                }
+            }
+        }
+    }
+}
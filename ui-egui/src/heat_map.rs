@@ -0,0 +1,160 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Move heat maps from the policy network: computed by the worker once per
+//! position, drawn by the board only while they match the position shown.
+//! Each map comes with an [`Outlook`]: how likely the model is to pass, and
+//! whether it would resign.
+
+use p2pgo_core::engine::{value_for_mover, NeuralConfig};
+use p2pgo_core::GameState;
+use std::collections::{HashMap, VecDeque};
+use trainer::evaluate::{pass_probability, point_probabilities, Evaluation};
+
+pub use crate::event_filter::position_hash;
+
+/// Positions whose maps the worker keeps
+pub const HEAT_MAP_CACHE_SIZE: usize = 64;
+
+/// Turn 9x9 policy logits into a probability per point of `state`'s board,
+/// as `p2pgo-cli analyze` does.
+///
+/// Smaller boards sit centred in the model's 9x9 input. Occupied points get
+/// zero and the rest sum to one; the pass logit is not part of the map.
+pub fn from_logits(logits: &[f32], state: &GameState) -> Vec<f32> {
+    point_probabilities(logits, 9, state)
+}
+
+/// Pass and resignation advice for a position, shown beside its heat map
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outlook {
+    /// Chance the model gives passing, among pass and the points worth playing
+    pub pass_probability: f32,
+    /// Whether the player to move has lost by `NeuralConfig::resign_threshold`
+    pub resign_recommended: bool,
+}
+
+impl Outlook {
+    /// Advice from the model's `evaluation` of `state`
+    pub fn from_evaluation(evaluation: &Evaluation, state: &GameState, config: &NeuralConfig) -> Self {
+        Self {
+            pass_probability: pass_probability(&evaluation.logits, 9, state),
+            resign_recommended: config.hopeless(value_for_mover(state, evaluation.value)),
+        }
+    }
+}
+
+/// Computed maps by position hash, dropping the oldest past capacity
+#[derive(Debug)]
+pub struct HeatMapCache<T = Vec<f32>> {
+    capacity: usize,
+    maps: HashMap<u64, T>,
+    order: VecDeque<u64>,
+}
+
+impl HeatMapCache {
+    pub fn new(capacity: usize) -> Self {
+        Self::with_capacity(capacity)
+    }
+}
+
+impl<T> HeatMapCache<T> {
+    /// A cache of anything computed per position, such as a map with its outlook
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            maps: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn get(&self, position_hash: u64) -> Option<&T> {
+        self.maps.get(&position_hash)
+    }
+
+    /// The cached map for `position_hash`, running `compute` only on a miss
+    pub fn get_or_compute(
+        &mut self,
+        position_hash: u64,
+        compute: impl FnOnce() -> anyhow::Result<T>,
+    ) -> anyhow::Result<&T> {
+        if !self.maps.contains_key(&position_hash) {
+            self.insert(position_hash, compute()?);
+        }
+        Ok(&self.maps[&position_hash])
+    }
+
+    /// Keep `map` for `position_hash`, replacing any map it had
+    pub fn insert(&mut self, position_hash: u64, map: T) {
+        if self.maps.insert(position_hash, map).is_some() {
+            return;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.maps.remove(&oldest);
+            }
+        }
+        self.order.push_back(position_hash);
+    }
+}
+
+/// What the board's heat map overlay shows
+#[derive(Debug, Default)]
+pub struct HeatMapOverlay {
+    /// Position on screen that a map was asked for
+    wanted: Option<u64>,
+    /// Latest map received for `wanted`
+    map: Option<(u64, Vec<f32>)>,
+    /// Latest outlook received for `wanted`
+    outlook: Option<(u64, Outlook)>,
+}
+
+impl HeatMapOverlay {
+    /// Note the position on screen; true if its map still has to be requested
+    pub fn want(&mut self, position_hash: u64) -> bool {
+        if self.wanted == Some(position_hash) {
+            return false;
+        }
+        self.wanted = Some(position_hash);
+        true
+    }
+
+    /// Keep a map from the worker unless the board has already moved on
+    pub fn receive(&mut self, position_hash: u64, map: Vec<f32>) {
+        if self.wanted == Some(position_hash) {
+            self.map = Some((position_hash, map));
+        }
+    }
+
+    /// Keep an outlook from the worker unless the board has already moved on
+    pub fn receive_outlook(&mut self, position_hash: u64, outlook: Outlook) {
+        if self.wanted == Some(position_hash) {
+            self.outlook = Some((position_hash, outlook));
+        }
+    }
+
+    /// The outlook to show, only if it belongs to the position on screen
+    pub fn outlook(&self) -> Option<Outlook> {
+        match self.outlook {
+            Some((hash, outlook)) if self.wanted == Some(hash) => Some(outlook),
+            _ => None,
+        }
+    }
+
+    /// The map to draw, only if it belongs to the position on screen
+    pub fn current(&self) -> Option<&[f32]> {
+        match &self.map {
+            Some((hash, map)) if self.wanted == Some(*hash) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Whether a map has been asked for and hasn't arrived yet
+    pub fn is_computing(&self) -> bool {
+        self.wanted.is_some() && self.current().is_none()
+    }
+
+    /// Forget the position, e.g. when the overlay is turned off
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The solo ladder: picking an unlocked opponent, and how a game against
+//! it went.
+
+use eframe::egui;
+use p2pgo_core::analysis::GameReport;
+use p2pgo_core::ladder::{LADDER_BOARD_SIZE, RUNGS};
+use p2pgo_core::settings::standard_komi;
+use p2pgo_core::{Color, Move};
+
+/// Game ID of a ladder game, which no peer ever sees
+pub const LADDER_GAME_ID: &str = "ladder";
+
+/// A game against a ladder opponent
+#[derive(Debug, Clone, PartialEq)]
+pub struct LadderGame {
+    /// Index into [`RUNGS`]
+    pub rung: usize,
+    /// The color the player has
+    pub human: Color,
+    /// Seed of the opponent's moves
+    pub seed: u64,
+    /// Whether the opponent's move has been asked for and not yet played
+    pub thinking: bool,
+    /// Whether the player won, once the game is over
+    pub won: Option<bool>,
+    /// Komi the result is counted with
+    pub komi: f32,
+    /// Whether the result counts towards the ladder; not for games from
+    /// an edited position
+    pub ranked: bool,
+}
+
+impl LadderGame {
+    pub fn new(rung: usize, seed: u64) -> Self {
+        Self {
+            rung,
+            human: Color::Black,
+            seed,
+            thinking: false,
+            won: None,
+            komi: standard_komi(LADDER_BOARD_SIZE),
+            ranked: true,
+        }
+    }
+
+    /// A practice game against the opponent on `rung`, with the player
+    /// taking `human` and the result counted with `komi`
+    pub fn unranked(rung: usize, seed: u64, human: Color, komi: f32) -> Self {
+        Self { human, komi, ranked: false, ..Self::new(rung, seed) }
+    }
+
+    pub fn name(&self) -> &'static str {
+        RUNGS[self.rung].name
+    }
+
+    /// Whether the player may move with `to_move` to play
+    pub fn awaits_player(&self, to_move: Color) -> bool {
+        !self.thinking && self.won.is_none() && to_move == self.human
+    }
+}
+
+/// The ladder with a button for each unlocked rung; the rung picked, if any
+pub fn render_rungs(ui: &mut egui::Ui, unlocked: usize) -> Option<usize> {
+    let mut picked = None;
+    for (i, rung) in RUNGS.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if i <= unlocked {
+                if ui.button(format!("Play {}", rung.name)).clicked() {
+                    picked = Some(i);
+                }
+            } else {
+                ui.add_enabled(false, egui::Button::new(format!("🔒 {}", rung.name)))
+                    .on_disabled_hover_text("Beat the rung before to unlock");
+            }
+        });
+    }
+    picked
+}
+
+/// Where the player's evaluation fell the most, if it fell enough to count
+pub fn biggest_mistake_text(report: &GameReport, human: Color, board_size: u8) -> Option<String> {
+    let mistake = report.biggest_mistake(human)?;
+    let mv = match &mistake.mv {
+        Move::Place(coord) => coord.display_label(board_size),
+        Move::Pass => "Pass".to_string(),
+        Move::Resign => "Resign".to_string(),
+    };
+    Some(format!(
+        "Your biggest mistake: move {} ({}), where the evaluation fell by {:.2}",
+        mistake.index + 1, mv, mistake.swing
+    ))
+}
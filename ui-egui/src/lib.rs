@@ -9,7 +9,27 @@ pub mod app;
 pub mod view;
 pub mod msg;
 pub mod board_widget;
+pub mod go_board;
 pub mod worker;
+pub mod repaint;
+pub mod event_filter;
+pub mod archive_view;
+pub mod puzzle_view;
+pub mod ladder_view;
+pub mod opening_view;
+pub mod editor_view;
+pub mod teaching_view;
+pub mod presence_view;
+pub mod clock_view;
+pub mod heat_map;
+pub mod messages;
+pub mod onboarding;
+pub mod ui_config;
+pub mod runtime_config;
+pub mod turn_alerts;
+pub mod move_confirm;
+pub mod win_rate;
+pub mod thermal;
 
 // Headless function for testing
 #[cfg(feature = "headless")]
@@ -29,7 +49,7 @@ pub fn headless() -> anyhow::Result<()> {
     let mut app = app::App::new_headless_with_channels(ui_tx.clone(), ui_rx);
     
     // Create a game
-    let _ = ui_tx.send(UiToNet::CreateGame { board_size: 9 });
+    let _ = ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() });
     std::thread::sleep(std::time::Duration::from_millis(200));
     
     // Process initial messages
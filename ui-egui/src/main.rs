@@ -4,7 +4,8 @@
 
 use clap::Parser;
 use crossbeam_channel::unbounded;
-use flexi_logger::{Logger, FileSpec, Naming, Cleanup, Criterion};
+use p2pgo_network::logging::{LogFilter, LogOptions};
+use tracing::level_filters::LevelFilter;
 use std::path::PathBuf;
 use anyhow::Result;
 
@@ -12,7 +13,28 @@ mod app;
 mod view;
 mod msg;
 mod board_widget;
+#[allow(dead_code)] // The embedding API is more than the app uses
+mod go_board;
 mod worker;
+mod repaint;
+mod event_filter;
+mod archive_view;
+mod puzzle_view;
+mod ladder_view;
+mod opening_view;
+mod editor_view;
+mod teaching_view;
+mod presence_view;
+mod clock_view;
+mod heat_map;
+mod messages;
+mod onboarding;
+mod ui_config;
+mod runtime_config;
+mod turn_alerts;
+mod move_confirm;
+mod win_rate;
+mod thermal;
 
 use app::App;
 use msg::{UiToNet, NetToUi};
@@ -32,56 +54,51 @@ struct Args {
     
     #[arg(long, help = "Connect directly using a ticket string")]
     ticket: Option<String>,
-}
-
-/// Initialize the application logging system with rotation
-fn init_logging(debug: bool) -> Result<()> {
-    // Get log directory
-    let log_dir = match std::env::consts::OS {
-        "macos" => {
-            let mut path = PathBuf::from(std::env::var("HOME")?);
-            path.push("Library");
-            path.push("Logs");
-            path.push("p2pgo");
-            path
-        },
-        _ => {
-            let mut path = PathBuf::from(".");
-            path.push("logs");
-            path
-        }
-    };
     
-    // Ensure log directory exists
-    std::fs::create_dir_all(&log_dir)?;
-    
-    // Configure and start the logger
-    let level = if debug { "debug" } else { "info" };
-    
-    Logger::try_with_str(level)?
-        .log_to_file(
-            FileSpec::default()
-                .directory(&log_dir)
-                .basename("p2pgo")
-                .suffix("log")
-        )
-        .rotate(
-            Criterion::Size(1024 * 1024 * 1024), // 1GB per file
-            Naming::Timestamps,
-            Cleanup::KeepLogFiles(5), // Keep 5 files
-        )
-        .start()?;
+    #[arg(long, help = "Join the game in an invite link", conflicts_with = "ticket")]
+    invite: Option<String>,
+    
+    /// Invite link passed by the OS URL handler for p2pgo://
+    #[arg(hide = true, conflicts_with_all = ["ticket", "invite"])]
+    url: Option<String>,
     
+    #[arg(long, help = "Record the session for `p2pgo-cli replay`")]
+    record: Option<PathBuf>,
+    
+    #[arg(long, help = "Keep config, games and logs in this directory")]
+    data_dir: Option<PathBuf>,
+}
+
+/// Log to daily files in the platform's log directory, or to stderr
+fn init_logging(debug: bool, to_files: bool) -> Result<()> {
+    let log_dir = if to_files { Some(p2pgo_core::p2pgo_dirs::Dirs::current()?.logs) } else { None };
+    
+    let level = if debug { LevelFilter::DEBUG } else { LevelFilter::INFO };
+    p2pgo_network::logging::init(LogOptions {
+        filter: LogFilter::level(level),
+        dir: log_dir,
+    })?;
     Ok(())
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    if let Some(dir) = &args.data_dir {
+        p2pgo_core::p2pgo_dirs::set_root(dir);
+    }
     
-    // Initialize logging with rotation
-    if let Err(e) = init_logging(args.debug) {
+    // Initialize logging where the runtime config says; the saved filter is
+    // applied once the app starts
+    let config_path = runtime_config::RuntimeConfig::default_path();
+    let (runtime_config, config_errors) = runtime_config::RuntimeConfig::read(&config_path);
+    if let Err(e) = init_logging(args.debug, runtime_config.log_to_files) {
         eprintln!("Warning: Failed to initialize logging: {}", e);
     }
+    for error in config_errors {
+        tracing::warn!("Ignoring invalid runtime config {}: {}", config_path.display(), error);
+    }
+    // Files older builds kept in the working directory
+    p2pgo_core::p2pgo_dirs::migrate_current();
     
     // Initialize crash logger asynchronously
     let rt = tokio::runtime::Runtime::new()?;
@@ -110,6 +127,12 @@ fn main() -> anyhow::Result<()> {
         eprintln!("PANIC: {}", error);
     }));
     
+    if let Some(path) = &args.record {
+        if let Err(e) = p2pgo_network::session_log::start_recording(path) {
+            eprintln!("Warning: Failed to start recording: {:#}", e);
+        }
+    }
+    
     // Setup channels for communication between UI and worker
     let (ui_tx, net_rx) = unbounded::<UiToNet>();
     let (net_tx, ui_rx) = unbounded::<NetToUi>();
@@ -118,18 +141,20 @@ fn main() -> anyhow::Result<()> {
     let board_size = args.board_size;
     let player_name = args.player_name.clone();
     let ticket = args.ticket.clone();
+    let invite = match args.invite.as_ref().or(args.url.as_ref()) {
+        Some(link) => match p2pgo_network::invite::Invite::parse(link) {
+            Ok(invite) => Some(invite),
+            Err(e) => {
+                eprintln!("Ignoring invite link: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
     
     // Spawn background worker
     let worker_handle = worker::spawn_worker(net_rx, net_tx.clone(), board_size, player_name.clone())?;
     
-    // If ticket is provided, connect on startup
-    if let Some(ticket_str) = ticket {
-        // Short delay to allow worker to initialize
-        std::thread::sleep(std::time::Duration::from_millis(500));
-        let _ = net_tx.send(msg::NetToUi::Debug(format!("Connecting via ticket: {}", &ticket_str)));
-        let _ = ui_tx.send(msg::UiToNet::ConnectByTicket { ticket: ticket_str });
-    }
-    
     // Launch egui app
     let options = eframe::NativeOptions {
         initial_window_size: Some(egui::vec2(800.0, 600.0)),
@@ -139,8 +164,25 @@ fn main() -> anyhow::Result<()> {
     eframe::run_native(
         "P2P Go",
         options,
-        Box::new(move |_cc| {
-            Box::new(App::new(ui_tx, ui_rx, board_size, player_name))
+        Box::new(move |cc| {
+            // Wake the UI whenever the worker sends something instead of
+            // repainting every frame
+            let ctx = cc.egui_ctx.clone();
+            let ui_rx = repaint::spawn_waker(ui_rx, move || ctx.request_repaint());
+            let mut app = App::new(ui_tx, ui_rx, board_size, player_name);
+            app.set_worker_handle(worker_handle);
+            
+            // If ticket is provided, connect once the worker reports ready
+            if let Some(ticket_str) = ticket {
+                tracing::info!("Connecting via ticket once worker is ready: {}", &ticket_str);
+                app.queue_startup_action(msg::UiToNet::ConnectByTicket { ticket: ticket_str });
+            }
+            if let Some(invite) = invite {
+                tracing::info!("Joining invited game {} once worker is ready", invite.game_id);
+                app.queue_startup_action(msg::UiToNet::JoinInvite { invite });
+            }
+            
+            Box::new(app)
         }),
     )
     .map_err(|e| anyhow::anyhow!("Failed to run eframe: {}", e))
@@ -163,7 +205,7 @@ pub fn headless() -> anyhow::Result<()> {
     app.set_worker_handle(worker_handle);
     
     // Create a game
-    let _ = ui_tx.send(UiToNet::CreateGame { board_size: 9 });
+    let _ = ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() });
     std::thread::sleep(std::time::Duration::from_millis(100));
     
     // Simulate the game moves: B D4, W F4, B E5, W pass, B pass
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! User-facing text for structured errors, kept in one place for translation.
+
+use p2pgo_network::Error as NetworkError;
+use crate::msg::GameErrorKind;
+
+/// What to tell the player about `kind`, with points labelled for `board_size`
+pub fn game_error_message(kind: &GameErrorKind, board_size: u8) -> String {
+    match kind {
+        GameErrorKind::InvalidCoordinate { at } => {
+            format!("{} is not on the board", at.display_label(board_size))
+        }
+        GameErrorKind::OccupiedPosition { at } => {
+            format!("There is already a stone at {}", at.display_label(board_size))
+        }
+        GameErrorKind::KoViolation { at } => format!(
+            "Ko: {} can't be retaken right away, play elsewhere first",
+            at.display_label(board_size)
+        ),
+        GameErrorKind::SelfCapture { at } => {
+            format!("A stone at {} would have no liberties", at.display_label(board_size))
+        }
+        GameErrorKind::InvalidMove { reason } => format!("Illegal move: {}", reason),
+        GameErrorKind::SendFailed { reason } => format!("Failed to send move: {}", reason),
+        GameErrorKind::JoinFailed { reason } => format!("Failed to join game: {}", reason),
+        GameErrorKind::OutOfSync { reason } => format!("Out of sync with your opponent, reloading the game: {}", reason),
+    }
+}
+
+/// Friendlier text for connection errors the player can do something about
+pub fn connect_error_message(error: &NetworkError) -> Option<String> {
+    match error {
+        NetworkError::SelfConnection => Some(
+            "That is your own ticket. Send it to your opponent, or choose \"Practice vs myself\" to play both colors here."
+                .to_string(),
+        ),
+        NetworkError::TicketInvalid(_) => Some(
+            "That ticket can't be read. Ask your opponent to copy the whole ticket again.".to_string(),
+        ),
+        NetworkError::RelayUnavailable(_) => Some(
+            "No relay could be reached, so players behind strict networks can't connect yet. Check your internet connection and try again."
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Asking before a pass or resignation the player may not have meant.
+//!
+//! Resigning always asks, with the score estimate when there is one.
+//! Passing asks within the first [`EARLY_PASS_MOVES`] moves, or while the
+//! ownership estimate has the player ahead. Experienced players can turn
+//! both off; moves an engine plays never go through here.
+
+use p2pgo_core::Move;
+
+/// Moves played before which a pass is probably a mis-click
+pub const EARLY_PASS_MOVES: usize = 10;
+
+/// Why a pass is being checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassReason {
+    /// Only this many moves have been played
+    Early(usize),
+    /// The estimate has the player ahead by this much
+    Ahead(f32),
+}
+
+/// A move held back until the player confirms it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Confirmation {
+    /// `lead` is the player's estimated margin, negative when behind
+    Resign { lead: Option<f32> },
+    Pass(PassReason),
+}
+
+impl Confirmation {
+    /// The move confirming plays
+    pub fn mv(&self) -> Move {
+        match self {
+            Confirmation::Resign { .. } => Move::Resign,
+            Confirmation::Pass(_) => Move::Pass,
+        }
+    }
+
+    /// Question shown to the player, e.g. "You are ahead by ~4.5 — resign anyway?"
+    pub fn prompt(&self) -> String {
+        match self {
+            Confirmation::Resign { lead: Some(lead) } => format!("{} — resign anyway?", standing(*lead)),
+            Confirmation::Resign { lead: None } => "Resign this game?".to_string(),
+            Confirmation::Pass(PassReason::Early(0)) => "Pass as the first move?".to_string(),
+            Confirmation::Pass(PassReason::Early(moves)) => format!("Pass after only {} moves?", moves),
+            Confirmation::Pass(PassReason::Ahead(lead)) => format!("{} — pass anyway?", standing(*lead)),
+        }
+    }
+}
+
+/// "You are ahead by ~4.5", "You are behind by ~2.0" or "The game is even"
+fn standing(lead: f32) -> String {
+    if lead.abs() < 0.5 {
+        "The game is even".to_string()
+    } else if lead > 0.0 {
+        format!("You are ahead by ~{:.1}", lead)
+    } else {
+        format!("You are behind by ~{:.1}", -lead)
+    }
+}
+
+/// What to ask before the player's `mv`, `moves_played` into the game;
+/// `lead` is the player's estimated margin, if one is known
+pub fn confirmation(mv: &Move, moves_played: usize, lead: Option<f32>, enabled: bool) -> Option<Confirmation> {
+    if !enabled {
+        return None;
+    }
+    match mv {
+        Move::Resign => Some(Confirmation::Resign { lead }),
+        Move::Pass if moves_played < EARLY_PASS_MOVES => Some(Confirmation::Pass(PassReason::Early(moves_played))),
+        Move::Pass => lead.filter(|lead| *lead > 0.0).map(|lead| Confirmation::Pass(PassReason::Ahead(lead))),
+        Move::Place(_) => None,
+    }
+}
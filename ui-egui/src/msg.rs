@@ -2,34 +2,101 @@
 
 //! Message types for UI-Network communication.
 
-use p2pgo_core::{Move, GameEvent, Coord, Tag};
-use p2pgo_network::lobby::GameInfo;
+use std::path::PathBuf;
+use p2pgo_core::{Move, GameEvent, GameError, Coord, Tag};
+use p2pgo_core::rules::RuleConfig;
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::teaching::TeachingAnnotation;
+use p2pgo_core::governor::{GovernorConfig, PauseReason};
+use serde::{Deserialize, Serialize};
+use p2pgo_network::game_list::{GamesPage, GamesQuery};
+use p2pgo_network::Error as NetworkError;
+use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+use p2pgo_network::credits::CreditEntry;
+use p2pgo_network::rating::{Rating, RatingEntry};
+use p2pgo_network::matchmaking::MatchPrefs;
+use p2pgo_network::channel_metrics::MetricsSnapshot;
+use p2pgo_network::channel_tuning::ChannelTuning;
+use p2pgo_network::traffic::TrafficSample;
+use p2pgo_network::invite::Invite;
+use p2pgo_network::sanitize::RejectionCounts;
+use p2pgo_network::logging::{CaptureReport, LogFilter};
+use p2pgo_network::alerts::AlertRule;
+use p2pgo_network::admission::{ConnectionLimits, ConnectionUsage};
+use p2pgo_network::relay_mode::RelayPreset;
+use trainer::backend::{TrainingBackend, TrainingReport};
+use trainer::stats::DatasetStats;
+use crate::ui_config::PassSuggestions;
+use crate::runtime_config::{ApplyReport, RuntimeConfig};
 
 /// Messages sent from UI to Network worker
 #[derive(Debug, Clone)]
 pub enum UiToNet {
-    /// Create a new game
-    CreateGame { board_size: u8 },
+    /// Create a new game, its moves checked under `rule_config`
+    CreateGame { board_size: u8, rule_config: RuleConfig },
+    /// Create a new game starting from a set-up position, played with `komi`
+    CreateGameFromPosition { position: p2pgo_core::GameState, komi: f32 },
+    /// Share the practice game in `state` for spectators to watch
+    ShareBoard { state: p2pgo_core::GameState },
+    /// A move played on the shared practice board, by either color
+    ShareMove { mv: Move },
+    /// Stop sharing the practice board, archiving the game if asked
+    StopSharing { archive: bool },
     /// Join an existing game by ID
     JoinGame { game_id: String },
+    /// Search for an opponent through the matchmaking queue
+    JoinQueue { prefs: MatchPrefs },
+    /// Stop searching for an opponent
+    LeaveQueue,
     /// Make a move in the current game
     MakeMove { mv: Move, board_size: Option<u8> },
+    /// Queue a move to play as soon as the opponent has moved
+    Premove { mv: Move, board_size: Option<u8> },
+    /// Drop the queued premove
+    ClearPremove { board_size: Option<u8> },
     /// Request refresh of available games
     RefreshGames,
+    /// Games matching `query` from `offset` on, a page at a time; later
+    /// refreshes and pushed updates keep to the query
+    RequestGamesPage { query: GamesQuery, offset: usize },
+    /// Request the full state of a game, e.g. after missing moves
+    GetGameState { game_id: String },
     /// Leave the current game
     LeaveGame,
+    /// Restore the game offered by `ResumableGame` and reconnect its peer
+    ResumeGame { game_id: String },
+    /// Drop the game offered by `ResumableGame` for good
+    DiscardResumable { game_id: String },
+    /// Play one of our games at a move a day, or live again
+    SetCorrespondence { game_id: String, enabled: bool },
+    /// Teach one of our games, annotating it for the student, or stop
+    SetTeaching { game_id: String, enabled: bool },
+    /// Tell the opponent of the current game whether we are looking at the board
+    SendPresence { presence: p2pgo_network::presence::Presence },
+    /// Annotate the current position of a game we teach
+    Annotate { annotation: TeachingAnnotation, board_size: Option<u8> },
+    /// Ask the opponent of the current game to take back our last `moves` moves
+    RequestUndo { moves: u8 },
+    /// Accept or decline the opponent's undo request in the current game
+    AnswerUndo { accept: bool },
     /// Shutdown the network worker
     Shutdown,
     /// Debug: Move placed at coordinate (for testing)
     DebugMovePlaced(Coord),
     /// Connect to peer by ticket
     ConnectByTicket { ticket: String },
+    /// Connect by the invite's ticket and join the game it names
+    JoinInvite { invite: Invite },
+    /// Make an invite link for one of our games
+    CreateInvite { game_id: String },
     /// Request node ID
     GetNodeId,
     /// Request connection ticket
     GetTicket,
     /// Run NAT report
     RunNetReport,
+    /// Start or stop sending traffic samples, while the graphs are shown
+    WatchTraffic { enabled: bool },
     /// Update default board size for gossip subscription
     UpdateBoardSize { board_size: u8 },
     /// Set tag for a move
@@ -37,6 +104,22 @@ pub enum UiToNet {
     SetTag { gid: String, seq: u32, tag: Tag },
     /// Request AI ghost moves for current board state
     GetGhostMoves,
+    /// Request AI suggestions for a position from the board editor
+    AnalyzePosition { position: p2pgo_core::GameState },
+    /// Post-game report for `state`, e.g. a finished game or one from the archive
+    RequestGameReport { game_id: String, state: p2pgo_core::GameState },
+    /// The ladder opponent's move on `rung` in `state`, for the game seeded with `seed`
+    LadderMove { rung: usize, seed: u64, state: p2pgo_core::GameState },
+    /// Import the SGF files in `dir` into an archive, reporting progress
+    ImportSgf { dir: std::path::PathBuf, options: p2pgo_core::archiver::ImportOptions },
+    /// Heat map for the current position of a game, keyed by `position_hash`
+    RequestHeatMap { game_id: String, position_hash: u64 },
+    /// Ownership estimate for the current position of a game, keyed by `position_hash`
+    RequestOwnership { game_id: String, position_hash: u64 },
+    /// Win rate after each move of `state`, then of each move `game_id` reaches
+    FollowWinRate { game_id: String, state: p2pgo_core::GameState },
+    /// Stop evaluating the game followed by `FollowWinRate`
+    StopWinRate,
     /// Calculate score at end of game
     CalculateScore { 
         dead_stones: std::collections::HashSet<p2pgo_core::Coord> 
@@ -45,6 +128,63 @@ pub enum UiToNet {
     AcceptScore { 
         score_proof: p2pgo_core::value_labeller::ScoreProof 
     },
+    /// Host's answer to a `JoinRequested`
+    RespondJoin {
+        game_id: String,
+        request_id: u64,
+        accept: bool,
+        reason: Option<String>,
+    },
+    /// How join requests for games we host are answered
+    SetJoinPolicy { policy: JoinPolicy },
+    /// End an idle game by forfeit after a `GameIdle` prompt
+    AbandonGame { game_id: String },
+    /// Keep an idle game open and restart its idle timer
+    KeepWaiting { game_id: String },
+    /// Passphrase for the encrypted key file, after `IdentityLocked`
+    UnlockIdentity { passphrase: String },
+    /// Encrypt the key file with a passphrase, or store it plain for `None`
+    SetIdentityPassphrase { passphrase: Option<String> },
+    /// Export the identity for another machine
+    ExportIdentity { passphrase: Option<String> },
+    /// Replace the identity with an exported one from next launch
+    ImportIdentity { data: String, passphrase: Option<String> },
+    /// Change the name shown to opponents
+    SetPlayerName { name: String },
+    /// Whether the player agreed to share finished games as training data
+    SetSharing { relay: bool, training: bool },
+    /// Keep one game out of training even while training data is shared
+    ExcludeFromTraining { game_id: String, excluded: bool },
+    /// Delete our training copy of a game and ask peers to drop theirs
+    DeleteTrainingCopies { game_id: String },
+    /// Whether and when to suggest passing
+    SetPassSuggestions { settings: PassSuggestions },
+    /// Send our moves with coarse timestamps and the first one late, or not
+    SetTimingPrivacy { enabled: bool },
+    /// Filter logs with `filter` from now on
+    SetLogFilter { filter: LogFilter },
+    /// Write the next `CAPTURE_DURATION` of logs at trace level to a separate file
+    CaptureTrace,
+    /// Check connection health against `rules` from now on
+    SetAlertRules { rules: Vec<AlertRule> },
+    /// Train briefly on `backend`, or the CPU if it doesn't start, to see
+    /// how fast training runs here
+    MeasureTraining { backend: TrainingBackend },
+    /// Play a few games in the background and train the AI model on them,
+    /// on `backend` or the CPU, as the governor allows
+    TrainOnSelfPlay { backend: TrainingBackend },
+    /// Load the training games in `dir` and work out what they hold
+    InspectDataset { dir: std::path::PathBuf },
+    /// Run background training as `config` allows from now on
+    SetGovernor { config: GovernorConfig },
+    /// Chase unacknowledged moves as `tuning` says in every game, or as
+    /// suits each game's clock when `None`
+    SetChannelTuning { tuning: Option<ChannelTuning> },
+    /// Apply the advanced config from the editor, as far as can be done
+    /// without a restart
+    ApplyConfig { config: RuntimeConfig },
+    /// Accept connections for other players as `preset` allows, from now on
+    SetRelayPreset { preset: RelayPreset },
 }
 
 /// Messages sent from Network worker to UI
@@ -52,15 +192,44 @@ pub enum UiToNet {
 pub enum NetToUi {
     /// Debug message for development
     Debug(String),
-    /// Game list updated
-    GamesUpdated { games: Vec<GameInfo> },
+    /// Worker finished startup and its endpoint is listening
+    WorkerReady {
+        node_id: String,
+        ticket: Option<String>,
+    },
+    /// A page of the game list, matching the last query
+    GamesUpdated { page: GamesPage },
     /// Game event occurred
     GameEvent { event: GameEvent },
-    /// Successfully joined/created a game
-    GameJoined { game_id: String },
+    /// Successfully joined/created a game, played with these settings
+    GameJoined { game_id: String, settings: GameSettings },
+    /// Matchmaking paired us; `GameJoined` follows for the game itself
+    MatchFound { game_id: String, opponent: String },
+    /// We are no longer in the matchmaking queue without a match
+    QueueLeft,
+    /// Full state of a game, sent in reply to `GetGameState`
+    GameStateSnapshot {
+        game_id: String,
+        state: p2pgo_core::GameState,
+    },
     /// Left the current game
     GameLeft,
-    /// Network error occurred
+    /// An unfinished game was saved when the app last closed
+    ResumableGame {
+        game_id: String,
+        move_count: usize,
+        opponent: Option<String>,
+    },
+    /// A saved game is active again; `GameJoined` came first.
+    /// `recovered_from_divergence` when its snapshot and move journal
+    /// disagreed and the longer one was kept
+    GameRestored { game_id: String, color: p2pgo_core::Color, move_count: usize, recovered_from_divergence: bool },
+    /// Opponent moves in a correspondence game that arrived while we were
+    /// away, counted until we move
+    UnreadMoves { game_id: String, count: usize },
+    /// Something went wrong in a game, for context-specific feedback
+    GameError { game_id: String, error: GameErrorKind },
+    /// Failure without more structure than its message
     Error { message: String },
     /// Connection status changed
     #[allow(dead_code)]
@@ -71,13 +240,37 @@ pub enum NetToUi {
     NodeId { node_id: String },
     /// Connection ticket response
     Ticket { ticket: String },
+    /// Invite link asked for with `CreateInvite`
+    Invite { link: String },
+    /// The practice board is shared as `game_id`; spectators join by `link`
+    BoardShared { game_id: String, link: String },
     /// NAT report result
     NetReport { report: String },
     /// Tag acknowledgment
     TagAck,
-    /// Ghost moves for AI suggestions
+    /// Ghost moves for AI suggestions, best first; may include `Move::Pass`
     #[allow(dead_code)]
-    GhostMoves(Vec<Coord>),
+    GhostMoves(Vec<Move>),
+    /// Sync counters for a game's channel, sent every few seconds
+    ChannelMetrics { game_id: String, metrics: MetricsSnapshot },
+    /// Network traffic during the last second, sent while watched
+    TrafficSample { sample: TrafficSample },
+    /// Move probabilities per point for the position with `position_hash`
+    HeatMap { position_hash: u64, map: Vec<f32> },
+    /// Pass probability and resignation advice for the position with `position_hash`
+    Outlook { position_hash: u64, outlook: crate::heat_map::Outlook },
+    /// Ownership per point for the position with `position_hash`, 1 Black to -1 White
+    Ownership { position_hash: u64, map: Vec<f32> },
+    /// Black's win rate, 0 to 1, after `move_number` moves of a game followed by `FollowWinRate`
+    EvalPoint { game_id: String, move_number: usize, value: f32 },
+    /// Answer to `RequestGameReport`
+    GameReport { game_id: String, report: p2pgo_core::analysis::GameReport },
+    /// Answer to `LadderMove`
+    LadderMove { mv: p2pgo_core::Move },
+    /// A file of an `ImportSgf` was looked at
+    ImportProgress { progress: p2pgo_core::archiver::ImportProgress },
+    /// An `ImportSgf` is over, or could not start
+    ImportFinished { result: Result<p2pgo_core::archiver::ImportReport, String> },
     /// Score calculation result
     ScoreCalculated {
         score_proof: p2pgo_core::value_labeller::ScoreProof,
@@ -96,6 +289,164 @@ pub enum NetToUi {
         host_id: String,
         board_size: u8,
     },
+    /// A player asks to join a game we host
+    JoinRequested {
+        game_id: String,
+        request_id: u64,
+        profile: PlayerProfile,
+    },
+    /// The host turned down our join request
+    JoinDeclined { game_id: String, reason: String },
+    /// The game we asked to join is full; we may watch instead
+    SpectateOffered { game_id: String, reason: String },
+    /// No move in `game_id` gains the player to move anything over passing;
+    /// sent once per game. `score_estimate` is Black's expected margin after
+    /// passing
+    PassSuggested { game_id: String, score_estimate: f32 },
+    /// A game's training copies were deleted here and retracted on the
+    /// training topic; `local` says whether we had one
+    TrainingCopiesDeleted { game_id: String, local: bool },
+    /// Maintenance removed, set aside or archived files
+    ArchiveRepaired { report: p2pgo_core::archiver::MaintenanceReport },
+    /// A trace capture is writing to `path`
+    TraceCaptureStarted { path: PathBuf },
+    /// The trace capture couldn't start
+    TraceCaptureFailed { message: String },
+    /// The trace capture ended and its file is ready to attach
+    TraceCaptureFinished { report: CaptureReport },
+    /// A background subsystem stopped or wedged and was started again
+    SubsystemRestarted { name: String },
+    /// A background subsystem kept stopping and was given up on
+    SubsystemFailed { name: String },
+    /// An alert rule fired; shown until dismissed
+    Alert { message: String },
+    /// Messages from peers dropped as invalid since startup, sent with
+    /// traffic samples
+    Rejections { counts: RejectionCounts },
+    /// A training speed measurement finished
+    TrainingMeasured { report: TrainingReport },
+    /// A `TrainOnSelfPlay` run finished and its weights were published
+    SelfPlayTrained { report: TrainingReport },
+    /// An `InspectDataset` is over, or the games could not be loaded
+    DatasetInspected { result: Result<DatasetStats, String> },
+    /// An `ApplyConfig` was applied, or refused with why
+    ConfigApplied { result: Result<ApplyReport, String> },
+    /// Background training was put on hold for `reason`, or resumed
+    TrainingPaused { reason: Option<PauseReason> },
+    /// The relay preset in effect changed; `drained` connections over its
+    /// limits are being closed
+    RelayModeChanged { preset: RelayPreset, limits: ConnectionLimits, drained: u32 },
+    /// Incoming connections and their traffic, sent with traffic samples
+    RelayUsage { usage: ConnectionUsage },
+    /// Persisted training credits changed
+    CreditsUpdated {
+        balance: u64,
+        history: Vec<CreditEntry>,
+    },
+    /// Our rating changed
+    RatingUpdated {
+        current: Rating,
+        history: Vec<RatingEntry>,
+    },
+    /// The queued premove became illegal after the opponent's move
+    PremoveDiscarded { mv: Move, reason: String },
+    /// No move or ping has arrived for a while; ask whether to abandon
+    GameIdle {
+        game_id: String,
+        idle_for: std::time::Duration,
+    },
+    /// The key file is encrypted; the worker waits for `UnlockIdentity`
+    IdentityLocked { error: Option<String> },
+    /// Whether the node ID survives restarts and needs a passphrase
+    IdentityStatus { persistent: bool, encrypted: bool },
+    /// Result of `ExportIdentity`
+    IdentityExported { data: String },
+    /// An imported identity was stored and is used from next launch
+    IdentityImported { node_id: String },
+}
+
+/// What went wrong in a game. Rule violations mirror
+/// [`p2pgo_core::GameError`] with the point that was tried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameErrorKind {
+    InvalidCoordinate { at: Coord },
+    OccupiedPosition { at: Coord },
+    KoViolation { at: Coord },
+    SelfCapture { at: Coord },
+    /// Any other rule violation, e.g. moving after the game ended
+    InvalidMove { reason: String },
+    /// The move could not be sent to the opponent
+    SendFailed { reason: String },
+    /// Joining the game failed
+    JoinFailed { reason: String },
+    /// Our copy of the game disagrees with the opponent's; it is being fetched again
+    OutOfSync { reason: String },
+}
+
+/// What to do about a failed network call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Recovery {
+    /// Try again shortly; the peer or the relay may be back by then
+    Retry,
+    /// Fetch the game again; ours has drifted from the opponent's
+    Resync,
+    /// Nothing to do but tell the player
+    Report,
+}
+
+impl Recovery {
+    /// How to recover from `error`
+    pub fn for_error(error: &NetworkError) -> Self {
+        match error {
+            NetworkError::ConnectionFailed { .. }
+            | NetworkError::RelayUnavailable(_)
+            | NetworkError::Timeout { .. } => Self::Retry,
+            NetworkError::SyncConflict { .. } => Self::Resync,
+            _ => Self::Report,
+        }
+    }
+}
+
+impl GameErrorKind {
+    /// The kind for `error`, raised by playing `mv`
+    pub fn from_rules(error: &GameError, mv: &Move) -> Self {
+        match (error, mv) {
+            (GameError::InvalidCoordinate, Move::Place(at)) => Self::InvalidCoordinate { at: *at },
+            (GameError::OccupiedPosition, Move::Place(at)) => Self::OccupiedPosition { at: *at },
+            (GameError::KoViolation, Move::Place(at)) => Self::KoViolation { at: *at },
+            (GameError::SelfCapture, Move::Place(at)) => Self::SelfCapture { at: *at },
+            (GameError::InvalidMove(reason), _) => Self::InvalidMove { reason: reason.clone() },
+            (error, _) => Self::InvalidMove { reason: error.to_string() },
+        }
+    }
+
+    /// The kind for `error`, raised by sending `mv`
+    pub fn from_network(error: &NetworkError, mv: &Move) -> Self {
+        match error {
+            NetworkError::Rules(rule) => Self::from_rules(rule, mv),
+            error if Recovery::for_error(error) == Recovery::Resync => Self::OutOfSync { reason: error.to_string() },
+            error => Self::SendFailed { reason: error.to_string() },
+        }
+    }
+
+    /// What the UI does about this error beyond showing it
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Self::OutOfSync { .. } => Recovery::Resync,
+            _ => Recovery::Report,
+        }
+    }
+
+    /// The intersection an illegal stone was played at
+    pub fn illegal_at(&self) -> Option<Coord> {
+        match self {
+            Self::InvalidCoordinate { at }
+            | Self::OccupiedPosition { at }
+            | Self::KoViolation { at }
+            | Self::SelfCapture { at } => Some(*at),
+            _ => None,
+        }
+    }
 }
 
 /// Extension trait for NetToUi messages
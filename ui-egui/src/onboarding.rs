@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! First-run wizard: display name, how tickets work, sharing consent and a
+//! connectivity check.
+
+use crate::ui_config::UiConfig;
+
+/// Longest display name accepted
+pub const MAX_NAME_LEN: usize = 32;
+
+/// Wizard pages, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnboardingPage {
+    Name,
+    Tickets,
+    Sharing,
+    Connectivity,
+}
+
+/// State of the onboarding wizard
+#[derive(Debug, Clone)]
+pub struct Onboarding {
+    page: OnboardingPage,
+    pub name: String,
+    pub relay_participation: bool,
+    pub share_training_data: bool,
+}
+
+impl Onboarding {
+    /// Start at the first page, prefilled from `config` or `default_name`
+    pub fn new(default_name: &str, config: &UiConfig) -> Self {
+        Self {
+            page: OnboardingPage::Name,
+            name: config.display_name.clone().unwrap_or_else(|| default_name.to_string()),
+            relay_participation: config.relay_participation,
+            share_training_data: config.share_training_data,
+        }
+    }
+
+    pub fn page(&self) -> OnboardingPage {
+        self.page
+    }
+
+    /// The name as it will be saved
+    pub fn display_name(&self) -> &str {
+        self.name.trim()
+    }
+
+    /// Whether the current page is filled in well enough to move on
+    pub fn can_advance(&self) -> bool {
+        match self.page {
+            OnboardingPage::Name => {
+                !self.display_name().is_empty() && self.display_name().chars().count() <= MAX_NAME_LEN
+            }
+            _ => true,
+        }
+    }
+
+    pub fn is_last_page(&self) -> bool {
+        self.page == OnboardingPage::Connectivity
+    }
+
+    /// Move to the next page; false if the current one is incomplete or last
+    pub fn advance(&mut self) -> bool {
+        if !self.can_advance() {
+            return false;
+        }
+        self.page = match self.page {
+            OnboardingPage::Name => OnboardingPage::Tickets,
+            OnboardingPage::Tickets => OnboardingPage::Sharing,
+            OnboardingPage::Sharing => OnboardingPage::Connectivity,
+            OnboardingPage::Connectivity => return false,
+        };
+        true
+    }
+
+    pub fn back(&mut self) {
+        self.page = match self.page {
+            OnboardingPage::Name | OnboardingPage::Tickets => OnboardingPage::Name,
+            OnboardingPage::Sharing => OnboardingPage::Tickets,
+            OnboardingPage::Connectivity => OnboardingPage::Sharing,
+        };
+    }
+
+    /// Record the choices in `config` and mark the wizard done
+    pub fn apply(&self, config: &mut UiConfig) {
+        if !self.display_name().is_empty() {
+            config.display_name = Some(self.display_name().to_string());
+        }
+        config.relay_participation = self.relay_participation;
+        config.share_training_data = self.share_training_data;
+        config.onboarded = true;
+    }
+}
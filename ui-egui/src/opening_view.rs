@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Opening tree of archived games: which moves were played from each
+//! position and how those games ended.
+
+use std::collections::HashSet;
+use std::path::Path;
+use anyhow::Result;
+use p2pgo_core::archiver;
+use p2pgo_core::{Color, GameState, Move};
+
+/// Moves of each game that go into the tree
+pub const OPENING_DEPTH: usize = 20;
+
+/// Games through a node of the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NodeStats {
+    pub games: usize,
+    pub black_wins: usize,
+    pub white_wins: usize,
+}
+
+impl NodeStats {
+    /// Share of decided games Black won, if any were decided
+    pub fn black_win_rate(&self) -> Option<f32> {
+        let decided = self.black_wins + self.white_wins;
+        (decided > 0).then(|| self.black_wins as f32 / decided as f32)
+    }
+
+    fn add(&mut self, other: NodeStats) {
+        self.games += other.games;
+        self.black_wins += other.black_wins;
+        self.white_wins += other.white_wins;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    /// Move leading here; `None` for the empty board
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Games whose opening stops at this node
+    ended: NodeStats,
+}
+
+/// Moves from the empty board, merged across games of one board size.
+///
+/// Node 0 is the empty board. Per-node stats are summed over the subtree
+/// the first time they're asked for.
+#[derive(Debug, Clone)]
+pub struct OpeningTree {
+    board_size: u8,
+    nodes: Vec<Node>,
+    stats: Vec<Option<NodeStats>>,
+}
+
+impl OpeningTree {
+    pub fn new(board_size: u8) -> Self {
+        let root = Node { mv: None, parent: None, children: Vec::new(), ended: NodeStats::default() };
+        Self { board_size, nodes: vec![root], stats: vec![None] }
+    }
+
+    /// Tree of every archived game in `dir` played on `board_size`
+    pub fn from_archive(dir: &Path, board_size: u8) -> Result<Self> {
+        let mut tree = Self::new(board_size);
+        for game in archiver::list_games_in(dir)? {
+            if game.board_size != board_size {
+                continue;
+            }
+            let winner = match game.result.as_deref().and_then(|r| r.chars().next()) {
+                Some('B') => Some(Color::Black),
+                Some('W') => Some(Color::White),
+                _ => None,
+            };
+            match archiver::load_game(dir, &game.id) {
+                Ok(state) => tree.add_game(&state, winner),
+                Err(e) => tracing::debug!("Skipping {} for openings: {}", game.id, e),
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Add the first [`OPENING_DEPTH`] moves of `game`; games from a set-up
+    /// position aren't openings and are skipped
+    pub fn add_game(&mut self, game: &GameState, winner: Option<Color>) {
+        if game.board_size != self.board_size || !game.setup.is_empty() {
+            return;
+        }
+        let mut node = 0;
+        for mv in game.moves.iter().take(OPENING_DEPTH) {
+            if *mv == Move::Resign {
+                break;
+            }
+            node = match self.nodes[node].children.iter().find(|&&c| self.nodes[c].mv.as_ref() == Some(mv)) {
+                Some(&child) => child,
+                None => {
+                    self.nodes.push(Node { mv: Some(mv.clone()), parent: Some(node), children: Vec::new(), ended: NodeStats::default() });
+                    self.stats.push(None);
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push(child);
+                    child
+                }
+            };
+        }
+        let ended = &mut self.nodes[node].ended;
+        ended.games += 1;
+        match winner {
+            Some(Color::Black) => ended.black_wins += 1,
+            Some(Color::White) => ended.white_wins += 1,
+            None => {}
+        }
+        // Only the counts along this line changed
+        let mut at = Some(node);
+        while let Some(node) = at {
+            self.stats[node] = None;
+            at = self.nodes[node].parent;
+        }
+    }
+
+    pub fn board_size(&self) -> u8 {
+        self.board_size
+    }
+
+    /// Nodes including the empty board
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// The move leading to `node`; `None` for the empty board
+    pub fn move_at(&self, node: usize) -> Option<&Move> {
+        self.nodes[node].mv.as_ref()
+    }
+
+    pub fn children(&self, node: usize) -> &[usize] {
+        &self.nodes[node].children
+    }
+
+    /// Games through `node`, computed on first use
+    pub fn stats(&mut self, node: usize) -> NodeStats {
+        if let Some(stats) = self.stats[node] {
+            return stats;
+        }
+        let mut stats = self.nodes[node].ended;
+        for child in self.nodes[node].children.clone() {
+            stats.add(self.stats(child));
+        }
+        self.stats[node] = Some(stats);
+        stats
+    }
+
+    /// Moves from the empty board to `node`
+    pub fn line(&self, node: usize) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut at = node;
+        while let Some(parent) = self.nodes[at].parent {
+            line.extend(self.nodes[at].mv.clone());
+            at = parent;
+        }
+        line.reverse();
+        line
+    }
+
+    /// The position at `node`
+    pub fn position(&self, node: usize) -> GameState {
+        let mut state = GameState::new(self.board_size);
+        for mv in self.line(node) {
+            // Every line was played in an archived game, so it replays
+            if let Err(e) = state.apply_move(mv) {
+                tracing::warn!("Opening line no longer replays: {}", e);
+                break;
+            }
+        }
+        state
+    }
+}
+
+/// A visible line of the tree widget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Row {
+    pub node: usize,
+    /// Moves from the empty board, less one
+    pub depth: usize,
+}
+
+/// State of the openings view
+#[derive(Debug, Clone)]
+pub struct OpeningExplorer {
+    tree: OpeningTree,
+    expanded: HashSet<usize>,
+    selected: usize,
+}
+
+impl OpeningExplorer {
+    pub fn new(tree: OpeningTree) -> Self {
+        Self { tree, expanded: HashSet::new(), selected: 0 }
+    }
+
+    /// Explore the archived games in `dir` played on `board_size`
+    pub fn from_archive(dir: &Path, board_size: u8) -> Result<Self> {
+        Ok(Self::new(OpeningTree::from_archive(dir, board_size)?))
+    }
+
+    pub fn tree(&self) -> &OpeningTree {
+        &self.tree
+    }
+
+    pub fn stats(&mut self, node: usize) -> NodeStats {
+        self.tree.stats(node)
+    }
+
+    pub fn is_expanded(&self, node: usize) -> bool {
+        self.expanded.contains(&node)
+    }
+
+    /// Show or hide the replies to `node`
+    pub fn toggle(&mut self, node: usize) {
+        if !self.expanded.remove(&node) {
+            self.expanded.insert(node);
+        }
+    }
+
+    /// Lines to draw: the first moves, and the replies of every expanded
+    /// node below its parent, most played first
+    pub fn rows(&mut self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut stack: Vec<Row> = self.sorted_children(0).into_iter().rev()
+            .map(|node| Row { node, depth: 0 })
+            .collect();
+        while let Some(row) = stack.pop() {
+            rows.push(row);
+            if self.expanded.contains(&row.node) {
+                stack.extend(self.sorted_children(row.node).into_iter().rev()
+                    .map(|node| Row { node, depth: row.depth + 1 }));
+            }
+        }
+        rows
+    }
+
+    pub fn select(&mut self, node: usize) {
+        if node < self.tree.len() {
+            self.selected = node;
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The board for the selected node, also where practice starts from
+    pub fn position(&self) -> GameState {
+        self.tree.position(self.selected)
+    }
+
+    fn sorted_children(&mut self, node: usize) -> Vec<usize> {
+        let mut children = self.tree.children(node).to_vec();
+        children.sort_by_key(|&child| std::cmp::Reverse(self.tree.stats(child).games));
+        children
+    }
+}
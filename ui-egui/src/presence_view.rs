@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Whether the opponent is at the board: a pulsing dot while they
+//! consider, "last seen" once they have gone quiet.
+
+use std::time::{Duration, Instant};
+use eframe::egui;
+use p2pgo_network::presence::PRESENCE_STALE_AFTER;
+
+/// The opponent's presence as last heard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpponentPresence {
+    pub considering: bool,
+    /// Last time they did anything, unix seconds on our clock
+    pub last_active: u64,
+    /// When the presence arrived
+    pub received: Instant,
+}
+
+/// What the player is shown about the opponent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresenceStatus {
+    /// Their pointer is over the board
+    Considering,
+    /// Around, but not looking at the board
+    Present,
+    /// Nothing heard for [`PRESENCE_STALE_AFTER`]; they did something this long ago
+    LastSeen(Duration),
+}
+
+impl OpponentPresence {
+    /// Status at `now`, with `now_unix` the same moment in unix seconds
+    pub fn status(&self, now: Instant, now_unix: u64) -> PresenceStatus {
+        if now.saturating_duration_since(self.received) >= PRESENCE_STALE_AFTER {
+            PresenceStatus::LastSeen(Duration::from_secs(now_unix.saturating_sub(self.last_active)))
+        } else if self.considering {
+            PresenceStatus::Considering
+        } else {
+            PresenceStatus::Present
+        }
+    }
+}
+
+/// "12s", "3m", "2h"
+fn ago(elapsed: Duration) -> String {
+    match elapsed.as_secs() {
+        s if s < 60 => format!("{}s", s),
+        s if s < 60 * 60 => format!("{}m", s / 60),
+        s => format!("{}h", s / (60 * 60)),
+    }
+}
+
+/// The opponent's status, subtly; nothing while they are simply around
+pub fn render_status(ui: &mut egui::Ui, status: PresenceStatus) {
+    match status {
+        PresenceStatus::Considering => {
+            let phase = (ui.input(|i| i.time) * 3.0).sin() as f32 * 0.5 + 0.5;
+            let alpha = (80.0 + 175.0 * phase) as u8;
+            ui.colored_label(egui::Color32::from_rgba_unmultiplied(60, 160, 90, alpha), "● Opponent is considering");
+            ui.ctx().request_repaint_after(Duration::from_millis(50));
+        }
+        PresenceStatus::Present => {
+            // Silence shows once the presence goes stale
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+        }
+        PresenceStatus::LastSeen(elapsed) => {
+            ui.weak(format!("Opponent last seen {} ago", ago(elapsed)));
+            ui.ctx().request_repaint_after(Duration::from_secs(1));
+        }
+    }
+}
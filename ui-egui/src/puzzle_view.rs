@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Practice puzzles taken from archived games.
+
+use std::path::Path;
+use anyhow::Result;
+use p2pgo_core::archiver;
+use p2pgo_core::puzzles::{self, Puzzle, PuzzleOutcome};
+use p2pgo_core::{Coord, GameState};
+use crate::ui_config::UiConfig;
+
+/// How the current attempt ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Feedback {
+    Solved,
+    Wrong(String),
+}
+
+/// State of the puzzle view
+#[derive(Debug, Clone)]
+pub struct PuzzleSession {
+    puzzles: Vec<Puzzle>,
+    index: usize,
+    /// The player's moves in the current attempt
+    line: Vec<Coord>,
+    feedback: Option<Feedback>,
+}
+
+impl PuzzleSession {
+    pub fn new(puzzles: Vec<Puzzle>) -> Self {
+        Self {
+            puzzles,
+            index: 0,
+            line: Vec::new(),
+            feedback: None,
+        }
+    }
+
+    /// Puzzles from every game archived in `dir`
+    pub fn from_archive(dir: &Path) -> Result<Self> {
+        let mut found = Vec::new();
+        for game in archiver::list_games_in(dir)? {
+            match archiver::load_game(dir, &game.id) {
+                Ok(state) => found.extend(puzzles::extract_from_game(&state)),
+                Err(e) => tracing::debug!("Skipping {} for puzzles: {}", game.id, e),
+            }
+        }
+        Ok(Self::new(found))
+    }
+
+    pub fn len(&self) -> usize {
+        self.puzzles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.puzzles.is_empty()
+    }
+
+    /// Position in the list, from zero
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn current(&self) -> Option<&Puzzle> {
+        self.puzzles.get(self.index)
+    }
+
+    pub fn feedback(&self) -> Option<&Feedback> {
+        self.feedback.as_ref()
+    }
+
+    /// The board to show
+    pub fn position(&self) -> Option<GameState> {
+        self.current().map(|p| p.position_after(&self.line))
+    }
+
+    /// Play a move in the current puzzle, updating the streak once it is
+    /// solved or failed
+    pub fn play(&mut self, coord: Coord, config: &mut UiConfig) -> Option<&Feedback> {
+        if self.feedback.is_some() {
+            return self.feedback.as_ref();
+        }
+        self.current()?;
+        self.line.push(coord);
+        match self.puzzles[self.index].check(&self.line) {
+            PuzzleOutcome::Solved => {
+                config.record_puzzle(true);
+                self.feedback = Some(Feedback::Solved);
+            }
+            PuzzleOutcome::Wrong { reason } => {
+                config.record_puzzle(false);
+                self.feedback = Some(Feedback::Wrong(reason));
+            }
+            PuzzleOutcome::Correct { .. } => {}
+        }
+        self.feedback.as_ref()
+    }
+
+    /// Start the current puzzle over
+    pub fn retry(&mut self) {
+        self.line.clear();
+        self.feedback = None;
+    }
+
+    /// Go to the next puzzle, wrapping around
+    pub fn next(&mut self) {
+        if !self.puzzles.is_empty() {
+            self.index = (self.index + 1) % self.puzzles.len();
+        }
+        self.retry();
+    }
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Repaint scheduling so the UI only redraws when something changed.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crossbeam_channel::{Receiver, unbounded};
+
+/// Interval for the clock heartbeat while a game is on screen
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Why the last frame was drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepaintCause {
+    /// User input (mouse, keyboard, window events)
+    Input,
+    /// A message arrived from the network worker
+    NetMessage,
+    /// A timed repaint: the clock heartbeat or an egui animation
+    Scheduled,
+}
+
+/// Spawn a waker thread that forwards messages from `rx` and calls
+/// `on_message` after each one, so an idle UI wakes up when the worker
+/// has something to say.
+///
+/// Returns the receiver the UI should read from instead of `rx`.
+pub fn spawn_waker<T, F>(rx: Receiver<T>, on_message: F) -> Receiver<T>
+where
+    T: Send + 'static,
+    F: Fn() + Send + 'static,
+{
+    let (tx, forwarded_rx) = unbounded();
+    std::thread::Builder::new()
+        .name("repaint-waker".to_string())
+        .spawn(move || {
+            while let Ok(msg) = rx.recv() {
+                if tx.send(msg).is_err() {
+                    break;
+                }
+                on_message();
+            }
+            tracing::debug!("Repaint waker exiting: channel closed");
+        })
+        .expect("Failed to spawn repaint waker thread");
+    forwarded_rx
+}
+
+/// Frame counter backing the debug overlay
+#[derive(Debug)]
+pub struct RepaintStats {
+    /// Timestamps of frames drawn within the last second
+    frames: VecDeque<Instant>,
+    /// Cause of the most recent frame
+    last_cause: Option<RepaintCause>,
+}
+
+impl Default for RepaintStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepaintStats {
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            last_cause: None,
+        }
+    }
+
+    /// Record a drawn frame and the reason it was drawn
+    pub fn record(&mut self, cause: RepaintCause) {
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while let Some(first) = self.frames.front() {
+            if now.duration_since(*first) > Duration::from_secs(1) {
+                self.frames.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.last_cause = Some(cause);
+    }
+
+    /// Frames drawn during the last second
+    pub fn fps(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Cause of the most recent frame
+    pub fn last_cause(&self) -> Option<RepaintCause> {
+        self.last_cause
+    }
+}
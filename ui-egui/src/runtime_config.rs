@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Settings edited as JSON in the advanced config editor.
+//!
+//! The worker applies a [`RuntimeConfig`] while the app runs where it can;
+//! the rest is read once at startup and reported as waiting for a restart.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use p2pgo_core::governor::GovernorConfig;
+use p2pgo_network::channel_tuning::ChannelTuning;
+use p2pgo_network::logging::LogFilter;
+use p2pgo_network::relay_mode::RelayPreset;
+
+/// File name of the runtime config, in the data directory
+pub const RUNTIME_CONFIG_FILE: &str = "runtime_config.json";
+
+/// Shortest ACK watchdog check interval the editor accepts
+pub const MIN_CHECK_INTERVAL_MS: u64 = 10;
+
+/// Advanced settings, all optional in the JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// ACK watchdog settings for every game; `null` suits each game's clock
+    pub channel_tuning: Option<ChannelTuning>,
+    /// How many connections we accept for other players
+    pub relay_preset: RelayPreset,
+    /// Comma-separated directives, e.g. `info,p2pgo_network=debug`
+    pub log_filter: LogFilter,
+    /// Whether logs go to daily files rather than stderr; read at startup
+    pub log_to_files: bool,
+    /// Finished games before ghost moves are shown
+    pub ghost_moves_after_games: u32,
+    /// How much background training may run
+    pub governor: GovernorConfig,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            channel_tuning: None,
+            relay_preset: RelayPreset::default(),
+            log_filter: LogFilter::default(),
+            log_to_files: true,
+            ghost_moves_after_games: 5,
+            governor: GovernorConfig::default(),
+        }
+    }
+}
+
+/// A problem with an edited config
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Field at fault, `None` when the JSON doesn't parse
+    pub field: Option<&'static str>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field {
+            Some(field) => write!(f, "{}: {}", field, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// What applying a config changed, from [`RuntimeConfig::update`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// Fields that took effect straight away
+    pub applied: Vec<&'static str>,
+    /// Fields that take effect on the next start
+    pub restart_needed: Vec<&'static str>,
+}
+
+impl RuntimeConfig {
+    /// Where the config lives
+    ///
+    /// The data directory, see [`p2pgo_core::p2pgo_dirs`], or the working
+    /// directory if there is none.
+    pub fn default_path() -> PathBuf {
+        match p2pgo_core::p2pgo_dirs::Dirs::current() {
+            Ok(dirs) => dirs.data.join(RUNTIME_CONFIG_FILE),
+            Err(_) => PathBuf::from(".").join(RUNTIME_CONFIG_FILE),
+        }
+    }
+
+    /// Load the config, falling back to defaults if it is missing or invalid
+    pub fn load(path: &Path) -> Self {
+        let (config, errors) = Self::read(path);
+        for error in errors {
+            tracing::warn!("Ignoring invalid runtime config {}: {}", path.display(), error);
+        }
+        config
+    }
+
+    /// Load the config like [`Self::load`], leaving what was wrong with it
+    /// for the caller to report, e.g. once logging is set up
+    pub fn read(path: &Path) -> (Self, Vec<ConfigError>) {
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return (Self::default(), Vec::new());
+        };
+        match Self::parse(&text) {
+            Ok(config) => (config, Vec::new()),
+            Err(errors) => (Self::default(), errors),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, self.to_json())?;
+        Ok(())
+    }
+
+    /// The config as the editor shows it
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Read and check a config typed in the editor
+    pub fn parse(text: &str) -> Result<Self, Vec<ConfigError>> {
+        let config: Self = serde_json::from_str(text)
+            .map_err(|e| vec![ConfigError { field: None, message: e.to_string() }])?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Every value that parses but can't be used
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, field: &'static str, message: &str| {
+            if !ok {
+                errors.push(ConfigError { field: Some(field), message: message.to_string() });
+            }
+        };
+        if let Some(tuning) = &self.channel_tuning {
+            check(
+                tuning.check_interval_ms >= MIN_CHECK_INTERVAL_MS,
+                "channel_tuning.check_interval_ms",
+                &format!("must be at least {}", MIN_CHECK_INTERVAL_MS),
+            );
+        }
+        check(self.governor.parallelism > 0, "governor.parallelism", "must be at least 1");
+        check(self.governor.period_ms > 0, "governor.period_ms", "must be at least 1");
+        check(self.governor.rest_ms <= self.governor.period_ms, "governor.rest_ms", "can't be longer than period_ms");
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Take on `next` as far as the running app can, keeping the fields
+    /// only read at startup, and report what changed
+    pub fn update(&mut self, next: &Self) -> ApplyReport {
+        let mut report = ApplyReport::default();
+        let mut live = |changed: bool, field: &'static str| {
+            if changed {
+                report.applied.push(field);
+            }
+        };
+        live(self.channel_tuning != next.channel_tuning, "channel_tuning");
+        live(self.relay_preset != next.relay_preset, "relay_preset");
+        live(self.log_filter != next.log_filter, "log_filter");
+        live(self.ghost_moves_after_games != next.ghost_moves_after_games, "ghost_moves_after_games");
+        live(self.governor != next.governor, "governor");
+        if self.log_to_files != next.log_to_files {
+            report.restart_needed.push("log_to_files");
+        }
+        *self = Self { log_to_files: self.log_to_files, ..next.clone() };
+        report
+    }
+}
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Tools the teacher of a lesson annotates the board with.
+//!
+//! A click with a tool becomes annotations for the worker to send; what the
+//! board shows comes back as `GameEvent::Annotated`, like any peer's.
+
+use eframe::egui;
+use p2pgo_core::teaching::{Marker, TeachingAnnotation, TeachingOverlay};
+use p2pgo_core::{Color, Coord};
+
+/// What a click on the board does while teaching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeachingTool {
+    /// Place or take back a demonstration stone
+    Stone(Color),
+    Triangle,
+    /// Label the point with the next free letter
+    Label,
+    /// Clear the point of stones and marks
+    Erase,
+}
+
+impl TeachingTool {
+    pub const ALL: [TeachingTool; 5] = [
+        TeachingTool::Stone(Color::Black),
+        TeachingTool::Stone(Color::White),
+        TeachingTool::Triangle,
+        TeachingTool::Label,
+        TeachingTool::Erase,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TeachingTool::Stone(Color::Black) => "Black stone",
+            TeachingTool::Stone(Color::White) => "White stone",
+            TeachingTool::Triangle => "Triangle",
+            TeachingTool::Label => "Letter",
+            TeachingTool::Erase => "Erase",
+        }
+    }
+}
+
+/// Annotations for a click at `at` with `tool`; clicking what the tool
+/// would place takes it back
+pub fn annotations_for(tool: TeachingTool, at: Coord, overlay: Option<&TeachingOverlay>) -> Vec<TeachingAnnotation> {
+    let stone = overlay.and_then(|o| o.stone_at(at));
+    let mark = overlay.and_then(|o| o.mark_at(at));
+    match tool {
+        TeachingTool::Stone(color) if stone == Some(color) => vec![TeachingAnnotation::RemoveStone { at }],
+        TeachingTool::Stone(color) => vec![TeachingAnnotation::AddStone { at, color }],
+        TeachingTool::Triangle if mark == Some(&Marker::Triangle) => vec![TeachingAnnotation::Unmark { at }],
+        TeachingTool::Triangle => vec![TeachingAnnotation::Mark { at, marker: Marker::Triangle }],
+        TeachingTool::Label if matches!(mark, Some(Marker::Label(_))) => vec![TeachingAnnotation::Unmark { at }],
+        TeachingTool::Label => vec![TeachingAnnotation::Mark { at, marker: Marker::Label(next_letter(overlay)) }],
+        TeachingTool::Erase => {
+            let mut erase = Vec::new();
+            if stone.is_some() {
+                erase.push(TeachingAnnotation::RemoveStone { at });
+            }
+            if mark.is_some() {
+                erase.push(TeachingAnnotation::Unmark { at });
+            }
+            erase
+        }
+    }
+}
+
+/// The first letter from A not yet labelling a point
+fn next_letter(overlay: Option<&TeachingOverlay>) -> String {
+    let used = |letter: &String| {
+        overlay.into_iter()
+            .flat_map(|o| o.marks.iter())
+            .any(|(_, m)| matches!(m, Marker::Label(text) if text == letter))
+    };
+    ('A'..='Z')
+        .map(String::from)
+        .find(|letter| !used(letter))
+        .unwrap_or_else(|| "?".to_string())
+}
+
+/// The tool bar and comment box; returns a comment to add, if sent
+pub fn render_tools(ui: &mut egui::Ui, tool: &mut Option<TeachingTool>, comment: &mut String) -> Option<TeachingAnnotation> {
+    ui.horizontal(|ui| {
+        ui.label("Teach:");
+        ui.selectable_value(tool, None, "Play");
+        for option in TeachingTool::ALL {
+            ui.selectable_value(tool, Some(option), option.label());
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(comment).hint_text("Comment on this position"));
+        let send = ui.button("Add comment").clicked();
+        (send && !comment.trim().is_empty()).then(|| TeachingAnnotation::Comment(std::mem::take(comment).trim().to_string()))
+    })
+    .inner
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The system's thermal pressure, where it reports one.
+
+use p2pgo_core::governor::ThermalPressure;
+
+/// Thermal pressure now, or `None` if this system doesn't say
+pub fn pressure() -> Option<ThermalPressure> {
+    imp::level().map(ThermalPressure::from_level)
+}
+
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+mod imp {
+    use std::ffi::{c_char, c_int};
+    use std::sync::OnceLock;
+
+    /// Posted by the kernel as the level changes
+    const THERMAL_PRESSURE: &[u8] = b"com.apple.system.thermalpressurelevel\0";
+    const NOTIFY_STATUS_OK: u32 = 0;
+
+    extern "C" {
+        fn notify_register_check(name: *const c_char, out_token: *mut c_int) -> u32;
+        fn notify_get_state(token: c_int, state: *mut u64) -> u32;
+    }
+
+    pub fn level() -> Option<u64> {
+        static TOKEN: OnceLock<Option<c_int>> = OnceLock::new();
+        let token = (*TOKEN.get_or_init(|| {
+            let mut token = 0;
+            // SAFETY: the name is NUL-terminated and the token outlives the call
+            let status = unsafe { notify_register_check(THERMAL_PRESSURE.as_ptr().cast(), &mut token) };
+            if status != NOTIFY_STATUS_OK {
+                tracing::warn!(status, "Failed to watch thermal pressure");
+            }
+            (status == NOTIFY_STATUS_OK).then_some(token)
+        }))?;
+        let mut state = 0;
+        // SAFETY: the token was registered above and `state` outlives the call
+        let status = unsafe { notify_get_state(token, &mut state) };
+        (status == NOTIFY_STATUS_OK).then_some(state)
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod imp {
+    pub fn level() -> Option<u64> {
+        None
+    }
+}
@@ -0,0 +1,243 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Telling the player it's their move while the window is in the
+//! background.
+//!
+//! [`TurnAlerts`] decides when: an opponent's move, with the window
+//! unfocused or minimized, at most once per move. A [`Notifier`] shows it,
+//! [`DesktopNotifier`] through the OS. Games awaiting our move are counted
+//! in the window title whether or not anyone was notified.
+
+use std::collections::BTreeMap;
+use p2pgo_core::Move;
+use serde::{Serialize, Deserialize};
+
+/// Which turn alerts the player wants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnAlertSettings {
+    /// Desktop notification when an opponent moves
+    pub notify: bool,
+    /// Games awaiting our move counted in the window title
+    pub badge: bool,
+}
+
+impl Default for TurnAlertSettings {
+    fn default() -> Self {
+        Self { notify: true, badge: true }
+    }
+}
+
+/// An opponent's move that made it our turn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TurnNotice {
+    pub game_id: String,
+    /// Moves played, the opponent's last among them
+    pub move_number: usize,
+    /// The move as labelled on the board
+    pub played: String,
+}
+
+impl TurnNotice {
+    pub fn new(game_id: &str, move_number: usize, mv: &Move, board_size: u8) -> Self {
+        let played = match mv {
+            Move::Place(coord) => coord.display_label(board_size),
+            Move::Pass => "a pass".to_string(),
+            Move::Resign => "resign".to_string(),
+        };
+        Self { game_id: game_id.to_string(), move_number, played }
+    }
+}
+
+/// Shows turn notices outside the window
+pub trait Notifier {
+    fn notify(&mut self, notice: &TurnNotice);
+
+    /// Sound that a byo-yomi period was used, by us if `ours`, leaving
+    /// `periods_left`
+    fn period_used(&mut self, _periods_left: u32, _ours: bool) {}
+
+    /// Game of a notification clicked since the last call
+    fn clicked(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Notifier that shows nothing, for headless apps
+pub struct NoNotifier;
+
+impl Notifier for NoNotifier {
+    fn notify(&mut self, _notice: &TurnNotice) {}
+}
+
+/// Games awaiting our move and who was told about them
+pub struct TurnAlerts {
+    notifier: Box<dyn Notifier>,
+    /// Games where it's our move, with the move count it became ours at
+    awaiting: BTreeMap<String, usize>,
+    /// Move count of each game's last notification
+    notified: BTreeMap<String, usize>,
+    /// Whether the window should ask for attention
+    attention: bool,
+}
+
+impl TurnAlerts {
+    pub fn new(notifier: Box<dyn Notifier>) -> Self {
+        Self { notifier, awaiting: BTreeMap::new(), notified: BTreeMap::new(), attention: false }
+    }
+
+    /// A move was played in `notice.game_id`, by us if `by_us`; true if the
+    /// player was notified
+    pub fn moved(&mut self, notice: TurnNotice, by_us: bool, focused: bool, settings: TurnAlertSettings) -> bool {
+        if by_us {
+            self.awaiting.remove(&notice.game_id);
+            return false;
+        }
+        self.awaiting.insert(notice.game_id.clone(), notice.move_number);
+        let seen = self.notified.get(&notice.game_id).is_some_and(|&n| n >= notice.move_number);
+        if focused || !settings.notify || seen {
+            return false;
+        }
+        self.notified.insert(notice.game_id.clone(), notice.move_number);
+        self.notifier.notify(&notice);
+        self.attention = true;
+        true
+    }
+
+    /// Moves were taken back in `game_id`, leaving `move_number`
+    pub fn rewound(&mut self, game_id: &str, move_number: usize) {
+        self.awaiting.remove(game_id);
+        if self.notified.get(game_id).is_some_and(|&n| n > move_number) {
+            self.notified.insert(game_id.to_string(), move_number);
+        }
+    }
+
+    /// The game ended or we left it
+    pub fn left(&mut self, game_id: &str) {
+        self.awaiting.remove(game_id);
+        self.notified.remove(game_id);
+    }
+
+    /// Games awaiting our move
+    pub fn awaiting(&self) -> usize {
+        self.awaiting.len()
+    }
+
+    /// Clicked notification of a game still awaiting our move
+    pub fn clicked(&mut self) -> Option<String> {
+        self.notifier.clicked().filter(|game_id| self.awaiting.contains_key(game_id))
+    }
+
+    /// A byo-yomi period was used, by us if `ours`; the player hears it
+    /// even with the window focused
+    pub fn period_used(&mut self, periods_left: u32, ours: bool, settings: TurnAlertSettings) {
+        if settings.notify {
+            self.notifier.period_used(periods_left, ours);
+        }
+    }
+
+    /// Whether a notification went out since the last call
+    pub fn take_attention(&mut self) -> bool {
+        std::mem::take(&mut self.attention)
+    }
+}
+
+/// Window title, counting games awaiting our move when there are any
+pub fn window_title(awaiting: usize, settings: TurnAlertSettings) -> String {
+    match awaiting {
+        n if n > 0 && settings.badge => format!("({}) P2P Go", n),
+        _ => "P2P Go".to_string(),
+    }
+}
+
+/// The OS notifier, where this build has one
+#[cfg(feature = "native")]
+pub fn desktop_notifier() -> Box<dyn Notifier> {
+    Box::new(DesktopNotifier::new())
+}
+
+#[cfg(not(feature = "native"))]
+pub fn desktop_notifier() -> Box<dyn Notifier> {
+    Box::new(NoNotifier)
+}
+
+/// Notifications through the OS notification center
+#[cfg(feature = "native")]
+pub struct DesktopNotifier {
+    #[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+    clicks_tx: crossbeam_channel::Sender<String>,
+    clicks_rx: crossbeam_channel::Receiver<String>,
+}
+
+#[cfg(feature = "native")]
+impl DesktopNotifier {
+    pub fn new() -> Self {
+        let (clicks_tx, clicks_rx) = crossbeam_channel::unbounded();
+        Self { clicks_tx, clicks_rx }
+    }
+}
+
+#[cfg(feature = "native")]
+impl Default for DesktopNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "native")]
+impl Notifier for DesktopNotifier {
+    fn notify(&mut self, notice: &TurnNotice) {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname("P2P Go")
+            .summary("Your move")
+            .body(&format!("Your opponent played {}", notice.played));
+        // Only freedesktop servers report clicks; elsewhere clicking just
+        // brings the app forward
+        #[cfg(all(unix, not(target_os = "macos")))]
+        {
+            notification.action("default", "Show game");
+            match notification.show() {
+                Ok(handle) => {
+                    let clicks_tx = self.clicks_tx.clone();
+                    let game_id = notice.game_id.clone();
+                    std::thread::spawn(move || {
+                        handle.wait_for_action(|action| {
+                            if action == "default" {
+                                let _ = clicks_tx.send(game_id);
+                            }
+                        });
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to show turn notification: {}", e),
+            }
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        if let Err(e) = notification.show() {
+            tracing::warn!("Failed to show turn notification: {}", e);
+        }
+    }
+
+    fn period_used(&mut self, periods_left: u32, ours: bool) {
+        let summary = if ours { "You used a byo-yomi period" } else { "Your opponent used a byo-yomi period" };
+        let body = match periods_left {
+            0 => "Out of time".to_string(),
+            1 => "Last period".to_string(),
+            n => format!("{} periods left", n),
+        };
+        // The last periods sound an alarm, the others a bell
+        let sound = if periods_left <= 1 { "alarm-clock-elapsed" } else { "bell" };
+        let result = notify_rust::Notification::new()
+            .appname("P2P Go")
+            .summary(summary)
+            .body(&body)
+            .sound_name(sound)
+            .show();
+        if let Err(e) = result {
+            tracing::warn!("Failed to sound byo-yomi period: {}", e);
+        }
+    }
+
+    fn clicked(&mut self) -> Option<String> {
+        self.clicks_rx.try_recv().ok()
+    }
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! UI settings and progress kept between runs.
+
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Serialize, Deserialize};
+use p2pgo_network::alerts::{self, AlertRule};
+use trainer::backend::TrainingBackend;
+use crate::turn_alerts::TurnAlertSettings;
+
+/// File name of the UI config
+pub const UI_CONFIG_FILE: &str = "ui_config.json";
+
+/// When the worker hints that passing is all that's left
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PassSuggestions {
+    pub enabled: bool,
+    /// Points of expected score a move must gain over passing to be worth playing
+    pub threshold: f32,
+}
+
+impl Default for PassSuggestions {
+    fn default() -> Self {
+        Self { enabled: true, threshold: 1.0 }
+    }
+}
+
+/// Persisted UI state
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Puzzles solved in a row
+    #[serde(default)]
+    pub puzzle_streak: u32,
+    #[serde(default)]
+    pub best_puzzle_streak: u32,
+    /// Highest ladder rung unlocked, from zero
+    #[serde(default)]
+    pub ladder_rung: usize,
+    /// Whether the first-run wizard has been completed
+    #[serde(default)]
+    pub onboarded: bool,
+    /// Name shown to opponents, overriding the command line default
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Whether we help relay other players' traffic, as the runtime
+    /// config's relay preset says
+    #[serde(default)]
+    pub relay_participation: bool,
+    /// Whether finished games are shared as training data
+    #[serde(default)]
+    pub share_training_data: bool,
+    /// Whether opponents are kept from seeing when we look at the board
+    #[serde(default)]
+    pub hide_presence: bool,
+    /// Whether our moves go out with coarse timestamps, the first one late
+    #[serde(default)]
+    pub coarse_move_times: bool,
+    #[serde(default)]
+    pub pass_suggestions: PassSuggestions,
+    #[serde(default)]
+    pub turn_alerts: TurnAlertSettings,
+    /// Alert rules edited in the settings; see [`Self::alert_rules`]
+    #[serde(default)]
+    pub alert_rules: Option<Vec<AlertRule>>,
+    /// Whether passes and resignations are played without asking first
+    #[serde(default)]
+    pub skip_move_confirmations: bool,
+    /// Backend training asks for, falling back to the CPU
+    #[serde(default)]
+    pub training_backend: TrainingBackend,
+    /// Whether the quick territory estimate tints the board during play
+    #[serde(default)]
+    pub territory_estimate: bool,
+}
+
+impl UiConfig {
+    /// The alert rules written in the settings, or the default ones
+    pub fn alert_rules(&self) -> Vec<AlertRule> {
+        self.alert_rules.clone().unwrap_or_else(alerts::default_rules)
+    }
+
+    /// Where the config lives
+    ///
+    /// The config directory, see [`p2pgo_core::p2pgo_dirs`], or the working
+    /// directory if there is none.
+    pub fn default_path() -> PathBuf {
+        match p2pgo_core::p2pgo_dirs::Dirs::current() {
+            Ok(dirs) => dirs.config.join(UI_CONFIG_FILE),
+            Err(_) => PathBuf::from(".").join(UI_CONFIG_FILE),
+        }
+    }
+
+    /// Load the config, falling back to defaults if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid UI config {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Count a finished ladder game on `rung`; a win there unlocks the
+    /// next rung, if there is one. True if it did.
+    pub fn record_ladder(&mut self, rung: usize, won: bool) -> bool {
+        let next = rung + 1;
+        if !won || next <= self.ladder_rung || next >= p2pgo_core::ladder::RUNGS.len() {
+            return false;
+        }
+        self.ladder_rung = next;
+        true
+    }
+
+    /// Count a finished puzzle towards the streak
+    pub fn record_puzzle(&mut self, solved: bool) {
+        if solved {
+            self.puzzle_streak += 1;
+            self.best_puzzle_streak = self.best_puzzle_streak.max(self.puzzle_streak);
+        } else {
+            self.puzzle_streak = 0;
+        }
+    }
+}
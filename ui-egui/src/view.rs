@@ -4,6 +4,11 @@
 
 use p2pgo_core::GameState;
 use p2pgo_network::lobby::GameInfo;
+use crate::archive_view::{ArchiveBrowser, Review};
+use crate::puzzle_view::PuzzleSession;
+use crate::opening_view::OpeningExplorer;
+use crate::editor_view::BoardEditor;
+use crate::onboarding::Onboarding;
 
 /// Different views/screens in the application
 #[derive(Debug, Clone)]
@@ -24,6 +29,8 @@ pub enum View {
         game_state: GameState,
         #[allow(dead_code)]
         our_color: Option<p2pgo_core::Color>,
+        /// Both colors are played here, without the network
+        practice: bool,
     },
     /// Score dialog at end of game
     ScoreDialog {
@@ -34,6 +41,31 @@ pub enum View {
         score_pending: bool,
         score_accepted: bool,
     },
+    /// Archived games
+    Archive {
+        browser: ArchiveBrowser,
+    },
+    /// Stepping through an archived game
+    Review {
+        browser: ArchiveBrowser,
+        review: Review,
+    },
+    /// Practice puzzles from archived games
+    Puzzle {
+        session: PuzzleSession,
+    },
+    /// Opening tree of archived games
+    Openings {
+        explorer: OpeningExplorer,
+    },
+    /// Setting up a position by hand
+    Editor {
+        editor: BoardEditor,
+    },
+    /// First-run wizard, also reachable from the main menu
+    Onboarding {
+        wizard: Onboarding,
+    },
 }
 
 impl Default for View {
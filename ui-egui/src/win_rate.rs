@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Win-rate graph of a game being watched or reviewed.
+//!
+//! The worker's [`WinRateFeed`] evaluates each position of the followed
+//! game, a few per pass of its loop so moves are never held up behind the
+//! model, and remembers values by position hash. A newer model makes the
+//! remembered values stale; they are evaluated again as they come up. The
+//! UI keeps the points in a [`WinRateGraph`] and plots Black's win rate
+//! under the board, where a click picks the move to show.
+
+use std::collections::{BTreeMap, VecDeque};
+use eframe::egui;
+use p2pgo_core::GameState;
+use crate::heat_map::{position_hash, HeatMapCache};
+
+/// Positions the worker evaluates per pass of its loop
+pub const EVALS_PER_POLL: usize = 2;
+
+/// Positions whose values the worker keeps
+pub const WIN_RATE_CACHE_SIZE: usize = 1024;
+
+/// Height of the graph under the board
+const GRAPH_HEIGHT: f32 = 80.0;
+
+/// Black's win rate from a value head output, -1 for a White win to 1 for a Black one
+pub fn win_rate(value: f32) -> f32 {
+    ((value + 1.0) / 2.0).clamp(0.0, 1.0)
+}
+
+/// Black's win rate after `move_number` moves of `game_id`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalPoint {
+    pub game_id: String,
+    pub move_number: usize,
+    pub value: f32,
+}
+
+/// The worker's side: positions of the followed game awaiting evaluation
+#[derive(Debug)]
+pub struct WinRateFeed {
+    /// The followed game as far as it was played
+    game: Option<(String, GameState)>,
+    /// Move numbers still to evaluate, oldest first
+    pending: VecDeque<(usize, GameState)>,
+    /// Win rates by position hash, with the model generation that gave them
+    values: HeatMapCache<(u64, f32)>,
+    generation: u64,
+}
+
+impl Default for WinRateFeed {
+    fn default() -> Self {
+        Self {
+            game: None,
+            pending: VecDeque::new(),
+            values: HeatMapCache::with_capacity(WIN_RATE_CACHE_SIZE),
+            generation: 0,
+        }
+    }
+}
+
+impl WinRateFeed {
+    /// Evaluate every position of `game` so far, then each one it reaches
+    pub fn follow(&mut self, game_id: String, game: GameState) {
+        self.pending.clear();
+        let mut position = game.initial_position();
+        self.pending.push_back((0, position.clone()));
+        for (index, mv) in game.moves.iter().enumerate() {
+            if position.apply_move(mv.clone()).is_err() {
+                break;
+            }
+            self.pending.push_back((index + 1, position.clone()));
+        }
+        self.game = Some((game_id, game));
+    }
+
+    /// Stop following, dropping what wasn't evaluated yet
+    pub fn stop(&mut self) {
+        self.game = None;
+        self.pending.clear();
+    }
+
+    /// The game followed, if any
+    pub fn following(&self) -> Option<&str> {
+        self.game.as_ref().map(|(game_id, _)| game_id.as_str())
+    }
+
+    /// `game_id` reached `state`, by a move or a take-back
+    pub fn moved(&mut self, game_id: &str, state: &GameState) {
+        let Some((followed, game)) = &mut self.game else {
+            return;
+        };
+        if followed != game_id {
+            return;
+        }
+        *game = state.clone();
+        self.pending.retain(|(move_number, _)| *move_number < state.moves.len());
+        self.pending.push_back((state.moves.len(), state.clone()));
+    }
+
+    /// A newer model was loaded: evaluate the followed game again with it
+    pub fn model_changed(&mut self) {
+        self.generation += 1;
+        if let Some((game_id, game)) = self.game.take() {
+            self.follow(game_id, game);
+        }
+    }
+
+    /// Whether positions are waiting to be evaluated
+    pub fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Evaluate the next few positions with `evaluate`, which gives the
+    /// value head output; values of this model are looked up, not evaluated
+    /// again. A failure drops the rest of the game.
+    pub fn poll(&mut self, mut evaluate: impl FnMut(&GameState) -> anyhow::Result<f32>) -> anyhow::Result<Vec<EvalPoint>> {
+        let Some((game_id, _)) = &self.game else {
+            return Ok(Vec::new());
+        };
+        let mut points = Vec::new();
+        for _ in 0..EVALS_PER_POLL {
+            let Some((move_number, state)) = self.pending.pop_front() else {
+                break;
+            };
+            let hash = position_hash(&state);
+            let value = match self.values.get(hash) {
+                Some(&(generation, value)) if generation == self.generation => value,
+                _ => match evaluate(&state) {
+                    Ok(value) => {
+                        self.values.insert(hash, (self.generation, value));
+                        value
+                    }
+                    Err(e) => {
+                        self.pending.clear();
+                        return Err(e);
+                    }
+                },
+            };
+            points.push(EvalPoint { game_id: game_id.clone(), move_number, value: win_rate(value) });
+        }
+        Ok(points)
+    }
+}
+
+/// The UI's side: Black's win rate after each move of one game
+#[derive(Debug, Clone)]
+pub struct WinRateGraph {
+    game_id: String,
+    points: BTreeMap<usize, f32>,
+}
+
+impl WinRateGraph {
+    pub fn new(game_id: String) -> Self {
+        Self { game_id, points: BTreeMap::new() }
+    }
+
+    pub fn game_id(&self) -> &str {
+        &self.game_id
+    }
+
+    /// Keep a point from the worker, if it is for this game
+    pub fn receive(&mut self, game_id: &str, move_number: usize, value: f32) {
+        if game_id == self.game_id {
+            self.points.insert(move_number, value);
+        }
+    }
+
+    /// Forget the points past `moves`, after a take-back
+    pub fn truncate(&mut self, moves: usize) {
+        self.points.retain(|&move_number, _| move_number <= moves);
+    }
+
+    /// Points received, by move number
+    pub fn points(&self) -> Vec<(usize, f32)> {
+        self.points.iter().map(|(&move_number, &value)| (move_number, value)).collect()
+    }
+
+    /// Black's win rate after `move_number` moves, once evaluated
+    pub fn value(&self, move_number: usize) -> Option<f32> {
+        self.points.get(&move_number).copied()
+    }
+}
+
+/// Move nearest to `x` on the graph of a game of `moves` moves
+pub fn move_at(x: f64, moves: usize) -> usize {
+    x.round().clamp(0.0, moves as f64) as usize
+}
+
+/// The graph of a game of `moves` moves, with `cursor` marked; the move
+/// clicked, if any
+pub fn render(ui: &mut egui::Ui, graph: &WinRateGraph, moves: usize, cursor: usize) -> Option<usize> {
+    let points: Vec<[f64; 2]> = graph.points.range(..=moves).map(|(&m, &v)| [m as f64, v as f64]).collect();
+    let hover = match graph.value(cursor) {
+        Some(value) => format!("Move {}: Black {:.0}%", cursor, value * 100.0),
+        None => format!("Move {}: not evaluated yet", cursor),
+    };
+    egui_plot::Plot::new("win_rate")
+        .width(ui.available_width().min(400.0))
+        .height(GRAPH_HEIGHT)
+        .include_x(0.0)
+        .include_x(moves.max(1) as f64)
+        .include_y(0.0)
+        .include_y(1.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .allow_scroll(false)
+        .allow_boxed_zoom(false)
+        .show(ui, |plot_ui| {
+            plot_ui.hline(egui_plot::HLine::new(0.5).color(egui::Color32::GRAY));
+            plot_ui.line(egui_plot::Line::new(egui_plot::PlotPoints::from(points)).name("Black"));
+            plot_ui.vline(egui_plot::VLine::new(cursor as f64).color(egui::Color32::RED).name(hover));
+            let clicked = plot_ui.plot_clicked().then(|| plot_ui.pointer_coordinate()).flatten();
+            clicked.map(|at| move_at(at.x, moves))
+        })
+        .inner
+}
@@ -7,19 +7,96 @@
 use std::thread;
 use std::sync::Mutex;
 use std::rc::Rc;
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use tokio::runtime::Runtime;
-use p2pgo_core::{GameState, GameEvent, Coord};
+use p2pgo_core::{GameState, GameEvent, EndReason};
+use p2pgo_core::engine::NeuralConfig;
+use p2pgo_core::archiver::{self, MaintenanceReport};
+use p2pgo_core::settings::GameSettings;
+use p2pgo_core::phase::GamePhase;
+use p2pgo_core::governor::{Governor, PauseReason};
 use p2pgo_network::{
-    lobby::Lobby,
-    game_channel::GameChannel,
+    lobby::{Lobby, LobbyEvent, GameTerms},
+    game_list::{GamesPage, GamesQuery, GAMES_PAGE_SIZE},
+    join::{JoinPolicy, JoinResponse, PlayerProfile},
+    idle::{IdleConfig, IdleStatus, IdleTracker, IDLE_FORFEIT_REASON},
+    correspondence,
+    event_log::{EventStream, SequencedEvent, EVENT_HISTORY},
+    traffic,
+    sanitize,
+    credits::CreditsLedger,
+    rating::{GameOutcome, RatedGame, RatingTracker},
+    matchmaking::{find_match, Match, MatchPrefs, MatchQueue, QueueEntry, QueueTopic},
+    identity::{Identity, IdentityError, IdentityManager},
+    snapshot::{GameSnapshot, SeatRole, SnapshotSchedule, SnapshotStore},
+    training::{Retraction, TrainingMessage, TrainingStore, TrainingTopic},
+    relay_robustness::now_secs,
+    relay_mode::RelayPreset,
+    timing_privacy::TimingPrivacy,
+    health::{HealthEvent, RestartPolicy, Supervisor, SUBSYSTEM_DEADLINE},
+    alerts::{AlertAction, AlertMetric, AlertMonitor},
+    logging::{self, CAPTURE_DURATION},
+    ArchiveManager,
+    game_channel::{ChannelRole, GameChannel, PremoveOutcome},
     IrohCtx,
 };
 use trainer::GoMini6E;
+use trainer::evaluate::Evaluation;
+use trainer::backend::{self as training, TrainingBackend};
+use trainer::checkpoint::CheckpointStore;
 use burn::backend::wgpu::Wgpu;
-use burn::tensor::{Tensor, backend::Backend};
+use burn::tensor::backend::Backend;
 
-use crate::msg::{UiToNet, NetToUi};
+use crate::msg::{UiToNet, NetToUi, GameErrorKind, Recovery};
+use crate::heat_map::{self, HeatMapCache, Outlook, HEAT_MAP_CACHE_SIZE};
+use crate::messages::connect_error_message;
+use crate::ui_config::PassSuggestions;
+use crate::runtime_config::{ApplyReport, RuntimeConfig};
+use crate::win_rate::{EvalPoint, WinRateFeed};
+use crate::thermal;
+use p2pgo_network::invite::Invite;
+
+/// How long to wait for a host's game advertisement after a ticket connect
+const ADVERT_WAIT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Attempts at connecting by ticket while the failure looks temporary
+const CONNECT_ATTEMPTS: u32 = 3;
+
+/// Wait before the second connect attempt, doubling after each failure
+const CONNECT_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How often the archive and snapshot directories are checked and tidied
+const MAINTENANCE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// Supervised subsystem forwarding lobby gossip
+const LOBBY_GOSSIP: &str = "lobby gossip";
+
+/// Watched worker loop, which can't be restarted without the app
+const WORKER_LOOP: &str = "network worker";
+
+/// How often the supervised subsystems are checked
+const HEALTH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How often each game's channel metrics are sent to the UI
+const METRICS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// How often traffic counts are taken; each sample covers this long
+const TRAFFIC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// How often the system's thermal pressure is read
+const THERMAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Quiet time before a heat map request is computed, so fast play only runs the latest
+const HEAT_MAP_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Random playouts behind each ownership estimate, about 10ms on 9x9
+const OWNERSHIP_PLAYOUTS: usize = 64;
+
+/// Random playouts behind each move compared when looking for a pass suggestion
+const PASS_CHECK_PLAYOUTS: usize = 16;
+
+/// Training checkpoints kept besides the published weights
+const CHECKPOINTS_KEPT: usize = 3;
 
 /// Tracker for score acceptance with 3-minute timeout
 #[derive(Debug)]
@@ -53,6 +130,92 @@ impl ScoreAcceptanceTracker {
     }
 }
 
+/// Minimum time between two unsolicited `GamesUpdated` messages
+pub const GAMES_COALESCE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Decides when a changed page of the game list should be pushed to the UI.
+///
+/// Pages are compared by their games, offset and total, so re-announcing
+/// the same games never produces an update, and bursts of changes are
+/// folded into at most one update per [`GAMES_COALESCE_INTERVAL`].
+#[derive(Debug, Default)]
+pub struct GamesCoalescer {
+    last_sent: Option<GamesPage>,
+    last_sent_at: Option<std::time::Instant>,
+    pending: Option<GamesPage>,
+}
+
+impl GamesCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Offer the current page, returning it if it should be sent now
+    pub fn offer(&mut self, page: GamesPage, now: std::time::Instant) -> Option<GamesPage> {
+        if self.last_sent.as_ref() == Some(&page) {
+            self.pending = None;
+            return None;
+        }
+        
+        if self.in_window(now) {
+            self.pending = Some(page);
+            return None;
+        }
+        
+        self.mark_sent(page.clone(), now);
+        Some(page)
+    }
+    
+    /// Release a held-back page once the coalescing window has passed
+    pub fn poll(&mut self, now: std::time::Instant) -> Option<GamesPage> {
+        if self.pending.is_none() || self.in_window(now) {
+            return None;
+        }
+        let page = self.pending.take()?;
+        self.mark_sent(page.clone(), now);
+        Some(page)
+    }
+    
+    /// Always send the page, e.g. for a manual refresh
+    pub fn force(&mut self, page: GamesPage, now: std::time::Instant) -> GamesPage {
+        self.pending = None;
+        self.mark_sent(page.clone(), now);
+        page
+    }
+    
+    fn in_window(&self, now: std::time::Instant) -> bool {
+        self.last_sent_at
+            .map(|at| now.duration_since(at) < GAMES_COALESCE_INTERVAL)
+            .unwrap_or(false)
+    }
+    
+    fn mark_sent(&mut self, page: GamesPage, now: std::time::Instant) {
+        self.last_sent = Some(page);
+        self.last_sent_at = Some(now);
+    }
+}
+
+/// Restart stalled subsystems from a task of its own and tell the UI what
+/// happened, passing the events on to the worker loop until it is gone
+fn check_health(health: Supervisor, ui_tx: Sender<NetToUi>) -> tokio::sync::mpsc::UnboundedReceiver<HealthEvent> {
+    let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut checks = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        while !events_tx.is_closed() {
+            checks.tick().await;
+            for event in health.check(std::time::Instant::now()) {
+                let msg = match &event {
+                    HealthEvent::Restarted { name, .. } => NetToUi::SubsystemRestarted { name: name.clone() },
+                    HealthEvent::Failed { name, .. } => NetToUi::SubsystemFailed { name: name.clone() },
+                };
+                let _ = ui_tx.send(msg);
+                let _ = events_tx.send(event);
+            }
+        }
+    });
+    events_rx
+}
+
 /// Spawn the background worker thread
 pub fn spawn_worker(
     net_rx: Receiver<UiToNet>,
@@ -61,7 +224,7 @@ pub fn spawn_worker(
     player_name: String,
 ) -> anyhow::Result<thread::JoinHandle<()>> {
     let handle = thread::spawn(move || {
-        if let Err(e) = run_worker(net_rx, ui_tx, default_board_size, player_name) {
+        if let Err(e) = run_worker(net_rx, ui_tx, default_board_size, player_name, WorkerEnv::default()) {
             eprintln!("Worker thread error: {}", e);
         }
     });
@@ -74,7 +237,47 @@ pub fn start(
     net_rx: Receiver<UiToNet>,
     ui_tx: Sender<NetToUi>,
 ) -> anyhow::Result<()> {
-    run_worker(net_rx, ui_tx, 9, "HeadlessPlayer".to_string())
+    run_worker(net_rx, ui_tx, 9, "HeadlessPlayer".to_string(), WorkerEnv::default())
+}
+
+/// Headless start that keeps game snapshots in `snapshot_dir`
+#[allow(dead_code)]
+pub fn start_with_snapshot_dir(
+    net_rx: Receiver<UiToNet>,
+    ui_tx: Sender<NetToUi>,
+    snapshot_dir: std::path::PathBuf,
+) -> anyhow::Result<()> {
+    let env = WorkerEnv { snapshots: Some(SnapshotStore::new(snapshot_dir)), ..WorkerEnv::default() };
+    run_worker(net_rx, ui_tx, 9, "HeadlessPlayer".to_string(), env)
+}
+
+/// Headless start for one of several workers in a process, as players.
+///
+/// Workers given clones of one `lobby` see each other's games and play
+/// them over shared channels, standing in for gossip between nodes. Each
+/// keeps its identity and snapshots in its own `data_dir`.
+#[allow(dead_code)]
+pub fn start_in_process(
+    net_rx: Receiver<UiToNet>,
+    ui_tx: Sender<NetToUi>,
+    player_name: String,
+    lobby: Lobby,
+    data_dir: std::path::PathBuf,
+) -> anyhow::Result<()> {
+    let env = WorkerEnv {
+        identity: Some(IdentityManager::new(data_dir.join("identity.key"))),
+        snapshots: Some(SnapshotStore::new(data_dir.join("snapshots"))),
+        lobby: Some(lobby),
+    };
+    run_worker(net_rx, ui_tx, 9, player_name, env)
+}
+
+/// Where a worker keeps its state; the platform defaults where unset
+#[derive(Default)]
+struct WorkerEnv {
+    identity: Option<IdentityManager>,
+    snapshots: Option<SnapshotStore>,
+    lobby: Option<Lobby>,
 }
 
 fn run_worker(
@@ -82,20 +285,133 @@ fn run_worker(
     ui_tx: Sender<NetToUi>,
     default_board_size: u8,
     player_name: String,
+    env: WorkerEnv,
 ) -> anyhow::Result<()> {
     let rt = Runtime::new()?;
     
     rt.block_on(async {
-        let mut worker = NetworkWorker::new(ui_tx, default_board_size, player_name).await?;
+        let identity = unlock_identity(&net_rx, &ui_tx, env.identity).await?;
+        let snapshots = env.snapshots.or_else(|| match SnapshotStore::open_default() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                tracing::warn!("Game snapshots unavailable: {:#}", e);
+                None
+            }
+        });
+        let lobby = env.lobby.unwrap_or_default();
+        let mut worker = NetworkWorker::new(ui_tx, default_board_size, player_name, identity, snapshots, lobby).await?;
         worker.run(net_rx).await
     })
 }
 
+/// Persistent identity and the key file it was loaded from
+struct LoadedIdentity {
+    manager: IdentityManager,
+    identity: Identity,
+}
+
+/// Load the persistent identity, asking the UI for the passphrase while the
+/// key file is encrypted.
+///
+/// Returns `None` when there is no usable key file; the node then runs with
+/// a throwaway identity rather than overwriting a file it cannot read.
+async fn unlock_identity(
+    net_rx: &Receiver<UiToNet>,
+    ui_tx: &Sender<NetToUi>,
+    manager: Option<IdentityManager>,
+) -> anyhow::Result<Option<LoadedIdentity>> {
+    let manager = match manager.map_or_else(IdentityManager::open_default, Ok) {
+        Ok(manager) => manager,
+        Err(e) => {
+            tracing::warn!("Persistent identity unavailable: {}", e);
+            return Ok(None);
+        }
+    };
+    
+    let mut passphrase: Option<String> = None;
+    loop {
+        let error = match manager.load_or_generate(passphrase.as_deref()) {
+            Ok(identity) => return Ok(Some(LoadedIdentity { manager, identity })),
+            Err(IdentityError::PassphraseRequired) => None,
+            Err(IdentityError::WrongPassphrase) => Some(IdentityError::WrongPassphrase.to_string()),
+            Err(e) => {
+                tracing::error!("Failed to load identity from {}: {}", manager.path().display(), e);
+                let _ = ui_tx.send(NetToUi::Error {
+                    message: format!("{}. Using a temporary node ID this session.", e),
+                });
+                return Ok(None);
+            }
+        };
+        ui_tx.send(NetToUi::IdentityLocked { error })?;
+        
+        // Nothing else can run without a node ID, so wait for the passphrase
+        passphrase = loop {
+            match net_rx.try_recv() {
+                Ok(UiToNet::UnlockIdentity { passphrase }) => break Some(passphrase),
+                Ok(UiToNet::Shutdown) => {
+                    let _ = ui_tx.send(NetToUi::ShutdownAck);
+                    anyhow::bail!("Shut down before the identity was unlocked");
+                }
+                Ok(msg) => tracing::debug!("Ignoring {:?} until the identity is unlocked", msg),
+                Err(TryRecvError::Empty) => tokio::time::sleep(std::time::Duration::from_millis(50)).await,
+                Err(TryRecvError::Disconnected) => anyhow::bail!("UI closed before the identity was unlocked"),
+            }
+        };
+    }
+}
+
+/// Maintain the archive, then the snapshots, folding finished games into
+/// the archive when there is one
+fn run_maintenance(archive_dir: Option<&std::path::Path>, snapshots: Option<&SnapshotStore>) -> anyhow::Result<MaintenanceReport> {
+    let mut report = MaintenanceReport::default();
+    if let Some(dir) = archive_dir {
+        report.merge(archiver::run_maintenance(dir)?);
+    }
+    if let Some(store) = snapshots {
+        report.merge(match archive_dir {
+            Some(dir) => store.run_maintenance(dir)?,
+            None => archiver::run_maintenance(store.dir())?,
+        });
+    }
+    Ok(report)
+}
+
 struct ActiveGameData {
     game: std::sync::Arc<GameChannel>,
     game_id: String,
+    // The color we play; the host plays Black
+    color: p2pgo_core::Color,
+    // Whether we host the game or joined it
+    role: SeatRole,
     game_state: Option<GameState>,
-    game_rx: tokio::sync::broadcast::Receiver<p2pgo_core::GameEvent>,
+    // Events after the game state we hold, none missed or repeated
+    game_rx: EventStream,
+    // Started by the first game event; a host waiting for an opponent is not idle
+    idle: Option<IdleTracker>,
+    // When the game is next saved for resuming after a restart
+    snapshot: SnapshotSchedule,
+    // Moves seen this session with when they arrived, for the post-game report
+    records: Vec<p2pgo_core::MoveRecord>,
+    // Played with a per-move budget of a day; the peer may be offline
+    correspondence: bool,
+    // Opponent moves since a correspondence game was restored, until we move
+    unread: Option<usize>,
+    // Moves in the position last checked for a pass suggestion
+    pass_checked: Option<usize>,
+    // Passing is suggested at most once a game
+    pass_suggested: bool,
+}
+
+/// The host's answer to a join request, sent back by the task that waited for it
+struct JoinAnswer {
+    game_id: String,
+    response: Result<JoinResponse, p2pgo_network::Error>,
+}
+
+/// A running matchmaking search
+struct QueueSearch {
+    cancel: tokio::sync::oneshot::Sender<()>,
+    task: tokio::task::JoinHandle<p2pgo_network::Result<Option<Match>>>,
 }
 
 struct NetworkWorker {
@@ -107,15 +423,90 @@ struct NetworkWorker {
     player_name: String,
     config: crate::app::AppConfig,
     #[allow(dead_code)]
-    lobby_rx: tokio::sync::broadcast::Receiver<p2pgo_network::lobby::LobbyEvent>,
-    iroh_ctx: IrohCtx,
+    lobby_rx: tokio::sync::broadcast::Receiver<LobbyEvent>,
+    iroh_ctx: std::sync::Arc<IrohCtx>,
     // AI model lazily loaded on first ghost move request
     ai_model: Option<Rc<Mutex<GoMini6E<Wgpu>>>>,
+    // Heat maps and their outlooks already computed, by position
+    heat_maps: HeatMapCache<(Vec<f32>, Outlook)>,
+    // Thresholds for ghost moves and resignation advice
+    neural: NeuralConfig,
+    // Game whose heat map was last requested, and when
+    pending_heat_map: Option<(String, std::time::Instant)>,
+    // Ownership estimates already computed, by position
+    ownership_maps: HeatMapCache,
+    // Game whose ownership was last requested, and when
+    pending_ownership: Option<(String, std::time::Instant)>,
+    // Positions of the game whose win rate is followed, and their values
+    win_rate: WinRateFeed,
+    // When channel metrics were last sent to the UI
+    metrics_sent_at: std::time::Instant,
+    // When the archive was last maintained; none yet this session
+    maintained_at: Option<std::time::Instant>,
+    // Restarts background subsystems that die or wedge
+    health: Supervisor,
+    // What the health checks did, for the alerts
+    health_rx: tokio::sync::mpsc::UnboundedReceiver<HealthEvent>,
+    // Turns lost ACKs and restarts into warnings and diagnostics
+    alerts: AlertMonitor,
+    // When traffic counts were last taken, and whether the UI wants them
+    traffic_taken_at: std::time::Instant,
+    watching_traffic: bool,
+    // Holds background training back, and why it is on hold as last told to the UI
+    governor: Governor,
+    training_paused: Option<PauseReason>,
+    // When thermal pressure was last read; not yet this session
+    thermal_checked_at: Option<std::time::Instant>,
     // Gossip buffer size configuration
     #[allow(dead_code)]
     gossip_buffer_size: usize,
     // Score acceptance tracking
     score_trackers: std::collections::HashMap<u8, ScoreAcceptanceTracker>,
+    // Push-based lobby updates
+    games_coalescer: GamesCoalescer,
+    // Filter and order of the games list the UI shows
+    games_query: GamesQuery,
+    // How join requests for games we host are answered
+    join_policy: JoinPolicy,
+    // Idle thresholds for abandoning games whose opponent vanished
+    idle_config: IdleConfig,
+    // Where abandoned games are archived, if the archive directory is usable
+    archive: Option<ArchiveManager>,
+    // Practice game broadcast to spectators, with its channel
+    shared_board: Option<(String, std::sync::Arc<GameChannel>)>,
+    // Persisted training credits, if the data directory is usable
+    credits: Option<CreditsLedger>,
+    // Our copies of games shared for training, if the data directory is usable
+    training: Option<TrainingStore>,
+    // Where retractions of shared games are published and heard
+    training_topic: TrainingTopic,
+    training_rx: tokio::sync::broadcast::Receiver<TrainingMessage>,
+    // Games the player kept out of training although sharing is on
+    excluded_from_training: std::collections::HashSet<String>,
+    // Whether and when to suggest passing
+    pass_suggestions: PassSuggestions,
+    // Coarse timestamps for the moves of our games, if the player asked
+    timing_privacy: Option<TimingPrivacy>,
+    // Advanced config in effect, from the first one the app sent
+    runtime_config: Option<RuntimeConfig>,
+    // Relay preset the endpoint enforces; none until the app sends one
+    relay_preset: Option<RelayPreset>,
+    // Our rating and results per opponent, if the data directory is usable
+    ratings: Option<RatingTracker>,
+    // Our place in the matchmaking queue, while searching
+    queue: Option<QueueSearch>,
+    // Games we asked to join and answers to those requests, which wait on the host
+    joining: std::collections::HashSet<String>,
+    join_tx: tokio::sync::mpsc::UnboundedSender<JoinAnswer>,
+    join_rx: tokio::sync::mpsc::UnboundedReceiver<JoinAnswer>,
+    // Persistent identity behind the node ID, if the key file is usable
+    identity: Option<LoadedIdentity>,
+    // Games in progress saved for resuming, if the data directory is usable
+    snapshots: Option<SnapshotStore>,
+    // Saved game offered to the UI at startup
+    resumable: Option<GameSnapshot>,
+    // Ticket of the last peer we connected to, kept in snapshots for reconnecting
+    peer_ticket: Option<String>,
     #[cfg(test)]
     last_coord: Option<p2pgo_core::Coord>,
 }
@@ -125,12 +516,24 @@ impl NetworkWorker {
         ui_tx: Sender<NetToUi>,
         default_board_size: u8,
         player_name: String,
+        identity: Option<LoadedIdentity>,
+        snapshots: Option<SnapshotStore>,
+        lobby: Lobby,
     ) -> anyhow::Result<Self> {
-        let lobby = Lobby::new();
         let lobby_rx = lobby.subscribe();
         
-        // Initialize the iroh context
-        let iroh_ctx = IrohCtx::new().await?;
+        // Initialize the iroh context with our stable node ID if we have one
+        let iroh_ctx = std::sync::Arc::new(match &identity {
+            Some(loaded) => IrohCtx::with_identity(&loaded.identity).await?,
+            None => IrohCtx::new().await?,
+        });
+        // Join requests for other nodes' games go out through it
+        lobby.set_transport(iroh_ctx.clone()).await;
+        
+        // Games' gossip listeners and watchdogs are restarted should they die
+        let health = Supervisor::new(RestartPolicy::default());
+        lobby.set_supervisor(health.clone()).await;
+        let health_rx = check_health(health.clone(), ui_tx.clone());
         
         // Get and send the local node ID to UI
         let node_id = iroh_ctx.node_id().to_string();
@@ -144,6 +547,34 @@ impl NetworkWorker {
             }
         }
         
+        let credits = match CreditsLedger::open_default(&node_id) {
+            Ok(ledger) => Some(ledger),
+            Err(e) => {
+                tracing::warn!("Training credits unavailable: {:#}", e);
+                None
+            }
+        };
+        let training = match TrainingStore::open_default() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                tracing::warn!("Training copies unavailable: {:#}", e);
+                None
+            }
+        };
+        #[cfg(feature = "iroh")]
+        let training_topic = TrainingTopic::gossip(IrohCtx::clone(&iroh_ctx), &health);
+        #[cfg(not(feature = "iroh"))]
+        let training_topic = TrainingTopic::local();
+        let training_rx = training_topic.subscribe();
+        let ratings = match RatingTracker::open_default() {
+            Ok(tracker) => Some(tracker),
+            Err(e) => {
+                tracing::warn!("Rating unavailable: {:#}", e);
+                None
+            }
+        };
+        let (join_tx, join_rx) = tokio::sync::mpsc::unbounded_channel();
+        
         Ok(Self {
             ui_tx,
             lobby,
@@ -154,8 +585,48 @@ impl NetworkWorker {
             lobby_rx,
             iroh_ctx,
             ai_model: None,
+            heat_maps: HeatMapCache::with_capacity(HEAT_MAP_CACHE_SIZE),
+            neural: NeuralConfig::default(),
+            pending_heat_map: None,
+            ownership_maps: HeatMapCache::new(HEAT_MAP_CACHE_SIZE),
+            pending_ownership: None,
+            win_rate: WinRateFeed::default(),
+            metrics_sent_at: std::time::Instant::now(),
+            maintained_at: None,
+            health,
+            health_rx,
+            alerts: AlertMonitor::default(),
+            traffic_taken_at: std::time::Instant::now(),
+            watching_traffic: false,
+            governor: Governor::default(),
+            training_paused: None,
+            thermal_checked_at: None,
             gossip_buffer_size: 32, // Default buffer size
             score_trackers: std::collections::HashMap::new(),
+            games_coalescer: GamesCoalescer::new(),
+            games_query: GamesQuery::default(),
+            join_policy: JoinPolicy::default(),
+            idle_config: IdleConfig::default(),
+            archive: ArchiveManager::new().ok(),
+            shared_board: None,
+            credits,
+            training,
+            training_topic,
+            training_rx,
+            excluded_from_training: std::collections::HashSet::new(),
+            pass_suggestions: PassSuggestions::default(),
+            timing_privacy: None,
+            runtime_config: None,
+            relay_preset: None,
+            ratings,
+            queue: None,
+            joining: std::collections::HashSet::new(),
+            join_tx,
+            join_rx,
+            identity,
+            snapshots,
+            resumable: None,
+            peer_ticket: None,
             #[cfg(test)]
             last_coord: None,
         })
@@ -171,18 +642,35 @@ impl NetworkWorker {
         // Initial game list refresh
         self.refresh_games().await?;
         
+        // Tell the UI it can send startup actions now that we are listening
+        let ticket = self.iroh_ctx.ticket().await.ok();
+        let _ = self.ui_tx.send(NetToUi::WorkerReady {
+            node_id: self.iroh_ctx.node_id().to_string(),
+            ticket,
+        });
+        self.send_credits();
+        self.send_ratings();
+        self.send_identity_status();
+        // Finished and corrupt snapshots are cleared before looking for one to resume
+        self.poll_maintenance(std::time::Instant::now()).await;
+        self.offer_resumable_game();
+        self.resume_correspondence_games().await?;
+        
         let mut heartbeat_timer = tokio::time::interval(tokio::time::Duration::from_secs(30));
-        let mut auto_refresh_timer = tokio::time::interval(tokio::time::Duration::from_secs(2));
+        let mut clock_timer = tokio::time::interval(tokio::time::Duration::from_secs(1));
+        // The health checks run apart from the loop, so they see it wedge
+        let heartbeat = self.health.watch(WORKER_LOOP, SUBSYSTEM_DEADLINE);
         
         loop {
+            heartbeat.beat();
             tokio::select! {
                 _ = heartbeat_timer.tick() => {
                     tracing::debug!("NetworkWorker heartbeat");
+                    p2pgo_network::session_log::record_tick();
                 }
-                _ = auto_refresh_timer.tick() => {
-                    if self.config.auto_refresh {
-                        tracing::debug!("Auto-refreshing lobby");
-                        self.refresh_games().await?;
+                _ = clock_timer.tick() => {
+                    for active_game in self.active_games.values() {
+                        active_game.game.send_clock_tick().await;
                     }
                 }
                 _ = tokio::time::sleep(tokio::time::Duration::from_millis(10)) => {
@@ -195,22 +683,129 @@ impl NetworkWorker {
                 #[cfg(feature = "headless")]
                 println!("Worker: Received UI message: {:?}", msg);
                 match msg {
-                    UiToNet::CreateGame { board_size } => {
-                        self.create_game(board_size).await?;
+                    UiToNet::CreateGame { board_size, rule_config } => {
+                        let settings = GameSettings { rule_config, ..GameSettings::standard(board_size) };
+                        self.create_game(settings.new_game(), settings, None).await?;
                     }
+                            UiToNet::CreateGameFromPosition { position, komi } => {
+                                let settings = GameSettings { komi, rule_config: position.rule_config, ..GameSettings::standard(position.board_size) };
+                                self.create_game(position, settings, None).await?;
+                            }
+                            UiToNet::ShareBoard { state } => {
+                                self.share_board(state).await;
+                            }
+                            UiToNet::ShareMove { mv } => {
+                                if let Some((game_id, channel)) = &self.shared_board {
+                                    if let Err(e) = channel.send_move(mv).await {
+                                        tracing::warn!("Shared board {} refused a move: {}", game_id, e);
+                                    }
+                                }
+                            }
+                            UiToNet::StopSharing { archive } => {
+                                self.stop_sharing(archive).await;
+                            }
+                            UiToNet::AnalyzePosition { position } => {
+                                self.handle_analyze_position(position).await;
+                            }
+                            UiToNet::RequestGameReport { game_id, state } => {
+                                self.send_game_report(game_id, state).await;
+                            }
+                            UiToNet::LadderMove { rung, seed, state } => {
+                                self.send_ladder_move(rung, seed, state).await;
+                            }
+                            UiToNet::ImportSgf { dir, options } => {
+                                self.import_sgf(dir, options);
+                            }
+                            UiToNet::RequestOwnership { game_id, position_hash } => {
+                                if let Some(map) = self.ownership_maps.get(position_hash) {
+                                    let _ = self.ui_tx.send(NetToUi::Ownership { position_hash, map: map.to_vec() });
+                                } else {
+                                    self.pending_ownership = Some((game_id, std::time::Instant::now()));
+                                }
+                            }
+                            UiToNet::FollowWinRate { game_id, state } => {
+                                if state.board_size > 9 {
+                                    let _ = self.ui_tx.send(NetToUi::Error {
+                                        message: "The win rate is only available on boards up to 9x9".to_string(),
+                                    });
+                                } else {
+                                    self.win_rate.follow(game_id, state);
+                                }
+                            }
+                            UiToNet::StopWinRate => {
+                                self.win_rate.stop();
+                            }
+                            UiToNet::RequestHeatMap { game_id, position_hash } => {
+                                if let Some((map, outlook)) = self.heat_maps.get(position_hash) {
+                                    let _ = self.ui_tx.send(NetToUi::HeatMap { position_hash, map: map.clone() });
+                                    let _ = self.ui_tx.send(NetToUi::Outlook { position_hash, outlook: *outlook });
+                                } else {
+                                    self.pending_heat_map = Some((game_id, std::time::Instant::now()));
+                                }
+                            }
                             UiToNet::JoinGame { game_id } => {
                                 self.join_game(game_id).await?;
                             }
+                            UiToNet::JoinQueue { prefs } => {
+                                self.join_queue(prefs).await;
+                            }
+                            UiToNet::LeaveQueue => {
+                                self.leave_queue();
+                            }
                             UiToNet::MakeMove { mv, board_size } => {
                                 self.make_move(mv, board_size).await?;
                             }
+                            UiToNet::Premove { mv, board_size } => {
+                                self.queue_premove(mv, board_size).await?;
+                            }
+                            UiToNet::ClearPremove { board_size } => {
+                                let board_size = board_size.unwrap_or(self.default_board_size);
+                                if let Some(active_game) = self.active_games.get(&board_size) {
+                                    active_game.game.clear_premove().await;
+                                }
+                            }
                             UiToNet::RefreshGames => {
                                 self.refresh_games().await?;
                             }
+                            UiToNet::RequestGamesPage { query, offset } => {
+                                self.send_games_page(query, offset).await;
+                            }
+                            UiToNet::GetGameState { game_id } => {
+                                self.send_game_state(game_id).await;
+                            }
                             UiToNet::LeaveGame => {
                                 self.leave_game().await?;
                             }
+                            UiToNet::ResumeGame { game_id } => {
+                                self.resume_game(&game_id).await?;
+                            }
+                            UiToNet::DiscardResumable { game_id } => {
+                                self.resumable = None;
+                                self.remove_snapshot(&game_id);
+                            }
+                            UiToNet::SetCorrespondence { game_id, enabled } => {
+                                self.set_correspondence(&game_id, enabled).await;
+                            }
+                            UiToNet::SetTeaching { game_id, enabled } => {
+                                self.set_teaching(&game_id, enabled).await;
+                            }
+                            UiToNet::Annotate { annotation, board_size } => {
+                                self.annotate(annotation, board_size).await;
+                            }
+                            UiToNet::RequestUndo { moves } => {
+                                self.request_undo(moves).await;
+                            }
+                            UiToNet::AnswerUndo { accept } => {
+                                self.answer_undo(accept).await;
+                            }
+                            UiToNet::SendPresence { presence } => {
+                                if let Some(active_game) = self.active_games.get(&self.default_board_size) {
+                                    active_game.game.send_presence(presence).await;
+                                }
+                            }
                             UiToNet::Shutdown => {
+                                // Closing mid-game must not lose moves since the last periodic snapshot
+                                self.save_snapshots(std::time::Instant::now(), true).await;
                                 let _ = self.ui_tx.send(NetToUi::ShutdownAck);
                                 break;
                             }
@@ -221,24 +816,45 @@ impl NetworkWorker {
                                 }
                             }
                             UiToNet::ConnectByTicket { ticket } => {
-                                if let Err(e) = self.iroh_ctx.connect_by_ticket(&ticket).await {
+                                if let Err(e) = self.connect_with_retry(&ticket).await {
                                     let _ = self.ui_tx.send(NetToUi::Error { 
-                                        message: format!("Failed to connect by ticket: {}", e) 
+                                        message: connect_error_message(&e)
+                                            .unwrap_or_else(|| format!("Failed to connect by ticket: {}", e)),
                                     });
                                 } else {
+                                    self.peer_ticket = Some(ticket);
+                                    
                                     // After successful connection, refresh games to see the host's advert
                                     self.refresh_games().await?;
                                     
-                                    // Wait a brief moment to allow game announcements to arrive
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                                    
-                                    // Refresh games again to ensure we have the most recent list
-                                    let games = self.refresh_games().await?;
-                                    
-                                    // Auto-join the first available game
-                                    if let Some(game) = games.first() {
+                                    // Wait for the host's advertisement rather than sleeping a fixed time
+                                    if let Some(game) = self.lobby.wait_for_game(ADVERT_WAIT_TIMEOUT).await {
+                                        self.refresh_games().await?;
+                                        
+                                        // Auto-join the first available game
                                         tracing::info!("Auto-joining game {} via ticket connection", game.id);
                                         self.join_game(game.id.clone()).await?;
+                                    } else {
+                                        tracing::info!("No game advertised within {:?} of ticket connection", ADVERT_WAIT_TIMEOUT);
+                                    }
+                                }
+                            }
+                            UiToNet::JoinInvite { invite } => {
+                                self.join_invite(invite).await?;
+                            }
+                            UiToNet::CreateInvite { game_id } => {
+                                match self.iroh_ctx.ticket().await {
+                                    Ok(ticket) => {
+                                        let board_size = self.lobby.list_games().await.into_iter()
+                                            .find(|game| game.id == game_id)
+                                            .map_or(self.default_board_size, |game| game.board_size);
+                                        let invite = Invite::new(ticket, game_id, board_size, self.player_name.clone());
+                                        let _ = self.ui_tx.send(NetToUi::Invite { link: invite.to_link() });
+                                    }
+                                    Err(e) => {
+                                        let _ = self.ui_tx.send(NetToUi::Error {
+                                            message: format!("Failed to generate ticket for invite: {}", e)
+                                        });
                                     }
                                 }
                             }
@@ -258,6 +874,9 @@ impl NetworkWorker {
                                     }
                                 }
                             }
+                            UiToNet::WatchTraffic { enabled } => {
+                                self.watching_traffic = enabled;
+                            }
                             UiToNet::RunNetReport => {
                                 // For now, just return a simple report showing node ID
                                 let report = format!("Node ID: {}\nEndpoint: Active", self.iroh_ctx.node_id());
@@ -275,6 +894,120 @@ impl NetworkWorker {
                             UiToNet::CalculateScore { dead_stones } => {
                                 self.handle_calculate_score(dead_stones).await?;
                             }
+                            UiToNet::RespondJoin { game_id, request_id, accept, reason } => {
+                                if let Err(e) = self.lobby.respond_to_join(&game_id, request_id, accept, reason).await {
+                                    tracing::warn!("Failed to answer join request {}: {}", request_id, e);
+                                }
+                            }
+                            UiToNet::SetJoinPolicy { policy } => {
+                                self.join_policy = policy;
+                                for active_game in self.active_games.values() {
+                                    let _ = self.lobby.set_join_policy(&active_game.game_id, policy).await;
+                                }
+                            }
+                            UiToNet::AbandonGame { game_id } => {
+                                self.abandon_game(&game_id).await?;
+                            }
+                            UiToNet::KeepWaiting { game_id } => {
+                                let now = std::time::Instant::now();
+                                if let Some(idle) = self.active_games.values_mut()
+                                    .find(|g| g.game_id == game_id)
+                                    .and_then(|g| g.idle.as_mut())
+                                {
+                                    idle.keep_waiting(now);
+                                }
+                            }
+                            UiToNet::UnlockIdentity { .. } => {
+                                tracing::debug!("Identity already unlocked");
+                            }
+                            UiToNet::SetIdentityPassphrase { passphrase } => {
+                                self.set_identity_passphrase(passphrase.as_deref());
+                            }
+                            UiToNet::ExportIdentity { passphrase } => {
+                                self.export_identity(passphrase.as_deref());
+                            }
+                            UiToNet::ImportIdentity { data, passphrase } => {
+                                self.import_identity(&data, passphrase.as_deref());
+                            }
+                            UiToNet::SetPlayerName { name } => {
+                                self.player_name = name;
+                            }
+                            UiToNet::SetSharing { relay, training } => {
+                                self.config.relay_participation = relay;
+                                self.config.share_training_data = training;
+                                tracing::info!(relay, training, "Sharing preferences updated");
+                                // Before the runtime config arrives there is no preset to adjust
+                                if let Some(preset) = self.relay_preset {
+                                    self.set_relay_preset(preset.with_participation(relay));
+                                }
+                            }
+                            UiToNet::ExcludeFromTraining { game_id, excluded } => {
+                                // The archive browser writes the flag itself; scored games
+                                // pick it up when they are archived
+                                if excluded {
+                                    self.excluded_from_training.insert(game_id);
+                                } else {
+                                    self.excluded_from_training.remove(&game_id);
+                                }
+                            }
+                            UiToNet::DeleteTrainingCopies { game_id } => {
+                                self.delete_training_copies(game_id).await;
+                            }
+                            UiToNet::SetPassSuggestions { settings } => {
+                                self.pass_suggestions = settings;
+                            }
+                            UiToNet::SetTimingPrivacy { enabled } => {
+                                self.timing_privacy = enabled.then(TimingPrivacy::default);
+                                for active_game in self.active_games.values() {
+                                    active_game.game.set_timing_privacy(self.timing_privacy).await;
+                                }
+                            }
+                            UiToNet::SetLogFilter { filter } => {
+                                if let Err(e) = logging::set_filter(&filter) {
+                                    let _ = self.ui_tx.send(NetToUi::Error {
+                                        message: format!("Failed to change the log filter: {:#}", e),
+                                    });
+                                }
+                                if let Some(running) = &mut self.runtime_config {
+                                    running.log_filter = filter;
+                                }
+                            }
+                            UiToNet::CaptureTrace => {
+                                self.capture_trace();
+                            }
+                            UiToNet::SetAlertRules { rules } => {
+                                self.alerts.set_rules(rules);
+                            }
+                            UiToNet::MeasureTraining { backend } => {
+                                self.measure_training(backend);
+                            }
+                            UiToNet::TrainOnSelfPlay { backend } => {
+                                self.train_on_self_play(backend);
+                            }
+                            UiToNet::InspectDataset { dir } => {
+                                self.inspect_dataset(dir);
+                            }
+                            UiToNet::SetGovernor { config } => {
+                                self.governor.set_config(config);
+                                if let Some(running) = &mut self.runtime_config {
+                                    running.governor = config;
+                                }
+                            }
+                            UiToNet::SetChannelTuning { tuning } => {
+                                self.lobby.set_channel_tuning(tuning).await;
+                                if let Some(running) = &mut self.runtime_config {
+                                    running.channel_tuning = tuning;
+                                }
+                            }
+                            UiToNet::ApplyConfig { config } => {
+                                self.apply_config(config).await;
+                            }
+                            UiToNet::SetRelayPreset { preset } => {
+                                self.set_relay_preset(preset);
+                                if let Some(running) = &mut self.runtime_config {
+                                    running.relay_preset = preset;
+                                }
+                            }
                             UiToNet::UpdateBoardSize { board_size } => {
                                 // Update default board size in worker
                                 self.default_board_size = board_size;
@@ -284,33 +1017,74 @@ impl NetworkWorker {
                     }
                     
                     // Handle lobby events
-                    if let Ok(p2pgo_network::lobby::LobbyEvent::GameCreated(game_info)) = self.lobby_rx.try_recv() {
-                        tracing::debug!(
-                            game_id = %game_info.id,
-                            board_size = game_info.board_size,
-                            "Received GameCreated event"
-                        );
-                        
-                        // Auto-join first game if not currently in one for this board size
-                        if !self.active_games.contains_key(&game_info.board_size) {
-                            tracing::debug!(
-                                game_id = %game_info.id,
-                                board_size = game_info.board_size,
-                                "Auto-joining first available game for board size"
-                            );
-                            self.join_game(game_info.id.clone()).await?;
+                    let mut lobby_changed = false;
+                    while let Ok(event) = self.lobby_rx.try_recv() {
+                        lobby_changed = true;
+                        match event {
+                            LobbyEvent::JoinRequested { game_id, request } => {
+                                // Only games we host can have their requests answered here
+                                if self.active_games.values().any(|g| g.game_id == game_id) {
+                                    let _ = self.ui_tx.send(NetToUi::JoinRequested {
+                                        game_id,
+                                        request_id: request.request_id,
+                                        profile: request.profile,
+                                    });
+                                }
+                            }
+                            LobbyEvent::GameCreated(game_info) => {
+                                tracing::debug!(
+                                    game_id = %game_info.id,
+                                    board_size = game_info.board_size,
+                                    "Received GameCreated event"
+                                );
+                                
+                                // Auto-join first game if not currently in one for this board size
+                                if game_info.state.joinable() && !self.active_games.contains_key(&game_info.board_size) && self.joining.is_empty() {
+                                    tracing::debug!(
+                                        game_id = %game_info.id,
+                                        board_size = game_info.board_size,
+                                        "Auto-joining first available game for board size"
+                                    );
+                                    self.join_game(game_info.id.clone()).await?;
+                                }
+                            }
+                            LobbyEvent::AdvertsChanged => {
+                                // Gossiped games only show up in the list, pushed below
+                                tracing::trace!("Game adverts changed");
+                            }
+                            _ => {}
                         }
-                        
-                        // Always refresh games when a new game is created
-                        self.refresh_games().await?;
-                    } else if self.lobby_rx.try_recv().is_ok() {
-                        // Handle other lobby events as needed
+                    }
+                    
+                    // Push the first page of the game list only when it actually changed
+                    let now = std::time::Instant::now();
+                    let update = if lobby_changed {
+                        let page = self.lobby.games_page(&self.games_query, 0, GAMES_PAGE_SIZE).await;
+                        self.games_coalescer.offer(page, now)
+                    } else {
+                        self.games_coalescer.poll(now)
+                    };
+                    if let Some(page) = update {
+                        let _ = self.ui_tx.send(NetToUi::GamesUpdated { page });
                     }
                     
                     // Handle game events from all active games
+                    let now = std::time::Instant::now();
+                    let idle_config = self.idle_config;
                     let mut game_events = Vec::new();
                     for (board_size, active_game) in &mut self.active_games {
-                        if let Ok(event) = active_game.game_rx.try_recv() {
+                        if let Ok(SequencedEvent { event, .. }) = active_game.game_rx.try_recv() {
+                            let config = if active_game.correspondence {
+                                correspondence::idle_config(correspondence::MOVE_BUDGET)
+                            } else {
+                                idle_config
+                            };
+                            // Clock ticks arrive whether anyone plays or not
+                            if !matches!(event, GameEvent::Clock { .. }) {
+                                active_game.idle
+                                    .get_or_insert_with(|| IdleTracker::new(config, now))
+                                    .record_activity(now);
+                            }
                             game_events.push((*board_size, event));
                         }
                     }
@@ -319,12 +1093,37 @@ impl NetworkWorker {
                         tracing::debug!("Worker received game event for board size {}: {:?}", board_size, event);
                         self.handle_game_event(board_size, event).await?;
                     }
+                    
+                    self.check_idle_games(now).await?;
+                    self.check_pass_suggestions().await;
+                    self.poll_queue().await?;
+                    self.poll_join_answers().await?;
+                    self.poll_training_retractions();
+                    self.poll_heat_map(now).await;
+                    self.poll_ownership(now).await;
+                    self.poll_win_rate().await;
+                    self.send_channel_metrics(now);
+                    self.send_traffic_sample(now);
+                    self.poll_governor(now).await;
+                    self.save_snapshots(now, false).await;
+                    self.poll_maintenance(now).await;
+                    self.poll_trace_capture(now);
+                    self.poll_health(now);
+                    self.poll_alerts(now);
         }
         
         Ok(())
     }
 
-    async fn create_game(&mut self, board_size: u8) -> anyhow::Result<()> {
+    /// Host a new game from `position`, under `game_id` if one was agreed on already
+    async fn create_game(&mut self, position: GameState, settings: GameSettings, game_id: Option<String>) -> anyhow::Result<()> {
+        self.open_game(position, settings, game_id, SeatRole::Host, p2pgo_core::Color::Black).await
+    }
+    
+    /// Set up a game from `position` in which we sit as `role` playing
+    /// `color`; only hosted games are advertised and take join requests
+    async fn open_game(&mut self, position: GameState, settings: GameSettings, game_id: Option<String>, role: SeatRole, color: p2pgo_core::Color) -> anyhow::Result<()> {
+        let board_size = position.board_size;
         #[cfg(feature = "headless")]
         println!("Worker: Starting create_game with board_size {}", board_size);
         
@@ -336,7 +1135,8 @@ impl NetworkWorker {
             return Ok(());
         }
         
-        match self.lobby.create_game(Some(self.player_name.clone()), board_size, false).await {
+        let game_id = game_id.unwrap_or_else(Lobby::new_game_id);
+        match self.lobby.create_game_from_position(game_id, Some(self.player_name.clone()), position.clone(), settings, false).await {
             Ok(game_id) => {
                 #[cfg(feature = "headless")]
                 println!("Worker: Successfully created game with ID: {}", game_id);
@@ -346,21 +1146,50 @@ impl NetworkWorker {
                         #[cfg(feature = "headless")]
                         println!("Worker: Got game channel for game {}", game_id);
                         
-                        let game_state = GameState::new(board_size);
-                        
                         // Subscribe to game events BEFORE adding to active games
-                        let game_rx = game_channel.subscribe();
+                        let mut game_rx = game_channel.subscribe_with_history(EVENT_HISTORY);
+                        let (state, seq) = game_channel.snapshot().await;
+                        game_rx.skip_through(seq);
+                        let position = state.unwrap_or(position);
+                        let settings = game_channel.settings();
+                        game_channel.set_timing_privacy(self.timing_privacy).await;
+                        
+                        // Moves are journalled from here on, next to the periodic snapshots
+                        Self::write_journal(self.snapshots.as_ref(), &game_id, &position);
                         
                         // Create ActiveGameData and add to HashMap
                         let active_game_data = ActiveGameData {
                             game: game_channel,
                             game_id: game_id.clone(),
-                            game_state: Some(game_state),
+                            color,
+                            role,
+                            snapshot: SnapshotSchedule::new(position.moves.len(), std::time::Instant::now()),
+                            records: Vec::new(),
+                            correspondence: false,
+                            unread: None,
+                            pass_checked: None,
+                            pass_suggested: false,
+                            game_state: Some(position),
                             game_rx,
+                            idle: None,
                         };
                         
                         self.active_games.insert(board_size, active_game_data);
                         
+                        if role == SeatRole::Guest {
+                            self.send_joined(&game_id, settings).await;
+                            return Ok(());
+                        }
+                        
+                        // We host this game: opponents need our approval to join,
+                        // and no move is played before one takes the seat
+                        if let Err(e) = self.lobby.set_host(&game_id, self.profile(), self.join_policy).await {
+                            tracing::warn!("Failed to register as host of {}: {}", game_id, e);
+                        }
+                        if let Err(e) = self.lobby.await_opponent(&game_id).await {
+                            tracing::warn!("Failed to wait for an opponent in {}: {}", game_id, e);
+                        }
+                        
                         #[cfg(feature = "headless")]
                         println!("Worker: Set up game state for {}", game_id);
                         
@@ -374,7 +1203,7 @@ impl NetworkWorker {
                         
                         #[cfg(feature = "headless")]
                         println!("Worker: Sending GameJoined message for {}", game_id);
-                        let _ = self.ui_tx.send(NetToUi::GameJoined { game_id: game_id.clone() });
+                        self.send_joined(&game_id, settings).await;
                         
                         // Immediately generate and send ticket for easy sharing
                         if let Ok(ticket) = self.iroh_ctx.ticket().await {
@@ -403,66 +1232,327 @@ impl NetworkWorker {
         Ok(())
     }
 
-    async fn join_game(&mut self, game_id: String) -> anyhow::Result<()> {
-        // First, try to get the game info to determine board size
-        let games = self.lobby.list_games().await;
-        let game_info = games.iter().find(|g| g.id == game_id);
-        
-        let board_size = if let Some(info) = game_info {
-            info.board_size
-        } else {
-            // Fall back to default board size if we can't find the game info
-            self.default_board_size
-        };
-        
-        // Check if we already have a game for this board size
-        if self.active_games.contains_key(&board_size) {
+    /// Start searching for an opponent through the matchmaking queue
+    async fn join_queue(&mut self, prefs: MatchPrefs) {
+        if self.queue.is_some() {
+            tracing::debug!("Already in the matchmaking queue");
+            return;
+        }
+        if self.active_games.contains_key(&prefs.board_size) {
             let _ = self.ui_tx.send(NetToUi::Error {
-                message: format!("Already have an active game for board size {}", board_size),
+                message: format!("Already have an active game for board size {}", prefs.board_size),
             });
-            return Ok(());
+            let _ = self.ui_tx.send(NetToUi::QueueLeft);
+            return;
         }
         
-        match self.lobby.get_game_channel(&game_id).await {
-            Ok(game_channel) => {
-                let game_state = GameState::new(board_size);
-                
-                // Subscribe to game events BEFORE adding to active games
-                let game_rx = game_channel.subscribe();
-                
-                // Create ActiveGameData and add to HashMap
-                let active_game_data = ActiveGameData {
-                    game: game_channel,
-                    game_id: game_id.clone(),
-                    game_state: Some(game_state),
-                    game_rx,
-                };
-                
-                self.active_games.insert(board_size, active_game_data);
-                
-                let _ = self.ui_tx.send(NetToUi::GameJoined { game_id });
-            }
+        #[cfg(feature = "iroh")]
+        let topic = match QueueTopic::gossip(IrohCtx::clone(&self.iroh_ctx), prefs.board_size).await {
+            Ok(topic) => topic,
             Err(e) => {
                 let _ = self.ui_tx.send(NetToUi::Error {
-                    message: format!("Failed to join game: {}", e),
+                    message: format!("Failed to join the matchmaking queue: {}", e),
                 });
+                let _ = self.ui_tx.send(NetToUi::QueueLeft);
+                return;
             }
-        }
+        };
+        // Without iroh there is nobody else to meet
+        #[cfg(not(feature = "iroh"))]
+        let topic = QueueTopic::local();
         
-        Ok(())
+        let entry = QueueEntry {
+            peer: self.iroh_ctx.node_id().to_string(),
+            prefs,
+            rating: self.ratings.as_ref().map(RatingTracker::current).unwrap_or_default().estimate().rating,
+        };
+        tracing::info!(board_size = prefs.board_size, rating = entry.rating, "Joining the matchmaking queue");
+        let (cancel, cancel_rx) = tokio::sync::oneshot::channel();
+        let task = tokio::spawn(async move { find_match(MatchQueue::new(entry), &topic, cancel_rx).await });
+        self.queue = Some(QueueSearch { cancel, task });
     }
-
-    async fn make_move(&mut self, mv: p2pgo_core::Move, board_size: Option<u8>) -> anyhow::Result<()> {
-        // Use provided board size or fall back to default
-        let board_size = board_size.unwrap_or(self.default_board_size);
-        
-        if let Some(active_game) = self.active_games.get(&board_size) {
-            // Store move for training (get sequence before making the move)
-            let sequence = if let Some(game_state) = &active_game.game_state {
-                game_state.moves.len() as u32
-            } else {
-                0
-            };
+    
+    /// Stop searching; the search task announces that we left
+    fn leave_queue(&mut self) {
+        if let Some(search) = self.queue.take() {
+            let _ = search.cancel.send(());
+        }
+        let _ = self.ui_tx.send(NetToUi::QueueLeft);
+    }
+    
+    /// Start the game once the matchmaking search has finished
+    async fn poll_queue(&mut self) -> anyhow::Result<()> {
+        if !matches!(&self.queue, Some(search) if search.task.is_finished()) {
+            return Ok(());
+        }
+        let Some(search) = self.queue.take() else {
+            return Ok(());
+        };
+        let found = match search.task.await {
+            Ok(Ok(Some(found))) => found,
+            Ok(Ok(None)) => {
+                let _ = self.ui_tx.send(NetToUi::QueueLeft);
+                return Ok(());
+            }
+            Ok(Err(e)) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Matchmaking failed: {}", e) });
+                let _ = self.ui_tx.send(NetToUi::QueueLeft);
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Matchmaking task failed: {}", e) });
+                let _ = self.ui_tx.send(NetToUi::QueueLeft);
+                return Ok(());
+            }
+        };
+        
+        let board_size = found.opponent.prefs.board_size;
+        if found.host {
+            self.create_game(GameState::new(board_size), GameSettings::standard(board_size), Some(found.game_id.clone())).await?;
+            // Both players queued for the same clock and disconnect policy
+            let prefs = found.opponent.prefs;
+            if let Err(e) = self.lobby.set_time_control(&found.game_id, Some(prefs.time_control), prefs.on_disconnect, true).await {
+                tracing::warn!("Failed to set the clock of {}: {}", found.game_id, e);
+            }
+            let _ = self.advertise_game(&found.game_id, board_size).await;
+            // The matched opponent takes the seat without asking us again
+            if let Err(e) = self.lobby.set_join_policy(&found.game_id, JoinPolicy::Everyone).await {
+                tracing::warn!("Failed to open matched game {}: {}", found.game_id, e);
+            }
+        } else {
+            // The host creates the game after our Accept; its advert may take a while
+            self.request_join(found.game_id.clone(), Some(ADVERT_WAIT_TIMEOUT));
+        }
+        let _ = self.ui_tx.send(NetToUi::MatchFound {
+            game_id: found.game_id,
+            opponent: found.opponent.peer,
+        });
+        Ok(())
+    }
+    
+    /// Connect by an invite's ticket and join the game it names, once the
+    /// host has advertised it
+    async fn join_invite(&mut self, invite: Invite) -> anyhow::Result<()> {
+        if let Err(e) = self.connect_with_retry(&invite.ticket).await {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: connect_error_message(&e)
+                    .unwrap_or_else(|| format!("Failed to connect to {}: {}", invite.host_name, e)),
+            });
+            return Ok(());
+        }
+        self.peer_ticket = Some(invite.ticket);
+        self.refresh_games().await?;
+        
+        match self.lobby.wait_for_game_id(&invite.game_id, ADVERT_WAIT_TIMEOUT).await {
+            Some(game) => {
+                self.refresh_games().await?;
+                tracing::info!("Joining invited game {}", game.id);
+                self.join_game(game.id).await
+            }
+            None => {
+                let _ = self.ui_tx.send(NetToUi::Error {
+                    message: format!("{}'s game was not advertised; it may have ended", invite.host_name)
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Connect by `ticket`, trying again while the failure may pass
+    async fn connect_with_retry(&self, ticket: &str) -> Result<(), p2pgo_network::Error> {
+        let mut delay = CONNECT_RETRY_DELAY;
+        for _ in 1..CONNECT_ATTEMPTS {
+            match self.iroh_ctx.connect_by_ticket(ticket).await {
+                Err(e) if Recovery::for_error(&e) == Recovery::Retry => {
+                    tracing::info!("Connecting failed, retrying in {:?}: {}", delay, e);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+        self.iroh_ctx.connect_by_ticket(ticket).await
+    }
+
+    /// Tell the UI we are in `game_id` and where the game stands, which
+    /// decides whether it shows the board or waits for the opponent
+    async fn send_joined(&self, game_id: &str, settings: GameSettings) {
+        let _ = self.ui_tx.send(NetToUi::GameJoined { game_id: game_id.to_string(), settings });
+        if let Ok(channel) = self.lobby.get_game_channel(&game_id.to_string()).await {
+            let phase = channel.phase().await;
+            let _ = self.ui_tx.send(NetToUi::GameEvent { event: GameEvent::PhaseChanged { phase } });
+        }
+    }
+    
+    /// Ask the host of `game_id` for the opponent seat; the answer comes
+    /// back to `poll_join_answers`
+    async fn join_game(&mut self, game_id: String) -> anyhow::Result<()> {
+        // The listing tells the board size, if the game is listed yet
+        let board_size = self.lobby.list_games().await.iter()
+            .find(|g| g.id == game_id)
+            .map_or(self.default_board_size, |info| info.board_size);
+        
+        // Check if we already have a game for this board size
+        if self.active_games.contains_key(&board_size) {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Already have an active game for board size {}", board_size),
+            });
+            return Ok(());
+        }
+        
+        self.request_join(game_id, None);
+        Ok(())
+    }
+    
+    /// Send our join request from a task of its own, once `game_id` is
+    /// listed or `advert_wait` is up, since the host may take its time
+    fn request_join(&mut self, game_id: String, advert_wait: Option<std::time::Duration>) {
+        if !self.joining.insert(game_id.clone()) {
+            return;
+        }
+        let (lobby, profile, answers) = (self.lobby.clone(), self.profile(), self.join_tx.clone());
+        tokio::spawn(async move {
+            if let Some(timeout) = advert_wait {
+                lobby.wait_for_game_id(&game_id, timeout).await;
+            }
+            let response = lobby.request_join(&game_id, profile).await;
+            let _ = answers.send(JoinAnswer { game_id, response });
+        });
+    }
+    
+    /// Take the seats our join requests were answered with
+    async fn poll_join_answers(&mut self) -> anyhow::Result<()> {
+        while let Ok(answer) = self.join_rx.try_recv() {
+            self.take_seat(answer).await?;
+        }
+        Ok(())
+    }
+    
+    async fn take_seat(&mut self, answer: JoinAnswer) -> anyhow::Result<()> {
+        let JoinAnswer { game_id, response } = answer;
+        self.joining.remove(&game_id);
+        // The host decides whether we get the opponent seat
+        let (color, settings) = match response {
+            Ok(JoinResponse::Accepted { color, settings }) => {
+                tracing::info!("Joined {} as {:?}, playing {}", game_id, color, settings);
+                (color, settings)
+            }
+            Ok(JoinResponse::Declined { reason }) => {
+                let _ = self.ui_tx.send(NetToUi::JoinDeclined { game_id, reason });
+                return Ok(());
+            }
+            Ok(JoinResponse::SpectateOffered { reason }) => {
+                let _ = self.ui_tx.send(NetToUi::SpectateOffered { game_id, reason });
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::GameError {
+                    game_id: game_id.clone(),
+                    error: GameErrorKind::JoinFailed { reason: e.to_string() },
+                });
+                return Ok(());
+            }
+        };
+        
+        let games = self.lobby.list_games().await;
+        let game_info = games.iter().find(|g| g.id == game_id);
+        // The host chose whether the game is played by correspondence
+        let correspondence = game_info.is_some_and(|info| info.correspondence);
+        // Teaching games name the peer allowed to annotate
+        let teacher = game_info.and_then(|info| info.teacher.clone());
+        // The clock and what it does while a player is away, as listed
+        let terms = game_info.map(|info| info.terms);
+        
+        // The host's board counts, whatever size the listing suggested
+        let board_size = settings.board_size;
+        if self.active_games.contains_key(&board_size) {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Already have an active game for board size {}", board_size),
+            });
+            return Ok(());
+        }
+        
+        match self.lobby.get_game_channel(&game_id).await {
+            Ok(game_channel) => {
+                // Events from the state we start from on, so a burst of moves
+                // while the view opens is neither missed nor applied twice
+                let mut game_rx = game_channel.subscribe_with_history(EVENT_HISTORY);
+                let (state, seq) = game_channel.snapshot().await;
+                game_rx.skip_through(seq);
+                // The game may start from a set-up position
+                let game_state = state.unwrap_or_else(|| settings.new_game());
+                game_channel.set_timing_privacy(self.timing_privacy).await;
+                game_channel.set_correspondence(correspondence);
+                game_channel.set_teacher(teacher).await;
+                if let Some(terms) = terms {
+                    if let Some(time_control) = terms.time_control {
+                        // A channel the host already runs keeps its clocks
+                        if game_channel.clock_snapshot().await.is_none() {
+                            game_channel.set_time_control(time_control.game_clock(), terms.on_disconnect).await;
+                        }
+                    }
+                }
+                
+                // Moves are journalled from here on, next to the periodic snapshots
+                Self::write_journal(self.snapshots.as_ref(), &game_id, &game_state);
+                
+                // Create ActiveGameData and add to HashMap
+                let active_game_data = ActiveGameData {
+                    game: game_channel,
+                    game_id: game_id.clone(),
+                    color,
+                    role: SeatRole::Guest,
+                    snapshot: SnapshotSchedule::new(game_state.moves.len(), std::time::Instant::now()),
+                    records: Vec::new(),
+                    correspondence,
+                    unread: None,
+                    pass_checked: None,
+                    pass_suggested: false,
+                    game_state: Some(game_state),
+                    game_rx,
+                    idle: None,
+                };
+                
+                self.active_games.insert(board_size, active_game_data);
+                
+                self.send_joined(&game_id, settings).await;
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::GameError {
+                    game_id: game_id.clone(),
+                    error: GameErrorKind::JoinFailed { reason: e.to_string() },
+                });
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn make_move(&mut self, mv: p2pgo_core::Move, board_size: Option<u8>) -> anyhow::Result<()> {
+        // Use provided board size or fall back to default
+        let board_size = board_size.unwrap_or(self.default_board_size);
+        
+        // Answering a correspondence game means its new moves were read
+        if let Some(active_game) = self.active_games.get_mut(&board_size) {
+            active_game.unread = None;
+        }
+        
+        if let Some(active_game) = self.active_games.get(&board_size) {
+            // Rule violations are reported with the point, so the board can show them
+            if let Err(e) = active_game.game.check_move(&mv).await {
+                let _ = self.ui_tx.send(NetToUi::GameError {
+                    game_id: active_game.game_id.clone(),
+                    error: GameErrorKind::from_rules(&e, &mv),
+                });
+                return Ok(());
+            }
+            
+            // Store move for training (get sequence before making the move)
+            let sequence = if let Some(game_state) = &active_game.game_state {
+                game_state.moves.len() as u32
+            } else {
+                0
+            };
             
             // Create a move record for storage
             let move_record = p2pgo_core::MoveRecord {
@@ -476,14 +1566,17 @@ impl NetworkWorker {
                 prev_hash: None,
             };
             
-            if let Err(e) = self.iroh_ctx.store_game_move(&active_game.game_id, sequence, &move_record).await {
-                tracing::warn!("Failed to store training move: {}", e);
+            if self.config.share_training_data {
+                if let Err(e) = self.iroh_ctx.store_game_move(&active_game.game_id, sequence, &move_record).await {
+                    tracing::warn!("Failed to store training move: {}", e);
+                }
             }
             
             // Send move to network - the channel will apply it and broadcast the event
             if let Err(e) = active_game.game.send_move(mv.clone()).await {
-                let _ = self.ui_tx.send(NetToUi::Error {
-                    message: format!("Failed to send move: {}", e),
+                let _ = self.ui_tx.send(NetToUi::GameError {
+                    game_id: active_game.game_id.clone(),
+                    error: GameErrorKind::from_network(&e, &mv),
                 });
             }
             // Note: GameEvent will be received through the game channel subscription
@@ -496,29 +1589,164 @@ impl NetworkWorker {
         Ok(())
     }
 
-    async fn refresh_games(&mut self) -> anyhow::Result<Vec<p2pgo_network::lobby::GameInfo>> {
-        // Re-subscribe to gossip with current board size to capture any potential board size changes
-        #[cfg(feature = "iroh")]
-        {
-            // Subscribe to the lobby topic for the default board size
-            let _ = self.subscribe_to_gossip_lobby().await;
+    /// Queue a move for the player who is waiting on the opponent
+    async fn queue_premove(&mut self, mv: p2pgo_core::Move, board_size: Option<u8>) -> anyhow::Result<()> {
+        let board_size = board_size.unwrap_or(self.default_board_size);
+        let Some(active_game) = self.active_games.get(&board_size) else {
+            return Ok(());
+        };
+        active_game.game.queue_premove(mv, active_game.color).await;
+        // The opponent's move may have landed while the premove was on its way
+        self.play_premove(board_size).await;
+        Ok(())
+    }
+    
+    /// Send the queued premove once the opponent has moved, if still legal
+    async fn play_premove(&mut self, board_size: u8) {
+        let Some(active_game) = self.active_games.get(&board_size) else {
+            return;
+        };
+        match active_game.game.play_premove().await {
+            Ok(Some(PremoveOutcome::Discarded { mv, error })) => {
+                let _ = self.ui_tx.send(NetToUi::PremoveDiscarded {
+                    mv,
+                    reason: error.to_string(),
+                });
+            }
+            Ok(Some(PremoveOutcome::Played(mv))) => {
+                tracing::debug!("Premove {:?} played", mv);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error {
+                    message: format!("Failed to send premove: {}", e),
+                });
+            }
         }
+    }
+    
+    /// Forced refresh: re-query advertisements and always send the first page
+    async fn refresh_games(&mut self) -> anyhow::Result<GamesPage> {
+        // Re-subscribe to gossip with current board size to capture any potential board size changes
+        let _ = self.subscribe_to_gossip_lobby().await;
         
         // Fetch available games
-        let games = self.lobby.list_games().await;
+        let page = self.lobby.games_page(&self.games_query, 0, GAMES_PAGE_SIZE).await;
+        let page = self.games_coalescer.force(page, std::time::Instant::now());
         
         // Send to UI
-        let _ = self.ui_tx.send(NetToUi::GamesUpdated { games: games.clone() });
+        let _ = self.ui_tx.send(NetToUi::GamesUpdated { page: page.clone() });
         
-        Ok(games)
+        Ok(page)
+    }
+    
+    /// A page the UI scrolled to, or the first under a new filter
+    async fn send_games_page(&mut self, query: GamesQuery, offset: usize) {
+        self.games_query = query;
+        let page = self.lobby.games_page(&query, offset, GAMES_PAGE_SIZE).await;
+        // Only the first page is what pushed updates are compared with
+        let page = if offset == 0 {
+            self.games_coalescer.force(page, std::time::Instant::now())
+        } else {
+            page
+        };
+        let _ = self.ui_tx.send(NetToUi::GamesUpdated { page });
     }
 
+    /// How we appear to hosts and joiners
+    fn profile(&self) -> PlayerProfile {
+        PlayerProfile {
+            node_id: self.iroh_ctx.node_id().to_string(),
+            name: self.player_name.clone(),
+            guild: None,
+            self_reported_rating: self.ratings.as_ref().map(|r| r.current().estimate()),
+        }
+    }
+    
+    /// Reply to `GetGameState` with the channel's latest state
+    async fn send_game_state(&mut self, game_id: String) {
+        let Some(active_game) = self.active_games.values().find(|g| g.game_id == game_id) else {
+            tracing::debug!("No active game {} for state request", game_id);
+            return;
+        };
+        
+        let game_state = match active_game.game.get_latest_state().await {
+            Some(state) => Some(state),
+            None => active_game.game_state.clone(),
+        };
+        
+        if let Some(state) = game_state {
+            let _ = self.ui_tx.send(NetToUi::GameStateSnapshot { game_id, state });
+        }
+    }
+    
+    /// Ask about, or abandon, games that have been quiet for too long
+    async fn check_idle_games(&mut self, now: std::time::Instant) -> anyhow::Result<()> {
+        let mut abandoned = Vec::new();
+        for active_game in self.active_games.values_mut() {
+            let Some(idle) = active_game.idle.as_mut() else {
+                continue;
+            };
+            // Only the player to move can be idle; while it is our turn the
+            // opponent's quiet period hasn't started
+            let our_turn = active_game.game_state.as_ref()
+                .is_some_and(|state| state.current_player == active_game.color);
+            if our_turn {
+                idle.record_activity(now);
+                continue;
+            }
+            match idle.check(now) {
+                IdleStatus::Active => {}
+                IdleStatus::ConfirmAbandon { idle_for } => {
+                    let _ = self.ui_tx.send(NetToUi::GameIdle {
+                        game_id: active_game.game_id.clone(),
+                        idle_for,
+                    });
+                }
+                IdleStatus::Abandoned => abandoned.push(active_game.game_id.clone()),
+            }
+        }
+        
+        for game_id in abandoned {
+            self.abandon_game(&game_id).await?;
+        }
+        Ok(())
+    }
+    
+    /// Forfeit a game whose opponent vanished and free its resources
+    async fn abandon_game(&mut self, game_id: &str) -> anyhow::Result<()> {
+        let Some(board_size) = self.active_games.iter()
+            .find(|(_, g)| g.game_id == game_id)
+            .map(|(board_size, _)| *board_size)
+        else {
+            return Ok(());
+        };
+        // Dropping our handle and receiver lets the channel shut down
+        self.active_games.remove(&board_size);
+        self.score_trackers.remove(&board_size);
+        self.remove_snapshot(game_id);
+        
+        let game_id = game_id.to_string();
+        match self.lobby.abandon_game(&game_id, IDLE_FORFEIT_REASON, self.archive.as_ref()).await {
+            Ok(event) => {
+                let _ = self.ui_tx.send(NetToUi::GameEvent { event });
+            }
+            Err(e) => {
+                tracing::warn!("Failed to abandon game {}: {}", game_id, e);
+            }
+        }
+        let _ = self.ui_tx.send(NetToUi::GameLeft);
+        
+        Ok(())
+    }
+    
     async fn leave_game(&mut self) -> anyhow::Result<()> {
         // Leave the game for the default board size
         // TODO: In the future, we might want to pass board_size as a parameter
         let board_size = self.default_board_size;
         
-        if self.active_games.remove(&board_size).is_some() {
+        if let Some(active_game) = self.active_games.remove(&board_size) {
+            self.remove_snapshot(&active_game.game_id);
             let _ = self.ui_tx.send(NetToUi::GameLeft);
         } else {
             let _ = self.ui_tx.send(NetToUi::Error {
@@ -529,22 +1757,51 @@ impl NetworkWorker {
         Ok(())
     }
 
-    async fn handle_game_event(&mut self, board_size: u8, event: GameEvent) -> anyhow::Result<()> {
+    async fn handle_game_event(&mut self, board_size: u8, mut event: GameEvent) -> anyhow::Result<()> {
         tracing::debug!("Worker handling game event for board size {}: {:?}", board_size, event);
         
+        if matches!(event, GameEvent::MoveMade { .. }) {
+            self.play_premove(board_size).await;
+        }
+        
         // Apply event to local game state if applicable
+        let mut final_score = None;
         if let Some(active_game) = self.active_games.get_mut(&board_size) {
             if let (Some(game_state), GameEvent::MoveMade { mv, .. }) = (&mut active_game.game_state, &event) {
-                let _ = game_state.apply_move(mv.clone());
+                if game_state.apply_move(mv.clone()).is_ok() {
+                    if let Some(store) = self.snapshots.clone() {
+                        // The journal syncs to disk, which mustn't stall the runtime
+                        let (game_id, mv) = (active_game.game_id.clone(), mv.clone());
+                        match tokio::task::spawn_blocking(move || store.append_move(&game_id, &mv)).await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => tracing::warn!("Failed to journal a move of {}: {:#}", active_game.game_id, e),
+                            Err(e) => tracing::warn!("Journalling a move of {} panicked: {}", active_game.game_id, e),
+                        }
+                    }
+                }
+                active_game.records.push(p2pgo_core::MoveRecord {
+                    mv: mv.clone(),
+                    tag: None,
+                    // When the move reached us, which the post-game report
+                    // times moves by; a peer's own timestamp may be coarse
+                    ts: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    broadcast_hash: None,
+                    prev_hash: None,
+                });
+                if let Some(unread) = &mut active_game.unread {
+                    *unread += 1;
+                    let _ = self.ui_tx.send(NetToUi::UnreadMoves {
+                        game_id: active_game.game_id.clone(),
+                        count: *unread,
+                    });
+                }
                 
                 // Check if the game is finished (2 passes or resign)
                 if game_state.is_game_over() {
-                    // Get komi based on board size
-                    let komi = match game_state.board_size {
-                        19 => 7.5,
-                        13 => 6.5,
-                        _ => 5.5, // 9x9 or other sizes
-                    };
+                    let komi = active_game.game.settings().scoring_komi();
                     
                     // Determine scoring method based on how the game ended
                     let scoring_method = match mv {
@@ -563,39 +1820,137 @@ impl NetworkWorker {
                         &std::collections::HashSet::new() // No dead stones initially
                     );
                     
-                    // Send score dialog event
-                    let black_score = score_proof.territory_black as f32;
-                    let white_score = score_proof.territory_white as f32 + komi;
-                    
-                    let _ = self.ui_tx.send(NetToUi::GameEvent { 
-                        event: GameEvent::GameFinished { 
-                            black_score,
-                            white_score,
-                        }
-                    });
-                    
-                    // Also send the more detailed score proof
-                    let _ = self.ui_tx.send(NetToUi::ScoreCalculated { 
-                        score_proof: score_proof.clone()
-                    });
-                    
-                    // Start score acceptance timeout
-                    self.start_score_timeout(board_size, score_proof).await;
+                    final_score = Some(score_proof);
+                }
+            }
+        }
+        
+        // Moves taken back by agreement leave our copy and the record too
+        if let GameEvent::MovesUndone { moves } = &event {
+            if let Some(active_game) = self.active_games.get_mut(&board_size) {
+                if let Some(game_state) = &mut active_game.game_state {
+                    if let Err(e) = game_state.undo(*moves as usize) {
+                        tracing::warn!("Failed to take back {} moves of {}: {}", moves, active_game.game_id, e);
+                    }
+                    Self::write_journal(self.snapshots.as_ref(), &active_game.game_id, game_state);
+                }
+                let kept = active_game.records.len().saturating_sub(*moves as usize);
+                active_game.records.truncate(kept);
+            }
+        }
+        
+        // A followed game's new position joins its win-rate graph
+        if matches!(event, GameEvent::MoveMade { .. } | GameEvent::MovesUndone { .. }) {
+            if let Some(active_game) = self.active_games.get(&board_size) {
+                if let Some(game_state) = &active_game.game_state {
+                    self.win_rate.moved(&active_game.game_id, game_state);
                 }
             }
         }
         
-        // Special handling for game finished events
-        if let GameEvent::GameFinished { black_score, white_score } = &event {
-            tracing::info!("Game finished for board size {}. Black: {}, White: {}", board_size, black_score, white_score);
+        // A double-pass game is announced unscored; add the provisional score
+        if matches!(event, GameEvent::GameEnded { reason: EndReason::DoublePass, scores: None, .. }) {
+            if let Some((game_state, settings)) = self.active_games.get(&board_size).and_then(|g| Some((g.game_state.as_ref()?, g.game.settings()))) {
+                let score_proof = p2pgo_core::scoring::calculate_final_score(
+                    game_state,
+                    settings.scoring_komi(),
+                    p2pgo_core::value_labeller::ScoringMethod::Territory,
+                    &std::collections::HashSet::new(),
+                );
+                event = p2pgo_core::scoring::game_ended_event(&score_proof);
+            }
+        }
+        // Both players accepted the score, here or on the peer's side
+        if let GameEvent::PhaseChanged { phase: GamePhase::Finished { result } } = &event {
+            if result.reason == EndReason::DoublePass {
+                self.finish_scored_game(board_size).await;
+            }
+            // Whoever ended it, ours or the peer's doing
+            if !matches!(result.reason, EndReason::Forfeit(_)) {
+                self.rate_game(board_size, result.winner).await;
+            }
+        }
+        if let GameEvent::GameEnded { winner, reason, scores, .. } = &event {
+            tracing::info!("Game ended for board size {}: {} (winner {:?}, scores {:?})", board_size, reason, winner, scores);
+            if let Some(game_id) = self.active_games.get(&board_size).map(|g| g.game_id.clone()) {
+                self.remove_snapshot(&game_id);
+            }
         }
         
         let _ = self.ui_tx.send(NetToUi::GameEvent { event });
+        
+        // The score dialog opens on the board with the final move on it
+        if let Some(score_proof) = final_score {
+            let _ = self.ui_tx.send(NetToUi::ScoreCalculated { score_proof: score_proof.clone() });
+            // Start score acceptance timeout
+            self.start_score_timeout(board_size, score_proof).await;
+        }
         Ok(())
     }
 
+    /// Broadcast a practice game from `state` on: spectators may watch, no
+    /// one else may play
+    async fn share_board(&mut self, state: GameState) {
+        if self.shared_board.is_some() {
+            return;
+        }
+        let board_size = state.board_size;
+        let game_id = match self.lobby.create_broadcast(Lobby::new_game_id(), Some(self.player_name.clone()), state).await {
+            Ok(game_id) => game_id,
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to share the board: {}", e) });
+                return;
+            }
+        };
+        let channel = match self.lobby.get_game_channel(&game_id).await {
+            Ok(channel) => channel,
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to share the board: {}", e) });
+                return;
+            }
+        };
+        self.shared_board = Some((game_id.clone(), channel));
+        if let Err(e) = self.lobby.set_host(&game_id, self.profile(), self.join_policy).await {
+            tracing::warn!("Failed to register as host of {}: {}", game_id, e);
+        }
+        let _ = self.advertise_game(&game_id, board_size).await;
+        match self.iroh_ctx.ticket().await {
+            Ok(ticket) => {
+                let link = Invite::new(ticket, game_id.clone(), board_size, self.player_name.clone()).to_link();
+                let _ = self.ui_tx.send(NetToUi::BoardShared { game_id, link });
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to generate ticket for sharing: {}", e) });
+            }
+        }
+    }
+
+    /// Close the shared board's broadcast, archiving the game if `archive`
+    async fn stop_sharing(&mut self, archive: bool) {
+        let Some((game_id, channel)) = self.shared_board.take() else {
+            return;
+        };
+        if let (true, Some(manager)) = (archive, &self.archive) {
+            if let Some(state) = channel.get_latest_state().await {
+                let (anomalies, clock_skews) = (channel.anomalies().await, channel.clock_skews().await);
+                if let Err(e) = manager.archive_game(game_id.clone(), state, None, None, anomalies, clock_skews).await {
+                    tracing::warn!("Failed to archive shared board {}: {}", game_id, e);
+                }
+            }
+        }
+        if let Err(e) = self.lobby.remove_game(&game_id).await {
+            tracing::warn!("Failed to close shared board {}: {}", game_id, e);
+        }
+    }
+
+    /// Advertise one of our games with the terms the lobby has for it
     async fn advertise_game(&mut self, game_id: &str, board_size: u8) -> anyhow::Result<()> {
-        if let Err(e) = self.iroh_ctx.advertise_game(game_id, board_size).await {
+        let info = self.lobby.list_games().await.into_iter().find(|g| g.id == game_id);
+        let (correspondence, terms) = match info {
+            Some(info) => (info.correspondence, info.terms),
+            None => (false, GameTerms::standard(board_size)),
+        };
+        if let Err(e) = self.iroh_ctx.advertise_game_with(game_id, board_size, correspondence, terms).await {
             tracing::warn!("Failed to advertise game: {}", e);
             let _ = self.ui_tx.send(NetToUi::Error {
                 message: format!("Failed to advertise game: {}", e),
@@ -603,225 +1958,803 @@ impl NetworkWorker {
         }
         Ok(())
     }
-
-    async fn subscribe_to_gossip_lobby(&mut self) -> anyhow::Result<()> {
-        #[cfg(feature = "iroh")]
-        {
-            // Subscribe to the lobby topic for the default board size using iroh
-            match self.iroh_ctx.subscribe_lobby(self.default_board_size).await {
-                Ok(mut event_rx) => {
-                    // Spawn a background task to process incoming gossip events
-                    let _ui_tx = self.ui_tx.clone();
-                    // Don't clone lobby, use a weak reference or remove this functionality for now
-                    // TODO: Fix gossip event handling for iroh v0.35
-                    
-                    tokio::spawn(async move {
-                        while let Some(_event) = event_rx.recv().await {
-                            tracing::debug!("Gossip event processing disabled - needs iroh v0.35 update");
-                            // TODO: Re-implement gossip event processing
-                        }
-                    });
-                    
-                    tracing::info!("Subscribed to gossip lobby for board size {}", self.default_board_size);
-                },
+
+    async fn subscribe_to_gossip_lobby(&mut self) -> anyhow::Result<()> {
+        // Feed adverts gossiped on the lobby topic for the default board size
+        // into the lobby, subscribing again whenever the forwarder dies or wedges
+        let iroh_ctx = self.iroh_ctx.clone();
+        let lobby = self.lobby.clone();
+        let board_size = self.default_board_size;
+        self.health.register(LOBBY_GOSSIP, SUBSYSTEM_DEADLINE, move |heartbeat| {
+            let iroh_ctx = iroh_ctx.clone();
+            let lobby = lobby.clone();
+            async move {
+                let mut adverts = match iroh_ctx.lobby_adverts(board_size).await {
+                    Ok(adverts) => adverts,
+                    Err(e) => {
+                        tracing::warn!("Failed to subscribe to gossip lobby: {}", e);
+                        return;
+                    }
+                };
+                tracing::info!("Subscribed to gossip lobby for board size {}", board_size);
+                while let Some((from, bytes)) = heartbeat.waiting(adverts.recv()).await {
+                    lobby.ingest_gossiped(&from, &bytes).await;
+                }
+                tracing::warn!("Gossip lobby stream ended");
+            }
+        });
+        Ok(())
+    }
+    
+    async fn handle_set_tag(&mut self, gid: String, seq: u32, tag: p2pgo_core::Tag) -> anyhow::Result<()> {
+        // Store the tag annotation for the specified move
+        if let Err(e) = self.iroh_ctx.store_move_tag(&gid, seq, tag).await {
+            tracing::warn!("Failed to store move tag: {}", e);
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Failed to store tag: {}", e),
+            });
+        } else {
+            let _ = self.ui_tx.send(NetToUi::TagAck);
+        }
+        Ok(())
+    }
+
+    async fn handle_get_ghost_moves(&mut self) -> anyhow::Result<()> {
+        // Check if the player has completed enough games to see ghost moves
+        let threshold = self.runtime_config.as_ref()
+            .map_or_else(|| RuntimeConfig::default().ghost_moves_after_games, |config| config.ghost_moves_after_games);
+        if self.config.games_finished < threshold {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Ghost moves will be available after completing {} games (currently: {})", 
+                                threshold, self.config.games_finished),
+            });
+            return Ok(());
+        }
+        
+        // Hints are judged on the channel's state, whose moves give the ko
+        let game_state = if let Some(active_game) = self.active_games.get(&self.default_board_size) {
+            match active_game.game.get_latest_state().await.or_else(|| active_game.game_state.clone()) {
+                Some(state) => state,
+                None => {
+                    let _ = self.ui_tx.send(NetToUi::Error {
+                        message: "No game state available for ghost moves".to_string(),
+                    });
+                    return Ok(());
+                }
+            }
+        } else {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("No active game for board size {} for ghost moves", self.default_board_size),
+            });
+            return Ok(());
+        };
+
+        let Some(model) = self.ai_model().await else {
+            return Ok(());
+        };
+
+        // Get ghost moves from the model
+        match self.compute_ghost_moves(&model, &game_state).await {
+            Ok(ghost_moves) => {
+                let _ = self.ui_tx.send(NetToUi::GhostMoves(ghost_moves));
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error {
+                    message: format!("Failed to compute ghost moves: {}", e),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Suggested moves for a position from the board editor
+    async fn handle_analyze_position(&mut self, position: GameState) {
+        let Some(model) = self.ai_model().await else {
+            return;
+        };
+        match self.compute_ghost_moves(&model, &position).await {
+            Ok(ghost_moves) => {
+                let _ = self.ui_tx.send(NetToUi::GhostMoves(ghost_moves));
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error {
+                    message: format!("Failed to analyze position: {}", e),
+                });
+            }
+        }
+    }
+
+    /// Think times and evaluation swings for `state`, scored by the value head
+    async fn send_game_report(&mut self, game_id: String, mut state: GameState) {
+        let Some(model) = self.ai_model().await else {
+            return;
+        };
+        // Only games still open have timestamps; archived ones get
+        // evaluations, and think times where their clocks were recorded
+        let active = self.active_games.values().find(|g| g.game_id == game_id);
+        let records = active.map(|g| g.records.clone()).unwrap_or_default();
+        if let (Some(game), true) = (active, state.timing.is_empty()) {
+            state.timing = game.game.timing().await;
+        }
+        let report = p2pgo_core::analysis::game_report(&state, &records, |position| {
+            Self::value_estimate(&model, position).unwrap_or_else(|e| {
+                tracing::warn!("Failed to evaluate position: {}", e);
+                0.0
+            })
+        });
+        let _ = self.ui_tx.send(NetToUi::GameReport { game_id, report });
+    }
+
+    /// The ladder opponent's move in `state`.
+    ///
+    /// Without a model the policy has no opinion, so policy rungs play
+    /// at random and search rungs rely on their playouts alone.
+    async fn send_ladder_move(&mut self, rung: usize, seed: u64, state: GameState) {
+        let Some(rung) = p2pgo_core::ladder::RUNGS.get(rung) else {
+            tracing::warn!("No ladder rung {}", rung);
+            return;
+        };
+        let model = if rung.strength.needs_policy() { self.ai_model().await } else { None };
+        let policy = |position: &GameState| {
+            let flat = || vec![0.0; p2pgo_core::policy::policy_len(position.board_size)];
+            match &model {
+                Some(model) => Self::policy_logits(model, position).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to evaluate position: {}", e);
+                    flat()
+                }),
+                None => flat(),
+            }
+        };
+        let mv = p2pgo_core::ladder::opponent(rung, seed, policy).next_move(&state, std::time::Duration::MAX);
+        let _ = self.ui_tx.send(NetToUi::LadderMove { mv });
+    }
+
+    /// Import the SGF files in `dir` on a thread of its own, so a big
+    /// folder doesn't hold up the network; progress goes to the UI per file
+    fn import_sgf(&self, dir: std::path::PathBuf, options: archiver::ImportOptions) {
+        let ui_tx = self.ui_tx.clone();
+        thread::spawn(move || {
+            let result = archiver::import_sgf_dir_with(&dir, &options, |progress| {
+                let _ = ui_tx.send(NetToUi::ImportProgress { progress: progress.clone() });
+            });
+            let _ = ui_tx.send(NetToUi::ImportFinished { result: result.map_err(|e| format!("{:#}", e)) });
+        });
+    }
+
+    /// The AI model, loaded on first use; load errors are reported to the UI
+    async fn ai_model(&mut self) -> Option<Rc<Mutex<GoMini6E<Wgpu>>>> {
+        if self.ai_model.is_none() {
+            match self.load_ai_model().await {
+                Ok(model) => {
+                    self.ai_model = Some(Rc::new(Mutex::new(model)));
+                    // Win rates of an earlier model are evaluated again
+                    self.win_rate.model_changed();
+                }
+                Err(e) => {
+                    let _ = self.ui_tx.send(NetToUi::Error {
+                        message: format!("Failed to load AI model: {}", e),
+                    });
+                }
+            }
+        }
+        self.ai_model.clone()
+    }
+
+    async fn load_ai_model(&self) -> anyhow::Result<GoMini6E<Wgpu>> {
+        // Create a new device
+        let device = <Wgpu as Backend>::Device::default();
+        
+        // The published weights, or fresh ones before any training
+        let model = match CheckpointStore::open_default(CHECKPOINTS_KEPT)?.published()? {
+            Some(checkpoint) => GoMini6E::from_checkpoint(&checkpoint, &device)?,
+            None => GoMini6E::new(&device, 9),
+        };
+        
+        tracing::info!("AI model loaded successfully");
+        Ok(model)
+    }
+
+    /// Check and tidy the archive and snapshots, at most every `MAINTENANCE_INTERVAL`
+    async fn poll_maintenance(&mut self, now: std::time::Instant) {
+        if matches!(self.maintained_at, Some(at) if now.duration_since(at) < MAINTENANCE_INTERVAL) {
+            return;
+        }
+        self.maintained_at = Some(now);
+        let archive_dir = self.archive.as_ref().map(|archive| archive.dir().to_path_buf());
+        let snapshots = self.snapshots.clone();
+        let report = tokio::task::spawn_blocking(move || run_maintenance(archive_dir.as_deref(), snapshots.as_ref())).await;
+        match report {
+            Ok(Ok(report)) if report.repaired_anything() => {
+                tracing::info!("Archive maintenance: {:?}", report);
+                let _ = self.ui_tx.send(NetToUi::ArchiveRepaired { report });
+            }
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!("Archive maintenance failed: {:#}", e),
+            Err(e) => tracing::warn!("Archive maintenance stopped: {}", e),
+        }
+    }
+
+    /// Time a short training run on `backend` off the worker loop and report
+    /// its speed
+    fn measure_training(&self, backend: TrainingBackend) {
+        let ui_tx = self.ui_tx.clone();
+        let governor = self.governor.clone();
+        tokio::spawn(async move {
+            let msg = match tokio::task::spawn_blocking(move || training::benchmark_with(backend, Some(governor))).await {
+                Ok(Ok(report)) => NetToUi::TrainingMeasured { report },
+                Ok(Err(e)) => NetToUi::Error { message: format!("Failed to measure training speed: {:#}", e) },
+                Err(e) => NetToUi::Error { message: format!("Training speed measurement stopped: {}", e) },
+            };
+            let _ = ui_tx.send(msg);
+        });
+    }
+
+    /// Train on self-play on `backend` off the worker loop, held back by
+    /// the governor, and report the run; the published weights are used
+    /// from the next time the model is loaded
+    fn train_on_self_play(&self, backend: TrainingBackend) {
+        let ui_tx = self.ui_tx.clone();
+        let governor = self.governor.clone();
+        tokio::spawn(async move {
+            let run = move || training::train_on_self_play(backend, governor, CheckpointStore::open_default(CHECKPOINTS_KEPT)?);
+            let msg = match tokio::task::spawn_blocking(run).await {
+                Ok(Ok(report)) => NetToUi::SelfPlayTrained { report },
+                Ok(Err(e)) => NetToUi::Error { message: format!("Failed to train on self-play: {:#}", e) },
+                Err(e) => NetToUi::Error { message: format!("Self-play training stopped: {}", e) },
+            };
+            let _ = ui_tx.send(msg);
+        });
+    }
+
+    /// Load the training games in `dir` off the event loop and report their stats
+    fn inspect_dataset(&self, dir: std::path::PathBuf) {
+        let ui_tx = self.ui_tx.clone();
+        thread::spawn(move || {
+            let result = trainer::load_games_from_dir(&dir)
+                .map(|dataset| dataset.stats())
+                .map_err(|e| e.to_string());
+            let _ = ui_tx.send(NetToUi::DatasetInspected { result });
+        });
+    }
+
+    /// Count the restarts the health checks made towards the alerts
+    fn poll_health(&mut self, now: std::time::Instant) {
+        while let Ok(event) = self.health_rx.try_recv() {
+            if matches!(event, HealthEvent::Restarted { .. }) {
+                self.alerts.record(AlertMetric::SubsystemRestarts, 1, now);
+            }
+        }
+    }
+
+    /// Count lost ACKs and act on the alert rules that fire
+    fn poll_alerts(&mut self, now: std::time::Instant) {
+        for active_game in self.active_games.values() {
+            self.alerts.record(AlertMetric::AckTimeouts, active_game.game.expire_acks(), now);
+        }
+        for alert in self.alerts.check(now) {
+            tracing::warn!(action = ?alert.action, "{}", alert);
+            let _ = self.ui_tx.send(NetToUi::Alert { message: alert.to_string() });
+            if alert.action == AlertAction::CaptureDiagnostics {
+                self.capture_trace();
+            }
+        }
+    }
+
+    /// Write the next `CAPTURE_DURATION` of logs at trace level for a bug report
+    fn capture_trace(&self) {
+        let reply = match logging::start_capture(CAPTURE_DURATION) {
+            Ok(path) => NetToUi::TraceCaptureStarted { path },
+            Err(e) => NetToUi::TraceCaptureFailed { message: format!("{:#}", e) },
+        };
+        let _ = self.ui_tx.send(reply);
+    }
+
+    /// Tell the UI once a trace capture has run its time or filled up
+    fn poll_trace_capture(&mut self, now: std::time::Instant) {
+        if let Some(report) = logging::finish_capture(now) {
+            let _ = self.ui_tx.send(NetToUi::TraceCaptureFinished { report });
+        }
+    }
+
+    /// Send every active game's channel metrics, at most every `METRICS_INTERVAL`
+    fn send_channel_metrics(&mut self, now: std::time::Instant) {
+        if now.duration_since(self.metrics_sent_at) < METRICS_INTERVAL {
+            return;
+        }
+        self.metrics_sent_at = now;
+        for active_game in self.active_games.values() {
+            let _ = self.ui_tx.send(NetToUi::ChannelMetrics {
+                game_id: active_game.game_id.clone(),
+                metrics: active_game.game.metrics(),
+            });
+        }
+    }
+
+    /// Take the traffic counts every `TRAFFIC_INTERVAL`, sending them if the
+    /// UI is watching; while it isn't they are dropped, not saved up
+    fn send_traffic_sample(&mut self, now: std::time::Instant) {
+        if now.duration_since(self.traffic_taken_at) < TRAFFIC_INTERVAL {
+            return;
+        }
+        self.traffic_taken_at = now;
+        let sample = traffic::TRAFFIC.take(p2pgo_network::relay_robustness::now_secs());
+        if self.watching_traffic {
+            let _ = self.ui_tx.send(NetToUi::TrafficSample { sample });
+            let _ = self.ui_tx.send(NetToUi::Rejections { counts: sanitize::REJECTED.counts() });
+            let _ = self.ui_tx.send(NetToUi::RelayUsage { usage: self.iroh_ctx.admission().usage(now) });
+        }
+    }
+
+    /// Switch relay preset without restarting the endpoint, which drains
+    /// the connections a smaller budget has no room for
+    fn set_relay_preset(&mut self, preset: RelayPreset) {
+        if self.relay_preset == Some(preset) {
+            return;
+        }
+        if let Err(e) = self.iroh_ctx.set_limits(preset.limits()) {
+            tracing::warn!("Relay preset not applied: {}", e);
+            return;
+        }
+        self.relay_preset = Some(preset);
+        let drained = self.iroh_ctx.admission().draining();
+        tracing::info!(preset = preset.label(), drained, "Relay preset changed");
+        let _ = self.ui_tx.send(NetToUi::RelayModeChanged { preset, limits: preset.limits(), drained });
+    }
+
+    /// Hold background training while we play a live game or the machine
+    /// runs hot, telling the UI when that changes
+    async fn poll_governor(&mut self, now: std::time::Instant) {
+        let mut live = false;
+        for active_game in self.active_games.values() {
+            // Correspondence games go on for days; spectators aren't playing
+            if active_game.correspondence || active_game.game.role() != ChannelRole::Player {
+                continue;
+            }
+            if active_game.game.phase().await == GamePhase::Active {
+                live = true;
+                break;
+            }
+        }
+        self.governor.set_live_game(live);
+        if !self.thermal_checked_at.is_some_and(|at| now.duration_since(at) < THERMAL_INTERVAL) {
+            self.thermal_checked_at = Some(now);
+            if let Some(pressure) = thermal::pressure() {
+                self.governor.set_thermal(pressure);
+            }
+        }
+        let paused = self.governor.paused();
+        if paused != self.training_paused {
+            self.training_paused = paused;
+            match paused {
+                Some(reason) => tracing::info!(%reason, "Background training paused"),
+                None => tracing::info!("Background training resumed"),
+            }
+            let _ = self.ui_tx.send(NetToUi::TrainingPaused { reason: paused });
+        }
+    }
+
+    /// Apply the parts of `config` that change while running and report
+    /// those left for the next start
+    async fn apply_config(&mut self, config: RuntimeConfig) {
+        if let Err(errors) = config.validate() {
+            let message = errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n");
+            let _ = self.ui_tx.send(NetToUi::ConfigApplied { result: Err(message) });
+            return;
+        }
+        if let Err(e) = logging::set_filter(&config.log_filter) {
+            tracing::warn!("Log filter not applied: {:#}", e);
+        }
+        self.lobby.set_channel_tuning(config.channel_tuning).await;
+        self.set_relay_preset(config.relay_preset);
+        self.governor.set_config(config.governor);
+        let report = match &mut self.runtime_config {
+            Some(running) => running.update(&config),
+            // The first is what the app started with
+            None => {
+                self.runtime_config = Some(config);
+                ApplyReport::default()
+            }
+        };
+        tracing::info!(applied = ?report.applied, restart_needed = ?report.restart_needed, "Runtime config applied");
+        let _ = self.ui_tx.send(NetToUi::ConfigApplied { result: Ok(report) });
+    }
+    
+    /// Save games that are due for a snapshot, or every unfinished game if `force`
+    async fn save_snapshots(&mut self, now: std::time::Instant, force: bool) {
+        let Some(store) = &self.snapshots else {
+            return;
+        };
+        let me = self.iroh_ctx.node_id().to_string();
+        let games = self.lobby.list_games().await;
+        for active_game in self.active_games.values_mut() {
+            let Some(state) = &active_game.game_state else {
+                continue;
+            };
+            let moves = state.moves.len();
+            if moves == 0 || state.is_game_over() || !(force || active_game.snapshot.is_due(moves, now)) {
+                continue;
+            }
+            let (host, guest) = self.lobby.seated_players(&active_game.game_id).await.unwrap_or_default();
+            let mut snapshot = GameSnapshot::new(active_game.game_id.clone(), state.clone());
+            snapshot.opponent = [host, guest].into_iter().flatten()
+                .find(|player| player.node_id != me)
+                .map(|player| player.name);
+            snapshot.peer_ticket = self.peer_ticket.clone();
+            snapshot.correspondence = active_game.correspondence;
+            snapshot.role = active_game.role;
+            snapshot.color = active_game.color;
+            snapshot.terms = games.iter().find(|g| g.id == active_game.game_id).map(|g| g.terms);
+            snapshot.outbox = active_game.game.pending_outbound().await;
+            match store.write_snapshot(&snapshot) {
+                Ok(()) => active_game.snapshot.mark_saved(moves, now),
+                Err(e) => tracing::warn!("Failed to snapshot game {}: {:#}", active_game.game_id, e),
+            }
+        }
+    }
+    
+    /// Start the game's move journal over from the moves in `state`
+    fn write_journal(snapshots: Option<&SnapshotStore>, game_id: &str, state: &GameState) {
+        if let Some(store) = snapshots {
+            if let Err(e) = store.write_journal(game_id, &state.moves) {
+                tracing::warn!("Failed to write the move journal of {}: {:#}", game_id, e);
+            }
+        }
+    }
+    
+    fn remove_snapshot(&self, game_id: &str) {
+        if let Some(store) = &self.snapshots {
+            if let Err(e) = store.remove(game_id) {
+                tracing::warn!("Failed to remove snapshot of {}: {:#}", game_id, e);
+            }
+        }
+    }
+    
+    /// Offer the most recent unfinished game saved by an earlier session
+    fn offer_resumable_game(&mut self) {
+        let Some(store) = &self.snapshots else {
+            return;
+        };
+        match store.latest_unfinished() {
+            Ok(Some(snapshot)) => {
+                let _ = self.ui_tx.send(NetToUi::ResumableGame {
+                    game_id: snapshot.game_id.clone(),
+                    move_count: snapshot.state.moves.len(),
+                    opponent: snapshot.opponent.clone(),
+                });
+                self.resumable = Some(snapshot);
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to look for saved games: {:#}", e),
+        }
+    }
+    
+    /// Host the offered game again from its snapshot and try to reach the peer
+    async fn resume_game(&mut self, game_id: &str) -> anyhow::Result<()> {
+        let Some(snapshot) = self.resumable.take().filter(|s| s.game_id == game_id) else {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("No saved game {} to resume", game_id),
+            });
+            return Ok(());
+        };
+        self.restore_game(snapshot).await
+    }
+    
+    /// Resume every saved correspondence game; moves we made while the
+    /// peer was away go out once it is reached
+    async fn resume_correspondence_games(&mut self) -> anyhow::Result<()> {
+        let Some(store) = &self.snapshots else {
+            return Ok(());
+        };
+        let games = match store.correspondence_games() {
+            Ok(games) => games,
+            Err(e) => {
+                tracing::warn!("Failed to look for correspondence games: {:#}", e);
+                return Ok(());
+            }
+        };
+        for snapshot in games {
+            // One game per board size; the rest wait for a later launch
+            if self.active_games.contains_key(&snapshot.state.board_size) {
+                tracing::info!("Not resuming correspondence game {}: board size in use", snapshot.game_id);
+                continue;
+            }
+            self.restore_game(snapshot).await?;
+        }
+        Ok(())
+    }
+    
+    /// Host a saved game again and try to reach its peer
+    async fn restore_game(&mut self, snapshot: GameSnapshot) -> anyhow::Result<()> {
+        // A crash may have left the snapshot and the move journal disagreeing
+        let (snapshot, recovered_from_divergence) = match &self.snapshots {
+            Some(store) => match store.reconcile(snapshot.clone()) {
+                Ok(reconciled) => (reconciled.snapshot, reconciled.recovered_from_divergence),
                 Err(e) => {
-                    tracing::warn!("Failed to subscribe to gossip lobby: {}", e);
-                    let _ = self.ui_tx.send(NetToUi::Error {
-                        message: format!("Failed to subscribe to lobby: {}", e),
-                    });
+                    tracing::warn!("Failed to check {} against its move journal: {:#}", snapshot.game_id, e);
+                    (snapshot, false)
                 }
+            },
+            None => (snapshot, false),
+        };
+        let game_id = snapshot.game_id.clone();
+        let move_count = snapshot.state.moves.len();
+        let board_size = snapshot.state.board_size;
+        let settings = match snapshot.terms {
+            Some(terms) => GameSettings {
+                komi: terms.komi,
+                handicap: terms.handicap,
+                rules: terms.rules,
+                rule_config: terms.rule_config,
+                ..GameSettings::standard(board_size)
+            },
+            None => GameSettings { rule_config: snapshot.state.rule_config, ..GameSettings::standard(board_size) },
+        };
+        self.open_game(snapshot.state, settings, Some(game_id.clone()), snapshot.role, snapshot.color).await?;
+        if !self.active_games.values().any(|g| g.game_id == game_id) {
+            return Ok(());
+        }
+        if let Some(terms) = snapshot.terms.filter(|terms| terms.time_control.is_some()) {
+            // The clocks start over; how much time was left isn't saved
+            if let Err(e) = self.lobby.set_time_control(&game_id, terms.time_control, terms.on_disconnect, terms.rated).await {
+                tracing::warn!("Failed to set the clock of {}: {}", game_id, e);
             }
         }
         
-        #[cfg(not(feature = "iroh"))]
-        {
-            tracing::debug!("Stub mode - skipping gossip lobby subscription");
+        if snapshot.correspondence {
+            self.set_correspondence(&game_id, true).await;
+            if let Some(active_game) = self.active_games.values_mut().find(|g| g.game_id == game_id) {
+                // Moves the peer syncs to us from here on came while we were away
+                active_game.unread = Some(0);
+                active_game.game.restore_outbound(snapshot.outbox).await;
+            }
         }
         
+        if let Some(ticket) = snapshot.peer_ticket {
+            if let Err(e) = self.connect_with_retry(&ticket).await {
+                tracing::warn!("Could not reconnect to the peer of {}: {}", game_id, e);
+            } else {
+                self.peer_ticket = Some(ticket);
+            }
+        }
+        let _ = self.ui_tx.send(NetToUi::GameRestored {
+            game_id,
+            color: snapshot.color,
+            move_count,
+            recovered_from_divergence,
+        });
         Ok(())
     }
     
-    async fn handle_set_tag(&mut self, gid: String, seq: u32, tag: p2pgo_core::Tag) -> anyhow::Result<()> {
-        // Store the tag annotation for the specified move
-        if let Err(e) = self.iroh_ctx.store_move_tag(&gid, seq, tag).await {
-            tracing::warn!("Failed to store move tag: {}", e);
+    /// Switch one of our games between live and correspondence play
+    async fn set_correspondence(&mut self, game_id: &str, enabled: bool) {
+        if let Err(e) = self.lobby.set_correspondence(&game_id.to_string(), enabled).await {
             let _ = self.ui_tx.send(NetToUi::Error {
-                message: format!("Failed to store tag: {}", e),
+                message: format!("Failed to change game {}: {}", game_id, e),
             });
-        } else {
-            let _ = self.ui_tx.send(NetToUi::TagAck);
+            return;
         }
-        Ok(())
+        let Some((&board_size, active_game)) = self.active_games.iter_mut().find(|(_, g)| g.game_id == game_id) else {
+            return;
+        };
+        active_game.correspondence = enabled;
+        // The next move starts a quiet period under the new budget
+        active_game.idle = None;
+        let _ = self.advertise_game(game_id, board_size).await;
     }
-
-    async fn handle_get_ghost_moves(&mut self) -> anyhow::Result<()> {
-        // Check if the player has completed enough games to see ghost moves
-        const GHOST_MOVES_THRESHOLD: u32 = 5;
-        if self.config.games_finished < GHOST_MOVES_THRESHOLD {
+    
+    /// Make us the teacher of one of our games, or end the lesson
+    async fn set_teaching(&self, game_id: &str, enabled: bool) {
+        let teacher = enabled.then(|| self.iroh_ctx.node_id().to_string());
+        if let Err(e) = self.lobby.set_teacher(&game_id.to_string(), teacher).await {
             let _ = self.ui_tx.send(NetToUi::Error {
-                message: format!("Ghost moves will be available after completing {} games (currently: {})", 
-                                GHOST_MOVES_THRESHOLD, self.config.games_finished),
+                message: format!("Failed to change game {}: {}", game_id, e),
             });
-            return Ok(());
         }
-        
-        // Ensure we have a current game state for the default board size
-        let game_state = if let Some(active_game) = self.active_games.get(&self.default_board_size) {
-            match &active_game.game_state {
-                Some(state) => state,
-                None => {
-                    let _ = self.ui_tx.send(NetToUi::Error {
-                        message: "No game state available for ghost moves".to_string(),
-                    });
-                    return Ok(());
-                }
-            }
-        } else {
+    }
+    
+    /// Annotate the current position of the game we teach on `board_size`
+    async fn annotate(&self, annotation: p2pgo_core::teaching::TeachingAnnotation, board_size: Option<u8>) {
+        let board_size = board_size.unwrap_or(self.default_board_size);
+        let Some(active_game) = self.active_games.get(&board_size) else {
+            return;
+        };
+        let me = self.iroh_ctx.node_id().to_string();
+        if let Err(e) = active_game.game.annotate(&me, annotation).await {
             let _ = self.ui_tx.send(NetToUi::Error {
-                message: format!("No active game for board size {} for ghost moves", self.default_board_size),
+                message: format!("Annotation not sent: {}", e),
             });
-            return Ok(());
+        }
+    }
+    
+    /// Ask the current game's opponent to take back our last `moves` moves
+    async fn request_undo(&self, moves: u8) {
+        let Some(active_game) = self.active_games.get(&self.default_board_size) else {
+            return;
         };
-
-        // Lazy load the AI model if not already loaded
-        if self.ai_model.is_none() {
-            match self.load_ai_model().await {
-                Ok(model) => {
-                    self.ai_model = Some(Rc::new(Mutex::new(model)));
-                }
-                Err(e) => {
-                    let _ = self.ui_tx.send(NetToUi::Error {
-                        message: format!("Failed to load AI model: {}", e),
-                    });
-                    return Ok(());
-                }
-            }
+        if let Err(e) = active_game.game.request_undo(moves).await {
+            let _ = self.ui_tx.send(NetToUi::Error { message: format!("Undo not requested: {}", e) });
         }
-
-        // Get the AI model
-        let model = match &self.ai_model {
-            Some(model) => model.clone(),
-            None => {
-                let _ = self.ui_tx.send(NetToUi::Error {
-                    message: "AI model not available".to_string(),
-                });
-                return Ok(());
-            }
+    }
+    
+    async fn answer_undo(&self, accept: bool) {
+        let Some(active_game) = self.active_games.get(&self.default_board_size) else {
+            return;
+        };
+        if let Err(e) = active_game.game.answer_undo(accept).await {
+            let _ = self.ui_tx.send(NetToUi::Error { message: format!("Undo not answered: {}", e) });
+        }
+    }
+    
+    /// Compute the heat map requested last, once requests have paused
+    async fn poll_heat_map(&mut self, now: std::time::Instant) {
+        match &self.pending_heat_map {
+            Some((_, since)) if now.duration_since(*since) >= HEAT_MAP_DEBOUNCE => {}
+            _ => return,
+        }
+        let Some((game_id, _)) = self.pending_heat_map.take() else {
+            return;
+        };
+        let Some(game) = self.active_games.values().find(|g| g.game_id == game_id).map(|g| g.game.clone()) else {
+            return;
+        };
+        let Some(state) = game.get_latest_state().await else {
+            return;
+        };
+        if state.board_size > 9 {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: "The heat map is only available on boards up to 9x9".to_string(),
+            });
+            return;
+        }
+        let Some(model) = self.ai_model().await else {
+            return;
         };
 
-        // Get ghost moves from the model
-        match self.compute_ghost_moves(&model, game_state).await {
-            Ok(ghost_coords) => {
-                let _ = self.ui_tx.send(NetToUi::GhostMoves(ghost_coords));
+        let position_hash = heat_map::position_hash(&state);
+        let neural = self.neural;
+        let computed = self.heat_maps.get_or_compute(position_hash, || {
+            let evaluation = Self::evaluate(&model, &state)?;
+            let outlook = Outlook::from_evaluation(&evaluation, &state, &neural);
+            Ok((heat_map::from_logits(&evaluation.logits, &state), outlook))
+        });
+        match computed {
+            Ok((map, outlook)) => {
+                let _ = self.ui_tx.send(NetToUi::HeatMap { position_hash, map: map.clone() });
+                let _ = self.ui_tx.send(NetToUi::Outlook { position_hash, outlook: *outlook });
             }
             Err(e) => {
                 let _ = self.ui_tx.send(NetToUi::Error {
-                    message: format!("Failed to compute ghost moves: {}", e),
+                    message: format!("Failed to compute heat map: {}", e),
                 });
             }
         }
-
-        Ok(())
     }
 
-    async fn load_ai_model(&self) -> anyhow::Result<GoMini6E<Wgpu>> {
-        // Create a new device
-        let device = <Wgpu as Backend>::Device::default();
-        
-        // Initialize the model
-        let model = GoMini6E::new(&device);
-        
-        tracing::info!("AI model loaded successfully");
-        Ok(model)
+    /// Suggest passing, once a game, when no move gains the player to move
+    /// anything over passing. Suggestions are only hints; we never pass for them.
+    async fn check_pass_suggestions(&mut self) {
+        if !self.pass_suggestions.enabled {
+            return;
+        }
+        let threshold = self.pass_suggestions.threshold;
+        let mut due = Vec::new();
+        for active_game in self.active_games.values_mut() {
+            let Some(state) = &active_game.game_state else {
+                continue;
+            };
+            if active_game.pass_suggested || state.is_game_over() || active_game.pass_checked == Some(state.moves.len()) {
+                continue;
+            }
+            active_game.pass_checked = Some(state.moves.len());
+            // A board still half empty has moves worth playing, and would take long to check
+            let empty = state.board.iter().filter(|point| point.is_none()).count();
+            if empty * 2 > state.board.len() {
+                continue;
+            }
+            due.push((active_game.game_id.clone(), state.clone(), active_game.game.settings().komi));
+        }
+        for (game_id, state, komi) in due {
+            // Playouts take a while; keep them off the runtime's threads
+            let check = tokio::task::spawn_blocking(move || {
+                p2pgo_core::analysis::pass_suggestion(&state, komi, threshold, PASS_CHECK_PLAYOUTS)
+            });
+            let Ok(Some(score_estimate)) = check.await else {
+                continue;
+            };
+            if let Some(active_game) = self.active_games.values_mut().find(|g| g.game_id == game_id) {
+                active_game.pass_suggested = true;
+            }
+            let _ = self.ui_tx.send(NetToUi::PassSuggested { game_id, score_estimate });
+        }
     }
 
-    async fn compute_ghost_moves(
-        &self,
-        model: &Rc<Mutex<GoMini6E<Wgpu>>>,
-        game_state: &GameState,
-    ) -> anyhow::Result<Vec<Coord>> {
-        // Convert board state to model input
-        let board_input = self.game_state_to_tensor(game_state)?;
-        
-        // Get model predictions
-        let model = model.lock().map_err(|e| anyhow::anyhow!("Failed to lock model: {}", e))?;
-        let device = <Wgpu as Backend>::Device::default();
-        
-        // Create input tensor [1, 81] for batch size 1
-        let input_tensor = Tensor::<Wgpu, 1>::from_floats(board_input.as_slice(), &device)
-            .reshape([1, 81]);
-        
-        // Forward pass
-        let (policy_logits, _value) = model.forward(input_tensor);
-        
-        // Extract top 3 moves as ghost suggestions
-        // Flatten the 2D tensor [1, 81] to 1D [81] first
-        let flat_policy = policy_logits.squeeze::<1>(0); // Remove batch dimension to get [81]
-        
-        // Convert to vector - we need to extract each element individually
-        let mut policy_data: Vec<f32> = Vec::new();
-        for i in 0..81 {
-            let slice = flat_policy.clone().narrow(0, i, 1);
-            let value: f32 = slice.into_scalar();
-            policy_data.push(value);
+    /// Estimate ownership for the position requested last, once requests have paused
+    async fn poll_ownership(&mut self, now: std::time::Instant) {
+        match &self.pending_ownership {
+            Some((_, since)) if now.duration_since(*since) >= HEAT_MAP_DEBOUNCE => {}
+            _ => return,
         }
-        let mut move_scores: Vec<(usize, f32)> = policy_data.iter().enumerate().map(|(i, &score)| (i, score)).collect();
-        
-        // Sort by score descending
-        move_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        
-        // Convert top moves to coordinates
-        let mut ghost_coords = Vec::new();
-        let board_size = game_state.board_size as usize;
-        
-        for (move_idx, _score) in move_scores.iter().take(3) {
-            if *move_idx < 81 && *move_idx < board_size * board_size {
-                let row = move_idx / board_size;
-                let col = move_idx % board_size;
-                
-                // Check if the position is empty
-                let coord = Coord::new(col as u8, row as u8);
-                let idx = row * board_size + col;
-                if idx < game_state.board.len() && game_state.board[idx].is_none() {
-                    ghost_coords.push(coord);
+        let Some((game_id, _)) = self.pending_ownership.take() else {
+            return;
+        };
+        let Some(game) = self.active_games.values().find(|g| g.game_id == game_id).map(|g| g.game.clone()) else {
+            return;
+        };
+        let Some(state) = game.get_latest_state().await else {
+            return;
+        };
+        let position_hash = heat_map::position_hash(&state);
+        if self.ownership_maps.get(position_hash).is_none() {
+            // Playouts need no model, so every board size gets an estimate;
+            // they run off the runtime's threads
+            let sweep = tokio::task::spawn_blocking(move || p2pgo_core::analysis::ownership(&state, OWNERSHIP_PLAYOUTS));
+            match sweep.await {
+                Ok(map) => self.ownership_maps.insert(position_hash, map),
+                Err(e) => {
+                    tracing::warn!("Ownership estimate failed: {}", e);
+                    return;
                 }
             }
         }
-        
-        tracing::debug!("Generated {} ghost move suggestions", ghost_coords.len());
-        Ok(ghost_coords)
+        if let Some(map) = self.ownership_maps.get(position_hash) {
+            let _ = self.ui_tx.send(NetToUi::Ownership { position_hash, map: map.to_vec() });
+        }
     }
 
-    fn game_state_to_tensor(&self, game_state: &GameState) -> anyhow::Result<Vec<f32>> {
-        let board_size = game_state.board_size as usize;
-        let mut tensor = vec![0.0f32; 81]; // Always use 9x9 for model consistency
-        
-        // Fill the tensor with board state (center smaller boards)
-        let offset = (9 - board_size) / 2;
-        
-        for row in 0..board_size {
-            for col in 0..board_size {
-                let board_idx = row * board_size + col;
-                let tensor_idx = (row + offset) * 9 + (col + offset);
-                
-                if tensor_idx < 81 && board_idx < game_state.board.len() {
-                    tensor[tensor_idx] = match game_state.board[board_idx] {
-                        Some(p2pgo_core::Color::Black) => 1.0,
-                        Some(p2pgo_core::Color::White) => -1.0,
-                        None => 0.0,
-                    };
+    /// Evaluate the next few positions of the game whose win rate is followed.
+    /// Called after the loop's game events, so moves reach the UI first.
+    async fn poll_win_rate(&mut self) {
+        if self.win_rate.is_idle() {
+            return;
+        }
+        let Some(model) = self.ai_model().await else {
+            self.win_rate.stop();
+            return;
+        };
+        match self.win_rate.poll(|state| Self::value_estimate(&model, state)) {
+            Ok(points) => {
+                for EvalPoint { game_id, move_number, value } in points {
+                    let _ = self.ui_tx.send(NetToUi::EvalPoint { game_id, move_number, value });
                 }
             }
+            Err(e) => tracing::warn!("Failed to evaluate the win rate: {}", e),
         }
-        
-        Ok(tensor)
+    }
+
+    async fn compute_ghost_moves(
+        &self,
+        model: &Rc<Mutex<GoMini6E<Wgpu>>>,
+        game_state: &GameState,
+    ) -> anyhow::Result<Vec<p2pgo_core::Move>> {
+        let policy_data = Self::policy_logits(model, game_state)?;
+        // Judged on the game's own state, so a ko retake is never suggested
+        let ghost_moves = trainer::evaluate::ghost_moves(&policy_data, 9, game_state, self.neural.ghost_moves);
+        tracing::debug!("Generated {} ghost move suggestions", ghost_moves.len());
+        Ok(ghost_moves)
+    }
+
+    /// Raw policy output for `game_state`: one logit per point of the 9x9 input, then pass
+    fn policy_logits(model: &Rc<Mutex<GoMini6E<Wgpu>>>, game_state: &GameState) -> anyhow::Result<Vec<f32>> {
+        Ok(Self::evaluate(model, game_state)?.logits)
+    }
+
+    /// Value head output for `game_state`: Black's outlook from -1 to 1
+    fn value_estimate(model: &Rc<Mutex<GoMini6E<Wgpu>>>, game_state: &GameState) -> anyhow::Result<f32> {
+        Ok(Self::evaluate(model, game_state)?.value)
+    }
+
+    fn evaluate(model: &Rc<Mutex<GoMini6E<Wgpu>>>, game_state: &GameState) -> anyhow::Result<Evaluation> {
+        let model = model.lock().map_err(|e| anyhow::anyhow!("Failed to lock model: {}", e))?;
+        let device = <Wgpu as Backend>::Device::default();
+        trainer::evaluate::evaluate(&*model, game_state, &device)
     }
 
     async fn handle_calculate_score(&mut self, dead_stones: std::collections::HashSet<p2pgo_core::Coord>) -> anyhow::Result<()> {
         // Ensure we have a current game state for the default board size
-        let game_state = if let Some(active_game) = self.active_games.get(&self.default_board_size) {
+        let (game_state, settings) = if let Some(active_game) = self.active_games.get(&self.default_board_size) {
             match &active_game.game_state {
-                Some(state) => state,
+                Some(state) => (state, active_game.game.settings()),
                 None => {
                     let _ = self.ui_tx.send(NetToUi::Error {
                         message: "No game state available for score calculation".to_string(),
@@ -836,12 +2769,8 @@ impl NetworkWorker {
             return Ok(());
         };
 
-        // Use territory scoring (Chinese rules) with komi appropriate for board size
-        let komi = match game_state.board_size {
-            19 => 7.5,
-            13 => 6.5,
-            _ => 5.5, // 9x9 or other sizes
-        };
+        // Territory scoring (Chinese rules) with the komi the game was set up with
+        let komi = settings.scoring_komi();
 
         let scoring_method = p2pgo_core::value_labeller::ScoringMethod::Territory;
         
@@ -867,29 +2796,256 @@ impl NetworkWorker {
     }
     
     async fn handle_accept_score(&mut self, score_proof: p2pgo_core::value_labeller::ScoreProof) -> anyhow::Result<()> {
-        // Store the final score for training for the default board size
+        // The game settles once our opponent accepts the same score
         if let Some(active_game) = self.active_games.get(&self.default_board_size) {
+            // A resigned game is over already; only a scored one settles here
+            if let Err(e) = active_game.game.accept_score(active_game.color, score_proof).await {
+                tracing::debug!("Score of {} not accepted: {}", active_game.game_id, e);
+            }
+        } else {
+            tracing::warn!("Cannot accept score: no active game for board size {}", self.default_board_size);
+        }
+        
+        Ok(())
+    }
+    
+    /// Archive, share and rate a game both players accepted the score of
+    async fn finish_scored_game(&mut self, board_size: u8) {
+        if let Some(active_game) = self.active_games.get(&board_size) {
+            let Some(score_proof) = active_game.game.accepted_score(active_game.color).await else {
+                tracing::warn!("No accepted score for {}", active_game.game_id);
+                return;
+            };
             // Create a value labeller to handle the score
             let mut labeller = p2pgo_core::value_labeller::ValueLabeller::new();
             labeller.set_final_score(score_proof.clone());
             
-            // In stub mode, we don't actually store the data, but log what we would store
-            tracing::info!("Would store score proof and value labels for game {}", active_game.game_id);
-            
             // Update metrics
             self.config.games_finished += 1;
             
+            let game_id = active_game.game_id.clone();
+            let final_state = active_game.game.game_record().await;
+            let excluded = self.excluded_from_training.contains(&game_id);
+            let winner = match score_proof.final_score.cmp(&0) {
+                std::cmp::Ordering::Greater => Some(p2pgo_core::Color::Black),
+                std::cmp::Ordering::Less => Some(p2pgo_core::Color::White),
+                std::cmp::Ordering::Equal => None,
+            };
+            if let (Some(archive), Some(state)) = (&self.archive, &final_state) {
+                let (anomalies, clock_skews) = (active_game.game.anomalies().await, active_game.game.clock_skews().await);
+                let archived = archive.archive_game(game_id.clone(), state.clone(), winner, Some(score_proof.final_score), anomalies, clock_skews).await;
+                if let Err(e) = archived.and_then(|_| if excluded { archive.set_training_eligible(&game_id, false) } else { Ok(()) }) {
+                    tracing::warn!("Failed to archive {}: {}", game_id, e);
+                }
+            }
+            
+            // Sharing a scored game earns training credits, unless the player kept it out
+            if self.config.share_training_data && !excluded {
+                if let (Some(store), Some(state)) = (&self.training, &final_state) {
+                    if let Err(e) = store.save(&game_id, state) {
+                        tracing::warn!("No training copy of {}: {}", game_id, e);
+                    }
+                }
+                if let Some(ledger) = self.credits.as_mut() {
+                    if let Err(e) = ledger.record_shared_game(&game_id) {
+                        tracing::warn!("No credits recorded for {}: {}", game_id, e);
+                    }
+                }
+            }
+            self.send_credits();
+            
             // Send ScoreAcceptedByBoth message to indicate successful scoring
             let _ = self.ui_tx.send(NetToUi::ScoreAcceptedByBoth { 
                 score_proof: score_proof.clone() 
             });
             
             tracing::info!("Score accepted and stored for training. Games completed: {}", self.config.games_finished);
-        } else {
-            tracing::warn!("Cannot accept score: no active game for board size {}", self.default_board_size);
         }
+    }
+    
+    /// Update our rating after a scored, resigned or timed-out game.
+    ///
+    /// Only games whose listed terms say they are rated count, also when
+    /// the opponent's profile gave no rating; a game without a known
+    /// opponent isn't recorded. Abandoned games never get here.
+    async fn rate_game(&mut self, board_size: u8, winner: Option<p2pgo_core::Color>) {
+        let Some(active_game) = self.active_games.get(&board_size) else {
+            return;
+        };
+        let (game_id, our_color) = (active_game.game_id.clone(), active_game.color);
         
-        Ok(())
+        let me = self.iroh_ctx.node_id().to_string();
+        let (host, opponent) = self.lobby.seated_players(&game_id).await.unwrap_or_default();
+        let Some(them) = [host, opponent].into_iter().flatten().find(|p| p.node_id != me) else {
+            tracing::warn!("Rating not updated for {}: the opponent is unknown", game_id);
+            return;
+        };
+        // Games whose terms are no longer listed aren't known to be rated
+        let rated = self.lobby.list_games().await.iter()
+            .find(|g| g.id == game_id)
+            .is_some_and(|g| g.terms.rated);
+        let outcome = match winner {
+            None => GameOutcome::Draw,
+            Some(color) if color == our_color => GameOutcome::Win,
+            Some(_) => GameOutcome::Loss,
+        };
+        
+        let Some(tracker) = &mut self.ratings else {
+            return;
+        };
+        let game = RatedGame {
+            game_id: game_id.clone(),
+            opponent: them.node_id,
+            opponent_estimate: them.self_reported_rating,
+            outcome,
+            rated,
+        };
+        if let Err(e) = tracker.record(game) {
+            tracing::warn!("Rating not updated for {}: {}", game_id, e);
+        }
+        self.send_ratings();
+    }
+    
+    /// Push our rating and its history to the UI
+    fn send_ratings(&self) {
+        if let Some(tracker) = &self.ratings {
+            let _ = self.ui_tx.send(NetToUi::RatingUpdated {
+                current: tracker.current(),
+                history: tracker.history().to_vec(),
+            });
+        }
+    }
+    
+    /// Delete our training copy of a game and ask peers to drop theirs
+    async fn delete_training_copies(&mut self, game_id: String) {
+        self.excluded_from_training.insert(game_id.clone());
+        let local = match self.training.as_ref().map(|store| store.delete(&game_id)) {
+            Some(Ok(deleted)) => deleted,
+            Some(Err(e)) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("{:#}", e) });
+                return;
+            }
+            None => false,
+        };
+        // Peers only honour retractions signed by the player who shared the game
+        let Some(loaded) = &self.identity else {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Peers can't be asked to drop {} without a persistent identity to sign with", game_id),
+            });
+            return;
+        };
+        let retraction = TrainingMessage::Retract(Retraction::sign(&loaded.identity, game_id.clone(), now_secs()));
+        if let Err(e) = self.training_topic.publish(retraction).await {
+            let _ = self.ui_tx.send(NetToUi::Error {
+                message: format!("Failed to ask peers to drop {}: {}", game_id, e),
+            });
+            return;
+        }
+        let _ = self.ui_tx.send(NetToUi::TrainingCopiesDeleted { game_id, local });
+    }
+    
+    /// Drop our training copies of games peers retracted
+    fn poll_training_retractions(&mut self) {
+        loop {
+            let message = match self.training_rx.try_recv() {
+                Ok(message) => message,
+                Err(tokio::sync::broadcast::error::TryRecvError::Lagged(missed)) => {
+                    tracing::warn!("Missed {} training retractions", missed);
+                    continue;
+                }
+                Err(_) => break,
+            };
+            let TrainingMessage::Retract(retraction) = &message;
+            if let Err(e) = retraction.verify() {
+                tracing::warn!("Ignoring retraction of {} from {}: {:#}", retraction.game_id, retraction.node_id, e);
+                continue;
+            }
+            if let Some(store) = &self.training {
+                if let Err(e) = store.apply(&message) {
+                    tracing::warn!("Failed to apply training retraction: {:#}", e);
+                }
+            }
+        }
+    }
+    
+    /// Push the persisted credits balance and history to the UI
+    fn send_credits(&self) {
+        if let Some(ledger) = &self.credits {
+            let _ = self.ui_tx.send(NetToUi::CreditsUpdated {
+                balance: ledger.balance(),
+                history: ledger.history().to_vec(),
+            });
+        }
+    }
+    
+    /// Tell the UI whether the key file is passphrase protected
+    fn send_identity_status(&self) {
+        let encrypted = match &self.identity {
+            Some(loaded) => loaded.manager.is_encrypted().unwrap_or(false),
+            None => false,
+        };
+        let _ = self.ui_tx.send(NetToUi::IdentityStatus {
+            persistent: self.identity.is_some(),
+            encrypted,
+        });
+    }
+    
+    /// Re-save the key file with a new passphrase, or unencrypted for `None`
+    fn set_identity_passphrase(&self, passphrase: Option<&str>) {
+        let Some(loaded) = &self.identity else {
+            let _ = self.ui_tx.send(NetToUi::Error { message: "No persistent identity to protect".to_string() });
+            return;
+        };
+        match loaded.manager.save(&loaded.identity, passphrase) {
+            Ok(()) => self.send_identity_status(),
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to save identity: {}", e) });
+            }
+        }
+    }
+    
+    fn export_identity(&self, passphrase: Option<&str>) {
+        let result = match &self.identity {
+            Some(loaded) => loaded.manager.export(&loaded.identity, passphrase),
+            None => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: "No persistent identity to export".to_string() });
+                return;
+            }
+        };
+        match result {
+            Ok(data) => {
+                let _ = self.ui_tx.send(NetToUi::IdentityExported { data });
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to export identity: {}", e) });
+            }
+        }
+    }
+    
+    /// Store an exported identity; the running endpoint keeps its node ID
+    /// until the next launch
+    fn import_identity(&mut self, data: &str, passphrase: Option<&str>) {
+        let manager = match &self.identity {
+            Some(loaded) => loaded.manager.clone(),
+            None => match IdentityManager::open_default() {
+                Ok(manager) => manager,
+                Err(e) => {
+                    let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to import identity: {}", e) });
+                    return;
+                }
+            },
+        };
+        match manager.import(data, passphrase) {
+            Ok(identity) => {
+                let node_id = identity.node_id();
+                self.identity = Some(LoadedIdentity { manager, identity });
+                let _ = self.ui_tx.send(NetToUi::IdentityImported { node_id });
+                self.send_identity_status();
+            }
+            Err(e) => {
+                let _ = self.ui_tx.send(NetToUi::Error { message: format!("Failed to import identity: {}", e) });
+            }
+        }
     }
     
     async fn start_score_timeout(&mut self, board_size: u8, score_proof: p2pgo_core::value_labeller::ScoreProof) {
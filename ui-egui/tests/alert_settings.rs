@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Alert rules edited in the settings, and the warnings they raise.
+
+#[cfg(feature = "headless")]
+#[test]
+fn only_rules_that_parse_reach_the_worker() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_network::alerts::{AlertAction, AlertMetric};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    app.set_alert_rules(r#"[{"metric": "PacketLoss", "window_secs": 60, "threshold": 1, "action": "Notify"}]"#);
+    assert!(app.alert_rules_error().unwrap().contains("PacketLoss"));
+    assert!(net_rx.try_recv().is_err());
+
+    app.set_alert_rules(r#"[{"metric": "SubsystemRestarts", "window_secs": 600, "threshold": 2, "action": "CaptureDiagnostics"}]"#);
+    assert_eq!(app.alert_rules_error(), None);
+    match net_rx.try_recv().unwrap() {
+        UiToNet::SetAlertRules { rules } => {
+            assert_eq!(rules.len(), 1);
+            assert_eq!(rules[0].metric, AlertMetric::SubsystemRestarts);
+            assert_eq!(rules[0].action, AlertAction::CaptureDiagnostics);
+        }
+        other => panic!("expected SetAlertRules, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn alerts_stay_until_the_next_one() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    let message = "Connection trouble: 3 moves went unacknowledged in the last 1 min".to_string();
+    net_tx.send(NetToUi::Alert { message: message.clone() }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.alert(), Some(message.as_str()));
+    assert_eq!(app.get_error_msg(), None);
+    app.tick_headless();
+    assert_eq!(app.alert(), Some(message.as_str()), "not cleared like a toast");
+}
@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Archive browser listing, search, review and export.
+
+use p2pgo_core::archiver::{self, ClassificationFilter, ExportFormat};
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_ui_egui::archive_view::{ArchiveBrowser, SortKey};
+
+fn play(board_size: u8, moves: &[Move]) -> GameState {
+    let mut game = GameState::new(board_size);
+    for mv in moves {
+        game.apply_move(mv.clone()).unwrap();
+    }
+    game
+}
+
+/// Archive three games and open a browser on them
+fn populated_archive() -> (tempfile::TempDir, ArchiveBrowser) {
+    let dir = tempfile::tempdir().unwrap();
+    let short = play(9, &[Move::Place(Coord::new(2, 2)), Move::Resign]);
+    let long = play(19, &[
+        Move::Place(Coord::new(3, 3)),
+        Move::Place(Coord::new(15, 15)),
+        Move::Place(Coord::new(3, 15)),
+        Move::Pass,
+        Move::Pass,
+    ]);
+    let empty = play(13, &[]);
+    archiver::archive_game_in(dir.path(), &short, "alice").unwrap();
+    archiver::archive_game_in(dir.path(), &long, "bob").unwrap();
+    archiver::archive_game_in(dir.path(), &empty, "carol").unwrap();
+
+    let browser = ArchiveBrowser::open(dir.path()).unwrap();
+    (dir, browser)
+}
+
+#[test]
+fn lists_metadata_for_each_game() {
+    let (_dir, browser) = populated_archive();
+    let games = browser.visible();
+    assert_eq!(games.len(), 3);
+
+    let alice = games.iter().find(|g| g.opponent == "alice").unwrap();
+    assert_eq!(alice.board_size, 9);
+    assert_eq!(alice.move_count, 2);
+    // White resigned on move two
+    assert_eq!(alice.result.as_deref(), Some("B+Resign"));
+    assert!(alice.training_eligible);
+    assert_eq!(alice.date.len(), "YYYY-MM-DD".len());
+
+    let bob = games.iter().find(|g| g.opponent == "bob").unwrap();
+    assert_eq!((bob.board_size, bob.move_count, bob.result.as_deref()), (19, 5, None));
+}
+
+#[test]
+fn search_and_sort() {
+    let (_dir, mut browser) = populated_archive();
+
+    browser.search = "BO".to_string();
+    let found: Vec<_> = browser.visible().iter().map(|g| g.opponent.clone()).collect();
+    assert_eq!(found, ["bob"]);
+
+    browser.search = "resign".to_string();
+    assert_eq!(browser.visible()[0].opponent, "alice");
+
+    browser.search.clear();
+    browser.sort = SortKey::BoardSize;
+    let sizes: Vec<_> = browser.visible().iter().map(|g| g.board_size).collect();
+    assert_eq!(sizes, [9, 13, 19]);
+    browser.sort = SortKey::MoveCount;
+    let moves: Vec<_> = browser.visible().iter().map(|g| g.move_count).collect();
+    assert_eq!(moves, [5, 2, 0]);
+}
+
+#[test]
+fn exports_sgf_and_reviews_lazily() {
+    let (_dir, browser) = populated_archive();
+    let bob = browser.visible().into_iter().find(|g| g.opponent == "bob").unwrap().id.clone();
+
+    let sgf = browser.export_sgf(&bob).unwrap();
+    assert!(sgf.starts_with("(;"));
+    assert!(sgf.contains("SZ[19]"));
+    assert!(sgf.contains("B[dd]"));
+
+    let mut review = browser.open_review(&bob).unwrap();
+    assert_eq!((review.cursor(), review.len()), (5, 5));
+    review.first();
+    assert!(review.position().board.iter().all(|p| p.is_none()));
+    review.step(2);
+    assert_eq!(review.position().moves.len(), 2);
+    review.step(10);
+    assert_eq!(review.cursor(), 5);
+    review.step(-10);
+    assert_eq!(review.cursor(), 0);
+}
+
+#[test]
+fn delete_and_mark_training() {
+    let (dir, mut browser) = populated_archive();
+    let alice = browser.visible().into_iter().find(|g| g.opponent == "alice").unwrap().id.clone();
+    let carol = browser.visible().into_iter().find(|g| g.opponent == "carol").unwrap().id.clone();
+
+    browser.set_training_eligible(&alice, false).unwrap();
+    browser.delete(&carol).unwrap();
+    assert_eq!(browser.visible().len(), 2);
+
+    // Both survive a fresh listing
+    let reopened = ArchiveBrowser::open(dir.path()).unwrap();
+    let games = reopened.visible();
+    assert_eq!(games.len(), 2);
+    assert!(!games.iter().find(|g| g.id == alice).unwrap().training_eligible);
+    assert!(games.iter().all(|g| g.id != carol));
+}
+
+#[test]
+fn exports_everything_next_to_the_records() {
+    let (dir, mut browser) = populated_archive();
+    let alice = browser.visible().into_iter().find(|g| g.opponent == "alice").unwrap().id.clone();
+    browser.set_training_eligible(&alice, false).unwrap();
+
+    let filter = ClassificationFilter { only_training_eligible: true, ..Default::default() };
+    let (path, report) = browser.export_all(ExportFormat::SgfCollection, &filter).unwrap();
+    assert_eq!(path.parent(), Some(dir.path()));
+    assert_eq!((report.exported, report.skipped.as_slice()), (2, [alice].as_slice()));
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    // The export isn't mistaken for a game
+    browser.refresh().unwrap();
+    assert_eq!(browser.visible().len(), 3);
+}
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The summary shown when archive maintenance repaired something.
+
+#[cfg(feature = "headless")]
+#[test]
+fn repairs_are_summed_up_in_a_toast() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::archiver::MaintenanceReport;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    let report = MaintenanceReport {
+        checked: 12,
+        removed_temps: vec![".tmp_game.cbor".to_string()],
+        quarantined: vec![("broken.cbor".to_string(), "Not a game record".to_string())],
+        compacted: vec!["game-1".to_string(), "game-2".to_string()],
+    };
+    net_tx.send(NetToUi::ArchiveRepaired { report }).unwrap();
+    app.tick_headless();
+    assert_eq!(
+        app.toast().as_deref(),
+        Some("Archive repaired: 1 leftover temporary file removed, 1 corrupt file set aside in corrupt, 2 finished games archived from snapshots"),
+    );
+}
@@ -1,51 +1,145 @@
-//! Test for auto-refresh functionality
+//! Test for push-based lobby updates
 //! SPDX-License-Identifier: MIT OR Apache-2.0
 
-use crossbeam_channel::unbounded;
-use p2pgo_ui_egui::msg::UiToNet;
-use p2pgo_ui_egui::app::AppConfig;
+use std::time::{Duration, Instant};
+use p2pgo_network::game_list::GamesPage;
+use p2pgo_network::lobby::{GameInfo, GameListingState, GameTerms};
+use p2pgo_ui_egui::worker::{GamesCoalescer, GAMES_COALESCE_INTERVAL};
+
+fn game(id: &str) -> GameInfo {
+    GameInfo {
+        id: id.to_string(),
+        name: None,
+        board_size: 9,
+        state: GameListingState::OPEN,
+        needs_password: false,
+        correspondence: false,
+        teacher: None,
+        terms: GameTerms::standard(9),
+    }
+}
+
+fn page(ids: &[&str]) -> GamesPage {
+    GamesPage { games: ids.iter().map(|id| game(id)).collect(), offset: 0, total: ids.len() }
+}
 
 #[test]
-fn auto_refresh_sends_message() {
-    // Create a simple channel pair to capture messages
-    let (tx, rx) = unbounded::<UiToNet>();
+fn identical_adverts_produce_one_update() {
+    let mut coalescer = GamesCoalescer::new();
+    let start = Instant::now();
     
-    // We'll manually send the refresh message rather than using the App struct
-    // This simulates the behavior we want to test
+    // Flood the coalescer with the same list over several windows
+    let mut sent = 0;
+    for i in 0..100u64 {
+        let now = start + Duration::from_millis(i * 20);
+        if coalescer.offer(page(&["a", "b"]), now).is_some() {
+            sent += 1;
+        }
+        if coalescer.poll(now).is_some() {
+            sent += 1;
+        }
+    }
     
-    // First with auto-refresh disabled
-    let config = AppConfig {
-        auto_refresh: false,
-        games_finished: 0,
-    };
+    assert_eq!(sent, 1, "Unchanged game list should only be pushed once");
+}
+
+#[test]
+fn games_beyond_the_page_count_as_change() {
+    let mut coalescer = GamesCoalescer::new();
+    let start = Instant::now();
     
-    // No message should be sent when auto_refresh is false
-    if config.auto_refresh {
-        tx.send(UiToNet::RefreshGames).unwrap();
+    assert!(coalescer.offer(page(&["a", "b"]), start).is_some());
+    let later = start + GAMES_COALESCE_INTERVAL * 2;
+    let grown = GamesPage { total: 3, ..page(&["a", "b"]) };
+    assert_eq!(coalescer.offer(grown.clone(), later), Some(grown));
+}
+
+#[test]
+fn changes_are_coalesced_per_window() {
+    let mut coalescer = GamesCoalescer::new();
+    let start = Instant::now();
+    
+    assert!(coalescer.offer(page(&["a"]), start).is_some());
+    
+    // A burst of changes inside the window is held back
+    let mut now = start;
+    for id in ["b", "c", "d"] {
+        now += Duration::from_millis(10);
+        assert!(coalescer.offer(page(&["a", id]), now).is_none());
     }
+    assert!(coalescer.poll(now).is_none());
     
-    // No messages should be sent
-    assert_eq!(rx.len(), 0);
+    // Only the latest list is released once the window has passed
+    let flushed = coalescer.poll(start + GAMES_COALESCE_INTERVAL).expect("pending update");
+    assert_eq!(flushed, page(&["a", "d"]));
+    assert!(coalescer.poll(start + GAMES_COALESCE_INTERVAL * 3).is_none());
+}
+
+#[test]
+fn forced_refresh_always_sends() {
+    let mut coalescer = GamesCoalescer::new();
+    let now = Instant::now();
     
-    // Now with auto-refresh enabled
-    let config = AppConfig {
-        auto_refresh: true,
-        games_finished: 0,
-    };
+    assert!(coalescer.offer(page(&["a"]), now).is_some());
+    assert_eq!(coalescer.force(page(&["a"]), now), page(&["a"]));
     
-    // A message should be sent when auto_refresh is true
-    if config.auto_refresh {
-        tx.send(UiToNet::RefreshGames).unwrap();
-    }
+    // A forced send also resets the baseline for pushed updates
+    assert!(coalescer.offer(page(&["a"]), now + GAMES_COALESCE_INTERVAL).is_none());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn headless_tick_does_not_poll() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
     
-    // Check if we received the RefreshGames message
-    let mut found_refresh = false;
-    while let Ok(msg) = rx.try_recv() {
-        if let UiToNet::RefreshGames = msg {
-            found_refresh = true;
-            break;
-        }
+    let (ui_tx, net_rx) = unbounded();
+    let (_net_tx, ui_rx) = unbounded();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    
+    for _ in 0..10 {
+        app.tick_headless();
     }
     
-    assert!(found_refresh, "Expected at least one RefreshGames message");
+    assert!(net_rx.try_recv().is_err(), "Ticking should not send RefreshGames");
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn scrolling_asks_for_the_next_page() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_network::game_list::{GamesQuery, GAMES_PAGE_SIZE};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    let ids: Vec<String> = (0..120).map(|i| format!("game-{}", i)).collect();
+    let listing = |offset: usize| GamesPage {
+        games: ids.iter().skip(offset).take(GAMES_PAGE_SIZE).map(|id| game(id)).collect(),
+        offset,
+        total: ids.len(),
+    };
+    
+    let query = GamesQuery { board_size: Some(9), ..GamesQuery::default() };
+    app.set_games_query(query);
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::RequestGamesPage { offset: 0, .. })));
+    net_tx.send(NetToUi::GamesUpdated { page: listing(0) }).unwrap();
+    app.tick_headless();
+    assert_eq!((app.listed_games().len(), app.games_total()), (GAMES_PAGE_SIZE, 120));
+    
+    app.request_more_games();
+    // Only one page is asked for at a time
+    app.request_more_games();
+    let asked: Vec<UiToNet> = net_rx.try_iter().collect();
+    assert!(matches!(&asked[..], [UiToNet::RequestGamesPage { query: q, offset }] if *q == query && *offset == GAMES_PAGE_SIZE));
+    net_tx.send(NetToUi::GamesUpdated { page: listing(GAMES_PAGE_SIZE) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.listed_games().len(), 2 * GAMES_PAGE_SIZE);
+    
+    // A pushed first page doesn't throw away what was scrolled to
+    net_tx.send(NetToUi::GamesUpdated { page: listing(0) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.listed_games().len(), 2 * GAMES_PAGE_SIZE);
 }
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Free placement in the board editor.
+
+use p2pgo_core::{Color, Coord};
+use p2pgo_ui_egui::editor_view::BoardEditor;
+
+#[test]
+fn clicks_cycle_and_play_needs_liberties() {
+    let mut editor = BoardEditor::new(9);
+    let corner = Coord::new(0, 0);
+    editor.cycle(corner);
+    editor.cycle(corner);
+    assert_eq!(editor.stones(), [(corner, Color::White)]);
+    editor.cycle(corner);
+    assert!(editor.stones().is_empty());
+
+    // White in the corner with no liberties can be drawn but not played from
+    editor.cycle(corner);
+    editor.cycle(corner);
+    editor.cycle(Coord::new(1, 0));
+    editor.cycle(Coord::new(0, 1));
+    assert_eq!(editor.position().board[0], Some(Color::White));
+    assert!(editor.to_game().is_err());
+
+    // Black → white → empty frees the liberty again
+    editor.cycle(Coord::new(0, 1));
+    editor.cycle(Coord::new(0, 1));
+    editor.to_move = Color::White;
+    let game = editor.to_game().unwrap();
+    assert_eq!(game.current_player, Color::White);
+    assert!(editor.export_sgf().contains("AB[ba]AW[aa]PL[W]"), "{}", editor.export_sgf());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn the_ai_plays_on_from_an_edited_position_without_ranking() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::Move;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.open_editor(9);
+    let editor = app.editor_mut().unwrap();
+    editor.cycle(Coord::new(2, 2));
+    editor.to_move = Color::White;
+    editor.komi = 0.5;
+    assert!(app.start_ai_from_editor());
+
+    // The player takes the side to move, here White
+    assert_eq!(app.get_current_view_debug(), "Game(ladder)");
+    let ladder = app.ladder().unwrap();
+    assert_eq!((ladder.human, ladder.komi, ladder.ranked), (Color::White, 0.5, false));
+    assert!(net_rx.try_recv().is_err(), "White moves first");
+
+    app.play_move(Move::Place(Coord::new(6, 6)));
+    match net_rx.try_recv().unwrap() {
+        UiToNet::LadderMove { state, .. } => {
+            assert_eq!(state.setup.len(), 1, "the engine sees the edited stones");
+            assert_eq!(state.board[Coord::new(2, 2).to_index(9)], Some(Color::Black));
+        }
+        other => panic!("expected LadderMove, got {:?}", other),
+    }
+
+    // The result leaves the ladder alone
+    net_tx.send(NetToUi::LadderMove { mv: Move::Resign }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.ladder().unwrap().won, Some(true));
+    assert_eq!(app.ui_config().ladder_rung, 0);
+    assert!(app.toast().unwrap().contains("from your position"));
+}
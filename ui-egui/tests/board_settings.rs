@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! A joined game is built from the host's settings, not our default size.
+
+#[cfg(feature = "headless")]
+#[test]
+fn joining_a_larger_game_builds_the_hosts_board() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::settings::GameSettings;
+    use p2pgo_core::{Color, Coord, GameEvent, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    // Headless apps default to 9x9
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: GameSettings::standard(19) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+
+    // A corner move off any 9x9 board
+    let moves = [
+        (Move::Place(Coord::new(15, 3)), Color::Black),
+        (Move::Place(Coord::new(3, 15)), Color::White),
+        (Move::Pass, Color::Black),
+    ];
+    for (mv, by) in moves {
+        net_tx.send(NetToUi::GameEvent { event: GameEvent::MoveMade { mv, by } }).unwrap();
+    }
+    app.tick_headless();
+
+    let state = app.get_current_game_state().unwrap();
+    assert_eq!(state.board_size, 19);
+    assert_eq!(state.moves.len(), 3);
+    assert_eq!(state.board[3 * 19 + 15], Some(Color::Black));
+    assert_eq!(app.get_error_msg(), None);
+}
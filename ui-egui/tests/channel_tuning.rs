@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! ACK watchdog overrides kept in the settings.
+
+use p2pgo_network::channel_tuning::ChannelTuning;
+use p2pgo_ui_egui::runtime_config::RuntimeConfig;
+
+#[test]
+fn games_follow_their_clock_unless_the_settings_override_it() {
+    let config = RuntimeConfig::parse(r#"{ "ghost_moves_after_games": 5 }"#).unwrap();
+    assert_eq!(config.channel_tuning, None);
+
+    let mut config = RuntimeConfig::default();
+    config.channel_tuning = Some(ChannelTuning { ack_timeout_ms: 1500, ..ChannelTuning::default() });
+    let saved = RuntimeConfig::parse(&config.to_json()).unwrap();
+    assert_eq!(saved.channel_tuning, config.channel_tuning);
+}
@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The clocks on screen: counting down smoothly between jittery snapshots,
+//! how they're written and the flash of a byo-yomi period used.
+
+use std::time::{Duration, Instant};
+use p2pgo_core::game_clock::{ClockSnapshot, PlayerTime};
+use p2pgo_core::Color;
+use p2pgo_ui_egui::clock_view::{self, ClockDisplay, PERIOD_FLASH, SNAP_THRESHOLD};
+
+fn snapshot(black_ms: u64) -> ClockSnapshot {
+    let white = PlayerTime { main_ms: 300_000, periods: 5, period_ms: 30_000 };
+    ClockSnapshot {
+        black: PlayerTime { main_ms: black_ms, ..white },
+        white,
+        running: Some(Color::Black),
+        paused: false,
+        byo_yomi_ms: 30_000,
+    }
+}
+
+#[test]
+fn jittered_snapshots_never_run_the_clock_backwards() {
+    // Black has been thinking since `start`; snapshots are taken every
+    // second and arrive up to 300ms late
+    let start = Instant::now();
+    let mut seed = 0x2545_f491_u64;
+    let mut jitter = || {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        Duration::from_millis((seed >> 33) % 300)
+    };
+    let truth = |at: Duration| 60_000u64.saturating_sub(at.as_millis() as u64);
+
+    let mut display = ClockDisplay::new(snapshot(60_000), start);
+    let mut pending = Vec::new();
+    let mut last_shown = display.time(Color::Black).main_ms;
+    let frame = Duration::from_millis(16);
+    let mut elapsed = Duration::ZERO;
+    while elapsed < Duration::from_secs(30) {
+        elapsed += frame;
+        if elapsed.as_millis() % 1000 < frame.as_millis() {
+            let taken = elapsed - Duration::from_millis(elapsed.as_millis() as u64 % 1000);
+            pending.push((elapsed + jitter(), snapshot(truth(taken))));
+        }
+        let now = start + elapsed;
+        let (arrived, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(at, _)| *at <= elapsed);
+        pending = waiting;
+        for (_, snapshot) in arrived {
+            display.update(snapshot, now);
+        }
+        display.tick(now);
+
+        let shown = display.time(Color::Black).main_ms;
+        assert!(shown <= last_shown, "counted up from {} to {} at {:?}", last_shown, shown, elapsed);
+        let error = (shown as i64 - truth(elapsed) as i64).unsigned_abs();
+        assert!(error <= 500, "{}ms off at {:?}", error, elapsed);
+        assert!(error < SNAP_THRESHOLD.as_millis() as u64);
+        last_shown = shown;
+    }
+}
+
+#[test]
+fn times_show_minutes_then_periods() {
+    let main = PlayerTime { main_ms: 293_400, periods: 5, period_ms: 30_000 };
+    assert_eq!(clock_view::format_time(main), "4:54");
+    assert!(!clock_view::is_low(main));
+
+    let byo_yomi = PlayerTime { main_ms: 0, periods: 3, period_ms: 9_200 };
+    assert_eq!(clock_view::format_time(byo_yomi), "0:10 (3)");
+    assert!(clock_view::is_low(byo_yomi));
+
+    // Without byo-yomi only the end of main time is low
+    let sudden_death = PlayerTime { main_ms: 9_000, periods: 0, period_ms: 0 };
+    assert_eq!(clock_view::format_time(sudden_death), "0:09");
+    assert!(clock_view::is_low(sudden_death));
+    assert!(!clock_view::is_low(PlayerTime { main_ms: 9_000, ..main }));
+}
+
+#[test]
+fn a_period_used_flashes_through_a_move() {
+    let start = Instant::now();
+    let mut display = ClockDisplay::new(snapshot(0), start);
+    assert!(!display.period_flash(Color::Black, start));
+    display.period_used(Color::Black, start);
+    assert!(display.period_flash(Color::Black, start + Duration::from_millis(500)));
+    assert!(!display.period_flash(Color::White, start));
+
+    // Black moves on straight away; the flash stays
+    let moved = ClockSnapshot { running: Some(Color::White), ..snapshot(0) };
+    display.update(moved, start + Duration::from_millis(600));
+    assert!(display.period_flash(Color::Black, start + Duration::from_millis(700)));
+    assert!(!display.period_flash(Color::Black, start + PERIOD_FLASH));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn clocks_follow_the_game() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::GameEvent;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: GameEvent::Clock { snapshot: snapshot(60_000) } }).unwrap();
+    app.tick_headless();
+    let clock = app.clock().expect("clocks once a snapshot arrives");
+    assert_eq!(clock.running(), Some(Color::Black));
+    assert!(clock.time(Color::Black).main_ms <= 60_000);
+
+    net_tx.send(NetToUi::GameLeft).unwrap();
+    app.tick_headless();
+    assert!(app.clock().is_none());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn periods_used_flash_the_clock_and_say_so() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::GameEvent;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: GameEvent::Clock { snapshot: snapshot(0) } }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: GameEvent::ByoYomiPeriodUsed { player: Color::Black, periods_left: 4 } }).unwrap();
+    app.tick_headless();
+    assert!(app.clock().unwrap().period_flash(Color::Black, Instant::now()));
+    assert!(app.toast().is_some_and(|toast| toast.contains("4 left")), "{:?}", app.toast());
+}
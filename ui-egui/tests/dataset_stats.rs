@@ -0,0 +1,44 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Inspecting a folder of training games from the training settings.
+
+#[cfg(feature = "headless")]
+#[test]
+fn typed_folder_is_inspected_and_its_stats_kept() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use trainer::{GoDataset, GoSample};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    app.inspect_dataset();
+    assert!(app.get_error_msg().is_some(), "no folder typed");
+    assert!(net_rx.try_recv().is_err());
+
+    app.set_dataset_input(" /data/games ");
+    app.inspect_dataset();
+    app.inspect_dataset();
+    match net_rx.try_recv() {
+        Ok(UiToNet::InspectDataset { dir }) => assert_eq!(dir, std::path::PathBuf::from("/data/games")),
+        other => panic!("expected an inspection, got {:?}", other),
+    }
+    assert!(net_rx.try_recv().is_err(), "one inspection at a time");
+
+    let stats = GoDataset::from_samples(vec![GoSample::empty(9, 40, 1.0)]).stats();
+    net_tx.send(NetToUi::DatasetInspected { result: Ok(stats.clone()) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.dataset_stats(), Some(&stats));
+
+    // A failed load is reported and allows another try
+    app.inspect_dataset();
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::InspectDataset { .. })));
+    net_tx.send(NetToUi::DatasetInspected { result: Err("Path does not exist".to_string()) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.dataset_stats(), None);
+    assert!(app.get_error_msg().unwrap().contains("Path does not exist"));
+    app.inspect_dataset();
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::InspectDataset { .. })));
+}
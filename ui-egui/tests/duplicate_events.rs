@@ -0,0 +1,107 @@
+//! Test that duplicated and out-of-order move events are applied idempotently
+//! SPDX-License-Identifier: MIT OR Apache-2.0
+
+use p2pgo_core::{Color, Coord, GameState, Move};
+use p2pgo_ui_egui::event_filter::{apply_move_event, position_hash, should_accept_snapshot, MoveOutcome};
+
+#[test]
+fn duplicate_move_is_dropped() {
+    let mut state = GameState::new(9);
+    let mv = Move::Place(Coord::new(4, 4));
+    
+    assert_eq!(apply_move_event(&mut state, &mv, Color::Black), MoveOutcome::Applied);
+    let hash = position_hash(&state);
+    
+    // Same move delivered again by the second transport
+    assert_eq!(apply_move_event(&mut state, &mv, Color::Black), MoveOutcome::Duplicate);
+    assert_eq!(position_hash(&state), hash);
+    assert_eq!(state.moves.len(), 1);
+}
+
+#[test]
+fn out_of_order_move_is_dropped() {
+    let mut state = GameState::new(9);
+    
+    // White's reply arrives before Black's move
+    let white = Move::Place(Coord::new(2, 2));
+    assert_eq!(apply_move_event(&mut state, &white, Color::White), MoveOutcome::OutOfOrder);
+    assert!(state.moves.is_empty());
+    
+    // A move onto an occupied point does not advance the state either
+    apply_move_event(&mut state, &Move::Place(Coord::new(4, 4)), Color::Black);
+    let occupied = Move::Place(Coord::new(4, 4));
+    assert_eq!(apply_move_event(&mut state, &occupied, Color::White), MoveOutcome::OutOfOrder);
+    assert_eq!(state.moves.len(), 1);
+}
+
+#[test]
+fn stale_snapshot_is_ignored() {
+    let mut current = GameState::new(9);
+    current.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    current.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+    
+    let mut stale = GameState::new(9);
+    stale.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    assert!(!should_accept_snapshot(&current, &stale));
+    assert!(!should_accept_snapshot(&current, &current.clone()));
+    
+    let mut newer = current.clone();
+    newer.apply_move(Move::Pass).unwrap();
+    assert!(should_accept_snapshot(&current, &newer));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn app_recovers_from_duplicated_and_reordered_events() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::GameEvent;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    
+    let moves = [
+        (Move::Place(Coord::new(4, 4)), Color::Black),
+        (Move::Place(Coord::new(2, 2)), Color::White),
+        (Move::Place(Coord::new(6, 6)), Color::Black),
+    ];
+    let made = |i: usize| NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: moves[i].0.clone(), by: moves[i].1 },
+    };
+    
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(made(0)).unwrap();
+    net_tx.send(made(0)).unwrap();
+    net_tx.send(made(2)).unwrap();
+    net_tx.send(made(1)).unwrap();
+    app.tick_headless();
+    
+    // Joining mid-game and the reordered move both ask for a snapshot, once
+    let requests = net_rx
+        .try_iter()
+        .filter(|msg| matches!(msg, UiToNet::GetGameState { .. }))
+        .count();
+    assert_eq!(requests, 1);
+    
+    // The worker answers with the authoritative state
+    let mut full = GameState::new(9);
+    for (mv, _) in &moves {
+        full.apply_move(mv.clone()).unwrap();
+    }
+    net_tx.send(NetToUi::GameStateSnapshot {
+        game_id: "game-1".to_string(),
+        state: full.clone(),
+    }).unwrap();
+    
+    // Late duplicates after the snapshot change nothing
+    net_tx.send(made(2)).unwrap();
+    app.tick_headless();
+    
+    let state = app.get_current_game_state().expect("in game");
+    assert_eq!(state.board, full.board);
+    assert_eq!(state.moves.len(), 3);
+    assert_eq!(app.get_error_msg(), None);
+}
@@ -17,11 +17,17 @@ use p2pgo_ui_egui::{
     worker,
 };
 use p2pgo_core::{Move, Coord, Color, GameState};
+use p2pgo_network::lobby::Lobby;
 
 /// Headless test runner for AI integration
 struct E2ETestRunner {
     app: App,
     ui_tx: crossbeam_channel::Sender<UiToNet>,
+    /// The worker's lobby, where the test seats its opponent
+    #[allow(dead_code)]
+    lobby: Lobby,
+    #[allow(dead_code)]
+    data_dir: tempfile::TempDir,
     #[allow(dead_code)]
     timeout_ms: u64,
 }
@@ -31,11 +37,14 @@ impl E2ETestRunner {
     fn new() -> anyhow::Result<Self> {
         let (ui_tx, net_rx) = unbounded();
         let (net_tx, ui_rx) = unbounded();
+        let lobby = Lobby::new();
+        let data_dir = tempfile::tempdir()?;
         
         // Spawn background worker
+        let (worker_lobby, worker_dir) = (lobby.clone(), data_dir.path().to_path_buf());
         let _worker_handle = std::thread::spawn(move || {
             println!("Worker thread starting...");
-            if let Err(e) = worker::start(net_rx, net_tx) {
+            if let Err(e) = worker::start_in_process(net_rx, net_tx, "HeadlessPlayer".to_string(), worker_lobby, worker_dir) {
                 eprintln!("Worker error: {}", e);
             }
             println!("Worker thread exiting");
@@ -73,6 +82,8 @@ impl E2ETestRunner {
         Ok(Self {
             app,
             ui_tx,
+            lobby,
+            data_dir,
             timeout_ms: 5000, // 5 second timeout
         })
     }
@@ -83,18 +94,18 @@ impl E2ETestRunner {
         
         // Step 1: Create a game
         println!("Step 1: Creating game");
-        self.ui_tx.send(UiToNet::CreateGame { board_size: 9 })?;
+        self.ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() })?;
         
         // Wait for transition to Lobby state
         self.wait_for_view_transition("Lobby", 2000)?;
         
-        // Step 2: Make initial move to transition to game state
-        println!("Step 2: Making first move to activate game (Black D4)");
+        // Step 2: An opponent sits down, which starts the game
+        println!("Step 2: Seating an opponent, then playing Black D4");
+        self.seat_opponent()?;
+        self.wait_for_view_transition("Game", 2000)?;
         let first_move = Move::Place(Coord::new(3, 3)); // D4
         self.ui_tx.send(UiToNet::MakeMove { mv: first_move.clone(), board_size: None })?;
-        
-        // Wait for transition to Game state
-        self.wait_for_view_transition("Game", 2000)?;
+        self.wait_and_process(500);
         
         // Step 3: Verify game state after first move
         println!("Step 3: Verifying game state after first move");
@@ -161,7 +172,7 @@ impl E2ETestRunner {
         // Step 1: Create a game
         println!("Step 1: Creating game");
         println!("  Sending CreateGame message...");
-        self.ui_tx.send(UiToNet::CreateGame { board_size: 9 })?;
+        self.ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() })?;
         
         // Give the worker a moment to process the message
         println!("  Allowing time for message processing...");
@@ -177,6 +188,34 @@ impl E2ETestRunner {
         Ok(())
     }
     
+    /// Take the opponent's seat of the game waiting in the lobby view
+    #[cfg(feature = "headless")]
+    fn seat_opponent(&mut self) -> anyhow::Result<()> {
+        use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+        
+        let view = self.app.get_current_view_debug();
+        let game_id = view.strip_prefix("Lobby(").and_then(|rest| rest.strip_suffix(')'))
+            .ok_or_else(|| anyhow::anyhow!("No game waiting in view {}", view))?
+            .to_string();
+        let opponent = PlayerProfile {
+            node_id: "opponent".to_string(),
+            name: "Opponent".to_string(),
+            guild: None,
+            self_reported_rating: None,
+        };
+        // The runner may itself be on a runtime's blocking thread
+        let lobby = &self.lobby;
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                tokio::runtime::Runtime::new()?.block_on(async {
+                    lobby.set_join_policy(&game_id, JoinPolicy::Everyone).await?;
+                    lobby.request_join(&game_id, opponent).await?;
+                    Ok::<(), anyhow::Error>(())
+                })
+            }).join().expect("seating thread panicked")
+        })
+    }
+    
     #[cfg(feature = "headless")]
     fn wait_for_view_transition(&mut self, expected_view_prefix: &str, timeout_ms: u64) -> anyhow::Result<()> {
         let start = std::time::Instant::now();
@@ -349,9 +388,9 @@ fn test_ai_message_types() {
     // Verify that AI-related message types compile and work correctly
     let ghost_request = UiToNet::GetGhostMoves;
     let ghost_response = NetToUi::GhostMoves(vec![
-        Coord::new(3, 3),
-        Coord::new(4, 4),
-        Coord::new(5, 5),
+        Move::Place(Coord::new(3, 3)),
+        Move::Place(Coord::new(4, 4)),
+        Move::Pass,
     ]);
     
     // Test message serialization concepts
@@ -363,7 +402,7 @@ fn test_ai_message_types() {
     match ghost_response {
         NetToUi::GhostMoves(coords) => {
             assert_eq!(coords.len(), 3);
-            assert_eq!(coords[0], Coord::new(3, 3));
+            assert_eq!(coords[0], Move::Place(Coord::new(3, 3)));
             println!("GhostMoves response type works with {} coordinates", coords.len());
         }
         _ => panic!("Unexpected response type"),
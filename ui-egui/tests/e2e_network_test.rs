@@ -52,7 +52,7 @@ mod iroh_tests {
         tokio::time::sleep(Duration::from_secs(2)).await;
         
         // Player 1 creates a 9x9 game
-        ui_tx1.send(UiToNet::CreateGame { board_size: 9 }).expect("Failed to send CreateGame");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).expect("Failed to send CreateGame");
         
         // Both players should eventually see a GameJoined event
         let mut player1_joined = false;
@@ -88,9 +88,9 @@ mod iroh_tests {
         tokio::time::sleep(Duration::from_millis(500)).await;
         
         // Create games for different board sizes
-        ui_tx1.send(UiToNet::CreateGame { board_size: 9 }).expect("Failed to create 9x9 game");
-        ui_tx1.send(UiToNet::CreateGame { board_size: 13 }).expect("Failed to create 13x13 game");
-        ui_tx1.send(UiToNet::CreateGame { board_size: 19 }).expect("Failed to create 19x19 game");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).expect("Failed to create 9x9 game");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 13, rule_config: Default::default() }).expect("Failed to create 13x13 game");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 19, rule_config: Default::default() }).expect("Failed to create 19x19 game");
         
         // Should receive GameJoined for all three
         let mut games_joined = 0;
@@ -110,7 +110,7 @@ mod iroh_tests {
         assert_eq!(games_joined, 3, "Should have joined 3 games for different board sizes");
         
         // Try to create another 9x9 game (should fail - already have one)
-        ui_tx1.send(UiToNet::CreateGame { board_size: 9 }).expect("Failed to send second 9x9 game request");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).expect("Failed to send second 9x9 game request");
         
         // Should receive an error
         let mut received_error = false;
@@ -129,13 +129,13 @@ mod iroh_tests {
     
     #[tokio::test]
     async fn test_move_with_board_size() {
-        // Test making moves with board_size parameter
+        // Moves with a board_size parameter reach that game
         let (ui_tx1, ui_rx1, _handle1) = setup_worker("Player1", 9);
         
         tokio::time::sleep(Duration::from_millis(500)).await;
         
         // Create a 13x13 game
-        ui_tx1.send(UiToNet::CreateGame { board_size: 13 }).expect("Failed to create 13x13 game");
+        ui_tx1.send(UiToNet::CreateGame { board_size: 13, rule_config: Default::default() }).expect("Failed to create 13x13 game");
         
         // Wait for game creation
         tokio::time::sleep(Duration::from_millis(500)).await;
@@ -143,27 +143,27 @@ mod iroh_tests {
         // Make a move specifying the board size
         let test_move = Move::Place(Coord::new(6, 6)); // Center of 13x13 board
         ui_tx1.send(UiToNet::MakeMove { 
-            mv: test_move.clone(), 
+            mv: test_move,
             board_size: Some(13) 
         }).expect("Failed to send move");
         
-        // Should receive a game event
-        let mut received_move_event = false;
+        // No move is played before an opponent takes the seat
+        let mut refused = false;
         for _ in 0..20 {
-            if let Ok(msg) = ui_rx1.try_recv() {
-                if let NetToUi::GameEvent { event } = msg {
-                    if let p2pgo_core::GameEvent::MoveMade { mv, .. } = event {
-                        if mv == test_move {
-                            received_move_event = true;
-                            break;
-                        }
-                    }
+            match ui_rx1.try_recv() {
+                Ok(NetToUi::GameError { .. }) => {
+                    refused = true;
+                    break;
+                }
+                Ok(NetToUi::GameEvent { event: p2pgo_core::GameEvent::MoveMade { mv, .. } }) => {
+                    panic!("{:?} was played without an opponent", mv);
                 }
+                _ => {}
             }
             tokio::time::sleep(Duration::from_millis(100)).await;
         }
         
-        assert!(received_move_event, "Should receive move event for the played move");
+        assert!(refused, "Should refuse the move until an opponent joins");
     }
 }
 
@@ -197,7 +197,7 @@ mod stub_tests {
         // Test basic operations
         ui_tx.send(UiToNet::GetNodeId).expect("Failed to send GetNodeId");
         ui_tx.send(UiToNet::GetTicket).expect("Failed to send GetTicket");
-        ui_tx.send(UiToNet::CreateGame { board_size: 9 }).expect("Failed to send CreateGame");
+        ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).expect("Failed to send CreateGame");
         
         // Should receive responses
         let mut received_node_id = false;
@@ -233,7 +233,7 @@ mod stub_tests {
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         
         // Create a game first
-        ui_tx.send(UiToNet::CreateGame { board_size: 9 }).expect("Failed to send CreateGame");
+        ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).expect("Failed to send CreateGame");
         
         // Wait for game creation
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
@@ -241,26 +241,26 @@ mod stub_tests {
         // Send a move
         let test_move = Move::Place(Coord::new(3, 3));
         ui_tx.send(UiToNet::MakeMove { 
-            mv: test_move.clone(), 
+            mv: test_move,
             board_size: None // Use default board size
         }).expect("Failed to send move");
         
-        // Should receive a game event
-        let mut received_move_event = false;
+        // No move is played before an opponent takes the seat
+        let mut refused = false;
         for _ in 0..20 {
-            if let Ok(msg) = ui_rx.try_recv() {
-                if let NetToUi::GameEvent { event } = msg {
-                    if let p2pgo_core::GameEvent::MoveMade { mv, .. } = event {
-                        if mv == test_move {
-                            received_move_event = true;
-                            break;
-                        }
-                    }
+            match ui_rx.try_recv() {
+                Ok(NetToUi::GameError { .. }) => {
+                    refused = true;
+                    break;
+                }
+                Ok(NetToUi::GameEvent { event: p2pgo_core::GameEvent::MoveMade { mv, .. } }) => {
+                    panic!("{:?} was played without an opponent", mv);
                 }
+                _ => {}
             }
             tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
         }
         
-        assert!(received_move_event, "Should receive move event for the played move");
+        assert!(refused, "Should refuse the move until an opponent joins");
     }
 }
@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! How the app reacts to the end of a game, as the channel's phases announce it.
+
+#[cfg(feature = "headless")]
+fn app_after(moves: &[p2pgo_core::Move]) -> (p2pgo_ui_egui::app::App, crossbeam_channel::Sender<p2pgo_ui_egui::msg::NetToUi>) {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::phase::GamePhase;
+    use p2pgo_core::{GameEvent, GameState};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+
+    // Events as the game channel emits them, without a worker to score the game
+    let mut state = GameState::new(9);
+    let mut phase = GamePhase::Active;
+    net_tx.send(NetToUi::GameEvent { event: GameEvent::PhaseChanged { phase: phase.clone() } }).unwrap();
+    for mv in moves {
+        for event in state.apply_move_events(mv.clone()).unwrap() {
+            let next = phase.after(&event);
+            net_tx.send(NetToUi::GameEvent { event }).unwrap();
+            if let Some(next) = next {
+                phase = next.clone();
+                net_tx.send(NetToUi::GameEvent { event: GameEvent::PhaseChanged { phase: next } }).unwrap();
+            }
+        }
+    }
+    app.tick_headless();
+    (app, net_tx)
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn double_pass_and_resignation_open_the_score_dialog() {
+    use p2pgo_core::{Coord, Move};
+
+    let (app, _net_tx) = app_after(&[Move::Place(Coord::new(4, 4)), Move::Pass, Move::Pass]);
+    assert_eq!(app.get_current_view_debug(), "ScoreDialog");
+    assert_eq!(app.get_error_msg(), None);
+
+    let (app, _net_tx) = app_after(&[Move::Place(Coord::new(4, 4)), Move::Resign]);
+    assert_eq!(app.get_current_view_debug(), "ScoreDialog");
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn forfeit_shows_the_result() {
+    use p2pgo_core::phase::{GamePhase, GameResult};
+    use p2pgo_core::{Color, Coord, EndReason, GameEvent, Move};
+    use p2pgo_ui_egui::msg::NetToUi;
+
+    let (mut app, net_tx) = app_after(&[Move::Place(Coord::new(4, 4))]);
+    let reason = EndReason::Forfeit("opponent stopped responding".to_string());
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::GameEnded { winner: Some(Color::Black), score_diff: 0.0, reason: reason.clone(), scores: None },
+    }).unwrap();
+    let result = GameResult { winner: Some(Color::Black), reason, score_diff: 0.0 };
+    net_tx.send(NetToUi::GameEvent { event: GameEvent::PhaseChanged { phase: GamePhase::Finished { result } } }).unwrap();
+    app.tick_headless();
+    assert_eq!(
+        app.get_error_msg().as_deref(),
+        Some("Game over: forfeit (opponent stopped responding). Black wins.")
+    );
+}
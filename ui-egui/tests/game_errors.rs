@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Structured game errors and the feedback they get in the UI.
+
+use p2pgo_core::{Coord, GameError, Move};
+use p2pgo_ui_egui::messages::game_error_message;
+use p2pgo_ui_egui::msg::GameErrorKind;
+
+#[test]
+fn rule_errors_keep_the_point_that_was_tried() {
+    let mv = Move::Place(Coord::new(2, 3));
+    let kind = GameErrorKind::from_rules(&GameError::KoViolation, &mv);
+    assert_eq!(kind, GameErrorKind::KoViolation { at: Coord::new(2, 3) });
+    assert_eq!(kind.illegal_at(), Some(Coord::new(2, 3)));
+    assert!(game_error_message(&kind, 9).contains("C6"), "{}", game_error_message(&kind, 9));
+
+    // Without a point there is nothing to show on the board
+    let late = GameErrorKind::from_rules(&GameError::InvalidMove("Game is over".into()), &Move::Pass);
+    assert_eq!(late.illegal_at(), None);
+    assert_eq!(game_error_message(&late, 9), "Illegal move: Game is over");
+
+    let kind = GameErrorKind::SendFailed { reason: "connection lost".into() };
+    let json = serde_json::to_string(&kind).unwrap();
+    assert_eq!(serde_json::from_str::<GameErrorKind>(&json).unwrap(), kind);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn ko_violation_flashes_the_board_instead_of_the_banner() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, GameEvent};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+
+    let ko = Coord::new(1, 1);
+    net_tx.send(NetToUi::GameError {
+        game_id: "game-1".to_string(),
+        error: GameErrorKind::KoViolation { at: ko },
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.board_flash(), Some(ko));
+    assert_eq!(app.get_error_msg(), None);
+
+    // Failures that aren't about a point still open the banner
+    net_tx.send(NetToUi::GameError {
+        game_id: "game-1".to_string(),
+        error: GameErrorKind::SendFailed { reason: "connection lost".into() },
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.get_error_msg().as_deref(), Some("Failed to send move: connection lost"));
+}
@@ -3,7 +3,7 @@
 //! Ghost overlay GUI tests (headless)
 
 use p2pgo_ui_egui::{board_widget::BoardWidget, msg::NetToUi};
-use p2pgo_core::{Coord, GameState};
+use p2pgo_core::{Coord, GameState, Move};
 
 /// Mock headless app for testing
 pub struct HeadlessApp {
@@ -31,7 +31,11 @@ impl HeadlessApp {
     
     pub fn process_net_message(&mut self, msg: NetToUi) {
         match msg {
-            NetToUi::GhostMoves(coords) => {
+            NetToUi::GhostMoves(moves) => {
+                let coords = moves.into_iter().filter_map(|mv| match mv {
+                    Move::Place(coord) => Some(coord),
+                    _ => None,
+                }).collect();
                 self.board_widget.set_ghost_stones(coords);
             }
             _ => {} // Ignore other messages for this test
@@ -65,7 +69,7 @@ fn test_ghost_overlay_rendering() {
     app.inject_legal_moves(moves.clone());
     
     // Send mock ghost moves message
-    let ghost_msg = NetToUi::GhostMoves(moves);
+    let ghost_msg = NetToUi::GhostMoves(moves.into_iter().map(Move::Place).collect());
     app.process_net_message(ghost_msg);
     
     // Render one frame and check ghost count
@@ -91,11 +95,11 @@ fn test_multiple_ghost_updates() {
     let mut app = HeadlessApp::new_headless();
     
     // First set of ghost stones
-    let moves1 = vec![Coord::new(1, 1), Coord::new(2, 2)];
+    let moves1 = vec![Move::Place(Coord::new(1, 1)), Move::Place(Coord::new(2, 2))];
     app.process_net_message(NetToUi::GhostMoves(moves1));
     
     // Second set of ghost stones (should replace first)
-    let moves2 = vec![Coord::new(7, 7), Coord::new(8, 8)];
+    let moves2 = vec![Move::Place(Coord::new(7, 7)), Move::Place(Coord::new(8, 8))];
     app.process_net_message(NetToUi::GhostMoves(moves2));
     
     let ghost_count = app.render_frame();
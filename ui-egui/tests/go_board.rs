@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The embeddable board widget, driven by raw egui input.
+
+use egui::{Event, PointerButton, Pos2, RawInput, Rect, Vec2};
+use p2pgo_core::{Coord, GameState};
+use p2pgo_ui_egui::go_board::{BoardGeometry, GoBoardWidget};
+
+/// Show a board at the screen's top-left for a frame with `events`
+fn frame(ctx: &egui::Context, interactive: bool, events: Vec<Event>) -> Option<Coord> {
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, Vec2::splat(600.0))),
+        events,
+        ..Default::default()
+    };
+    let mut clicked = None;
+    let _ = ctx.run(input, |ctx| {
+        egui::Area::new("board").fixed_pos(Pos2::ZERO).show(ctx, |ui| {
+            clicked = GoBoardWidget::new(9).interactive(interactive).show(ui, &GameState::new(9)).clicked;
+        });
+    });
+    clicked
+}
+
+fn click_at(ctx: &egui::Context, interactive: bool, pos: Pos2) -> Option<Coord> {
+    let button = |pressed| Event::PointerButton { pos, button: PointerButton::Primary, pressed, modifiers: Default::default() };
+    frame(ctx, interactive, vec![Event::PointerMoved(pos)]);
+    frame(ctx, interactive, vec![button(true)]);
+    frame(ctx, interactive, vec![button(false)])
+}
+
+#[test]
+fn clicks_land_on_the_nearest_point() {
+    let geometry = BoardGeometry::new(9, 30.0, Rect::from_min_size(Pos2::ZERO, Vec2::splat(280.0)));
+    assert_eq!(geometry.pos(Coord::new(0, 0)), Pos2::new(20.0, 20.0));
+    assert_eq!(geometry.coord_at(Pos2::new(20.0 + 2.0 * 30.0 + 10.0, 20.0 + 4.0 * 30.0 - 10.0)), Some(Coord::new(2, 4)));
+    assert_eq!(geometry.coord_at(Pos2::new(5.0, 5.0)), None, "the margin is off the board");
+
+    let ctx = egui::Context::default();
+    assert_eq!(click_at(&ctx, true, geometry.pos(Coord::new(3, 5))), Some(Coord::new(3, 5)));
+    // A static board never reports clicks
+    assert_eq!(click_at(&ctx, false, geometry.pos(Coord::new(3, 5))), None);
+}
+
+#[test]
+fn widget_stands_alone() {
+    // Embedders get the board without the app behind it
+    let source = include_str!("../src/go_board.rs");
+    for module in ["app", "msg", "worker"] {
+        assert!(!source.contains(&format!("crate::{}", module)), "go_board uses crate::{}", module);
+    }
+}
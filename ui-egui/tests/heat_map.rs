@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Heat map and ownership caching, and which map the board is allowed to draw.
+
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_ui_egui::heat_map::{from_logits, position_hash, HeatMapCache, HeatMapOverlay};
+
+#[test]
+fn cache_runs_one_inference_per_position() {
+    let mut cache = HeatMapCache::new(2);
+    let mut inferences = 0;
+    let mut lookup = |cache: &mut HeatMapCache, hash: u64| {
+        cache
+            .get_or_compute(hash, || {
+                inferences += 1;
+                Ok(vec![hash as f32])
+            })
+            .unwrap()
+            .to_vec()
+    };
+
+    assert_eq!(lookup(&mut cache, 1), vec![1.0]);
+    assert_eq!(lookup(&mut cache, 1), vec![1.0]);
+    assert_eq!(lookup(&mut cache, 2), vec![2.0]);
+    assert_eq!(lookup(&mut cache, 2), vec![2.0]);
+    // A third position pushes out the oldest
+    lookup(&mut cache, 3);
+    lookup(&mut cache, 1);
+    assert_eq!(inferences, 4);
+    assert!(cache.get(2).is_none());
+}
+
+#[test]
+fn failed_inference_is_not_cached() {
+    let mut cache = HeatMapCache::new(4);
+    assert!(cache.get_or_compute(7, || Err(anyhow::anyhow!("no model"))).is_err());
+    assert!(cache.get(7).is_none());
+}
+
+#[test]
+fn map_covers_empty_points_only() {
+    let mut state = GameState::new(5);
+    state.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+
+    // The 5x5 board sits in the middle of the model's 9x9 input
+    let mut logits = vec![0.0; 81];
+    logits[3 * 9 + 4] = 2.0; // column 2, row 1 of the small board
+    let map = from_logits(&logits, &state);
+
+    assert_eq!(map.len(), 25);
+    assert_eq!(map[2 * 5 + 2], 0.0);
+    assert!((map.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+    let likeliest = map.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap().0;
+    assert_eq!(likeliest, 5 + 2);
+}
+
+#[test]
+fn pass_logit_is_not_part_of_the_map() {
+    let state = GameState::new(9);
+    let mut logits = vec![0.0; 82];
+    logits[40] = 1.0;
+    let without_pass = from_logits(&logits, &state);
+    logits[81] = 50.0;
+    let with_pass = from_logits(&logits, &state);
+
+    assert_eq!(with_pass.len(), 81);
+    assert_eq!(with_pass, without_pass);
+}
+
+#[test]
+fn stale_maps_are_never_drawn_over_a_newer_position() {
+    let mut state = GameState::new(9);
+    let mut overlay = HeatMapOverlay::default();
+    let before = position_hash(&state);
+    assert!(overlay.want(before));
+    assert!(!overlay.want(before), "same position is only requested once");
+    assert!(overlay.is_computing());
+
+    state.apply_move(Move::Place(Coord::new(4, 4))).unwrap();
+    let after = position_hash(&state);
+    assert_ne!(before, after);
+    assert!(overlay.want(after));
+
+    // The map for the old position arrives late
+    overlay.receive(before, vec![1.0; 81]);
+    assert_eq!(overlay.current(), None);
+    assert!(overlay.is_computing());
+
+    overlay.receive(after, vec![0.5; 81]);
+    assert_eq!(overlay.current().map(|m| m[0]), Some(0.5));
+
+    // Moving on hides the map that was current
+    state.apply_move(Move::Pass).unwrap();
+    assert!(overlay.want(position_hash(&state)));
+    assert_eq!(overlay.current(), None);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn app_requests_each_position_once_and_draws_only_its_map() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, GameEvent};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+
+    let requests = |rx: &crossbeam_channel::Receiver<UiToNet>| -> Vec<u64> {
+        rx.try_iter()
+            .filter_map(|msg| match msg {
+                UiToNet::RequestHeatMap { position_hash, .. } => Some(position_hash),
+                _ => None,
+            })
+            .collect()
+    };
+
+    app.set_heat_map(true);
+    app.tick_headless();
+    app.tick_headless();
+    let first = requests(&net_rx);
+    assert_eq!(first.len(), 1);
+
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(2, 2)), by: Color::White },
+    }).unwrap();
+    app.tick_headless();
+    app.tick_headless();
+    let second = requests(&net_rx);
+    assert_eq!(second.len(), 1);
+    assert_ne!(second[0], first[0]);
+
+    net_tx.send(NetToUi::HeatMap { position_hash: first[0], map: vec![1.0; 81] }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.drawn_heat_map(), None);
+
+    net_tx.send(NetToUi::HeatMap { position_hash: second[0], map: vec![0.25; 81] }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.drawn_heat_map(), Some(vec![0.25; 81]));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn ownership_is_requested_apart_from_the_heat_map() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, GameEvent};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+
+    app.set_ownership(true);
+    app.tick_headless();
+    app.tick_headless();
+    let requests: Vec<UiToNet> = net_rx.try_iter()
+        .filter(|msg| matches!(msg, UiToNet::RequestOwnership { .. } | UiToNet::RequestHeatMap { .. }))
+        .collect();
+    assert_eq!(requests.len(), 1);
+    let UiToNet::RequestOwnership { position_hash, .. } = requests[0] else {
+        panic!("expected an ownership request, got {:?}", requests[0]);
+    };
+
+    // A heat map for the same position doesn't land in the ownership overlay
+    net_tx.send(NetToUi::HeatMap { position_hash, map: vec![0.5; 81] }).unwrap();
+    net_tx.send(NetToUi::Ownership { position_hash, map: vec![-1.0; 81] }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.drawn_ownership(), Some(vec![-1.0; 81]));
+    assert_eq!(app.drawn_heat_map(), None);
+
+    app.set_ownership(false);
+    assert_eq!(app.drawn_ownership(), None);
+}
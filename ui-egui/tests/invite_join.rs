@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Pasting an invite link joins the game it names; a bare ticket still just connects.
+
+#[cfg(feature = "headless")]
+#[test]
+fn pasted_invite_joins_its_game() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_network::invite::Invite;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    let link = Invite::new("ticket-abc".to_string(), "game-2".to_string(), 9, "alice".to_string()).to_link();
+    app.connect_with(&format!(" {}\n", link));
+    match net_rx.try_recv() {
+        Ok(UiToNet::JoinInvite { invite }) => {
+            assert_eq!(invite.game_id, "game-2");
+            assert_eq!(invite.ticket, "ticket-abc");
+        }
+        other => panic!("expected an invite join, got {:?}", other),
+    }
+
+    // A link cut short is reported instead of connecting anywhere
+    app.connect_with(&link[..link.len() - 3]);
+    assert!(net_rx.try_recv().is_err());
+    assert!(app.get_error_msg().unwrap().contains("damaged"));
+
+    app.connect_with("ticket-abc");
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::ConnectByTicket { ticket }) if ticket == "ticket-abc"));
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Confirming a lobby game's terms before joining it.
+
+#[cfg(feature = "headless")]
+mod confirm {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::settings::RULESET_VERSION;
+    use p2pgo_network::lobby::{GameInfo, GameListingState, GameTerms};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    fn game(ruleset_version: u8) -> GameInfo {
+        GameInfo {
+            id: "game-1".to_string(),
+            name: None,
+            board_size: 9,
+            state: GameListingState::OPEN,
+            needs_password: false,
+            correspondence: false,
+            teacher: None,
+            terms: GameTerms { ruleset_version, ..GameTerms::standard(9) },
+        }
+    }
+
+    #[test]
+    fn confirmed_games_are_joined() {
+        let (ui_tx, net_rx) = unbounded::<UiToNet>();
+        let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+        let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+        app.ask_to_join(game(RULESET_VERSION));
+        assert_eq!(app.join_confirmation().map(|g| g.id.as_str()), Some("game-1"));
+        app.answer_join_confirmation(true);
+        assert!(app.join_confirmation().is_none());
+        assert!(net_rx.try_iter().any(|msg| matches!(msg, UiToNet::JoinGame { game_id } if game_id == "game-1")));
+    }
+
+    #[test]
+    fn unsupported_rulesets_are_not_joined() {
+        let (ui_tx, net_rx) = unbounded::<UiToNet>();
+        let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+        let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+        app.ask_to_join(game(RULESET_VERSION + 1));
+        app.answer_join_confirmation(true);
+        assert!(!net_rx.try_iter().any(|msg| matches!(msg, UiToNet::JoinGame { .. })));
+        let error = app.get_error_msg().expect("the player is told why");
+        assert!(error.contains("ruleset version"), "{}", error);
+    }
+}
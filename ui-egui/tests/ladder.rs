@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The solo ladder: unlocking rungs and keeping them unlocked.
+
+use p2pgo_core::ladder::RUNGS;
+use p2pgo_ui_egui::ui_config::UiConfig;
+
+#[cfg(feature = "headless")]
+#[test]
+fn a_win_unlocks_the_next_rung_and_a_loss_does_not() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ui_config.json");
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.set_ui_config_path(path.clone());
+
+    // Only the first rung is open at the start
+    app.start_ladder(1);
+    assert!(app.ladder().is_none());
+    app.start_ladder(0);
+    assert_eq!(app.get_current_view_debug(), "Game(ladder)");
+    assert!(net_rx.try_recv().is_err(), "the player has Black and moves first");
+
+    app.play_move(Move::Place(Coord::new(4, 4)));
+    match net_rx.try_recv().unwrap() {
+        UiToNet::LadderMove { rung: 0, state, .. } => assert_eq!(state.moves.len(), 1),
+        other => panic!("expected LadderMove, got {:?}", other),
+    }
+    // The board waits while the opponent thinks
+    app.play_move(Move::Place(Coord::new(2, 2)));
+    assert_eq!(app.get_current_game_state().unwrap().moves.len(), 1);
+    net_tx.send(NetToUi::LadderMove { mv: Move::Place(Coord::new(6, 6)) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.get_current_game_state().unwrap().moves.len(), 2);
+
+    // Resigning loses and unlocks nothing
+    app.play_move(Move::Resign);
+    assert_eq!(app.ladder().unwrap().won, Some(false));
+    assert_eq!(app.ui_config().ladder_rung, 0);
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::RequestGameReport { .. })), "the report looks for the biggest mistake");
+    assert_eq!(UiConfig::load(&path).ladder_rung, 0);
+
+    // The opponent resigning is a win, which unlocks the next rung
+    app.start_ladder(0);
+    app.play_move(Move::Place(Coord::new(4, 4)));
+    assert!(matches!(net_rx.try_recv(), Ok(UiToNet::LadderMove { .. })));
+    net_tx.send(NetToUi::LadderMove { mv: Move::Resign }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.ladder().unwrap().won, Some(true));
+    assert_eq!(app.ui_config().ladder_rung, 1);
+    let toast = app.toast().unwrap();
+    assert!(toast.contains(&format!("{} is unlocked", RUNGS[1].name)), "{}", toast);
+    assert_eq!(UiConfig::load(&path).ladder_rung, 1, "progress survives a restart");
+
+    app.start_ladder(1);
+    assert_eq!(app.ladder().unwrap().rung, 1);
+}
+
+#[test]
+fn wins_only_unlock_the_rung_above() {
+    let mut config = UiConfig::default();
+    assert!(!config.record_ladder(0, false));
+    assert!(config.record_ladder(0, true));
+    assert_eq!(config.ladder_rung, 1);
+
+    // Winning a lower rung again changes nothing
+    assert!(!config.record_ladder(0, true));
+    assert_eq!(config.ladder_rung, 1);
+
+    // Beating the top rung has nothing left to unlock
+    let top = RUNGS.len() - 1;
+    config.ladder_rung = top;
+    assert!(!config.record_ladder(top, true));
+    assert_eq!(config.ladder_rung, top);
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The log filter setting and the trace capture button.
+
+#[cfg(feature = "headless")]
+#[test]
+fn only_filters_that_parse_reach_the_worker() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    app.set_log_filter("info,p2pgo_network::game_channel=verbose");
+    assert!(app.log_filter_error().unwrap().contains("\"verbose\""));
+    assert!(net_rx.try_recv().is_err());
+
+    app.set_log_filter(" info, p2pgo_network::game_channel=trace ");
+    assert_eq!(app.log_filter_error(), None);
+    match net_rx.try_recv().unwrap() {
+        UiToNet::SetLogFilter { filter } => assert_eq!(filter.to_string(), "info,p2pgo_network::game_channel=trace"),
+        other => panic!("expected SetLogFilter, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn one_trace_capture_runs_at_a_time() {
+    use crossbeam_channel::unbounded;
+    use std::path::PathBuf;
+    use p2pgo_network::logging::CaptureReport;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    app.capture_trace();
+    app.capture_trace();
+    let sent: Vec<UiToNet> = net_rx.try_iter().collect();
+    assert!(matches!(sent.as_slice(), [UiToNet::CaptureTrace]), "{:?}", sent);
+    assert!(app.capturing_trace());
+
+    let path = PathBuf::from("logs/p2pgo-trace-20240501-120000.log");
+    net_tx.send(NetToUi::TraceCaptureStarted { path: path.clone() }).unwrap();
+    app.tick_headless();
+    assert!(app.toast().unwrap().starts_with("Capturing trace logs for 60 seconds"));
+
+    net_tx.send(NetToUi::TraceCaptureFinished {
+        report: CaptureReport { path, bytes: 16 * 1024 * 1024, truncated: true },
+    }).unwrap();
+    app.tick_headless();
+    assert!(!app.capturing_trace());
+    let toast = app.toast().unwrap();
+    assert!(toast.contains("p2pgo-trace-20240501-120000.log (cut short at the size limit)"), "{}", toast);
+
+    // A failed start frees the button again
+    app.capture_trace();
+    net_tx.send(NetToUi::TraceCaptureFailed { message: "Logging is not initialized".to_string() }).unwrap();
+    app.tick_headless();
+    assert!(!app.capturing_trace());
+    assert!(app.get_error_msg().unwrap().contains("Logging is not initialized"));
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Resigning, and passing early or while ahead, ask first unless turned off.
+
+use p2pgo_core::{Coord, Move};
+use p2pgo_ui_egui::move_confirm::{confirmation, Confirmation, PassReason, EARLY_PASS_MOVES};
+
+#[test]
+fn resigning_always_asks_with_the_estimate() {
+    let confirm = confirmation(&Move::Resign, 120, Some(4.5), true).unwrap();
+    assert_eq!(confirm, Confirmation::Resign { lead: Some(4.5) });
+    assert_eq!(confirm.prompt(), "You are ahead by ~4.5 — resign anyway?");
+    assert_eq!(confirm.mv(), Move::Resign);
+
+    let behind = confirmation(&Move::Resign, 120, Some(-12.0), true).unwrap();
+    assert_eq!(behind.prompt(), "You are behind by ~12.0 — resign anyway?");
+    let unknown = confirmation(&Move::Resign, 0, None, true).unwrap();
+    assert_eq!(unknown.prompt(), "Resign this game?");
+}
+
+#[test]
+fn passing_asks_only_early_or_while_ahead() {
+    assert_eq!(confirmation(&Move::Pass, 0, None, true), Some(Confirmation::Pass(PassReason::Early(0))));
+    assert_eq!(
+        confirmation(&Move::Pass, EARLY_PASS_MOVES - 1, Some(-3.0), true),
+        Some(Confirmation::Pass(PassReason::Early(EARLY_PASS_MOVES - 1)))
+    );
+    // Past the opening, only a lead makes a pass suspicious
+    assert_eq!(confirmation(&Move::Pass, EARLY_PASS_MOVES, None, true), None);
+    assert_eq!(confirmation(&Move::Pass, EARLY_PASS_MOVES, Some(-3.0), true), None);
+    assert_eq!(confirmation(&Move::Pass, EARLY_PASS_MOVES, Some(0.0), true), None);
+    let ahead = confirmation(&Move::Pass, 80, Some(2.5), true).unwrap();
+    assert_eq!(ahead, Confirmation::Pass(PassReason::Ahead(2.5)));
+    assert_eq!(ahead.prompt(), "You are ahead by ~2.5 — pass anyway?");
+    assert_eq!(ahead.mv(), Move::Pass);
+}
+
+#[test]
+fn stones_never_ask() {
+    assert_eq!(confirmation(&Move::Place(Coord::new(3, 3)), 0, Some(10.0), true), None);
+}
+
+#[test]
+fn the_setting_turns_every_confirmation_off() {
+    for (mv, moves_played, lead) in [(Move::Resign, 50, Some(4.5)), (Move::Pass, 0, None), (Move::Pass, 80, Some(2.5))] {
+        assert!(confirmation(&mv, moves_played, lead, true).is_some());
+        assert_eq!(confirmation(&mv, moves_played, lead, false), None);
+    }
+}
+
+#[cfg(feature = "headless")]
+mod app {
+    use crossbeam_channel::{unbounded, Receiver};
+    use p2pgo_core::Move;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::move_confirm::{Confirmation, PassReason};
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    fn app_in_game() -> (App, Receiver<UiToNet>) {
+        let (ui_tx, net_rx) = unbounded::<UiToNet>();
+        let (net_tx, ui_rx) = unbounded::<NetToUi>();
+        let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+        net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+        net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 0, recovered_from_divergence: false }).unwrap();
+        app.tick_headless();
+        net_rx.try_iter().for_each(drop);
+        (app, net_rx)
+    }
+
+    fn sent_moves(net_rx: &Receiver<UiToNet>) -> Vec<Move> {
+        net_rx.try_iter().filter_map(|msg| match msg {
+            UiToNet::MakeMove { mv, .. } => Some(mv),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn resign_waits_for_confirmation() {
+        let (mut app, net_rx) = app_in_game();
+        app.ask_to_play(Move::Resign);
+        assert_eq!(app.move_confirmation(), Some(&Confirmation::Resign { lead: None }));
+        assert!(sent_moves(&net_rx).is_empty());
+
+        app.answer_move_confirmation(false);
+        assert_eq!(app.move_confirmation(), None);
+        assert!(sent_moves(&net_rx).is_empty());
+
+        app.ask_to_play(Move::Resign);
+        app.answer_move_confirmation(true);
+        assert_eq!(sent_moves(&net_rx), vec![Move::Resign]);
+    }
+
+    #[test]
+    fn opening_pass_waits_for_confirmation() {
+        let (mut app, net_rx) = app_in_game();
+        app.ask_to_play(Move::Pass);
+        assert_eq!(app.move_confirmation(), Some(&Confirmation::Pass(PassReason::Early(0))));
+        app.answer_move_confirmation(true);
+        assert_eq!(sent_moves(&net_rx), vec![Move::Pass]);
+    }
+
+    #[test]
+    fn experienced_players_skip_confirmations() {
+        let (mut app, net_rx) = app_in_game();
+        app.ui_config_mut().skip_move_confirmations = true;
+        app.ask_to_play(Move::Pass);
+        assert_eq!(app.move_confirmation(), None);
+        assert_eq!(sent_moves(&net_rx), vec![Move::Pass]);
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Typed network errors and how the UI recovers from each.
+
+use std::time::Duration;
+use p2pgo_core::{Coord, GameError, Move};
+use p2pgo_network::Error;
+use p2pgo_ui_egui::msg::{GameErrorKind, Recovery};
+
+#[test]
+fn unreachable_peers_are_retried() {
+    let refused = Error::ConnectionFailed {
+        peer: "peer-1".to_string(),
+        source: std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into(),
+    };
+    assert_eq!(Recovery::for_error(&refused), Recovery::Retry);
+    let timeout = Error::Timeout { what: "the relay", after: Duration::from_secs(5) };
+    assert_eq!(Recovery::for_error(&timeout), Recovery::Retry);
+    assert_eq!(Recovery::for_error(&Error::RelayUnavailable("no home relay".into())), Recovery::Retry);
+}
+
+#[test]
+fn sync_conflicts_fetch_the_game_again() {
+    let conflict = Error::SyncConflict { game_id: "game-1".to_string(), reason: "no game state yet".to_string() };
+    assert_eq!(Recovery::for_error(&conflict), Recovery::Resync);
+
+    let kind = GameErrorKind::from_network(&conflict, &Move::Pass);
+    assert!(matches!(&kind, GameErrorKind::OutOfSync { reason } if reason.contains("no game state yet")));
+    assert_eq!(kind.recovery(), Recovery::Resync);
+}
+
+#[test]
+fn everything_else_is_reported() {
+    assert_eq!(Recovery::for_error(&Error::GameNotFound("game-1".to_string())), Recovery::Report);
+    assert_eq!(Recovery::for_error(&Error::SelfConnection), Recovery::Report);
+
+    // Rule errors keep the point that was tried
+    let mv = Move::Place(Coord::new(2, 3));
+    let kind = GameErrorKind::from_network(&Error::Rules(GameError::KoViolation), &mv);
+    assert_eq!(kind, GameErrorKind::KoViolation { at: Coord::new(2, 3) });
+    assert_eq!(kind.recovery(), Recovery::Report);
+
+    let kind = GameErrorKind::from_network(&Error::GameNotFound("game-1".to_string()), &mv);
+    assert!(matches!(kind, GameErrorKind::SendFailed { .. }));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn out_of_sync_games_are_requested_again() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::GameState;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 0, recovered_from_divergence: false }).unwrap();
+    app.tick_headless();
+    // Answer the fetch made on entering the game
+    net_tx.send(NetToUi::GameStateSnapshot { game_id: "game-1".to_string(), state: GameState::new(9) }).unwrap();
+    app.tick_headless();
+    while net_rx.try_recv().is_ok() {}
+
+    net_tx.send(NetToUi::GameError {
+        game_id: "game-1".to_string(),
+        error: GameErrorKind::OutOfSync { reason: "no game state yet".into() },
+    }).unwrap();
+    app.tick_headless();
+    let requested = net_rx.try_iter().any(|msg| matches!(msg, UiToNet::GetGameState { ref game_id } if game_id == "game-1"));
+    assert!(requested);
+    assert!(app.get_error_msg().unwrap().contains("Out of sync"));
+}
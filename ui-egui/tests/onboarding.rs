@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The first-run wizard and the choices it saves.
+
+use p2pgo_ui_egui::onboarding::{Onboarding, OnboardingPage};
+use p2pgo_ui_egui::ui_config::UiConfig;
+
+#[test]
+fn wizard_needs_a_name_and_saves_its_choices() {
+    let mut config = UiConfig::default();
+    let mut wizard = Onboarding::new("Player", &config);
+    assert_eq!(wizard.page(), OnboardingPage::Name);
+    assert_eq!(wizard.name, "Player");
+
+    wizard.name = "   ".to_string();
+    assert!(!wizard.advance());
+    wizard.name = "  Shusaku ".to_string();
+    assert!(wizard.advance());
+    assert!(wizard.advance());
+    assert_eq!(wizard.page(), OnboardingPage::Sharing);
+    assert!(!wizard.relay_participation && !wizard.share_training_data);
+    wizard.share_training_data = true;
+    wizard.back();
+    assert_eq!(wizard.page(), OnboardingPage::Tickets);
+    assert!(wizard.advance() && wizard.advance());
+    assert!(wizard.is_last_page());
+    assert!(!wizard.advance());
+
+    wizard.apply(&mut config);
+    assert!(config.onboarded);
+    assert_eq!(config.display_name.as_deref(), Some("Shusaku"));
+    assert!(config.share_training_data);
+    assert!(!config.relay_participation);
+
+    // Running it again starts from what was saved
+    let again = Onboarding::new("Player", &config);
+    assert_eq!(again.name, "Shusaku");
+    assert!(again.share_training_data);
+    assert!(!again.relay_participation);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn finishing_the_wizard_sends_name_and_sharing_choices() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_network::relay_mode::RelayPreset;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    app.open_onboarding();
+    assert_eq!(app.get_current_view_debug(), "Onboarding");
+    app.onboarding_mut().unwrap().name = "Honinbo".to_string();
+    app.onboarding_next();
+    app.onboarding_next();
+    let wizard = app.onboarding_mut().unwrap();
+    wizard.relay_participation = true;
+    wizard.share_training_data = true;
+    app.onboarding_next();
+
+    // The connectivity page runs the NAT diagnostics
+    assert!(net_rx.try_iter().any(|msg| matches!(msg, UiToNet::RunNetReport)));
+    net_tx.send(NetToUi::NetReport { report: "Endpoint: Active".to_string() }).unwrap();
+    app.tick_headless();
+
+    app.onboarding_next();
+    assert_eq!(app.get_current_view_debug(), "MainMenu");
+    assert!(app.ui_config().onboarded);
+    assert_eq!(app.ui_config().display_name.as_deref(), Some("Honinbo"));
+
+    let sent: Vec<UiToNet> = net_rx.try_iter().collect();
+    assert!(sent.iter().any(|msg| matches!(msg, UiToNet::SetPlayerName { name } if name == "Honinbo")));
+    assert!(sent.iter().any(|msg| matches!(msg, UiToNet::SetSharing { relay: true, training: true })));
+    // Opting into relaying moves the player up to contributing
+    assert!(app.ui_config().relay_participation);
+    assert_eq!(app.runtime_config().relay_preset, RelayPreset::Contributor);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn withdrawing_relay_consent_drops_the_worker_to_a_player() {
+    use std::time::{Duration, Instant};
+    use crossbeam_channel::unbounded;
+    use p2pgo_network::relay_mode::RelayPreset;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().to_path_buf();
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+
+    ui_tx.send(UiToNet::SetRelayPreset { preset: RelayPreset::Dedicated }).unwrap();
+    // Consent that the preset already keeps to changes nothing
+    ui_tx.send(UiToNet::SetSharing { relay: true, training: false }).unwrap();
+    ui_tx.send(UiToNet::SetSharing { relay: false, training: false }).unwrap();
+    let mut presets = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline && presets.len() < 2 {
+        if let Ok(NetToUi::RelayModeChanged { preset, .. }) = ui_rx.recv_timeout(Duration::from_millis(100)) {
+            presets.push(preset);
+        }
+    }
+    ui_tx.send(UiToNet::Shutdown).unwrap();
+
+    assert_eq!(presets, [RelayPreset::Dedicated, RelayPreset::Player]);
+}
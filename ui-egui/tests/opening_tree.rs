@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Opening tree of archived games: expansion, stats and practice positions.
+
+use p2pgo_core::archiver;
+use p2pgo_core::{Color, Coord, GameState, Move};
+use p2pgo_ui_egui::opening_view::{NodeStats, OpeningExplorer, OpeningTree, Row};
+
+fn at(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+fn game(moves: &[Move]) -> GameState {
+    let mut game = GameState::new(9);
+    for mv in moves {
+        game.apply_move(mv.clone()).unwrap();
+    }
+    game
+}
+
+/// Three games opening on the 3-3 point, one on tengen
+fn tree() -> OpeningTree {
+    let mut tree = OpeningTree::new(9);
+    tree.add_game(&game(&[at(2, 2), at(6, 6), at(6, 2)]), Some(Color::Black));
+    tree.add_game(&game(&[at(2, 2), at(6, 6), at(2, 6)]), Some(Color::White));
+    tree.add_game(&game(&[at(2, 2), at(6, 2)]), None);
+    tree.add_game(&game(&[at(4, 4), at(2, 2)]), Some(Color::Black));
+    tree
+}
+
+#[test]
+fn stats_add_up_through_the_tree() {
+    let mut tree = tree();
+    assert_eq!(tree.stats(0), NodeStats { games: 4, black_wins: 2, white_wins: 1 });
+
+    let three_three = tree.children(0)[0];
+    assert_eq!(tree.move_at(three_three), Some(&at(2, 2)));
+    let stats = tree.stats(three_three);
+    assert_eq!(stats, NodeStats { games: 3, black_wins: 1, white_wins: 1 });
+    assert_eq!(stats.black_win_rate(), Some(0.5));
+
+    // Adding a game refreshes the cached counts along its line
+    tree.add_game(&game(&[at(2, 2), at(6, 6)]), Some(Color::Black));
+    assert_eq!(tree.stats(three_three).games, 4);
+    assert_eq!(tree.stats(0).black_wins, 3);
+    assert_eq!(NodeStats::default().black_win_rate(), None);
+}
+
+#[test]
+fn expanding_shows_replies_most_played_first() {
+    let mut explorer = OpeningExplorer::new(tree());
+    let rows = explorer.rows();
+    assert_eq!(rows.len(), 2);
+    let three_three = rows[0].node;
+    assert_eq!(explorer.tree().move_at(three_three), Some(&at(2, 2)));
+
+    explorer.toggle(three_three);
+    let rows = explorer.rows();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[0], Row { node: three_three, depth: 0 });
+    assert_eq!(explorer.tree().move_at(rows[1].node), Some(&at(6, 6)));
+    assert_eq!(explorer.stats(rows[1].node).games, 2);
+    assert_eq!(explorer.tree().move_at(rows[2].node), Some(&at(6, 2)));
+    assert_eq!(rows[2].depth, 1);
+    assert_eq!(explorer.tree().move_at(rows[3].node), Some(&at(4, 4)));
+
+    explorer.toggle(three_three);
+    assert_eq!(explorer.rows().len(), 2);
+}
+
+#[test]
+fn selected_line_is_the_practice_position() {
+    let mut explorer = OpeningExplorer::new(tree());
+    assert!(explorer.position().moves.is_empty());
+
+    let three_three = explorer.rows()[0].node;
+    explorer.toggle(three_three);
+    let reply = explorer.rows()[1].node;
+    explorer.select(reply);
+    let position = explorer.position();
+    assert_eq!(position.moves, vec![at(2, 2), at(6, 6)]);
+    assert_eq!(position.current_player, Color::Black);
+    assert_eq!(position.board[Coord::new(6, 6).to_index(9)], Some(Color::White));
+}
+
+#[test]
+fn archive_games_of_other_sizes_are_left_out() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut resigned = game(&[at(2, 2), at(6, 6)]);
+    resigned.apply_move(Move::Resign).unwrap();
+    archiver::archive_game_in(dir.path(), &resigned, "alice").unwrap();
+    let mut small = GameState::new(7);
+    small.apply_move(at(3, 3)).unwrap();
+    archiver::archive_game_in(dir.path(), &small, "bob").unwrap();
+
+    let mut tree = OpeningTree::from_archive(dir.path(), 9).unwrap();
+    assert_eq!(tree.len(), 3);
+    // Black resigned on move three
+    assert_eq!(tree.stats(0), NodeStats { games: 1, black_wins: 0, white_wins: 1 });
+    assert!(OpeningTree::from_archive(dir.path(), 13).unwrap().is_empty());
+}
@@ -52,7 +52,7 @@ fn two_clients_pair_and_play() {
     let _guest_app = App::new(guest_ui_tx.clone(), guest_ui_rx.clone(), 9, "GuestPlayer".to_string());
     
     // Host creates a game
-    host_ui_tx.send(UiToNet::CreateGame { board_size: 9 }).unwrap();
+    host_ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).unwrap();
     
     // Wait for the game to be created and get the ticket
     let ticket_msg = wait_for_message(&host_ui_rx, 
@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The worker suggests passing once when only moves inside one's own area are left.
+
+/// The settled position the core analysis tests use; neither side has
+/// passed yet
+#[cfg(feature = "headless")]
+fn finished_position() -> p2pgo_core::GameState {
+    use p2pgo_core::{sgf::SgfProcessor, GameState};
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures/positions/finished_9x9.sgf");
+    SgfProcessor::new(GameState::new(9)).parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn finished_position_is_suggested_once() {
+    use std::time::{Duration, Instant};
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, GameEvent, Move};
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().to_path_buf();
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+
+    ui_tx.send(UiToNet::CreateGameFromPosition { position: finished_position(), komi: 5.5 }).unwrap();
+    // Both players fill an eye of their own instead of passing
+    let mut suggestions = Vec::new();
+    let mut moves_seen = 0;
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline && moves_seen < 2 {
+        match ui_rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(NetToUi::GameJoined { .. }) => {
+                ui_tx.send(UiToNet::MakeMove { mv: Move::Place(Coord::new(1, 1)), board_size: Some(9) }).unwrap();
+            }
+            Ok(NetToUi::GameEvent { event: GameEvent::MoveMade { .. } }) => {
+                moves_seen += 1;
+                if moves_seen == 1 {
+                    ui_tx.send(UiToNet::MakeMove { mv: Move::Place(Coord::new(6, 1)), board_size: Some(9) }).unwrap();
+                }
+            }
+            Ok(NetToUi::PassSuggested { score_estimate, .. }) => suggestions.push(score_estimate),
+            _ => {}
+        }
+    }
+    assert_eq!(moves_seen, 2);
+    // Give the worker time to look at the last position too
+    let settle = Instant::now() + Duration::from_millis(500);
+    while Instant::now() < settle {
+        if let Ok(NetToUi::PassSuggested { score_estimate, .. }) = ui_rx.recv_timeout(Duration::from_millis(50)) {
+            suggestions.push(score_estimate);
+        }
+    }
+    ui_tx.send(UiToNet::Shutdown).unwrap();
+
+    // 45 points against 36, less 5.5 komi
+    assert_eq!(suggestions, [3.5]);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn suggestion_is_a_hint_cleared_by_the_next_game() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    net_tx.send(NetToUi::GameJoined {
+        game_id: "first".to_string(),
+        settings: p2pgo_core::settings::GameSettings::standard(9),
+    }).unwrap();
+    net_tx.send(NetToUi::PassSuggested { game_id: "other".to_string(), score_estimate: 1.5 }).unwrap();
+    app.tick_headless();
+    // Suggestions for a game not on screen are dropped
+    assert_eq!(app.pass_hint(), None);
+
+    net_tx.send(NetToUi::PassSuggested { game_id: "first".to_string(), score_estimate: -2.5 }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.pass_hint(), Some(-2.5));
+    // Only a hint: nothing is played for the player
+    assert!(net_rx.try_iter().all(|msg| !matches!(msg, UiToNet::MakeMove { .. })));
+
+    net_tx.send(NetToUi::GameJoined {
+        game_id: "next".to_string(),
+        settings: p2pgo_core::settings::GameSettings::standard(9),
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.pass_hint(), None);
+}
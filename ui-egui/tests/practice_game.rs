@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Practicing against yourself without the network, from scratch or a
+//! chosen position, and the message for a pasted own ticket.
+
+#[cfg(feature = "headless")]
+#[test]
+fn practice_plays_both_colors_locally() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, Coord, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.start_practice(9);
+    assert_eq!(app.get_current_view_debug(), "Game(practice)");
+
+    app.play_move(Move::Place(Coord::new(3, 5)));
+    app.play_typed_move("e5");
+    let state = app.get_current_game_state().unwrap();
+    assert_eq!(state.moves, [Move::Place(Coord::new(3, 5)), Move::Place(Coord::new(4, 4))]);
+    assert_eq!(state.current_player, Color::Black);
+
+    // Illegal moves are refused on the board, as in a networked game
+    app.play_move(Move::Place(Coord::new(4, 4)));
+    assert_eq!(app.board_flash(), Some(Coord::new(4, 4)));
+    assert_eq!(app.get_current_game_state().unwrap().moves.len(), 2);
+
+    app.play_move(Move::Pass);
+    assert_eq!(app.get_current_game_state().unwrap().current_player, Color::White);
+    assert!(net_rx.try_recv().is_err(), "practice games never reach the worker");
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn practice_from_a_position_stays_local() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, Coord, GameState, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    let mut position = GameState::new(9);
+    position.apply_move(Move::Place(Coord::new(2, 2))).unwrap();
+    app.start_practice_from(position);
+    assert_eq!(app.get_current_view_debug(), "Game(practice)");
+
+    app.play_move(Move::Place(Coord::new(6, 6)));
+    let state = app.get_current_game_state().unwrap();
+    assert_eq!(state.moves, [Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6))]);
+    assert_eq!(state.current_player, Color::Black);
+    assert!(net_rx.try_recv().is_err(), "practice games never reach the worker");
+}
+
+#[test]
+fn own_ticket_gets_a_friendly_message() {
+    use std::time::Duration;
+    use p2pgo_network::Error;
+    use p2pgo_ui_egui::messages::connect_error_message;
+
+    let message = connect_error_message(&Error::SelfConnection).unwrap();
+    assert!(message.contains("your own ticket"));
+    assert!(message.contains("Practice vs myself"));
+    let timeout = Error::Timeout { what: "the relay", after: Duration::from_secs(1) };
+    assert_eq!(connect_error_message(&timeout), None);
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! The opponent's presence on the game screen, and our own going out.
+
+use std::time::{Duration, Instant};
+use p2pgo_network::presence::PRESENCE_STALE_AFTER;
+use p2pgo_ui_egui::presence_view::{OpponentPresence, PresenceStatus};
+
+#[test]
+fn quiet_opponents_show_when_last_seen() {
+    let received = Instant::now();
+    let presence = OpponentPresence { considering: true, last_active: 1_000, received };
+    assert_eq!(presence.status(received, 1_000), PresenceStatus::Considering);
+    let around = OpponentPresence { considering: false, ..presence };
+    assert_eq!(around.status(received + Duration::from_secs(29), 1_029), PresenceStatus::Present);
+
+    // Silence for the stale period shows how long since they did anything
+    let later = received + PRESENCE_STALE_AFTER;
+    assert_eq!(presence.status(later, 1_045), PresenceStatus::LastSeen(Duration::from_secs(45)));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn presence_follows_the_board_and_the_setting() {
+    use crossbeam_channel::{unbounded, Receiver};
+    use p2pgo_core::{Color, Coord, GameEvent, Move};
+    use p2pgo_network::presence::Presence;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    fn sent(net_rx: &Receiver<UiToNet>) -> Vec<Presence> {
+        net_rx.try_iter().filter_map(|msg| match msg {
+            UiToNet::SendPresence { presence } => Some(presence),
+            _ => None,
+        }).collect()
+    }
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+    }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::Presence { peer: "opponent".to_string(), considering: true, last_active: 1_000 },
+    }).unwrap();
+    app.tick_headless();
+    let presence = app.opponent_presence().unwrap();
+    assert!(presence.considering);
+    assert_eq!(presence.last_active, 1_000);
+
+    // Hovering every frame sends once, until the interval is up
+    for _ in 0..10 {
+        app.update_presence(true);
+    }
+    let out = sent(&net_rx);
+    assert_eq!(out.len(), 1);
+    assert!(out[0].considering);
+
+    // Hidden in the settings: nothing goes out
+    app.ui_config_mut().hide_presence = true;
+    app.update_presence(false);
+    assert!(sent(&net_rx).is_empty());
+
+    // A new game starts without the last opponent's presence
+    net_tx.send(NetToUi::GameLeft).unwrap();
+    app.tick_headless();
+    assert!(app.opponent_presence().is_none());
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Puzzles from the archive and the persisted streak.
+
+use p2pgo_core::archiver;
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_ui_egui::puzzle_view::{Feedback, PuzzleSession};
+use p2pgo_ui_egui::ui_config::UiConfig;
+
+fn at(x: u8, y: u8) -> Move {
+    Move::Place(Coord::new(x, y))
+}
+
+/// A game ending in a snapback at the top-left corner
+fn snapback_game() -> GameState {
+    let mut game = GameState::new(9);
+    game.moves = vec![
+        at(3, 0), at(2, 0), at(3, 1), at(2, 1), at(3, 2), at(1, 1),
+        at(2, 2), at(0, 1), at(1, 2), at(6, 6), at(0, 2), at(7, 7),
+        at(1, 0), at(0, 0), at(1, 0), Move::Pass, Move::Pass,
+    ];
+    game
+}
+
+#[test]
+fn solving_and_failing_update_the_streak() {
+    let dir = tempfile::tempdir().unwrap();
+    archiver::archive_game_in(dir.path(), &snapback_game(), "alice").unwrap();
+    let mut session = PuzzleSession::from_archive(dir.path()).unwrap();
+    assert_eq!(session.len(), 1);
+
+    let mut config = UiConfig::default();
+    let throw_in = Coord::new(1, 0);
+    assert_eq!(session.play(throw_in, &mut config), None);
+    assert_eq!(session.play(throw_in, &mut config), Some(&Feedback::Solved));
+    assert_eq!(config.puzzle_streak, 1);
+
+    session.next();
+    assert!(matches!(session.play(Coord::new(8, 8), &mut config), Some(Feedback::Wrong(_))));
+    assert_eq!((config.puzzle_streak, config.best_puzzle_streak), (0, 1));
+
+    // Further clicks do not count twice
+    session.play(throw_in, &mut config);
+    assert_eq!(config.puzzle_streak, 0);
+}
+
+#[test]
+fn streak_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ui_config.json");
+    assert_eq!(UiConfig::load(&path), UiConfig::default());
+
+    let mut config = UiConfig::default();
+    config.record_puzzle(true);
+    config.record_puzzle(true);
+    config.save(&path).unwrap();
+
+    let loaded = UiConfig::load(&path);
+    assert_eq!((loaded.puzzle_streak, loaded.best_puzzle_streak), (2, 2));
+}
@@ -0,0 +1,72 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Relay presets chosen in the settings, applied by the worker at runtime.
+
+use p2pgo_network::relay_mode::RelayPreset;
+use p2pgo_ui_egui::runtime_config::RuntimeConfig;
+
+#[test]
+fn configs_saved_before_presets_relay_as_a_player() {
+    let config = RuntimeConfig::parse(r#"{ "ghost_moves_after_games": 5 }"#).unwrap();
+    assert_eq!(config.relay_preset, RelayPreset::Player);
+
+    let config = RuntimeConfig { relay_preset: RelayPreset::Dedicated, ..RuntimeConfig::default() };
+    assert_eq!(RuntimeConfig::parse(&config.to_json()).unwrap().relay_preset, RelayPreset::Dedicated);
+
+    let mut running = RuntimeConfig::default();
+    let report = running.update(&RuntimeConfig { relay_preset: RelayPreset::Contributor, ..RuntimeConfig::default() });
+    assert_eq!(report.applied, vec!["relay_preset"]);
+    assert!(report.restart_needed.is_empty());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn worker_switches_presets_without_restarting() {
+    use std::time::{Duration, Instant};
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let dir = tempfile::tempdir().unwrap();
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().to_path_buf();
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+
+    let mut changes = Vec::new();
+    ui_tx.send(UiToNet::SetRelayPreset { preset: RelayPreset::Contributor }).unwrap();
+    // The same preset again changes nothing
+    ui_tx.send(UiToNet::SetRelayPreset { preset: RelayPreset::Contributor }).unwrap();
+    ui_tx.send(UiToNet::SetRelayPreset { preset: RelayPreset::Player }).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline && changes.len() < 2 {
+        if let Ok(NetToUi::RelayModeChanged { preset, limits, drained }) = ui_rx.recv_timeout(Duration::from_millis(100)) {
+            changes.push((preset, limits, drained));
+        }
+    }
+    ui_tx.send(UiToNet::Shutdown).unwrap();
+
+    assert_eq!(changes, [
+        (RelayPreset::Contributor, RelayPreset::Contributor.limits(), 0),
+        (RelayPreset::Player, RelayPreset::Player.limits(), 0),
+    ]);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn app_tracks_the_active_preset() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    assert_eq!(app.relay_mode(), None);
+
+    let limits = RelayPreset::Player.limits();
+    net_tx.send(NetToUi::RelayModeChanged { preset: RelayPreset::Player, limits, drained: 3 }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.relay_mode(), Some((RelayPreset::Player, limits)));
+    assert!(app.toast().unwrap().contains("Closing 3 connections"));
+}
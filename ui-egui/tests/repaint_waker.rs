@@ -0,0 +1,53 @@
+//! Test that worker messages wake an idle UI
+//! SPDX-License-Identifier: MIT OR Apache-2.0
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use crossbeam_channel::unbounded;
+use p2pgo_ui_egui::msg::NetToUi;
+use p2pgo_ui_egui::repaint::{spawn_waker, RepaintCause, RepaintStats};
+
+#[test]
+fn waker_fires_repaint_on_net_message() {
+    let (net_tx, net_rx) = unbounded::<NetToUi>();
+    let repaints = Arc::new(AtomicUsize::new(0));
+    
+    let counter = repaints.clone();
+    let ui_rx = spawn_waker(net_rx, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    
+    // Nothing sent yet, so nothing should have woken the UI
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(repaints.load(Ordering::SeqCst), 0);
+    
+    // Inject a message as the worker would
+    net_tx.send(NetToUi::NodeId { node_id: "test-node".to_string() }).unwrap();
+    
+    // The message must be forwarded to the UI receiver
+    let msg = ui_rx.recv_timeout(Duration::from_secs(1)).expect("message should be forwarded");
+    assert!(matches!(msg, NetToUi::NodeId { .. }));
+    
+    // And a repaint must have been requested for it
+    for _ in 0..100 {
+        if repaints.load(Ordering::SeqCst) == 1 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(repaints.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn repaint_stats_track_fps_and_cause() {
+    let mut stats = RepaintStats::new();
+    assert_eq!(stats.fps(), 0);
+    assert_eq!(stats.last_cause(), None);
+    
+    stats.record(RepaintCause::Input);
+    stats.record(RepaintCause::NetMessage);
+    
+    assert_eq!(stats.fps(), 2);
+    assert_eq!(stats.last_cause(), Some(RepaintCause::NetMessage));
+}
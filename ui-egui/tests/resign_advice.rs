@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Resignation and pass advice shown with the heat map.
+
+use p2pgo_core::engine::NeuralConfig;
+use p2pgo_core::policy::{pass_index, policy_len};
+use p2pgo_core::{Color, Coord, GameState};
+use p2pgo_ui_egui::heat_map::Outlook;
+use trainer::evaluate::Evaluation;
+
+/// White owns the whole board around single-point eyes; `to_move` plays next
+fn hopeless_for_black(to_move: Color) -> GameState {
+    let mut stones = Vec::new();
+    for y in 0..9 {
+        for x in 0..9 {
+            if x % 3 != 1 || y % 3 != 1 {
+                stones.push((Coord::new(x, y), Color::White));
+            }
+        }
+    }
+    GameState::from_setup(9, &stones, to_move).unwrap()
+}
+
+#[test]
+fn lost_positions_recommend_resigning_to_the_loser_only() {
+    let config = NeuralConfig::default();
+    let mut logits = vec![0.0; policy_len(9)];
+    logits[pass_index(9)] = 8.0;
+    // Black's outlook, as the value head reports it
+    let evaluation = Evaluation { logits, value: -0.99 };
+
+    let black = Outlook::from_evaluation(&evaluation, &hopeless_for_black(Color::Black), &config);
+    assert!(black.resign_recommended);
+    // Black can't play inside White's eyes, so pass is all that's left
+    assert!((black.pass_probability - 1.0).abs() < 1e-5);
+
+    let white = Outlook::from_evaluation(&evaluation, &hopeless_for_black(Color::White), &config);
+    assert!(!white.resign_recommended);
+
+    let close = Evaluation { value: -0.5, ..evaluation };
+    assert!(!Outlook::from_evaluation(&close, &hopeless_for_black(Color::Black), &config).resign_recommended);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn banner_shows_only_for_the_position_on_screen() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{GameEvent, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+    app.set_heat_map(true);
+    app.tick_headless();
+    let position_hash = net_rx.try_iter()
+        .find_map(|msg| match msg {
+            UiToNet::RequestHeatMap { position_hash, .. } => Some(position_hash),
+            _ => None,
+        })
+        .unwrap();
+
+    let lost = Outlook { pass_probability: 0.1, resign_recommended: true };
+    net_tx.send(NetToUi::Outlook { position_hash: position_hash ^ 1, outlook: lost }).unwrap();
+    app.tick_headless();
+    assert!(!app.resign_recommended(), "advice for another position is dropped");
+
+    net_tx.send(NetToUi::Outlook { position_hash, outlook: lost }).unwrap();
+    app.tick_headless();
+    assert!(app.resign_recommended());
+    assert_eq!(app.outlook(), Some(lost));
+
+    app.set_heat_map(false);
+    assert!(!app.resign_recommended());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn ghost_moves_led_by_pass_suggest_passing() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::Move;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    net_tx.send(NetToUi::GhostMoves(vec![Move::Pass, Move::Place(Coord::new(2, 2))])).unwrap();
+    app.tick_headless();
+    assert!(app.ghost_pass());
+
+    net_tx.send(NetToUi::GhostMoves(vec![Move::Place(Coord::new(2, 2))])).unwrap();
+    app.tick_headless();
+    assert!(!app.ghost_pass());
+}
@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Games in progress are saved on shutdown and offered again on the next start.
+
+#[cfg(feature = "headless")]
+fn wait_for(
+    rx: &crossbeam_channel::Receiver<p2pgo_ui_egui::msg::NetToUi>,
+    pred: impl Fn(&p2pgo_ui_egui::msg::NetToUi) -> bool,
+) -> Option<p2pgo_ui_egui::msg::NetToUi> {
+    use std::time::{Duration, Instant};
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if let Ok(msg) = rx.recv_timeout(Duration::from_millis(100)) {
+            if pred(&msg) {
+                return Some(msg);
+            }
+        }
+    }
+    None
+}
+
+/// Seat an opponent in a game `lobby` holds, so that its moves may be played
+#[cfg(feature = "headless")]
+fn seat_opponent(lobby: &p2pgo_network::lobby::Lobby, game_id: &str) {
+    use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+
+    let opponent = PlayerProfile {
+        node_id: "opponent".to_string(),
+        name: "Opponent".to_string(),
+        guild: None,
+        self_reported_rating: None,
+    };
+    let game_id = game_id.to_string();
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        lobby.set_join_policy(&game_id, JoinPolicy::Everyone).await.unwrap();
+        lobby.request_join(&game_id, opponent).await.unwrap();
+    });
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn game_closed_after_three_moves_is_resumed() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, GameEvent, Move};
+    use p2pgo_network::lobby::Lobby;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let dir = tempfile::tempdir().unwrap();
+
+    // First session: three moves, then the app closes
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let (lobby, data_dir) = (Lobby::new(), dir.path().to_path_buf());
+    let first = {
+        let lobby = lobby.clone();
+        std::thread::spawn(move || worker::start_in_process(net_rx, net_tx, "Host".to_string(), lobby, data_dir))
+    };
+
+    ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).unwrap();
+    let game_id = match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameJoined { .. })) {
+        Some(NetToUi::GameJoined { game_id, .. }) => game_id,
+        _ => panic!("Worker did not create a game"),
+    };
+    seat_opponent(&lobby, &game_id);
+    for coord in [Coord::new(2, 2), Coord::new(6, 6), Coord::new(4, 4)] {
+        ui_tx.send(UiToNet::MakeMove { mv: Move::Place(coord), board_size: Some(9) }).unwrap();
+        wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameEvent { event: GameEvent::MoveMade { .. } }))
+            .expect("move event");
+    }
+    ui_tx.send(UiToNet::Shutdown).unwrap();
+    wait_for(&ui_rx, |msg| matches!(msg, NetToUi::ShutdownAck)).expect("shutdown ack");
+    first.join().unwrap().unwrap();
+
+    // Second session against the same directory
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().join("snapshots");
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+
+    match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::ResumableGame { .. })) {
+        Some(NetToUi::ResumableGame { game_id: offered, move_count, .. }) => {
+            assert_eq!(offered, game_id);
+            assert_eq!(move_count, 3);
+        }
+        _ => panic!("No resume offer after restart"),
+    }
+
+    ui_tx.send(UiToNet::ResumeGame { game_id: game_id.clone() }).unwrap();
+    match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameRestored { .. })) {
+        Some(NetToUi::GameRestored { game_id: restored, move_count, recovered_from_divergence, .. }) => {
+            assert_eq!(restored, game_id);
+            assert_eq!(move_count, 3);
+            assert!(!recovered_from_divergence, "the clean shutdown saved both");
+        }
+        _ => panic!("Game was not restored"),
+    }
+
+    ui_tx.send(UiToNet::GetGameState { game_id: game_id.clone() }).unwrap();
+    match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameStateSnapshot { .. })) {
+        Some(NetToUi::GameStateSnapshot { state, .. }) => {
+            assert_eq!(state.moves.len(), 3);
+            assert_eq!(state.moves[2], Move::Place(Coord::new(4, 4)));
+        }
+        _ => panic!("Worker did not answer GetGameState"),
+    }
+
+    let _ = ui_tx.send(UiToNet::Shutdown);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn app_offers_the_saved_game_and_confirms_the_restore() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::ResumableGame {
+        game_id: "game-1".to_string(),
+        move_count: 3,
+        opponent: Some("Alice".to_string()),
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.resume_offer(), Some(("game-1".to_string(), 3, Some("Alice".to_string()))));
+
+    app.answer_resume_offer(true);
+    assert_eq!(app.resume_offer(), None);
+    assert!(net_rx.try_iter().any(|msg| matches!(&msg, UiToNet::ResumeGame { game_id } if game_id == "game-1")));
+
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 3, recovered_from_divergence: false }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.get_current_view_debug(), "Game(game-1)");
+    assert_eq!(app.toast().as_deref(), Some("Game restored after 3 moves"));
+    assert!(net_rx.try_iter().any(|msg| matches!(&msg, UiToNet::GetGameState { game_id } if game_id == "game-1")));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn stale_snapshot_is_recovered_from_the_move_journal() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, GameState, Move};
+    use p2pgo_network::snapshot::{GameSnapshot, SnapshotStore};
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    // A crash after the journal got the third move but before the next snapshot
+    let dir = tempfile::tempdir().unwrap();
+    let store = SnapshotStore::new(dir.path());
+    let moves = [Move::Place(Coord::new(2, 2)), Move::Place(Coord::new(6, 6)), Move::Place(Coord::new(4, 4))];
+    let mut state = GameState::new(9);
+    for mv in &moves[..2] {
+        state.apply_move(mv.clone()).unwrap();
+    }
+    store.write_snapshot(&GameSnapshot::new("crashed".to_string(), state)).unwrap();
+    store.write_journal("crashed", &moves).unwrap();
+
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().to_path_buf();
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+    wait_for(&ui_rx, |msg| matches!(msg, NetToUi::ResumableGame { .. })).expect("resume offer");
+
+    ui_tx.send(UiToNet::ResumeGame { game_id: "crashed".to_string() }).unwrap();
+    match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameRestored { .. })) {
+        Some(NetToUi::GameRestored { move_count, recovered_from_divergence, .. }) => {
+            assert_eq!(move_count, 3);
+            assert!(recovered_from_divergence);
+        }
+        _ => panic!("Game was not restored"),
+    }
+    assert!(dir.path().join(p2pgo_core::archiver::CORRUPT_DIR).join("crashed.cbor").exists());
+
+    let _ = ui_tx.send(UiToNet::Shutdown);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn recovered_restore_says_so() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 3, recovered_from_divergence: true }).unwrap();
+    app.tick_headless();
+    assert!(app.toast().unwrap().contains("recovered from the move log"));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn correspondence_game_resumes_with_its_pending_move() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, GameEvent, Move};
+    use p2pgo_network::lobby::Lobby;
+    use p2pgo_network::snapshot::SnapshotStore;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let dir = tempfile::tempdir().unwrap();
+
+    // First session: one move nobody received, then the app closes
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let (lobby, data_dir) = (Lobby::new(), dir.path().to_path_buf());
+    let first = {
+        let lobby = lobby.clone();
+        std::thread::spawn(move || worker::start_in_process(net_rx, net_tx, "Host".to_string(), lobby, data_dir))
+    };
+
+    ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).unwrap();
+    let game_id = match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameJoined { .. })) {
+        Some(NetToUi::GameJoined { game_id, .. }) => game_id,
+        _ => panic!("Worker did not create a game"),
+    };
+    seat_opponent(&lobby, &game_id);
+    ui_tx.send(UiToNet::SetCorrespondence { game_id: game_id.clone(), enabled: true }).unwrap();
+    ui_tx.send(UiToNet::MakeMove { mv: Move::Place(Coord::new(4, 4)), board_size: Some(9) }).unwrap();
+    wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameEvent { event: GameEvent::MoveMade { .. } }))
+        .expect("move event");
+    ui_tx.send(UiToNet::Shutdown).unwrap();
+    wait_for(&ui_rx, |msg| matches!(msg, NetToUi::ShutdownAck)).expect("shutdown ack");
+    first.join().unwrap().unwrap();
+
+    let saved = SnapshotStore::new(dir.path().join("snapshots")).correspondence_games().unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].outbox.len(), 1);
+
+    // The next start resumes it without asking
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let snapshot_dir = dir.path().join("snapshots");
+    std::thread::spawn(move || worker::start_with_snapshot_dir(net_rx, net_tx, snapshot_dir));
+
+    match wait_for(&ui_rx, |msg| matches!(msg, NetToUi::GameRestored { .. } | NetToUi::ResumableGame { .. })) {
+        Some(NetToUi::GameRestored { game_id: restored, move_count, recovered_from_divergence, .. }) => {
+            assert_eq!(restored, game_id);
+            assert_eq!(move_count, 1);
+            assert!(!recovered_from_divergence);
+        }
+        other => panic!("Correspondence game was not restored: {:?}", other),
+    }
+
+    let _ = ui_tx.send(UiToNet::Shutdown);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn unread_moves_badge_follows_the_worker() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    net_tx.send(NetToUi::UnreadMoves { game_id: "game-1".to_string(), count: 1 }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.unread_moves(), Some(("game-1".to_string(), 1)));
+
+    net_tx.send(NetToUi::UnreadMoves { game_id: "game-1".to_string(), count: 0 }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.unread_moves(), None);
+}
@@ -0,0 +1,163 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Advanced config editor: checking the JSON, applying what can change
+//! while running and keeping the config between runs.
+
+use p2pgo_ui_egui::runtime_config::RuntimeConfig;
+
+#[test]
+fn json_that_does_not_parse_or_check_out_is_refused() {
+    let errors = RuntimeConfig::parse("{ \"log_filter\": ").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, None);
+    assert!(errors[0].message.contains("line 1"), "{}", errors[0]);
+
+    // Typos aren't silently dropped
+    let errors = RuntimeConfig::parse(r#"{ "ghost_moves_after_game": 3 }"#).unwrap_err();
+    assert!(errors[0].message.contains("unknown field"), "{}", errors[0]);
+    let errors = RuntimeConfig::parse(r#"{ "log_filter": "info,p2pgo=loud" }"#).unwrap_err();
+    assert_eq!(errors.len(), 1);
+
+    // Every unusable value is listed at once
+    let errors = RuntimeConfig::parse(r#"{
+        "channel_tuning": { "ack_timeout_ms": 1000, "check_interval_ms": 1, "max_sync_requests": 3 },
+        "governor": { "parallelism": 0, "period_ms": 100, "rest_ms": 500 }
+    }"#).unwrap_err();
+    let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+    assert_eq!(fields, [
+        Some("channel_tuning.check_interval_ms"),
+        Some("governor.parallelism"),
+        Some("governor.rest_ms"),
+    ]);
+    assert_eq!(errors[1].to_string(), "governor.parallelism: must be at least 1");
+}
+
+#[test]
+fn missing_fields_take_their_defaults() {
+    let config = RuntimeConfig::parse(r#"{ "log_filter": "warn" }"#).unwrap();
+    assert_eq!(config.log_filter.to_string(), "warn");
+    assert_eq!(config.ghost_moves_after_games, RuntimeConfig::default().ghost_moves_after_games);
+    assert_eq!(RuntimeConfig::parse(&RuntimeConfig::default().to_json()).unwrap(), RuntimeConfig::default());
+}
+
+#[test]
+fn fields_read_at_startup_wait_for_a_restart() {
+    let mut running = RuntimeConfig::default();
+    let mut next = running.clone();
+    next.governor.rest_ms = 500;
+    next.ghost_moves_after_games = 0;
+    next.log_to_files = false;
+
+    let report = running.update(&next);
+    assert_eq!(report.applied, ["ghost_moves_after_games", "governor"]);
+    assert_eq!(report.restart_needed, ["log_to_files"]);
+    assert_eq!(running.governor.rest_ms, 500);
+    assert!(running.log_to_files, "still logging to files until a restart");
+
+    // Still waiting on the next apply; nothing else changed
+    let report = running.update(&next);
+    assert!(report.applied.is_empty());
+    assert_eq!(report.restart_needed, ["log_to_files"]);
+}
+
+#[test]
+fn config_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data").join("runtime_config.json");
+    assert_eq!(RuntimeConfig::load(&path), RuntimeConfig::default());
+
+    let config = RuntimeConfig {
+        log_filter: "info,p2pgo_network::game_channel=trace".parse().unwrap(),
+        log_to_files: false,
+        channel_tuning: Some(p2pgo_network::channel_tuning::ChannelTuning::BLITZ),
+        ..RuntimeConfig::default()
+    };
+    config.save(&path).unwrap();
+    assert_eq!(RuntimeConfig::load(&path), config);
+
+    // A config edited by hand into something unusable falls back
+    std::fs::write(&path, r#"{ "governor": { "parallelism": 0, "period_ms": 1, "rest_ms": 0 } }"#).unwrap();
+    assert_eq!(RuntimeConfig::load(&path), RuntimeConfig::default());
+    // Read before logging starts, the problems are handed back to report later
+    let (config, errors) = RuntimeConfig::read(&path);
+    assert_eq!(config, RuntimeConfig::default());
+    assert_eq!(errors[0].field, Some("governor.parallelism"));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn editor_shows_errors_inline_and_applies_a_valid_config() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::runtime_config::ApplyReport;
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.open_config_editor();
+    assert_eq!(RuntimeConfig::parse(app.config_json()).unwrap(), RuntimeConfig::default());
+
+    app.set_config_json(r#"{ "governor": { "parallelism": 0, "period_ms": 1000, "rest_ms": 0 } }"#);
+    app.apply_config();
+    assert_eq!(app.config_errors().len(), 1);
+    assert!(net_rx.try_recv().is_err(), "nothing applied");
+
+    app.set_config_json(r#"{ "ghost_moves_after_games": 2, "log_to_files": false }"#);
+    app.apply_config();
+    assert!(app.config_errors().is_empty());
+    match net_rx.try_recv() {
+        Ok(UiToNet::ApplyConfig { config }) => assert!(!config.log_to_files),
+        other => panic!("expected the config, got {:?}", other),
+    }
+
+    let report = ApplyReport { applied: vec!["ghost_moves_after_games"], restart_needed: vec!["log_to_files"] };
+    net_tx.send(NetToUi::ConfigApplied { result: Ok(report.clone()) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.config_report(), Some(&report));
+
+    // The editor reopens on what was applied
+    app.open_config_editor();
+    let shown = RuntimeConfig::parse(app.config_json()).unwrap();
+    assert_eq!(shown.ghost_moves_after_games, 2);
+    assert!(!shown.log_to_files);
+    assert_eq!(app.config_report(), None);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn worker_reports_what_it_applied() {
+    use std::time::Duration;
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    std::thread::spawn(move || worker::start(net_rx, net_tx));
+    let applied = || loop {
+        match ui_rx.recv_timeout(Duration::from_secs(10)) {
+            Ok(NetToUi::ConfigApplied { result }) => return result,
+            Ok(_) => continue,
+            Err(e) => panic!("no answer to the config: {}", e),
+        }
+    };
+
+    // The config the app starts with is in effect as it is
+    ui_tx.send(UiToNet::ApplyConfig { config: RuntimeConfig::default() }).unwrap();
+    assert_eq!(applied().unwrap(), Default::default());
+
+    let mut next = RuntimeConfig { log_to_files: false, ..RuntimeConfig::default() };
+    next.governor.parallelism = 1;
+    next.governor.rest_ms = 300;
+    ui_tx.send(UiToNet::ApplyConfig { config: next.clone() }).unwrap();
+    let report = applied().unwrap();
+    assert_eq!(report.applied, ["governor"]);
+    assert_eq!(report.restart_needed, ["log_to_files"]);
+
+    next.governor.parallelism = 0;
+    ui_tx.send(UiToNet::ApplyConfig { config: next }).unwrap();
+    assert!(applied().unwrap_err().contains("governor.parallelism"));
+
+    let _ = ui_tx.send(UiToNet::Shutdown);
+}
@@ -9,6 +9,7 @@ fn score_dialog_updates_game_counter() {
     let mut config = AppConfig {
         auto_refresh: true,
         games_finished: 0,
+        ..AppConfig::default()
     };
     
     // Initial game count should be 0
@@ -32,6 +33,7 @@ fn score_dialog_view_structure() {
         captures_white: 0,
         komi: 6.5,
         method: p2pgo_core::value_labeller::ScoringMethod::Territory,
+        final_position: None,
     };
     
     let view = View::ScoreDialog {
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Importing a folder of SGF files from the archive view.
+
+#[cfg(feature = "headless")]
+#[test]
+fn import_shows_progress_then_the_report() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::archiver;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::archive_view::ImportDialog;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../core/tests/fixtures/sgf_import");
+    let archive = tempfile::tempdir().unwrap();
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.open_archive_in(archive.path().to_path_buf()).unwrap();
+
+    // Nothing to import without a folder
+    app.archive_browser_mut().unwrap().import = Some(ImportDialog::default());
+    app.start_sgf_import();
+    assert!(net_rx.try_recv().is_err());
+    assert!(app.get_error_msg().is_some());
+
+    app.archive_browser_mut().unwrap().import.as_mut().unwrap().folder = fixtures.display().to_string();
+    app.start_sgf_import();
+    let (dir, options) = match net_rx.try_recv().unwrap() {
+        UiToNet::ImportSgf { dir, options } => (dir, options),
+        other => panic!("expected ImportSgf, got {:?}", other),
+    };
+    assert_eq!(options.archive_dir, archive.path());
+    // One import at a time
+    app.start_sgf_import();
+    assert!(net_rx.try_recv().is_err());
+
+    // Answer as the worker does
+    let result = archiver::import_sgf_dir_with(&dir, &options, |progress| {
+        net_tx.send(NetToUi::ImportProgress { progress: progress.clone() }).unwrap();
+    });
+    app.tick_headless();
+    let dialog = app.archive_browser_mut().unwrap().import.clone().unwrap();
+    assert!(dialog.running, "still running until the report arrives");
+    let progress = dialog.progress.unwrap();
+    assert_eq!((progress.done, progress.total), (5, 5));
+
+    net_tx.send(NetToUi::ImportFinished { result: result.map_err(|e| e.to_string()) }).unwrap();
+    app.tick_headless();
+    let browser = app.archive_browser_mut().unwrap();
+    let dialog = browser.import.clone().unwrap();
+    assert!(!dialog.running);
+    let report = dialog.report.unwrap();
+    assert_eq!((report.imported.len(), report.duplicates.len(), report.failed.len()), (2, 1, 1));
+    assert_eq!(browser.visible().len(), 2, "the listing shows the new games");
+    assert!(app.toast().unwrap().starts_with("Imported 2 games"));
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Sharing a practice board for spectators to watch.
+
+#[cfg(feature = "headless")]
+#[test]
+fn shared_practice_moves_go_to_the_worker_until_the_game_ends() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.start_practice(9);
+
+    // Moves before sharing stay on this machine
+    app.play_move(Move::Place(Coord::new(2, 2)));
+    assert!(net_rx.try_recv().is_err());
+
+    app.share_board();
+    assert!(app.sharing_board());
+    let shared = net_rx.try_recv().unwrap();
+    assert!(matches!(&shared, UiToNet::ShareBoard { state } if state.moves.len() == 1), "{:?}", shared);
+    // Asking twice shares once
+    app.share_board();
+    assert!(net_rx.try_recv().is_err());
+
+    net_tx.send(NetToUi::BoardShared { game_id: "game-1".to_string(), link: "p2pgo://invite/abc".to_string() }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.toast().as_deref(), Some("Link copied: friends can now watch this board"));
+
+    // Both colors' moves go out in order, then the end closes the broadcast
+    let script = [Move::Place(Coord::new(6, 6)), Move::Place(Coord::new(4, 4)), Move::Pass, Move::Pass];
+    for mv in script.clone() {
+        app.play_move(mv);
+    }
+    let sent: Vec<UiToNet> = net_rx.try_iter().collect();
+    assert_eq!(sent.len(), script.len() + 1, "{:?}", sent);
+    for (msg, mv) in sent.iter().zip(&script) {
+        assert!(matches!(msg, UiToNet::ShareMove { mv: shared } if shared == mv), "{:?}", msg);
+    }
+    assert!(matches!(sent.last(), Some(UiToNet::StopSharing { archive: true })), "{:?}", sent.last());
+    assert!(!app.sharing_board());
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn network_games_cannot_be_shared_as_a_board() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 0, recovered_from_divergence: false }).unwrap();
+    app.tick_headless();
+    let _ = net_rx.try_iter().count();
+
+    app.share_board();
+    assert!(!app.sharing_board());
+    assert!(!net_rx.try_iter().any(|msg| matches!(msg, UiToNet::ShareBoard { .. })));
+}
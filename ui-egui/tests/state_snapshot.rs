@@ -0,0 +1,128 @@
+//! Test that the app resyncs from worker snapshots after missing moves
+//! SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(feature = "headless")]
+#[test]
+fn missed_move_converges_after_snapshot() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Color, Coord, GameEvent, GameState, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    
+    let moves = [
+        Move::Place(Coord::new(4, 4)),
+        Move::Place(Coord::new(2, 2)),
+        Move::Place(Coord::new(6, 6)),
+    ];
+    
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: moves[0].clone(), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+    
+    // Entering the game view asks for a snapshot
+    let requested = |rx: &crossbeam_channel::Receiver<UiToNet>| {
+        rx.try_iter()
+            .filter(|msg| matches!(msg, UiToNet::GetGameState { game_id } if game_id == "game-1"))
+            .count()
+    };
+    assert_eq!(requested(&net_rx), 1);
+    net_tx.send(NetToUi::GameStateSnapshot {
+        game_id: "game-1".to_string(),
+        state: {
+            let mut state = GameState::new(9);
+            state.apply_move(moves[0].clone()).unwrap();
+            state
+        },
+    }).unwrap();
+    app.tick_headless();
+    
+    // White's move is missed; Black's next move cannot be applied
+    net_tx.send(NetToUi::GameEvent {
+        event: GameEvent::MoveMade { mv: moves[2].clone(), by: Color::Black },
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(requested(&net_rx), 1, "Desync should request a snapshot");
+    assert_eq!(app.get_current_game_state().unwrap().moves.len(), 1);
+    
+    let mut full = GameState::new(9);
+    for mv in &moves {
+        full.apply_move(mv.clone()).unwrap();
+    }
+    net_tx.send(NetToUi::GameStateSnapshot {
+        game_id: "game-1".to_string(),
+        state: full.clone(),
+    }).unwrap();
+    app.tick_headless();
+    
+    let state = app.get_current_game_state().unwrap();
+    assert_eq!(state.board, full.board);
+    assert_eq!(state.current_player, full.current_player);
+    assert_eq!(app.get_error_msg(), None);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn worker_answers_get_game_state() {
+    use std::time::{Duration, Instant};
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::{Coord, GameEvent, Move};
+    use p2pgo_network::join::{JoinPolicy, PlayerProfile};
+    use p2pgo_network::lobby::Lobby;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+    
+    let dir = tempfile::tempdir().unwrap();
+    let (ui_tx, net_rx) = unbounded();
+    let (net_tx, ui_rx) = unbounded();
+    let (lobby, data_dir) = (Lobby::new(), dir.path().to_path_buf());
+    {
+        let lobby = lobby.clone();
+        std::thread::spawn(move || worker::start_in_process(net_rx, net_tx, "Host".to_string(), lobby, data_dir));
+    }
+    
+    let wait_for = |pred: &dyn Fn(&NetToUi) -> bool| -> Option<NetToUi> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if let Ok(msg) = ui_rx.recv_timeout(Duration::from_millis(100)) {
+                if pred(&msg) {
+                    return Some(msg);
+                }
+            }
+        }
+        None
+    };
+    
+    ui_tx.send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() }).unwrap();
+    let game_id = match wait_for(&|msg| matches!(msg, NetToUi::GameJoined { .. })) {
+        Some(NetToUi::GameJoined { game_id, .. }) => game_id,
+        _ => panic!("Worker did not create a game"),
+    };
+    
+    // Moves wait for an opponent in the seat
+    let opponent = PlayerProfile { node_id: "opponent".to_string(), name: "Opponent".to_string(), guild: None, self_reported_rating: None };
+    tokio::runtime::Runtime::new().unwrap().block_on(async {
+        lobby.set_join_policy(&game_id, JoinPolicy::Everyone).await.unwrap();
+        lobby.request_join(&game_id, opponent).await.unwrap();
+    });
+    
+    ui_tx.send(UiToNet::MakeMove { mv: Move::Place(Coord::new(4, 4)), board_size: Some(9) }).unwrap();
+    wait_for(&|msg| matches!(msg, NetToUi::GameEvent { event: GameEvent::MoveMade { .. } })).expect("move event");
+    
+    ui_tx.send(UiToNet::GetGameState { game_id: game_id.clone() }).unwrap();
+    match wait_for(&|msg| matches!(msg, NetToUi::GameStateSnapshot { .. })) {
+        Some(NetToUi::GameStateSnapshot { game_id: snapshot_id, state }) => {
+            assert_eq!(snapshot_id, game_id);
+            assert_eq!(state.moves, vec![Move::Place(Coord::new(4, 4))]);
+        }
+        _ => panic!("Worker did not answer GetGameState"),
+    }
+    
+    let _ = ui_tx.send(UiToNet::Shutdown);
+}
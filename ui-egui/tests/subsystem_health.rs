@@ -0,0 +1,24 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Telling the player about restarted and failed background subsystems.
+
+#[cfg(feature = "headless")]
+#[test]
+fn restarts_are_noted_and_give_ups_shown_as_errors() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    net_tx.send(NetToUi::SubsystemRestarted { name: "lobby gossip".to_string() }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.toast().as_deref(), Some("Restarted the lobby gossip after it stopped responding"));
+    assert_eq!(app.get_error_msg(), None);
+
+    net_tx.send(NetToUi::SubsystemFailed { name: "lobby gossip".to_string() }).unwrap();
+    app.tick_headless();
+    assert!(app.get_error_msg().unwrap().starts_with("The lobby gossip keeps stopping"));
+}
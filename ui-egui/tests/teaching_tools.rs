@@ -0,0 +1,83 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Board clicks with the teacher's tools, and the overlay drawn from annotations.
+
+use p2pgo_core::teaching::{Marker, TeachingAnnotation, TeachingLayer};
+use p2pgo_core::{Color, Coord};
+use p2pgo_ui_egui::teaching_view::{annotations_for, TeachingTool};
+
+fn click(layer: &mut TeachingLayer, tool: TeachingTool, at: Coord) {
+    for annotation in annotations_for(tool, at, layer.overlay(0)) {
+        layer.apply(0, &annotation, 9).unwrap();
+    }
+}
+
+#[test]
+fn clicking_again_takes_an_annotation_back() {
+    let at = Coord::new(2, 3);
+    let mut layer = TeachingLayer::new();
+    click(&mut layer, TeachingTool::Stone(Color::White), at);
+    click(&mut layer, TeachingTool::Triangle, at);
+    let overlay = layer.overlay(0).unwrap();
+    assert_eq!(overlay.stone_at(at), Some(Color::White));
+    assert_eq!(overlay.mark_at(at), Some(&Marker::Triangle));
+
+    // The other color replaces the stone; the same color removes it
+    click(&mut layer, TeachingTool::Stone(Color::Black), at);
+    assert_eq!(layer.overlay(0).unwrap().stone_at(at), Some(Color::Black));
+    click(&mut layer, TeachingTool::Stone(Color::Black), at);
+    assert_eq!(layer.overlay(0).unwrap().stone_at(at), None);
+
+    click(&mut layer, TeachingTool::Erase, at);
+    assert!(layer.overlay(0).is_none());
+}
+
+#[test]
+fn letters_are_handed_out_in_order() {
+    let mut layer = TeachingLayer::new();
+    for x in 0..3 {
+        click(&mut layer, TeachingTool::Label, Coord::new(x, 0));
+    }
+    let labels: Vec<Marker> = layer.overlay(0).unwrap().marks.iter().map(|(_, m)| m.clone()).collect();
+    assert_eq!(labels, ["A", "B", "C"].map(|l| Marker::Label(l.to_string())));
+
+    // A freed letter is used again first
+    layer.apply(0, &TeachingAnnotation::Unmark { at: Coord::new(1, 0) }, 9).unwrap();
+    let next = annotations_for(TeachingTool::Label, Coord::new(5, 5), layer.overlay(0));
+    assert_eq!(next, [TeachingAnnotation::Mark { at: Coord::new(5, 5), marker: Marker::Label("B".to_string()) }]);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn teacher_clicks_annotate_instead_of_playing() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::GameEvent;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "lesson".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    app.tick_headless();
+    app.set_teaching(true);
+    net_tx.send(NetToUi::GameRestored { game_id: "lesson".to_string(), color: p2pgo_core::Color::Black, move_count: 0, recovered_from_divergence: false }).unwrap();
+    app.tick_headless();
+    assert!(net_rx.try_iter().any(|msg| matches!(msg, UiToNet::SetTeaching { enabled: true, .. })));
+
+    app.set_teaching_tool(Some(TeachingTool::Triangle));
+    app.annotate_at(Coord::new(4, 4));
+    let sent: Vec<UiToNet> = net_rx.try_iter().collect();
+    let [UiToNet::Annotate { annotation, .. }] = sent.as_slice() else {
+        panic!("Expected one annotation, got {:?}", sent);
+    };
+    assert_eq!(*annotation, TeachingAnnotation::Mark { at: Coord::new(4, 4), marker: Marker::Triangle });
+
+    // The board shows what the channel accepted, not what was sent
+    assert_eq!(app.drawn_teaching(), None);
+    let event = GameEvent::Annotated { move_index: 0, annotation: annotation.clone() };
+    net_tx.send(NetToUi::GameEvent { event }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.drawn_teaching().unwrap().mark_at(Coord::new(4, 4)), Some(&Marker::Triangle));
+    assert_eq!(app.get_current_game_state().unwrap().moves.len(), 0);
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Territory estimate overlay: remembered in the settings, recomputed as the
+//! game moves on, and part of the overlay cycle.
+
+use p2pgo_ui_egui::ui_config::UiConfig;
+
+#[test]
+fn territory_estimate_is_off_until_chosen() {
+    let config: UiConfig = serde_json::from_str(r#"{ "onboarded": true }"#).unwrap();
+    assert!(!config.territory_estimate);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn estimate_follows_the_position_on_screen() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::territory::Region;
+    use p2pgo_core::{Color, Coord, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (_net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.start_practice(9);
+    assert_eq!(app.drawn_territory(), None);
+
+    app.set_territory(true);
+    let empty = app.drawn_territory().expect("estimated without the worker");
+    assert!(empty.regions.iter().all(|region| *region == Region::Neutral));
+
+    app.play_move(Move::Place(Coord::new(4, 4)));
+    app.tick_headless();
+    let estimate = app.drawn_territory().unwrap();
+    assert_eq!(estimate.regions[4 * 9 + 4], Region::Stone(Color::Black));
+    assert!(estimate.influence[4 * 9 + 5] > 0.0);
+
+    app.set_territory(false);
+    assert_eq!(app.drawn_territory(), None);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn overlay_shortcut_cycles_through_each_overlay() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::settings::GameSettings;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+
+    // Practice games have no analysis, only the estimate
+    app.start_practice(9);
+    app.cycle_overlay();
+    assert_eq!(app.overlays_shown(), (false, false, true));
+    app.cycle_overlay();
+    assert_eq!(app.overlays_shown(), (false, false, false));
+
+    net_tx.send(NetToUi::GameJoined { game_id: "cycled".to_string(), settings: GameSettings::standard(9) }).unwrap();
+    app.tick_headless();
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+        app.cycle_overlay();
+        seen.push(app.overlays_shown());
+    }
+    assert_eq!(seen, [
+        (true, false, false),
+        (false, true, false),
+        (false, false, true),
+        (false, false, false),
+    ]);
+}
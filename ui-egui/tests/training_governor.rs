@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Background training limits kept in the settings, and the pause shown in
+//! the training panel.
+
+use p2pgo_core::governor::GovernorConfig;
+use p2pgo_ui_egui::runtime_config::RuntimeConfig;
+
+#[test]
+fn configs_saved_before_the_governor_get_the_default_limits() {
+    let config = RuntimeConfig::parse(r#"{ "ghost_moves_after_games": 5 }"#).unwrap();
+    assert_eq!(config.governor, GovernorConfig::default());
+
+    let mut config = RuntimeConfig::default();
+    config.governor.rest_ms = 500;
+    let saved = RuntimeConfig::parse(&config.to_json()).unwrap();
+    assert_eq!(saved.governor.rest_ms, 500);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn app_shows_why_training_is_paused() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::governor::PauseReason;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, _net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    assert_eq!(app.training_paused(), None);
+
+    net_tx.send(NetToUi::TrainingPaused { reason: Some(PauseReason::LiveGame) }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.training_paused(), Some(PauseReason::LiveGame));
+
+    net_tx.send(NetToUi::TrainingPaused { reason: None }).unwrap();
+    app.tick_headless();
+    assert_eq!(app.training_paused(), None);
+}
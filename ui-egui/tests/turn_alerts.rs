@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Notifying the player of their move while the window is in the
+//! background.
+
+#[cfg(feature = "headless")]
+mod turn_alerts {
+    use std::sync::{Arc, Mutex};
+    use crossbeam_channel::{unbounded, Receiver, Sender};
+    use p2pgo_core::settings::GameSettings;
+    use p2pgo_core::{Color, Coord, GameEvent, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::turn_alerts::{Notifier, TurnAlertSettings, TurnNotice};
+
+    /// Keeps the notices it is asked to show
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<TurnNotice>>>);
+
+    impl Notifier for Recorder {
+        fn notify(&mut self, notice: &TurnNotice) {
+            self.0.lock().unwrap().push(notice.clone());
+        }
+    }
+
+    impl Recorder {
+        fn shown(&self) -> Vec<TurnNotice> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    /// An app playing White in a game, before any move
+    fn white_in_game() -> (App, Sender<NetToUi>, Receiver<UiToNet>, Recorder) {
+        let (ui_tx, net_rx) = unbounded::<UiToNet>();
+        let (net_tx, ui_rx) = unbounded::<NetToUi>();
+        let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+        let recorder = Recorder::default();
+        app.set_notifier(Box::new(recorder.clone()));
+        net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: GameSettings::standard(9) }).unwrap();
+        net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+        app.tick_headless();
+        (app, net_tx, net_rx, recorder)
+    }
+
+    fn moved(net_tx: &Sender<NetToUi>, app: &mut App, x: u8, y: u8, by: Color) {
+        net_tx.send(NetToUi::GameEvent {
+            event: GameEvent::MoveMade { mv: Move::Place(Coord::new(x, y)), by },
+        }).unwrap();
+        app.tick_headless();
+    }
+
+    #[test]
+    fn opponent_moves_notify_only_in_the_background() {
+        let (mut app, net_tx, _net_rx, recorder) = white_in_game();
+        // Looking at the board, the board is enough
+        moved(&net_tx, &mut app, 4, 4, Color::Black);
+        assert!(recorder.shown().is_empty());
+        assert_eq!(app.games_awaiting_move(), 1);
+
+        app.play_move(Move::Place(Coord::new(2, 2)));
+        moved(&net_tx, &mut app, 2, 2, Color::White);
+        assert_eq!(app.games_awaiting_move(), 0);
+
+        app.set_window_focused(false);
+        moved(&net_tx, &mut app, 6, 6, Color::Black);
+        let shown = recorder.shown();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].game_id, "game-1");
+        assert_eq!(shown[0].move_number, 3);
+        assert_eq!(app.games_awaiting_move(), 1);
+    }
+
+    #[test]
+    fn one_notification_per_opponent_move() {
+        let (mut app, net_tx, _net_rx, recorder) = white_in_game();
+        app.set_window_focused(false);
+        moved(&net_tx, &mut app, 4, 4, Color::Black);
+        // The same move again, as a retransmission would deliver it
+        moved(&net_tx, &mut app, 4, 4, Color::Black);
+        assert_eq!(recorder.shown().len(), 1);
+
+        // Our own move tells us nothing
+        app.play_move(Move::Place(Coord::new(2, 2)));
+        moved(&net_tx, &mut app, 2, 2, Color::White);
+        assert_eq!(recorder.shown().len(), 1);
+    }
+
+    #[test]
+    fn notifications_can_be_turned_off() {
+        let (mut app, net_tx, _net_rx, recorder) = white_in_game();
+        app.set_turn_alerts(TurnAlertSettings { notify: false, badge: true });
+        app.set_window_focused(false);
+        moved(&net_tx, &mut app, 4, 4, Color::Black);
+        assert!(recorder.shown().is_empty());
+        // Still counted for the title
+        assert_eq!(app.games_awaiting_move(), 1);
+
+        net_tx.send(NetToUi::GameLeft).unwrap();
+        app.tick_headless();
+        assert_eq!(app.games_awaiting_move(), 0);
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Moves typed in the game controls are played exactly like clicked ones.
+
+#[cfg(feature = "headless")]
+fn app_in_game() -> (
+    p2pgo_ui_egui::app::App,
+    crossbeam_channel::Receiver<p2pgo_ui_egui::msg::UiToNet>,
+) {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: p2pgo_core::settings::GameSettings::standard(9) }).unwrap();
+    net_tx.send(NetToUi::GameRestored { game_id: "game-1".to_string(), color: p2pgo_core::Color::Black, move_count: 0, recovered_from_divergence: false }).unwrap();
+    app.tick_headless();
+    net_rx.try_iter().for_each(drop);
+    (app, net_rx)
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn typed_move_sends_what_a_click_sends() {
+    use p2pgo_core::{Coord, Move};
+    use p2pgo_ui_egui::msg::UiToNet;
+
+    let describe = |msgs: Vec<UiToNet>| msgs.iter().map(|msg| format!("{:?}", msg)).collect::<Vec<_>>();
+
+    let (mut clicked, clicked_rx) = app_in_game();
+    assert_eq!(clicked.get_current_view_debug(), "Game(game-1)");
+    clicked.play_move(Move::Place(Coord::new(3, 5)));
+
+    let (mut typed, typed_rx) = app_in_game();
+    typed.play_typed_move(" ｄ４\n");
+
+    let clicked_msgs = describe(clicked_rx.try_iter().collect());
+    assert!(clicked_msgs[0].starts_with("MakeMove"), "{:?}", clicked_msgs);
+    assert_eq!(describe(typed_rx.try_iter().collect()), clicked_msgs);
+    assert_eq!(typed.move_input_error(), None);
+
+    typed.play_typed_move("pass");
+    assert!(matches!(typed_rx.try_recv(), Ok(UiToNet::MakeMove { mv: Move::Pass, board_size: None })));
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn unreadable_moves_are_explained_and_not_sent() {
+    let (mut app, net_rx) = app_in_game();
+
+    app.play_typed_move("T4");
+    assert_eq!(app.move_input_error().as_deref(), Some("Column T is off the 9x9 board"));
+    app.play_typed_move("D10");
+    assert_eq!(app.move_input_error().as_deref(), Some("Row 10 is off the 9x9 board"));
+    app.play_typed_move("hello");
+    assert!(app.move_input_error().unwrap().contains("try D4, pass or resign"));
+    assert!(net_rx.try_recv().is_err());
+
+    app.play_typed_move("e5");
+    assert_eq!(app.move_input_error(), None);
+    assert!(net_rx.try_recv().is_ok());
+}
@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Asking the opponent for an undo, and answering theirs.
+
+#[cfg(feature = "headless")]
+mod undo {
+    use crossbeam_channel::{unbounded, Receiver, Sender};
+    use p2pgo_core::settings::GameSettings;
+    use p2pgo_core::{Color, Coord, GameEvent, Move};
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    /// An app in a game with Black's first move on the board
+    fn in_game() -> (App, Sender<NetToUi>, Receiver<UiToNet>) {
+        let (ui_tx, net_rx) = unbounded::<UiToNet>();
+        let (net_tx, ui_rx) = unbounded::<NetToUi>();
+        let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+        net_tx.send(NetToUi::GameJoined { game_id: "game-1".to_string(), settings: GameSettings::standard(9) }).unwrap();
+        net_tx.send(NetToUi::GameEvent { event: p2pgo_core::GameEvent::PhaseChanged { phase: p2pgo_core::phase::GamePhase::Active } }).unwrap();
+        net_tx.send(NetToUi::GameEvent {
+            event: GameEvent::MoveMade { mv: Move::Place(Coord::new(4, 4)), by: Color::Black },
+        }).unwrap();
+        app.tick_headless();
+        net_rx.try_iter().count();
+        (app, net_tx, net_rx)
+    }
+
+    #[test]
+    fn requests_wait_for_the_answer() {
+        let (mut app, net_tx, net_rx) = in_game();
+        app.request_undo();
+        assert!(app.undo_pending());
+        assert!(net_rx.try_iter().any(|msg| matches!(msg, UiToNet::RequestUndo { moves: 1 })));
+        // One request at a time
+        app.request_undo();
+        assert!(!net_rx.try_iter().any(|msg| matches!(msg, UiToNet::RequestUndo { .. })));
+
+        net_tx.send(NetToUi::GameEvent { event: GameEvent::UndoAnswered { accepted: false } }).unwrap();
+        app.tick_headless();
+        assert!(!app.undo_pending());
+        assert!(app.get_error_msg().is_some_and(|e| e.contains("declined")));
+    }
+
+    #[test]
+    fn opponent_requests_are_answered_once() {
+        let (mut app, net_tx, net_rx) = in_game();
+        net_tx.send(NetToUi::GameEvent { event: GameEvent::UndoRequested { peer: "opponent".to_string(), moves: 1 } }).unwrap();
+        app.tick_headless();
+        assert_eq!(app.undo_request(), Some(1));
+
+        app.answer_undo(true);
+        app.answer_undo(false);
+        let answers: Vec<bool> = net_rx.try_iter().filter_map(|msg| match msg {
+            UiToNet::AnswerUndo { accept } => Some(accept),
+            _ => None,
+        }).collect();
+        assert_eq!(answers, vec![true]);
+        assert_eq!(app.undo_request(), None);
+
+        // Both sides agreed, so the board goes back
+        net_tx.send(NetToUi::GameEvent { event: GameEvent::MovesUndone { moves: 1 } }).unwrap();
+        app.tick_headless();
+        assert!(app.get_current_game_state().unwrap().moves.is_empty());
+    }
+}
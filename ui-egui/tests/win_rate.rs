@@ -0,0 +1,138 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Win-rate points evaluated a few at a time as moves arrive, and the graph
+//! that moves the review cursor.
+
+use std::collections::HashMap;
+use p2pgo_core::{Coord, GameState, Move};
+use p2pgo_ui_egui::heat_map::position_hash;
+use p2pgo_ui_egui::win_rate::{self, EvalPoint, WinRateFeed, WinRateGraph, EVALS_PER_POLL};
+
+fn scripted_moves() -> Vec<Move> {
+    vec![
+        Move::Place(Coord::new(4, 4)),
+        Move::Place(Coord::new(2, 2)),
+        Move::Place(Coord::new(6, 6)),
+        Move::Place(Coord::new(2, 6)),
+        Move::Pass,
+    ]
+}
+
+/// Drain the feed, counting the positions `evaluate` was run on
+fn drain(feed: &mut WinRateFeed, evaluations: &mut HashMap<u64, usize>) -> Vec<EvalPoint> {
+    let mut points = Vec::new();
+    while !feed.is_idle() {
+        let polled = feed.poll(|state| {
+            *evaluations.entry(position_hash(state)).or_default() += 1;
+            Ok(state.moves.len() as f32 / 10.0)
+        }).unwrap();
+        assert!(polled.len() <= EVALS_PER_POLL);
+        points.extend(polled);
+    }
+    points
+}
+
+#[test]
+fn one_point_per_move_in_order_as_moves_arrive() {
+    let mut feed = WinRateFeed::default();
+    let mut evaluations = HashMap::new();
+    let mut state = GameState::new(9);
+    feed.follow("game-1".to_string(), state.clone());
+    let mut points = drain(&mut feed, &mut evaluations);
+
+    for mv in scripted_moves() {
+        state.apply_move(mv).unwrap();
+        feed.moved("game-1", &state);
+        // Another game's moves aren't evaluated
+        feed.moved("game-2", &GameState::new(9));
+        points.extend(drain(&mut feed, &mut evaluations));
+    }
+
+    let moves: Vec<_> = points.iter().map(|p| p.move_number).collect();
+    assert_eq!(moves, (0..=5).collect::<Vec<_>>());
+    assert!(points.iter().all(|p| p.game_id == "game-1"));
+    assert_eq!(points[2].value, win_rate::win_rate(0.2));
+    assert!(evaluations.values().all(|&n| n == 1));
+}
+
+#[test]
+fn values_are_cached_until_a_newer_model_loads() {
+    let mut game = GameState::new(9);
+    for mv in scripted_moves() {
+        game.apply_move(mv).unwrap();
+    }
+    let mut feed = WinRateFeed::default();
+    let mut evaluations = HashMap::new();
+    feed.follow("game-1".to_string(), game.clone());
+    assert_eq!(drain(&mut feed, &mut evaluations).len(), 6);
+
+    // Following again, as reopening the review does, runs no inference
+    feed.follow("game-1".to_string(), game.clone());
+    assert_eq!(drain(&mut feed, &mut evaluations).len(), 6);
+    assert_eq!(evaluations.values().sum::<usize>(), 6);
+
+    feed.model_changed();
+    assert_eq!(drain(&mut feed, &mut evaluations).len(), 6);
+    assert!(evaluations.values().all(|&n| n == 2));
+
+    // A failing model drops the rest of the game
+    feed.follow("game-1".to_string(), game);
+    feed.model_changed();
+    assert!(feed.poll(|_| Err(anyhow::anyhow!("no model"))).is_err());
+    assert!(feed.is_idle());
+}
+
+#[test]
+fn the_graph_keeps_its_game_and_maps_clicks_to_moves() {
+    let mut graph = WinRateGraph::new("game-1".to_string());
+    for move_number in [0, 2, 1] {
+        graph.receive("game-1", move_number, 0.5);
+    }
+    graph.receive("game-2", 3, 0.9);
+    assert_eq!(graph.points().iter().map(|p| p.0).collect::<Vec<_>>(), vec![0, 1, 2]);
+    graph.truncate(1);
+    assert_eq!(graph.value(2), None);
+    assert_eq!(graph.value(1), Some(0.5));
+
+    assert_eq!(win_rate::move_at(3.4, 10), 3);
+    assert_eq!(win_rate::move_at(-2.0, 10), 0);
+    assert_eq!(win_rate::move_at(12.0, 10), 10);
+}
+
+#[cfg(feature = "headless")]
+#[test]
+fn reviewing_graphs_the_game_and_clicks_move_the_cursor() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_core::archiver;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut game = GameState::new(9);
+    for mv in scripted_moves() {
+        game.apply_move(mv).unwrap();
+    }
+    archiver::archive_game_in(dir.path(), &game, "alice").unwrap();
+
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    app.open_archive_in(dir.path().to_path_buf()).unwrap();
+    let id = app.archive_browser_mut().unwrap().visible()[0].id.clone();
+    app.review_archived(&id).unwrap();
+    let followed = net_rx.try_iter().find_map(|msg| match msg {
+        UiToNet::FollowWinRate { game_id, state } => Some((game_id, state.moves.len())),
+        _ => None,
+    });
+    assert_eq!(followed, Some((id.clone(), 5)));
+
+    for move_number in 0..=5 {
+        net_tx.send(NetToUi::EvalPoint { game_id: id.clone(), move_number, value: 0.5 }).unwrap();
+    }
+    app.tick_headless();
+    assert_eq!(app.win_rate().unwrap().points().len(), 6);
+
+    assert_eq!(app.review_cursor(), Some(5));
+    app.seek_review(win_rate::move_at(2.2, 5));
+    assert_eq!(app.review_cursor(), Some(2));
+}
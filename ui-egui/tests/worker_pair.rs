@@ -0,0 +1,188 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Two workers and their apps playing a whole game against each other in
+//! one process.
+//!
+//! The harness sits between each worker and its app, forwarding messages
+//! one at a time and waiting on them rather than on the clock, so a step
+//! is over as soon as both sides have seen what it should produce.
+
+#[cfg(feature = "headless")]
+mod pair {
+    use std::time::{Duration, Instant};
+    use crossbeam_channel::{unbounded, Receiver, Select, Sender};
+    use p2pgo_core::{Color, Coord, GameEvent, Move};
+    use p2pgo_network::lobby::Lobby;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{NetToUi, UiToNet};
+    use p2pgo_ui_egui::worker;
+
+    /// Longest wait for a step before the test fails
+    const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// 18 stones, Black capturing at E5 on move 7, then two passes
+    fn script() -> Vec<Move> {
+        let stones = [
+            (3, 4), (4, 4), (5, 4), (6, 6), (4, 3), (2, 6), (4, 5), (6, 2), (2, 2),
+            (7, 4), (3, 6), (5, 6), (3, 2), (6, 4), (5, 2), (6, 3), (2, 4), (5, 5),
+        ];
+        let mut moves: Vec<Move> = stones.iter().map(|&(x, y)| Move::Place(Coord::new(x, y))).collect();
+        moves.extend([Move::Pass, Move::Pass]);
+        moves
+    }
+
+    struct Player {
+        app: App,
+        to_worker: Sender<UiToNet>,
+        from_worker: Receiver<NetToUi>,
+        to_app: Sender<NetToUi>,
+        /// Everything the worker sent, in order
+        seen: Vec<NetToUi>,
+        _data_dir: tempfile::TempDir,
+    }
+
+    impl Player {
+        fn spawn(name: &str, lobby: &Lobby) -> Self {
+            let data_dir = tempfile::tempdir().unwrap();
+            let (to_worker, net_rx) = unbounded();
+            let (net_tx, from_worker) = unbounded();
+            let (to_app, ui_rx) = unbounded();
+            let (name, lobby, dir) = (name.to_string(), lobby.clone(), data_dir.path().to_path_buf());
+            std::thread::spawn(move || worker::start_in_process(net_rx, net_tx, name, lobby, dir));
+            Self {
+                app: App::new_headless_with_channels(to_worker.clone(), ui_rx),
+                to_worker,
+                from_worker,
+                to_app,
+                seen: Vec::new(),
+                _data_dir: data_dir,
+            }
+        }
+
+        fn send(&self, msg: UiToNet) {
+            self.to_worker.send(msg).unwrap();
+        }
+
+        fn deliver(&mut self, msg: NetToUi) {
+            self.seen.push(msg.clone());
+            self.to_app.send(msg).unwrap();
+            self.app.tick_headless();
+        }
+
+        fn saw(&self, pred: impl Fn(&NetToUi) -> bool) -> bool {
+            self.seen.iter().any(pred)
+        }
+
+        /// Moves in the order the worker reported them
+        fn moves(&self) -> Vec<(Move, Color)> {
+            self.seen.iter().filter_map(|msg| match msg {
+                NetToUi::GameEvent { event: GameEvent::MoveMade { mv, by } } => Some((mv.clone(), *by)),
+                _ => None,
+            }).collect()
+        }
+    }
+
+    /// Forward worker messages to the apps until `done` holds for both
+    fn settle(players: &mut [Player; 2], what: &str, done: impl Fn(&Player) -> bool) {
+        settle_until(players, what, |players| players.iter().all(&done));
+    }
+
+    /// Forward worker messages to the apps until `done` holds
+    fn settle_until(players: &mut [Player; 2], what: &str, done: impl Fn(&[Player; 2]) -> bool) {
+        let deadline = Instant::now() + STEP_TIMEOUT;
+        while !done(players) {
+            let (index, msg) = {
+                let mut select = Select::new();
+                for player in players.iter() {
+                    select.recv(&player.from_worker);
+                }
+                let Ok(ready) = select.select_deadline(deadline) else {
+                    panic!("timed out waiting for {}", what);
+                };
+                let index = ready.index();
+                (index, ready.recv(&players[index].from_worker).expect("worker alive"))
+            };
+            players[index].deliver(msg);
+        }
+    }
+
+    #[test]
+    fn two_workers_play_a_scored_game() {
+        let lobby = Lobby::new();
+        let mut players = [Player::spawn("Host", &lobby), Player::spawn("Guest", &lobby)];
+        settle(&mut players, "both workers", |p| p.saw(|m| matches!(m, NetToUi::WorkerReady { .. })));
+
+        // The guest asks for the host's game as soon as it is advertised
+        players[0].send(UiToNet::CreateGame { board_size: 9, rule_config: Default::default() });
+        settle_until(&mut players, "the join request", |[host, _]| {
+            host.saw(|m| matches!(m, NetToUi::JoinRequested { .. }))
+        });
+        let (game_id, request_id) = players[0].seen.iter().find_map(|m| match m {
+            NetToUi::JoinRequested { game_id, request_id, profile } => {
+                assert_eq!(profile.name, "Guest");
+                Some((game_id.clone(), *request_id))
+            }
+            _ => None,
+        }).unwrap();
+        players[0].send(UiToNet::RespondJoin { game_id: game_id.clone(), request_id, accept: true, reason: None });
+        settle(&mut players, "both to join", |p| {
+            p.saw(|m| matches!(m, NetToUi::GameJoined { game_id: id, .. } if *id == game_id))
+        });
+
+        let moves = script();
+        for (i, mv) in moves.iter().enumerate() {
+            players[i % 2].send(UiToNet::MakeMove { mv: mv.clone(), board_size: Some(9) });
+            settle(&mut players, &format!("move {}", i + 1), |p| p.moves().len() == i + 1);
+
+            if i == 17 {
+                let boards: Vec<_> = players.iter().map(|p| p.app.get_current_game_state().unwrap()).collect();
+                assert_eq!(boards[0].board, boards[1].board);
+                assert_eq!(boards[0].moves.len(), 18);
+                assert_eq!(boards[0].captures, (1, 0));
+                assert_eq!(boards[0].board[4 * 9 + 4], None, "the captured stone is gone");
+            }
+        }
+
+        // Both saw every move once, in order, with the right colors
+        let expected: Vec<_> = moves.iter().enumerate()
+            .map(|(i, mv)| (mv.clone(), if i % 2 == 0 { Color::Black } else { Color::White }))
+            .collect();
+        for player in &players {
+            assert_eq!(player.moves(), expected);
+        }
+
+        // The score dialog opens after the last pass is on the board
+        settle(&mut players, "the score", |p| p.app.score_dialog().is_some());
+        for player in &players {
+            let last_move = player.seen.iter()
+                .rposition(|m| matches!(m, NetToUi::GameEvent { event: GameEvent::MoveMade { .. } }))
+                .unwrap();
+            let scored = player.seen.iter().position(|m| matches!(m, NetToUi::ScoreCalculated { .. })).unwrap();
+            assert!(scored > last_move, "scored before the last move arrived");
+            let (state, _, accepted) = player.app.score_dialog().unwrap();
+            assert_eq!(state.moves.len(), 20);
+            assert!(!accepted);
+        }
+
+        for player in &players {
+            let (_, proof, _) = player.app.score_dialog().unwrap();
+            player.send(UiToNet::AcceptScore { score_proof: proof.clone() });
+        }
+        settle(&mut players, "both to accept", |p| matches!(p.app.score_dialog(), Some((_, _, true))));
+        let proofs: Vec<_> = players.iter().map(|p| p.app.score_dialog().unwrap().1.clone()).collect();
+        for proof in &proofs {
+            proof.verify().unwrap();
+            assert_eq!(proof.captures_black, 1);
+        }
+        assert_eq!(proofs[0].final_score, proofs[1].final_score);
+        assert_eq!(
+            proofs[0].final_position.as_ref().map(|p| p.hash),
+            proofs[1].final_position.as_ref().map(|p| p.hash),
+        );
+
+        for player in &players {
+            player.send(UiToNet::Shutdown);
+        }
+        settle(&mut players, "shutdown", |p| p.saw(|m| matches!(m, NetToUi::ShutdownAck)));
+    }
+}
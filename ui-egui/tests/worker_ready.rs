@@ -0,0 +1,42 @@
+//! Test that startup actions wait for the worker readiness handshake
+//! SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(feature = "headless")]
+#[test]
+fn queued_ticket_connect_delivered_once_after_ready() {
+    use crossbeam_channel::unbounded;
+    use p2pgo_ui_egui::app::App;
+    use p2pgo_ui_egui::msg::{UiToNet, NetToUi};
+    
+    let (ui_tx, net_rx) = unbounded::<UiToNet>();
+    let (net_tx, ui_rx) = unbounded::<NetToUi>();
+    let mut app = App::new_headless_with_channels(ui_tx, ui_rx);
+    
+    let count_connects = |rx: &crossbeam_channel::Receiver<UiToNet>| {
+        rx.try_iter()
+            .filter(|msg| matches!(msg, UiToNet::ConnectByTicket { .. }))
+            .count()
+    };
+    
+    // The UI asks to connect before the worker has started
+    app.queue_startup_action(UiToNet::ConnectByTicket { ticket: "ticket-abc".to_string() });
+    app.tick_headless();
+    assert_eq!(count_connects(&net_rx), 0, "Connect must wait for WorkerReady");
+    
+    // Worker reports ready
+    net_tx.send(NetToUi::WorkerReady {
+        node_id: "node-1".to_string(),
+        ticket: Some("my-ticket".to_string()),
+    }).unwrap();
+    app.tick_headless();
+    assert_eq!(count_connects(&net_rx), 1, "Connect must be sent exactly once after ready");
+    
+    // A repeated ready notification must not resend the queued action
+    net_tx.send(NetToUi::WorkerReady { node_id: "node-1".to_string(), ticket: None }).unwrap();
+    app.tick_headless();
+    assert_eq!(count_connects(&net_rx), 0);
+    
+    // Once ready, new actions go straight through
+    app.queue_startup_action(UiToNet::ConnectByTicket { ticket: "ticket-def".to_string() });
+    assert_eq!(count_connects(&net_rx), 1);
+}